@@ -282,6 +282,7 @@ pub unsafe fn enter_baremetal(config: BaremetalEntryConfig) -> ! {
         memory_map_size: MMAP_SIZE,
         descriptor_size: DESC_SIZE,
         descriptor_version: DESC_VERSION,
+        rsdp: 0,
     };
     
     let platform = match morpheus_hwinit::platform_init_selfcontained(hwinit_config) {
@@ -34,13 +34,15 @@ use morpheus_hwinit::{
     // Common types (platform only - no device types)
     InitError, PlatformInit,
 };
-use morpheus_network::boot::handoff::BootHandoff;
+use morpheus_network::boot::handoff::{BootHandoff, HandoffFramebuffer};
 use morpheus_network::boot::probe::{probe_network_device, scan_for_nic, DetectedNic, ProbeResult, ProbeError};
 use morpheus_network::driver::traits::NetworkDriver;
 use morpheus_network::driver::virtio::{VirtioConfig, VirtioNetDriver};
-use morpheus_network::driver::intel::{E1000eConfig, E1000eDriver};
-use morpheus_network::mainloop::{download_with_config, DownloadConfig, DownloadResult};
+use morpheus_network::driver::intel::{E1000eConfig, E1000eDriver, LinkMode};
+use morpheus_network::mainloop::{download_with_config, DownloadConfig, DownloadResult, PostActions};
 use morpheus_network::device::UnifiedBlockDevice;
+use morpheus_core::iso::ExitReport;
+use morpheus_network::transfer::disk::{DiskSelector, Placement};
 
 /// Network boot result.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -159,6 +161,21 @@ pub unsafe fn enter_baremetal_download(
         partition_uuid: [0u8; 16],
         iso_name: config.iso_name,
         expected_size: 0,
+        expected_sha256: None,
+        checksums: "",
+        placement: Placement::default(),
+        post_actions: PostActions::REBOOT,
+        target_disk: DiskSelector::First,
+        esp_disk: DiskSelector::First,
+        zeroed_target: false,
+        spot_check_every: 0,
+        require_tls: false,
+        beeps: false,
+        hosts: "",
+        rate_limit: 0,
+        proxy: "",
+        link_wait_secs: 0,
+        progress: None,
     };
 
     // Step 5: Create driver (this does brutal reset) and run download
@@ -197,12 +214,13 @@ pub unsafe fn enter_baremetal_download(
         }
     };
 
+    log_exit_report("[BOOT]", result.report());
     match result {
         DownloadResult::Success { .. } => {
             puts("[BOOT] download complete!\n");
             RunResult::Success
         }
-        DownloadResult::Failed { reason } => {
+        DownloadResult::Failed { reason, .. } => {
             puts("[BOOT] download failed: ");
             puts(reason);
             newline();
@@ -211,6 +229,29 @@ pub unsafe fn enter_baremetal_download(
     }
 }
 
+/// Log the session's exit report (the same one the orchestrator leaves on
+/// the ESP when it has one).
+fn log_exit_report(tag: &str, report: &ExitReport) {
+    use morpheus_hwinit::serial::{newline, puts};
+
+    puts(tag);
+    puts(" ended in ");
+    puts(report.final_state());
+    if let Some(failure) = report.failure {
+        puts(" (");
+        puts(failure.as_str());
+        puts(")");
+    }
+    puts(&alloc::format!(
+        ": {} bytes in {} ms, {} B/s, {} retries",
+        report.bytes_downloaded,
+        report.duration_ms,
+        report.throughput(),
+        report.retries.total()
+    ));
+    newline();
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // SELF-CONTAINED BARE-METAL ENTRY (RECOMMENDED)
 // ═══════════════════════════════════════════════════════════════════════════
@@ -227,6 +268,8 @@ pub struct BaremetalEntryConfig {
     pub descriptor_size: usize,
     /// Descriptor version (from UEFI)
     pub descriptor_version: u32,
+    /// ACPI RSDP address (0 = none; SMP stays off)
+    pub rsdp: u64,
 }
 
 /// Download request for bare-metal mode.
@@ -237,6 +280,24 @@ pub struct DownloadRequest {
     pub name: &'static str,
     /// ESP start LBA for disk writes (0 = don't persist)
     pub esp_start_lba: u64,
+    /// Where the ISO partition goes
+    pub placement: Placement,
+    /// What to do once the ISO is stored
+    pub post_actions: PostActions,
+    /// GOP framebuffer for the on-screen status, if any
+    pub framebuffer: Option<HandoffFramebuffer>,
+    /// Beep codes on the PC speaker
+    pub beeps: bool,
+    /// Static hostname mappings (`/etc/hosts` format, empty = none)
+    pub hosts: &'static str,
+    /// Download rate cap in bytes per second (0 = none)
+    pub rate_limit: u64,
+    /// HTTP proxy "host:port" (empty = direct)
+    pub proxy: &'static str,
+    /// Speeds the Intel PHY advertises
+    pub link_mode: LinkMode,
+    /// Checksum list next to the ISO to verify it against (empty = none)
+    pub checksums: &'static str,
 }
 
 /// Result of bare-metal operations.
@@ -287,6 +348,7 @@ pub unsafe fn enter_baremetal_world(
         memory_map_size: config.memory_map_size,
         descriptor_size: config.descriptor_size,
         descriptor_version: config.descriptor_version,
+        rsdp: config.rsdp,
     };
 
     let platform = match platform_init_selfcontained(hwinit_config) {
@@ -310,6 +372,16 @@ pub unsafe fn enter_baremetal_world(
         }
     };
 
+    // Status on screen for machines without a serial console
+    if let Some(fb) = download.framebuffer {
+        morpheus_network::display::init_display(fb.base, fb.width, fb.height, fb.stride, fb.format);
+    }
+
+    // Hashing runs on the APs when there are any
+    if platform.cpus_online > 1 {
+        morpheus_network::offload::install(morpheus_hwinit::smp::spawn);
+    }
+
     // ─────────────────────────────────────────────────────────────────────
     // PHASE 2: Execute download
     // ─────────────────────────────────────────────────────────────────────
@@ -377,6 +449,21 @@ unsafe fn execute_download(platform: &PlatformInit, download: &DownloadRequest)
         partition_uuid: [0u8; 16],
         iso_name: download.name,
         expected_size: 0,
+        expected_sha256: None,
+        checksums: download.checksums,
+        placement: download.placement,
+        post_actions: download.post_actions,
+        target_disk: DiskSelector::First,
+        esp_disk: DiskSelector::First,
+        zeroed_target: false,
+        spot_check_every: 0,
+        require_tls: false,
+        beeps: download.beeps,
+        hosts: download.hosts,
+        rate_limit: download.rate_limit,
+        proxy: download.proxy,
+        link_wait_secs: 0,
+        progress: None,
     };
 
     let dma_cpu = platform.dma_region.cpu_base();
@@ -405,7 +492,8 @@ unsafe fn execute_download(platform: &PlatformInit, download: &DownloadRequest)
             }
         }
         NetDeviceType::IntelE1000e => {
-            let intel_cfg = E1000eConfig::new(dma_cpu, dma_bus, tsc_freq);
+            let intel_cfg =
+                E1000eConfig::new(dma_cpu, dma_bus, tsc_freq).with_link_mode(download.link_mode);
             match E1000eDriver::new(net_dev.mmio_base, intel_cfg) {
                 Ok(mut driver) => {
                     download_with_config(&mut driver, download_config, None, tsc_freq)
@@ -418,11 +506,12 @@ unsafe fn execute_download(platform: &PlatformInit, download: &DownloadRequest)
         }
     };
 
+    log_exit_report("[BAREMETAL]", result.report());
     match result {
         DownloadResult::Success { bytes_written, .. } => {
             BaremetalResult::DownloadComplete { bytes: bytes_written as u64 }
         }
-        DownloadResult::Failed { reason } => {
+        DownloadResult::Failed { reason, .. } => {
             puts("[BAREMETAL] Download failed: ");
             puts(reason);
             newline();
@@ -495,11 +584,21 @@ pub unsafe fn enter_selfcontained_download(config: SelfContainedDownloadConfig)
             memory_map_size: config.memory_map_size,
             descriptor_size: config.descriptor_size,
             descriptor_version: config.descriptor_version,
+            rsdp: 0,
         },
         DownloadRequest {
             url: config.iso_url,
             name: config.iso_name,
             esp_start_lba: config.esp_start_lba,
+            placement: Placement::default(),
+            post_actions: PostActions::REBOOT,
+            framebuffer: None,
+            beeps: false,
+            hosts: "",
+            rate_limit: 0,
+            proxy: "",
+            link_mode: LinkMode::Auto,
+            checksums: "",
         },
     )
 }
@@ -2,6 +2,7 @@
 // Handles installing Morpheus to EFI System Partition
 
 use crate::BootServices;
+use morpheus_core::fs::{FsKind, VolumeInfo};
 extern crate alloc;
 
 #[derive(Debug)]
@@ -15,6 +16,9 @@ pub enum InstallError {
     FormatFailed,     // Failed to format ESP
 }
 
+/// Free space below which an ESP is flagged as full
+pub const ESP_MIN_FREE_MB: u64 = 16;
+
 /// Information about located ESP
 #[derive(Debug)]
 pub struct EspInfo {
@@ -22,8 +26,75 @@ pub struct EspInfo {
     pub partition_index: usize,
     pub start_lba: u64,
     pub size_mb: u64,
+    /// What the partition holds (`None` if it couldn't be read)
+    pub volume: Option<VolumeInfo>,
+}
+
+/// Whether an ESP can take Morpheus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EspHealth {
+    Ready,
+    /// FAT32, but with less than `ESP_MIN_FREE_MB` free
+    Full,
+    /// Typed as an ESP but holding another filesystem
+    NotFat32(FsKind),
+    /// FAT32 whose boot sector won't do (the reason)
+    Damaged(&'static str),
+    /// The partition couldn't be read
+    Unreadable,
+}
+
+impl EspHealth {
+    /// Status column text
+    pub fn label(&self) -> &'static str {
+        match self {
+            EspHealth::Ready => "Ready",
+            EspHealth::Full => "Full",
+            EspHealth::NotFat32(FsKind::Unknown) => "No FS",
+            EspHealth::NotFat32(kind) => kind.name(),
+            EspHealth::Damaged(_) => "Damaged",
+            EspHealth::Unreadable => "I/O err",
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        *self == EspHealth::Ready
+    }
+}
+
+impl EspInfo {
+    pub fn health(&self) -> EspHealth {
+        let Some(volume) = &self.volume else {
+            return EspHealth::Unreadable;
+        };
+        if volume.kind != FsKind::Fat32 {
+            return EspHealth::NotFat32(volume.kind);
+        }
+        if let Some(reason) = volume.problem {
+            return EspHealth::Damaged(reason);
+        }
+        match volume.free_bytes {
+            Some(free) if free < ESP_MIN_FREE_MB * 1024 * 1024 => EspHealth::Full,
+            _ => EspHealth::Ready,
+        }
+    }
+
+    /// Free space in MB, when it could be counted
+    pub fn free_mb(&self) -> Option<u64> {
+        self.volume
+            .as_ref()
+            .and_then(|v| v.free_bytes)
+            .map(|free| free / (1024 * 1024))
+    }
+
+    /// Volume label ("" when there is none)
+    pub fn label(&self) -> &str {
+        self.volume.as_ref().map_or("", |v| v.label.as_str())
+    }
 }
 
 /// Find EFI System Partition on any disk
 mod operations;
+mod sync;
 pub use operations::*;
+pub use sync::{has_morpheus, sync_esps, SyncReport};
@@ -4,6 +4,7 @@ use crate::uefi::file_system::{
 };
 use crate::BootServices;
 use morpheus_core::disk::partition::PartitionType;
+use morpheus_core::fs::self_check;
 use morpheus_persistent::pe::header::PeHeaders;
 
 pub fn find_esp(bs: &BootServices) -> Result<EspInfo, InstallError> {
@@ -56,11 +57,20 @@ pub fn find_esp(bs: &BootServices) -> Result<EspInfo, InstallError> {
                         continue;
                     }
 
+                    let volume = crate::uefi::gpt_adapter::UefiBlockIoAdapter::with_block_size(
+                        block_io, 512,
+                    )
+                    .ok()
+                    .and_then(|mut adapter| {
+                        let lba = adapter.adapter_lba(part.start_lba);
+                        morpheus_core::fs::probe_volume(&mut adapter, lba).ok()
+                    });
                     return Ok(EspInfo {
                         disk_index: disk_idx,
                         partition_index: part_idx,
                         start_lba: part.start_lba,
                         size_mb,
+                        volume,
                     });
                 }
             }
@@ -249,14 +259,22 @@ pub fn install_to_esp_with_progress(
 
         // Write to fallback boot path - UEFI auto-detects and boots this
         // Use UEFI allocate_pages for temporary buffers (we're still pre-EBS)
-        fat32_ops::write_file_with_progress_uefi(
+        let pages = uefi_alloc::UefiPages::new(bs.allocate_pages, bs.free_pages);
+        fat32_ops::write_file_with_allocator(
             &mut adapter,
             esp.start_lba,
             "/EFI/BOOT/BOOTX64.EFI",
             &binary_data,
             &mut progress,
-            Some(bs.allocate_pages),
-            Some(bs.free_pages),
+            &pages,
+        )
+        .map_err(|_| InstallError::IoError)?;
+
+        // Hashes for the boot-time self-check
+        self_check::write_install_record(
+            &mut adapter,
+            esp.start_lba,
+            &[(self_check::BOOT_PATH, &binary_data)],
         )
         .map_err(|_| InstallError::IoError)?;
 
@@ -383,21 +401,22 @@ pub fn create_esp_and_install(
         .get(partition_index)
         .ok_or(InstallError::IoError)?;
 
-    // Recreate adapter for formatting
-    let mut adapter = crate::uefi::gpt_adapter::UefiBlockIoAdapter::new(block_io)
+    // Recreate adapter for formatting, in the 512-byte sectors FAT32 uses
+    let mut adapter = crate::uefi::gpt_adapter::UefiBlockIoAdapter::with_block_size(block_io, 512)
         .map_err(|_| InstallError::ProtocolError)?;
+    let fat_start = adapter.adapter_lba(part.start_lba);
 
     // Format as FAT32
-    let partition_sectors = part.end_lba - part.start_lba + 1;
-    morpheus_core::fs::format_fat32(&mut adapter, part.start_lba, partition_sectors)
+    let partition_sectors = adapter.adapter_lba(part.end_lba + 1) - fat_start;
+    morpheus_core::fs::format_fat32(&mut adapter, fat_start, partition_sectors)
         .map_err(|_| InstallError::FormatFailed)?;
 
     // Recreate adapter for verification
-    let mut adapter = crate::uefi::gpt_adapter::UefiBlockIoAdapter::new(block_io)
+    let mut adapter = crate::uefi::gpt_adapter::UefiBlockIoAdapter::with_block_size(block_io, 512)
         .map_err(|_| InstallError::ProtocolError)?;
 
     // Verify filesystem
-    morpheus_core::fs::verify_fat32(&mut adapter, part.start_lba)
+    morpheus_core::fs::verify_fat32(&mut adapter, fat_start)
         .map_err(|_| InstallError::FormatFailed)?;
 
     Ok(EspInfo {
@@ -405,5 +424,6 @@ pub fn create_esp_and_install(
         partition_index,
         start_lba: part.start_lba,
         size_mb: part.size_mb(),
+        volume: morpheus_core::fs::probe_volume(&mut adapter, fat_start).ok(),
     })
 }
@@ -2,6 +2,7 @@
 // Handles installing Morpheus to EFI System Partition
 
 use crate::BootServices;
+use morpheus_core::fs::self_check;
 extern crate alloc;
 
 #[derive(Debug)]
@@ -129,20 +130,25 @@ pub fn install_to_esp(
 
         // Write directly to FAT32 partition
         // Bypasses UEFI FS protocol (works on runtime-created partitions)
-        use morpheus_core::fs::fat32_ops;
+        // Opened once for both files
+        let mut volume = morpheus_core::fs::Fat32Volume::open(&mut adapter, esp.start_lba)
+            .map_err(|_| InstallError::IoError)?;
 
         // DEBUG: Write buffer to /EFI/DEBUG.BIN before FAT32 write
-        fat32_ops::write_file(&mut adapter, esp.start_lba, "/EFI/DEBUG.BIN", &binary_data)
+        volume
+            .write_file("/EFI/DEBUG.BIN", &binary_data)
             .map_err(|_| InstallError::IoError)?;
 
         // Write to fallback boot path - UEFI auto-detects and boots this
-        fat32_ops::write_file(
-            &mut adapter,
-            esp.start_lba,
-            "/EFI/BOOT/BOOTX64.EFI",
-            &binary_data,
-        )
-        .map_err(|_| InstallError::IoError)?;
+        volume
+            .write_file("/EFI/BOOT/BOOTX64.EFI", &binary_data)
+            .map_err(|_| InstallError::IoError)?;
+
+        // Hashes for the boot-time self-check
+        let record = self_check::format_record(&[(self_check::BOOT_PATH, &binary_data)]);
+        volume
+            .replace_file(self_check::INSTALL_RECORD, record.as_bytes())
+            .map_err(|_| InstallError::IoError)?;
 
         // Verify write by reading back critical sectors
         #[cfg(feature = "fat32_debug")]
@@ -0,0 +1,71 @@
+// Mirrored ESP sync
+// Brings a second MorpheusX ESP in line with the primary one
+
+use super::{EspInfo, InstallError, ProgressCallback};
+use crate::uefi::gpt_adapter::UefiBlockIoAdapter;
+use crate::BootServices;
+use morpheus_core::fs::esp_sync::{self, Direction};
+use morpheus_core::fs::Fat32Error;
+
+pub use morpheus_core::fs::esp_sync::SyncReport;
+
+/// Path firmware boots from an ESP, and where the installer puts MorpheusX
+const BOOT_PATH: &str = "/EFI/BOOT/BOOTX64.EFI";
+
+/// Check whether MorpheusX is installed on `esp`
+pub fn has_morpheus(bs: &BootServices, esp: &EspInfo) -> bool {
+    with_esp(bs, esp, |io, lba| {
+        morpheus_core::fs::file_exists(io, lba, BOOT_PATH)
+    })
+    .unwrap_or(false)
+}
+
+/// Copy MorpheusX's binary, settings and ISO manifests between `primary`
+/// and `mirror`, primary winning any file that differs.
+///
+/// Both ESPs may sit on the same disk, so only one is open at a time:
+/// each copy is read into memory, then written.
+pub fn sync_esps(
+    bs: &BootServices,
+    primary: &EspInfo,
+    mirror: &EspInfo,
+    mut progress: ProgressCallback,
+) -> Result<SyncReport, InstallError> {
+    let primary_files = with_esp(bs, primary, |io, lba| esp_sync::snapshot(io, lba))?;
+    let mirror_files = with_esp(bs, mirror, |io, lba| esp_sync::snapshot(io, lba))?;
+    let items = esp_sync::plan(&primary_files, &mirror_files);
+
+    for (done, item) in items.iter().enumerate() {
+        if let Some(ref mut cb) = progress {
+            cb(done, items.len(), &item.path);
+        }
+        let (src, dst) = match item.direction {
+            Direction::ToMirror => (primary, mirror),
+            Direction::ToPrimary => (mirror, primary),
+        };
+        let data = with_esp(bs, src, |io, lba| {
+            morpheus_core::fs::read_file(io, lba, &item.path)
+        })?;
+        with_esp(bs, dst, |io, lba| {
+            morpheus_core::fs::replace_file(io, lba, &item.path, &data)
+        })?;
+    }
+    if let Some(ref mut cb) = progress {
+        cb(items.len(), items.len(), "Sync complete");
+    }
+
+    Ok(esp_sync::report(&primary_files, &items))
+}
+
+/// Run `f` on the filesystem of `esp`
+fn with_esp<T>(
+    bs: &BootServices,
+    esp: &EspInfo,
+    f: impl FnOnce(&mut UefiBlockIoAdapter, u64) -> Result<T, Fat32Error>,
+) -> Result<T, InstallError> {
+    let block_io = crate::uefi::disk::get_disk_protocol(bs, esp.disk_index)
+        .map_err(|_| InstallError::ProtocolError)?;
+    let mut adapter = UefiBlockIoAdapter::new(unsafe { &mut *block_io })
+        .map_err(|_| InstallError::ProtocolError)?;
+    f(&mut adapter, esp.start_lba).map_err(|_| InstallError::IoError)
+}
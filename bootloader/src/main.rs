@@ -20,6 +20,7 @@ use core::panic::PanicInfo;
 
 mod baremetal;
 mod boot;
+#[cfg(not(feature = "no-installer"))]
 mod installer;
 mod tui;
 mod uefi;
@@ -302,6 +303,10 @@ pub extern "efiapi" fn efi_main(image_handle: *mut (), system_table: *const ())
 
         // Set boot services for UEFI-backed global allocator (briefly needed for setup)
         uefi_allocator::set_boot_services(st.boot_services);
+        uefi::disk::set_boot_image(bs, image_handle);
+        if let Some(rt) = uefi::runtime::runtime_services(bs, image_handle) {
+            uefi::runtime::install_rtc_clock(rt);
+        }
 
         // ═══════════════════════════════════════════════════════════════════
         // STEP 1: Get GOP framebuffer info
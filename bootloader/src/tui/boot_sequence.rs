@@ -152,5 +152,8 @@ impl BootSequence {
         }
 
         self.last_rendered_count = total_count;
+
+        // Rendered between boot steps, not while waiting for a key
+        screen.present();
     }
 }
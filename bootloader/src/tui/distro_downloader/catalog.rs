@@ -5,6 +5,9 @@
 //!
 //! This module is pure Rust with no UEFI dependencies - fully unit testable.
 
+use alloc::format;
+use alloc::string::String;
+
 /// Category of Linux distribution
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DistroCategory {
@@ -49,12 +52,16 @@ pub struct DistroEntry {
     pub filename: &'static str,
     /// SHA256 checksum (hex string, if known)
     pub sha256: Option<&'static str>,
+    /// Checksum list published next to the ISO (empty = none)
+    pub checksums: &'static str,
     /// Category
     pub category: DistroCategory,
     /// Architecture (x86_64, aarch64, etc.)
     pub arch: &'static str,
     /// Whether this is a live ISO
     pub is_live: bool,
+    /// Release notes shown by the viewer (may be empty)
+    pub notes: &'static str,
 }
 
 impl DistroEntry {
@@ -77,9 +84,11 @@ impl DistroEntry {
             size_bytes,
             filename,
             sha256: None,
+            checksums: "SHA256SUMS",
             category,
             arch: "x86_64",
             is_live: true,
+            notes: "",
         }
     }
 
@@ -95,6 +104,12 @@ impl DistroEntry {
         self
     }
 
+    /// Name the checksum list next to the ISO ("" if there is none)
+    pub const fn with_checksums(mut self, checksums: &'static str) -> Self {
+        self.checksums = checksums;
+        self
+    }
+
     /// Set architecture
     pub const fn with_arch(mut self, arch: &'static str) -> Self {
         self.arch = arch;
@@ -107,6 +122,50 @@ impl DistroEntry {
         self
     }
 
+    /// Add release notes
+    pub const fn with_notes(mut self, notes: &'static str) -> Self {
+        self.notes = notes;
+        self
+    }
+
+    /// Everything known about the entry, for the text viewer.
+    pub fn release_notes(&self) -> String {
+        let mut text = format!("{} {}\n{}\n\n", self.name, self.version, self.description);
+        text.push_str(&format!("Category:  {}\n", self.category.name()));
+        text.push_str(&format!("Arch:      {}\n", self.arch));
+        text.push_str(&format!(
+            "Size:      {} ({} bytes)\n",
+            self.size_str(),
+            self.size_bytes
+        ));
+        text.push_str(&format!(
+            "Live ISO:  {}\n",
+            if self.is_live { "yes" } else { "no" }
+        ));
+        text.push_str(&format!("Filename:  {}\n", self.filename));
+        text.push_str(&format!(
+            "SHA256:    {}\n",
+            self.sha256.unwrap_or("not published in catalog")
+        ));
+        if !self.checksums.is_empty() {
+            text.push_str(&format!("Sums:      {} next to the ISO\n", self.checksums));
+        }
+
+        text.push_str("\nSources:\n");
+        for index in 0..self.url_count() {
+            if let Some(url) = self.get_url(index) {
+                text.push_str(&format!("  {}\n", url));
+            }
+        }
+
+        if !self.notes.is_empty() {
+            text.push_str("\nNotes:\n");
+            text.push_str(self.notes);
+            text.push('\n');
+        }
+        text
+    }
+
     /// Human-readable size string
     pub fn size_str(&self) -> &'static str {
         if self.size_bytes < 100 * 1024 * 1024 {
@@ -124,9 +183,9 @@ impl DistroEntry {
         }
     }
 
-    /// Check if URL is valid (basic check)
+    /// Check if URL is valid: http(s) scheme, a host and a usable port
     pub fn is_valid_url(&self) -> bool {
-        self.url.starts_with("http://") || self.url.starts_with("https://")
+        morpheus_network::mainloop::split_url(self.url).is_ok()
     }
 
     /// Check if filename is valid
@@ -164,7 +223,11 @@ pub static DISTRO_CATALOG: &[DistroEntry] = &[
     )
     .with_mirrors(&[
         "http://ftp.acc.umu.se/mirror/tails.boum.org/tails/stable/tails-amd64-6.10/tails-amd64-6.10.iso",
-    ]),
+    ])
+    .with_notes(
+        "Routes all traffic through Tor and forgets everything on shutdown \
+         unless Persistent Storage is set up. Needs at least 2 GB of RAM.",
+    ),
 
     DistroEntry::new(
         "Parrot OS",
@@ -293,4 +356,28 @@ mod tests {
 
         assert!(!entry.is_live);
     }
+
+    #[test]
+    fn test_release_notes_lists_sources() {
+        let entry = DistroEntry::new(
+            "Test",
+            "Test",
+            "1.0",
+            "https://example.com/test.iso",
+            100_000_000,
+            "test.iso",
+            DistroCategory::Security,
+        )
+        .with_mirrors(&["https://mirror.example.com/test.iso"])
+        .with_notes("Read me");
+
+        let notes = entry.release_notes();
+        assert!(notes.starts_with("Test 1.0\n"));
+        assert!(notes.contains("  https://example.com/test.iso\n"));
+        assert!(notes.contains("  https://mirror.example.com/test.iso\n"));
+        assert!(notes.contains("SHA256:    not published"));
+        assert!(notes.contains("Sums:      SHA256SUMS next to the ISO\n"));
+        assert!(!entry.with_checksums("").release_notes().contains("Sums:"));
+        assert!(notes.ends_with("Notes:\nRead me\n"));
+    }
 }
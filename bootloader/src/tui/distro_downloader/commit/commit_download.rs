@@ -39,12 +39,23 @@ use super::resources::{
     allocate_dma_region, allocate_stack, prepare_boot_handoff, DMA_SIZE, STACK_SIZE,
 };
 use super::uefi::{
-    calibrate_tsc_with_stall, exit_boot_services_with_retry, find_esp_lba, leak_string,
+    acpi_rsdp, calibrate_tsc, capture_system_reset, exit_boot_services_with_retry, find_esp_lba, leak_string,
+    EbsError,
 };
 use crate::boot::network_boot::{NIC_TYPE_INTEL, NIC_TYPE_VIRTIO};
+use morpheus_network::boot::handoff::{
+    BootHandoffV2, HandoffFramebuffer, TSC_DISCREPANCY_LIMIT_PPM,
+};
+use morpheus_network::driver::intel::LinkMode;
+use morpheus_network::mainloop::PostActions;
+use morpheus_network::transfer::disk::{DiskPreference, Placement, PlacementPolicy};
 
 use crate::tui::renderer::{EFI_BLACK, EFI_LIGHTGREEN, EFI_RED};
 
+/// Memory map buffer. Sized up front: once the first ExitBootServices
+/// attempt fails nothing may be allocated, so it can't grow on retry.
+const MMAP_BUF_SIZE: usize = 32768;
+
 /// Result of download commit operation.
 #[derive(Debug)]
 pub enum CommitResult {
@@ -103,8 +114,28 @@ pub unsafe fn commit_to_download(
 
     // Phase 3: Calibrate TSC
     debug_log.add("Calibrating TSC timing...", LOG_YELLOW);
-    let tsc_freq = calibrate_tsc_with_stall(bs);
-    debug_log.add(&alloc::format!("  TSC: {} Hz", tsc_freq), LOG_CYAN);
+    let tsc_cal = calibrate_tsc(bs);
+    let tsc_freq = tsc_cal.frequency;
+    debug_log.add(
+        &alloc::format!("  TSC: {} Hz ({})", tsc_freq, tsc_cal.source.name()),
+        LOG_CYAN,
+    );
+    if let Some(cpuid_freq) = tsc_cal.cpuid_frequency {
+        let color = if tsc_cal.discrepancy_ppm > TSC_DISCREPANCY_LIMIT_PPM {
+            LOG_RED
+        } else {
+            LOG_DARKGRAY
+        };
+        debug_log.add(
+            &alloc::format!(
+                "  CPUID {} Hz vs Stall {} Hz ({} ppm)",
+                cpuid_freq,
+                tsc_cal.measured_frequency,
+                tsc_cal.discrepancy_ppm
+            ),
+            color,
+        );
+    }
 
     // Phase 4: Probe network device (VirtIO or Intel e1000e)
     debug_log.add("Probing network device...", LOG_YELLOW);
@@ -228,13 +259,18 @@ pub unsafe fn commit_to_download(
 
     // Phase 8: Leak URL for bare-metal use
     let url_copy = leak_string(&config.iso_url);
+
+    // Phase 9: Capture reset mechanisms (runtime services / FADT)
+    install_system_reset(bs, image_handle, &mut debug_log);
     debug_log.add("All systems ready!", LOG_GREEN);
 
     // SUCCESS: Show clean ASCII art (debug log not needed)
     display_download_start(screen, bs);
 
     // CRITICAL: Exit boot services NOW
-    if exit_boot_services_with_retry(bs, image_handle).is_err() {
+    static mut LEGACY_MMAP_BUF: [u8; MMAP_BUF_SIZE] = [0u8; MMAP_BUF_SIZE];
+    let map_buf = &mut *core::ptr::addr_of_mut!(LEGACY_MMAP_BUF);
+    if exit_boot_services_with_retry(bs, image_handle, map_buf).is_err() {
         // Can't show debug log after failed EBS attempt - just hang
         loop {
             core::hint::spin_loop();
@@ -306,6 +342,30 @@ pub unsafe fn commit_to_download_selfcontained(
     let url_copy = leak_string(&config.iso_url);
     // Derive ISO name from distro name (or use a default)
     let name_copy = leak_string(&config.distro_name);
+    // Static name mappings for DNS, if the ESP has any
+    let hosts = crate::tui::distro_downloader::load_hosts(bs, image_handle).unwrap_or_default();
+    if !hosts.is_empty() {
+        debug_log.add("  Using hosts file from ESP", LOG_GREEN);
+    }
+    let hosts_copy = leak_string(&hosts);
+    // HTTP proxy, if the ESP names a usable one
+    let proxy = match crate::tui::distro_downloader::load_proxy(bs, image_handle) {
+        Ok(proxy) => match morpheus_network::mainloop::split_proxy(&proxy) {
+            Ok(_) => {
+                debug_log.add(&alloc::format!("  Using proxy {}", proxy), LOG_GREEN);
+                proxy
+            }
+            Err(why) => {
+                debug_log.add(&alloc::format!("  Ignoring proxy {}: {}", proxy, why), LOG_RED);
+                alloc::string::String::new()
+            }
+        },
+        Err(_) => alloc::string::String::new(),
+    };
+    let proxy_copy = leak_string(&proxy);
+
+    // Phase 3: Capture reset mechanisms so an abort can reboot cleanly
+    install_system_reset(bs, image_handle, &mut debug_log);
 
     debug_log.add("All systems ready!", LOG_GREEN);
 
@@ -318,15 +378,27 @@ pub unsafe fn commit_to_download_selfcontained(
 
     // Use static buffers for data that needs to survive the stack switch
     // This is a one-shot operation, no concurrency concerns
-    static mut MMAP_BUF: [u8; 8192] = [0u8; 8192];
-    static mut MMAP_SIZE: usize = 0;
-    static mut DESC_SIZE: usize = 0;
-    static mut DESC_VERSION: u32 = 0;
+    static mut MMAP_BUF: [u8; MMAP_BUF_SIZE] = [0u8; MMAP_BUF_SIZE];
+    static mut HANDOFF: BootHandoffV2 = BootHandoffV2::new();
     static mut URL_PTR: *const u8 = core::ptr::null();
     static mut URL_LEN: usize = 0;
     static mut NAME_PTR: *const u8 = core::ptr::null();
     static mut NAME_LEN: usize = 0;
+    static mut HOSTS_PTR: *const u8 = core::ptr::null();
+    static mut HOSTS_LEN: usize = 0;
+    static mut PROXY_PTR: *const u8 = core::ptr::null();
+    static mut PROXY_LEN: usize = 0;
     static mut ESP_LBA: u64 = 0;
+    static mut PLACEMENT: Placement = Placement {
+        policy: PlacementPolicy::LargestGap,
+        disk: DiskPreference::Any,
+    };
+    static mut POST_ACTIONS: PostActions = PostActions::REBOOT;
+    static mut BEEPS: bool = false;
+    static mut RATE_LIMIT: u64 = 0;
+    static mut LINK_MODE: LinkMode = LinkMode::Auto;
+    // Catalog string, in the loaded image
+    static mut CHECKSUMS: &str = "";
     static mut NEW_STACK_TOP: u64 = 0;
 
     // Store values in statics before EBS
@@ -334,42 +406,49 @@ pub unsafe fn commit_to_download_selfcontained(
     URL_LEN = url_copy.len();
     NAME_PTR = name_copy.as_ptr();
     NAME_LEN = name_copy.len();
+    HOSTS_PTR = hosts_copy.as_ptr();
+    HOSTS_LEN = hosts_copy.len();
+    PROXY_PTR = proxy_copy.as_ptr();
+    PROXY_LEN = proxy_copy.len();
     ESP_LBA = esp_lba;
-    NEW_STACK_TOP = stack_top;
-
-    let mut map_key: usize = 0;
-    MMAP_SIZE = MMAP_BUF.len();
-
-    // Disable watchdog timer BEFORE GetMemoryMap
-    let _ = (bs.set_watchdog_timer)(0, 0, 0, core::ptr::null());
-
-    // Get memory map into static buffer
-    let status = (bs.get_memory_map)(
-        &mut MMAP_SIZE,
-        MMAP_BUF.as_mut_ptr(),
-        &mut map_key,
-        &mut DESC_SIZE,
-        &mut DESC_VERSION,
-    );
-
-    if status != 0 {
-        loop { core::hint::spin_loop(); }
+    PLACEMENT = config.placement;
+    POST_ACTIONS = config.post_actions;
+    BEEPS = config.beeps;
+    RATE_LIMIT = config.rate_limit;
+    LINK_MODE = config.link_mode;
+    CHECKSUMS = config.checksums;
+    HANDOFF.rsdp = acpi_rsdp(bs, image_handle);
+    // GOP stays mapped after EBS; the download draws its status there
+    if let Some(fb) = query_gop(bs).filter(|fb| fb.is_valid() && fb.format <= 1) {
+        HANDOFF.set_framebuffer(&HandoffFramebuffer {
+            base: fb.base,
+            width: fb.width,
+            height: fb.height,
+            stride: fb.stride,
+            format: fb.format,
+        });
     }
+    NEW_STACK_TOP = stack_top;
 
-    // Exit boot services IMMEDIATELY (memory map is stale if we do anything else)
-    let status = (bs.exit_boot_services)(image_handle, map_key);
-    if status != 0 {
-        // Retry once with fresh map
-        MMAP_SIZE = MMAP_BUF.len();
-        let _ = (bs.get_memory_map)(
-            &mut MMAP_SIZE,
-            MMAP_BUF.as_mut_ptr(),
-            &mut map_key,
-            &mut DESC_SIZE,
-            &mut DESC_VERSION,
-        );
-        let status = (bs.exit_boot_services)(image_handle, map_key);
-        if status != 0 {
+    // Re-fetches the map and retries while the firmware keeps changing it
+    let map_buf = &mut *core::ptr::addr_of_mut!(MMAP_BUF);
+    match exit_boot_services_with_retry(bs, image_handle, map_buf) {
+        Ok(map) => {
+            HANDOFF.set_memory_map(
+                MMAP_BUF.as_ptr() as u64,
+                map.size as u32,
+                map.desc_size as u32,
+                map.desc_version,
+            );
+        }
+        Err(e) => {
+            // No screen left to report on; the attempts are on serial
+            morpheus_hwinit::serial::puts(match e {
+                EbsError::BufferTooSmall { .. } => "[EBS] FATAL: memory map too large\n",
+                EbsError::MemoryMap(_) => "[EBS] FATAL: GetMemoryMap failed\n",
+                EbsError::Exit(_) => "[EBS] FATAL: ExitBootServices failed\n",
+                EbsError::MapChurn => "[EBS] FATAL: memory map never settled\n",
+            });
             loop { core::hint::spin_loop(); }
         }
     }
@@ -388,10 +467,11 @@ pub unsafe fn commit_to_download_selfcontained(
 
     // Now on new stack - read from statics and call hwinit
     let entry_config = BaremetalEntryConfig {
-        memory_map_ptr: MMAP_BUF.as_ptr(),
-        memory_map_size: MMAP_SIZE,
-        descriptor_size: DESC_SIZE,
-        descriptor_version: DESC_VERSION,
+        memory_map_ptr: HANDOFF.base.memory_map_ptr as *const u8,
+        memory_map_size: HANDOFF.base.memory_map_size as usize,
+        descriptor_size: HANDOFF.base.memory_map_desc_size as usize,
+        descriptor_version: HANDOFF.memory_map_desc_version,
+        rsdp: HANDOFF.rsdp,
     };
 
     let url_slice = core::str::from_utf8_unchecked(
@@ -400,12 +480,48 @@ pub unsafe fn commit_to_download_selfcontained(
     let name_slice = core::str::from_utf8_unchecked(
         core::slice::from_raw_parts(NAME_PTR, NAME_LEN)
     );
+    let hosts_slice = core::str::from_utf8_unchecked(
+        core::slice::from_raw_parts(HOSTS_PTR, HOSTS_LEN)
+    );
+    let proxy_slice = core::str::from_utf8_unchecked(
+        core::slice::from_raw_parts(PROXY_PTR, PROXY_LEN)
+    );
 
     let download_req = DownloadRequest {
         url: url_slice,
         name: name_slice,
         esp_start_lba: ESP_LBA,
+        placement: PLACEMENT,
+        post_actions: POST_ACTIONS,
+        framebuffer: HANDOFF.base.framebuffer(),
+        beeps: BEEPS,
+        hosts: hosts_slice,
+        rate_limit: RATE_LIMIT,
+        proxy: proxy_slice,
+        link_mode: LINK_MODE,
+        checksums: CHECKSUMS,
     };
 
     enter_baremetal_world(entry_config, download_req);
 }
+
+/// Capture ACPI / UEFI reset mechanisms for the post-EBS session.
+unsafe fn install_system_reset(
+    bs: &crate::BootServices,
+    image_handle: *mut (),
+    debug_log: &mut DebugLog,
+) {
+    debug_log.add("Capturing reset mechanisms...", LOG_YELLOW);
+    let reset = capture_system_reset(bs, image_handle);
+    match reset.acpi {
+        Some(reg) => debug_log.add(
+            &alloc::format!("  ACPI reset: {:?} {:#x} <- {:#x}", reg.space, reg.address, reg.value),
+            LOG_GREEN,
+        ),
+        None => debug_log.add("  ACPI reset register: none", LOG_DARKGRAY),
+    }
+    if reset.efi_reset_system.is_some() {
+        debug_log.add("  UEFI ResetSystem: captured", LOG_GREEN);
+    }
+    morpheus_network::power::reset::install(reset);
+}
@@ -4,6 +4,9 @@ extern crate alloc;
 
 use crate::tui::logo::LOGO_LINES_RAW;
 use crate::tui::renderer::{Screen, EFI_BLACK, EFI_CYAN, EFI_LIGHTGREEN, EFI_RED, EFI_YELLOW};
+use morpheus_network::driver::intel::LinkMode;
+use morpheus_network::mainloop::PostActions;
+use morpheus_network::transfer::disk::Placement;
 
 /// Download commit configuration.
 pub struct DownloadCommitConfig {
@@ -13,6 +16,18 @@ pub struct DownloadCommitConfig {
     pub iso_size: u64,
     /// Name of the distro (for display)
     pub distro_name: alloc::string::String,
+    /// Where the ISO partition goes
+    pub placement: Placement,
+    /// What to do once the ISO is stored
+    pub post_actions: PostActions,
+    /// Beep codes on the PC speaker during the download
+    pub beeps: bool,
+    /// Download rate cap in bytes per second (0 = none)
+    pub rate_limit: u64,
+    /// Speeds the Intel PHY advertises
+    pub link_mode: LinkMode,
+    /// Checksum list next to the ISO to verify it against (empty = none)
+    pub checksums: &'static str,
 }
 
 /// Display countdown before committing to download.
@@ -31,6 +46,8 @@ pub fn display_commit_countdown(
     screen.put_str_at(screen.center_x(config.distro_name.len()), 5, &config.distro_name, EFI_CYAN, EFI_BLACK);
     let size_str = alloc::format!("Size: {} MB", config.iso_size / (1024 * 1024));
     screen.put_str_at(screen.center_x(size_str.len()), 6, &size_str, EFI_CYAN, EFI_BLACK);
+    let after_str = alloc::format!("When done: {}", config.post_actions.name());
+    screen.put_str_at(screen.center_x(after_str.len()), 7, &after_str, EFI_CYAN, EFI_BLACK);
 
     let warn1 = "WARNING: This will exit UEFI boot services!";
     screen.put_str_at(screen.center_x(warn1.len()), 8, warn1, EFI_RED, EFI_BLACK);
@@ -40,14 +57,17 @@ pub fn display_commit_countdown(
     // Countdown with UEFI Stall (1 second = 1,000,000 microseconds)
     let count3 = "Starting in 3...";
     screen.put_str_at(screen.center_x(count3.len()), 11, count3, EFI_YELLOW, EFI_BLACK);
+    screen.present();
     let _ = (bs.stall)(1_000_000);
 
     let count2 = "Starting in 2...";
     screen.put_str_at(screen.center_x(count2.len()), 11, count2, EFI_YELLOW, EFI_BLACK);
+    screen.present();
     let _ = (bs.stall)(1_000_000);
 
     let count1 = "Starting in 1...";
     screen.put_str_at(screen.center_x(count1.len()), 11, count1, EFI_YELLOW, EFI_BLACK);
+    screen.present();
     let _ = (bs.stall)(1_000_000);
 }
 
@@ -217,6 +237,7 @@ pub fn display_download_start(screen: &mut Screen, bs: &crate::BootServices) {
     );
 
     // Brief pause so user can see the message
+    screen.present();
     let _ = (bs.stall)(1_430_000); // 1.5 seconds
 }
 
@@ -237,6 +258,7 @@ pub fn display_error_and_halt(
     screen.put_str_at(screen.center_x(halt.len()), error_y + 3, halt, EFI_YELLOW, EFI_BLACK);
 
     // Give time to read
+    screen.present();
     let _ = (bs.stall)(5_000_000);
 
     loop {
@@ -6,11 +6,13 @@
 //! - `resources/` - Resource allocation (DMA, stack, handoff)
 //! - `uefi/` - UEFI utilities (timing, ESP, helpers)
 //! - `display` - UI and display functions
+//! - `review` - Pre-commit summary of what the download will run with
 
 pub mod commit_download;
 pub mod display;
 pub mod pci;
 pub mod resources;
+pub mod review;
 pub mod uefi;
 
 // Re-export main types and functions
@@ -20,3 +22,4 @@ pub use commit_download::{
     CommitResult,
 };
 pub use display::DownloadCommitConfig;
+pub use review::CommitReview;
@@ -7,4 +7,6 @@ pub mod nic_probe;
 
 pub use ahci_probe::probe_ahci_with_debug;
 pub use blk_probe::probe_virtio_blk_with_debug;
-pub use nic_probe::{probe_nic_with_debug, probe_virtio_nic_with_debug};
+pub use nic_probe::{
+    find_nic, probe_nic_with_debug, probe_virtio_nic_with_debug, LinkState, NicSummary,
+};
@@ -8,6 +8,7 @@ extern crate alloc;
 
 use super::config_space::{pci_read16, pci_read32, pci_read8, read_bar};
 use crate::boot::network_boot::{NicProbeResult, NIC_TYPE_INTEL, NIC_TYPE_VIRTIO};
+use morpheus_network::driver::LinkInfo;
 use crate::tui::renderer::{
     Screen, EFI_BLACK, EFI_CYAN, EFI_DARKGRAY, EFI_LIGHTGREEN, EFI_RED, EFI_YELLOW,
 };
@@ -474,17 +475,7 @@ fn probe_intel_nic_device(
     function: u8,
     dev_id: u16,
 ) -> NicProbeResult {
-    let name = match dev_id {
-        0x100E => "82540EM (QEMU e1000)",
-        0x10D3 => "82574L (QEMU e1000e)",
-        0x1502 => "I218-LM (ThinkPad)",
-        0x1503 => "I218-V",
-        0x1533 => "I210",
-        0x1539 => "I211",
-        0x156F => "I219-LM",
-        0x1570 => "I219-V",
-        _ => "Intel e1000e",
-    };
+    let name = intel_model_name(dev_id);
 
     screen.put_str_at(
         9,
@@ -546,6 +537,149 @@ fn probe_intel_nic_device(
     NicProbeResult::intel(mmio_base, bus, device, function)
 }
 
+/// Marketing name for an e1000e device ID.
+fn intel_model_name(dev_id: u16) -> &'static str {
+    match dev_id {
+        0x100E => "82540EM (QEMU e1000)",
+        0x10D3 => "82574L (QEMU e1000e)",
+        0x1502 => "I218-LM (ThinkPad)",
+        0x1503 => "I218-V",
+        0x1533 => "I210",
+        0x1539 => "I211",
+        0x156F => "I219-LM",
+        0x1570 => "I219-V",
+        _ => "Intel e1000e",
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// QUIET SCAN
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// e1000e STATUS register and its Full Duplex, Link Up and Speed bits.
+const E1000_STATUS: u64 = 0x08;
+const E1000_STATUS_FD: u32 = 1 << 0;
+const E1000_STATUS_LU: u32 = 1 << 1;
+const E1000_STATUS_SPEED_SHIFT: u32 = 6;
+
+/// Link state as far as it can be read without initialising the NIC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkState {
+    Up,
+    Down,
+    /// Not readable before the driver negotiates (VirtIO), or MMIO disabled
+    Unknown,
+}
+
+/// A supported NIC, as seen on the PCI bus.
+#[derive(Debug, Clone, Copy)]
+pub struct NicSummary {
+    pub bus: u8,
+    pub device: u8,
+    pub function: u8,
+    pub model: &'static str,
+    pub link: LinkState,
+    /// Speed and duplex, when the link is up and the NIC reports them
+    pub link_info: Option<LinkInfo>,
+}
+
+/// Find the NIC the download path would pick, without drawing anything or
+/// touching the device beyond config space and one status read.
+///
+/// Same search order as [`probe_nic_with_debug`].
+pub fn find_nic() -> Option<NicSummary> {
+    for bus in 0..=255u8 {
+        for device in 0..32u8 {
+            for function in 0..8u8 {
+                let id = pci_read32(bus, device, function, 0);
+
+                if id == 0xFFFFFFFF || id == 0 {
+                    if function == 0 {
+                        break;
+                    }
+                    continue;
+                }
+
+                let vendor = (id & 0xFFFF) as u16;
+                let dev_id = ((id >> 16) & 0xFFFF) as u16;
+
+                if vendor == VIRTIO_VENDOR
+                    && (dev_id == VIRTIO_NET_LEGACY || dev_id == VIRTIO_NET_MODERN)
+                {
+                    return Some(NicSummary {
+                        bus,
+                        device,
+                        function,
+                        model: "VirtIO-net",
+                        link: LinkState::Unknown,
+                        link_info: None,
+                    });
+                }
+
+                if vendor == INTEL_VENDOR && INTEL_E1000E_DEVICES.contains(&dev_id) {
+                    let (link, link_info) = intel_link_state(bus, device, function);
+                    return Some(NicSummary {
+                        bus,
+                        device,
+                        function,
+                        model: intel_model_name(dev_id),
+                        link,
+                        link_info,
+                    });
+                }
+
+                if function == 0 {
+                    let header = pci_read8(bus, device, function, 0x0E);
+                    if header & 0x80 == 0 {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Read Link Up, speed and duplex from the e1000e STATUS register, if its
+/// BAR is decoded. The speed is what firmware negotiated; the download
+/// path negotiates again.
+fn intel_link_state(bus: u8, device: u8, function: u8) -> (LinkState, Option<LinkInfo>) {
+    // Memory Space must already be enabled; the quiet scan doesn't change it
+    if pci_read16(bus, device, function, 0x04) & 0x02 == 0 {
+        return (LinkState::Unknown, None);
+    }
+
+    let bar0 = pci_read32(bus, device, function, 0x10);
+    if bar0 & 1 != 0 {
+        return (LinkState::Unknown, None);
+    }
+    let mmio_base = if (bar0 >> 1) & 0x3 == 2 {
+        let bar1 = pci_read32(bus, device, function, 0x14);
+        ((bar0 & 0xFFFFFFF0) as u64) | ((bar1 as u64) << 32)
+    } else {
+        (bar0 & 0xFFFFFFF0) as u64
+    };
+    if mmio_base == 0 {
+        return (LinkState::Unknown, None);
+    }
+
+    let status = unsafe { core::ptr::read_volatile((mmio_base + E1000_STATUS) as *const u32) };
+    if status & E1000_STATUS_LU == 0 {
+        return (LinkState::Down, None);
+    }
+    let speed_mbps = match (status >> E1000_STATUS_SPEED_SHIFT) & 0x3 {
+        0 => 10,
+        1 => 100,
+        _ => 1000,
+    };
+    let info = LinkInfo {
+        speed_mbps,
+        full_duplex: status & E1000_STATUS_FD != 0,
+    };
+    (LinkState::Up, Some(info))
+}
+
 /// Write to PCI configuration space (16-bit).
 fn pci_write16(bus: u8, device: u8, function: u8, offset: u8, value: u16) {
     use super::config_space::pci_write32;
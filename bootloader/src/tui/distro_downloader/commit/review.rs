@@ -0,0 +1,181 @@
+//! Pre-commit review.
+//!
+//! Everything the download will run with once boot services are gone,
+//! gathered while UEFI can still answer: the NIC the driver will pick, the
+//! disk and sector range the ISO partition will get, the ESP, the URL and
+//! the timeouts. Shown for an explicit yes before the point of no return,
+//! since nothing can be corrected after ExitBootServices.
+
+use super::display::DownloadCommitConfig;
+use super::pci::{find_nic, LinkState, NicSummary};
+use super::uefi::find_esp_lba;
+use crate::uefi::gpt_adapter::UefiBlockIoAdapter;
+use crate::BootServices;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use morpheus_core::disk::gpt_ops;
+use morpheus_core::disk::manager::DiskManager;
+use morpheus_core::iso::RAW_MANIFEST_SECTORS;
+use morpheus_network::driver::intel::LinkMode;
+use morpheus_network::mainloop::Timeouts;
+use morpheus_network::transfer::disk::GptOps;
+
+/// ESP start assumed when it can't be found (same as the commit path)
+const DEFAULT_ESP_LBA: u64 = 2048;
+
+/// The download's setup as it will run after ExitBootServices.
+#[derive(Debug, Clone)]
+pub struct CommitReview {
+    pub nic: Option<NicSummary>,
+    /// Model of the disk holding the ESP, which the ISO is written to
+    pub disk: Option<String>,
+    /// First and last sector the ISO partition will take
+    pub target: Option<(u64, u64)>,
+    pub esp_lba: Option<u64>,
+    pub url: String,
+    pub iso_size: u64,
+    /// Free space on the target disk
+    pub free_bytes: u64,
+    pub link_mode: LinkMode,
+    /// Checksum list the expected hash comes from (empty = none)
+    pub checksums: &'static str,
+}
+
+impl CommitReview {
+    /// Work out where the download described by `config` will go.
+    pub unsafe fn gather(
+        bs: &BootServices,
+        image_handle: *mut (),
+        config: &DownloadCommitConfig,
+    ) -> Self {
+        let mut review = Self {
+            nic: find_nic(),
+            disk: None,
+            target: None,
+            esp_lba: find_esp_lba(bs, image_handle),
+            url: config.iso_url.clone(),
+            iso_size: config.iso_size,
+            free_bytes: 0,
+            link_mode: config.link_mode,
+            checksums: config.checksums,
+        };
+
+        let mut disks = DiskManager::new();
+        if crate::uefi::disk::enumerate_disks(bs, &mut disks).is_err() {
+            return review;
+        }
+        let index = (0..disks.disk_count())
+            .find(|&i| disks.get_disk(i).is_some_and(|d| d.boot_disk))
+            .unwrap_or(0);
+        let Some(disk) = disks.get_disk(index) else {
+            return review;
+        };
+        review.disk = Some(String::from(if disk.identity.is_known() {
+            disk.identity.model()
+        } else {
+            "unidentified disk"
+        }));
+
+        let Ok(block_io_ptr) = crate::uefi::disk::get_disk_protocol(bs, index) else {
+            return review;
+        };
+        let block_size = (*(*block_io_ptr).media).block_size as usize;
+        if let Ok(adapter) = UefiBlockIoAdapter::new(&mut *block_io_ptr) {
+            review.free_bytes = gpt_ops::calculate_total_free_space(adapter, block_size)
+                .map_or(0, |mb| mb * 1024 * 1024);
+        }
+        // Same size GPT prep asks for: the image plus the raw manifest copy
+        let needed = config.iso_size.div_ceil(512) + RAW_MANIFEST_SECTORS;
+        if let Ok(mut adapter) = UefiBlockIoAdapter::new(&mut *block_io_ptr) {
+            review.target =
+                GptOps::find_free_space(&mut adapter, config.placement.policy, needed).ok();
+        }
+        review
+    }
+
+    /// Reasons the download can't work as configured. Committing is only
+    /// offered when there are none.
+    pub fn blockers(&self) -> Vec<&'static str> {
+        let mut blockers = Vec::new();
+        if self.nic.is_none() {
+            blockers.push("No supported network controller");
+        }
+        if self.disk.is_none() {
+            blockers.push("No disk to write the ISO to");
+        } else if self.target.is_none() {
+            blockers.push("No free gap on the disk is large enough");
+        }
+        blockers
+    }
+
+    /// Label and value rows for the review screen.
+    pub fn rows(&self) -> Vec<(&'static str, String)> {
+        let nic = match self.nic {
+            Some(nic) => format!(
+                "{} at {:02x}:{:02x}.{}{}",
+                nic.model,
+                nic.bus,
+                nic.device,
+                nic.function,
+                match (nic.link, nic.link_info) {
+                    (LinkState::Up, Some(info)) => format!(", link up ({})", info),
+                    (LinkState::Up, None) => String::from(", link up"),
+                    (LinkState::Down, _) => String::from(", NO LINK"),
+                    (LinkState::Unknown, _) => String::new(),
+                }
+            ),
+            None => String::from("none found"),
+        };
+        let target = match self.target {
+            Some((start, end)) => format!("sectors {} - {}", start, end),
+            None => String::from("no room"),
+        };
+        let esp = match self.esp_lba {
+            Some(lba) => format!("LBA {}", lba),
+            None => format!("not found, assuming LBA {}", DEFAULT_ESP_LBA),
+        };
+        // A 1 Hz clock turns the tick counts into seconds
+        let timeouts = Timeouts::new(1);
+
+        let link = match self.link_mode {
+            LinkMode::Auto => String::from("auto-negotiate"),
+            mode => format!("{} only (Intel NICs)", mode.name()),
+        };
+
+        alloc::vec![
+            ("NIC", nic),
+            ("Link", link),
+            ("Disk", self.disk.clone().unwrap_or_else(|| String::from("none found"))),
+            ("Target", target),
+            ("ESP", esp),
+            ("URL", self.url.clone()),
+            (
+                "Verify",
+                match self.checksums {
+                    "" => String::from("record the hash only"),
+                    list => format!("SHA-256 from {} next to the ISO", list),
+                },
+            ),
+            (
+                "Size",
+                format!(
+                    "{} MB of {} MB free",
+                    self.iso_size / (1024 * 1024),
+                    self.free_bytes / (1024 * 1024)
+                ),
+            ),
+            (
+                "Timeouts",
+                format!(
+                    "link {}s, DHCP {}s, DNS {}s, connect {}s, idle {}s",
+                    timeouts.link_wait(),
+                    timeouts.dhcp(),
+                    timeouts.dns(),
+                    timeouts.tcp_connect(),
+                    timeouts.http_idle()
+                ),
+            ),
+        ]
+    }
+}
@@ -1,70 +1,120 @@
 //! UEFI utility functions for boot services exit.
+//!
+//! ExitBootServices fails with EFI_INVALID_PARAMETER whenever the map key
+//! is stale, and firmware with timers or drivers still running can change
+//! the memory map between GetMemoryMap and the exit. Per the UEFI spec the
+//! caller then fetches the map again and retries, calling nothing else in
+//! between: no allocations, no console output. The retry loop therefore
+//! writes into a buffer sized up front and logs to the serial port only,
+//! which touches no firmware state.
 
-/// Exit boot services with memory map handling.
+use morpheus_hwinit::serial::{newline, put_hex32, put_hex64, puts};
+
+/// Attempts before giving up on a memory map that keeps changing
+pub const MAX_EBS_ATTEMPTS: u32 = 8;
+
+const EFI_INVALID_PARAMETER: usize = 0x8000_0000_0000_0002;
+const EFI_BUFFER_TOO_SMALL: usize = 0x8000_0000_0000_0005;
+
+/// Why ExitBootServices could not be completed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EbsError {
+    /// GetMemoryMap needs more room than the caller's buffer has
+    BufferTooSmall { needed: usize },
+    /// GetMemoryMap failed with this status
+    MemoryMap(usize),
+    /// ExitBootServices failed with this status
+    Exit(usize),
+    /// The map changed on every attempt
+    MapChurn,
+}
+
+/// Memory map current at the moment boot services ended.
+#[derive(Debug, Clone, Copy)]
+pub struct FinalMemoryMap {
+    /// Bytes of the caller's buffer holding descriptors
+    pub size: usize,
+    pub desc_size: usize,
+    pub desc_version: u32,
+    /// ExitBootServices calls it took
+    pub attempts: u32,
+}
+
+/// Exit boot services, re-fetching the memory map into `map_buf` and
+/// retrying while the firmware keeps changing it.
 ///
-/// Returns Ok(()) on success, Err(()) on failure.
-/// Also switches the allocator to post-EBS mode.
+/// On success `map_buf` holds the final map, and the allocator has been
+/// switched to post-EBS mode (UEFI allocate_pool is gone). On failure
+/// boot services may already be partly shut down; the caller can only
+/// halt.
 pub unsafe fn exit_boot_services_with_retry(
     bs: &crate::BootServices,
     image_handle: *mut (),
-) -> Result<(), ()> {
+    map_buf: &mut [u8],
+) -> Result<FinalMemoryMap, EbsError> {
     // Disable watchdog timer BEFORE GetMemoryMap
     let _ = (bs.set_watchdog_timer)(0, 0, 0, core::ptr::null());
 
-    let mut mmap_size: usize = 4096;
-    let mut mmap_buf = [0u8; 8192];
     let mut map_key: usize = 0;
     let mut desc_size: usize = 0;
     let mut desc_version: u32 = 0;
 
-    // First call to get required size
-    let _ = (bs.get_memory_map)(
-        &mut mmap_size,
-        mmap_buf.as_mut_ptr(),
-        &mut map_key,
-        &mut desc_size,
-        &mut desc_version,
-    );
-
-    // Increase buffer size to be safe
-    mmap_size += 2048;
-
-    // Second call with proper size
-    let status = (bs.get_memory_map)(
-        &mut mmap_size,
-        mmap_buf.as_mut_ptr(),
-        &mut map_key,
-        &mut desc_size,
-        &mut desc_version,
-    );
-
-    if status != 0 {
-        return Err(());
-    }
-
-    // Exit boot services IMMEDIATELY
-    let status = (bs.exit_boot_services)(image_handle, map_key);
-
-    if status != 0 {
-        // Retry once with fresh map
-        let _ = (bs.get_memory_map)(
-            &mut mmap_size,
-            mmap_buf.as_mut_ptr(),
+    for attempt in 1..=MAX_EBS_ATTEMPTS {
+        let mut size = map_buf.len();
+        let status = (bs.get_memory_map)(
+            &mut size,
+            map_buf.as_mut_ptr(),
             &mut map_key,
             &mut desc_size,
             &mut desc_version,
         );
+
+        puts("[EBS] attempt ");
+        put_hex32(attempt);
+        puts(": GetMemoryMap status=");
+        put_hex64(status as u64);
+        puts(" size=");
+        put_hex64(size as u64);
+        puts(" key=");
+        put_hex64(map_key as u64);
+        newline();
+
+        match status {
+            0 => {}
+            // Growing the buffer would allocate, which changes the map
+            // again and is not allowed after a failed exit anyway
+            EFI_BUFFER_TOO_SMALL => {
+                puts("[EBS] memory map buffer too small\n");
+                return Err(EbsError::BufferTooSmall { needed: size });
+            }
+            _ => return Err(EbsError::MemoryMap(status)),
+        }
+
         let status = (bs.exit_boot_services)(image_handle, map_key);
-        if status != 0 {
-            return Err(());
+        puts("[EBS] ExitBootServices status=");
+        put_hex64(status as u64);
+        newline();
+
+        match status {
+            0 => {
+                // CRITICAL: Switch allocator to post-EBS mode (uses linked_list_allocator)
+                // UEFI allocate_pool is no longer available after ExitBootServices
+                crate::uefi_allocator::switch_to_post_ebs();
+                return Ok(FinalMemoryMap {
+                    size,
+                    desc_size,
+                    desc_version,
+                    attempts: attempt,
+                });
+            }
+            // Stale map key: the map changed under us, fetch it again
+            EFI_INVALID_PARAMETER => {}
+            _ => return Err(EbsError::Exit(status)),
         }
     }
 
-    // CRITICAL: Switch allocator to post-EBS mode (uses linked_list_allocator)
-    // UEFI allocate_pool is no longer available after ExitBootServices
-    crate::uefi_allocator::switch_to_post_ebs();
-
-    Ok(())
+    puts("[EBS] memory map kept changing, giving up\n");
+    Err(EbsError::MapChurn)
 }
 
 /// Leak a string to make it 'static.
@@ -2,8 +2,10 @@
 
 pub mod esp;
 pub mod helpers;
+pub mod reset;
 pub mod timing;
 
 pub use esp::find_esp_lba;
-pub use helpers::{exit_boot_services_with_retry, leak_string};
-pub use timing::calibrate_tsc_with_stall;
+pub use helpers::{exit_boot_services_with_retry, leak_string, EbsError, FinalMemoryMap};
+pub use reset::{acpi_rsdp, capture_system_reset};
+pub use timing::{calibrate_tsc, calibrate_tsc_with_stall};
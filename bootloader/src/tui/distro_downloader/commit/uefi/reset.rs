@@ -0,0 +1,137 @@
+//! Capture platform reset mechanisms before ExitBootServices.
+//!
+//! The bare-metal session can only end in a reset. The ACPI reset register
+//! lives in the FADT (found via the RSDP in the configuration table) and
+//! `ResetSystem` in the runtime services table; both are looked up here
+//! while the system table is still trustworthy.
+
+use core::ffi::c_void;
+use core::ptr;
+
+use morpheus_network::power::reset::{AcpiResetRegister, EfiResetSystem, SystemReset};
+use morpheus_network::power::PsciConduit;
+
+const EFI_LOADED_IMAGE_PROTOCOL_GUID: [u8; 16] = [
+    0xa1, 0x31, 0x1b, 0x5b, 0x62, 0x95, 0xd2, 0x11, 0x8e, 0x3f, 0x00, 0xa0, 0xc9, 0x69, 0x72, 0x3b,
+];
+
+const EFI_ACPI_TABLE_GUID: [u8; 16] = [
+    0x30, 0x2d, 0x9d, 0xeb, 0x88, 0x2d, 0xd3, 0x11, 0x9a, 0x16, 0x00, 0x90, 0x27, 0x3f, 0xc1, 0x4d,
+];
+
+const EFI_ACPI_20_TABLE_GUID: [u8; 16] = [
+    0x71, 0xe8, 0x68, 0x88, 0xf1, 0x04, 0xd3, 0x11, 0xbc, 0x22, 0x00, 0x80, 0xc7, 0x3c, 0x88, 0x81,
+];
+
+#[repr(C)]
+struct LoadedImageProtocol {
+    revision: u32,
+    parent_handle: *mut c_void,
+    system_table: *const RawSystemTable,
+}
+
+#[repr(C)]
+struct RawSystemTable {
+    _header: [u8; 24],
+    _firmware_vendor: *const u16,
+    _firmware_revision: u32,
+    _console_in_handle: *const (),
+    _con_in: *const (),
+    _console_out_handle: *const (),
+    _con_out: *const (),
+    _stderr_handle: *const (),
+    _stderr: *const (),
+    runtime_services: *const RawRuntimeServices,
+    _boot_services: *const (),
+    number_of_table_entries: usize,
+    configuration_table: *const RawConfigurationTable,
+}
+
+#[repr(C)]
+struct RawRuntimeServices {
+    _header: [u8; 24],
+    // GetTime .. GetNextHighMonotonicCount
+    _services: [usize; 10],
+    reset_system: Option<EfiResetSystem>,
+}
+
+#[repr(C)]
+struct RawConfigurationTable {
+    vendor_guid: [u8; 16],
+    vendor_table: *const (),
+}
+
+/// Look up the ACPI reset register, PSCI conduit and `ResetSystem`.
+///
+/// Either may be missing; `reset_system()` falls back to legacy ports.
+pub unsafe fn capture_system_reset(bs: &crate::BootServices, image_handle: *mut ()) -> SystemReset {
+    let Some(st) = system_table(bs, image_handle) else {
+        return SystemReset::default();
+    };
+
+    let efi_reset_system = if st.runtime_services.is_null() {
+        None
+    } else {
+        (*st.runtime_services).reset_system
+    };
+
+    let rsdp = find_rsdp(st);
+    let acpi = rsdp.and_then(|rsdp| AcpiResetRegister::from_rsdp(rsdp));
+    let psci = rsdp.and_then(|rsdp| PsciConduit::from_rsdp(rsdp));
+
+    SystemReset {
+        acpi,
+        psci,
+        efi_reset_system,
+    }
+}
+
+/// ACPI RSDP address, or 0 if the firmware publishes none.
+///
+/// hwinit walks the ACPI tables after ExitBootServices (MADT for SMP).
+pub unsafe fn acpi_rsdp(bs: &crate::BootServices, image_handle: *mut ()) -> u64 {
+    system_table(bs, image_handle)
+        .and_then(|st| find_rsdp(st))
+        .unwrap_or(0)
+}
+
+/// The system table, via our loaded image.
+unsafe fn system_table(
+    bs: &crate::BootServices,
+    image_handle: *mut (),
+) -> Option<&'static RawSystemTable> {
+    let mut loaded_image_ptr: *mut c_void = ptr::null_mut();
+    let status = (bs.handle_protocol)(
+        image_handle,
+        &EFI_LOADED_IMAGE_PROTOCOL_GUID,
+        &mut loaded_image_ptr as *mut *mut c_void as *mut *mut (),
+    );
+    if status != 0 || loaded_image_ptr.is_null() {
+        return None;
+    }
+    let loaded_image = &*(loaded_image_ptr as *const LoadedImageProtocol);
+    if loaded_image.system_table.is_null() {
+        return None;
+    }
+    Some(&*loaded_image.system_table)
+}
+
+/// RSDP address from the configuration table, preferring ACPI 2.0+.
+unsafe fn find_rsdp(st: &RawSystemTable) -> Option<u64> {
+    let mut acpi1 = None;
+    let mut entry = st.configuration_table;
+    for _ in 0..st.number_of_table_entries {
+        if entry.is_null() {
+            break;
+        }
+        let config = &*entry;
+        if config.vendor_guid == EFI_ACPI_20_TABLE_GUID {
+            return Some(config.vendor_table as u64);
+        }
+        if config.vendor_guid == EFI_ACPI_TABLE_GUID {
+            acpi1 = Some(config.vendor_table as u64);
+        }
+        entry = entry.add(1);
+    }
+    acpi1
+}
@@ -1,9 +1,30 @@
 //! TSC (Time Stamp Counter) calibration using UEFI services.
 
+use morpheus_network::boot::handoff::{
+    cpuid_tsc_frequency, has_invariant_tsc, reconcile_tsc_frequency, TscCalibration,
+};
+
 /// Calibrate TSC frequency using UEFI Stall service.
 ///
 /// Must be called BEFORE ExitBootServices.
 pub fn calibrate_tsc_with_stall(bs: &crate::BootServices) -> u64 {
+    calibrate_tsc(bs).frequency
+}
+
+/// Calibrate TSC, cross-checking CPUID leaf 0x15/0x16 against UEFI Stall.
+///
+/// Some firmware implements Stall inaccurately, so the timed value alone
+/// is not trusted. The returned calibration records both inputs and
+/// their disagreement so the caller can log it.
+///
+/// Must be called BEFORE ExitBootServices.
+pub fn calibrate_tsc(bs: &crate::BootServices) -> TscCalibration {
+    let measured = measure_tsc_with_stall(bs);
+    reconcile_tsc_frequency(cpuid_tsc_frequency(), measured, has_invariant_tsc())
+}
+
+/// Time a 10ms UEFI Stall with the TSC. Returns raw ticks/second, unchecked.
+fn measure_tsc_with_stall(bs: &crate::BootServices) -> u64 {
     let start_tsc = read_tsc();
 
     // UEFI Stall takes microseconds - stall for 10ms (10,000 us)
@@ -15,15 +36,7 @@ pub fn calibrate_tsc_with_stall(bs: &crate::BootServices) -> u64 {
     let ticks_10ms = end_tsc.saturating_sub(start_tsc);
 
     // Extrapolate to 1 second (multiply by 100)
-    let tsc_freq = ticks_10ms.saturating_mul(100);
-
-    // Sanity check: expect 1-10 GHz range
-    if tsc_freq < 1_000_000_000 || tsc_freq > 10_000_000_000 {
-        // Fallback to 2.5 GHz if result seems wrong
-        2_500_000_000
-    } else {
-        tsc_freq
-    }
+    ticks_10ms.saturating_mul(100)
 }
 
 /// Read TSC (Time Stamp Counter).
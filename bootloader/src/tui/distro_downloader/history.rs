@@ -0,0 +1,171 @@
+//! Download history report.
+//!
+//! Turns the history records the download path leaves in `/.iso` into text
+//! for the viewer: every download, newest first, then a per-mirror summary
+//! for comparing mirror speed.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use morpheus_core::iso::{DownloadRecord, Verification};
+
+/// Per-mirror totals. Downloads without a recorded duration count towards
+/// `downloads` but not the average.
+struct MirrorStats<'a> {
+    host: &'a str,
+    downloads: usize,
+    timed_bytes: u64,
+    timed_ms: u64,
+}
+
+impl MirrorStats<'_> {
+    fn throughput(&self) -> u64 {
+        if self.timed_ms == 0 {
+            return 0;
+        }
+        ((self.timed_bytes as u128 * 1000) / self.timed_ms as u128) as u64
+    }
+}
+
+/// Report text for `records`, given oldest first.
+pub fn report(records: &[DownloadRecord]) -> String {
+    if records.is_empty() {
+        return String::from(
+            "No downloads recorded yet.\n\n\
+             Each completed download adds an entry here.",
+        );
+    }
+
+    let mut text = format!("{} downloads recorded on this machine\n\n", records.len());
+
+    for (i, record) in records.iter().enumerate().rev() {
+        text.push_str(&format!("{:>3}  {}\n", i + 1, record.name_str()));
+        text.push_str(&format!(
+            "     {} MB in {}, {}, {}\n",
+            record.size / (1024 * 1024),
+            format_duration(record.duration_ms),
+            format_rate(record.throughput()),
+            verification_label(record.verification)
+        ));
+        text.push_str(&format!("     {}\n\n", record.mirror_str()));
+    }
+
+    text.push_str("Mirrors (fastest first)\n\n");
+    for stats in mirror_stats(records) {
+        text.push_str(&format!(
+            "  {:<32} {:>3} downloads  avg {}\n",
+            stats.host,
+            stats.downloads,
+            format_rate(stats.throughput())
+        ));
+    }
+
+    text
+}
+
+fn mirror_stats(records: &[DownloadRecord]) -> Vec<MirrorStats<'_>> {
+    let mut stats: Vec<MirrorStats> = Vec::new();
+
+    for record in records {
+        let host = record.mirror_host();
+        let index = match stats.iter().position(|s| s.host == host) {
+            Some(index) => index,
+            None => {
+                stats.push(MirrorStats {
+                    host,
+                    downloads: 0,
+                    timed_bytes: 0,
+                    timed_ms: 0,
+                });
+                stats.len() - 1
+            }
+        };
+        let entry = &mut stats[index];
+        entry.downloads += 1;
+        if record.duration_ms > 0 {
+            entry.timed_bytes += record.size;
+            entry.timed_ms += record.duration_ms;
+        }
+    }
+
+    stats.sort_by_key(|s| core::cmp::Reverse(s.throughput()));
+    stats
+}
+
+fn verification_label(verification: Verification) -> &'static str {
+    match verification {
+        Verification::Unchecked => "not verified",
+        Verification::Verified => "SHA256 OK",
+        Verification::Mismatch => "SHA256 MISMATCH",
+    }
+}
+
+/// "1h02m", "3m07s" or "42s"; "?" if no time was recorded.
+fn format_duration(ms: u64) -> String {
+    if ms == 0 {
+        return String::from("?");
+    }
+    let secs = ms.div_ceil(1000);
+    if secs >= 3600 {
+        format!("{}h{:02}m", secs / 3600, secs % 3600 / 60)
+    } else if secs >= 60 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+/// Bytes per second as "12.3 MB/s" or "850 KB/s"; "? MB/s" if unknown.
+fn format_rate(bytes_per_sec: u64) -> String {
+    const MB: u64 = 1024 * 1024;
+    if bytes_per_sec == 0 {
+        String::from("? MB/s")
+    } else if bytes_per_sec >= MB {
+        let tenths = bytes_per_sec * 10 / MB;
+        format!("{}.{} MB/s", tenths / 10, tenths % 10)
+    } else {
+        format!("{} KB/s", bytes_per_sec / 1024)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(mirror: &str, size_mb: u64, secs: u64) -> DownloadRecord {
+        DownloadRecord::new(
+            "test.iso",
+            mirror,
+            size_mb * 1024 * 1024,
+            secs * 1000,
+            Verification::Verified,
+        )
+    }
+
+    #[test]
+    fn test_format_helpers() {
+        assert_eq!(format_duration(0), "?");
+        assert_eq!(format_duration(42_000), "42s");
+        assert_eq!(format_duration(187_000), "3m07s");
+        assert_eq!(format_duration(3_720_000), "1h02m");
+        assert_eq!(format_rate(25 * 1024 * 1024 / 2), "12.5 MB/s");
+        assert_eq!(format_rate(850 * 1024), "850 KB/s");
+    }
+
+    #[test]
+    fn test_mirror_stats_sorted_by_speed() {
+        let records = [
+            record("http://slow.example/a.iso", 100, 100),
+            record("http://fast.example/a.iso", 100, 10),
+            record("http://slow.example:8080/b.iso", 100, 0),
+        ];
+        let stats = mirror_stats(&records);
+
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].host, "fast.example");
+        assert_eq!(stats[0].throughput(), 10 * 1024 * 1024);
+        assert_eq!(stats[1].host, "slow.example");
+        assert_eq!(stats[1].downloads, 2);
+        assert_eq!(stats[1].throughput(), 1024 * 1024);
+    }
+}
@@ -10,21 +10,69 @@
 //! └── .iso/
 //!     ├── A1B2C3D4.MFS    (e.g., for tails-6.10.iso)
 //!     ├── E5F6A7B8.MFS    (e.g., for ubuntu-24.04.iso)
+//!     ├── DL0000.HST      (download history, one record per download)
+//!     ├── POLICY.CFG      (retention policy for the store)
+//!     ├── BOOTREQ.CFG     (ISO to select or boot, left by a download)
 //!     └── ...
 //! ```
+//!
+//! Downloads may also keep a raw copy of the manifest in the last sectors
+//! of the ISO's chunk partition. Loading falls back to those copies for
+//! ISOs whose `.MFS` file is missing or unreadable.
 
 use crate::uefi::file_system::{
     ascii_to_utf16, close_file, create_directory, create_file, flush_file, get_loaded_image,
-    open_file_read, write_file, FileProtocol, EFI_FILE_MODE_READ,
+    open_file_read, read_esp_file, write_file, FileProtocol, EFI_FILE_MODE_READ,
 };
+use crate::uefi::gpt_adapter::UefiBlockIoAdapter;
 use crate::BootServices;
 use alloc::string::String;
+use alloc::vec;
 use alloc::vec::Vec;
-use morpheus_core::iso::{IsoManifest, IsoStorageManager, MAX_MANIFEST_SIZE};
+use morpheus_core::disk::gpt_ops;
+use morpheus_core::disk::manager::DiskManager;
+use morpheus_core::disk::partition::PartitionTable;
+use morpheus_core::iso::{
+    raw_manifest_lba, BootMenuConfig, BootRequest, DownloadRecord, ExitReport, IsoError,
+    IsoManifest, IsoStorageManager, RetentionPolicy, BOOT_MENU_SIZE, BOOT_REQUEST_SIZE,
+    EXIT_REPORT_SIZE, HISTORY_EXT, HISTORY_RECORD_SIZE, MAX_HISTORY_RECORDS, MAX_MANIFEST_SIZE,
+    POLICY_SIZE, RAW_MANIFEST_SECTORS, RAW_MANIFEST_SIZE,
+};
+use morpheus_gpt::part_type;
 
 /// Manifest directory path on ESP (without leading backslash for open)
 const MANIFEST_DIR: &str = "\\.iso";
 
+/// Checksum list for stored ISOs, in `sha256sum` format
+pub const CHECKSUMS_PATH: &str = "\\.iso\\SHA256SUMS";
+
+/// Static hostname mappings for the download, in `/etc/hosts` format
+pub const HOSTS_PATH: &str = "\\.iso\\HOSTS";
+
+/// HTTP proxy for the download, one "host:port" line
+pub const PROXY_PATH: &str = "\\.iso\\PROXY";
+
+/// Retention policy file (`morpheus_core::iso::POLICY_PATH`)
+pub const POLICY_FILE: &str = "\\.iso\\POLICY.CFG";
+
+/// Boot request left by a download (`morpheus_core::iso::BOOT_REQUEST_PATH`)
+pub const BOOT_REQUEST_FILE: &str = "\\.iso\\BOOTREQ.CFG";
+
+/// Outcome of the last download session (`morpheus_core::iso::EXIT_REPORT_PATH`)
+pub const EXIT_REPORT_FILE: &str = "\\.iso\\LASTRUN.RPT";
+
+/// Exit report once it has been shown (`morpheus_core::iso::EXIT_REPORT_ARCHIVE_PATH`)
+pub const EXIT_REPORT_ARCHIVE_FILE: &str = "\\.iso\\LASTRUN.OLD";
+
+/// Boot menu timeout and default (`morpheus_core::iso::BOOT_MENU_PATH`)
+pub const BOOT_MENU_FILE: &str = "\\.iso\\BOOTMENU.CFG";
+
+/// Largest checksum file the viewer loads
+const MAX_CHECKSUMS_SIZE: usize = 64 * 1024;
+
+/// Largest hosts file loaded
+const MAX_HOSTS_SIZE: usize = 8 * 1024;
+
 /// Maximum number of manifests to scan
 const MAX_MANIFESTS: usize = 16;
 
@@ -50,6 +98,8 @@ pub enum ManifestIoError {
     DeserializeFailed,
     /// File not found
     NotFound,
+    /// Failed to remove a chunk partition from the GPT
+    PartitionDeleteFailed,
 }
 
 /// Persist a manifest to the ESP filesystem
@@ -114,7 +164,8 @@ pub unsafe fn persist_manifest(
 
 /// Load all manifests from ESP and populate a storage manager
 ///
-/// Scans `/.iso/` for .MFS manifest files and loads them.
+/// Scans `/.iso/` for .MFS manifest files and loads them, then adds any
+/// ISO that only has a raw copy left (see [`recover_raw_manifests`]).
 ///
 /// # Arguments
 /// * `bs` - UEFI Boot Services
@@ -143,7 +194,7 @@ pub unsafe fn load_manifests_from_esp(
         let _ = close_file(root);
         // Directory doesn't exist yet - that's OK, no manifests
         morpheus_core::logger::log("Manifest dir not found or cannot open");
-        return Ok(0);
+        return Ok(recover_raw_manifests(bs, storage, MAX_MANIFESTS));
     }
 
     morpheus_core::logger::log("Scanning manifest directory...");
@@ -231,9 +282,95 @@ pub unsafe fn load_manifests_from_esp(
 
     morpheus_core::logger::log(alloc::format!("Loaded {} manifests from ESP", count).leak());
 
+    count += recover_raw_manifests(bs, storage, MAX_MANIFESTS - count);
     Ok(count)
 }
 
+/// Add manifests found only as raw copies at the end of chunk partitions
+///
+/// Reads the tail of every `ISO_CHUNK` partition on every disk (never
+/// anyone else's partitions) and adds each valid copy whose ISO is not in
+/// `storage` yet, i.e. whose `.MFS` file is missing or failed to load.
+/// Damaged copies are skipped.
+///
+/// # Returns
+/// Number of manifests added (at most `limit`)
+pub unsafe fn recover_raw_manifests(
+    bs: &BootServices,
+    storage: &mut IsoStorageManager,
+    limit: usize,
+) -> usize {
+    let mut disks = DiskManager::new();
+    if limit == 0 || crate::uefi::disk::enumerate_disks(bs, &mut disks).is_err() {
+        return 0;
+    }
+
+    let mut count = 0;
+    let mut buffer = vec![0u8; RAW_MANIFEST_SIZE];
+    for index in 0..disks.disk_count() {
+        let Ok(block_io_ptr) = crate::uefi::disk::get_disk_protocol(bs, index) else {
+            continue;
+        };
+        // Raw copies are written in 512-byte sectors
+        let block_size = (*(*block_io_ptr).media).block_size;
+        if block_size != 512 {
+            continue;
+        }
+
+        let mut table = PartitionTable::new();
+        let Ok(adapter) = UefiBlockIoAdapter::new(&mut *block_io_ptr) else {
+            continue;
+        };
+        if gpt_ops::scan_partitions(adapter, &mut table, block_size as usize).is_err() {
+            continue;
+        }
+
+        for part in table.iter() {
+            if part.type_guid != part_type::ISO_CHUNK
+                || part.end_lba - part.start_lba + 1 < RAW_MANIFEST_SECTORS
+            {
+                continue;
+            }
+            let lba = raw_manifest_lba(part.end_lba);
+            if (*block_io_ptr)
+                .read_sectors(lba, RAW_MANIFEST_SECTORS, &mut buffer)
+                .is_err()
+            {
+                continue;
+            }
+            let manifest = match IsoManifest::deserialize_raw(&buffer) {
+                Ok(manifest) => manifest,
+                Err(IsoError::InvalidManifest) => continue,
+                Err(e) => {
+                    morpheus_core::logger::log(
+                        alloc::format!("Raw manifest at LBA {} damaged: {:?}", lba, e).leak(),
+                    );
+                    continue;
+                }
+            };
+            if storage.find_by_name(manifest.name_str()).is_some() {
+                continue;
+            }
+
+            morpheus_core::logger::log(
+                alloc::format!(
+                    "Recovered manifest '{}' from raw copy at LBA {}",
+                    manifest.name_str(),
+                    lba
+                )
+                .leak(),
+            );
+            if storage.add_entry(manifest).is_ok() {
+                count += 1;
+                if count >= limit {
+                    return count;
+                }
+            }
+        }
+    }
+    count
+}
+
 /// Load a single manifest file by name
 unsafe fn load_single_manifest(
     root: *mut FileProtocol,
@@ -326,6 +463,187 @@ fn extract_filename_from_file_info(buffer: &[u8]) -> String {
     filename
 }
 
+/// Read the checksum file from the ESP
+///
+/// # Returns
+/// * `Ok(text)` - File contents (invalid UTF-8 replaced)
+/// * `Err(ManifestIoError::NotFound)` if there is no checksum file
+pub unsafe fn load_checksums(bs: &BootServices, image_handle: *mut ()) -> ManifestIoResult<String> {
+    let data = read_esp_file(bs, image_handle, CHECKSUMS_PATH, MAX_CHECKSUMS_SIZE)
+        .map_err(|_| ManifestIoError::NotFound)?;
+    Ok(String::from_utf8_lossy(&data).into_owned())
+}
+
+/// Read the hosts file from the ESP
+///
+/// # Returns
+/// * `Ok(text)` - File contents (invalid UTF-8 replaced)
+/// * `Err(ManifestIoError::NotFound)` if there is no hosts file
+pub unsafe fn load_hosts(bs: &BootServices, image_handle: *mut ()) -> ManifestIoResult<String> {
+    let data = read_esp_file(bs, image_handle, HOSTS_PATH, MAX_HOSTS_SIZE)
+        .map_err(|_| ManifestIoError::NotFound)?;
+    Ok(String::from_utf8_lossy(&data).into_owned())
+}
+
+/// Read the proxy setting from the ESP: the first line that isn't blank
+/// or a `#` comment, trimmed
+///
+/// # Returns
+/// * `Ok(proxy)` - "host:port" as written (not validated)
+/// * `Err(ManifestIoError::NotFound)` if there is no proxy file or no entry
+pub unsafe fn load_proxy(bs: &BootServices, image_handle: *mut ()) -> ManifestIoResult<String> {
+    let data = read_esp_file(bs, image_handle, PROXY_PATH, MAX_HOSTS_SIZE)
+        .map_err(|_| ManifestIoError::NotFound)?;
+    String::from_utf8_lossy(&data)
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .ok_or(ManifestIoError::NotFound)
+}
+
+/// Load the store's retention policy; no limits if there is none or it
+/// fails to verify.
+pub unsafe fn load_policy(bs: &BootServices, image_handle: *mut ()) -> RetentionPolicy {
+    read_esp_file(bs, image_handle, POLICY_FILE, POLICY_SIZE)
+        .ok()
+        .and_then(|data| RetentionPolicy::deserialize(&data).ok())
+        .unwrap_or_default()
+}
+
+/// Write the store's retention policy to the ESP
+pub unsafe fn save_policy(
+    bs: &BootServices,
+    image_handle: *mut (),
+    policy: &RetentionPolicy,
+) -> ManifestIoResult<()> {
+    let root = get_esp_root(bs, image_handle)?;
+
+    let mut iso_path = [0u16; 32];
+    ascii_to_utf16(MANIFEST_DIR, &mut iso_path);
+    let _ = create_directory(root, &iso_path); // Ignore error if exists
+
+    let mut path_utf16 = [0u16; 64];
+    ascii_to_utf16(POLICY_FILE, &mut path_utf16);
+    let file = create_file(root, &path_utf16).map_err(|_| ManifestIoError::FileCreateFailed)?;
+
+    let mut buffer = [0u8; POLICY_SIZE];
+    let size = policy
+        .serialize(&mut buffer)
+        .map_err(|_| ManifestIoError::SerializeFailed)?;
+    let written = write_file(file, &buffer[..size]).map_err(|_| ManifestIoError::WriteFailed);
+
+    let _ = flush_file(file);
+    let _ = close_file(file);
+    let _ = close_file(root);
+    written
+}
+
+/// Load the download history from the ESP, oldest first
+///
+/// Records that fail to read or verify are skipped. A missing directory
+/// is an empty history.
+pub unsafe fn load_history(
+    bs: &BootServices,
+    image_handle: *mut (),
+) -> ManifestIoResult<Vec<DownloadRecord>> {
+    let root = get_esp_root(bs, image_handle)?;
+
+    let mut dir_path = [0u16; 32];
+    ascii_to_utf16(MANIFEST_DIR, &mut dir_path);
+
+    let mut dir: *mut FileProtocol = core::ptr::null_mut();
+    let status = ((*root).open)(root, &mut dir, dir_path.as_ptr(), EFI_FILE_MODE_READ, 0);
+    if status != 0 || dir.is_null() {
+        let _ = close_file(root);
+        return Ok(Vec::new());
+    }
+
+    let suffix = alloc::format!(".{}", HISTORY_EXT);
+    let mut filenames = Vec::new();
+    let mut buffer = [0u8; 512];
+
+    loop {
+        let mut size = buffer.len();
+        let status = ((*dir).read)(dir, &mut size, buffer.as_mut_ptr());
+        if status != 0 || size == 0 {
+            break;
+        }
+        if size < 82 {
+            continue;
+        }
+
+        let attributes = u64::from_le_bytes([
+            buffer[0x48],
+            buffer[0x49],
+            buffer[0x4A],
+            buffer[0x4B],
+            buffer[0x4C],
+            buffer[0x4D],
+            buffer[0x4E],
+            buffer[0x4F],
+        ]);
+        if attributes & 0x10 != 0 {
+            continue;
+        }
+
+        let filename = extract_filename_from_file_info(&buffer);
+        if filename.to_uppercase().ends_with(&suffix) {
+            filenames.push(filename.to_uppercase());
+            if filenames.len() >= MAX_HISTORY_RECORDS {
+                break;
+            }
+        }
+    }
+    let _ = close_file(dir);
+
+    // Slots are numbered in the order they were written
+    filenames.sort();
+
+    let mut records = Vec::new();
+    for filename in &filenames {
+        match load_single_record(root, filename) {
+            Ok(record) => records.push(record),
+            Err(e) => morpheus_core::logger::log(
+                alloc::format!("History {} unreadable: {:?}", filename, e).leak(),
+            ),
+        }
+    }
+    let _ = close_file(root);
+
+    Ok(records)
+}
+
+/// Load one history record by filename
+unsafe fn load_single_record(
+    root: *mut FileProtocol,
+    filename: &str,
+) -> ManifestIoResult<DownloadRecord> {
+    let mut full_path = String::new();
+    full_path.push_str("\\.iso\\");
+    full_path.push_str(filename);
+
+    let mut path_utf16 = [0u16; 128];
+    ascii_to_utf16(&full_path, &mut path_utf16);
+
+    let mut file: *mut FileProtocol = core::ptr::null_mut();
+    let status = ((*root).open)(root, &mut file, path_utf16.as_ptr(), EFI_FILE_MODE_READ, 0);
+    if status != 0 || file.is_null() {
+        return Err(ManifestIoError::NotFound);
+    }
+
+    let mut buffer = [0u8; HISTORY_RECORD_SIZE];
+    let mut size = buffer.len();
+    let status = ((*file).read)(file, &mut size, buffer.as_mut_ptr());
+    let _ = close_file(file);
+
+    if status != 0 || size < HISTORY_RECORD_SIZE {
+        return Err(ManifestIoError::ReadFailed);
+    }
+
+    DownloadRecord::deserialize(&buffer).map_err(|_| ManifestIoError::DeserializeFailed)
+}
+
 /// Delete a manifest file from ESP
 ///
 /// # Arguments
@@ -337,17 +655,26 @@ pub unsafe fn delete_manifest(
     image_handle: *mut (),
     name: &str,
 ) -> ManifestIoResult<()> {
-    let root = get_esp_root(bs, image_handle)?;
-
     // Build manifest filename using 8.3 compatible hash
     let manifest_filename = morpheus_core::fs::generate_8_3_manifest_name(name);
     let mut filename = String::new();
     filename.push_str("\\.iso\\");
     filename.push_str(&manifest_filename);
 
+    delete_esp_file(bs, image_handle, &filename)
+}
+
+/// Delete a file on the ESP
+unsafe fn delete_esp_file(
+    bs: &BootServices,
+    image_handle: *mut (),
+    path: &str,
+) -> ManifestIoResult<()> {
+    let root = get_esp_root(bs, image_handle)?;
+
     // Convert to UTF-16
     let mut path_utf16 = [0u16; 128];
-    ascii_to_utf16(&filename, &mut path_utf16);
+    ascii_to_utf16(path, &mut path_utf16);
 
     // Open file
     let mut file: *mut FileProtocol = core::ptr::null_mut();
@@ -376,3 +703,110 @@ pub unsafe fn delete_manifest(
 
     Ok(())
 }
+
+/// Load the boot request a download left behind, if there is a valid one
+pub unsafe fn load_boot_request(bs: &BootServices, image_handle: *mut ()) -> Option<BootRequest> {
+    read_esp_file(bs, image_handle, BOOT_REQUEST_FILE, BOOT_REQUEST_SIZE)
+        .ok()
+        .and_then(|data| BootRequest::deserialize(&data).ok())
+}
+
+/// Remove the boot request, so a one-shot boot happens only once
+pub unsafe fn clear_boot_request(bs: &BootServices, image_handle: *mut ()) -> ManifestIoResult<()> {
+    delete_esp_file(bs, image_handle, BOOT_REQUEST_FILE)
+}
+
+/// Move a file on the ESP whose contents are `data` to `to`, replacing
+/// what is there. The copy is written first, so `data` is not lost if
+/// either step fails.
+pub unsafe fn archive_esp_file(
+    bs: &BootServices,
+    image_handle: *mut (),
+    from: &str,
+    to: &str,
+    data: &[u8],
+) -> ManifestIoResult<()> {
+    // create_file doesn't truncate, so a longer old copy would show through
+    let _ = delete_esp_file(bs, image_handle, to);
+
+    let root = get_esp_root(bs, image_handle)?;
+    let mut path_utf16 = [0u16; 128];
+    ascii_to_utf16(to, &mut path_utf16);
+    let file = create_file(root, &path_utf16).map_err(|_| ManifestIoError::FileCreateFailed);
+    let _ = close_file(root);
+    let file = file?;
+    let written = write_file(file, data).map_err(|_| ManifestIoError::WriteFailed);
+    let _ = flush_file(file);
+    let _ = close_file(file);
+    written?;
+
+    delete_esp_file(bs, image_handle, from)
+}
+
+/// Load the last download session's exit report, if one was left and it
+/// verifies; the archived one once it has been shown
+pub unsafe fn load_exit_report(bs: &BootServices, image_handle: *mut ()) -> Option<ExitReport> {
+    [EXIT_REPORT_FILE, EXIT_REPORT_ARCHIVE_FILE]
+        .into_iter()
+        .find_map(|path| {
+            read_esp_file(bs, image_handle, path, EXIT_REPORT_SIZE)
+                .ok()
+                .and_then(|data| ExitReport::deserialize(&data).ok())
+        })
+}
+
+/// Load an exit report not shown yet and archive it, so it is shown only
+/// once. `None` if there is no new one.
+pub unsafe fn take_exit_report(bs: &BootServices, image_handle: *mut ()) -> Option<ExitReport> {
+    let data = read_esp_file(bs, image_handle, EXIT_REPORT_FILE, EXIT_REPORT_SIZE).ok()?;
+    let report = ExitReport::deserialize(&data).ok()?;
+    if archive_esp_file(
+        bs,
+        image_handle,
+        EXIT_REPORT_FILE,
+        EXIT_REPORT_ARCHIVE_FILE,
+        &data,
+    )
+    .is_err()
+    {
+        morpheus_core::logger::log("Failed to archive the exit report");
+    }
+    Some(report)
+}
+
+/// Load the boot menu timeout and default; no countdown if there is none
+/// or it fails to verify.
+pub unsafe fn load_boot_menu(bs: &BootServices, image_handle: *mut ()) -> BootMenuConfig {
+    read_esp_file(bs, image_handle, BOOT_MENU_FILE, BOOT_MENU_SIZE)
+        .ok()
+        .and_then(|data| BootMenuConfig::deserialize(&data).ok())
+        .unwrap_or_default()
+}
+
+/// Write the boot menu timeout and default to the ESP
+pub unsafe fn save_boot_menu(
+    bs: &BootServices,
+    image_handle: *mut (),
+    config: &BootMenuConfig,
+) -> ManifestIoResult<()> {
+    let root = get_esp_root(bs, image_handle)?;
+
+    let mut iso_path = [0u16; 32];
+    ascii_to_utf16(MANIFEST_DIR, &mut iso_path);
+    let _ = create_directory(root, &iso_path); // Ignore error if exists
+
+    let mut path_utf16 = [0u16; 64];
+    ascii_to_utf16(BOOT_MENU_FILE, &mut path_utf16);
+    let file = create_file(root, &path_utf16).map_err(|_| ManifestIoError::FileCreateFailed)?;
+
+    let mut buffer = [0u8; BOOT_MENU_SIZE];
+    let size = config
+        .serialize(&mut buffer)
+        .map_err(|_| ManifestIoError::SerializeFailed)?;
+    let written = write_file(file, &buffer[..size]).map_err(|_| ManifestIoError::WriteFailed);
+
+    let _ = flush_file(file);
+    let _ = close_file(file);
+    let _ = close_file(root);
+    written
+}
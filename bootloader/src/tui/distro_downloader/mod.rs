@@ -24,15 +24,19 @@
 
 pub mod catalog;
 pub mod commit; // Modular commit download infrastructure
+pub mod history;
 pub mod manifest_io;
 pub mod renderer;
+pub mod retention;
 pub mod state;
 pub mod ui; // Post-EBS download flow
 
 pub use catalog::{get_by_category, DistroCategory, DistroEntry, CATEGORIES, DISTRO_CATALOG};
 pub use commit::{commit_to_download, CommitResult, DownloadCommitConfig};
 pub use manifest_io::{
-    delete_manifest, load_manifests_from_esp, persist_manifest, ManifestIoError,
+    delete_manifest, load_checksums, load_history, load_hosts, load_manifests_from_esp,
+    load_policy, load_proxy, persist_manifest, save_policy, ManifestIoError, CHECKSUMS_PATH,
+    HOSTS_PATH, POLICY_FILE, PROXY_PATH,
 };
 pub use state::{DownloadState, DownloadStatus, UiMode, UiState};
 pub use ui::{DistroDownloader, ManageAction};
@@ -0,0 +1,186 @@
+//! Retention policy enforcement
+//!
+//! Before a download starts, the stored ISOs are loaded with their
+//! download history and handed to `morpheus_core::iso::plan_cleanup`
+//! together with the free space on every disk. ISOs picked for deletion
+//! are removed for real: chunk partitions from the GPT first, then the
+//! manifest, so a half-finished delete never leaves a manifest pointing
+//! at missing partitions.
+
+use super::manifest_io::{
+    delete_manifest, load_history, load_manifests_from_esp, ManifestIoError, ManifestIoResult,
+};
+use crate::uefi::gpt_adapter::UefiBlockIoAdapter;
+use crate::BootServices;
+use alloc::vec::Vec;
+use morpheus_core::disk::gpt_ops;
+use morpheus_core::disk::manager::DiskManager;
+use morpheus_core::disk::partition::PartitionTable;
+use morpheus_core::iso::{
+    plan_cleanup, DownloadRecord, IsoManifest, IsoStorageManager, RetentionPolicy, StoredIso,
+};
+use morpheus_gpt::part_type;
+
+/// Stored ISOs to delete before a download
+pub struct Cleanup {
+    /// Unverified ISOs the policy lets go without asking
+    pub auto: Vec<IsoManifest>,
+    /// ISOs the user has to agree to delete
+    pub proposed: Vec<IsoManifest>,
+    /// Whether the download fits once both lists are gone
+    pub fits: bool,
+}
+
+/// Work out what has to go for an ISO of `iso_size` bytes to fit.
+pub unsafe fn plan(
+    bs: &BootServices,
+    image_handle: *mut (),
+    policy: &RetentionPolicy,
+    iso_size: u64,
+) -> Cleanup {
+    let mut storage = IsoStorageManager::new(0, 0);
+    let _ = load_manifests_from_esp(bs, image_handle, &mut storage);
+    let history = load_history(bs, image_handle).unwrap_or_default();
+
+    let manifests: Vec<IsoManifest> = storage
+        .iter()
+        .map(|(_, entry)| entry.manifest.clone())
+        .collect();
+    let stored: Vec<StoredIso> = manifests
+        .iter()
+        .map(|manifest| StoredIso {
+            size: manifest.total_size,
+            footprint: footprint(manifest),
+            verified: manifest.is_verified(),
+            age: age(&history, manifest.name_str()),
+        })
+        .collect();
+
+    let plan = plan_cleanup(
+        policy,
+        &stored,
+        iso_size,
+        storage.space_needed(iso_size),
+        free_space(bs),
+    );
+    Cleanup {
+        auto: plan.auto.iter().map(|&i| manifests[i].clone()).collect(),
+        proposed: plan.proposed.iter().map(|&i| manifests[i].clone()).collect(),
+        fits: plan.fits,
+    }
+}
+
+/// Delete an ISO: its chunk partitions on every disk, then its manifest.
+pub unsafe fn delete_iso(
+    bs: &BootServices,
+    image_handle: *mut (),
+    manifest: &IsoManifest,
+) -> ManifestIoResult<()> {
+    let chunks = &manifest.chunks.chunks[..manifest.chunks.count];
+
+    let mut disks = DiskManager::new();
+    crate::uefi::disk::enumerate_disks(bs, &mut disks)
+        .map_err(|_| ManifestIoError::PartitionDeleteFailed)?;
+
+    for index in 0..disks.disk_count() {
+        let Some(disk) = disks.get_disk(index) else {
+            continue;
+        };
+        // disk_id 0 is the disk the manifest's ESP is on
+        let on_disk: Vec<(u64, u64)> = chunks
+            .iter()
+            .filter(|c| c.is_valid())
+            .filter(|c| match c.disk_id {
+                0 => disk.boot_disk,
+                id => disk.identity.disk_id() == id,
+            })
+            .map(|c| (c.start_lba, c.end_lba))
+            .collect();
+        if on_disk.is_empty() {
+            continue;
+        }
+
+        let block_io_ptr = crate::uefi::disk::get_disk_protocol(bs, index)
+            .map_err(|_| ManifestIoError::PartitionDeleteFailed)?;
+        let block_size = (*(*block_io_ptr).media).block_size as usize;
+
+        let mut table = PartitionTable::new();
+        let adapter = UefiBlockIoAdapter::new(&mut *block_io_ptr)
+            .map_err(|_| ManifestIoError::PartitionDeleteFailed)?;
+        gpt_ops::scan_partitions(adapter, &mut table, block_size)
+            .map_err(|_| ManifestIoError::PartitionDeleteFailed)?;
+
+        for part in table.iter() {
+            if !on_disk.contains(&(part.start_lba, part.end_lba))
+                || !part_type::is_chunk_type(&part.type_guid)
+            {
+                continue;
+            }
+            let adapter = UefiBlockIoAdapter::new(&mut *block_io_ptr)
+                .map_err(|_| ManifestIoError::PartitionDeleteFailed)?;
+            gpt_ops::delete_partition(adapter, part.index as usize)
+                .map_err(|_| ManifestIoError::PartitionDeleteFailed)?;
+        }
+    }
+
+    match delete_manifest(bs, image_handle, manifest.name_str()) {
+        Ok(()) | Err(ManifestIoError::NotFound) => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Delete the stored ISOs called `names`.
+pub unsafe fn delete_named(
+    bs: &BootServices,
+    image_handle: *mut (),
+    names: &[&str],
+) -> ManifestIoResult<()> {
+    let mut storage = IsoStorageManager::new(0, 0);
+    load_manifests_from_esp(bs, image_handle, &mut storage)?;
+    for name in names {
+        let Some(entry) = storage.find_by_name(name).and_then(|i| storage.get(i)) else {
+            continue;
+        };
+        delete_iso(bs, image_handle, &entry.manifest)?;
+    }
+    Ok(())
+}
+
+/// Disk space taken by an ISO's chunk partitions
+fn footprint(manifest: &IsoManifest) -> u64 {
+    manifest.chunks.chunks[..manifest.chunks.count]
+        .iter()
+        .filter(|c| c.is_valid())
+        .map(|c| c.partition_size())
+        .sum()
+}
+
+/// Position of the ISO's latest download in the history, counting from 1;
+/// 0 if it isn't in the history (downloaded before history was kept).
+fn age(history: &[DownloadRecord], name: &str) -> u32 {
+    history
+        .iter()
+        .rposition(|record| record.name_str() == name)
+        .map_or(0, |i| i as u32 + 1)
+}
+
+/// Free space in bytes across all disks
+unsafe fn free_space(bs: &BootServices) -> u64 {
+    let mut disks = DiskManager::new();
+    if crate::uefi::disk::enumerate_disks(bs, &mut disks).is_err() {
+        return 0;
+    }
+
+    let mut free_mb = 0;
+    for index in 0..disks.disk_count() {
+        let Ok(block_io_ptr) = crate::uefi::disk::get_disk_protocol(bs, index) else {
+            continue;
+        };
+        let block_size = (*(*block_io_ptr).media).block_size as usize;
+        free_mb += UefiBlockIoAdapter::new(&mut *block_io_ptr)
+            .ok()
+            .and_then(|adapter| gpt_ops::calculate_total_free_space(adapter, block_size).ok())
+            .unwrap_or(0);
+    }
+    free_mb * 1024 * 1024
+}
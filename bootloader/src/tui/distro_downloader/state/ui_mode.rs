@@ -15,6 +15,10 @@ pub enum UiMode {
     Manage,
     /// Confirm delete ISO
     ConfirmDelete,
+    /// Propose ISOs to delete so a download fits the retention policy
+    ConfirmCleanup,
+    /// Review the download setup before leaving UEFI
+    Review,
 }
 
 impl UiMode {
@@ -27,6 +31,8 @@ impl UiMode {
             Self::Result => "Result",
             Self::Manage => "Manage",
             Self::ConfirmDelete => "Confirm Delete",
+            Self::ConfirmCleanup => "Confirm Cleanup",
+            Self::Review => "Review",
         }
     }
 
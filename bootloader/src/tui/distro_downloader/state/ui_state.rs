@@ -1,7 +1,14 @@
 //! UI state for navigation
 
 use super::super::catalog::{DistroCategory, CATEGORIES};
+use super::super::commit::CommitReview;
 use super::UiMode;
+use alloc::string::String;
+use alloc::vec::Vec;
+use morpheus_core::iso::RetentionPolicy;
+use morpheus_network::driver::intel::LinkMode;
+use morpheus_network::mainloop::PostActions;
+use morpheus_network::transfer::disk::Placement;
 
 /// UI state for navigation
 #[derive(Debug, Clone)]
@@ -20,6 +27,28 @@ pub struct UiState {
     pub selected_iso: usize,
     /// Total ISO count (cached from storage)
     pub iso_count: usize,
+    /// Where downloaded ISOs go, picked in the confirm dialog
+    pub placement: Placement,
+    /// What happens after a successful download, picked in the confirm dialog
+    pub post_actions: PostActions,
+    /// Beep codes for milestones and failures, picked in the confirm dialog
+    pub beeps: bool,
+    /// Download rate cap in bytes per second (0 = none), picked in the
+    /// confirm dialog
+    pub rate_limit: u64,
+    /// Speeds the Intel PHY advertises, picked in the confirm dialog
+    pub link_mode: LinkMode,
+    /// Verify against the distro's checksum list, picked in the confirm
+    /// dialog
+    pub checksums: bool,
+    /// Limits for the ISO store, loaded from the ESP
+    pub policy: RetentionPolicy,
+    /// ISOs (name, size in MB) proposed for deletion before a download
+    pub cleanup: Vec<(String, u64)>,
+    /// Whether the download fits once the proposed ISOs are gone
+    pub cleanup_fits: bool,
+    /// Setup of the download about to start, shown before committing
+    pub review: Option<CommitReview>,
 }
 
 impl UiState {
@@ -37,6 +66,16 @@ impl UiState {
             status_message: None,
             selected_iso: 0,
             iso_count: 0,
+            placement: Placement::default(),
+            post_actions: PostActions::REBOOT,
+            beeps: false,
+            rate_limit: 0,
+            link_mode: LinkMode::Auto,
+            checksums: true,
+            policy: RetentionPolicy::default(),
+            cleanup: Vec::new(),
+            cleanup_fits: false,
+            review: None,
         }
     }
 
@@ -98,11 +137,28 @@ impl UiState {
         self.mode = UiMode::Confirm;
     }
 
+    /// Propose deleting `isos` so the selected download fits
+    pub fn show_cleanup(&mut self, isos: Vec<(String, u64)>, fits: bool) {
+        morpheus_core::logger::log("UiState::show_cleanup()");
+        self.mode = UiMode::ConfirmCleanup;
+        self.cleanup = isos;
+        self.cleanup_fits = fits;
+    }
+
+    /// Show the pre-commit review
+    pub fn show_review(&mut self, review: CommitReview) {
+        morpheus_core::logger::log("UiState::show_review()");
+        self.mode = UiMode::Review;
+        self.review = Some(review);
+    }
+
     /// Return to browse mode
     pub fn return_to_browse(&mut self) {
         morpheus_core::logger::log("UiState::return_to_browse()");
         self.mode = UiMode::Browse;
         self.status_message = None;
+        self.cleanup.clear();
+        self.review = None;
     }
 
     /// Show result mode
@@ -8,15 +8,24 @@
 
 extern crate alloc;
 
+use alloc::string::String;
 use alloc::vec::Vec;
 
 use super::helpers::ManageAction;
-use super::input::{handle_input, InputContext};
+use super::input::{bindings, handle_input, InputContext};
+use super::policy;
 use super::render::{render_full, RenderContext};
 use crate::tui::distro_downloader::catalog::{get_by_category, DistroEntry};
+use crate::tui::distro_downloader::history;
+use crate::tui::distro_downloader::manifest_io::{
+    load_checksums, load_history, load_policy, save_policy, CHECKSUMS_PATH,
+};
 use crate::tui::distro_downloader::state::{DownloadState, UiState};
 use crate::tui::input::Keyboard;
+use crate::tui::keymap;
 use crate::tui::renderer::Screen;
+use crate::tui::screensaver::Event;
+use crate::tui::widgets::textview;
 use crate::BootServices;
 use morpheus_core::iso::{IsoStorageManager, MAX_ISOS};
 
@@ -60,7 +69,8 @@ impl DistroDownloader {
         esp_start_lba: u64,
         disk_size_lba: u64,
     ) -> Self {
-        let ui_state = UiState::new();
+        let mut ui_state = UiState::new();
+        ui_state.policy = unsafe { load_policy(&*boot_services, image_handle) };
         let current_category = ui_state.current_category();
         let current_distros: Vec<_> = get_by_category(current_category).collect();
         let iso_storage = IsoStorageManager::new(esp_start_lba, disk_size_lba);
@@ -162,29 +172,73 @@ impl DistroDownloader {
         render_full(&ctx, screen, true);
 
         loop {
-            // Render global rain if active
-            crate::tui::rain::render_rain(screen);
-
             // Poll for input with frame delay (~60fps timing)
-            if let Some(key) = keyboard.poll_key_with_delay() {
-                // Global rain toggle
-                if key.unicode_char == b'x' as u16 || key.unicode_char == b'X' as u16 {
-                    crate::tui::rain::toggle_rain(screen);
-                    self.needs_full_redraw = true;
-                    let ctx = self.render_context();
-                    render_full(&ctx, screen, true);
-                    continue;
-                }
-
-                // Handle mode-specific input
-                let mut input_ctx = self.input_context();
-                match handle_input(&mut input_ctx, &key, screen) {
-                    ManageAction::Continue => {}
-                    ManageAction::Exit => return,
+            match keymap::poll(screen, keyboard, bindings(self.ui_state.mode)) {
+                Some(Event::Key(key)) => {
+                    // Handle mode-specific input
+                    let mut input_ctx = self.input_context();
+                    match handle_input(&mut input_ctx, &key, screen) {
+                        ManageAction::Continue => {}
+                        ManageAction::Exit => return,
+                        ManageAction::ViewNotes(distro) => {
+                            textview::show(screen, keyboard, distro.name, &distro.release_notes());
+                            self.redraw(screen);
+                        }
+                        ManageAction::ViewChecksums => {
+                            self.view_checksums(screen, keyboard);
+                            self.redraw(screen);
+                        }
+                        ManageAction::ViewHistory => {
+                            self.view_history(screen, keyboard);
+                            self.redraw(screen);
+                        }
+                        ManageAction::EditPolicy => {
+                            self.edit_policy(screen, keyboard);
+                            self.redraw(screen);
+                        }
+                    }
                 }
+                Some(Event::Redraw) => self.redraw(screen),
+                None => {}
             }
         }
     }
+
+    fn redraw(&mut self, screen: &mut Screen) {
+        self.needs_full_redraw = true;
+        let ctx = self.render_context();
+        render_full(&ctx, screen, true);
+    }
+
+    fn view_checksums(&self, screen: &mut Screen, keyboard: &mut Keyboard) {
+        let bs = unsafe { &*self.boot_services };
+        let text = unsafe { load_checksums(bs, self.image_handle) }.unwrap_or_else(|_| {
+            alloc::format!(
+                "No checksum file on the ESP.\n\nExpected at {}",
+                CHECKSUMS_PATH
+            )
+        });
+        textview::show(screen, keyboard, "SHA256SUMS", &text);
+    }
+
+    fn view_history(&self, screen: &mut Screen, keyboard: &mut Keyboard) {
+        let bs = unsafe { &*self.boot_services };
+        let text = match unsafe { load_history(bs, self.image_handle) } {
+            Ok(records) => history::report(&records),
+            Err(_) => String::from("Cannot read the ESP."),
+        };
+        textview::show(screen, keyboard, "Download history", &text);
+    }
+
+    fn edit_policy(&mut self, screen: &mut Screen, keyboard: &mut Keyboard) {
+        if !policy::edit(screen, keyboard, &mut self.ui_state.policy) {
+            return;
+        }
+        let bs = unsafe { &*self.boot_services };
+        if let Err(e) = unsafe { save_policy(bs, self.image_handle, &self.ui_state.policy) } {
+            morpheus_core::logger::log(alloc::format!("Saving policy failed: {:?}", e).leak());
+        }
+    }
 }
 
 // ============================================================================
@@ -1,5 +1,6 @@
 //! Helper types and constants for the Distro Downloader UI.
 
+use crate::tui::distro_downloader::catalog::DistroEntry;
 use morpheus_core::iso::MAX_ISOS;
 
 // Layout constants
@@ -17,6 +18,14 @@ pub enum ManageAction {
     Continue,
     /// Exit UI
     Exit,
+    /// Open the text viewer on a catalog entry's release notes
+    ViewNotes(&'static DistroEntry),
+    /// Open the text viewer on the checksum file
+    ViewChecksums,
+    /// Open the text viewer on the download history
+    ViewHistory,
+    /// Open the retention policy editor
+    EditPolicy,
 }
 
 /// Helper: pad or truncate string to exact length
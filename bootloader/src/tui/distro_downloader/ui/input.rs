@@ -1,6 +1,7 @@
 //! Input handling for the Distro Downloader UI.
 //!
-//! Handles keyboard input for all UI modes (Browse, Confirm, Download, Result, Manage).
+//! Handles keyboard input for all UI modes (Browse, Confirm, Cleanup, Review,
+//! Download, Result, Manage).
 
 extern crate alloc;
 
@@ -10,10 +11,102 @@ use alloc::vec::Vec;
 use super::helpers::ManageAction;
 use super::render::{render_full, render_list_and_details, RenderContext};
 use crate::tui::distro_downloader::catalog::{get_by_category, DistroEntry, CATEGORIES};
+use crate::tui::distro_downloader::retention;
 use crate::tui::distro_downloader::state::{DownloadState, DownloadStatus, UiMode, UiState};
 use crate::tui::input::InputKey;
+use crate::tui::keymap::{Bindings, Command, Key, KeyBinding};
 use crate::tui::renderer::Screen;
+use morpheus_core::disk::manager::DiskManager;
 use morpheus_core::iso::{IsoStorageManager, MAX_ISOS};
+use morpheus_network::driver::intel::LinkMode;
+use morpheus_network::transfer::disk::DiskPreference;
+
+const BROWSE_BINDINGS: Bindings = Bindings {
+    title: "Distro Downloader",
+    keys: &[
+        KeyBinding::new(&[Key::Up], Command::Up, "Previous distro"),
+        KeyBinding::new(&[Key::Down], Command::Down, "Next distro"),
+        KeyBinding::new(&[Key::Left], Command::Left, "Previous category"),
+        KeyBinding::new(&[Key::Right], Command::Right, "Next category"),
+        KeyBinding::new(&[Key::Enter], Command::Select, "Download distro"),
+        KeyBinding::new(&[Key::Char(b'i')], Command::Details, "Release notes"),
+        KeyBinding::new(&[Key::Char(b'm')], Command::Manage, "Manage ISOs"),
+        KeyBinding::new(&[Key::Esc], Command::Back, "Back to main menu"),
+    ],
+};
+
+const CONFIRM_BINDINGS: Bindings = Bindings {
+    title: "Confirm",
+    keys: &[
+        KeyBinding::new(&[Key::Char(b'y')], Command::Yes, "Confirm"),
+        KeyBinding::new(&[Key::Char(b'n'), Key::Esc], Command::No, "Cancel"),
+        KeyBinding::new(&[Key::Char(b'p')], Command::Placement, "Chunk placement"),
+        KeyBinding::new(&[Key::Char(b'd')], Command::TargetDisk, "Target disk"),
+        KeyBinding::new(&[Key::Char(b'a')], Command::AfterDownload, "When the download is done"),
+        KeyBinding::new(&[Key::Char(b'b')], Command::Beeps, "Beep codes on the PC speaker"),
+        KeyBinding::new(&[Key::Char(b'l')], Command::RateLimit, "Download rate limit"),
+        KeyBinding::new(&[Key::Char(b's')], Command::LinkSpeed, "Link speed (Intel NICs)"),
+        KeyBinding::new(&[Key::Char(b'c')], Command::Checksums, "Verify with SHA256SUMS"),
+    ],
+};
+
+const CLEANUP_BINDINGS: Bindings = Bindings {
+    title: "Make Room",
+    keys: &[
+        KeyBinding::new(&[Key::Char(b'y')], Command::Yes, "Delete ISOs and download"),
+        KeyBinding::new(&[Key::Char(b'n'), Key::Esc], Command::No, "Back"),
+    ],
+};
+
+const REVIEW_BINDINGS: Bindings = Bindings {
+    title: "Review",
+    keys: &[
+        KeyBinding::new(&[Key::Char(b'y')], Command::Yes, "Exit UEFI and download"),
+        KeyBinding::new(&[Key::Char(b'n'), Key::Esc], Command::No, "Back"),
+    ],
+};
+
+const DOWNLOAD_BINDINGS: Bindings = Bindings {
+    title: "Downloading",
+    keys: &[KeyBinding::new(
+        &[Key::Esc],
+        Command::Back,
+        "Cancel download",
+    )],
+};
+
+/// Result screen: every key dismisses it, so nothing is bound
+const RESULT_BINDINGS: Bindings = Bindings {
+    title: "Result",
+    keys: &[],
+};
+
+const MANAGE_BINDINGS: Bindings = Bindings {
+    title: "Manage ISOs",
+    keys: &[
+        KeyBinding::new(&[Key::Up], Command::Up, "Previous ISO"),
+        KeyBinding::new(&[Key::Down], Command::Down, "Next ISO"),
+        KeyBinding::new(&[Key::Char(b'd')], Command::Delete, "Delete ISO"),
+        KeyBinding::new(&[Key::Char(b'r')], Command::Rescan, "Refresh list"),
+        KeyBinding::new(&[Key::Char(b's')], Command::Checksums, "View SHA256SUMS"),
+        KeyBinding::new(&[Key::Char(b'h')], Command::History, "Download history"),
+        KeyBinding::new(&[Key::Char(b'p')], Command::Policy, "Storage policy"),
+        KeyBinding::new(&[Key::Esc], Command::Back, "Back to browse"),
+    ],
+};
+
+/// Key table for a UI mode
+pub fn bindings(mode: UiMode) -> &'static Bindings {
+    match mode {
+        UiMode::Browse => &BROWSE_BINDINGS,
+        UiMode::Confirm | UiMode::ConfirmDelete => &CONFIRM_BINDINGS,
+        UiMode::ConfirmCleanup => &CLEANUP_BINDINGS,
+        UiMode::Review => &REVIEW_BINDINGS,
+        UiMode::Downloading => &DOWNLOAD_BINDINGS,
+        UiMode::Result => &RESULT_BINDINGS,
+        UiMode::Manage => &MANAGE_BINDINGS,
+    }
+}
 
 /// Input handling context - mutable references for state modifications
 pub struct InputContext<'a> {
@@ -86,114 +179,186 @@ impl<'a> InputContext<'a> {
 
 /// Handle input and return action
 pub fn handle_input(ctx: &mut InputContext, key: &InputKey, screen: &mut Screen) -> ManageAction {
+    let command = bindings(ctx.ui_state.mode).lookup(key);
     match ctx.ui_state.mode {
-        UiMode::Browse => handle_browse_input(ctx, key, screen),
-        UiMode::Confirm => handle_confirm_input(ctx, key, screen),
-        UiMode::Downloading => handle_download_input(ctx, key, screen),
+        UiMode::Browse => handle_browse_input(ctx, command, screen),
+        UiMode::Confirm => handle_confirm_input(ctx, command, screen),
+        UiMode::Downloading => handle_download_input(ctx, command, screen),
         UiMode::Result => handle_result_input(ctx, key, screen),
-        UiMode::Manage => handle_manage_input(ctx, key, screen),
-        UiMode::ConfirmDelete => handle_confirm_delete_input(ctx, key, screen),
+        UiMode::Manage => handle_manage_input(ctx, command, screen),
+        UiMode::ConfirmDelete => handle_confirm_delete_input(ctx, command, screen),
+        UiMode::ConfirmCleanup => handle_confirm_cleanup_input(ctx, command, screen),
+        UiMode::Review => handle_review_input(ctx, command, screen),
     }
 }
 
 fn handle_browse_input(
     ctx: &mut InputContext,
-    key: &InputKey,
+    command: Option<Command>,
     screen: &mut Screen,
 ) -> ManageAction {
-    match key.scan_code {
-        // Up arrow
-        0x01 => {
+    match command {
+        Some(Command::Up) => {
             ctx.ui_state.prev_distro();
             let render_ctx = ctx.render_context();
             render_list_and_details(&render_ctx, screen);
         }
-        // Down arrow
-        0x02 => {
+        Some(Command::Down) => {
             let count = ctx.current_distros.len();
             ctx.ui_state.next_distro(count);
             let render_ctx = ctx.render_context();
             render_list_and_details(&render_ctx, screen);
         }
-        // Left arrow - previous category
-        0x04 => {
+        Some(Command::Left) => {
             ctx.ui_state.prev_category();
             ctx.refresh_distro_list();
             let render_ctx = ctx.render_context();
             render_full(&render_ctx, screen, true);
         }
-        // Right arrow - next category
-        0x03 => {
+        Some(Command::Right) => {
             ctx.ui_state.next_category(CATEGORIES.len());
             ctx.refresh_distro_list();
             let render_ctx = ctx.render_context();
             render_full(&render_ctx, screen, true);
         }
-        // ESC - exit
-        0x17 => {
+        Some(Command::Back) => {
             return ManageAction::Exit;
         }
-        _ => {
-            // Enter key - show confirm dialog
-            if key.unicode_char == 0x0D && ctx.selected_distro().is_some() {
-                ctx.ui_state.show_confirm();
-                *ctx.needs_full_redraw = true;
-                let render_ctx = ctx.render_context();
-                render_full(&render_ctx, screen, true);
-            }
-            // 'm' or 'M' - switch to manage view
-            else if key.unicode_char == b'm' as u16 || key.unicode_char == b'M' as u16 {
-                ctx.refresh_iso_cache();
-                ctx.ui_state.show_manage();
-                *ctx.needs_full_redraw = true;
-                let render_ctx = ctx.render_context();
-                render_full(&render_ctx, screen, true);
+        // Show confirm dialog
+        Some(Command::Select) if ctx.selected_distro().is_some() => {
+            ctx.ui_state.show_confirm();
+            *ctx.needs_full_redraw = true;
+            let render_ctx = ctx.render_context();
+            render_full(&render_ctx, screen, true);
+        }
+        Some(Command::Details) => {
+            if let Some(distro) = ctx.selected_distro() {
+                return ManageAction::ViewNotes(distro);
             }
         }
+        Some(Command::Manage) => {
+            ctx.refresh_iso_cache();
+            ctx.ui_state.show_manage();
+            *ctx.needs_full_redraw = true;
+            let render_ctx = ctx.render_context();
+            render_full(&render_ctx, screen, true);
+        }
+        _ => {}
     }
     ManageAction::Continue
 }
 
 fn handle_confirm_input(
     ctx: &mut InputContext,
-    key: &InputKey,
+    command: Option<Command>,
     screen: &mut Screen,
 ) -> ManageAction {
-    // ESC - cancel
-    if key.scan_code == 0x17 {
-        ctx.ui_state.return_to_browse();
-        *ctx.needs_full_redraw = true;
-        let render_ctx = ctx.render_context();
-        render_full(&render_ctx, screen, true);
-        return ManageAction::Continue;
-    }
-
-    // Y/y - confirm download
-    if key.unicode_char == b'y' as u16 || key.unicode_char == b'Y' as u16 {
-        if let Some(distro) = ctx.selected_distro() {
-            start_download(ctx, distro, screen);
+    match command {
+        Some(Command::Yes) => {
+            if let Some(distro) = ctx.selected_distro() {
+                make_room_and_download(ctx, distro, screen);
+            }
+        }
+        Some(Command::No) => {
+            ctx.ui_state.return_to_browse();
+            *ctx.needs_full_redraw = true;
+            let render_ctx = ctx.render_context();
+            render_full(&render_ctx, screen, true);
         }
-        return ManageAction::Continue;
+        Some(Command::Placement) => {
+            let placement = &mut ctx.ui_state.placement;
+            placement.policy = placement.policy.next();
+            let render_ctx = ctx.render_context();
+            render_full(&render_ctx, screen, true);
+        }
+        Some(Command::TargetDisk) => {
+            ctx.ui_state.placement.disk = next_disk_preference(ctx);
+            let render_ctx = ctx.render_context();
+            render_full(&render_ctx, screen, true);
+        }
+        Some(Command::AfterDownload) => {
+            let post_actions = &mut ctx.ui_state.post_actions;
+            *post_actions = post_actions.next_preset();
+            let render_ctx = ctx.render_context();
+            render_full(&render_ctx, screen, true);
+        }
+        Some(Command::Beeps) => {
+            ctx.ui_state.beeps = !ctx.ui_state.beeps;
+            let render_ctx = ctx.render_context();
+            render_full(&render_ctx, screen, true);
+        }
+        Some(Command::RateLimit) => {
+            ctx.ui_state.rate_limit = next_rate_limit(ctx.ui_state.rate_limit);
+            let render_ctx = ctx.render_context();
+            render_full(&render_ctx, screen, true);
+        }
+        Some(Command::LinkSpeed) => {
+            ctx.ui_state.link_mode = match ctx.ui_state.link_mode {
+                LinkMode::Auto => LinkMode::Force100Full,
+                LinkMode::Force100Full => LinkMode::Auto,
+            };
+            let render_ctx = ctx.render_context();
+            render_full(&render_ctx, screen, true);
+        }
+        Some(Command::Checksums) => {
+            ctx.ui_state.checksums = !ctx.ui_state.checksums;
+            let render_ctx = ctx.render_context();
+            render_full(&render_ctx, screen, true);
+        }
+        _ => {}
     }
+    ManageAction::Continue
+}
 
-    // N/n - cancel
-    if key.unicode_char == b'n' as u16 || key.unicode_char == b'N' as u16 {
-        ctx.ui_state.return_to_browse();
-        *ctx.needs_full_redraw = true;
-        let render_ctx = ctx.render_context();
-        render_full(&render_ctx, screen, true);
+/// Rate caps offered in the confirm dialog, in MB/s (0 = none)
+const RATE_LIMITS_MB: [u64; 6] = [0, 1, 2, 5, 10, 25];
+
+/// Rate cap after `current` (bytes per second), wrapping back to none
+fn next_rate_limit(current: u64) -> u64 {
+    let mb = current / (1024 * 1024);
+    let next = RATE_LIMITS_MB
+        .iter()
+        .position(|&limit| limit == mb)
+        .map_or(0, |i| RATE_LIMITS_MB[(i + 1) % RATE_LIMITS_MB.len()]);
+    next * 1024 * 1024
+}
+
+/// Disk preference after the current one: any disk, avoid SSDs, avoid
+/// HDDs, then each disk that reports a model and serial.
+fn next_disk_preference(ctx: &InputContext) -> DiskPreference {
+    let mut choices = alloc::vec![
+        DiskPreference::Any,
+        DiskPreference::AvoidSolidState,
+        DiskPreference::AvoidRotational,
+    ];
+    let mut disks = DiskManager::new();
+    let bs = unsafe { &*ctx.boot_services };
+    if crate::uefi::disk::enumerate_disks(bs, &mut disks).is_ok() {
+        for i in 0..disks.disk_count() {
+            let Some(disk) = disks.get_disk(i) else {
+                continue;
+            };
+            let choice = DiskPreference::Disk(disk.identity);
+            if disk.identity.is_known() && !choices.contains(&choice) {
+                choices.push(choice);
+            }
+        }
     }
 
-    ManageAction::Continue
+    let current = ctx.ui_state.placement.disk;
+    let next = choices
+        .iter()
+        .position(|c| *c == current)
+        .map_or(0, |i| (i + 1) % choices.len());
+    choices[next]
 }
 
 fn handle_download_input(
     ctx: &mut InputContext,
-    key: &InputKey,
+    command: Option<Command>,
     screen: &mut Screen,
 ) -> ManageAction {
-    // ESC cancels download
-    if key.scan_code == 0x17 {
+    if command == Some(Command::Back) {
         ctx.download_state.fail("Cancelled by user");
         ctx.ui_state.show_result("Download cancelled");
         *ctx.needs_full_redraw = true;
@@ -222,78 +387,230 @@ fn handle_result_input(
 
 fn handle_manage_input(
     ctx: &mut InputContext,
-    key: &InputKey,
+    command: Option<Command>,
     screen: &mut Screen,
 ) -> ManageAction {
-    match key.scan_code {
-        // Up arrow
-        0x01 => {
+    match command {
+        Some(Command::Up) => {
             ctx.ui_state.prev_iso();
             let render_ctx = ctx.render_context();
             render_full(&render_ctx, screen, false);
         }
-        // Down arrow
-        0x02 => {
+        Some(Command::Down) => {
             ctx.ui_state.next_iso();
             let render_ctx = ctx.render_context();
             render_full(&render_ctx, screen, false);
         }
-        // ESC - back to browse
-        0x17 => {
+        Some(Command::Back) => {
             ctx.ui_state.return_from_manage();
             *ctx.needs_full_redraw = true;
             let render_ctx = ctx.render_context();
             render_full(&render_ctx, screen, true);
         }
-        _ => {
-            // 'd' or 'D' - delete
-            if (key.unicode_char == b'd' as u16 || key.unicode_char == b'D' as u16)
-                && ctx.ui_state.iso_count > 0
-            {
-                ctx.ui_state.show_confirm_delete();
-                *ctx.needs_full_redraw = true;
-                let render_ctx = ctx.render_context();
-                render_full(&render_ctx, screen, true);
-            }
-            // 'r' or 'R' - refresh
-            else if key.unicode_char == b'r' as u16 || key.unicode_char == b'R' as u16 {
+        Some(Command::Delete) if ctx.ui_state.iso_count > 0 => {
+            ctx.ui_state.show_confirm_delete();
+            *ctx.needs_full_redraw = true;
+            let render_ctx = ctx.render_context();
+            render_full(&render_ctx, screen, true);
+        }
+        Some(Command::Rescan) => {
+            ctx.refresh_iso_cache();
+            *ctx.needs_full_redraw = true;
+            let render_ctx = ctx.render_context();
+            render_full(&render_ctx, screen, true);
+        }
+        Some(Command::Checksums) => return ManageAction::ViewChecksums,
+        Some(Command::History) => return ManageAction::ViewHistory,
+        Some(Command::Policy) => return ManageAction::EditPolicy,
+        _ => {}
+    }
+    ManageAction::Continue
+}
+
+fn handle_confirm_delete_input(
+    ctx: &mut InputContext,
+    command: Option<Command>,
+    screen: &mut Screen,
+) -> ManageAction {
+    match command {
+        Some(Command::Yes) => {
+            let idx = ctx.ui_state.selected_iso;
+            if ctx.iso_storage.remove_entry(idx).is_ok() {
                 ctx.refresh_iso_cache();
-                *ctx.needs_full_redraw = true;
-                let render_ctx = ctx.render_context();
-                render_full(&render_ctx, screen, true);
             }
+            ctx.ui_state.cancel_confirm();
+            *ctx.needs_full_redraw = true;
+            let render_ctx = ctx.render_context();
+            render_full(&render_ctx, screen, true);
+        }
+        Some(Command::No) => {
+            ctx.ui_state.cancel_confirm();
+            *ctx.needs_full_redraw = true;
+            let render_ctx = ctx.render_context();
+            render_full(&render_ctx, screen, true);
         }
+        _ => {}
     }
     ManageAction::Continue
 }
 
-fn handle_confirm_delete_input(
+fn handle_confirm_cleanup_input(
     ctx: &mut InputContext,
-    key: &InputKey,
+    command: Option<Command>,
     screen: &mut Screen,
 ) -> ManageAction {
-    // Y/y - confirm delete
-    if key.unicode_char == b'y' as u16 || key.unicode_char == b'Y' as u16 {
-        let idx = ctx.ui_state.selected_iso;
-        if ctx.iso_storage.remove_entry(idx).is_ok() {
-            ctx.refresh_iso_cache();
+    match command {
+        Some(Command::Yes) if ctx.ui_state.cleanup_fits => {
+            let Some(distro) = ctx.selected_distro() else {
+                return ManageAction::Continue;
+            };
+            let bs = unsafe { &*ctx.boot_services };
+            let names: Vec<&str> = ctx
+                .ui_state
+                .cleanup
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect();
+            match unsafe { retention::delete_named(bs, ctx.image_handle, &names) } {
+                Ok(()) => show_review(ctx, distro, screen),
+                Err(e) => {
+                    morpheus_core::logger::log(
+                        alloc::format!("Deleting ISOs failed: {:?}", e).leak(),
+                    );
+                    ctx.download_state.fail("Could not delete ISOs");
+                    ctx.ui_state.show_result("Could not make room for the download");
+                    *ctx.needs_full_redraw = true;
+                    let render_ctx = ctx.render_context();
+                    render_full(&render_ctx, screen, true);
+                }
+            }
         }
-        ctx.ui_state.cancel_confirm();
-        *ctx.needs_full_redraw = true;
-        let render_ctx = ctx.render_context();
-        render_full(&render_ctx, screen, true);
-        return ManageAction::Continue;
+        Some(Command::No) => {
+            ctx.ui_state.cleanup.clear();
+            ctx.ui_state.show_confirm();
+            *ctx.needs_full_redraw = true;
+            let render_ctx = ctx.render_context();
+            render_full(&render_ctx, screen, true);
+        }
+        _ => {}
     }
+    ManageAction::Continue
+}
 
-    // N/n/ESC - cancel
-    if key.unicode_char == b'n' as u16 || key.unicode_char == b'N' as u16 || key.scan_code == 0x17 {
-        ctx.ui_state.cancel_confirm();
+fn handle_review_input(
+    ctx: &mut InputContext,
+    command: Option<Command>,
+    screen: &mut Screen,
+) -> ManageAction {
+    match command {
+        Some(Command::Yes)
+            if ctx
+                .ui_state
+                .review
+                .as_ref()
+                .is_some_and(|r| r.blockers().is_empty()) =>
+        {
+            if let Some(distro) = ctx.selected_distro() {
+                start_download(ctx, distro, screen);
+            }
+        }
+        Some(Command::No) => {
+            ctx.ui_state.review = None;
+            ctx.ui_state.show_confirm();
+            *ctx.needs_full_redraw = true;
+            let render_ctx = ctx.render_context();
+            render_full(&render_ctx, screen, true);
+        }
+        _ => {}
+    }
+    ManageAction::Continue
+}
+
+/// Check a download against the store's retention policy before starting
+/// it. Unverified ISOs the policy lets go are deleted straight away; if
+/// more has to go, the user is asked first instead of the download failing
+/// for lack of space.
+fn make_room_and_download(
+    ctx: &mut InputContext,
+    distro: &'static DistroEntry,
+    screen: &mut Screen,
+) {
+    let bs = unsafe { &*ctx.boot_services };
+    let cleanup =
+        unsafe { retention::plan(bs, ctx.image_handle, &ctx.ui_state.policy, distro.size_bytes) };
+
+    if cleanup.fits {
+        for manifest in &cleanup.auto {
+            if let Err(e) = unsafe { retention::delete_iso(bs, ctx.image_handle, manifest) } {
+                morpheus_core::logger::log(
+                    alloc::format!("Auto-delete of {} failed: {:?}", manifest.name_str(), e)
+                        .leak(),
+                );
+            }
+        }
+        if cleanup.proposed.is_empty() {
+            show_review(ctx, distro, screen);
+            return;
+        }
+    }
+
+    let proposed = cleanup
+        .proposed
+        .iter()
+        .map(|m| (String::from(m.name_str()), m.total_size / (1024 * 1024)))
+        .collect();
+    ctx.ui_state.show_cleanup(proposed, cleanup.fits);
+    *ctx.needs_full_redraw = true;
+    let render_ctx = ctx.render_context();
+    render_full(&render_ctx, screen, true);
+}
+
+/// Download configuration for `distro` with the settings from the confirm
+/// dialog
+fn commit_config(
+    ctx: &InputContext,
+    distro: &'static DistroEntry,
+) -> crate::tui::distro_downloader::commit::DownloadCommitConfig {
+    crate::tui::distro_downloader::commit::DownloadCommitConfig {
+        iso_url: String::from(distro.url),
+        iso_size: distro.size_bytes,
+        distro_name: String::from(distro.name),
+        placement: ctx.ui_state.placement,
+        post_actions: ctx.ui_state.post_actions,
+        beeps: ctx.ui_state.beeps,
+        rate_limit: ctx.ui_state.rate_limit,
+        link_mode: ctx.ui_state.link_mode,
+        checksums: if ctx.ui_state.checksums {
+            distro.checksums
+        } else {
+            ""
+        },
+    }
+}
+
+/// Show what the download will run with and wait for an explicit yes
+/// before leaving UEFI.
+fn show_review(ctx: &mut InputContext, distro: &'static DistroEntry, screen: &mut Screen) {
+    // A bad port or host would only fail after leaving UEFI
+    if let Err(why) = morpheus_network::mainloop::split_url(distro.url) {
+        ctx.ui_state.set_status(why);
         *ctx.needs_full_redraw = true;
         let render_ctx = ctx.render_context();
         render_full(&render_ctx, screen, true);
+        return;
     }
-
-    ManageAction::Continue
+    let config = commit_config(ctx, distro);
+    let review = unsafe {
+        crate::tui::distro_downloader::commit::CommitReview::gather(
+            &*ctx.boot_services,
+            ctx.image_handle,
+            &config,
+        )
+    };
+    ctx.ui_state.show_review(review);
+    *ctx.needs_full_redraw = true;
+    let render_ctx = ctx.render_context();
+    render_full(&render_ctx, screen, true);
 }
 
 /// Start downloading a distribution
@@ -308,12 +625,7 @@ fn start_download(ctx: &mut InputContext, distro: &'static DistroEntry, screen:
     let render_ctx = ctx.render_context();
     render_full(&render_ctx, screen, true);
 
-    // Build download configuration
-    let config = crate::tui::distro_downloader::commit::DownloadCommitConfig {
-        iso_url: String::from(distro.url),
-        iso_size: distro.size_bytes,
-        distro_name: String::from(distro.name),
-    };
+    let config = commit_config(ctx, distro);
 
     // ═══════════════════════════════════════════════════════════════════════
     // POINT OF NO RETURN - Exit UEFI, hwinit owns the world
@@ -11,6 +11,7 @@
 //! ├── controller.rs # DistroDownloader struct and core logic (~200 LOC)
 //! ├── input.rs      # Input handling methods (~200 LOC)
 //! ├── render.rs     # All rendering methods (~400 LOC)
+//! ├── policy.rs     # Retention policy editor
 //! └── helpers.rs    # Helper functions and constants (~50 LOC)
 //! ```
 //!
@@ -24,6 +25,7 @@
 mod controller;
 mod helpers;
 mod input;
+mod policy;
 mod render;
 
 pub use controller::DistroDownloader;
@@ -0,0 +1,120 @@
+//! Retention policy editor for the ISO store.
+
+extern crate alloc;
+
+use alloc::format;
+
+use crate::tui::input::Keyboard;
+use crate::tui::keymap::{self, Bindings, Command, Key, KeyBinding};
+use crate::tui::renderer::{Screen, EFI_BLACK, EFI_DARKGREEN, EFI_GREEN, EFI_LIGHTGREEN};
+use crate::tui::screensaver::Event;
+use morpheus_core::iso::{RetentionPolicy, MAX_ISOS};
+
+const GB: u64 = 1024 * 1024 * 1024;
+
+/// Size limits offered, in GB (0 = no limit)
+const SIZE_STEPS: &[u64] = &[0, 16, 32, 64, 128, 256, 512];
+
+const BINDINGS: Bindings = Bindings {
+    title: "Storage Policy",
+    keys: &[
+        KeyBinding::new(&[Key::Char(b's')], Command::SizeLimit, "Max total size"),
+        KeyBinding::new(&[Key::Char(b'c')], Command::CountLimit, "Max ISO count"),
+        KeyBinding::new(&[Key::Char(b'a')], Command::AutoDelete, "Auto-delete unverified"),
+        KeyBinding::new(&[Key::Enter], Command::Select, "Save"),
+        KeyBinding::new(&[Key::Esc], Command::Back, "Cancel"),
+    ],
+};
+
+/// Edit `policy` in place. Returns true if the user saved it.
+pub fn edit(screen: &mut Screen, keyboard: &mut Keyboard, policy: &mut RetentionPolicy) -> bool {
+    let mut edited = *policy;
+    screen.clear();
+    draw(screen, &edited);
+
+    loop {
+        let key = match keymap::poll(screen, keyboard, &BINDINGS) {
+            Some(Event::Key(key)) => key,
+            Some(Event::Redraw) => {
+                screen.clear();
+                draw(screen, &edited);
+                continue;
+            }
+            None => continue,
+        };
+
+        match BINDINGS.lookup(&key) {
+            Some(Command::SizeLimit) => {
+                let current = edited.max_total_bytes / GB;
+                let next = SIZE_STEPS
+                    .iter()
+                    .position(|&step| step == current)
+                    .map_or(0, |i| (i + 1) % SIZE_STEPS.len());
+                edited.max_total_bytes = SIZE_STEPS[next] * GB;
+            }
+            Some(Command::CountLimit) => {
+                edited.max_count = (edited.max_count + 1) % (MAX_ISOS + 1);
+            }
+            Some(Command::AutoDelete) => {
+                edited.auto_delete_unverified = !edited.auto_delete_unverified;
+            }
+            Some(Command::Select) => {
+                *policy = edited;
+                return true;
+            }
+            Some(Command::Back) => return false,
+            _ => continue,
+        }
+        draw(screen, &edited);
+    }
+}
+
+fn draw(screen: &mut Screen, policy: &RetentionPolicy) {
+    let x = 4;
+    let y = 3;
+
+    screen.put_str_at(x, y, "=== ISO STORE POLICY ===", EFI_LIGHTGREEN, EFI_BLACK);
+    screen.put_str_at(
+        x,
+        y + 2,
+        "Checked before every download. When a new ISO would not fit,",
+        EFI_DARKGREEN,
+        EFI_BLACK,
+    );
+    screen.put_str_at(
+        x,
+        y + 3,
+        "you are offered older ISOs to delete, unverified ones first.",
+        EFI_DARKGREEN,
+        EFI_BLACK,
+    );
+
+    let size = match policy.max_total_bytes / GB {
+        0 => format!("{:<12}", "no limit"),
+        gb => format!("{:<12}", format!("{} GB", gb)),
+    };
+    let count = match policy.max_count {
+        0 => format!("{:<12}", "no limit"),
+        n => format!("{:<12}", n),
+    };
+    let auto = if policy.auto_delete_unverified {
+        "yes"
+    } else {
+        "no "
+    };
+
+    screen.put_str_at(x, y + 5, "[S] Max total size:  ", EFI_GREEN, EFI_BLACK);
+    screen.put_str_at(x + 22, y + 5, &size, EFI_LIGHTGREEN, EFI_BLACK);
+    screen.put_str_at(x, y + 6, "[C] Max ISO count:   ", EFI_GREEN, EFI_BLACK);
+    screen.put_str_at(x + 22, y + 6, &count, EFI_LIGHTGREEN, EFI_BLACK);
+    screen.put_str_at(x, y + 7, "[A] Auto-delete oldest unverified: ", EFI_GREEN, EFI_BLACK);
+    screen.put_str_at(x + 35, y + 7, auto, EFI_LIGHTGREEN, EFI_BLACK);
+
+    screen.put_str_at(
+        x,
+        y + 9,
+        "[ENTER] Save   [ESC] Cancel",
+        EFI_DARKGREEN,
+        EFI_BLACK,
+    );
+}
@@ -5,6 +5,7 @@
 extern crate alloc;
 
 use alloc::format;
+use alloc::string::String;
 use alloc::vec::Vec;
 
 use super::helpers::{
@@ -18,6 +19,7 @@ use crate::tui::renderer::{
     EFI_YELLOW,
 };
 use morpheus_core::iso::MAX_ISOS;
+use morpheus_network::driver::intel::LinkMode;
 
 /// Rendering context for the distro downloader UI.
 ///
@@ -80,6 +82,14 @@ pub fn render_full(ctx: &RenderContext, screen: &mut Screen, needs_clear: bool)
             render_iso_list(ctx, screen);
             render_manage_confirm_dialog(ctx, screen, "Delete this ISO?");
         }
+        UiMode::ConfirmCleanup => {
+            render_header(screen);
+            render_cleanup_dialog(ctx, screen);
+        }
+        UiMode::Review => {
+            render_header(screen);
+            render_review_dialog(ctx, screen);
+        }
     }
 }
 
@@ -295,9 +305,10 @@ fn render_footer(screen: &mut Screen) {
 
     screen.put_str_at(x, y + 1, "|", EFI_GREEN, EFI_BLACK);
     screen.put_str_at(x + 2, y + 1, "[Arrows] Nav", EFI_DARKGREEN, EFI_BLACK);
-    screen.put_str_at(x + 17, y + 1, "[ENTER] Download", EFI_DARKGREEN, EFI_BLACK);
-    screen.put_str_at(x + 37, y + 1, "[M] Manage ISOs", EFI_DARKGREEN, EFI_BLACK);
-    screen.put_str_at(x + 56, y + 1, "[ESC] Back", EFI_DARKGREEN, EFI_BLACK);
+    screen.put_str_at(x + 16, y + 1, "[ENTER] Download", EFI_DARKGREEN, EFI_BLACK);
+    screen.put_str_at(x + 34, y + 1, "[I] Notes", EFI_DARKGREEN, EFI_BLACK);
+    screen.put_str_at(x + 45, y + 1, "[M] Manage ISOs", EFI_DARKGREEN, EFI_BLACK);
+    screen.put_str_at(x + 62, y + 1, "[ESC] Back", EFI_DARKGREEN, EFI_BLACK);
     screen.put_str_at(x + 78, y + 1, "|", EFI_GREEN, EFI_BLACK);
 
     screen.put_str_at(x, y + 2, "+", EFI_GREEN, EFI_BLACK);
@@ -358,20 +369,48 @@ fn render_confirm_dialog(ctx: &RenderContext, screen: &mut Screen) {
         screen.put_str_at(
             x,
             y + 6,
-            "+--------------------------------------------------------+",
+            "|                                                        |",
             EFI_GREEN,
             EFI_BLACK,
         );
         screen.put_str_at(
             x,
             y + 7,
-            "|     Download to /isos/ on ESP?    [Y]es   [N]o         |",
+            "|                                                        |",
             EFI_GREEN,
             EFI_BLACK,
         );
         screen.put_str_at(
             x,
             y + 8,
+            "|                                                        |",
+            EFI_GREEN,
+            EFI_BLACK,
+        );
+        screen.put_str_at(
+            x,
+            y + 9,
+            "|                                                        |",
+            EFI_GREEN,
+            EFI_BLACK,
+        );
+        screen.put_str_at(
+            x,
+            y + 10,
+            "+--------------------------------------------------------+",
+            EFI_GREEN,
+            EFI_BLACK,
+        );
+        screen.put_str_at(
+            x,
+            y + 11,
+            "|[Y]/[N] [P]lace [D]isk [A]fter [B]eep [L]im [S]pd [C]sum|",
+            EFI_GREEN,
+            EFI_BLACK,
+        );
+        screen.put_str_at(
+            x,
+            y + 12,
             "+--------------------------------------------------------+",
             EFI_GREEN,
             EFI_BLACK,
@@ -391,6 +430,118 @@ fn render_confirm_dialog(ctx: &RenderContext, screen: &mut Screen) {
             distro.filename
         };
         screen.put_str_at(x + 11, y + 5, filename, EFI_GREEN, EFI_BLACK);
+
+        let placement = ctx.ui_state.placement;
+        screen.put_str_at(x + 3, y + 6, "Place:  ", EFI_DARKGREEN, EFI_BLACK);
+        screen.put_str_at(x + 11, y + 6, placement.policy.name(), EFI_GREEN, EFI_BLACK);
+
+        screen.put_str_at(x + 3, y + 7, "Disk:   ", EFI_DARKGREEN, EFI_BLACK);
+        let disk = placement.disk.name();
+        let disk = if disk.len() > 40 { &disk[..40] } else { disk };
+        screen.put_str_at(x + 11, y + 7, disk, EFI_GREEN, EFI_BLACK);
+
+        screen.put_str_at(x + 3, y + 8, "After:  ", EFI_DARKGREEN, EFI_BLACK);
+        let after = ctx.ui_state.post_actions.name();
+        screen.put_str_at(x + 11, y + 8, after, EFI_GREEN, EFI_BLACK);
+
+        screen.put_str_at(x + 38, y + 8, "Sums:  ", EFI_DARKGREEN, EFI_BLACK);
+        let sums = match (ctx.ui_state.checksums, distro.checksums.is_empty()) {
+            (false, _) => "off",
+            (true, true) => "none",
+            (true, false) => "auto",
+        };
+        screen.put_str_at(x + 45, y + 8, sums, EFI_GREEN, EFI_BLACK);
+
+        screen.put_str_at(x + 3, y + 9, "Beeps:  ", EFI_DARKGREEN, EFI_BLACK);
+        let beeps = if ctx.ui_state.beeps { "on" } else { "off" };
+        screen.put_str_at(x + 11, y + 9, beeps, EFI_GREEN, EFI_BLACK);
+
+        screen.put_str_at(x + 20, y + 9, "Limit:  ", EFI_DARKGREEN, EFI_BLACK);
+        let limit = match ctx.ui_state.rate_limit / (1024 * 1024) {
+            0 => String::from("none"),
+            mb => format!("{} MB/s", mb),
+        };
+        screen.put_str_at(x + 28, y + 9, &limit, EFI_GREEN, EFI_BLACK);
+
+        screen.put_str_at(x + 38, y + 9, "Link:  ", EFI_DARKGREEN, EFI_BLACK);
+        let link = match ctx.ui_state.link_mode {
+            LinkMode::Auto => "auto",
+            LinkMode::Force100Full => "100 FD",
+        };
+        screen.put_str_at(x + 45, y + 9, link, EFI_GREEN, EFI_BLACK);
+    }
+}
+
+/// List the ISOs that have to go for the selected download to fit
+fn render_cleanup_dialog(ctx: &RenderContext, screen: &mut Screen) {
+    let x = 10;
+    let y = 5;
+    let border = "+--------------------------------------------------------+";
+    let blank = "|                                                        |";
+    let cleanup = &ctx.ui_state.cleanup;
+
+    screen.put_str_at(x, y, border, EFI_GREEN, EFI_BLACK);
+    screen.put_str_at(
+        x,
+        y + 1,
+        "|              MAKE ROOM FOR DOWNLOAD                    |",
+        EFI_LIGHTGREEN,
+        EFI_BLACK,
+    );
+    screen.put_str_at(x, y + 2, border, EFI_GREEN, EFI_BLACK);
+
+    let rows = cleanup.len().max(1) + 2;
+    for row in 0..rows {
+        screen.put_str_at(x, y + 3 + row, blank, EFI_GREEN, EFI_BLACK);
+    }
+    let footer_y = y + 3 + rows;
+    screen.put_str_at(x, footer_y, border, EFI_GREEN, EFI_BLACK);
+    screen.put_str_at(x, footer_y + 1, blank, EFI_GREEN, EFI_BLACK);
+    screen.put_str_at(x, footer_y + 2, border, EFI_GREEN, EFI_BLACK);
+
+    if ctx.ui_state.cleanup_fits {
+        screen.put_str_at(
+            x + 3,
+            y + 3,
+            "Not enough room under the store policy. Delete:",
+            EFI_WHITE,
+            EFI_BLACK,
+        );
+        for (i, (name, size_mb)) in cleanup.iter().enumerate() {
+            let row_y = y + 4 + i;
+            screen.put_str_at(
+                x + 5,
+                row_y,
+                &pad_or_truncate(name, 38),
+                EFI_LIGHTGREEN,
+                EFI_BLACK,
+            );
+            screen.put_str_at(x + 44, row_y, &format_size_mb(*size_mb), EFI_GREEN, EFI_BLACK);
+            screen.put_str_at(x + 52, row_y, " MB", EFI_GREEN, EFI_BLACK);
+        }
+        screen.put_str_at(
+            x + 3,
+            footer_y + 1,
+            "[Y] Delete and download   [N] Back",
+            EFI_GREEN,
+            EFI_BLACK,
+        );
+    } else {
+        screen.put_str_at(
+            x + 3,
+            y + 3,
+            "This ISO does not fit, even with every stored",
+            EFI_WHITE,
+            EFI_BLACK,
+        );
+        screen.put_str_at(
+            x + 3,
+            y + 4,
+            "ISO deleted. Raise the store limits or free disk.",
+            EFI_WHITE,
+            EFI_BLACK,
+        );
+        screen.put_str_at(x + 3, footer_y + 1, "[N] Back", EFI_GREEN, EFI_BLACK);
     }
 }
 
@@ -547,23 +698,25 @@ fn render_manage_footer(screen: &mut Screen) {
     let y = FOOTER_Y;
 
     screen.put_str_at(x, y, "+-[ Controls ]", EFI_GREEN, EFI_BLACK);
-    for i in 15..70 {
+    for i in 15..78 {
         screen.put_str_at(x + i, y, "-", EFI_GREEN, EFI_BLACK);
     }
-    screen.put_str_at(x + 70, y, "+", EFI_GREEN, EFI_BLACK);
+    screen.put_str_at(x + 78, y, "+", EFI_GREEN, EFI_BLACK);
 
     screen.put_str_at(x, y + 1, "|", EFI_GREEN, EFI_BLACK);
-    screen.put_str_at(x + 2, y + 1, "[UP/DOWN] Select", EFI_DARKGREEN, EFI_BLACK);
-    screen.put_str_at(x + 22, y + 1, "[D] Delete", EFI_DARKGREEN, EFI_BLACK);
-    screen.put_str_at(x + 38, y + 1, "[R] Refresh", EFI_DARKGREEN, EFI_BLACK);
-    screen.put_str_at(x + 54, y + 1, "[ESC] Back", EFI_DARKGREEN, EFI_BLACK);
-    screen.put_str_at(x + 70, y + 1, "|", EFI_GREEN, EFI_BLACK);
+    screen.put_str_at(x + 2, y + 1, "[D] Delete", EFI_DARKGREEN, EFI_BLACK);
+    screen.put_str_at(x + 15, y + 1, "[R] Refresh", EFI_DARKGREEN, EFI_BLACK);
+    screen.put_str_at(x + 29, y + 1, "[S] Sums", EFI_DARKGREEN, EFI_BLACK);
+    screen.put_str_at(x + 40, y + 1, "[H] History", EFI_DARKGREEN, EFI_BLACK);
+    screen.put_str_at(x + 54, y + 1, "[P] Policy", EFI_DARKGREEN, EFI_BLACK);
+    screen.put_str_at(x + 67, y + 1, "[ESC] Back", EFI_DARKGREEN, EFI_BLACK);
+    screen.put_str_at(x + 78, y + 1, "|", EFI_GREEN, EFI_BLACK);
 
     screen.put_str_at(x, y + 2, "+", EFI_GREEN, EFI_BLACK);
-    for i in 1..70 {
+    for i in 1..78 {
         screen.put_str_at(x + i, y + 2, "-", EFI_GREEN, EFI_BLACK);
     }
-    screen.put_str_at(x + 70, y + 2, "+", EFI_GREEN, EFI_BLACK);
+    screen.put_str_at(x + 78, y + 2, "+", EFI_GREEN, EFI_BLACK);
 }
 
 fn render_manage_confirm_dialog(ctx: &RenderContext, screen: &mut Screen, message: &str) {
@@ -641,3 +794,56 @@ fn render_manage_confirm_dialog(ctx: &RenderContext, screen: &mut Screen, messag
     screen.put_str_at(x + 3, y + 3, message, EFI_WHITE, EFI_BLACK);
     screen.put_str_at(x + 3, y + 4, name, EFI_LIGHTGREEN, EFI_BLACK);
 }
+
+fn render_review_dialog(ctx: &RenderContext, screen: &mut Screen) {
+    let Some(review) = &ctx.ui_state.review else {
+        return;
+    };
+    let x = 10;
+    let y = 5;
+    let border = "+--------------------------------------------------------+";
+    let blank = "|                                                        |";
+    let rows = review.rows();
+    let blockers = review.blockers();
+
+    screen.put_str_at(x, y, border, EFI_GREEN, EFI_BLACK);
+    screen.put_str_at(
+        x,
+        y + 1,
+        "|          REVIEW BEFORE LEAVING UEFI                    |",
+        EFI_LIGHTGREEN,
+        EFI_BLACK,
+    );
+    screen.put_str_at(x, y + 2, border, EFI_GREEN, EFI_BLACK);
+
+    let body = rows.len() + blockers.len() + 2;
+    for row in 0..body {
+        screen.put_str_at(x, y + 3 + row, blank, EFI_GREEN, EFI_BLACK);
+    }
+    let footer_y = y + 3 + body;
+    screen.put_str_at(x, footer_y, border, EFI_GREEN, EFI_BLACK);
+    screen.put_str_at(x, footer_y + 1, blank, EFI_GREEN, EFI_BLACK);
+    screen.put_str_at(x, footer_y + 2, border, EFI_GREEN, EFI_BLACK);
+
+    for (i, (label, value)) in rows.iter().enumerate() {
+        let row_y = y + 4 + i;
+        screen.put_str_at(x + 3, row_y, label, EFI_GREEN, EFI_BLACK);
+        screen.put_str_at(
+            x + 13,
+            row_y,
+            &pad_or_truncate(value, 42),
+            EFI_WHITE,
+            EFI_BLACK,
+        );
+    }
+    for (i, blocker) in blockers.iter().enumerate() {
+        screen.put_str_at(x + 3, y + 5 + rows.len() + i, blocker, EFI_RED, EFI_BLACK);
+    }
+
+    let footer = if blockers.is_empty() {
+        "[Y] Exit UEFI and download   [N] Back"
+    } else {
+        "[N] Back"
+    };
+    screen.put_str_at(x + 3, footer_y + 1, footer, EFI_GREEN, EFI_BLACK);
+}
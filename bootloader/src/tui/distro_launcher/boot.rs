@@ -272,7 +272,7 @@ impl DistroLauncher {
         use crate::tui::boot_sequence::BootSequence;
         use crate::tui::widgets::progressbar::ProgressBar;
         use crate::uefi::block_io_adapter::UefiBlockIo;
-        use morpheus_core::iso::{IsoBlockIoAdapter, IsoStorageManager};
+        use morpheus_core::iso::{DiskPool, IsoBlockIoAdapter, IsoStorageManager};
 
         screen.clear();
         screen.put_str_at(5, 2, "Booting from Chunked ISO", EFI_LIGHTGREEN, EFI_BLACK);
@@ -362,7 +362,8 @@ impl DistroLauncher {
                 )
                 .leak(),
             );
-            if end_lba > max_lba_needed {
+            // Chunks on other disks are found by identity, not size
+            if read_ctx.chunk_disks[i] == 0 && end_lba > max_lba_needed {
                 max_lba_needed = end_lba;
             }
         }
@@ -405,8 +406,42 @@ impl DistroLauncher {
             .leak(),
         );
 
+        // Open the other disks of a pooled ISO
+        let mut pool_disks = match Self::open_pool_disks(boot_services, &read_ctx) {
+            Ok(disks) => disks,
+            Err(disk_id) => {
+                screen.put_str_at(
+                    5,
+                    18,
+                    "ERROR: A disk holding ISO data is missing",
+                    EFI_RED,
+                    EFI_BLACK,
+                );
+                screen.put_str_at(
+                    5,
+                    19,
+                    &format!("Disk ID {:08x}", disk_id),
+                    EFI_YELLOW,
+                    EFI_BLACK,
+                );
+                keyboard.wait_for_key();
+                return;
+            }
+        };
+        let mut pool = DiskPool::single(&mut uefi_block_io);
+        for (disk_id, block_io) in pool_disks.iter_mut() {
+            let _ = pool.add(*disk_id, block_io);
+        }
+
         // Create ISO adapter that bridges chunked storage to iso9660
-        let mut iso_adapter = IsoBlockIoAdapter::new(read_ctx, &mut uefi_block_io);
+        let mut iso_adapter = match IsoBlockIoAdapter::with_pool(read_ctx, pool) {
+            Ok(adapter) => adapter,
+            Err(_) => {
+                screen.put_str_at(5, 18, "ERROR: ISO spans too many disks", EFI_RED, EFI_BLACK);
+                keyboard.wait_for_key();
+                return;
+            }
+        };
 
         // DEBUG: Clear screen and show debug info
         screen.clear();
@@ -968,6 +1003,43 @@ impl DistroLauncher {
 
     /// Get BlockIoProtocol for a disk that can contain the given LBA
     /// This finds a physical disk (not partition) where last_block >= required_lba
+    /// Block I/O for every disk, other than the ESP's, that holds a chunk
+    /// of the ISO. Fails with the ID of a disk that isn't attached.
+    fn open_pool_disks(
+        boot_services: &crate::BootServices,
+        read_ctx: &morpheus_core::iso::IsoReadContext,
+    ) -> Result<Vec<(u32, crate::uefi::block_io_adapter::UefiBlockIo)>, u32> {
+        use crate::uefi::block_io_adapter::UefiBlockIo;
+
+        let mut disks: Vec<(u32, UefiBlockIo)> = Vec::new();
+        let needed = read_ctx.chunk_disks[..read_ctx.num_chunks]
+            .iter()
+            .copied()
+            .filter(|&id| id != 0);
+        let Some(first) = needed.clone().next() else {
+            return Ok(disks);
+        };
+        let mut dm = morpheus_core::disk::manager::DiskManager::new();
+        crate::uefi::disk::enumerate_disks(boot_services, &mut dm).map_err(|_| first)?;
+
+        for disk_id in needed {
+            if disks.iter().any(|(id, _)| *id == disk_id) {
+                continue;
+            }
+            let index = (0..dm.disk_count())
+                .find(|&i| dm.get_disk(i).is_some_and(|d| d.identity.disk_id() == disk_id))
+                .ok_or(disk_id)?;
+            let protocol =
+                crate::uefi::disk::get_disk_protocol(boot_services, index).map_err(|_| disk_id)?;
+            morpheus_core::logger::log(
+                format!("Pooled ISO: disk {:08x} is disk {}", disk_id, index).leak(),
+            );
+            // SAFETY: protocol pointer comes from get_disk_protocol
+            disks.push((disk_id, unsafe { UefiBlockIo::new(protocol) }));
+        }
+        Ok(disks)
+    }
+
     fn get_disk_for_lba(
         boot_services: &crate::BootServices,
         required_lba: u64,
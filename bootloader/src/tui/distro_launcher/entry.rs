@@ -7,6 +7,8 @@ pub struct BootEntry {
     pub initrd_path: Option<String>,
     pub cmdline: String,
     pub root_device: Option<String>,
+    /// Name of the stored ISO this entry boots, for chunked ISOs
+    pub iso_name: Option<String>,
 }
 
 impl BootEntry {
@@ -22,6 +24,7 @@ impl BootEntry {
             initrd_path,
             cmdline,
             root_device: None,
+            iso_name: None,
         }
     }
 
@@ -29,4 +32,9 @@ impl BootEntry {
         self.root_device = Some(root);
         self
     }
+
+    pub fn with_iso(mut self, name: String) -> Self {
+        self.iso_name = Some(name);
+        self
+    }
 }
@@ -69,7 +69,7 @@ impl EntryRenderer {
 
         // Instructions
         screen.put_str_at(x, current_y, "|", EFI_GREEN, EFI_BLACK);
-        let instr = "[UP/DOWN] Navigate  |  [ENTER] Boot  |  [ESC] Back";
+        let instr = "[UP/DOWN] Navigate  |  [ENTER] Boot  |  [F] Default  |  [ESC] Back";
         let instr_padding = (75 - instr.len()) / 2;
         screen.put_str_at(
             x + 1 + instr_padding,
@@ -137,12 +137,15 @@ impl EntryScanner {
             let distro_name = Self::extract_distro_from_name(name);
 
             // Create entry with special chunked: prefix to indicate chunked ISO
-            entries.push(BootEntry::new(
-                format!("{} (Chunked ISO)", distro_name),
-                format!("chunked:{}", idx), // Special path indicating chunked ISO index
-                None,
-                format!("chunked_iso:{}", idx),
-            ));
+            entries.push(
+                BootEntry::new(
+                    format!("{} (Chunked ISO)", distro_name),
+                    format!("chunked:{}", idx), // Special path indicating chunked ISO index
+                    None,
+                    format!("chunked_iso:{}", idx),
+                )
+                .with_iso(name.to_string()),
+            );
         }
 
         morpheus_core::logger::log(format!("Found {} chunked ISOs", entries.len()).leak());
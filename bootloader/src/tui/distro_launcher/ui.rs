@@ -2,13 +2,38 @@ use super::entry::BootEntry;
 use super::renderer::EntryRenderer;
 use super::scanner::EntryScanner;
 use crate::boot::loader::BootError;
+use crate::tui::distro_downloader::manifest_io;
 use crate::tui::input::Keyboard;
+use crate::tui::keymap::{self, Bindings, Command, Key, KeyBinding};
 use crate::tui::renderer::Screen;
+use crate::tui::screensaver::Event;
 use alloc::vec::Vec;
+use morpheus_core::iso::{BootMenuConfig, DefaultKind};
+
+/// Timeout set along with a default entry when there was none, in seconds
+const DEFAULT_TIMEOUT_SECS: u16 = 5;
+
+const BINDINGS: Bindings = Bindings {
+    title: "Distro Launcher",
+    keys: &[
+        KeyBinding::new(&[Key::Up], Command::Up, "Previous entry"),
+        KeyBinding::new(&[Key::Down], Command::Down, "Next entry"),
+        KeyBinding::new(&[Key::Enter], Command::Boot, "Boot selected entry"),
+        KeyBinding::new(
+            &[Key::Char(b'f')],
+            Command::MakeDefault,
+            "Boot this entry by default",
+        ),
+        KeyBinding::new(&[Key::Esc], Command::Back, "Back to main menu"),
+    ],
+};
 
 pub struct DistroLauncher {
     entries: Vec<BootEntry>,
     selected_index: usize,
+    /// Boot the selected entry without waiting for a key (one-shot boot
+    /// request from a download)
+    auto_boot: bool,
 }
 
 impl DistroLauncher {
@@ -20,11 +45,82 @@ impl DistroLauncher {
 
         morpheus_core::logger::log(alloc::format!("Found {} boot entries", entries.len()).leak());
 
-        Self {
+        let mut launcher = Self {
             entries,
             selected_index: 0,
+            auto_boot: false,
+        };
+        unsafe { launcher.apply_boot_request(&*boot_services, image_handle) };
+        launcher
+    }
+
+    /// Select the ISO a download asked for. A one-shot request is removed
+    /// before it is acted on, so an ISO that fails to boot is not retried
+    /// on every start.
+    unsafe fn apply_boot_request(&mut self, bs: &crate::BootServices, image_handle: *mut ()) {
+        let Some(request) = manifest_io::load_boot_request(bs, image_handle) else {
+            return;
+        };
+        let Some(index) = self
+            .entries
+            .iter()
+            .position(|e| e.iso_name.as_deref() == Some(request.name_str()))
+        else {
+            morpheus_core::logger::log("Boot request names a missing ISO, ignoring");
+            return;
+        };
+        self.selected_index = index;
+        if request.once {
+            self.auto_boot = manifest_io::clear_boot_request(bs, image_handle).is_ok();
+        }
+    }
+
+    /// Select the main menu's default entry and boot it on `run`, once
+    /// its countdown ran out. Returns false if no entry matches.
+    pub fn boot_default(&mut self, config: &BootMenuConfig) -> bool {
+        let name = config.name_str();
+        let index = match config.kind {
+            DefaultKind::Iso => self
+                .entries
+                .iter()
+                .position(|e| e.iso_name.as_deref() == Some(name)),
+            DefaultKind::Installed => self
+                .entries
+                .iter()
+                .position(|e| e.iso_name.is_none() && e.name == name),
+            DefaultKind::None | DefaultKind::Firmware => None,
+        };
+        let Some(index) = index else {
+            morpheus_core::logger::log("Default boot entry is missing, waiting for a key");
+            return false;
+        };
+        self.selected_index = index;
+        self.auto_boot = true;
+        true
+    }
+
+    /// Make the selected entry the one the main menu boots on its own
+    unsafe fn make_default(&self, bs: &crate::BootServices, image_handle: *mut ()) {
+        let Some(entry) = self.entries.get(self.selected_index) else {
+            return;
+        };
+        let mut config = manifest_io::load_boot_menu(bs, image_handle);
+        match &entry.iso_name {
+            Some(iso) => config.set_default(DefaultKind::Iso, iso),
+            None => config.set_default(DefaultKind::Installed, &entry.name),
+        }
+        if config.timeout_secs == 0 {
+            config.timeout_secs = DEFAULT_TIMEOUT_SECS;
+        }
+        if manifest_io::save_boot_menu(bs, image_handle, &config).is_err() {
+            morpheus_core::logger::log("Failed to save default boot entry");
+        } else {
+            morpheus_core::logger::log(
+                alloc::format!("Default boot entry: {}", config.name_str()).leak(),
+            );
         }
     }
+
     fn select_next(&mut self) {
         if self.selected_index < self.entries.len() - 1 {
             self.selected_index += 1;
@@ -53,27 +149,46 @@ impl DistroLauncher {
         screen.clear();
         self.render(screen);
 
+        if core::mem::take(&mut self.auto_boot) {
+            let entry = &self.entries[self.selected_index];
+            self.boot_entry(
+                screen,
+                keyboard,
+                boot_services,
+                system_table,
+                image_handle,
+                entry,
+            );
+            // If we return here, boot failed
+            screen.clear();
+            self.render(screen);
+        }
+
         loop {
-            if let Some(key) = keyboard.poll_key_with_delay() {
-                // ESC - return to main menu
-                if key.scan_code == 0x17 {
-                    return;
+            let key = match keymap::poll(screen, keyboard, &BINDINGS) {
+                Some(Event::Key(key)) => key,
+                Some(Event::Redraw) => {
+                    screen.clear();
+                    self.render(screen);
+                    continue;
                 }
+                None => continue,
+            };
 
-                // Up arrow
-                if key.scan_code == 0x01 {
+            match BINDINGS.lookup(&key) {
+                Some(Command::Back) => return,
+                Some(Command::Up) => {
                     self.select_prev();
                     self.render(screen);
                 }
-
-                // Down arrow
-                if key.scan_code == 0x02 {
+                Some(Command::Down) => {
                     self.select_next();
                     self.render(screen);
                 }
-
-                // Enter - boot selected kernel
-                if key.unicode_char == 0x0D {
+                Some(Command::MakeDefault) => unsafe {
+                    self.make_default(boot_services, image_handle);
+                },
+                Some(Command::Boot) => {
                     morpheus_core::logger::log("enter pressed");
                     let entry = &self.entries[self.selected_index];
                     morpheus_core::logger::log("entry selected");
@@ -93,6 +208,7 @@ impl DistroLauncher {
                     self.render(screen);
                     morpheus_core::logger::log("render complete");
                 }
+                _ => {}
             }
         }
     }
@@ -0,0 +1,516 @@
+//! Factory reset - one guided path from any disk to a bootable MorpheusX.
+//!
+//! Runs the steps the storage manager, installer and distro downloader
+//! already provide, in the order a new user needs them, so nobody has to
+//! visit four menus and get the order right:
+//!
+//! ```text
+//! pick disk → confirm → new GPT → ESP → ISO store → install → boot entry
+//!                                                      └→ download a distro (optional)
+//! ```
+//!
+//! The ESP takes the first 512MB of the disk. Everything after it is left
+//! free for the ISO store, which carves out chunk partitions as distros
+//! are downloaded.
+
+use crate::installer::{self, EspInfo, InstallError};
+use crate::tui::distro_downloader::DistroDownloader;
+use crate::tui::input::Keyboard;
+use crate::tui::keymap::{self, Bindings, Command, Key, KeyBinding};
+use crate::tui::renderer::{
+    Screen, EFI_BLACK, EFI_DARKGREEN, EFI_GREEN, EFI_LIGHTGREEN, EFI_WHITE,
+};
+use crate::tui::screensaver::Event;
+use crate::tui::storage_manager::{disk_label, guard::confirm_protected};
+use crate::tui::widgets::progressbar::ProgressBar;
+use crate::uefi::gpt_adapter::UefiBlockIoAdapter;
+use crate::BootServices;
+use alloc::format;
+use morpheus_core::disk::gpt_ops;
+use morpheus_core::disk::guard;
+use morpheus_core::disk::manager::DiskManager;
+use morpheus_core::iso::MANIFEST_DIR;
+
+const BINDINGS: Bindings = Bindings {
+    title: "Factory Reset",
+    keys: &[
+        KeyBinding::new(&[Key::Up], Command::Up, "Previous disk"),
+        KeyBinding::new(&[Key::Down], Command::Down, "Next disk"),
+        KeyBinding::new(&[Key::Enter], Command::Select, "Reset selected disk"),
+        KeyBinding::new(&[Key::Esc], Command::Back, "Back to main menu"),
+    ],
+};
+
+/// Workflow steps, in the order they run.
+const STEPS: [&str; 5] = [
+    "Wipe partition table (new GPT)",
+    "Create 512MB EFI System Partition",
+    "Prepare ISO store",
+    "Install MorpheusX to the ESP",
+    "Register boot entry",
+];
+
+/// Removable-media fallback path. Firmware boots it from any ESP without
+/// an NVRAM Boot#### entry.
+const BOOT_PATH: &str = "/EFI/BOOT/BOOTX64.EFI";
+
+/// First row of the step checklist.
+const STEPS_Y: usize = 7;
+const PROGRESS_Y: usize = STEPS_Y + STEPS.len() + 1;
+const STATUS_Y: usize = PROGRESS_Y + 2;
+
+#[derive(Clone, Copy, PartialEq)]
+enum StepState {
+    Pending,
+    Running,
+    Done,
+    Failed,
+}
+
+impl StepState {
+    fn tag(self) -> &'static str {
+        match self {
+            StepState::Pending => "[  ]",
+            StepState::Running => "[..]",
+            StepState::Done => "[OK]",
+            StepState::Failed => "[!!]",
+        }
+    }
+}
+
+pub struct FactoryReset {
+    disk_manager: DiskManager,
+    selected_disk: usize,
+    steps: [StepState; STEPS.len()],
+    image_handle: *mut (),
+}
+
+impl FactoryReset {
+    pub fn new(image_handle: *mut ()) -> Self {
+        Self {
+            disk_manager: DiskManager::new(),
+            selected_disk: 0,
+            steps: [StepState::Pending; STEPS.len()],
+            image_handle,
+        }
+    }
+
+    /// Run the workflow. Returns whether a disk was modified, so the caller
+    /// knows to refresh anything it has cached about disks and ESPs.
+    pub fn run(&mut self, screen: &mut Screen, keyboard: &mut Keyboard, bs: &BootServices) -> bool {
+        if crate::uefi::disk::enumerate_disks(bs, &mut self.disk_manager).is_err()
+            || self.disk_manager.disk_count() == 0
+        {
+            screen.clear();
+            let msg = "ERROR: No storage devices found";
+            screen.put_str_at(
+                screen.center_x(msg.len()),
+                5,
+                msg,
+                EFI_LIGHTGREEN,
+                EFI_BLACK,
+            );
+            let cont = "Press any key to return...";
+            screen.put_str_at(
+                screen.center_x(cont.len()),
+                7,
+                cont,
+                EFI_DARKGREEN,
+                EFI_BLACK,
+            );
+            keyboard.wait_for_key();
+            return false;
+        }
+
+        if !self.select_disk(screen, keyboard) || !self.confirm_wipe(screen, keyboard) {
+            return false;
+        }
+
+        self.steps = [StepState::Pending; STEPS.len()];
+        match self.execute(screen, bs) {
+            Ok((esp, iso_store_mb)) => self.finish(screen, keyboard, bs, &esp, iso_store_mb),
+            Err(msg) => {
+                let err = format!("ERROR: {}", msg);
+                screen.put_str_at(
+                    screen.center_x(err.len()),
+                    STATUS_Y,
+                    &err,
+                    EFI_WHITE,
+                    EFI_BLACK,
+                );
+                let hint = "The disk may be left partly set up; run Factory Reset again.";
+                screen.put_str_at(
+                    screen.center_x(hint.len()),
+                    STATUS_Y + 1,
+                    hint,
+                    EFI_GREEN,
+                    EFI_BLACK,
+                );
+                let cont = "Press any key to return...";
+                screen.put_str_at(
+                    screen.center_x(cont.len()),
+                    STATUS_Y + 3,
+                    cont,
+                    EFI_DARKGREEN,
+                    EFI_BLACK,
+                );
+                keyboard.wait_for_key();
+            }
+        }
+        true
+    }
+
+    /// Disk picker. Returns false if the user backed out.
+    fn select_disk(&mut self, screen: &mut Screen, keyboard: &mut Keyboard) -> bool {
+        let disk_count = self.disk_manager.disk_count();
+        screen.clear();
+        self.render_disk_list(screen);
+
+        loop {
+            match keymap::poll(screen, keyboard, &BINDINGS) {
+                Some(Event::Key(key)) => match BINDINGS.lookup(&key) {
+                    Some(Command::Up) if self.selected_disk > 0 => {
+                        self.selected_disk -= 1;
+                        self.render_disk_list(screen);
+                    }
+                    Some(Command::Down) if self.selected_disk + 1 < disk_count => {
+                        self.selected_disk += 1;
+                        self.render_disk_list(screen);
+                    }
+                    Some(Command::Select) => return true,
+                    Some(Command::Back) => return false,
+                    _ => {}
+                },
+                Some(Event::Redraw) => {
+                    screen.clear();
+                    self.render_disk_list(screen);
+                }
+                None => {}
+            }
+        }
+    }
+
+    fn render_disk_list(&self, screen: &mut Screen) {
+        let title = "=== FACTORY RESET ===";
+        screen.put_str_at(
+            screen.center_x(title.len()),
+            2,
+            title,
+            EFI_LIGHTGREEN,
+            EFI_BLACK,
+        );
+        let intro = "Sets up a disk from scratch: GPT, ESP, ISO store, MorpheusX, boot entry.";
+        screen.put_str_at(screen.center_x(intro.len()), 4, intro, EFI_GREEN, EFI_BLACK);
+        let prompt = "Select the disk to reset:";
+        screen.put_str_at(
+            screen.center_x(prompt.len()),
+            5,
+            prompt,
+            EFI_GREEN,
+            EFI_BLACK,
+        );
+
+        let list_width = 76;
+        let list_x = screen.center_x(list_width);
+        for i in 0..self.disk_manager.disk_count() {
+            let Some(disk) = self.disk_manager.get_disk(i) else {
+                continue;
+            };
+            let selected = i == self.selected_disk;
+            let color = if selected { EFI_LIGHTGREEN } else { EFI_GREEN };
+            let marker = if selected { ">" } else { " " };
+
+            let mut line = format!("{} {}", marker, disk_label(&self.disk_manager, i));
+            if disk.boot_disk {
+                line.push_str("  [Boot disk]");
+            }
+            if disk.removable {
+                line.push_str("  [Removable]");
+            }
+            line.truncate(list_width);
+            // Pad so a shorter line fully overwrites the previous render
+            let line = format!("{:<width$}", line, width = list_width);
+            screen.put_str_at(list_x, STEPS_Y + i, &line, color, EFI_BLACK);
+        }
+
+        let help = "[UP/DOWN] Select | [ENTER] Reset disk | [ESC] Back";
+        let help_y = STEPS_Y + self.disk_manager.disk_count() + 2;
+        screen.put_str_at(
+            screen.center_x(help.len()),
+            help_y,
+            help,
+            EFI_DARKGREEN,
+            EFI_BLACK,
+        );
+    }
+
+    /// List what is about to happen and ask for Y, then the confirmation
+    /// phrase if the target is the disk MorpheusX booted from.
+    fn confirm_wipe(&self, screen: &mut Screen, keyboard: &mut Keyboard) -> bool {
+        screen.clear();
+        let title = "=== FACTORY RESET ===";
+        screen.put_str_at(
+            screen.center_x(title.len()),
+            2,
+            title,
+            EFI_LIGHTGREEN,
+            EFI_BLACK,
+        );
+        let target = "You are about to wipe:";
+        screen.put_str_at(
+            screen.center_x(target.len()),
+            4,
+            target,
+            EFI_GREEN,
+            EFI_BLACK,
+        );
+        let label = disk_label(&self.disk_manager, self.selected_disk);
+        screen.put_str_at(
+            screen.center_x(label.len()),
+            5,
+            &label,
+            EFI_LIGHTGREEN,
+            EFI_BLACK,
+        );
+
+        let steps_x = screen.center_x(40);
+        for (i, step) in STEPS.iter().enumerate() {
+            let line = format!("{}. {}", i + 1, step);
+            screen.put_str_at(steps_x, STEPS_Y + i, &line, EFI_GREEN, EFI_BLACK);
+        }
+
+        let warn = "WARNING: Every partition on this disk will be lost!";
+        screen.put_str_at(
+            screen.center_x(warn.len()),
+            PROGRESS_Y,
+            warn,
+            EFI_LIGHTGREEN,
+            EFI_BLACK,
+        );
+        let confirm = "Press Y to confirm, any other key to cancel";
+        screen.put_str_at(
+            screen.center_x(confirm.len()),
+            STATUS_Y,
+            confirm,
+            EFI_GREEN,
+            EFI_BLACK,
+        );
+
+        let key = keyboard.wait_for_key();
+        if key.unicode_char != b'y' as u16 && key.unicode_char != b'Y' as u16 {
+            return false;
+        }
+
+        let protection = self
+            .disk_manager
+            .get_disk(self.selected_disk)
+            .map_or(guard::Protection::None, guard::disk_protection);
+        confirm_protected(protection, screen, keyboard)
+    }
+
+    /// Run every step, stopping at the first failure.
+    /// Returns the new ESP and the space left for ISOs, in MB.
+    fn execute(
+        &mut self,
+        screen: &mut Screen,
+        bs: &BootServices,
+    ) -> Result<(EspInfo, u64), &'static str> {
+        let disk_index = self.selected_disk;
+        let image_handle = self.image_handle;
+
+        screen.clear();
+        let title = "=== FACTORY RESET ===";
+        screen.put_str_at(
+            screen.center_x(title.len()),
+            2,
+            title,
+            EFI_LIGHTGREEN,
+            EFI_BLACK,
+        );
+        let label = disk_label(&self.disk_manager, disk_index);
+        screen.put_str_at(
+            screen.center_x(label.len()),
+            4,
+            &label,
+            EFI_GREEN,
+            EFI_BLACK,
+        );
+
+        self.step(screen, 0, |_| wipe_disk(bs, disk_index))?;
+        let esp = self.step(screen, 1, |_| {
+            installer::create_esp_and_install(bs, disk_index).map_err(install_error)
+        })?;
+        let iso_store_mb = self.step(screen, 2, |_| prepare_iso_store(bs, &esp))?;
+        self.step(screen, 3, |screen| install(screen, bs, &esp, image_handle))?;
+        self.step(screen, 4, |_| check_boot_entry(bs, &esp))?;
+
+        Ok((esp, iso_store_mb))
+    }
+
+    /// Run step `index`, keeping the checklist up to date.
+    fn step<T>(
+        &mut self,
+        screen: &mut Screen,
+        index: usize,
+        f: impl FnOnce(&mut Screen) -> Result<T, &'static str>,
+    ) -> Result<T, &'static str> {
+        self.steps[index] = StepState::Running;
+        self.render_steps(screen);
+        screen.present();
+
+        let result = f(screen);
+        self.steps[index] = if result.is_ok() {
+            StepState::Done
+        } else {
+            StepState::Failed
+        };
+        self.render_steps(screen);
+        result
+    }
+
+    fn render_steps(&self, screen: &mut Screen) {
+        let steps_x = screen.center_x(40);
+        for (i, (step, state)) in STEPS.iter().zip(self.steps).enumerate() {
+            let color = match state {
+                StepState::Pending => EFI_DARKGREEN,
+                StepState::Running => EFI_LIGHTGREEN,
+                StepState::Done => EFI_GREEN,
+                StepState::Failed => EFI_WHITE,
+            };
+            let line = format!("{} {}", state.tag(), step);
+            screen.put_str_at(steps_x, STEPS_Y + i, &line, color, EFI_BLACK);
+        }
+    }
+
+    /// Summary, then offer to go straight to the distro downloader.
+    fn finish(
+        &self,
+        screen: &mut Screen,
+        keyboard: &mut Keyboard,
+        bs: &BootServices,
+        esp: &EspInfo,
+        iso_store_mb: u64,
+    ) {
+        let done = "[OK] Factory reset complete - this disk now boots MorpheusX";
+        screen.put_str_at(
+            screen.center_x(done.len()),
+            STATUS_Y,
+            done,
+            EFI_LIGHTGREEN,
+            EFI_BLACK,
+        );
+        let store = format!("ISO store: {} MB free for downloads", iso_store_mb);
+        screen.put_str_at(
+            screen.center_x(store.len()),
+            STATUS_Y + 1,
+            &store,
+            EFI_GREEN,
+            EFI_BLACK,
+        );
+        let prompt = "[D] Download a distro now | any other key to finish";
+        screen.put_str_at(
+            screen.center_x(prompt.len()),
+            STATUS_Y + 3,
+            prompt,
+            EFI_DARKGREEN,
+            EFI_BLACK,
+        );
+
+        let key = keyboard.wait_for_key();
+        if key.unicode_char != b'd' as u16 && key.unicode_char != b'D' as u16 {
+            return;
+        }
+
+        let disk_size_lba = self
+            .disk_manager
+            .get_disk(esp.disk_index)
+            .map_or(0, |disk| disk.last_block + 1);
+        let mut downloader =
+            DistroDownloader::new(bs, self.image_handle, esp.start_lba, disk_size_lba);
+        downloader.run(screen, keyboard);
+    }
+}
+
+/// Write an empty GPT over whatever the disk held.
+fn wipe_disk(bs: &BootServices, disk_index: usize) -> Result<(), &'static str> {
+    let block_io_ptr = crate::uefi::disk::get_disk_protocol(bs, disk_index)
+        .map_err(|_| "Failed to get BlockIO protocol")?;
+    let block_io = unsafe { &mut *block_io_ptr };
+    let disk_size_lba = unsafe { (*block_io.media).last_block } + 1;
+
+    let adapter = UefiBlockIoAdapter::new(block_io).map_err(|_| "Failed to create adapter")?;
+    gpt_ops::create_gpt(adapter, disk_size_lba).map_err(|e| match e {
+        gpt_ops::GptError::IoError => "I/O error writing GPT",
+        _ => "Failed to create GPT",
+    })
+}
+
+/// The ISO store is the manifest directory on the ESP plus the free space
+/// after it. Creates the directory and returns the free space in MB.
+fn prepare_iso_store(bs: &BootServices, esp: &EspInfo) -> Result<u64, &'static str> {
+    let block_io_ptr = crate::uefi::disk::get_disk_protocol(bs, esp.disk_index)
+        .map_err(|_| "Failed to get BlockIO protocol")?;
+    let block_io = unsafe { &mut *block_io_ptr };
+    let block_size = unsafe { (*block_io.media).block_size } as usize;
+
+    let mut adapter = UefiBlockIoAdapter::new(block_io).map_err(|_| "Failed to create adapter")?;
+    morpheus_core::fs::create_directory(&mut adapter, esp.start_lba, MANIFEST_DIR)
+        .map_err(|_| "Failed to create ISO directory on the ESP")?;
+
+    let adapter = UefiBlockIoAdapter::new(block_io).map_err(|_| "Failed to create adapter")?;
+    match gpt_ops::calculate_total_free_space(adapter, block_size) {
+        Ok(0) => Err("No space left on the disk for ISOs"),
+        Ok(free_mb) => Ok(free_mb),
+        Err(_) => Err("Failed to read free space"),
+    }
+}
+
+fn install(
+    screen: &mut Screen,
+    bs: &BootServices,
+    esp: &EspInfo,
+    image_handle: *mut (),
+) -> Result<(), &'static str> {
+    let mut progress_bar = ProgressBar::new(screen.center_x(60), PROGRESS_Y, 60, "Installing:");
+    progress_bar.render(screen);
+
+    let mut last_percent = 0;
+    let mut progress = |bytes: usize, total: usize, _msg: &str| {
+        let percent = if total > 0 { bytes * 100 / total } else { 0 };
+        if percent != last_percent {
+            progress_bar.set_progress(percent);
+            progress_bar.render(screen);
+            last_percent = percent;
+        }
+    };
+    installer::install_to_esp_with_progress(bs, esp, image_handle, Some(&mut progress))
+        .map_err(install_error)
+}
+
+/// Firmware falls back to `BOOT_PATH` on any ESP it finds, so making sure
+/// the install landed there is what makes the disk bootable. No Boot####
+/// variable is written: the TUI has no access to runtime services.
+fn check_boot_entry(bs: &BootServices, esp: &EspInfo) -> Result<(), &'static str> {
+    let block_io_ptr = crate::uefi::disk::get_disk_protocol(bs, esp.disk_index)
+        .map_err(|_| "Failed to get BlockIO protocol")?;
+    let block_io = unsafe { &mut *block_io_ptr };
+
+    let mut adapter = UefiBlockIoAdapter::new(block_io).map_err(|_| "Failed to create adapter")?;
+    match morpheus_core::fs::file_exists(&mut adapter, esp.start_lba, BOOT_PATH) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err("BOOTX64.EFI missing after install"),
+        Err(_) => Err("Failed to read the new ESP"),
+    }
+}
+
+fn install_error(e: InstallError) -> &'static str {
+    match e {
+        InstallError::NoEsp => "No EFI System Partition found",
+        InstallError::EspTooSmall => "ESP too small",
+        InstallError::IoError => "I/O error",
+        InstallError::ProtocolError => "UEFI protocol error",
+        InstallError::AlreadyInstalled => "Already installed",
+        InstallError::NoFreeSpc => "Not enough free space for a 512MB ESP",
+        InstallError::FormatFailed => "Failed to format the ESP as FAT32",
+    }
+}
@@ -1,4 +1,5 @@
 use crate::SimpleTextInputProtocol;
+use core::sync::atomic::{AtomicU8, Ordering};
 
 #[repr(C)]
 pub struct InputKey {
@@ -11,6 +12,10 @@ pub const SCAN_UP: u16 = 0x01;
 pub const SCAN_DOWN: u16 = 0x02;
 pub const SCAN_RIGHT: u16 = 0x03;
 pub const SCAN_LEFT: u16 = 0x04;
+pub const SCAN_HOME: u16 = 0x05;
+pub const SCAN_END: u16 = 0x06;
+pub const SCAN_PAGE_UP: u16 = 0x09;
+pub const SCAN_PAGE_DOWN: u16 = 0x0A;
 pub const SCAN_ESC: u16 = 0x17;
 
 // ASCII codes
@@ -26,7 +31,11 @@ impl Keyboard {
         Self { input }
     }
 
+    /// Also presents any pending frame, so whatever was drawn before asking
+    /// for input is on screen.
     pub fn read_key(&mut self) -> Option<InputKey> {
+        crate::tui::renderer::present();
+
         unsafe {
             let mut key = InputKey {
                 scan_code: 0,
@@ -37,6 +46,7 @@ impl Keyboard {
 
             // EFI_SUCCESS = 0
             if status == 0 {
+                key.unicode_char = layout().translate(key.unicode_char);
                 Some(key)
             } else {
                 None
@@ -72,3 +82,134 @@ impl Keyboard {
         key
     }
 }
+
+// ═══════════════════════════════════════════════════════════════════════════
+// KEYBOARD LAYOUTS
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Keyboard layouts.
+///
+/// Firmware reports characters as if the keyboard were US QWERTY. Each
+/// layout maps the US character back to its physical key and returns what
+/// that key carries on the real layout, shifted or not. SimpleTextInput
+/// doesn't report AltGr, so characters only reachable through it (DE `@`,
+/// FR `#`) are not available.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum KeyboardLayout {
+    Us,
+    De,
+    Fr,
+    Uk,
+    Dvorak,
+}
+
+/// (US characters, layout characters), matched position by position.
+const DE_TABLE: (&str, &str) = (
+    "`-=yz[];'\\/~@#^&*()_+YZ{}:\"|<>?",
+    "^ß´zyü+öä#-°\"§&/()=?`ZYÜ*ÖÄ';:_",
+);
+
+const FR_TABLE: (&str, &str) = (
+    "`1234567890-qwaz[];'\\m,./!@#$%^&*()_QWAZ{}:\"|M<>?",
+    "²&é\"'(-è_çà)azqw^$mù*,;:!1234567890°AZQW¨£M%µ?./§",
+);
+
+const UK_TABLE: (&str, &str) = ("@\"#\\|~", "\"@£#~¬");
+
+const DVORAK_TABLE: (&str, &str) = (
+    "-=qwertyuiop[]sdfghjkl;'zxcvbn,./_+QWERTYUIOP{}SDFGHJKL:\"ZXCVBN<>?",
+    "[]',.pyfgcrl/=oeuidhtns-;qjkxbwvz{}\"<>PYFGCRL?+OEUIDHTNS_:QJKXBWVZ",
+);
+
+static LAYOUT: AtomicU8 = AtomicU8::new(KeyboardLayout::Us as u8);
+
+impl KeyboardLayout {
+    pub const ALL: [KeyboardLayout; 5] = [
+        KeyboardLayout::Us,
+        KeyboardLayout::De,
+        KeyboardLayout::Fr,
+        KeyboardLayout::Uk,
+        KeyboardLayout::Dvorak,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyboardLayout::Us => "US",
+            KeyboardLayout::De => "DE",
+            KeyboardLayout::Fr => "FR",
+            KeyboardLayout::Uk => "UK",
+            KeyboardLayout::Dvorak => "DVORAK",
+        }
+    }
+
+    /// The following layout, wrapping; for cycling in settings.
+    pub fn next(self) -> Self {
+        Self::ALL[(self as usize + 1) % Self::ALL.len()]
+    }
+
+    fn table(self) -> Option<(&'static str, &'static str)> {
+        match self {
+            KeyboardLayout::Us => None,
+            KeyboardLayout::De => Some(DE_TABLE),
+            KeyboardLayout::Fr => Some(FR_TABLE),
+            KeyboardLayout::Uk => Some(UK_TABLE),
+            KeyboardLayout::Dvorak => Some(DVORAK_TABLE),
+        }
+    }
+
+    /// Translate a character reported by the firmware.
+    pub fn translate(self, ch: u16) -> u16 {
+        let Some((us, mapped)) = self.table() else {
+            return ch;
+        };
+        us.chars()
+            .zip(mapped.chars())
+            .find(|&(from, _)| from as u32 == ch as u32)
+            .map_or(ch, |(_, to)| to as u16)
+    }
+}
+
+/// Layout applied by `Keyboard::read_key`.
+pub fn layout() -> KeyboardLayout {
+    KeyboardLayout::ALL[LAYOUT.load(Ordering::Relaxed) as usize]
+}
+
+pub fn set_layout(layout: KeyboardLayout) {
+    LAYOUT.store(layout as u8, Ordering::Relaxed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tables_line_up() {
+        for layout in KeyboardLayout::ALL {
+            if let Some((us, mapped)) = layout.table() {
+                assert_eq!(
+                    us.chars().count(),
+                    mapped.chars().count(),
+                    "{}",
+                    layout.name()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_translate() {
+        let t = |layout: KeyboardLayout, ch: char| layout.translate(ch as u16);
+
+        assert_eq!(t(KeyboardLayout::Us, 'y'), 'y' as u16);
+        assert_eq!(t(KeyboardLayout::De, 'y'), 'z' as u16);
+        assert_eq!(t(KeyboardLayout::De, '&'), '/' as u16);
+        assert_eq!(t(KeyboardLayout::De, '>'), ':' as u16);
+        assert_eq!(t(KeyboardLayout::Fr, 'q'), 'a' as u16);
+        assert_eq!(t(KeyboardLayout::Fr, '.'), ':' as u16);
+        assert_eq!(t(KeyboardLayout::Uk, '"'), '@' as u16);
+        assert_eq!(t(KeyboardLayout::Dvorak, 'd'), 'e' as u16);
+        // Letters not on the table pass through
+        assert_eq!(t(KeyboardLayout::De, 'h'), 'h' as u16);
+    }
+}
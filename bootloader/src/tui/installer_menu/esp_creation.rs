@@ -30,6 +30,7 @@ pub fn create_new_esp(
         EFI_BLACK,
     );
     screen.put_str_at(start_x, 5, "Scanning disk...", EFI_GREEN, EFI_BLACK);
+    screen.present();
 
     let result = installer::create_esp_and_install(bs, 0);
     render_creation_result(screen, keyboard, start_x, result)
@@ -44,11 +44,21 @@ pub fn scan_for_esps(bs: &BootServices) -> Vec<EspInfo> {
                     part.partition_type,
                     morpheus_core::disk::partition::PartitionType::EfiSystem
                 ) {
+                    // Label, free space and whether it really is FAT32
+                    let volume = crate::uefi::gpt_adapter::UefiBlockIoAdapter::with_block_size(
+                        block_io, 512,
+                    )
+                    .ok()
+                    .and_then(|mut adapter| {
+                        let lba = adapter.adapter_lba(part.start_lba);
+                        morpheus_core::fs::probe_volume(&mut adapter, lba).ok()
+                    });
                     esp_list.push(EspInfo {
                         disk_index: disk_idx,
                         partition_index: part_idx,
                         start_lba: part.start_lba,
                         size_mb: part.size_mb(),
+                        volume,
                     });
                 }
             }
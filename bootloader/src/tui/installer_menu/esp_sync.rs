@@ -0,0 +1,154 @@
+// Mirrored ESP sync screens
+
+use crate::installer::{self, EspInfo, SyncReport};
+use crate::tui::input::Keyboard;
+use crate::tui::renderer::{
+    Screen, EFI_BLACK, EFI_DARKGREEN, EFI_GREEN, EFI_LIGHTGREEN, EFI_WHITE,
+};
+use crate::tui::widgets::progressbar::ProgressBar;
+use crate::BootServices;
+use alloc::format;
+use morpheus_core::disk::manager::DiskManager;
+
+/// Sync the selected ESP with the one MorpheusX booted from.
+pub fn sync_with_boot_esp(
+    esp_list: &[EspInfo],
+    selected: usize,
+    screen: &mut Screen,
+    keyboard: &mut Keyboard,
+    bs: &BootServices,
+) {
+    screen.clear();
+    let start_x = 2;
+    screen.put_str_at(
+        start_x,
+        1,
+        "=== SYNC MIRRORED ESP ===",
+        EFI_LIGHTGREEN,
+        EFI_BLACK,
+    );
+
+    let Some(mirror) = esp_list.get(selected) else {
+        return;
+    };
+    let primary = boot_esp(esp_list, bs);
+    let error = match primary {
+        None => Some("MorpheusX was not booted from any of these ESPs."),
+        Some(primary) if same_esp(primary, mirror) => {
+            Some("Select the mirror ESP, not the one MorpheusX booted from.")
+        }
+        Some(_) => None,
+    };
+
+    if let (Some(primary), None) = (primary, error) {
+        screen.put_str_at(start_x, 3, &describe("From", primary), EFI_GREEN, EFI_BLACK);
+        screen.put_str_at(start_x, 4, &describe("To  ", mirror), EFI_GREEN, EFI_BLACK);
+        let mut y = 6;
+        sync_one(primary, mirror, screen, bs, start_x, &mut y);
+        screen.put_str_at(
+            start_x,
+            y + 1,
+            "Press any key to return...",
+            EFI_DARKGREEN,
+            EFI_BLACK,
+        );
+    } else if let Some(error) = error {
+        screen.put_str_at(start_x, 3, error, EFI_WHITE, EFI_BLACK);
+        screen.put_str_at(
+            start_x,
+            5,
+            "Press any key to return...",
+            EFI_DARKGREEN,
+            EFI_BLACK,
+        );
+    }
+    keyboard.wait_for_key();
+}
+
+/// After installing to `primary`, bring every other ESP that carries
+/// MorpheusX up to date with it. Advances `y` past what it prints.
+pub fn sync_mirrors(
+    primary: &EspInfo,
+    esp_list: &[EspInfo],
+    screen: &mut Screen,
+    bs: &BootServices,
+    start_x: usize,
+    y: &mut usize,
+) {
+    for mirror in esp_list.iter().filter(|esp| !same_esp(esp, primary)) {
+        if !installer::has_morpheus(bs, mirror) {
+            continue;
+        }
+        screen.put_str_at(
+            start_x,
+            *y,
+            &describe("Mirror", mirror),
+            EFI_GREEN,
+            EFI_BLACK,
+        );
+        *y += 1;
+        sync_one(primary, mirror, screen, bs, start_x, y);
+    }
+}
+
+fn sync_one(
+    primary: &EspInfo,
+    mirror: &EspInfo,
+    screen: &mut Screen,
+    bs: &BootServices,
+    start_x: usize,
+    y: &mut usize,
+) {
+    let mut progress_bar = ProgressBar::new(start_x, *y, 60, "Syncing:");
+    progress_bar.render(screen);
+    let file_y = *y + 1;
+
+    let result = {
+        let mut progress = |done: usize, total: usize, path: &str| {
+            let percent = if total > 0 { done * 100 / total } else { 100 };
+            progress_bar.set_progress(percent);
+            progress_bar.render(screen);
+            let line = format!("{:<60}", path);
+            screen.put_str_at(start_x, file_y, &line, EFI_DARKGREEN, EFI_BLACK);
+        };
+        installer::sync_esps(bs, primary, mirror, Some(&mut progress))
+    };
+    *y += 2;
+
+    match result {
+        Ok(SyncReport { copied, up_to_date }) => {
+            let msg = format!(
+                "[OK] Mirror in sync: {} file(s) copied, {} already up to date",
+                copied, up_to_date
+            );
+            screen.put_str_at(start_x, *y, &msg, EFI_LIGHTGREEN, EFI_BLACK);
+        }
+        Err(e) => {
+            let msg = format!("[ERR] Sync failed: {:?}", e);
+            screen.put_str_at(start_x, *y, &msg, EFI_WHITE, EFI_BLACK);
+        }
+    }
+    *y += 1;
+}
+
+/// The ESP in `esp_list` MorpheusX booted from, if any.
+fn boot_esp<'a>(esp_list: &'a [EspInfo], bs: &BootServices) -> Option<&'a EspInfo> {
+    let mut disks = DiskManager::new();
+    crate::uefi::disk::enumerate_disks(bs, &mut disks).ok()?;
+    esp_list.iter().find(|esp| {
+        disks
+            .get_disk(esp.disk_index)
+            .is_some_and(|disk| disk.boot_esp_lba == Some(esp.start_lba))
+    })
+}
+
+fn same_esp(a: &EspInfo, b: &EspInfo) -> bool {
+    a.disk_index == b.disk_index && a.start_lba == b.start_lba
+}
+
+fn describe(role: &str, esp: &EspInfo) -> alloc::string::String {
+    format!(
+        "{}: Disk {} Part {} ({}MB)",
+        role, esp.disk_index, esp.partition_index, esp.size_mb
+    )
+}
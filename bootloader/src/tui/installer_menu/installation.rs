@@ -1,22 +1,31 @@
 // Installation operations and feedback rendering
 
-use crate::installer::{self, EspInfo};
+use crate::installer::{self, EspHealth, EspInfo};
 use crate::tui::input::Keyboard;
 use crate::tui::renderer::{
     Screen, EFI_BLACK, EFI_CYAN, EFI_DARKGREEN, EFI_GREEN, EFI_LIGHTGREEN, EFI_WHITE,
 };
 use crate::BootServices;
 use alloc::format;
+use alloc::string::String;
+use morpheus_core::disk::manager::DiskManager;
 use morpheus_persistent::feedback::{FeedbackCategory, FeedbackCollector, FeedbackLevel};
 use morpheus_persistent::pe::header::PeHeaders;
 
 pub fn install_to_selected(
     esp: &EspInfo,
+    esp_list: &[EspInfo],
     screen: &mut Screen,
     keyboard: &mut Keyboard,
     bs: &BootServices,
     image_handle: *mut (),
 ) {
+    if !confirm_unhealthy_target(esp, screen, keyboard)
+        || !confirm_removable_target(esp, screen, keyboard, bs)
+    {
+        return;
+    }
+
     screen.clear();
     let start_x = 2;
     let mut y = 1;
@@ -101,7 +110,7 @@ pub fn install_to_selected(
                 start_x,
                 &mut y,
             );
-            perform_installation(
+            let installed = perform_installation(
                 esp,
                 bs,
                 image_handle,
@@ -110,6 +119,10 @@ pub fn install_to_selected(
                 start_x,
                 &mut y,
             );
+            // Keep mirrored installs on the version just written
+            if installed {
+                super::esp_sync::sync_mirrors(esp, esp_list, screen, bs, start_x, &mut y);
+            }
         }
         Err(e) => {
             feedback.error(
@@ -130,6 +143,118 @@ pub fn install_to_selected(
     keyboard.wait_for_key();
 }
 
+/// Installing to a USB stick or SD card leaves the machine unbootable once
+/// it's unplugged, so ask before doing that. Returns whether to go ahead.
+fn confirm_removable_target(
+    esp: &EspInfo,
+    screen: &mut Screen,
+    keyboard: &mut Keyboard,
+    bs: &BootServices,
+) -> bool {
+    let mut disks = DiskManager::new();
+    if crate::uefi::disk::enumerate_disks(bs, &mut disks).is_err() {
+        return true;
+    }
+    let Some(disk) = disks.get_disk(esp.disk_index) else {
+        return true;
+    };
+    if !disk.removable {
+        return true;
+    }
+
+    screen.clear();
+    let model = match disk.identity.model() {
+        "" => "This disk",
+        model => model,
+    };
+    let title = "=== REMOVABLE TARGET ===";
+    screen.put_str_at(
+        screen.center_x(title.len()),
+        5,
+        title,
+        EFI_LIGHTGREEN,
+        EFI_BLACK,
+    );
+    let warn = format!("{} is removable media.", model);
+    screen.put_str_at(screen.center_x(warn.len()), 7, &warn, EFI_WHITE, EFI_BLACK);
+    let info1 = "MorpheusX will only boot while it is plugged in,";
+    screen.put_str_at(screen.center_x(info1.len()), 8, info1, EFI_GREEN, EFI_BLACK);
+    let info2 = "and firmware may drop its boot entry once it is removed.";
+    screen.put_str_at(screen.center_x(info2.len()), 9, info2, EFI_GREEN, EFI_BLACK);
+    let confirm = "Install anyway? [Y] Yes  [N] No";
+    screen.put_str_at(
+        screen.center_x(confirm.len()),
+        11,
+        confirm,
+        EFI_DARKGREEN,
+        EFI_BLACK,
+    );
+
+    let key = keyboard.wait_for_key();
+    key.unicode_char == b'y' as u16 || key.unicode_char == b'Y' as u16
+}
+
+/// Ask before installing to an ESP that isn't plainly usable: full, not
+/// FAT32, or with a boot sector the FAT32 code won't walk.
+fn confirm_unhealthy_target(esp: &EspInfo, screen: &mut Screen, keyboard: &mut Keyboard) -> bool {
+    let health = esp.health();
+    let problem = match health {
+        EspHealth::Ready => return true,
+        EspHealth::Full => format!(
+            "Only {} MB free; the install needs about {} MB.",
+            esp.free_mb().unwrap_or(0),
+            installer::ESP_MIN_FREE_MB
+        ),
+        EspHealth::NotFat32(kind) => format!(
+            "It holds {}, not FAT32: a mislabeled partition?",
+            kind.name()
+        ),
+        EspHealth::Damaged(reason) => format!("Its FAT32 boot sector is bad: {}.", reason),
+        EspHealth::Unreadable => String::from("It could not be read."),
+    };
+
+    screen.clear();
+    let title = "=== CHECK THIS ESP ===";
+    screen.put_str_at(
+        screen.center_x(title.len()),
+        5,
+        title,
+        EFI_LIGHTGREEN,
+        EFI_BLACK,
+    );
+    let which = format!(
+        "Disk {} partition {} is flagged \"{}\".",
+        esp.disk_index,
+        esp.partition_index,
+        health.label()
+    );
+    screen.put_str_at(
+        screen.center_x(which.len()),
+        7,
+        &which,
+        EFI_WHITE,
+        EFI_BLACK,
+    );
+    screen.put_str_at(
+        screen.center_x(problem.len()),
+        8,
+        &problem,
+        EFI_GREEN,
+        EFI_BLACK,
+    );
+    let confirm = "Install anyway? [Y] Yes  [N] No";
+    screen.put_str_at(
+        screen.center_x(confirm.len()),
+        10,
+        confirm,
+        EFI_DARKGREEN,
+        EFI_BLACK,
+    );
+
+    let key = keyboard.wait_for_key();
+    key.unicode_char == b'y' as u16 || key.unicode_char == b'Y' as u16
+}
+
 fn analyze_pe_headers(
     headers: &PeHeaders,
     feedback: &mut FeedbackCollector,
@@ -233,6 +358,7 @@ fn reconstruct_image_base(
     }
 }
 
+/// Write MorpheusX to `esp`. Returns whether it succeeded.
 fn perform_installation(
     esp: &EspInfo,
     bs: &BootServices,
@@ -241,7 +367,7 @@ fn perform_installation(
     screen: &mut Screen,
     start_x: usize,
     y: &mut usize,
-) {
+) -> bool {
     use crate::tui::boot_sequence::BootSequence;
     use crate::tui::widgets::progressbar::ProgressBar;
 
@@ -330,6 +456,7 @@ fn perform_installation(
                 EFI_BLACK,
             );
             *y = status_y + 2;
+            true
         }
         Err(e) => {
             morpheus_core::logger::log(alloc::format!("Installation: FAILED - {:?}", e).leak());
@@ -344,6 +471,7 @@ fn perform_installation(
                 EFI_BLACK,
             );
             *y = status_y + 1;
+            false
         }
     }
 }
@@ -2,13 +2,18 @@
 
 mod esp_creation;
 mod esp_scan;
+mod esp_sync;
 mod installation;
 
 use crate::installer::EspInfo;
 use crate::tui::input::Keyboard;
-use crate::tui::renderer::{Screen, EFI_BLACK, EFI_DARKGREEN, EFI_GREEN, EFI_LIGHTGREEN};
+use crate::tui::keymap::{self, Bindings, Command, Key, KeyBinding};
+use crate::tui::renderer::{
+    Screen, EFI_BLACK, EFI_DARKGREEN, EFI_GREEN, EFI_LIGHTGREEN, EFI_YELLOW,
+};
+use crate::tui::screensaver::Event;
 use crate::BootServices;
-use alloc::string::ToString;
+use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 
 // Box constants for centered UI
@@ -31,6 +36,23 @@ const HEADER_ART: &[&str] = &[
     "|___|_| |_|___/\\__\\__,_|_|_|\\___|_|   ",
 ];
 
+const BINDINGS: Bindings = Bindings {
+    title: "Installer",
+    keys: &[
+        KeyBinding::new(&[Key::Up], Command::Up, "Previous ESP"),
+        KeyBinding::new(&[Key::Down], Command::Down, "Next ESP"),
+        KeyBinding::new(&[Key::Enter], Command::Select, "Install to selected ESP"),
+        KeyBinding::new(
+            &[Key::Char(b's')],
+            Command::Sync,
+            "Sync selected ESP with boot ESP",
+        ),
+        KeyBinding::new(&[Key::Char(b'r')], Command::Rescan, "Rescan disks"),
+        KeyBinding::new(&[Key::Char(b'c')], Command::Create, "Create ESP / help"),
+        KeyBinding::new(&[Key::Esc], Command::Back, "Back to main menu"),
+    ],
+};
+
 pub struct InstallerMenu {
     esp_list: Vec<EspInfo>,
     selected_esp: usize,
@@ -52,77 +74,72 @@ impl InstallerMenu {
         loop {
             self.render(screen, bs);
 
-            // Render global rain and check for input
+            // Wait for input via the keymap hook
             loop {
-                crate::tui::rain::render_rain(screen);
-
-                if let Some(key) = keyboard.read_key() {
-                    // Global rain toggle
-                    if key.unicode_char == b'x' as u16 || key.unicode_char == b'X' as u16 {
-                        crate::tui::rain::toggle_rain(screen);
-                        screen.clear();
+                let key = match keymap::poll(screen, keyboard, &BINDINGS) {
+                    Some(Event::Key(key)) => key,
+                    Some(Event::Redraw) => {
                         self.render(screen, bs);
                         continue;
                     }
+                    None => continue,
+                };
 
-                    match key.scan_code {
-                        0x01 => {
-                            // Up arrow
-                            if self.selected_esp > 0 {
-                                self.selected_esp -= 1;
-                            }
+                match BINDINGS.lookup(&key) {
+                    Some(Command::Up) => {
+                        if self.selected_esp > 0 {
+                            self.selected_esp -= 1;
                         }
-                        0x02 => {
-                            // Down arrow
-                            if self.selected_esp + 1 < self.esp_list.len() {
-                                self.selected_esp += 1;
-                            }
+                    }
+                    Some(Command::Down) => {
+                        if self.selected_esp + 1 < self.esp_list.len() {
+                            self.selected_esp += 1;
                         }
-                        0x17 => {
-                            // ESC
-                            return;
+                    }
+                    Some(Command::Back) => return,
+                    Some(Command::Select) => {
+                        // Install to selected ESP
+                        if !self.esp_list.is_empty() && self.selected_esp < self.esp_list.len() {
+                            let esp = &self.esp_list[self.selected_esp];
+                            installation::install_to_selected(
+                                esp,
+                                &self.esp_list,
+                                screen,
+                                keyboard,
+                                bs,
+                                self.image_handle,
+                            );
                         }
-                        _ => {
-                            if key.unicode_char == b'\r' as u16 || key.unicode_char == b'\n' as u16
-                            {
-                                // Enter key - install to selected ESP
-                                if !self.esp_list.is_empty()
-                                    && self.selected_esp < self.esp_list.len()
-                                {
-                                    let esp = &self.esp_list[self.selected_esp];
-                                    installation::install_to_selected(
-                                        esp,
-                                        screen,
-                                        keyboard,
-                                        bs,
-                                        self.image_handle,
-                                    );
-                                }
-                            } else if key.unicode_char == b'r' as u16
-                                || key.unicode_char == b'R' as u16
+                    }
+                    Some(Command::Sync) => {
+                        if self.esp_list.len() > 1 {
+                            esp_sync::sync_with_boot_esp(
+                                &self.esp_list,
+                                self.selected_esp,
+                                screen,
+                                keyboard,
+                                bs,
+                            );
+                        }
+                    }
+                    Some(Command::Rescan) => self.scan_complete = false,
+                    Some(Command::Create) => {
+                        // Show help or create ESP
+                        if self.esp_list.is_empty() {
+                            if let Some(new_esp) =
+                                esp_creation::create_new_esp(screen, keyboard, bs)
                             {
-                                // Rescan
+                                self.esp_list.push(new_esp);
+                                self.selected_esp = self.esp_list.len() - 1;
                                 self.scan_complete = false;
-                            } else if key.unicode_char == b'c' as u16
-                                || key.unicode_char == b'C' as u16
-                            {
-                                // Show help or create ESP
-                                if self.esp_list.is_empty() {
-                                    if let Some(new_esp) =
-                                        esp_creation::create_new_esp(screen, keyboard, bs)
-                                    {
-                                        self.esp_list.push(new_esp);
-                                        self.selected_esp = self.esp_list.len() - 1;
-                                        self.scan_complete = false;
-                                    }
-                                } else {
-                                    esp_creation::show_create_esp_help(screen, keyboard);
-                                }
                             }
+                        } else {
+                            esp_creation::show_create_esp_help(screen, keyboard);
                         }
                     }
-                    break;
+                    _ => continue,
                 }
+                break;
             }
         }
     }
@@ -250,9 +267,12 @@ impl InstallerMenu {
 
         // Table header
         screen.put_str_at(x, *current_y, "|", EFI_GREEN, EFI_BLACK);
-        let header = "DISK    PART    SIZE (MB)    STATUS";
+        let header = esp_row(
+            "   ",
+            ["DISK", "PART", "LABEL", "SIZE (MB)", "FREE (MB)", "FS", "STATUS"],
+        );
         let padding = (75 - header.len()) / 2;
-        screen.put_str_at(x + 1 + padding, *current_y, header, EFI_GREEN, EFI_BLACK);
+        screen.put_str_at(x + 1 + padding, *current_y, &header, EFI_GREEN, EFI_BLACK);
         screen.put_str_at(x + 76, *current_y, "|", EFI_GREEN, EFI_BLACK);
         *current_y += 1;
 
@@ -265,15 +285,28 @@ impl InstallerMenu {
             } else {
                 "   "
             };
-            let entry = alloc::format!(
-                "{}{}       {}       {}         Ready",
+            let health = esp.health();
+            let free = esp
+                .free_mb()
+                .map_or(String::from("?"), |free| free.to_string());
+            let fs = esp.volume.as_ref().map_or("?", |v| v.kind.name());
+            let entry = esp_row(
                 marker,
-                esp.disk_index,
-                esp.partition_index,
-                esp.size_mb
+                [
+                    &esp.disk_index.to_string(),
+                    &esp.partition_index.to_string(),
+                    esp.label(),
+                    &esp.size_mb.to_string(),
+                    &free,
+                    fs,
+                    health.label(),
+                ],
             );
             let padding = (75 - entry.len()) / 2;
-            let color = if idx == self.selected_esp {
+            // Flagged ESPs stand out so they aren't picked by accident
+            let color = if !health.is_ready() {
+                EFI_YELLOW
+            } else if idx == self.selected_esp {
                 EFI_LIGHTGREEN
             } else {
                 EFI_GREEN
@@ -297,10 +330,27 @@ impl InstallerMenu {
 
         // Instructions
         screen.put_str_at(x, *current_y, "|", EFI_GREEN, EFI_BLACK);
-        let instr = "[UP/DOWN] Select  |  [ENTER] Install  |  [R] Rescan  |  [ESC] Back";
+        let instr = "[UP/DOWN] Select | [ENTER] Install | [S] Sync | [R] Rescan | [ESC] Back";
         let padding = (75 - instr.len()) / 2;
         screen.put_str_at(x + 1 + padding, *current_y, instr, EFI_DARKGREEN, EFI_BLACK);
         screen.put_str_at(x + 76, *current_y, "|", EFI_GREEN, EFI_BLACK);
         *current_y += 1;
     }
 }
+
+/// One line of the ESP table: disk, partition, label, size, free space,
+/// filesystem and status, in fixed-width columns after `marker`.
+fn esp_row(marker: &str, cells: [&str; 7]) -> String {
+    let [disk, part, label, size, free, fs, status] = cells;
+    alloc::format!(
+        "{}{:<4}  {:<4}  {:<11}  {:>9}  {:>9}  {:<7}  {}",
+        marker,
+        disk,
+        part,
+        label,
+        size,
+        free,
+        fs,
+        status
+    )
+}
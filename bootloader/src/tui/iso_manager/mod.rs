@@ -2,9 +2,11 @@
 //!
 //! Provides a TUI for managing stored ISO images:
 //! - View downloaded ISOs
-//! - Delete ISOs (reclaim space)
+//! - Delete ISOs (reclaim space), one at a time or all marked ones
+//! - Verify ISOs against the SHA-256 in their manifest
 //! - View ISO details (size, chunks, status)
 //! - Boot from ISO
+//! - Check for newer versions and queue re-downloads
 //!
 //! # Architecture
 //!
@@ -22,6 +24,10 @@
 mod renderer;
 mod state;
 mod ui;
+mod updates;
+mod verify;
 
 pub use state::{IsoManagerState, ViewMode};
 pub use ui::IsoManager;
+pub use updates::{RemoteValidators, UpdateStatus};
+pub use verify::{SectorReader, VerifyStatus};
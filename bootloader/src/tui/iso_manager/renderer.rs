@@ -3,9 +3,12 @@
 //! Renders the ISO manager TUI components.
 
 use super::state::{IsoManagerState, ViewMode};
+use super::updates::UpdateStatus;
+use super::verify::VerifyStatus;
 use crate::tui::renderer::{
     Screen, EFI_BLACK, EFI_DARKGRAY, EFI_GREEN, EFI_LIGHTGREEN, EFI_RED, EFI_WHITE, EFI_YELLOW,
 };
+use alloc::format;
 
 /// Box drawing characters (ASCII fallback for UEFI)
 const BOX_H: char = '-';
@@ -24,12 +27,29 @@ pub fn render(screen: &mut Screen, state: &IsoManagerState) {
         ViewMode::Details => render_details(screen, state),
         ViewMode::ConfirmDelete => {
             render_list(screen, state);
-            render_confirm_dialog(screen, "Delete ISO?", state.selected_name());
+            if state.batch {
+                render_batch_confirm(screen, state, "Delete");
+            } else {
+                render_confirm_dialog(screen, "Delete ISO?", state.selected_name());
+            }
+        }
+        ViewMode::ConfirmVerify => {
+            render_list(screen, state);
+            if state.batch {
+                render_batch_confirm(screen, state, "Verify");
+            } else {
+                render_confirm_dialog(screen, "Verify ISO?", state.selected_name());
+            }
         }
         ViewMode::ConfirmBoot => {
             render_list(screen, state);
             render_confirm_dialog(screen, "Boot ISO?", state.selected_name());
         }
+        ViewMode::ConfirmRedownload => {
+            render_list(screen, state);
+            let newer = state.selected_update().newer().map_or("", |d| d.filename);
+            render_confirm_dialog(screen, "Queue re-download?", newer);
+        }
     }
 
     render_footer(screen, state);
@@ -76,6 +96,10 @@ fn render_list(screen: &mut Screen, state: &IsoManagerState) {
         return;
     }
 
+    if state.checked() {
+        render_update_summary(screen, state, start_row - 1);
+    }
+
     // Column headers
     screen.set_cursor(2, start_row);
     screen.set_colors(EFI_GREEN, EFI_BLACK);
@@ -92,14 +116,15 @@ fn render_list(screen: &mut Screen, state: &IsoManagerState) {
         let row = start_row + 2 + i;
         screen.set_cursor(2, row);
 
-        // Selection indicator
+        // Selection and mark indicators
         if i == state.selected {
             screen.set_colors(EFI_BLACK, EFI_GREEN);
-            screen.print("> ");
+            screen.print_char('>');
         } else {
             screen.set_colors(EFI_LIGHTGREEN, EFI_BLACK);
-            screen.print("  ");
+            screen.print_char(' ');
         }
+        screen.print_char(if state.marked[i] { 'x' } else { ' ' });
 
         // Name (max 40 chars)
         let name =
@@ -122,10 +147,31 @@ fn render_list(screen: &mut Screen, state: &IsoManagerState) {
             screen.print_char(' ');
         }
         print_number(screen, chunks as u64);
-        screen.print("     ");
+        screen.print("  ");
+        if state.queued[i] {
+            screen.set_colors(EFI_GREEN, EFI_BLACK);
+            screen.print_char('+');
+        } else if state.updates[i].newer().is_some() {
+            screen.set_colors(EFI_YELLOW, EFI_BLACK);
+            screen.print_char('*');
+        } else {
+            screen.print_char(' ');
+        }
+        screen.print("  ");
 
         // Status
-        if state.complete[i] {
+        if state.stale[i] {
+            screen.set_colors(EFI_RED, EFI_BLACK);
+            screen.print("Stale");
+        } else if state.verified[i] != VerifyStatus::Unchecked {
+            let color = match state.verified[i] {
+                VerifyStatus::Verified => EFI_GREEN,
+                VerifyStatus::NoHash => EFI_YELLOW,
+                _ => EFI_RED,
+            };
+            screen.set_colors(color, EFI_BLACK);
+            screen.print(state.verified[i].label());
+        } else if state.complete[i] {
             screen.set_colors(EFI_GREEN, EFI_BLACK);
             screen.print("Ready");
         } else {
@@ -140,6 +186,20 @@ fn render_list(screen: &mut Screen, state: &IsoManagerState) {
     }
 }
 
+/// One line on what the last update check found.
+fn render_update_summary(screen: &mut Screen, state: &IsoManagerState, row: usize) {
+    screen.set_cursor(2, row);
+    let available = state.updates_available();
+    if available == 0 {
+        screen.set_colors(EFI_GREEN, EFI_BLACK);
+        screen.print("No newer versions found");
+    } else {
+        screen.set_colors(EFI_YELLOW, EFI_BLACK);
+        print_number(screen, available as u64);
+        screen.print(" ISO(s) with a newer version (*) - [G] queues a re-download (+)");
+    }
+}
+
 fn render_details(screen: &mut Screen, state: &IsoManagerState) {
     let start_row = 4;
 
@@ -202,7 +262,15 @@ fn render_details(screen: &mut Screen, state: &IsoManagerState) {
     screen.set_colors(EFI_WHITE, EFI_BLACK);
     print_number(screen, state.selected_chunks() as u64);
     screen.print(" partitions");
-    for _ in 0..34 {
+    let chunk_type = state.selected_chunk_type();
+    let mut padding = 34;
+    if !chunk_type.is_empty() {
+        screen.print(" (");
+        screen.print(chunk_type);
+        screen.print(")");
+        padding -= chunk_type.len() + 3;
+    }
+    for _ in 0..padding {
         screen.print_char(' ');
     }
     screen.set_colors(EFI_DARKGRAY, EFI_BLACK);
@@ -213,21 +281,37 @@ fn render_details(screen: &mut Screen, state: &IsoManagerState) {
     screen.print_char(BOX_V);
     screen.set_colors(EFI_LIGHTGREEN, EFI_BLACK);
     screen.print(" Status: ");
-    if state.selected_complete() {
+    if state.selected_stale() {
+        screen.set_colors(EFI_RED, EFI_BLACK);
+        screen.print("Stale (partition gone)");
+        for _ in 0..27 {
+            screen.print_char(' ');
+        }
+    } else if state.selected_complete() {
         screen.set_colors(EFI_GREEN, EFI_BLACK);
         screen.print("Ready to boot");
+        for _ in 0..36 {
+            screen.print_char(' ');
+        }
     } else {
         screen.set_colors(EFI_YELLOW, EFI_BLACK);
         screen.print("Download incomplete");
-    }
-    for _ in 0..30 {
-        screen.print_char(' ');
+        for _ in 0..30 {
+            screen.print_char(' ');
+        }
     }
     screen.set_colors(EFI_DARKGRAY, EFI_BLACK);
     screen.print_char(BOX_V);
 
+    // Update row, once a check has run
+    let mut bottom = box_top + 5;
+    if state.checked() {
+        render_update_row(screen, state, box_left, bottom, box_width);
+        bottom += 1;
+    }
+
     // Bottom border
-    screen.set_cursor(box_left, box_top + 5);
+    screen.set_cursor(box_left, bottom);
     screen.print_char(BOX_BL);
     for _ in 0..box_width - 2 {
         screen.print_char(BOX_H);
@@ -235,13 +319,129 @@ fn render_details(screen: &mut Screen, state: &IsoManagerState) {
     screen.print_char(BOX_BR);
 
     // Actions hint
-    screen.set_cursor(box_left, box_top + 7);
+    screen.set_cursor(box_left, bottom + 2);
     screen.set_colors(EFI_DARKGRAY, EFI_BLACK);
-    if state.selected_complete() {
-        screen.print("[B] Boot   [D] Delete   [ESC] Back");
+    if state.bootable() {
+        screen.print("[B] Boot   ");
+    }
+    screen.print("[D] Delete   [V] Verify   ");
+    if state.can_redownload() {
+        screen.print("[G] Re-download   ");
+    }
+    screen.print("[ESC] Back");
+}
+
+fn render_update_row(
+    screen: &mut Screen,
+    state: &IsoManagerState,
+    left: usize,
+    row: usize,
+    width: usize,
+) {
+    let update = state.selected_update();
+    screen.set_cursor(left, row);
+    screen.set_colors(EFI_DARKGRAY, EFI_BLACK);
+    screen.print_char(BOX_V);
+    screen.set_colors(EFI_LIGHTGREEN, EFI_BLACK);
+    screen.print(" Update: ");
+    let mut used = 9;
+    if state.queued[state.selected] {
+        screen.set_colors(EFI_GREEN, EFI_BLACK);
+        screen.print("Re-download queued");
+        used += 18;
     } else {
-        screen.print("[D] Delete   [ESC] Back");
+        let color = if update.newer().is_some() {
+            EFI_YELLOW
+        } else {
+            EFI_WHITE
+        };
+        screen.set_colors(color, EFI_BLACK);
+        screen.print(update.label());
+        used += update.label().len();
+    }
+    if let UpdateStatus::NewRelease(entry) = update {
+        screen.print(" (");
+        screen.print(entry.version);
+        screen.print(")");
+        used += 3 + entry.version.len();
     }
+    for _ in used..width - 1 {
+        screen.print_char(' ');
+    }
+    screen.set_colors(EFI_DARKGRAY, EFI_BLACK);
+    screen.print_char(BOX_V);
+}
+
+/// Confirm dialog for `verb` over all marked ISOs.
+fn render_batch_confirm(screen: &mut Screen, state: &IsoManagerState, verb: &str) {
+    let count = state.marked_count();
+    let title = format!(
+        "{} {} marked ISO{}?",
+        verb,
+        count,
+        if count == 1 { "" } else { "s" }
+    );
+    let total_mb = state.targets_size_mb();
+    let total = format!(
+        "{}.{} GB in total",
+        total_mb / 1024,
+        total_mb % 1024 * 10 / 1024
+    );
+    render_confirm_dialog(screen, &title, &total);
+}
+
+/// Where a batch delete or verify is.
+pub struct BatchProgress<'a> {
+    pub title: &'a str,
+    /// 1-based number of the ISO being worked on
+    pub item: usize,
+    pub items: usize,
+    pub name: &'a str,
+    /// Across the whole batch
+    pub done_mb: u64,
+    pub total_mb: u64,
+}
+
+/// Progress of a batch operation, over the whole batch.
+pub fn render_batch_progress(screen: &mut Screen, progress: &BatchProgress) {
+    render_header(screen);
+    let row = 5;
+    let bar_width = 50;
+
+    screen.set_cursor(2, row);
+    screen.set_colors(EFI_GREEN, EFI_BLACK);
+    screen.print(progress.title);
+
+    screen.set_cursor(2, row + 2);
+    screen.set_colors(EFI_LIGHTGREEN, EFI_BLACK);
+    let line = format!(
+        "ISO {} of {}: {}",
+        progress.item, progress.items, progress.name
+    );
+    screen.print(&line);
+    for _ in line.len()..70 {
+        screen.print_char(' ');
+    }
+
+    let filled = if progress.total_mb == 0 {
+        0
+    } else {
+        (progress.done_mb.min(progress.total_mb) * bar_width / progress.total_mb) as usize
+    };
+    screen.set_cursor(2, row + 4);
+    screen.set_colors(EFI_DARKGRAY, EFI_BLACK);
+    screen.print_char('[');
+    screen.set_colors(EFI_GREEN, EFI_BLACK);
+    for i in 0..bar_width as usize {
+        screen.print_char(if i < filled { '#' } else { '.' });
+    }
+    screen.set_colors(EFI_DARKGRAY, EFI_BLACK);
+    screen.print("]  ");
+    screen.set_colors(EFI_WHITE, EFI_BLACK);
+    print_size_mb(screen, progress.done_mb);
+    screen.print(" of ");
+    print_size_mb(screen, progress.total_mb);
+    screen.print("    ");
 }
 
 fn render_confirm_dialog(screen: &mut Screen, title: &str, item_name: &str) {
@@ -319,13 +519,15 @@ fn render_footer(screen: &mut Screen, state: &IsoManagerState) {
     match state.mode {
         ViewMode::List => {
             if state.count > 0 {
-                screen.print("[UP/DOWN] Select  [ENTER] Details  [B] Boot  [D] Delete  [R] Refresh  [ESC] Back");
+                screen.print(
+                    "[ENTER] Details [SPACE] Mark [B] Boot [D] Delete [V] Verify [U] Updates [ESC]",
+                );
             } else {
                 screen.print("[ESC] Back to main menu");
             }
         }
         ViewMode::Details => {
-            screen.print("[B] Boot  [D] Delete  [ESC] Back to list");
+            screen.print("[B] Boot  [D] Delete  [V] Verify  [G] Re-download  [ESC] Back to list");
         }
         _ => {}
     }
@@ -2,7 +2,58 @@
 //!
 //! State management for the ISO manager TUI.
 
+use super::updates::UpdateStatus;
+use super::verify::VerifyStatus;
+use crate::tui::input::InputKey;
+use crate::tui::keymap::{Bindings, Command, Key, KeyBinding};
+use alloc::vec;
+use alloc::vec::Vec;
 use morpheus_core::iso::{IsoEntry, IsoStorageManager, MAX_ISOS};
+use morpheus_network::transfer::disk::{PartitionInfo, ScannedManifest};
+
+const LIST_BINDINGS: Bindings = Bindings {
+    title: "ISO Manager",
+    keys: &[
+        KeyBinding::new(&[Key::Up], Command::Up, "Previous ISO"),
+        KeyBinding::new(&[Key::Down], Command::Down, "Next ISO"),
+        KeyBinding::new(&[Key::Enter], Command::Select, "Show details"),
+        KeyBinding::new(&[Key::Char(b' ')], Command::Mark, "Mark / unmark ISO"),
+        KeyBinding::new(&[Key::Char(b'b')], Command::Boot, "Boot ISO"),
+        KeyBinding::new(&[Key::Char(b'd')], Command::Delete, "Delete ISO(s)"),
+        KeyBinding::new(
+            &[Key::Char(b'v')],
+            Command::Verify,
+            "Verify SHA-256 of ISO(s)",
+        ),
+        KeyBinding::new(&[Key::Char(b'r')], Command::Rescan, "Refresh list"),
+        KeyBinding::new(
+            &[Key::Char(b'u')],
+            Command::CheckUpdates,
+            "Check for newer versions",
+        ),
+        KeyBinding::new(&[Key::Char(b'g')], Command::Redownload, "Queue re-download"),
+        KeyBinding::new(&[Key::Esc], Command::Back, "Back to main menu"),
+    ],
+};
+
+const DETAILS_BINDINGS: Bindings = Bindings {
+    title: "ISO Details",
+    keys: &[
+        KeyBinding::new(&[Key::Char(b'b')], Command::Boot, "Boot ISO"),
+        KeyBinding::new(&[Key::Char(b'd')], Command::Delete, "Delete ISO"),
+        KeyBinding::new(&[Key::Char(b'v')], Command::Verify, "Verify SHA-256"),
+        KeyBinding::new(&[Key::Char(b'g')], Command::Redownload, "Queue re-download"),
+        KeyBinding::new(&[Key::Esc, Key::Backspace], Command::Back, "Back to list"),
+    ],
+};
+
+const CONFIRM_BINDINGS: Bindings = Bindings {
+    title: "Confirm",
+    keys: &[
+        KeyBinding::new(&[Key::Char(b'y')], Command::Yes, "Confirm"),
+        KeyBinding::new(&[Key::Char(b'n'), Key::Esc], Command::No, "Cancel"),
+    ],
+};
 
 /// View mode for the ISO manager
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -11,10 +62,14 @@ pub enum ViewMode {
     List,
     /// Detail view for selected ISO
     Details,
-    /// Confirm delete dialog
+    /// Confirm delete dialog (the marked ISOs, or the selected one)
     ConfirmDelete,
+    /// Confirm verify dialog (the marked ISOs, or the selected one)
+    ConfirmVerify,
     /// Confirm boot dialog
     ConfirmBoot,
+    /// Confirm queueing the newer release
+    ConfirmRedownload,
 }
 
 /// Action result from user input
@@ -28,8 +83,18 @@ pub enum Action {
     Boot(usize),
     /// Delete the selected ISO
     Delete(usize),
+    /// Delete every marked ISO
+    DeleteMarked,
+    /// Verify the selected ISO
+    Verify(usize),
+    /// Verify every marked ISO
+    VerifyMarked,
     /// Refresh the ISO list
     Refresh,
+    /// Check stored ISOs for newer versions
+    CheckUpdates,
+    /// Queue the newer release of the selected ISO
+    Redownload(usize),
 }
 
 /// ISO manager state
@@ -48,8 +113,23 @@ pub struct IsoManagerState {
     pub sizes_mb: [u64; MAX_ISOS],
     /// Cached chunk counts
     pub chunk_counts: [usize; MAX_ISOS],
+    /// Type name of the first chunk partition ("" when the manifest
+    /// doesn't record it)
+    pub chunk_types: [&'static str; MAX_ISOS],
     /// Cached completion status
     pub complete: [bool; MAX_ISOS],
+    /// Manifest points at partitions that no longer exist
+    pub stale: [bool; MAX_ISOS],
+    /// Result of the last update check
+    pub updates: [UpdateStatus; MAX_ISOS],
+    /// Newer release queued for re-download
+    pub queued: [bool; MAX_ISOS],
+    /// Marked for a batch delete or verify
+    pub marked: [bool; MAX_ISOS],
+    /// Result of the last verify
+    pub verified: [VerifyStatus; MAX_ISOS],
+    /// The open confirm dialog applies to the marked ISOs
+    pub batch: bool,
     /// Error message to display (if any)
     pub error_msg: Option<&'static str>,
 }
@@ -65,7 +145,14 @@ impl IsoManagerState {
             name_lens: [0; MAX_ISOS],
             sizes_mb: [0; MAX_ISOS],
             chunk_counts: [0; MAX_ISOS],
+            chunk_types: [""; MAX_ISOS],
             complete: [false; MAX_ISOS],
+            stale: [false; MAX_ISOS],
+            updates: [UpdateStatus::Unchecked; MAX_ISOS],
+            queued: [false; MAX_ISOS],
+            marked: [false; MAX_ISOS],
+            verified: [VerifyStatus::Unchecked; MAX_ISOS],
+            batch: false,
             error_msg: None,
         }
     }
@@ -91,12 +178,60 @@ impl IsoManagerState {
 
             // Chunk count
             self.chunk_counts[i] = manifest.chunks.count;
+            self.chunk_types[i] = "";
 
             // Completion status
             self.complete[i] = manifest.is_complete();
+            self.stale[i] = false;
+            self.updates[i] = UpdateStatus::Unchecked;
+            self.queued[i] = false;
+            self.marked[i] = false;
+            self.verified[i] = VerifyStatus::Unchecked;
         }
     }
 
+    /// Load ISO data from a manifest directory scan, marking manifests whose
+    /// chunks aren't inside any of `partitions` as stale. Unreadable
+    /// manifests are skipped.
+    pub fn load_from_scan(
+        &mut self,
+        scan: impl Iterator<Item = ScannedManifest>,
+        partitions: &[PartitionInfo],
+    ) {
+        self.count = 0;
+
+        for scanned in scan {
+            if self.count >= MAX_ISOS {
+                break;
+            }
+            let stale = scanned.is_stale(partitions);
+            let Ok((info, chunks)) = scanned.parsed else {
+                continue;
+            };
+
+            let i = self.count;
+            let name = info.name_str().as_bytes();
+            let name_len = name.len().min(64);
+            self.names[i][..name_len].copy_from_slice(&name[..name_len]);
+            self.name_lens[i] = name_len;
+            self.sizes_mb[i] = info.total_size / (1024 * 1024);
+            self.chunk_counts[i] = chunks.count;
+            self.chunk_types[i] = match chunks.count {
+                0 => "",
+                _ => chunks.chunks[0].info.type_name(),
+            };
+            self.complete[i] = info.is_complete();
+            self.stale[i] = stale;
+            self.updates[i] = UpdateStatus::Unchecked;
+            self.queued[i] = false;
+            self.marked[i] = false;
+            self.verified[i] = VerifyStatus::Unchecked;
+            self.count += 1;
+        }
+
+        self.selected = self.selected.min(self.count.saturating_sub(1));
+    }
+
     /// Get selected ISO name as str
     pub fn selected_name(&self) -> &str {
         if self.selected < self.count {
@@ -116,6 +251,15 @@ impl IsoManagerState {
         }
     }
 
+    /// Type name of the selected ISO's chunk partitions, if known
+    pub fn selected_chunk_type(&self) -> &'static str {
+        if self.selected < self.count {
+            self.chunk_types[self.selected]
+        } else {
+            ""
+        }
+    }
+
     /// Get selected ISO chunk count
     pub fn selected_chunks(&self) -> usize {
         if self.selected < self.count {
@@ -134,128 +278,222 @@ impl IsoManagerState {
         }
     }
 
-    /// Move selection up
-    pub fn select_prev(&mut self) {
-        if self.selected > 0 {
-            self.selected -= 1;
-        }
+    /// Check if selected ISO's manifest is stale
+    pub fn selected_stale(&self) -> bool {
+        self.selected < self.count && self.stale[self.selected]
     }
 
-    /// Move selection down
-    pub fn select_next(&mut self) {
-        if self.count > 0 && self.selected < self.count - 1 {
-            self.selected += 1;
+    /// Update status of the selected ISO
+    pub fn selected_update(&self) -> UpdateStatus {
+        if self.selected < self.count {
+            self.updates[self.selected]
+        } else {
+            UpdateStatus::Unchecked
         }
     }
 
-    /// Handle key input, return action
-    pub fn handle_key(&mut self, scan_code: u16, unicode: u16) -> Action {
-        match self.mode {
-            ViewMode::List => self.handle_list_key(scan_code, unicode),
-            ViewMode::Details => self.handle_details_key(scan_code, unicode),
-            ViewMode::ConfirmDelete => self.handle_confirm_delete_key(scan_code, unicode),
-            ViewMode::ConfirmBoot => self.handle_confirm_boot_key(scan_code, unicode),
-        }
+    /// Selected ISO has a newer release that isn't queued yet
+    pub fn can_redownload(&self) -> bool {
+        self.selected_update().newer().is_some() && !self.queued[self.selected]
     }
 
-    fn handle_list_key(&mut self, scan_code: u16, unicode: u16) -> Action {
-        // ESC - return to main menu
-        if scan_code == 0x17 {
-            return Action::Back;
-        }
+    /// Stored ISOs the last check found a newer version of
+    pub fn updates_available(&self) -> usize {
+        self.updates[..self.count]
+            .iter()
+            .filter(|u| u.newer().is_some())
+            .count()
+    }
 
-        // Up arrow
-        if scan_code == 0x01 {
-            self.select_prev();
-            return Action::None;
-        }
+    /// Whether an update check has run since the list was loaded
+    pub fn checked(&self) -> bool {
+        self.updates[..self.count]
+            .iter()
+            .any(|u| !matches!(u, UpdateStatus::Unchecked))
+    }
 
-        // Down arrow
-        if scan_code == 0x02 {
-            self.select_next();
-            return Action::None;
-        }
+    /// Number of marked ISOs
+    pub fn marked_count(&self) -> usize {
+        self.marked[..self.count].iter().filter(|&&m| m).count()
+    }
+
+    /// Open a confirm dialog for the marked ISOs (`batch`) or the
+    /// selected one
+    fn confirm(&mut self, mode: ViewMode, batch: bool) {
+        self.mode = mode;
+        self.batch = batch;
+    }
 
-        // Enter - show details
-        if unicode == 0x0D && self.count > 0 {
-            self.mode = ViewMode::Details;
-            return Action::None;
+    /// Indices the open confirm dialog applies to
+    pub fn targets(&self) -> Vec<usize> {
+        if self.batch {
+            (0..self.count).filter(|&i| self.marked[i]).collect()
+        } else if self.selected < self.count {
+            vec![self.selected]
+        } else {
+            Vec::new()
         }
+    }
+
+    /// Total size of the ISOs the confirm dialog applies to, in MB
+    pub fn targets_size_mb(&self) -> u64 {
+        self.targets().iter().map(|&i| self.sizes_mb[i]).sum()
+    }
 
-        // 'd' or 'D' - delete
-        if (unicode == 0x64 || unicode == 0x44) && self.count > 0 {
-            self.mode = ViewMode::ConfirmDelete;
-            return Action::None;
+    /// Mark or unmark the selected ISO and move to the next one
+    pub fn toggle_mark(&mut self) {
+        if self.selected < self.count {
+            self.marked[self.selected] = !self.marked[self.selected];
+            self.select_next();
         }
+    }
 
-        // 'b' or 'B' - boot
-        if (unicode == 0x62 || unicode == 0x42) && self.count > 0 && self.selected_complete() {
-            self.mode = ViewMode::ConfirmBoot;
-            return Action::None;
+    /// Move selection up
+    pub fn select_prev(&mut self) {
+        if self.selected > 0 {
+            self.selected -= 1;
         }
+    }
 
-        // 'r' or 'R' - refresh
-        if unicode == 0x72 || unicode == 0x52 {
-            return Action::Refresh;
+    /// Move selection down
+    pub fn select_next(&mut self) {
+        if self.count > 0 && self.selected < self.count - 1 {
+            self.selected += 1;
         }
+    }
 
-        Action::None
+    /// Selected ISO is complete and its partitions are still there
+    pub fn bootable(&self) -> bool {
+        self.selected_complete() && !self.selected_stale()
     }
 
-    fn handle_details_key(&mut self, scan_code: u16, unicode: u16) -> Action {
-        // ESC or Backspace - back to list
-        if scan_code == 0x17 || unicode == 0x08 {
-            self.mode = ViewMode::List;
-            return Action::None;
+    /// Key table for the current view
+    pub fn bindings(&self) -> &'static Bindings {
+        match self.mode {
+            ViewMode::List => &LIST_BINDINGS,
+            ViewMode::Details => &DETAILS_BINDINGS,
+            ViewMode::ConfirmDelete
+            | ViewMode::ConfirmVerify
+            | ViewMode::ConfirmBoot
+            | ViewMode::ConfirmRedownload => &CONFIRM_BINDINGS,
         }
+    }
 
-        // 'b' or 'B' - boot from details
-        if (unicode == 0x62 || unicode == 0x42) && self.selected_complete() {
-            self.mode = ViewMode::ConfirmBoot;
-            return Action::None;
+    /// Handle key input, return action
+    pub fn handle_key(&mut self, key: &InputKey) -> Action {
+        let command = self.bindings().lookup(key);
+        match self.mode {
+            ViewMode::List => self.handle_list_command(command),
+            ViewMode::Details => self.handle_details_command(command),
+            ViewMode::ConfirmDelete => self.handle_confirm_delete_command(command),
+            ViewMode::ConfirmVerify => self.handle_confirm_verify_command(command),
+            ViewMode::ConfirmBoot => self.handle_confirm_boot_command(command),
+            ViewMode::ConfirmRedownload => self.handle_confirm_redownload_command(command),
         }
+    }
 
-        // 'd' or 'D' - delete from details
-        if unicode == 0x64 || unicode == 0x44 {
-            self.mode = ViewMode::ConfirmDelete;
-            return Action::None;
+    fn handle_list_command(&mut self, command: Option<Command>) -> Action {
+        match command {
+            Some(Command::Back) => return Action::Back,
+            Some(Command::Up) => self.select_prev(),
+            Some(Command::Down) => self.select_next(),
+            Some(Command::Select) if self.count > 0 => self.mode = ViewMode::Details,
+            Some(Command::Mark) => self.toggle_mark(),
+            Some(Command::Delete) if self.count > 0 => {
+                self.confirm(ViewMode::ConfirmDelete, self.marked_count() > 0);
+            }
+            Some(Command::Verify) if self.count > 0 => {
+                self.confirm(ViewMode::ConfirmVerify, self.marked_count() > 0);
+            }
+            Some(Command::Boot) if self.count > 0 && self.bootable() => {
+                self.mode = ViewMode::ConfirmBoot;
+            }
+            Some(Command::Rescan) => return Action::Refresh,
+            Some(Command::CheckUpdates) if self.count > 0 => return Action::CheckUpdates,
+            Some(Command::Redownload) if self.can_redownload() => {
+                self.mode = ViewMode::ConfirmRedownload;
+            }
+            _ => {}
         }
-
         Action::None
     }
 
-    fn handle_confirm_delete_key(&mut self, _scan_code: u16, unicode: u16) -> Action {
-        // 'y' or 'Y' - confirm delete
-        if unicode == 0x79 || unicode == 0x59 {
-            let idx = self.selected;
-            self.mode = ViewMode::List;
-            return Action::Delete(idx);
+    fn handle_details_command(&mut self, command: Option<Command>) -> Action {
+        match command {
+            Some(Command::Back) => self.mode = ViewMode::List,
+            Some(Command::Boot) if self.bootable() => self.mode = ViewMode::ConfirmBoot,
+            Some(Command::Delete) => self.confirm(ViewMode::ConfirmDelete, false),
+            Some(Command::Verify) => self.confirm(ViewMode::ConfirmVerify, false),
+            Some(Command::Redownload) if self.can_redownload() => {
+                self.mode = ViewMode::ConfirmRedownload;
+            }
+            _ => {}
         }
+        Action::None
+    }
 
-        // 'n' or 'N' or ESC - cancel
-        if unicode == 0x6E || unicode == 0x4E || unicode == 0x1B {
-            self.mode = ViewMode::List;
-            return Action::None;
+    fn handle_confirm_delete_command(&mut self, command: Option<Command>) -> Action {
+        match command {
+            Some(Command::Yes) => {
+                self.mode = ViewMode::List;
+                if self.batch {
+                    Action::DeleteMarked
+                } else {
+                    Action::Delete(self.selected)
+                }
+            }
+            Some(Command::No) => {
+                self.mode = ViewMode::List;
+                Action::None
+            }
+            _ => Action::None,
         }
-
-        Action::None
     }
 
-    fn handle_confirm_boot_key(&mut self, _scan_code: u16, unicode: u16) -> Action {
-        // 'y' or 'Y' - confirm boot
-        if unicode == 0x79 || unicode == 0x59 {
-            let idx = self.selected;
-            self.mode = ViewMode::List;
-            return Action::Boot(idx);
+    fn handle_confirm_verify_command(&mut self, command: Option<Command>) -> Action {
+        match command {
+            Some(Command::Yes) => {
+                self.mode = ViewMode::List;
+                if self.batch {
+                    Action::VerifyMarked
+                } else {
+                    Action::Verify(self.selected)
+                }
+            }
+            Some(Command::No) => {
+                self.mode = ViewMode::List;
+                Action::None
+            }
+            _ => Action::None,
         }
+    }
 
-        // 'n' or 'N' or ESC - cancel
-        if unicode == 0x6E || unicode == 0x4E || unicode == 0x1B {
-            self.mode = ViewMode::List;
-            return Action::None;
+    fn handle_confirm_boot_command(&mut self, command: Option<Command>) -> Action {
+        match command {
+            Some(Command::Yes) => {
+                self.mode = ViewMode::List;
+                Action::Boot(self.selected)
+            }
+            Some(Command::No) => {
+                self.mode = ViewMode::List;
+                Action::None
+            }
+            _ => Action::None,
         }
+    }
 
-        Action::None
+    fn handle_confirm_redownload_command(&mut self, command: Option<Command>) -> Action {
+        match command {
+            Some(Command::Yes) => {
+                self.mode = ViewMode::List;
+                Action::Redownload(self.selected)
+            }
+            Some(Command::No) => {
+                self.mode = ViewMode::List;
+                Action::None
+            }
+            _ => Action::None,
+        }
     }
 
     /// Set error message
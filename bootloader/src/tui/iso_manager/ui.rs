@@ -4,14 +4,28 @@
 
 use super::renderer;
 use super::state::{Action, IsoManagerState, ViewMode};
+use super::updates::{self, RemoteValidators};
+use super::verify::{self, SectorReader, VerifyStatus};
+use crate::tui::distro_downloader::catalog::DistroEntry;
 use crate::tui::input::Keyboard;
+use crate::tui::keymap;
 use crate::tui::renderer::Screen;
-use morpheus_core::iso::IsoStorageManager;
+use crate::tui::screensaver::Event;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use morpheus_core::iso::{IsoError, IsoStorageManager, MAX_ISOS};
+use morpheus_network::HttpClient;
 
 /// ISO Manager TUI component
 pub struct IsoManager {
     state: IsoManagerState,
     storage: IsoStorageManager,
+    /// Server validators from `check_online`, by catalog URL
+    remote: Vec<(&'static str, RemoteValidators)>,
+    /// Newer releases the user asked to download again
+    queued: Vec<&'static DistroEntry>,
+    /// Sector reader for the chunk partitions (None = can't verify)
+    disk_reader: Option<SectorReader>,
 }
 
 impl IsoManager {
@@ -25,7 +39,13 @@ impl IsoManager {
         let mut state = IsoManagerState::new();
         state.load_from_manager(&storage);
 
-        Self { state, storage }
+        Self {
+            state,
+            storage,
+            remote: Vec::new(),
+            queued: Vec::new(),
+            disk_reader: None,
+        }
     }
 
     /// Create with existing storage manager
@@ -33,7 +53,13 @@ impl IsoManager {
         let mut state = IsoManagerState::new();
         state.load_from_manager(&storage);
 
-        Self { state, storage }
+        Self {
+            state,
+            storage,
+            remote: Vec::new(),
+            queued: Vec::new(),
+            disk_reader: None,
+        }
     }
 
     /// Get reference to storage manager
@@ -46,12 +72,66 @@ impl IsoManager {
         &mut self.storage
     }
 
+    /// Give the manager a way to read the chunk partitions, which
+    /// verifying needs: `read(lba, buffer)` fills `buffer` from `lba` on.
+    pub fn set_disk_reader(
+        &mut self,
+        read: impl FnMut(u64, &mut [u8]) -> Result<(), IsoError> + 'static,
+    ) {
+        self.disk_reader = Some(Box::new(read));
+    }
+
     /// Reload ISO list from storage
     pub fn refresh(&mut self) {
+        let checked = self.state.checked();
         self.state.load_from_manager(&self.storage);
+        if checked {
+            self.check_updates();
+        }
         self.state.clear_error();
     }
 
+    /// Compare every stored ISO with the catalog, and with the server
+    /// validators `check_online` fetched, if it ran.
+    pub fn check_updates(&mut self) {
+        for (i, (_, entry)) in self.storage.iter().enumerate().take(self.state.count) {
+            let name = entry.manifest.name_str();
+            let remote = updates::catalog_match(name).and_then(|distro| {
+                self.remote
+                    .iter()
+                    .find(|(url, _)| *url == distro.url)
+                    .map(|(_, validators)| *validators)
+            });
+            let status = updates::status(&entry.manifest, remote);
+            self.state.updates[i] = status;
+            self.state.queued[i] = status
+                .newer()
+                .is_some_and(|newer| self.queued.iter().any(|q| q.url == newer.url));
+        }
+    }
+
+    /// HEAD the catalog URL of every stored catalog release and check for
+    /// updates. Meant for when the network is up before ExitBootServices;
+    /// without it the check goes by the catalog alone.
+    pub fn check_online<C: HttpClient>(&mut self, client: &mut C) {
+        self.remote.clear();
+        for (_, entry) in self.storage.iter() {
+            let name = entry.manifest.name_str();
+            let Some(distro) = updates::catalog_match(name).filter(|d| d.filename == name) else {
+                continue;
+            };
+            if let Some(validators) = updates::fetch_validators(client, distro.url) {
+                self.remote.push((distro.url, validators));
+            }
+        }
+        self.check_updates();
+    }
+
+    /// Releases queued for re-download, emptying the queue
+    pub fn take_redownloads(&mut self) -> Vec<&'static DistroEntry> {
+        core::mem::take(&mut self.queued)
+    }
+
     /// Run the ISO manager UI
     ///
     /// Returns when user presses ESC or selects boot action.
@@ -61,30 +141,63 @@ impl IsoManager {
         renderer::render(screen, &self.state);
 
         loop {
-            if let Some(key) = keyboard.poll_key_with_delay() {
-                let action = self.state.handle_key(key.scan_code, key.unicode_char);
-
-                match action {
-                    Action::None => {
-                        // Just re-render
-                        renderer::render(screen, &self.state);
-                    }
-                    Action::Back => {
-                        return None;
-                    }
-                    Action::Boot(idx) => {
-                        return Some(idx);
-                    }
-                    Action::Delete(idx) => {
-                        self.handle_delete(idx);
-                        screen.clear();
-                        renderer::render(screen, &self.state);
-                    }
-                    Action::Refresh => {
-                        self.refresh();
-                        screen.clear();
-                        renderer::render(screen, &self.state);
-                    }
+            let key = match keymap::poll(screen, keyboard, self.state.bindings()) {
+                Some(Event::Key(key)) => key,
+                Some(Event::Redraw) => {
+                    screen.clear();
+                    renderer::render(screen, &self.state);
+                    continue;
+                }
+                None => continue,
+            };
+
+            match self.state.handle_key(&key) {
+                Action::None => {
+                    // Just re-render
+                    renderer::render(screen, &self.state);
+                }
+                Action::Back => {
+                    return None;
+                }
+                Action::Boot(idx) => {
+                    return Some(idx);
+                }
+                Action::Delete(idx) => {
+                    self.handle_delete(idx);
+                    screen.clear();
+                    renderer::render(screen, &self.state);
+                }
+                Action::DeleteMarked => {
+                    self.delete_marked(screen);
+                    screen.clear();
+                    renderer::render(screen, &self.state);
+                }
+                Action::Verify(idx) => {
+                    self.verify_isos(screen, &[idx]);
+                    screen.clear();
+                    renderer::render(screen, &self.state);
+                }
+                Action::VerifyMarked => {
+                    let targets = self.state.targets();
+                    self.verify_isos(screen, &targets);
+                    self.state.marked = [false; MAX_ISOS];
+                    screen.clear();
+                    renderer::render(screen, &self.state);
+                }
+                Action::Refresh => {
+                    self.refresh();
+                    screen.clear();
+                    renderer::render(screen, &self.state);
+                }
+                Action::CheckUpdates => {
+                    self.check_updates();
+                    screen.clear();
+                    renderer::render(screen, &self.state);
+                }
+                Action::Redownload(idx) => {
+                    self.queue_redownload(idx);
+                    screen.clear();
+                    renderer::render(screen, &self.state);
                 }
             }
         }
@@ -92,9 +205,13 @@ impl IsoManager {
 
     /// Handle delete action
     fn handle_delete(&mut self, idx: usize) {
+        let checked = self.state.checked();
         match self.storage.remove_entry(idx) {
             Ok(()) => {
                 self.state.load_from_manager(&self.storage);
+                if checked {
+                    self.check_updates();
+                }
                 self.state.clear_error();
             }
             Err(_) => {
@@ -103,6 +220,105 @@ impl IsoManager {
         }
     }
 
+    /// Delete every marked ISO, last first so the indices left to do
+    /// stay valid
+    fn delete_marked(&mut self, screen: &mut Screen) {
+        let targets = self.state.targets();
+        let total_mb = self.state.targets_size_mb();
+        let checked = self.state.checked();
+        let mut done_mb = 0;
+        let mut failed = 0;
+        screen.clear();
+        for (n, &idx) in targets.iter().enumerate().rev() {
+            let name = self.get_name(idx).unwrap_or("???");
+            let progress = renderer::BatchProgress {
+                title: "Deleting ISOs",
+                item: targets.len() - n,
+                items: targets.len(),
+                name,
+                done_mb,
+                total_mb,
+            };
+            renderer::render_batch_progress(screen, &progress);
+            done_mb += self.state.sizes_mb[idx];
+            if self.storage.remove_entry(idx).is_err() {
+                failed += 1;
+            }
+        }
+
+        self.state.load_from_manager(&self.storage);
+        if checked {
+            self.check_updates();
+        }
+        if failed > 0 {
+            self.state.set_error("Failed to delete some ISOs");
+        } else {
+            self.state.clear_error();
+        }
+    }
+
+    /// Hash the ISOs at `targets` and compare with their manifests,
+    /// showing progress across all of them
+    fn verify_isos(&mut self, screen: &mut Screen, targets: &[usize]) {
+        let Some(read) = self.disk_reader.as_mut() else {
+            self.state.set_error("No disk access to verify with");
+            return;
+        };
+        let total_mb: u64 = targets.iter().map(|&i| self.state.sizes_mb[i]).sum();
+        let mut done_mb = 0;
+        let mut bad = 0;
+        screen.clear();
+        for (n, &idx) in targets.iter().enumerate() {
+            let Some(entry) = self.storage.get(idx) else {
+                continue;
+            };
+            let mut progress = renderer::BatchProgress {
+                title: "Verifying ISOs",
+                item: n + 1,
+                items: targets.len(),
+                name: entry.manifest.name_str(),
+                done_mb,
+                total_mb,
+            };
+            renderer::render_batch_progress(screen, &progress);
+            let mut shown_mb = 0;
+            let status = verify::verify(&entry.manifest, &mut *read, |bytes| {
+                let mb = bytes / (1024 * 1024);
+                // Redrawing costs more than hashing a few sectors
+                if mb != shown_mb {
+                    shown_mb = mb;
+                    progress.done_mb = done_mb + mb;
+                    renderer::render_batch_progress(screen, &progress);
+                }
+            });
+            done_mb += self.state.sizes_mb[idx];
+            if matches!(
+                status,
+                VerifyStatus::Mismatch | VerifyStatus::NotZeroed | VerifyStatus::ReadError
+            ) {
+                bad += 1;
+            }
+            self.state.verified[idx] = status;
+        }
+
+        if bad > 0 {
+            self.state
+                .set_error("Verification failed - see STATUS column");
+        } else {
+            self.state.clear_error();
+        }
+    }
+
+    /// Queue the newer release found for the ISO at `idx`
+    fn queue_redownload(&mut self, idx: usize) {
+        if let Some(newer) = self.state.updates[idx].newer() {
+            if !self.queued.iter().any(|q| q.url == newer.url) {
+                self.queued.push(newer);
+            }
+            self.state.queued[idx] = true;
+        }
+    }
+
     /// Get read context for booting an ISO
     pub fn get_boot_context(
         &self,
@@ -0,0 +1,119 @@
+//! Newer-version checks for stored ISOs.
+//!
+//! Two things can say a stored ISO is out of date. The catalog shipped
+//! with the bootloader names the file of each distro's current release,
+//! so a stored ISO of a catalog distro under another filename is an older
+//! release. When the network is up before ExitBootServices, a HEAD of the
+//! catalog URL also returns the server's Last-Modified and ETag, which are
+//! compared with the validators the manifest recorded at download time;
+//! that catches a release re-spun under the same name.
+
+use crate::tui::distro_downloader::catalog::{DistroEntry, DISTRO_CATALOG};
+use morpheus_core::iso::{etag_hash, IsoManifest};
+use morpheus_network::http::headers::parse_http_date;
+use morpheus_network::http::Request;
+use morpheus_network::url::Url;
+use morpheus_network::HttpClient;
+
+/// What the last check found for one stored ISO
+#[derive(Debug, Clone, Copy)]
+pub enum UpdateStatus {
+    /// No check has run
+    Unchecked,
+    /// The ISO is not from a catalog distro
+    NotInCatalog,
+    /// Catalog release, no server validators to compare
+    Unknown,
+    /// Catalog release, and the server still has the same copy
+    UpToDate,
+    /// The catalog lists a newer release
+    NewRelease(&'static DistroEntry),
+    /// Same release name, but the server's copy changed
+    Changed(&'static DistroEntry),
+}
+
+impl UpdateStatus {
+    /// Short text for the list and details views
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Unchecked => "",
+            Self::NotInCatalog => "Not in catalog",
+            Self::Unknown => "Latest in catalog",
+            Self::UpToDate => "Up to date",
+            Self::NewRelease(_) => "Newer release",
+            Self::Changed(_) => "Changed upstream",
+        }
+    }
+
+    /// Catalog entry to re-download, when there is something newer
+    pub fn newer(&self) -> Option<&'static DistroEntry> {
+        match self {
+            Self::NewRelease(entry) | Self::Changed(entry) => Some(entry),
+            _ => None,
+        }
+    }
+}
+
+/// Server validators from a HEAD of the catalog URL
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RemoteValidators {
+    /// Last-Modified, Unix seconds (0 = not sent)
+    pub last_modified: u32,
+    /// `etag_hash` of the ETag (0 = not sent)
+    pub etag_hash: u32,
+}
+
+/// Filename up to the version: "tails-6.10.iso" -> "tails".
+fn distro_stem(filename: &str) -> &str {
+    let bytes = filename.as_bytes();
+    (1..bytes.len())
+        .find(|&i| bytes[i - 1] == b'-' && bytes[i].is_ascii_digit())
+        .map(|i| &filename[..i - 1])
+        .unwrap_or(filename)
+}
+
+/// Catalog entry a stored ISO was downloaded from, or the current release
+/// of the same distro.
+pub fn catalog_match(filename: &str) -> Option<&'static DistroEntry> {
+    DISTRO_CATALOG
+        .iter()
+        .find(|d| d.filename == filename)
+        .or_else(|| {
+            let stem = distro_stem(filename);
+            DISTRO_CATALOG
+                .iter()
+                .find(|d| distro_stem(d.filename).eq_ignore_ascii_case(stem))
+        })
+}
+
+/// Update status of `manifest`, with `remote` from a HEAD if one was made.
+pub fn status(manifest: &IsoManifest, remote: Option<RemoteValidators>) -> UpdateStatus {
+    let Some(entry) = catalog_match(manifest.name_str()) else {
+        return UpdateStatus::NotInCatalog;
+    };
+    if entry.filename != manifest.name_str() {
+        return UpdateStatus::NewRelease(entry);
+    }
+    match remote.and_then(|r| manifest.changed_upstream(r.last_modified, r.etag_hash)) {
+        Some(true) => UpdateStatus::Changed(entry),
+        Some(false) => UpdateStatus::UpToDate,
+        None => UpdateStatus::Unknown,
+    }
+}
+
+/// HEAD `url` and return the validators of a 200 response.
+pub fn fetch_validators<C: HttpClient>(client: &mut C, url: &str) -> Option<RemoteValidators> {
+    let request = Request::head(Url::parse(url).ok()?);
+    let response = client.request(&request).ok()?;
+    if response.status_code != 200 {
+        return None;
+    }
+    Some(RemoteValidators {
+        last_modified: response
+            .headers
+            .get("Last-Modified")
+            .and_then(parse_http_date)
+            .unwrap_or(0),
+        etag_hash: response.headers.get("ETag").map(etag_hash).unwrap_or(0),
+    })
+}
@@ -0,0 +1,88 @@
+//! SHA-256 check of stored ISOs.
+//!
+//! A download records the SHA-256 of the bytes it wrote in the manifest.
+//! Verifying reads the ISO back through its chunk partitions and compares.
+//! Sectors the download skipped as zero (the manifest's skipped ranges)
+//! are hashed as read; if they aren't zero the disk wasn't zeroed as the
+//! download assumed, which is reported apart from other corruption.
+//! The manager has no disk of its own; whoever opens it hands over a
+//! sector reader (see [`IsoManager::set_disk_reader`](super::IsoManager)).
+
+use alloc::vec;
+use morpheus_core::iso::{ChunkedReader, IsoError, IsoManifest, IsoReadContext};
+use morpheus_network::transfer::Sha256;
+
+/// Bytes read and hashed per step
+const READ_SIZE: usize = 64 * 1024;
+
+/// Reads 512-byte sectors from the disk holding the chunk partitions
+pub type SectorReader = alloc::boxed::Box<dyn FnMut(u64, &mut [u8]) -> Result<(), IsoError>>;
+
+/// What the last verify found for one stored ISO
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyStatus {
+    /// Not verified since the list was loaded
+    Unchecked,
+    /// The manifest has no hash to compare with
+    NoHash,
+    /// Data matches the recorded hash
+    Verified,
+    /// Data differs from the recorded hash
+    Mismatch,
+    /// Sectors the download skipped as zero aren't zero on disk
+    NotZeroed,
+    /// A chunk could not be read
+    ReadError,
+}
+
+impl VerifyStatus {
+    /// Short text for the list and details views
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Unchecked => "",
+            Self::NoHash => "No hash",
+            Self::Verified => "Verified",
+            Self::Mismatch => "CORRUPT",
+            Self::NotZeroed => "Not zeroed",
+            Self::ReadError => "Read error",
+        }
+    }
+}
+
+/// Hash the ISO described by `manifest`, reading sectors with `read_fn`.
+/// `progress` gets the bytes hashed so far after every read.
+pub fn verify<F, P>(manifest: &IsoManifest, read_fn: F, mut progress: P) -> VerifyStatus
+where
+    F: FnMut(u64, &mut [u8]) -> Result<(), IsoError>,
+    P: FnMut(u64),
+{
+    if manifest.sha256 == [0u8; 32] {
+        return VerifyStatus::NoHash;
+    }
+
+    let mut reader = ChunkedReader::new(IsoReadContext::from_manifest(manifest), read_fn);
+    let total = reader.total_size();
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; READ_SIZE];
+    let mut done = 0u64;
+    let mut not_zeroed = false;
+    while done < total {
+        match reader.read(&mut buffer) {
+            Ok(0) | Err(_) => return VerifyStatus::ReadError,
+            Ok(n) => {
+                hasher.update(&buffer[..n]);
+                not_zeroed |= manifest.skipped.nonzero_in(done, &buffer[..n]);
+                done += n as u64;
+                progress(done);
+            }
+        }
+    }
+
+    if hasher.finalize() == manifest.sha256 {
+        VerifyStatus::Verified
+    } else if not_zeroed {
+        VerifyStatus::NotZeroed
+    } else {
+        VerifyStatus::Mismatch
+    }
+}
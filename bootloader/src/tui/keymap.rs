@@ -0,0 +1,320 @@
+//! Key bindings and the `?` help overlay.
+//!
+//! Screens declare a [`Bindings`] table and match on [`Command`]s instead of
+//! raw scan codes. The table is the only place a key is assigned, so the
+//! help overlay always agrees with what the screen does, and remapping a
+//! key means editing one table.
+//!
+//! ```ignore
+//! const BINDINGS: Bindings = Bindings {
+//!     title: "Example",
+//!     keys: &[
+//!         KeyBinding::new(&[Key::Up], Command::Up, "Previous item"),
+//!         KeyBinding::new(&[Key::Esc], Command::Back, "Back"),
+//!     ],
+//! };
+//!
+//! match keymap::poll(screen, keyboard, &BINDINGS) {
+//!     Some(Event::Key(key)) => match BINDINGS.lookup(&key) {
+//!         Some(Command::Up) => self.select_prev(),
+//!         Some(Command::Back) => return,
+//!         _ => {}
+//!     },
+//!     Some(Event::Redraw) => self.render(screen),
+//!     None => {}
+//! }
+//! ```
+
+use crate::tui::input::{
+    InputKey, Keyboard, KEY_ENTER, SCAN_DOWN, SCAN_END, SCAN_ESC, SCAN_HOME, SCAN_LEFT,
+    SCAN_PAGE_DOWN, SCAN_PAGE_UP, SCAN_RIGHT, SCAN_UP,
+};
+use crate::tui::renderer::{Screen, EFI_BLACK, EFI_DARKGREEN, EFI_GREEN, EFI_LIGHTGREEN};
+use crate::tui::screensaver::{self, Event};
+use alloc::format;
+use alloc::string::String;
+
+/// A physical key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    Esc,
+    Enter,
+    Backspace,
+    /// A printable ASCII key; letters match either case
+    Char(u8),
+}
+
+impl Key {
+    pub fn matches(self, key: &InputKey) -> bool {
+        match self {
+            Key::Up => key.scan_code == SCAN_UP,
+            Key::Down => key.scan_code == SCAN_DOWN,
+            Key::Left => key.scan_code == SCAN_LEFT,
+            Key::Right => key.scan_code == SCAN_RIGHT,
+            Key::PageUp => key.scan_code == SCAN_PAGE_UP,
+            Key::PageDown => key.scan_code == SCAN_PAGE_DOWN,
+            Key::Home => key.scan_code == SCAN_HOME,
+            Key::End => key.scan_code == SCAN_END,
+            // Some firmware reports ESC as a character instead
+            Key::Esc => key.scan_code == SCAN_ESC || key.unicode_char == 0x1B,
+            Key::Enter => {
+                key.scan_code == 0
+                    && (key.unicode_char == KEY_ENTER || key.unicode_char == b'\n' as u16)
+            }
+            Key::Backspace => key.scan_code == 0 && key.unicode_char == 0x08,
+            Key::Char(c) => {
+                key.scan_code == 0
+                    && key.unicode_char < 0x80
+                    && (key.unicode_char as u8).eq_ignore_ascii_case(&c)
+            }
+        }
+    }
+
+    /// Name shown in the help overlay.
+    pub fn name(self) -> String {
+        match self {
+            Key::Up => String::from("UP"),
+            Key::Down => String::from("DOWN"),
+            Key::Left => String::from("LEFT"),
+            Key::Right => String::from("RIGHT"),
+            Key::PageUp => String::from("PGUP"),
+            Key::PageDown => String::from("PGDN"),
+            Key::Home => String::from("HOME"),
+            Key::End => String::from("END"),
+            Key::Esc => String::from("ESC"),
+            Key::Enter => String::from("ENTER"),
+            Key::Backspace => String::from("BKSP"),
+            Key::Char(b' ') => String::from("SPACE"),
+            Key::Char(c) => format!("{}", c.to_ascii_uppercase() as char),
+        }
+    }
+}
+
+/// What a key does. Screens only handle the commands in their table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Up,
+    Down,
+    Left,
+    Right,
+    PageUp,
+    PageDown,
+    Top,
+    Bottom,
+    Search,
+    NextMatch,
+    Select,
+    Back,
+    Yes,
+    No,
+    Help,
+    Screensaver,
+    Debug,
+    CycleLayout,
+    Rescan,
+    Create,
+    NewPartition,
+    Delete,
+    Shrink,
+    Format,
+    Manage,
+    Boot,
+    Details,
+    ViewLog,
+    CrashReport,
+    NicSelfTest,
+    Checksums,
+    History,
+    Sync,
+    Placement,
+    TargetDisk,
+    AfterDownload,
+    Beeps,
+    RateLimit,
+    LinkSpeed,
+    Compact,
+    Check,
+    Export,
+    Policy,
+    SizeLimit,
+    CountLimit,
+    AutoDelete,
+    CheckUpdates,
+    Redownload,
+    Mark,
+    Verify,
+    BootTimeout,
+    MakeDefault,
+    Variables,
+}
+
+pub struct KeyBinding {
+    pub keys: &'static [Key],
+    pub command: Command,
+    /// Help overlay text
+    pub label: &'static str,
+}
+
+impl KeyBinding {
+    pub const fn new(keys: &'static [Key], command: Command, label: &'static str) -> Self {
+        Self {
+            keys,
+            command,
+            label,
+        }
+    }
+}
+
+/// One screen's key table.
+pub struct Bindings {
+    /// Help overlay heading
+    pub title: &'static str,
+    pub keys: &'static [KeyBinding],
+}
+
+impl Bindings {
+    /// The command bound to `key`, if any. First match wins.
+    pub fn lookup(&self, key: &InputKey) -> Option<Command> {
+        self.keys
+            .iter()
+            .find(|binding| binding.keys.iter().any(|k| k.matches(key)))
+            .map(|binding| binding.command)
+    }
+}
+
+/// Bound on every screen that reads input through [`poll`].
+pub const GLOBAL: Bindings = Bindings {
+    title: "Everywhere",
+    keys: &[
+        KeyBinding::new(&[Key::Char(b'?')], Command::Help, "Show this help"),
+        KeyBinding::new(&[Key::Char(b'x')], Command::Screensaver, "Screensaver"),
+    ],
+};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// INPUT HOOK
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// [`screensaver::poll`] plus the help overlay: `?` shows `bindings` and
+/// reports a redraw once it is dismissed.
+pub fn poll(screen: &mut Screen, keyboard: &mut Keyboard, bindings: &Bindings) -> Option<Event> {
+    match screensaver::poll(screen, keyboard) {
+        Some(Event::Key(key)) if GLOBAL.lookup(&key) == Some(Command::Help) => {
+            show_help(screen, keyboard, bindings);
+            Some(Event::Redraw)
+        }
+        event => event,
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// HELP OVERLAY
+// ═══════════════════════════════════════════════════════════════════════════
+
+const HELP_WIDTH: usize = 52;
+const KEY_COLUMN: usize = 16;
+
+/// Draw `bindings` and the global keys in a centred box and wait for a key.
+/// The caller redraws its screen afterwards.
+pub fn show_help(screen: &mut Screen, keyboard: &mut Keyboard, bindings: &Bindings) {
+    // Border, title, divider, screen keys, divider, global keys, divider,
+    // footer, border
+    let height = 3 + bindings.keys.len() + 1 + GLOBAL.keys.len() + 1 + 2;
+    let x = screen.center_x(HELP_WIDTH);
+    let mut y = screen.center_y(height);
+
+    let border = format!("+{}+", "=".repeat(HELP_WIDTH - 2));
+    let divider = format!("+{}+", "-".repeat(HELP_WIDTH - 2));
+    let blank = format!("|{}|", " ".repeat(HELP_WIDTH - 2));
+
+    screen.put_str_at(x, y, &border, EFI_GREEN, EFI_BLACK);
+    y += 1;
+    screen.put_str_at(x, y, &blank, EFI_GREEN, EFI_BLACK);
+    let title = format!("{} - keys", bindings.title);
+    screen.put_str_at(
+        x + (HELP_WIDTH - title.len()) / 2,
+        y,
+        &title,
+        EFI_LIGHTGREEN,
+        EFI_BLACK,
+    );
+    y += 1;
+    screen.put_str_at(x, y, &divider, EFI_GREEN, EFI_BLACK);
+    y += 1;
+
+    for table in [bindings, &GLOBAL] {
+        for binding in table.keys {
+            let mut keys = String::new();
+            for (i, key) in binding.keys.iter().enumerate() {
+                if i > 0 {
+                    keys.push('/');
+                }
+                keys.push_str(&key.name());
+            }
+            screen.put_str_at(x, y, &blank, EFI_GREEN, EFI_BLACK);
+            screen.put_str_at(x + 3, y, &keys, EFI_LIGHTGREEN, EFI_BLACK);
+            screen.put_str_at(x + 3 + KEY_COLUMN, y, binding.label, EFI_GREEN, EFI_BLACK);
+            y += 1;
+        }
+        screen.put_str_at(x, y, &divider, EFI_GREEN, EFI_BLACK);
+        y += 1;
+    }
+
+    screen.put_str_at(x, y, &blank, EFI_GREEN, EFI_BLACK);
+    let footer = "Press any key to close";
+    screen.put_str_at(
+        x + (HELP_WIDTH - footer.len()) / 2,
+        y,
+        footer,
+        EFI_DARKGREEN,
+        EFI_BLACK,
+    );
+    y += 1;
+    screen.put_str_at(x, y, &border, EFI_GREEN, EFI_BLACK);
+
+    keyboard.wait_for_key();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(scan_code: u16, unicode_char: u16) -> InputKey {
+        InputKey {
+            scan_code,
+            unicode_char,
+        }
+    }
+
+    #[test]
+    fn test_char_matches_either_case() {
+        assert!(Key::Char(b'd').matches(&key(0, b'd' as u16)));
+        assert!(Key::Char(b'd').matches(&key(0, b'D' as u16)));
+        assert!(!Key::Char(b'd').matches(&key(SCAN_UP, b'd' as u16)));
+        assert!(Key::Esc.matches(&key(SCAN_ESC, 0)));
+        assert!(Key::Esc.matches(&key(0, 0x1B)));
+    }
+
+    #[test]
+    fn test_lookup_first_match_wins() {
+        const TABLE: Bindings = Bindings {
+            title: "Test",
+            keys: &[
+                KeyBinding::new(&[Key::Esc, Key::Backspace], Command::Back, "Back"),
+                KeyBinding::new(&[Key::Esc], Command::No, "Cancel"),
+            ],
+        };
+        assert_eq!(TABLE.lookup(&key(0, 0x08)), Some(Command::Back));
+        assert_eq!(TABLE.lookup(&key(SCAN_ESC, 0)), Some(Command::Back));
+        assert_eq!(TABLE.lookup(&key(0, b'q' as u16)), None);
+        assert_eq!(GLOBAL.lookup(&key(0, b'?' as u16)), Some(Command::Help));
+    }
+}
@@ -0,0 +1,594 @@
+//! System status dashboard for the main menu.
+//!
+//! [`SystemStatus::gather`] takes one snapshot from the NIC probe, a GPT
+//! scan of every disk, the ISO manifest directory and the exit report the
+//! last download left, so the user can see whether the machine is ready
+//! before opening a submenu. Rendering only
+//! reads the snapshot; gather again after anything that changes disks or
+//! downloads.
+//!
+//! The same pass self-checks the installed environment: the ESP's files
+//! against the hashes the installer recorded, every manifest on it, and
+//! each disk's SMART critical warnings. Problems are listed as
+//! [`HealthIssue`]s, each with the fix to try.
+
+use crate::tui::distro_downloader::commit::pci::{find_nic, LinkState, NicSummary};
+use crate::tui::distro_downloader::manifest_io;
+use crate::tui::renderer::{EFI_DARKGREEN, EFI_GREEN, EFI_LIGHTGREEN, EFI_YELLOW};
+use crate::uefi::gpt_adapter::UefiBlockIoAdapter;
+use crate::BootServices;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use morpheus_core::disk::gpt_ops;
+use morpheus_core::disk::health::CriticalWarning;
+use morpheus_core::disk::manager::{DiskInfo, DiskManager};
+use morpheus_core::disk::partition::{PartitionTable, PartitionType};
+use morpheus_core::fs::fat32_ops;
+use morpheus_core::fs::self_check::{self, EspIssue};
+use morpheus_core::iso::{ExitReport, IsoStorageManager, Verification};
+
+/// The binary the installer writes; its presence means "installed".
+const INSTALLED_BINARY: &str = "/EFI/BOOT/BOOTX64.EFI";
+
+/// Disk rows shown before collapsing the rest into "+N more".
+const MAX_DISK_ROWS: usize = 3;
+
+/// Health issue rows shown before collapsing the rest into "+N more".
+const MAX_ISSUE_ROWS: usize = 3;
+
+pub struct DiskSummary {
+    pub index: usize,
+    pub size_mb: u64,
+    pub has_gpt: bool,
+    pub free_mb: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EspStatus {
+    /// ESP found and the bootloader binary is on it
+    Installed { disk: usize, partition: usize },
+    /// ESP found, nothing installed
+    NotInstalled { disk: usize, partition: usize },
+    /// No disk has an ESP
+    Missing,
+}
+
+/// Something the self-check found wrong with the installed environment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HealthIssue {
+    /// A file or manifest on the checked ESP
+    Esp(EspIssue),
+    /// The checked ESP couldn't be read as FAT32
+    EspUnreadable,
+    /// A disk raised SMART critical warnings
+    Disk {
+        index: usize,
+        warning: CriticalWarning,
+    },
+}
+
+impl HealthIssue {
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Esp(EspIssue::BootFileMissing) => String::from("boot file missing"),
+            Self::Esp(EspIssue::FileMissing(path)) => format!("{} missing", path),
+            Self::Esp(EspIssue::FileModified(path)) => format!("{} changed", path),
+            Self::Esp(EspIssue::BadManifest(path)) => format!("{} corrupt", path),
+            Self::EspUnreadable => String::from("ESP unreadable"),
+            Self::Disk { index, warning } => format!("disk{} {}", index, warning),
+        }
+    }
+
+    /// What to do about it
+    pub fn fix(&self) -> &'static str {
+        match self {
+            Self::Esp(EspIssue::BadManifest(_)) => "Repair: re-download or delete it",
+            Self::Esp(_) => "Reinstall from Installation",
+            Self::EspUnreadable => "Check the ESP in Storage Manager",
+            Self::Disk { .. } => "Back up data, replace the disk",
+        }
+    }
+}
+
+/// Snapshot of everything the dashboard shows.
+pub struct SystemStatus {
+    pub nic: Option<NicSummary>,
+    pub disks: Vec<DiskSummary>,
+    pub esp: EspStatus,
+    pub isos: usize,
+    pub isos_complete: usize,
+    /// How the last download session ended
+    pub last_run: Option<ExitReport>,
+    /// What the self-check found
+    pub issues: Vec<HealthIssue>,
+}
+
+/// One dashboard line.
+pub struct Row {
+    pub label: &'static str,
+    pub value: String,
+    pub color: usize,
+}
+
+impl SystemStatus {
+    /// Probe everything. Touches PCI config space, reads every disk's GPT,
+    /// SMART log and the ESP's files; nothing is written.
+    pub fn gather(bs: &BootServices, image_handle: *mut ()) -> Self {
+        let mut status = Self {
+            nic: find_nic(),
+            disks: Vec::new(),
+            esp: EspStatus::Missing,
+            isos: 0,
+            isos_complete: 0,
+            last_run: unsafe { manifest_io::load_exit_report(bs, image_handle) },
+            issues: Vec::new(),
+        };
+
+        // ESP to self-check: the one we booted from, else the installed one
+        let mut checked_esp = None;
+        let mut disk_manager = DiskManager::new();
+        if crate::uefi::disk::enumerate_disks(bs, &mut disk_manager).is_ok() {
+            for index in 0..disk_manager.disk_count() {
+                let Some(disk) = disk_manager.get_disk(index) else {
+                    continue;
+                };
+                if let Some(warning) = disk.critical_warning.filter(|w| !w.is_clear()) {
+                    status.issues.push(HealthIssue::Disk { index, warning });
+                }
+                if let Some(lba) = status.scan_disk(bs, index, disk) {
+                    if checked_esp.is_none() || disk.boot_esp_lba == Some(lba) {
+                        checked_esp = Some((index, lba));
+                    }
+                }
+            }
+        }
+        if let Some((index, lba)) = checked_esp {
+            status.check_esp(bs, index, lba);
+        }
+
+        let mut storage = IsoStorageManager::new(0, 0);
+        if unsafe {
+            crate::tui::distro_downloader::load_manifests_from_esp(bs, image_handle, &mut storage)
+        }
+        .is_ok()
+        {
+            status.isos = storage.count();
+            status.isos_complete = storage
+                .iter()
+                .filter(|(_, entry)| entry.manifest.is_complete())
+                .count();
+        }
+
+        status
+    }
+
+    /// GPT scan of one disk; records free space and the first ESP seen.
+    /// Returns the start LBA of the ESP on this disk worth a self-check:
+    /// the one we booted from, or the first ESP seen if it's installed.
+    fn scan_disk(&mut self, bs: &BootServices, index: usize, disk: &DiskInfo) -> Option<u64> {
+        let mut summary = DiskSummary {
+            index,
+            size_mb: disk.size_mb(),
+            has_gpt: false,
+            free_mb: 0,
+        };
+        let mut checked_esp = None;
+
+        let block_io_ptr = match crate::uefi::disk::get_disk_protocol(bs, index) {
+            Ok(ptr) => ptr,
+            Err(_) => {
+                self.disks.push(summary);
+                return None;
+            }
+        };
+        let block_size = unsafe { (*(*block_io_ptr).media).block_size as usize };
+
+        let mut table = PartitionTable::new();
+        let scanned = UefiBlockIoAdapter::new(unsafe { &mut *block_io_ptr })
+            .ok()
+            .and_then(|adapter| gpt_ops::scan_partitions(adapter, &mut table, block_size).ok());
+
+        if scanned.is_some() && table.has_gpt {
+            summary.has_gpt = true;
+            summary.free_mb = UefiBlockIoAdapter::new(unsafe { &mut *block_io_ptr })
+                .ok()
+                .and_then(|adapter| gpt_ops::calculate_total_free_space(adapter, block_size).ok())
+                .unwrap_or(0);
+
+            if self.esp == EspStatus::Missing {
+                let esp = (0..table.count())
+                    .filter_map(|i| table.get(i).map(|part| (i, part)))
+                    .find(|(_, part)| part.partition_type == PartitionType::EfiSystem);
+
+                if let Some((partition, part)) = esp {
+                    let installed = UefiBlockIoAdapter::new(unsafe { &mut *block_io_ptr })
+                        .ok()
+                        .and_then(|mut adapter| {
+                            fat32_ops::file_exists(&mut adapter, part.start_lba, INSTALLED_BINARY)
+                                .ok()
+                        })
+                        .unwrap_or(false);
+
+                    if installed {
+                        checked_esp = Some(part.start_lba);
+                    }
+                    self.esp = if installed {
+                        EspStatus::Installed {
+                            disk: index,
+                            partition,
+                        }
+                    } else {
+                        EspStatus::NotInstalled {
+                            disk: index,
+                            partition,
+                        }
+                    };
+                }
+            }
+
+            let booted_esp = disk.boot_esp_lba.filter(|&lba| {
+                (0..table.count()).filter_map(|i| table.get(i)).any(|part| {
+                    part.partition_type == PartitionType::EfiSystem && part.start_lba == lba
+                })
+            });
+            checked_esp = booted_esp.or(checked_esp);
+        }
+
+        self.disks.push(summary);
+        checked_esp
+    }
+
+    /// Self-check the ESP at `lba` on disk `index`.
+    fn check_esp(&mut self, bs: &BootServices, index: usize, lba: u64) {
+        let issues = crate::uefi::disk::get_disk_protocol(bs, index)
+            .ok()
+            .and_then(|ptr| UefiBlockIoAdapter::new(unsafe { &mut *ptr }).ok())
+            .and_then(|mut adapter| self_check::check_esp(&mut adapter, lba).ok());
+        match issues {
+            Some(issues) => self.issues.extend(issues.into_iter().map(HealthIssue::Esp)),
+            None => self.issues.push(HealthIssue::EspUnreadable),
+        }
+    }
+
+    /// Network state before handoff. The stack itself only comes up after
+    /// ExitBootServices, so this is what the download path will find.
+    pub fn network_state(&self) -> (&'static str, usize) {
+        match self.nic.map(|nic| nic.link) {
+            None => ("unavailable (no NIC)", EFI_YELLOW),
+            Some(LinkState::Down) => ("no link (check cable)", EFI_YELLOW),
+            Some(_) => ("ready, DHCP at download time", EFI_LIGHTGREEN),
+        }
+    }
+
+    /// Overall verdict and a hint for the status bar.
+    pub fn readiness(&self) -> (&'static str, &'static str, usize) {
+        if self.nic.is_none() {
+            ("NO NIC", "Downloads unavailable", EFI_YELLOW)
+        } else if self.esp == EspStatus::Missing {
+            ("NO ESP", "Create one in Storage Manager", EFI_YELLOW)
+        } else if let Some(issue) = self.issues.first() {
+            ("ISSUES", issue.fix(), EFI_YELLOW)
+        } else {
+            ("READY", "System: Operational", EFI_LIGHTGREEN)
+        }
+    }
+
+    pub fn rows(&self) -> Vec<Row> {
+        let mut rows = Vec::new();
+
+        rows.push(match &self.nic {
+            Some(nic) => {
+                let link = match (nic.link, nic.link_info) {
+                    (LinkState::Up, Some(info)) => format!("link up, {}", info),
+                    (LinkState::Up, None) => String::from("link up"),
+                    (LinkState::Down, _) => String::from("link down"),
+                    (LinkState::Unknown, _) => String::from("link unknown"),
+                };
+                Row {
+                    label: "NIC",
+                    value: format!(
+                        "{}  {:02x}:{:02x}.{}  {}",
+                        nic.model, nic.bus, nic.device, nic.function, link
+                    ),
+                    color: if nic.link == LinkState::Down {
+                        EFI_YELLOW
+                    } else {
+                        EFI_GREEN
+                    },
+                }
+            }
+            None => Row {
+                label: "NIC",
+                value: String::from("none found"),
+                color: EFI_YELLOW,
+            },
+        });
+
+        let (network, color) = self.network_state();
+        rows.push(Row {
+            label: "Network",
+            value: String::from(network),
+            color,
+        });
+
+        if self.disks.is_empty() {
+            rows.push(Row {
+                label: "Disks",
+                value: String::from("none found"),
+                color: EFI_YELLOW,
+            });
+        }
+        for (i, disk) in self.disks.iter().take(MAX_DISK_ROWS).enumerate() {
+            let value = if disk.has_gpt {
+                format!(
+                    "disk{}  {} MB  GPT  {} MB free",
+                    disk.index, disk.size_mb, disk.free_mb
+                )
+            } else {
+                format!("disk{}  {} MB  no GPT", disk.index, disk.size_mb)
+            };
+            rows.push(Row {
+                label: if i == 0 { "Disks" } else { "" },
+                value,
+                color: EFI_GREEN,
+            });
+        }
+        if self.disks.len() > MAX_DISK_ROWS {
+            rows.push(Row {
+                label: "",
+                value: format!("+{} more", self.disks.len() - MAX_DISK_ROWS),
+                color: EFI_DARKGREEN,
+            });
+        }
+
+        rows.push(match self.esp {
+            EspStatus::Installed { disk, partition } => Row {
+                label: "ESP",
+                value: format!("disk{} part{}  installed", disk, partition + 1),
+                color: EFI_LIGHTGREEN,
+            },
+            EspStatus::NotInstalled { disk, partition } => Row {
+                label: "ESP",
+                value: format!("disk{} part{}  not installed", disk, partition + 1),
+                color: EFI_GREEN,
+            },
+            EspStatus::Missing => Row {
+                label: "ESP",
+                value: String::from("none found"),
+                color: EFI_YELLOW,
+            },
+        });
+
+        rows.push(Row {
+            label: "ISOs",
+            value: format!("{} stored, {} complete", self.isos, self.isos_complete),
+            color: EFI_GREEN,
+        });
+
+        for (i, issue) in self.issues.iter().take(MAX_ISSUE_ROWS).enumerate() {
+            rows.push(Row {
+                label: if i == 0 { "Health" } else { "" },
+                value: format!("{} - {}", issue.describe(), issue.fix()),
+                color: EFI_YELLOW,
+            });
+        }
+        if self.issues.len() > MAX_ISSUE_ROWS {
+            rows.push(Row {
+                label: "",
+                value: format!("+{} more", self.issues.len() - MAX_ISSUE_ROWS),
+                color: EFI_DARKGREEN,
+            });
+        }
+
+        if let Some(report) = &self.last_run {
+            rows.push(last_run_row(report));
+        }
+
+        rows
+    }
+}
+
+/// "Last run" line: what the last download got through, or where it
+/// stopped and why.
+fn last_run_row(report: &ExitReport) -> Row {
+    let mb = report.bytes_downloaded / (1024 * 1024);
+    let (value, color) = match report.failure {
+        None => (
+            format!(
+                "completed, {} MB in {} s, {}",
+                mb,
+                report.duration_ms / 1000,
+                rate(report.throughput())
+            ),
+            EFI_LIGHTGREEN,
+        ),
+        Some(failure) => (
+            format!(
+                "failed in {}: {}, {} MB, {} retries",
+                report.final_state(),
+                failure.as_str(),
+                mb,
+                report.retries.total()
+            ),
+            EFI_YELLOW,
+        ),
+    };
+    Row {
+        label: "Last run",
+        value,
+        color,
+    }
+}
+
+/// One-line banner for a session's report, shown on the first start
+/// after it: "[OK] tails.iso downloaded and verified" or "[!!] tails.iso
+/// failed at DHCP: DHCP failed - [L] view log".
+pub fn session_banner(report: &ExitReport) -> (String, usize) {
+    let name = match report.iso_name() {
+        "" => "Download",
+        name => name,
+    };
+    match (report.failure, report.verification) {
+        (None, Verification::Verified) => (
+            format!("[OK] {} downloaded and verified", name),
+            EFI_LIGHTGREEN,
+        ),
+        (None, Verification::Unchecked) => (format!("[OK] {} downloaded", name), EFI_LIGHTGREEN),
+        (None, Verification::Mismatch) => (
+            format!("[!!] {} downloaded, SHA-256 MISMATCH", name),
+            EFI_YELLOW,
+        ),
+        (Some(failure), _) => (
+            format!(
+                "[!!] {} failed at {}: {} - [L] view log",
+                name,
+                report.final_state(),
+                failure.as_str()
+            ),
+            EFI_YELLOW,
+        ),
+    }
+}
+
+fn rate(bytes_per_sec: u64) -> String {
+    if bytes_per_sec >= 1024 * 1024 {
+        format!("{} MB/s", bytes_per_sec / (1024 * 1024))
+    } else {
+        format!("{} KB/s", bytes_per_sec / 1024)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn status(disks: usize) -> SystemStatus {
+        SystemStatus {
+            nic: None,
+            disks: (0..disks)
+                .map(|index| DiskSummary {
+                    index,
+                    size_mb: 1024,
+                    has_gpt: true,
+                    free_mb: 512,
+                })
+                .collect(),
+            esp: EspStatus::Missing,
+            isos: 0,
+            isos_complete: 0,
+            last_run: None,
+            issues: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_disk_rows_collapse() {
+        // NIC, Network, ESP, ISOs + disk rows
+        assert_eq!(status(0).rows().len(), 5);
+        assert_eq!(status(MAX_DISK_ROWS).rows().len(), 4 + MAX_DISK_ROWS);
+        assert_eq!(status(MAX_DISK_ROWS + 2).rows().len(), 5 + MAX_DISK_ROWS);
+    }
+
+    #[test]
+    fn test_last_run_row() {
+        use morpheus_core::iso::FailureReason;
+
+        let mut report = ExitReport::new("Done", None);
+        report.bytes_downloaded = 600 * 1024 * 1024;
+        report.duration_ms = 70_000;
+        report.transfer_ms = 60_000;
+        let mut s = status(0);
+        s.last_run = Some(report);
+        let rows = s.rows();
+        assert_eq!(rows.len(), 6);
+        assert_eq!(rows[5].value, "completed, 600 MB in 70 s, 10 MB/s");
+
+        let mut report = ExitReport::new("DNS", Some(FailureReason::Dns));
+        report.retries.dns = 3;
+        assert_eq!(
+            last_run_row(&report).value,
+            "failed in DNS: DNS failed, 0 MB, 3 retries"
+        );
+    }
+
+    #[test]
+    fn test_session_banner() {
+        use morpheus_core::iso::FailureReason;
+
+        let mut report = ExitReport::new("Done", None);
+        report.set_iso_name("tails-6.10.iso");
+        report.verification = Verification::Verified;
+        assert_eq!(
+            session_banner(&report),
+            (
+                String::from("[OK] tails-6.10.iso downloaded and verified"),
+                EFI_LIGHTGREEN
+            )
+        );
+
+        let report = ExitReport::new("DHCP", Some(FailureReason::Dhcp));
+        assert_eq!(
+            session_banner(&report).0,
+            "[!!] Download failed at DHCP: DHCP failed - [L] view log"
+        );
+    }
+
+    #[test]
+    fn test_readiness() {
+        let mut s = status(1);
+        assert_eq!(s.readiness().0, "NO NIC");
+
+        s.nic = Some(NicSummary {
+            bus: 0,
+            device: 3,
+            function: 0,
+            model: "VirtIO-net",
+            link: LinkState::Unknown,
+            link_info: None,
+        });
+        assert_eq!(s.readiness().0, "NO ESP");
+
+        s.esp = EspStatus::NotInstalled {
+            disk: 0,
+            partition: 0,
+        };
+        assert_eq!(s.readiness().0, "READY");
+
+        s.issues
+            .push(HealthIssue::Esp(EspIssue::BadManifest(String::from(
+                "/.iso/AAAA0001.MFS",
+            ))));
+        assert_eq!(
+            s.readiness(),
+            ("ISSUES", "Repair: re-download or delete it", EFI_YELLOW)
+        );
+    }
+
+    #[test]
+    fn test_health_rows() {
+        let mut s = status(0);
+        s.issues = vec![
+            HealthIssue::Disk {
+                index: 1,
+                warning: CriticalWarning(0x01),
+            },
+            HealthIssue::Esp(EspIssue::FileModified(String::from(self_check::BOOT_PATH))),
+        ];
+        let rows = s.rows();
+        assert_eq!(rows.len(), 7);
+        assert_eq!(rows[4].label, "Health");
+        assert_eq!(
+            rows[4].value,
+            "disk1 spare capacity low - Back up data, replace the disk"
+        );
+        assert_eq!(
+            rows[5].value,
+            "/EFI/BOOT/BOOTX64.EFI changed - Reinstall from Installation"
+        );
+
+        s.issues = vec![HealthIssue::EspUnreadable; MAX_ISSUE_ROWS + 2];
+        assert_eq!(s.rows().len(), 5 + MAX_ISSUE_ROWS + 1);
+    }
+}
@@ -0,0 +1,642 @@
+mod dashboard;
+pub mod power;
+
+pub use dashboard::{DiskSummary, EspStatus, SystemStatus};
+
+use crate::tui::debug::DebugOverlay;
+use crate::tui::distro_downloader::manifest_io;
+use crate::tui::input::{self, InputKey, Keyboard};
+use crate::tui::keymap::{self, Bindings, Command, Key, KeyBinding};
+use crate::tui::renderer::{
+    Screen, EFI_BLACK, EFI_DARKGREEN, EFI_GREEN, EFI_LIGHTGREEN, EFI_YELLOW,
+};
+use crate::tui::screensaver::{Event, POLLS_PER_SECOND};
+use crate::tui::widgets::textview;
+use crate::uefi::file_system;
+use crate::BootServices;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use morpheus_core::iso::{BootMenuConfig, DefaultKind};
+
+/// Where a crash report is left on the ESP for the next boot to show
+const CRASH_REPORT_PATH: &str = "\\EFI\\MORPHEUS\\CRASH.TXT";
+
+/// Where a crash report goes once the banner has announced it
+const CRASH_ARCHIVE_PATH: &str = "\\EFI\\MORPHEUS\\CRASH.OLD";
+
+/// Largest crash report the viewer loads
+const MAX_CRASH_REPORT_SIZE: usize = 64 * 1024;
+
+/// Boot timeouts `T` cycles through, in seconds (0 = wait for a key)
+const BOOT_TIMEOUTS: [u16; 5] = [0, 3, 5, 10, 30];
+
+// Smaller header that fits in the box
+const HEADER_ART: &[&str] = &[
+    " __  __  ___  ____  ____  _   _ _____ _   _ ______  __",
+    "|  \\/  |/ _ \\|  _ \\|  _ \\| | | | ____| | | / ___\\ \\/ /",
+    "| |\\/| | | | | |_) | |_) | |_| |  _| | | | \\___ \\\\  / ",
+    "| |  | | |_| |  _ <|  __/|  _  | |___| |_| |___) /  \\ ",
+    "|_|  |_|\\___/|_| \\_\\_|   |_| |_|_____|\\___/|____/_/\\_\\",
+];
+
+// Box width (inner content is 75 chars)
+const BOX_WIDTH: usize = 77;
+const EMPTY_LINE: &str =
+    "|                                                                           |";
+const TOP_BORDER: &str =
+    "+===========================================================================+";
+const BOTTOM_BORDER: &str =
+    "+===========================================================================+";
+const DIVIDER: &str =
+    "+---------------------------------------------------------------------------+";
+
+const BINDINGS: Bindings = Bindings {
+    title: "Main Menu",
+    keys: &[
+        KeyBinding::new(&[Key::Up], Command::Up, "Previous entry"),
+        KeyBinding::new(&[Key::Down], Command::Down, "Next entry"),
+        KeyBinding::new(&[Key::Enter], Command::Select, "Open entry"),
+        KeyBinding::new(&[Key::Esc], Command::Back, "Exit to firmware"),
+        KeyBinding::new(&[Key::Char(b'k')], Command::CycleLayout, "Keyboard layout"),
+        KeyBinding::new(&[Key::Char(b'l')], Command::ViewLog, "View log"),
+        KeyBinding::new(&[Key::Char(b'c')], Command::CrashReport, "Crash report"),
+        KeyBinding::new(&[Key::Char(b'n')], Command::NicSelfTest, "NIC self-test"),
+        KeyBinding::new(&[Key::Char(b'u')], Command::Variables, "UEFI variables"),
+        KeyBinding::new(&[Key::Char(b't')], Command::BootTimeout, "Boot timeout"),
+        KeyBinding::new(
+            &[Key::Char(b'f')],
+            Command::MakeDefault,
+            "Boot firmware by default",
+        ),
+        KeyBinding::new(&[Key::Char(b'd')], Command::Debug, "Toggle debug overlay"),
+    ],
+};
+
+pub struct MainMenu {
+    selected_index: usize,
+    menu_items: Vec<MenuItem>,
+    debug: DebugOverlay,
+    /// Dashboard snapshot; `None` until `refresh_status`
+    status: Option<SystemStatus>,
+    /// Contents of `CRASH_REPORT_PATH` (or its archived copy), read by
+    /// `refresh_status`
+    crash_report: Option<String>,
+    /// How the last download session ended, from `load_last_session`;
+    /// cleared by the first key
+    banner: Option<(String, usize)>,
+    /// Boot timeout and default entry, from `load_boot_menu`
+    boot_menu: BootMenuConfig,
+    /// Polls left until the default entry boots (`None` = not counting)
+    countdown: Option<u32>,
+}
+
+pub struct MenuItem {
+    pub label: &'static str,
+    pub description: &'static str,
+    pub icon: &'static str,
+    pub action: MenuAction,
+}
+
+impl MainMenu {
+    pub fn new(_screen: &Screen) -> Self {
+        // Entries for apps compiled out of slim builds are left off
+        let mut menu_items = Vec::new();
+        #[cfg(not(feature = "downloader-only"))]
+        menu_items.push(MenuItem {
+            label: "Distro Launcher",
+            description: "Boot into ephemeral Linux distribution",
+            icon: "[>>]",
+            action: MenuAction::DistroLauncher,
+        });
+        menu_items.push(MenuItem {
+            label: "Distro Downloader",
+            description: "Download and manage distro templates",
+            icon: "[DN]",
+            action: MenuAction::DistroDownloader,
+        });
+        #[cfg(not(feature = "downloader-only"))]
+        menu_items.push(MenuItem {
+            label: "Storage Manager",
+            description: "Manage partitions and overlay filesystems",
+            icon: "[FS]",
+            action: MenuAction::StorageManager,
+        });
+        #[cfg(not(feature = "no-installer"))]
+        menu_items.push(MenuItem {
+            label: "Installation",
+            description: "Persists the bootloader to disk",
+            icon: "[INS]",
+            action: MenuAction::SystemSettings,
+        });
+        #[cfg(not(feature = "no-installer"))]
+        menu_items.push(MenuItem {
+            label: "Factory Reset",
+            description: "Wipe a disk and set up MorpheusX step by step",
+            icon: "[RST]",
+            action: MenuAction::FactoryReset,
+        });
+        menu_items.push(MenuItem {
+            label: "Power",
+            description: "Reboot, shut down or enter firmware setup",
+            icon: "[PWR]",
+            action: MenuAction::Power,
+        });
+        menu_items.push(MenuItem {
+            label: "Exit to Firmware",
+            description: "Return to UEFI boot menu",
+            icon: "[EXT]",
+            action: MenuAction::ExitToFirmware,
+        });
+
+        Self {
+            selected_index: 0,
+            debug: DebugOverlay::new(),
+            menu_items,
+            status: None,
+            crash_report: None,
+            banner: None,
+            boot_menu: BootMenuConfig::default(),
+            countdown: None,
+        }
+    }
+
+    /// Read the boot timeout and default entry, and start counting down
+    /// if both are set. Call once, before the first `run`.
+    pub fn load_boot_menu(&mut self, bs: &BootServices, image_handle: *mut ()) {
+        self.boot_menu = unsafe { manifest_io::load_boot_menu(bs, image_handle) };
+        self.countdown = self
+            .boot_menu
+            .auto_boot()
+            .then_some(self.boot_menu.timeout_secs as u32 * POLLS_PER_SECOND);
+    }
+
+    /// Announce how the download session before this boot ended, from its
+    /// exit report or, if it died before writing one, its crash report.
+    /// Both are archived, so the banner shows only once. Call once, before
+    /// the first `refresh_status`.
+    pub fn load_last_session(&mut self, bs: &BootServices, image_handle: *mut ()) {
+        if let Some(report) = unsafe { manifest_io::take_exit_report(bs, image_handle) } {
+            self.banner = Some(dashboard::session_banner(&report));
+            return;
+        }
+        let Ok(crash) = (unsafe {
+            file_system::read_esp_file(bs, image_handle, CRASH_REPORT_PATH, MAX_CRASH_REPORT_SIZE)
+        }) else {
+            return;
+        };
+        self.banner = Some((
+            String::from("[!!] The last session crashed - [C] crash report"),
+            EFI_YELLOW,
+        ));
+        let archived = unsafe {
+            manifest_io::archive_esp_file(
+                bs,
+                image_handle,
+                CRASH_REPORT_PATH,
+                CRASH_ARCHIVE_PATH,
+                &crash,
+            )
+        };
+        if archived.is_err() {
+            morpheus_core::logger::log("Failed to archive the crash report");
+        }
+    }
+
+    /// Write the boot timeout and default entry back to the ESP, after
+    /// `run` returned `MenuAction::SaveBootMenu`.
+    pub fn save_boot_menu(&self, bs: &BootServices, image_handle: *mut ()) {
+        if unsafe { manifest_io::save_boot_menu(bs, image_handle, &self.boot_menu) }.is_err() {
+            morpheus_core::logger::log("Failed to save boot menu settings");
+        }
+    }
+
+    /// Boot timeout and default entry
+    pub fn boot_menu(&self) -> &BootMenuConfig {
+        &self.boot_menu
+    }
+
+    /// Next timeout in `BOOT_TIMEOUTS` after the current one
+    fn cycle_timeout(&mut self) {
+        let current = self.boot_menu.timeout_secs;
+        self.boot_menu.timeout_secs = BOOT_TIMEOUTS
+            .iter()
+            .copied()
+            .find(|&t| t > current)
+            .unwrap_or(0);
+    }
+
+    /// "Auto-boot: tails.iso in 4s - any key cancels" while counting
+    /// down, otherwise the configured timeout and default
+    fn boot_menu_text(&self) -> String {
+        let target = match self.boot_menu.kind {
+            DefaultKind::None => "no default",
+            DefaultKind::Firmware => "firmware",
+            DefaultKind::Iso | DefaultKind::Installed => self.boot_menu.name_str(),
+        };
+        let target = &target[..target.len().min(24)];
+        match self.countdown {
+            Some(polls) => format!(
+                "Auto-boot: {} in {}s - any key cancels",
+                target,
+                polls.div_ceil(POLLS_PER_SECOND)
+            ),
+            None if self.boot_menu.timeout_secs == 0 => String::from("Auto-boot: off"),
+            None => format!(
+                "Auto-boot: {} after {}s",
+                target, self.boot_menu.timeout_secs
+            ),
+        }
+    }
+
+    /// Re-probe NIC, disks, ESP and stored ISOs for the dashboard.
+    ///
+    /// Call before `run` and again after returning from a submenu that may
+    /// have changed any of them.
+    pub fn refresh_status(&mut self, bs: &BootServices, image_handle: *mut ()) {
+        self.status = Some(SystemStatus::gather(bs, image_handle));
+        self.crash_report = [CRASH_REPORT_PATH, CRASH_ARCHIVE_PATH]
+            .into_iter()
+            .find_map(|path| {
+                unsafe {
+                    file_system::read_esp_file(bs, image_handle, path, MAX_CRASH_REPORT_SIZE)
+                }
+                .ok()
+            })
+            .map(|data| String::from_utf8_lossy(&data).into_owned());
+    }
+
+    fn view_log(&self, screen: &mut Screen, keyboard: &mut Keyboard) {
+        let mut text = String::new();
+        for line in morpheus_core::logger::get_logs_iter() {
+            text.push_str(line);
+            text.push('\n');
+        }
+        if text.is_empty() {
+            text.push_str("Log is empty.");
+        }
+        textview::show(screen, keyboard, "Log", &text);
+    }
+
+    fn view_crash_report(&self, screen: &mut Screen, keyboard: &mut Keyboard) {
+        match &self.crash_report {
+            Some(report) => textview::show(screen, keyboard, "Crash report", report),
+            None => textview::show(
+                screen,
+                keyboard,
+                "Crash report",
+                &format!(
+                    "No crash report on the ESP.\n\nLooked for {}",
+                    CRASH_REPORT_PATH
+                ),
+            ),
+        }
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected_index < self.menu_items.len() - 1 {
+            self.selected_index += 1;
+        }
+    }
+
+    pub fn select_prev(&mut self) {
+        if self.selected_index > 0 {
+            self.selected_index -= 1;
+        }
+    }
+
+    pub fn render(&mut self, screen: &mut Screen) {
+        let mut dashboard_rows = self
+            .status
+            .as_ref()
+            .map_or_else(Vec::new, SystemStatus::rows);
+        if !dashboard_rows.is_empty() {
+            dashboard_rows.push(dashboard::Row {
+                label: "Boot",
+                value: self.boot_menu_text(),
+                color: if self.countdown.is_some() {
+                    EFI_LIGHTGREEN
+                } else {
+                    EFI_GREEN
+                },
+            });
+        }
+
+        // Calculate total menu height
+        // Top border (1) + empty (1) + header art (5) + empty (1) + divider (1) +
+        // empty (1) + instructions (1) + empty (1) + divider (1) +
+        // menu items (5 * 2 = 10) + empty lines between (4) +
+        // divider (1) + dashboard (7 or 1) + divider (1) + status (1) + bottom border (1) = ~34
+        let total_height = 1
+            + 1
+            + HEADER_ART.len()
+            + 1
+            + 1
+            + 1
+            + 1
+            + 1
+            + 1
+            + (self.menu_items.len() * 2 - 1)
+            + 1
+            + dashboard_rows.len().max(1)
+            + 1
+            + 1
+            + 1;
+
+        // Center horizontally and vertically
+        let x = screen.center_x(BOX_WIDTH);
+        let y = screen.center_y(total_height);
+
+        let mut current_y = y;
+
+        // Draw top border
+        screen.put_str_at(x, current_y, TOP_BORDER, EFI_GREEN, EFI_BLACK);
+        current_y += 1;
+
+        // Empty line after top border
+        screen.put_str_at(x, current_y, EMPTY_LINE, EFI_GREEN, EFI_BLACK);
+        current_y += 1;
+
+        // Draw header ASCII art centered within border
+        for line in HEADER_ART.iter() {
+            // Draw left border
+            screen.put_str_at(x, current_y, "|", EFI_GREEN, EFI_BLACK);
+
+            // Center the art within the box (75 inner width)
+            let padding = (75 - line.len()) / 2;
+            let art_x = x + 1 + padding;
+            screen.put_str_at(art_x, current_y, line, EFI_DARKGREEN, EFI_BLACK);
+
+            // Draw right border
+            screen.put_str_at(x + 76, current_y, "|", EFI_GREEN, EFI_BLACK);
+            current_y += 1;
+        }
+
+        // Empty line after header
+        screen.put_str_at(x, current_y, EMPTY_LINE, EFI_GREEN, EFI_BLACK);
+        current_y += 1;
+
+        // Divider after header
+        screen.put_str_at(x, current_y, DIVIDER, EFI_GREEN, EFI_BLACK);
+        current_y += 1;
+
+        // Empty line before instructions
+        screen.put_str_at(x, current_y, EMPTY_LINE, EFI_GREEN, EFI_BLACK);
+        current_y += 1;
+
+        // Instructions line (blanked first; its length follows the layout name)
+        screen.put_str_at(x, current_y, EMPTY_LINE, EFI_GREEN, EFI_BLACK);
+        let instr = alloc::format!(
+            "[UP/DOWN] Navigate  |  [ENTER] Select  |  [K] Keys: {}  |  [?] Help",
+            input::layout().name()
+        );
+        let instr_padding = (75 - instr.len()) / 2;
+        screen.put_str_at(
+            x + 1 + instr_padding,
+            current_y,
+            &instr,
+            EFI_DARKGREEN,
+            EFI_BLACK,
+        );
+        screen.put_str_at(x + 76, current_y, "|", EFI_GREEN, EFI_BLACK);
+        current_y += 1;
+
+        // Empty line after instructions, or the last session's banner
+        screen.put_str_at(x, current_y, EMPTY_LINE, EFI_GREEN, EFI_BLACK);
+        if let Some((text, color)) = &self.banner {
+            let text = &text[..text.len().min(73)];
+            let padding = (75 - text.len()) / 2;
+            screen.put_str_at(x + 1 + padding, current_y, text, *color, EFI_BLACK);
+        }
+        current_y += 1;
+
+        // Divider before menu
+        screen.put_str_at(x, current_y, DIVIDER, EFI_GREEN, EFI_BLACK);
+        current_y += 1;
+
+        // Menu items
+        for (i, item) in self.menu_items.iter().enumerate() {
+            // Draw item line with borders
+            screen.put_str_at(x, current_y, "|", EFI_GREEN, EFI_BLACK);
+
+            // Build the menu item string: ">> [ICN] Label" or "   [ICN] Label"
+            let item_text = if i == self.selected_index {
+                alloc::format!(">> {} {}", item.icon, item.label)
+            } else {
+                alloc::format!("   {} {}", item.icon, item.label)
+            };
+
+            // Center the item text within the box (75 inner width)
+            let item_padding = (75 - item_text.len()) / 2;
+            let item_x = x + 1 + item_padding;
+
+            if i == self.selected_index {
+                screen.put_str_at(item_x, current_y, &item_text, EFI_LIGHTGREEN, EFI_BLACK);
+            } else {
+                screen.put_str_at(item_x, current_y, &item_text, EFI_GREEN, EFI_BLACK);
+            }
+
+            screen.put_str_at(x + 76, current_y, "|", EFI_GREEN, EFI_BLACK);
+            current_y += 1;
+
+            // Empty line between menu items (except after last)
+            if i < self.menu_items.len() - 1 {
+                screen.put_str_at(x, current_y, EMPTY_LINE, EFI_GREEN, EFI_BLACK);
+                current_y += 1;
+            }
+        }
+
+        // Divider after menu
+        screen.put_str_at(x, current_y, DIVIDER, EFI_GREEN, EFI_BLACK);
+        current_y += 1;
+
+        // Dashboard: label column, value column
+        if dashboard_rows.is_empty() {
+            screen.put_str_at(x, current_y, EMPTY_LINE, EFI_GREEN, EFI_BLACK);
+            screen.put_str_at(
+                x + 3,
+                current_y,
+                "System status not scanned",
+                EFI_DARKGREEN,
+                EFI_BLACK,
+            );
+            current_y += 1;
+        }
+        for row in dashboard_rows.iter() {
+            screen.put_str_at(x, current_y, EMPTY_LINE, EFI_GREEN, EFI_BLACK);
+            screen.put_str_at(x + 3, current_y, row.label, EFI_DARKGREEN, EFI_BLACK);
+            // Values longer than the box are cut at the border
+            let value = &row.value[..row.value.len().min(62)];
+            screen.put_str_at(x + 13, current_y, value, row.color, EFI_BLACK);
+            current_y += 1;
+        }
+
+        // Divider before status
+        screen.put_str_at(x, current_y, DIVIDER, EFI_GREEN, EFI_BLACK);
+        current_y += 1;
+
+        // Status bar
+        let (verdict, hint, verdict_color) = match &self.status {
+            Some(status) => status.readiness(),
+            None => ("UNKNOWN", "System: Not scanned", EFI_GREEN),
+        };
+        screen.put_str_at(x, current_y, EMPTY_LINE, EFI_GREEN, EFI_BLACK);
+        screen.put_str_at(
+            x + 3,
+            current_y,
+            &alloc::format!("Status: {}", verdict),
+            verdict_color,
+            EFI_BLACK,
+        );
+        screen.put_str_at(x + 20, current_y, "|", EFI_GREEN, EFI_BLACK);
+        screen.put_str_at(x + 22, current_y, hint, EFI_GREEN, EFI_BLACK);
+        current_y += 1;
+
+        // Bottom border
+        screen.put_str_at(x, current_y, BOTTOM_BORDER, EFI_GREEN, EFI_BLACK);
+    }
+
+    pub fn handle_input(&mut self, key: &InputKey) -> MenuAction {
+        match BINDINGS.lookup(key) {
+            Some(Command::Up) => self.select_prev(),
+            Some(Command::Down) => self.select_next(),
+            Some(Command::Select) => {
+                return self
+                    .menu_items
+                    .get(self.selected_index)
+                    .map_or(MenuAction::Navigate, |item| item.action);
+            }
+            Some(Command::Back) => return MenuAction::ExitToFirmware,
+            // Needs boot services; the caller runs it and comes back here
+            Some(Command::NicSelfTest) => return MenuAction::NicSelfTest,
+            Some(Command::Variables) => return MenuAction::Variables,
+            Some(Command::BootTimeout) => {
+                self.cycle_timeout();
+                return MenuAction::SaveBootMenu;
+            }
+            Some(Command::MakeDefault) => {
+                self.boot_menu.set_default(DefaultKind::Firmware, "");
+                return MenuAction::SaveBootMenu;
+            }
+            _ => {}
+        }
+        MenuAction::Navigate
+    }
+
+    /// One poll without input: count down, redraw when the seconds shown
+    /// change, and return the default's action once time is up.
+    fn tick_countdown(&mut self, screen: &mut Screen) -> Option<MenuAction> {
+        let polls = self.countdown.as_mut()?;
+        *polls = polls.saturating_sub(1);
+        if *polls == 0 {
+            self.countdown = None;
+            return Some(match self.boot_menu.kind {
+                DefaultKind::Firmware => MenuAction::ExitToFirmware,
+                _ => MenuAction::BootDefault,
+            });
+        }
+        if (*polls).is_multiple_of(POLLS_PER_SECOND) {
+            self.render(screen);
+        }
+        None
+    }
+
+    pub fn run(&mut self, screen: &mut Screen, keyboard: &mut Keyboard) -> MenuAction {
+        // Initial render
+        screen.clear();
+        self.render(screen);
+        self.debug.render(screen);
+
+        loop {
+            // Input via the keymap hook: screensaver and help overlay (~60 FPS)
+            let key = match keymap::poll(screen, keyboard, &BINDINGS) {
+                Some(Event::Key(key)) => key,
+                Some(Event::Redraw) => {
+                    screen.clear();
+                    self.render(screen);
+                    self.debug.render(screen);
+                    continue;
+                }
+                None => {
+                    if let Some(action) = self.tick_countdown(screen) {
+                        return action;
+                    }
+                    // Always render debug overlay on top
+                    self.debug.render(screen);
+                    continue;
+                }
+            };
+
+            // Any key stops the countdown and dismisses the banner, and is
+            // handled as usual
+            let banner = self.banner.take().is_some();
+            if self.countdown.take().is_some() || banner {
+                self.render(screen);
+            }
+
+            match BINDINGS.lookup(&key) {
+                Some(Command::Debug) => {
+                    self.debug.toggle();
+                    screen.clear();
+                    self.render(screen);
+                    self.debug.render(screen);
+                    continue;
+                }
+                Some(Command::ViewLog) => {
+                    self.view_log(screen, keyboard);
+                    screen.clear();
+                    self.render(screen);
+                    self.debug.render(screen);
+                    continue;
+                }
+                Some(Command::CrashReport) => {
+                    self.view_crash_report(screen, keyboard);
+                    screen.clear();
+                    self.render(screen);
+                    self.debug.render(screen);
+                    continue;
+                }
+                // Keyboard layout cycles in place
+                Some(Command::CycleLayout) => {
+                    input::set_layout(input::layout().next());
+                    self.render(screen);
+                    self.debug.render(screen);
+                    continue;
+                }
+                _ => {}
+            }
+
+            let action = self.handle_input(&key);
+            if !matches!(action, MenuAction::Navigate) {
+                return action;
+            }
+
+            // Re-render UI after navigation (without clearing)
+            self.render(screen);
+            self.debug.render(screen);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MenuAction {
+    Navigate,
+    DistroLauncher,
+    DistroDownloader,
+    StorageManager,
+    SystemSettings,
+    FactoryReset,
+    AdminFunctions,
+    NicSelfTest,
+    /// UEFI variable browser; run with `var_browser::run`
+    Variables,
+    /// Reboot, shutdown and firmware entries; run with `power::run`
+    Power,
+    ExitToFirmware,
+    EnterBaremetal,
+    /// Boot timeout or default changed; save with `MainMenu::save_boot_menu`
+    SaveBootMenu,
+    /// The countdown ran out; boot the stored ISO or installed OS named in
+    /// `MainMenu::boot_menu` (see `DistroLauncher::boot_default`)
+    BootDefault,
+}
@@ -0,0 +1,231 @@
+//! Power and firmware entries of the main menu.
+//!
+//! Reboot and shut down go straight to `ResetSystem`. "Firmware setup"
+//! sets the boot-to-setup bit in OsIndications before rebooting, and is
+//! only offered when OsIndicationsSupported has it. "Firmware boot menu"
+//! points BootNext at the firmware's own boot manager option; firmware
+//! without one gets the old Exit to Firmware, which usually lands there.
+
+use super::MenuAction;
+use crate::tui::input::Keyboard;
+use crate::tui::keymap::{self, Bindings, Command, Key, KeyBinding};
+use crate::tui::renderer::{
+    Screen, EFI_BLACK, EFI_DARKGRAY, EFI_DARKGREEN, EFI_GREEN, EFI_LIGHTGREEN, EFI_LIGHTRED,
+    EFI_YELLOW,
+};
+use crate::tui::screensaver::Event;
+use crate::uefi::runtime::{self, ResetType, RuntimeServices};
+use crate::BootServices;
+use alloc::format;
+use alloc::string::String;
+
+const BINDINGS: Bindings = Bindings {
+    title: "Power",
+    keys: &[
+        KeyBinding::new(&[Key::Up], Command::Up, "Previous entry"),
+        KeyBinding::new(&[Key::Down], Command::Down, "Next entry"),
+        KeyBinding::new(&[Key::Enter], Command::Select, "Choose entry"),
+        KeyBinding::new(
+            &[Key::Esc, Key::Backspace],
+            Command::Back,
+            "Back to main menu",
+        ),
+    ],
+};
+
+const CONFIRM_BINDINGS: Bindings = Bindings {
+    title: "Confirm",
+    keys: &[
+        KeyBinding::new(&[Key::Char(b'y')], Command::Yes, "Confirm"),
+        KeyBinding::new(&[Key::Char(b'n'), Key::Esc], Command::No, "Cancel"),
+    ],
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Entry {
+    Reboot,
+    Shutdown,
+    FirmwareSetup,
+    FirmwareBootMenu,
+}
+
+const ENTRIES: [Entry; 4] = [
+    Entry::Reboot,
+    Entry::Shutdown,
+    Entry::FirmwareSetup,
+    Entry::FirmwareBootMenu,
+];
+
+/// Row of the status line and the confirm prompt
+const STATUS_ROW: usize = 4 + ENTRIES.len() * 2 + 2;
+
+impl Entry {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Reboot => "Reboot",
+            Self::Shutdown => "Shut down",
+            Self::FirmwareSetup => "Firmware setup",
+            Self::FirmwareBootMenu => "Firmware boot menu",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            Self::Reboot => "Restart the machine",
+            Self::Shutdown => "Power the machine off",
+            Self::FirmwareSetup => "Reboot into the UEFI setup screen",
+            Self::FirmwareBootMenu => "Reboot into the firmware's boot menu",
+        }
+    }
+}
+
+/// Show the power menu. Returns `MenuAction::ExitToFirmware` when the
+/// firmware boot menu can only be reached that way, otherwise
+/// `MenuAction::Navigate` once the user backs out.
+pub fn run(
+    bs: &BootServices,
+    image_handle: *mut (),
+    screen: &mut Screen,
+    keyboard: &mut Keyboard,
+) -> MenuAction {
+    let Some(rt) = (unsafe { runtime::runtime_services(bs, image_handle) }) else {
+        morpheus_core::logger::log("Power menu: runtime services not found");
+        return MenuAction::Navigate;
+    };
+    let setup_supported = rt.supports_firmware_setup();
+    let mut selected = 0;
+    let mut status = String::new();
+
+    screen.clear();
+    render(screen, selected, setup_supported, &status);
+    loop {
+        let key = match keymap::poll(screen, keyboard, &BINDINGS) {
+            Some(Event::Key(key)) => key,
+            Some(Event::Redraw) => {
+                screen.clear();
+                render(screen, selected, setup_supported, &status);
+                continue;
+            }
+            None => continue,
+        };
+
+        match BINDINGS.lookup(&key) {
+            Some(Command::Up) => selected = selected.saturating_sub(1),
+            Some(Command::Down) => selected = (selected + 1).min(ENTRIES.len() - 1),
+            Some(Command::Back) => return MenuAction::Navigate,
+            Some(Command::Select) => {
+                let entry = ENTRIES[selected];
+                if entry == Entry::FirmwareSetup && !setup_supported {
+                    status = String::from("This firmware cannot be asked to open its setup");
+                } else if confirm(screen, keyboard, selected, setup_supported) {
+                    match choose(rt, entry) {
+                        Ok(()) => return MenuAction::ExitToFirmware,
+                        Err(message) => status = message,
+                    }
+                }
+                screen.clear();
+            }
+            _ => {}
+        }
+        render(screen, selected, setup_supported, &status);
+    }
+}
+
+/// Act on `entry`. Only returns for the boot menu fallback (`Ok`) or
+/// when a variable could not be written (`Err`).
+fn choose(rt: &RuntimeServices, entry: Entry) -> Result<(), String> {
+    match entry {
+        Entry::Reboot => rt.reset(ResetType::Cold),
+        Entry::Shutdown => rt.reset(ResetType::Shutdown),
+        Entry::FirmwareSetup => {
+            rt.request_firmware_setup()
+                .map_err(|status| format!("Could not set OsIndications ({:#x})", status))?;
+            rt.reset(ResetType::Cold)
+        }
+        Entry::FirmwareBootMenu => {
+            let Some(option) = rt.firmware_boot_menu_option() else {
+                morpheus_core::logger::log("No firmware boot menu option; exiting to firmware");
+                return Ok(());
+            };
+            rt.set_boot_next(option)
+                .map_err(|status| format!("Could not set BootNext ({:#x})", status))?;
+            rt.reset(ResetType::Cold)
+        }
+    }
+}
+
+/// Ask before resetting; any unsaved state in other screens is lost
+fn confirm(
+    screen: &mut Screen,
+    keyboard: &mut Keyboard,
+    selected: usize,
+    setup_supported: bool,
+) -> bool {
+    let prompt = format!("{}? [Y] Yes  [N] No", ENTRIES[selected].label());
+    screen.put_str_at(
+        5,
+        STATUS_ROW,
+        &format!("{:<60}", prompt),
+        EFI_YELLOW,
+        EFI_BLACK,
+    );
+    loop {
+        match keymap::poll(screen, keyboard, &CONFIRM_BINDINGS) {
+            Some(Event::Key(key)) => match CONFIRM_BINDINGS.lookup(&key) {
+                Some(Command::Yes) => return true,
+                Some(Command::No) => return false,
+                _ => {}
+            },
+            Some(Event::Redraw) => {
+                screen.clear();
+                render(screen, selected, setup_supported, "");
+                screen.put_str_at(5, STATUS_ROW, &prompt, EFI_YELLOW, EFI_BLACK);
+            }
+            None => {}
+        }
+    }
+}
+
+fn render(screen: &mut Screen, selected: usize, setup_supported: bool, status: &str) {
+    screen.put_str_at(5, 1, "=== POWER ===", EFI_LIGHTGREEN, EFI_BLACK);
+
+    let mut y = 4;
+    for (i, entry) in ENTRIES.iter().enumerate() {
+        let available = *entry != Entry::FirmwareSetup || setup_supported;
+        let marker = if i == selected { ">>" } else { "  " };
+        let color = match (i == selected, available) {
+            (_, false) => EFI_DARKGRAY,
+            (true, true) => EFI_LIGHTGREEN,
+            (false, true) => EFI_GREEN,
+        };
+        screen.put_str_at(
+            5,
+            y,
+            &format!("{} {:<20}", marker, entry.label()),
+            color,
+            EFI_BLACK,
+        );
+        let description = if available {
+            entry.description()
+        } else {
+            "Not supported by this firmware"
+        };
+        screen.put_str_at(30, y, description, EFI_DARKGREEN, EFI_BLACK);
+        y += 2;
+    }
+
+    screen.put_str_at(
+        5,
+        STATUS_ROW,
+        &format!("{:<60}", status),
+        EFI_LIGHTRED,
+        EFI_BLACK,
+    );
+    screen.put_str_at(
+        5,
+        STATUS_ROW + 2,
+        "[UP/DOWN] Navigate  |  [ENTER] Select  |  [ESC] Back",
+        EFI_DARKGREEN,
+        EFI_BLACK,
+    );
+}
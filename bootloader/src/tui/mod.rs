@@ -1,13 +1,28 @@
 pub mod boot_sequence;
 pub mod debug;
+#[cfg(not(feature = "netboot-only"))]
 pub mod distro_downloader;
+#[cfg(not(feature = "downloader-only"))]
 pub mod distro_launcher;
+#[cfg(not(feature = "no-installer"))]
+pub mod factory_reset;
 pub mod input;
+#[cfg(not(feature = "no-installer"))]
 pub mod installer_menu;
+#[cfg(not(feature = "downloader-only"))]
 pub mod iso_manager;
+pub mod keymap;
 pub mod logo;
+#[cfg(not(feature = "netboot-only"))]
 pub mod main_menu;
-pub mod rain;
+#[cfg(not(feature = "netboot-only"))]
+pub mod nic_selftest;
+#[cfg(not(feature = "downloader-only"))]
+pub mod partition_wizard;
 pub mod renderer;
+pub mod screensaver;
+#[cfg(not(feature = "downloader-only"))]
 pub mod storage_manager;
+#[cfg(not(feature = "netboot-only"))]
+pub mod var_browser;
 pub mod widgets;
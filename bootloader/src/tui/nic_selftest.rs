@@ -0,0 +1,115 @@
+//! NIC loopback self-test.
+//!
+//! Brings up the network driver the same way a download does, checks it
+//! against the reset contract, runs MAC and then PHY loopback, and resets
+//! the NIC again afterwards. Tells a
+//! broken NIC or driver apart from a broken cable, switch or mirror
+//! before anyone blames the network.
+
+use crate::boot::network_boot::{NIC_TYPE_INTEL, NIC_TYPE_VIRTIO};
+use crate::tui::distro_downloader::commit::pci::probe_nic_with_debug;
+use crate::tui::distro_downloader::commit::resources::{allocate_dma_region, DMA_SIZE};
+use crate::tui::distro_downloader::commit::uefi::calibrate_tsc;
+use crate::tui::input::Keyboard;
+use crate::tui::renderer::{Screen, EFI_BLACK, EFI_DARKGRAY, EFI_LIGHTGREEN};
+use crate::tui::widgets::textview;
+use crate::BootServices;
+use alloc::format;
+use alloc::string::String;
+use morpheus_network::driver::conformance::{check_network, ConformanceError};
+use morpheus_network::driver::intel::{E1000eConfig, E1000eDriver};
+use morpheus_network::driver::selftest::{run_loopback, SelfTestError, TEST_FRAMES};
+use morpheus_network::driver::traits::{LoopbackMode, NetworkDriver};
+use morpheus_network::pci::config::PciAddr;
+
+/// Run the self-test and show the results.
+pub fn run(bs: &BootServices, screen: &mut Screen, keyboard: &mut Keyboard) {
+    screen.clear();
+    screen.put_str_at(5, 1, "=== NIC SELF-TEST ===", EFI_LIGHTGREEN, EFI_BLACK);
+    let mut log_y = 3;
+
+    let report = unsafe { test_nic(bs, screen, &mut log_y) };
+    textview::show(screen, keyboard, "NIC self-test", &report);
+}
+
+/// Probe the NIC, run both loopback modes and return the report text.
+unsafe fn test_nic(bs: &BootServices, screen: &mut Screen, log_y: &mut usize) -> String {
+    let nic = probe_nic_with_debug(screen, log_y);
+    match nic.nic_type {
+        NIC_TYPE_INTEL => {}
+        NIC_TYPE_VIRTIO => {
+            return String::from(
+                "VirtIO-net found.\n\nVirtIO has no MAC or PHY loopback; nothing to test.",
+            )
+        }
+        _ => return String::from("No supported network controller found."),
+    }
+
+    let Ok(dma) = allocate_dma_region(bs, screen, log_y) else {
+        return String::from("Could not allocate DMA memory for the NIC.");
+    };
+    let tsc_freq = calibrate_tsc(bs).frequency;
+
+    screen.put_str_at(7, *log_y, "Running loopback tests...", EFI_DARKGRAY, EFI_BLACK);
+    *log_y += 1;
+
+    let mut report = format!("Intel NIC at MMIO {:#x}\n\n", nic.mmio_base);
+    // Pre-EBS memory is identity mapped, so bus address == CPU address
+    let pci_addr = PciAddr::new(nic.pci_bus, nic.pci_device, nic.pci_function);
+    let config = || E1000eConfig::new(dma as *mut u8, dma, tsc_freq).with_pci_addr(pci_addr);
+
+    match check_network(|| E1000eDriver::new(nic.mmio_base, config()), tsc_freq) {
+        Ok(result) if result.passed() => report.push_str("Reset contract: PASS\n"),
+        Ok(result) => {
+            report.push_str("Reset contract: FAIL\n");
+            for violation in &result.violations {
+                report.push_str(&format!("  - {}\n", violation.describe()));
+            }
+        }
+        Err(ConformanceError::FirstInit(e) | ConformanceError::Reinit(e)) => {
+            report.push_str(&format!("Reset contract: init failed: {:?}\n", e))
+        }
+    }
+    match E1000eDriver::new(nic.mmio_base, config()) {
+        Ok(mut driver) => {
+            let mut all_passed = true;
+            for mode in [LoopbackMode::Mac, LoopbackMode::Phy] {
+                let line = match run_loopback(&mut driver, mode, tsc_freq) {
+                    Ok(result) => {
+                        all_passed &= result.passed();
+                        format!(
+                            "{} loopback: {}  ({}/{} frames back intact, {} corrupted, {} not sent)",
+                            mode.name(),
+                            if result.passed() { "PASS" } else { "FAIL" },
+                            result.received,
+                            TEST_FRAMES,
+                            result.corrupted,
+                            result.tx_failed
+                        )
+                    }
+                    Err(SelfTestError::Unsupported) => {
+                        all_passed = false;
+                        format!("{} loopback: not supported", mode.name())
+                    }
+                };
+                report.push_str(&line);
+                report.push('\n');
+            }
+            report.push_str(if all_passed {
+                "\nThe NIC and driver work. Download trouble is on the network side."
+            } else {
+                "\nThe NIC failed loopback. Try another NIC or report the hardware."
+            });
+        }
+        Err(e) => report.push_str(&format!("Driver init failed: {:?}", e)),
+    }
+
+    // Loopback leaves forced link settings behind: reset the NIC again and
+    // stop its DMA before the memory goes back to the firmware.
+    if let Ok(mut driver) = E1000eDriver::new(nic.mmio_base, config()) {
+        driver.quiesce();
+    }
+    (bs.free_pages)(dma, DMA_SIZE / 4096);
+
+    report
+}
@@ -127,7 +127,9 @@ impl PartitionWizard {
             }
         };
         
-        if free_regions.is_empty() {
+        let region = free_regions.iter().find(|r| r.is_some()).and_then(|r| *r);
+
+        if region.is_none() {
             screen.put_str_at(x, current_y, "|", EFI_GREEN, EFI_BLACK);
             let msg = "No free space on disk";
             let padding = (75 - msg.len()) / 2;
@@ -147,7 +149,7 @@ impl PartitionWizard {
         screen.put_str_at(x, current_y, EMPTY_LINE, EFI_GREEN, EFI_BLACK);
         current_y += 1;
         
-        let region = &free_regions[0];
+        let region = region.unwrap();
         let size_mb = region.size_lba() * 512 / 1024 / 1024;
         
         screen.put_str_at(x, current_y, "|", EFI_GREEN, EFI_BLACK);
@@ -242,7 +244,7 @@ impl PartitionWizard {
             }
         };
         
-        match gpt_ops::create_partition(adapter, region.start_lba, region.end_lba, partition_type) {
+        match gpt_ops::create_partition(adapter, partition_type, region.start_lba, region.end_lba) {
             Ok(()) => {
                 screen.clear();
                 let current_y = screen.center_y(10);
@@ -1,6 +1,7 @@
 use crate::SimpleTextOutputProtocol;
 use alloc::vec;
 use alloc::vec::Vec;
+use spin::Mutex;
 
 // EFI text colors
 pub const EFI_BLACK: usize = 0x00;
@@ -21,42 +22,201 @@ pub const EFI_LIGHTMAGENTA: usize = 0x0D;
 pub const EFI_YELLOW: usize = 0x0E;
 pub const EFI_WHITE: usize = 0x0F;
 
-fn str_to_ucs2(s: &str, buf: &mut [u16]) {
-    let mut i = 0;
-    for ch in s.chars() {
-        if i >= buf.len() - 1 {
-            break;
+// ═══════════════════════════════════════════════════════════════════════════
+// SHADOW BUFFER
+// ═══════════════════════════════════════════════════════════════════════════
+//
+// Every draw lands in a cell buffer first and only cells that differ from
+// what the console already shows are sent to firmware, as runs of one
+// attribute. `clear()` doesn't touch the console: it starts a frame that is
+// diffed against the screen as a whole when presented, so clear + redraw
+// costs only the cells that actually changed and never flashes a blank
+// screen. A frame is presented by `present()`, which `Keyboard` calls
+// before every read, so screens don't have to.
+//
+// The buffer is global because there is one console and `Keyboard` has no
+// `Screen` to flush.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Cell {
+    ch: u16,
+    attr: u8,
+}
+
+impl Cell {
+    /// Placeholder for "unknown": differs from anything drawn
+    const UNKNOWN: Cell = Cell {
+        ch: 0xFFFF,
+        attr: 0xFF,
+    };
+
+    /// Spaces only show their background
+    fn looks_like(self, other: Cell) -> bool {
+        if self.ch == b' ' as u16 && other.ch == b' ' as u16 {
+            self.attr & 0xF0 == other.attr & 0xF0
+        } else {
+            self == other
         }
-        buf[i] = ch as u16;
-        i += 1;
     }
-    buf[i] = 0;
 }
 
-pub struct Screen {
+/// Changed cells of one row sharing an attribute.
+#[derive(Debug, PartialEq, Eq)]
+struct Run {
+    x: usize,
+    len: usize,
+    attr: u8,
+}
+
+/// Runs in `frame` that differ from `shown` (one row each).
+fn changed_runs(shown: &[Cell], frame: &[Cell]) -> Vec<Run> {
+    let mut runs: Vec<Run> = Vec::new();
+    for (x, (&old, &new)) in shown.iter().zip(frame.iter()).enumerate() {
+        if old.looks_like(new) {
+            continue;
+        }
+        match runs.last_mut() {
+            Some(run) if run.x + run.len == x && run.attr == new.attr => run.len += 1,
+            _ => runs.push(Run {
+                x,
+                len: 1,
+                attr: new.attr,
+            }),
+        }
+    }
+    runs
+}
+
+struct Console {
     con_out: *mut SimpleTextOutputProtocol,
     width: usize,
     height: usize,
-    pub mask: Vec<Vec<bool>>,
+    /// What the console shows
+    shown: Vec<Cell>,
+    /// What has been drawn
+    frame: Vec<Cell>,
+    /// A `clear()` started a frame that hasn't been presented
+    batching: bool,
+    cursor: (usize, usize),
+    attr: u8,
+    /// Attribute last sent to firmware
+    console_attr: Option<u8>,
+}
+
+// The console pointer is only used from the boot CPU before ExitBootServices.
+unsafe impl Send for Console {}
+
+static CONSOLE: Mutex<Option<Console>> = Mutex::new(None);
+
+impl Console {
+    fn write(&mut self, ch: char) {
+        let (x, y) = self.cursor;
+        match ch {
+            '\r' => self.cursor.0 = 0,
+            '\n' => self.cursor.1 += 1,
+            _ => {
+                if x < self.width && y < self.height {
+                    self.frame[y * self.width + x] = Cell {
+                        ch: ch as u16,
+                        attr: self.attr,
+                    };
+                }
+                self.cursor.0 += 1;
+            }
+        }
+    }
+
+    /// Output whatever `text` just drew, unless a frame is being batched.
+    fn write_str(&mut self, text: &str) {
+        let start = self.cursor;
+        for ch in text.chars() {
+            self.write(ch);
+        }
+        if !self.batching {
+            for y in start.1..=self.cursor.1.min(self.height.saturating_sub(1)) {
+                self.emit_row(y);
+            }
+        }
+    }
+
+    fn emit_row(&mut self, y: usize) {
+        let row = y * self.width..(y + 1) * self.width;
+        let runs = changed_runs(&self.shown[row.clone()], &self.frame[row.clone()]);
+        let mut buffer = [0u16; 256];
+
+        for run in runs {
+            // Writing the bottom-right cell scrolls some consoles
+            let mut len = run.len;
+            if y + 1 == self.height && run.x + len == self.width {
+                len -= 1;
+            }
+            let start = y * self.width + run.x;
+
+            for chunk_start in (0..len).step_by(buffer.len() - 1) {
+                let chunk_len = (len - chunk_start).min(buffer.len() - 1);
+                let cells = &self.frame[start + chunk_start..start + chunk_start + chunk_len];
+                for (slot, cell) in buffer.iter_mut().zip(cells) {
+                    *slot = cell.ch;
+                }
+                buffer[chunk_len] = 0;
+
+                unsafe {
+                    let con_out = &mut *self.con_out;
+                    (con_out.set_cursor_position)(con_out, run.x + chunk_start, y);
+                    if self.console_attr != Some(run.attr) {
+                        (con_out.set_attribute)(con_out, run.attr as usize);
+                        self.console_attr = Some(run.attr);
+                    }
+                    (con_out.output_string)(con_out, buffer.as_ptr());
+                }
+            }
+            self.shown[start..start + len].copy_from_slice(&self.frame[start..start + len]);
+        }
+    }
+
+    fn present(&mut self) {
+        if !self.batching {
+            return;
+        }
+        self.batching = false;
+        for y in 0..self.height {
+            self.emit_row(y);
+        }
+    }
+}
+
+fn with_console<R>(f: impl FnOnce(&mut Console) -> R) -> Option<R> {
+    CONSOLE.lock().as_mut().map(f)
+}
+
+/// Push the pending frame, if any, to the console.
+pub fn present() {
+    with_console(Console::present);
+}
+
+pub struct Screen {
+    width: usize,
+    height: usize,
 }
 
 impl Screen {
     pub fn new(con_out: *mut SimpleTextOutputProtocol) -> Self {
         let (width, height) = Self::get_screen_size(con_out);
 
-        // Create dynamic mask based on actual screen size
-        let mut mask = Vec::new();
-        for _ in 0..height {
-            let row = vec![false; width];
-            mask.push(row);
-        }
-
-        Self {
+        // Console contents are unknown until drawn
+        *CONSOLE.lock() = Some(Console {
             con_out,
             width,
             height,
-            mask,
-        }
+            shown: vec![Cell::UNKNOWN; width * height],
+            frame: vec![Cell::UNKNOWN; width * height],
+            batching: false,
+            cursor: (0, 0),
+            attr: (EFI_LIGHTGRAY | (EFI_BLACK << 4)) as u8,
+            console_attr: None,
+        });
+
+        Self { width, height }
     }
 
     fn get_screen_size(con_out: *mut SimpleTextOutputProtocol) -> (usize, usize) {
@@ -125,49 +285,41 @@ impl Screen {
         (self.center_x(content_width), self.center_y(content_height))
     }
 
+    /// Start a new frame: everything not redrawn before the next
+    /// `present()` is blanked then.
     pub fn clear(&mut self) {
-        unsafe {
-            let con_out = &mut *self.con_out;
-            (con_out.clear_screen)(con_out);
-        }
-        // Reset mask when clearing
-        for row in &mut self.mask {
-            for cell in row {
-                *cell = false;
-            }
-        }
+        with_console(|console| {
+            let blank = Cell {
+                ch: b' ' as u16,
+                attr: console.attr,
+            };
+            console.frame.fill(blank);
+            console.cursor = (0, 0);
+            console.batching = true;
+        });
+    }
+
+    /// Push the pending frame now instead of at the next key read.
+    pub fn present(&mut self) {
+        present();
     }
 
     pub fn set_color(&mut self, fg: usize, bg: usize) {
-        let attr = fg | (bg << 4);
-        unsafe {
-            let con_out = &mut *self.con_out;
-            (con_out.set_attribute)(con_out, attr);
-        }
+        let attr = (fg | (bg << 4)) as u8;
+        with_console(|console| console.attr = attr);
     }
 
     pub fn set_cursor(&mut self, x: usize, y: usize) {
-        unsafe {
-            let con_out = &mut *self.con_out;
-            (con_out.set_cursor_position)(con_out, x, y);
-        }
+        with_console(|console| console.cursor = (x, y));
     }
 
     pub fn put_char(&mut self, ch: char) {
-        let buf = [ch as u16, 0];
-        unsafe {
-            let con_out = &mut *self.con_out;
-            (con_out.output_string)(con_out, buf.as_ptr());
-        }
+        let mut buf = [0u8; 4];
+        self.put_str(ch.encode_utf8(&mut buf));
     }
 
     pub fn put_str(&mut self, s: &str) {
-        let mut buffer = [0u16; 256];
-        str_to_ucs2(s, &mut buffer);
-        unsafe {
-            let con_out = &mut *self.con_out;
-            (con_out.output_string)(con_out, buffer.as_ptr());
-        }
+        with_console(|console| console.write_str(s));
     }
 
     pub fn put_char_at(&mut self, x: usize, y: usize, ch: char, fg: usize, bg: usize) {
@@ -180,35 +332,13 @@ impl Screen {
         self.set_cursor(x, y);
         self.set_color(fg, bg);
         self.put_str(s);
-
-        // Update mask for written content
-        if y < self.height {
-            for (i, _ch) in s.chars().enumerate() {
-                let pos = x + i;
-                if pos < self.width {
-                    self.mask[y][pos] = true;
-                }
-            }
-        }
     }
 
     // Draw multi-line text block with proper spacing
     pub fn draw_block(&mut self, lines: &[&str]) {
-        let mut buffer = [0u16; 512];
-
         for line in lines {
-            str_to_ucs2(line, &mut buffer);
-            unsafe {
-                let con_out = &mut *self.con_out;
-                (con_out.output_string)(con_out, buffer.as_ptr());
-            }
-
-            // Add newline
-            str_to_ucs2("\r\n", &mut buffer);
-            unsafe {
-                let con_out = &mut *self.con_out;
-                (con_out.output_string)(con_out, buffer.as_ptr());
-            }
+            self.put_str(line);
+            self.put_str("\r\n");
         }
     }
 
@@ -261,3 +391,61 @@ impl Screen {
         self.put_char(ch);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cells(text: &str, attr: u8) -> Vec<Cell> {
+        text.chars()
+            .map(|ch| Cell {
+                ch: ch as u16,
+                attr,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_changed_runs_skip_unchanged() {
+        let shown = cells("hello world", 0x02);
+        let frame = cells("hello there", 0x02);
+        assert_eq!(
+            changed_runs(&shown, &frame),
+            vec![Run {
+                x: 6,
+                len: 5,
+                attr: 0x02
+            }]
+        );
+        assert!(changed_runs(&shown, &shown).is_empty());
+    }
+
+    #[test]
+    fn test_changed_runs_split_on_attr() {
+        let shown = cells("    ", 0x00);
+        let mut frame = cells("ab", 0x0A);
+        frame.extend(cells("cd", 0x02));
+        assert_eq!(
+            changed_runs(&shown, &frame),
+            vec![
+                Run {
+                    x: 0,
+                    len: 2,
+                    attr: 0x0A
+                },
+                Run {
+                    x: 2,
+                    len: 2,
+                    attr: 0x02
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_blank_cells_compare_by_background() {
+        let shown = cells("  ", 0x0A);
+        assert!(changed_runs(&shown, &cells("  ", 0x02)).is_empty());
+        assert_eq!(changed_runs(&shown, &cells("  ", 0x12)).len(), 1);
+    }
+}
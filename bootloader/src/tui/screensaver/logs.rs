@@ -0,0 +1,95 @@
+//! Log scroll: the core log ring rolling up the screen.
+
+use super::Effect;
+use crate::tui::renderer::{Screen, EFI_BLACK};
+use alloc::vec::Vec;
+
+/// Polls between one-line scroll steps.
+const SCROLL_INTERVAL: u32 = 20;
+
+pub struct LogScroll {
+    width: usize,
+    height: usize,
+    /// Index of the log entry on the top row
+    offset: usize,
+    tick: u32,
+    bright: usize,
+    dim: usize,
+}
+
+impl LogScroll {
+    pub fn new(width: usize, height: usize, bright: usize, dim: usize) -> Self {
+        Self {
+            width,
+            height,
+            offset: 0,
+            tick: 0,
+            bright,
+            dim,
+        }
+    }
+
+    fn draw(&self, screen: &mut Screen, logs: &[&str]) {
+        if logs.is_empty() {
+            let msg = "no log entries";
+            screen.put_str_at(
+                screen.center_x(msg.len()),
+                self.height / 2,
+                msg,
+                self.dim,
+                EFI_BLACK,
+            );
+            return;
+        }
+
+        // Leave the last column alone so the console doesn't scroll
+        let cols = self.width.saturating_sub(1);
+        for row in 0..self.height {
+            let idx = (self.offset + row) % logs.len();
+            // Newest entry stands out
+            let color = if idx == logs.len() - 1 {
+                self.bright
+            } else {
+                self.dim
+            };
+            let line = logs[idx];
+            let shown = &line[..floor_char_boundary(line, cols)];
+            screen.put_str_at(0, row, shown, color, EFI_BLACK);
+            for x in shown.chars().count()..cols {
+                screen.put_char_at(x, row, ' ', EFI_BLACK, EFI_BLACK);
+            }
+        }
+    }
+}
+
+/// Largest char boundary in `s` not past `max` bytes.
+fn floor_char_boundary(s: &str, max: usize) -> usize {
+    if max >= s.len() {
+        return s.len();
+    }
+    let mut end = max;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    end
+}
+
+impl Effect for LogScroll {
+    fn start(&mut self, screen: &mut Screen) {
+        screen.clear();
+        let logs: Vec<&str> = morpheus_core::logger::get_logs_iter().collect();
+        // Start with the newest entry on the bottom row
+        self.offset = logs.len().saturating_sub(self.height);
+        self.draw(screen, &logs);
+    }
+
+    fn frame(&mut self, screen: &mut Screen) {
+        self.tick = self.tick.wrapping_add(1);
+        if self.tick % SCROLL_INTERVAL != 0 {
+            return;
+        }
+        let logs: Vec<&str> = morpheus_core::logger::get_logs_iter().collect();
+        self.offset = self.offset.wrapping_add(1);
+        self.draw(screen, &logs);
+    }
+}
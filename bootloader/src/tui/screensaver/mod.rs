@@ -0,0 +1,268 @@
+//! Screensaver subsystem.
+//!
+//! Menus read the keyboard through [`poll`] instead of calling the
+//! keyboard directly. It counts idle polls, starts the theme's effect once
+//! the idle timeout passes, draws a frame per poll while active and
+//! swallows the key that wakes it. The global screensaver key (`x`, see
+//! [`crate::tui::keymap::GLOBAL`]) starts the effect by hand.
+//!
+//! ```ignore
+//! loop {
+//!     match screensaver::poll(screen, keyboard) {
+//!         Some(Event::Key(key)) => self.handle_input(&key),
+//!         Some(Event::Redraw) => {
+//!             screen.clear();
+//!             self.render(screen);
+//!         }
+//!         None => {}
+//!     }
+//! }
+//! ```
+
+mod logs;
+#[cfg(not(feature = "no-rain"))]
+mod rain;
+mod starfield;
+mod theme;
+
+pub use theme::{Theme, CONSOLE, DEEP_SPACE, MATRIX};
+
+use crate::tui::input::{InputKey, Keyboard};
+use crate::tui::keymap::{Command, GLOBAL};
+use crate::tui::renderer::Screen;
+use alloc::boxed::Box;
+use spin::Mutex;
+
+/// Keyboard polls per second (`poll_key_with_delay` waits ~16ms).
+pub const POLLS_PER_SECOND: u32 = 60;
+
+/// One animation.
+pub trait Effect: Send {
+    /// Called once when the screensaver starts. Default keeps the screen.
+    fn start(&mut self, _screen: &mut Screen) {}
+
+    /// Draw one frame.
+    fn frame(&mut self, screen: &mut Screen);
+}
+
+/// Available effects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EffectKind {
+    /// Falling binary columns over the UI
+    #[cfg(not(feature = "no-rain"))]
+    Rain,
+    /// Stars flying out of the centre
+    Starfield,
+    /// The core log ring, scrolling
+    Logs,
+}
+
+impl EffectKind {
+    /// Short name for settings screens.
+    pub fn name(self) -> &'static str {
+        match self {
+            #[cfg(not(feature = "no-rain"))]
+            EffectKind::Rain => "rain",
+            EffectKind::Starfield => "starfield",
+            EffectKind::Logs => "logs",
+        }
+    }
+
+    fn create(self, screen: &Screen, theme: &Theme) -> Box<dyn Effect> {
+        let (w, h) = (screen.width(), screen.height());
+        match self {
+            #[cfg(not(feature = "no-rain"))]
+            EffectKind::Rain => Box::new(rain::MatrixRain::new(w, h, theme.bright, theme.dim)),
+            EffectKind::Starfield => {
+                Box::new(starfield::Starfield::new(w, h, theme.bright, theme.dim))
+            }
+            EffectKind::Logs => Box::new(logs::LogScroll::new(w, h, theme.bright, theme.dim)),
+        }
+    }
+}
+
+/// What a menu has to do after [`poll`].
+pub enum Event {
+    /// A key for the menu.
+    Key(InputKey),
+    /// The screensaver stopped and left the screen dirty; redraw everything.
+    Redraw,
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// STATE
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Counts idle polls against a timeout.
+pub struct IdleTimer {
+    polls: u32,
+    timeout: u32,
+}
+
+impl IdleTimer {
+    /// `timeout` in polls; 0 never expires.
+    pub const fn new(timeout: u32) -> Self {
+        Self { polls: 0, timeout }
+    }
+
+    /// Input seen.
+    pub fn reset(&mut self) {
+        self.polls = 0;
+    }
+
+    /// One poll without input. Returns true once, when the timeout passes.
+    pub fn tick(&mut self) -> bool {
+        if self.timeout == 0 || self.polls > self.timeout {
+            return false;
+        }
+        self.polls += 1;
+        self.polls > self.timeout
+    }
+
+    pub fn set_timeout(&mut self, timeout: u32) {
+        self.timeout = timeout;
+        self.polls = 0;
+    }
+}
+
+struct Screensaver {
+    theme: &'static Theme,
+    effect: EffectKind,
+    idle: IdleTimer,
+    active: Option<Box<dyn Effect>>,
+}
+
+static SCREENSAVER: Mutex<Screensaver> = Mutex::new(Screensaver {
+    theme: &MATRIX,
+    effect: MATRIX.effect,
+    idle: IdleTimer::new(MATRIX.idle_timeout_secs * POLLS_PER_SECOND),
+    active: None,
+});
+
+impl Screensaver {
+    fn start(&mut self, screen: &mut Screen) {
+        let mut effect = self.effect.create(screen, self.theme);
+        effect.start(screen);
+        self.active = Some(effect);
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// PUBLIC API
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// The input hook. Call once per iteration of a menu's input loop.
+pub fn poll(screen: &mut Screen, keyboard: &mut Keyboard) -> Option<Event> {
+    let key = keyboard.poll_key_with_delay();
+    let mut saver = SCREENSAVER.lock();
+
+    if let Some(effect) = saver.active.as_mut() {
+        if key.is_none() {
+            effect.frame(screen);
+            return None;
+        }
+        // Any key wakes; it is not passed on
+        saver.active = None;
+        saver.idle.reset();
+        return Some(Event::Redraw);
+    }
+
+    match key {
+        Some(key) if GLOBAL.lookup(&key) == Some(Command::Screensaver) => {
+            saver.start(screen);
+            None
+        }
+        Some(key) => {
+            saver.idle.reset();
+            Some(Event::Key(key))
+        }
+        None => {
+            if saver.idle.tick() {
+                saver.start(screen);
+            }
+            None
+        }
+    }
+}
+
+/// Switch theme. Also resets the effect and idle timeout to its defaults.
+pub fn set_theme(theme: &'static Theme) {
+    let mut saver = SCREENSAVER.lock();
+    saver.theme = theme;
+    saver.effect = theme.effect;
+    saver
+        .idle
+        .set_timeout(theme.idle_timeout_secs * POLLS_PER_SECOND);
+}
+
+/// Override the theme's effect.
+pub fn set_effect(effect: EffectKind) {
+    SCREENSAVER.lock().effect = effect;
+}
+
+/// Override the theme's idle timeout. 0 disables idle activation; `x`
+/// still works.
+pub fn set_idle_timeout_secs(secs: u32) {
+    SCREENSAVER
+        .lock()
+        .idle
+        .set_timeout(secs * POLLS_PER_SECOND);
+}
+
+/// Whether an effect is on screen.
+pub fn is_active() -> bool {
+    SCREENSAVER.lock().active.is_some()
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// SHARED HELPERS
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Simple PRNG for effects (LCG algorithm)
+pub struct Rng {
+    state: u32,
+}
+
+impl Rng {
+    pub fn new(seed: u32) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next(&mut self) -> u32 {
+        self.state = self.state.wrapping_mul(1103515245).wrapping_add(12345);
+        self.state
+    }
+
+    pub fn range(&mut self, max: u32) -> u32 {
+        if max == 0 {
+            return 0;
+        }
+        self.next() % max
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idle_timer_fires_once() {
+        let mut idle = IdleTimer::new(3);
+        assert!(!idle.tick());
+        assert!(!idle.tick());
+        assert!(!idle.tick());
+        assert!(idle.tick());
+        assert!(!idle.tick());
+
+        idle.reset();
+        assert!(!idle.tick());
+    }
+
+    #[test]
+    fn test_idle_timer_disabled() {
+        let mut idle = IdleTimer::new(0);
+        for _ in 0..1000 {
+            assert!(!idle.tick());
+        }
+    }
+}
@@ -1,56 +1,8 @@
-use super::renderer::{Screen, EFI_BLACK, EFI_DARKGREEN, EFI_LIGHTGREEN};
-use alloc::vec::Vec;
-use core::sync::atomic::{AtomicBool, Ordering};
-
-// Global rain toggle
-static RAIN_ENABLED: AtomicBool = AtomicBool::new(false);
-static mut GLOBAL_RAIN: Option<MatrixRain> = None;
-
-pub fn toggle_rain(screen: &Screen) {
-    let was_enabled = RAIN_ENABLED.load(Ordering::Relaxed);
-    RAIN_ENABLED.store(!was_enabled, Ordering::Relaxed);
-
-    unsafe {
-        if !was_enabled {
-            GLOBAL_RAIN = Some(MatrixRain::new(screen.width(), screen.height()));
-        } else {
-            GLOBAL_RAIN = None;
-        }
-    }
-}
+//! Matrix rain: falling binary columns that eat through the UI.
 
-pub fn render_rain(screen: &mut Screen) {
-    if RAIN_ENABLED.load(Ordering::Relaxed) {
-        unsafe {
-            if let Some(ref mut rain) = GLOBAL_RAIN {
-                rain.render_frame(screen);
-            }
-        }
-    }
-}
-
-// Simple PRNG for rain animation (LCG algorithm)
-pub struct Rng {
-    state: u32,
-}
-
-impl Rng {
-    pub fn new(seed: u32) -> Self {
-        Self { state: seed }
-    }
-
-    pub fn next(&mut self) -> u32 {
-        self.state = self.state.wrapping_mul(1103515245).wrapping_add(12345);
-        self.state
-    }
-
-    pub fn range(&mut self, max: u32) -> u32 {
-        if max == 0 {
-            return 0;
-        }
-        self.next() % max
-    }
-}
+use super::{Effect, Rng};
+use crate::tui::renderer::{Screen, EFI_BLACK};
+use alloc::vec::Vec;
 
 pub struct RainColumn {
     pub x: usize,
@@ -98,10 +50,12 @@ pub struct MatrixRain {
     screen_height: usize,
     screen_width: usize,
     frame_count: u32,
+    bright: usize,
+    dim: usize,
 }
 
 impl MatrixRain {
-    pub fn new(screen_width: usize, screen_height: usize) -> Self {
+    pub fn new(screen_width: usize, screen_height: usize, bright: usize, dim: usize) -> Self {
         let mut rng = Rng::new(0x1337BEEF);
         // One column per character position for dense rain
         let num_cols = screen_width;
@@ -130,6 +84,8 @@ impl MatrixRain {
             screen_height,
             screen_width,
             frame_count: 0,
+            bright,
+            dim,
         }
     }
 
@@ -166,19 +122,19 @@ impl MatrixRain {
                         // Head: bright green binary
                         (
                             if self.rng.range(2) == 0 { '1' } else { '0' },
-                            EFI_LIGHTGREEN,
+                            self.bright,
                         )
                     } else if i < 3 {
                         // Middle: normal green binary
                         (
                             if self.rng.range(2) == 0 { '1' } else { '0' },
-                            EFI_DARKGREEN,
+                            self.dim,
                         )
                     } else {
                         // Tail: dim green dots for cosmic trail effect
                         (
                             if self.rng.range(3) == 0 { '.' } else { ' ' },
-                            EFI_DARKGREEN,
+                            self.dim,
                         )
                     };
                     screen.put_char_at(x_pos, y_pos, ch, color, EFI_BLACK);
@@ -190,3 +146,9 @@ impl MatrixRain {
         self.delay();
     }
 }
+
+impl Effect for MatrixRain {
+    fn frame(&mut self, screen: &mut Screen) {
+        self.render_frame(screen);
+    }
+}
@@ -0,0 +1,123 @@
+//! Starfield: stars flying out of the screen centre.
+
+use super::{Effect, Rng};
+use crate::tui::renderer::{Screen, EFI_BLACK};
+use alloc::vec::Vec;
+
+/// Stars on screen at once.
+const STAR_COUNT: usize = 80;
+
+/// Depth a new star starts at. Lower depth = closer = further out.
+const MAX_DEPTH: i32 = 64;
+
+/// Projection scale: a star at depth `MAX_DEPTH / SCALE` is at its full
+/// offset from the centre.
+const SCALE: i32 = 8;
+
+/// Polls between frames, so the flight speed isn't tied to the poll rate.
+const FRAME_INTERVAL: u32 = 2;
+
+struct Star {
+    /// Offset from the centre at depth `SCALE`
+    x: i32,
+    y: i32,
+    z: i32,
+    /// Where it was last drawn, for erasing
+    drawn: Option<(usize, usize)>,
+}
+
+pub struct Starfield {
+    stars: Vec<Star>,
+    rng: Rng,
+    width: usize,
+    height: usize,
+    tick: u32,
+    bright: usize,
+    dim: usize,
+}
+
+impl Starfield {
+    pub fn new(width: usize, height: usize, bright: usize, dim: usize) -> Self {
+        let mut field = Self {
+            stars: Vec::with_capacity(STAR_COUNT),
+            rng: Rng::new(0x5EED_57A2),
+            width,
+            height,
+            tick: 0,
+            bright,
+            dim,
+        };
+        for _ in 0..STAR_COUNT {
+            let mut star = Star {
+                x: 0,
+                y: 0,
+                z: 0,
+                drawn: None,
+            };
+            field.respawn(&mut star);
+            // Spread the first batch over all depths so they don't arrive together
+            star.z = 1 + field.rng.range(MAX_DEPTH as u32) as i32;
+            field.stars.push(star);
+        }
+        field
+    }
+
+    fn respawn(&mut self, star: &mut Star) {
+        let half_w = (self.width / 2).max(1) as u32;
+        let half_h = (self.height / 2).max(1) as u32;
+        star.x = self.rng.range(half_w * 2) as i32 - half_w as i32;
+        star.y = self.rng.range(half_h * 2) as i32 - half_h as i32;
+        star.z = MAX_DEPTH;
+    }
+
+    /// Screen position at the star's depth, if it is on screen.
+    fn project(&self, star: &Star) -> Option<(usize, usize)> {
+        let cx = (self.width / 2) as i32;
+        let cy = (self.height / 2) as i32;
+        let sx = cx + star.x * SCALE / star.z;
+        let sy = cy + star.y * SCALE / star.z;
+        if sx < 0 || sy < 0 || sx >= self.width as i32 || sy >= self.height as i32 {
+            return None;
+        }
+        Some((sx as usize, sy as usize))
+    }
+}
+
+impl Effect for Starfield {
+    fn start(&mut self, screen: &mut Screen) {
+        screen.clear();
+    }
+
+    fn frame(&mut self, screen: &mut Screen) {
+        self.tick = self.tick.wrapping_add(1);
+        if self.tick % FRAME_INTERVAL != 0 {
+            return;
+        }
+
+        let mut stars = core::mem::take(&mut self.stars);
+        for star in stars.iter_mut() {
+            if let Some((x, y)) = star.drawn.take() {
+                screen.put_char_at(x, y, ' ', EFI_BLACK, EFI_BLACK);
+            }
+
+            star.z -= 1;
+            let pos = if star.z > 0 { self.project(star) } else { None };
+            let (x, y) = match pos {
+                Some(pos) => pos,
+                None => {
+                    self.respawn(star);
+                    continue;
+                }
+            };
+
+            let (ch, color) = match star.z {
+                z if z > MAX_DEPTH * 2 / 3 => ('.', self.dim),
+                z if z > MAX_DEPTH / 3 => ('+', self.dim),
+                _ => ('*', self.bright),
+            };
+            screen.put_char_at(x, y, ch, color, EFI_BLACK);
+            star.drawn = Some((x, y));
+        }
+        self.stars = stars;
+    }
+}
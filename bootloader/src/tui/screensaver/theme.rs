@@ -0,0 +1,48 @@
+//! Screensaver themes: default effect, idle timeout and colours.
+
+use super::EffectKind;
+use crate::tui::renderer::{
+    EFI_BLUE, EFI_DARKGRAY, EFI_DARKGREEN, EFI_LIGHTGRAY, EFI_LIGHTGREEN, EFI_WHITE,
+};
+
+pub struct Theme {
+    pub name: &'static str,
+    /// Effect started on idle or `x`
+    pub effect: EffectKind,
+    /// Idle time before the effect starts (0 = only on `x`)
+    pub idle_timeout_secs: u32,
+    /// Foreground for the near/leading parts of an effect
+    pub bright: usize,
+    /// Foreground for the far/trailing parts
+    pub dim: usize,
+}
+
+/// Green on black, the default look.
+pub const MATRIX: Theme = Theme {
+    name: "matrix",
+    #[cfg(not(feature = "no-rain"))]
+    effect: EffectKind::Rain,
+    #[cfg(feature = "no-rain")]
+    effect: EffectKind::Starfield,
+    idle_timeout_secs: 120,
+    bright: EFI_LIGHTGREEN,
+    dim: EFI_DARKGREEN,
+};
+
+/// White stars on dark blue.
+pub const DEEP_SPACE: Theme = Theme {
+    name: "deep-space",
+    effect: EffectKind::Starfield,
+    idle_timeout_secs: 120,
+    bright: EFI_WHITE,
+    dim: EFI_BLUE,
+};
+
+/// Grey log scroll, slower to kick in.
+pub const CONSOLE: Theme = Theme {
+    name: "console",
+    effect: EffectKind::Logs,
+    idle_timeout_secs: 300,
+    bright: EFI_LIGHTGRAY,
+    dim: EFI_DARKGRAY,
+};
@@ -0,0 +1,132 @@
+use super::StorageManager;
+use crate::tui::input::Keyboard;
+use crate::tui::renderer::{Screen, EFI_BLACK, EFI_DARKGREEN, EFI_GREEN, EFI_LIGHTGREEN, EFI_WHITE};
+use crate::uefi::gpt_adapter::UefiBlockIoAdapter;
+use crate::BootServices;
+use alloc::format;
+use morpheus_core::disk::partition::PartitionType;
+use morpheus_core::fs::fat32_ops::{self, CheckReport, Problem};
+
+/// Problems listed before the rest are summarised as "...and N more"
+const MAX_LISTED: usize = 12;
+
+impl StorageManager {
+    /// Check the FAT32 filesystem of the ESP on the current disk and offer
+    /// to repair what's wrong with it.
+    pub(super) fn check_esp_ui(
+        &mut self,
+        screen: &mut Screen,
+        keyboard: &mut Keyboard,
+        bs: &BootServices,
+    ) {
+        screen.clear();
+        let start_x = 2;
+        screen.put_str_at(start_x, 1, "=== CHECK ESP ===", EFI_LIGHTGREEN, EFI_BLACK);
+
+        let Some(esp_lba) = self
+            .partition_table
+            .iter()
+            .find(|p| p.partition_type == PartitionType::EfiSystem)
+            .map(|p| p.start_lba)
+        else {
+            finish(screen, keyboard, 3, "[ERR] No ESP on this disk");
+            return;
+        };
+
+        let Ok(block_io_ptr) = crate::uefi::disk::get_disk_protocol(bs, self.current_disk_index)
+        else {
+            finish(screen, keyboard, 3, "[ERR] Failed to access disk");
+            return;
+        };
+        let block_io = unsafe { &mut *block_io_ptr };
+        let Ok(mut adapter) = UefiBlockIoAdapter::new(block_io) else {
+            finish(screen, keyboard, 3, "[ERR] Failed to create adapter");
+            return;
+        };
+
+        screen.put_str_at(start_x, 3, "Checking filesystem...", EFI_GREEN, EFI_BLACK);
+        screen.present();
+        let report = match fat32_ops::check(&mut adapter, esp_lba, false) {
+            Ok(report) => report,
+            Err(e) => {
+                finish(screen, keyboard, 5, &format!("[ERR] Check failed: {:?}", e));
+                return;
+            }
+        };
+
+        let mut y = show_report(screen, 3, &report);
+        if report.is_clean() {
+            finish(screen, keyboard, y + 1, "[OK] No problems found");
+            return;
+        }
+        if matches!(report.problems[..], [Problem::BadBootSector(_)]) {
+            finish(screen, keyboard, y + 1, "[ERR] Not a FAT32 volume; nothing to repair");
+            return;
+        }
+
+        y += 1;
+        screen.put_str_at(
+            start_x,
+            y,
+            "Press Y to repair (truncates bad chains, frees orphans), any other key to cancel",
+            EFI_DARKGREEN,
+            EFI_BLACK,
+        );
+        let key = keyboard.wait_for_key();
+        if key.unicode_char != b'y' as u16 && key.unicode_char != b'Y' as u16 {
+            return;
+        }
+        y += 2;
+        screen.put_str_at(start_x, y, "Repairing...", EFI_GREEN, EFI_BLACK);
+        screen.present();
+
+        let repaired = fat32_ops::check(&mut adapter, esp_lba, true)
+            .and_then(|_| fat32_ops::check(&mut adapter, esp_lba, false));
+        y += 1;
+        match repaired {
+            Ok(after) if after.is_clean() => {
+                let msg = format!("[OK] Repaired {} problem(s)", report.problems.len());
+                finish(screen, keyboard, y, &msg);
+            }
+            Ok(after) => {
+                let msg = format!("[ERR] {} problem(s) remain after repair", after.problems.len());
+                finish(screen, keyboard, y, &msg);
+            }
+            Err(e) => finish(screen, keyboard, y, &format!("[ERR] Repair failed: {:?}", e)),
+        }
+    }
+}
+
+/// Print the totals and problems of `report` from row `y`; returns the
+/// first row after them.
+fn show_report(screen: &mut Screen, mut y: usize, report: &CheckReport) -> usize {
+    let totals = format!(
+        "{} files, {} directories, {} clusters used, {} free",
+        report.files, report.directories, report.used_clusters, report.free_clusters
+    );
+    screen.put_str_at(2, y, &format!("{:<70}", totals), EFI_GREEN, EFI_BLACK);
+    y += 2;
+
+    for problem in report.problems.iter().take(MAX_LISTED) {
+        screen.put_str_at(2, y, &format!("  {}", problem), EFI_WHITE, EFI_BLACK);
+        y += 1;
+    }
+    if report.problems.len() > MAX_LISTED {
+        let more = format!("  ...and {} more", report.problems.len() - MAX_LISTED);
+        screen.put_str_at(2, y, &more, EFI_WHITE, EFI_BLACK);
+        y += 1;
+    }
+    y
+}
+
+/// Show `msg` at row `y` (an "[OK]" one highlighted) and wait for a key.
+pub(super) fn finish(screen: &mut Screen, keyboard: &mut Keyboard, y: usize, msg: &str) {
+    let color = if msg.starts_with("[OK]") {
+        EFI_LIGHTGREEN
+    } else {
+        EFI_WHITE
+    };
+    screen.put_str_at(2, y, msg, color, EFI_BLACK);
+    screen.put_str_at(2, y + 2, "Press any key...", EFI_DARKGREEN, EFI_BLACK);
+    keyboard.wait_for_key();
+}
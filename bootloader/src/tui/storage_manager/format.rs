@@ -1,4 +1,4 @@
-use super::StorageManager;
+use super::{disk_label, guard, StorageManager};
 use crate::tui::input::Keyboard;
 use crate::tui::renderer::{Screen, EFI_BLACK, EFI_DARKGREEN, EFI_GREEN, EFI_LIGHTGREEN};
 use crate::uefi::gpt_adapter::UefiBlockIoAdapter;
@@ -62,6 +62,10 @@ impl StorageManager {
         screen.put_str_at(content_x + 11, 9, core::str::from_utf8(&buf[..len]).unwrap_or("?"), EFI_LIGHTGREEN, EFI_BLACK);
         screen.put_str_at(content_x + 11 + len, 9, " MB", EFI_LIGHTGREEN, EFI_BLACK);
 
+        screen.put_str_at(content_x, 10, "Disk:", EFI_GREEN, EFI_BLACK);
+        let label = disk_label(&self.disk_manager, self.current_disk_index);
+        screen.put_str_at(content_x + 11, 10, &label, EFI_LIGHTGREEN, EFI_BLACK);
+
         let warn = "WARNING: This will erase all data on the partition!";
         screen.put_str_at(screen.center_x(warn.len()), 11, warn, EFI_LIGHTGREEN, EFI_BLACK);
         let confirm = "Format as FAT32?";
@@ -73,12 +77,17 @@ impl StorageManager {
         if key.unicode_char != b'y' as u16 && key.unicode_char != b'Y' as u16 {
             return;
         }
+        let protection = self.partition_protection(part.start_lba);
+        if !guard::confirm_protected(protection, screen, keyboard) {
+            return;
+        }
 
         screen.clear();
         let title2 = "=== FORMATTING PARTITION ===";
         screen.put_str_at(screen.center_x(title2.len()), 5, title2, EFI_LIGHTGREEN, EFI_BLACK);
         let formatting = "Formatting as FAT32...";
         screen.put_str_at(screen.center_x(formatting.len()), 7, formatting, EFI_GREEN, EFI_BLACK);
+        screen.present();
 
         // Get disk protocol
         let block_io_ptr = match crate::uefi::disk::get_disk_protocol(bs, self.current_disk_index) {
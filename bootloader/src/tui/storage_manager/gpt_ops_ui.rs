@@ -1,4 +1,4 @@
-use super::StorageManager;
+use super::{disk_label, guard, StorageManager};
 use crate::tui::input::Keyboard;
 use crate::tui::renderer::{Screen, EFI_BLACK, EFI_DARKGREEN, EFI_GREEN, EFI_LIGHTGREEN};
 use crate::uefi::gpt_adapter::UefiBlockIoAdapter;
@@ -50,13 +50,19 @@ impl StorageManager {
         screen.clear();
         let title = "=== CREATE GPT PARTITION TABLE ===";
         screen.put_str_at(screen.center_x(title.len()), 5, title, EFI_LIGHTGREEN, EFI_BLACK);
+        let target = "You are about to wipe:";
+        screen.put_str_at(screen.center_x(target.len()), 7, target, EFI_GREEN, EFI_BLACK);
+        let label = disk_label(&self.disk_manager, self.current_disk_index);
+        screen.put_str_at(screen.center_x(label.len()), 8, &label, EFI_LIGHTGREEN, EFI_BLACK);
         let warn = "WARNING: This will erase all data on the disk!";
-        screen.put_str_at(screen.center_x(warn.len()), 7, warn, EFI_LIGHTGREEN, EFI_BLACK);
+        screen.put_str_at(screen.center_x(warn.len()), 10, warn, EFI_LIGHTGREEN, EFI_BLACK);
         let confirm = "Press Y to confirm, any other key to cancel";
-        screen.put_str_at(screen.center_x(confirm.len()), 9, confirm, EFI_GREEN, EFI_BLACK);
+        screen.put_str_at(screen.center_x(confirm.len()), 12, confirm, EFI_GREEN, EFI_BLACK);
 
         let key = keyboard.wait_for_key();
-        if key.unicode_char != b'y' as u16 && key.unicode_char != b'Y' as u16 {
+        let confirmed = (key.unicode_char == b'y' as u16 || key.unicode_char == b'Y' as u16)
+            && guard::confirm_protected(self.disk_protection(), screen, keyboard);
+        if !confirmed {
             screen.clear();
             let cancelled = "GPT creation cancelled";
             screen.put_str_at(screen.center_x(cancelled.len()), 5, cancelled, EFI_GREEN, EFI_BLACK);
@@ -69,6 +75,7 @@ impl StorageManager {
         screen.clear();
         let creating = "Creating GPT table...";
         screen.put_str_at(screen.center_x(creating.len()), 5, creating, EFI_LIGHTGREEN, EFI_BLACK);
+        screen.present();
 
         // Get the block IO protocol for current disk
         let block_io_ptr = match crate::uefi::disk::get_disk_protocol(bs, self.current_disk_index) {
@@ -0,0 +1,73 @@
+use super::StorageManager;
+use crate::tui::input::Keyboard;
+use crate::tui::renderer::{Screen, EFI_BLACK, EFI_DARKGREEN, EFI_GREEN, EFI_LIGHTGREEN, EFI_WHITE};
+use crate::tui::widgets::textbox::TextBox;
+use morpheus_core::disk::guard::{self, Protection, CONFIRM_PHRASE};
+
+impl StorageManager {
+    /// Protection of the whole current disk.
+    pub(super) fn disk_protection(&self) -> Protection {
+        self.disk_manager
+            .get_disk(self.current_disk_index)
+            .map_or(Protection::None, guard::disk_protection)
+    }
+
+    /// Protection of the partition starting at `start_lba` on the current disk.
+    pub(super) fn partition_protection(&self, start_lba: u64) -> Protection {
+        self.disk_manager
+            .get_disk(self.current_disk_index)
+            .map_or(Protection::None, |disk| {
+                guard::partition_protection(disk, start_lba)
+            })
+    }
+}
+
+/// Second confirmation for targets MorpheusX runs from: the user has to
+/// type `CONFIRM_PHRASE`. Returns whether to go ahead.
+pub(crate) fn confirm_protected(
+    protection: Protection,
+    screen: &mut Screen,
+    keyboard: &mut Keyboard,
+) -> bool {
+    if !protection.needs_phrase() {
+        return true;
+    }
+
+    let content_x = screen.center_x(CONFIRM_PHRASE.len() + 2);
+    let mut textbox = TextBox::new(content_x, 11, CONFIRM_PHRASE.len() + 3);
+    textbox.selected = true;
+
+    loop {
+        screen.clear();
+        let title = "=== PROTECTED TARGET ===";
+        screen.put_str_at(screen.center_x(title.len()), 3, title, EFI_LIGHTGREEN, EFI_BLACK);
+        let reason = protection.describe();
+        screen.put_str_at(screen.center_x(reason.len()), 5, reason, EFI_WHITE, EFI_BLACK);
+        let warn = "Going ahead will leave this machine unable to boot MorpheusX.";
+        screen.put_str_at(screen.center_x(warn.len()), 6, warn, EFI_GREEN, EFI_BLACK);
+        let prompt = "Type the following to continue:";
+        screen.put_str_at(screen.center_x(prompt.len()), 8, prompt, EFI_GREEN, EFI_BLACK);
+        screen.put_str_at(
+            screen.center_x(CONFIRM_PHRASE.len()),
+            9,
+            CONFIRM_PHRASE,
+            EFI_LIGHTGREEN,
+            EFI_BLACK,
+        );
+        textbox.render(screen);
+        let help = "[ENTER] Confirm | [ESC] Cancel";
+        screen.put_str_at(screen.center_x(help.len()), 13, help, EFI_DARKGREEN, EFI_BLACK);
+
+        let key = keyboard.wait_for_key();
+
+        if key.scan_code == 0 && key.unicode_char == 0x000D {
+            return guard::phrase_matches(textbox.get_text());
+        } else if key.scan_code == 0x17 {
+            return false; // Cancel
+        } else if key.scan_code == 0 && key.unicode_char == 0x0008 {
+            textbox.backspace();
+        } else if (0x20..0x7F).contains(&key.unicode_char) {
+            textbox.add_char(key.unicode_char as u8);
+        }
+    }
+}
@@ -1,16 +1,48 @@
 use crate::tui::input::Keyboard;
-use crate::tui::rain::MatrixRain;
+use crate::tui::keymap::{self, Bindings, Command, Key, KeyBinding};
 use crate::tui::renderer::{Screen, EFI_BLACK, EFI_DARKGREEN, EFI_GREEN, EFI_LIGHTGREEN};
+use crate::tui::screensaver::Event;
 use crate::BootServices;
+use alloc::format;
+use alloc::string::String;
 use morpheus_core::disk::manager::DiskManager;
 use morpheus_core::disk::partition::PartitionTable;
 
+mod check;
 mod format;
 mod gpt_ops_ui;
+pub(crate) mod guard;
 mod partition_ops;
 mod render;
 mod utils;
 
+const DISK_LIST_BINDINGS: Bindings = Bindings {
+    title: "Storage Manager",
+    keys: &[
+        KeyBinding::new(&[Key::Up], Command::Up, "Previous disk"),
+        KeyBinding::new(&[Key::Down], Command::Down, "Next disk"),
+        KeyBinding::new(&[Key::Enter], Command::Select, "Open disk"),
+        KeyBinding::new(&[Key::Esc], Command::Back, "Back to main menu"),
+    ],
+};
+
+const PARTITION_BINDINGS: Bindings = Bindings {
+    title: "Partitions",
+    keys: &[
+        KeyBinding::new(&[Key::Up], Command::Up, "Previous partition"),
+        KeyBinding::new(&[Key::Down], Command::Down, "Next partition"),
+        KeyBinding::new(&[Key::Char(b'c')], Command::Create, "Create GPT"),
+        KeyBinding::new(&[Key::Char(b'n')], Command::NewPartition, "New partition"),
+        KeyBinding::new(&[Key::Char(b'd')], Command::Delete, "Delete partition"),
+        KeyBinding::new(&[Key::Char(b's')], Command::Shrink, "Shrink partition"),
+        KeyBinding::new(&[Key::Char(b'f')], Command::Format, "Format partition"),
+        KeyBinding::new(&[Key::Char(b'o')], Command::Compact, "Compact ISO store"),
+        KeyBinding::new(&[Key::Char(b'k')], Command::Check, "Check ESP"),
+        KeyBinding::new(&[Key::Char(b'x')], Command::Export, "Export/hide ISO chunk"),
+        KeyBinding::new(&[Key::Esc], Command::Back, "Back to disk list"),
+    ],
+};
+
 pub struct StorageManager {
     disk_manager: DiskManager,
     selected_disk: usize,
@@ -21,9 +53,6 @@ pub struct StorageManager {
     pub(self) selected_partition: usize,
     pub(self) current_disk_index: usize,
     pub(self) free_space_mb: u64,
-
-    // Rain effect
-    rain: MatrixRain,
 }
 
 enum ViewMode {
@@ -31,8 +60,26 @@ enum ViewMode {
     PartitionView,
 }
 
+/// "Samsung SSD 970 EVO 1TB (1.0 TB), SN S4EW..." for confirmation
+/// prompts, so the user can check which physical disk is affected.
+pub(crate) fn disk_label(disk_manager: &DiskManager, disk_index: usize) -> String {
+    let Some(disk) = disk_manager.get_disk(disk_index) else {
+        return format!("Disk {}", disk_index);
+    };
+    let identity = &disk.identity;
+    let mut label = match identity.model() {
+        "" => format!("Disk {} ({})", disk_index, disk.capacity()),
+        model => format!("{} ({})", model, disk.capacity()),
+    };
+    if !identity.serial().is_empty() {
+        label.push_str(", SN ");
+        label.push_str(identity.serial());
+    }
+    label
+}
+
 impl StorageManager {
-    pub fn new(screen: &Screen) -> Self {
+    pub fn new(_screen: &Screen) -> Self {
         Self {
             disk_manager: DiskManager::new(),
             selected_disk: 0,
@@ -41,7 +88,6 @@ impl StorageManager {
             selected_partition: 0,
             current_disk_index: 0,
             free_space_mb: 0,
-            rain: MatrixRain::new(screen.width(), screen.height()),
         }
     }
 
@@ -122,26 +168,28 @@ impl StorageManager {
         self.render(screen);
 
         loop {
-            // Render global rain if active
-            crate::tui::rain::render_rain(screen);
-
-            // Check for input (non-blocking)
-            if let Some(key) = keyboard.read_key() {
-                // Global rain toggle
-                if key.unicode_char == b'x' as u16 || key.unicode_char == b'X' as u16 {
-                    crate::tui::rain::toggle_rain(screen);
+            match keymap::poll(screen, keyboard, self.bindings()) {
+                Some(Event::Key(key)) => {
+                    if self.handle_input(key, screen, keyboard, bs) {
+                        return true; // Exit back to main menu
+                    }
+                }
+                Some(Event::Redraw) => {
                     screen.clear();
                     self.render(screen);
-                    continue;
-                }
-
-                if self.handle_input(key, screen, keyboard, bs) {
-                    return true; // Exit back to main menu
                 }
+                None => {}
             }
         }
     }
 
+    fn bindings(&self) -> &'static Bindings {
+        match self.view_mode {
+            ViewMode::DiskList => &DISK_LIST_BINDINGS,
+            ViewMode::PartitionView => &PARTITION_BINDINGS,
+        }
+    }
+
     fn handle_input(
         &mut self,
         key: crate::tui::input::InputKey,
@@ -149,38 +197,43 @@ impl StorageManager {
         keyboard: &mut Keyboard,
         bs: &BootServices,
     ) -> bool {
+        let command = self.bindings().lookup(&key);
         match self.view_mode {
-            ViewMode::DiskList => {
-                if key.scan_code == 0x01 {
+            ViewMode::DiskList => match command {
+                Some(Command::Up) => {
                     self.select_prev();
                     self.render(screen);
-                } else if key.scan_code == 0x02 {
+                }
+                Some(Command::Down) => {
                     self.select_next();
                     self.render(screen);
-                } else if key.scan_code == 0 && key.unicode_char == 0x000D {
-                    self.handle_disk_selection(screen, keyboard, bs);
-                } else if key.scan_code == 0x17 {
-                    return true; // ESC pressed - exit to main menu
                 }
-            }
-            ViewMode::PartitionView => {
-                if key.scan_code == 0x01 {
+                Some(Command::Select) => self.handle_disk_selection(screen, keyboard, bs),
+                Some(Command::Back) => return true, // Exit to main menu
+                _ => {}
+            },
+            ViewMode::PartitionView => match command {
+                Some(Command::Up) => {
                     self.select_prev();
                     self.render(screen);
-                } else if key.scan_code == 0x02 {
+                }
+                Some(Command::Down) => {
                     self.select_next();
                     self.render(screen);
-                } else if key.unicode_char == b'c' as u16 || key.unicode_char == b'C' as u16 {
+                }
+                Some(Command::Create) => {
                     if !self.partition_table.has_gpt {
                         self.create_gpt_interactive(screen, keyboard, bs);
                         let _ = self.scan_disk(self.current_disk_index, bs);
                         self.render(screen);
                     }
-                } else if key.unicode_char == b'n' as u16 || key.unicode_char == b'N' as u16 {
+                }
+                Some(Command::NewPartition) => {
                     self.create_partition_ui(screen, keyboard, bs);
                     let _ = self.scan_disk(self.current_disk_index, bs);
                     self.render(screen);
-                } else if key.unicode_char == b'd' as u16 || key.unicode_char == b'D' as u16 {
+                }
+                Some(Command::Delete) => {
                     self.delete_partition_ui(screen, keyboard, bs);
                     let _ = self.scan_disk(self.current_disk_index, bs);
                     if self.selected_partition >= self.partition_table.count()
@@ -189,18 +242,36 @@ impl StorageManager {
                         self.selected_partition -= 1;
                     }
                     self.render(screen);
-                } else if key.unicode_char == b's' as u16 || key.unicode_char == b'S' as u16 {
+                }
+                Some(Command::Shrink) => {
                     self.shrink_partition_ui(screen, keyboard, bs);
                     let _ = self.scan_disk(self.current_disk_index, bs);
                     self.render(screen);
-                } else if key.unicode_char == b'f' as u16 || key.unicode_char == b'F' as u16 {
+                }
+                Some(Command::Format) => {
                     self.format_partition_ui(screen, keyboard, bs);
                     self.render(screen);
-                } else if key.scan_code == 0x17 {
+                }
+                Some(Command::Compact) => {
+                    self.compact_iso_store_ui(screen, keyboard, bs);
+                    let _ = self.scan_disk(self.current_disk_index, bs);
+                    self.render(screen);
+                }
+                Some(Command::Check) => {
+                    self.check_esp_ui(screen, keyboard, bs);
+                    self.render(screen);
+                }
+                Some(Command::Export) => {
+                    self.export_chunk_ui(screen, keyboard, bs);
+                    let _ = self.scan_disk(self.current_disk_index, bs);
+                    self.render(screen);
+                }
+                Some(Command::Back) => {
                     self.view_mode = ViewMode::DiskList;
                     self.render(screen);
                 }
-            }
+                _ => {}
+            },
         }
         false // Continue running
     }
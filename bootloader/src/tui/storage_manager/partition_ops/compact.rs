@@ -0,0 +1,185 @@
+use super::super::StorageManager;
+use crate::tui::input::Keyboard;
+use crate::tui::renderer::{Screen, EFI_BLACK, EFI_DARKGREEN, EFI_GREEN, EFI_LIGHTGREEN, EFI_WHITE};
+use crate::tui::widgets::progressbar::ProgressBar;
+use crate::uefi::gpt_adapter::UefiBlockIoAdapter;
+use crate::BootServices;
+use alloc::format;
+use alloc::vec;
+use morpheus_core::disk::partition::PartitionType;
+use morpheus_network::transfer::disk::{
+    CompactPlan, CompactProgress, DiskError, DiskResult, Journal, JournalStep, JOURNAL_PATH,
+    SECTOR_SIZE,
+};
+
+/// Bytes copied per batch
+const COMPACT_BUFFER_SIZE: usize = 1024 * 1024;
+
+impl StorageManager {
+    /// Pack the ISO chunk partitions on the current disk towards its end so
+    /// the free space between them joins up.
+    pub(in super::super) fn compact_iso_store_ui(
+        &mut self,
+        screen: &mut Screen,
+        keyboard: &mut Keyboard,
+        bs: &BootServices,
+    ) {
+        screen.clear();
+        let start_x = 2;
+        screen.put_str_at(
+            start_x,
+            1,
+            "=== COMPACT ISO STORE ===",
+            EFI_LIGHTGREEN,
+            EFI_BLACK,
+        );
+
+        let Some(esp_lba) = self
+            .partition_table
+            .iter()
+            .find(|p| p.partition_type == PartitionType::EfiSystem)
+            .map(|p| p.start_lba)
+        else {
+            finish(screen, keyboard, 3, "[ERR] No ESP on this disk, nothing to compact");
+            return;
+        };
+
+        let Ok(block_io_ptr) = crate::uefi::disk::get_disk_protocol(bs, self.current_disk_index)
+        else {
+            finish(screen, keyboard, 3, "[ERR] Failed to access disk");
+            return;
+        };
+        let block_io = unsafe { &mut *block_io_ptr };
+        let Ok(mut adapter) = UefiBlockIoAdapter::new(block_io) else {
+            finish(screen, keyboard, 3, "[ERR] Failed to create adapter");
+            return;
+        };
+
+        let mut journal = match open_journal(&mut adapter, esp_lba) {
+            Ok(journal) => journal,
+            Err(e) => {
+                finish(screen, keyboard, 3, &format!("[ERR] Cannot open journal: {}", e));
+                return;
+            }
+        };
+        let mut buffer = vec![0u8; COMPACT_BUFFER_SIZE];
+        let mut y = 3;
+
+        // Finish a move an earlier run didn't get to complete
+        if let Some(pending) = journal.pending() {
+            if pending.step != JournalStep::Relocate {
+                finish(
+                    screen,
+                    keyboard,
+                    y,
+                    "[ERR] An interrupted download is pending; start a download to recover it",
+                );
+                return;
+            }
+            let msg = format!("Finishing interrupted move of {}...", pending.name_str());
+            screen.put_str_at(start_x, y, &msg, EFI_GREEN, EFI_BLACK);
+            screen.present();
+            y += 1;
+            let resumed =
+                CompactPlan::resume(&mut adapter, esp_lba, &mut journal, &mut buffer, None);
+            if let Err(e) = resumed {
+                finish(screen, keyboard, y, &format!("[ERR] Move failed: {}", e));
+                return;
+            }
+        }
+
+        let plan = match CompactPlan::scan(&mut adapter, esp_lba) {
+            Ok(plan) => plan,
+            Err(e) => {
+                finish(screen, keyboard, y, &format!("[ERR] Scan failed: {}", e));
+                return;
+            }
+        };
+        if plan.is_empty() {
+            finish(screen, keyboard, y, "[OK] ISO store is already compact");
+            return;
+        }
+
+        for reloc in plan.moves() {
+            let line = format!(
+                "Move {} ({} MB): LBA {} -> {}",
+                reloc.name_str(),
+                reloc.sectors() * SECTOR_SIZE as u64 / (1024 * 1024),
+                reloc.start_lba,
+                reloc.to_lba
+            );
+            screen.put_str_at(start_x, y, &line, EFI_GREEN, EFI_BLACK);
+            y += 1;
+        }
+        y += 1;
+        screen.put_str_at(
+            start_x,
+            y,
+            "Press Y to compact, any other key to cancel",
+            EFI_DARKGREEN,
+            EFI_BLACK,
+        );
+        let key = keyboard.wait_for_key();
+        if key.unicode_char != b'y' as u16 && key.unicode_char != b'Y' as u16 {
+            return;
+        }
+        y += 2;
+
+        let mut progress_bar = ProgressBar::new(start_x, y, 60, "Moving:");
+        progress_bar.render(screen);
+        let name_y = y + 1;
+        let result = {
+            let mut progress = |p: &CompactProgress| {
+                progress_bar.set_progress(p.percent() as usize);
+                progress_bar.render(screen);
+                let reloc = &plan.moves()[p.relocation];
+                let line = format!(
+                    "{:<60}",
+                    format!("{} ({}/{})", reloc.name_str(), p.relocation + 1, p.relocations)
+                );
+                screen.put_str_at(start_x, name_y, &line, EFI_DARKGREEN, EFI_BLACK);
+            };
+            plan.run(
+                &mut adapter,
+                esp_lba,
+                &mut journal,
+                &mut buffer,
+                Some(&mut progress),
+            )
+        };
+        y += 3;
+
+        match result {
+            Ok(()) => {
+                let msg = format!("[OK] Moved {} partition(s)", plan.moves().len());
+                finish(screen, keyboard, y, &msg);
+            }
+            Err(e) => {
+                let msg = format!("[ERR] Compaction stopped: {} (resumes next time)", e);
+                finish(screen, keyboard, y, &msg);
+            }
+        }
+    }
+}
+
+/// Open the journal on the ESP, creating it on first use.
+fn open_journal(adapter: &mut UefiBlockIoAdapter, esp_lba: u64) -> DiskResult<Journal> {
+    if let Some(journal) = Journal::open(adapter, esp_lba)? {
+        return Ok(journal);
+    }
+    let _ = morpheus_core::fs::create_directory(adapter, esp_lba, "/.iso");
+    morpheus_core::fs::write_file(adapter, esp_lba, JOURNAL_PATH, &[0u8; SECTOR_SIZE])
+        .map_err(|_| DiskError::IoError)?;
+    Journal::open(adapter, esp_lba)?.ok_or(DiskError::IoError)
+}
+
+fn finish(screen: &mut Screen, keyboard: &mut Keyboard, y: usize, msg: &str) {
+    let color = if msg.starts_with("[OK]") {
+        EFI_LIGHTGREEN
+    } else {
+        EFI_WHITE
+    };
+    screen.put_str_at(2, y, msg, color, EFI_BLACK);
+    screen.put_str_at(2, y + 2, "Press any key...", EFI_DARKGREEN, EFI_BLACK);
+    keyboard.wait_for_key();
+}
@@ -5,6 +5,14 @@ use crate::tui::widgets::textbox::TextBox;
 use crate::uefi::gpt_adapter::UefiBlockIoAdapter;
 use crate::BootServices;
 use morpheus_core::disk::gpt_ops;
+use morpheus_core::disk::partition::PartitionType;
+
+/// Types offered when creating a partition, in menu order
+const CREATE_TYPES: [PartitionType; 3] = [
+    PartitionType::EfiSystem,
+    PartitionType::LinuxFilesystem,
+    PartitionType::LinuxSwap,
+];
 
 impl StorageManager {
     pub(in super::super) fn create_partition_ui(
@@ -149,7 +157,6 @@ impl StorageManager {
 
         // Step 1: Select partition type
         let mut selected_type = 0;
-        let type_names = ["EFI System", "Linux Filesystem", "Linux Swap"];
 
         loop {
             screen.clear();
@@ -180,7 +187,7 @@ impl StorageManager {
                 EFI_BLACK,
             );
 
-            for i in 0..3 {
+            for (i, partition_type) in CREATE_TYPES.iter().enumerate() {
                 let y = 10 + i;
                 let marker = if i == selected_type { ">" } else { " " };
                 let color = if i == selected_type {
@@ -188,11 +195,12 @@ impl StorageManager {
                 } else {
                     EFI_GREEN
                 };
-                let type_line_len = 2 + type_names[i].len();
+                let type_name = partition_type.name();
+                let type_line_len = 2 + type_name.len();
                 let type_x = screen.center_x(type_line_len);
 
                 screen.put_str_at(type_x, y, marker, color, EFI_BLACK);
-                screen.put_str_at(type_x + 2, y, type_names[i], color, EFI_BLACK);
+                screen.put_str_at(type_x + 2, y, type_name, color, EFI_BLACK);
             }
 
             let help = "[UP/DOWN] Navigate | [ENTER] Select | [ESC] Cancel";
@@ -208,7 +216,7 @@ impl StorageManager {
 
             if key.scan_code == 0x01 && selected_type > 0 {
                 selected_type -= 1;
-            } else if key.scan_code == 0x02 && selected_type < 2 {
+            } else if key.scan_code == 0x02 && selected_type < CREATE_TYPES.len() - 1 {
                 selected_type += 1;
             } else if key.scan_code == 0 && key.unicode_char == 0x000D {
                 break; // Selected
@@ -217,12 +225,7 @@ impl StorageManager {
             }
         }
 
-        let partition_type = match selected_type {
-            0 => morpheus_core::disk::partition::PartitionType::EfiSystem,
-            1 => morpheus_core::disk::partition::PartitionType::LinuxFilesystem,
-            2 => morpheus_core::disk::partition::PartitionType::LinuxSwap,
-            _ => return,
-        };
+        let partition_type = CREATE_TYPES[selected_type];
 
         // Step 2: Enter size - calculate centered position for textbox
         let content_width = 50;
@@ -245,7 +248,7 @@ impl StorageManager {
             screen.put_str_at(
                 content_x + 6,
                 5,
-                type_names[selected_type],
+                partition_type.name(),
                 EFI_LIGHTGREEN,
                 EFI_BLACK,
             );
@@ -304,20 +307,18 @@ impl StorageManager {
 
             for byte in size_text.bytes() {
                 if (b'0'..=b'9').contains(&byte) {
-                    requested_mb = requested_mb * 10 + (byte - b'0') as u64;
+                    requested_mb = requested_mb.saturating_mul(10).saturating_add((byte - b'0') as u64);
                 }
             }
 
             if requested_mb == 0 {
                 region.end_lba
             } else {
-                let requested_lba = (requested_mb * 1024 * 1024) / 512;
-                let calculated_end = region.start_lba + requested_lba - 1;
-
-                if calculated_end <= region.end_lba {
-                    calculated_end
-                } else {
+                let requested_lba = gpt_ops::mb_to_lba(requested_mb, region.block_size);
+                if requested_lba == 0 || requested_lba > region.size_lba() {
                     region.end_lba
+                } else {
+                    region.start_lba + requested_lba - 1
                 }
             }
         };
@@ -332,6 +333,7 @@ impl StorageManager {
             EFI_LIGHTGREEN,
             EFI_BLACK,
         );
+        screen.present();
 
         let block_io = unsafe { &mut *block_io_ptr };
         let adapter = match UefiBlockIoAdapter::new(block_io) {
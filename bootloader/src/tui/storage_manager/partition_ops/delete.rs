@@ -1,4 +1,4 @@
-use super::super::StorageManager;
+use super::super::{disk_label, guard, StorageManager};
 use crate::tui::input::Keyboard;
 use crate::tui::renderer::{Screen, EFI_BLACK, EFI_DARKGREEN, EFI_GREEN, EFI_LIGHTGREEN};
 use crate::tui::widgets::textbox::TextBox;
@@ -96,6 +96,10 @@ impl StorageManager {
         );
         screen.put_str_at(content_x + 6 + size_len, 11, " MB", EFI_GREEN, EFI_BLACK);
 
+        screen.put_str_at(content_x, 12, "Disk: ", EFI_GREEN, EFI_BLACK);
+        let label = disk_label(&self.disk_manager, self.current_disk_index);
+        screen.put_str_at(content_x + 6, 12, &label, EFI_GREEN, EFI_BLACK);
+
         let confirm = "Press Y to confirm, any other key to cancel";
         screen.put_str_at(
             screen.center_x(confirm.len()),
@@ -109,6 +113,10 @@ impl StorageManager {
         if key.unicode_char != b'y' as u16 && key.unicode_char != b'Y' as u16 {
             return;
         }
+        let protection = self.partition_protection(partition.start_lba);
+        if !guard::confirm_protected(protection, screen, keyboard) {
+            return;
+        }
 
         screen.clear();
         let deleting = "Deleting partition...";
@@ -119,6 +127,7 @@ impl StorageManager {
             EFI_LIGHTGREEN,
             EFI_BLACK,
         );
+        screen.present();
 
         // Get disk access
         let block_io_ptr = match crate::uefi::disk::get_disk_protocol(bs, self.current_disk_index) {
@@ -0,0 +1,99 @@
+use super::super::check::finish;
+use super::super::{disk_label, StorageManager};
+use crate::tui::input::Keyboard;
+use crate::tui::renderer::{Screen, EFI_BLACK, EFI_DARKGREEN, EFI_GREEN, EFI_LIGHTGREEN};
+use crate::uefi::gpt_adapter::UefiBlockIoAdapter;
+use crate::BootServices;
+use alloc::format;
+use morpheus_core::disk::gpt_ops;
+use morpheus_core::disk::partition::PartitionType;
+use morpheus_gpt::part_type::CHUNK_HIDDEN;
+
+impl StorageManager {
+    /// Export or re-hide the selected ISO chunk partition. Chunks are
+    /// created hidden and no-automount so other systems leave them alone;
+    /// exporting clears both bits so another OS can read the chunk, and
+    /// running this again sets them back. Only the attributes change.
+    pub(in super::super) fn export_chunk_ui(
+        &mut self,
+        screen: &mut Screen,
+        keyboard: &mut Keyboard,
+        bs: &BootServices,
+    ) {
+        screen.clear();
+        let start_x = 2;
+        screen.put_str_at(
+            start_x,
+            1,
+            "=== EXPORT CHUNK ===",
+            EFI_LIGHTGREEN,
+            EFI_BLACK,
+        );
+
+        let Some(partition) = self.partition_table.get(self.selected_partition).copied() else {
+            finish(screen, keyboard, 3, "[ERR] No partition selected");
+            return;
+        };
+        if partition.partition_type != PartitionType::IsoChunk {
+            finish(screen, keyboard, 3, "[ERR] Not an ISO chunk partition");
+            return;
+        }
+
+        let hidden = partition.is_hidden();
+        let label = disk_label(&self.disk_manager, self.current_disk_index);
+        let summary = format!(
+            "Partition {} on {}, {} MB: {}",
+            partition.index,
+            label,
+            partition.size_mb(),
+            if hidden { "hidden" } else { "exported" }
+        );
+        screen.put_str_at(start_x, 3, &summary, EFI_GREEN, EFI_BLACK);
+        let (action, prompt) = if hidden {
+            (
+                "Export",
+                "Press Y to clear the hidden/no-automount flags so other systems can read it",
+            )
+        } else {
+            ("Hide", "Press Y to hide it from other systems again")
+        };
+        screen.put_str_at(start_x, 5, prompt, EFI_DARKGREEN, EFI_BLACK);
+        screen.put_str_at(
+            start_x,
+            6,
+            "Any other key cancels. The chunk's data is not touched.",
+            EFI_DARKGREEN,
+            EFI_BLACK,
+        );
+        let key = keyboard.wait_for_key();
+        if key.unicode_char != b'y' as u16 && key.unicode_char != b'Y' as u16 {
+            return;
+        }
+
+        let Ok(block_io_ptr) = crate::uefi::disk::get_disk_protocol(bs, self.current_disk_index)
+        else {
+            finish(screen, keyboard, 8, "[ERR] Failed to access disk");
+            return;
+        };
+        let block_io = unsafe { &mut *block_io_ptr };
+        let Ok(adapter) = UefiBlockIoAdapter::new(block_io) else {
+            finish(screen, keyboard, 8, "[ERR] Failed to create adapter");
+            return;
+        };
+
+        let attributes = if hidden {
+            partition.attributes & !CHUNK_HIDDEN
+        } else {
+            partition.attributes | CHUNK_HIDDEN
+        };
+        match gpt_ops::set_partition_attributes(adapter, partition.index as usize, attributes) {
+            Ok(()) => finish(screen, keyboard, 8, &format!("[OK] {} done", action)),
+            Err(e) => finish(
+                screen,
+                keyboard,
+                8,
+                &format!("[ERR] {} failed: {:?}", action, e),
+            ),
+        }
+    }
+}
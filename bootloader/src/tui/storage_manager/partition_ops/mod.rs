@@ -1,3 +1,5 @@
+mod compact;
 mod create;
 mod delete;
+mod export;
 mod shrink;
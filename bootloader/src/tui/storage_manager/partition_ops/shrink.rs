@@ -1,4 +1,4 @@
-use super::super::StorageManager;
+use super::super::{guard, StorageManager};
 use crate::tui::input::Keyboard;
 use crate::tui::renderer::{Screen, EFI_BLACK, EFI_DARKGREEN, EFI_GREEN, EFI_LIGHTGREEN};
 use crate::tui::widgets::textbox::TextBox;
@@ -102,7 +102,7 @@ impl StorageManager {
 
         for byte in size_text.bytes() {
             if (b'0'..=b'9').contains(&byte) {
-                new_size_mb = new_size_mb * 10 + (byte - b'0') as u64;
+                new_size_mb = new_size_mb.saturating_mul(10).saturating_add((byte - b'0') as u64);
             }
         }
 
@@ -144,11 +144,16 @@ impl StorageManager {
         if key.unicode_char != b'y' as u16 && key.unicode_char != b'Y' as u16 {
             return;
         }
+        let protection = self.partition_protection(partition.start_lba);
+        if !guard::confirm_protected(protection, screen, keyboard) {
+            return;
+        }
 
         // Step 3: Perform shrink
         screen.clear();
         let shrinking = "Shrinking partition...";
         screen.put_str_at(screen.center_x(shrinking.len()), 5, shrinking, EFI_LIGHTGREEN, EFI_BLACK);
+        screen.present();
 
         // Get disk access
         let block_io_ptr = match crate::uefi::disk::get_disk_protocol(bs, self.current_disk_index) {
@@ -1,5 +1,9 @@
 use super::super::StorageManager;
 use crate::tui::renderer::{Screen, EFI_BLACK, EFI_CYAN, EFI_DARKGREEN, EFI_GREEN, EFI_LIGHTGREEN};
+use alloc::format;
+
+/// Model column width; ATA models can be 40 characters.
+const MODEL_WIDTH: usize = 30;
 
 impl StorageManager {
     pub(in super::super) fn render_disk_list(&self, screen: &mut Screen) {
@@ -38,23 +42,18 @@ impl StorageManager {
         );
 
         // Table with dynamic centering
-        let table_width = 70;
+        let table_width = 76;
         let table_x = screen.center_x(table_width);
         let table_y = 6;
 
         screen.put_str_at(table_x, table_y, "IDX", EFI_LIGHTGREEN, EFI_BLACK);
-        screen.put_str_at(table_x + 7, table_y, "SIZE (MB)", EFI_LIGHTGREEN, EFI_BLACK);
-        screen.put_str_at(
-            table_x + 22,
-            table_y,
-            "BLOCK SIZE",
-            EFI_LIGHTGREEN,
-            EFI_BLACK,
-        );
-        screen.put_str_at(table_x + 37, table_y, "TYPE", EFI_LIGHTGREEN, EFI_BLACK);
-        screen.put_str_at(table_x + 52, table_y, "STATUS", EFI_LIGHTGREEN, EFI_BLACK);
+        screen.put_str_at(table_x + 5, table_y, "MODEL", EFI_LIGHTGREEN, EFI_BLACK);
+        screen.put_str_at(table_x + 36, table_y, "SIZE", EFI_LIGHTGREEN, EFI_BLACK);
+        screen.put_str_at(table_x + 47, table_y, "BLOCK", EFI_LIGHTGREEN, EFI_BLACK);
+        screen.put_str_at(table_x + 54, table_y, "TYPE", EFI_LIGHTGREEN, EFI_BLACK);
+        screen.put_str_at(table_x + 65, table_y, "STATUS", EFI_LIGHTGREEN, EFI_BLACK);
 
-        let sep = "======================================================================";
+        let sep = "============================================================================";
         screen.put_str_at(table_x, table_y + 1, sep, EFI_GREEN, EFI_BLACK);
 
         let disk_count = self.disk_manager.disk_count();
@@ -81,20 +80,20 @@ impl StorageManager {
                     EFI_BLACK,
                 );
 
-                let mut size_buf = [0u8; 16];
-                let size_len = Self::format_number(disk.size_mb(), &mut size_buf);
-                screen.put_str_at(
-                    table_x + 7,
-                    entry_y,
-                    core::str::from_utf8(&size_buf[..size_len]).unwrap_or("?"),
-                    color,
-                    EFI_BLACK,
-                );
+                let model = match disk.identity.model() {
+                    "" => "Unknown",
+                    model => model,
+                };
+                let model = &model[..model.len().min(MODEL_WIDTH)];
+                screen.put_str_at(table_x + 5, entry_y, model, color, EFI_BLACK);
+
+                let size = format!("{}", disk.capacity());
+                screen.put_str_at(table_x + 36, entry_y, &size, color, EFI_BLACK);
 
                 let mut bs_buf = [0u8; 16];
                 let bs_len = Self::format_number(disk.block_size as u64, &mut bs_buf);
                 screen.put_str_at(
-                    table_x + 22,
+                    table_x + 47,
                     entry_y,
                     core::str::from_utf8(&bs_buf[..bs_len]).unwrap_or("?"),
                     color,
@@ -102,19 +101,37 @@ impl StorageManager {
                 );
 
                 let disk_type = if disk.removable { "Removable" } else { "Fixed" };
-                screen.put_str_at(table_x + 37, entry_y, disk_type, color, EFI_BLACK);
+                screen.put_str_at(table_x + 54, entry_y, disk_type, color, EFI_BLACK);
 
-                let status = if disk.read_only {
+                let status = if disk.boot_disk {
+                    "Boot disk"
+                } else if disk.read_only {
                     "Read-Only"
                 } else {
                     "Read/Write"
                 };
-                screen.put_str_at(table_x + 52, entry_y, status, color, EFI_BLACK);
+                screen.put_str_at(table_x + 65, entry_y, status, color, EFI_BLACK);
             }
         }
 
-        let status_y = table_y + 2 + disk_count + 1;
-        let help_text = "[UP/DOWN] Navigate | [ENTER] View Partitions | [ESC] Back";
+        // Serial numbers are long; show the selected disk's below the table
+        if let Some(disk) = self.disk_manager.get_disk(self.selected_disk) {
+            let serial = match disk.identity.serial() {
+                "" => "unknown",
+                serial => serial,
+            };
+            let serial = format!("Serial: {}", serial);
+            screen.put_str_at(
+                table_x,
+                table_y + 2 + disk_count + 1,
+                &serial,
+                EFI_DARKGREEN,
+                EFI_BLACK,
+            );
+        }
+
+        let status_y = table_y + 2 + disk_count + 3;
+        let help_text = "[UP/DOWN] Navigate | [ENTER] View Partitions | [ESC] Back | [?] Help";
         screen.put_str_at(
             screen.center_x(help_text.len()),
             status_y,
@@ -1,5 +1,6 @@
 use super::super::StorageManager;
 use crate::tui::renderer::{Screen, EFI_BLACK, EFI_CYAN, EFI_DARKGREEN, EFI_GREEN, EFI_LIGHTGREEN};
+use morpheus_core::disk::guard::Protection;
 
 impl StorageManager {
     pub(in super::super) fn render_partition_view(&self, screen: &mut Screen) {
@@ -123,6 +124,10 @@ impl StorageManager {
                 );
 
                 screen.put_str_at(table_x + 7, entry_y, part.type_name(), color, EFI_BLACK);
+                if self.partition_protection(part.start_lba) == Protection::BootEsp {
+                    let x = table_x + 7 + part.type_name().len();
+                    screen.put_str_at(x, entry_y, " (boot)", color, EFI_BLACK);
+                }
 
                 let mut start_buf = [0u8; 20];
                 let start_len = Self::format_number(part.start_lba, &mut start_buf);
@@ -160,7 +165,7 @@ impl StorageManager {
 
         let status_y = table_y + 2 + row_count + 1;
         let help_text =
-            "[UP/DOWN] Navigate | [N] New | [F] Format | [S] Shrink | [D] Delete | [ESC] Back";
+            "[UP/DOWN] Navigate | [N] New | [F] Format | [S] Shrink | [D] Delete | [O] Compact | [K] Check | [X] Export | [ESC] Back";
         screen.put_str_at(
             screen.center_x(help_text.len()),
             status_y,
@@ -0,0 +1,366 @@
+//! UEFI variable browser.
+//!
+//! Lists every variable with its vendor GUID, size and attributes, shows
+//! one as a hex/ASCII dump, and deletes those that are safe to delete:
+//! stale Boot#### entries left behind by old installs and vendor-specific
+//! variables. Secure Boot keys and databases, the rest of the global boot
+//! configuration, volatile variables and the Boot#### entry we were
+//! started from are refused.
+
+use crate::tui::input::Keyboard;
+use crate::tui::keymap::{self, Bindings, Command, Key, KeyBinding};
+use crate::tui::renderer::{
+    Screen, EFI_BLACK, EFI_DARKGREEN, EFI_GREEN, EFI_LIGHTGREEN, EFI_LIGHTRED, EFI_YELLOW,
+};
+use crate::tui::screensaver::Event;
+use crate::tui::widgets::textview;
+use crate::uefi::runtime::{
+    self, RuntimeServices, EFI_GLOBAL_VARIABLE_GUID, EFI_IMAGE_SECURITY_DATABASE_GUID,
+    EFI_VARIABLE_APPEND_WRITE, EFI_VARIABLE_AUTHENTICATED_WRITE_ACCESS,
+    EFI_VARIABLE_BOOTSERVICE_ACCESS, EFI_VARIABLE_HARDWARE_ERROR_RECORD, EFI_VARIABLE_NON_VOLATILE,
+    EFI_VARIABLE_RUNTIME_ACCESS, EFI_VARIABLE_TIME_BASED_AUTHENTICATED_WRITE_ACCESS,
+};
+use crate::BootServices;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+const BINDINGS: Bindings = Bindings {
+    title: "UEFI Variables",
+    keys: &[
+        KeyBinding::new(&[Key::Up], Command::Up, "Previous variable"),
+        KeyBinding::new(&[Key::Down], Command::Down, "Next variable"),
+        KeyBinding::new(&[Key::PageUp], Command::PageUp, "Page up"),
+        KeyBinding::new(&[Key::PageDown], Command::PageDown, "Page down"),
+        KeyBinding::new(&[Key::Enter], Command::Details, "Show contents"),
+        KeyBinding::new(&[Key::Char(b'd')], Command::Delete, "Delete variable"),
+        KeyBinding::new(&[Key::Char(b'r')], Command::Rescan, "Reload list"),
+        KeyBinding::new(
+            &[Key::Esc, Key::Backspace],
+            Command::Back,
+            "Back to main menu",
+        ),
+    ],
+};
+
+const CONFIRM_BINDINGS: Bindings = Bindings {
+    title: "Confirm",
+    keys: &[
+        KeyBinding::new(&[Key::Char(b'y')], Command::Yes, "Delete"),
+        KeyBinding::new(&[Key::Char(b'n'), Key::Esc], Command::No, "Cancel"),
+    ],
+};
+
+/// First list row
+const LIST_Y: usize = 4;
+
+/// Rows below the list: blank, status, blank, footer
+const FOOTER_ROWS: usize = 4;
+
+/// Bytes per hex dump line
+const DUMP_WIDTH: usize = 16;
+
+struct Variable {
+    name: String,
+    guid: [u8; 16],
+    attributes: u32,
+    data: Vec<u8>,
+}
+
+impl Variable {
+    fn boot_option(&self) -> Option<u16> {
+        if self.guid == EFI_GLOBAL_VARIABLE_GUID {
+            runtime::boot_option_number(&self.name)
+        } else {
+            None
+        }
+    }
+
+    /// Why this variable must not be deleted, or `None` if it may be
+    fn protected(&self, boot_current: Option<u16>) -> Option<&'static str> {
+        if self.attributes & EFI_VARIABLE_NON_VOLATILE == 0 {
+            return Some("Volatile variable; the firmware owns it");
+        }
+        if self.guid == EFI_IMAGE_SECURITY_DATABASE_GUID
+            || self.attributes
+                & (EFI_VARIABLE_AUTHENTICATED_WRITE_ACCESS
+                    | EFI_VARIABLE_TIME_BASED_AUTHENTICATED_WRITE_ACCESS)
+                != 0
+        {
+            return Some("Secure Boot variable");
+        }
+        if self.guid == EFI_GLOBAL_VARIABLE_GUID {
+            return match self.boot_option() {
+                Some(number) if Some(number) == boot_current => {
+                    Some("This is the boot entry MorpheusX was started from")
+                }
+                Some(_) => None,
+                None => Some("Part of the firmware's boot configuration"),
+            };
+        }
+        None
+    }
+}
+
+/// Every variable the firmware lists, with its contents
+fn load(rt: &RuntimeServices) -> Vec<Variable> {
+    rt.variable_names()
+        .into_iter()
+        .filter_map(|(name, guid)| {
+            let (attributes, data) = rt.get_variable(&name, &guid).ok()?;
+            Some(Variable {
+                name,
+                guid,
+                attributes,
+                data,
+            })
+        })
+        .collect()
+}
+
+/// "8BE4DF61-93CA-11D2-AA0D-00E098032B8C"
+fn format_guid(guid: &[u8; 16]) -> String {
+    format!(
+        "{:08X}-{:04X}-{:04X}-{:02X}{:02X}-{:02X}{:02X}{:02X}{:02X}{:02X}{:02X}",
+        u32::from_le_bytes([guid[0], guid[1], guid[2], guid[3]]),
+        u16::from_le_bytes([guid[4], guid[5]]),
+        u16::from_le_bytes([guid[6], guid[7]]),
+        guid[8],
+        guid[9],
+        guid[10],
+        guid[11],
+        guid[12],
+        guid[13],
+        guid[14],
+        guid[15]
+    )
+}
+
+/// Short vendor name for the list: the well-known ones by name, others
+/// by the first group of the GUID
+fn vendor_label(guid: &[u8; 16]) -> String {
+    match *guid {
+        EFI_GLOBAL_VARIABLE_GUID => String::from("EFI global"),
+        EFI_IMAGE_SECURITY_DATABASE_GUID => String::from("Security DB"),
+        _ => format!("{}-...", &format_guid(guid)[..8]),
+    }
+}
+
+/// "NV BS RT" and friends
+fn format_attributes(attributes: u32) -> String {
+    const NAMES: [(u32, &str); 7] = [
+        (EFI_VARIABLE_NON_VOLATILE, "NV"),
+        (EFI_VARIABLE_BOOTSERVICE_ACCESS, "BS"),
+        (EFI_VARIABLE_RUNTIME_ACCESS, "RT"),
+        (EFI_VARIABLE_HARDWARE_ERROR_RECORD, "HW"),
+        (EFI_VARIABLE_AUTHENTICATED_WRITE_ACCESS, "AW"),
+        (EFI_VARIABLE_TIME_BASED_AUTHENTICATED_WRITE_ACCESS, "AT"),
+        (EFI_VARIABLE_APPEND_WRITE, "AP"),
+    ];
+    let names: Vec<&str> = NAMES
+        .iter()
+        .filter(|(bit, _)| attributes & bit != 0)
+        .map(|(_, name)| *name)
+        .collect();
+    names.join(" ")
+}
+
+/// Offset, hex bytes and printable ASCII, sixteen bytes a line
+fn hex_dump(data: &[u8]) -> String {
+    let mut text = String::new();
+    for (line, chunk) in data.chunks(DUMP_WIDTH).enumerate() {
+        text.push_str(&format!("{:04X}  ", line * DUMP_WIDTH));
+        for i in 0..DUMP_WIDTH {
+            match chunk.get(i) {
+                Some(byte) => text.push_str(&format!("{:02X} ", byte)),
+                None => text.push_str("   "),
+            }
+        }
+        text.push_str(" |");
+        text.extend(chunk.iter().map(|&b| {
+            if b.is_ascii_graphic() || b == b' ' {
+                b as char
+            } else {
+                '.'
+            }
+        }));
+        text.push_str("|\n");
+    }
+    text
+}
+
+fn details(var: &Variable) -> String {
+    let mut text = format!(
+        "Name:        {}\nVendor GUID: {}\nAttributes:  {:#010x} ({})\nSize:        {} bytes\n",
+        var.name,
+        format_guid(&var.guid),
+        var.attributes,
+        format_attributes(var.attributes),
+        var.data.len()
+    );
+    if var.boot_option().is_some() {
+        if let Some(description) = runtime::load_option_description(&var.data) {
+            text.push_str(&format!("Description: {}\n", description));
+        }
+    }
+    text.push('\n');
+    text.push_str(&hex_dump(&var.data));
+    text
+}
+
+/// Show the variable browser until the user backs out.
+pub fn run(bs: &BootServices, image_handle: *mut (), screen: &mut Screen, keyboard: &mut Keyboard) {
+    let Some(rt) = (unsafe { runtime::runtime_services(bs, image_handle) }) else {
+        textview::show(
+            screen,
+            keyboard,
+            "UEFI variables",
+            "Runtime services not found.",
+        );
+        return;
+    };
+
+    let mut vars = load(rt);
+    let boot_current = rt.boot_current();
+    let mut selected = 0;
+    let mut top = 0;
+    let mut status = format!("{} variables", vars.len());
+
+    screen.clear();
+    loop {
+        let rows = screen.height().saturating_sub(LIST_Y + FOOTER_ROWS).max(1);
+        selected = selected.min(vars.len().saturating_sub(1));
+        if selected < top {
+            top = selected;
+        } else if selected >= top + rows {
+            top = selected + 1 - rows;
+        }
+        render(screen, &vars, selected, top, rows, &status);
+
+        let key = match keymap::poll(screen, keyboard, &BINDINGS) {
+            Some(Event::Key(key)) => key,
+            Some(Event::Redraw) => {
+                screen.clear();
+                continue;
+            }
+            None => continue,
+        };
+
+        match BINDINGS.lookup(&key) {
+            Some(Command::Up) => selected = selected.saturating_sub(1),
+            Some(Command::Down) => selected += 1,
+            Some(Command::PageUp) => selected = selected.saturating_sub(rows),
+            Some(Command::PageDown) => selected += rows,
+            Some(Command::Back) => return,
+            Some(Command::Rescan) => {
+                vars = load(rt);
+                status = format!("{} variables", vars.len());
+                screen.clear();
+            }
+            Some(Command::Details) => {
+                if let Some(var) = vars.get(selected) {
+                    textview::show(screen, keyboard, &var.name, &details(var));
+                    screen.clear();
+                }
+            }
+            Some(Command::Delete) => {
+                let Some(var) = vars.get(selected) else {
+                    continue;
+                };
+                if let Some(reason) = var.protected(boot_current) {
+                    status = format!("Not deleting {}: {}", var.name, reason);
+                    continue;
+                }
+                if !confirm(screen, keyboard, &var.name) {
+                    status = String::new();
+                    continue;
+                }
+                let result = match var.boot_option() {
+                    Some(number) => rt.remove_boot_option(number),
+                    None => rt.delete_variable(&var.name, &var.guid),
+                };
+                status = match result {
+                    Ok(()) => format!("Deleted {}", var.name),
+                    Err(code) => format!("Could not delete {} ({:#x})", var.name, code),
+                };
+                vars = load(rt);
+                screen.clear();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Ask before deleting `name`
+fn confirm(screen: &mut Screen, keyboard: &mut Keyboard, name: &str) -> bool {
+    let y = screen.height().saturating_sub(3);
+    let prompt = format!("Delete {}? [Y] Yes  [N] No", name);
+    screen.put_str_at(2, y, &format!("{:<70}", prompt), EFI_YELLOW, EFI_BLACK);
+    loop {
+        match keymap::poll(screen, keyboard, &CONFIRM_BINDINGS) {
+            Some(Event::Key(key)) => match CONFIRM_BINDINGS.lookup(&key) {
+                Some(Command::Yes) => return true,
+                Some(Command::No) => return false,
+                _ => {}
+            },
+            Some(Event::Redraw) => {
+                screen.put_str_at(2, y, &format!("{:<70}", prompt), EFI_YELLOW, EFI_BLACK)
+            }
+            None => {}
+        }
+    }
+}
+
+fn render(
+    screen: &mut Screen,
+    vars: &[Variable],
+    selected: usize,
+    top: usize,
+    rows: usize,
+    status: &str,
+) {
+    screen.put_str_at(2, 1, "=== UEFI VARIABLES ===", EFI_LIGHTGREEN, EFI_BLACK);
+    screen.put_str_at(
+        2,
+        LIST_Y - 1,
+        &format!(
+            "  {:<32} {:<14} {:>6}  {}",
+            "Name", "Vendor", "Size", "Attributes"
+        ),
+        EFI_DARKGREEN,
+        EFI_BLACK,
+    );
+
+    for row in 0..rows {
+        let line = match vars.get(top + row) {
+            Some(var) => {
+                let name: String = var.name.chars().take(32).collect();
+                format!(
+                    "{} {:<32} {:<14} {:>6}  {:<14}",
+                    if top + row == selected { ">" } else { " " },
+                    name,
+                    vendor_label(&var.guid),
+                    var.data.len(),
+                    format_attributes(var.attributes)
+                )
+            }
+            None => format!("{:<74}", ""),
+        };
+        let color = if top + row == selected {
+            EFI_LIGHTGREEN
+        } else {
+            EFI_GREEN
+        };
+        screen.put_str_at(2, LIST_Y + row, &line, color, EFI_BLACK);
+    }
+
+    let y = LIST_Y + rows + 1;
+    screen.put_str_at(2, y, &format!("{:<74}", status), EFI_LIGHTRED, EFI_BLACK);
+    screen.put_str_at(
+        2,
+        y + 2,
+        "[UP/DOWN] Select  [ENTER] Contents  [D] Delete  [R] Reload  [ESC] Back",
+        EFI_DARKGREEN,
+        EFI_BLACK,
+    );
+}
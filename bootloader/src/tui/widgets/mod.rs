@@ -5,3 +5,4 @@ pub mod menu;
 pub mod panel;
 pub mod progressbar;
 pub mod textbox;
+pub mod textview;
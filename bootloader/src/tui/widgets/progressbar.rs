@@ -53,5 +53,8 @@ impl ProgressBar {
 
         let text = core::str::from_utf8(&buf[..idx]).unwrap_or("");
         screen.put_str_at(self.x, self.y + 1, text, EFI_LIGHTGREEN, EFI_BLACK);
+
+        // Progress is drawn while work runs, not while waiting for a key
+        screen.present();
     }
 }
@@ -0,0 +1,362 @@
+//! Scrollable read-only text viewer.
+//!
+//! [`TextView`] wraps arbitrary text (logs, checksum files, release notes,
+//! crash reports) to its width and shows one window of it. [`show`] runs it
+//! full screen with scrolling and search until the user leaves.
+
+use crate::tui::input::Keyboard;
+use crate::tui::keymap::{self, Bindings, Command, Key, KeyBinding};
+use crate::tui::renderer::{
+    Screen, EFI_BLACK, EFI_DARKGREEN, EFI_GREEN, EFI_LIGHTGREEN, EFI_YELLOW,
+};
+use crate::tui::screensaver::Event;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Columns a tab advances to a multiple of.
+const TAB_WIDTH: usize = 4;
+
+/// Longest search term.
+const MAX_QUERY: usize = 40;
+
+pub struct TextView {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    /// Text after wrapping, one entry per screen row
+    lines: Vec<String>,
+    /// First visible line
+    top: usize,
+    /// Line of the last search hit
+    found: Option<usize>,
+}
+
+impl TextView {
+    pub fn new(x: usize, y: usize, width: usize, height: usize, text: &str) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+            lines: wrap(text, width),
+            top: 0,
+            found: None,
+        }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// First and one-past-last visible line.
+    pub fn visible(&self) -> (usize, usize) {
+        (self.top, (self.top + self.height).min(self.lines.len()))
+    }
+
+    fn max_top(&self) -> usize {
+        self.lines.len().saturating_sub(self.height)
+    }
+
+    pub fn scroll_up(&mut self, lines: usize) {
+        self.top = self.top.saturating_sub(lines);
+    }
+
+    pub fn scroll_down(&mut self, lines: usize) {
+        self.top = (self.top + lines).min(self.max_top());
+    }
+
+    pub fn page_up(&mut self) {
+        self.scroll_up(self.height.saturating_sub(1).max(1));
+    }
+
+    pub fn page_down(&mut self) {
+        self.scroll_down(self.height.saturating_sub(1).max(1));
+    }
+
+    pub fn to_top(&mut self) {
+        self.top = 0;
+    }
+
+    pub fn to_bottom(&mut self) {
+        self.top = self.max_top();
+    }
+
+    /// Jump to the next line containing `query` (ASCII case-insensitive),
+    /// wrapping around past the end. Returns false if nothing matches.
+    pub fn find_next(&mut self, query: &str) -> bool {
+        if query.is_empty() || self.lines.is_empty() {
+            return false;
+        }
+        let start = self.found.map_or(self.top, |line| line + 1);
+        let count = self.lines.len();
+
+        for offset in 0..count {
+            let line = (start + offset) % count;
+            if contains_ignore_case(&self.lines[line], query) {
+                self.found = Some(line);
+                // Keep the hit in view, scrolling as little as possible
+                if line < self.top || line >= self.top + self.height {
+                    self.top = line.min(self.max_top());
+                }
+                return true;
+            }
+        }
+        self.found = None;
+        false
+    }
+
+    pub fn render(&self, screen: &mut Screen) {
+        let blank = " ".repeat(self.width);
+        for row in 0..self.height {
+            let y = self.y + row;
+            screen.put_str_at(self.x, y, &blank, EFI_GREEN, EFI_BLACK);
+            if let Some(line) = self.lines.get(self.top + row) {
+                let color = if self.found == Some(self.top + row) {
+                    EFI_LIGHTGREEN
+                } else {
+                    EFI_GREEN
+                };
+                screen.put_str_at(self.x, y, line, color, EFI_BLACK);
+            }
+        }
+    }
+}
+
+/// Wrap `text` to `width` columns: at the last space that fits, or mid-word
+/// if a word is longer than a line. Tabs are expanded and other control
+/// characters dropped so nothing moves the cursor.
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines: Vec<String> = Vec::new();
+
+    for raw in text.split('\n') {
+        let mut line = String::new();
+        for ch in raw.chars() {
+            match ch {
+                '\t' => {
+                    let pad = TAB_WIDTH - line.chars().count() % TAB_WIDTH;
+                    line.extend(core::iter::repeat(' ').take(pad));
+                }
+                c if c.is_control() => {}
+                c => line.push(c),
+            }
+        }
+
+        let mut rest: Vec<char> = line.chars().collect();
+        while rest.len() > width {
+            let cut = rest[..=width]
+                .iter()
+                .rposition(|&c| c == ' ')
+                .filter(|&pos| pos > 0)
+                .unwrap_or(width);
+            lines.push(rest[..cut].iter().collect());
+            // The space a line broke at isn't carried over
+            let next = if rest[cut] == ' ' { cut + 1 } else { cut };
+            rest.drain(..next);
+        }
+        lines.push(rest.into_iter().collect());
+    }
+
+    // A trailing newline doesn't make an extra line
+    if lines.len() > 1 && lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+    lines
+}
+
+fn contains_ignore_case(haystack: &str, needle: &str) -> bool {
+    let haystack = haystack.as_bytes();
+    let needle = needle.as_bytes();
+    haystack
+        .windows(needle.len())
+        .any(|window| window.eq_ignore_ascii_case(needle))
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// FULL-SCREEN VIEWER
+// ═══════════════════════════════════════════════════════════════════════════
+
+const BINDINGS: Bindings = Bindings {
+    title: "Viewer",
+    keys: &[
+        KeyBinding::new(&[Key::Up], Command::Up, "Scroll up"),
+        KeyBinding::new(&[Key::Down], Command::Down, "Scroll down"),
+        KeyBinding::new(&[Key::PageUp], Command::PageUp, "Page up"),
+        KeyBinding::new(
+            &[Key::PageDown, Key::Char(b' ')],
+            Command::PageDown,
+            "Page down",
+        ),
+        KeyBinding::new(&[Key::Home], Command::Top, "Go to start"),
+        KeyBinding::new(&[Key::End], Command::Bottom, "Go to end"),
+        KeyBinding::new(&[Key::Char(b'/')], Command::Search, "Search"),
+        KeyBinding::new(&[Key::Char(b'n')], Command::NextMatch, "Next match"),
+        KeyBinding::new(&[Key::Esc, Key::Char(b'q')], Command::Back, "Close viewer"),
+    ],
+};
+
+/// Show `text` full screen until the user closes it. The caller redraws its
+/// screen afterwards.
+pub fn show(screen: &mut Screen, keyboard: &mut Keyboard, title: &str, text: &str) {
+    // Title and divider on top, divider and footer at the bottom
+    let height = screen.height().saturating_sub(4).max(1);
+    let mut view = TextView::new(1, 2, screen.width().saturating_sub(2), height, text);
+    let mut query = String::new();
+    let mut message: Option<String> = None;
+
+    screen.clear();
+    draw(screen, &view, title, message.as_deref());
+
+    loop {
+        let key = match keymap::poll(screen, keyboard, &BINDINGS) {
+            Some(Event::Key(key)) => key,
+            Some(Event::Redraw) => {
+                screen.clear();
+                draw(screen, &view, title, message.as_deref());
+                continue;
+            }
+            None => continue,
+        };
+
+        message = None;
+        match BINDINGS.lookup(&key) {
+            Some(Command::Up) => view.scroll_up(1),
+            Some(Command::Down) => view.scroll_down(1),
+            Some(Command::PageUp) => view.page_up(),
+            Some(Command::PageDown) => view.page_down(),
+            Some(Command::Top) => view.to_top(),
+            Some(Command::Bottom) => view.to_bottom(),
+            Some(Command::Search) => {
+                if let Some(entered) = prompt(screen, keyboard, &query) {
+                    query = entered;
+                    view.found = None;
+                    if !view.find_next(&query) {
+                        message = Some(format!("Not found: {}", query));
+                    }
+                }
+            }
+            Some(Command::NextMatch) if !query.is_empty() => {
+                if !view.find_next(&query) {
+                    message = Some(format!("Not found: {}", query));
+                }
+            }
+            Some(Command::Back) => return,
+            _ => continue,
+        }
+        draw(screen, &view, title, message.as_deref());
+    }
+}
+
+fn draw(screen: &mut Screen, view: &TextView, title: &str, message: Option<&str>) {
+    let width = screen.width();
+    let bottom = screen.height().saturating_sub(1);
+    let rule = "=".repeat(width.saturating_sub(1));
+
+    screen.put_str_at(
+        1,
+        0,
+        &" ".repeat(width.saturating_sub(2)),
+        EFI_BLACK,
+        EFI_BLACK,
+    );
+    screen.put_str_at(1, 0, title, EFI_LIGHTGREEN, EFI_BLACK);
+    screen.put_str_at(0, 1, &rule, EFI_GREEN, EFI_BLACK);
+
+    view.render(screen);
+
+    screen.put_str_at(0, bottom - 1, &rule, EFI_GREEN, EFI_BLACK);
+    footer(screen, bottom, EFI_DARKGREEN, "");
+    match message {
+        Some(message) => footer(screen, bottom, EFI_YELLOW, message),
+        None => {
+            let (first, last) = view.visible();
+            let position = format!(
+                "Lines {}-{} of {}  [/] Search  [N] Next  [?] Help  [ESC] Close",
+                first + 1,
+                last,
+                view.line_count()
+            );
+            footer(screen, bottom, EFI_DARKGREEN, &position);
+        }
+    }
+}
+
+/// Write `text` over a blanked footer row. The last column is left alone
+/// so the console doesn't scroll.
+fn footer(screen: &mut Screen, y: usize, color: usize, text: &str) {
+    let width = screen.width().saturating_sub(2);
+    screen.put_str_at(1, y, &" ".repeat(width), color, EFI_BLACK);
+    let shown: String = text.chars().take(width).collect();
+    screen.put_str_at(1, y, &shown, color, EFI_BLACK);
+}
+
+/// Read a search term in the footer. `None` if cancelled with ESC.
+fn prompt(screen: &mut Screen, keyboard: &mut Keyboard, initial: &str) -> Option<String> {
+    let bottom = screen.height().saturating_sub(1);
+    let mut query = String::from(initial);
+
+    loop {
+        footer(
+            screen,
+            bottom,
+            EFI_LIGHTGREEN,
+            &format!("Search: {}_", query),
+        );
+        let key = keyboard.wait_for_key();
+
+        if Key::Esc.matches(&key) {
+            return None;
+        } else if Key::Enter.matches(&key) {
+            return Some(query);
+        } else if Key::Backspace.matches(&key) {
+            query.pop();
+        } else if key.scan_code == 0
+            && (0x20..0x7F).contains(&key.unicode_char)
+            && query.len() < MAX_QUERY
+        {
+            query.push(key.unicode_char as u8 as char);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_breaks_at_spaces() {
+        assert_eq!(wrap("one two three", 8), ["one two", "three"]);
+        assert_eq!(wrap("abcdefghij", 4), ["abcd", "efgh", "ij"]);
+        assert_eq!(wrap("a\n\nb\n", 10), ["a", "", "b"]);
+        assert_eq!(wrap("\tx\r", 10), ["    x"]);
+    }
+
+    #[test]
+    fn test_find_next_wraps_around() {
+        let text = "alpha\nbeta\ngamma\nBETA\ndelta";
+        let mut view = TextView::new(0, 0, 20, 2, text);
+
+        assert!(view.find_next("beta"));
+        assert_eq!(view.found, Some(1));
+        assert!(view.find_next("beta"));
+        assert_eq!(view.found, Some(3));
+        assert_eq!(view.visible(), (3, 5));
+        assert!(view.find_next("beta"));
+        assert_eq!(view.found, Some(1));
+        assert!(!view.find_next("omega"));
+    }
+
+    #[test]
+    fn test_scroll_clamps() {
+        let text = "1\n2\n3\n4\n5";
+        let mut view = TextView::new(0, 0, 10, 2, text);
+        view.page_down();
+        view.page_down();
+        view.page_down();
+        assert_eq!(view.visible(), (3, 5));
+        view.scroll_up(10);
+        assert_eq!(view.visible(), (0, 2));
+    }
+}
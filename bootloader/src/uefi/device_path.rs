@@ -0,0 +1,101 @@
+// UEFI Device Path walking
+//
+// A device path is a packed list of variable-length nodes, each starting
+// with a 4-byte header (type, subtype, length), ended by an End node.
+
+use crate::BootServices;
+
+pub const EFI_DEVICE_PATH_PROTOCOL_GUID: [u8; 16] = [
+    0x91, 0x6e, 0x57, 0x09, 0x3f, 0x6d, 0xd2, 0x11, 0x8e, 0x39, 0x00, 0xa0, 0xc9, 0x69, 0x72, 0x3b,
+];
+
+// Node types
+pub const MESSAGING: u8 = 0x03;
+pub const MEDIA: u8 = 0x04;
+pub const END: u8 = 0x7f;
+
+// Media subtypes
+pub const MEDIA_HARD_DRIVE: u8 = 0x01;
+pub const MEDIA_CDROM: u8 = 0x02;
+
+/// Device path of `handle`, if it has one.
+pub fn of_handle(bs: &BootServices, handle: *mut ()) -> Option<*const u8> {
+    let mut path: *mut () = core::ptr::null_mut();
+    if (bs.handle_protocol)(handle, &EFI_DEVICE_PATH_PROTOCOL_GUID, &mut path) != 0
+        || path.is_null()
+    {
+        return None;
+    }
+    Some(path as *const u8)
+}
+
+/// One device path node.
+pub struct Node {
+    pub node_type: u8,
+    pub sub_type: u8,
+    pub ptr: *const u8,
+    pub len: usize,
+}
+
+/// Iterator over the nodes of a device path, stopping at the End node.
+pub struct Nodes {
+    next: *const u8,
+}
+
+/// Walk the nodes of the device path at `path`.
+///
+/// # Safety
+/// `path` must point to a well-formed device path.
+pub unsafe fn nodes(path: *const u8) -> Nodes {
+    Nodes { next: path }
+}
+
+impl Iterator for Nodes {
+    type Item = Node;
+
+    fn next(&mut self) -> Option<Node> {
+        if self.next.is_null() {
+            return None;
+        }
+        let node = unsafe {
+            Node {
+                node_type: *self.next,
+                sub_type: *self.next.add(1),
+                ptr: self.next,
+                len: u16::from_le_bytes([*self.next.add(2), *self.next.add(3)]) as usize,
+            }
+        };
+        // A malformed length would loop forever
+        if node.node_type == END || node.len < 4 {
+            self.next = core::ptr::null();
+            return None;
+        }
+        self.next = unsafe { self.next.add(node.len) };
+        Some(node)
+    }
+}
+
+/// Whether `path` starts with every node of `prefix`, i.e. `path` is a
+/// device below `prefix` (a partition below its disk).
+///
+/// # Safety
+/// Both must point to well-formed device paths.
+pub unsafe fn starts_with(path: *const u8, prefix: *const u8) -> bool {
+    let prefix_len: usize = nodes(prefix).map(|n| n.len).sum();
+    let path_len: usize = nodes(path).map(|n| n.len).sum();
+    prefix_len > 0
+        && prefix_len <= path_len
+        && core::slice::from_raw_parts(path, prefix_len)
+            == core::slice::from_raw_parts(prefix, prefix_len)
+}
+
+/// Start LBA from the HardDrive media node of a partition's device path.
+///
+/// # Safety
+/// `path` must point to a well-formed device path.
+pub unsafe fn partition_start_lba(path: *const u8) -> Option<u64> {
+    nodes(path)
+        .find(|n| n.node_type == MEDIA && n.sub_type == MEDIA_HARD_DRIVE)
+        // Header (4) + PartitionNumber (4) + PartitionStart (8) + ...
+        .map(|n| (n.ptr.add(8) as *const u64).read_unaligned())
+}
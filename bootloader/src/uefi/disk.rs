@@ -1,9 +1,35 @@
 // UEFI-specific disk operations
 
 use super::block_io::{BlockIoProtocol, EFI_BLOCK_IO_PROTOCOL_GUID};
+use super::device_path;
+use super::disk_info::{critical_warning, identify_disk, is_removable_transport};
 use crate::BootServices;
+use core::sync::atomic::{AtomicPtr, Ordering};
 use morpheus_core::disk::manager::{DiskInfo, DiskManager};
 
+/// Partition handle MorpheusX was loaded from - set by efi_main
+static BOOT_DEVICE: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+
+/// Remember which partition the running image came from, so enumeration
+/// can flag its disk and ESP (call once at start of efi_main)
+pub fn set_boot_image(bs: &BootServices, image_handle: *mut ()) {
+    if let Ok(loaded_image) = unsafe { super::file_system::get_loaded_image(bs, image_handle) } {
+        let device = unsafe { (*loaded_image).device_handle };
+        BOOT_DEVICE.store(device, Ordering::SeqCst);
+    }
+}
+
+/// Flag `disk` if the boot partition sits on it
+fn mark_boot_disk(disk: &mut DiskInfo, bs: &BootServices, handle: *mut (), boot_path: *const u8) {
+    let Some(disk_path) = device_path::of_handle(bs, handle) else {
+        return;
+    };
+    if unsafe { device_path::starts_with(boot_path, disk_path) } {
+        disk.boot_disk = true;
+        disk.boot_esp_lba = unsafe { device_path::partition_start_lba(boot_path) };
+    }
+}
+
 /// Enumerate all physical disks in the system
 pub fn enumerate_disks(bs: &BootServices, manager: &mut DiskManager) -> Result<(), usize> {
     manager.clear();
@@ -47,6 +73,10 @@ pub fn enumerate_disks(bs: &BootServices, manager: &mut DiskManager) -> Result<(
     // Iterate through handles and find physical disks
     let handles = handle_buffer as *const *mut ();
     let handle_count = buffer_size / core::mem::size_of::<*mut ()>();
+    let boot_path = match BOOT_DEVICE.load(Ordering::SeqCst) {
+        device if device.is_null() => None,
+        device => device_path::of_handle(bs, device),
+    };
 
     for i in 0..handle_count {
         let handle = unsafe { *handles.add(i) };
@@ -61,13 +91,18 @@ pub fn enumerate_disks(bs: &BootServices, manager: &mut DiskManager) -> Result<(
 
             // Only add physical disks (not partitions)
             if !media.logical_partition && media.media_present {
-                let disk_info = DiskInfo::new(
+                let mut disk_info = DiskInfo::new(
                     media.media_id,
                     media.block_size,
                     media.last_block,
-                    media.removable_media,
+                    media.removable_media || is_removable_transport(bs, handle),
                     media.read_only,
                 );
+                disk_info.identity = identify_disk(bs, handle);
+                disk_info.critical_warning = critical_warning(bs, handle);
+                if let Some(boot_path) = boot_path {
+                    mark_boot_disk(&mut disk_info, bs, handle, boot_path);
+                }
 
                 let _ = manager.add_disk(disk_info);
             }
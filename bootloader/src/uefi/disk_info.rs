@@ -0,0 +1,278 @@
+// Disk identification - model, serial number and transport for a Block I/O handle
+//
+// ATA and SCSI/USB disks expose their identify data through the Disk Info
+// protocol. NVMe's Disk Info only returns namespace data, so the controller
+// is asked directly through NVM Express Pass Thru, which also serves the
+// SMART / Health log.
+//
+// Block I/O's RemovableMedia only covers drives with swappable media, so a
+// USB stick or SD card is recognised by its device path instead.
+
+use super::device_path::{self, MEDIA, MEDIA_CDROM, MESSAGING};
+use crate::BootServices;
+use morpheus_core::disk::health::{CriticalWarning, NVME_SMART_LOG_LEN};
+use morpheus_core::disk::identity::DeviceIdentity;
+
+pub const EFI_DISK_INFO_PROTOCOL_GUID: [u8; 16] = [
+    0x7f, 0xa6, 0x32, 0xd4, 0xdc, 0x14, 0x4b, 0x48, 0xb3, 0xbb, 0x3f, 0x02, 0x91, 0x84, 0x93, 0x27,
+];
+
+pub const EFI_NVM_EXPRESS_PASS_THRU_PROTOCOL_GUID: [u8; 16] = [
+    0x12, 0x83, 0xc7, 0x52, 0xdc, 0x8e, 0x33, 0x42, 0x98, 0xf2, 0x1a, 0x1a, 0xa5, 0xe3, 0x88, 0xa5,
+];
+
+// Disk Info interface types
+const DISK_INFO_IDE: [u8; 16] = [
+    0xe3, 0x8f, 0x94, 0x5e, 0xd3, 0x26, 0xb5, 0x42, 0xaf, 0x17, 0x61, 0x02, 0x87, 0x18, 0x8d, 0xec,
+];
+const DISK_INFO_AHCI: [u8; 16] = [
+    0x32, 0x89, 0x49, 0x9e, 0xbc, 0x4a, 0xaf, 0x45, 0xa3, 0x4d, 0x02, 0x47, 0x78, 0x7b, 0xe7, 0xc6,
+];
+const DISK_INFO_SCSI: [u8; 16] = [
+    0xaa, 0x4b, 0xf7, 0x08, 0x36, 0xea, 0xd9, 0x41, 0x95, 0x21, 0x21, 0xa7, 0x0f, 0x87, 0x80, 0xbc,
+];
+const DISK_INFO_USB: [u8; 16] = [
+    0x72, 0x15, 0x87, 0xcb, 0x1a, 0xc1, 0xb5, 0x47, 0xb4, 0x92, 0x67, 0x5e, 0xaf, 0xa7, 0x77, 0x27,
+];
+
+#[repr(C)]
+pub struct DiskInfoProtocol {
+    pub interface: [u8; 16],
+    pub inquiry: extern "efiapi" fn(*mut DiskInfoProtocol, *mut u8, *mut u32) -> usize,
+    pub identify: extern "efiapi" fn(*mut DiskInfoProtocol, *mut u8, *mut u32) -> usize,
+    pub sense_data: extern "efiapi" fn(*mut DiskInfoProtocol, *mut u8, *mut u32, *mut u8) -> usize,
+    pub which_ide: extern "efiapi" fn(*mut DiskInfoProtocol, *mut u32, *mut u32) -> usize,
+}
+
+#[repr(C)]
+pub struct NvmePassThruProtocol {
+    pub mode: *const NvmePassThruMode,
+    pub pass_thru: extern "efiapi" fn(
+        *mut NvmePassThruProtocol,
+        u32,                    // NamespaceId
+        *mut NvmeCommandPacket, // Packet
+        *mut (),                // Event (null = blocking)
+    ) -> usize,
+    pub get_next_namespace: usize,
+    pub build_device_path: usize,
+    pub get_namespace: usize,
+}
+
+#[repr(C)]
+pub struct NvmePassThruMode {
+    pub attributes: u32,
+    pub io_align: u32,
+    pub nvme_version: u32,
+}
+
+#[repr(C)]
+pub struct NvmeCommandPacket {
+    pub command_timeout: u64,
+    pub transfer_buffer: *mut u8,
+    pub transfer_length: u32,
+    pub metadata_buffer: *mut u8,
+    pub metadata_length: u32,
+    pub queue_type: u8,
+    pub command: *mut NvmeCommand,
+    pub completion: *mut NvmeCompletion,
+}
+
+#[repr(C)]
+#[derive(Default)]
+pub struct NvmeCommand {
+    pub cdw0: u32, // Opcode in bits 0-7
+    pub flags: u8,
+    pub nsid: u32,
+    pub cdw2: u32,
+    pub cdw3: u32,
+    pub cdw10: u32,
+    pub cdw11: u32,
+    pub cdw12: u32,
+    pub cdw13: u32,
+    pub cdw14: u32,
+    pub cdw15: u32,
+}
+
+#[repr(C)]
+#[derive(Default)]
+pub struct NvmeCompletion {
+    pub dw: [u32; 4],
+}
+
+const NVME_ADMIN_QUEUE: u8 = 0;
+const NVME_ADMIN_IDENTIFY: u32 = 0x06;
+const NVME_IDENTIFY_CONTROLLER: u32 = 1;
+const NVME_ADMIN_GET_LOG_PAGE: u32 = 0x02;
+const NVME_LOG_SMART: u32 = 0x02;
+const NVME_ALL_NAMESPACES: u32 = 0xFFFF_FFFF;
+const NVME_CDW10_VALID: u8 = 0x04;
+/// Pass Thru timeout, in 100ns units (1 second)
+const NVME_TIMEOUT: u64 = 10_000_000;
+
+// Messaging subtypes for pluggable transports
+const MSG_USB: u8 = 0x05;
+const MSG_USB_CLASS: u8 = 0x0f;
+const MSG_USB_WWID: u8 = 0x10;
+const MSG_SD: u8 = 0x1a;
+const MSG_EMMC: u8 = 0x1d;
+
+/// Whether the disk behind `handle` sits on a transport the user can
+/// unplug (USB, SD, eMMC) or is an optical drive.
+pub fn is_removable_transport(bs: &BootServices, handle: *mut ()) -> bool {
+    let Some(path) = device_path::of_handle(bs, handle) else {
+        return false;
+    };
+    unsafe { device_path::nodes(path) }.any(|node| {
+        matches!(
+            (node.node_type, node.sub_type),
+            (
+                MESSAGING,
+                MSG_USB | MSG_USB_CLASS | MSG_USB_WWID | MSG_SD | MSG_EMMC
+            ) | (MEDIA, MEDIA_CDROM)
+        )
+    })
+}
+
+/// Identify the disk behind a Block I/O handle.
+/// Returns `DeviceIdentity::unknown()` if the firmware can't tell.
+pub fn identify_disk(bs: &BootServices, handle: *mut ()) -> DeviceIdentity {
+    if let Some(identity) = identify_via_disk_info(bs, handle) {
+        return identity;
+    }
+    identify_via_nvme(bs, handle).unwrap_or_default()
+}
+
+fn identify_via_disk_info(bs: &BootServices, handle: *mut ()) -> Option<DeviceIdentity> {
+    let mut ptr: *mut () = core::ptr::null_mut();
+    if (bs.handle_protocol)(handle, &EFI_DISK_INFO_PROTOCOL_GUID, &mut ptr) != 0 {
+        return None;
+    }
+    let disk_info = ptr as *mut DiskInfoProtocol;
+    let interface = unsafe { (*disk_info).interface };
+
+    let mut data = [0u8; 512];
+    let mut size = data.len() as u32;
+
+    let identity = if interface == DISK_INFO_IDE || interface == DISK_INFO_AHCI {
+        let identify = unsafe { (*disk_info).identify };
+        if identify(disk_info, data.as_mut_ptr(), &mut size) != 0 {
+            return None;
+        }
+        DeviceIdentity::from_ata_identify(&data[..size as usize])
+    } else if interface == DISK_INFO_SCSI || interface == DISK_INFO_USB {
+        let inquiry = unsafe { (*disk_info).inquiry };
+        if inquiry(disk_info, data.as_mut_ptr(), &mut size) != 0 {
+            return None;
+        }
+        DeviceIdentity::from_scsi_inquiry(&data[..size as usize])
+    } else {
+        return None;
+    };
+
+    identity.is_known().then_some(identity)
+}
+
+/// Send Identify Controller to the NVMe controller owning `handle`.
+fn identify_via_nvme(bs: &BootServices, handle: *mut ()) -> Option<DeviceIdentity> {
+    let command = NvmeCommand {
+        cdw0: NVME_ADMIN_IDENTIFY,
+        flags: NVME_CDW10_VALID,
+        cdw10: NVME_IDENTIFY_CONTROLLER,
+        ..Default::default()
+    };
+    nvme_admin(
+        bs,
+        handle,
+        command,
+        4096,
+        DeviceIdentity::from_nvme_identify,
+    )
+    .filter(DeviceIdentity::is_known)
+}
+
+/// Critical warnings from the SMART / Health log of the NVMe controller
+/// owning `handle`. `None` for other transports; ATA SMART needs ATA
+/// Pass Thru, which firmware rarely exposes.
+pub fn critical_warning(bs: &BootServices, handle: *mut ()) -> Option<CriticalWarning> {
+    let command = NvmeCommand {
+        cdw0: NVME_ADMIN_GET_LOG_PAGE,
+        flags: NVME_CDW10_VALID,
+        // Controller-wide; NUMDL is the length in dwords, zero-based
+        nsid: NVME_ALL_NAMESPACES,
+        cdw10: NVME_LOG_SMART | ((NVME_SMART_LOG_LEN as u32 / 4 - 1) << 16),
+        ..Default::default()
+    };
+    nvme_admin(
+        bs,
+        handle,
+        command,
+        NVME_SMART_LOG_LEN as u32,
+        CriticalWarning::from_nvme_smart_log,
+    )
+    .flatten()
+}
+
+/// Run an admin command on the NVMe controller owning `handle` and parse
+/// the `len` bytes it returns.
+fn nvme_admin<T>(
+    bs: &BootServices,
+    handle: *mut (),
+    mut command: NvmeCommand,
+    len: u32,
+    parse: impl FnOnce(&[u8]) -> T,
+) -> Option<T> {
+    // The namespace handle has no Pass Thru; its controller is the
+    // nearest device path ancestor that does.
+    let mut device_path = device_path::of_handle(bs, handle)? as *mut ();
+    let mut controller: *mut () = core::ptr::null_mut();
+    if (bs.locate_device_path)(
+        &EFI_NVM_EXPRESS_PASS_THRU_PROTOCOL_GUID,
+        &mut device_path,
+        &mut controller,
+    ) != 0
+    {
+        return None;
+    }
+    let mut ptr: *mut () = core::ptr::null_mut();
+    if (bs.handle_protocol)(
+        controller,
+        &EFI_NVM_EXPRESS_PASS_THRU_PROTOCOL_GUID,
+        &mut ptr,
+    ) != 0
+    {
+        return None;
+    }
+    let pass_thru = ptr as *mut NvmePassThruProtocol;
+
+    // One page satisfies any IoAlign the controller asks for
+    let mut buffer: u64 = 0;
+    if (bs.allocate_pages)(0, 2, 1, &mut buffer) != 0 {
+        return None;
+    }
+
+    let namespace = command.nsid;
+    let mut completion = NvmeCompletion::default();
+    let mut packet = NvmeCommandPacket {
+        command_timeout: NVME_TIMEOUT,
+        transfer_buffer: buffer as *mut u8,
+        transfer_length: len,
+        metadata_buffer: core::ptr::null_mut(),
+        metadata_length: 0,
+        queue_type: NVME_ADMIN_QUEUE,
+        command: &mut command,
+        completion: &mut completion,
+    };
+
+    let status = unsafe {
+        ((*pass_thru).pass_thru)(pass_thru, namespace, &mut packet, core::ptr::null_mut())
+    };
+    let result = if status == 0 {
+        let data = unsafe { core::slice::from_raw_parts(buffer as *const u8, len as usize) };
+        Some(parse(data))
+    } else {
+        None
+    };
+
+    (bs.free_pages)(buffer, 1);
+    result
+}
@@ -1,5 +1,9 @@
-use super::{FileProtocol, LoadedImageProtocol, EFI_FILE_MODE_READ, LOADED_IMAGE_PROTOCOL_GUID};
+use super::{
+    close_file, get_file_system_protocol, open_root_volume, FileProtocol, LoadedImageProtocol,
+    EFI_FILE_MODE_READ, LOADED_IMAGE_PROTOCOL_GUID,
+};
 use crate::BootServices;
+use alloc::vec::Vec;
 
 pub fn ascii_to_utf16(ascii: &str, buf: &mut [u16]) -> usize {
     let mut i = 0;
@@ -178,3 +182,41 @@ pub unsafe fn open_file_read(
 
     Ok(file)
 }
+
+/// Read up to `max_len` bytes of a file on the volume this image was loaded
+/// from (the ESP). `path` is a backslash path such as `\EFI\BOOT\X.TXT`.
+pub unsafe fn read_esp_file(
+    bs: &BootServices,
+    image_handle: *mut (),
+    path: &str,
+    max_len: usize,
+) -> Result<Vec<u8>, ()> {
+    let loaded_image = get_loaded_image(bs, image_handle)?;
+    let fs = get_file_system_protocol(bs, (*loaded_image).device_handle)?;
+    let root = open_root_volume(fs)?;
+
+    let mut path_utf16 = [0u16; 128];
+    ascii_to_utf16(path, &mut path_utf16);
+    let file = open_file_read(root, &path_utf16);
+    let _ = close_file(root);
+    let file = file.map_err(|_| ())?;
+
+    let mut data = Vec::new();
+    let mut chunk = [0u8; 4096];
+    while data.len() < max_len {
+        let mut size = chunk.len().min(max_len - data.len());
+        let status = ((*file).read)(file, &mut size, chunk.as_mut_ptr());
+        if status != 0 {
+            let _ = close_file(file);
+            return Err(());
+        }
+        // Zero bytes read = end of file
+        if size == 0 {
+            break;
+        }
+        data.extend_from_slice(&chunk[..size]);
+    }
+
+    let _ = close_file(file);
+    Ok(data)
+}
@@ -27,7 +27,7 @@ pub const EFI_FILE_DIRECTORY: u64 = 0x0000000000000010;
 pub struct LoadedImageProtocol {
     revision: u32,
     parent_handle: *mut (),
-    system_table: *mut (),
+    pub system_table: *mut (),
     pub device_handle: *mut (),
     file_path: *mut (),
     _reserved: *mut (),
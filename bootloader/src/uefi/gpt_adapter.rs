@@ -1,30 +1,72 @@
 // Adapter to use UEFI BlockIoProtocol with gpt_disk_io
+//
+// By default blocks are the media's own (512, 4096, or any other power
+// of two UEFI reports). `with_block_size` addresses the media in smaller
+// blocks instead - 512-byte sectors for the FAT32 code - reading whole
+// media blocks around each request and writing partial ones back with
+// read-modify-write.
 
 use crate::uefi::block_io::BlockIoProtocol;
+use alloc::vec;
 use core::fmt;
 use gpt_disk_io::BlockIo;
 use gpt_disk_types::{BlockSize, Lba};
 
 pub struct UefiBlockIoAdapter<'a> {
     protocol: &'a mut BlockIoProtocol,
+    /// Block size the adapter presents
     block_size: BlockSize,
+    /// Block size of the media itself
+    media_block_size: u64,
 }
 
 impl<'a> UefiBlockIoAdapter<'a> {
     pub fn new(protocol: &'a mut BlockIoProtocol) -> Result<Self, AdapterError> {
-        let media = unsafe { &*protocol.media };
+        let media_block_size = unsafe { (*protocol.media).block_size };
+        Self::with_block_size(protocol, media_block_size)
+    }
 
-        let block_size = match media.block_size {
-            512 => BlockSize::BS_512,
-            4096 => BlockSize::BS_4096,
-            _ => return Err(AdapterError::UnsupportedBlockSize(media.block_size)),
-        };
+    /// Adapter presenting `block_size`-byte blocks on media whose own
+    /// blocks are that size or a power-of-two multiple of it.
+    pub fn with_block_size(
+        protocol: &'a mut BlockIoProtocol,
+        block_size: u32,
+    ) -> Result<Self, AdapterError> {
+        let media_block_size = unsafe { (*protocol.media).block_size };
+        if !media_block_size.is_power_of_two() || media_block_size < 512 {
+            return Err(AdapterError::UnsupportedBlockSize(media_block_size));
+        }
+        let block_size = BlockSize::new(block_size)
+            .filter(|size| size.to_u32().is_power_of_two() && size.to_u32() <= media_block_size)
+            .ok_or(AdapterError::UnsupportedBlockSize(block_size))?;
 
         Ok(Self {
             protocol,
             block_size,
+            media_block_size: media_block_size as u64,
         })
     }
+
+    /// Media block `lba` as counted in blocks of this adapter.
+    pub fn adapter_lba(&self, media_lba: u64) -> u64 {
+        media_lba * (self.media_block_size / self.block_size.to_u64())
+    }
+
+    /// Whether adapter blocks are the media's own
+    fn is_native(&self) -> bool {
+        self.block_size.to_u64() == self.media_block_size
+    }
+
+    /// Media blocks covering `len` bytes at adapter block `start_lba`:
+    /// the first one, how many, and the byte offset of the request in them.
+    fn media_span(&self, start_lba: Lba, len: usize) -> (u64, u64, usize) {
+        let start = start_lba.to_u64() * self.block_size.to_u64();
+        let end = start + len as u64;
+        let first = start / self.media_block_size;
+        let last = end.div_ceil(self.media_block_size);
+        let offset = (start - first * self.media_block_size) as usize;
+        (first, last - first, offset)
+    }
 }
 
 #[derive(Debug)]
@@ -55,24 +97,49 @@ impl BlockIo for UefiBlockIoAdapter<'_> {
 
     fn num_blocks(&mut self) -> Result<u64, Self::Error> {
         let media = unsafe { &*self.protocol.media };
-        Ok(media.last_block + 1)
+        Ok(self.adapter_lba(media.last_block + 1))
     }
 
     fn read_blocks(&mut self, start_lba: Lba, dst: &mut [u8]) -> Result<(), Self::Error> {
         self.block_size.assert_valid_block_buffer(dst);
 
-        let num_blocks = dst.len() / self.block_size.to_usize().unwrap();
+        if self.is_native() {
+            let num_blocks = dst.len() / self.block_size.to_usize().unwrap();
+            return self
+                .protocol
+                .read_sectors(start_lba.to_u64(), num_blocks as u64, dst)
+                .map_err(AdapterError::UefiError);
+        }
+
+        let (first, count, offset) = self.media_span(start_lba, dst.len());
+        let mut bounce = vec![0u8; (count * self.media_block_size) as usize];
         self.protocol
-            .read_sectors(start_lba.to_u64(), num_blocks as u64, dst)
-            .map_err(AdapterError::UefiError)
+            .read_sectors(first, count, &mut bounce)
+            .map_err(AdapterError::UefiError)?;
+        dst.copy_from_slice(&bounce[offset..offset + dst.len()]);
+        Ok(())
     }
 
     fn write_blocks(&mut self, start_lba: Lba, src: &[u8]) -> Result<(), Self::Error> {
         self.block_size.assert_valid_block_buffer(src);
 
-        let num_blocks = src.len() / self.block_size.to_usize().unwrap();
+        let (first, count, offset) = self.media_span(start_lba, src.len());
+        let whole = offset == 0 && src.len() as u64 == count * self.media_block_size;
+        if whole {
+            return self
+                .protocol
+                .write_sectors(first, count, src)
+                .map_err(AdapterError::UefiError);
+        }
+
+        // Part of a media block: keep the rest of it as it is on disk
+        let mut bounce = vec![0u8; (count * self.media_block_size) as usize];
+        self.protocol
+            .read_sectors(first, count, &mut bounce)
+            .map_err(AdapterError::UefiError)?;
+        bounce[offset..offset + src.len()].copy_from_slice(src);
         self.protocol
-            .write_sectors(start_lba.to_u64(), num_blocks as u64, src)
+            .write_sectors(first, count, &bounce)
             .map_err(AdapterError::UefiError)
     }
 
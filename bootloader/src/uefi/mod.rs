@@ -1,8 +1,11 @@
 pub mod block_io;
 pub mod block_io_adapter;
+pub mod device_path;
 pub mod disk;
+pub mod disk_info;
 pub mod file_system;
 pub mod gpt_adapter;
+pub mod runtime;
 
 // Note: HTTP is handled by post-EBS network stack (morpheus_network crate)
 // UEFI HTTP Protocol bindings removed - we use our own bare-metal TCP/IP stack
@@ -0,0 +1,366 @@
+// UEFI runtime services - variables, GetTime and ResetSystem
+//
+// Reached through the system table of our loaded image. Only used before
+// ExitBootServices; the bare-metal side captures ResetSystem separately
+// (see distro_downloader::commit::uefi::reset).
+//
+// Rebooting into firmware setup is standard: set EFI_OS_INDICATIONS_BOOT_TO_FW_UI
+// in OsIndications and reset. There is no such bit for the firmware's boot
+// menu, but EDK2-based firmware registers its boot manager as a (hidden)
+// Boot#### load option, which BootNext can point at for one boot.
+
+use super::file_system::get_loaded_image;
+use crate::BootServices;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicPtr, Ordering};
+use morpheus_core::time::DateTime;
+
+pub const EFI_GLOBAL_VARIABLE_GUID: [u8; 16] = [
+    0x61, 0xdf, 0xe4, 0x8b, 0xca, 0x93, 0xd2, 0x11, 0xaa, 0x0d, 0x00, 0xe0, 0x98, 0x03, 0x2b, 0x8c,
+];
+
+/// Vendor GUID of the Secure Boot signature databases (db, dbx, ...)
+pub const EFI_IMAGE_SECURITY_DATABASE_GUID: [u8; 16] = [
+    0xcb, 0xb2, 0x19, 0xd7, 0x3a, 0x3d, 0x96, 0x45, 0xa3, 0xbc, 0xda, 0xd0, 0x0e, 0x67, 0x65, 0x6f,
+];
+
+// Variable attributes
+pub const EFI_VARIABLE_NON_VOLATILE: u32 = 0x1;
+pub const EFI_VARIABLE_BOOTSERVICE_ACCESS: u32 = 0x2;
+pub const EFI_VARIABLE_RUNTIME_ACCESS: u32 = 0x4;
+pub const EFI_VARIABLE_HARDWARE_ERROR_RECORD: u32 = 0x8;
+pub const EFI_VARIABLE_AUTHENTICATED_WRITE_ACCESS: u32 = 0x10;
+pub const EFI_VARIABLE_TIME_BASED_AUTHENTICATED_WRITE_ACCESS: u32 = 0x20;
+pub const EFI_VARIABLE_APPEND_WRITE: u32 = 0x40;
+
+/// Attributes of the boot manager's own variables (Boot####, BootOrder, ...)
+const BOOT_VARIABLE_ATTRIBUTES: u32 =
+    EFI_VARIABLE_NON_VOLATILE | EFI_VARIABLE_BOOTSERVICE_ACCESS | EFI_VARIABLE_RUNTIME_ACCESS;
+
+/// OsIndications bit: stop in the firmware's setup UI on the next boot
+pub const EFI_OS_INDICATIONS_BOOT_TO_FW_UI: u64 = 0x1;
+
+const EFI_BUFFER_TOO_SMALL: usize = (1 << (usize::BITS - 1)) | 5;
+
+/// Variables `variable_names` lists at most, in case the firmware's
+/// enumeration never ends
+const MAX_VARIABLES: usize = 4096;
+
+/// Load option descriptions of the firmware's own boot manager
+const BOOT_MENU_DESCRIPTIONS: &[&str] = &["boot manager", "boot menu", "bootmanagermenu"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum ResetType {
+    Cold = 0,
+    Warm = 1,
+    Shutdown = 2,
+}
+
+#[repr(C)]
+struct SystemTable {
+    _header: [u8; 24],
+    _firmware_vendor: *const u16,
+    _firmware_revision: u32,
+    _console_in_handle: *const (),
+    _con_in: *const (),
+    _console_out_handle: *const (),
+    _con_out: *const (),
+    _stderr_handle: *const (),
+    _stderr: *const (),
+    runtime_services: *const RuntimeServices,
+}
+
+#[repr(C)]
+pub struct RuntimeServices {
+    _header: [u8; 24],
+    get_time: extern "efiapi" fn(time: *mut EfiTime, capabilities: *mut ()) -> usize,
+    // SetTime, GetWakeupTime, SetWakeupTime
+    _time_services: [usize; 3],
+    // SetVirtualAddressMap, ConvertPointer
+    _virtual_memory_services: [usize; 2],
+    get_variable: extern "efiapi" fn(
+        name: *const u16,
+        vendor_guid: *const [u8; 16],
+        attributes: *mut u32,
+        data_size: *mut usize,
+        data: *mut u8,
+    ) -> usize,
+    get_next_variable_name: extern "efiapi" fn(
+        name_size: *mut usize,
+        name: *mut u16,
+        vendor_guid: *mut [u8; 16],
+    ) -> usize,
+    set_variable: extern "efiapi" fn(
+        name: *const u16,
+        vendor_guid: *const [u8; 16],
+        attributes: u32,
+        data_size: usize,
+        data: *const u8,
+    ) -> usize,
+    _get_next_high_monotonic_count: usize,
+    reset_system: extern "efiapi" fn(
+        reset_type: u32,
+        reset_status: usize,
+        data_size: usize,
+        reset_data: *const (),
+    ) -> !,
+}
+
+/// EFI_TIME
+#[repr(C)]
+#[derive(Default)]
+struct EfiTime {
+    year: u16,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8,
+    _pad1: u8,
+    _nanosecond: u32,
+    _time_zone: i16,
+    _daylight: u8,
+    _pad2: u8,
+}
+
+/// Runtime services `rtc_clock` reads, null until `install_rtc_clock`
+static RTC_SERVICES: AtomicPtr<RuntimeServices> = AtomicPtr::new(core::ptr::null_mut());
+
+/// Date new FAT32 files get: the firmware RTC, which keeps local time
+/// like FAT timestamps do
+fn rtc_clock() -> Option<DateTime> {
+    let rt = RTC_SERVICES.load(Ordering::Acquire);
+    if rt.is_null() {
+        return None;
+    }
+    unsafe { (*rt).get_time() }
+}
+
+/// Make the firmware RTC the clock for file timestamps. The network
+/// stack replaces it with its own after ExitBootServices.
+pub fn install_rtc_clock(rt: &'static RuntimeServices) {
+    RTC_SERVICES.store(rt as *const _ as *mut _, Ordering::Release);
+    morpheus_core::time::set_clock(Some(rtc_clock));
+}
+
+/// Runtime services table, via our loaded image
+pub unsafe fn runtime_services(
+    bs: &BootServices,
+    image_handle: *mut (),
+) -> Option<&'static RuntimeServices> {
+    let loaded_image = get_loaded_image(bs, image_handle).ok()?;
+    let st = (*loaded_image).system_table as *const SystemTable;
+    if st.is_null() || (*st).runtime_services.is_null() {
+        return None;
+    }
+    Some(&*(*st).runtime_services)
+}
+
+/// Null-terminated UCS-2 copy of `name`
+fn ucs2(name: &str) -> Vec<u16> {
+    name.encode_utf16().chain(core::iter::once(0)).collect()
+}
+
+impl RuntimeServices {
+    /// Current RTC date and time, None if the firmware has none
+    pub fn get_time(&self) -> Option<DateTime> {
+        let mut time = EfiTime::default();
+        if (self.get_time)(&mut time, core::ptr::null_mut()) != 0 {
+            return None;
+        }
+        Some(DateTime {
+            year: time.year,
+            month: time.month,
+            day: time.day,
+            hour: time.hour,
+            minute: time.minute,
+            second: time.second,
+        })
+    }
+
+    /// Attributes and data of variable `name`, or the EFI status
+    /// (EFI_NOT_FOUND when it does not exist)
+    pub fn get_variable(&self, name: &str, guid: &[u8; 16]) -> Result<(u32, Vec<u8>), usize> {
+        let name = ucs2(name);
+        let mut attributes = 0u32;
+        let mut data = vec![0u8; 64];
+        loop {
+            let mut size = data.len();
+            let status = (self.get_variable)(
+                name.as_ptr(),
+                guid,
+                &mut attributes,
+                &mut size,
+                data.as_mut_ptr(),
+            );
+            match status {
+                0 => {
+                    data.truncate(size);
+                    return Ok((attributes, data));
+                }
+                EFI_BUFFER_TOO_SMALL if size > data.len() => data.resize(size, 0),
+                _ => return Err(status),
+            }
+        }
+    }
+
+    /// Create, replace or (with empty `data`) delete variable `name`
+    pub fn set_variable(
+        &self,
+        name: &str,
+        guid: &[u8; 16],
+        attributes: u32,
+        data: &[u8],
+    ) -> Result<(), usize> {
+        let name = ucs2(name);
+        match (self.set_variable)(name.as_ptr(), guid, attributes, data.len(), data.as_ptr()) {
+            0 => Ok(()),
+            status => Err(status),
+        }
+    }
+
+    /// Delete variable `name`
+    pub fn delete_variable(&self, name: &str, guid: &[u8; 16]) -> Result<(), usize> {
+        self.set_variable(name, guid, 0, &[])
+    }
+
+    /// Name and vendor GUID of every variable, in firmware order
+    pub fn variable_names(&self) -> Vec<(String, [u8; 16])> {
+        let mut names = Vec::new();
+        // Enumeration starts from the empty name
+        let mut name = vec![0u16; 128];
+        let mut guid = [0u8; 16];
+        while names.len() < MAX_VARIABLES {
+            let mut size = name.len() * 2;
+            let mut status = (self.get_next_variable_name)(&mut size, name.as_mut_ptr(), &mut guid);
+            if status == EFI_BUFFER_TOO_SMALL {
+                // The previous name stays in the (grown) buffer
+                name.resize(size / 2 + 1, 0);
+                size = name.len() * 2;
+                status = (self.get_next_variable_name)(&mut size, name.as_mut_ptr(), &mut guid);
+            }
+            // EFI_NOT_FOUND ends the list
+            if status != 0 {
+                break;
+            }
+            let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+            names.push((String::from_utf16_lossy(&name[..len]), guid));
+        }
+        names
+    }
+
+    /// A little-endian u64 global variable such as OsIndications, 0 if unset
+    fn global_u64(&self, name: &str) -> u64 {
+        match self.get_variable(name, &EFI_GLOBAL_VARIABLE_GUID) {
+            Ok((_, data)) if data.len() >= 8 => {
+                u64::from_le_bytes(data[..8].try_into().unwrap_or([0; 8]))
+            }
+            _ => 0,
+        }
+    }
+
+    /// Whether the firmware can stop in its setup UI on request
+    pub fn supports_firmware_setup(&self) -> bool {
+        self.global_u64("OsIndicationsSupported") & EFI_OS_INDICATIONS_BOOT_TO_FW_UI != 0
+    }
+
+    /// Ask the firmware to enter its setup UI on the next boot
+    pub fn request_firmware_setup(&self) -> Result<(), usize> {
+        let indications = self.global_u64("OsIndications") | EFI_OS_INDICATIONS_BOOT_TO_FW_UI;
+        self.set_variable(
+            "OsIndications",
+            &EFI_GLOBAL_VARIABLE_GUID,
+            BOOT_VARIABLE_ATTRIBUTES,
+            &indications.to_le_bytes(),
+        )
+    }
+
+    /// Number of the Boot#### option that starts the firmware's own boot
+    /// menu, if it registers one
+    pub fn firmware_boot_menu_option(&self) -> Option<u16> {
+        self.variable_names()
+            .into_iter()
+            .filter(|(_, guid)| *guid == EFI_GLOBAL_VARIABLE_GUID)
+            .filter_map(|(name, _)| boot_option_number(&name))
+            .find(|&number| {
+                let name = alloc::format!("Boot{:04X}", number);
+                self.get_variable(&name, &EFI_GLOBAL_VARIABLE_GUID)
+                    .ok()
+                    .and_then(|(_, data)| load_option_description(&data))
+                    .is_some_and(|description| {
+                        let description = description.to_ascii_lowercase();
+                        BOOT_MENU_DESCRIPTIONS
+                            .iter()
+                            .any(|known| description.contains(known))
+                    })
+            })
+    }
+
+    /// Boot#### option the firmware started this boot from
+    pub fn boot_current(&self) -> Option<u16> {
+        match self.get_variable("BootCurrent", &EFI_GLOBAL_VARIABLE_GUID) {
+            Ok((_, data)) if data.len() >= 2 => Some(u16::from_le_bytes([data[0], data[1]])),
+            _ => None,
+        }
+    }
+
+    /// Delete boot option `number` and drop it from BootOrder
+    pub fn remove_boot_option(&self, number: u16) -> Result<(), usize> {
+        let name = alloc::format!("Boot{:04X}", number);
+        self.delete_variable(&name, &EFI_GLOBAL_VARIABLE_GUID)?;
+
+        let Ok((attributes, order)) = self.get_variable("BootOrder", &EFI_GLOBAL_VARIABLE_GUID)
+        else {
+            return Ok(());
+        };
+        let kept: Vec<u8> = order
+            .chunks_exact(2)
+            .filter(|entry| u16::from_le_bytes([entry[0], entry[1]]) != number)
+            .flatten()
+            .copied()
+            .collect();
+        if kept.len() == order.len() {
+            return Ok(());
+        }
+        self.set_variable("BootOrder", &EFI_GLOBAL_VARIABLE_GUID, attributes, &kept)
+    }
+
+    /// Boot option `number` once, on the next boot only
+    pub fn set_boot_next(&self, number: u16) -> Result<(), usize> {
+        self.set_variable(
+            "BootNext",
+            &EFI_GLOBAL_VARIABLE_GUID,
+            BOOT_VARIABLE_ATTRIBUTES,
+            &number.to_le_bytes(),
+        )
+    }
+
+    /// Reset or power off the machine
+    pub fn reset(&self, reset_type: ResetType) -> ! {
+        (self.reset_system)(reset_type as u32, 0, 0, core::ptr::null())
+    }
+}
+
+/// "Boot0003" -> 3
+pub fn boot_option_number(name: &str) -> Option<u16> {
+    let digits = name.strip_prefix("Boot")?;
+    if digits.len() != 4 || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    u16::from_str_radix(digits, 16).ok()
+}
+
+/// Description of an EFI_LOAD_OPTION: attributes (u32), file path list
+/// length (u16), then the null-terminated UCS-2 description
+pub fn load_option_description(data: &[u8]) -> Option<String> {
+    let chars: Vec<u16> = data
+        .get(6..)?
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .take_while(|&c| c != 0)
+        .collect();
+    Some(String::from_utf16_lossy(&chars))
+}
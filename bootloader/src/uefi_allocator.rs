@@ -1,9 +1,13 @@
 //! Hybrid Global Allocator
 //!
 //! Pre-EBS: Uses UEFI's allocate_pool/free_pool
-//! Post-EBS: Uses linked_list_allocator with static buffer
+//! Post-EBS: Uses the hwinit heap once `platform_init_selfcontained` has
+//! set it up (memory from the registry, grows on demand)
+//! Fallback: linked_list_allocator with static buffer
 //!
-//! Call `switch_to_post_ebs()` after ExitBootServices to switch modes.
+//! Call `switch_to_post_ebs()` after ExitBootServices if the hwinit heap
+//! is not going to be initialized. Pointers from UEFI pool allocations
+//! made before EBS are leaked when freed afterwards.
 
 use core::alloc::{GlobalAlloc, Layout};
 use core::ptr::{self, NonNull};
@@ -59,7 +63,10 @@ pub struct HybridAllocator;
 
 unsafe impl GlobalAlloc for HybridAllocator {
     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
-        if POST_EBS.load(Ordering::SeqCst) {
+        if morpheus_hwinit::is_heap_initialized() {
+            // Post-EBS: registry-backed heap
+            morpheus_hwinit::heap_alloc(layout)
+        } else if POST_EBS.load(Ordering::SeqCst) {
             // Post-EBS: use linked_list_allocator
             POST_EBS_HEAP
                 .lock()
@@ -77,11 +84,17 @@ unsafe impl GlobalAlloc for HybridAllocator {
             return;
         }
 
-        if POST_EBS.load(Ordering::SeqCst) {
+        if morpheus_hwinit::heap_dealloc(ptr, layout) {
+            return;
+        }
+
+        if in_static_heap(ptr) {
             // Post-EBS: use linked_list_allocator
             if let Some(nn) = NonNull::new(ptr) {
                 POST_EBS_HEAP.lock().deallocate(nn, layout);
             }
+        } else if POST_EBS.load(Ordering::SeqCst) || morpheus_hwinit::is_heap_initialized() {
+            // UEFI pool memory after EBS: nothing to return it to
         } else {
             // Pre-EBS: use UEFI free_pool
             dealloc_uefi(ptr, layout);
@@ -89,6 +102,12 @@ unsafe impl GlobalAlloc for HybridAllocator {
     }
 }
 
+/// Whether `ptr` came from the static post-EBS buffer.
+fn in_static_heap(ptr: *mut u8) -> bool {
+    let start = ptr::addr_of!(HEAP_BUFFER) as usize;
+    (start..start + HEAP_SIZE).contains(&(ptr as usize))
+}
+
 unsafe fn alloc_uefi(layout: Layout) -> *mut u8 {
     let bs_ptr = BOOT_SERVICES.load(Ordering::SeqCst);
     if bs_ptr.is_null() {
@@ -0,0 +1,45 @@
+//! Throughput of the hash fast paths against the portable code.
+//!
+//! ```text
+//! cargo bench -p morpheus-core --bench hash
+//! ```
+//!
+//! A plain `main` rather than libtest's `#[bench]`, which is nightly only.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use morpheus_core::hash::{cpu, crc32, sha256::Sha256};
+
+/// Bytes hashed per run
+const SIZE: usize = 64 * 1024 * 1024;
+/// Runs per function; the best one counts
+const RUNS: usize = 5;
+
+fn main() {
+    let data: Vec<u8> = (0..SIZE as u32)
+        .map(|i| (i.wrapping_mul(2_654_435_761) >> 11) as u8)
+        .collect();
+    println!("{:?}", cpu::features());
+
+    report("crc32 table", &data, |d| crc32::crc32_soft(0, d));
+    report("crc32", &data, crc32::crc32);
+    report("crc32c table", &data, |d| crc32::crc32c_soft(0, d));
+    report("crc32c", &data, crc32::crc32c);
+    report("sha256 soft", &data, |d| {
+        let mut h = Sha256::new_soft();
+        h.update(d);
+        h.finalize()
+    });
+    report("sha256", &data, Sha256::digest);
+}
+
+fn report<T>(name: &str, data: &[u8], f: impl Fn(&[u8]) -> T) {
+    let mut best = f64::MAX;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        black_box(f(black_box(data)));
+        best = best.min(start.elapsed().as_secs_f64());
+    }
+    println!("{name:<14} {:>8.0} MB/s", data.len() as f64 / best / 1e6);
+}
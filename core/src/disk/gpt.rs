@@ -28,14 +28,10 @@ pub struct GptPartitionEntry {
     pub partition_name: [u16; 36], // UTF-16LE
 }
 
-// Common partition type GUIDs
-pub const GUID_EFI_SYSTEM: [u8; 16] = [
-    0x28, 0x73, 0x2a, 0xc1, 0x1f, 0xf8, 0xd2, 0x11, 0xba, 0x4b, 0x00, 0xa0, 0xc9, 0x3e, 0xc9, 0x3b,
-];
-
-pub const GUID_LINUX_FILESYSTEM: [u8; 16] = [
-    0xaf, 0x3d, 0xc6, 0x0f, 0x83, 0x84, 0x72, 0x47, 0x8e, 0x79, 0x3d, 0x69, 0xd8, 0x47, 0x7d, 0xe4,
-];
+// Common partition type GUIDs (see morpheus_gpt::part_type for the rest)
+pub const GUID_EFI_SYSTEM: [u8; 16] = morpheus_gpt::part_type::EFI_SYSTEM;
+
+pub const GUID_LINUX_FILESYSTEM: [u8; 16] = morpheus_gpt::part_type::LINUX_FS;
 
 pub const GPT_SIGNATURE: &[u8; 8] = b"EFI PART";
 
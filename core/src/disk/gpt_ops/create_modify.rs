@@ -1,219 +1,74 @@
-// GPT operations using gpt-disk-rs
+// GPT operations on the shared morpheus-gpt engine
 
 use super::{mb_to_lba, GptError};
 use crate::disk::partition::PartitionType;
-use gpt_disk_io::{BlockIo, Disk};
-use gpt_disk_types::{
-    guid, BlockSize, GptHeader, GptPartitionEntry, GptPartitionEntryArray, LbaLe, U32Le,
-};
-
-/// Helper function to write both primary and secondary GPT headers and partition arrays.
-/// This ensures both copies stay in sync to avoid CRC mismatch errors.
-fn write_gpt_both<B: BlockIo>(
-    disk: &mut Disk<B>,
-    header: &mut GptHeader,
-    entry_array: &GptPartitionEntryArray,
-) -> Result<(), GptError> {
-    // Write primary GPT header
-    disk.write_primary_gpt_header(header, &mut [0u8; 512])
-        .map_err(|_| GptError::IoError)?;
-
-    // Write primary partition entry array
-    disk.write_gpt_partition_entry_array(entry_array)
-        .map_err(|_| GptError::IoError)?;
-
-    // Create secondary header (swap my_lba and alternate_lba)
-    let mut secondary_header = header.clone();
-    let primary_lba = header.my_lba;
-    let alternate_lba = header.alternate_lba;
-
-    secondary_header.my_lba = alternate_lba;
-    secondary_header.alternate_lba = primary_lba;
-
-    // Secondary partition entry array is before the secondary header
-    // Calculate: alternate_lba - (num_entries * entry_size / block_size)
-    let num_entries = header.number_of_partition_entries.to_u32() as u64;
-    let entry_size = header.size_of_partition_entry.to_u32() as u64;
-    let block_size = 512u64; // Assume 512-byte blocks
-    let entries_sectors = (num_entries * entry_size + block_size - 1) / block_size;
-    let secondary_entry_lba = alternate_lba.to_u64() - entries_sectors;
-
-    secondary_header.partition_entry_lba = LbaLe::from_u64(secondary_entry_lba);
-
-    // Recalculate CRC for secondary header (partition array CRC is the same)
-    secondary_header.update_header_crc32();
-
-    // Write secondary GPT header
-    disk.write_secondary_gpt_header(&secondary_header, &mut [0u8; 512])
-        .map_err(|_| GptError::IoError)?;
-
-    // Create secondary partition entry array with correct layout
-    let secondary_layout = secondary_header
-        .get_partition_entry_array_layout()
-        .map_err(|_| GptError::InvalidHeader)?;
-
-    // Copy primary entry data to a new buffer for secondary
-    let primary_storage = entry_array.storage();
-    let mut secondary_buf = [0u8; 16384];
-    secondary_buf[..primary_storage.len()].copy_from_slice(primary_storage);
-
-    let secondary_entry_array =
-        GptPartitionEntryArray::new(secondary_layout, BlockSize::BS_512, &mut secondary_buf)
-            .map_err(|_| GptError::IoError)?;
-
-    // Write secondary partition entry array
-    disk.write_gpt_partition_entry_array(&secondary_entry_array)
-        .map_err(|_| GptError::IoError)?;
-
-    // Flush to ensure all writes are committed
-    disk.flush().map_err(|_| GptError::IoError)?;
-
-    Ok(())
+extern crate alloc;
+use alloc::vec;
+use alloc::vec::Vec;
+use gpt_disk_io::BlockIo;
+use gpt_disk_types::guid;
+use morpheus_gpt::{write_protective_mbr, Gpt};
+
+/// Smallest disk with room for both GPT copies and one usable sector
+const MIN_GPT_BLOCKS: u64 = 68;
+
+/// Read the disk's GPT, partition array on the heap
+fn read_gpt<B: BlockIo>(block_io: &mut B) -> Result<Gpt<Vec<u8>>, GptError> {
+    Ok(Gpt::read(block_io, |len| vec![0u8; len])?)
 }
 
-/// Scan disk for GPT and populate partition table
-pub fn create_gpt<B: BlockIo>(block_io: B, num_blocks: u64) -> Result<(), GptError> {
-    let mut disk = Disk::new(block_io).map_err(|_| GptError::IoError)?;
-
-    // Create GPT header
-    let mut header = GptHeader {
-        my_lba: LbaLe::from_u64(1),
-        alternate_lba: LbaLe::from_u64(num_blocks - 1),
-        first_usable_lba: LbaLe::from_u64(34),
-        last_usable_lba: LbaLe::from_u64(num_blocks - 34),
-        disk_guid: guid!("12345678-1234-1234-1234-123456789012"),
-        partition_entry_lba: LbaLe::from_u64(2),
-        number_of_partition_entries: U32Le::from_u32(128),
-        ..Default::default()
-    };
-
-    // Write protective MBR
-    let mut buf = [0u8; 512];
-    disk.write_protective_mbr(&mut buf)
-        .map_err(|_| GptError::IoError)?;
-
-    // Create empty partition array
-    let layout = header
-        .get_partition_entry_array_layout()
-        .map_err(|_| GptError::InvalidHeader)?;
-
-    let block_size = BlockSize::BS_512;
-
-    let mut entry_buf = [0u8; 16384];
-    let entry_array = GptPartitionEntryArray::new(layout, block_size, &mut entry_buf)
-        .map_err(|_| GptError::IoError)?;
-
-    // Update header CRC with empty partition array
-    header.partition_entry_array_crc32 = entry_array.calculate_crc32();
-    header.update_header_crc32();
+/// Write a protective MBR and an empty GPT (both copies)
+pub fn create_gpt<B: BlockIo>(mut block_io: B, num_blocks: u64) -> Result<(), GptError> {
+    if num_blocks < MIN_GPT_BLOCKS {
+        return Err(GptError::InvalidSize);
+    }
 
-    // Write both primary and secondary GPT
-    write_gpt_both(&mut disk, &mut header, &entry_array)?;
+    let disk_guid = guid!("12345678-1234-1234-1234-123456789012").to_bytes();
+    let entries = vec![0u8; morpheus_gpt::DEFAULT_ARRAY_BYTES];
+    let block_size = block_io.block_size().to_u32();
+    let mut gpt = Gpt::with_block_size(num_blocks, block_size, disk_guid, entries)?;
 
+    write_protective_mbr(&mut block_io, num_blocks)?;
+    gpt.write(&mut block_io)?;
     Ok(())
 }
 
-/// Find all free space regions on disk
+/// Add a partition covering `start_lba..=end_lba` in the first free slot
 pub fn create_partition<B: BlockIo>(
-    block_io: B,
+    mut block_io: B,
     partition_type: PartitionType,
     start_lba: u64,
     end_lba: u64,
 ) -> Result<(), GptError> {
-    let mut disk = Disk::new(block_io).map_err(|_| GptError::IoError)?;
-
-    // Read existing header
-    let mut header = disk
-        .read_primary_gpt_header(&mut [0u8; 512])
-        .map_err(|_| GptError::InvalidHeader)?;
-
-    // Validate range
-    let first_usable = header.first_usable_lba.to_u64();
-    let last_usable = header.last_usable_lba.to_u64();
-
-    if start_lba < first_usable || end_lba > last_usable || start_lba >= end_lba {
-        return Err(GptError::InvalidSize);
-    }
-
-    // Read partition array
-    let layout = header
-        .get_partition_entry_array_layout()
-        .map_err(|_| GptError::InvalidHeader)?;
-
-    let mut entry_buf = [0u8; 16384];
-    let mut entry_array = disk
-        .read_gpt_partition_entry_array(layout, &mut entry_buf)
-        .map_err(|_| GptError::IoError)?;
-
-    // Find first empty slot
-    let mut slot_index = None;
-    let num_entries = layout.num_entries as usize;
-
-    for i in 0..num_entries {
-        if let Some(entry) = entry_array.get_partition_entry(i.try_into().unwrap()) {
-            if !entry.is_used() {
-                slot_index = Some(i);
-                break;
-            }
-        }
-    }
-
-    let slot = slot_index.ok_or(GptError::NoSpace)?;
-
-    // Create new entry directly in buffer
-    let entry = entry_array
-        .get_partition_entry_mut(slot.try_into().unwrap())
-        .ok_or(GptError::NoSpace)?;
-
-    entry.partition_type_guid = partition_type.to_gpt_guid();
-    entry.unique_partition_guid = guid!("12345678-1234-5678-1234-567812345678"); // TODO: generate unique
-    entry.starting_lba = LbaLe::from_u64(start_lba);
-    entry.ending_lba = LbaLe::from_u64(end_lba);
-    entry.attributes = Default::default();
-
-    // Name is already zeroed in default entry
-
-    // Update CRC in header
-    header.partition_entry_array_crc32 = entry_array.calculate_crc32();
-    header.update_header_crc32();
-
-    // Write both primary and secondary GPT
-    write_gpt_both(&mut disk, &mut header, &entry_array)?;
-
+    let mut gpt = read_gpt(&mut block_io)?;
+    gpt.add(partition_type.type_guid(), start_lba, end_lba, "")?;
+    gpt.write(&mut block_io)?;
     Ok(())
 }
 
 ///Delete a partition by index
-pub fn delete_partition<B: BlockIo>(block_io: B, partition_index: usize) -> Result<(), GptError> {
-    let mut disk = Disk::new(block_io).map_err(|_| GptError::IoError)?;
-
-    let mut header = disk
-        .read_primary_gpt_header(&mut [0u8; 512])
-        .map_err(|_| GptError::InvalidHeader)?;
-
-    let layout = header
-        .get_partition_entry_array_layout()
-        .map_err(|_| GptError::InvalidHeader)?;
-
-    let mut entry_buf = [0u8; 16384];
-    let mut entry_array = disk
-        .read_gpt_partition_entry_array(layout, &mut entry_buf)
-        .map_err(|_| GptError::IoError)?;
-
-    // Clear the entry
-    if let Some(entry) = entry_array.get_partition_entry_mut(partition_index.try_into().unwrap()) {
-        *entry = GptPartitionEntry::default(); // Zero out the entry
-    } else {
-        return Err(GptError::PartitionNotFound);
-    }
-
-    // Update CRCs
-    header.partition_entry_array_crc32 = entry_array.calculate_crc32();
-    header.update_header_crc32();
-
-    // Write both primary and secondary GPT
-    write_gpt_both(&mut disk, &mut header, &entry_array)?;
+pub fn delete_partition<B: BlockIo>(
+    mut block_io: B,
+    partition_index: usize,
+) -> Result<(), GptError> {
+    let slot = u32::try_from(partition_index).map_err(|_| GptError::PartitionNotFound)?;
+    let mut gpt = read_gpt(&mut block_io)?;
+    gpt.remove(slot)?;
+    gpt.write(&mut block_io)?;
+    Ok(())
+}
 
+/// Replace a partition's GPT attribute bits, e.g. to show a hidden chunk
+/// partition to other systems
+pub fn set_partition_attributes<B: BlockIo>(
+    mut block_io: B,
+    partition_index: usize,
+    attributes: u64,
+) -> Result<(), GptError> {
+    let slot = u32::try_from(partition_index).map_err(|_| GptError::PartitionNotFound)?;
+    let mut gpt = read_gpt(&mut block_io)?;
+    gpt.set_attributes(slot, attributes)?;
+    gpt.write(&mut block_io)?;
     Ok(())
 }
 
@@ -221,41 +76,18 @@ pub fn delete_partition<B: BlockIo>(block_io: B, partition_index: usize) -> Resu
 /// partition_index: GPT entry index (0-127)
 /// new_size_mb: New size in megabytes (must be smaller than current)
 pub fn shrink_partition<B: BlockIo>(
-    block_io: B,
+    mut block_io: B,
     partition_index: usize,
     new_size_mb: u64,
 ) -> Result<(), GptError> {
-    let mut disk = Disk::new(block_io).map_err(|_| GptError::IoError)?;
-
-    let mut header = disk
-        .read_primary_gpt_header(&mut [0u8; 512])
-        .map_err(|_| GptError::InvalidHeader)?;
-
-    let layout = header
-        .get_partition_entry_array_layout()
-        .map_err(|_| GptError::InvalidHeader)?;
-
-    let mut entry_buf = [0u8; 16384];
-    let mut entry_array = disk
-        .read_gpt_partition_entry_array(layout, &mut entry_buf)
-        .map_err(|_| GptError::IoError)?;
-
-    // Get the partition entry
-    let entry = entry_array
-        .get_partition_entry_mut(partition_index.try_into().unwrap())
-        .ok_or(GptError::PartitionNotFound)?;
-
-    // Check if partition is used
-    if !entry.is_used() {
-        return Err(GptError::PartitionNotFound);
-    }
+    let slot = u32::try_from(partition_index).map_err(|_| GptError::PartitionNotFound)?;
+    let mut gpt = read_gpt(&mut block_io)?;
+    let entry = gpt.entry(slot).ok_or(GptError::PartitionNotFound)?;
 
-    let start_lba = entry.starting_lba.to_u64();
-    let current_end_lba = entry.ending_lba.to_u64();
-    let current_size_lba = current_end_lba - start_lba + 1;
+    let current_size_lba = entry.end_lba - entry.start_lba + 1;
 
-    // Calculate new size in LBA (assume 512-byte blocks)
-    let new_size_lba = mb_to_lba(new_size_mb, 512);
+    // Calculate new size in LBA of the disk's block size
+    let new_size_lba = mb_to_lba(new_size_mb, block_io.block_size().to_u32());
 
     if new_size_lba == 0 {
         return Err(GptError::InvalidSize);
@@ -265,18 +97,115 @@ pub fn shrink_partition<B: BlockIo>(
         return Err(GptError::InvalidSize); // Can only shrink
     }
 
-    // Calculate new end LBA
-    let new_end_lba = start_lba + new_size_lba - 1;
+    let new_end_lba = entry.start_lba + new_size_lba - 1;
+    gpt.set_range(slot, entry.start_lba, new_end_lba)?;
+    gpt.write(&mut block_io)?;
+    Ok(())
+}
 
-    // Update the partition entry
-    entry.ending_lba = LbaLe::from_u64(new_end_lba);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disk::gpt_ops::{calculate_total_free_space, find_free_space, scan_partitions};
+    use crate::disk::partition::PartitionTable;
+    use crate::testing::SparseDisk;
+
+    /// 4 TiB in 512-byte sectors - twice what 32-bit LBAs can address
+    const BIG_DISK: u64 = 1 << 33;
+
+    #[test]
+    fn test_partitions_beyond_2tib() {
+        let mut disk = SparseDisk::new(BIG_DISK);
+        create_gpt(&mut disk, BIG_DISK).unwrap();
+
+        // Backup header sits on the last sector, far past LBA 2^32
+        assert!(disk.is_written(BIG_DISK - 1));
+
+        let high_start = 5_000_000_000u64;
+        let high_end = BIG_DISK - 34;
+        create_partition(&mut disk, PartitionType::EfiSystem, 2048, 526_335).unwrap();
+        create_partition(&mut disk, PartitionType::BasicData, high_start, high_end).unwrap();
+
+        let mut table = PartitionTable::new();
+        scan_partitions(&mut disk, &mut table, 512).unwrap();
+        assert_eq!(table.count(), 2);
+        let high = table.iter().find(|p| p.start_lba == high_start).unwrap();
+        assert_eq!(high.end_lba, high_end);
+        assert_eq!(high.size_mb(), (high_end - high_start + 1) / 2048);
+
+        let regions = find_free_space(&mut disk, 512).unwrap();
+        let gap = regions.iter().flatten().last().unwrap();
+        assert_eq!((gap.start_lba, gap.end_lba), (526_336, high_start - 1));
+
+        let free_mb = calculate_total_free_space(&mut disk, 512).unwrap();
+        assert!(free_mb > 2 * 1024 * 1024);
+    }
 
-    // Update CRCs
-    header.partition_entry_array_crc32 = entry_array.calculate_crc32();
-    header.update_header_crc32();
+    #[test]
+    fn test_usable_range_validation() {
+        let mut disk = SparseDisk::new(BIG_DISK);
+        assert!(matches!(
+            create_gpt(&mut disk, 16),
+            Err(GptError::InvalidSize)
+        ));
+
+        create_gpt(&mut disk, BIG_DISK).unwrap();
+        create_partition(
+            &mut disk,
+            PartitionType::BasicData,
+            4_300_000_000,
+            4_400_000_000,
+        )
+        .unwrap();
+
+        // Past the last usable LBA, into the backup partition array
+        assert!(matches!(
+            create_partition(
+                &mut disk,
+                PartitionType::BasicData,
+                BIG_DISK - 100,
+                BIG_DISK - 2,
+            ),
+            Err(GptError::InvalidSize)
+        ));
+        // Overlaps the partition above the 32-bit boundary
+        assert!(matches!(
+            create_partition(
+                &mut disk,
+                PartitionType::BasicData,
+                4_399_999_000,
+                4_500_000_000,
+            ),
+            Err(GptError::OverlappingPartitions)
+        ));
+    }
 
-    // Write both primary and secondary GPT
-    write_gpt_both(&mut disk, &mut header, &entry_array)?;
+    /// A disk of 4096-byte blocks in memory
+    fn disk_4k(storage: &mut [u8]) -> gpt_disk_io::BlockIoAdapter<&mut [u8]> {
+        gpt_disk_io::BlockIoAdapter::new(storage, gpt_disk_types::BlockSize::BS_4096)
+    }
 
-    Ok(())
+    #[test]
+    fn test_sizes_on_4k_disk() {
+        // 64 MiB in 4096-byte blocks
+        const BLOCKS: u64 = 16_384;
+        let mut storage = alloc::vec![0u8; BLOCKS as usize * 4096];
+        create_gpt(disk_4k(&mut storage), BLOCKS).unwrap();
+        // 1 MiB aligned, 16 MiB long
+        create_partition(disk_4k(&mut storage), PartitionType::EfiSystem, 256, 4351).unwrap();
+
+        let mut table = PartitionTable::new();
+        scan_partitions(disk_4k(&mut storage), &mut table, 4096).unwrap();
+        let esp = *table.get(0).unwrap();
+        assert_eq!(esp.size_mb(), 16);
+        let regions = find_free_space(disk_4k(&mut storage), 4096).unwrap();
+        // Usable from LBA 6: the partition array is 4 blocks
+        let before = regions[0].unwrap();
+        assert_eq!((before.start_lba, before.end_lba), (6, 255));
+        assert_eq!(regions[1].unwrap().size_mb(), 46);
+
+        shrink_partition(disk_4k(&mut storage), esp.index as usize, 8).unwrap();
+        scan_partitions(disk_4k(&mut storage), &mut table, 4096).unwrap();
+        assert_eq!(table.get(0).unwrap().end_lba, 256 + 2048 - 1);
+    }
 }
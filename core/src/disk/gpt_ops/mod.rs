@@ -4,7 +4,9 @@ mod scan;
 mod types;
 mod utils;
 
-pub use create_modify::{create_gpt, create_partition, delete_partition, shrink_partition};
+pub use create_modify::{
+    create_gpt, create_partition, delete_partition, set_partition_attributes, shrink_partition,
+};
 pub use find::find_free_space;
 pub use scan::scan_partitions;
 pub use types::{FreeRegion, GptError};
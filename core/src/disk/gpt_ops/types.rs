@@ -11,11 +11,29 @@ pub enum GptError {
     AlignmentError,
 }
 
+impl From<morpheus_gpt::GptError> for GptError {
+    fn from(e: morpheus_gpt::GptError) -> Self {
+        use morpheus_gpt::GptError as E;
+        match e {
+            E::IoError => GptError::IoError,
+            E::InvalidHeader | E::BufferTooSmall | E::UnsupportedBlockSize => {
+                GptError::InvalidHeader
+            }
+            E::NoSpace => GptError::NoSpace,
+            E::PartitionNotFound => GptError::PartitionNotFound,
+            E::OverlappingPartitions => GptError::OverlappingPartitions,
+            E::InvalidSize => GptError::InvalidSize,
+        }
+    }
+}
+
 /// Represents a free space region on disk
 #[derive(Copy, Clone, Debug)]
 pub struct FreeRegion {
     pub start_lba: u64,
     pub end_lba: u64,
+    /// Bytes per LBA on the disk
+    pub block_size: u32,
 }
 
 impl FreeRegion {
@@ -24,6 +42,6 @@ impl FreeRegion {
     }
 
     pub fn size_mb(&self) -> u64 {
-        (self.size_lba() * 512) / (1024 * 1024)
+        (self.size_lba() * self.block_size as u64) / (1024 * 1024)
     }
 }
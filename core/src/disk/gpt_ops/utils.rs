@@ -1,17 +1,16 @@
-// GPT operations using gpt-disk-rs
+// GPT operations on the shared morpheus-gpt engine
 
 use super::{find_free_space, GptError};
 use gpt_disk_io::BlockIo;
 
 /// Scan disk for GPT and populate partition table
 pub fn align_lba(lba: u64, block_size_bytes: u32) -> u64 {
-    let alignment = (1024 * 1024) / block_size_bytes as u64; // 1MB alignment
-    lba.div_ceil(alignment) * alignment
+    morpheus_gpt::align_up(lba, block_size_bytes) // 1MB alignment
 }
 
-/// Calculate size in LBA from MB
+/// Calculate size in LBA from MB, saturating for sizes typed by the user
 pub fn mb_to_lba(size_mb: u64, block_size_bytes: u32) -> u64 {
-    (size_mb * 1024 * 1024) / block_size_bytes as u64
+    size_mb.saturating_mul(1024 * 1024) / block_size_bytes as u64
 }
 
 /// Calculate total free space on disk in MB
@@ -27,5 +26,5 @@ pub fn calculate_total_free_space<B: BlockIo>(
     }
 
     // Convert to MB
-    Ok((total_free_lba * 512) / (1024 * 1024))
+    Ok((total_free_lba * block_size_bytes as u64) / (1024 * 1024))
 }
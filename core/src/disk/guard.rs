@@ -0,0 +1,98 @@
+// Guard rails for destructive disk operations
+//
+// The disk MorpheusX booted from, and the ESP it runs from, are flagged
+// when disks are enumerated. Wiping either takes the running install with
+// it, so those operations need the user to type a confirmation phrase on
+// top of the usual Y/N.
+
+use crate::disk::manager::DiskInfo;
+
+/// What the user has to type to go ahead anyway.
+pub const CONFIRM_PHRASE: &str = "DESTROY MORPHEUSX";
+
+/// Why an operation target is protected.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Protection {
+    /// Nothing MorpheusX depends on
+    None,
+    /// The disk MorpheusX booted from
+    BootDisk,
+    /// The ESP MorpheusX booted from
+    BootEsp,
+}
+
+impl Protection {
+    /// Whether the confirmation phrase is required.
+    pub fn needs_phrase(self) -> bool {
+        self != Protection::None
+    }
+
+    /// One-line explanation for the confirmation screen.
+    pub fn describe(self) -> &'static str {
+        match self {
+            Protection::None => "",
+            Protection::BootDisk => "This is the disk MorpheusX is running from.",
+            Protection::BootEsp => "This is the ESP MorpheusX is running from.",
+        }
+    }
+}
+
+/// Protection for an operation that rewrites the whole disk.
+pub fn disk_protection(disk: &DiskInfo) -> Protection {
+    if disk.boot_disk {
+        Protection::BootDisk
+    } else {
+        Protection::None
+    }
+}
+
+/// Protection for an operation on the partition starting at `start_lba`.
+pub fn partition_protection(disk: &DiskInfo, start_lba: u64) -> Protection {
+    if disk.boot_esp_lba == Some(start_lba) {
+        Protection::BootEsp
+    } else {
+        Protection::None
+    }
+}
+
+/// Whether `input` is the confirmation phrase.
+pub fn phrase_matches(input: &str) -> bool {
+    input.trim() == CONFIRM_PHRASE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn boot_disk() -> DiskInfo {
+        let mut disk = DiskInfo::new(0, 512, 1_000_000, false, false);
+        disk.boot_disk = true;
+        disk.boot_esp_lba = Some(2048);
+        disk
+    }
+
+    #[test]
+    fn test_boot_disk_protected() {
+        let disk = boot_disk();
+        assert_eq!(disk_protection(&disk), Protection::BootDisk);
+        assert_eq!(partition_protection(&disk, 2048), Protection::BootEsp);
+        assert_eq!(partition_protection(&disk, 206848), Protection::None);
+        assert!(disk_protection(&disk).needs_phrase());
+    }
+
+    #[test]
+    fn test_other_disk_unprotected() {
+        let disk = DiskInfo::new(1, 512, 1_000_000, true, false);
+        assert_eq!(disk_protection(&disk), Protection::None);
+        assert_eq!(partition_protection(&disk, 2048), Protection::None);
+        assert!(!Protection::None.needs_phrase());
+    }
+
+    #[test]
+    fn test_phrase() {
+        assert!(phrase_matches("DESTROY MORPHEUSX"));
+        assert!(phrase_matches(" DESTROY MORPHEUSX "));
+        assert!(!phrase_matches("destroy morpheusx"));
+        assert!(!phrase_matches("y"));
+    }
+}
@@ -0,0 +1,89 @@
+// Disk health - SMART critical warnings
+//
+// NVMe controllers keep a SMART / Health Information log page whose first
+// byte is a set of critical warnings, raised when the drive runs out of
+// spare blocks, overheats, degrades or drops to read-only. Any of them
+// means the disk should be backed up before it is trusted with an install.
+
+use core::fmt;
+
+/// SMART / Health Information log page length
+pub const NVME_SMART_LOG_LEN: usize = 512;
+
+/// Critical warning bits and what each means
+const WARNINGS: [(u8, &str); 6] = [
+    (0x01, "spare capacity low"),
+    (0x02, "temperature out of range"),
+    (0x04, "reliability degraded"),
+    (0x08, "media read-only"),
+    (0x10, "volatile backup failed"),
+    (0x20, "persistent memory read-only"),
+];
+
+/// Critical warnings a disk reported.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CriticalWarning(pub u8);
+
+impl CriticalWarning {
+    /// Parse the NVMe SMART / Health Information log page.
+    pub fn from_nvme_smart_log(log: &[u8]) -> Option<Self> {
+        if log.len() < NVME_SMART_LOG_LEN {
+            return None;
+        }
+        Some(Self(log[0]))
+    }
+
+    /// Whether the disk raised no warning
+    pub fn is_clear(self) -> bool {
+        self.0 == 0
+    }
+
+    /// What each raised warning means
+    pub fn reasons(self) -> impl Iterator<Item = &'static str> {
+        WARNINGS
+            .into_iter()
+            .filter(move |(bit, _)| self.0 & bit != 0)
+            .map(|(_, reason)| reason)
+    }
+}
+
+/// "spare capacity low, media read-only"; bits no known warning uses
+/// show as "unknown warning".
+impl fmt::Display for CriticalWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let known = WARNINGS.iter().fold(0, |bits, (bit, _)| bits | bit);
+        let unknown = (self.0 & !known != 0).then_some("unknown warning");
+        for (i, reason) in self.reasons().chain(unknown).enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            f.write_str(reason)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate alloc;
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_smart_log() {
+        let mut log = [0u8; NVME_SMART_LOG_LEN];
+        assert!(CriticalWarning::from_nvme_smart_log(&log)
+            .unwrap()
+            .is_clear());
+        assert_eq!(CriticalWarning::from_nvme_smart_log(&log[..64]), None);
+
+        log[0] = 0x09;
+        let warning = CriticalWarning::from_nvme_smart_log(&log).unwrap();
+        assert!(!warning.is_clear());
+        assert_eq!(warning.to_string(), "spare capacity low, media read-only");
+        assert_eq!(
+            CriticalWarning(0x84).to_string(),
+            "reliability degraded, unknown warning"
+        );
+    }
+}
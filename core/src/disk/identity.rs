@@ -0,0 +1,328 @@
+// Block device identity - model, serial number and capacity
+//
+// Every transport reports model and serial in its own identify structure.
+// These parse them into one form, so a disk can be shown as
+// "Samsung SSD 970 EVO 1TB, SN S4EWNX0M..." rather than "Disk 0".
+
+use core::fmt;
+
+/// Longest model string (ATA IDENTIFY words 27-46).
+pub const MODEL_LEN: usize = 40;
+
+/// Longest serial number (ATA IDENTIFY words 10-19, NVMe SN).
+pub const SERIAL_LEN: usize = 20;
+
+/// Model written for virtio-blk, which has no model string.
+const VIRTIO_MODEL: &[u8] = b"VirtIO Block Device";
+
+/// Model and serial number of a block device.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DeviceIdentity {
+    model: [u8; MODEL_LEN],
+    model_len: u8,
+    serial: [u8; SERIAL_LEN],
+    serial_len: u8,
+}
+
+impl DeviceIdentity {
+    /// Identity of a device that couldn't be identified.
+    pub const fn unknown() -> Self {
+        Self {
+            model: [0; MODEL_LEN],
+            model_len: 0,
+            serial: [0; SERIAL_LEN],
+            serial_len: 0,
+        }
+    }
+
+    /// Build from raw model and serial fields. Padding (spaces, NULs) is
+    /// trimmed and anything unprintable shown as '?'.
+    pub fn new(model: &[u8], serial: &[u8]) -> Self {
+        let mut identity = Self::unknown();
+        identity.model_len = copy_field(&mut identity.model, model);
+        identity.serial_len = copy_field(&mut identity.serial, serial);
+        identity
+    }
+
+    /// Parse ATA IDENTIFY DEVICE data (512 bytes).
+    ///
+    /// ATA strings hold two characters per word, first character in the
+    /// high byte.
+    pub fn from_ata_identify(data: &[u8]) -> Self {
+        if data.len() < 512 {
+            return Self::unknown();
+        }
+        let mut serial = [0u8; SERIAL_LEN];
+        let mut model = [0u8; MODEL_LEN];
+        ata_string(&data[20..40], &mut serial);
+        ata_string(&data[54..94], &mut model);
+        Self::new(&model, &serial)
+    }
+
+    /// Parse NVMe Identify Controller data (SN at byte 4, MN at byte 24).
+    pub fn from_nvme_identify(data: &[u8]) -> Self {
+        if data.len() < 64 {
+            return Self::unknown();
+        }
+        Self::new(&data[24..64], &data[4..24])
+    }
+
+    /// Parse SCSI INQUIRY data (vendor at byte 8, product at byte 16).
+    /// Standard inquiry data carries no serial number.
+    pub fn from_scsi_inquiry(data: &[u8]) -> Self {
+        if data.len() < 32 {
+            return Self::unknown();
+        }
+        let mut model = [b' '; 25];
+        model[..8].copy_from_slice(&data[8..16]);
+        model[9..].copy_from_slice(&data[16..32]);
+        // Vendors pad to the field width: "ATA     " -> "ATA"
+        let vendor_len = copy_field(&mut [0u8; 8], &data[8..16]) as usize;
+        model.copy_within(9.., vendor_len + 1);
+        Self::new(&model[..vendor_len + 17], &[])
+    }
+
+    /// Identity of a virtio-blk device from its GET_ID string (20 bytes,
+    /// NUL-padded serial).
+    pub fn from_virtio_id(id: &[u8]) -> Self {
+        Self::new(VIRTIO_MODEL, id)
+    }
+
+    /// Model string ("" if unknown).
+    pub fn model(&self) -> &str {
+        core::str::from_utf8(&self.model[..self.model_len as usize]).unwrap_or("")
+    }
+
+    /// Serial number ("" if unknown).
+    pub fn serial(&self) -> &str {
+        core::str::from_utf8(&self.serial[..self.serial_len as usize]).unwrap_or("")
+    }
+
+    /// Whether anything was learned about the device.
+    pub fn is_known(&self) -> bool {
+        self.model_len > 0 || self.serial_len > 0
+    }
+
+    /// Short fingerprint of model and serial, for records with no room for
+    /// the whole identity (ISO manifest chunk entries). 0 if unknown.
+    pub fn disk_id(&self) -> u32 {
+        if !self.is_known() {
+            return 0;
+        }
+        let mut bytes = [0u8; MODEL_LEN + 1 + SERIAL_LEN];
+        let model = self.model().as_bytes();
+        let serial = self.serial().as_bytes();
+        bytes[..model.len()].copy_from_slice(model);
+        bytes[model.len() + 1..][..serial.len()].copy_from_slice(serial);
+        // 0 means "unknown", so a real disk never gets it
+        crate::iso::crc32(&bytes[..model.len() + 1 + serial.len()]).max(1)
+    }
+}
+
+impl Default for DeviceIdentity {
+    fn default() -> Self {
+        Self::unknown()
+    }
+}
+
+/// Whether ATA IDENTIFY data describes removable media (word 0, bit 7):
+/// CompactFlash, some card readers and eSATA docks set it.
+pub fn ata_is_removable(data: &[u8]) -> bool {
+    data.len() >= 2 && data[0] & 0x80 != 0
+}
+
+/// Storage medium behind a block device, where the device reports it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MediaKind {
+    Unknown,
+    SolidState,
+    Rotational,
+}
+
+impl MediaKind {
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Unknown => "unknown",
+            Self::SolidState => "SSD",
+            Self::Rotational => "HDD",
+        }
+    }
+}
+
+/// Medium from ATA IDENTIFY word 217 (nominal media rotation rate):
+/// 1 means non-rotating, 0x0401-0xFFFE is the spindle speed in RPM.
+pub fn ata_media_kind(data: &[u8]) -> MediaKind {
+    if data.len() < 436 {
+        return MediaKind::Unknown;
+    }
+    match u16::from_le_bytes([data[434], data[435]]) {
+        1 => MediaKind::SolidState,
+        0x0401..=0xFFFE => MediaKind::Rotational,
+        _ => MediaKind::Unknown,
+    }
+}
+
+/// Capacity in decimal units, the way drives are labelled: "1.0 TB",
+/// "500 GB", "64.0 GB".
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Capacity(pub u64);
+
+impl fmt::Display for Capacity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const UNITS: [(u64, &str); 3] = [
+            (1_000_000_000_000, "TB"),
+            (1_000_000_000, "GB"),
+            (1_000_000, "MB"),
+        ];
+        let (unit, name) = UNITS
+            .iter()
+            .copied()
+            .find(|&(unit, _)| self.0 >= unit)
+            .unwrap_or(UNITS[2]);
+
+        let tenths = (self.0 as u128 * 10 / unit as u128) as u64;
+        if tenths >= 1000 {
+            write!(f, "{} {}", tenths / 10, name)
+        } else {
+            write!(f, "{}.{} {}", tenths / 10, tenths % 10, name)
+        }
+    }
+}
+
+/// Un-swap an ATA string into `out`.
+fn ata_string(words: &[u8], out: &mut [u8]) {
+    for (pair, chars) in words.chunks_exact(2).zip(out.chunks_exact_mut(2)) {
+        chars[0] = pair[1];
+        chars[1] = pair[0];
+    }
+}
+
+/// Copy `src` into `dst` without padding; returns the length.
+fn copy_field(dst: &mut [u8], src: &[u8]) -> u8 {
+    let src = match src.iter().position(|&b| b == 0) {
+        Some(end) => &src[..end],
+        None => src,
+    };
+    let start = src.iter().position(|&b| b != b' ').unwrap_or(src.len());
+    let end = src
+        .iter()
+        .rposition(|&b| b != b' ')
+        .map_or(start, |i| i + 1);
+    let src = &src[start..end];
+
+    let len = src.len().min(dst.len());
+    for (d, &s) in dst.iter_mut().zip(&src[..len]) {
+        *d = if s.is_ascii_graphic() || s == b' ' {
+            s
+        } else {
+            b'?'
+        };
+    }
+    len as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate alloc;
+    use alloc::string::ToString;
+
+    /// Store `s` the way ATA IDENTIFY does (byte-swapped, space-padded).
+    fn ata_field(data: &mut [u8], s: &str) {
+        let mut padded = [b' '; 40];
+        padded[..s.len()].copy_from_slice(s.as_bytes());
+        for (i, pair) in data.chunks_exact_mut(2).enumerate() {
+            pair[0] = padded[i * 2 + 1];
+            pair[1] = padded[i * 2];
+        }
+    }
+
+    #[test]
+    fn test_ata_identify() {
+        let mut data = [0u8; 512];
+        ata_field(&mut data[20..40], "  S4EWNX0M123456");
+        ata_field(&mut data[54..94], "Samsung SSD 860 EVO 1TB");
+
+        let identity = DeviceIdentity::from_ata_identify(&data);
+        assert_eq!(identity.model(), "Samsung SSD 860 EVO 1TB");
+        assert_eq!(identity.serial(), "S4EWNX0M123456");
+        assert!(identity.is_known());
+
+        assert!(!DeviceIdentity::from_ata_identify(&data[..256]).is_known());
+        assert!(!ata_is_removable(&data));
+        data[0] = 0x80;
+        assert!(ata_is_removable(&data));
+
+        assert_eq!(ata_media_kind(&data), MediaKind::Unknown);
+        data[434..436].copy_from_slice(&1u16.to_le_bytes());
+        assert_eq!(ata_media_kind(&data), MediaKind::SolidState);
+        data[434..436].copy_from_slice(&7200u16.to_le_bytes());
+        assert_eq!(ata_media_kind(&data), MediaKind::Rotational);
+        assert_eq!(ata_media_kind(&data[..256]), MediaKind::Unknown);
+    }
+
+    #[test]
+    fn test_nvme_identify() {
+        let mut data = [0u8; 4096];
+        data[4..24].copy_from_slice(b"S4EWNX0M654321      ");
+        data[24..47].copy_from_slice(b"Samsung SSD 970 EVO 1TB");
+        data[47..64].fill(b' ');
+
+        let identity = DeviceIdentity::from_nvme_identify(&data);
+        assert_eq!(identity.model(), "Samsung SSD 970 EVO 1TB");
+        assert_eq!(identity.serial(), "S4EWNX0M654321");
+    }
+
+    #[test]
+    fn test_scsi_inquiry() {
+        let mut data = [0u8; 36];
+        data[8..16].copy_from_slice(b"SanDisk ");
+        data[16..32].copy_from_slice(b"Cruzer Blade    ");
+
+        let identity = DeviceIdentity::from_scsi_inquiry(&data);
+        assert_eq!(identity.model(), "SanDisk Cruzer Blade");
+        assert_eq!(identity.serial(), "");
+    }
+
+    #[test]
+    fn test_virtio_and_unprintable() {
+        let identity = DeviceIdentity::from_virtio_id(b"disk-0\0\0\0\0\0\0\0\0\0\0\0\0\0\0");
+        assert_eq!(identity.model(), "VirtIO Block Device");
+        assert_eq!(identity.serial(), "disk-0");
+
+        let identity = DeviceIdentity::new(b"Bad\x01Model", b"");
+        assert_eq!(identity.model(), "Bad?Model");
+        assert!(!DeviceIdentity::unknown().is_known());
+    }
+
+    #[test]
+    fn test_disk_id() {
+        assert_eq!(DeviceIdentity::unknown().disk_id(), 0);
+
+        let disk = DeviceIdentity::new(b"WDC WD10EZEX", b"WD-1234");
+        assert_ne!(disk.disk_id(), 0);
+        assert_eq!(
+            disk.disk_id(),
+            DeviceIdentity::new(b"WDC WD10EZEX  ", b"WD-1234").disk_id()
+        );
+        assert_ne!(
+            disk.disk_id(),
+            DeviceIdentity::new(b"WDC WD10EZEX", b"WD-1235").disk_id()
+        );
+        // Model and serial don't run together
+        assert_ne!(
+            DeviceIdentity::new(b"AB", b"C").disk_id(),
+            DeviceIdentity::new(b"A", b"BC").disk_id()
+        );
+    }
+
+    #[test]
+    fn test_capacity() {
+        assert_eq!(Capacity(1_000_204_886_016).to_string(), "1.0 TB");
+        assert_eq!(Capacity(500_107_862_016).to_string(), "500 GB");
+        assert_eq!(Capacity(64_023_257_088).to_string(), "64.0 GB");
+        assert_eq!(Capacity(524_288_000).to_string(), "524 MB");
+        assert_eq!(Capacity(1_048_576).to_string(), "1.0 MB");
+        assert_eq!(Capacity(0).to_string(), "0.0 MB");
+    }
+}
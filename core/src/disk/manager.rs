@@ -1,5 +1,7 @@
 // Disk manager - handle enumeration and detection
 
+use crate::disk::health::CriticalWarning;
+use crate::disk::identity::{Capacity, DeviceIdentity};
 use crate::disk::partition::PartitionTable;
 
 /// Represents a physical disk device
@@ -10,6 +12,14 @@ pub struct DiskInfo {
     pub removable: bool,
     pub read_only: bool,
     pub partitions: PartitionTable,
+    /// Model and serial number, if the device could be identified
+    pub identity: DeviceIdentity,
+    /// MorpheusX was booted from this disk
+    pub boot_disk: bool,
+    /// Start LBA of the ESP MorpheusX was booted from, if on this disk
+    pub boot_esp_lba: Option<u64>,
+    /// SMART critical warnings, if the disk reports them
+    pub critical_warning: Option<CriticalWarning>,
 }
 
 /// Manager for discovering and accessing disks
@@ -71,10 +81,19 @@ impl DiskInfo {
             removable,
             read_only,
             partitions: PartitionTable::new(),
+            identity: DeviceIdentity::unknown(),
+            boot_disk: false,
+            boot_esp_lba: None,
+            critical_warning: None,
         }
     }
 
     pub fn size_mb(&self) -> u64 {
         ((self.last_block + 1) * self.block_size as u64) / (1024 * 1024)
     }
+
+    /// Size in decimal units, as printed on the drive label
+    pub fn capacity(&self) -> Capacity {
+        Capacity((self.last_block + 1) * self.block_size as u64)
+    }
 }
@@ -1,5 +1,9 @@
 pub mod gpt;
 pub mod gpt_ops;
 pub mod gpt_writer;
+pub mod guard;
+pub mod health;
+pub mod identity;
 pub mod manager;
 pub mod partition;
+pub mod partition_io;
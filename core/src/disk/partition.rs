@@ -1,11 +1,20 @@
 // Partition information and management
 
+use morpheus_gpt::part_type;
+
 #[derive(Copy, Clone, Debug)]
 pub struct PartitionInfo {
     pub index: u32,
     pub partition_type: PartitionType,
+    /// Type GUID as stored on disk; names types `PartitionType` lumps
+    /// into `Unknown`
+    pub type_guid: [u8; 16],
+    /// GPT attribute bits (`part_type::ATTR_*`)
+    pub attributes: u64,
     pub start_lba: u64,
     pub end_lba: u64,
+    /// Bytes per LBA on the disk it was read from
+    pub block_size: u32,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -14,63 +23,73 @@ pub enum PartitionType {
     LinuxFilesystem,
     LinuxSwap,
     BasicData,
+    /// A piece of a downloaded ISO
+    IsoChunk,
     Unknown,
 }
 
 impl PartitionInfo {
     pub fn size_mb(&self) -> u64 {
-        ((self.end_lba - self.start_lba + 1) * 512) / (1024 * 1024)
+        ((self.end_lba - self.start_lba + 1) * self.block_size as u64) / (1024 * 1024)
     }
 
     pub fn type_name(&self) -> &'static str {
-        match self.partition_type {
-            PartitionType::EfiSystem => "EFI System",
-            PartitionType::LinuxFilesystem => "Linux FS",
-            PartitionType::LinuxSwap => "Linux Swap",
-            PartitionType::BasicData => "Basic Data",
-            PartitionType::Unknown => "Unknown",
-        }
+        part_type::name(&self.type_guid)
+    }
+
+    /// gdisk type code ("EF00", "8300", ...)
+    pub fn type_code(&self) -> &'static str {
+        part_type::code(&self.type_guid)
+    }
+
+    /// Whether other systems are told to hide and not mount it
+    pub fn is_hidden(&self) -> bool {
+        self.attributes & part_type::CHUNK_HIDDEN != 0
     }
 }
 
 impl PartitionType {
-    /// Convert from gpt_disk_types GUID to PartitionType
-    pub fn from_gpt_guid(guid: &gpt_disk_types::GptPartitionType) -> Self {
-        use gpt_disk_types::{guid, GptPartitionType as GptType};
-
-        if guid == &GptType::EFI_SYSTEM {
-            PartitionType::EfiSystem
-        } else if guid == &GptType::BASIC_DATA {
-            PartitionType::BasicData
-        } else {
-            // Check Linux types
-            let linux_fs = GptType(guid!("0fc63daf-8483-4772-8e79-3d69d8477de4"));
-            let linux_swap = GptType(guid!("0657fd6d-a4ab-43c4-84e5-0933c84b4f4f"));
-
-            if guid == &linux_fs {
-                PartitionType::LinuxFilesystem
-            } else if guid == &linux_swap {
-                PartitionType::LinuxSwap
-            } else {
-                PartitionType::Unknown
-            }
+    /// Type of a partition with this on-disk type GUID
+    pub fn from_type_guid(guid: &[u8; 16]) -> Self {
+        match *guid {
+            part_type::EFI_SYSTEM => PartitionType::EfiSystem,
+            part_type::BASIC_DATA => PartitionType::BasicData,
+            part_type::LINUX_FS => PartitionType::LinuxFilesystem,
+            part_type::LINUX_SWAP => PartitionType::LinuxSwap,
+            part_type::ISO_CHUNK => PartitionType::IsoChunk,
+            _ => PartitionType::Unknown,
         }
     }
 
-    /// Convert to gpt_disk_types GUID
-    pub fn to_gpt_guid(&self) -> gpt_disk_types::GptPartitionType {
-        use gpt_disk_types::{guid, GptPartitionType as GptType};
+    /// On-disk type GUID (all zero for `Unknown`)
+    pub fn type_guid(&self) -> [u8; 16] {
+        match self {
+            PartitionType::EfiSystem => part_type::EFI_SYSTEM,
+            PartitionType::BasicData => part_type::BASIC_DATA,
+            PartitionType::LinuxFilesystem => part_type::LINUX_FS,
+            PartitionType::LinuxSwap => part_type::LINUX_SWAP,
+            PartitionType::IsoChunk => part_type::ISO_CHUNK,
+            PartitionType::Unknown => part_type::UNUSED,
+        }
+    }
 
+    /// Display name, from the type registry
+    pub fn name(&self) -> &'static str {
         match self {
-            PartitionType::EfiSystem => GptType::EFI_SYSTEM,
-            PartitionType::BasicData => GptType::BASIC_DATA,
-            PartitionType::LinuxFilesystem => {
-                GptType(guid!("0fc63daf-8483-4772-8e79-3d69d8477de4"))
-            }
-            PartitionType::LinuxSwap => GptType(guid!("0657fd6d-a4ab-43c4-84e5-0933c84b4f4f")),
-            PartitionType::Unknown => GptType::UNUSED,
+            PartitionType::Unknown => "Unknown",
+            known => part_type::name(&known.type_guid()),
         }
     }
+
+    /// Convert from gpt_disk_types GUID to PartitionType
+    pub fn from_gpt_guid(guid: &gpt_disk_types::GptPartitionType) -> Self {
+        Self::from_type_guid(&guid.0.to_bytes())
+    }
+
+    /// Convert to gpt_disk_types GUID
+    pub fn to_gpt_guid(&self) -> gpt_disk_types::GptPartitionType {
+        gpt_disk_types::GptPartitionType(gpt_disk_types::Guid::from_bytes(self.type_guid()))
+    }
 }
 
 /// Partition table for a disk
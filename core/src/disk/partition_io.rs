@@ -0,0 +1,155 @@
+// Block I/O scoped to one partition
+//
+// Filesystem code addresses blocks from the start of its partition. Going
+// through `PartitionBlockIo` instead of adding the partition's start LBA
+// by hand means a miscounted offset fails with `OutOfRange` rather than
+// writing over the GPT or the partition next door.
+
+use core::fmt;
+use gpt_disk_io::BlockIo;
+use gpt_disk_types::{BlockSize, Lba};
+
+/// A partition's blocks, numbered from 0, on top of the whole-disk device.
+pub struct PartitionBlockIo<'a, B: BlockIo> {
+    disk: &'a mut B,
+    start_lba: u64,
+    num_blocks: u64,
+}
+
+impl<'a, B: BlockIo> PartitionBlockIo<'a, B> {
+    /// The `num_blocks` blocks of `disk` from `start_lba` on.
+    pub fn new(disk: &'a mut B, start_lba: u64, num_blocks: u64) -> Self {
+        Self {
+            disk,
+            start_lba,
+            num_blocks,
+        }
+    }
+
+    /// The partition `start_lba..=end_lba`, as a GPT entry gives it.
+    pub fn from_range(disk: &'a mut B, start_lba: u64, end_lba: u64) -> Self {
+        let num_blocks = (end_lba + 1).saturating_sub(start_lba);
+        Self::new(disk, start_lba, num_blocks)
+    }
+
+    /// Disk LBA of the partition's first block
+    pub fn start_lba(&self) -> u64 {
+        self.start_lba
+    }
+
+    /// Disk LBA of partition block `lba`
+    pub fn disk_lba(&self, lba: u64) -> u64 {
+        self.start_lba + lba
+    }
+
+    /// Disk LBA of `len` bytes at partition block `lba`, if they are all
+    /// inside the partition.
+    fn translate(&self, lba: Lba, len: usize) -> Result<Lba, PartitionIoError<B::Error>> {
+        let blocks = (len as u64).div_ceil(self.disk.block_size().to_u64());
+        match lba.0.checked_add(blocks) {
+            Some(end) if end <= self.num_blocks => Ok(Lba(self.start_lba + lba.0)),
+            _ => Err(PartitionIoError::OutOfRange {
+                lba: lba.0,
+                blocks,
+                num_blocks: self.num_blocks,
+            }),
+        }
+    }
+}
+
+/// Why a [`PartitionBlockIo`] access failed.
+#[derive(Debug)]
+pub enum PartitionIoError<E> {
+    /// Blocks `lba..lba + blocks` are not all inside the partition
+    OutOfRange {
+        lba: u64,
+        blocks: u64,
+        num_blocks: u64,
+    },
+    /// The disk failed the access
+    Disk(E),
+}
+
+impl<E: fmt::Display> fmt::Display for PartitionIoError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutOfRange {
+                lba,
+                blocks,
+                num_blocks,
+            } => write!(
+                f,
+                "blocks {}..{} outside partition of {} blocks",
+                lba,
+                lba + blocks,
+                num_blocks
+            ),
+            Self::Disk(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl<B: BlockIo> BlockIo for PartitionBlockIo<'_, B> {
+    type Error = PartitionIoError<B::Error>;
+
+    fn block_size(&self) -> BlockSize {
+        self.disk.block_size()
+    }
+
+    fn num_blocks(&mut self) -> Result<u64, Self::Error> {
+        Ok(self.num_blocks)
+    }
+
+    fn read_blocks(&mut self, start_lba: Lba, dst: &mut [u8]) -> Result<(), Self::Error> {
+        let lba = self.translate(start_lba, dst.len())?;
+        self.disk
+            .read_blocks(lba, dst)
+            .map_err(PartitionIoError::Disk)
+    }
+
+    fn write_blocks(&mut self, start_lba: Lba, src: &[u8]) -> Result<(), Self::Error> {
+        let lba = self.translate(start_lba, src.len())?;
+        self.disk
+            .write_blocks(lba, src)
+            .map_err(PartitionIoError::Disk)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.disk.flush().map_err(PartitionIoError::Disk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::SparseDisk;
+
+    #[test]
+    fn test_offsets_and_bounds() {
+        let mut disk = SparseDisk::new(4096);
+        {
+            let mut part = PartitionBlockIo::from_range(&mut disk, 2048, 2055);
+            assert_eq!(part.num_blocks().unwrap(), 8);
+            part.write_blocks(Lba(0), &[1; 512]).unwrap();
+            part.write_blocks(Lba(6), &[2; 1024]).unwrap();
+
+            // Running past the end, or wrapping around, touches nothing
+            assert!(matches!(
+                part.write_blocks(Lba(7), &[3; 1024]),
+                Err(PartitionIoError::OutOfRange {
+                    lba: 7,
+                    blocks: 2,
+                    ..
+                })
+            ));
+            assert!(part.write_blocks(Lba(u64::MAX), &[3; 512]).is_err());
+            let mut sector = [0u8; 512];
+            assert!(part.read_blocks(Lba(8), &mut sector).is_err());
+            part.read_blocks(Lba(7), &mut sector).unwrap();
+            assert_eq!(sector, [2; 512]);
+        }
+        assert_eq!(disk.sector(2048), [1; 512]);
+        assert_eq!(disk.sector(2055), [2; 512]);
+        assert!(!disk.is_written(2056) && !disk.is_written(2047));
+    }
+}
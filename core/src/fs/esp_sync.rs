@@ -0,0 +1,264 @@
+// Mirrored ESP synchronisation
+//
+// Users who keep MorpheusX on two ESPs (one per disk of a mirror) need
+// both to carry the same binary, settings and ISO manifests. Each ESP is
+// snapshotted on its own - file list plus a CRC32 of every managed file -
+// and the two snapshots are diffed into a list of copies, so callers that
+// can only have one disk open at a time can still sync.
+//
+// These FAT32 ops don't keep timestamps, so "newer" is decided by role:
+// the primary ESP (the one MorpheusX booted from, or was just updated on)
+// wins any file that differs. Files only the mirror has, such as a
+// manifest for an ISO downloaded while booted from it, are copied back.
+
+use super::fat32_ops::{read_file, replace_file, Fat32Volume};
+use super::Fat32Error;
+use crate::iso::{crc32, MANIFEST_DIR};
+use gpt_disk_io::BlockIo;
+
+extern crate alloc;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Managed files at fixed paths
+pub const MANAGED_FILES: &[&str] = &["/EFI/BOOT/BOOTX64.EFI"];
+
+/// Directories whose files are all managed (not recursive)
+pub const MANAGED_DIRS: &[&str] = &["/EFI/MORPHEUS", MANIFEST_DIR];
+
+/// Files in managed directories that describe their own ESP
+const LOCAL_FILES: &[&str] = &[
+    "/EFI/MORPHEUS/CRASH.TXT",
+    "/EFI/MORPHEUS/CRASH.OLD",
+    "/.iso/INTENT.JNL",
+    "/.iso/LASTRUN.RPT",
+    "/.iso/LASTRUN.OLD",
+];
+
+/// A managed file as found on one ESP
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileDigest {
+    pub path: String,
+    pub size: u32,
+    pub crc: u32,
+}
+
+/// Managed files on one ESP
+#[derive(Debug, Default)]
+pub struct EspSnapshot {
+    pub files: Vec<FileDigest>,
+}
+
+impl EspSnapshot {
+    fn get(&self, path: &str) -> Option<&FileDigest> {
+        self.files
+            .iter()
+            .find(|f| f.path.eq_ignore_ascii_case(path))
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Direction {
+    ToMirror,
+    ToPrimary,
+}
+
+/// One file to copy
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SyncItem {
+    pub path: String,
+    pub direction: Direction,
+    pub size: u32,
+}
+
+/// Outcome of `sync`
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    pub copied: usize,
+    pub up_to_date: usize,
+}
+
+/// Read and hash every managed file on the ESP at `partition_lba_start`.
+/// Missing files and directories are simply left out.
+pub fn snapshot<B: BlockIo>(
+    block_io: &mut B,
+    partition_lba_start: u64,
+) -> Result<EspSnapshot, Fat32Error> {
+    let mut volume = Fat32Volume::open(block_io, partition_lba_start)?;
+    let mut paths: Vec<String> = Vec::new();
+    for path in MANAGED_FILES {
+        if volume.file_exists(path)? {
+            paths.push(String::from(*path));
+        }
+    }
+    for dir in MANAGED_DIRS {
+        // A missing directory lists as an error; nothing to sync from it
+        let Ok(files) = volume.list_directory(dir) else {
+            continue;
+        };
+        for file in files.iter().filter(|f| !f.is_dir) {
+            let path = format!("{}/{}", dir, file.name);
+            if !LOCAL_FILES.iter().any(|l| l.eq_ignore_ascii_case(&path)) {
+                paths.push(path);
+            }
+        }
+    }
+
+    let mut snapshot = EspSnapshot::default();
+    for path in paths {
+        let data = volume.read_file(&path)?;
+        snapshot.files.push(FileDigest {
+            size: data.len() as u32,
+            crc: crc32(&data),
+            path,
+        });
+    }
+    Ok(snapshot)
+}
+
+/// Diff two snapshots into the copies that bring both ESPs in line.
+pub fn plan(primary: &EspSnapshot, mirror: &EspSnapshot) -> Vec<SyncItem> {
+    let mut items = Vec::new();
+    for file in &primary.files {
+        let differs = mirror
+            .get(&file.path)
+            .is_none_or(|m| m.size != file.size || m.crc != file.crc);
+        if differs {
+            items.push(SyncItem {
+                path: file.path.clone(),
+                direction: Direction::ToMirror,
+                size: file.size,
+            });
+        }
+    }
+    for file in &mirror.files {
+        if primary.get(&file.path).is_none() {
+            items.push(SyncItem {
+                path: file.path.clone(),
+                direction: Direction::ToPrimary,
+                size: file.size,
+            });
+        }
+    }
+    items
+}
+
+/// Copy one file between ESPs.
+pub fn copy_file<S: BlockIo, D: BlockIo>(
+    src: &mut S,
+    src_lba: u64,
+    dst: &mut D,
+    dst_lba: u64,
+    path: &str,
+) -> Result<(), Fat32Error> {
+    let data = read_file(src, src_lba, path)?;
+    replace_file(dst, dst_lba, path, &data)
+}
+
+/// Snapshot, diff and copy in one go, for callers that can have both
+/// ESPs open at once.
+pub fn sync<P: BlockIo, M: BlockIo>(
+    primary: &mut P,
+    primary_lba: u64,
+    mirror: &mut M,
+    mirror_lba: u64,
+) -> Result<SyncReport, Fat32Error> {
+    let primary_snapshot = snapshot(primary, primary_lba)?;
+    let mirror_snapshot = snapshot(mirror, mirror_lba)?;
+    let items = plan(&primary_snapshot, &mirror_snapshot);
+
+    for item in &items {
+        match item.direction {
+            Direction::ToMirror => copy_file(primary, primary_lba, mirror, mirror_lba, &item.path)?,
+            Direction::ToPrimary => {
+                copy_file(mirror, mirror_lba, primary, primary_lba, &item.path)?
+            }
+        }
+    }
+
+    Ok(report(&primary_snapshot, &items))
+}
+
+/// Summarise a sync of `items`, planned against `primary`.
+pub fn report(primary: &EspSnapshot, items: &[SyncItem]) -> SyncReport {
+    let copied_back = items
+        .iter()
+        .filter(|i| i.direction == Direction::ToPrimary)
+        .count();
+    SyncReport {
+        copied: items.len(),
+        up_to_date: primary.files.len() + copied_back - items.len(),
+    }
+}
+
+#[cfg(all(test, not(feature = "no-format")))]
+mod tests {
+    use super::*;
+    use crate::fs::{create_directory, file_exists, format_fat32, list_directory, write_file};
+    use alloc::vec;
+    use gpt_disk_io::BlockIoAdapter;
+    use gpt_disk_types::BlockSize;
+
+    /// Smallest volume the formatter accepts
+    const ESP_SECTORS: u64 = 133_120;
+
+    #[test]
+    fn test_sync_mirrors() {
+        let mut primary_storage = vec![0u8; ESP_SECTORS as usize * 512];
+        let mut mirror_storage = vec![0u8; ESP_SECTORS as usize * 512];
+        let mut primary = BlockIoAdapter::new(primary_storage.as_mut_slice(), BlockSize::BS_512);
+        let mut mirror = BlockIoAdapter::new(mirror_storage.as_mut_slice(), BlockSize::BS_512);
+        format_fat32(&mut primary, 0, ESP_SECTORS).unwrap();
+        format_fat32(&mut mirror, 0, ESP_SECTORS).unwrap();
+
+        // Primary has the updated binary and a manifest; the mirror has
+        // the old binary, its own manifest and a journal
+        write_file(&mut primary, 0, "/EFI/BOOT/BOOTX64.EFI", b"morpheus v2").unwrap();
+        write_file(&mut primary, 0, "/.iso/AAAA0001.MFS", b"arch").unwrap();
+        write_file(&mut mirror, 0, "/EFI/BOOT/BOOTX64.EFI", b"morpheus v1").unwrap();
+        create_directory(&mut mirror, 0, "/.iso").unwrap();
+        write_file(&mut mirror, 0, "/.iso/BBBB0002.MFS", b"tails").unwrap();
+        write_file(&mut mirror, 0, "/.iso/INTENT.JNL", b"journal").unwrap();
+
+        let items = plan(
+            &snapshot(&mut primary, 0).unwrap(),
+            &snapshot(&mut mirror, 0).unwrap(),
+        );
+        assert_eq!(items.len(), 3);
+        assert!(items
+            .iter()
+            .any(|i| i.path == "/.iso/BBBB0002.MFS" && i.direction == Direction::ToPrimary));
+
+        let report = sync(&mut primary, 0, &mut mirror, 0).unwrap();
+        assert_eq!(
+            report,
+            SyncReport {
+                copied: 3,
+                up_to_date: 0
+            }
+        );
+
+        assert_eq!(
+            read_file(&mut mirror, 0, "/EFI/BOOT/BOOTX64.EFI").unwrap(),
+            b"morpheus v2"
+        );
+        assert_eq!(
+            read_file(&mut primary, 0, "/.iso/BBBB0002.MFS").unwrap(),
+            b"tails"
+        );
+        assert!(!file_exists(&mut primary, 0, "/.iso/INTENT.JNL").unwrap());
+        // The old binary was replaced, not shadowed by a second entry
+        let boot = list_directory(&mut mirror, 0, "/EFI/BOOT").unwrap();
+        assert_eq!(boot.len(), 1);
+
+        let report = sync(&mut primary, 0, &mut mirror, 0).unwrap();
+        assert_eq!(
+            report,
+            SyncReport {
+                copied: 0,
+                up_to_date: 3
+            }
+        );
+    }
+}
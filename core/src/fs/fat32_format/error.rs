@@ -1,5 +1,7 @@
 // Common error type for FAT32 formatting operations
 
+use crate::fs::path::PathError;
+
 #[derive(Debug)]
 pub enum Fat32Error {
     IoError,
@@ -7,4 +9,17 @@ pub enum Fat32Error {
     PartitionTooLarge,
     InvalidBlockSize,
     NotImplemented,
+    /// Path has more components than `path::MAX_PATH_DEPTH`
+    PathTooDeep,
+    /// Path leaves the root, has control characters, or names no file
+    InvalidPath,
+}
+
+impl From<PathError> for Fat32Error {
+    fn from(error: PathError) -> Self {
+        match error {
+            PathError::TooDeep => Fat32Error::PathTooDeep,
+            PathError::Invalid => Fat32Error::InvalidPath,
+        }
+    }
 }
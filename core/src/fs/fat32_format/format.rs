@@ -161,8 +161,12 @@ pub fn format_fat32<B: BlockIo>(
     let data_sectors = total_sectors - reserved_sectors as u32 - fat_sectors;
     let cluster_count = data_sectors / sectors_per_cluster as u32;
 
+    // Hidden sectors is a 32-bit BPB field; partitions starting past 2TiB
+    // record 0, which is what GPT-aware readers expect anyway
+    let hidden_sectors = u32::try_from(partition_lba_start).unwrap_or(0);
+
     // Create boot sector
-    let boot_sector = Fat32BootSector::new(total_sectors, fat_size, partition_lba_start as u32);
+    let boot_sector = Fat32BootSector::new(total_sectors, fat_size, hidden_sectors);
     let boot_bytes = boot_sector.to_bytes();
 
     // Write boot sector to LBA 0 of partition
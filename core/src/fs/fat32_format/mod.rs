@@ -1,7 +1,11 @@
 mod error;
+#[cfg(not(feature = "no-format"))]
 mod format;
+#[cfg(not(feature = "no-format"))]
 mod verify;
 
 pub use error::Fat32Error;
+#[cfg(not(feature = "no-format"))]
 pub use format::format_fat32;
+#[cfg(not(feature = "no-format"))]
 pub use verify::verify_fat32;
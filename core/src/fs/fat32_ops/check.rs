@@ -0,0 +1,749 @@
+// FAT32 consistency check and repair
+//
+// Walks the directory tree from the root, claiming every cluster of every
+// chain it reaches. A cluster claimed twice is cross-linked, a chain that
+// runs into a free, bad or out-of-range cluster is broken, and an in-use
+// cluster nobody claimed is orphaned. Repairs only ever shorten or drop
+// things: chains are truncated where they go wrong, entries whose first
+// cluster is unusable are deleted, orphans are freed and FSInfo is rebuilt
+// from the FAT. Nothing is salvaged into files the way fsck.vfat does.
+
+use super::super::Fat32Error;
+use super::context::Fat32Context;
+use super::types::{DirEntry, ATTR_DIRECTORY, ATTR_VOLUME_ID};
+use crate::disk::partition_io::PartitionBlockIo;
+use gpt_disk_io::BlockIo;
+use gpt_disk_types::Lba;
+
+extern crate alloc;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+const SECTOR_SIZE: usize = 512;
+
+/// FAT sectors read per batch when loading and comparing the FATs
+const FAT_BATCH_SECTORS: u32 = 32;
+
+/// Lowest end-of-chain value
+const EOC_MIN: u32 = 0x0FFFFFF8;
+const EOC: u32 = 0x0FFFFFFF;
+const BAD_CLUSTER: u32 = 0x0FFFFFF7;
+
+/// `owner` of a cluster found orphaned
+const ORPHAN: u32 = u32::MAX;
+
+const FSINFO_LEAD_SIG: u32 = 0x41615252;
+const FSINFO_STRUC_SIG: u32 = 0x61417272;
+const FSINFO_TRAIL_SIG: u32 = 0xAA550000;
+/// FSInfo free count meaning "not known"
+const FSINFO_UNKNOWN: u32 = 0xFFFFFFFF;
+
+/// Something `check` found wrong with the volume.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    /// The boot sector doesn't describe a usable FAT32 volume; nothing
+    /// else is checked
+    BadBootSector(&'static str),
+    /// FAT copy `copy` (counting from 0) differs from the active FAT in
+    /// `sectors` sectors
+    FatCopiesDiffer { copy: u32, sectors: u32 },
+    /// The chain of `path` runs into `cluster`, which is free, bad or
+    /// outside the volume
+    BrokenChain { path: String, cluster: u32 },
+    /// The chain of `path` runs into `cluster`, which an earlier chain
+    /// (or its own) already holds
+    CrossLinked { path: String, cluster: u32 },
+    /// `clusters` in-use clusters from `first_cluster` on that no entry
+    /// reaches
+    Orphaned { first_cluster: u32, clusters: u32 },
+    /// The FSInfo sector is missing its signatures
+    FsInfoInvalid,
+    /// FSInfo's free cluster count disagrees with the FAT
+    FsInfoStale { recorded: u32, actual: u32 },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::BadBootSector(reason) => write!(f, "Bad boot sector: {}", reason),
+            Problem::FatCopiesDiffer { copy, sectors } => {
+                write!(f, "FAT copy {} differs in {} sectors", copy + 1, sectors)
+            }
+            Problem::BrokenChain { path, cluster } => {
+                write!(f, "{}: broken chain at cluster {}", path, cluster)
+            }
+            Problem::CrossLinked { path, cluster } => {
+                write!(f, "{}: cross-linked at cluster {}", path, cluster)
+            }
+            Problem::Orphaned {
+                first_cluster,
+                clusters,
+            } => write!(
+                f,
+                "Orphaned chain of {} clusters at cluster {}",
+                clusters, first_cluster
+            ),
+            Problem::FsInfoInvalid => write!(f, "FSInfo sector invalid"),
+            Problem::FsInfoStale { recorded, actual } => write!(
+                f,
+                "FSInfo free count {} (FAT has {} free)",
+                recorded, actual
+            ),
+        }
+    }
+}
+
+/// What `check` found, and what the volume looks like afterwards.
+#[derive(Debug, Clone, Default)]
+pub struct CheckReport {
+    pub problems: Vec<Problem>,
+    pub files: u32,
+    pub directories: u32,
+    /// Clusters reachable from the root
+    pub used_clusters: u32,
+    pub free_clusters: u32,
+    /// Whether the problems were repaired
+    pub repaired: bool,
+}
+
+impl CheckReport {
+    /// Whether nothing was wrong.
+    pub fn is_clean(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Check the FAT32 volume at `partition_lba_start`, repairing what it
+/// finds if `repair` is set.
+///
+/// A volume whose boot sector is unusable comes back with a single
+/// [`Problem::BadBootSector`] and is never written to. Errors are only
+/// returned when the disk itself fails.
+pub fn check<B: BlockIo>(
+    block_io: &mut B,
+    partition_lba_start: u64,
+    repair: bool,
+) -> Result<CheckReport, Fat32Error> {
+    let mut boot_sector = [0u8; SECTOR_SIZE];
+    PartitionBlockIo::new(block_io, partition_lba_start, 1)
+        .read_blocks(Lba(0), &mut boot_sector)
+        .map_err(|_| Fat32Error::IoError)?;
+    if let Err(reason) = check_boot_sector(&boot_sector) {
+        return Ok(CheckReport {
+            problems: vec![Problem::BadBootSector(reason)],
+            ..CheckReport::default()
+        });
+    }
+
+    let (ctx, block_io) = Fat32Context::from_partition(block_io, partition_lba_start)?;
+    let mut checker = Checker {
+        block_io,
+        fat: Vec::new(),
+        owner: vec![0; ctx.cluster_limit as usize],
+        ctx,
+        repair,
+        report: CheckReport {
+            repaired: repair,
+            ..CheckReport::default()
+        },
+    };
+    checker.load_fats()?;
+    checker.walk_tree()?;
+    checker.find_orphans()?;
+    let fs_info_sector = u16::from_le_bytes([boot_sector[0x30], boot_sector[0x31]]) as u32;
+    checker.check_fs_info(fs_info_sector)?;
+
+    if repair {
+        checker.block_io.flush().map_err(|_| Fat32Error::IoError)?;
+    }
+    let mut report = checker.report;
+    report.repaired &= !report.problems.is_empty();
+    Ok(report)
+}
+
+/// Why `boot_sector` can't be a FAT32 volume this driver can walk.
+pub(super) fn check_boot_sector(boot_sector: &[u8; SECTOR_SIZE]) -> Result<(), &'static str> {
+    let u16_at = |at: usize| u16::from_le_bytes([boot_sector[at], boot_sector[at + 1]]) as u32;
+    let u32_at = |at: usize| {
+        u32::from_le_bytes([
+            boot_sector[at],
+            boot_sector[at + 1],
+            boot_sector[at + 2],
+            boot_sector[at + 3],
+        ])
+    };
+
+    if boot_sector[510..512] != [0x55, 0xAA] {
+        return Err("missing 0x55AA signature");
+    }
+    if u16_at(0x0B) != SECTOR_SIZE as u32 {
+        return Err("sector size is not 512 bytes");
+    }
+    let sectors_per_cluster = boot_sector[0x0D] as u32;
+    if !sectors_per_cluster.is_power_of_two() {
+        return Err("bad sectors per cluster");
+    }
+    let reserved_sectors = u16_at(0x0E);
+    if reserved_sectors == 0 {
+        return Err("no reserved sectors");
+    }
+    let num_fats = boot_sector[0x10] as u32;
+    if num_fats == 0 {
+        return Err("no FATs");
+    }
+    let fat_size = u32_at(0x24);
+    if u16_at(0x11) != 0 || u16_at(0x16) != 0 || fat_size == 0 {
+        return Err("not FAT32 (FAT12/16 fields set)");
+    }
+    let ext_flags = u16_at(0x28);
+    if ext_flags & 0x80 != 0 && ext_flags & 0x0F >= num_fats {
+        return Err("active FAT doesn't exist");
+    }
+    let data_start = reserved_sectors as u64 + num_fats as u64 * fat_size as u64;
+    let total_sectors = u32_at(0x20);
+    if total_sectors as u64 <= data_start {
+        return Err("volume smaller than its FATs");
+    }
+    let cluster_count = (total_sectors - data_start as u32) / sectors_per_cluster;
+    if (fat_size as u64) * (SECTOR_SIZE as u64 / 4) < cluster_count as u64 + 2 {
+        return Err("FAT too small for the volume");
+    }
+    if !(2..cluster_count + 2).contains(&u32_at(0x2C)) {
+        return Err("root cluster outside the volume");
+    }
+    Ok(())
+}
+
+/// A directory entry as found on disk, with where to rewrite it.
+struct Found {
+    entry: DirEntry,
+    lba: u64,
+    index: usize,
+}
+
+struct Checker<'a, 'c, B: BlockIo> {
+    block_io: PartitionBlockIo<'a, B>,
+    ctx: Fat32Context<'c>,
+    /// The first FAT, kept in step with every repair
+    fat: Vec<u32>,
+    /// First cluster of the chain holding each cluster (0 = unclaimed)
+    owner: Vec<u32>,
+    repair: bool,
+    report: CheckReport,
+}
+
+impl<B: BlockIo> Checker<'_, '_, B> {
+    /// Read the active FAT and compare the other copies against it when
+    /// they are mirrored, overwriting them with it when repairing.
+    fn load_fats(&mut self) -> Result<(), Fat32Error> {
+        let ctx = &self.ctx;
+        let mut active = vec![0u8; FAT_BATCH_SECTORS as usize * SECTOR_SIZE];
+        let mut other = vec![0u8; active.len()];
+        let mut differing = vec![0u32; ctx.num_fats as usize];
+        self.fat.reserve(ctx.cluster_limit as usize);
+
+        for batch in (0..ctx.fat_size).step_by(FAT_BATCH_SECTORS as usize) {
+            let sectors = FAT_BATCH_SECTORS.min(ctx.fat_size - batch);
+            let len = sectors as usize * SECTOR_SIZE;
+            let fat_lba = |copy: u32| Lba(ctx.fat_sector_lba(copy, batch));
+            self.block_io
+                .read_blocks(fat_lba(ctx.active_fat), &mut active[..len])
+                .map_err(|_| Fat32Error::IoError)?;
+
+            // Unmirrored copies are allowed to go stale
+            let copies = if ctx.mirrored { ctx.num_fats } else { 0 };
+            for copy in (0..copies).filter(|&copy| copy != ctx.active_fat) {
+                self.block_io
+                    .read_blocks(fat_lba(copy), &mut other[..len])
+                    .map_err(|_| Fat32Error::IoError)?;
+                let mismatched = active[..len]
+                    .chunks(SECTOR_SIZE)
+                    .zip(other[..len].chunks(SECTOR_SIZE))
+                    .filter(|(a, b)| a != b)
+                    .count();
+                if mismatched > 0 {
+                    differing[copy as usize] += mismatched as u32;
+                    if self.repair {
+                        self.block_io
+                            .write_blocks(fat_lba(copy), &active[..len])
+                            .map_err(|_| Fat32Error::IoError)?;
+                    }
+                }
+            }
+
+            for entry in active[..len].chunks(4) {
+                if self.fat.len() == ctx.cluster_limit as usize {
+                    break;
+                }
+                let value = u32::from_le_bytes([entry[0], entry[1], entry[2], entry[3]]);
+                self.fat.push(value & 0x0FFFFFFF);
+            }
+        }
+
+        for (copy, &sectors) in differing.iter().enumerate() {
+            if sectors > 0 {
+                self.report.problems.push(Problem::FatCopiesDiffer {
+                    copy: copy as u32,
+                    sectors,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Claim every chain reachable from the root.
+    fn walk_tree(&mut self) -> Result<(), Fat32Error> {
+        let root = self.ctx.root_cluster;
+        let mut pending = Vec::new();
+        let root_chain = match self.claim_chain(root, "/", None)? {
+            Some(chain) => chain,
+            None => {
+                // The root's own FAT entry is unusable: make it a
+                // one-cluster directory again
+                self.set_fat(root, EOC)?;
+                self.owner[root as usize] = root;
+                self.report.used_clusters += 1;
+                vec![root]
+            }
+        };
+        pending.push((String::new(), root_chain));
+
+        while let Some((dir_path, chain)) = pending.pop() {
+            for found in self.read_directory(&chain)? {
+                let entry = found.entry;
+                if entry.is_free() || entry.attr & ATTR_VOLUME_ID != 0 || entry.name[0] == b'.' {
+                    continue; // Deleted, long-name fragment, label, dot entry
+                }
+                let is_dir = entry.attr & ATTR_DIRECTORY != 0;
+                let path = alloc::format!("{}/{}", dir_path, entry.short_name());
+                if is_dir {
+                    self.report.directories += 1;
+                } else {
+                    self.report.files += 1;
+                }
+
+                let first = entry.first_cluster();
+                if first == 0 && !is_dir {
+                    continue; // Empty file
+                }
+                match self.claim_chain(first, &path, Some(&found))? {
+                    Some(chain) if is_dir => pending.push((path, chain)),
+                    Some(chain) => {
+                        // A truncated file can't be longer than its chain
+                        let cluster_bytes = self.ctx.sectors_per_cluster * SECTOR_SIZE as u32;
+                        let max_size = (chain.len() as u32).saturating_mul(cluster_bytes);
+                        if self.repair && entry.file_size > max_size {
+                            self.rewrite_entry(&found, |raw| {
+                                raw[28..32].copy_from_slice(&max_size.to_le_bytes())
+                            })?;
+                        }
+                    }
+                    None => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Follow the chain starting at `first`, claiming its clusters for
+    /// `path`. Returns the clusters kept, or None if even the first one
+    /// is unusable (the entry at `found` is deleted when repairing).
+    fn claim_chain(
+        &mut self,
+        first: u32,
+        path: &str,
+        found: Option<&Found>,
+    ) -> Result<Option<Vec<u32>>, Fat32Error> {
+        let mut chain: Vec<u32> = Vec::new();
+        let mut cluster = first;
+        loop {
+            let problem = if !(2..self.ctx.cluster_limit).contains(&cluster)
+                || self.fat[cluster as usize] == 0
+            {
+                Some(Problem::BrokenChain {
+                    path: String::from(path),
+                    cluster,
+                })
+            } else if self.owner[cluster as usize] != 0 {
+                Some(Problem::CrossLinked {
+                    path: String::from(path),
+                    cluster,
+                })
+            } else {
+                None
+            };
+            if let Some(problem) = problem {
+                self.report.problems.push(problem);
+                match chain.last() {
+                    Some(&last) => self.set_fat(last, EOC)?,
+                    None => {
+                        if let (Some(found), true) = (found, self.repair) {
+                            self.rewrite_entry(found, |raw| raw[0] = 0xE5)?;
+                        }
+                        return Ok(None);
+                    }
+                }
+                break;
+            }
+
+            self.owner[cluster as usize] = first;
+            self.report.used_clusters += 1;
+            chain.push(cluster);
+            let next = self.fat[cluster as usize];
+            if next >= EOC_MIN {
+                break;
+            }
+            cluster = next;
+        }
+        Ok(Some(chain))
+    }
+
+    /// Every entry of the directory stored in `chain`, up to the end marker.
+    fn read_directory(&mut self, chain: &[u32]) -> Result<Vec<Found>, Fat32Error> {
+        let entry_size = core::mem::size_of::<DirEntry>();
+        let mut entries = Vec::new();
+        for &cluster in chain {
+            let first_sector = self.ctx.cluster_to_sector(cluster);
+            for offset in 0..self.ctx.sectors_per_cluster {
+                let lba = (first_sector + offset) as u64;
+                let mut sector = [0u8; SECTOR_SIZE];
+                self.block_io
+                    .read_blocks(Lba(lba), &mut sector)
+                    .map_err(|_| Fat32Error::IoError)?;
+                for (index, raw) in sector.chunks(entry_size).enumerate() {
+                    if raw[0] == 0x00 {
+                        return Ok(entries); // End of directory
+                    }
+                    let entry =
+                        unsafe { core::ptr::read_unaligned(raw.as_ptr() as *const DirEntry) };
+                    entries.push(Found { entry, lba, index });
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Free every in-use cluster no chain claimed.
+    fn find_orphans(&mut self) -> Result<(), Fat32Error> {
+        let limit = self.ctx.cluster_limit;
+        let is_orphan = |fat: &[u32], owner: &[u32], c: u32| {
+            (2..limit).contains(&c)
+                && owner[c as usize] == 0
+                && !matches!(fat[c as usize], 0 | BAD_CLUSTER)
+        };
+
+        // Orphans another orphan points at aren't the start of a chain
+        let mut pointed_at = vec![false; limit as usize];
+        for cluster in 2..limit {
+            let next = self.fat[cluster as usize];
+            if is_orphan(&self.fat, &self.owner, cluster) && next < limit {
+                pointed_at[next as usize] = true;
+            }
+        }
+
+        for head in 2..limit {
+            if !is_orphan(&self.fat, &self.owner, head) || pointed_at[head as usize] {
+                continue;
+            }
+            let mut clusters = 0;
+            let mut cluster = head;
+            while is_orphan(&self.fat, &self.owner, cluster) {
+                self.owner[cluster as usize] = ORPHAN;
+                clusters += 1;
+                cluster = self.fat[cluster as usize];
+            }
+            self.report.problems.push(Problem::Orphaned {
+                first_cluster: head,
+                clusters,
+            });
+        }
+
+        // Orphaned loops have no head; everything left over is one
+        for cluster in 2..limit {
+            if is_orphan(&self.fat, &self.owner, cluster) {
+                self.owner[cluster as usize] = ORPHAN;
+                self.report.problems.push(Problem::Orphaned {
+                    first_cluster: cluster,
+                    clusters: 1,
+                });
+            }
+        }
+
+        if self.repair {
+            for cluster in 2..limit {
+                if self.owner[cluster as usize] == ORPHAN {
+                    self.set_fat(cluster, 0)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Compare FSInfo at `sector` with the FAT; rebuild it when repairing.
+    fn check_fs_info(&mut self, sector: u32) -> Result<(), Fat32Error> {
+        let free = self.fat[2..].iter().filter(|&&value| value == 0).count() as u32;
+        self.report.free_clusters = free;
+        if sector == 0 || sector >= self.ctx.reserved_sectors {
+            self.report.problems.push(Problem::FsInfoInvalid);
+            return Ok(()); // Nowhere to rebuild it
+        }
+
+        let lba = Lba(sector as u64);
+        let mut fs_info = [0u8; SECTOR_SIZE];
+        self.block_io
+            .read_blocks(lba, &mut fs_info)
+            .map_err(|_| Fat32Error::IoError)?;
+        let u32_at = |at: usize| {
+            u32::from_le_bytes([
+                fs_info[at],
+                fs_info[at + 1],
+                fs_info[at + 2],
+                fs_info[at + 3],
+            ])
+        };
+
+        let problem = if u32_at(0) != FSINFO_LEAD_SIG
+            || u32_at(484) != FSINFO_STRUC_SIG
+            || u32_at(508) != FSINFO_TRAIL_SIG
+        {
+            Some(Problem::FsInfoInvalid)
+        } else if u32_at(488) != free && u32_at(488) != FSINFO_UNKNOWN {
+            Some(Problem::FsInfoStale {
+                recorded: u32_at(488),
+                actual: free,
+            })
+        } else {
+            None
+        };
+        let Some(problem) = problem else {
+            return Ok(());
+        };
+        self.report.problems.push(problem);
+
+        if self.repair {
+            let next_free = (2..self.ctx.cluster_limit)
+                .find(|&c| self.fat[c as usize] == 0)
+                .unwrap_or(FSINFO_UNKNOWN);
+            let mut rebuilt = [0u8; SECTOR_SIZE];
+            rebuilt[0..4].copy_from_slice(&FSINFO_LEAD_SIG.to_le_bytes());
+            rebuilt[484..488].copy_from_slice(&FSINFO_STRUC_SIG.to_le_bytes());
+            rebuilt[488..492].copy_from_slice(&free.to_le_bytes());
+            rebuilt[492..496].copy_from_slice(&next_free.to_le_bytes());
+            rebuilt[508..512].copy_from_slice(&FSINFO_TRAIL_SIG.to_le_bytes());
+            self.block_io
+                .write_blocks(lba, &rebuilt)
+                .map_err(|_| Fat32Error::IoError)?;
+        }
+        Ok(())
+    }
+
+    /// Set FAT entry `cluster` to `value` if repairing.
+    fn set_fat(&mut self, cluster: u32, value: u32) -> Result<(), Fat32Error> {
+        if self.repair {
+            self.ctx
+                .write_fat_entry(&mut self.block_io, cluster, value)?;
+            self.fat[cluster as usize] = value;
+        }
+        Ok(())
+    }
+
+    /// Read the sector holding `found`, let `f` edit its 32 bytes, write it
+    /// back.
+    fn rewrite_entry(
+        &mut self,
+        found: &Found,
+        f: impl FnOnce(&mut [u8]),
+    ) -> Result<(), Fat32Error> {
+        let entry_size = core::mem::size_of::<DirEntry>();
+        let mut sector = [0u8; SECTOR_SIZE];
+        self.block_io
+            .read_blocks(Lba(found.lba), &mut sector)
+            .map_err(|_| Fat32Error::IoError)?;
+        f(&mut sector[found.index * entry_size..(found.index + 1) * entry_size]);
+        self.block_io
+            .write_blocks(Lba(found.lba), &sector)
+            .map_err(|_| Fat32Error::IoError)
+    }
+}
+
+// Needs format_fat32 to build a filesystem to check
+#[cfg(all(test, not(feature = "no-format")))]
+mod tests {
+    use super::*;
+    use crate::fs::fat32_ops::{file_exists, read_file, write_file};
+    use crate::fs::format_fat32;
+    use crate::testing::SparseDisk;
+
+    const START: u64 = 2048;
+    const SECTORS: u64 = 133_120;
+    /// 4 KiB clusters
+    const CLUSTER: usize = 4096;
+
+    /// Fresh volume holding /A.BIN in clusters 3-5 and /B.BIN in 6-7
+    fn two_files() -> (SparseDisk, Vec<u8>, Vec<u8>) {
+        let mut disk = SparseDisk::new(START + SECTORS);
+        format_fat32(&mut disk, START, SECTORS).unwrap();
+        let a = vec![0xAA; 3 * CLUSTER];
+        let b: Vec<u8> = (0..2 * CLUSTER).map(|i| i as u8).collect();
+        write_file(&mut disk, START, "/A.BIN", &a).unwrap();
+        write_file(&mut disk, START, "/B.BIN", &b).unwrap();
+        (disk, a, b)
+    }
+
+    fn set_fat(disk: &mut SparseDisk, cluster: u32, value: u32) {
+        let (ctx, mut partition) = Fat32Context::from_partition(disk, START).unwrap();
+        ctx.write_fat_entry(&mut partition, cluster, value).unwrap();
+    }
+
+    /// Problems besides FSInfo's free count, which writes leave stale
+    fn found(report: &CheckReport) -> Vec<Problem> {
+        report
+            .problems
+            .iter()
+            .filter(|p| !matches!(p, Problem::FsInfoStale { .. }))
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn test_clean_volume() {
+        let mut disk = SparseDisk::new(START + SECTORS);
+        format_fat32(&mut disk, START, SECTORS).unwrap();
+        assert!(check(&mut disk, START, false).unwrap().is_clean());
+
+        write_file(&mut disk, START, "/EFI/BOOT/BOOTX64.EFI", &[1; 5000]).unwrap();
+        write_file(&mut disk, START, "/EFI/BOOT/CONFIG.TXT", b"quiet").unwrap();
+        let report = check(&mut disk, START, false).unwrap();
+        assert!(found(&report).is_empty(), "{:?}", report.problems);
+        assert_eq!((report.files, report.directories), (2, 2));
+        // Root, EFI, BOOT, two clusters of BOOTX64.EFI, one of CONFIG.TXT
+        assert_eq!(report.used_clusters, 6);
+
+        // The driver doesn't keep FSInfo's count; a repair rebuilds it
+        assert!(matches!(
+            report.problems[..],
+            [Problem::FsInfoStale { actual, .. }] if actual == report.free_clusters
+        ));
+        assert!(check(&mut disk, START, true).unwrap().repaired);
+        assert!(check(&mut disk, START, false).unwrap().is_clean());
+    }
+
+    #[test]
+    fn test_fat_copies_differ() {
+        let (mut disk, _, _) = two_files();
+        let fat_size = u32::from_le_bytes(disk.sector(START)[0x24..0x28].try_into().unwrap());
+        let second_fat = START + 32 + fat_size as u64;
+        disk.write_blocks(Lba(second_fat), &[0u8; SECTOR_SIZE])
+            .unwrap();
+
+        let report = check(&mut disk, START, true).unwrap();
+        assert_eq!(
+            found(&report),
+            [Problem::FatCopiesDiffer {
+                copy: 1,
+                sectors: 1
+            }]
+        );
+        assert_eq!(disk.sector(second_fat), disk.sector(START + 32));
+        assert!(check(&mut disk, START, false).unwrap().is_clean());
+    }
+
+    #[test]
+    fn test_cross_link_truncates_later_chain() {
+        let (mut disk, a, b) = two_files();
+        set_fat(&mut disk, 6, 4); // B.BIN: 6 -> 4 -> 5, into A.BIN
+
+        let report = check(&mut disk, START, false).unwrap();
+        assert_eq!(
+            found(&report),
+            [
+                Problem::CrossLinked {
+                    path: String::from("/B.BIN"),
+                    cluster: 4
+                },
+                Problem::Orphaned {
+                    first_cluster: 7,
+                    clusters: 1
+                },
+            ]
+        );
+        assert!(!report.repaired);
+
+        assert!(check(&mut disk, START, true).unwrap().repaired);
+        assert!(check(&mut disk, START, false).unwrap().is_clean());
+        // B.BIN keeps its first cluster, A.BIN is untouched
+        assert_eq!(read_file(&mut disk, START, "/A.BIN").unwrap(), a);
+        assert_eq!(read_file(&mut disk, START, "/B.BIN").unwrap(), b[..CLUSTER]);
+    }
+
+    #[test]
+    fn test_broken_chain() {
+        let (mut disk, a, _) = two_files();
+        set_fat(&mut disk, 4, 0x0FFFFFF7); // A.BIN: 3 -> 4 -> bad
+        set_fat(&mut disk, 6, 0); // B.BIN's first cluster marked free
+
+        let report = check(&mut disk, START, true).unwrap();
+        assert_eq!(
+            found(&report),
+            [
+                Problem::BrokenChain {
+                    path: String::from("/A.BIN"),
+                    cluster: 0x0FFFFFF7
+                },
+                Problem::BrokenChain {
+                    path: String::from("/B.BIN"),
+                    cluster: 6
+                },
+                Problem::Orphaned {
+                    first_cluster: 5,
+                    clusters: 1
+                },
+                Problem::Orphaned {
+                    first_cluster: 7,
+                    clusters: 1
+                },
+            ]
+        );
+        assert!(check(&mut disk, START, false).unwrap().is_clean());
+        assert_eq!(
+            read_file(&mut disk, START, "/A.BIN").unwrap(),
+            a[..2 * CLUSTER]
+        );
+        // Nothing left of B.BIN to keep
+        assert!(!file_exists(&mut disk, START, "/B.BIN").unwrap());
+    }
+
+    #[test]
+    fn test_orphaned_chain_freed() {
+        let (mut disk, _, _) = two_files();
+        set_fat(&mut disk, 100, 101);
+        set_fat(&mut disk, 101, 0x0FFFFFFF);
+
+        let before = check(&mut disk, START, false).unwrap();
+        assert_eq!(
+            found(&before),
+            [Problem::Orphaned {
+                first_cluster: 100,
+                clusters: 2
+            }]
+        );
+        check(&mut disk, START, true).unwrap();
+        let after = check(&mut disk, START, false).unwrap();
+        assert!(after.is_clean());
+        assert_eq!(after.free_clusters, before.free_clusters + 2);
+    }
+
+    #[test]
+    fn test_bad_boot_sector() {
+        let mut disk = SparseDisk::new(START + SECTORS);
+        let report = check(&mut disk, START, true).unwrap();
+        assert_eq!(
+            report.problems,
+            [Problem::BadBootSector("missing 0x55AA signature")]
+        );
+        assert!(!report.repaired);
+        assert_eq!(disk.writes(), 0);
+    }
+}
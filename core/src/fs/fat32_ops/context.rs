@@ -1,29 +1,76 @@
 // FAT32 filesystem context and FAT operations
 
 use super::super::Fat32Error;
+use crate::disk::partition_io::PartitionBlockIo;
+use crate::time::{self, Clock};
+use crate::uefi_alloc::{Allocator, GLOBAL_HEAP};
 use gpt_disk_io::BlockIo;
 use gpt_disk_types::Lba;
 
+use core::cell::{Cell, RefCell};
+
 const SECTOR_SIZE: usize = 512;
 
+/// FAT entries per sector
+const ENTRIES_PER_SECTOR: u32 = (SECTOR_SIZE / 4) as u32;
+
+/// FAT sectors kept in memory, direct-mapped by sector index
+const FAT_CACHE_SLOTS: usize = 8;
+
+/// Recently used sectors of the active FAT. Writes go through to the disk
+/// (every FAT copy when mirrored) before updating the cached sector, so the
+/// cache never holds anything the disk doesn't.
+struct FatCache {
+    /// FAT-relative sector held by each slot (None = empty)
+    tags: [Option<u32>; FAT_CACHE_SLOTS],
+    data: [[u8; SECTOR_SIZE]; FAT_CACHE_SLOTS],
+}
+
 /// FAT32 filesystem context
-pub struct Fat32Context {
+pub struct Fat32Context<'a> {
     pub sectors_per_cluster: u32,
     pub reserved_sectors: u32,
     pub fat_size: u32,
     pub num_fats: u32,
+    /// FAT copy reads come from: the first, unless ExtFlags turns
+    /// mirroring off and names another
+    pub active_fat: u32,
+    /// Whether FAT writes go to every copy (ExtFlags bit 7 clear)
+    pub mirrored: bool,
     pub root_cluster: u32,
     pub data_start_sector: u32,
+    /// One past the highest cluster the volume has
+    pub cluster_limit: u32,
+    /// Sectors the volume spans, boot sector included
+    pub total_sectors: u32,
+    /// Temporary buffers for writes (global heap unless set)
+    pub allocator: &'a dyn Allocator,
+    /// Timestamps for new entries (the global clock unless set)
+    pub clock: Clock,
+    fat_cache: RefCell<FatCache>,
+    /// Where the next free cluster search starts; nothing below is free
+    next_free: Cell<u32>,
 }
 
-impl Fat32Context {
-    pub fn from_boot_sector<B: BlockIo>(
-        block_io: &mut B,
-        partition_start: u64,
-    ) -> Result<Self, Fat32Error> {
+impl<'a> Fat32Context<'a> {
+    /// Open the volume at `partition_lba_start` of `disk`: its context, and
+    /// the partition cut down to the sectors the boot sector gives it, which
+    /// every later access goes through.
+    pub fn from_partition<'d, B: BlockIo>(
+        disk: &'d mut B,
+        partition_lba_start: u64,
+    ) -> Result<(Self, PartitionBlockIo<'d, B>), Fat32Error> {
+        let boot = &mut PartitionBlockIo::new(disk, partition_lba_start, 1);
+        let ctx = Self::from_boot_sector(boot)?;
+        let partition = PartitionBlockIo::new(disk, partition_lba_start, ctx.total_sectors as u64);
+        Ok((ctx, partition))
+    }
+
+    /// Parse the boot sector, block 0 of `block_io`.
+    pub fn from_boot_sector<B: BlockIo>(block_io: &mut B) -> Result<Self, Fat32Error> {
         let mut boot_sector = [0u8; SECTOR_SIZE];
         block_io
-            .read_blocks(Lba(partition_start), &mut boot_sector)
+            .read_blocks(Lba(0), &mut boot_sector)
             .map_err(|_| Fat32Error::IoError)?;
 
         // Parse boot sector
@@ -42,44 +89,115 @@ impl Fat32Context {
             boot_sector[0x2E],
             boot_sector[0x2F],
         ]);
+        let total_sectors = u32::from_le_bytes([
+            boot_sector[0x20],
+            boot_sector[0x21],
+            boot_sector[0x22],
+            boot_sector[0x23],
+        ]);
+        let ext_flags = u16::from_le_bytes([boot_sector[0x28], boot_sector[0x29]]);
+        let mirrored = ext_flags & 0x80 == 0;
+        let active_fat = if mirrored {
+            0
+        } else {
+            (ext_flags & 0x0F) as u32
+        };
+
+        // Not a FAT32 boot sector (unformatted, or another filesystem):
+        // these would send cluster arithmetic out of the partition
+        if boot_sector[510..512] != [0x55, 0xAA]
+            || !sectors_per_cluster.is_power_of_two()
+            || num_fats == 0
+            || fat_size == 0
+            || root_cluster < 2
+            || active_fat >= num_fats
+        {
+            return Err(Fat32Error::IoError);
+        }
 
         let data_start_sector = reserved_sectors + (num_fats * fat_size);
+        let data_clusters = total_sectors.saturating_sub(data_start_sector) / sectors_per_cluster;
+        let cluster_limit = (data_clusters + 2)
+            .min(fat_size.saturating_mul(ENTRIES_PER_SECTOR))
+            .min(0x0FFFFFF7);
 
         Ok(Self {
             sectors_per_cluster,
             reserved_sectors,
             fat_size,
             num_fats,
+            active_fat,
+            mirrored,
             root_cluster,
             data_start_sector,
+            cluster_limit,
+            total_sectors,
+            allocator: &GLOBAL_HEAP,
+            clock: time::now,
+            fat_cache: RefCell::new(FatCache {
+                tags: [None; FAT_CACHE_SLOTS],
+                data: [[0; SECTOR_SIZE]; FAT_CACHE_SLOTS],
+            }),
+            next_free: Cell::new(2),
         })
     }
 
+    /// Take write buffers from `allocator` (UEFI pages pre-EBS).
+    pub fn with_allocator(mut self, allocator: &'a dyn Allocator) -> Self {
+        self.allocator = allocator;
+        self
+    }
+
     pub fn cluster_to_sector(&self, cluster: u32) -> u32 {
         self.data_start_sector + ((cluster - 2) * self.sectors_per_cluster)
     }
 
+    /// Partition-relative LBA of FAT-relative sector `fat_sector` in FAT
+    /// copy `fat_num`
+    pub fn fat_sector_lba(&self, fat_num: u32, fat_sector: u32) -> u64 {
+        (self.reserved_sectors + fat_num * self.fat_size + fat_sector) as u64
+    }
+
+    /// Read FAT-relative sector `fat_sector` of the active FAT through the
+    /// cache and hand it to `f`
+    fn with_fat_sector<B: BlockIo, T>(
+        &self,
+        block_io: &mut B,
+        fat_sector: u32,
+        f: impl FnOnce(&mut [u8; SECTOR_SIZE]) -> T,
+    ) -> Result<T, Fat32Error> {
+        let mut cache = self.fat_cache.borrow_mut();
+        let slot = fat_sector as usize % FAT_CACHE_SLOTS;
+        if cache.tags[slot] != Some(fat_sector) {
+            // Invalidate first: a failed read must not leave a stale tag
+            cache.tags[slot] = None;
+            block_io
+                .read_blocks(
+                    Lba(self.fat_sector_lba(self.active_fat, fat_sector)),
+                    &mut cache.data[slot],
+                )
+                .map_err(|_| Fat32Error::IoError)?;
+            cache.tags[slot] = Some(fat_sector);
+        }
+        Ok(f(&mut cache.data[slot]))
+    }
+
     pub fn read_fat_entry<B: BlockIo>(
         &self,
         block_io: &mut B,
-        partition_start: u64,
         cluster: u32,
     ) -> Result<u32, Fat32Error> {
         let fat_offset = cluster * 4;
-        let fat_sector = self.reserved_sectors + (fat_offset / SECTOR_SIZE as u32);
         let entry_offset = (fat_offset % SECTOR_SIZE as u32) as usize;
 
-        let mut sector = [0u8; SECTOR_SIZE];
-        block_io
-            .read_blocks(Lba(partition_start + fat_sector as u64), &mut sector)
-            .map_err(|_| Fat32Error::IoError)?;
-
-        let entry = u32::from_le_bytes([
-            sector[entry_offset],
-            sector[entry_offset + 1],
-            sector[entry_offset + 2],
-            sector[entry_offset + 3],
-        ]) & 0x0FFFFFFF; // FAT32 uses only 28 bits
+        let entry = self.with_fat_sector(block_io, fat_offset / SECTOR_SIZE as u32, |sector| {
+            u32::from_le_bytes([
+                sector[entry_offset],
+                sector[entry_offset + 1],
+                sector[entry_offset + 2],
+                sector[entry_offset + 3],
+            ])
+        })? & 0x0FFFFFFF; // FAT32 uses only 28 bits
 
         Ok(entry)
     }
@@ -87,32 +205,33 @@ impl Fat32Context {
     pub fn write_fat_entry<B: BlockIo>(
         &self,
         block_io: &mut B,
-        partition_start: u64,
         cluster: u32,
         value: u32,
     ) -> Result<(), Fat32Error> {
         let fat_offset = cluster * 4;
-        let fat_sector = self.reserved_sectors + (fat_offset / SECTOR_SIZE as u32);
         let entry_offset = (fat_offset % SECTOR_SIZE as u32) as usize;
+        let masked_value = value & 0x0FFFFFFF;
 
+        // Modify the active FAT's sector and write it to every copy, which
+        // also mirrors it (or to the active one alone when mirroring is
+        // off); cache it once all of them took it
+        let fat_sector = fat_offset / SECTOR_SIZE as u32;
+        let mut sector = self.with_fat_sector(block_io, fat_sector, |s| *s)?;
+        sector[entry_offset..entry_offset + 4].copy_from_slice(&masked_value.to_le_bytes());
         for fat_num in 0..self.num_fats {
-            let sector_lba = partition_start
-                + (self.reserved_sectors
-                    + fat_num * self.fat_size
-                    + (fat_offset / SECTOR_SIZE as u32)) as u64;
-
-            let mut sector = [0u8; SECTOR_SIZE];
-            block_io
-                .read_blocks(Lba(sector_lba), &mut sector)
-                .map_err(|_| Fat32Error::IoError)?;
-
-            let masked_value = value & 0x0FFFFFFF;
-            sector[entry_offset..entry_offset + 4].copy_from_slice(&masked_value.to_le_bytes());
-
+            if !self.mirrored && fat_num != self.active_fat {
+                continue;
+            }
+            let sector_lba = self.fat_sector_lba(fat_num, fat_sector);
             block_io
                 .write_blocks(Lba(sector_lba), &sector)
                 .map_err(|_| Fat32Error::IoError)?;
         }
+        self.with_fat_sector(block_io, fat_sector, |s| *s = sector)?;
+
+        if masked_value == 0 && cluster < self.next_free.get() {
+            self.next_free.set(cluster);
+        }
 
         Ok(())
     }
@@ -120,12 +239,11 @@ impl Fat32Context {
     pub fn find_free_cluster<B: BlockIo>(
         &self,
         block_io: &mut B,
-        partition_start: u64,
         start_from: u32,
     ) -> Result<u32, Fat32Error> {
-        // Simple linear search for free cluster
-        for cluster in start_from..0x0FFFFFF7 {
-            let entry = self.read_fat_entry(block_io, partition_start, cluster)?;
+        // Linear search; the cache makes it one read per 128 clusters
+        for cluster in start_from.max(2)..self.cluster_limit {
+            let entry = self.read_fat_entry(block_io, cluster)?;
             if entry == 0 {
                 return Ok(cluster);
             }
@@ -133,13 +251,10 @@ impl Fat32Context {
         Err(Fat32Error::IoError) // No free clusters
     }
 
-    pub fn allocate_cluster<B: BlockIo>(
-        &self,
-        block_io: &mut B,
-        partition_start: u64,
-    ) -> Result<u32, Fat32Error> {
-        let cluster = self.find_free_cluster(block_io, partition_start, 2)?;
-        self.write_fat_entry(block_io, partition_start, cluster, 0x0FFFFFF8)?; // EOC marker
+    pub fn allocate_cluster<B: BlockIo>(&self, block_io: &mut B) -> Result<u32, Fat32Error> {
+        let cluster = self.find_free_cluster(block_io, self.next_free.get())?;
+        self.write_fat_entry(block_io, cluster, 0x0FFFFFF8)?; // EOC marker
+        self.next_free.set(cluster + 1);
         Ok(cluster)
     }
 }
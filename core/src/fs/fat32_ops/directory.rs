@@ -1,11 +1,14 @@
 // FAT32 directory operations
 
-use super::super::Fat32Error;
+use super::super::{path, Fat32Error};
 use super::context::Fat32Context;
-use super::types::{DirEntry, ATTR_DIRECTORY};
+use super::types::{DirEntry, FileInfo, ATTR_DIRECTORY, ATTR_VOLUME_ID};
 use gpt_disk_io::BlockIo;
 use gpt_disk_types::Lba;
 
+extern crate alloc;
+use alloc::vec::Vec;
+
 const SECTOR_SIZE: usize = 512;
 
 /// Compare two 8.3 names case-insensitively
@@ -85,7 +88,6 @@ fn entry_matches_lfn_short_name(entry_name: &[u8; 11], target: &[u8]) -> bool {
 
 pub fn ensure_directory_exists<B: BlockIo>(
     block_io: &mut B,
-    partition_start: u64,
     ctx: &Fat32Context,
     parent_cluster: u32,
     name: &str,
@@ -107,10 +109,7 @@ pub fn ensure_directory_exists<B: BlockIo>(
     for sec_offset in 0..ctx.sectors_per_cluster {
         let mut sector_data = [0u8; SECTOR_SIZE];
         block_io
-            .read_blocks(
-                Lba(partition_start + sector as u64 + sec_offset as u64),
-                &mut sector_data,
-            )
+            .read_blocks(Lba(sector as u64 + sec_offset as u64), &mut sector_data)
             .map_err(|_| Fat32Error::IoError)?;
 
         let entries = unsafe {
@@ -137,32 +136,35 @@ pub fn ensure_directory_exists<B: BlockIo>(
     }
 
     // Directory doesn't exist - create it
-    create_directory_in_parent(block_io, partition_start, ctx, parent_cluster, name)
+    create_directory_in_parent(block_io, ctx, parent_cluster, name)
 }
 
 pub fn create_directory_in_parent<B: BlockIo>(
     block_io: &mut B,
-    partition_start: u64,
     ctx: &Fat32Context,
     parent_cluster: u32,
     name: &str,
 ) -> Result<u32, Fat32Error> {
-    let new_cluster = ctx.allocate_cluster(block_io, partition_start)?;
+    let new_cluster = ctx.allocate_cluster(block_io)?;
 
     // Initialize new directory cluster with . and .. entries
     // Use sector-sized stack buffer instead of vec! to avoid heap allocation
 
+    let now = (ctx.clock)();
+
     // Create '.' entry (points to self)
     let mut dot_entry = DirEntry::empty();
     dot_entry.name = *b".          "; // '.' padded with spaces
     dot_entry.attr = ATTR_DIRECTORY;
     dot_entry.set_first_cluster(new_cluster);
+    dot_entry.set_timestamps(now);
 
     // Create '..' entry (points to parent)
     let mut dotdot_entry = DirEntry::empty();
     dotdot_entry.name = *b"..         "; // '..' padded with spaces
     dotdot_entry.attr = ATTR_DIRECTORY;
     dotdot_entry.set_first_cluster(parent_cluster);
+    dotdot_entry.set_timestamps(now);
 
     // Write first sector with . and .. entries
     let mut sector_data = [0u8; SECTOR_SIZE];
@@ -175,24 +177,20 @@ pub fn create_directory_in_parent<B: BlockIo>(
 
     // Write first sector (with . and .. entries)
     block_io
-        .write_blocks(Lba(partition_start + sector as u64), &sector_data)
+        .write_blocks(Lba(sector as u64), &sector_data)
         .map_err(|_| Fat32Error::IoError)?;
 
     // Write remaining sectors as zeros
     let zero_sector = [0u8; SECTOR_SIZE];
     for sec_offset in 1..ctx.sectors_per_cluster {
         block_io
-            .write_blocks(
-                Lba(partition_start + sector as u64 + sec_offset as u64),
-                &zero_sector,
-            )
+            .write_blocks(Lba(sector as u64 + sec_offset as u64), &zero_sector)
             .map_err(|_| Fat32Error::IoError)?;
     }
 
     // Add entry to parent directory
     add_dir_entry_to_cluster(
         block_io,
-        partition_start,
         ctx,
         parent_cluster,
         name,
@@ -207,7 +205,6 @@ pub fn create_directory_in_parent<B: BlockIo>(
 #[allow(clippy::too_many_arguments)]
 pub fn add_dir_entry_to_cluster<B: BlockIo>(
     block_io: &mut B,
-    partition_start: u64,
     ctx: &Fat32Context,
     cluster: u32,
     name: &str,
@@ -217,14 +214,12 @@ pub fn add_dir_entry_to_cluster<B: BlockIo>(
 ) -> Result<(), Fat32Error> {
     let sector = ctx.cluster_to_sector(cluster);
     let entries_per_sector = SECTOR_SIZE / core::mem::size_of::<DirEntry>();
+    let now = (ctx.clock)();
 
     for sec_offset in 0..ctx.sectors_per_cluster {
         let mut sector_data = [0u8; SECTOR_SIZE];
         block_io
-            .read_blocks(
-                Lba(partition_start + sector as u64 + sec_offset as u64),
-                &mut sector_data,
-            )
+            .read_blocks(Lba(sector as u64 + sec_offset as u64), &mut sector_data)
             .map_err(|_| Fat32Error::IoError)?;
 
         let entries = unsafe {
@@ -241,12 +236,10 @@ pub fn add_dir_entry_to_cluster<B: BlockIo>(
                 entry.attr = attr;
                 entry.set_first_cluster(first_cluster);
                 entry.file_size = file_size;
+                entry.set_timestamps(now);
 
                 block_io
-                    .write_blocks(
-                        Lba(partition_start + sector as u64 + sec_offset as u64),
-                        &sector_data,
-                    )
+                    .write_blocks(Lba(sector as u64 + sec_offset as u64), &sector_data)
                     .map_err(|_| Fat32Error::IoError)?;
 
                 return Ok(());
@@ -259,34 +252,138 @@ pub fn add_dir_entry_to_cluster<B: BlockIo>(
 
 pub fn create_directory<B: BlockIo>(
     block_io: &mut B,
-    partition_lba_start: u64,
     ctx: &Fat32Context,
     path: &str,
 ) -> Result<(), Fat32Error> {
-    let path = path.trim_start_matches('/');
-
-    // Use fixed array instead of Vec for path parts (no heap allocation)
-    const MAX_PATH_PARTS: usize = 8;
-    let mut parts: [&str; MAX_PATH_PARTS] = [""; MAX_PATH_PARTS];
-    let mut parts_count = 0;
-    for part in path.split('/') {
-        if parts_count >= MAX_PATH_PARTS {
-            return Err(Fat32Error::IoError); // Path too deep
-        }
-        parts[parts_count] = part;
-        parts_count += 1;
+    let mut current_cluster = ctx.root_cluster;
+    for part in path::normalize(path)?.components() {
+        current_cluster = ensure_directory_exists(block_io, ctx, current_cluster, part)?;
     }
 
-    let mut current_cluster = ctx.root_cluster;
-    for i in 0..parts_count {
-        current_cluster = ensure_directory_exists(
-            block_io,
-            partition_lba_start,
-            ctx,
-            current_cluster,
-            parts[i],
-        )?;
+    Ok(())
+}
+
+/// Where a directory entry lives on disk.
+pub struct EntryLocation {
+    /// Partition-relative LBA of the sector holding the entry
+    pub lba: u64,
+    /// Index of the entry within that sector
+    pub index: usize,
+    pub entry: DirEntry,
+}
+
+/// Find the entry for `path`, file or directory.
+pub fn find_entry<B: BlockIo>(
+    block_io: &mut B,
+    ctx: &Fat32Context,
+    path: &str,
+) -> Result<Option<EntryLocation>, Fat32Error> {
+    let path = path::normalize(path)?;
+    let mut parts = path.components().iter().peekable();
+    let mut cluster = ctx.root_cluster;
+
+    while let Some(part) = parts.next() {
+        let mut target = DirEntry::empty();
+        target.set_name(part);
+
+        let mut found = None;
+        for_each_entry(block_io, ctx, cluster, |lba, index, entry| {
+            if names_match_case_insensitive(&entry.name, &target.name) {
+                found = Some(EntryLocation {
+                    lba,
+                    index,
+                    entry: *entry,
+                });
+                return false;
+            }
+            true
+        })?;
+
+        match found {
+            Some(location) if parts.peek().is_none() => return Ok(Some(location)),
+            Some(location) if location.entry.attr & ATTR_DIRECTORY != 0 => {
+                cluster = location.entry.first_cluster();
+            }
+            _ => return Ok(None),
+        }
     }
+    Ok(None)
+}
+
+/// List the files and subdirectories of `path` ("/" for the root).
+pub fn list_directory<B: BlockIo>(
+    block_io: &mut B,
+    ctx: &Fat32Context,
+    path: &str,
+) -> Result<Vec<FileInfo>, Fat32Error> {
+    let cluster = if path::normalize(path)?.is_root() {
+        ctx.root_cluster
+    } else {
+        match find_entry(block_io, ctx, path)? {
+            Some(location) if location.entry.attr & ATTR_DIRECTORY != 0 => {
+                location.entry.first_cluster()
+            }
+            _ => return Err(Fat32Error::IoError),
+        }
+    };
+
+    let mut files = Vec::new();
+    for_each_entry(block_io, ctx, cluster, |_, _, entry| {
+        let dot_entry = entry.name[0] == b'.' && matches!(entry.name[1], b' ' | b'.');
+        if !dot_entry {
+            files.push(FileInfo {
+                name: entry.short_name(),
+                size: entry.file_size,
+                is_dir: entry.attr & ATTR_DIRECTORY != 0,
+            });
+        }
+        true
+    })?;
+    Ok(files)
+}
 
+/// Call `f` with every short-name entry of the directory starting at
+/// `first_cluster`, following its cluster chain, until `f` returns false.
+/// Deleted entries, long-name fragments and the volume label are skipped.
+fn for_each_entry<B: BlockIo>(
+    block_io: &mut B,
+    ctx: &Fat32Context,
+    first_cluster: u32,
+    mut f: impl FnMut(u64, usize, &DirEntry) -> bool,
+) -> Result<(), Fat32Error> {
+    let entries_per_sector = SECTOR_SIZE / core::mem::size_of::<DirEntry>();
+    let mut cluster = first_cluster;
+
+    while (2..0x0FFFFFF8).contains(&cluster) {
+        let sector = ctx.cluster_to_sector(cluster);
+        for sec_offset in 0..ctx.sectors_per_cluster {
+            let lba = sector as u64 + sec_offset as u64;
+            let mut sector_data = [0u8; SECTOR_SIZE];
+            block_io
+                .read_blocks(Lba(lba), &mut sector_data)
+                .map_err(|_| Fat32Error::IoError)?;
+
+            let entries = unsafe {
+                core::slice::from_raw_parts(
+                    sector_data.as_ptr() as *const DirEntry,
+                    entries_per_sector,
+                )
+            };
+
+            for (index, entry) in entries.iter().enumerate() {
+                if entry.name[0] == 0x00 {
+                    return Ok(()); // End of directory
+                }
+                // Long-name fragments carry the volume label bit too
+                if entry.is_free() || entry.attr & ATTR_VOLUME_ID != 0 {
+                    continue;
+                }
+                if !f(lba, index, entry) {
+                    return Ok(());
+                }
+            }
+        }
+        cluster = ctx.read_fat_entry(block_io, cluster)?;
+    }
     Ok(())
 }
@@ -1,47 +1,23 @@
 // FAT32 file read/write operations
 
-use super::super::Fat32Error;
+use super::super::{path, Fat32Error};
 use super::context::Fat32Context;
-use super::directory::add_dir_entry_to_cluster;
-use super::types::{DirEntry, ATTR_ARCHIVE, ATTR_DIRECTORY};
+use super::directory::{add_dir_entry_to_cluster, find_entry};
+use super::types::{DirEntry, ATTR_ARCHIVE, ATTR_DIRECTORY, ATTR_LONG_NAME};
 use gpt_disk_io::BlockIo;
 use gpt_disk_types::Lba;
 
 extern crate alloc;
-use crate::uefi_alloc;
+use crate::uefi_alloc::TempBuffer;
 use alloc::vec;
 use alloc::vec::Vec;
 
 const SECTOR_SIZE: usize = 512;
 
-/// Helper to allocate and free a temporary buffer using UEFI
-/// Pre-EBS: uses UEFI allocate_pages
-/// Must provide boot_services_alloc when calling from pre-EBS context
-unsafe fn with_temp_buffer<F>(
-    size: usize,
-    boot_services_alloc: uefi_alloc::AllocatePages,
-    boot_services_free: uefi_alloc::FreePages,
-    f: F,
-) -> Result<(), Fat32Error>
-where
-    F: FnOnce(&mut [u8]) -> Result<(), Fat32Error>,
-{
-    let pages = uefi_alloc::bytes_to_pages(size);
-    let addr =
-        uefi_alloc::allocate_pages(boot_services_alloc, pages).map_err(|_| Fat32Error::IoError)?;
-
-    let buffer = core::slice::from_raw_parts_mut(addr as *mut u8, size);
-    let result = f(buffer);
-
-    let _ = uefi_alloc::free_pages(boot_services_free, addr, pages);
-    result
-}
-
 /// Helper to write data to a cluster's sectors
 fn write_cluster_data<B: BlockIo>(
     block_io: &mut B,
     ctx: &Fat32Context,
-    partition_start: u64,
     cluster: u32,
     cluster_data: &mut [u8],
     data_chunk: &[u8],
@@ -60,7 +36,7 @@ fn write_cluster_data<B: BlockIo>(
         let end = start + SECTOR_SIZE;
         block_io
             .write_blocks(
-                Lba(partition_start + sector as u64 + sec_offset as u64),
+                Lba(sector as u64 + sec_offset as u64),
                 &cluster_data[start..end],
             )
             .map_err(|_| Fat32Error::IoError)?;
@@ -77,57 +53,21 @@ fn write_cluster_data<B: BlockIo>(
 
 pub fn write_file_in_directory<B: BlockIo>(
     block_io: &mut B,
-    partition_start: u64,
     ctx: &Fat32Context,
     dir_cluster: u32,
     name: &str,
     data: &[u8],
 ) -> Result<(), Fat32Error> {
-    write_file_in_directory_with_progress(
-        block_io,
-        partition_start,
-        ctx,
-        dir_cluster,
-        name,
-        data,
-        &mut None,
-    )
+    write_file_in_directory_with_progress(block_io, ctx, dir_cluster, name, data, &mut None)
 }
 
 pub fn write_file_in_directory_with_progress<B: BlockIo>(
     block_io: &mut B,
-    partition_start: u64,
     ctx: &Fat32Context,
     dir_cluster: u32,
     name: &str,
     data: &[u8],
     progress: &mut Option<&mut dyn FnMut(usize, usize, &str)>,
-) -> Result<(), Fat32Error> {
-    write_file_in_directory_with_progress_uefi(
-        block_io,
-        partition_start,
-        ctx,
-        dir_cluster,
-        name,
-        data,
-        progress,
-        None,
-        None,
-    )
-}
-
-/// UEFI-aware write that can use pre-EBS allocations
-/// When boot_services is Some, uses UEFI allocate_pages for temporary buffers
-pub fn write_file_in_directory_with_progress_uefi<B: BlockIo>(
-    block_io: &mut B,
-    partition_start: u64,
-    ctx: &Fat32Context,
-    dir_cluster: u32,
-    name: &str,
-    data: &[u8],
-    progress: &mut Option<&mut dyn FnMut(usize, usize, &str)>,
-    boot_services_alloc: Option<uefi_alloc::AllocatePages>,
-    boot_services_free: Option<uefi_alloc::FreePages>,
 ) -> Result<(), Fat32Error> {
     let total_size = data.len();
 
@@ -138,6 +78,7 @@ pub fn write_file_in_directory_with_progress_uefi<B: BlockIo>(
 
     // Allocate clusters for file data
     let cluster_size = (ctx.sectors_per_cluster * SECTOR_SIZE as u32) as usize;
+
     let clusters_needed = ((data.len() + cluster_size - 1) / cluster_size).max(1);
 
     // Use fixed-size array instead of Vec - no heap allocation pre-EBS
@@ -147,73 +88,44 @@ pub fn write_file_in_directory_with_progress_uefi<B: BlockIo>(
         return Err(Fat32Error::IoError); // File too large
     }
 
+    // One cluster buffer from the context's allocator (UEFI pages pre-EBS),
+    // taken before any cluster so running out of memory leaks nothing
+    let mut cluster_data =
+        TempBuffer::new(ctx.allocator, cluster_size).ok_or(Fat32Error::IoError)?;
+
     let mut file_clusters = [0u32; MAX_CLUSTERS];
     for i in 0..clusters_needed {
-        let cluster = ctx.allocate_cluster(block_io, partition_start)?;
+        let cluster = ctx.allocate_cluster(block_io)?;
         file_clusters[i] = cluster;
     }
 
     // Chain clusters together in FAT
     for i in 0..clusters_needed - 1 {
-        ctx.write_fat_entry(
-            block_io,
-            partition_start,
-            file_clusters[i],
-            file_clusters[i + 1],
-        )?;
+        ctx.write_fat_entry(block_io, file_clusters[i], file_clusters[i + 1])?;
     }
     // Last cluster is already marked with EOC by allocate_cluster
 
     // Write file data to clusters with progress reporting
-    // Use UEFI allocation if provided (pre-EBS), otherwise use global heap (post-EBS)
     let mut bytes_written = 0;
     for i in 0..clusters_needed {
-        let cluster = file_clusters[i];
         let data_offset = i * cluster_size;
         let data_end = (data_offset + cluster_size).min(data.len());
-        let chunk_size = data_end - data_offset;
-
-        // Choose allocation strategy based on available UEFI services
-        if let (Some(alloc_fn), Some(free_fn)) = (boot_services_alloc, boot_services_free) {
-            // Pre-EBS: use UEFI allocate_pages
-            unsafe {
-                with_temp_buffer(cluster_size, alloc_fn, free_fn, |cluster_data| {
-                    write_cluster_data(
-                        block_io,
-                        ctx,
-                        partition_start,
-                        cluster,
-                        cluster_data,
-                        &data[data_offset..data_end],
-                        chunk_size,
-                        total_size,
-                        &mut bytes_written,
-                        progress,
-                    )
-                })?;
-            }
-        } else {
-            // Post-EBS: use global heap allocator (Vec)
-            let mut cluster_data = vec![0u8; cluster_size];
-            write_cluster_data(
-                block_io,
-                ctx,
-                partition_start,
-                cluster,
-                &mut cluster_data,
-                &data[data_offset..data_end],
-                chunk_size,
-                total_size,
-                &mut bytes_written,
-                progress,
-            )?;
-        }
+        write_cluster_data(
+            block_io,
+            ctx,
+            file_clusters[i],
+            cluster_data.as_mut_slice(),
+            &data[data_offset..data_end],
+            data_end - data_offset,
+            total_size,
+            &mut bytes_written,
+            progress,
+        )?;
     }
 
     // Add directory entry
     add_dir_entry_to_cluster(
         block_io,
-        partition_start,
         ctx,
         dir_cluster,
         name,
@@ -232,12 +144,14 @@ pub fn write_file_in_directory_with_progress_uefi<B: BlockIo>(
 
 pub fn read_file<B: BlockIo>(
     block_io: &mut B,
-    partition_lba_start: u64,
     ctx: &Fat32Context,
     path: &str,
 ) -> Result<Vec<u8>, Fat32Error> {
-    let path = path.trim_start_matches('/');
-    let parts: Vec<&str> = path.split('/').collect();
+    let path = path::normalize(path)?;
+    let parts = path.components();
+    if path.names_directory() {
+        return Err(Fat32Error::InvalidPath);
+    }
 
     let mut current_cluster = ctx.root_cluster;
     for (i, part) in parts.iter().enumerate() {
@@ -250,10 +164,7 @@ pub fn read_file<B: BlockIo>(
         for sec_offset in 0..ctx.sectors_per_cluster {
             let mut sector_data = [0u8; SECTOR_SIZE];
             block_io
-                .read_blocks(
-                    Lba(partition_lba_start + sector as u64 + sec_offset as u64),
-                    &mut sector_data,
-                )
+                .read_blocks(Lba(sector as u64 + sec_offset as u64), &mut sector_data)
                 .map_err(|_| Fat32Error::IoError)?;
 
             let entries = unsafe {
@@ -277,7 +188,6 @@ pub fn read_file<B: BlockIo>(
 
                             return read_file_data(
                                 block_io,
-                                partition_lba_start,
                                 ctx,
                                 entry.first_cluster(),
                                 entry.file_size as usize,
@@ -306,7 +216,6 @@ pub fn read_file<B: BlockIo>(
 
 fn read_file_data<B: BlockIo>(
     block_io: &mut B,
-    partition_start: u64,
     ctx: &Fat32Context,
     first_cluster: u32,
     file_size: usize,
@@ -328,7 +237,7 @@ fn read_file_data<B: BlockIo>(
             let end = start + SECTOR_SIZE;
             block_io
                 .read_blocks(
-                    Lba(partition_start + sector as u64 + sec_offset as u64),
+                    Lba(sector as u64 + sec_offset as u64),
                     &mut cluster_data[start..end],
                 )
                 .map_err(|_| Fat32Error::IoError)?;
@@ -343,8 +252,7 @@ fn read_file_data<B: BlockIo>(
         }
 
         // Get next cluster from FAT
-        current_file_cluster =
-            ctx.read_fat_entry(block_io, partition_start, current_file_cluster)?;
+        current_file_cluster = ctx.read_fat_entry(block_io, current_file_cluster)?;
     }
 
     Ok(data)
@@ -352,12 +260,11 @@ fn read_file_data<B: BlockIo>(
 
 pub fn file_exists<B: BlockIo>(
     block_io: &mut B,
-    partition_lba_start: u64,
     ctx: &Fat32Context,
     path: &str,
 ) -> Result<bool, Fat32Error> {
-    let path = path.trim_start_matches('/');
-    let parts: Vec<&str> = path.split('/').collect();
+    let path = path::normalize(path)?;
+    let parts = path.components();
 
     let mut current_cluster = ctx.root_cluster;
     for (i, part) in parts.iter().enumerate() {
@@ -370,10 +277,7 @@ pub fn file_exists<B: BlockIo>(
         for sec_offset in 0..ctx.sectors_per_cluster {
             let mut sector_data = [0u8; SECTOR_SIZE];
             block_io
-                .read_blocks(
-                    Lba(partition_lba_start + sector as u64 + sec_offset as u64),
-                    &mut sector_data,
-                )
+                .read_blocks(Lba(sector as u64 + sec_offset as u64), &mut sector_data)
                 .map_err(|_| Fat32Error::IoError)?;
 
             let entries = unsafe {
@@ -412,3 +316,42 @@ pub fn file_exists<B: BlockIo>(
 
     Ok(false)
 }
+
+/// Delete the file at `path`: free its cluster chain and mark its
+/// directory entry, and any long-name fragments before it, deleted.
+pub fn delete_file<B: BlockIo>(
+    block_io: &mut B,
+    ctx: &Fat32Context,
+    path: &str,
+) -> Result<(), Fat32Error> {
+    let location = match find_entry(block_io, ctx, path)? {
+        Some(location) if location.entry.attr & ATTR_DIRECTORY == 0 => location,
+        _ => return Err(Fat32Error::IoError), // Missing, or a directory
+    };
+
+    let mut cluster = location.entry.first_cluster();
+    while (2..0x0FFFFFF8).contains(&cluster) {
+        let next = ctx.read_fat_entry(block_io, cluster)?;
+        ctx.write_fat_entry(block_io, cluster, 0)?;
+        cluster = next;
+    }
+
+    let entry_size = core::mem::size_of::<DirEntry>();
+    let mut sector_data = [0u8; SECTOR_SIZE];
+    block_io
+        .read_blocks(Lba(location.lba), &mut sector_data)
+        .map_err(|_| Fat32Error::IoError)?;
+    sector_data[location.index * entry_size] = 0xE5;
+    // Long-name fragments precede the short entry. Any that spill into the
+    // previous sector are left behind; readers ignore orphaned fragments.
+    for index in (0..location.index).rev() {
+        let offset = index * entry_size;
+        if sector_data[offset + 11] != ATTR_LONG_NAME || sector_data[offset] == 0xE5 {
+            break;
+        }
+        sector_data[offset] = 0xE5;
+    }
+    block_io
+        .write_blocks(Lba(location.lba), &sector_data)
+        .map_err(|_| Fat32Error::IoError)
+}
@@ -6,6 +6,8 @@ extern crate alloc;
 use alloc::format;
 use alloc::string::String;
 
+use crate::hash::crc32;
+
 /// Generate 8.3 compatible manifest filename from ISO name using CRC32 hash.
 ///
 /// FAT32 8.3 format limits names to 8 characters + 3 character extension.
@@ -19,6 +21,8 @@ use alloc::string::String;
 ///
 /// # Examples
 /// ```
+/// use morpheus_core::fs::generate_8_3_manifest_name;
+///
 /// let filename = generate_8_3_manifest_name("tails-6.10.iso");
 /// assert_eq!(filename.len(), 12); // "XXXXXXXX.MFS"
 /// assert!(filename.ends_with(".MFS"));
@@ -28,34 +32,6 @@ pub fn generate_8_3_manifest_name(iso_name: &str) -> String {
     format!("{:08X}.MFS", hash)
 }
 
-/// Calculate CRC32 checksum of data.
-///
-/// Uses standard CRC32 algorithm with polynomial 0xEDB88320.
-///
-/// # Arguments
-/// * `data` - Input bytes to hash
-///
-/// # Returns
-/// * CRC32 checksum as u32
-fn crc32(data: &[u8]) -> u32 {
-    const POLYNOMIAL: u32 = 0xEDB88320;
-
-    let mut crc: u32 = 0xFFFFFFFF;
-
-    for &byte in data {
-        crc ^= byte as u32;
-        for _ in 0..8 {
-            if crc & 1 != 0 {
-                crc = (crc >> 1) ^ POLYNOMIAL;
-            } else {
-                crc >>= 1;
-            }
-        }
-    }
-
-    !crc
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,22 +1,32 @@
 // FAT32 filesystem operations - minimal implementation for bootloader installation
 
+mod check;
 mod context;
 mod directory;
 mod file_ops;
 pub mod filename;
+mod probe;
 mod types;
+mod volume;
 
 use super::Fat32Error;
-use crate::uefi_alloc;
-use context::Fat32Context;
+use crate::uefi_alloc::{Allocator, GLOBAL_HEAP};
 use gpt_disk_io::BlockIo;
 
 extern crate alloc;
-use alloc::vec::Vec; // Only used by read_file (post-EBS)
+use alloc::vec::Vec; // Only used by read_file and list_directory (post-EBS)
+
+pub use check::{check, CheckReport, Problem};
+pub use probe::{probe_volume, FsKind, VolumeInfo};
+pub use types::FileInfo;
+pub use volume::Fat32Volume;
 
 /// Progress callback type: (bytes_written, total_bytes, message)
 pub type ProgressCallback<'a> = Option<&'a mut dyn FnMut(usize, usize, &str)>;
 
+// One-shot wrappers around Fat32Volume: each call re-reads the boot sector
+// and starts with a cold FAT cache
+
 /// Write file to FAT32 partition
 pub fn write_file<B: BlockIo>(
     block_io: &mut B,
@@ -35,77 +45,29 @@ pub fn write_file_with_progress<B: BlockIo>(
     data: &[u8],
     progress: &mut ProgressCallback,
 ) -> Result<(), Fat32Error> {
-    write_file_with_progress_uefi(
+    write_file_with_allocator(
         block_io,
         partition_lba_start,
         path,
         data,
         progress,
-        None,
-        None,
+        &GLOBAL_HEAP,
     )
 }
 
-/// Write file to FAT32 partition with progress reporting and UEFI allocation support
-/// Pass Some() for boot_services_* to use UEFI allocate_pages for pre-EBS memory
-pub fn write_file_with_progress_uefi<B: BlockIo>(
+/// Write file to FAT32 partition with progress reporting, taking temporary
+/// buffers from `allocator` (`UefiPages` pre-EBS)
+pub fn write_file_with_allocator<B: BlockIo>(
     block_io: &mut B,
     partition_lba_start: u64,
     path: &str,
     data: &[u8],
     progress: &mut ProgressCallback,
-    boot_services_alloc: Option<uefi_alloc::AllocatePages>,
-    boot_services_free: Option<uefi_alloc::FreePages>,
+    allocator: &dyn Allocator,
 ) -> Result<(), Fat32Error> {
-    let ctx = Fat32Context::from_boot_sector(block_io, partition_lba_start)?;
-
-    // Parse path - use fixed array instead of Vec (no heap allocation pre-EBS)
-    // Max 8 path components should be plenty for EFI paths
-    let path = path.trim_start_matches('/');
-    const MAX_PATH_PARTS: usize = 8;
-    let mut parts: [&str; MAX_PATH_PARTS] = [""; MAX_PATH_PARTS];
-    let mut parts_count = 0;
-    for part in path.split('/') {
-        if parts_count >= MAX_PATH_PARTS {
-            return Err(Fat32Error::IoError); // Path too deep
-        }
-        parts[parts_count] = part;
-        parts_count += 1;
-    }
-
-    // Navigate/create directory structure
-    let mut current_cluster = ctx.root_cluster;
-    for i in 0..parts_count {
-        let part = parts[i];
-        let is_last = i == parts_count - 1;
-
-        if !is_last {
-            // This is a directory component
-            current_cluster = directory::ensure_directory_exists(
-                block_io,
-                partition_lba_start,
-                &ctx,
-                current_cluster,
-                part,
-            )?;
-        } else {
-            // This is the file name - create/write it with UEFI support
-            file_ops::write_file_in_directory_with_progress_uefi(
-                block_io,
-                partition_lba_start,
-                &ctx,
-                current_cluster,
-                part,
-                data,
-                progress,
-                boot_services_alloc,
-                boot_services_free,
-            )?;
-        }
-    }
-
-    block_io.flush().map_err(|_| Fat32Error::IoError)?;
-    Ok(())
+    Fat32Volume::open(block_io, partition_lba_start)?
+        .with_allocator(allocator)
+        .write_file_with_progress(path, data, progress)
 }
 
 /// Create directory (creates full path)
@@ -114,10 +76,7 @@ pub fn create_directory<B: BlockIo>(
     partition_lba_start: u64,
     path: &str,
 ) -> Result<(), Fat32Error> {
-    let ctx = Fat32Context::from_boot_sector(block_io, partition_lba_start)?;
-    directory::create_directory(block_io, partition_lba_start, &ctx, path)?;
-    block_io.flush().map_err(|_| Fat32Error::IoError)?;
-    Ok(())
+    Fat32Volume::open(block_io, partition_lba_start)?.create_directory(path)
 }
 
 /// Read file data from FAT32 partition
@@ -126,8 +85,7 @@ pub fn read_file<B: BlockIo>(
     partition_lba_start: u64,
     path: &str,
 ) -> Result<Vec<u8>, Fat32Error> {
-    let ctx = Fat32Context::from_boot_sector(block_io, partition_lba_start)?;
-    file_ops::read_file(block_io, partition_lba_start, &ctx, path)
+    Fat32Volume::open(block_io, partition_lba_start)?.read_file(path)
 }
 
 /// Check if file exists
@@ -136,6 +94,336 @@ pub fn file_exists<B: BlockIo>(
     partition_lba_start: u64,
     path: &str,
 ) -> Result<bool, Fat32Error> {
-    let ctx = Fat32Context::from_boot_sector(block_io, partition_lba_start)?;
-    file_ops::file_exists(block_io, partition_lba_start, &ctx, path)
+    Fat32Volume::open(block_io, partition_lba_start)?.file_exists(path)
+}
+
+/// List the files and subdirectories of `path` ("/" for the root)
+pub fn list_directory<B: BlockIo>(
+    block_io: &mut B,
+    partition_lba_start: u64,
+    path: &str,
+) -> Result<Vec<FileInfo>, Fat32Error> {
+    Fat32Volume::open(block_io, partition_lba_start)?.list_directory(path)
+}
+
+/// Delete a file
+pub fn delete_file<B: BlockIo>(
+    block_io: &mut B,
+    partition_lba_start: u64,
+    path: &str,
+) -> Result<(), Fat32Error> {
+    Fat32Volume::open(block_io, partition_lba_start)?.delete_file(path)
+}
+
+/// Write a file, replacing any existing file at `path`
+/// (`write_file` alone would add a second entry with the same name)
+pub fn replace_file<B: BlockIo>(
+    block_io: &mut B,
+    partition_lba_start: u64,
+    path: &str,
+    data: &[u8],
+) -> Result<(), Fat32Error> {
+    Fat32Volume::open(block_io, partition_lba_start)?.replace_file(path, data)
+}
+
+// Needs format_fat32 to build a filesystem to work on
+#[cfg(all(test, not(feature = "no-format")))]
+mod tests {
+    use super::*;
+    use crate::fs::{format_fat32, path};
+    use crate::testing::SparseDisk;
+    use crate::uefi_alloc::{host, UefiPages};
+    use alloc::vec;
+
+    /// Partition start and size (the smallest FAT32 we format).
+    const START: u64 = 2048;
+    const SECTORS: u64 = 133_120;
+
+    fn formatted() -> SparseDisk {
+        let mut disk = SparseDisk::new(START + SECTORS);
+        format_fat32(&mut disk, START, SECTORS).unwrap();
+        disk
+    }
+
+    #[test]
+    fn test_write_read_roundtrip() {
+        let mut disk = formatted();
+        // Spans several clusters, last one partial
+        let data: Vec<u8> = (0..20_000u32).map(|i| (i * 7) as u8).collect();
+        write_file(&mut disk, START, "/EFI/BOOT/BOOTX64.EFI", &data).unwrap();
+
+        assert!(file_exists(&mut disk, START, "/EFI/BOOT/BOOTX64.EFI").unwrap());
+        assert!(!file_exists(&mut disk, START, "/EFI/BOOT/OTHER.EFI").unwrap());
+        assert_eq!(
+            read_file(&mut disk, START, "/EFI/BOOT/BOOTX64.EFI").unwrap(),
+            data
+        );
+
+        let root = list_directory(&mut disk, START, "/").unwrap();
+        assert!(root.iter().any(|f| f.name == "EFI" && f.is_dir));
+        let boot = list_directory(&mut disk, START, "/EFI/BOOT").unwrap();
+        assert!(boot
+            .iter()
+            .any(|f| f.name == "BOOTX64.EFI" && f.size == 20_000 && !f.is_dir));
+    }
+
+    #[test]
+    fn test_replace_and_delete() {
+        let mut disk = formatted();
+        write_file(&mut disk, START, "/CONFIG.TXT", b"first").unwrap();
+        replace_file(&mut disk, START, "/CONFIG.TXT", b"second").unwrap();
+        assert_eq!(
+            read_file(&mut disk, START, "/CONFIG.TXT").unwrap(),
+            b"second"
+        );
+        let root = list_directory(&mut disk, START, "/").unwrap();
+        assert_eq!(root.iter().filter(|f| f.name == "CONFIG.TXT").count(), 1);
+
+        delete_file(&mut disk, START, "/CONFIG.TXT").unwrap();
+        assert!(!file_exists(&mut disk, START, "/CONFIG.TXT").unwrap());
+        assert!(read_file(&mut disk, START, "/CONFIG.TXT").is_err());
+    }
+
+    #[test]
+    fn test_pre_ebs_allocation_path() {
+        let mut disk = formatted();
+        let data = vec![0x5Au8; 9000];
+        let pages = unsafe { UefiPages::new(host::allocate_pages, host::free_pages) };
+        write_file_with_allocator(&mut disk, START, "/ISO/DATA.BIN", &data, &mut None, &pages)
+            .unwrap();
+        assert_eq!(read_file(&mut disk, START, "/ISO/DATA.BIN").unwrap(), data);
+    }
+
+    #[test]
+    fn test_unformatted_partition() {
+        let mut disk = SparseDisk::new(START + SECTORS);
+        assert!(read_file(&mut disk, START, "/ANY.TXT").is_err());
+    }
+
+    #[test]
+    fn test_stays_inside_volume() {
+        let mut disk = SparseDisk::new(START + 2 * SECTORS);
+        format_fat32(&mut disk, START, SECTORS).unwrap();
+        // Root directory cluster far past the end of the volume
+        let mut boot = disk.sector(START);
+        boot[0x2C..0x30].copy_from_slice(&0x0010_0000u32.to_le_bytes());
+        disk.write_blocks(gpt_disk_types::Lba(START), &boot).unwrap();
+
+        assert!(write_file(&mut disk, START, "/A.BIN", b"x").is_err());
+        assert!(list_directory(&mut disk, START, "/").is_err());
+        let mut past_end = START + SECTORS..START + 2 * SECTORS;
+        assert!(!past_end.any(|lba| disk.is_written(lba)));
+    }
+
+    #[test]
+    fn test_volume_saves_reads() {
+        // 40 clusters each, so the used part of the FAT grows past one
+        // sector that every one-shot call has to read again
+        let data = vec![0xA5u8; 160 * 1024];
+        let files = ["/EFI/A.BIN", "/EFI/B.BIN", "/EFI/C.BIN", "/EFI/D.BIN"];
+
+        let mut one_shot = formatted();
+        let before = one_shot.reads();
+        for path in files {
+            write_file(&mut one_shot, START, path, &data).unwrap();
+        }
+        let one_shot_reads = one_shot.reads() - before;
+
+        let mut disk = formatted();
+        let before = disk.reads();
+        {
+            let mut volume = Fat32Volume::open(&mut disk, START).unwrap();
+            for path in files {
+                volume.write_file(path, &data).unwrap();
+            }
+        }
+        let volume_reads = disk.reads() - before;
+
+        assert!(
+            volume_reads < one_shot_reads,
+            "{} vs {}",
+            volume_reads,
+            one_shot_reads
+        );
+        // Same filesystem either way
+        for path in files {
+            assert_eq!(read_file(&mut one_shot, START, path).unwrap(), data);
+            assert_eq!(read_file(&mut disk, START, path).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn test_volume_reuses_freed_clusters() {
+        let first_cluster = |disk: &mut SparseDisk, path| {
+            let (ctx, mut partition) = context::Fat32Context::from_partition(disk, START).unwrap();
+            let location = directory::find_entry(&mut partition, &ctx, path).unwrap();
+            location.unwrap().entry.first_cluster()
+        };
+
+        let mut disk = formatted();
+        Fat32Volume::open(&mut disk, START)
+            .unwrap()
+            .write_file("/A.BIN", &[1; 4096])
+            .unwrap();
+        let a = first_cluster(&mut disk, "/A.BIN");
+        {
+            let mut volume = Fat32Volume::open(&mut disk, START).unwrap();
+            volume.write_file("/B.BIN", &[2; 4096]).unwrap();
+            volume.delete_file("/A.BIN").unwrap();
+            volume.write_file("/C.BIN", &[3; 4096]).unwrap();
+        }
+
+        // The search for free clusters went back for A's
+        assert_eq!(first_cluster(&mut disk, "/C.BIN"), a);
+        assert_eq!(read_file(&mut disk, START, "/B.BIN").unwrap(), [2; 4096]);
+        assert_eq!(read_file(&mut disk, START, "/C.BIN").unwrap(), [3; 4096]);
+        let root = list_directory(&mut disk, START, "/").unwrap();
+        assert_eq!(root.iter().filter(|f| !f.is_dir).count(), 2);
+    }
+
+    #[test]
+    fn test_path_normalization() {
+        let mut disk = formatted();
+        write_file(&mut disk, START, "EFI//BOOT/./BOOTX64.EFI", b"MZ").unwrap();
+        for path in [
+            "/EFI/BOOT/BOOTX64.EFI",
+            "\\EFI\\BOOT\\BOOTX64.EFI",
+            "/EFI/TMP/../BOOT/BOOTX64.EFI",
+        ] {
+            assert_eq!(
+                read_file(&mut disk, START, path).unwrap(),
+                b"MZ",
+                "{}",
+                path
+            );
+        }
+        let boot = list_directory(&mut disk, START, "/EFI/BOOT/").unwrap();
+        assert_eq!(boot.len(), 1);
+        assert_eq!(list_directory(&mut disk, START, "/./").unwrap().len(), 1);
+
+        // Deeper than the old limit of 8
+        let deep = "/A/B/C/D/E/F/G/H/I/J/FILE.TXT";
+        write_file(&mut disk, START, deep, b"deep").unwrap();
+        assert_eq!(read_file(&mut disk, START, deep).unwrap(), b"deep");
+
+        let too_deep = "/D".repeat(path::MAX_PATH_DEPTH) + "/FILE.TXT";
+        assert!(matches!(
+            write_file(&mut disk, START, &too_deep, b"x"),
+            Err(Fat32Error::PathTooDeep)
+        ));
+        for invalid in ["/EFI/BOOT/", "/", "/../X.TXT"] {
+            assert!(
+                matches!(
+                    write_file(&mut disk, START, invalid, b"x"),
+                    Err(Fat32Error::InvalidPath)
+                ),
+                "{}",
+                invalid
+            );
+        }
+        for not_a_file in ["/", "/EFI/BOOT/BOOTX64.EFI/"] {
+            assert!(matches!(
+                read_file(&mut disk, START, not_a_file),
+                Err(Fat32Error::InvalidPath)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_timestamps() {
+        use crate::time::DateTime;
+
+        fn clock() -> Option<DateTime> {
+            // 2024-02-29 12:34:57
+            Some(DateTime::from_unix(1_709_210_097))
+        }
+        let entry = |disk: &mut SparseDisk, path| {
+            let (ctx, mut partition) = context::Fat32Context::from_partition(disk, START).unwrap();
+            directory::find_entry(&mut partition, &ctx, path)
+                .unwrap()
+                .unwrap()
+                .entry
+        };
+
+        let mut disk = formatted();
+        Fat32Volume::open(&mut disk, START)
+            .unwrap()
+            .with_clock(clock)
+            .write_file("/EFI/BOOT/BOOTX64.EFI", b"MZ")
+            .unwrap();
+
+        // 44 years after 1980, month 2, day 29; 12:34, 56 s + 1 s in tenths
+        let date = (44 << 9) | (2 << 5) | 29;
+        let time = (12 << 11) | (34 << 5) | 28;
+        for path in ["/EFI", "/EFI/BOOT", "/EFI/BOOT/BOOTX64.EFI"] {
+            let e = entry(&mut disk, path);
+            let stamps = (e.create_date, e.create_time, e.create_time_tenth);
+            assert_eq!(stamps, (date, time, 100), "{}", path);
+            let (modified, accessed) = (e.modify_date, e.access_date);
+            assert_eq!((modified, accessed), (date, date));
+        }
+
+        // No clock: no timestamp
+        let mut volume = Fat32Volume::open(&mut disk, START)
+            .unwrap()
+            .with_clock(|| None);
+        volume.write_file("/OLD.TXT", b"x").unwrap();
+        let e = entry(&mut disk, "/OLD.TXT");
+        let stamps = (e.create_date, e.create_time, e.modify_date);
+        assert_eq!(stamps, (0, 0, 0));
+    }
+
+    /// Every sector of FAT copy `copy`
+    fn fat_copy(disk: &SparseDisk, copy: u64) -> Vec<[u8; 512]> {
+        let fat_size = u32::from_le_bytes(disk.sector(START)[0x24..0x28].try_into().unwrap());
+        let first = START + 32 + copy * fat_size as u64;
+        (first..first + fat_size as u64)
+            .map(|lba| disk.sector(lba))
+            .collect()
+    }
+
+    #[test]
+    fn test_fat_copies_match() {
+        let mut disk = formatted();
+        {
+            let mut volume = Fat32Volume::open(&mut disk, START).unwrap();
+            volume
+                .write_file("/EFI/BOOT/BOOTX64.EFI", &[1; 20_000])
+                .unwrap();
+            volume.write_file("/A.BIN", &[2; 9000]).unwrap();
+            volume.delete_file("/A.BIN").unwrap();
+            volume
+                .replace_file("/EFI/BOOT/BOOTX64.EFI", &[3; 5000])
+                .unwrap();
+        }
+        let first = fat_copy(&disk, 0);
+        assert_ne!(first[0], [0; 512]);
+        assert!(first == fat_copy(&disk, 1));
+    }
+
+    #[test]
+    fn test_unmirrored_fat() {
+        let mut disk = formatted();
+        // ExtFlags: mirroring off, FAT 1 active
+        let mut boot = disk.sector(START);
+        boot[0x28] = 0x81;
+        disk.write_blocks(gpt_disk_types::Lba(START), &boot).unwrap();
+        let untouched = fat_copy(&disk, 0);
+
+        write_file(&mut disk, START, "/A.BIN", &[7; 9000]).unwrap();
+        assert_eq!(read_file(&mut disk, START, "/A.BIN").unwrap(), [7; 9000]);
+        assert!(fat_copy(&disk, 0) == untouched);
+        assert!(fat_copy(&disk, 1) != untouched);
+        // The inactive copy being stale is not a problem
+        let report = check(&mut disk, START, false).unwrap();
+        assert!(!report
+            .problems
+            .iter()
+            .any(|p| matches!(p, Problem::FatCopiesDiffer { .. })));
+
+        // An active FAT that isn't there
+        boot[0x28] = 0x82;
+        disk.write_blocks(gpt_disk_types::Lba(START), &boot).unwrap();
+        assert!(read_file(&mut disk, START, "/A.BIN").is_err());
+    }
 }
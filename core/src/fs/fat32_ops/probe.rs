@@ -0,0 +1,345 @@
+// Volume probe
+//
+// Says what is on a partition before anything is written to it: which
+// filesystem its boot sector describes, the volume label and how much of
+// a FAT32 volume is free. Partitions typed as ESPs are not always FAT32 -
+// some tools put FAT16 on small ones, and a mislabeled NTFS or exFAT
+// partition is not unheard of - so the installer shows this next to each
+// ESP rather than trusting the GPT type.
+//
+// Free space is counted from the FAT rather than taken from FSInfo, which
+// is only a hint and goes stale when another OS doesn't update it.
+
+use super::super::Fat32Error;
+use super::check::check_boot_sector;
+use super::context::Fat32Context;
+use super::types::{ATTR_LONG_NAME, ATTR_VOLUME_ID};
+use crate::disk::partition_io::PartitionBlockIo;
+use gpt_disk_io::BlockIo;
+use gpt_disk_types::Lba;
+
+extern crate alloc;
+use alloc::string::String;
+use alloc::vec;
+
+const SECTOR_SIZE: usize = 512;
+
+/// FAT sectors read per batch when counting free clusters
+const FAT_BATCH_SECTORS: u32 = 32;
+
+/// Label mkfs tools write when none is given
+const NO_NAME: &str = "NO NAME";
+
+/// Filesystem a boot sector describes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FsKind {
+    Fat32,
+    Fat16,
+    Fat12,
+    ExFat,
+    Ntfs,
+    /// No recognizable boot sector (unformatted, or something else)
+    Unknown,
+}
+
+impl FsKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FsKind::Fat32 => "FAT32",
+            FsKind::Fat16 => "FAT16",
+            FsKind::Fat12 => "FAT12",
+            FsKind::ExFat => "exFAT",
+            FsKind::Ntfs => "NTFS",
+            FsKind::Unknown => "unknown",
+        }
+    }
+}
+
+/// What `probe_volume` found on a partition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VolumeInfo {
+    pub kind: FsKind,
+    /// Volume label, trimmed ("" when there is none)
+    pub label: String,
+    /// Data area of a FAT volume in bytes (0 for anything else)
+    pub total_bytes: u64,
+    /// Free bytes, counted from the FAT (FAT32 only)
+    pub free_bytes: Option<u64>,
+    /// Why a FAT32 boot sector won't do, as `check` would report it
+    pub problem: Option<&'static str>,
+}
+
+impl VolumeInfo {
+    /// Whether this is a FAT32 volume the filesystem code can use.
+    pub fn is_usable_fat32(&self) -> bool {
+        self.kind == FsKind::Fat32 && self.problem.is_none()
+    }
+}
+
+/// Identify the volume at `partition_lba_start`. Only reads.
+///
+/// Errors are only returned when the disk itself fails; a partition
+/// holding nothing recognizable comes back as [`FsKind::Unknown`].
+pub fn probe_volume<B: BlockIo>(
+    block_io: &mut B,
+    partition_lba_start: u64,
+) -> Result<VolumeInfo, Fat32Error> {
+    let mut boot_sector = [0u8; SECTOR_SIZE];
+    PartitionBlockIo::new(block_io, partition_lba_start, 1)
+        .read_blocks(Lba(0), &mut boot_sector)
+        .map_err(|_| Fat32Error::IoError)?;
+
+    let kind = classify(&boot_sector);
+    let mut info = VolumeInfo {
+        kind,
+        label: String::new(),
+        total_bytes: 0,
+        free_bytes: None,
+        problem: None,
+    };
+    match kind {
+        FsKind::Fat12 | FsKind::Fat16 => {
+            info.label = bpb_label(&boot_sector, 0x2B);
+            info.total_bytes = fat16_clusters(&boot_sector) as u64 * cluster_bytes(&boot_sector);
+            return Ok(info);
+        }
+        FsKind::Fat32 => {}
+        _ => return Ok(info),
+    }
+
+    info.label = bpb_label(&boot_sector, 0x47);
+    if let Err(reason) = check_boot_sector(&boot_sector) {
+        info.problem = Some(reason);
+        return Ok(info);
+    }
+    let (ctx, mut partition) = Fat32Context::from_partition(block_io, partition_lba_start)?;
+    let cluster_bytes = ctx.sectors_per_cluster as u64 * SECTOR_SIZE as u64;
+    info.total_bytes = (ctx.cluster_limit as u64 - 2) * cluster_bytes;
+    info.free_bytes = Some(free_clusters(&mut partition, &ctx)? as u64 * cluster_bytes);
+    // The root directory entry is the label Windows and Linux show
+    if let Some(label) = root_label(&mut partition, &ctx)? {
+        info.label = label;
+    }
+    Ok(info)
+}
+
+/// Filesystem of a boot sector.
+///
+/// FAT12 and FAT16 are told apart by cluster count, as the FAT spec says,
+/// but FAT32 by its BPB alone: small FAT32 volumes (the formatter's
+/// minimum has about 16k clusters) are common on ESPs and every firmware
+/// reads them.
+fn classify(boot_sector: &[u8; SECTOR_SIZE]) -> FsKind {
+    match &boot_sector[3..11] {
+        b"NTFS    " => return FsKind::Ntfs,
+        b"EXFAT   " => return FsKind::ExFat,
+        _ => {}
+    }
+    let u16_at = |at: usize| u16::from_le_bytes([boot_sector[at], boot_sector[at + 1]]);
+    if boot_sector[510..512] != [0x55, 0xAA] || u16_at(0x0B) == 0 || boot_sector[0x0D] == 0 {
+        return FsKind::Unknown;
+    }
+    if u16_at(0x16) != 0 {
+        return match fat16_clusters(boot_sector) {
+            0 => FsKind::Unknown,
+            1..=4084 => FsKind::Fat12,
+            _ => FsKind::Fat16,
+        };
+    }
+    let fat_size = u32::from_le_bytes([
+        boot_sector[0x24],
+        boot_sector[0x25],
+        boot_sector[0x26],
+        boot_sector[0x27],
+    ]);
+    if fat_size != 0 {
+        FsKind::Fat32
+    } else {
+        FsKind::Unknown
+    }
+}
+
+/// Data clusters of a FAT12/16 volume (0 if the BPB doesn't add up).
+fn fat16_clusters(boot_sector: &[u8; SECTOR_SIZE]) -> u32 {
+    let u16_at = |at: usize| u16::from_le_bytes([boot_sector[at], boot_sector[at + 1]]) as u32;
+    let bytes_per_sector = u16_at(0x0B);
+    let root_dir_sectors = (u16_at(0x11) * 32).div_ceil(bytes_per_sector);
+    let total_sectors = match u16_at(0x13) {
+        0 => u32::from_le_bytes([
+            boot_sector[0x20],
+            boot_sector[0x21],
+            boot_sector[0x22],
+            boot_sector[0x23],
+        ]),
+        sectors => sectors,
+    };
+    let metadata = u16_at(0x0E) + boot_sector[0x10] as u32 * u16_at(0x16) + root_dir_sectors;
+    total_sectors.saturating_sub(metadata) / boot_sector[0x0D] as u32
+}
+
+fn cluster_bytes(boot_sector: &[u8; SECTOR_SIZE]) -> u64 {
+    let bytes_per_sector = u16::from_le_bytes([boot_sector[0x0B], boot_sector[0x0C]]) as u64;
+    boot_sector[0x0D] as u64 * bytes_per_sector
+}
+
+/// Label field of the extended BPB at `at`, if the boot signature says
+/// it is there.
+fn bpb_label(boot_sector: &[u8; SECTOR_SIZE], at: usize) -> String {
+    // The extended boot signature comes 5 bytes before the label in both
+    if boot_sector[at - 5] != 0x29 {
+        return String::new();
+    }
+    label_text(&boot_sector[at..at + 11])
+}
+
+/// Label bytes as text: trailing padding dropped, anything outside
+/// printable ASCII (OEM code page) shown as '?'.
+fn label_text(raw: &[u8]) -> String {
+    let text: String = raw
+        .iter()
+        .map(|&b| {
+            if b.is_ascii_graphic() || b == b' ' {
+                b as char
+            } else {
+                '?'
+            }
+        })
+        .collect();
+    let text = text.trim_end();
+    if text == NO_NAME {
+        String::new()
+    } else {
+        String::from(text)
+    }
+}
+
+/// Clusters the active FAT marks free.
+fn free_clusters<B: BlockIo>(block_io: &mut B, ctx: &Fat32Context) -> Result<u32, Fat32Error> {
+    let entries_per_sector = (SECTOR_SIZE / 4) as u32;
+    let fat_sectors = ctx
+        .cluster_limit
+        .div_ceil(entries_per_sector)
+        .min(ctx.fat_size);
+    let mut buffer = vec![0u8; FAT_BATCH_SECTORS as usize * SECTOR_SIZE];
+    let mut free = 0;
+    let mut sector = 0;
+    while sector < fat_sectors {
+        let count = FAT_BATCH_SECTORS.min(fat_sectors - sector);
+        let batch = &mut buffer[..count as usize * SECTOR_SIZE];
+        let lba = ctx.fat_sector_lba(ctx.active_fat, sector);
+        block_io
+            .read_blocks(Lba(lba), batch)
+            .map_err(|_| Fat32Error::IoError)?;
+        for (i, entry) in batch.chunks_exact(4).enumerate() {
+            let cluster = sector * entries_per_sector + i as u32;
+            let value = u32::from_le_bytes([entry[0], entry[1], entry[2], entry[3]]);
+            if (2..ctx.cluster_limit).contains(&cluster) && value & 0x0FFF_FFFF == 0 {
+                free += 1;
+            }
+        }
+        sector += count;
+    }
+    Ok(free)
+}
+
+/// Label from the volume ID entry in the root directory's first cluster,
+/// where formatters put it.
+fn root_label<B: BlockIo>(
+    block_io: &mut B,
+    ctx: &Fat32Context,
+) -> Result<Option<String>, Fat32Error> {
+    let mut cluster = vec![0u8; ctx.sectors_per_cluster as usize * SECTOR_SIZE];
+    let lba = ctx.cluster_to_sector(ctx.root_cluster) as u64;
+    block_io
+        .read_blocks(Lba(lba), &mut cluster)
+        .map_err(|_| Fat32Error::IoError)?;
+    for entry in cluster.chunks_exact(32) {
+        match entry[0] {
+            0x00 => break,
+            0xE5 => continue,
+            _ => {}
+        }
+        let attr = entry[11];
+        if attr != ATTR_LONG_NAME && attr & ATTR_VOLUME_ID != 0 {
+            return Ok(Some(label_text(&entry[..11])));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::{format_fat32, write_file};
+    use crate::testing::SparseDisk;
+
+    const START: u64 = 2048;
+    const SECTORS: u64 = 133_120;
+
+    fn formatted() -> SparseDisk {
+        let mut disk = SparseDisk::new(START + SECTORS);
+        format_fat32(&mut disk, START, SECTORS).unwrap();
+        disk
+    }
+
+    #[test]
+    fn test_probe_fat32() {
+        let mut disk = formatted();
+        let info = probe_volume(&mut disk, START).unwrap();
+        assert_eq!(info.kind, FsKind::Fat32);
+        assert!(info.is_usable_fat32());
+        assert_eq!(info.label, "MORPHEUS");
+        let free = info.free_bytes.unwrap();
+        // Everything but the root directory's cluster
+        assert_eq!(free, info.total_bytes - 4096);
+
+        write_file(&mut disk, START, "/a.bin", &[0x5A; 10_000]).unwrap();
+        let after = probe_volume(&mut disk, START).unwrap();
+        assert_eq!(after.free_bytes, Some(free - 3 * 4096));
+    }
+
+    #[test]
+    fn test_probe_other_filesystems() {
+        let mut disk = formatted();
+        let mut sector = disk.sector(START);
+
+        // A FAT16 BPB: 16 root entry sectors, FAT16 size set
+        let mut fat16 = sector;
+        fat16[0x11..0x13].copy_from_slice(&512u16.to_le_bytes());
+        fat16[0x16..0x18].copy_from_slice(&64u16.to_le_bytes());
+        fat16[0x26] = 0x29;
+        fat16[0x2B..0x36].copy_from_slice(b"SMALLESP   ");
+        assert_eq!(classify(&fat16), FsKind::Fat16);
+        assert_eq!(bpb_label(&fat16, 0x2B), "SMALLESP");
+
+        sector[3..11].copy_from_slice(b"NTFS    ");
+        assert_eq!(classify(&sector), FsKind::Ntfs);
+        disk.write_blocks(Lba(START), &sector).unwrap();
+        let info = probe_volume(&mut disk, START).unwrap();
+        assert_eq!(info.kind, FsKind::Ntfs);
+        assert!(!info.is_usable_fat32());
+        assert_eq!(info.free_bytes, None);
+
+        assert_eq!(classify(&[0u8; SECTOR_SIZE]), FsKind::Unknown);
+    }
+
+    #[test]
+    fn test_probe_bad_fat32() {
+        let mut disk = formatted();
+        let mut sector = disk.sector(START);
+        // No FATs: FAT32 by its fields, but unusable
+        sector[0x10] = 0;
+        disk.write_blocks(Lba(START), &sector).unwrap();
+        let info = probe_volume(&mut disk, START).unwrap();
+        assert_eq!(info.kind, FsKind::Fat32);
+        assert_eq!(info.problem, Some("no FATs"));
+        assert!(!info.is_usable_fat32());
+    }
+
+    #[test]
+    fn test_label_text() {
+        assert_eq!(label_text(b"EFI        "), "EFI");
+        assert_eq!(label_text(b"NO NAME    "), "");
+        assert_eq!(label_text(b"CAF\x82     "), "CAF?");
+    }
+}
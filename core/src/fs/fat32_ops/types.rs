@@ -1,12 +1,27 @@
 // FAT32 directory entry types
 
+use crate::time::DateTime;
+
 extern crate alloc;
+use alloc::string::String;
 use alloc::vec::Vec;
 
 const SECTOR_SIZE: usize = 512;
+pub const ATTR_VOLUME_ID: u8 = 0x08;
+/// Long filename fragment (read-only | hidden | system | volume ID)
+pub const ATTR_LONG_NAME: u8 = 0x0F;
 pub const ATTR_DIRECTORY: u8 = 0x10;
 pub const ATTR_ARCHIVE: u8 = 0x20;
 
+/// A file or directory as returned by `list_directory`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileInfo {
+    /// 8.3 name, "NAME.EXT"
+    pub name: String,
+    pub size: u32,
+    pub is_dir: bool,
+}
+
 /// FAT32 directory entry (32 bytes)
 #[repr(C, packed)]
 #[derive(Clone, Copy)]
@@ -14,13 +29,13 @@ pub struct DirEntry {
     pub name: [u8; 11], // 8.3 filename
     pub attr: u8,       // File attributes
     pub _reserved: u8,
-    pub _create_time_tenth: u8,
-    pub _create_time: u16,
-    pub _create_date: u16,
-    pub _access_date: u16,
+    pub create_time_tenth: u8,
+    pub create_time: u16,
+    pub create_date: u16,
+    pub access_date: u16,
     pub cluster_high: u16, // High word of first cluster
-    pub _modify_time: u16,
-    pub _modify_date: u16,
+    pub modify_time: u16,
+    pub modify_date: u16,
     pub cluster_low: u16, // Low word of first cluster
     pub file_size: u32,   // File size in bytes
 }
@@ -31,13 +46,13 @@ impl DirEntry {
             name: [0; 11],
             attr: 0,
             _reserved: 0,
-            _create_time_tenth: 0,
-            _create_time: 0,
-            _create_date: 0,
-            _access_date: 0,
+            create_time_tenth: 0,
+            create_time: 0,
+            create_date: 0,
+            access_date: 0,
             cluster_high: 0,
-            _modify_time: 0,
-            _modify_date: 0,
+            modify_time: 0,
+            modify_date: 0,
             cluster_low: 0,
             file_size: 0,
         }
@@ -73,6 +88,19 @@ impl DirEntry {
         }
     }
 
+    /// The 8.3 name as "NAME.EXT" ("NAME" without an extension).
+    pub fn short_name(&self) -> String {
+        let base = core::str::from_utf8(&self.name[..8]).unwrap_or("");
+        let ext = core::str::from_utf8(&self.name[8..]).unwrap_or("");
+        let (base, ext) = (base.trim_end(), ext.trim_end());
+        let mut name = String::from(base);
+        if !ext.is_empty() {
+            name.push('.');
+            name.push_str(ext);
+        }
+        name
+    }
+
     pub fn first_cluster(&self) -> u32 {
         ((self.cluster_high as u32) << 16) | (self.cluster_low as u32)
     }
@@ -81,4 +109,24 @@ impl DirEntry {
         self.cluster_high = (cluster >> 16) as u16;
         self.cluster_low = (cluster & 0xFFFF) as u16;
     }
+
+    /// Set the create, modify and access stamps to `time`; zero (no
+    /// timestamp) for None or dates FAT can't hold (before 1980, after 2107).
+    pub fn set_timestamps(&mut self, time: Option<DateTime>) {
+        let (date, time, tenths) = match time {
+            Some(t) if (1980..=2107).contains(&t.year) => (
+                ((t.year - 1980) << 9) | (t.month as u16) << 5 | t.day as u16,
+                (t.hour as u16) << 11 | (t.minute as u16) << 5 | (t.second / 2) as u16,
+                // Odd seconds; the field counts 10 ms units up to 199
+                (t.second % 2) * 100,
+            ),
+            _ => (0, 0, 0),
+        };
+        self.create_time_tenth = tenths;
+        self.create_time = time;
+        self.create_date = date;
+        self.access_date = date;
+        self.modify_time = time;
+        self.modify_date = date;
+    }
 }
@@ -0,0 +1,141 @@
+// Open FAT32 volume: boot sector parsed once, FAT sectors cached
+
+use super::super::{path, Fat32Error};
+use super::context::Fat32Context;
+use super::{directory, file_ops, FileInfo, ProgressCallback};
+use crate::disk::partition_io::PartitionBlockIo;
+use crate::time::Clock;
+use crate::uefi_alloc::Allocator;
+use gpt_disk_io::BlockIo;
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+/// A FAT32 partition opened for a series of operations.
+///
+/// The free functions in [`fat32_ops`](super) open the volume for each
+/// call; an installer or manifest writer touching several files should
+/// open it once instead. The volume holds the block device for as long as
+/// it lives, so nothing else can change the FAT behind its cache, and
+/// only reaches it through a [`PartitionBlockIo`] ending where the boot
+/// sector says the volume does.
+///
+/// ```
+/// use morpheus_core::fs::fat32_ops::Fat32Volume;
+/// use morpheus_core::fs::format_fat32;
+/// use morpheus_core::testing::SparseDisk;
+///
+/// let mut disk = SparseDisk::new(2048 + 133_120);
+/// format_fat32(&mut disk, 2048, 133_120).unwrap();
+///
+/// let mut volume = Fat32Volume::open(&mut disk, 2048).unwrap();
+/// volume.write_file("/EFI/BOOT/BOOTX64.EFI", b"MZ").unwrap();
+/// volume.write_file("/EFI/BOOT/CONFIG.TXT", b"quiet").unwrap();
+/// assert_eq!(volume.read_file("/EFI/BOOT/CONFIG.TXT").unwrap(), b"quiet");
+/// ```
+pub struct Fat32Volume<'a, B: BlockIo> {
+    partition: PartitionBlockIo<'a, B>,
+    ctx: Fat32Context<'a>,
+}
+
+impl<'a, B: BlockIo> Fat32Volume<'a, B> {
+    /// Read the boot sector of the FAT32 partition at `partition_lba_start`
+    pub fn open(block_io: &'a mut B, partition_lba_start: u64) -> Result<Self, Fat32Error> {
+        let (ctx, partition) = Fat32Context::from_partition(block_io, partition_lba_start)?;
+        Ok(Self { partition, ctx })
+    }
+
+    /// Take temporary write buffers from `allocator` (`UefiPages` pre-EBS)
+    pub fn with_allocator(mut self, allocator: &'a dyn Allocator) -> Self {
+        self.ctx = self.ctx.with_allocator(allocator);
+        self
+    }
+
+    /// Timestamp new entries with `clock` instead of the global
+    /// [`time::now`](crate::time::now)
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.ctx.clock = clock;
+        self
+    }
+
+    /// Write file, creating missing directories on the way
+    pub fn write_file(&mut self, path: &str, data: &[u8]) -> Result<(), Fat32Error> {
+        self.write_file_with_progress(path, data, &mut None)
+    }
+
+    /// Write file with progress reporting
+    pub fn write_file_with_progress(
+        &mut self,
+        path: &str,
+        data: &[u8],
+        progress: &mut ProgressCallback,
+    ) -> Result<(), Fat32Error> {
+        let path = path::normalize(path)?;
+        let (dirs, name) = match path.split_last() {
+            Some(split) if !path.names_directory() => split,
+            _ => return Err(Fat32Error::InvalidPath), // Names a directory
+        };
+
+        // Navigate/create directory structure
+        let mut current_cluster = self.ctx.root_cluster;
+        for dir in dirs {
+            current_cluster = directory::ensure_directory_exists(
+                &mut self.partition,
+                &self.ctx,
+                current_cluster,
+                dir,
+            )?;
+        }
+
+        // Create/write the file itself
+        file_ops::write_file_in_directory_with_progress(
+            &mut self.partition,
+            &self.ctx,
+            current_cluster,
+            name,
+            data,
+            progress,
+        )?;
+
+        self.flush()
+    }
+
+    /// Create directory (creates full path)
+    pub fn create_directory(&mut self, path: &str) -> Result<(), Fat32Error> {
+        directory::create_directory(&mut self.partition, &self.ctx, path)?;
+        self.flush()
+    }
+
+    /// Read file data
+    pub fn read_file(&mut self, path: &str) -> Result<Vec<u8>, Fat32Error> {
+        file_ops::read_file(&mut self.partition, &self.ctx, path)
+    }
+
+    /// Check if file exists
+    pub fn file_exists(&mut self, path: &str) -> Result<bool, Fat32Error> {
+        file_ops::file_exists(&mut self.partition, &self.ctx, path)
+    }
+
+    /// List the files and subdirectories of `path` ("/" for the root)
+    pub fn list_directory(&mut self, path: &str) -> Result<Vec<FileInfo>, Fat32Error> {
+        directory::list_directory(&mut self.partition, &self.ctx, path)
+    }
+
+    /// Delete a file
+    pub fn delete_file(&mut self, path: &str) -> Result<(), Fat32Error> {
+        file_ops::delete_file(&mut self.partition, &self.ctx, path)?;
+        self.flush()
+    }
+
+    /// Write a file, replacing any existing file at `path`
+    pub fn replace_file(&mut self, path: &str, data: &[u8]) -> Result<(), Fat32Error> {
+        if self.file_exists(path)? {
+            self.delete_file(path)?;
+        }
+        self.write_file(path, data)
+    }
+
+    fn flush(&mut self) -> Result<(), Fat32Error> {
+        self.partition.flush().map_err(|_| Fat32Error::IoError)
+    }
+}
@@ -1,10 +1,18 @@
 // Filesystem operations
 
+pub mod esp_sync;
 pub mod fat32_format;
 pub mod fat32_ops;
+pub mod path;
+pub mod self_check;
 
-pub use fat32_format::{format_fat32, verify_fat32, Fat32Error};
-pub use fat32_ops::{create_directory, file_exists, read_file, write_file};
+pub use fat32_format::Fat32Error;
+#[cfg(not(feature = "no-format"))]
+pub use fat32_format::{format_fat32, verify_fat32};
+pub use fat32_ops::{
+    create_directory, delete_file, file_exists, list_directory, probe_volume, read_file,
+    replace_file, write_file, Fat32Volume, FileInfo, FsKind, VolumeInfo,
+};
 
 // Re-export filename utilities for 8.3 compatibility
 pub use fat32_ops::filename::generate_8_3_manifest_name;
@@ -0,0 +1,159 @@
+// Path normalization
+//
+// Filesystem paths come from users, manifests and UEFI device paths, so
+// they arrive as "/EFI/BOOT/", "\EFI\BOOT\BOOTX64.EFI" or "EFI//BOOT/.".
+// `normalize` turns all of those into the same list of components before
+// any filesystem code walks them. It borrows from the input and keeps the
+// components in a fixed array, so it works pre-EBS without a heap.
+
+/// Most components a path may have.
+pub const MAX_PATH_DEPTH: usize = 32;
+
+/// Why a path was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathError {
+    /// More than [`MAX_PATH_DEPTH`] components
+    TooDeep,
+    /// `..` above the root, or a component with control characters
+    Invalid,
+}
+
+/// Components of a normalized path, root first. No component is empty,
+/// `.` or `..`; the root itself has none.
+#[derive(Debug, Clone, Copy)]
+pub struct NormalizedPath<'a> {
+    parts: [&'a str; MAX_PATH_DEPTH],
+    len: usize,
+    /// Ended in a separator, `.` or `..`
+    directory_form: bool,
+}
+
+impl<'a> NormalizedPath<'a> {
+    /// All components.
+    pub fn components(&self) -> &[&'a str] {
+        &self.parts[..self.len]
+    }
+
+    /// Whether this is the root.
+    pub fn is_root(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Whether the path can only name a directory: the root, or a path
+    /// ending in a separator, `.` or `..` ("/EFI/BOOT/").
+    pub fn names_directory(&self) -> bool {
+        self.is_root() || self.directory_form
+    }
+
+    /// Parent components and the last one; None for the root.
+    pub fn split_last(&self) -> Option<(&[&'a str], &'a str)> {
+        self.components()
+            .split_last()
+            .map(|(last, parents)| (parents, *last))
+    }
+}
+
+/// Normalize `path`. Both `/` and `\` separate components; leading,
+/// trailing and repeated separators and `.` are dropped, `..` removes
+/// the component before it. Paths are always taken from the root.
+///
+/// ```
+/// use morpheus_core::fs::path::{normalize, PathError};
+///
+/// let path = normalize("//EFI/./BOOT/../BOOT/BOOTX64.EFI").unwrap();
+/// assert_eq!(path.components(), ["EFI", "BOOT", "BOOTX64.EFI"]);
+/// assert!(normalize("\\").unwrap().is_root());
+/// assert_eq!(normalize("/..").unwrap_err(), PathError::Invalid);
+/// ```
+pub fn normalize(path: &str) -> Result<NormalizedPath<'_>, PathError> {
+    let mut normalized = NormalizedPath {
+        parts: [""; MAX_PATH_DEPTH],
+        len: 0,
+        directory_form: false,
+    };
+    for part in path.split(['/', '\\']) {
+        normalized.directory_form = matches!(part, "" | "." | "..");
+        match part {
+            "" | "." => {}
+            ".." => {
+                if normalized.len == 0 {
+                    return Err(PathError::Invalid); // Above the root
+                }
+                normalized.len -= 1;
+            }
+            _ => {
+                if part.chars().any(|c| c.is_control()) {
+                    return Err(PathError::Invalid);
+                }
+                if normalized.len == MAX_PATH_DEPTH {
+                    return Err(PathError::TooDeep);
+                }
+                normalized.parts[normalized.len] = part;
+                normalized.len += 1;
+            }
+        }
+    }
+    Ok(normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    extern crate alloc;
+    use alloc::format;
+    use alloc::vec::Vec;
+
+    fn parts(path: &str) -> Result<Vec<&str>, PathError> {
+        normalize(path).map(|p| p.components().to_vec())
+    }
+
+    #[test]
+    fn test_normalize() {
+        let boot = ["EFI", "BOOT", "BOOTX64.EFI"];
+        assert_eq!(parts("/EFI/BOOT/BOOTX64.EFI").unwrap(), boot);
+        assert_eq!(parts("EFI/BOOT/BOOTX64.EFI").unwrap(), boot);
+        assert_eq!(parts("\\EFI\\BOOT\\BOOTX64.EFI").unwrap(), boot);
+        assert_eq!(parts("//EFI///BOOT/./BOOTX64.EFI").unwrap(), boot);
+        assert_eq!(parts("/EFI/TMP/../BOOT/BOOTX64.EFI").unwrap(), boot);
+        assert_eq!(parts("/EFI/BOOT/").unwrap(), ["EFI", "BOOT"]);
+
+        // Dot files are names, not special components
+        assert_eq!(parts("/.iso/A.MFS").unwrap(), [".iso", "A.MFS"]);
+
+        for root in ["", "/", "//", "/.", "/EFI/.."] {
+            assert!(normalize(root).unwrap().is_root(), "{:?}", root);
+        }
+    }
+
+    #[test]
+    fn test_normalize_errors() {
+        assert_eq!(parts("/..").unwrap_err(), PathError::Invalid);
+        assert_eq!(parts("/EFI/../../BOOT").unwrap_err(), PathError::Invalid);
+        assert_eq!(parts("/EFI/BO\0OT").unwrap_err(), PathError::Invalid);
+
+        let deepest = "/D".repeat(MAX_PATH_DEPTH);
+        assert_eq!(parts(&deepest).unwrap().len(), MAX_PATH_DEPTH);
+        let too_deep = format!("{}/F", deepest);
+        assert_eq!(parts(&too_deep).unwrap_err(), PathError::TooDeep);
+        // Depth counts after `..`
+        assert!(normalize(&format!("{}/../F", deepest)).is_ok());
+    }
+
+    #[test]
+    fn test_split_last() {
+        let path = normalize("/EFI/BOOT/BOOTX64.EFI").unwrap();
+        let (parents, name) = path.split_last().unwrap();
+        assert_eq!(parents, ["EFI", "BOOT"]);
+        assert_eq!(name, "BOOTX64.EFI");
+        assert!(normalize("/").unwrap().split_last().is_none());
+    }
+
+    #[test]
+    fn test_names_directory() {
+        for dir in ["/", "/EFI/BOOT/", "/EFI/BOOT/.", "/EFI/BOOT/X/.."] {
+            assert!(normalize(dir).unwrap().names_directory(), "{:?}", dir);
+        }
+        assert!(!normalize("/EFI/BOOT").unwrap().names_directory());
+    }
+}
@@ -0,0 +1,220 @@
+// Installed environment self-check
+//
+// The installer records a SHA-256 of every file it puts on the ESP in
+// INSTALL_RECORD, one `sha256sum`-style line each. At boot the files are
+// hashed again and every ISO manifest is parsed, so a half-written update,
+// a tool that replaced the boot binary or a torn manifest shows up on the
+// dashboard instead of as a failed boot or a missing ISO later.
+//
+// The record sits in /EFI/MORPHEUS, so ESP sync carries it to a mirror
+// along with the binary it describes. An ESP without a record (installed
+// before the record existed) only gets the boot binary and manifest checks.
+
+use super::fat32_ops::{replace_file, Fat32Volume};
+use super::Fat32Error;
+use crate::hash::Sha256;
+use crate::iso::{IsoManifest, MANIFEST_DIR};
+use gpt_disk_io::BlockIo;
+
+extern crate alloc;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+/// Hashes of the files the installer wrote
+pub const INSTALL_RECORD: &str = "/EFI/MORPHEUS/INSTALL.SUM";
+
+/// Path firmware boots from an ESP
+pub const BOOT_PATH: &str = "/EFI/BOOT/BOOTX64.EFI";
+
+/// Something wrong with the installed files
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EspIssue {
+    /// Firmware has nothing to boot on this ESP
+    BootFileMissing,
+    /// A recorded file is gone
+    FileMissing(String),
+    /// A recorded file no longer matches its hash
+    FileModified(String),
+    /// A manifest that doesn't parse
+    BadManifest(String),
+}
+
+/// Record lines for `files` (path, contents).
+pub fn format_record(files: &[(&str, &[u8])]) -> String {
+    let mut record = String::new();
+    for (path, data) in files {
+        for byte in Sha256::digest(data) {
+            let _ = write!(record, "{:02x}", byte);
+        }
+        let _ = writeln!(record, "  {}", path);
+    }
+    record
+}
+
+/// Paths and hashes in a record; lines that aren't `<hex>  <path>` are
+/// skipped.
+pub fn parse_record(record: &str) -> Vec<(String, [u8; 32])> {
+    record
+        .lines()
+        .filter_map(|line| {
+            let (hex, path) = line.trim().split_once(char::is_whitespace)?;
+            Some((String::from(path.trim_start()), parse_hex(hex)?))
+        })
+        .collect()
+}
+
+/// Write the record for `files` to the ESP at `partition_lba_start`,
+/// replacing the last install's.
+pub fn write_install_record<B: BlockIo>(
+    block_io: &mut B,
+    partition_lba_start: u64,
+    files: &[(&str, &[u8])],
+) -> Result<(), Fat32Error> {
+    let record = format_record(files);
+    replace_file(
+        block_io,
+        partition_lba_start,
+        INSTALL_RECORD,
+        record.as_bytes(),
+    )
+}
+
+/// Check the ESP at `partition_lba_start` against its install record and
+/// parse every manifest on it. Nothing is written.
+pub fn check_esp<B: BlockIo>(
+    block_io: &mut B,
+    partition_lba_start: u64,
+) -> Result<Vec<EspIssue>, Fat32Error> {
+    let mut volume = Fat32Volume::open(block_io, partition_lba_start)?;
+    let mut issues = Vec::new();
+
+    if !volume.file_exists(BOOT_PATH)? {
+        issues.push(EspIssue::BootFileMissing);
+    }
+
+    if volume.file_exists(INSTALL_RECORD)? {
+        let record = volume.read_file(INSTALL_RECORD)?;
+        for (path, hash) in parse_record(&String::from_utf8_lossy(&record)) {
+            if !volume.file_exists(&path)? {
+                // Already reported, with its own fix
+                if !path.eq_ignore_ascii_case(BOOT_PATH) {
+                    issues.push(EspIssue::FileMissing(path));
+                }
+            } else if Sha256::digest(&volume.read_file(&path)?) != hash {
+                issues.push(EspIssue::FileModified(path));
+            }
+        }
+    }
+
+    // A missing directory lists as an error; no manifests to check
+    if let Ok(files) = volume.list_directory(MANIFEST_DIR) {
+        for file in files.iter().filter(|f| !f.is_dir && is_manifest(&f.name)) {
+            let path = format!("{}/{}", MANIFEST_DIR, file.name);
+            if IsoManifest::deserialize(&volume.read_file(&path)?).is_err() {
+                issues.push(EspIssue::BadManifest(path));
+            }
+        }
+    }
+
+    Ok(issues)
+}
+
+/// Manifest files are `<CRC32>.MFS`; older builds wrote `.MANIFEST`
+fn is_manifest(name: &str) -> bool {
+    let name = name.to_ascii_uppercase();
+    name.ends_with(".MFS") || name.ends_with(".MANIFEST")
+}
+
+/// 64 hex digits to a digest
+fn parse_hex(hex: &str) -> Option<[u8; 32]> {
+    let hex = hex.as_bytes();
+    if hex.len() != 64 {
+        return None;
+    }
+    let mut digest = [0u8; 32];
+    for (byte, pair) in digest.iter_mut().zip(hex.chunks_exact(2)) {
+        let high = (pair[0] as char).to_digit(16)?;
+        let low = (pair[1] as char).to_digit(16)?;
+        *byte = ((high << 4) | low) as u8;
+    }
+    Some(digest)
+}
+
+#[cfg(all(test, not(feature = "no-format")))]
+mod tests {
+    use super::*;
+    use crate::fs::{delete_file, format_fat32, write_file};
+    use crate::testing::SparseDisk;
+    use alloc::vec;
+
+    /// Smallest volume the formatter accepts
+    const ESP_SECTORS: u64 = 133_120;
+
+    #[test]
+    fn test_record_round_trip() {
+        let record = format_record(&[(BOOT_PATH, b"morpheus"), ("/EFI/MORPHEUS/A.CFG", b"")]);
+        let lines: Vec<&str> = record.lines().collect();
+        assert_eq!(
+            lines[1],
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855  \
+             /EFI/MORPHEUS/A.CFG"
+        );
+
+        let entries = parse_record(&format!("{}garbage\n\n", record));
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            entries[0],
+            (String::from(BOOT_PATH), Sha256::digest(b"morpheus"))
+        );
+    }
+
+    #[test]
+    fn test_check_esp() {
+        let mut disk = SparseDisk::new(ESP_SECTORS);
+        format_fat32(&mut disk, 0, ESP_SECTORS).unwrap();
+        assert_eq!(
+            check_esp(&mut disk, 0).unwrap(),
+            [EspIssue::BootFileMissing]
+        );
+
+        // Freshly installed, with one good manifest
+        let mut manifest = vec![0u8; 4096];
+        let len = IsoManifest::new("tails.iso", 1 << 30)
+            .serialize(&mut manifest)
+            .unwrap();
+        write_file(&mut disk, 0, BOOT_PATH, b"morpheus v1").unwrap();
+        write_file(&mut disk, 0, "/EFI/MORPHEUS/A.CFG", b"config").unwrap();
+        write_file(&mut disk, 0, "/.iso/AAAA0001.MFS", &manifest[..len]).unwrap();
+        write_install_record(
+            &mut disk,
+            0,
+            &[
+                (BOOT_PATH, b"morpheus v1"),
+                ("/EFI/MORPHEUS/A.CFG", b"config"),
+            ],
+        )
+        .unwrap();
+        assert!(check_esp(&mut disk, 0).unwrap().is_empty());
+
+        // Binary replaced, config deleted, manifest torn
+        replace_file(&mut disk, 0, BOOT_PATH, b"someone else").unwrap();
+        delete_file(&mut disk, 0, "/EFI/MORPHEUS/A.CFG").unwrap();
+        replace_file(&mut disk, 0, "/.iso/AAAA0001.MFS", &manifest[..len / 2]).unwrap();
+        assert_eq!(
+            check_esp(&mut disk, 0).unwrap(),
+            [
+                EspIssue::FileModified(String::from(BOOT_PATH)),
+                EspIssue::FileMissing(String::from("/EFI/MORPHEUS/A.CFG")),
+                EspIssue::BadManifest(String::from("/.iso/AAAA0001.MFS")),
+            ]
+        );
+
+        // Binary gone: reported once
+        delete_file(&mut disk, 0, BOOT_PATH).unwrap();
+        let issues = check_esp(&mut disk, 0).unwrap();
+        assert_eq!(issues[0], EspIssue::BootFileMissing);
+        assert_eq!(issues.len(), 3);
+    }
+}
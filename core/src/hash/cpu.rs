@@ -0,0 +1,68 @@
+//! CPU features the hash fast paths need, detected once with CPUID.
+
+use core::sync::atomic::{AtomicU8, Ordering};
+
+/// What the CPU offers the hash functions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Features {
+    /// SSE4.2 `crc32` (CRC-32C only)
+    pub crc32c: bool,
+    /// PCLMULQDQ and SSE4.1, for the IEEE CRC-32
+    pub clmul: bool,
+    /// SHA extensions, SSSE3 and SSE4.1, for SHA-256
+    pub sha: bool,
+}
+
+const CRC32C: u8 = 1 << 0;
+const CLMUL: u8 = 1 << 1;
+const SHA: u8 = 1 << 2;
+/// Set once detected, so a CPU with none of them isn't probed each time
+const DETECTED: u8 = 1 << 7;
+
+static CACHE: AtomicU8 = AtomicU8::new(0);
+
+/// The features of this CPU (the boot processor's; APs match it).
+pub fn features() -> Features {
+    let mut bits = CACHE.load(Ordering::Relaxed);
+    if bits == 0 {
+        bits = detect() | DETECTED;
+        CACHE.store(bits, Ordering::Relaxed);
+    }
+    Features {
+        crc32c: bits & CRC32C != 0,
+        clmul: bits & CLMUL != 0,
+        sha: bits & SHA != 0,
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn detect() -> u8 {
+    use core::arch::x86_64::{__cpuid, __cpuid_count};
+
+    let max_leaf = __cpuid(0).eax;
+    // CPUID.01H:ECX
+    let ecx = __cpuid(1).ecx;
+    let pclmulqdq = ecx & (1 << 1) != 0;
+    let ssse3 = ecx & (1 << 9) != 0;
+    let sse41 = ecx & (1 << 19) != 0;
+    let sse42 = ecx & (1 << 20) != 0;
+    // CPUID.(EAX=07H,ECX=0):EBX.SHA[bit 29]
+    let sha = max_leaf >= 7 && __cpuid_count(7, 0).ebx & (1 << 29) != 0;
+
+    let mut bits = 0;
+    if sse42 {
+        bits |= CRC32C;
+    }
+    if pclmulqdq && sse41 {
+        bits |= CLMUL;
+    }
+    if sha && ssse3 && sse41 {
+        bits |= SHA;
+    }
+    bits
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn detect() -> u8 {
+    0
+}
@@ -0,0 +1,233 @@
+//! CRC-32 (IEEE 802.3: GPT, zip, the ISO manifests) and CRC-32C
+//! (Castagnoli).
+//!
+//! The IEEE CRC folds 64 bytes per step with PCLMULQDQ, after Intel's
+//! "Fast CRC Computation for Generic Polynomials Using PCLMULQDQ"; the
+//! SSE4.2 `crc32` instruction only does CRC-32C. Both fall back to a
+//! table, which also takes short inputs and the tail the folding leaves.
+
+#[cfg(target_arch = "x86_64")]
+use super::cpu;
+
+/// Reflected IEEE 802.3 polynomial
+const IEEE: u32 = 0xEDB8_8320;
+/// Reflected Castagnoli polynomial
+const CASTAGNOLI: u32 = 0x82F6_3B78;
+
+static IEEE_TABLE: [u32; 256] = table(IEEE);
+static CASTAGNOLI_TABLE: [u32; 256] = table(CASTAGNOLI);
+
+/// Shortest input worth setting up the folding for
+#[cfg(target_arch = "x86_64")]
+const CLMUL_MIN: usize = 128;
+
+/// CRC-32 of `data`.
+pub fn crc32(data: &[u8]) -> u32 {
+    crc32_update(0, data)
+}
+
+/// Carry `crc`, the CRC-32 of what came before, on over `data`:
+/// `crc32_update(crc32(a), b)` is the CRC-32 of `a` followed by `b`.
+pub fn crc32_update(crc: u32, data: &[u8]) -> u32 {
+    #[cfg(target_arch = "x86_64")]
+    if data.len() >= CLMUL_MIN && cpu::features().clmul {
+        // Safety: CPUID reports PCLMULQDQ and SSE4.1
+        return unsafe { x86::crc32_clmul(crc, data) };
+    }
+    crc32_soft(crc, data)
+}
+
+/// `crc32_update` with the table only.
+pub fn crc32_soft(crc: u32, data: &[u8]) -> u32 {
+    !update_table(&IEEE_TABLE, !crc, data)
+}
+
+/// CRC-32C of `data`.
+pub fn crc32c(data: &[u8]) -> u32 {
+    crc32c_update(0, data)
+}
+
+/// Carry `crc`, the CRC-32C of what came before, on over `data`.
+pub fn crc32c_update(crc: u32, data: &[u8]) -> u32 {
+    #[cfg(target_arch = "x86_64")]
+    if cpu::features().crc32c {
+        // Safety: CPUID reports SSE4.2
+        return unsafe { x86::crc32c_sse42(crc, data) };
+    }
+    crc32c_soft(crc, data)
+}
+
+/// `crc32c_update` with the table only.
+pub fn crc32c_soft(crc: u32, data: &[u8]) -> u32 {
+    !update_table(&CASTAGNOLI_TABLE, !crc, data)
+}
+
+/// Byte-at-a-time update of a raw (not inverted) CRC register.
+fn update_table(table: &[u32; 256], mut crc: u32, data: &[u8]) -> u32 {
+    for &byte in data {
+        crc = (crc >> 8) ^ table[((crc ^ byte as u32) & 0xFF) as usize];
+    }
+    crc
+}
+
+/// Lookup table of a reflected polynomial, built at compile time.
+const fn table(polynomial: u32) -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut j = 0;
+        while j < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ polynomial
+            } else {
+                crc >> 1
+            };
+            j += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use core::arch::x86_64::*;
+
+    use super::{update_table, IEEE_TABLE};
+
+    // x^n mod P(x) for the IEEE polynomial, bit-reflected and shifted
+    // left one: the fold-by-4 distances (4*128 +- 32), the fold-by-1
+    // distances (128 +- 32) and x^64 for the 96 -> 64 bit step.
+    const K1: i64 = 0x1_5444_2bd4;
+    const K2: i64 = 0x1_c6e4_1596;
+    const K3: i64 = 0x1_7519_97d0;
+    const K4: i64 = 0x0_ccaa_009e;
+    const K5: i64 = 0x1_63cd_6124;
+    /// P(x) and μ = x^64 / P(x), for the Barrett reduction
+    const P_X: i64 = 0x1_DB71_0641;
+    const MU: i64 = 0x1_F701_1641;
+
+    /// IEEE CRC-32 with PCLMULQDQ; `data` must be at least 64 bytes.
+    #[target_feature(enable = "pclmulqdq,sse4.1")]
+    pub unsafe fn crc32_clmul(crc: u32, mut data: &[u8]) -> u32 {
+        // Four 128-bit lanes, folded 64 bytes at a time
+        let mut x3 = load(&mut data);
+        let mut x2 = load(&mut data);
+        let mut x1 = load(&mut data);
+        let mut x0 = load(&mut data);
+        x3 = _mm_xor_si128(x3, _mm_cvtsi32_si128(!crc as i32));
+
+        let k1k2 = _mm_set_epi64x(K2, K1);
+        while data.len() >= 64 {
+            x3 = fold(x3, load(&mut data), k1k2);
+            x2 = fold(x2, load(&mut data), k1k2);
+            x1 = fold(x1, load(&mut data), k1k2);
+            x0 = fold(x0, load(&mut data), k1k2);
+        }
+
+        // Down to one lane, then 16 bytes at a time
+        let k3k4 = _mm_set_epi64x(K4, K3);
+        let mut x = fold(x3, x2, k3k4);
+        x = fold(x, x1, k3k4);
+        x = fold(x, x0, k3k4);
+        while data.len() >= 16 {
+            x = fold(x, load(&mut data), k3k4);
+        }
+
+        // 128 -> 96 -> 64 bits
+        let low32 = _mm_set_epi32(0, 0, 0, !0);
+        let x = _mm_xor_si128(_mm_clmulepi64_si128(x, k3k4, 0x10), _mm_srli_si128(x, 8));
+        let x = _mm_xor_si128(
+            _mm_clmulepi64_si128(_mm_and_si128(x, low32), _mm_set_epi64x(0, K5), 0x00),
+            _mm_srli_si128(x, 4),
+        );
+
+        // Barrett reduction to 32 bits; reflected, so the result is the
+        // upper half
+        let pu = _mm_set_epi64x(MU, P_X);
+        let t1 = _mm_clmulepi64_si128(_mm_and_si128(x, low32), pu, 0x10);
+        let t2 = _mm_clmulepi64_si128(_mm_and_si128(t1, low32), pu, 0x00);
+        let crc = _mm_extract_epi32(_mm_xor_si128(x, t2), 1) as u32;
+
+        !update_table(&IEEE_TABLE, crc, data)
+    }
+
+    /// `a` carried forward by the distance in `keys`, plus `b`.
+    #[target_feature(enable = "pclmulqdq,sse4.1")]
+    unsafe fn fold(a: __m128i, b: __m128i, keys: __m128i) -> __m128i {
+        let lo = _mm_clmulepi64_si128(a, keys, 0x00);
+        let hi = _mm_clmulepi64_si128(a, keys, 0x11);
+        _mm_xor_si128(_mm_xor_si128(b, lo), hi)
+    }
+
+    /// Take 16 bytes off the front of `data`.
+    #[target_feature(enable = "sse2")]
+    unsafe fn load(data: &mut &[u8]) -> __m128i {
+        let (head, rest) = data.split_at(16);
+        *data = rest;
+        _mm_loadu_si128(head.as_ptr() as *const __m128i)
+    }
+
+    /// CRC-32C with the SSE4.2 `crc32` instruction.
+    #[target_feature(enable = "sse4.2")]
+    pub unsafe fn crc32c_sse42(crc: u32, data: &[u8]) -> u32 {
+        let mut words = data.chunks_exact(8);
+        let mut crc = !crc as u64;
+        for word in &mut words {
+            crc = _mm_crc32_u64(crc, u64::from_le_bytes(word.try_into().unwrap()));
+        }
+        let mut crc = crc as u32;
+        for &byte in words.remainder() {
+            crc = _mm_crc32_u8(crc, byte);
+        }
+        !crc
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(len: usize) -> Vec<u8> {
+        let mut x = 0x1234_5678u32;
+        (0..len)
+            .map(|_| {
+                x = x.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                (x >> 16) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_known_values() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32c(b""), 0);
+        assert_eq!(crc32c(b"123456789"), 0xE306_9283);
+        // RFC 3720 B.4: 32 bytes of zeros
+        assert_eq!(crc32c(&[0u8; 32]), 0x8A91_36AA);
+    }
+
+    #[test]
+    fn test_fast_paths_match_table() {
+        // Around every boundary of the folding and the 8-byte words
+        let data = pattern(4096 + 77);
+        for len in (0..300).chain([511, 512, 513, 1000, 4096, 4096 + 77]) {
+            let part = &data[..len];
+            assert_eq!(crc32(part), crc32_soft(0, part), "crc32, {len} bytes");
+            assert_eq!(crc32c(part), crc32c_soft(0, part), "crc32c, {len} bytes");
+        }
+    }
+
+    #[test]
+    fn test_update_continues() {
+        let data = pattern(10_000);
+        for split in [0, 1, 100, 127, 128, 5000, 10_000] {
+            let (a, b) = data.split_at(split);
+            assert_eq!(crc32_update(crc32(a), b), crc32(&data));
+            assert_eq!(crc32c_update(crc32c(a), b), crc32c(&data));
+        }
+    }
+}
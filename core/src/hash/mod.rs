@@ -0,0 +1,26 @@
+//! Checksums and hashes over large buffers.
+//!
+//! CRC-32 and SHA-256 run over whole images (several GB) when a download
+//! is written or verified, so each has an x86_64 fast path, picked at run
+//! time from CPUID:
+//!
+//! - CRC-32 (IEEE): PCLMULQDQ folding
+//! - CRC-32C: the SSE4.2 `crc32` instruction, which only computes this one
+//! - SHA-256: the SHA extensions (SHA-NI)
+//!
+//! Without them, or off x86_64, the portable code runs. The `*_soft`
+//! functions are that code on its own, for tests and `benches/hash.rs`.
+//!
+//! There is no AVX2 path. A single SHA-256 stream gains little from it
+//! next to SHA-NI, and post-EBS nobody has necessarily enabled the AVX
+//! register state (XCR0).
+//!
+//! `morpheus-gpt` sits below this crate and keeps its own bitwise CRC;
+//! it only ever covers a header or a 16 KB partition array.
+
+pub mod cpu;
+pub mod crc32;
+pub mod sha256;
+
+pub use crc32::{crc32, crc32_update, crc32c, crc32c_update};
+pub use sha256::Sha256;
@@ -0,0 +1,341 @@
+//! SHA-256 (FIPS 180-4).
+//!
+//! Streaming, allocation-free. Used to verify downloaded images, so the
+//! compression runs on the SHA extensions where the CPU has them.
+
+#[cfg(target_arch = "x86_64")]
+use super::cpu;
+
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+const H0: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+/// Incremental SHA-256 state.
+#[derive(Clone)]
+pub struct Sha256 {
+    state: [u32; 8],
+    block: [u8; 64],
+    block_len: usize,
+    total_len: u64,
+    /// Never use the SHA extensions
+    soft: bool,
+}
+
+impl Sha256 {
+    pub const fn new() -> Self {
+        Self {
+            state: H0,
+            block: [0u8; 64],
+            block_len: 0,
+            total_len: 0,
+            soft: false,
+        }
+    }
+
+    /// A hasher that sticks to the portable code, to compare against.
+    pub const fn new_soft() -> Self {
+        Self {
+            soft: true,
+            ..Self::new()
+        }
+    }
+
+    /// Bytes hashed so far.
+    pub fn len(&self) -> u64 {
+        self.total_len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total_len == 0
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.total_len += data.len() as u64;
+
+        if self.block_len > 0 {
+            let take = (64 - self.block_len).min(data.len());
+            self.block[self.block_len..self.block_len + take].copy_from_slice(&data[..take]);
+            self.block_len += take;
+            data = &data[take..];
+            if self.block_len < 64 {
+                return;
+            }
+            let block = self.block;
+            self.compress(&block);
+            self.block_len = 0;
+        }
+
+        let whole = data.len() / 64 * 64;
+        self.compress(&data[..whole]);
+        let rest = &data[whole..];
+        self.block[..rest.len()].copy_from_slice(rest);
+        self.block_len = rest.len();
+    }
+
+    pub fn finalize(mut self) -> [u8; 32] {
+        let bit_len = self.total_len.wrapping_mul(8);
+
+        let mut pad = [0u8; 72];
+        pad[0] = 0x80;
+        let pad_len = if self.block_len < 56 {
+            56 - self.block_len
+        } else {
+            120 - self.block_len
+        };
+        pad[pad_len..pad_len + 8].copy_from_slice(&bit_len.to_be_bytes());
+        let total = self.total_len;
+        self.update(&pad[..pad_len + 8]);
+        self.total_len = total;
+        debug_assert_eq!(self.block_len, 0);
+
+        let mut out = [0u8; 32];
+        for (chunk, word) in out.chunks_exact_mut(4).zip(self.state.iter()) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        out
+    }
+
+    /// The chaining state after the bytes hashed so far, `None` unless
+    /// they are a whole number of blocks. With the length, enough to
+    /// carry on later ([`from_midstate`](Self::from_midstate)).
+    pub fn midstate(&self) -> Option<[u8; 32]> {
+        if self.block_len != 0 {
+            return None;
+        }
+        let mut out = [0u8; 32];
+        for (chunk, word) in out.chunks_exact_mut(4).zip(self.state.iter()) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        Some(out)
+    }
+
+    /// Carry on a hash from its [`midstate`](Self::midstate) after `len`
+    /// bytes, a multiple of 64.
+    pub fn from_midstate(midstate: &[u8; 32], len: u64) -> Self {
+        debug_assert_eq!(len % 64, 0);
+        let mut state = [0u32; 8];
+        for (word, chunk) in state.iter_mut().zip(midstate.chunks_exact(4)) {
+            *word = u32::from_be_bytes(chunk.try_into().unwrap());
+        }
+        Self {
+            state,
+            total_len: len,
+            ..Self::new()
+        }
+    }
+
+    /// One-shot digest.
+    pub fn digest(data: &[u8]) -> [u8; 32] {
+        let mut h = Self::new();
+        h.update(data);
+        h.finalize()
+    }
+}
+
+impl Sha256 {
+    /// Run the compression over `blocks`, a multiple of 64 bytes.
+    fn compress(&mut self, blocks: &[u8]) {
+        #[cfg(target_arch = "x86_64")]
+        if !self.soft && cpu::features().sha {
+            // Safety: CPUID reports the SHA extensions, SSSE3 and SSE4.1
+            unsafe { x86::compress_sha_ni(&mut self.state, blocks) };
+            return;
+        }
+        for block in blocks.chunks_exact(64) {
+            compress(&mut self.state, block.try_into().unwrap());
+        }
+    }
+}
+
+impl Default for Sha256 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn compress(state: &mut [u32; 8], block: &[u8; 64]) {
+    let mut w = [0u32; 64];
+    for (i, word) in block.chunks_exact(4).enumerate() {
+        w[i] = u32::from_be_bytes(word.try_into().unwrap());
+    }
+    for i in 16..64 {
+        let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+        let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+        w[i] = w[i - 16]
+            .wrapping_add(s0)
+            .wrapping_add(w[i - 7])
+            .wrapping_add(s1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for i in 0..64 {
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let ch = (e & f) ^ (!e & g);
+        let t1 = h
+            .wrapping_add(s1)
+            .wrapping_add(ch)
+            .wrapping_add(K[i])
+            .wrapping_add(w[i]);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let t2 = s0.wrapping_add(maj);
+
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(t1);
+        d = c;
+        c = b;
+        b = a;
+        a = t1.wrapping_add(t2);
+    }
+
+    for (s, v) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *s = s.wrapping_add(v);
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use core::arch::x86_64::*;
+
+    use super::K;
+
+    /// `compress` over `blocks` (a multiple of 64 bytes) with the SHA
+    /// extensions, after Intel's reference code.
+    #[target_feature(enable = "sha,sse2,ssse3,sse4.1")]
+    pub unsafe fn compress_sha_ni(state: &mut [u32; 8], blocks: &[u8]) {
+        // Byte swap each 32-bit word: the message is big-endian
+        let mask = _mm_set_epi64x(0x0C0D_0E0F_0809_0A0B, 0x0405_0607_0001_0203);
+        let state_ptr = state.as_mut_ptr() as *mut __m128i;
+
+        // The round instructions want the state as ABEF and CDGH
+        let dcba = _mm_loadu_si128(state_ptr);
+        let efgh = _mm_loadu_si128(state_ptr.add(1));
+        let cdab = _mm_shuffle_epi32(dcba, 0xB1);
+        let hgfe = _mm_shuffle_epi32(efgh, 0x1B);
+        let mut abef = _mm_alignr_epi8(cdab, hgfe, 8);
+        let mut cdgh = _mm_blend_epi16(hgfe, cdab, 0xF0);
+
+        for block in blocks.chunks_exact(64) {
+            let (abef_in, cdgh_in) = (abef, cdgh);
+            let ptr = block.as_ptr() as *const __m128i;
+            // Message words i*4..i*4+4 of the current 16
+            let mut w = [
+                _mm_shuffle_epi8(_mm_loadu_si128(ptr), mask),
+                _mm_shuffle_epi8(_mm_loadu_si128(ptr.add(1)), mask),
+                _mm_shuffle_epi8(_mm_loadu_si128(ptr.add(2)), mask),
+                _mm_shuffle_epi8(_mm_loadu_si128(ptr.add(3)), mask),
+            ];
+            for i in 0..16 {
+                if i >= 4 {
+                    w[i % 4] = schedule(w[i % 4], w[(i + 1) % 4], w[(i + 2) % 4], w[(i + 3) % 4]);
+                }
+                // Four rounds, two per instruction
+                let k = _mm_loadu_si128(K.as_ptr().add(i * 4) as *const __m128i);
+                let wk = _mm_add_epi32(w[i % 4], k);
+                cdgh = _mm_sha256rnds2_epu32(cdgh, abef, wk);
+                abef = _mm_sha256rnds2_epu32(abef, cdgh, _mm_shuffle_epi32(wk, 0x0E));
+            }
+            abef = _mm_add_epi32(abef, abef_in);
+            cdgh = _mm_add_epi32(cdgh, cdgh_in);
+        }
+
+        let feba = _mm_shuffle_epi32(abef, 0x1B);
+        let dchg = _mm_shuffle_epi32(cdgh, 0xB1);
+        _mm_storeu_si128(state_ptr, _mm_blend_epi16(feba, dchg, 0xF0));
+        _mm_storeu_si128(state_ptr.add(1), _mm_alignr_epi8(dchg, feba, 8));
+    }
+
+    /// The next four message words from the previous sixteen.
+    #[target_feature(enable = "sha,sse2,ssse3")]
+    unsafe fn schedule(w0: __m128i, w1: __m128i, w2: __m128i, w3: __m128i) -> __m128i {
+        let t = _mm_add_epi32(_mm_sha256msg1_epu32(w0, w1), _mm_alignr_epi8(w3, w2, 4));
+        _mm_sha256msg2_epu32(t, w3)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(digest: [u8; 32]) -> [u8; 64] {
+        let mut out = [0u8; 64];
+        for (i, b) in digest.iter().enumerate() {
+            out[i * 2] = b"0123456789abcdef"[(b >> 4) as usize];
+            out[i * 2 + 1] = b"0123456789abcdef"[(b & 0xF) as usize];
+        }
+        out
+    }
+
+    #[test]
+    fn test_sha256_known_vectors() {
+        assert_eq!(
+            &hex(Sha256::digest(b"")),
+            b"e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            &hex(Sha256::digest(b"abc")),
+            b"ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            &hex(Sha256::digest(
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+            )),
+            b"248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+    }
+
+    #[test]
+    fn test_sha256_streaming_matches_oneshot() {
+        let data: [u8; 1000] = core::array::from_fn(|i| (i * 7) as u8);
+        let mut h = Sha256::new();
+        for chunk in data.chunks(37) {
+            h.update(chunk);
+        }
+        assert_eq!(h.len(), 1000);
+        assert_eq!(h.finalize(), Sha256::digest(&data));
+    }
+
+    #[test]
+    fn test_sha256_fast_path_matches_soft() {
+        let data: Vec<u8> = (0..70_000u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+            .collect();
+        for len in [0, 55, 56, 64, 65, 128, 1000, 4096, 70_000] {
+            let mut fast = Sha256::new();
+            let mut soft = Sha256::new_soft();
+            for chunk in data[..len].chunks(333) {
+                fast.update(chunk);
+                soft.update(chunk);
+            }
+            assert_eq!(fast.finalize(), soft.finalize(), "{len} bytes");
+        }
+    }
+
+    #[test]
+    fn test_sha256_midstate_resumes() {
+        let data: [u8; 1000] = core::array::from_fn(|i| (i * 13) as u8);
+        let mut h = Sha256::new();
+        h.update(&data[..100]);
+        assert!(h.midstate().is_none());
+        h.update(&data[100..512]);
+        let midstate = h.midstate().unwrap();
+
+        let mut resumed = Sha256::from_midstate(&midstate, 512);
+        resumed.update(&data[512..]);
+        assert_eq!(resumed.len(), 1000);
+        assert_eq!(resumed.finalize(), Sha256::digest(&data));
+    }
+}
@@ -267,6 +267,7 @@ mod tests {
         let mut ctx = IsoReadContext {
             chunk_lbas: [(0, 0); MAX_CHUNKS],
             chunk_sizes: [0; MAX_CHUNKS],
+            chunk_disks: [0; MAX_CHUNKS],
             num_chunks: 2,
             total_size: 2_000_000_000, // 2GB
         };
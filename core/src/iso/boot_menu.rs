@@ -0,0 +1,203 @@
+//! Boot menu timeout and default entry
+//!
+//! Like any boot manager, the bootloader can boot a default entry on its
+//! own when nobody touches the keyboard: the main menu counts down the
+//! timeout and then boots a stored ISO, an installed OS, or hands back to
+//! the firmware. A key cancels the countdown. A timeout of 0, or no
+//! default, waits for input as before.
+//!
+//! # Config File (`/.iso/BOOTMENU.CFG`, 80 bytes, little endian)
+//!
+//! ```text
+//! 0x00  8   Magic "MXBMENU\x01"
+//! 0x08  2   Timeout in seconds (0 = wait for a key)
+//! 0x0A  1   Default kind (0 none, 1 firmware, 2 stored ISO, 3 installed OS)
+//! 0x0B  1   Reserved (zero)
+//! 0x0C  64  Default entry name (null-terminated; ISO name or entry name)
+//! 0x4C  4   CRC32 of bytes 0x00-0x4B
+//! ```
+
+use super::error::IsoError;
+use super::manifest::{crc32, MAX_ISO_NAME_LEN};
+
+/// Config file path on the ESP
+pub const BOOT_MENU_PATH: &str = "/.iso/BOOTMENU.CFG";
+
+/// Serialized config size
+pub const BOOT_MENU_SIZE: usize = 80;
+
+const BOOT_MENU_MAGIC: [u8; 8] = *b"MXBMENU\x01";
+
+/// What the countdown boots
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DefaultKind {
+    /// Nothing; the menu waits for input
+    #[default]
+    None,
+    /// Leave the bootloader for the firmware's boot menu
+    Firmware,
+    /// A stored ISO, by ISO name
+    Iso,
+    /// An installed OS entry of the launcher, by entry name
+    Installed,
+}
+
+impl DefaultKind {
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::None),
+            1 => Some(Self::Firmware),
+            2 => Some(Self::Iso),
+            3 => Some(Self::Installed),
+            _ => None,
+        }
+    }
+
+    fn as_u8(self) -> u8 {
+        match self {
+            Self::None => 0,
+            Self::Firmware => 1,
+            Self::Iso => 2,
+            Self::Installed => 3,
+        }
+    }
+}
+
+/// Boot menu timeout and the entry it boots
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BootMenuConfig {
+    /// Seconds to wait before booting the default (0 = wait for a key)
+    pub timeout_secs: u16,
+    pub kind: DefaultKind,
+    name: [u8; MAX_ISO_NAME_LEN],
+    name_len: usize,
+}
+
+impl Default for BootMenuConfig {
+    fn default() -> Self {
+        Self::new(0, DefaultKind::None, "")
+    }
+}
+
+impl BootMenuConfig {
+    /// Config booting `kind` / `name` after `timeout_secs`, the name
+    /// truncated to fit the record
+    pub fn new(timeout_secs: u16, kind: DefaultKind, name: &str) -> Self {
+        let mut config = Self {
+            timeout_secs,
+            kind,
+            name: [0u8; MAX_ISO_NAME_LEN],
+            name_len: 0,
+        };
+        config.set_default(kind, name);
+        config
+    }
+
+    /// Make `kind` / `name` the default entry
+    pub fn set_default(&mut self, kind: DefaultKind, name: &str) {
+        let mut len = name.len().min(MAX_ISO_NAME_LEN - 1);
+        while !name.is_char_boundary(len) {
+            len -= 1;
+        }
+        self.kind = kind;
+        self.name = [0u8; MAX_ISO_NAME_LEN];
+        self.name[..len].copy_from_slice(&name.as_bytes()[..len]);
+        self.name_len = len;
+    }
+
+    /// ISO or entry name of the default ("" for none or firmware)
+    pub fn name_str(&self) -> &str {
+        core::str::from_utf8(&self.name[..self.name_len]).unwrap_or("")
+    }
+
+    /// Whether the menu should count down at all
+    pub fn auto_boot(&self) -> bool {
+        self.timeout_secs > 0 && self.kind != DefaultKind::None
+    }
+
+    /// Serialize to a buffer of at least [`BOOT_MENU_SIZE`] bytes
+    pub fn serialize(&self, buffer: &mut [u8]) -> Result<usize, IsoError> {
+        if buffer.len() < BOOT_MENU_SIZE {
+            return Err(IsoError::IoError);
+        }
+        let buffer = &mut buffer[..BOOT_MENU_SIZE];
+        buffer.fill(0);
+
+        buffer[0..8].copy_from_slice(&BOOT_MENU_MAGIC);
+        buffer[0x08..0x0A].copy_from_slice(&self.timeout_secs.to_le_bytes());
+        buffer[0x0A] = self.kind.as_u8();
+        buffer[0x0C..0x0C + self.name_len].copy_from_slice(&self.name[..self.name_len]);
+
+        let crc = crc32(&buffer[..0x4C]);
+        buffer[0x4C..0x50].copy_from_slice(&crc.to_le_bytes());
+
+        Ok(BOOT_MENU_SIZE)
+    }
+
+    /// Deserialize a config written by [`serialize`](Self::serialize)
+    pub fn deserialize(buffer: &[u8]) -> Result<Self, IsoError> {
+        if buffer.len() < BOOT_MENU_SIZE || buffer[0..8] != BOOT_MENU_MAGIC {
+            return Err(IsoError::InvalidManifest);
+        }
+
+        let stored_crc =
+            u32::from_le_bytes([buffer[0x4C], buffer[0x4D], buffer[0x4E], buffer[0x4F]]);
+        if stored_crc != crc32(&buffer[..0x4C]) {
+            return Err(IsoError::DataCorruption);
+        }
+
+        let kind = DefaultKind::from_u8(buffer[0x0A]).ok_or(IsoError::InvalidManifest)?;
+        let name = &buffer[0x0C..0x0C + MAX_ISO_NAME_LEN - 1];
+        let name_len = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+        let name = core::str::from_utf8(&name[..name_len]).map_err(|_| IsoError::DataCorruption)?;
+        if name.is_empty() && matches!(kind, DefaultKind::Iso | DefaultKind::Installed) {
+            return Err(IsoError::InvalidManifest);
+        }
+
+        let timeout_secs = u16::from_le_bytes([buffer[0x08], buffer[0x09]]);
+        Ok(Self::new(timeout_secs, kind, name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_roundtrip() {
+        let config = BootMenuConfig::new(5, DefaultKind::Iso, "tails-amd64-6.10.iso");
+        let mut buffer = [0u8; BOOT_MENU_SIZE];
+        assert_eq!(config.serialize(&mut buffer), Ok(BOOT_MENU_SIZE));
+
+        let parsed = BootMenuConfig::deserialize(&buffer).unwrap();
+        assert_eq!(parsed, config);
+        assert_eq!(parsed.name_str(), "tails-amd64-6.10.iso");
+        assert!(parsed.auto_boot());
+
+        buffer[0x10] ^= 0xFF;
+        assert_eq!(
+            BootMenuConfig::deserialize(&buffer),
+            Err(IsoError::DataCorruption)
+        );
+        assert_eq!(
+            BootMenuConfig::deserialize(&[0u8; BOOT_MENU_SIZE]),
+            Err(IsoError::InvalidManifest)
+        );
+    }
+
+    #[test]
+    fn test_auto_boot() {
+        assert!(!BootMenuConfig::default().auto_boot());
+        assert!(!BootMenuConfig::new(0, DefaultKind::Firmware, "").auto_boot());
+        assert!(!BootMenuConfig::new(10, DefaultKind::None, "").auto_boot());
+
+        let mut config = BootMenuConfig::new(3, DefaultKind::Firmware, "");
+        assert!(config.auto_boot());
+        let mut buffer = [0u8; BOOT_MENU_SIZE];
+        config.serialize(&mut buffer).unwrap();
+        assert_eq!(BootMenuConfig::deserialize(&buffer), Ok(config));
+
+        config.set_default(DefaultKind::Installed, &"x".repeat(100));
+        assert_eq!(config.name_str().len(), MAX_ISO_NAME_LEN - 1);
+    }
+}
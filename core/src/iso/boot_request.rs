@@ -0,0 +1,138 @@
+//! Boot request left by the download path
+//!
+//! A download can end by asking the bootloader to do something with the
+//! ISO it just stored: make it the entry selected by default, or boot it
+//! once at the next start. The download side writes the request after the
+//! manifest; the bootloader reads it when it builds its entry list and
+//! deletes one-shot requests before acting on them, so an ISO that fails
+//! to boot doesn't trap the machine in a loop.
+//!
+//! # Request File (`/.iso/BOOTREQ.CFG`, 80 bytes, little endian)
+//!
+//! ```text
+//! 0x00  8   Magic "MXBOOTR\x01"
+//! 0x08  64  ISO name (null-terminated)
+//! 0x48  1   Flags (bit 0: boot once)
+//! 0x49  3   Reserved (zero)
+//! 0x4C  4   CRC32 of bytes 0x00-0x4B
+//! ```
+
+use super::error::IsoError;
+use super::manifest::{crc32, MAX_ISO_NAME_LEN};
+
+/// Request file path on the ESP
+pub const BOOT_REQUEST_PATH: &str = "/.iso/BOOTREQ.CFG";
+
+/// Serialized request size
+pub const BOOT_REQUEST_SIZE: usize = 80;
+
+const BOOT_REQUEST_MAGIC: [u8; 8] = *b"MXBOOTR\x01";
+
+const FLAG_ONCE: u8 = 0x01;
+
+/// ISO the bootloader should select or boot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BootRequest {
+    name: [u8; MAX_ISO_NAME_LEN],
+    name_len: usize,
+    /// Boot it at the next start, then forget the request; otherwise
+    /// only select it by default
+    pub once: bool,
+}
+
+impl BootRequest {
+    /// Request for `name`, truncated to fit the record
+    pub fn new(name: &str, once: bool) -> Self {
+        let mut len = name.len().min(MAX_ISO_NAME_LEN - 1);
+        while !name.is_char_boundary(len) {
+            len -= 1;
+        }
+        let mut request = Self {
+            name: [0u8; MAX_ISO_NAME_LEN],
+            name_len: len,
+            once,
+        };
+        request.name[..len].copy_from_slice(&name.as_bytes()[..len]);
+        request
+    }
+
+    pub fn name_str(&self) -> &str {
+        core::str::from_utf8(&self.name[..self.name_len]).unwrap_or("")
+    }
+
+    /// Serialize to a buffer of at least [`BOOT_REQUEST_SIZE`] bytes
+    pub fn serialize(&self, buffer: &mut [u8]) -> Result<usize, IsoError> {
+        if buffer.len() < BOOT_REQUEST_SIZE {
+            return Err(IsoError::IoError);
+        }
+        let buffer = &mut buffer[..BOOT_REQUEST_SIZE];
+        buffer.fill(0);
+
+        buffer[0..8].copy_from_slice(&BOOT_REQUEST_MAGIC);
+        buffer[0x08..0x08 + self.name_len].copy_from_slice(&self.name[..self.name_len]);
+        if self.once {
+            buffer[0x48] |= FLAG_ONCE;
+        }
+
+        let crc = crc32(&buffer[..0x4C]);
+        buffer[0x4C..0x50].copy_from_slice(&crc.to_le_bytes());
+
+        Ok(BOOT_REQUEST_SIZE)
+    }
+
+    /// Deserialize a request written by [`serialize`](Self::serialize)
+    pub fn deserialize(buffer: &[u8]) -> Result<Self, IsoError> {
+        if buffer.len() < BOOT_REQUEST_SIZE || buffer[0..8] != BOOT_REQUEST_MAGIC {
+            return Err(IsoError::InvalidManifest);
+        }
+
+        let stored_crc =
+            u32::from_le_bytes([buffer[0x4C], buffer[0x4D], buffer[0x4E], buffer[0x4F]]);
+        if stored_crc != crc32(&buffer[..0x4C]) {
+            return Err(IsoError::DataCorruption);
+        }
+
+        let name = &buffer[0x08..0x08 + MAX_ISO_NAME_LEN - 1];
+        let name_len = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+        let name = core::str::from_utf8(&name[..name_len]).map_err(|_| IsoError::DataCorruption)?;
+        if name.is_empty() {
+            return Err(IsoError::InvalidManifest);
+        }
+
+        Ok(Self::new(name, buffer[0x48] & FLAG_ONCE != 0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_roundtrip() {
+        let request = BootRequest::new("debian-12.5.0-amd64-netinst.iso", true);
+        let mut buffer = [0u8; BOOT_REQUEST_SIZE];
+        assert_eq!(request.serialize(&mut buffer), Ok(BOOT_REQUEST_SIZE));
+
+        let parsed = BootRequest::deserialize(&buffer).unwrap();
+        assert_eq!(parsed, request);
+        assert_eq!(parsed.name_str(), "debian-12.5.0-amd64-netinst.iso");
+        assert!(parsed.once);
+
+        buffer[0x10] ^= 0xFF;
+        assert_eq!(
+            BootRequest::deserialize(&buffer),
+            Err(IsoError::DataCorruption)
+        );
+        assert_eq!(
+            BootRequest::deserialize(&[0u8; BOOT_REQUEST_SIZE]),
+            Err(IsoError::InvalidManifest)
+        );
+    }
+
+    #[test]
+    fn test_long_name_truncated() {
+        let long = "x".repeat(100);
+        let request = BootRequest::new(&long, false);
+        assert_eq!(request.name_str().len(), MAX_ISO_NAME_LEN - 1);
+    }
+}
@@ -24,6 +24,9 @@ pub struct ChunkInfo {
     pub index: u8,
     /// Whether this chunk has been written
     pub written: bool,
+    /// Disk holding the partition (`DeviceIdentity::disk_id`); 0 for the
+    /// disk the manifest's ESP is on
+    pub disk_id: u32,
 }
 
 impl ChunkInfo {
@@ -36,6 +39,7 @@ impl ChunkInfo {
             data_size: 0,
             index: 0,
             written: false,
+            disk_id: 0,
         }
     }
 
@@ -58,6 +62,7 @@ impl ChunkInfo {
             data_size: 0,
             index,
             written: false,
+            disk_id: 0,
         }
     }
 }
@@ -160,6 +165,11 @@ impl ChunkSet {
         ((self.bytes_written * 100) / self.total_size) as u8
     }
 
+    /// Whether the chunks are spread over more than one disk
+    pub fn spans_disks(&self) -> bool {
+        self.iter().any(|c| c.disk_id != self.chunks[0].disk_id)
+    }
+
     /// Iterator over valid chunks
     pub fn iter(&self) -> ChunkIterator<'_> {
         ChunkIterator {
@@ -0,0 +1,371 @@
+//! Exit report of the last download session
+//!
+//! The download path ends a session by rebooting or powering off, so its
+//! outcome would only survive on the serial log. Before it goes down it
+//! leaves this record on the ESP: how the session ended and what it got
+//! through, for the bootloader's dashboard to show at the next start.
+//! Each session replaces the last one's report.
+//!
+//! The bootloader shows a fresh report once, as a banner, then moves it to
+//! `/.iso/LASTRUN.OLD`; the dashboard reads whichever is there.
+//!
+//! # Report File (`/.iso/LASTRUN.RPT`, 144 bytes, little endian)
+//!
+//! ```text
+//! 0x00  8   Magic "MXEXIT\x02\0"
+//! 0x08  1   Failure reason (0 = completed)
+//! 0x09  1   Final state name length
+//! 0x0A  1   ISO name length
+//! 0x0B  1   SHA-256 check (0 = unchecked, 1 = verified, 2 = mismatch)
+//! 0x0C  16  Final state name
+//! 0x1C  4   Reserved (zero)
+//! 0x20  8   Bytes downloaded
+//! 0x28  8   Bytes written
+//! 0x30  8   Session duration in milliseconds
+//! 0x38  8   Transfer duration in milliseconds
+//! 0x40  8   Retries: DHCP, DNS, TCP connect, HTTP (u16 each)
+//! 0x48  4   Reserved (zero)
+//! 0x4C  64  ISO name
+//! 0x8C  4   CRC32 of bytes 0x00-0x8B
+//! ```
+
+use super::error::IsoError;
+use super::history::Verification;
+use super::manifest::crc32;
+
+/// Report file path on the ESP
+pub const EXIT_REPORT_PATH: &str = "/.iso/LASTRUN.RPT";
+
+/// Where a report goes once it has been shown
+pub const EXIT_REPORT_ARCHIVE_PATH: &str = "/.iso/LASTRUN.OLD";
+
+/// Serialized report size
+pub const EXIT_REPORT_SIZE: usize = 144;
+
+/// Longest final state name kept
+pub const MAX_STATE_NAME_LEN: usize = 16;
+
+/// Longest ISO name kept
+pub const MAX_REPORT_NAME_LEN: usize = 64;
+
+const EXIT_REPORT_MAGIC: [u8; 8] = *b"MXEXIT\x02\x00";
+const CRC_OFFSET: usize = 0x8C;
+
+/// Why a session failed, by the step that gave up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureReason {
+    /// No network link came up
+    NoLink,
+    /// No address from DHCP
+    Dhcp,
+    /// The host name did not resolve
+    Dns,
+    /// The TCP (or TLS) connection could not be made
+    Connect,
+    /// The server's response was refused or cut short
+    Http,
+    /// A captive portal answered instead of the server
+    CaptivePortal,
+    /// Partitioning, writing or flushing the disk failed
+    Disk,
+    /// The image's hash did not match
+    Verification,
+    /// The user aborted the download
+    Aborted,
+    /// Anything else
+    Other,
+}
+
+impl FailureReason {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::NoLink => "no link",
+            Self::Dhcp => "DHCP failed",
+            Self::Dns => "DNS failed",
+            Self::Connect => "connect failed",
+            Self::Http => "HTTP error",
+            Self::CaptivePortal => "captive portal",
+            Self::Disk => "disk error",
+            Self::Verification => "hash mismatch",
+            Self::Aborted => "aborted",
+            Self::Other => "failed",
+        }
+    }
+
+    const fn to_byte(self) -> u8 {
+        match self {
+            Self::NoLink => 1,
+            Self::Dhcp => 2,
+            Self::Dns => 3,
+            Self::Connect => 4,
+            Self::Http => 5,
+            Self::CaptivePortal => 6,
+            Self::Disk => 7,
+            Self::Verification => 8,
+            Self::Aborted => 9,
+            Self::Other => 10,
+        }
+    }
+
+    const fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            1 => Some(Self::NoLink),
+            2 => Some(Self::Dhcp),
+            3 => Some(Self::Dns),
+            4 => Some(Self::Connect),
+            5 => Some(Self::Http),
+            6 => Some(Self::CaptivePortal),
+            7 => Some(Self::Disk),
+            8 => Some(Self::Verification),
+            9 => Some(Self::Aborted),
+            10 => Some(Self::Other),
+            _ => None,
+        }
+    }
+}
+
+/// Retries a session needed, per phase
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetryCounts {
+    pub dhcp: u16,
+    pub dns: u16,
+    pub connect: u16,
+    pub http: u16,
+}
+
+impl RetryCounts {
+    /// Sum of all retries
+    pub fn total(&self) -> u32 {
+        self.dhcp as u32 + self.dns as u32 + self.connect as u32 + self.http as u32
+    }
+}
+
+/// How a download session ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExitReport {
+    state: [u8; MAX_STATE_NAME_LEN],
+    state_len: usize,
+    name: [u8; MAX_REPORT_NAME_LEN],
+    name_len: usize,
+    /// `None` if the download completed
+    pub failure: Option<FailureReason>,
+    pub bytes_downloaded: u64,
+    pub bytes_written: u64,
+    /// From the start of the session to its end
+    pub duration_ms: u64,
+    /// From the first HTTP request to the end (0 if none was sent)
+    pub transfer_ms: u64,
+    pub retries: RetryCounts,
+    /// Outcome of the SHA-256 check, if the download got that far
+    pub verification: Verification,
+}
+
+impl ExitReport {
+    /// Report of a session that ended in `final_state` (truncated to fit)
+    pub fn new(final_state: &str, failure: Option<FailureReason>) -> Self {
+        let mut report = Self {
+            state: [0u8; MAX_STATE_NAME_LEN],
+            state_len: 0,
+            name: [0u8; MAX_REPORT_NAME_LEN],
+            name_len: 0,
+            failure,
+            bytes_downloaded: 0,
+            bytes_written: 0,
+            duration_ms: 0,
+            transfer_ms: 0,
+            retries: RetryCounts::default(),
+            verification: Verification::Unchecked,
+        };
+        report.state_len = copy_truncated(&mut report.state, final_state);
+        report
+    }
+
+    /// State the session ended in, or failed from
+    pub fn final_state(&self) -> &str {
+        core::str::from_utf8(&self.state[..self.state_len]).unwrap_or("")
+    }
+
+    /// Name the ISO, truncated to [`MAX_REPORT_NAME_LEN`] bytes
+    pub fn set_iso_name(&mut self, name: &str) {
+        self.name = [0u8; MAX_REPORT_NAME_LEN];
+        self.name_len = copy_truncated(&mut self.name, name);
+    }
+
+    /// ISO the session downloaded (empty if it had no name)
+    pub fn iso_name(&self) -> &str {
+        core::str::from_utf8(&self.name[..self.name_len]).unwrap_or("")
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.failure.is_none()
+    }
+
+    /// Average download rate in bytes per second (0 if nothing was timed)
+    pub fn throughput(&self) -> u64 {
+        if self.transfer_ms == 0 {
+            return 0;
+        }
+        ((self.bytes_downloaded as u128 * 1000) / self.transfer_ms as u128) as u64
+    }
+
+    /// Serialize to a buffer of at least [`EXIT_REPORT_SIZE`] bytes
+    pub fn serialize(&self, buffer: &mut [u8]) -> Result<usize, IsoError> {
+        if buffer.len() < EXIT_REPORT_SIZE {
+            return Err(IsoError::IoError);
+        }
+        let buffer = &mut buffer[..EXIT_REPORT_SIZE];
+        buffer.fill(0);
+
+        buffer[0..8].copy_from_slice(&EXIT_REPORT_MAGIC);
+        buffer[0x08] = self.failure.map_or(0, FailureReason::to_byte);
+        buffer[0x09] = self.state_len as u8;
+        buffer[0x0A] = self.name_len as u8;
+        buffer[0x0B] = self.verification.to_byte();
+        buffer[0x0C..0x0C + self.state_len].copy_from_slice(&self.state[..self.state_len]);
+        buffer[0x20..0x28].copy_from_slice(&self.bytes_downloaded.to_le_bytes());
+        buffer[0x28..0x30].copy_from_slice(&self.bytes_written.to_le_bytes());
+        buffer[0x30..0x38].copy_from_slice(&self.duration_ms.to_le_bytes());
+        buffer[0x38..0x40].copy_from_slice(&self.transfer_ms.to_le_bytes());
+        let retries = [
+            self.retries.dhcp,
+            self.retries.dns,
+            self.retries.connect,
+            self.retries.http,
+        ];
+        for (i, count) in retries.iter().enumerate() {
+            buffer[0x40 + i * 2..0x42 + i * 2].copy_from_slice(&count.to_le_bytes());
+        }
+        buffer[0x4C..0x4C + self.name_len].copy_from_slice(&self.name[..self.name_len]);
+
+        let crc = crc32(&buffer[..CRC_OFFSET]);
+        buffer[CRC_OFFSET..EXIT_REPORT_SIZE].copy_from_slice(&crc.to_le_bytes());
+
+        Ok(EXIT_REPORT_SIZE)
+    }
+
+    /// Deserialize a report written by [`serialize`](Self::serialize)
+    pub fn deserialize(buffer: &[u8]) -> Result<Self, IsoError> {
+        if buffer.len() < EXIT_REPORT_SIZE || buffer[0..8] != EXIT_REPORT_MAGIC {
+            return Err(IsoError::InvalidManifest);
+        }
+
+        let mut crc = [0u8; 4];
+        crc.copy_from_slice(&buffer[CRC_OFFSET..EXIT_REPORT_SIZE]);
+        if u32::from_le_bytes(crc) != crc32(&buffer[..CRC_OFFSET]) {
+            return Err(IsoError::DataCorruption);
+        }
+
+        let failure = match buffer[0x08] {
+            0 => None,
+            byte => Some(FailureReason::from_byte(byte).ok_or(IsoError::InvalidManifest)?),
+        };
+        let state_len = buffer[0x09] as usize;
+        let name_len = buffer[0x0A] as usize;
+        if state_len > MAX_STATE_NAME_LEN || name_len > MAX_REPORT_NAME_LEN {
+            return Err(IsoError::InvalidManifest);
+        }
+        let state = core::str::from_utf8(&buffer[0x0C..0x0C + state_len])
+            .map_err(|_| IsoError::DataCorruption)?;
+        let name = core::str::from_utf8(&buffer[0x4C..0x4C + name_len])
+            .map_err(|_| IsoError::DataCorruption)?;
+
+        let mut report = Self::new(state, failure);
+        report.set_iso_name(name);
+        report.verification =
+            Verification::from_byte(buffer[0x0B]).ok_or(IsoError::InvalidManifest)?;
+        report.bytes_downloaded = read_u64(buffer, 0x20);
+        report.bytes_written = read_u64(buffer, 0x28);
+        report.duration_ms = read_u64(buffer, 0x30);
+        report.transfer_ms = read_u64(buffer, 0x38);
+        report.retries = RetryCounts {
+            dhcp: read_u16(buffer, 0x40),
+            dns: read_u16(buffer, 0x42),
+            connect: read_u16(buffer, 0x44),
+            http: read_u16(buffer, 0x46),
+        };
+        Ok(report)
+    }
+}
+
+fn copy_truncated(dest: &mut [u8], s: &str) -> usize {
+    let mut len = s.len().min(dest.len());
+    while !s.is_char_boundary(len) {
+        len -= 1;
+    }
+    dest[..len].copy_from_slice(&s.as_bytes()[..len]);
+    len
+}
+
+fn read_u64(buffer: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&buffer[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+fn read_u16(buffer: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([buffer[offset], buffer[offset + 1]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_roundtrip() {
+        let mut report = ExitReport::new("HTTP", Some(FailureReason::Http));
+        report.set_iso_name("tails-amd64-6.10.iso");
+        report.bytes_downloaded = 3 * 1024 * 1024 * 1024;
+        report.bytes_written = 3 * 1024 * 1024 * 1024 - 65536;
+        report.duration_ms = 310_000;
+        report.transfer_ms = 300_000;
+        report.retries = RetryCounts {
+            dhcp: 1,
+            dns: 0,
+            connect: 2,
+            http: 3,
+        };
+
+        let mut buffer = [0u8; EXIT_REPORT_SIZE];
+        assert_eq!(report.serialize(&mut buffer), Ok(EXIT_REPORT_SIZE));
+        let parsed = ExitReport::deserialize(&buffer).unwrap();
+        assert_eq!(parsed, report);
+        assert_eq!(parsed.final_state(), "HTTP");
+        assert_eq!(parsed.iso_name(), "tails-amd64-6.10.iso");
+        assert_eq!(parsed.throughput(), 10_737_418);
+        assert_eq!(parsed.retries.total(), 6);
+        assert!(!parsed.is_success());
+    }
+
+    #[test]
+    fn test_completed_report() {
+        let mut report = ExitReport::new("a state name longer than the field", None);
+        report.verification = Verification::Verified;
+        assert_eq!(report.final_state().len(), MAX_STATE_NAME_LEN);
+        assert_eq!(report.throughput(), 0);
+        assert_eq!(report.iso_name(), "");
+
+        let mut buffer = [0u8; EXIT_REPORT_SIZE];
+        report.serialize(&mut buffer).unwrap();
+        assert_eq!(buffer[0x08], 0);
+        let parsed = ExitReport::deserialize(&buffer).unwrap();
+        assert!(parsed.is_success());
+        assert_eq!(parsed.verification, Verification::Verified);
+    }
+
+    #[test]
+    fn test_corrupt_report_rejected() {
+        let mut buffer = [0u8; EXIT_REPORT_SIZE];
+        ExitReport::new("Done", None)
+            .serialize(&mut buffer)
+            .unwrap();
+        buffer[0x21] ^= 0xFF;
+        assert_eq!(
+            ExitReport::deserialize(&buffer),
+            Err(IsoError::DataCorruption)
+        );
+        assert_eq!(
+            ExitReport::deserialize(&buffer[..40]),
+            Err(IsoError::InvalidManifest)
+        );
+    }
+}
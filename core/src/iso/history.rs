@@ -0,0 +1,282 @@
+//! Download history records
+//!
+//! One fixed-size record per completed download, stored next to the
+//! manifests as `/.iso/DLnnnn.HST`. The download path writes them after the
+//! manifest; the bootloader lists them so mirrors can be compared and the
+//! user can see what was put on this machine.
+//!
+//! # Record Format (224 bytes)
+//!
+//! ```text
+//! Offset  Size  Field
+//! 0x00    8     Magic "MXHST\x01\0\0"
+//! 0x08    64    ISO name (null-terminated)
+//! 0x48    8     ISO size in bytes
+//! 0x50    8     Download duration in milliseconds
+//! 0x58    1     Verification result
+//! 0x59    1     Mirror URL length
+//! 0x5A    2     Reserved
+//! 0x5C    4     CRC32 of bytes 0x00-0x5B and the mirror URL
+//! 0x60    128   Mirror URL
+//! ```
+
+extern crate alloc;
+use alloc::format;
+use alloc::string::String;
+
+use super::error::IsoError;
+use super::manifest::crc32;
+
+/// Magic bytes identifying a history record
+pub const HISTORY_MAGIC: [u8; 8] = *b"MXHST\x01\x00\x00";
+
+/// Serialized record size
+pub const HISTORY_RECORD_SIZE: usize = 224;
+
+/// Maximum ISO name length in a record
+pub const MAX_HISTORY_NAME_LEN: usize = 64;
+
+/// Maximum mirror URL length in a record
+pub const MAX_MIRROR_LEN: usize = 128;
+
+/// Record slots before the oldest are no longer probed
+pub const MAX_HISTORY_RECORDS: usize = 1000;
+
+/// File extension of history records in the manifest directory
+pub const HISTORY_EXT: &str = "HST";
+
+/// Outcome of the SHA256 check for a download
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verification {
+    /// No expected hash was configured
+    Unchecked,
+    /// Hash matched
+    Verified,
+    /// Hash did not match
+    Mismatch,
+}
+
+impl Verification {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Unchecked => "unchecked",
+            Self::Verified => "verified",
+            Self::Mismatch => "MISMATCH",
+        }
+    }
+
+    pub(super) const fn to_byte(self) -> u8 {
+        match self {
+            Self::Unchecked => 0,
+            Self::Verified => 1,
+            Self::Mismatch => 2,
+        }
+    }
+
+    pub(super) const fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Self::Unchecked),
+            1 => Some(Self::Verified),
+            2 => Some(Self::Mismatch),
+            _ => None,
+        }
+    }
+}
+
+/// One completed download
+#[derive(Clone)]
+pub struct DownloadRecord {
+    name: [u8; MAX_HISTORY_NAME_LEN],
+    name_len: usize,
+    mirror: [u8; MAX_MIRROR_LEN],
+    mirror_len: usize,
+    /// ISO size in bytes
+    pub size: u64,
+    /// Time from the first HTTP request until the data was on disk
+    pub duration_ms: u64,
+    pub verification: Verification,
+}
+
+impl DownloadRecord {
+    pub fn new(
+        name: &str,
+        mirror: &str,
+        size: u64,
+        duration_ms: u64,
+        verification: Verification,
+    ) -> Self {
+        let mut record = Self {
+            name: [0u8; MAX_HISTORY_NAME_LEN],
+            name_len: 0,
+            mirror: [0u8; MAX_MIRROR_LEN],
+            mirror_len: 0,
+            size,
+            duration_ms,
+            verification,
+        };
+        record.name_len = copy_truncated(&mut record.name[..MAX_HISTORY_NAME_LEN - 1], name);
+        record.mirror_len = copy_truncated(&mut record.mirror, mirror);
+        record
+    }
+
+    pub fn name_str(&self) -> &str {
+        core::str::from_utf8(&self.name[..self.name_len]).unwrap_or("")
+    }
+
+    /// URL the ISO was fetched from
+    pub fn mirror_str(&self) -> &str {
+        core::str::from_utf8(&self.mirror[..self.mirror_len]).unwrap_or("")
+    }
+
+    /// Host part of the mirror URL, for grouping downloads by mirror
+    pub fn mirror_host(&self) -> &str {
+        let url = self.mirror_str();
+        let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+        rest.split(['/', ':']).next().unwrap_or(rest)
+    }
+
+    /// Average throughput in bytes per second (0 if no time was recorded)
+    pub fn throughput(&self) -> u64 {
+        if self.duration_ms == 0 {
+            return 0;
+        }
+        ((self.size as u128 * 1000) / self.duration_ms as u128) as u64
+    }
+
+    /// Serialize to a buffer of at least [`HISTORY_RECORD_SIZE`] bytes
+    pub fn serialize(&self, buffer: &mut [u8]) -> Result<usize, IsoError> {
+        if buffer.len() < HISTORY_RECORD_SIZE {
+            return Err(IsoError::IoError);
+        }
+        let buffer = &mut buffer[..HISTORY_RECORD_SIZE];
+        buffer.fill(0);
+
+        buffer[0..8].copy_from_slice(&HISTORY_MAGIC);
+        buffer[0x08..0x08 + self.name_len].copy_from_slice(&self.name[..self.name_len]);
+        buffer[0x48..0x50].copy_from_slice(&self.size.to_le_bytes());
+        buffer[0x50..0x58].copy_from_slice(&self.duration_ms.to_le_bytes());
+        buffer[0x58] = self.verification.to_byte();
+        buffer[0x59] = self.mirror_len as u8;
+        buffer[0x60..0x60 + self.mirror_len].copy_from_slice(&self.mirror[..self.mirror_len]);
+
+        let crc = record_crc(buffer);
+        buffer[0x5C..0x60].copy_from_slice(&crc.to_le_bytes());
+
+        Ok(HISTORY_RECORD_SIZE)
+    }
+
+    /// Deserialize a record written by [`serialize`](Self::serialize)
+    pub fn deserialize(buffer: &[u8]) -> Result<Self, IsoError> {
+        if buffer.len() < HISTORY_RECORD_SIZE || buffer[0..8] != HISTORY_MAGIC {
+            return Err(IsoError::InvalidManifest);
+        }
+
+        let stored_crc =
+            u32::from_le_bytes([buffer[0x5C], buffer[0x5D], buffer[0x5E], buffer[0x5F]]);
+        if stored_crc != record_crc(buffer) {
+            return Err(IsoError::DataCorruption);
+        }
+
+        let verification =
+            Verification::from_byte(buffer[0x58]).ok_or(IsoError::InvalidManifest)?;
+        let mirror_len = buffer[0x59] as usize;
+        if mirror_len > MAX_MIRROR_LEN {
+            return Err(IsoError::InvalidManifest);
+        }
+
+        let mut record = Self {
+            name: [0u8; MAX_HISTORY_NAME_LEN],
+            name_len: 0,
+            mirror: [0u8; MAX_MIRROR_LEN],
+            mirror_len,
+            size: read_u64(buffer, 0x48),
+            duration_ms: read_u64(buffer, 0x50),
+            verification,
+        };
+
+        let name = &buffer[0x08..0x08 + MAX_HISTORY_NAME_LEN - 1];
+        record.name_len = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+        record.name[..record.name_len].copy_from_slice(&name[..record.name_len]);
+        record.mirror[..mirror_len].copy_from_slice(&buffer[0x60..0x60 + mirror_len]);
+
+        Ok(record)
+    }
+}
+
+/// 8.3 filename of history slot `index`, e.g. "DL0007.HST"
+pub fn history_filename(index: usize) -> String {
+    format!("DL{:04}.{}", index, HISTORY_EXT)
+}
+
+/// CRC over the header (minus the CRC field) and the mirror URL
+fn record_crc(buffer: &[u8]) -> u32 {
+    let mut covered = [0u8; 0x5C + MAX_MIRROR_LEN];
+    covered[..0x5C].copy_from_slice(&buffer[..0x5C]);
+    covered[0x5C..].copy_from_slice(&buffer[0x60..0x60 + MAX_MIRROR_LEN]);
+    crc32(&covered)
+}
+
+fn read_u64(buffer: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&buffer[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+/// Copy as much of `s` as fits into `dest` without splitting a character.
+fn copy_truncated(dest: &mut [u8], s: &str) -> usize {
+    let mut len = s.len().min(dest.len());
+    while !s.is_char_boundary(len) {
+        len -= 1;
+    }
+    dest[..len].copy_from_slice(&s.as_bytes()[..len]);
+    len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_roundtrip() {
+        let record = DownloadRecord::new(
+            "tails-amd64-6.10.iso",
+            "http://mirror.example.org:8080/tails/stable/tails-amd64-6.10.iso",
+            1_400_000_000,
+            70_000,
+            Verification::Verified,
+        );
+
+        let mut buffer = [0u8; HISTORY_RECORD_SIZE];
+        assert_eq!(record.serialize(&mut buffer).unwrap(), HISTORY_RECORD_SIZE);
+
+        let restored = DownloadRecord::deserialize(&buffer).unwrap();
+        assert_eq!(restored.name_str(), "tails-amd64-6.10.iso");
+        assert_eq!(restored.mirror_host(), "mirror.example.org");
+        assert_eq!(restored.size, 1_400_000_000);
+        assert_eq!(restored.throughput(), 20_000_000);
+        assert_eq!(restored.verification, Verification::Verified);
+    }
+
+    #[test]
+    fn test_corrupt_record_rejected() {
+        let record = DownloadRecord::new("a.iso", "http://h/a.iso", 1, 1, Verification::Unchecked);
+        let mut buffer = [0u8; HISTORY_RECORD_SIZE];
+        record.serialize(&mut buffer).unwrap();
+
+        buffer[0x70] ^= 0xFF;
+        assert!(matches!(
+            DownloadRecord::deserialize(&buffer),
+            Err(IsoError::DataCorruption)
+        ));
+    }
+
+    #[test]
+    fn test_long_fields_truncate() {
+        let long = "x".repeat(300);
+        let record = DownloadRecord::new(&long, &long, 0, 0, Verification::Mismatch);
+        assert_eq!(record.name_str().len(), MAX_HISTORY_NAME_LEN - 1);
+        assert_eq!(record.mirror_str().len(), MAX_MIRROR_LEN);
+        assert_eq!(record.throughput(), 0);
+        assert_eq!(history_filename(7), "DL0007.HST");
+    }
+}
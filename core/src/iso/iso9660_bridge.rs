@@ -22,7 +22,9 @@
 //! ```
 
 extern crate alloc;
+use super::error::IsoError;
 use super::reader::IsoReadContext;
+use crate::disk::partition_io::{PartitionBlockIo, PartitionIoError};
 use gpt_disk_io::BlockIo;
 use gpt_disk_types::Lba;
 
@@ -35,18 +37,87 @@ const DISK_BLOCK_SIZE: usize = 512;
 /// Ratio of ISO sectors to disk blocks
 const BLOCKS_PER_ISO_SECTOR: usize = ISO_SECTOR_SIZE / DISK_BLOCK_SIZE;
 
+/// Most disks one ISO can be spread over
+pub const MAX_POOL_DISKS: usize = 8;
+
+/// Block devices an ISO's chunks live on, looked up by chunk disk ID.
+///
+/// The device added as disk 0 is the default: it serves every chunk whose
+/// disk isn't in the pool, which is all of them for single-disk ISOs.
+pub struct DiskPool<'a, B: BlockIo> {
+    disks: [Option<(u32, &'a mut B)>; MAX_POOL_DISKS],
+}
+
+impl<'a, B: BlockIo> DiskPool<'a, B> {
+    /// Create an empty pool
+    pub fn new() -> Self {
+        Self {
+            disks: [const { None }; MAX_POOL_DISKS],
+        }
+    }
+
+    /// Pool of one device that serves every chunk
+    pub fn single(block_io: &'a mut B) -> Self {
+        let mut pool = Self::new();
+        pool.disks[0] = Some((0, block_io));
+        pool
+    }
+
+    /// Add the device for `disk_id`
+    pub fn add(&mut self, disk_id: u32, block_io: &'a mut B) -> Result<(), IsoError> {
+        let slot = self
+            .disks
+            .iter_mut()
+            .find(|slot| slot.is_none())
+            .ok_or(IsoError::NotSupported)?;
+        *slot = Some((disk_id, block_io));
+        Ok(())
+    }
+
+    /// Whether the pool has a device for every chunk of `ctx`
+    pub fn covers(&self, ctx: &IsoReadContext) -> bool {
+        ctx.chunk_disks[..ctx.num_chunks]
+            .iter()
+            .all(|&disk_id| self.position(disk_id).is_some())
+    }
+
+    fn position(&self, disk_id: u32) -> Option<usize> {
+        let find = |id: u32| {
+            self.disks
+                .iter()
+                .position(|slot| matches!(slot, Some((d, _)) if *d == id))
+        };
+        find(disk_id).or_else(|| find(0))
+    }
+
+    fn get(&mut self, disk_id: u32) -> Option<&mut B> {
+        let index = self.position(disk_id)?;
+        self.disks[index]
+            .as_mut()
+            .map(|(_, block_io)| &mut **block_io)
+    }
+}
+
+impl<B: BlockIo> Default for DiskPool<'_, B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Block I/O adapter that implements gpt_disk_io::BlockIo
 ///
 /// This allows iso9660 to read from chunked ISO storage transparently.
 /// The adapter translates virtual sector addresses to physical disk locations.
 ///
 /// iso9660 uses 2048-byte sectors, but physical disk uses 512-byte blocks.
-/// This adapter handles the translation.
+/// This adapter handles the translation. Each chunk is read through a
+/// [`PartitionBlockIo`] over its partition, so a chunk size that overruns
+/// the partition fails the read instead of returning the next partition.
 pub struct IsoBlockIoAdapter<'a, B: BlockIo> {
     /// ISO read context (chunk partition info)
     ctx: IsoReadContext,
-    /// Underlying block device
-    block_io: &'a mut B,
+    /// Underlying block devices
+    disks: DiskPool<'a, B>,
 }
 
 impl<'a, B: BlockIo> IsoBlockIoAdapter<'a, B> {
@@ -56,7 +127,20 @@ impl<'a, B: BlockIo> IsoBlockIoAdapter<'a, B> {
     /// * `ctx` - ISO read context from IsoStorageManager
     /// * `block_io` - Underlying disk block device
     pub fn new(ctx: IsoReadContext, block_io: &'a mut B) -> Self {
-        Self { ctx, block_io }
+        Self {
+            ctx,
+            disks: DiskPool::single(block_io),
+        }
+    }
+
+    /// Create an adapter for an ISO spread over several disks
+    ///
+    /// Fails with `ChunkNotFound` if a chunk's disk isn't in `disks`.
+    pub fn with_pool(ctx: IsoReadContext, disks: DiskPool<'a, B>) -> Result<Self, IsoError> {
+        if !disks.covers(&ctx) {
+            return Err(IsoError::ChunkNotFound);
+        }
+        Ok(Self { ctx, disks })
     }
 
     /// Get total size in bytes
@@ -84,26 +168,18 @@ impl<'a, B: BlockIo> IsoBlockIoAdapter<'a, B> {
         None
     }
 
-    /// Translate ISO byte offset to physical disk LBA (512-byte sectors)
-    fn translate_byte_offset_to_disk_lba(&self, byte_offset: u64) -> Option<u64> {
+    /// Translate ISO byte offset to the chunk holding it and the LBA
+    /// within the chunk's partition (512-byte sectors)
+    fn translate_byte_offset_to_chunk_lba(&self, byte_offset: u64) -> Option<(usize, u64)> {
         let (chunk_idx, offset_in_chunk) = self.find_chunk_for_offset(byte_offset)?;
 
-        // Get partition start LBA from manifest
-        let (part_start, _) = self.ctx.chunk_lbas[chunk_idx];
-
-        // Calculate sector within the chunk
         // ISO data is written directly at partition start (no offset)
-        let disk_sector_in_chunk = offset_in_chunk / DISK_BLOCK_SIZE as u64;
-
-        // Physical LBA = partition start + sector offset
-        let physical_lba = part_start + disk_sector_in_chunk;
-
-        Some(physical_lba)
+        Some((chunk_idx, offset_in_chunk / DISK_BLOCK_SIZE as u64))
     }
 }
 
 impl<'a, B: BlockIo> BlockIo for IsoBlockIoAdapter<'a, B> {
-    type Error = B::Error;
+    type Error = PartitionIoError<B::Error>;
 
     fn block_size(&self) -> gpt_disk_types::BlockSize {
         // Report 2048-byte block size (ISO9660 sector size)
@@ -138,20 +214,21 @@ impl<'a, B: BlockIo> BlockIo for IsoBlockIoAdapter<'a, B> {
                 break;
             }
 
-            // Get the physical LBA for the start of this ISO sector
-            let first_physical_lba = match self.translate_byte_offset_to_disk_lba(byte_offset) {
-                Some(lba) => lba,
-                None => {
-                    // Chunk not found - fill this sector with zeros and continue
-                    let buf_offset = current_pos * ISO_SECTOR_SIZE;
-                    buffer[buf_offset..buf_offset + ISO_SECTOR_SIZE].fill(0);
-                    current_pos += 1;
-                    continue;
-                }
-            };
+            // Get the chunk LBA for the start of this ISO sector
+            let (chunk_idx, first_chunk_lba) =
+                match self.translate_byte_offset_to_chunk_lba(byte_offset) {
+                    Some(location) => location,
+                    None => {
+                        // Chunk not found - fill this sector with zeros and continue
+                        let buf_offset = current_pos * ISO_SECTOR_SIZE;
+                        buffer[buf_offset..buf_offset + ISO_SECTOR_SIZE].fill(0);
+                        current_pos += 1;
+                        continue;
+                    }
+                };
 
             // Determine how many contiguous ISO sectors we can read at once
-            // Sectors are contiguous if they map to consecutive physical LBAs
+            // Sectors are contiguous if they map to consecutive LBAs of one chunk
             let mut batch_count = 1usize;
 
             while current_pos + batch_count < num_iso_sectors {
@@ -164,14 +241,13 @@ impl<'a, B: BlockIo> BlockIo for IsoBlockIoAdapter<'a, B> {
                 }
 
                 // Check if this sector is contiguous with the batch
-                let expected_lba =
-                    first_physical_lba + (batch_count * BLOCKS_PER_ISO_SECTOR) as u64;
-                let actual_lba = match self.translate_byte_offset_to_disk_lba(next_byte_offset) {
-                    Some(lba) => lba,
+                let expected_lba = first_chunk_lba + (batch_count * BLOCKS_PER_ISO_SECTOR) as u64;
+                let actual = match self.translate_byte_offset_to_chunk_lba(next_byte_offset) {
+                    Some(location) => location,
                     None => break, // End batch at chunk boundary
                 };
 
-                if actual_lba != expected_lba {
+                if actual != (chunk_idx, expected_lba) {
                     // Not contiguous (chunk boundary), end this batch
                     break;
                 }
@@ -183,8 +259,14 @@ impl<'a, B: BlockIo> BlockIo for IsoBlockIoAdapter<'a, B> {
             let buf_start = current_pos * ISO_SECTOR_SIZE;
             let buf_end = buf_start + batch_count * ISO_SECTOR_SIZE;
 
-            self.block_io
-                .read_blocks(Lba(first_physical_lba), &mut buffer[buf_start..buf_end])?;
+            let (part_start, part_end) = self.ctx.chunk_lbas[chunk_idx];
+            match self.disks.get(self.ctx.chunk_disks[chunk_idx]) {
+                Some(block_io) => PartitionBlockIo::from_range(block_io, part_start, part_end)
+                    .read_blocks(Lba(first_chunk_lba), &mut buffer[buf_start..buf_end])?,
+                // Only a pool without a default disk can miss one, and
+                // with_pool checked it covers every chunk
+                None => buffer[buf_start..buf_end].fill(0),
+            }
 
             current_pos += batch_count;
         }
@@ -199,7 +281,10 @@ impl<'a, B: BlockIo> BlockIo for IsoBlockIoAdapter<'a, B> {
     }
 
     fn flush(&mut self) -> Result<(), Self::Error> {
-        self.block_io.flush()
+        for (_, block_io) in self.disks.disks.iter_mut().flatten() {
+            block_io.flush().map_err(PartitionIoError::Disk)?;
+        }
+        Ok(())
     }
 }
 
@@ -283,6 +368,7 @@ mod tests {
         let mut ctx = IsoReadContext {
             chunk_lbas: [(0, 0); MAX_CHUNKS],
             chunk_sizes: [0; MAX_CHUNKS],
+            chunk_disks: [0; MAX_CHUNKS],
             num_chunks: 1,
             total_size: 1_000_000,
         };
@@ -294,15 +380,79 @@ mod tests {
         let adapter = IsoBlockIoAdapter::new(ctx, &mut mock);
 
         // Byte offset 0 should translate to partition start
-        let phys = adapter.translate_byte_offset_to_disk_lba(0).unwrap();
-        assert_eq!(phys, 1000); // part_start
+        let (_, lba) = adapter.translate_byte_offset_to_chunk_lba(0).unwrap();
+        assert_eq!(lba, 0);
 
         // Byte offset 512 should be 1 disk sector further
-        let phys = adapter.translate_byte_offset_to_disk_lba(512).unwrap();
-        assert_eq!(phys, 1000 + 1);
+        let (_, lba) = adapter.translate_byte_offset_to_chunk_lba(512).unwrap();
+        assert_eq!(lba, 1);
 
         // Byte offset 2048 (1 ISO sector) should be 4 disk sectors from start
-        let phys = adapter.translate_byte_offset_to_disk_lba(2048).unwrap();
-        assert_eq!(phys, 1000 + 4);
+        let (_, lba) = adapter.translate_byte_offset_to_chunk_lba(2048).unwrap();
+        assert_eq!(lba, 4);
+    }
+
+    #[test]
+    fn test_pooled_read() {
+        use crate::iso::MAX_CHUNKS;
+        let mut ctx = IsoReadContext {
+            chunk_lbas: [(0, 0); MAX_CHUNKS],
+            chunk_sizes: [0; MAX_CHUNKS],
+            chunk_disks: [0; MAX_CHUNKS],
+            num_chunks: 2,
+            total_size: 4096,
+        };
+        // One ISO sector on the ESP's disk, the next on disk 7 - right
+        // where it would follow on the first disk
+        ctx.chunk_lbas[0] = (0, 3);
+        ctx.chunk_sizes[0] = 2048;
+        ctx.chunk_lbas[1] = (4, 7);
+        ctx.chunk_sizes[1] = 2048;
+        ctx.chunk_disks[1] = 7;
+
+        let mut first = MockBlockIo { data: [0xAA; 4096] };
+        let mut second = MockBlockIo { data: [0xBB; 4096] };
+
+        let mut only_second = DiskPool::new();
+        only_second.add(7, &mut second).unwrap();
+        assert!(IsoBlockIoAdapter::with_pool(ctx.clone(), only_second).is_err());
+
+        let mut pool = DiskPool::new();
+        pool.add(0, &mut first).unwrap();
+        pool.add(7, &mut second).unwrap();
+        let mut adapter = IsoBlockIoAdapter::with_pool(ctx, pool).unwrap();
+
+        let mut buffer = [0u8; 4096];
+        adapter.read_blocks(Lba(0), &mut buffer).unwrap();
+        assert!(buffer[..2048].iter().all(|&b| b == 0xAA));
+        assert!(buffer[2048..].iter().all(|&b| b == 0xBB));
+    }
+
+    #[test]
+    fn test_chunk_stays_in_partition() {
+        use crate::iso::MAX_CHUNKS;
+        let mut ctx = IsoReadContext {
+            chunk_lbas: [(0, 0); MAX_CHUNKS],
+            chunk_sizes: [0; MAX_CHUNKS],
+            chunk_disks: [0; MAX_CHUNKS],
+            num_chunks: 1,
+            total_size: 4096,
+        };
+        // The manifest claims two ISO sectors in a partition of one
+        ctx.chunk_lbas[0] = (2, 5);
+        ctx.chunk_sizes[0] = 4096;
+
+        let mut data = [0xAA; 4096];
+        data[3072..].fill(0xBB);
+        let mut mock = MockBlockIo { data };
+        let mut adapter = IsoBlockIoAdapter::new(ctx, &mut mock);
+
+        let mut buffer = [0u8; 2048];
+        adapter.read_blocks(Lba(0), &mut buffer).unwrap();
+        assert!(buffer.iter().all(|&b| b == 0xAA));
+        assert!(matches!(
+            adapter.read_blocks(Lba(1), &mut buffer),
+            Err(PartitionIoError::OutOfRange { lba: 4, .. })
+        ));
     }
 }
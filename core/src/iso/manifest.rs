@@ -14,9 +14,11 @@
 //! 0x50    32    SHA256 hash (or zeros if not verified)
 //! 0x70    1     Number of chunks
 //! 0x71    1     Flags (bit 0 = complete, bit 1 = verified)
-//! 0x72    2     Reserved
+//! 0x72    1     Number of skipped ranges
+//! 0x73    1     Reserved
 //! 0x74    4     CRC32 of header (offset 0x00-0x73)
-//! 0x78    8     Reserved (align to 128 bytes)
+//! 0x78    4     Server Last-Modified, Unix seconds (0 = unknown)
+//! 0x7C    4     Hash of the server ETag (0 = none)
 //! 0x80    N*48  Chunk entries (48 bytes each)
 //!
 //! Chunk Entry (48 bytes):
@@ -26,13 +28,43 @@
 //! 0x20    8     Data size in this chunk
 //! 0x28    1     Chunk index
 //! 0x29    1     Flags (bit 0 = written)
-//! 0x2A    6     Reserved
+//! 0x2A    2     Reserved
+//! 0x2C    4     Disk ID (0 = the disk this ESP is on)
+//!
+//! Skipped Range (12 bytes, after the chunk entries):
+//! 0x00    8     First sector, from the start of the image
+//! 0x08    4     Number of sectors
 //! ```
 //!
-//! Total header size: 128 + (num_chunks * 48) bytes
+//! Total size: 128 + (num_chunks * 48) + (num_skipped * 12) bytes
+//!
+//! Skipped ranges are all-zero sectors a download didn't write because the
+//! disk already read as zero there (see [`SkipList`]). Older manifests
+//! have none.
+//!
+//! The Last-Modified and ETag fields were reserved (zero) space in older
+//! manifests, which read back as unknown. They sit outside the header CRC
+//! and only serve update checks: a server answering with another ETag or a
+//! later Last-Modified has a newer image.
+//!
+//! # Raw Copy
+//!
+//! A second copy can be kept outside the filesystem, in the last
+//! [`RAW_MANIFEST_SECTORS`] sectors of the ISO's first chunk partition, so
+//! the ISO survives a lost or damaged manifest file. The copy is the
+//! manifest above behind a 16-byte header with a CRC32 over all of it
+//! (the manifest's own CRC only covers its header):
+//!
+//! ```text
+//! 0x00    8     Magic "MXRAWMF\x01"
+//! 0x08    4     Manifest length (little-endian u32)
+//! 0x0C    4     CRC32 of the manifest bytes
+//! 0x10    N     Manifest (format above), zero padded to the sector end
+//! ```
 
 use super::chunk::{ChunkInfo, ChunkSet, MAX_CHUNKS};
 use super::error::IsoError;
+use super::sparse::{SkipList, MAX_SKIPPED_RANGES, SKIPPED_RANGE_SIZE};
 
 /// Magic number for manifest files: "MXISO\x01\x00\x00"
 pub const MANIFEST_MAGIC: [u8; 8] = [b'M', b'X', b'I', b'S', b'O', 0x01, 0x00, 0x00];
@@ -43,12 +75,32 @@ pub const MANIFEST_HEADER_SIZE: usize = 128;
 /// Chunk entry size in manifest
 pub const CHUNK_ENTRY_SIZE: usize = 48;
 
-/// Maximum manifest size (header + 16 chunks)
-pub const MAX_MANIFEST_SIZE: usize = MANIFEST_HEADER_SIZE + (MAX_CHUNKS * CHUNK_ENTRY_SIZE);
+/// Maximum manifest size (header + 16 chunks + skipped ranges)
+pub const MAX_MANIFEST_SIZE: usize = MANIFEST_HEADER_SIZE
+    + (MAX_CHUNKS * CHUNK_ENTRY_SIZE)
+    + (MAX_SKIPPED_RANGES * SKIPPED_RANGE_SIZE);
 
 /// Maximum filename length in manifest
 pub const MAX_ISO_NAME_LEN: usize = 64;
 
+/// Magic number of the raw manifest copy: "MXRAWMF\x01"
+pub const RAW_MANIFEST_MAGIC: [u8; 8] = [b'M', b'X', b'R', b'A', b'W', b'M', b'F', 0x01];
+
+/// Raw copy header size
+const RAW_HEADER_SIZE: usize = 16;
+
+/// Sectors reserved for the raw copy at the end of a chunk partition
+pub const RAW_MANIFEST_SECTORS: u64 = (RAW_HEADER_SIZE + MAX_MANIFEST_SIZE).div_ceil(512) as u64;
+
+/// Raw copy size in bytes (whole sectors)
+pub const RAW_MANIFEST_SIZE: usize = RAW_MANIFEST_SECTORS as usize * 512;
+
+/// First LBA of the raw copy in a chunk partition ending at `end_lba`
+/// (inclusive).
+pub const fn raw_manifest_lba(end_lba: u64) -> u64 {
+    end_lba + 1 - RAW_MANIFEST_SECTORS
+}
+
 /// Manifest flags
 pub mod flags {
     /// ISO download is complete
@@ -72,6 +124,12 @@ pub struct IsoManifest {
     pub chunks: ChunkSet,
     /// Flags (complete, verified)
     pub flags: u8,
+    /// Last-Modified reported by the server, Unix seconds (0 = unknown)
+    pub last_modified: u32,
+    /// `etag_hash` of the server's ETag (0 = none)
+    pub etag_hash: u32,
+    /// Zero sectors the download left unwritten
+    pub skipped: SkipList,
 }
 
 impl IsoManifest {
@@ -84,6 +142,9 @@ impl IsoManifest {
             sha256: [0u8; 32],
             chunks: ChunkSet::new(),
             flags: 0,
+            last_modified: 0,
+            etag_hash: 0,
+            skipped: SkipList::new(),
         };
         manifest.set_name(name);
         manifest.chunks.total_size = total_size;
@@ -130,24 +191,57 @@ impl IsoManifest {
         self.flags |= flags::VERIFIED;
     }
 
+    /// Whether the server's copy changed since this one was downloaded,
+    /// given the validators of a fresh HEAD response (0 = not sent).
+    ///
+    /// ETags are compared first since they change with the content;
+    /// Last-Modified only counts when the server's is later. `None` when
+    /// neither validator is known on both sides.
+    pub fn changed_upstream(&self, last_modified: u32, etag_hash: u32) -> Option<bool> {
+        if self.etag_hash != 0 && etag_hash != 0 {
+            Some(self.etag_hash != etag_hash)
+        } else if self.last_modified != 0 && last_modified != 0 {
+            Some(last_modified > self.last_modified)
+        } else {
+            None
+        }
+    }
+
     /// Add a chunk partition to the manifest
     pub fn add_chunk(
         &mut self,
         partition_uuid: [u8; 16],
         start_lba: u64,
         end_lba: u64,
+    ) -> Result<usize, IsoError> {
+        self.add_chunk_on_disk(0, partition_uuid, start_lba, end_lba)
+    }
+
+    /// Add a chunk partition on another disk than the ESP's
+    pub fn add_chunk_on_disk(
+        &mut self,
+        disk_id: u32,
+        partition_uuid: [u8; 16],
+        start_lba: u64,
+        end_lba: u64,
     ) -> Result<usize, IsoError> {
         let index = self.chunks.count;
         if index >= MAX_CHUNKS {
             return Err(IsoError::IsoTooLarge);
         }
 
-        let info = ChunkInfo::new(partition_uuid, start_lba, end_lba, index as u8);
+        let mut info = ChunkInfo::new(partition_uuid, start_lba, end_lba, index as u8);
+        info.disk_id = disk_id;
         self.chunks.add_chunk(info).ok_or(IsoError::IsoTooLarge)
     }
 
     /// Calculate required manifest size
     pub fn serialized_size(&self) -> usize {
+        self.skipped_offset() + (self.skipped.len() * SKIPPED_RANGE_SIZE)
+    }
+
+    /// Offset of the skipped ranges, right after the chunk entries
+    fn skipped_offset(&self) -> usize {
         MANIFEST_HEADER_SIZE + (self.chunks.count * CHUNK_ENTRY_SIZE)
     }
 
@@ -181,10 +275,17 @@ impl IsoManifest {
         // Flags
         buffer[0x71] = self.flags;
 
+        // Skipped range count
+        buffer[0x72] = self.skipped.len() as u8;
+
         // CRC32 placeholder (0x74-0x78) - calculate after header is written
         let crc = crc32(&buffer[0..0x74]);
         buffer[0x74..0x78].copy_from_slice(&crc.to_le_bytes());
 
+        // Server validators
+        buffer[0x78..0x7C].copy_from_slice(&self.last_modified.to_le_bytes());
+        buffer[0x7C..0x80].copy_from_slice(&self.etag_hash.to_le_bytes());
+
         // Chunk entries
         for i in 0..self.chunks.count {
             let chunk = &self.chunks.chunks[i];
@@ -207,6 +308,16 @@ impl IsoManifest {
 
             // Flags
             buffer[offset + 0x29] = if chunk.written { 0x01 } else { 0x00 };
+
+            // Disk ID
+            buffer[offset + 0x2C..offset + 0x30].copy_from_slice(&chunk.disk_id.to_le_bytes());
+        }
+
+        // Skipped ranges
+        for (i, range) in self.skipped.as_slice().iter().enumerate() {
+            let offset = self.skipped_offset() + (i * SKIPPED_RANGE_SIZE);
+            buffer[offset..offset + 8].copy_from_slice(&range.sector.to_le_bytes());
+            buffer[offset + 8..offset + 12].copy_from_slice(&range.sectors.to_le_bytes());
         }
 
         Ok(size)
@@ -267,8 +378,21 @@ impl IsoManifest {
         // Flags
         let flags = buffer[0x71];
 
+        // Skipped range count
+        let skipped_count = buffer[0x72] as usize;
+        if skipped_count > MAX_SKIPPED_RANGES {
+            return Err(IsoError::InvalidManifest);
+        }
+
+        // Server validators
+        let last_modified =
+            u32::from_le_bytes([buffer[0x78], buffer[0x79], buffer[0x7A], buffer[0x7B]]);
+        let etag_hash =
+            u32::from_le_bytes([buffer[0x7C], buffer[0x7D], buffer[0x7E], buffer[0x7F]]);
+
         // Check buffer has enough data for chunks
-        let required_size = MANIFEST_HEADER_SIZE + (chunk_count * CHUNK_ENTRY_SIZE);
+        let skipped_offset = MANIFEST_HEADER_SIZE + (chunk_count * CHUNK_ENTRY_SIZE);
+        let required_size = skipped_offset + (skipped_count * SKIPPED_RANGE_SIZE);
         if buffer.len() < required_size {
             return Err(IsoError::InvalidManifest);
         }
@@ -318,10 +442,17 @@ impl IsoManifest {
 
             let index = buffer[offset + 0x28];
             let written = buffer[offset + 0x29] & 0x01 != 0;
+            let disk_id = u32::from_le_bytes([
+                buffer[offset + 0x2C],
+                buffer[offset + 0x2D],
+                buffer[offset + 0x2E],
+                buffer[offset + 0x2F],
+            ]);
 
             let mut info = ChunkInfo::new(partition_uuid, start_lba, end_lba, index);
             info.data_size = data_size;
             info.written = written;
+            info.disk_id = disk_id;
 
             chunks.add_chunk(info);
         }
@@ -335,6 +466,17 @@ impl IsoManifest {
         }
         chunks.bytes_written = bytes_written;
 
+        // Skipped ranges
+        let mut skipped = SkipList::new();
+        for i in 0..skipped_count {
+            let offset = skipped_offset + (i * SKIPPED_RANGE_SIZE);
+            let sector = u64::from_le_bytes(buffer[offset..offset + 8].try_into().unwrap());
+            let sectors = u32::from_le_bytes(buffer[offset + 8..offset + 12].try_into().unwrap());
+            if !skipped.push(sector, sectors) {
+                return Err(IsoError::InvalidManifest);
+            }
+        }
+
         Ok(Self {
             name,
             name_len,
@@ -342,50 +484,66 @@ impl IsoManifest {
             sha256,
             chunks,
             flags,
+            last_modified,
+            etag_hash,
+            skipped,
         })
     }
 }
 
+impl IsoManifest {
+    /// Serialize the raw copy into `buffer` (at least `RAW_MANIFEST_SIZE`).
+    pub fn serialize_raw(&self, buffer: &mut [u8]) -> Result<(), IsoError> {
+        if buffer.len() < RAW_MANIFEST_SIZE {
+            return Err(IsoError::IoError);
+        }
+        buffer[..RAW_MANIFEST_SIZE].fill(0);
+        let len = self.serialize(&mut buffer[RAW_HEADER_SIZE..])?;
+        let crc = crc32(&buffer[RAW_HEADER_SIZE..RAW_HEADER_SIZE + len]);
+        buffer[0..8].copy_from_slice(&RAW_MANIFEST_MAGIC);
+        buffer[8..12].copy_from_slice(&(len as u32).to_le_bytes());
+        buffer[12..16].copy_from_slice(&crc.to_le_bytes());
+        Ok(())
+    }
+
+    /// Parse a raw copy read from disk.
+    ///
+    /// `InvalidManifest` means there is no raw copy here; `DataCorruption`
+    /// that there is one but it is damaged.
+    pub fn deserialize_raw(buffer: &[u8]) -> Result<Self, IsoError> {
+        if buffer.len() < RAW_HEADER_SIZE || buffer[0..8] != RAW_MANIFEST_MAGIC {
+            return Err(IsoError::InvalidManifest);
+        }
+        let len = u32::from_le_bytes([buffer[8], buffer[9], buffer[10], buffer[11]]) as usize;
+        let crc = u32::from_le_bytes([buffer[12], buffer[13], buffer[14], buffer[15]]);
+        if len > MAX_MANIFEST_SIZE || buffer.len() < RAW_HEADER_SIZE + len {
+            return Err(IsoError::DataCorruption);
+        }
+        let manifest = &buffer[RAW_HEADER_SIZE..RAW_HEADER_SIZE + len];
+        if crc32(manifest) != crc {
+            return Err(IsoError::DataCorruption);
+        }
+        Self::deserialize(manifest)
+    }
+}
+
 impl Default for IsoManifest {
     fn default() -> Self {
         Self::new("", 0)
     }
 }
 
-/// Simple CRC32 implementation (no_std compatible)
-/// Uses the standard CRC32 polynomial (IEEE 802.3)
-fn crc32(data: &[u8]) -> u32 {
-    const CRC32_TABLE: [u32; 256] = generate_crc32_table();
-
-    let mut crc = 0xFFFFFFFF;
-    for &byte in data {
-        let index = ((crc ^ byte as u32) & 0xFF) as usize;
-        crc = (crc >> 8) ^ CRC32_TABLE[index];
-    }
-    !crc
+/// 32-bit FNV-1a of an ETag as sent (quotes and `W/` prefix included),
+/// never 0 so that 0 can mean "no ETag".
+pub fn etag_hash(etag: &str) -> u32 {
+    let hash = etag.bytes().fold(0x811C_9DC5u32, |h, b| {
+        (h ^ b as u32).wrapping_mul(0x0100_0193)
+    });
+    hash.max(1)
 }
 
-/// Generate CRC32 lookup table at compile time
-const fn generate_crc32_table() -> [u32; 256] {
-    const POLYNOMIAL: u32 = 0xEDB88320;
-    let mut table = [0u32; 256];
-    let mut i = 0;
-    while i < 256 {
-        let mut crc = i as u32;
-        let mut j = 0;
-        while j < 8 {
-            if crc & 1 != 0 {
-                crc = (crc >> 1) ^ POLYNOMIAL;
-            } else {
-                crc >>= 1;
-            }
-            j += 1;
-        }
-        table[i] = crc;
-        i += 1;
-    }
-    table
-}
+/// CRC32 (IEEE 802.3) of the on-disk structures
+pub(crate) use crate::hash::crc32;
 
 #[cfg(test)]
 mod tests {
@@ -407,6 +565,113 @@ mod tests {
         assert_eq!(restored.total_size, 6_000_000_000);
         assert_eq!(restored.chunks.count, 2);
         assert!(restored.is_complete());
+        assert!(!restored.chunks.spans_disks());
+    }
+
+    #[test]
+    fn test_raw_copy_roundtrip() {
+        let mut manifest = IsoManifest::new("tails-6.10.iso", 1_500_000_000);
+        for i in 0..MAX_CHUNKS as u64 {
+            manifest
+                .add_chunk([i as u8; 16], i * 1000, i * 1000 + 999)
+                .unwrap();
+        }
+        manifest.mark_complete();
+
+        let mut raw = [0u8; RAW_MANIFEST_SIZE];
+        manifest.serialize_raw(&mut raw).unwrap();
+        let restored = IsoManifest::deserialize_raw(&raw).unwrap();
+        assert_eq!(restored.name_str(), "tails-6.10.iso");
+        assert_eq!(restored.chunks.count, MAX_CHUNKS);
+
+        // A flipped bit in a chunk entry, which the header CRC misses
+        raw[RAW_MANIFEST_SIZE - 600] ^= 1;
+        assert_eq!(
+            IsoManifest::deserialize_raw(&raw).err(),
+            Some(IsoError::DataCorruption)
+        );
+        assert_eq!(
+            IsoManifest::deserialize_raw(&[0u8; RAW_MANIFEST_SIZE]).err(),
+            Some(IsoError::InvalidManifest)
+        );
+        assert_eq!(raw_manifest_lba(9999), 10000 - RAW_MANIFEST_SECTORS);
+    }
+
+    #[test]
+    fn test_manifest_disk_ids() {
+        let mut manifest = IsoManifest::new("big.iso", 12_000_000_000);
+        manifest.add_chunk([1u8; 16], 2048, 8_390_655).unwrap();
+        manifest
+            .add_chunk_on_disk(0xDEAD_BEEF, [2u8; 16], 2048, 8_390_655)
+            .unwrap();
+
+        let mut buffer = [0u8; MAX_MANIFEST_SIZE];
+        let size = manifest.serialize(&mut buffer).unwrap();
+        let restored = IsoManifest::deserialize(&buffer[..size]).unwrap();
+
+        assert_eq!(restored.chunks.chunks[0].disk_id, 0);
+        assert_eq!(restored.chunks.chunks[1].disk_id, 0xDEAD_BEEF);
+        assert!(restored.chunks.spans_disks());
+    }
+
+    #[test]
+    fn test_manifest_validators() {
+        let mut manifest = IsoManifest::new("debian-12.iso", 700_000_000);
+        manifest.add_chunk([1u8; 16], 2048, 1_369_186).unwrap();
+        manifest.last_modified = 1_718_000_000;
+        manifest.etag_hash = etag_hash("\"66a1-61a0b3c2\"");
+
+        let mut buffer = [0u8; MAX_MANIFEST_SIZE];
+        let size = manifest.serialize(&mut buffer).unwrap();
+        let restored = IsoManifest::deserialize(&buffer[..size]).unwrap();
+        assert_eq!(restored.last_modified, 1_718_000_000);
+        assert_eq!(restored.etag_hash, manifest.etag_hash);
+
+        // Older manifests had zeros here
+        buffer[0x78..0x80].fill(0);
+        let old = IsoManifest::deserialize(&buffer[..size]).unwrap();
+        assert_eq!((old.last_modified, old.etag_hash), (0, 0));
+
+        assert_ne!(etag_hash("\"a\""), etag_hash("W/\"a\""));
+        assert_ne!(etag_hash(""), 0);
+    }
+
+    #[test]
+    fn test_manifest_skipped_ranges() {
+        let mut manifest = IsoManifest::new("fedora.iso", 2_000_000_000);
+        manifest.add_chunk([1u8; 16], 2048, 3_908_297).unwrap();
+        manifest.skipped.push(100, 128);
+        manifest.skipped.push(3_000_000, 906_250);
+
+        let mut buffer = [0u8; MAX_MANIFEST_SIZE];
+        let size = manifest.serialize(&mut buffer).unwrap();
+        assert_eq!(size, MANIFEST_HEADER_SIZE + CHUNK_ENTRY_SIZE + 2 * SKIPPED_RANGE_SIZE);
+        let restored = IsoManifest::deserialize(&buffer[..size]).unwrap();
+        assert_eq!(restored.skipped, manifest.skipped);
+
+        // A count the entries don't fit behind
+        assert!(IsoManifest::deserialize(&buffer[..size - 1]).is_err());
+
+        // The raw copy still fits the two sectors it always had, so
+        // existing chunk partitions keep it at the same LBA
+        assert_eq!(RAW_MANIFEST_SECTORS, 2);
+    }
+
+    #[test]
+    fn test_changed_upstream() {
+        let mut manifest = IsoManifest::new("tails.iso", 1024);
+        assert_eq!(manifest.changed_upstream(1_700_000_000, 7), None);
+
+        manifest.last_modified = 1_700_000_000;
+        assert_eq!(manifest.changed_upstream(1_700_000_000, 7), Some(false));
+        assert_eq!(manifest.changed_upstream(1_800_000_000, 7), Some(true));
+        // An older copy on a mirror is not an update
+        assert_eq!(manifest.changed_upstream(1_600_000_000, 0), Some(false));
+
+        // ETags win over dates
+        manifest.etag_hash = 7;
+        assert_eq!(manifest.changed_upstream(1_800_000_000, 7), Some(false));
+        assert_eq!(manifest.changed_upstream(1_600_000_000, 8), Some(true));
     }
 
     #[test]
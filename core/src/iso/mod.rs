@@ -48,26 +48,51 @@
 //!
 //! - Maximum chunk size: 4GB - 1 byte (FAT32 limit)
 //! - Maximum chunks per ISO: 16 (fixed array)
-//! - Chunk partition type: Linux filesystem GUID (for now)
+//! - Chunk partition type: `morpheus_gpt::part_type::ISO_CHUNK`, hidden and no-automount
+//!   (the storage manager's Export clears both so another OS can read a chunk)
 //! - Each chunk partition is formatted as FAT32 with single file
 
 #![allow(dead_code)] // Module under construction
 
 mod adapter;
+mod boot_menu;
+mod boot_request;
 mod chunk;
 mod error;
+mod exit_report;
+mod history;
 mod iso9660_bridge;
 mod manifest;
 mod reader;
+mod retention;
+mod sparse;
 mod storage;
 mod writer;
 
 pub use adapter::{ChunkedBlockIo, ChunkedReader, VirtualBlockIo};
+pub use boot_menu::{BootMenuConfig, DefaultKind, BOOT_MENU_PATH, BOOT_MENU_SIZE};
+pub use boot_request::{BootRequest, BOOT_REQUEST_PATH, BOOT_REQUEST_SIZE};
 pub use chunk::{ChunkInfo, ChunkSet, MAX_CHUNKS};
 pub use error::IsoError;
-pub use iso9660_bridge::{ChunkedIso, IsoBlockIoAdapter};
-pub use manifest::{IsoManifest, MANIFEST_MAGIC, MAX_MANIFEST_SIZE};
+pub use exit_report::{
+    ExitReport, FailureReason, RetryCounts, EXIT_REPORT_ARCHIVE_PATH, EXIT_REPORT_PATH,
+    EXIT_REPORT_SIZE, MAX_REPORT_NAME_LEN, MAX_STATE_NAME_LEN,
+};
+pub use history::{
+    history_filename, DownloadRecord, Verification, HISTORY_EXT, HISTORY_RECORD_SIZE,
+    MAX_HISTORY_RECORDS,
+};
+pub use iso9660_bridge::{ChunkedIso, DiskPool, IsoBlockIoAdapter, MAX_POOL_DISKS};
+pub(crate) use manifest::crc32;
+pub use manifest::{
+    etag_hash, raw_manifest_lba, IsoManifest, MANIFEST_MAGIC, MAX_MANIFEST_SIZE,
+    RAW_MANIFEST_SECTORS, RAW_MANIFEST_SIZE,
+};
 pub use reader::{ChunkReader, IsoReadContext};
+pub use retention::{
+    plan_cleanup, CleanupPlan, RetentionPolicy, StoredIso, POLICY_PATH, POLICY_SIZE,
+};
+pub use sparse::{SkipList, SkippedRange, MAX_SKIPPED_RANGES};
 pub use storage::{IsoEntry, IsoStorageManager, PartitionRequest, MANIFEST_DIR, MAX_ISOS};
 pub use writer::{ChunkWriter, WriterState};
 
@@ -256,6 +256,8 @@ pub struct IsoReadContext {
     pub chunk_lbas: [(u64, u64); MAX_CHUNKS],
     /// Data size in each chunk
     pub chunk_sizes: [u64; MAX_CHUNKS],
+    /// Disk holding each chunk (see `ChunkInfo::disk_id`)
+    pub chunk_disks: [u32; MAX_CHUNKS],
     /// Number of valid chunks
     pub num_chunks: usize,
     /// Total ISO size
@@ -267,16 +269,19 @@ impl IsoReadContext {
     pub fn from_manifest(manifest: &IsoManifest) -> Self {
         let mut chunk_lbas = [(0u64, 0u64); MAX_CHUNKS];
         let mut chunk_sizes = [0u64; MAX_CHUNKS];
+        let mut chunk_disks = [0u32; MAX_CHUNKS];
 
         for i in 0..manifest.chunks.count {
             let chunk = &manifest.chunks.chunks[i];
             chunk_lbas[i] = (chunk.start_lba, chunk.end_lba);
             chunk_sizes[i] = chunk.data_size;
+            chunk_disks[i] = chunk.disk_id;
         }
 
         Self {
             chunk_lbas,
             chunk_sizes,
+            chunk_disks,
             num_chunks: manifest.chunks.count,
             total_size: manifest.total_size,
         }
@@ -286,16 +291,19 @@ impl IsoReadContext {
     pub fn from_reader(reader: &ChunkReader) -> Self {
         let mut chunk_lbas = [(0u64, 0u64); MAX_CHUNKS];
         let mut chunk_sizes = [0u64; MAX_CHUNKS];
+        let mut chunk_disks = [0u32; MAX_CHUNKS];
 
         for i in 0..reader.chunks.count {
             let chunk = &reader.chunks.chunks[i];
             chunk_lbas[i] = (chunk.start_lba, chunk.end_lba);
             chunk_sizes[i] = chunk.data_size;
+            chunk_disks[i] = chunk.disk_id;
         }
 
         Self {
             chunk_lbas,
             chunk_sizes,
+            chunk_disks,
             num_chunks: reader.chunks.count,
             total_size: reader.total_size,
         }
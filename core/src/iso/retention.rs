@@ -0,0 +1,271 @@
+//! ISO store retention policy
+//!
+//! Limits on what the store may hold: total size, ISO count, and whether
+//! unverified ISOs may be dropped without asking. They are checked before
+//! a download starts. When the new ISO would break a limit or not fit on
+//! disk, [`plan_cleanup`] picks ISOs to delete instead of letting the
+//! download fail for lack of space.
+//!
+//! # Policy File (`/.iso/POLICY.CFG`, 32 bytes, little endian)
+//!
+//! ```text
+//! 0x00  8  Magic "MXPOLCY\x01"
+//! 0x08  8  Max total ISO size in bytes (0 = no limit)
+//! 0x10  2  Max ISO count (0 = no limit)
+//! 0x12  1  Flags (bit 0: auto-delete oldest unverified)
+//! 0x13  9  Reserved (zero)
+//! 0x1C  4  CRC32 of bytes 0x00-0x1B
+//! ```
+
+use super::error::IsoError;
+use super::manifest::crc32;
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+/// Policy file path on the ESP
+pub const POLICY_PATH: &str = "/.iso/POLICY.CFG";
+
+/// Serialized policy size
+pub const POLICY_SIZE: usize = 32;
+
+const POLICY_MAGIC: [u8; 8] = *b"MXPOLCY\x01";
+
+const FLAG_AUTO_DELETE: u8 = 0x01;
+
+/// Limits for one ISO store
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RetentionPolicy {
+    /// Largest combined size of all ISOs in bytes (0 = no limit)
+    pub max_total_bytes: u64,
+    /// Most ISOs kept at once (0 = no limit)
+    pub max_count: usize,
+    /// Delete the oldest unverified ISOs without asking when space runs out
+    pub auto_delete_unverified: bool,
+}
+
+impl RetentionPolicy {
+    /// Serialize to a buffer of at least [`POLICY_SIZE`] bytes
+    pub fn serialize(&self, buffer: &mut [u8]) -> Result<usize, IsoError> {
+        if buffer.len() < POLICY_SIZE {
+            return Err(IsoError::IoError);
+        }
+        let buffer = &mut buffer[..POLICY_SIZE];
+        buffer.fill(0);
+
+        buffer[0..8].copy_from_slice(&POLICY_MAGIC);
+        buffer[0x08..0x10].copy_from_slice(&self.max_total_bytes.to_le_bytes());
+        let max_count = self.max_count.min(u16::MAX as usize) as u16;
+        buffer[0x10..0x12].copy_from_slice(&max_count.to_le_bytes());
+        if self.auto_delete_unverified {
+            buffer[0x12] |= FLAG_AUTO_DELETE;
+        }
+
+        let crc = crc32(&buffer[..0x1C]);
+        buffer[0x1C..0x20].copy_from_slice(&crc.to_le_bytes());
+
+        Ok(POLICY_SIZE)
+    }
+
+    /// Deserialize a policy written by [`serialize`](Self::serialize)
+    pub fn deserialize(buffer: &[u8]) -> Result<Self, IsoError> {
+        if buffer.len() < POLICY_SIZE || buffer[0..8] != POLICY_MAGIC {
+            return Err(IsoError::InvalidManifest);
+        }
+
+        let stored_crc =
+            u32::from_le_bytes([buffer[0x1C], buffer[0x1D], buffer[0x1E], buffer[0x1F]]);
+        if stored_crc != crc32(&buffer[..0x1C]) {
+            return Err(IsoError::DataCorruption);
+        }
+
+        let mut max_total = [0u8; 8];
+        max_total.copy_from_slice(&buffer[0x08..0x10]);
+
+        Ok(Self {
+            max_total_bytes: u64::from_le_bytes(max_total),
+            max_count: u16::from_le_bytes([buffer[0x10], buffer[0x11]]) as usize,
+            auto_delete_unverified: buffer[0x12] & FLAG_AUTO_DELETE != 0,
+        })
+    }
+
+    /// Whether the store may hold `count` ISOs of `total_bytes` combined
+    pub fn allows(&self, count: usize, total_bytes: u64) -> bool {
+        (self.max_count == 0 || count <= self.max_count)
+            && (self.max_total_bytes == 0 || total_bytes <= self.max_total_bytes)
+    }
+}
+
+/// A stored ISO as the cleanup planner sees it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StoredIso {
+    /// ISO size in bytes, counted against the size limit
+    pub size: u64,
+    /// Disk space its chunk partitions take up
+    pub footprint: u64,
+    pub verified: bool,
+    /// Larger is newer; ISOs of unknown age should use 0
+    pub age: u32,
+}
+
+/// ISOs to delete so a download can go ahead
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CleanupPlan {
+    /// Indices into the stored list that may go without asking
+    pub auto: Vec<usize>,
+    /// Indices into the stored list to propose to the user
+    pub proposed: Vec<usize>,
+    /// Whether the download fits once all of the above are gone
+    pub fits: bool,
+}
+
+impl CleanupPlan {
+    /// Nothing to delete and nothing in the way
+    pub fn is_clear(&self) -> bool {
+        self.fits && self.auto.is_empty() && self.proposed.is_empty()
+    }
+}
+
+/// Pick ISOs to delete so one of `size` bytes taking `footprint` bytes of
+/// disk fits both the policy and `free_bytes` of free disk space.
+///
+/// Unverified ISOs go first, then verified ones, oldest first within
+/// each group. If the download can't fit even with the whole store gone,
+/// nothing is picked.
+pub fn plan_cleanup(
+    policy: &RetentionPolicy,
+    stored: &[StoredIso],
+    size: u64,
+    footprint: u64,
+    free_bytes: u64,
+) -> CleanupPlan {
+    let mut count = stored.len() + 1;
+    let mut total = stored.iter().map(|iso| iso.size).sum::<u64>() + size;
+    let mut free = free_bytes;
+    let fits =
+        |count: usize, total: u64, free: u64| policy.allows(count, total) && footprint <= free;
+
+    let mut plan = CleanupPlan::default();
+    let all_freed = free_bytes + stored.iter().map(|iso| iso.footprint).sum::<u64>();
+    if !fits(1, size, all_freed) {
+        return plan;
+    }
+
+    let mut order: Vec<usize> = (0..stored.len()).collect();
+    order.sort_by_key(|&i| (stored[i].verified, stored[i].age));
+
+    for i in order {
+        if fits(count, total, free) {
+            break;
+        }
+        let iso = &stored[i];
+        if !iso.verified && policy.auto_delete_unverified {
+            plan.auto.push(i);
+        } else {
+            plan.proposed.push(i);
+        }
+        count -= 1;
+        total -= iso.size;
+        free += iso.footprint;
+    }
+    plan.fits = fits(count, total, free);
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GB: u64 = 1024 * 1024 * 1024;
+
+    fn iso(size_gb: u64, verified: bool, age: u32) -> StoredIso {
+        StoredIso {
+            size: size_gb * GB,
+            footprint: size_gb * GB,
+            verified,
+            age,
+        }
+    }
+
+    #[test]
+    fn test_policy_roundtrip() {
+        let policy = RetentionPolicy {
+            max_total_bytes: 64 * GB,
+            max_count: 4,
+            auto_delete_unverified: true,
+        };
+        let mut buffer = [0u8; POLICY_SIZE];
+        assert_eq!(policy.serialize(&mut buffer).unwrap(), POLICY_SIZE);
+        assert_eq!(RetentionPolicy::deserialize(&buffer).unwrap(), policy);
+
+        buffer[0x10] ^= 1;
+        assert_eq!(
+            RetentionPolicy::deserialize(&buffer),
+            Err(IsoError::DataCorruption)
+        );
+    }
+
+    #[test]
+    fn test_plan_within_limits() {
+        let policy = RetentionPolicy::default();
+        let stored = [iso(4, true, 1), iso(2, false, 2)];
+        assert!(plan_cleanup(&policy, &stored, 3 * GB, 3 * GB, 10 * GB).is_clear());
+    }
+
+    #[test]
+    fn test_plan_unverified_oldest_first() {
+        let policy = RetentionPolicy {
+            max_count: 3,
+            ..Default::default()
+        };
+        // Newest unverified, oldest verified, oldest unverified
+        let stored = [iso(1, false, 3), iso(1, true, 1), iso(1, false, 2)];
+
+        let plan = plan_cleanup(&policy, &stored, GB, GB, 100 * GB);
+        assert_eq!(plan.proposed, [2]);
+        assert!(plan.auto.is_empty());
+        assert!(plan.fits);
+
+        // Short of disk space for 3GB: both unverified, then the verified one
+        let plan = plan_cleanup(&RetentionPolicy::default(), &stored, 3 * GB, 3 * GB, 0);
+        assert_eq!(plan.proposed, [2, 0, 1]);
+        assert!(plan.fits);
+    }
+
+    #[test]
+    fn test_plan_auto_delete() {
+        let policy = RetentionPolicy {
+            max_total_bytes: 10 * GB,
+            auto_delete_unverified: true,
+            ..Default::default()
+        };
+        let stored = [iso(4, true, 1), iso(4, false, 2)];
+
+        // 4 + 4 + 4 > 10: the unverified ISO goes on its own
+        let plan = plan_cleanup(&policy, &stored, 4 * GB, 4 * GB, 100 * GB);
+        assert_eq!(plan.auto, [1]);
+        assert!(plan.proposed.is_empty());
+
+        // 4 + 4 + 8 > 10 even then: the verified one is only proposed
+        let plan = plan_cleanup(&policy, &stored, 8 * GB, 8 * GB, 100 * GB);
+        assert_eq!(plan.auto, [1]);
+        assert_eq!(plan.proposed, [0]);
+        assert!(plan.fits);
+    }
+
+    #[test]
+    fn test_plan_cannot_fit() {
+        let policy = RetentionPolicy {
+            max_total_bytes: 4 * GB,
+            ..Default::default()
+        };
+        let stored = [iso(2, false, 1)];
+        let plan = plan_cleanup(&policy, &stored, 5 * GB, 5 * GB, 100 * GB);
+        assert!(!plan.fits);
+        assert!(plan.proposed.is_empty());
+
+        // Not enough disk even with the store emptied
+        let plan = plan_cleanup(&RetentionPolicy::default(), &stored, 5 * GB, 5 * GB, GB);
+        assert!(!plan.fits);
+    }
+}
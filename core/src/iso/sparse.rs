@@ -0,0 +1,162 @@
+//! Zero runs a download skipped
+//!
+//! A download onto a disk that already reads as zero (freshly trimmed, or a
+//! sparse VM image) doesn't need to write the image's all-zero blocks. The
+//! sectors it left alone are recorded here, relative to the start of the
+//! image, so a later verify knows they were never written and can tell a
+//! disk that wasn't zero after all from one that corrupted the data.
+
+/// Most skipped ranges a manifest records; a writer whose list is full
+/// writes further zero blocks normally
+pub const MAX_SKIPPED_RANGES: usize = 8;
+
+/// Bytes of one range in the manifest: start (u64) and length (u32)
+pub const SKIPPED_RANGE_SIZE: usize = 12;
+
+/// Sectors of the image left unwritten
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SkippedRange {
+    /// First sector, counted from the start of the image
+    pub sector: u64,
+    pub sectors: u32,
+}
+
+impl SkippedRange {
+    /// One past the last sector
+    pub const fn end(&self) -> u64 {
+        self.sector + self.sectors as u64
+    }
+}
+
+/// Skipped ranges in image order, adjacent ones merged
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SkipList {
+    ranges: [SkippedRange; MAX_SKIPPED_RANGES],
+    count: usize,
+}
+
+impl SkipList {
+    pub const fn new() -> Self {
+        Self {
+            ranges: [SkippedRange {
+                sector: 0,
+                sectors: 0,
+            }; MAX_SKIPPED_RANGES],
+            count: 0,
+        }
+    }
+
+    /// Record `sectors` skipped at `sector`, which must lie after every
+    /// range so far. Extends the last range when it ends there; `false`
+    /// when a new range is needed and the list is full.
+    pub fn push(&mut self, sector: u64, sectors: u32) -> bool {
+        if let Some(last) = self.ranges[..self.count].last_mut() {
+            if last.end() == sector {
+                if let Some(total) = last.sectors.checked_add(sectors) {
+                    last.sectors = total;
+                    return true;
+                }
+            }
+        }
+        if self.count == MAX_SKIPPED_RANGES {
+            return false;
+        }
+        self.ranges[self.count] = SkippedRange { sector, sectors };
+        self.count += 1;
+        true
+    }
+
+    pub fn as_slice(&self) -> &[SkippedRange] {
+        &self.ranges[..self.count]
+    }
+
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.count = 0;
+    }
+
+    /// Total sectors skipped
+    pub fn sectors(&self) -> u64 {
+        self.as_slice().iter().map(|r| r.sectors as u64).sum()
+    }
+
+    /// Whether any byte of `data`, read from byte `offset` of the image,
+    /// falls in a skipped range and isn't zero
+    pub fn nonzero_in(&self, offset: u64, data: &[u8]) -> bool {
+        let end = offset + data.len() as u64;
+        self.as_slice().iter().any(|range| {
+            let from = (range.sector * 512).max(offset);
+            let to = (range.end() * 512).min(end);
+            from < to
+                && data[(from - offset) as usize..(to - offset) as usize]
+                    .iter()
+                    .any(|&b| b != 0)
+        })
+    }
+}
+
+impl Default for SkipList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_merges_adjacent() {
+        let mut list = SkipList::new();
+        assert!(list.push(128, 128));
+        assert!(list.push(256, 64));
+        assert!(list.push(1024, 128));
+        assert_eq!(
+            list.as_slice(),
+            &[
+                SkippedRange {
+                    sector: 128,
+                    sectors: 192
+                },
+                SkippedRange {
+                    sector: 1024,
+                    sectors: 128
+                },
+            ]
+        );
+        assert_eq!(list.sectors(), 320);
+
+        for i in 2..MAX_SKIPPED_RANGES as u64 {
+            assert!(list.push(i * 10_000, 1));
+        }
+        // Full: a new range is refused, extending the last one isn't
+        assert!(!list.push(1_000_000, 1));
+        assert!(list.push((MAX_SKIPPED_RANGES as u64 - 1) * 10_000 + 1, 1));
+        assert_eq!(list.len(), MAX_SKIPPED_RANGES);
+    }
+
+    #[test]
+    fn test_nonzero_in() {
+        let mut list = SkipList::new();
+        list.push(2, 2);
+
+        let mut data = [0u8; 4096];
+        assert!(!list.nonzero_in(0, &data));
+        // Data outside the range doesn't count
+        data[1023] = 1;
+        data[2048] = 1;
+        assert!(!list.nonzero_in(0, &data));
+        data[1024] = 1;
+        assert!(list.nonzero_in(0, &data));
+        // The same byte read as part of a later buffer
+        assert!(list.nonzero_in(1024, &data[1024..]));
+        assert!(!list.nonzero_in(2048, &data));
+    }
+}
@@ -26,6 +26,7 @@ use super::chunk::{ChunkSet, MAX_CHUNKS};
 use super::error::IsoError;
 use super::manifest::IsoManifest;
 use super::reader::{ChunkReader, IsoReadContext};
+use super::sparse::SkipList;
 use super::writer::ChunkWriter;
 use super::{DEFAULT_CHUNK_SIZE, FAT32_MAX_FILE_SIZE};
 
@@ -55,6 +56,9 @@ impl IsoEntry {
                 sha256: [0u8; 32],
                 chunks: ChunkSet::new(),
                 flags: 0,
+                last_modified: 0,
+                etag_hash: 0,
+                skipped: SkipList::new(),
             },
             valid: false,
         }
@@ -1,17 +1,21 @@
 //! Morpheus Core Library
 //!
 //! Low-level operations for disk, filesystem, networking, and distro management.
-//! Designed to be no_std compatible.
+//! Designed to be no_std compatible. The `std` feature builds it for the
+//! host instead, which is how its unit tests and doctests run.
 //!
 //! # Modules
 //!
 //! - [`disk`] - GPT disk operations and partition management
 //! - [`fs`] - FAT32 filesystem operations
+//! - [`hash`] - CRC-32 and SHA-256, with CPU fast paths
 //! - [`iso`] - ISO storage and chunk management
 //! - [`net`] - Network initialization orchestration
 //! - [`logger`] - Logging infrastructure
+//! - [`time`] - Wall clock for filesystem timestamps
+//! - `testing` - In-memory block devices for host tests (`std` only)
 
-#![no_std]
+#![cfg_attr(not(feature = "std"), no_std)]
 #![allow(dead_code)]
 #![allow(unused_variables)]
 #![allow(clippy::needless_range_loop)]
@@ -22,7 +26,11 @@
 
 pub mod disk;
 pub mod fs;
+pub mod hash;
 pub mod iso;
 pub mod logger;
 pub mod net;
+#[cfg(feature = "std")]
+pub mod testing;
+pub mod time;
 pub mod uefi_alloc;
@@ -0,0 +1,147 @@
+//! Block devices for host tests.
+//!
+//! Only built with `std`. [`SparseDisk`] stores the sectors that were
+//! written and reads the rest as zeros, so multi-terabyte disks and
+//! full-size FAT32 partitions cost only what the code under test writes.
+//!
+//! ```
+//! use morpheus_core::disk::gpt_ops::create_gpt;
+//! use morpheus_core::testing::SparseDisk;
+//!
+//! let mut disk = SparseDisk::new(1 << 20);
+//! create_gpt(&mut disk, 1 << 20).unwrap();
+//! assert!(disk.is_written(1)); // primary header
+//! assert!(disk.is_written((1 << 20) - 1)); // backup header
+//! ```
+
+extern crate alloc;
+
+use alloc::collections::BTreeMap;
+use core::fmt;
+use gpt_disk_io::BlockIo;
+use gpt_disk_types::{BlockSize, Lba};
+
+/// Sector size of [`SparseDisk`].
+pub const SECTOR_SIZE: usize = 512;
+
+/// Access past the end of a [`SparseDisk`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutOfRange(pub u64);
+
+impl fmt::Display for OutOfRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "sector {} out of range", self.0)
+    }
+}
+
+/// In-memory disk of 512-byte sectors; unwritten sectors read as zero.
+///
+/// `&mut SparseDisk` is a [`BlockIo`] too, for functions that take the
+/// device by value.
+#[derive(Debug, Clone, Default)]
+pub struct SparseDisk {
+    sectors: BTreeMap<u64, [u8; SECTOR_SIZE]>,
+    num_blocks: u64,
+    reads: usize,
+    writes: usize,
+}
+
+impl SparseDisk {
+    /// Blank disk of `num_blocks` sectors.
+    pub fn new(num_blocks: u64) -> Self {
+        Self {
+            sectors: BTreeMap::new(),
+            num_blocks,
+            reads: 0,
+            writes: 0,
+        }
+    }
+
+    /// Whether `lba` was ever written.
+    pub fn is_written(&self, lba: u64) -> bool {
+        self.sectors.contains_key(&lba)
+    }
+
+    /// Contents of `lba`.
+    pub fn sector(&self, lba: u64) -> [u8; SECTOR_SIZE] {
+        self.sectors.get(&lba).copied().unwrap_or([0; SECTOR_SIZE])
+    }
+
+    /// Sector reads so far.
+    pub fn reads(&self) -> usize {
+        self.reads
+    }
+
+    /// Sector writes so far.
+    pub fn writes(&self) -> usize {
+        self.writes
+    }
+}
+
+impl BlockIo for SparseDisk {
+    type Error = OutOfRange;
+
+    fn block_size(&self) -> BlockSize {
+        BlockSize::BS_512
+    }
+
+    fn num_blocks(&mut self) -> Result<u64, Self::Error> {
+        Ok(self.num_blocks)
+    }
+
+    fn read_blocks(&mut self, lba: Lba, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        for (i, chunk) in buffer.chunks_mut(SECTOR_SIZE).enumerate() {
+            let sector = lba.0 + i as u64;
+            if sector >= self.num_blocks {
+                return Err(OutOfRange(sector));
+            }
+            match self.sectors.get(&sector) {
+                Some(data) => chunk.copy_from_slice(&data[..chunk.len()]),
+                None => chunk.fill(0),
+            }
+            self.reads += 1;
+        }
+        Ok(())
+    }
+
+    fn write_blocks(&mut self, lba: Lba, buffer: &[u8]) -> Result<(), Self::Error> {
+        for (i, chunk) in buffer.chunks(SECTOR_SIZE).enumerate() {
+            let sector = lba.0 + i as u64;
+            if sector >= self.num_blocks {
+                return Err(OutOfRange(sector));
+            }
+            let data = self.sectors.entry(sector).or_insert([0; SECTOR_SIZE]);
+            data[..chunk.len()].copy_from_slice(chunk);
+            self.writes += 1;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl BlockIo for &mut SparseDisk {
+    type Error = OutOfRange;
+
+    fn block_size(&self) -> BlockSize {
+        (**self).block_size()
+    }
+
+    fn num_blocks(&mut self) -> Result<u64, Self::Error> {
+        (**self).num_blocks()
+    }
+
+    fn read_blocks(&mut self, lba: Lba, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        (**self).read_blocks(lba, buffer)
+    }
+
+    fn write_blocks(&mut self, lba: Lba, buffer: &[u8]) -> Result<(), Self::Error> {
+        (**self).write_blocks(lba, buffer)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        (**self).flush()
+    }
+}
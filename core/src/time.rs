@@ -0,0 +1,133 @@
+// Wall-clock time for filesystem timestamps
+//
+// Core has no clock of its own. Whoever knows the date installs one with
+// `set_clock`: the bootloader reads the UEFI RTC before ExitBootServices,
+// the network stack afterwards derives the time from what servers report.
+// Without a clock `now()` is None and new files get zeroed timestamps.
+
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+/// Calendar date and time: local time from the firmware RTC, UTC from
+/// anything network-derived (there is no time zone to convert with).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DateTime {
+    pub year: u16,
+    /// 1-12
+    pub month: u8,
+    /// 1-31
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl DateTime {
+    /// Date and time `secs` seconds after 1970-01-01 00:00:00 UTC.
+    ///
+    /// ```
+    /// use morpheus_core::time::DateTime;
+    ///
+    /// let t = DateTime::from_unix(1_709_208_000); // 2024-02-29 12:00:00
+    /// assert_eq!((t.year, t.month, t.day, t.hour), (2024, 2, 29, 12));
+    /// ```
+    pub fn from_unix(secs: u64) -> Self {
+        let days = (secs / 86_400) as i64;
+        let rem = secs % 86_400;
+
+        // civil-from-days (Howard Hinnant)
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z - era * 146_097;
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+        let year = yoe + era * 400 + i64::from(month <= 2);
+
+        Self {
+            year: year as u16,
+            month,
+            day,
+            hour: (rem / 3600) as u8,
+            minute: (rem / 60 % 60) as u8,
+            second: (rem % 60) as u8,
+        }
+    }
+
+    /// Whether every field is in range (firmware RTCs return garbage
+    /// when their battery is flat).
+    pub fn is_valid(&self) -> bool {
+        (1..=12).contains(&self.month)
+            && (1..=31).contains(&self.day)
+            && self.hour < 24
+            && self.minute < 60
+            && self.second < 60
+    }
+}
+
+/// Source of the current time; None when it can't tell.
+pub type Clock = fn() -> Option<DateTime>;
+
+/// The installed `Clock`, null if none.
+static CLOCK: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+
+/// Install `clock` as the source of [`now`], or remove it with None.
+pub fn set_clock(clock: Option<Clock>) {
+    let ptr = clock.map_or(core::ptr::null_mut(), |f| f as *mut ());
+    CLOCK.store(ptr, Ordering::Release);
+}
+
+/// Current time from the installed clock, if there is one and it gives a
+/// valid date.
+pub fn now() -> Option<DateTime> {
+    let ptr = CLOCK.load(Ordering::Acquire);
+    if ptr.is_null() {
+        return None;
+    }
+    // Safety: only `set_clock` stores non-null values, all `Clock`s
+    let clock: Clock = unsafe { core::mem::transmute::<*mut (), Clock>(ptr) };
+    clock().filter(DateTime::is_valid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_unix() {
+        let epoch = DateTime::from_unix(0);
+        assert_eq!((epoch.year, epoch.month, epoch.day), (1970, 1, 1));
+
+        let t = DateTime::from_unix(1_709_208_000 + 3 * 3600 + 25 * 60 + 7);
+        assert_eq!(
+            t,
+            DateTime {
+                year: 2024,
+                month: 2,
+                day: 29,
+                hour: 15,
+                minute: 25,
+                second: 7,
+            }
+        );
+        // Last second of 1999, first of 2000
+        assert_eq!(DateTime::from_unix(946_684_799).year, 1999);
+        let y2k = DateTime::from_unix(946_684_800);
+        assert_eq!((y2k.year, y2k.month, y2k.day, y2k.hour), (2000, 1, 1, 0));
+    }
+
+    #[test]
+    fn test_is_valid() {
+        assert!(DateTime::from_unix(0).is_valid());
+        let flat_battery = DateTime {
+            year: 2000,
+            month: 0,
+            day: 0,
+            hour: 0,
+            minute: 0,
+            second: 0,
+        };
+        assert!(!flat_battery.is_valid());
+    }
+}
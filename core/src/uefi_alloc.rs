@@ -3,6 +3,17 @@
 //! Provides utilities to allocate and free memory using UEFI BootServices
 //! allocate_pages. This is the correct way to allocate memory pre-EBS when
 //! the global heap allocator is not initialized.
+//!
+//! Code that runs on both sides of ExitBootServices takes an
+//! [`Allocator`] instead: [`UefiPages`] before EBS, [`GlobalHeap`] after.
+//! [`TempBuffer`] frees through the allocator it came from on drop.
+//!
+//! Host builds (`std`) get [`host`], the system allocator behind the same
+//! signatures, so the pre-EBS code paths can be tested off-firmware.
+
+extern crate alloc;
+
+use core::ptr::NonNull;
 
 /// UEFI BootServices allocate_pages signature
 pub type AllocatePages = extern "efiapi" fn(
@@ -24,6 +35,12 @@ pub const EFI_LOADER_DATA: usize = 2;
 /// EFI_ALLOCATE_ANY_PAGES - allocate from anywhere in memory
 pub const EFI_ALLOCATE_ANY_PAGES: usize = 0;
 
+/// EFI_INVALID_PARAMETER
+pub const EFI_INVALID_PARAMETER: usize = (1 << 63) | 2;
+
+/// EFI_OUT_OF_RESOURCES
+pub const EFI_OUT_OF_RESOURCES: usize = (1 << 63) | 9;
+
 /// Allocate memory pages from UEFI
 ///
 /// # Safety
@@ -61,6 +78,120 @@ pub fn bytes_to_pages(bytes: usize) -> usize {
     (bytes + 4095) / 4096
 }
 
+/// Alignment of every [`Allocator`] allocation (one page).
+pub const ALLOC_ALIGN: usize = 4096;
+
+/// Source of temporary buffers.
+///
+/// Object safe: callers hold a `&dyn Allocator` picked once for the
+/// session, not a pair of optional function pointers per call.
+pub trait Allocator {
+    /// Allocate `size` zeroed bytes aligned to [`ALLOC_ALIGN`]; None if
+    /// out of memory or `size` is 0.
+    fn allocate(&self, size: usize) -> Option<NonNull<u8>>;
+
+    /// Free an allocation.
+    ///
+    /// # Safety
+    /// `ptr` must come from `allocate` on this allocator with the same
+    /// `size`, and not be used afterwards.
+    unsafe fn free(&self, ptr: NonNull<u8>, size: usize);
+}
+
+/// Pre-EBS allocator: BootServices allocate_pages/free_pages.
+#[derive(Clone, Copy)]
+pub struct UefiPages {
+    allocate: AllocatePages,
+    free: FreePages,
+}
+
+impl UefiPages {
+    /// Wrap BootServices' allocate_pages and free_pages.
+    ///
+    /// # Safety
+    /// Both must be valid until the last allocation is freed, which has
+    /// to happen before ExitBootServices.
+    pub unsafe fn new(allocate: AllocatePages, free: FreePages) -> Self {
+        Self { allocate, free }
+    }
+}
+
+impl Allocator for UefiPages {
+    fn allocate(&self, size: usize) -> Option<NonNull<u8>> {
+        if size == 0 {
+            return None;
+        }
+        let addr = unsafe { allocate_pages(self.allocate, bytes_to_pages(size)) }.ok()?;
+        let ptr = NonNull::new(addr as *mut u8)?;
+        // Firmware pages aren't zeroed
+        unsafe { ptr.as_ptr().write_bytes(0, size) };
+        Some(ptr)
+    }
+
+    unsafe fn free(&self, ptr: NonNull<u8>, size: usize) {
+        let _ = free_pages(self.free, ptr.as_ptr() as u64, bytes_to_pages(size));
+    }
+}
+
+/// Post-EBS allocator: the global heap (the system allocator on `std`).
+#[derive(Clone, Copy, Default)]
+pub struct GlobalHeap;
+
+/// The [`GlobalHeap`] allocator.
+pub static GLOBAL_HEAP: GlobalHeap = GlobalHeap;
+
+impl GlobalHeap {
+    fn layout(size: usize) -> Option<alloc::alloc::Layout> {
+        alloc::alloc::Layout::from_size_align(size, ALLOC_ALIGN).ok()
+    }
+}
+
+impl Allocator for GlobalHeap {
+    fn allocate(&self, size: usize) -> Option<NonNull<u8>> {
+        let layout = Self::layout(size).filter(|_| size > 0)?;
+        NonNull::new(unsafe { alloc::alloc::alloc_zeroed(layout) })
+    }
+
+    unsafe fn free(&self, ptr: NonNull<u8>, size: usize) {
+        if let Some(layout) = Self::layout(size) {
+            alloc::alloc::dealloc(ptr.as_ptr(), layout);
+        }
+    }
+}
+
+/// Zeroed buffer from an [`Allocator`], freed on drop.
+pub struct TempBuffer<'a> {
+    allocator: &'a dyn Allocator,
+    ptr: NonNull<u8>,
+    size: usize,
+}
+
+impl<'a> TempBuffer<'a> {
+    /// Allocate `size` zeroed bytes from `allocator`.
+    pub fn new(allocator: &'a dyn Allocator, size: usize) -> Option<Self> {
+        let ptr = allocator.allocate(size)?;
+        Some(Self {
+            allocator,
+            ptr,
+            size,
+        })
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        unsafe { core::slice::from_raw_parts(self.ptr.as_ptr(), self.size) }
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { core::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.size) }
+    }
+}
+
+impl Drop for TempBuffer<'_> {
+    fn drop(&mut self) {
+        unsafe { self.allocator.free(self.ptr, self.size) };
+    }
+}
+
 /// A scoped UEFI-allocated buffer that automatically frees on drop
 #[allow(dead_code)]
 pub struct UefiBuffer {
@@ -114,3 +245,85 @@ impl Drop for UefiBuffer {
         let _ = (self.free_pages)(self.addr, self.pages);
     }
 }
+
+/// System allocator standing in for BootServices on the host.
+///
+/// Wrap them in [`UefiPages`] to run the pre-EBS paths on the host.
+/// Pages are zeroed and 4 KiB aligned, like firmware pages.
+#[cfg(feature = "std")]
+pub mod host {
+    use super::{EFI_INVALID_PARAMETER, EFI_OUT_OF_RESOURCES, EFI_SUCCESS};
+    use std::alloc::{alloc_zeroed, dealloc, Layout};
+
+    fn layout(pages: usize) -> Option<Layout> {
+        let size = pages.checked_mul(4096).filter(|&size| size > 0)?;
+        Layout::from_size_align(size, 4096).ok()
+    }
+
+    /// Host [`AllocatePages`](super::AllocatePages). The allocation type
+    /// and memory type are ignored.
+    // Signature fixed by AllocatePages, which firmware pointers also fit
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
+    pub extern "efiapi" fn allocate_pages(
+        _allocate_type: usize,
+        _memory_type: usize,
+        pages: usize,
+        address: *mut u64,
+    ) -> usize {
+        let Some(layout) = layout(pages) else {
+            return EFI_INVALID_PARAMETER;
+        };
+        let ptr = unsafe { alloc_zeroed(layout) };
+        if ptr.is_null() {
+            return EFI_OUT_OF_RESOURCES;
+        }
+        unsafe { *address = ptr as u64 };
+        EFI_SUCCESS
+    }
+
+    /// Host [`FreePages`](super::FreePages).
+    pub extern "efiapi" fn free_pages(memory: u64, pages: usize) -> usize {
+        match layout(pages) {
+            Some(layout) if memory != 0 => {
+                unsafe { dealloc(memory as *mut u8, layout) };
+                EFI_SUCCESS
+            }
+            _ => EFI_INVALID_PARAMETER,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_pages() {
+        let mut buffer =
+            unsafe { UefiBuffer::new(host::allocate_pages, host::free_pages, 2) }.unwrap();
+        assert_eq!(buffer.as_slice().len(), 8192);
+        assert!(buffer.as_slice().iter().all(|&b| b == 0));
+        buffer.as_mut_slice()[8191] = 0xAA;
+
+        let addr = unsafe { allocate_pages(host::allocate_pages, 1) }.unwrap();
+        assert_eq!(addr % 4096, 0);
+        assert_eq!(unsafe { free_pages(host::free_pages, addr, 1) }, Ok(()));
+        assert_eq!(
+            unsafe { allocate_pages(host::allocate_pages, 0) },
+            Err(EFI_INVALID_PARAMETER)
+        );
+    }
+
+    #[test]
+    fn test_allocators() {
+        let pages = unsafe { UefiPages::new(host::allocate_pages, host::free_pages) };
+        for allocator in [&pages as &dyn Allocator, &GLOBAL_HEAP] {
+            let mut buffer = TempBuffer::new(allocator, 5000).unwrap();
+            assert_eq!(buffer.as_slice().len(), 5000);
+            assert!(buffer.as_slice().iter().all(|&b| b == 0));
+            assert_eq!(buffer.as_slice().as_ptr() as usize % ALLOC_ALIGN, 0);
+            buffer.as_mut_slice()[4999] = 1;
+            assert!(TempBuffer::new(allocator, 0).is_none());
+        }
+    }
+}
@@ -0,0 +1,222 @@
+// GPT header (LBA 1, and its backup on the last LBA)
+
+use super::{crc32, GptError, ENTRY_SIZE, SECTOR_SIZE};
+
+const SIGNATURE: &[u8; 8] = b"EFI PART";
+const REVISION: u32 = 0x00010000;
+/// Bytes of the header the CRC covers (UEFI 2.x)
+const HEADER_SIZE: u32 = 92;
+
+/// Largest partition array the engine accepts (1 MiB, 8192 entries)
+const MAX_ARRAY_BYTES: u64 = 1024 * 1024;
+
+/// The fields of a GPT header that mean anything; the CRCs are recomputed
+/// on every write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Header {
+    /// LBA of this header
+    pub my_lba: u64,
+    /// LBA of the other copy
+    pub alternate_lba: u64,
+    pub first_usable_lba: u64,
+    pub last_usable_lba: u64,
+    /// As stored on disk (mixed-endian)
+    pub disk_guid: [u8; 16],
+    /// First LBA of this copy's partition array
+    pub entry_lba: u64,
+    pub num_entries: u32,
+    pub entry_size: u32,
+    pub array_crc32: u32,
+    /// Bytes per LBA on the disk it is for (not stored in the header)
+    pub block_size: u32,
+}
+
+impl Header {
+    /// Parse and validate a header sector (the start of its block) on a
+    /// disk of `block_size`-byte blocks: signature, CRC, entry size and a
+    /// usable range that stays clear of both copies.
+    pub fn parse(sector: &[u8; SECTOR_SIZE], block_size: u32) -> Result<Self, GptError> {
+        let u32_at = |at: usize| u32::from_le_bytes(sector[at..at + 4].try_into().unwrap());
+        let u64_at = |at: usize| u64::from_le_bytes(sector[at..at + 8].try_into().unwrap());
+
+        if &sector[0..8] != SIGNATURE {
+            return Err(GptError::InvalidHeader);
+        }
+        let header_size = u32_at(12);
+        if !(HEADER_SIZE..=SECTOR_SIZE as u32).contains(&header_size) {
+            return Err(GptError::InvalidHeader);
+        }
+        let mut crc_copy = [0u8; SECTOR_SIZE];
+        crc_copy[..header_size as usize].copy_from_slice(&sector[..header_size as usize]);
+        crc_copy[16..20].fill(0);
+        if crc32(&crc_copy[..header_size as usize]) != u32_at(16) {
+            return Err(GptError::InvalidHeader);
+        }
+
+        let header = Self {
+            my_lba: u64_at(24),
+            alternate_lba: u64_at(32),
+            first_usable_lba: u64_at(40),
+            last_usable_lba: u64_at(48),
+            disk_guid: sector[56..72].try_into().unwrap(),
+            entry_lba: u64_at(72),
+            num_entries: u32_at(80),
+            entry_size: u32_at(84),
+            array_crc32: u32_at(88),
+            block_size,
+        };
+        header.validate()?;
+        Ok(header)
+    }
+
+    /// The header as a sector, with its CRC.
+    pub fn to_sector(&self) -> [u8; SECTOR_SIZE] {
+        let mut sector = [0u8; SECTOR_SIZE];
+        sector[0..8].copy_from_slice(SIGNATURE);
+        sector[8..12].copy_from_slice(&REVISION.to_le_bytes());
+        sector[12..16].copy_from_slice(&HEADER_SIZE.to_le_bytes());
+        sector[24..32].copy_from_slice(&self.my_lba.to_le_bytes());
+        sector[32..40].copy_from_slice(&self.alternate_lba.to_le_bytes());
+        sector[40..48].copy_from_slice(&self.first_usable_lba.to_le_bytes());
+        sector[48..56].copy_from_slice(&self.last_usable_lba.to_le_bytes());
+        sector[56..72].copy_from_slice(&self.disk_guid);
+        sector[72..80].copy_from_slice(&self.entry_lba.to_le_bytes());
+        sector[80..84].copy_from_slice(&self.num_entries.to_le_bytes());
+        sector[84..88].copy_from_slice(&self.entry_size.to_le_bytes());
+        sector[88..92].copy_from_slice(&self.array_crc32.to_le_bytes());
+        let crc = crc32(&sector[..HEADER_SIZE as usize]);
+        sector[16..20].copy_from_slice(&crc.to_le_bytes());
+        sector
+    }
+
+    /// Bytes of the partition array the array CRC covers.
+    pub fn array_bytes(&self) -> usize {
+        self.num_entries as usize * self.entry_size as usize
+    }
+
+    /// Blocks the partition array takes up.
+    pub fn array_blocks(&self) -> u64 {
+        (self.array_bytes() as u64).div_ceil(self.block_size as u64)
+    }
+
+    /// The header of the other copy: LBAs swapped, its array just below
+    /// the backup header (or at LBA 2 for the primary).
+    pub fn other_copy(&self) -> Self {
+        let entry_lba = if self.alternate_lba > self.my_lba {
+            self.alternate_lba - self.array_blocks()
+        } else {
+            2
+        };
+        Self {
+            my_lba: self.alternate_lba,
+            alternate_lba: self.my_lba,
+            entry_lba,
+            ..*self
+        }
+    }
+
+    /// Reject values that would send reads or writes somewhere they
+    /// mustn't go: odd entry sizes, huge arrays, and usable ranges that run
+    /// into either copy of the table.
+    fn validate(&self) -> Result<(), GptError> {
+        let entry_size = self.entry_size as usize;
+        if entry_size < ENTRY_SIZE || !entry_size.is_multiple_of(8) {
+            return Err(GptError::InvalidHeader);
+        }
+        if self.array_bytes() as u64 > MAX_ARRAY_BYTES || self.num_entries == 0 {
+            return Err(GptError::InvalidHeader);
+        }
+
+        let primary = self.my_lba.min(self.alternate_lba);
+        let backup = self.my_lba.max(self.alternate_lba);
+        let backup_array = backup
+            .checked_sub(self.array_blocks())
+            .ok_or(GptError::InvalidHeader)?;
+        if primary == backup
+            || self.first_usable_lba <= primary
+            || self.first_usable_lba > self.last_usable_lba
+            || self.last_usable_lba >= backup_array
+        {
+            return Err(GptError::InvalidHeader);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header() -> Header {
+        Header {
+            my_lba: 1,
+            alternate_lba: 99_999,
+            first_usable_lba: 34,
+            last_usable_lba: 99_966,
+            disk_guid: [7; 16],
+            entry_lba: 2,
+            num_entries: 128,
+            entry_size: 128,
+            array_crc32: 0x1234,
+            block_size: 512,
+        }
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let sector = header().to_sector();
+        assert_eq!(Header::parse(&sector, 512), Ok(header()));
+
+        let backup = header().other_copy();
+        assert_eq!(
+            (backup.my_lba, backup.alternate_lba, backup.entry_lba),
+            (99_999, 1, 99_967)
+        );
+        assert_eq!(Header::parse(&backup.to_sector(), 512), Ok(backup));
+        assert_eq!(backup.other_copy(), header());
+    }
+
+    #[test]
+    fn test_4k_blocks() {
+        // 128 entries fill 4 blocks of 4096 bytes
+        let header = Header {
+            first_usable_lba: 6,
+            last_usable_lba: 99_994,
+            block_size: 4096,
+            ..header()
+        };
+        assert_eq!(header.array_blocks(), 4);
+        assert_eq!(header.other_copy().entry_lba, 99_995);
+        assert_eq!(Header::parse(&header.to_sector(), 4096), Ok(header));
+        // On 512-byte sectors that range runs into the backup array
+        assert_eq!(
+            Header::parse(&header.to_sector(), 512),
+            Err(GptError::InvalidHeader)
+        );
+    }
+
+    #[test]
+    fn test_rejects_bad_headers() {
+        let mut corrupt = header().to_sector();
+        corrupt[40] ^= 1;
+        assert_eq!(Header::parse(&corrupt, 512), Err(GptError::InvalidHeader));
+
+        // Usable range running into the backup partition array
+        let into_backup = Header {
+            last_usable_lba: 99_967,
+            ..header()
+        };
+        assert_eq!(
+            Header::parse(&into_backup.to_sector(), 512),
+            Err(GptError::InvalidHeader)
+        );
+        let odd_entries = Header {
+            entry_size: 100,
+            ..header()
+        };
+        assert_eq!(
+            Header::parse(&odd_entries.to_sector(), 512),
+            Err(GptError::InvalidHeader)
+        );
+    }
+}
@@ -0,0 +1,204 @@
+//! MorpheusX GPT Engine
+//!
+//! The one place GPT headers and partition arrays are parsed, checked and
+//! written. The bootloader's storage manager (through `morpheus_core`'s
+//! `gpt_ops`, pre-EBS) and the download path (through `morpheus_network`'s
+//! `GptOps`, post-EBS) both sit on top of it, so CRC handling, the backup
+//! copy and the usable range are the same on either side of
+//! ExitBootServices.
+//!
+//! # Allocation
+//!
+//! The engine never allocates. A [`Gpt`] keeps its partition array in
+//! storage the caller hands it: a `Vec<u8>` where there is a heap, a
+//! `[u8; DEFAULT_ARRAY_BYTES]` on the stack where there isn't.
+//!
+//! ```
+//! use morpheus_gpt::{Gpt, GptError, DEFAULT_ARRAY_BYTES};
+//!
+//! // A 32 MiB disk, partition array on the stack
+//! let mut gpt = Gpt::new(1 << 16, [0x42; 16], [0u8; DEFAULT_ARRAY_BYTES]).unwrap();
+//! let slot = gpt.add([0xAF; 16], 2048, 4095, "data").unwrap();
+//! assert_eq!(gpt.entry(slot).unwrap().start_lba, 2048);
+//! assert_eq!(
+//!     gpt.add([0xAF; 16], 4000, 8191, "overlap"),
+//!     Err(GptError::OverlappingPartitions)
+//! );
+//! // gpt.write(&mut block_io) puts both copies on the disk
+//! ```
+//!
+//! # Layout
+//!
+//! - [`Header`] - one GPT header, parsed and validated
+//! - [`Gpt`] - a header plus its partition array: read, edit, write both copies
+//! - [`part_type`] - well-known partition type GUIDs, their names and codes
+//! - [`crc32`], [`align_up`], [`align_down`] - helpers both callers need
+//!
+//! # Block sizes
+//!
+//! LBAs are in the disk's own blocks (`BlockIo::block_size`), 512 to
+//! [`MAX_BLOCK_SIZE`] bytes: on a 4Kn disk the primary header is at byte
+//! 4096 and 128 entries take 4 blocks instead of 32 sectors.
+
+#![no_std]
+
+mod header;
+pub mod part_type;
+mod table;
+
+pub use header::Header;
+pub use table::{write_protective_mbr, Entry, Gaps, Gpt};
+
+/// Size of a header and of the protective MBR; the block size of most disks.
+pub const SECTOR_SIZE: usize = 512;
+
+/// Largest block size tables are laid out in. Headers and the protective
+/// MBR are padded to a block on the stack.
+pub const MAX_BLOCK_SIZE: usize = 4096;
+
+/// Bytes per partition entry in tables this crate creates.
+pub const ENTRY_SIZE: usize = 128;
+
+/// Partition entries in tables this crate creates.
+pub const DEFAULT_ENTRIES: u32 = 128;
+
+/// Size of a partition array of [`DEFAULT_ENTRIES`]: the stack buffer
+/// post-EBS callers read tables into.
+pub const DEFAULT_ARRAY_BYTES: usize = DEFAULT_ENTRIES as usize * ENTRY_SIZE;
+
+/// Partitions start on 1 MiB boundaries.
+pub const ALIGNMENT_BYTES: u64 = 1024 * 1024;
+
+/// Why a GPT operation failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GptError {
+    /// The disk failed a read or write
+    IoError,
+    /// No valid GPT (primary nor backup), or a header with impossible values
+    InvalidHeader,
+    /// Every partition entry is in use
+    NoSpace,
+    /// No partition in that slot
+    PartitionNotFound,
+    /// The range overlaps another partition
+    OverlappingPartitions,
+    /// The range is empty or outside the usable area
+    InvalidSize,
+    /// The storage handed in can't hold the partition array
+    BufferTooSmall,
+    /// The disk's blocks are larger than [`MAX_BLOCK_SIZE`], or not the
+    /// size the table was laid out for
+    UnsupportedBlockSize,
+}
+
+/// CRC32 (IEEE 802.3), as GPT headers and partition arrays use.
+pub fn crc32(data: &[u8]) -> u32 {
+    const POLYNOMIAL: u32 = 0xEDB88320;
+    let mut crc = 0xFFFF_FFFFu32;
+
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            if crc & 1 != 0 {
+                crc = (crc >> 1) ^ POLYNOMIAL;
+            } else {
+                crc >>= 1;
+            }
+        }
+    }
+
+    !crc
+}
+
+/// `lba` rounded up to the partition alignment for `block_size`-byte blocks.
+pub fn align_up(lba: u64, block_size: u32) -> u64 {
+    let alignment = ALIGNMENT_BYTES / block_size as u64;
+    lba.div_ceil(alignment) * alignment
+}
+
+/// `lba` rounded down to the partition alignment for `block_size`-byte blocks.
+pub fn align_down(lba: u64, block_size: u32) -> u64 {
+    let alignment = ALIGNMENT_BYTES / block_size as u64;
+    lba / alignment * alignment
+}
+
+/// In-memory disk for the tests.
+#[cfg(test)]
+mod testing {
+    extern crate alloc;
+
+    use super::SECTOR_SIZE;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use gpt_disk_io::BlockIo;
+    use gpt_disk_types::{BlockSize, Lba};
+
+    /// Disk of zeroed sectors held in memory.
+    pub struct MemDisk(pub Vec<[u8; SECTOR_SIZE]>);
+
+    impl MemDisk {
+        pub fn new(num_blocks: usize) -> Self {
+            Self(vec![[0; SECTOR_SIZE]; num_blocks])
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct OutOfRange;
+
+    impl core::fmt::Display for OutOfRange {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            write!(f, "out of range")
+        }
+    }
+
+    impl BlockIo for MemDisk {
+        type Error = OutOfRange;
+
+        fn block_size(&self) -> BlockSize {
+            BlockSize::BS_512
+        }
+
+        fn num_blocks(&mut self) -> Result<u64, Self::Error> {
+            Ok(self.0.len() as u64)
+        }
+
+        fn read_blocks(&mut self, lba: Lba, buffer: &mut [u8]) -> Result<(), Self::Error> {
+            for (i, chunk) in buffer.chunks_mut(SECTOR_SIZE).enumerate() {
+                let sector = self.0.get(lba.0 as usize + i).ok_or(OutOfRange)?;
+                chunk.copy_from_slice(&sector[..chunk.len()]);
+            }
+            Ok(())
+        }
+
+        fn write_blocks(&mut self, lba: Lba, buffer: &[u8]) -> Result<(), Self::Error> {
+            for (i, chunk) in buffer.chunks(SECTOR_SIZE).enumerate() {
+                let sector = self.0.get_mut(lba.0 as usize + i).ok_or(OutOfRange)?;
+                sector[..chunk.len()].copy_from_slice(chunk);
+            }
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF43926);
+    }
+
+    #[test]
+    fn test_alignment() {
+        assert_eq!(align_up(34, 512), 2048);
+        assert_eq!(align_up(2048, 512), 2048);
+        assert_eq!(align_down(4095, 512), 2048);
+        assert_eq!(align_up(1, 4096), 256);
+    }
+}
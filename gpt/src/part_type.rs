@@ -0,0 +1,156 @@
+//! Partition type GUIDs the tools know by name, and attribute bits.
+//!
+//! GUIDs are kept as stored on disk (the first three fields
+//! little-endian), so they compare directly against [`Entry::type_guid`].
+//! Each known type has a display name, the four-digit code gdisk uses for
+//! it and the attributes [`Gpt::add`] gives new partitions of that type.
+//!
+//! ISO chunk partitions get a type of their own, [`ISO_CHUNK`], marked
+//! hidden and no-automount, so other systems leave them alone and we
+//! never take someone else's partition for a chunk. Chunks written before
+//! it existed are Basic Data ([`LEGACY_ISO_CHUNK`]).
+//!
+//! Exporting a chunk, to read it from another OS, clears both bits with
+//! [`Gpt::set_attributes`]; hiding it again sets them back. The type
+//! stays, so chunk discovery finds it either way.
+//!
+//! [`Entry::type_guid`]: crate::Entry::type_guid
+//! [`Gpt::add`]: crate::Gpt::add
+//! [`Gpt::set_attributes`]: crate::Gpt::set_attributes
+
+use gpt_disk_types::guid;
+
+/// Type of an unused entry
+pub const UNUSED: [u8; 16] = [0; 16];
+
+pub const EFI_SYSTEM: [u8; 16] = guid!("c12a7328-f81f-11d2-ba4b-00a0c93ec93b").to_bytes();
+
+/// Microsoft Basic Data: FAT and NTFS data partitions
+pub const BASIC_DATA: [u8; 16] = guid!("ebd0a0a2-b9e5-4433-87c0-68b6b72699c7").to_bytes();
+
+/// Microsoft Reserved: the empty MSR Windows puts on every GPT disk
+pub const MS_RESERVED: [u8; 16] = guid!("e3c9e316-0b5c-4db8-817d-f92df00215ae").to_bytes();
+
+pub const LINUX_FS: [u8; 16] = guid!("0fc63daf-8483-4772-8e79-3d69d8477de4").to_bytes();
+
+pub const LINUX_SWAP: [u8; 16] = guid!("0657fd6d-a4ab-43c4-84e5-0933c84b4f4f").to_bytes();
+
+pub const LINUX_LVM: [u8; 16] = guid!("e6d6d379-f507-44c2-a23c-238f2a3df928").to_bytes();
+
+/// Linux dm-crypt/LUKS container
+pub const LINUX_LUKS: [u8; 16] = guid!("ca7d7ccb-63ed-4c53-861c-1742536059cc").to_bytes();
+
+/// Type of the partitions a downloaded ISO is split across (MorpheusX's
+/// own)
+pub const ISO_CHUNK: [u8; 16] = guid!("6d58a7c2-3f1e-4b6a-9c0d-4d5854434b30").to_bytes();
+
+/// Type chunk partitions had before [`ISO_CHUNK`]
+pub const LEGACY_ISO_CHUNK: [u8; 16] = BASIC_DATA;
+
+/// Attribute: hidden (bit 62, defined for Basic Data and honoured by
+/// Windows for any type)
+pub const ATTR_HIDDEN: u64 = 1 << 62;
+
+/// Attribute: don't assign a drive letter or automount (bit 63)
+pub const ATTR_NO_AUTOMOUNT: u64 = 1 << 63;
+
+/// The bits that keep a chunk out of other systems' way
+pub const CHUNK_HIDDEN: u64 = ATTR_HIDDEN | ATTR_NO_AUTOMOUNT;
+
+/// Whether a partition of this type may hold an ISO chunk a manifest
+/// points at. Only for partitions a manifest names by exact LBA range;
+/// searching the disk for chunks goes by [`ISO_CHUNK`] alone.
+pub fn is_chunk_type(guid: &[u8; 16]) -> bool {
+    *guid == ISO_CHUNK || *guid == LEGACY_ISO_CHUNK
+}
+
+/// A partition type the tools know.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PartitionKind {
+    pub guid: [u8; 16],
+    /// Short enough for a table column (at most 18 characters)
+    pub name: &'static str,
+    /// gdisk type code, e.g. "EF00"
+    pub code: &'static str,
+    /// Attributes new partitions of this type get
+    pub attributes: u64,
+}
+
+/// Every known type.
+pub const KNOWN: &[PartitionKind] = &[
+    kind(EFI_SYSTEM, "EFI System", "EF00"),
+    kind(BASIC_DATA, "Basic Data", "0700"),
+    kind(MS_RESERVED, "MS Reserved", "0C01"),
+    kind(LINUX_FS, "Linux FS", "8300"),
+    kind(LINUX_SWAP, "Linux Swap", "8200"),
+    kind(LINUX_LVM, "Linux LVM", "8E00"),
+    kind(LINUX_LUKS, "Linux LUKS", "8309"),
+    PartitionKind {
+        attributes: CHUNK_HIDDEN,
+        ..kind(ISO_CHUNK, "MorpheusX Chunk", "MX00")
+    },
+];
+
+const fn kind(guid: [u8; 16], name: &'static str, code: &'static str) -> PartitionKind {
+    PartitionKind {
+        guid,
+        name,
+        code,
+        attributes: 0,
+    }
+}
+
+/// Attributes [`Gpt::add`](crate::Gpt::add) gives a new partition of this
+/// type: none unless [`KNOWN`] says otherwise.
+pub fn default_attributes(guid: &[u8; 16]) -> u64 {
+    lookup(guid).map_or(0, |kind| kind.attributes)
+}
+
+/// The known type with this GUID.
+pub fn lookup(guid: &[u8; 16]) -> Option<&'static PartitionKind> {
+    KNOWN.iter().find(|kind| &kind.guid == guid)
+}
+
+/// Display name of a type: "Unused" for the zero GUID, "Unknown" for
+/// types not in [`KNOWN`].
+pub fn name(guid: &[u8; 16]) -> &'static str {
+    match lookup(guid) {
+        Some(kind) => kind.name,
+        None if *guid == UNUSED => "Unused",
+        None => "Unknown",
+    }
+}
+
+/// gdisk code of a type: "0000" for the zero GUID, "????" for types not
+/// in [`KNOWN`].
+pub fn code(guid: &[u8; 16]) -> &'static str {
+    match lookup(guid) {
+        Some(kind) => kind.code,
+        None if *guid == UNUSED => "0000",
+        None => "????",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup() {
+        // Mixed-endian, as on disk
+        assert_eq!(EFI_SYSTEM[..4], [0x28, 0x73, 0x2A, 0xC1]);
+        assert_eq!(name(&EFI_SYSTEM), "EFI System");
+        assert_eq!(code(&LINUX_LUKS), "8309");
+        assert_eq!(name(&ISO_CHUNK), "MorpheusX Chunk");
+        assert_eq!(default_attributes(&ISO_CHUNK), CHUNK_HIDDEN);
+        assert_eq!(default_attributes(&BASIC_DATA), 0);
+        assert!(is_chunk_type(&LEGACY_ISO_CHUNK) && !is_chunk_type(&LINUX_FS));
+        assert_eq!((name(&UNUSED), code(&UNUSED)), ("Unused", "0000"));
+        assert_eq!((name(&[1; 16]), code(&[1; 16])), ("Unknown", "????"));
+
+        for (i, kind) in KNOWN.iter().enumerate() {
+            assert!(kind.name.len() <= 18 && kind.code.len() == 4);
+            assert!(KNOWN[i + 1..].iter().all(|other| other.guid != kind.guid));
+        }
+    }
+}
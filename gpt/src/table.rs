@@ -0,0 +1,568 @@
+// GPT partition array: read, edit, write both copies
+
+use super::{
+    part_type, GptError, Header, DEFAULT_ENTRIES, ENTRY_SIZE, MAX_BLOCK_SIZE, SECTOR_SIZE,
+};
+use gpt_disk_io::BlockIo;
+use gpt_disk_types::Lba;
+
+/// UTF-16 code units in a partition name
+const NAME_UNITS: usize = 36;
+
+/// One used partition entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entry {
+    /// Index in the partition array
+    pub slot: u32,
+    /// As stored on disk (mixed-endian)
+    pub type_guid: [u8; 16],
+    pub unique_guid: [u8; 16],
+    pub start_lba: u64,
+    /// Inclusive
+    pub end_lba: u64,
+    pub attributes: u64,
+    /// UTF-16LE, zero-padded
+    pub name: [u16; NAME_UNITS],
+}
+
+/// A GPT read from (or about to be written to) a disk: the primary header
+/// and the partition array, kept in `S`.
+pub struct Gpt<S> {
+    header: Header,
+    entries: S,
+}
+
+impl<S: AsRef<[u8]> + AsMut<[u8]>> Gpt<S> {
+    /// Empty table for a disk of `num_blocks` sectors: 128 entries, usable
+    /// space from LBA 34 to 34 sectors before the end.
+    pub fn new(num_blocks: u64, disk_guid: [u8; 16], entries: S) -> Result<Self, GptError> {
+        Self::with_block_size(num_blocks, SECTOR_SIZE as u32, disk_guid, entries)
+    }
+
+    /// Empty table for a disk of `num_blocks` blocks of `block_size`
+    /// bytes: 128 entries, usable space between the two partition arrays.
+    pub fn with_block_size(
+        num_blocks: u64,
+        block_size: u32,
+        disk_guid: [u8; 16],
+        mut entries: S,
+    ) -> Result<Self, GptError> {
+        if !(SECTOR_SIZE as u32..=MAX_BLOCK_SIZE as u32).contains(&block_size) {
+            return Err(GptError::UnsupportedBlockSize);
+        }
+        let mut header = Header {
+            my_lba: 1,
+            alternate_lba: num_blocks.checked_sub(1).ok_or(GptError::InvalidSize)?,
+            first_usable_lba: 0,
+            last_usable_lba: 0,
+            disk_guid,
+            entry_lba: 2,
+            num_entries: DEFAULT_ENTRIES,
+            entry_size: ENTRY_SIZE as u32,
+            array_crc32: 0,
+            block_size,
+        };
+        let reserved = 2 + header.array_blocks();
+        header.first_usable_lba = reserved;
+        header.last_usable_lba = num_blocks.saturating_sub(reserved);
+        if header.first_usable_lba > header.last_usable_lba {
+            return Err(GptError::InvalidSize);
+        }
+        let storage = entries.as_mut();
+        if storage.len() < header.array_bytes() {
+            return Err(GptError::BufferTooSmall);
+        }
+        storage.fill(0);
+        Ok(Self { header, entries })
+    }
+
+    /// Read the GPT of `block_io`, falling back to the backup copy when the
+    /// primary header or array fails its CRC. `storage` is called once with
+    /// the size of the partition array to get somewhere to keep it.
+    pub fn read<B: BlockIo>(
+        block_io: &mut B,
+        storage: impl FnOnce(usize) -> S,
+    ) -> Result<Self, GptError> {
+        let mut storage = Some(storage);
+        let mut entries = None;
+
+        let primary = read_header(block_io, 1)?;
+        if let Some(header) = primary {
+            let mut buf = (storage.take().unwrap())(array_storage(&header));
+            if read_array(block_io, &header, buf.as_mut())? {
+                return Ok(Self {
+                    header,
+                    entries: buf,
+                });
+            }
+            entries = Some(buf);
+        }
+
+        // Primary unusable: the backup is where its header says, or on the
+        // last sector if the header itself is gone
+        let backup_lba = match primary {
+            Some(header) => header.alternate_lba,
+            None => {
+                let num_blocks = block_io.num_blocks().map_err(|_| GptError::IoError)?;
+                num_blocks.checked_sub(1).ok_or(GptError::InvalidHeader)?
+            }
+        };
+        let backup = read_header(block_io, backup_lba)?.ok_or(GptError::InvalidHeader)?;
+        let mut buf = match (entries, storage) {
+            (Some(buf), _) => buf,
+            (None, Some(storage)) => storage(array_storage(&backup)),
+            (None, None) => unreachable!(),
+        };
+        if !read_array(block_io, &backup, buf.as_mut())? {
+            return Err(GptError::InvalidHeader);
+        }
+        let mut header = backup.other_copy();
+        if let Some(primary) = primary {
+            header.entry_lba = primary.entry_lba;
+        }
+        Ok(Self {
+            header,
+            entries: buf,
+        })
+    }
+
+    /// The primary header (array CRC as of the last read or write).
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// First and last LBA partitions may use.
+    pub fn usable_range(&self) -> (u64, u64) {
+        (self.header.first_usable_lba, self.header.last_usable_lba)
+    }
+
+    /// Entry in `slot`, if it is in use.
+    pub fn entry(&self, slot: u32) -> Option<Entry> {
+        let raw = self.raw_entry(slot)?;
+        let type_guid: [u8; 16] = raw[0..16].try_into().unwrap();
+        if type_guid == [0; 16] {
+            return None;
+        }
+        let u64_at = |at: usize| u64::from_le_bytes(raw[at..at + 8].try_into().unwrap());
+        let mut name = [0u16; NAME_UNITS];
+        for (i, unit) in name.iter_mut().enumerate() {
+            *unit = u16::from_le_bytes([raw[56 + i * 2], raw[57 + i * 2]]);
+        }
+        Some(Entry {
+            slot,
+            type_guid,
+            unique_guid: raw[16..32].try_into().unwrap(),
+            start_lba: u64_at(32),
+            end_lba: u64_at(40),
+            attributes: u64_at(48),
+            name,
+        })
+    }
+
+    /// Every used entry, by slot.
+    pub fn entries(&self) -> impl Iterator<Item = Entry> + '_ {
+        (0..self.header.num_entries).filter_map(move |slot| self.entry(slot))
+    }
+
+    /// Check that `start_lba..=end_lba` is inside the usable range and
+    /// overlaps no partition except the one in `except`.
+    pub fn check_range(
+        &self,
+        start_lba: u64,
+        end_lba: u64,
+        except: Option<u32>,
+    ) -> Result<(), GptError> {
+        let (first, last) = self.usable_range();
+        if start_lba > end_lba || start_lba < first || end_lba > last {
+            return Err(GptError::InvalidSize);
+        }
+        let overlaps = self
+            .entries()
+            .any(|e| Some(e.slot) != except && start_lba <= e.end_lba && e.start_lba <= end_lba);
+        if overlaps {
+            return Err(GptError::OverlappingPartitions);
+        }
+        Ok(())
+    }
+
+    /// Add a partition in the first free slot and return the slot. `name`
+    /// is stored as UTF-16, cut at 36 characters; the unique GUID is the
+    /// disk GUID with the slot mixed in. Attributes are the type's
+    /// [`default_attributes`](part_type::default_attributes).
+    pub fn add(
+        &mut self,
+        type_guid: [u8; 16],
+        start_lba: u64,
+        end_lba: u64,
+        name: &str,
+    ) -> Result<u32, GptError> {
+        if type_guid == [0; 16] {
+            return Err(GptError::InvalidSize); // Would read back as unused
+        }
+        self.check_range(start_lba, end_lba, None)?;
+        let slot = (0..self.header.num_entries)
+            .find(|&slot| self.entry(slot).is_none())
+            .ok_or(GptError::NoSpace)?;
+
+        let mut unique_guid = self.header.disk_guid;
+        for (byte, slot_byte) in unique_guid[12..].iter_mut().zip((slot + 1).to_le_bytes()) {
+            *byte ^= slot_byte;
+        }
+        let raw = self.raw_entry_mut(slot).ok_or(GptError::NoSpace)?;
+        raw.fill(0);
+        raw[0..16].copy_from_slice(&type_guid);
+        raw[16..32].copy_from_slice(&unique_guid);
+        raw[32..40].copy_from_slice(&start_lba.to_le_bytes());
+        raw[40..48].copy_from_slice(&end_lba.to_le_bytes());
+        raw[48..56].copy_from_slice(&part_type::default_attributes(&type_guid).to_le_bytes());
+        for (i, unit) in name.encode_utf16().take(NAME_UNITS).enumerate() {
+            raw[56 + i * 2..58 + i * 2].copy_from_slice(&unit.to_le_bytes());
+        }
+        Ok(slot)
+    }
+
+    /// Clear the entry in `slot`.
+    pub fn remove(&mut self, slot: u32) -> Result<(), GptError> {
+        if self.entry(slot).is_none() {
+            return Err(GptError::PartitionNotFound);
+        }
+        self.raw_entry_mut(slot)
+            .ok_or(GptError::PartitionNotFound)?
+            .fill(0);
+        Ok(())
+    }
+
+    /// Move or resize the partition in `slot` to `start_lba..=end_lba`,
+    /// which may overlap only its own old range.
+    pub fn set_range(&mut self, slot: u32, start_lba: u64, end_lba: u64) -> Result<(), GptError> {
+        if self.entry(slot).is_none() {
+            return Err(GptError::PartitionNotFound);
+        }
+        self.check_range(start_lba, end_lba, Some(slot))?;
+        let raw = self
+            .raw_entry_mut(slot)
+            .ok_or(GptError::PartitionNotFound)?;
+        raw[32..40].copy_from_slice(&start_lba.to_le_bytes());
+        raw[40..48].copy_from_slice(&end_lba.to_le_bytes());
+        Ok(())
+    }
+
+    /// Replace the attribute bits of the partition in `slot`.
+    pub fn set_attributes(&mut self, slot: u32, attributes: u64) -> Result<(), GptError> {
+        if self.entry(slot).is_none() {
+            return Err(GptError::PartitionNotFound);
+        }
+        let raw = self
+            .raw_entry_mut(slot)
+            .ok_or(GptError::PartitionNotFound)?;
+        raw[48..56].copy_from_slice(&attributes.to_le_bytes());
+        Ok(())
+    }
+
+    /// Free gaps in the usable range (inclusive LBA ranges, by start).
+    pub fn gaps(&self) -> Gaps<'_, S> {
+        Gaps {
+            gpt: self,
+            cursor: self.header.first_usable_lba,
+        }
+    }
+
+    /// Write the partition array and header, then the backup array and
+    /// header, with fresh CRCs. The disk's blocks have to be the size the
+    /// table was laid out for.
+    pub fn write<B: BlockIo>(&mut self, block_io: &mut B) -> Result<(), GptError> {
+        let block_size = disk_block_size(block_io)?;
+        if block_size != self.header.block_size as usize {
+            return Err(GptError::UnsupportedBlockSize);
+        }
+        self.header.array_crc32 = super::crc32(&self.entries.as_ref()[..self.header.array_bytes()]);
+        let backup = self.header.other_copy();
+        let mut block = [0u8; MAX_BLOCK_SIZE];
+        for header in [self.header, backup] {
+            write_array(block_io, &header, self.entries.as_ref())?;
+            block[..SECTOR_SIZE].copy_from_slice(&header.to_sector());
+            block_io
+                .write_blocks(Lba(header.my_lba), &block[..block_size])
+                .map_err(|_| GptError::IoError)?;
+        }
+        block_io.flush().map_err(|_| GptError::IoError)
+    }
+
+    fn raw_entry(&self, slot: u32) -> Option<&[u8]> {
+        if slot >= self.header.num_entries {
+            return None;
+        }
+        let size = self.header.entry_size as usize;
+        let at = slot as usize * size;
+        self.entries.as_ref().get(at..at + size)
+    }
+
+    fn raw_entry_mut(&mut self, slot: u32) -> Option<&mut [u8]> {
+        if slot >= self.header.num_entries {
+            return None;
+        }
+        let size = self.header.entry_size as usize;
+        let at = slot as usize * size;
+        self.entries.as_mut().get_mut(at..at + size)
+    }
+}
+
+/// Free gaps of a [`Gpt`], in LBA order. Partitions reaching outside the
+/// usable range only count for the part inside it.
+pub struct Gaps<'a, S> {
+    gpt: &'a Gpt<S>,
+    /// First LBA not yet known to be used or reported
+    cursor: u64,
+}
+
+impl<S: AsRef<[u8]> + AsMut<[u8]>> Iterator for Gaps<'_, S> {
+    type Item = (u64, u64);
+
+    fn next(&mut self) -> Option<(u64, u64)> {
+        let last = self.gpt.header.last_usable_lba;
+        while self.cursor <= last {
+            // The partition at or after the cursor that starts first
+            let next = self
+                .gpt
+                .entries()
+                .filter(|e| e.end_lba >= self.cursor)
+                .min_by_key(|e| e.start_lba);
+            match next {
+                None => {
+                    let gap = (self.cursor, last);
+                    self.cursor = last + 1;
+                    return Some(gap);
+                }
+                Some(e) if e.start_lba > self.cursor => {
+                    let gap = (self.cursor, (e.start_lba - 1).min(last));
+                    self.cursor = e.end_lba.saturating_add(1);
+                    return Some(gap);
+                }
+                Some(e) => self.cursor = e.end_lba.saturating_add(1),
+            }
+        }
+        None
+    }
+}
+
+/// Protective MBR for a GPT disk of `num_blocks` blocks: one partition
+/// of type 0xEE covering the disk (or as much of it as 32 bits count).
+pub fn write_protective_mbr<B: BlockIo>(block_io: &mut B, num_blocks: u64) -> Result<(), GptError> {
+    let block_size = disk_block_size(block_io)?;
+    let mut mbr = [0u8; MAX_BLOCK_SIZE];
+    let record = &mut mbr[446..462];
+    record[1..4].copy_from_slice(&[0x00, 0x02, 0x00]); // CHS of LBA 1
+    record[4] = 0xEE;
+    record[5..8].copy_from_slice(&[0xFF, 0xFF, 0xFF]);
+    record[8..12].copy_from_slice(&1u32.to_le_bytes());
+    let size = u32::try_from(num_blocks.saturating_sub(1)).unwrap_or(u32::MAX);
+    record[12..16].copy_from_slice(&size.to_le_bytes());
+    mbr[510] = 0x55;
+    mbr[511] = 0xAA;
+    block_io
+        .write_blocks(Lba(0), &mbr[..block_size])
+        .map_err(|_| GptError::IoError)
+}
+
+/// Bytes per block of `block_io`, if the engine can lay a table out in it.
+fn disk_block_size<B: BlockIo>(block_io: &B) -> Result<usize, GptError> {
+    let block_size = block_io.block_size().to_usize().unwrap_or(usize::MAX);
+    if block_size > MAX_BLOCK_SIZE {
+        return Err(GptError::UnsupportedBlockSize);
+    }
+    Ok(block_size)
+}
+
+/// Storage `header`'s partition array needs: whole blocks.
+fn array_storage(header: &Header) -> usize {
+    header.array_blocks() as usize * header.block_size as usize
+}
+
+/// Header at `lba`, None if it isn't a valid header claiming to be there.
+fn read_header<B: BlockIo>(block_io: &mut B, lba: u64) -> Result<Option<Header>, GptError> {
+    let block_size = disk_block_size(block_io)?;
+    let mut block = [0u8; MAX_BLOCK_SIZE];
+    block_io
+        .read_blocks(Lba(lba), &mut block[..block_size])
+        .map_err(|_| GptError::IoError)?;
+    let sector = block[..SECTOR_SIZE].try_into().unwrap();
+    Ok(Header::parse(sector, block_size as u32)
+        .ok()
+        .filter(|header| header.my_lba == lba))
+}
+
+/// Read `header`'s partition array into `buf`; whether it matched the CRC.
+/// Block by block: some post-EBS drivers take one sector per request.
+fn read_array<B: BlockIo>(
+    block_io: &mut B,
+    header: &Header,
+    buf: &mut [u8],
+) -> Result<bool, GptError> {
+    let len = array_storage(header);
+    if buf.len() < len {
+        return Err(GptError::BufferTooSmall);
+    }
+    let block_size = header.block_size as usize;
+    for (i, block) in buf[..len].chunks_mut(block_size).enumerate() {
+        block_io
+            .read_blocks(Lba(header.entry_lba + i as u64), block)
+            .map_err(|_| GptError::IoError)?;
+    }
+    Ok(super::crc32(&buf[..header.array_bytes()]) == header.array_crc32)
+}
+
+fn write_array<B: BlockIo>(block_io: &mut B, header: &Header, buf: &[u8]) -> Result<(), GptError> {
+    let len = array_storage(header);
+    let block_size = header.block_size as usize;
+    let mut last = [0u8; MAX_BLOCK_SIZE];
+    for (i, block) in buf[..len.min(buf.len())].chunks(block_size).enumerate() {
+        // Storage from `new` may end mid-block; pad with zeros
+        let block = if block.len() < block_size {
+            last[..block.len()].copy_from_slice(block);
+            &last[..block_size]
+        } else {
+            block
+        };
+        block_io
+            .write_blocks(Lba(header.entry_lba + i as u64), block)
+            .map_err(|_| GptError::IoError)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+
+    use super::*;
+    use crate::testing::MemDisk;
+    use crate::DEFAULT_ARRAY_BYTES;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    const BLOCKS: u64 = 1 << 16;
+    const DATA: [u8; 16] = [0xAF; 16];
+
+    fn stack(_: usize) -> [u8; DEFAULT_ARRAY_BYTES] {
+        [0; DEFAULT_ARRAY_BYTES]
+    }
+
+    fn fresh() -> MemDisk {
+        let mut disk = MemDisk::new(BLOCKS as usize);
+        write_protective_mbr(&mut disk, BLOCKS).unwrap();
+        Gpt::new(BLOCKS, [0x42; 16], stack(0))
+            .unwrap()
+            .write(&mut disk)
+            .unwrap();
+        disk
+    }
+
+    #[test]
+    fn test_write_both_copies() {
+        let mut disk = fresh();
+        let mut gpt = Gpt::read(&mut disk, stack).unwrap();
+        let slot = gpt.add(DATA, 2048, 4095, "chunk").unwrap();
+        gpt.write(&mut disk).unwrap();
+
+        let primary = Header::parse(&disk.0[1], 512).unwrap();
+        let backup = Header::parse(&disk.0[BLOCKS as usize - 1], 512).unwrap();
+        assert_eq!(backup, primary.other_copy());
+        assert_eq!(backup.entry_lba, BLOCKS - 33);
+        assert_eq!(
+            disk.0[2..34],
+            disk.0[BLOCKS as usize - 33..BLOCKS as usize - 1]
+        );
+        assert_eq!(disk.0[0][450], 0xEE);
+
+        // Heap storage reads the same table
+        let gpt = Gpt::read(&mut disk, |len| vec![0u8; len]).unwrap();
+        let entry = gpt.entry(slot).unwrap();
+        assert_eq!((entry.start_lba, entry.end_lba), (2048, 4095));
+        let name: Vec<u16> = "chunk".encode_utf16().collect();
+        assert_eq!(entry.name[..5], name[..]);
+        assert_ne!(entry.unique_guid, gpt.header().disk_guid);
+    }
+
+    #[test]
+    fn test_falls_back_to_backup() {
+        let mut disk = fresh();
+        let mut gpt = Gpt::read(&mut disk, stack).unwrap();
+        gpt.add(DATA, 2048, 4095, "a").unwrap();
+        gpt.write(&mut disk).unwrap();
+
+        // Primary array corrupted: read the backup, writing restores it
+        disk.0[2][40] ^= 0xFF;
+        let mut gpt = Gpt::read(&mut disk, stack).unwrap();
+        assert_eq!(gpt.entries().count(), 1);
+        gpt.write(&mut disk).unwrap();
+        assert_eq!(disk.0[2][40], 0xFF); // Low byte of end LBA 4095
+
+        // Primary header gone entirely
+        disk.0[1] = [0; SECTOR_SIZE];
+        let gpt = Gpt::read(&mut disk, stack).unwrap();
+        assert_eq!(gpt.header().my_lba, 1);
+        assert_eq!(gpt.entries().count(), 1);
+
+        disk.0[BLOCKS as usize - 1] = [0; SECTOR_SIZE];
+        assert_eq!(
+            Gpt::read(&mut disk, stack).err(),
+            Some(GptError::InvalidHeader)
+        );
+    }
+
+    #[test]
+    fn test_edit_checks() {
+        let mut gpt = Gpt::new(BLOCKS, [0x42; 16], stack(0)).unwrap();
+        let a = gpt.add(DATA, 2048, 4095, "a").unwrap();
+        let b = gpt.add(DATA, 8192, 10239, "b").unwrap();
+        assert_eq!((a, b), (0, 1));
+
+        assert_eq!(
+            gpt.add(DATA, 4000, 5000, "c"),
+            Err(GptError::OverlappingPartitions)
+        );
+        assert_eq!(gpt.add(DATA, 10, 100, "c"), Err(GptError::InvalidSize));
+        assert_eq!(
+            gpt.add(DATA, BLOCKS - 100, BLOCKS - 2, "c"),
+            Err(GptError::InvalidSize)
+        );
+
+        // Overlapping its own old range is fine, its neighbour isn't
+        gpt.set_range(a, 3072, 5119).unwrap();
+        assert_eq!(
+            gpt.set_range(a, 7168, 9215),
+            Err(GptError::OverlappingPartitions)
+        );
+
+        gpt.remove(a).unwrap();
+        assert_eq!(gpt.remove(a), Err(GptError::PartitionNotFound));
+        assert_eq!(gpt.add(DATA, 2048, 4095, "d"), Ok(a)); // Slot reused
+        assert_eq!(gpt.entry(a).unwrap().attributes, 0);
+
+        // Chunk partitions come out hidden
+        let chunk = gpt.add(part_type::ISO_CHUNK, 20480, 40959, "c").unwrap();
+        let attributes = gpt.entry(chunk).unwrap().attributes;
+        assert_eq!(attributes, part_type::CHUNK_HIDDEN);
+        gpt.set_attributes(chunk, 0).unwrap();
+        assert_eq!(gpt.entry(chunk).unwrap().attributes, 0);
+        assert_eq!(gpt.set_attributes(5, 0), Err(GptError::PartitionNotFound));
+        assert_eq!(
+            Gpt::new(BLOCKS, [0; 16], [0u8; 512]).err(),
+            Some(GptError::BufferTooSmall)
+        );
+    }
+
+    #[test]
+    fn test_gaps() {
+        let mut gpt = Gpt::new(BLOCKS, [0x42; 16], stack(0)).unwrap();
+        let all: Vec<_> = gpt.gaps().collect();
+        assert_eq!(all, [(34, BLOCKS - 34)]);
+
+        // Added out of order; the gaps still come sorted
+        gpt.add(DATA, 8192, 10239, "b").unwrap();
+        gpt.add(DATA, 2048, 4095, "a").unwrap();
+        gpt.add(DATA, 10240, BLOCKS - 34, "c").unwrap();
+        let gaps: Vec<_> = gpt.gaps().collect();
+        assert_eq!(gaps, [(34, 2047), (4096, 8191)]);
+    }
+}
@@ -280,6 +280,23 @@ pub unsafe fn init_gdt(kernel_stack: u64, ist1_stack: u64) {
     puts("[GDT] initialized\n");
 }
 
+/// Load our GDT on an application processor.
+///
+/// APs share the BSP's GDT but not its TSS: `ltr` marks the descriptor
+/// busy, so only one core can load it. APs therefore run without IST
+/// stacks.
+///
+/// # Safety
+/// `init_gdt` must have run on the BSP.
+pub unsafe fn load_gdt_ap() {
+    let gdt_ptr = GdtPtr {
+        limit: (size_of::<Gdt>() - 1) as u16,
+        base: &raw const GDT as u64,
+    };
+    load_gdt(&gdt_ptr);
+    reload_segments();
+}
+
 /// Load GDT via lgdt instruction
 #[inline(always)]
 unsafe fn load_gdt(ptr: &GdtPtr) {
@@ -450,6 +450,25 @@ pub unsafe fn init_idt() {
     puts("[IDT] initialized (exceptions only)\n");
 }
 
+/// Load our IDT on an application processor.
+///
+/// The NMI, double fault and machine check gates use IST1, which APs do
+/// not have (see `gdt::load_gdt_ap`); those exceptions escalate on an AP.
+///
+/// # Safety
+/// `init_idt` must have run on the BSP.
+pub unsafe fn load_idt_ap() {
+    let idt_ptr = IdtPtr {
+        limit: (core::mem::size_of::<Idt>() - 1) as u16,
+        base: &raw const IDT as u64,
+    };
+    core::arch::asm!(
+        "lidt [{}]",
+        in(reg) &idt_ptr,
+        options(nostack, preserves_flags)
+    );
+}
+
 /// Set a custom interrupt handler for a vector.
 ///
 /// # Safety
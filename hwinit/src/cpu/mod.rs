@@ -10,6 +10,7 @@
 //! - `barriers` - Memory barriers
 //! - `cache` - Cache management
 //! - `mmio` - Memory-mapped I/O
+//! - `msr` - Model-specific registers and CPUID
 //! - `pio` - Port I/O
 //! - `tsc` - Time Stamp Counter
 
@@ -18,6 +19,7 @@ pub mod cache;
 pub mod gdt;
 pub mod idt;
 pub mod mmio;
+pub mod msr;
 pub mod pic;
 pub mod pio;
 pub mod tsc;
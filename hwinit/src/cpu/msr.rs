@@ -0,0 +1,93 @@
+//! Model-specific register access.
+//!
+//! # Safety
+//! Reading or writing an MSR the CPU does not implement raises #GP.
+//! Callers check the relevant CPUID feature bit first.
+
+/// IA32_APIC_BASE
+pub const IA32_APIC_BASE: u32 = 0x1B;
+/// IA32_MTRRCAP
+pub const IA32_MTRRCAP: u32 = 0xFE;
+/// IA32_MTRR_PHYSBASE0 (PHYSMASKn = PHYSBASEn + 1, stride 2)
+pub const IA32_MTRR_PHYSBASE0: u32 = 0x200;
+/// IA32_PAT
+pub const IA32_PAT: u32 = 0x277;
+/// IA32_MTRR_DEF_TYPE
+pub const IA32_MTRR_DEF_TYPE: u32 = 0x2FF;
+/// IA32_EFER
+pub const IA32_EFER: u32 = 0xC000_0080;
+
+/// EFER.NXE - execute-disable bit is honoured in page tables
+pub const EFER_NXE: u64 = 1 << 11;
+
+/// Read an MSR.
+///
+/// # Safety
+/// The MSR must exist on this CPU.
+#[cfg(target_arch = "x86_64")]
+#[inline]
+pub unsafe fn rdmsr(msr: u32) -> u64 {
+    let lo: u32;
+    let hi: u32;
+    core::arch::asm!(
+        "rdmsr",
+        in("ecx") msr,
+        out("eax") lo,
+        out("edx") hi,
+        options(nomem, nostack, preserves_flags)
+    );
+    ((hi as u64) << 32) | lo as u64
+}
+
+/// Write an MSR.
+///
+/// # Safety
+/// The MSR must exist and `value` must be valid for it.
+#[cfg(target_arch = "x86_64")]
+#[inline]
+pub unsafe fn wrmsr(msr: u32, value: u64) {
+    core::arch::asm!(
+        "wrmsr",
+        in("ecx") msr,
+        in("eax") value as u32,
+        in("edx") (value >> 32) as u32,
+        options(nostack, preserves_flags)
+    );
+}
+
+/// CPUID leaf `leaf`, subleaf 0: (eax, ebx, ecx, edx).
+#[cfg(target_arch = "x86_64")]
+pub fn cpuid(leaf: u32) -> (u32, u32, u32, u32) {
+    let eax: u32;
+    let ebx: u32;
+    let ecx: u32;
+    let edx: u32;
+    unsafe {
+        // Save rbx (used by LLVM), run cpuid, restore rbx
+        core::arch::asm!(
+            "push rbx",
+            "cpuid",
+            "mov {0:e}, ebx",
+            "pop rbx",
+            out(reg) ebx,
+            inout("eax") leaf => eax,
+            inout("ecx") 0u32 => ecx,
+            out("edx") edx,
+        );
+    }
+    (eax, ebx, ecx, edx)
+}
+
+// Stubs for non-x86_64
+#[cfg(not(target_arch = "x86_64"))]
+#[inline]
+pub unsafe fn rdmsr(_msr: u32) -> u64 {
+    0
+}
+#[cfg(not(target_arch = "x86_64"))]
+#[inline]
+pub unsafe fn wrmsr(_msr: u32, _value: u64) {}
+#[cfg(not(target_arch = "x86_64"))]
+pub fn cpuid(_leaf: u32) -> (u32, u32, u32, u32) {
+    (0, 0, 0, 0)
+}
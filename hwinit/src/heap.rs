@@ -24,6 +24,16 @@
 //! └─────────────────────────────────────────────────────────┘
 //! ```
 //!
+//! # Regions
+//!
+//! The registry is imported from the UEFI memory map, whose free memory is
+//! fragmented. The heap is therefore a set of up to `MAX_HEAP_REGIONS`
+//! independent `linked_list_allocator` regions: growth first tries to
+//! extend the last region in place and otherwise adds a new region
+//! anywhere in conventional memory. Allocation is first-fit across
+//! regions; deallocation finds the owning region by address, and pointers
+//! no region owns (e.g. UEFI pool memory allocated before EBS) are ignored.
+//!
 //! # Usage
 //!
 //! ```ignore
@@ -43,32 +53,113 @@
 
 use core::alloc::{GlobalAlloc, Layout};
 use core::ptr::{self, NonNull};
+use core::sync::atomic::{AtomicBool, Ordering};
+use linked_list_allocator::Heap;
 use spin::Mutex;
 
-use crate::memory::{global_registry_mut, is_registry_initialized, MemoryType, PAGE_SIZE};
+use crate::memory::{global_registry_mut, is_registry_initialized, AllocateType, MemoryType, PAGE_SIZE};
 use crate::serial::{puts, put_hex64, put_hex32};
 
+// ═══════════════════════════════════════════════════════════════════════════
+// CONSTANTS
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Maximum number of discontiguous heap regions.
+pub const MAX_HEAP_REGIONS: usize = 32;
+
+/// Minimum size of a new region added on growth (1MB).
+const GROW_CHUNK: usize = 1024 * 1024;
+
+/// Never let the heap take more than this fraction (1/N) of free memory.
+const MAX_FREE_FRACTION: u64 = 2;
+
 // ═══════════════════════════════════════════════════════════════════════════
 // HEAP STATE
 // ═══════════════════════════════════════════════════════════════════════════
 
+/// One contiguous heap region.
+struct HeapRegion {
+    heap: Heap,
+    base: u64,
+    size: usize,
+}
+
+impl HeapRegion {
+    fn contains(&self, addr: u64) -> bool {
+        addr >= self.base && addr < self.base + self.size as u64
+    }
+}
+
 /// Heap metadata
 struct HeapState {
-    /// The actual heap allocator
-    heap: linked_list_allocator::Heap,
-    /// Base address of heap region
-    base: u64,
-    /// Current size of heap
+    /// Regions, filled from index 0
+    regions: [Option<HeapRegion>; MAX_HEAP_REGIONS],
+    /// Total bytes across regions
     size: usize,
-    /// Maximum size we can grow to
+    /// Maximum total size we can grow to
     max_size: usize,
+    /// Whether regions may be added from the registry
+    growable: bool,
+}
+
+impl HeapState {
+    const fn empty() -> Self {
+        Self {
+            regions: [const { None }; MAX_HEAP_REGIONS],
+            size: 0,
+            max_size: 0,
+            growable: false,
+        }
+    }
+
+    fn region_count(&self) -> usize {
+        self.regions.iter().take_while(|r| r.is_some()).count()
+    }
+
+    /// Add a region. Returns false if all slots are used.
+    unsafe fn add_region(&mut self, base: u64, size: usize) -> bool {
+        let idx = self.region_count();
+        if idx >= MAX_HEAP_REGIONS {
+            return false;
+        }
+        let mut heap = Heap::empty();
+        heap.init(base as *mut u8, size);
+        self.regions[idx] = Some(HeapRegion { heap, base, size });
+        self.size += size;
+        true
+    }
+
+    fn allocate(&mut self, layout: Layout) -> *mut u8 {
+        for region in self.regions.iter_mut().flatten() {
+            if let Ok(ptr) = region.heap.allocate_first_fit(layout) {
+                return ptr.as_ptr();
+            }
+        }
+        ptr::null_mut()
+    }
+
+    /// Free `ptr` if one of our regions owns it.
+    unsafe fn deallocate(&mut self, ptr: *mut u8, layout: Layout) -> bool {
+        let Some(nn) = NonNull::new(ptr) else { return false };
+        for region in self.regions.iter_mut().flatten() {
+            if region.contains(ptr as u64) {
+                region.heap.deallocate(nn, layout);
+                return true;
+            }
+        }
+        false
+    }
+
+    fn owns(&self, ptr: *const u8) -> bool {
+        self.regions.iter().flatten().any(|r| r.contains(ptr as u64))
+    }
 }
 
 /// Global heap state
-static HEAP: Mutex<Option<HeapState>> = Mutex::new(None);
+static HEAP: Mutex<HeapState> = Mutex::new(HeapState::empty());
 
 /// Heap initialized flag (for fast path check)
-static mut HEAP_INITIALIZED: bool = false;
+static HEAP_INITIALIZED: AtomicBool = AtomicBool::new(false);
 
 // ═══════════════════════════════════════════════════════════════════════════
 // HEAP ALLOCATOR
@@ -88,46 +179,11 @@ impl HeapAllocator {
 
 unsafe impl GlobalAlloc for HeapAllocator {
     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
-        // Fast path: heap not initialized
-        if !HEAP_INITIALIZED {
-            return ptr::null_mut();
-        }
-
-        let mut guard = HEAP.lock();
-        let state = match guard.as_mut() {
-            Some(s) => s,
-            None => return ptr::null_mut(),
-        };
-
-        // Try to allocate
-        match state.heap.allocate_first_fit(layout) {
-            Ok(ptr) => ptr.as_ptr(),
-            Err(_) => {
-                // Try to grow the heap
-                if try_grow_heap(state, layout.size()) {
-                    // Retry allocation
-                    state.heap
-                        .allocate_first_fit(layout)
-                        .map(|p| p.as_ptr())
-                        .unwrap_or(ptr::null_mut())
-                } else {
-                    ptr::null_mut()
-                }
-            }
-        }
+        heap_alloc(layout)
     }
 
     unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
-        if ptr.is_null() || !HEAP_INITIALIZED {
-            return;
-        }
-
-        let mut guard = HEAP.lock();
-        if let Some(state) = guard.as_mut() {
-            if let Some(nn) = NonNull::new(ptr) {
-                state.heap.deallocate(nn, layout);
-            }
-        }
+        heap_dealloc(ptr, layout);
     }
 
     unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
@@ -147,19 +203,69 @@ unsafe impl GlobalAlloc for HeapAllocator {
     }
 }
 
+/// Allocate from the heap, growing it if needed.
+///
+/// Returns null if the heap is not initialized or memory is exhausted.
+/// For allocators that wrap this one (e.g. a pre/post-EBS hybrid).
+///
+/// # Safety
+/// Same contract as `GlobalAlloc::alloc`.
+pub unsafe fn heap_alloc(layout: Layout) -> *mut u8 {
+    // Fast path: heap not initialized
+    if !HEAP_INITIALIZED.load(Ordering::Acquire) {
+        return ptr::null_mut();
+    }
+
+    let mut state = HEAP.lock();
+    let ptr = state.allocate(layout);
+    if !ptr.is_null() {
+        return ptr;
+    }
+
+    if try_grow_heap(&mut state, layout) {
+        state.allocate(layout)
+    } else {
+        ptr::null_mut()
+    }
+}
+
+/// Return memory to the heap.
+///
+/// Returns false (and does nothing) if `ptr` is not heap memory.
+///
+/// # Safety
+/// If owned by the heap, `ptr` must have been allocated with `layout`.
+pub unsafe fn heap_dealloc(ptr: *mut u8, layout: Layout) -> bool {
+    if ptr.is_null() || !HEAP_INITIALIZED.load(Ordering::Acquire) {
+        return false;
+    }
+    HEAP.lock().deallocate(ptr, layout)
+}
+
+/// Whether `ptr` lies inside a heap region.
+pub fn heap_owns(ptr: *const u8) -> bool {
+    HEAP_INITIALIZED.load(Ordering::Acquire) && HEAP.lock().owns(ptr)
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // HEAP GROWTH
 // ═══════════════════════════════════════════════════════════════════════════
 
-/// Try to grow the heap by at least `needed` bytes.
+/// Try to grow the heap enough to satisfy `layout`.
 ///
-/// Returns true if growth succeeded.
-unsafe fn try_grow_heap(state: &mut HeapState, needed: usize) -> bool {
-    // Round up to page size
-    let grow_size = ((needed + PAGE_SIZE as usize - 1) / PAGE_SIZE as usize) * PAGE_SIZE as usize;
+/// Extends the last region in place when the pages after it are free,
+/// otherwise adds a new region anywhere. Returns true if growth succeeded.
+unsafe fn try_grow_heap(state: &mut HeapState, layout: Layout) -> bool {
+    if !state.growable {
+        return false;
+    }
+
+    // Room for the allocation, its alignment and allocator bookkeeping
+    let needed = layout.size() + layout.align() + 64;
+    let page = PAGE_SIZE as usize;
 
     // Don't exceed max size
-    if state.size + grow_size > state.max_size {
+    if state.size + needed.div_ceil(page) * page > state.max_size {
         puts("[HEAP] cannot grow: would exceed max\n");
         return false;
     }
@@ -169,30 +275,36 @@ unsafe fn try_grow_heap(state: &mut HeapState, needed: usize) -> bool {
         puts("[HEAP] cannot grow: registry not initialized\n");
         return false;
     }
-
     let registry = global_registry_mut();
-    let pages = (grow_size as u64 + PAGE_SIZE - 1) / PAGE_SIZE;
-
-    // We need contiguous memory, so allocate at a specific address
-    // For simplicity, we extend from the current heap end
-    let extend_addr = state.base + state.size as u64;
 
-    match registry.allocate_pages(
-        crate::memory::AllocateType::Address(extend_addr),
-        MemoryType::AllocatedHeap,
-        pages,
-    ) {
-        Ok(_) => {
-            // Extend the heap
-            state.heap.extend(grow_size);
+    // 1. Extend the last region in place (keeps large blocks contiguous)
+    let count = state.region_count();
+    if let Some(last) = count.checked_sub(1).and_then(|i| state.regions[i].as_mut()) {
+        let grow_size = needed.div_ceil(page) * page;
+        let extend_addr = last.base + last.size as u64;
+        if registry
+            .allocate_pages(AllocateType::Address(extend_addr), MemoryType::AllocatedHeap, (grow_size / page) as u64)
+            .is_ok()
+        {
+            last.heap.extend(grow_size);
+            last.size += grow_size;
             state.size += grow_size;
+            log_growth("extended", extend_addr, grow_size, state.size);
+            return true;
+        }
+    }
 
-            puts("[HEAP] grew by ");
-            put_hex32(grow_size as u32);
-            puts(" bytes, total ");
-            put_hex32(state.size as u32);
-            puts("\n");
-
+    // 2. New region anywhere
+    let chunk = needed.max(GROW_CHUNK).min(state.max_size - state.size);
+    let chunk = chunk.div_ceil(page) * page;
+    if chunk < needed || count >= MAX_HEAP_REGIONS {
+        puts("[HEAP] cannot grow: no region slots\n");
+        return false;
+    }
+    match registry.allocate_pages(AllocateType::AnyPages, MemoryType::AllocatedHeap, (chunk / page) as u64) {
+        Ok(base) => {
+            state.add_region(base, chunk);
+            log_growth("new region", base, chunk, state.size);
             true
         }
         Err(_) => {
@@ -202,12 +314,28 @@ unsafe fn try_grow_heap(state: &mut HeapState, needed: usize) -> bool {
     }
 }
 
+fn log_growth(how: &str, base: u64, size: usize, total: usize) {
+    puts("[HEAP] ");
+    puts(how);
+    puts(" at ");
+    put_hex64(base);
+    puts(" by ");
+    put_hex32(size as u32);
+    puts(" bytes, total ");
+    put_hex32(total as u32);
+    puts("\n");
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // INITIALIZATION
 // ═══════════════════════════════════════════════════════════════════════════
 
 /// Initialize the heap allocator.
 ///
+/// The first region is allocated from the memory registry (i.e. from
+/// conventional memory in the UEFI map); the heap then grows region by
+/// region up to half of the free memory at init time.
+///
 /// # Arguments
 /// - `initial_size`: Initial heap size in bytes (will be rounded up to page size)
 ///
@@ -215,7 +343,7 @@ unsafe fn try_grow_heap(state: &mut HeapState, needed: usize) -> bool {
 /// - Must be called after memory registry is initialized
 /// - Must be called exactly once
 pub unsafe fn init_heap(initial_size: usize) -> Result<(), &'static str> {
-    if HEAP_INITIALIZED {
+    if HEAP_INITIALIZED.load(Ordering::Acquire) {
         return Err("heap already initialized");
     }
 
@@ -226,35 +354,33 @@ pub unsafe fn init_heap(initial_size: usize) -> Result<(), &'static str> {
     let registry = global_registry_mut();
 
     // Round up to page size
-    let size = ((initial_size + PAGE_SIZE as usize - 1) / PAGE_SIZE as usize) * PAGE_SIZE as usize;
+    let size = initial_size.div_ceil(PAGE_SIZE as usize) * PAGE_SIZE as usize;
     let pages = size as u64 / PAGE_SIZE;
+    let max_size = ((registry.free_memory() / MAX_FREE_FRACTION) as usize).max(size);
 
     // Allocate heap memory
     let base = registry.allocate_pages(
-        crate::memory::AllocateType::AnyPages,
+        AllocateType::AnyPages,
         MemoryType::AllocatedHeap,
         pages,
     ).map_err(|_| "failed to allocate heap memory")?;
 
-    // Initialize the linked_list_allocator heap
-    let mut heap = linked_list_allocator::Heap::empty();
-    heap.init(base as *mut u8, size);
-
-    // Store state
-    *HEAP.lock() = Some(HeapState {
-        heap,
-        base,
-        size,
-        max_size: size * 4, // Allow growing up to 4x initial size
-    });
+    {
+        let mut state = HEAP.lock();
+        state.add_region(base, size);
+        state.max_size = max_size;
+        state.growable = true;
+    }
 
-    HEAP_INITIALIZED = true;
+    HEAP_INITIALIZED.store(true, Ordering::Release);
 
     puts("[HEAP] initialized at ");
     put_hex64(base);
     puts(", size ");
     put_hex32(size as u32);
-    puts(" bytes\n");
+    puts(" bytes, max ");
+    put_hex64(max_size as u64);
+    puts("\n");
 
     Ok(())
 }
@@ -267,7 +393,7 @@ pub unsafe fn init_heap(initial_size: usize) -> Result<(), &'static str> {
 /// - Buffer must be valid and not used for anything else
 /// - Buffer must be at least `size` bytes
 pub unsafe fn init_heap_with_buffer(buffer: *mut u8, size: usize) -> Result<(), &'static str> {
-    if HEAP_INITIALIZED {
+    if HEAP_INITIALIZED.load(Ordering::Acquire) {
         return Err("heap already initialized");
     }
 
@@ -275,17 +401,14 @@ pub unsafe fn init_heap_with_buffer(buffer: *mut u8, size: usize) -> Result<(),
         return Err("invalid buffer");
     }
 
-    let mut heap = linked_list_allocator::Heap::empty();
-    heap.init(buffer, size);
-
-    *HEAP.lock() = Some(HeapState {
-        heap,
-        base: buffer as u64,
-        size,
-        max_size: size, // Can't grow a pre-allocated buffer
-    });
+    {
+        let mut state = HEAP.lock();
+        state.add_region(buffer as u64, size);
+        state.max_size = size; // Can't grow a pre-allocated buffer
+        state.growable = false;
+    }
 
-    HEAP_INITIALIZED = true;
+    HEAP_INITIALIZED.store(true, Ordering::Release);
 
     puts("[HEAP] initialized with buffer at ");
     put_hex64(buffer as u64);
@@ -298,13 +421,24 @@ pub unsafe fn init_heap_with_buffer(buffer: *mut u8, size: usize) -> Result<(),
 
 /// Check if heap is initialized.
 pub fn is_heap_initialized() -> bool {
-    unsafe { HEAP_INITIALIZED }
+    HEAP_INITIALIZED.load(Ordering::Acquire)
 }
 
-/// Get heap statistics.
+/// Get heap statistics: (total size, used, free).
 pub fn heap_stats() -> Option<(usize, usize, usize)> {
-    let guard = HEAP.lock();
-    guard.as_ref().map(|state| {
-        (state.size, state.heap.used(), state.heap.free())
-    })
+    if !is_heap_initialized() {
+        return None;
+    }
+    let state = HEAP.lock();
+    let (used, free) = state
+        .regions
+        .iter()
+        .flatten()
+        .fold((0, 0), |(u, f), r| (u + r.heap.used(), f + r.heap.free()));
+    Some((state.size, used, free))
+}
+
+/// Number of heap regions in use.
+pub fn heap_region_count() -> usize {
+    HEAP.lock().region_count()
 }
@@ -52,6 +52,8 @@
 //!     memory_map_ptr: map_ptr,
 //!     memory_map_size: map_size,
 //!     descriptor_size: desc_size,
+//!     descriptor_version: desc_version,
+//!     rsdp: rsdp_phys,
 //! };
 //!
 //! let platform = unsafe { platform_init_selfcontained(config)? };
@@ -61,12 +63,14 @@
 //!
 //! - Memory services (mirrors UEFI: GetMemoryMap, AllocatePages, etc.)
 //! - CPU state management (GDT, IDT, TSS)
+//! - Identity page tables (UC MMIO BARs, PAT/MTRR aware)
 //! - Interrupt controller setup (PIC remapping)
 //! - Heap allocator (backed by MemoryRegistry)
 //! - TSC calibration via PIT (no UEFI needed)
 //! - PCI enumeration (bus/device/function scanning)
 //! - BAR decoding and device classification
 //! - Bus mastering enablement
+//! - SMP bring-up (APs serve a work queue for CPU-bound jobs)
 //! - E820 export for Linux handoff
 //! - Synchronization primitives (spinlocks, etc.)
 //!
@@ -79,13 +83,16 @@
 #![no_std]
 #![allow(dead_code)]
 
+pub mod acpi;
 pub mod cpu;
 pub mod dma;
 pub mod heap;
 pub mod memory;
+pub mod paging;
 pub mod pci;
 pub mod platform;
 pub mod serial;
+pub mod smp;
 pub mod sync;
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -133,11 +140,23 @@ pub use memory::{
     fallback_allocator,
 };
 
+// ═══════════════════════════════════════════════════════════════════════════
+// PAGING RE-EXPORTS
+// ═══════════════════════════════════════════════════════════════════════════
+
+pub use paging::{init_paging, CacheType, MapFlags, PagingConfig, PagingError, PagingInfo};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// SMP RE-EXPORTS
+// ═══════════════════════════════════════════════════════════════════════════
+
+pub use smp::{cpus_online, start_aps, SmpError, SmpInfo};
+
 // ═══════════════════════════════════════════════════════════════════════════
 // HEAP RE-EXPORTS
 // ═══════════════════════════════════════════════════════════════════════════
 
-pub use heap::{HeapAllocator, init_heap, init_heap_with_buffer, is_heap_initialized, heap_stats};
+pub use heap::{HeapAllocator, init_heap, init_heap_with_buffer, is_heap_initialized, heap_stats, heap_alloc, heap_dealloc, heap_owns};
 
 // ═══════════════════════════════════════════════════════════════════════════
 // SYNC RE-EXPORTS
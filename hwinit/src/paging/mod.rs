@@ -0,0 +1,381 @@
+//! Post-EBS page tables.
+//!
+//! Until now everything assumed the firmware's identity mapping covers
+//! every MMIO BAR with a sane memory type. That is not guaranteed: some
+//! firmware maps only RAM, some maps high BARs WB and relies on MTRRs,
+//! and some leaves 64-bit BARs above 4GB unmapped entirely.
+//!
+//! `init_paging` replaces the firmware tables with our own identity map:
+//!
+//! ```text
+//! 0 - 4GB              UC    (holes below 4GB are device space)
+//! RAM descriptors      WB    (from the imported UEFI memory map)
+//! MMIO descriptors     UC
+//! PCI memory BARs      UC    (sized by probing, any address)
+//! DMA regions          WB/NX (x86 DMA is cache-coherent)
+//! ```
+//!
+//! The PAT is reprogrammed first (entries 0-3 unchanged) and the MTRRs are
+//! consulted to report ranges where the effective type differs from what
+//! we asked for, e.g. a DMA buffer the firmware's MTRRs leave uncached.
+//!
+//! # Modules
+//!
+//! - `table` - Page table construction
+//! - `pat` - Page Attribute Table and memory types
+//! - `mtrr` - MTRR readout
+
+pub mod mtrr;
+pub mod pat;
+pub mod table;
+
+pub use mtrr::Mtrrs;
+pub use pat::CacheType;
+pub use table::{AddressSpace, FramePool, MapFlags, MapStats, PagingError};
+
+use crate::cpu::msr::{rdmsr, EFER_NXE, IA32_EFER};
+use crate::memory::{AllocateType, MemoryRegistry, MemoryType, PAGE_SIZE};
+use crate::pci::{pci_cfg_read16, pci_cfg_read32, pci_cfg_write16, pci_cfg_write32, offset, PciAddr};
+use crate::serial::{newline, put_hex32, put_hex64, puts};
+
+/// Everything below this is mapped UC before RAM is refined.
+const LOW_DEVICE_LIMIT: u64 = 0x1_0000_0000;
+
+/// Frames reserved for splitting 2MB pages into 4KB tables.
+const SPLIT_BUDGET_PAGES: u64 = 384;
+
+/// CR4.LA57
+const CR4_LA57: u64 = 1 << 12;
+
+/// PCI command: memory space decode
+const CMD_MEM_SPACE: u16 = 1 << 1;
+
+// ═══════════════════════════════════════════════════════════════════════════
+// CONFIG / RESULT
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Paging configuration.
+#[derive(Debug, Clone, Copy)]
+pub struct PagingConfig {
+    /// Extra DMA range not typed `AllocatedDma` in the registry
+    pub dma: Option<(u64, u64)>,
+    /// Memory type for DMA buffers (WB unless the platform is non-coherent)
+    pub dma_cache: CacheType,
+}
+
+impl Default for PagingConfig {
+    fn default() -> Self {
+        Self {
+            dma: None,
+            dma_cache: CacheType::WriteBack,
+        }
+    }
+}
+
+/// Result of `init_paging`.
+#[derive(Debug, Clone, Copy)]
+pub struct PagingInfo {
+    /// New CR3
+    pub root: u64,
+    /// Page table frames used
+    pub table_pages: u64,
+    /// PCI memory BARs mapped UC
+    pub bars_mapped: u32,
+    /// Ranges whose MTRR type overrides the type we mapped
+    pub mtrr_conflicts: u32,
+    pub stats: MapStats,
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// ENTRY POINT
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Build and load identity page tables from the memory registry.
+///
+/// On error the firmware tables stay active and the frame pool is
+/// returned to the registry.
+///
+/// # Safety
+/// - Post-EBS, registry initialized, interrupts disabled
+/// - All code, data and stacks in use must be in registry RAM regions
+pub unsafe fn init_paging(
+    registry: &mut MemoryRegistry,
+    config: &PagingConfig,
+) -> Result<PagingInfo, PagingError> {
+    if read_cr4() & CR4_LA57 != 0 {
+        return Err(PagingError::FiveLevelPaging);
+    }
+
+    let pat_supported = pat::pat_supported();
+    let nx_enabled = rdmsr(IA32_EFER) & EFER_NXE != 0;
+    let mtrrs = Mtrrs::read();
+
+    // Reserve the pool before snapshotting the map so it is part of it.
+    // Prefer memory below 4GB: AP start-up loads CR3 from 32-bit mode.
+    let pool_pages = pool_size(registry);
+    let pool_base = registry
+        .allocate_pages(AllocateType::MaxAddress(0x1_0000_0000), MemoryType::AllocatedPageTable, pool_pages)
+        .or_else(|_| registry.allocate_pages(AllocateType::AnyPages, MemoryType::AllocatedPageTable, pool_pages))
+        .map_err(|_| PagingError::PoolAllocationFailed)?;
+    let mut pool = FramePool::new(pool_base, pool_pages);
+
+    let result = build(registry, config, &mut pool, &mtrrs, pat_supported, nx_enabled);
+
+    // The registry only frees whole allocations, so on success the unused
+    // tail stays reserved (a few hundred KB); on failure return it all.
+    let (space, bars_mapped, mtrr_conflicts) = match result {
+        Ok(built) => built,
+        Err(e) => {
+            let _ = registry.free_pages(pool_base, pool_pages);
+            return Err(e);
+        }
+    };
+
+    activate(space.root(), pat_supported);
+
+    Ok(PagingInfo {
+        root: space.root(),
+        table_pages: pool.used(),
+        bars_mapped,
+        mtrr_conflicts,
+        stats: space.stats(),
+    })
+}
+
+/// Frames needed: one PD per GB mapped plus PML4/PDPTs and split budget.
+fn pool_size(registry: &MemoryRegistry) -> u64 {
+    let (_, count) = registry.get_memory_map();
+    let top = (0..count)
+        .filter_map(|i| registry.get_descriptor(i))
+        .map(|d| d.physical_end())
+        .fold(LOW_DEVICE_LIMIT, u64::max);
+    let gib = top.div_ceil(1 << 30);
+    // PML4 + a PDPT per 512GB + PDs, plus slack for BARs in new GBs
+    2 + gib / 512 + gib + 16 + SPLIT_BUDGET_PAGES
+}
+
+unsafe fn build(
+    registry: &MemoryRegistry,
+    config: &PagingConfig,
+    pool: &mut FramePool,
+    mtrrs: &Mtrrs,
+    pat_supported: bool,
+    nx_enabled: bool,
+) -> Result<(AddressSpace, u32, u32), PagingError> {
+    let mut space = AddressSpace::new(pool, pat_supported, nx_enabled)?;
+    let mut conflicts = 0u32;
+
+    // 1. Low 4GB as device space
+    space.map_range(pool, 0, LOW_DEVICE_LIMIT, MapFlags::MMIO)?;
+
+    // 2. Memory map: RAM WB, device ranges UC, DMA per config
+    let (_, count) = registry.get_memory_map();
+    for i in 0..count {
+        let Some(desc) = registry.get_descriptor(i) else { continue };
+        let flags = match desc.mem_type {
+            MemoryType::AllocatedDma => MapFlags::dma(config.dma_cache),
+            MemoryType::Mmio | MemoryType::MmioPortSpace | MemoryType::Reserved => MapFlags::MMIO,
+            MemoryType::Unusable => continue,
+            _ => MapFlags::RAM,
+        };
+        let (start, end) = (desc.physical_start, desc.physical_end());
+        space.map_range(pool, start, end, flags)?;
+        if desc.mem_type == MemoryType::AllocatedDma {
+            conflicts += check_mtrr(mtrrs, start, end, config.dma_cache, "DMA");
+        }
+    }
+
+    // 3. PCI memory BARs
+    let bars_mapped = map_pci_bars(&mut space, pool)?;
+
+    // 4. Explicit DMA range
+    if let Some((base, size)) = config.dma {
+        space.map_range(pool, base, base + size, MapFlags::dma(config.dma_cache))?;
+        conflicts += check_mtrr(mtrrs, base, base + size, config.dma_cache, "DMA");
+    }
+
+    Ok((space, bars_mapped, conflicts))
+}
+
+/// Warn when the MTRRs make `[start, end)` behave unlike `wanted`.
+fn check_mtrr(mtrrs: &Mtrrs, start: u64, end: u64, wanted: CacheType, what: &str) -> u32 {
+    let effective = wanted.combine_with_mtrr(mtrrs.worst_type_in(start, end));
+    if effective == wanted {
+        return 0;
+    }
+    puts("[PAGING]   WARNING: ");
+    puts(what);
+    puts(" ");
+    put_hex64(start);
+    puts(" mapped ");
+    puts(wanted.name());
+    puts(" but MTRR makes it ");
+    puts(effective.name());
+    newline();
+    1
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// PCI BARS
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Map every PCI memory BAR UC. Returns the number mapped.
+unsafe fn map_pci_bars(space: &mut AddressSpace, pool: &mut FramePool) -> Result<u32, PagingError> {
+    let mut mapped = 0u32;
+
+    for bus in 0..=255u8 {
+        for device in 0..32u8 {
+            let addr = PciAddr::new(bus, device, 0);
+            let vendor = pci_cfg_read16(addr, offset::VENDOR_ID);
+            if vendor == 0xFFFF || vendor == 0x0000 {
+                continue;
+            }
+
+            let header_type = pci_cfg_read16(addr, offset::HEADER_TYPE) as u8;
+            let functions = if header_type & 0x80 != 0 { 8 } else { 1 };
+            for function in 0..functions {
+                let faddr = PciAddr::new(bus, device, function);
+                let v = pci_cfg_read16(faddr, offset::VENDOR_ID);
+                if v == 0xFFFF || v == 0x0000 {
+                    continue;
+                }
+                mapped += map_function_bars(space, pool, faddr)?;
+            }
+        }
+    }
+
+    Ok(mapped)
+}
+
+/// Map the memory BARs of one function.
+unsafe fn map_function_bars(
+    space: &mut AddressSpace,
+    pool: &mut FramePool,
+    addr: PciAddr,
+) -> Result<u32, PagingError> {
+    // Type 0 headers have 6 BARs, bridges 2
+    let bar_count = match pci_cfg_read16(addr, offset::HEADER_TYPE) as u8 & 0x7F {
+        0 => 6,
+        1 => 2,
+        _ => return Ok(0),
+    };
+
+    let mut mapped = 0u32;
+    let mut bar = 0u8;
+    while bar < bar_count {
+        let (base, size, is_64) = size_bar(addr, bar);
+        bar += if is_64 { 2 } else { 1 };
+        if base == 0 || size == 0 {
+            continue;
+        }
+        // Small BARs share a page with neighbours; mapping whole pages UC
+        // is correct because nothing else lives in BAR space.
+        space.map_range(pool, base, base + size.max(PAGE_SIZE), MapFlags::MMIO)?;
+        mapped += 1;
+    }
+    Ok(mapped)
+}
+
+/// Decode and size a memory BAR: (base, size, is_64bit).
+///
+/// Memory decode is disabled while the all-ones probe value is in place so
+/// the device never claims a bogus range.
+unsafe fn size_bar(addr: PciAddr, bar: u8) -> (u64, u64, bool) {
+    let reg = offset::BAR0 + bar * 4;
+    let low = pci_cfg_read32(addr, reg);
+    if low & 1 != 0 {
+        return (0, 0, false); // I/O BAR
+    }
+    let is_64 = (low >> 1) & 0x3 == 0x2;
+
+    let cmd = pci_cfg_read16(addr, offset::COMMAND);
+    pci_cfg_write16(addr, offset::COMMAND, cmd & !CMD_MEM_SPACE);
+
+    pci_cfg_write32(addr, reg, 0xFFFF_FFFF);
+    let low_mask = pci_cfg_read32(addr, reg);
+    pci_cfg_write32(addr, reg, low);
+
+    let (high, high_mask) = if is_64 {
+        let high = pci_cfg_read32(addr, reg + 4);
+        pci_cfg_write32(addr, reg + 4, 0xFFFF_FFFF);
+        let high_mask = pci_cfg_read32(addr, reg + 4);
+        pci_cfg_write32(addr, reg + 4, high);
+        (high, high_mask)
+    } else {
+        (0, 0xFFFF_FFFF)
+    };
+
+    pci_cfg_write16(addr, offset::COMMAND, cmd);
+
+    let base = ((high as u64) << 32) | (low & !0xF) as u64;
+    let mask = ((high_mask as u64) << 32) | (low_mask & !0xF) as u64;
+    if mask == 0 {
+        return (0, 0, is_64);
+    }
+    let size = (!mask).wrapping_add(1);
+    // A 32-bit BAR reports a 32-bit mask; keep the size within 4GB.
+    let size = if is_64 { size } else { size & 0xFFFF_FFFF };
+    (base, size, is_64)
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// ACTIVATION
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Program the PAT and switch CR3 (SDM §11.12.4 sequence).
+unsafe fn activate(root: u64, pat_supported: bool) {
+    wbinvd();
+    if pat_supported {
+        pat::program_pat();
+    }
+    write_cr3(root);
+    wbinvd();
+}
+
+/// Log a summary of `init_paging`.
+pub fn log_paging_info(info: &PagingInfo) {
+    puts("[PAGING]   CR3=");
+    put_hex64(info.root);
+    puts(" tables=");
+    put_hex32(info.table_pages as u32);
+    puts(" 2M=");
+    put_hex32(info.stats.pages_2m as u32);
+    puts(" 4K=");
+    put_hex32(info.stats.pages_4k as u32);
+    puts(" BARs=");
+    put_hex32(info.bars_mapped);
+    newline();
+    if info.mtrr_conflicts > 0 {
+        puts("[PAGING]   MTRR conflicts: ");
+        put_hex32(info.mtrr_conflicts);
+        newline();
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+unsafe fn read_cr4() -> u64 {
+    let cr4: u64;
+    core::arch::asm!("mov {}, cr4", out(reg) cr4, options(nomem, nostack, preserves_flags));
+    cr4
+}
+
+#[cfg(target_arch = "x86_64")]
+unsafe fn write_cr3(value: u64) {
+    core::arch::asm!("mov cr3, {}", in(reg) value, options(nostack, preserves_flags));
+}
+
+#[cfg(target_arch = "x86_64")]
+unsafe fn wbinvd() {
+    core::arch::asm!("wbinvd", options(nostack, preserves_flags));
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+unsafe fn read_cr4() -> u64 {
+    0
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+unsafe fn write_cr3(_value: u64) {}
+
+#[cfg(not(target_arch = "x86_64"))]
+unsafe fn wbinvd() {}
@@ -0,0 +1,166 @@
+//! Memory Type Range Registers (read-only).
+//!
+//! Firmware programs the MTRRs; we never change them. They matter because
+//! the effective memory type of a page is the PAT type combined with the
+//! MTRR type: a DMA buffer we map WB is still uncached if an MTRR says UC,
+//! and an MMIO BAR the firmware left WB in the MTRRs is only safe because
+//! our PAT entry says UC.
+//!
+//! Fixed-range MTRRs (below 1MB) are not decoded; that range holds no BARs
+//! or DMA buffers.
+//!
+//! # Reference
+//! Intel SDM Vol. 3A §11.11
+
+use super::pat::CacheType;
+use crate::cpu::msr::{cpuid, rdmsr, IA32_MTRRCAP, IA32_MTRR_DEF_TYPE, IA32_MTRR_PHYSBASE0};
+
+/// Variable-range MTRRs we track (CPUs implement 8-10).
+const MAX_VARIABLE: usize = 16;
+
+/// IA32_MTRR_DEF_TYPE.E
+const DEF_TYPE_ENABLE: u64 = 1 << 11;
+/// IA32_MTRR_PHYSMASKn.V
+const PHYSMASK_VALID: u64 = 1 << 11;
+
+/// One enabled variable-range MTRR.
+#[derive(Debug, Clone, Copy)]
+pub struct VariableMtrr {
+    pub base: u64,
+    pub mask: u64,
+    pub cache: CacheType,
+}
+
+impl VariableMtrr {
+    /// Whether `addr` falls in this range.
+    pub fn contains(&self, addr: u64) -> bool {
+        addr & self.mask == self.base & self.mask
+    }
+}
+
+/// Snapshot of the MTRR configuration.
+#[derive(Debug, Clone, Copy)]
+pub struct Mtrrs {
+    /// MTRRs enabled at all (otherwise everything is UC)
+    pub enabled: bool,
+    /// Type for addresses no variable range covers
+    pub default_type: CacheType,
+    variable: [Option<VariableMtrr>; MAX_VARIABLE],
+}
+
+impl Mtrrs {
+    /// No MTRR support: every address reads as WB so PAT alone decides.
+    pub const fn absent() -> Self {
+        Self {
+            enabled: true,
+            default_type: CacheType::WriteBack,
+            variable: [None; MAX_VARIABLE],
+        }
+    }
+
+    /// Read the MTRRs (CPUID.1:EDX bit 12 gates them).
+    pub fn read() -> Self {
+        let (_, _, _, edx) = cpuid(1);
+        if edx & (1 << 12) == 0 {
+            return Self::absent();
+        }
+
+        let phys_bits = physical_address_bits();
+        let addr_mask = ((1u64 << phys_bits) - 1) & !0xFFF;
+
+        unsafe {
+            let cap = rdmsr(IA32_MTRRCAP);
+            let def = rdmsr(IA32_MTRR_DEF_TYPE);
+
+            let mut mtrrs = Self {
+                enabled: def & DEF_TYPE_ENABLE != 0,
+                default_type: CacheType::from_raw(def as u8).unwrap_or(CacheType::Uncached),
+                variable: [None; MAX_VARIABLE],
+            };
+
+            let count = ((cap & 0xFF) as usize).min(MAX_VARIABLE);
+            for i in 0..count {
+                let base = rdmsr(IA32_MTRR_PHYSBASE0 + 2 * i as u32);
+                let mask = rdmsr(IA32_MTRR_PHYSBASE0 + 2 * i as u32 + 1);
+                if mask & PHYSMASK_VALID == 0 {
+                    continue;
+                }
+                if let Some(cache) = CacheType::from_raw(base as u8) {
+                    mtrrs.variable[i] = Some(VariableMtrr {
+                        base: base & addr_mask,
+                        mask: mask & addr_mask,
+                        cache,
+                    });
+                }
+            }
+            mtrrs
+        }
+    }
+
+    /// MTRR memory type at `addr`.
+    pub fn type_at(&self, addr: u64) -> CacheType {
+        if !self.enabled {
+            return CacheType::Uncached;
+        }
+
+        let mut found: Option<CacheType> = None;
+        for mtrr in self.variable.iter().flatten() {
+            if !mtrr.contains(addr) {
+                continue;
+            }
+            // Overlap rules: UC wins, WT beats WB, otherwise undefined
+            // (first match kept).
+            found = Some(match (found, mtrr.cache) {
+                (None, c) => c,
+                (Some(CacheType::Uncached), _) | (_, CacheType::Uncached) => CacheType::Uncached,
+                (Some(CacheType::WriteThrough), CacheType::WriteBack)
+                | (Some(CacheType::WriteBack), CacheType::WriteThrough) => CacheType::WriteThrough,
+                (Some(c), _) => c,
+            });
+        }
+        found.unwrap_or(self.default_type)
+    }
+
+    /// Strongest (least cacheable) MTRR type anywhere in `[start, end)`.
+    ///
+    /// Checked at 4KB granularity at range boundaries and at every MTRR
+    /// base inside the range, which is where the type can change.
+    pub fn worst_type_in(&self, start: u64, end: u64) -> CacheType {
+        let mut worst = self.type_at(start);
+        let mut consider = |t: CacheType| {
+            if rank(t) < rank(worst) {
+                worst = t;
+            }
+        };
+        consider(self.type_at(end.saturating_sub(1)));
+        for mtrr in self.variable.iter().flatten() {
+            if mtrr.base > start && mtrr.base < end {
+                consider(self.type_at(mtrr.base));
+            }
+        }
+        worst
+    }
+}
+
+/// Cacheability order used to find the "worst" type in a range.
+fn rank(t: CacheType) -> u8 {
+    match t {
+        CacheType::Uncached | CacheType::UncachedMinus => 0,
+        CacheType::WriteCombining => 1,
+        CacheType::WriteThrough | CacheType::WriteProtect => 2,
+        CacheType::WriteBack => 3,
+    }
+}
+
+/// Physical address width (CPUID 0x80000008, default 36).
+pub fn physical_address_bits() -> u32 {
+    let (max_ext, _, _, _) = cpuid(0x8000_0000);
+    if max_ext >= 0x8000_0008 {
+        let (eax, _, _, _) = cpuid(0x8000_0008);
+        let bits = eax & 0xFF;
+        if (32..=52).contains(&bits) {
+            return bits;
+        }
+    }
+    36
+}
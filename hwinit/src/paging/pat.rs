@@ -0,0 +1,132 @@
+//! Page Attribute Table.
+//!
+//! The PAT turns the PWT/PCD/PAT bits of a page table entry into a memory
+//! type. Entries 0-3 keep their power-on defaults (WB, WT, UC-, UC) so the
+//! firmware's page tables mean the same thing while we build ours; entry 4
+//! is repurposed for write-combining and 5 for write-protect.
+//!
+//! # Reference
+//! Intel SDM Vol. 3A §11.12
+
+use crate::cpu::msr::{cpuid, rdmsr, wrmsr, IA32_PAT};
+
+/// Memory types as encoded in PAT and MTRR registers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum CacheType {
+    /// Strong uncacheable - MMIO registers
+    Uncached = 0x00,
+    /// Write-combining - framebuffers
+    WriteCombining = 0x01,
+    /// Write-through
+    WriteThrough = 0x04,
+    /// Write-protect
+    WriteProtect = 0x05,
+    /// Write-back - normal RAM (and DMA buffers: x86 DMA is snooped)
+    WriteBack = 0x06,
+    /// UC- (PAT only): uncacheable unless the MTRR says WC
+    UncachedMinus = 0x07,
+}
+
+impl CacheType {
+    /// Decode a PAT / MTRR type field.
+    pub fn from_raw(raw: u8) -> Option<Self> {
+        match raw {
+            0x00 => Some(Self::Uncached),
+            0x01 => Some(Self::WriteCombining),
+            0x04 => Some(Self::WriteThrough),
+            0x05 => Some(Self::WriteProtect),
+            0x06 => Some(Self::WriteBack),
+            0x07 => Some(Self::UncachedMinus),
+            _ => None,
+        }
+    }
+
+    /// Short name for logs.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Uncached => "UC",
+            Self::WriteCombining => "WC",
+            Self::WriteThrough => "WT",
+            Self::WriteProtect => "WP",
+            Self::WriteBack => "WB",
+            Self::UncachedMinus => "UC-",
+        }
+    }
+
+    /// PAT index selecting this type under `PAT_LAYOUT`.
+    ///
+    /// Without PAT only indices 0-3 exist; WC and WP degrade to UC there.
+    pub fn pat_index(self, pat_supported: bool) -> u8 {
+        match self {
+            Self::WriteBack => 0,
+            Self::WriteThrough => 1,
+            Self::UncachedMinus => 2,
+            Self::Uncached => 3,
+            Self::WriteCombining if pat_supported => 4,
+            Self::WriteProtect if pat_supported => 5,
+            Self::WriteCombining | Self::WriteProtect => 3,
+        }
+    }
+
+    /// Memory type the CPU actually uses for a page with PAT type `self`
+    /// inside an MTRR range of type `mtrr` (SDM table 11-7).
+    pub fn combine_with_mtrr(self, mtrr: CacheType) -> CacheType {
+        use CacheType::*;
+        match (self, mtrr) {
+            (Uncached, _) => Uncached,
+            (UncachedMinus, WriteCombining) => WriteCombining,
+            (UncachedMinus, _) => Uncached,
+            (WriteCombining, _) => WriteCombining,
+            (WriteBack, m) => m,
+            (_, Uncached) | (_, WriteCombining) | (_, UncachedMinus) => Uncached,
+            (WriteThrough, WriteProtect) | (WriteProtect, _) => WriteProtect,
+            (WriteThrough, _) => WriteThrough,
+        }
+    }
+}
+
+/// PAT contents we program, index 0 first.
+pub const PAT_LAYOUT: [CacheType; 8] = [
+    CacheType::WriteBack,
+    CacheType::WriteThrough,
+    CacheType::UncachedMinus,
+    CacheType::Uncached,
+    CacheType::WriteCombining,
+    CacheType::WriteProtect,
+    CacheType::UncachedMinus,
+    CacheType::Uncached,
+];
+
+/// `PAT_LAYOUT` as an IA32_PAT value.
+pub const fn pat_msr_value() -> u64 {
+    let mut value = 0u64;
+    let mut i = 0;
+    while i < 8 {
+        value |= (PAT_LAYOUT[i] as u64) << (i * 8);
+        i += 1;
+    }
+    value
+}
+
+/// Whether the CPU has a PAT (CPUID.1:EDX bit 16).
+pub fn pat_supported() -> bool {
+    let (_, _, _, edx) = cpuid(1);
+    edx & (1 << 16) != 0
+}
+
+/// Program IA32_PAT with `PAT_LAYOUT`.
+///
+/// # Safety
+/// Caches must be flushed around the change (see `paging::activate`).
+pub unsafe fn program_pat() {
+    wrmsr(IA32_PAT, pat_msr_value());
+}
+
+/// Current IA32_PAT value.
+///
+/// # Safety
+/// PAT must be supported.
+pub unsafe fn read_pat() -> u64 {
+    rdmsr(IA32_PAT)
+}
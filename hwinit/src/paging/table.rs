@@ -0,0 +1,303 @@
+//! 4-level page table construction.
+//!
+//! Builds identity mappings with 2MB pages where a range allows it and
+//! 4KB pages elsewhere. Mapping a sub-range of an existing 2MB page splits
+//! it into a page table that preserves the old attributes, so callers can
+//! map coarse defaults first and refine them afterwards.
+//!
+//! Table frames come from a `FramePool` carved out up front, which keeps
+//! the memory registry stable while we walk it.
+
+use super::pat::CacheType;
+
+// ═══════════════════════════════════════════════════════════════════════════
+// ENTRY BITS
+// ═══════════════════════════════════════════════════════════════════════════
+
+const PRESENT: u64 = 1 << 0;
+const WRITABLE: u64 = 1 << 1;
+const PWT: u64 = 1 << 3;
+const PCD: u64 = 1 << 4;
+/// PS in a PDE, PAT in a PTE
+const HUGE_OR_PAT_4K: u64 = 1 << 7;
+/// PAT bit of a 2MB/1GB entry
+const PAT_LARGE: u64 = 1 << 12;
+const NO_EXECUTE: u64 = 1 << 63;
+
+const ADDR_MASK: u64 = 0x000F_FFFF_FFFF_F000;
+const ADDR_MASK_2M: u64 = 0x000F_FFFF_FFE0_0000;
+
+pub const PAGE_4K: u64 = 4096;
+pub const PAGE_2M: u64 = 2 * 1024 * 1024;
+
+const ENTRIES: usize = 512;
+
+// ═══════════════════════════════════════════════════════════════════════════
+// MAPPING ATTRIBUTES
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// How a range is mapped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MapFlags {
+    pub cache: CacheType,
+    pub writable: bool,
+    pub no_execute: bool,
+}
+
+impl MapFlags {
+    /// Normal RAM: WB, RW, executable.
+    pub const RAM: Self = Self {
+        cache: CacheType::WriteBack,
+        writable: true,
+        no_execute: false,
+    };
+
+    /// Device registers: UC, RW, NX.
+    pub const MMIO: Self = Self {
+        cache: CacheType::Uncached,
+        writable: true,
+        no_execute: true,
+    };
+
+    /// Device-visible buffers: NX, caller picks the cache type.
+    pub const fn dma(cache: CacheType) -> Self {
+        Self {
+            cache,
+            writable: true,
+            no_execute: true,
+        }
+    }
+}
+
+/// Errors building page tables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PagingError {
+    /// Frame pool exhausted
+    OutOfFrames,
+    /// Could not reserve the frame pool
+    PoolAllocationFailed,
+    /// Firmware enabled 5-level paging; we only build 4 levels
+    FiveLevelPaging,
+    /// Address beyond the 48-bit canonical range
+    AddressTooHigh,
+}
+
+impl PagingError {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::OutOfFrames => "page table frame pool exhausted",
+            Self::PoolAllocationFailed => "could not allocate page table pool",
+            Self::FiveLevelPaging => "5-level paging active",
+            Self::AddressTooHigh => "address above 256TB",
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// FRAME POOL
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Bump allocator over a contiguous, identity-mapped run of pages.
+pub struct FramePool {
+    base: u64,
+    pages: u64,
+    used: u64,
+}
+
+impl FramePool {
+    /// # Safety
+    /// `base..base + pages * 4KB` must be free RAM, identity mapped under
+    /// the current page tables and reserved for page tables.
+    pub const unsafe fn new(base: u64, pages: u64) -> Self {
+        Self {
+            base,
+            pages,
+            used: 0,
+        }
+    }
+
+    /// Next zeroed frame.
+    fn alloc(&mut self) -> Result<u64, PagingError> {
+        if self.used >= self.pages {
+            return Err(PagingError::OutOfFrames);
+        }
+        let frame = self.base + self.used * PAGE_4K;
+        self.used += 1;
+        unsafe { core::ptr::write_bytes(frame as *mut u8, 0, PAGE_4K as usize) };
+        Ok(frame)
+    }
+
+    /// Frames handed out so far.
+    pub fn used(&self) -> u64 {
+        self.used
+    }
+
+    /// Unused tail of the pool, as (address, pages).
+    pub fn remaining(&self) -> (u64, u64) {
+        (self.base + self.used * PAGE_4K, self.pages - self.used)
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// ADDRESS SPACE
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Mapping statistics.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MapStats {
+    pub pages_2m: u64,
+    pub pages_4k: u64,
+    pub splits: u64,
+}
+
+/// An identity-mapped 4-level address space under construction.
+pub struct AddressSpace {
+    pml4: u64,
+    pat_supported: bool,
+    nx_enabled: bool,
+    stats: MapStats,
+}
+
+impl AddressSpace {
+    /// Allocate an empty PML4.
+    ///
+    /// `nx_enabled` must reflect EFER.NXE: the NX bit is reserved (and
+    /// faults) when it is clear.
+    pub fn new(
+        pool: &mut FramePool,
+        pat_supported: bool,
+        nx_enabled: bool,
+    ) -> Result<Self, PagingError> {
+        Ok(Self {
+            pml4: pool.alloc()?,
+            pat_supported,
+            nx_enabled,
+            stats: MapStats::default(),
+        })
+    }
+
+    /// Physical address of the PML4 (the CR3 value).
+    pub fn root(&self) -> u64 {
+        self.pml4
+    }
+
+    pub fn stats(&self) -> MapStats {
+        self.stats
+    }
+
+    /// Identity map `[start, end)` (rounded out to 4KB) with `flags`,
+    /// replacing whatever was mapped there before.
+    pub fn map_range(
+        &mut self,
+        pool: &mut FramePool,
+        start: u64,
+        end: u64,
+        flags: MapFlags,
+    ) -> Result<(), PagingError> {
+        let mut addr = start & !(PAGE_4K - 1);
+        let end = (end + PAGE_4K - 1) & !(PAGE_4K - 1);
+        if end > 1 << 48 {
+            return Err(PagingError::AddressTooHigh);
+        }
+
+        while addr < end {
+            if addr.is_multiple_of(PAGE_2M) && end - addr >= PAGE_2M {
+                self.map_2m(pool, addr, flags)?;
+                addr += PAGE_2M;
+            } else {
+                self.map_4k(pool, addr, flags)?;
+                addr += PAGE_4K;
+            }
+        }
+        Ok(())
+    }
+
+    fn leaf_bits(&self, flags: MapFlags, large: bool) -> u64 {
+        let mut bits = PRESENT;
+        if flags.writable {
+            bits |= WRITABLE;
+        }
+        if flags.no_execute && self.nx_enabled {
+            bits |= NO_EXECUTE;
+        }
+        let index = flags.cache.pat_index(self.pat_supported);
+        if index & 1 != 0 {
+            bits |= PWT;
+        }
+        if index & 2 != 0 {
+            bits |= PCD;
+        }
+        if index & 4 != 0 {
+            bits |= if large { PAT_LARGE } else { HUGE_OR_PAT_4K };
+        }
+        if large {
+            bits |= HUGE_OR_PAT_4K;
+        }
+        bits
+    }
+
+    fn map_2m(&mut self, pool: &mut FramePool, addr: u64, flags: MapFlags) -> Result<(), PagingError> {
+        let pd = self.page_directory(pool, addr)?;
+        let entry = unsafe { &mut *pd.add(index(addr, 21)) };
+        // A page table already here is abandoned; mapping large ranges
+        // first keeps that rare.
+        *entry = addr | self.leaf_bits(flags, true);
+        self.stats.pages_2m += 1;
+        Ok(())
+    }
+
+    fn map_4k(&mut self, pool: &mut FramePool, addr: u64, flags: MapFlags) -> Result<(), PagingError> {
+        let pd = self.page_directory(pool, addr)?;
+        let pde = unsafe { &mut *pd.add(index(addr, 21)) };
+
+        if *pde & PRESENT == 0 {
+            *pde = pool.alloc()? | PRESENT | WRITABLE;
+        } else if *pde & HUGE_OR_PAT_4K != 0 {
+            *pde = split_2m(pool, *pde)? | PRESENT | WRITABLE;
+            self.stats.splits += 1;
+        }
+
+        let pt = (*pde & ADDR_MASK) as *mut u64;
+        unsafe { *pt.add(index(addr, 12)) = addr | self.leaf_bits(flags, false) };
+        self.stats.pages_4k += 1;
+        Ok(())
+    }
+
+    /// Page directory covering `addr`, creating intermediate tables.
+    fn page_directory(&mut self, pool: &mut FramePool, addr: u64) -> Result<*mut u64, PagingError> {
+        let pdpt = next_table(pool, self.pml4 as *mut u64, index(addr, 39))?;
+        next_table(pool, pdpt, index(addr, 30))
+    }
+}
+
+/// Table index of `addr` at the level whose entries cover `1 << shift`.
+fn index(addr: u64, shift: u32) -> usize {
+    ((addr >> shift) & 0x1FF) as usize
+}
+
+/// Follow (or create) the entry `idx` of `table`.
+fn next_table(pool: &mut FramePool, table: *mut u64, idx: usize) -> Result<*mut u64, PagingError> {
+    let entry = unsafe { &mut *table.add(idx) };
+    if *entry & PRESENT == 0 {
+        *entry = pool.alloc()? | PRESENT | WRITABLE;
+    }
+    Ok((*entry & ADDR_MASK) as *mut u64)
+}
+
+/// Page table equivalent to the 2MB page `pde`.
+fn split_2m(pool: &mut FramePool, pde: u64) -> Result<u64, PagingError> {
+    let pt = pool.alloc()?;
+    let base = pde & ADDR_MASK_2M;
+
+    // Same bits, PS dropped and PAT moved from bit 12 to bit 7.
+    let mut bits = pde & !(ADDR_MASK | HUGE_OR_PAT_4K);
+    if pde & PAT_LARGE != 0 {
+        bits |= HUGE_OR_PAT_4K;
+    }
+
+    let entries = pt as *mut u64;
+    for i in 0..ENTRIES {
+        unsafe { *entries.add(i) = (base + i as u64 * PAGE_4K) | bits };
+    }
+    Ok(pt)
+}
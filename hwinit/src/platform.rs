@@ -20,6 +20,8 @@
 //! │  6. Calibrate TSC (timing works)                             │
 //! │  7. Allocate DMA region (DMA legal)                          │
 //! │  8. Enable bus mastering on PCI devices                      │
+//! │  9. Load our own page tables (UC BARs, WB RAM/DMA)           │
+//! │ 10. Start application processors (work queue)                │
 //! │                                                              │
 //! │  Result: Machine is SANE. Drivers just do driver work.       │
 //! └──────────────────────────────────────────────────────────────┘
@@ -41,6 +43,7 @@
 //!     memory_map_size: map_size,
 //!     descriptor_size: desc_size,
 //!     descriptor_version: desc_version,
+//!     rsdp: rsdp_phys, // 0 = single core
 //! })? };
 //!
 //! // Now safe to use:
@@ -62,6 +65,8 @@ use crate::cpu::gdt::init_gdt;
 use crate::cpu::idt::init_idt;
 use crate::cpu::pic::init_pic;
 use crate::heap::init_heap;
+use crate::paging::{init_paging, log_paging_info, pat, PagingConfig};
+use crate::smp::start_aps;
 use crate::pci::{pci_cfg_read16, pci_cfg_write16, PciAddr, offset};
 use crate::serial::{puts, put_hex32, put_hex64, newline};
 
@@ -94,6 +99,9 @@ pub struct SelfContainedConfig {
     pub descriptor_size: usize,
     /// Descriptor version (from UEFI)
     pub descriptor_version: u32,
+    /// ACPI RSDP physical address (from the UEFI configuration table),
+    /// 0 if unknown. Needed to find the other CPUs.
+    pub rsdp: u64,
 }
 
 /// Platform configuration input (legacy - externally allocated).
@@ -115,6 +123,8 @@ pub struct PlatformInit {
     pub dma_region: DmaRegion,
     /// Physical allocator for additional allocations
     pub allocator: PhysicalAllocator,
+    /// CPUs running, BSP included (APs serve `smp::work`)
+    pub cpus_online: usize,
 }
 
 /// Initialization error.
@@ -289,6 +299,46 @@ pub unsafe fn platform_init_selfcontained(
     put_hex32(devices_enabled as u32);
     puts(" devices\n");
 
+    // ─────────────────────────────────────────────────────────────────────
+    // PHASE 8: PAGING - Our page tables, BARs uncached
+    // ─────────────────────────────────────────────────────────────────────
+    puts("[HWINIT] Phase 8: Page tables\n");
+
+    let paging_config = PagingConfig {
+        dma: Some((dma_phys, DMA_SIZE as u64)),
+        ..PagingConfig::default()
+    };
+    let paging_ok = match init_paging(global_registry_mut(), &paging_config) {
+        Ok(info) => {
+            log_paging_info(&info);
+            true
+        }
+        Err(e) => {
+            // Not fatal: the firmware mapping worked before this existed.
+            puts("[HWINIT]   WARNING: keeping firmware page tables: ");
+            puts(e.as_str());
+            newline();
+            false
+        }
+    };
+
+    // ─────────────────────────────────────────────────────────────────────
+    // PHASE 9: SMP - Wake the other cores for CPU-bound work
+    // ─────────────────────────────────────────────────────────────────────
+    puts("[HWINIT] Phase 9: Application processors\n");
+
+    let pat_programmed = paging_ok && pat::pat_supported();
+    let cpus_online = match start_aps(global_registry_mut(), config.rsdp, tsc_freq, pat_programmed) {
+        Ok(info) => info.cpus_online,
+        Err(e) => {
+            // Not fatal: everything runs on the BSP.
+            puts("[HWINIT]   single core: ");
+            puts(e.as_str());
+            newline();
+            1
+        }
+    };
+
     // ─────────────────────────────────────────────────────────────────────
     // DONE - Machine is sane
     // ─────────────────────────────────────────────────────────────────────
@@ -303,6 +353,7 @@ pub unsafe fn platform_init_selfcontained(
         tsc_freq,
         dma_region,
         allocator,
+        cpus_online,
     })
 }
 
@@ -346,6 +397,7 @@ pub unsafe fn platform_init(config: PlatformConfig) -> Result<PlatformInit, Init
         tsc_freq: config.tsc_freq,
         dma_region,
         allocator,
+        cpus_online: 1,
     })
 }
 
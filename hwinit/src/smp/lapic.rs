@@ -0,0 +1,104 @@
+//! Local APIC access for AP start-up.
+//!
+//! Only the pieces INIT-SIPI-SIPI needs: our own APIC ID and the
+//! interrupt command register. Works in both xAPIC (MMIO) and x2APIC
+//! (MSR) mode, whichever the firmware left enabled.
+//!
+//! # Reference
+//! Intel SDM Vol. 3A §10.6 (ICR), §8.4.4 (MP initialization)
+
+use crate::cpu::mmio::{read32, write32};
+use crate::cpu::msr::{rdmsr, wrmsr, IA32_APIC_BASE};
+
+/// IA32_APIC_BASE.EXTD - x2APIC mode
+const APIC_BASE_X2APIC: u64 = 1 << 10;
+/// IA32_APIC_BASE.EN
+const APIC_BASE_ENABLE: u64 = 1 << 11;
+
+// xAPIC register offsets
+const REG_ID: u64 = 0x20;
+const REG_ICR_LOW: u64 = 0x300;
+const REG_ICR_HIGH: u64 = 0x310;
+
+// x2APIC MSRs
+const MSR_X2APIC_ID: u32 = 0x802;
+const MSR_X2APIC_ICR: u32 = 0x830;
+
+// ICR fields
+const ICR_INIT: u32 = 0b101 << 8;
+const ICR_STARTUP: u32 = 0b110 << 8;
+const ICR_DELIVERY_PENDING: u32 = 1 << 12;
+const ICR_LEVEL_ASSERT: u32 = 1 << 14;
+
+/// The local APIC of the current core.
+#[derive(Debug, Clone, Copy)]
+pub struct LocalApic {
+    /// MMIO base (xAPIC mode only)
+    base: u64,
+    x2apic: bool,
+}
+
+impl LocalApic {
+    /// Current mode and base, from IA32_APIC_BASE.
+    ///
+    /// `None` if the APIC is globally disabled.
+    ///
+    /// # Safety
+    /// The CPU must have an APIC (CPUID.1:EDX bit 9).
+    pub unsafe fn current() -> Option<Self> {
+        let msr = rdmsr(IA32_APIC_BASE);
+        if msr & APIC_BASE_ENABLE == 0 {
+            return None;
+        }
+        Some(Self {
+            base: msr & 0x000F_FFFF_FFFF_F000,
+            x2apic: msr & APIC_BASE_X2APIC != 0,
+        })
+    }
+
+    pub fn is_x2apic(&self) -> bool {
+        self.x2apic
+    }
+
+    /// APIC ID of the current core.
+    pub fn id(&self) -> u32 {
+        unsafe {
+            if self.x2apic {
+                rdmsr(MSR_X2APIC_ID) as u32
+            } else {
+                read32(self.base + REG_ID) >> 24
+            }
+        }
+    }
+
+    /// Send INIT to `apic_id`.
+    ///
+    /// # Safety
+    /// Resets the target core.
+    pub unsafe fn send_init(&self, apic_id: u32) {
+        self.send_ipi(apic_id, ICR_INIT | ICR_LEVEL_ASSERT);
+    }
+
+    /// Send a start-up IPI: the target starts in real mode at
+    /// `vector << 12`.
+    ///
+    /// # Safety
+    /// The target must be in wait-for-SIPI state with code at that page.
+    pub unsafe fn send_startup(&self, apic_id: u32, vector: u8) {
+        self.send_ipi(apic_id, ICR_STARTUP | vector as u32);
+    }
+
+    unsafe fn send_ipi(&self, apic_id: u32, low: u32) {
+        if self.x2apic {
+            wrmsr(MSR_X2APIC_ICR, ((apic_id as u64) << 32) | low as u64);
+        } else {
+            write32(self.base + REG_ICR_HIGH, apic_id << 24);
+            write32(self.base + REG_ICR_LOW, low);
+            // x2APIC has no delivery status; xAPIC needs the ICR idle
+            // before it is written again.
+            while read32(self.base + REG_ICR_LOW) & ICR_DELIVERY_PENDING != 0 {
+                core::hint::spin_loop();
+            }
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! MADT (Multiple APIC Description Table) parsing.
+//!
+//! Lists the processors' local APIC IDs and the local APIC base.
+//!
+//! # Reference
+//! ACPI 6.5 §5.2.12
+
+use super::MAX_CPUS;
+use crate::acpi::{find_table, table_bytes, SDT_HEADER_LEN};
+
+/// Processor Local APIC
+const ENTRY_LOCAL_APIC: u8 = 0;
+/// Local APIC Address Override
+const ENTRY_LAPIC_OVERRIDE: u8 = 5;
+/// Processor Local x2APIC
+const ENTRY_LOCAL_X2APIC: u8 = 9;
+
+/// Processor is usable now
+const FLAG_ENABLED: u32 = 1 << 0;
+/// Processor can be brought online (ACPI 6.3+)
+const FLAG_ONLINE_CAPABLE: u32 = 1 << 1;
+
+/// Processors and local APIC base from the MADT.
+#[derive(Debug, Clone, Copy)]
+pub struct Madt {
+    /// Physical address of the local APIC registers
+    pub lapic_base: u64,
+    apic_ids: [u32; MAX_CPUS],
+    count: usize,
+    /// Enabled processors we had no room for
+    pub dropped: usize,
+}
+
+impl Madt {
+    /// Find and parse the MADT.
+    ///
+    /// # Safety
+    /// See `acpi::find_table`.
+    pub unsafe fn from_rsdp(rsdp: u64) -> Option<Self> {
+        let table = find_table(rsdp, b"APIC")?;
+        Self::parse(table_bytes(table))
+    }
+
+    /// Parse a raw MADT.
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < SDT_HEADER_LEN + 8 || &bytes[0..4] != b"APIC" {
+            return None;
+        }
+
+        let mut madt = Self {
+            lapic_base: u32::from_le_bytes(bytes[36..40].try_into().ok()?) as u64,
+            apic_ids: [0; MAX_CPUS],
+            count: 0,
+            dropped: 0,
+        };
+
+        let mut off = SDT_HEADER_LEN + 8;
+        while off + 2 <= bytes.len() {
+            let kind = bytes[off];
+            let len = bytes[off + 1] as usize;
+            if len < 2 || off + len > bytes.len() {
+                break;
+            }
+            let entry = &bytes[off..off + len];
+            match kind {
+                ENTRY_LOCAL_APIC if len >= 8 => {
+                    let flags = u32::from_le_bytes(entry[4..8].try_into().ok()?);
+                    madt.push(entry[3] as u32, flags);
+                }
+                ENTRY_LOCAL_X2APIC if len >= 16 => {
+                    let id = u32::from_le_bytes(entry[4..8].try_into().ok()?);
+                    let flags = u32::from_le_bytes(entry[8..12].try_into().ok()?);
+                    madt.push(id, flags);
+                }
+                ENTRY_LAPIC_OVERRIDE if len >= 12 => {
+                    madt.lapic_base = u64::from_le_bytes(entry[4..12].try_into().ok()?);
+                }
+                _ => {}
+            }
+            off += len;
+        }
+        Some(madt)
+    }
+
+    fn push(&mut self, apic_id: u32, flags: u32) {
+        if flags & (FLAG_ENABLED | FLAG_ONLINE_CAPABLE) == 0 {
+            return;
+        }
+        // Firmware may list a CPU in both the xAPIC and x2APIC entries.
+        if self.apic_ids().contains(&apic_id) {
+            return;
+        }
+        if self.count == MAX_CPUS {
+            self.dropped += 1;
+            return;
+        }
+        self.apic_ids[self.count] = apic_id;
+        self.count += 1;
+    }
+
+    /// APIC IDs of all usable processors, BSP included.
+    pub fn apic_ids(&self) -> &[u32] {
+        &self.apic_ids[..self.count]
+    }
+}
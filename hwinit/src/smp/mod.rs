@@ -0,0 +1,237 @@
+//! Symmetric multiprocessing: application processor bring-up.
+//!
+//! After ExitBootServices only the bootstrap processor (BSP) runs; the
+//! firmware's MP services are gone. We find the other cores in the MADT,
+//! wake each with INIT-SIPI-SIPI into a real-mode trampoline that enters
+//! long mode on our page tables, and park them in the `work` queue.
+//!
+//! # Per-AP state
+//!
+//! | What      | Source                                   |
+//! |-----------|------------------------------------------|
+//! | Stack     | 64KB from the registry (AllocatedStack)  |
+//! | GDT / IDT | Shared with the BSP (no TSS, no IST)     |
+//! | CR3       | The BSP's (identity mapped)              |
+//! | PAT       | Programmed like the BSP's                |
+//!
+//! APs never take interrupts and only run work queue jobs, so they need
+//! nothing else.
+//!
+//! # Reference
+//! Intel SDM Vol. 3A §8.4 (MP initialization)
+
+pub mod lapic;
+pub mod madt;
+pub mod trampoline;
+pub mod work;
+
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use crate::cpu::pic::apic_available;
+use crate::cpu::tsc::read_tsc;
+use crate::memory::{AllocateType, MemoryRegistry, MemoryType, PAGE_SIZE};
+use crate::paging::pat;
+use crate::serial::{newline, put_hex32, puts};
+use lapic::LocalApic;
+use madt::Madt;
+use trampoline::Trampoline;
+
+pub use work::{spawn, worker_count, JobFn};
+
+/// Maximum number of CPUs (BSP included) we bring up.
+pub const MAX_CPUS: usize = 16;
+
+/// Stack per AP.
+const AP_STACK_SIZE: usize = 64 * 1024;
+
+/// Highest address (exclusive) a SIPI can start an AP at.
+const TRAMPOLINE_LIMIT: u64 = 0x10_0000;
+
+/// Set by an AP once it is on its own stack and off the trampoline.
+static AP_STARTED: AtomicBool = AtomicBool::new(false);
+
+/// CPUs running, BSP included.
+static CPUS_ONLINE: AtomicUsize = AtomicUsize::new(1);
+
+/// Whether APs should program the PAT (set if the BSP did).
+static AP_PROGRAM_PAT: AtomicBool = AtomicBool::new(false);
+
+/// Result of AP bring-up.
+#[derive(Debug, Clone, Copy)]
+pub struct SmpInfo {
+    /// Usable CPUs listed in the MADT, BSP included
+    pub cpus_found: usize,
+    /// CPUs running, BSP included
+    pub cpus_online: usize,
+    /// APIC ID of the BSP
+    pub bsp_apic_id: u32,
+}
+
+/// Why APs were not started.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmpError {
+    /// No RSDP was handed over
+    NoRsdp,
+    /// No MADT in the ACPI tables
+    NoMadt,
+    /// CPU has no (enabled) local APIC
+    NoApic,
+    /// Page tables above 4GB; the trampoline loads CR3 in 32-bit mode
+    PageTablesAbove4G,
+    /// No free page below 1MB for the trampoline
+    NoTrampolinePage,
+}
+
+impl SmpError {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::NoRsdp => "no ACPI RSDP",
+            Self::NoMadt => "no MADT",
+            Self::NoApic => "no local APIC",
+            Self::PageTablesAbove4G => "page tables above 4GB",
+            Self::NoTrampolinePage => "no low page for trampoline",
+        }
+    }
+}
+
+/// CPUs running, BSP included.
+pub fn cpus_online() -> usize {
+    CPUS_ONLINE.load(Ordering::Acquire)
+}
+
+/// Start every AP listed in the MADT and put it to work.
+///
+/// APs are started one at a time because they share the trampoline page.
+/// An AP that does not check in within 100ms is skipped.
+///
+/// # Safety
+/// - Call once, on the BSP, after `init_gdt`, `init_idt` and (if used)
+///   `init_paging`
+/// - `rsdp` must be 0 or the firmware's RSDP
+pub unsafe fn start_aps(
+    registry: &mut MemoryRegistry,
+    rsdp: u64,
+    tsc_freq: u64,
+    pat_programmed: bool,
+) -> Result<SmpInfo, SmpError> {
+    if rsdp == 0 {
+        return Err(SmpError::NoRsdp);
+    }
+    let madt = Madt::from_rsdp(rsdp).ok_or(SmpError::NoMadt)?;
+    if !apic_available() {
+        return Err(SmpError::NoApic);
+    }
+    let apic = LocalApic::current().ok_or(SmpError::NoApic)?;
+    let bsp_id = apic.id();
+
+    let cr3 = read_cr3();
+    if cr3 >= 0x1_0000_0000 {
+        return Err(SmpError::PageTablesAbove4G);
+    }
+
+    let page = registry
+        .allocate_pages(AllocateType::MaxAddress(TRAMPOLINE_LIMIT), MemoryType::Allocated, 1)
+        .map_err(|_| SmpError::NoTrampolinePage)?;
+    let trampoline = Trampoline::install(page, cr3, ap_main);
+    AP_PROGRAM_PAT.store(pat_programmed, Ordering::Release);
+
+    puts("[SMP] BSP APIC ");
+    put_hex32(bsp_id);
+    puts(", ");
+    put_hex32(madt.apic_ids().len() as u32);
+    puts(" CPUs in MADT");
+    if apic.is_x2apic() {
+        puts(" (x2APIC)");
+    }
+    newline();
+
+    let stack_pages = (AP_STACK_SIZE as u64).div_ceil(PAGE_SIZE);
+    for &apic_id in madt.apic_ids() {
+        if apic_id == bsp_id {
+            continue;
+        }
+        let Ok(stack) = registry.alloc_stack(stack_pages) else {
+            puts("[SMP] out of memory for AP stacks\n");
+            break;
+        };
+        let cpu_index = cpus_online() as u64;
+        trampoline.prepare(stack + AP_STACK_SIZE as u64, cpu_index);
+        AP_STARTED.store(false, Ordering::Release);
+
+        if wake(&apic, apic_id, trampoline.vector(), tsc_freq) {
+            CPUS_ONLINE.fetch_add(1, Ordering::AcqRel);
+        } else {
+            puts("[SMP] AP ");
+            put_hex32(apic_id);
+            puts(" did not start\n");
+            let _ = registry.free_pages(stack, stack_pages);
+        }
+    }
+
+    let info = SmpInfo {
+        cpus_found: madt.apic_ids().len(),
+        cpus_online: cpus_online(),
+        bsp_apic_id: bsp_id,
+    };
+    puts("[SMP] ");
+    put_hex32(info.cpus_online as u32);
+    puts(" CPUs online\n");
+    Ok(info)
+}
+
+/// INIT-SIPI-SIPI one AP and wait for it to check in.
+unsafe fn wake(apic: &LocalApic, apic_id: u32, vector: u8, tsc_freq: u64) -> bool {
+    apic.send_init(apic_id);
+    delay_us(tsc_freq, 10_000);
+
+    for _ in 0..2 {
+        apic.send_startup(apic_id, vector);
+        if wait_started(tsc_freq, 200) {
+            return true;
+        }
+    }
+    wait_started(tsc_freq, 100_000)
+}
+
+fn wait_started(tsc_freq: u64, us: u64) -> bool {
+    let deadline = read_tsc() + tsc_freq / 1_000_000 * us;
+    while read_tsc() < deadline {
+        if AP_STARTED.load(Ordering::Acquire) {
+            return true;
+        }
+        core::hint::spin_loop();
+    }
+    AP_STARTED.load(Ordering::Acquire)
+}
+
+fn delay_us(tsc_freq: u64, us: u64) {
+    let deadline = read_tsc() + tsc_freq / 1_000_000 * us;
+    while read_tsc() < deadline {
+        core::hint::spin_loop();
+    }
+}
+
+/// First Rust code on an AP.
+extern "sysv64" fn ap_main(_cpu_index: u64) -> ! {
+    unsafe {
+        crate::cpu::gdt::load_gdt_ap();
+        crate::cpu::idt::load_idt_ap();
+        if AP_PROGRAM_PAT.load(Ordering::Acquire) {
+            pat::program_pat();
+        }
+    }
+    AP_STARTED.store(true, Ordering::Release);
+    work::worker_loop()
+}
+
+#[cfg(target_arch = "x86_64")]
+fn read_cr3() -> u64 {
+    let cr3: u64;
+    unsafe { core::arch::asm!("mov {}, cr3", out(reg) cr3, options(nomem, nostack, preserves_flags)) };
+    cr3 & !0xFFF
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn read_cr3() -> u64 {
+    0
+}
@@ -0,0 +1,183 @@
+//! Real-mode start-up code for application processors.
+//!
+//! A SIPI starts the AP in 16-bit real mode at a page below 1MB. The blob
+//! below is copied to such a page and walks the AP up to long mode:
+//!
+//! ```text
+//! real mode ──lgdt──► 32-bit protected ──PAE, CR3, EFER.LME, PG──► long mode
+//!                                                                   │
+//!                                    rsp = stack, call entry(arg) ◄─┘
+//! ```
+//!
+//! The code is position independent: the page's linear base is derived
+//! from CS and every absolute value (GDT base, far jump targets, CR3, the
+//! stack) is read from a parameter block at `PARAMS` within the page.
+//! CR3 is loaded from 32-bit mode, so the PML4 must lie below 4GB.
+
+use crate::cpu::msr::{rdmsr, IA32_EFER, EFER_NXE};
+
+/// Offset of the parameter block in the trampoline page.
+const PARAMS: usize = 0x800;
+
+// Parameter block layout (offsets from PARAMS)
+const P_GDTR: usize = 0x00; // u16 limit, u32 base
+const P_PM_JUMP: usize = 0x08; // u32 offset, u16 selector
+const P_LM_JUMP: usize = 0x10; // u32 offset, u16 selector
+const P_CR3: usize = 0x18; // u32
+const P_EFER: usize = 0x20; // u32, OR-ed into EFER
+const P_STACK: usize = 0x28; // u64
+const P_ENTRY: usize = 0x30; // u64
+const P_ARG: usize = 0x38; // u64
+const P_GDT: usize = 0x40; // [u64; 4]
+
+/// Temporary GDT: null, 32-bit code, data, 64-bit code.
+const TRAMPOLINE_GDT: [u64; 4] = [
+    0,
+    0x00CF_9A00_0000_FFFF,
+    0x00CF_9200_0000_FFFF,
+    0x00AF_9A00_0000_FFFF,
+];
+const SEL_CODE32: u16 = 0x08;
+const SEL_CODE64: u16 = 0x18;
+
+/// EFER.LME
+const EFER_LME: u64 = 1 << 8;
+
+/// Rust entry point of an AP: runs on its own stack, never returns.
+pub type ApEntry = extern "sysv64" fn(arg: u64) -> !;
+
+#[cfg(target_arch = "x86_64")]
+core::arch::global_asm!(
+    ".global ap_trampoline_start",
+    ".global ap_trampoline_pm32",
+    ".global ap_trampoline_lm64",
+    ".global ap_trampoline_end",
+    ".code16",
+    "ap_trampoline_start:",
+    "    cli",
+    "    cld",
+    "    mov ax, cs",
+    "    mov ds, ax",
+    "    xor ebx, ebx",
+    "    mov bx, ax",
+    "    shl ebx, 4",
+    "    lgdt [{params} + {gdtr}]",
+    "    mov eax, cr0",
+    "    or eax, 1",
+    "    mov cr0, eax",
+    "    jmp fword ptr [{params} + {pm_jump}]",
+    ".code32",
+    "ap_trampoline_pm32:",
+    "    mov ax, 0x10",
+    "    mov ds, ax",
+    "    mov es, ax",
+    "    mov ss, ax",
+    // PAE | OSFXSR | OSXMMEXCPT: Rust code uses SSE
+    "    mov eax, cr4",
+    "    or eax, 0x620",
+    "    mov cr4, eax",
+    "    mov eax, [ebx + {params} + {cr3}]",
+    "    mov cr3, eax",
+    "    mov ecx, 0xC0000080",
+    "    rdmsr",
+    "    or eax, [ebx + {params} + {efer}]",
+    "    wrmsr",
+    // Caches on (INIT leaves CD/NW set), FPU native, paging on
+    "    mov eax, cr0",
+    "    and eax, 0x9FFFFFFB",
+    "    or eax, 0x80000022",
+    "    mov cr0, eax",
+    "    jmp fword ptr [ebx + {params} + {lm_jump}]",
+    ".code64",
+    "ap_trampoline_lm64:",
+    "    mov ax, 0x10",
+    "    mov ds, ax",
+    "    mov es, ax",
+    "    mov ss, ax",
+    "    mov ebx, ebx",
+    "    mov rsp, [rbx + {params} + {stack}]",
+    "    mov rdi, [rbx + {params} + {arg}]",
+    "    mov rax, [rbx + {params} + {entry}]",
+    "    fninit",
+    "    call rax",
+    "2:",
+    "    cli",
+    "    hlt",
+    "    jmp 2b",
+    "ap_trampoline_end:",
+    params = const PARAMS,
+    gdtr = const P_GDTR,
+    pm_jump = const P_PM_JUMP,
+    lm_jump = const P_LM_JUMP,
+    cr3 = const P_CR3,
+    efer = const P_EFER,
+    stack = const P_STACK,
+    entry = const P_ENTRY,
+    arg = const P_ARG,
+);
+
+#[cfg(target_arch = "x86_64")]
+extern "C" {
+    static ap_trampoline_start: u8;
+    static ap_trampoline_pm32: u8;
+    static ap_trampoline_lm64: u8;
+    static ap_trampoline_end: u8;
+}
+
+/// The trampoline installed in a page below 1MB.
+pub struct Trampoline {
+    page: u64,
+}
+
+impl Trampoline {
+    /// Copy the start-up code to `page` and fill in everything that is
+    /// the same for every AP.
+    ///
+    /// # Safety
+    /// `page` must be a free, identity-mapped 4KB page below 1MB, and
+    /// `cr3` the PML4 the APs should use (below 4GB).
+    #[cfg(target_arch = "x86_64")]
+    pub unsafe fn install(page: u64, cr3: u64, entry: ApEntry) -> Self {
+        let start = &raw const ap_trampoline_start as u64;
+        let len = &raw const ap_trampoline_end as u64 - start;
+        debug_assert!(len as usize <= PARAMS);
+        core::ptr::copy_nonoverlapping(start as *const u8, page as *mut u8, len as usize);
+
+        let pm32 = page + (&raw const ap_trampoline_pm32 as u64 - start);
+        let lm64 = page + (&raw const ap_trampoline_lm64 as u64 - start);
+        let efer = EFER_LME | (rdmsr(IA32_EFER) & EFER_NXE);
+
+        let t = Self { page };
+        for (i, desc) in TRAMPOLINE_GDT.iter().enumerate() {
+            t.write(P_GDT + i * 8, *desc);
+        }
+        t.write(P_GDTR, (TRAMPOLINE_GDT.len() * 8 - 1) as u16);
+        t.write(P_GDTR + 2, (page + (PARAMS + P_GDT) as u64) as u32);
+        t.write(P_PM_JUMP, pm32 as u32);
+        t.write(P_PM_JUMP + 4, SEL_CODE32);
+        t.write(P_LM_JUMP, lm64 as u32);
+        t.write(P_LM_JUMP + 4, SEL_CODE64);
+        t.write(P_CR3, cr3 as u32);
+        t.write(P_EFER, efer as u32);
+        t.write(P_ENTRY, entry as usize as u64);
+        t
+    }
+
+    /// Set the stack and argument for the next AP to start.
+    ///
+    /// # Safety
+    /// No AP may be running the trampoline.
+    pub unsafe fn prepare(&self, stack_top: u64, arg: u64) {
+        self.write(P_STACK, stack_top);
+        self.write(P_ARG, arg);
+    }
+
+    /// SIPI vector pointing at the trampoline.
+    pub fn vector(&self) -> u8 {
+        (self.page >> 12) as u8
+    }
+
+    unsafe fn write<T>(&self, offset: usize, value: T) {
+        core::ptr::write_unaligned((self.page as usize + PARAMS + offset) as *mut T, value);
+    }
+}
@@ -0,0 +1,107 @@
+//! Work queue served by the application processors.
+//!
+//! Core 0 keeps driving devices and hands CPU-bound jobs (hashing,
+//! compression) to the APs. A job is a function pointer, an argument and
+//! a completion flag the worker sets when the function returns:
+//!
+//! ```ignore
+//! let done = AtomicBool::new(false);
+//! if !spawn(hash_chunk, &mut job as *mut _ as *mut (), &done) {
+//!     hash_chunk(&mut job as *mut _ as *mut ()); // no worker: run inline
+//!     done.store(true, Ordering::Release);
+//! }
+//! // ... drive I/O ...
+//! while !done.load(Ordering::Acquire) { core::hint::spin_loop(); }
+//! ```
+//!
+//! Jobs run in submission order when there is one worker; with several,
+//! jobs that depend on each other must be chained by the submitter.
+
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use crate::sync::SpinLock;
+
+/// A job function. The argument is whatever the submitter passed.
+pub type JobFn = unsafe fn(*mut ());
+
+/// Maximum queued (not yet started) jobs.
+const QUEUE_LEN: usize = 64;
+
+#[derive(Clone, Copy)]
+struct Job {
+    func: JobFn,
+    arg: *mut (),
+    done: *const AtomicBool,
+}
+
+// SAFETY: `spawn`'s contract makes the pointers valid on any core.
+unsafe impl Send for Job {}
+
+struct Queue {
+    jobs: [Option<Job>; QUEUE_LEN],
+    head: usize,
+    len: usize,
+}
+
+static QUEUE: SpinLock<Queue> = SpinLock::new(Queue {
+    jobs: [None; QUEUE_LEN],
+    head: 0,
+    len: 0,
+});
+
+/// APs that have entered `worker_loop`.
+static WORKERS: AtomicUsize = AtomicUsize::new(0);
+
+/// Queue `func(arg)` for an AP; `done` is set once it has returned.
+///
+/// Returns false without queuing anything if no AP is online or the queue
+/// is full; the caller then runs the job itself.
+///
+/// # Safety
+/// `func` must be safe to call with `arg` from another core, and `arg`
+/// and `done` must stay valid until `done` reads true.
+pub unsafe fn spawn(func: JobFn, arg: *mut (), done: *const AtomicBool) -> bool {
+    if WORKERS.load(Ordering::Acquire) == 0 {
+        return false;
+    }
+    let mut queue = QUEUE.lock();
+    if queue.len == QUEUE_LEN {
+        return false;
+    }
+    (*done).store(false, Ordering::Relaxed);
+    let tail = (queue.head + queue.len) % QUEUE_LEN;
+    queue.jobs[tail] = Some(Job { func, arg, done });
+    queue.len += 1;
+    true
+}
+
+/// Number of APs serving the queue.
+pub fn worker_count() -> usize {
+    WORKERS.load(Ordering::Acquire)
+}
+
+fn pop() -> Option<Job> {
+    let mut queue = QUEUE.lock();
+    if queue.len == 0 {
+        return None;
+    }
+    let head = queue.head;
+    let job = queue.jobs[head].take();
+    queue.head = (head + 1) % QUEUE_LEN;
+    queue.len -= 1;
+    job
+}
+
+/// AP main loop: run jobs forever.
+pub(super) fn worker_loop() -> ! {
+    WORKERS.fetch_add(1, Ordering::AcqRel);
+    loop {
+        match pop() {
+            Some(job) => unsafe {
+                (job.func)(job.arg);
+                (*job.done).store(true, Ordering::Release);
+            },
+            None => core::hint::spin_loop(),
+        }
+    }
+}
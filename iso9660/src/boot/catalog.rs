@@ -2,9 +2,12 @@
 //!
 //! El Torito Boot Catalog structure and parsing.
 
-use super::entry::BootEntry;
+use super::entry::{BootEntry, SectionHeader};
 use super::validation::ValidationEntry;
 use crate::error::{Iso9660Error, Result};
+use crate::types::{BootImage, BootPlatform};
+use alloc::vec;
+use alloc::vec::Vec;
 
 /// Boot Catalog
 ///
@@ -16,6 +19,9 @@ pub struct BootCatalog<'a> {
 
     /// Initial/default boot entry (next 32 bytes)
     pub initial: &'a BootEntry,
+
+    /// The whole catalog, for the sections after the initial entry
+    data: &'a [u8],
 }
 
 impl<'a> BootCatalog<'a> {
@@ -50,6 +56,7 @@ impl<'a> BootCatalog<'a> {
         Ok(Self {
             validation,
             initial,
+            data,
         })
     }
 
@@ -62,4 +69,58 @@ impl<'a> BootCatalog<'a> {
     pub fn platform_id(&self) -> u8 {
         self.validation.platform_id
     }
+
+    /// Every image in the catalog: the initial/default entry for the
+    /// validation entry's platform, then each section's entries for their
+    /// section header's platform. Section entry extensions are skipped.
+    ///
+    /// Stops at the final section header, at an entry that should be a
+    /// section header and isn't, or at the end of the catalog data.
+    pub fn images(&self) -> Vec<BootImage> {
+        let mut images = vec![self
+            .initial
+            .to_image(BootPlatform::from_id(self.platform_id()))];
+
+        let mut index = 2;
+        while let Some(bytes) = self.entry(index) {
+            if !SectionHeader::is_header(bytes[0]) {
+                break;
+            }
+            let header = unsafe { &*(bytes.as_ptr() as *const SectionHeader) };
+            let platform = BootPlatform::from_id(header.platform_id);
+            index += 1;
+
+            for _ in 0..header.section_entries {
+                let Some(bytes) = self.entry(index) else {
+                    return images;
+                };
+                let entry = unsafe { &*(bytes.as_ptr() as *const BootEntry) };
+                images.push(entry.to_image(platform));
+                index += 1;
+
+                let mut more = entry.has_extensions();
+                while more {
+                    match self.entry(index) {
+                        Some(extension) if extension[0] == BootEntry::EXTENSION => {
+                            more = extension[1] & BootEntry::MORE_EXTENSIONS != 0;
+                            index += 1;
+                        }
+                        _ => break,
+                    }
+                }
+            }
+
+            if header.is_final() {
+                break;
+            }
+        }
+
+        images
+    }
+
+    /// The 32-byte entry at `index`, if the catalog data holds it
+    fn entry(&self, index: usize) -> Option<&'a [u8]> {
+        self.data
+            .get(index * Self::ENTRY_SIZE..(index + 1) * Self::ENTRY_SIZE)
+    }
 }
@@ -2,7 +2,7 @@
 //!
 //! Initial/Default, Section Header, and Section entries.
 
-use crate::types::BootMediaType;
+use crate::types::{BootImage, BootMediaType, BootPlatform};
 
 /// Boot Catalog Entry (32 bytes)
 #[repr(C, packed)]
@@ -39,14 +39,20 @@ impl BootEntry {
     /// Not bootable indicator
     pub const NOT_BOOTABLE: u8 = 0x00;
 
+    /// Section entry extension indicator
+    pub const EXTENSION: u8 = 0x44;
+
+    /// Media type bit: extension entries follow this section entry
+    pub const MORE_EXTENSIONS: u8 = 0x20;
+
     /// Is this entry bootable?
     pub fn is_bootable(&self) -> bool {
         self.boot_indicator == Self::BOOTABLE
     }
 
-    /// Parse boot media type
+    /// Parse boot media type (section entries keep flags in the high bits)
     pub fn media_type(&self) -> BootMediaType {
-        match self.boot_media_type {
+        match self.boot_media_type & 0x0F {
             0 => BootMediaType::NoEmulation,
             1 => BootMediaType::Floppy12M,
             2 => BootMediaType::Floppy144M,
@@ -60,4 +66,59 @@ impl BootEntry {
     pub fn image_size(&self) -> u32 {
         self.sector_count as u32 * 512
     }
+
+    /// Boot image this entry describes, in a section for `platform`
+    pub fn to_image(&self, platform: BootPlatform) -> BootImage {
+        BootImage {
+            bootable: self.is_bootable(),
+            media_type: self.media_type(),
+            load_segment: self.load_segment,
+            system_type: self.system_type,
+            sector_count: self.sector_count,
+            load_rba: self.load_rba,
+            platform,
+        }
+    }
+
+    /// Is this entry followed by section entry extensions?
+    pub fn has_extensions(&self) -> bool {
+        self.boot_media_type & Self::MORE_EXTENSIONS != 0
+    }
+}
+
+/// Section Header Entry (32 bytes)
+///
+/// Starts a list of section entries for one platform, after the
+/// initial/default entry. Hybrid ISOs put their EFI image in a section.
+#[repr(C, packed)]
+pub struct SectionHeader {
+    /// Header indicator (0x90 = more headers follow, 0x91 = final header)
+    pub header_indicator: u8,
+
+    /// Platform ID of the entries in this section
+    pub platform_id: u8,
+
+    /// Number of section entries that follow
+    pub section_entries: u16,
+
+    /// ID string (28 bytes)
+    pub id_string: [u8; 28],
+}
+
+impl SectionHeader {
+    /// More section headers follow this section
+    pub const MORE: u8 = 0x90;
+
+    /// Last section header
+    pub const FINAL: u8 = 0x91;
+
+    /// Is this entry a section header?
+    pub fn is_header(indicator: u8) -> bool {
+        indicator == Self::MORE || indicator == Self::FINAL
+    }
+
+    /// Is this the last section?
+    pub fn is_final(&self) -> bool {
+        self.header_indicator == Self::FINAL
+    }
 }
@@ -1,6 +1,9 @@
 //! El Torito boot support
 //!
-//! Parsing boot catalogs and boot images from ISO9660 volumes.
+//! Parsing boot catalogs and boot images from ISO9660 volumes. `mount`
+//! finds the Boot Record volume descriptor and keeps the catalog's LBA in
+//! [`VolumeInfo::boot_catalog_lba`]; everything here starts from there.
+//! Also available as `iso9660::eltorito`.
 
 pub mod catalog;
 pub mod entry;
@@ -8,7 +11,10 @@ pub mod platform;
 pub mod validation;
 
 use crate::error::{Iso9660Error, Result};
+use crate::file::extent::Extent;
 use crate::types::{BootImage, BootPlatform, VolumeInfo, SECTOR_SIZE};
+use alloc::vec::Vec;
+use catalog::BootCatalog;
 use entry::BootEntry;
 use gpt_disk_io::BlockIo;
 use gpt_disk_types::Lba;
@@ -62,13 +68,95 @@ pub fn find_boot_image<B: BlockIo>(block_io: &mut B, volume: &VolumeInfo) -> Res
     }
 
     // Build BootImage from entry
-    Ok(BootImage {
-        bootable: true,
-        media_type: initial.media_type(),
-        load_segment: initial.load_segment,
-        system_type: initial.system_type,
-        sector_count: initial.sector_count,
-        load_rba: initial.load_rba,
-        platform: BootPlatform::from_id(validation.platform_id),
-    })
+    Ok(initial.to_image(BootPlatform::from_id(validation.platform_id)))
+}
+
+/// List every image in the El Torito boot catalog
+///
+/// The initial/default entry comes first, then the entries of each
+/// section, each with its section's platform. Non-bootable entries are
+/// included with `bootable: false`.
+pub fn boot_images<B: BlockIo>(block_io: &mut B, volume: &VolumeInfo) -> Result<Vec<BootImage>> {
+    let catalog_lba = volume.boot_catalog_lba.ok_or(Iso9660Error::NoBootCatalog)?;
+
+    let mut buffer = [0u8; SECTOR_SIZE];
+    block_io
+        .read_blocks(Lba(catalog_lba as u64), &mut buffer)
+        .map_err(|_| Iso9660Error::IoError)?;
+
+    Ok(BootCatalog::parse(&buffer)?.images())
+}
+
+/// Find the EFI boot image in the El Torito boot catalog
+///
+/// Hybrid ISOs list their BIOS loader as the default entry and the EFI
+/// image in a section for platform 0xEF; UEFI-only ISOs may make it the
+/// default entry. The first bootable EFI entry is used. The image is a FAT
+/// filesystem holding `/EFI/BOOT/BOOTX64.EFI`, which is what firmware
+/// (or a bootloader chainloading it) runs.
+///
+/// The catalog counts the image in 512-byte sectors, 16 bits wide: mkisofs
+/// writes 0 or 1 when it can't tell, and the count wraps past 32 MB. The
+/// returned length is the larger of the count and the size in the image's
+/// own FAT boot sector, cut at the end of the volume.
+///
+/// # Returns
+/// The image's extent (start LBA, length in bytes), or
+/// `UnsupportedPlatform` if the catalog has no bootable EFI entry
+///
+/// # Example
+/// ```ignore
+/// use iso9660::{mount, find_efi_boot_image};
+///
+/// let volume = mount(&mut block_io, 0)?;
+/// let efi = find_efi_boot_image(&mut block_io, &volume)?;
+/// println!("EFI image at sector {}, {} bytes", efi.lba, efi.length);
+/// ```
+pub fn find_efi_boot_image<B: BlockIo>(block_io: &mut B, volume: &VolumeInfo) -> Result<Extent> {
+    let image = boot_images(block_io, volume)?
+        .into_iter()
+        .find(|image| image.bootable && image.platform == BootPlatform::Efi)
+        .ok_or(Iso9660Error::UnsupportedPlatform)?;
+
+    let volume_bytes = (volume.volume_space_size as u64)
+        .checked_sub(image.load_rba as u64)
+        .filter(|&sectors| sectors > 0)
+        .ok_or(Iso9660Error::InvalidBootEntry)?
+        * SECTOR_SIZE as u64;
+
+    let mut buffer = [0u8; SECTOR_SIZE];
+    block_io
+        .read_blocks(Lba(image.load_rba as u64), &mut buffer)
+        .map_err(|_| Iso9660Error::IoError)?;
+
+    let declared = image.sector_count as u64 * 512;
+    let length = fat_image_size(&buffer)
+        .map_or(declared, |size| size.max(declared))
+        .min(volume_bytes);
+    if length == 0 {
+        return Err(Iso9660Error::InvalidBootEntry);
+    }
+
+    Ok(Extent::new(image.load_rba, length as u32))
+}
+
+/// Size of a FAT filesystem image from its boot sector, `None` if the
+/// sector doesn't look like one
+fn fat_image_size(boot_sector: &[u8]) -> Option<u64> {
+    if boot_sector[510..512] != [0x55, 0xAA] {
+        return None;
+    }
+    let bytes_per_sector = u16::from_le_bytes([boot_sector[11], boot_sector[12]]) as u64;
+    if !(512..=4096).contains(&bytes_per_sector) || !bytes_per_sector.is_power_of_two() {
+        return None;
+    }
+    let total_16 = u16::from_le_bytes([boot_sector[19], boot_sector[20]]) as u64;
+    let total_32 = u32::from_le_bytes([
+        boot_sector[32],
+        boot_sector[33],
+        boot_sector[34],
+        boot_sector[35],
+    ]) as u64;
+    let sectors = if total_16 != 0 { total_16 } else { total_32 };
+    (sectors != 0).then_some(sectors * bytes_per_sector)
 }
@@ -4,7 +4,7 @@
 
 use crate::directory::record::DirectoryRecord;
 use crate::error::{Iso9660Error, Result};
-use crate::types::{FileEntry, SECTOR_SIZE};
+use crate::types::{FileEntry, NameFormat, SECTOR_SIZE};
 use crate::utils::string;
 use alloc::boxed::Box;
 use alloc::string::String;
@@ -19,6 +19,7 @@ pub struct DirectoryIterator<'a, B: BlockIo> {
     offset: usize,
     current_sector: Box<[u8; SECTOR_SIZE]>,
     current_sector_lba: Option<u64>,
+    names: NameFormat,
 }
 
 impl<'a, B: BlockIo> DirectoryIterator<'a, B> {
@@ -31,10 +32,64 @@ impl<'a, B: BlockIo> DirectoryIterator<'a, B> {
             offset: 0,
             current_sector: Box::new([0u8; SECTOR_SIZE]),
             current_sector_lba: None,
+            names: NameFormat::Iso,
+        }
+    }
+
+    /// Read names as `names` says (plain ISO9660 by default)
+    pub fn with_names(mut self, names: NameFormat) -> Self {
+        self.names = names;
+        self
+    }
+}
+
+/// Most continuation areas followed for one record
+#[cfg(feature = "rock-ridge")]
+const MAX_CONTINUATIONS: usize = 8;
+
+/// Name from an ISO9660 file identifier
+fn iso_name(file_id: &[u8]) -> String {
+    match string::dchars_to_str(file_id) {
+        Ok(s) => {
+            // Strip version suffix (e.g., ";1")
+            let stripped = string::strip_version(s);
+            String::from(stripped)
+        }
+        Err(_) => {
+            // If not valid UTF-8, use lossy conversion
+            let s = String::from_utf8_lossy(file_id);
+            let stripped = string::strip_version(&s);
+            String::from(stripped)
         }
     }
 }
 
+/// Take a record's Rock Ridge entries, following `CE` continuation areas.
+#[cfg(feature = "rock-ridge")]
+fn read_system_use<B: BlockIo>(
+    block_io: &mut B,
+    area: &[u8],
+) -> Result<crate::extensions::rock_ridge::SystemUse> {
+    let mut system_use = crate::extensions::rock_ridge::SystemUse::default();
+    system_use.parse(area);
+
+    let mut sector = [0u8; SECTOR_SIZE];
+    for _ in 0..MAX_CONTINUATIONS {
+        let Some((block, offset, len)) = system_use.continuation else {
+            break;
+        };
+        let (offset, len) = (offset as usize, len as usize);
+        if offset + len > SECTOR_SIZE {
+            return Err(Iso9660Error::RockRidgeError);
+        }
+        block_io
+            .read_blocks(Lba(block as u64), &mut sector)
+            .map_err(|_| Iso9660Error::IoError)?;
+        system_use.parse(&sector[offset..offset + len]);
+    }
+    Ok(system_use)
+}
+
 impl<'a, B: BlockIo> Iterator for DirectoryIterator<'a, B> {
     type Item = Result<FileEntry>;
 
@@ -85,36 +140,42 @@ impl<'a, B: BlockIo> Iterator for DirectoryIterator<'a, B> {
             // Convert file identifier to string
             let file_id = record.file_identifier();
 
-            // Handle special directory entries
-            let name = if file_id.len() == 1 && file_id[0] == 0 {
-                String::from(".")
-            } else if file_id.len() == 1 && file_id[0] == 1 {
-                String::from("..")
-            } else {
-                match string::dchars_to_str(file_id) {
-                    Ok(s) => {
-                        // Strip version suffix (e.g., ";1")
-                        let stripped = string::strip_version(s);
-                        String::from(stripped)
-                    }
-                    Err(_) => {
-                        // If not valid UTF-8, use lossy conversion
-                        let s = String::from_utf8_lossy(file_id);
-                        let stripped = string::strip_version(&s);
-                        String::from(stripped)
-                    }
-                }
-            };
-
             // Build FileEntry
-            let entry = FileEntry {
-                name,
+            let mut entry = FileEntry {
+                name: String::new(),
                 size: record.get_data_length() as u64,
                 extent_lba: record.get_extent_lba(),
                 data_length: record.get_data_length(),
                 flags: record.get_flags(),
                 file_unit_size: record.file_unit_size,
                 interleave_gap: record.interleave_gap,
+                posix: None,
+            };
+
+            // Handle special directory entries
+            entry.name = if file_id.len() == 1 && file_id[0] == 0 {
+                String::from(".")
+            } else if file_id.len() == 1 && file_id[0] == 1 {
+                String::from("..")
+            } else {
+                match self.names {
+                    #[cfg(feature = "joliet")]
+                    NameFormat::Joliet => crate::volume::supplementary::decode_name(file_id),
+                    #[cfg(feature = "rock-ridge")]
+                    NameFormat::RockRidge { skip } => {
+                        let area = record.system_use().get(skip as usize..).unwrap_or(&[]);
+                        let system_use = match read_system_use(self.block_io, area) {
+                            Ok(system_use) => system_use,
+                            Err(e) => return Some(Err(e)),
+                        };
+                        entry.posix = system_use.posix;
+                        match system_use.name {
+                            Some(name) if !name.is_empty() => name,
+                            _ => iso_name(file_id),
+                        }
+                    }
+                    _ => iso_name(file_id),
+                }
             };
 
             return Some(Ok(entry));
@@ -31,6 +31,8 @@ fn trace(_msg: &str) {}
 ///
 /// Navigates the directory tree from root to locate a file/directory.
 /// Paths are case-insensitive and support both `/` and `\` separators.
+/// Names are matched as Rock Ridge records them if the volume has Rock
+/// Ridge, else as Joliet does if it has Joliet, else as plain ISO9660.
 ///
 /// # Arguments
 /// * `block_io` - Block device
@@ -61,16 +63,16 @@ pub fn find_file<B: BlockIo>(
         return Err(Iso9660Error::PathTooLong);
     }
 
-    // Start at root directory
-    let mut current_lba = volume.root_extent_lba;
-    let mut current_len = volume.root_extent_len;
+    // Start at root directory of the tree with the best names
+    let (mut current_lba, mut current_len) = volume.root();
 
     // Navigate through each component
     for (depth, component) in components.iter().enumerate() {
         let is_last = depth == components.len() - 1;
 
         // Create iterator for current directory
-        let iter = iterator::DirectoryIterator::new(block_io, current_lba, current_len);
+        let iter = iterator::DirectoryIterator::new(block_io, current_lba, current_len)
+            .with_names(volume.name_format());
 
         // Search for matching entry (case-insensitive)
         let mut found = None;
@@ -108,9 +110,9 @@ pub fn find_file<B: BlockIo>(
     if components.is_empty() {
         Ok(FileEntry {
             name: String::from("/"),
-            size: volume.root_extent_len as u64,
-            extent_lba: volume.root_extent_lba,
-            data_length: volume.root_extent_len,
+            size: current_len as u64,
+            extent_lba: current_lba,
+            data_length: current_len,
             flags: crate::types::FileFlags {
                 hidden: false,
                 directory: true,
@@ -121,6 +123,7 @@ pub fn find_file<B: BlockIo>(
             },
             file_unit_size: 0,
             interleave_gap: 0,
+            posix: None,
         })
     } else {
         Err(Iso9660Error::NotFound)
@@ -119,4 +119,18 @@ impl DirectoryRecord {
             core::slice::from_raw_parts(base_ptr.add(start), len)
         }
     }
+
+    /// Get System Use area bytes (after the identifier and its padding)
+    pub fn system_use(&self) -> &[u8] {
+        let id_len = self.file_id_len as usize;
+        // A padding byte follows an identifier of even length
+        let start = (33 + id_len + (1 - id_len % 2)).min(self.length as usize);
+        let len = self.length as usize - start;
+
+        // Safety: parse() checked `length` bytes of record are in the buffer
+        unsafe {
+            let base_ptr = self as *const _ as *const u8;
+            core::slice::from_raw_parts(base_ptr.add(start), len)
+        }
+    }
 }
@@ -1,6 +1,15 @@
 //! Rock Ridge extension support
 //!
 //! Rock Ridge adds POSIX filesystem semantics (permissions, symlinks, long names).
+//!
+//! Its entries live in the System Use area at the end of each directory
+//! record, in the SUSP format: a 2-byte signature, a length and a version,
+//! then the entry's data. A `CE` entry continues the area in another block
+//! when it doesn't fit in the record. The root's `.` record starts with an
+//! `SP` entry that marks the volume as using SUSP at all.
+
+use crate::types::PosixInfo;
+use alloc::string::String;
 
 /// System Use Entry header
 #[repr(C, packed)]
@@ -62,4 +71,92 @@ pub mod signatures {
     pub const RELOCATED_DIR: &[u8; 2] = b"RE";
     /// Timestamps signature
     pub const TIMESTAMPS: &[u8; 2] = b"TF";
+    /// SUSP indicator signature
+    pub const SUSP_INDICATOR: &[u8; 2] = b"SP";
+    /// Continuation area signature
+    pub const CONTINUATION: &[u8; 2] = b"CE";
+    /// System Use area terminator signature
+    pub const TERMINATOR: &[u8; 2] = b"ST";
+}
+
+/// `NM` flag: the name continues in the next `NM` entry
+const NM_CONTINUE: u8 = 0x01;
+
+/// `NM` flags: the entry stands for `.` or `..` and has no name
+const NM_CURRENT_OR_PARENT: u8 = 0x06;
+
+/// What a record's System Use entries say about its file
+#[derive(Debug, Default)]
+pub struct SystemUse {
+    /// Name from the `NM` entries
+    pub name: Option<String>,
+
+    /// Attributes from the `PX` entry
+    pub posix: Option<PosixInfo>,
+
+    /// Continuation area (`CE`): block, offset in it, length
+    pub continuation: Option<(u32, u32, u32)>,
+
+    /// Whether the last `NM` entry asked for another
+    name_continues: bool,
+}
+
+impl SystemUse {
+    /// Take the entries in `area`, after those already taken.
+    ///
+    /// Any `continuation` it sets is where the caller reads the next area
+    /// from; entries after a malformed one are ignored.
+    pub fn parse(&mut self, mut area: &[u8]) {
+        self.continuation = None;
+        while area.len() >= 4 {
+            let len = area[2] as usize;
+            if len < 4 || len > area.len() {
+                break;
+            }
+            let (entry, rest) = area.split_at(len);
+            area = rest;
+
+            let signature = &entry[..2];
+            if signature == signatures::ALTERNATE_NAME
+                && len >= 5
+                && entry[4] & NM_CURRENT_OR_PARENT == 0
+            {
+                let part = String::from_utf8_lossy(&entry[5..]);
+                match self.name.as_mut() {
+                    Some(name) if self.name_continues => name.push_str(&part),
+                    _ => self.name = Some(String::from(part)),
+                }
+                self.name_continues = entry[4] & NM_CONTINUE != 0;
+            } else if signature == signatures::POSIX_ATTRS && len >= 36 {
+                self.posix = Some(PosixInfo {
+                    mode: both_endian_u32(&entry[4..]),
+                    links: both_endian_u32(&entry[12..]),
+                    uid: both_endian_u32(&entry[20..]),
+                    gid: both_endian_u32(&entry[28..]),
+                });
+            } else if signature == signatures::CONTINUATION && len >= 28 {
+                self.continuation = Some((
+                    both_endian_u32(&entry[4..]),
+                    both_endian_u32(&entry[12..]),
+                    both_endian_u32(&entry[20..]),
+                ));
+            } else if signature == signatures::TERMINATOR {
+                break;
+            }
+        }
+    }
+}
+
+/// Bytes to skip in each System Use area, if `area` (the root `.` record's)
+/// starts with an `SP` entry.
+pub fn susp_skip(area: &[u8]) -> Option<u8> {
+    match area {
+        [b'S', b'P', 7, _, 0xBE, 0xEF, skip, ..] => Some(*skip),
+        _ => None,
+    }
+}
+
+/// Little-endian half of a both-endian 32-bit field
+fn both_endian_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
 }
@@ -9,7 +9,8 @@
 //! - Directory tree navigation
 //! - File reading from extent-based storage
 //! - El Torito bootable CD support for kernel extraction
-//! - Optional Rock Ridge (POSIX) and Joliet (Unicode) extensions
+//! - Rock Ridge (POSIX) and Joliet (Unicode) names, behind the `rock-ridge`
+//!   and `joliet` features (both on by default)
 //!
 //! # Architecture
 //!
@@ -42,6 +43,10 @@
 //! // Extract bootable image (kernel) from ISO
 //! let boot = find_boot_image(&mut block_io, &volume)?;
 //! let kernel = read_file(&mut block_io, &volume, &boot.file)?;
+//!
+//! // Locate the EFI image (a FAT filesystem) to chainload from
+//! let efi = iso9660::eltorito::find_efi_boot_image(&mut block_io, &volume)?;
+//! println!("EFI image at sector {}, {} bytes", efi.lba, efi.length);
 //! ```
 
 #![no_std]
@@ -59,10 +64,16 @@ pub mod utils;
 pub mod volume;
 
 pub use error::{Iso9660Error, Result};
-pub use types::{BootImage, BootMediaType, BootPlatform, FileEntry, FileFlags, VolumeInfo};
+pub use types::{
+    BootImage, BootMediaType, BootPlatform, FileEntry, FileFlags, NameFormat, PosixInfo,
+    VolumeInfo,
+};
+
+/// El Torito boot support, under the specification's name
+pub use boot as eltorito;
 
 // High-level API exports
-pub use boot::find_boot_image;
+pub use boot::{boot_images, find_boot_image, find_efi_boot_image};
 pub use directory::find_file;
 pub use directory::iterator::DirectoryIterator;
 pub use file::reader::FileReader;
@@ -58,6 +58,28 @@ pub struct VolumeInfo {
 
     /// Whether Rock Ridge extensions are present
     pub has_rock_ridge: bool,
+
+    /// Joliet root directory extent (LBA, length in bytes), if present
+    pub joliet_root: Option<(u32, u32)>,
+
+    /// Bytes to skip at the start of each System Use area (SUSP `SP` entry)
+    pub susp_skip: u8,
+}
+
+/// How file identifiers in a directory tree are read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameFormat {
+    /// ISO9660 identifiers (d-characters, version suffix stripped)
+    Iso,
+
+    /// Joliet identifiers (UCS-2 big-endian)
+    Joliet,
+
+    /// Rock Ridge `NM` names, falling back to the ISO9660 identifier
+    RockRidge {
+        /// Bytes to skip at the start of each System Use area
+        skip: u8,
+    },
 }
 
 /// File entry metadata
@@ -83,6 +105,25 @@ pub struct FileEntry {
 
     /// Interleave gap size
     pub interleave_gap: u8,
+
+    /// POSIX attributes (Rock Ridge `PX` entry), if recorded
+    pub posix: Option<PosixInfo>,
+}
+
+/// POSIX file attributes from a Rock Ridge `PX` entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PosixInfo {
+    /// File mode (type and permission bits, as in `st_mode`)
+    pub mode: u32,
+
+    /// Number of links
+    pub links: u32,
+
+    /// Owner user ID
+    pub uid: u32,
+
+    /// Owner group ID
+    pub gid: u32,
 }
 
 /// File flags from directory record
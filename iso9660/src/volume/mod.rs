@@ -9,14 +9,16 @@ pub mod supplementary;
 
 use crate::directory::record::DirectoryRecord;
 use crate::error::{Iso9660Error, Result};
-use crate::types::{VolumeInfo, SECTOR_SIZE, VOLUME_DESCRIPTOR_START};
+use crate::types::{NameFormat, VolumeInfo, SECTOR_SIZE, VOLUME_DESCRIPTOR_START};
 use gpt_disk_io::BlockIo;
 use gpt_disk_types::Lba;
 
 /// Mount an ISO9660 volume from a block device
 ///
 /// Reads volume descriptors starting at sector 16 and builds `VolumeInfo`.
-/// This is the entry point for all ISO9660 operations.
+/// This is the entry point for all ISO9660 operations. With the `joliet`
+/// feature a Joliet descriptor's root is recorded, and with `rock-ridge`
+/// the root directory is checked for an `SP` entry.
 ///
 /// # Arguments
 /// * `block_io` - Block device containing the ISO
@@ -39,6 +41,8 @@ pub fn mount<B: BlockIo>(block_io: &mut B, start_sector: u64) -> Result<VolumeIn
 
     // Volume info that we'll build from Primary VD
     let mut volume_info: Option<VolumeInfo> = None;
+    #[cfg_attr(not(feature = "joliet"), allow(unused_mut))]
+    let mut joliet_root: Option<(u32, u32)> = None;
 
     // Read volume descriptors starting at sector 16
     let mut sector = VOLUME_DESCRIPTOR_START;
@@ -107,7 +111,9 @@ pub fn mount<B: BlockIo>(block_io: &mut B, start_sector: u64) -> Result<VolumeIn
                     volume_space_size: pvd.volume_space_size.get(),
                     boot_catalog_lba,      // Use currently found catalog LBA
                     has_joliet: false,     // Will be set if we find supplementary VD
-                    has_rock_ridge: false, // TODO: Detect from root directory system use
+                    has_rock_ridge: false, // Set below from the root's System Use area
+                    joliet_root: None,
+                    susp_skip: 0,
                 });
             }
             2 => {
@@ -116,6 +122,10 @@ pub fn mount<B: BlockIo>(block_io: &mut B, start_sector: u64) -> Result<VolumeIn
                 if let Some(ref mut vi) = volume_info {
                     vi.has_joliet = true;
                 }
+                #[cfg(feature = "joliet")]
+                if supplementary::is_joliet(&buffer) {
+                    joliet_root = Some(supplementary::root_extent(&buffer)?);
+                }
             }
             255 => {
                 // Terminator - we're done
@@ -135,7 +145,48 @@ pub fn mount<B: BlockIo>(block_io: &mut B, start_sector: u64) -> Result<VolumeIn
     }
 
     // Return volume info or error if not found
-    volume_info.ok_or(Iso9660Error::InvalidSignature)
+    let mut volume_info = volume_info.ok_or(Iso9660Error::InvalidSignature)?;
+    volume_info.joliet_root = joliet_root;
+
+    // Rock Ridge: the root's "." record starts its System Use area with SP
+    #[cfg(feature = "rock-ridge")]
+    {
+        let root_lba = Lba(start_sector + volume_info.root_extent_lba as u64);
+        block_io
+            .read_blocks(root_lba, &mut buffer)
+            .map_err(|_| Iso9660Error::IoError)?;
+        let dot = DirectoryRecord::parse(&buffer)?;
+        if let Some(skip) = crate::extensions::rock_ridge::susp_skip(dot.system_use()) {
+            volume_info.has_rock_ridge = true;
+            volume_info.susp_skip = skip;
+        }
+    }
+
+    Ok(volume_info)
+}
+
+impl VolumeInfo {
+    /// How names in the tree at [`root`](Self::root) are read: Rock Ridge
+    /// if present, then Joliet, then plain ISO9660.
+    pub fn name_format(&self) -> NameFormat {
+        if self.has_rock_ridge {
+            NameFormat::RockRidge {
+                skip: self.susp_skip,
+            }
+        } else if self.joliet_root.is_some() {
+            NameFormat::Joliet
+        } else {
+            NameFormat::Iso
+        }
+    }
+
+    /// Root directory extent (LBA, length) of the tree to navigate
+    pub fn root(&self) -> (u32, u32) {
+        match self.joliet_root {
+            Some(root) if !self.has_rock_ridge => root,
+            _ => (self.root_extent_lba, self.root_extent_len),
+        }
+    }
 }
 
 /// Volume Descriptor header (first 7 bytes of each descriptor)
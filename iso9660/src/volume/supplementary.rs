@@ -1,6 +1,23 @@
 //! Supplementary Volume Descriptor (Joliet support)
 //!
 //! The Supplementary VD enables Joliet extensions for long Unicode filenames.
+//! A Joliet SVD has the layout of the Primary VD, with escape sequences at
+//! offset 88 naming UCS-2, and roots a second directory tree whose file
+//! identifiers are UCS-2 big-endian.
+
+use crate::directory::record::DirectoryRecord;
+use crate::error::{Iso9660Error, Result};
+use crate::utils::string;
+use alloc::string::String;
+
+/// Offset of the escape sequences field
+const ESCAPE_SEQUENCES_OFFSET: usize = 88;
+
+/// Offset of the root directory record, as in the Primary VD
+const ROOT_RECORD_OFFSET: usize = 156;
+
+/// Escape sequences for UCS-2 Level 1, 2 and 3
+const JOLIET_ESCAPES: [&[u8; 3]; 3] = [b"%/@", b"%/C", b"%/E"];
 
 /// Supplementary Volume Descriptor (type 2)
 ///
@@ -11,8 +28,30 @@ pub struct SupplementaryVolumeDescriptor {
 }
 
 /// Check if supplementary descriptor is Joliet
-pub fn is_joliet(_data: &[u8]) -> bool {
-    // TODO: Check escape sequences at offset 88:
-    // %/@, %/C, or %/E indicate Joliet Level 1/2/3
-    false
+pub fn is_joliet(data: &[u8]) -> bool {
+    let Some(escapes) = data.get(ESCAPE_SEQUENCES_OFFSET..ESCAPE_SEQUENCES_OFFSET + 3) else {
+        return false;
+    };
+    data[0] == 2 && JOLIET_ESCAPES.iter().any(|escape| escapes == &escape[..])
+}
+
+/// Root directory extent (LBA, length) of a Joliet descriptor
+pub fn root_extent(data: &[u8]) -> Result<(u32, u32)> {
+    let record = data
+        .get(ROOT_RECORD_OFFSET..)
+        .ok_or(Iso9660Error::JolietError)?;
+    let root = DirectoryRecord::parse(record)?;
+    Ok((root.get_extent_lba(), root.get_data_length()))
+}
+
+/// Decode a Joliet file identifier (UCS-2 big-endian, version suffix
+/// stripped)
+pub fn decode_name(file_id: &[u8]) -> String {
+    let units = file_id
+        .chunks_exact(2)
+        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]));
+    let name: String = char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect();
+    String::from(string::strip_version(&name))
 }
@@ -4,7 +4,7 @@ mod common;
 
 use common::MemoryBlockDevice;
 use iso9660::error::Iso9660Error;
-use iso9660::{find_boot_image, mount};
+use iso9660::{boot_images, find_boot_image, mount, BootMediaType, BootPlatform};
 
 fn create_bootable_iso() -> MemoryBlockDevice {
     let mut device = MemoryBlockDevice::create_minimal_iso();
@@ -128,6 +128,46 @@ fn create_bootable_iso() -> MemoryBlockDevice {
     device
 }
 
+/// Set the validation entry's platform and redo its checksum
+fn set_catalog_platform(device: &mut MemoryBlockDevice, platform: u8) {
+    let cat_offset = 20 * 2048;
+    device.data[cat_offset + 1] = platform;
+    device.data[cat_offset + 28..cat_offset + 30].fill(0);
+    let sum = device.data[cat_offset..cat_offset + 32]
+        .chunks_exact(2)
+        .fold(0u16, |sum, word| {
+            sum.wrapping_add(u16::from_le_bytes([word[0], word[1]]))
+        });
+    device.data[cat_offset + 28..cat_offset + 30]
+        .copy_from_slice(&0u16.wrapping_sub(sum).to_le_bytes());
+}
+
+/// Hybrid layout: the BIOS image stays the default entry, and a final
+/// section for EFI lists a 64 KiB FAT image at sector 24 whose entry only
+/// counts one 512-byte sector, as mkisofs writes it
+fn add_efi_section(device: &mut MemoryBlockDevice) {
+    set_catalog_platform(device, 0x00);
+
+    let header = 20 * 2048 + 64;
+    device.data[header] = 0x91; // Final section header
+    device.data[header + 1] = 0xEF; // EFI
+    device.data[header + 2..header + 4].copy_from_slice(&1u16.to_le_bytes());
+
+    let entry = header + 32;
+    device.data[entry] = 0x88; // Bootable
+    device.data[entry + 1] = 0x20; // No emulation, extension follows
+    device.data[entry + 6..entry + 8].copy_from_slice(&1u16.to_le_bytes());
+    device.data[entry + 8..entry + 12].copy_from_slice(&24u32.to_le_bytes());
+    device.data[entry + 32] = 0x44; // Extension, the last one
+
+    // FAT boot sector: 512-byte sectors, 128 of them
+    let image = 24 * 2048;
+    device.data[image + 11..image + 13].copy_from_slice(&512u16.to_le_bytes());
+    device.data[image + 19..image + 21].copy_from_slice(&128u16.to_le_bytes());
+    device.data[image + 510] = 0x55;
+    device.data[image + 511] = 0xAA;
+}
+
 #[test]
 fn test_find_boot_image() {
     let mut device = create_bootable_iso();
@@ -166,3 +206,61 @@ fn test_invalid_boot_catalog_signature() {
     let result = find_boot_image(&mut device, &volume);
     assert_eq!(result.err(), Some(Iso9660Error::InvalidBootCatalog));
 }
+
+#[test]
+fn test_efi_boot_image_in_section() {
+    let mut device = create_bootable_iso();
+    add_efi_section(&mut device);
+    let volume = mount(&mut device, 0).expect("mount success");
+
+    let images = boot_images(&mut device, &volume).expect("catalog");
+    assert_eq!(images.len(), 2);
+    assert_eq!(
+        (images[0].platform, images[0].load_rba),
+        (BootPlatform::X86, 21)
+    );
+    assert_eq!(images[1].platform, BootPlatform::Efi);
+    assert_eq!(images[1].media_type, BootMediaType::NoEmulation);
+    assert!(images[1].bootable);
+
+    // The default entry is still what find_boot_image reports
+    let boot = find_boot_image(&mut device, &volume).expect("boot image");
+    assert_eq!(boot.load_rba, 21);
+
+    // Length from the FAT boot sector, not the one-sector count
+    let efi = iso9660::eltorito::find_efi_boot_image(&mut device, &volume).expect("EFI image");
+    assert_eq!((efi.lba, efi.length), (24, 128 * 512));
+}
+
+#[test]
+fn test_efi_boot_image_length() {
+    let mut device = create_bootable_iso();
+    add_efi_section(&mut device);
+
+    // A FAT image larger than the volume is cut at its end (sector 64)
+    device.data[24 * 2048 + 19..24 * 2048 + 21].copy_from_slice(&0xFFFFu16.to_le_bytes());
+    let volume = mount(&mut device, 0).expect("mount success");
+    let efi = iso9660::find_efi_boot_image(&mut device, &volume).expect("EFI image");
+    assert_eq!(efi.length, 40 * 2048);
+
+    // Not a FAT image: the catalog's count stands
+    device.data[24 * 2048 + 510] = 0;
+    let efi = iso9660::find_efi_boot_image(&mut device, &volume).expect("EFI image");
+    assert_eq!(efi.length, 512);
+}
+
+#[test]
+fn test_efi_default_entry() {
+    // UEFI-only: the default entry is the EFI image
+    let mut device = create_bootable_iso();
+    let volume = mount(&mut device, 0).expect("mount success");
+    let efi = iso9660::find_efi_boot_image(&mut device, &volume).expect("EFI image");
+    assert_eq!((efi.lba, efi.length), (21, 4 * 512));
+
+    // BIOS-only
+    set_catalog_platform(&mut device, 0x00);
+    assert_eq!(
+        iso9660::find_efi_boot_image(&mut device, &volume).err(),
+        Some(Iso9660Error::UnsupportedPlatform)
+    );
+}
@@ -4,16 +4,27 @@ use std::collections::HashMap;
 #[allow(dead_code)]
 pub struct IsoBuilder {
     files: HashMap<String, Vec<u8>>,
+    /// Rock Ridge / Joliet name of a file, by ISO name
+    long_names: HashMap<String, String>,
+    rock_ridge: bool,
+    joliet: bool,
     pvd_lba: u32,
     root_lba: u32,
     next_free_lba: u32,
 }
 
+/// Longest Rock Ridge name kept in the directory record; longer ones go
+/// to a continuation area
+const MAX_INLINE_NM: usize = 64;
+
 #[allow(dead_code)]
 impl IsoBuilder {
     pub fn new() -> Self {
         Self {
             files: HashMap::new(),
+            long_names: HashMap::new(),
+            rock_ridge: false,
+            joliet: false,
             pvd_lba: 16,
             root_lba: 18,
             next_free_lba: 19, // 16=PVD, 17=Terminator, 18=Root
@@ -24,9 +35,35 @@ impl IsoBuilder {
         self.files.insert(name.to_string(), content.to_vec());
     }
 
+    /// Add a file whose Rock Ridge and Joliet name is `long_name`
+    pub fn add_file_with_long_name(&mut self, iso_name: &str, long_name: &str, content: &[u8]) {
+        self.add_file(iso_name, content);
+        self.long_names
+            .insert(iso_name.to_string(), long_name.to_string());
+    }
+
+    /// Record long names as Rock Ridge `NM` entries
+    pub fn with_rock_ridge(&mut self) {
+        self.rock_ridge = true;
+    }
+
+    /// Add a Joliet descriptor and directory tree
+    pub fn with_joliet(&mut self) {
+        self.joliet = true;
+    }
+
     pub fn build(self) -> MemoryBlockDevice {
+        // 16=PVD, then the Joliet SVD, the terminator, the root, the
+        // Joliet root and the Rock Ridge continuation area
+        let svd_lba = 17;
+        let term_lba = if self.joliet { 18 } else { 17 };
+        let root_lba = if self.joliet { 19 } else { self.root_lba };
+        let joliet_root_lba = root_lba + 1;
+        let ce_lba = root_lba + 1 + self.joliet as u32;
+        let first_file_lba = ce_lba + self.rock_ridge as u32;
+
         // Calculate total size needed
-        let mut max_lba = self.next_free_lba;
+        let mut max_lba = first_file_lba.max(self.next_free_lba);
         let mut file_lbas = HashMap::new();
 
         // Assign LBAs to files
@@ -46,55 +83,123 @@ impl IsoBuilder {
         data[pvd_offset + 6] = 1; // Version
 
         // Root dir record in PVD
-        let root_entry_len = 34; // 33 fixed + 1 name
-        data[pvd_offset + 156] = root_entry_len;
-        // Extent LBA (both endian)
-        Self::write_both_endian_u32(&mut data[pvd_offset + 158..], self.root_lba);
-        // Data Length (both endian) - Root dir size (1 sector for now)
-        Self::write_both_endian_u32(&mut data[pvd_offset + 166..], 2048);
-
-        data[pvd_offset + 181] = 0x02; // Directory flag
-        data[pvd_offset + 188] = 1; // Name len
-        data[pvd_offset + 189] = 0; // Name "."
+        Self::write_root_record(&mut data[pvd_offset + 156..], root_lba);
 
         // Set volume space size (PVD 80)
         Self::write_both_endian_u32(&mut data[pvd_offset + 80..], max_lba);
         // Set logical block size (PVD 128)
         Self::write_both_endian_u16(&mut data[pvd_offset + 128..], 2048);
 
-        // 2. Terminator at 17
-        let term_offset = 17 * 2048;
+        // Joliet SVD: PVD layout, UCS-2 Level 3 escape sequence
+        if self.joliet {
+            let svd_offset = svd_lba * 2048;
+            data[svd_offset] = 2; // Supplementary
+            data[svd_offset + 1..svd_offset + 6].copy_from_slice(b"CD001");
+            data[svd_offset + 6] = 1;
+            data[svd_offset + 88..svd_offset + 91].copy_from_slice(b"%/E");
+            Self::write_root_record(&mut data[svd_offset + 156..], joliet_root_lba);
+        }
+
+        // 2. Terminator
+        let term_offset = term_lba as usize * 2048;
         data[term_offset] = 255;
         data[term_offset + 1..term_offset + 6].copy_from_slice(b"CD001");
         data[term_offset + 6] = 1;
 
-        // 3. Root Directory at 18
-        let root_offset = self.root_lba as usize * 2048;
+        // 3. Root Directory
+        let root_offset = root_lba as usize * 2048;
         let mut dir_offset = root_offset;
 
-        // "." entry
-        Self::write_dir_entry(&mut data, &mut dir_offset, self.root_lba, 2048, 0x02, "\0");
+        // "." entry, with the SUSP indicator on Rock Ridge volumes
+        let sp: &[u8] = if self.rock_ridge {
+            &[b'S', b'P', 7, 1, 0xBE, 0xEF, 0]
+        } else {
+            &[]
+        };
+        Self::write_dir_entry(&mut data, &mut dir_offset, root_lba, 2048, 0x02, b"\0", sp);
         // ".." entry
         Self::write_dir_entry(
             &mut data,
             &mut dir_offset,
-            self.root_lba,
+            root_lba,
             2048,
             0x02,
-            "\x01",
+            b"\x01",
+            &[],
         );
 
         // File entries
+        let mut ce_offset = 0;
         for (name, content) in &self.files {
             let lba = file_lbas[name];
             let size = content.len() as u32;
-            Self::write_dir_entry(&mut data, &mut dir_offset, lba, size, 0x00, name);
+            let long_name = self.long_names.get(name).filter(|_| self.rock_ridge);
+            let system_use = match long_name {
+                Some(long_name) if long_name.len() > MAX_INLINE_NM => {
+                    // Name in the continuation area, after any earlier ones
+                    let nm = Self::rock_ridge_nm(long_name);
+                    let ce_start = ce_lba as usize * 2048 + ce_offset;
+                    data[ce_start..ce_start + nm.len()].copy_from_slice(&nm);
+                    let ce = Self::rock_ridge_ce(ce_lba, ce_offset as u32, nm.len() as u32);
+                    ce_offset += nm.len();
+                    [Self::rock_ridge_px(0o100644), ce].concat()
+                }
+                Some(long_name) => [
+                    Self::rock_ridge_px(0o100644),
+                    Self::rock_ridge_nm(long_name),
+                ]
+                .concat(),
+                None => Vec::new(),
+            };
+            let iso_id = format!("{};1", name);
+            Self::write_dir_entry(
+                &mut data,
+                &mut dir_offset,
+                lba,
+                size,
+                0x00,
+                iso_id.as_bytes(),
+                &system_use,
+            );
 
             // Write file content
             let file_offset = lba as usize * 2048;
             data[file_offset..file_offset + content.len()].copy_from_slice(content);
         }
 
+        // 4. Joliet root directory, same files under UCS-2 names
+        if self.joliet {
+            let mut dir_offset = joliet_root_lba as usize * 2048;
+            for id in [&b"\0"[..], b"\x01"] {
+                Self::write_dir_entry(
+                    &mut data,
+                    &mut dir_offset,
+                    joliet_root_lba,
+                    2048,
+                    0x02,
+                    id,
+                    &[],
+                );
+            }
+            for (name, content) in &self.files {
+                let long_name = self.long_names.get(name).unwrap_or(name);
+                let id: Vec<u8> = format!("{};1", long_name)
+                    .encode_utf16()
+                    .flat_map(u16::to_be_bytes)
+                    .collect();
+                let size = content.len() as u32;
+                Self::write_dir_entry(
+                    &mut data,
+                    &mut dir_offset,
+                    file_lbas[name],
+                    size,
+                    0,
+                    &id,
+                    &[],
+                );
+            }
+        }
+
         MemoryBlockDevice::new(data)
     }
 
@@ -108,20 +213,68 @@ impl IsoBuilder {
         dst[2..4].copy_from_slice(&value.to_be_bytes());
     }
 
+    /// Root directory record, as embedded in a volume descriptor
+    fn write_root_record(dst: &mut [u8], root_lba: u32) {
+        dst[0] = 34; // 33 fixed + 1 name
+                     // Extent LBA (both endian)
+        Self::write_both_endian_u32(&mut dst[2..], root_lba);
+        // Data Length (both endian) - Root dir size (1 sector for now)
+        Self::write_both_endian_u32(&mut dst[10..], 2048);
+
+        dst[25] = 0x02; // Directory flag
+        dst[32] = 1; // Name len
+        dst[33] = 0; // Name "."
+    }
+
+    /// Rock Ridge `PX` entry (RRIP 1.12 layout, with the inode number)
+    fn rock_ridge_px(mode: u32) -> Vec<u8> {
+        let mut px = vec![b'P', b'X', 44, 1];
+        for value in [mode, 1, 1000, 1000, 0] {
+            px.extend_from_slice(&value.to_le_bytes());
+            px.extend_from_slice(&value.to_be_bytes());
+        }
+        px
+    }
+
+    /// Rock Ridge `NM` entries for `name`, split so that none is over
+    /// 255 bytes
+    fn rock_ridge_nm(name: &str) -> Vec<u8> {
+        let chunks: Vec<&[u8]> = name.as_bytes().chunks(200).collect();
+        let mut nm = Vec::new();
+        for (i, chunk) in chunks.iter().enumerate() {
+            let continues = (i + 1 < chunks.len()) as u8;
+            nm.extend_from_slice(&[b'N', b'M', 5 + chunk.len() as u8, 1, continues]);
+            nm.extend_from_slice(chunk);
+        }
+        nm
+    }
+
+    /// SUSP `CE` entry pointing at `len` bytes at `offset` in block `lba`
+    fn rock_ridge_ce(lba: u32, offset: u32, len: u32) -> Vec<u8> {
+        let mut ce = vec![b'C', b'E', 28, 1];
+        for value in [lba, offset, len] {
+            ce.extend_from_slice(&value.to_le_bytes());
+            ce.extend_from_slice(&value.to_be_bytes());
+        }
+        ce
+    }
+
     fn write_dir_entry(
         data: &mut [u8],
         offset: &mut usize,
         lba: u32,
         size: u32,
         flags: u8,
-        name: &str,
+        name_bytes: &[u8],
+        system_use: &[u8],
     ) {
-        let name_bytes = name.as_bytes();
         let name_len = name_bytes.len();
         let mut entry_len = 33 + name_len;
         if entry_len % 2 != 0 {
             entry_len += 1;
         } // Padding to even
+        let su_start = entry_len;
+        entry_len += system_use.len() + system_use.len() % 2;
 
         let start = *offset;
         data[start] = entry_len as u8;
@@ -138,6 +291,7 @@ impl IsoBuilder {
         data[start + 32] = name_len as u8;
 
         data[start + 33..start + 33 + name_len].copy_from_slice(name_bytes);
+        data[start + su_start..start + su_start + system_use.len()].copy_from_slice(system_use);
 
         *offset += entry_len;
     }
@@ -0,0 +1,96 @@
+//! Rock Ridge and Joliet name tests
+
+#![cfg(all(feature = "rock-ridge", feature = "joliet"))]
+
+mod common;
+
+use common::IsoBuilder;
+use iso9660::error::Iso9660Error;
+use iso9660::{find_file, mount, read_file_vec, NameFormat};
+
+#[test]
+fn test_rock_ridge_names() {
+    let long_name = format!("{}.squashfs", "filesystem-".repeat(25));
+    let mut builder = IsoBuilder::new();
+    builder.with_rock_ridge();
+    builder.add_file_with_long_name("VMLINUZ_.6_8", "vmlinuz-6.8.0-41-generic", b"kernel");
+    builder.add_file_with_long_name("FILESYST.SQU", &long_name, b"squashfs");
+    builder.add_file("README.TXT", b"plain");
+    let mut device = builder.build();
+
+    let volume = mount(&mut device, 0).expect("mount");
+    assert!(volume.has_rock_ridge);
+    assert_eq!(volume.name_format(), NameFormat::RockRidge { skip: 0 });
+
+    let kernel = find_file(&mut device, &volume, "/vmlinuz-6.8.0-41-generic").expect("find");
+    assert_eq!(
+        read_file_vec(&mut device, &kernel).expect("read"),
+        b"kernel"
+    );
+    let posix = kernel.posix.expect("PX entry");
+    assert_eq!((posix.mode, posix.uid, posix.gid), (0o100644, 1000, 1000));
+    assert_eq!(
+        find_file(&mut device, &volume, "/VMLINUZ_.6_8").unwrap_err(),
+        Iso9660Error::NotFound
+    );
+
+    // Over 255 bytes, split over two NM entries in a continuation area
+    let squashfs = find_file(&mut device, &volume, &format!("/{}", long_name)).expect("find");
+    assert_eq!(squashfs.name.len(), 284);
+    assert_eq!(
+        read_file_vec(&mut device, &squashfs).expect("read"),
+        b"squashfs"
+    );
+
+    // No NM entry: the ISO9660 name stands
+    let readme = find_file(&mut device, &volume, "/readme.txt").expect("find");
+    assert_eq!(readme.name, "README.TXT");
+    assert!(readme.posix.is_none());
+}
+
+#[test]
+fn test_joliet_names() {
+    let mut builder = IsoBuilder::new();
+    builder.with_joliet();
+    builder.add_file_with_long_name("INITRD_I.IMG", "initrd.img-6.8.0-Generic", b"initrd");
+    builder.add_file("README.TXT", b"plain");
+    let mut device = builder.build();
+
+    let volume = mount(&mut device, 0).expect("mount");
+    assert!(volume.has_joliet && !volume.has_rock_ridge);
+    assert_eq!(volume.name_format(), NameFormat::Joliet);
+    assert_eq!(volume.root(), (20, 2048));
+    assert_eq!(volume.root_extent_lba, 19);
+
+    let initrd = find_file(&mut device, &volume, "/initrd.img-6.8.0-Generic").expect("find");
+    assert_eq!(initrd.name, "initrd.img-6.8.0-Generic");
+    assert_eq!(
+        read_file_vec(&mut device, &initrd).expect("read"),
+        b"initrd"
+    );
+    assert!(find_file(&mut device, &volume, "/INITRD_I.IMG").is_err());
+    assert!(find_file(&mut device, &volume, "/README.TXT").is_ok());
+    assert_eq!(
+        find_file(&mut device, &volume, "/")
+            .expect("root")
+            .extent_lba,
+        20
+    );
+}
+
+#[test]
+fn test_rock_ridge_preferred_over_joliet() {
+    let mut builder = IsoBuilder::new();
+    builder.with_rock_ridge();
+    builder.with_joliet();
+    builder.add_file_with_long_name("GRUB.CFG", "grub.cfg", b"menuentry");
+    let mut device = builder.build();
+
+    let volume = mount(&mut device, 0).expect("mount");
+    assert!(volume.has_joliet && volume.joliet_root.is_some());
+    assert_eq!(volume.name_format(), NameFormat::RockRidge { skip: 0 });
+    assert_eq!(volume.root(), (19, 2048));
+
+    let file = find_file(&mut device, &volume, "/grub.cfg").expect("find");
+    assert!(file.posix.is_some());
+}
@@ -16,6 +16,8 @@
 ;   Words 27-46: Model number (40 ASCII chars)
 ;   Word 49: Capabilities
 ;   Word 60-61: Total sectors (28-bit LBA)
+;   Word 75: NCQ queue depth - 1
+;   Word 76: SATA capabilities (bit 8 = NCQ)
 ;   Word 83: Command set support
 ;   Word 86: Command set enabled
 ;   Words 100-103: Total sectors (48-bit LBA)
@@ -243,3 +245,36 @@ asm_ahci_get_identify_sector_size:
 .default_512:
     mov     eax, 512
     ret
+
+; ═══════════════════════════════════════════════════════════════════════════
+; asm_ahci_get_identify_ncq_depth
+; ═══════════════════════════════════════════════════════════════════════════
+; Get the NCQ queue depth from IDENTIFY data.
+;
+; Parameters:
+;   RCX = identify_buf_ptr
+;
+; Returns:
+;   EAX = queue depth (1-32), 0 if NCQ is not supported
+;
+; Note: Word 76 bit 8 = NCQ supported (word 76 = 0xFFFF means not
+;       reported). Word 75 bits 4:0 = maximum queue depth - 1.
+;
+; Calling Convention: Microsoft x64
+; ═══════════════════════════════════════════════════════════════════════════
+global asm_ahci_get_identify_ncq_depth
+asm_ahci_get_identify_ncq_depth:
+    movzx   eax, word [rcx + 76*2]
+    cmp     eax, 0xFFFF
+    je      .no_ncq
+    test    eax, (1 << 8)
+    jz      .no_ncq
+
+    movzx   eax, word [rcx + 75*2]
+    and     eax, 0x1F
+    inc     eax
+    ret
+
+.no_ncq:
+    xor     eax, eax
+    ret
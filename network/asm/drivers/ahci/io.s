@@ -8,6 +8,7 @@
 ; Functions:
 ;   - asm_ahci_submit_read: Submit a read request (non-blocking)
 ;   - asm_ahci_submit_write: Submit a write request (non-blocking)
+;   - asm_ahci_submit_fpdma: Submit a queued (NCQ) read or write
 ;
 ; Reference: AHCI 1.3.1 Specification, ATA/ATAPI-8
 ; ═══════════════════════════════════════════════════════════════════════════
@@ -20,6 +21,7 @@ section .text
 ; External
 extern asm_bar_sfence
 extern asm_bar_mfence
+extern asm_mmio_write32
 extern asm_ahci_build_h2d_fis
 extern asm_ahci_build_prdt
 extern asm_ahci_setup_cmd_header
@@ -234,6 +236,143 @@ asm_ahci_submit_write:
     pop     rbx
     ret
 
+; ═══════════════════════════════════════════════════════════════════════════
+; asm_ahci_submit_fpdma
+; ═══════════════════════════════════════════════════════════════════════════
+; Submit a READ/WRITE FPDMA QUEUED (NCQ) request (fire-and-forget).
+;
+; Parameters:
+;   RCX = abar
+;   EDX = port_num
+;   R8  = lba
+;   R9  = data_buf_phys
+;   [RSP+40] = num_sectors
+;   [RSP+48] = cmd_slot (also the NCQ tag, must be below the queue depth)
+;   [RSP+56] = cmd_header_ptr
+;   [RSP+64] = cmd_table_ptr
+;   [RSP+72] = cmd_table_phys
+;   [RSP+80] = write (0 = read, 1 = write)
+;
+; Returns:
+;   EAX = 0 on submit success
+;
+; FPDMA FIS layout differs from READ/WRITE DMA EXT: the sector count goes
+; in the Features registers and Count[7:3] carries the tag. PxSACT is set
+; before PxCI (AHCI 1.3.1 §5.3.1); the command is complete once its SACT
+; bit clears.
+;
+; Calling Convention: Microsoft x64
+; ═══════════════════════════════════════════════════════════════════════════
+global asm_ahci_submit_fpdma
+asm_ahci_submit_fpdma:
+    push    rbx
+    push    rsi
+    push    rdi
+    push    r12
+    push    r13
+    push    r14
+    push    r15
+    sub     rsp, 64
+
+    mov     r12, rcx            ; abar
+    mov     r13d, edx           ; port_num
+    mov     r14, r8             ; lba
+    mov     r15, r9             ; data_buf_phys
+
+    ; 7 pushes × 8 = 56, + sub 64 = 120 total
+    ; Locals live above the callees' shadow space
+    mov     ebx, [rsp + 120 + 40]   ; num_sectors
+    mov     esi, [rsp + 120 + 48]   ; cmd_slot / tag
+    mov     rdi, [rsp + 120 + 56]   ; cmd_header_ptr
+    mov     rax, [rsp + 120 + 64]   ; cmd_table_ptr
+    mov     [rsp + 32], rax
+    mov     rax, [rsp + 120 + 72]   ; cmd_table_phys
+    mov     [rsp + 40], rax
+    mov     eax, [rsp + 120 + 80]   ; write
+    mov     [rsp + 48], rax
+
+    ; ───────────────────────────────────────────────────────────────────
+    ; Build H2D FIS for READ/WRITE FPDMA QUEUED (0x60/0x61)
+    ; ───────────────────────────────────────────────────────────────────
+    mov     rcx, [rsp + 32]
+    mov     dl, ATA_CMD_READ_FPDMA_QUEUED
+    cmp     dword [rsp + 48], 0
+    je      .build_fis
+    mov     dl, ATA_CMD_WRITE_FPDMA_QUEUED
+.build_fis:
+    mov     r8, r14
+    xor     r9d, r9d            ; count field is patched below
+    call    asm_ahci_build_h2d_fis
+
+    mov     rcx, [rsp + 32]
+    mov     [rcx + 3], bl       ; Features[7:0] = sectors[7:0]
+    mov     eax, ebx
+    shr     eax, 8
+    mov     [rcx + 11], al      ; Features[15:8] = sectors[15:8]
+    mov     eax, esi
+    shl     eax, 3
+    mov     [rcx + 12], al      ; Count[7:3] = tag
+    mov     byte [rcx + 13], 0
+    sfence
+
+    ; ───────────────────────────────────────────────────────────────────
+    ; Build PRDT
+    ; ───────────────────────────────────────────────────────────────────
+    mov     rcx, [rsp + 32]
+    add     rcx, 0x80
+    mov     rdx, r15
+    mov     eax, ebx
+    shl     eax, 9
+    dec     eax
+    mov     r8d, eax
+    call    asm_ahci_build_prdt
+
+    ; ───────────────────────────────────────────────────────────────────
+    ; Setup command header
+    ; CFL = 5, W = write, PRDTL = 1
+    ; ───────────────────────────────────────────────────────────────────
+    mov     edx, (1 << 16) | 5
+    cmp     dword [rsp + 48], 0
+    je      .setup_header
+    or      edx, AHCI_CMD_WRITE
+.setup_header:
+    mov     rcx, rdi
+    mov     r8, [rsp + 40]
+    call    asm_ahci_setup_cmd_header
+
+    ; ───────────────────────────────────────────────────────────────────
+    ; Mark the tag active in PxSACT, then issue through PxCI
+    ; ───────────────────────────────────────────────────────────────────
+    mov     eax, 1
+    mov     ecx, esi
+    shl     eax, cl
+    mov     ebx, eax            ; slot_mask (sector count no longer needed)
+
+    mov     eax, r13d
+    shl     eax, 7
+    add     eax, AHCI_PORT_BASE
+    lea     rcx, [r12 + rax + AHCI_PxSACT]
+    mov     edx, ebx
+    call    asm_mmio_write32
+    call    asm_bar_mfence
+
+    mov     rcx, r12
+    mov     edx, r13d
+    mov     r8d, ebx
+    call    asm_ahci_issue_cmd
+
+    xor     eax, eax
+
+    add     rsp, 64
+    pop     r15
+    pop     r14
+    pop     r13
+    pop     r12
+    pop     rdi
+    pop     rsi
+    pop     rbx
+    ret
+
 ; ═══════════════════════════════════════════════════════════════════════════
 ; asm_ahci_flush_cache
 ; ═══════════════════════════════════════════════════════════════════════════
@@ -12,6 +12,8 @@
 ;   - asm_ahci_port_setup: Configure command list and FIS base
 ;   - asm_ahci_port_clear_errors: Clear port error state
 ;   - asm_ahci_port_read_sig: Read device signature
+;   - asm_ahci_port_read_sact: Read outstanding NCQ tags
+;   - asm_ahci_port_read_ci: Read issued command slots
 ;   - asm_ahci_port_get_status: Read port status registers
 ;
 ; Reference: AHCI 1.3.1 Specification §3.3
@@ -540,6 +542,70 @@ asm_ahci_port_read_is:
     pop     rbx
     ret
 
+; ═══════════════════════════════════════════════════════════════════════════
+; asm_ahci_port_read_sact
+; ═══════════════════════════════════════════════════════════════════════════
+; Read SATA Active. A queued (NCQ) command's bit stays set until the device
+; reports its completion with a Set Device Bits FIS.
+;
+; Parameters:
+;   RCX = abar
+;   EDX = port_num
+;
+; Returns:
+;   EAX = PxSACT value
+;
+; Calling Convention: Microsoft x64
+; ═══════════════════════════════════════════════════════════════════════════
+global asm_ahci_port_read_sact
+asm_ahci_port_read_sact:
+    push    rbx
+    sub     rsp, 32
+
+    mov     rax, rcx
+    mov     ebx, edx
+
+    CALC_PORT_BASE
+
+    lea     rcx, [rax + AHCI_PxSACT]
+    call    asm_mmio_read32
+
+    add     rsp, 32
+    pop     rbx
+    ret
+
+; ═══════════════════════════════════════════════════════════════════════════
+; asm_ahci_port_read_ci
+; ═══════════════════════════════════════════════════════════════════════════
+; Read Command Issue. A slot's bit clears once the HBA has sent the command
+; (queued) or the command has finished (non-queued).
+;
+; Parameters:
+;   RCX = abar
+;   EDX = port_num
+;
+; Returns:
+;   EAX = PxCI value
+;
+; Calling Convention: Microsoft x64
+; ═══════════════════════════════════════════════════════════════════════════
+global asm_ahci_port_read_ci
+asm_ahci_port_read_ci:
+    push    rbx
+    sub     rsp, 32
+
+    mov     rax, rcx
+    mov     ebx, edx
+
+    CALC_PORT_BASE
+
+    lea     rcx, [rax + AHCI_PxCI]
+    call    asm_mmio_read32
+
+    add     rsp, 32
+    pop     rbx
+    ret
+
 ; ═══════════════════════════════════════════════════════════════════════════
 ; asm_ahci_port_clear_is
 ; ═══════════════════════════════════════════════════════════════════════════
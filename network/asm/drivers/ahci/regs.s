@@ -215,6 +215,8 @@ ATA_CMD_IDENTIFY        equ 0xEC        ; IDENTIFY DEVICE
 ATA_CMD_FLUSH_CACHE_EXT equ 0xEA        ; FLUSH CACHE EXT
 ATA_CMD_READ_SECTORS    equ 0x20        ; READ SECTORS (28-bit LBA)
 ATA_CMD_WRITE_SECTORS   equ 0x30        ; WRITE SECTORS (28-bit LBA)
+ATA_CMD_READ_FPDMA_QUEUED  equ 0x60     ; READ FPDMA QUEUED (NCQ)
+ATA_CMD_WRITE_FPDMA_QUEUED equ 0x61     ; WRITE FPDMA QUEUED (NCQ)
 
 ; ═══════════════════════════════════════════════════════════════════════════
 ; FIS Types
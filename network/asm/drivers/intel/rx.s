@@ -43,7 +43,7 @@ section .data
     RX_ERR_CE   equ (1 << 0)
     RX_ERR_SE   equ (1 << 1)
     RX_ERR_SEQ  equ (1 << 2)
-    RX_ERR_RXE  equ (1 << 5)
+    RX_ERR_RXE  equ (1 << 7)
     RX_ERR_MASK equ (RX_ERR_CE | RX_ERR_SE | RX_ERR_SEQ | RX_ERR_RXE)
 
 section .text
@@ -11,6 +11,9 @@
 ;   Descriptor 1: Data buffer (n bytes, read or write depending on op)
 ;   Descriptor 2: Status byte (1 byte, device-writable)
 ;
+; With VIRTIO_F_INDIRECT_DESC the same chain goes in a per-request indirect
+; table and the ring holds one descriptor pointing at it.
+;
 ; VirtioBlkReqHeader:
 ;   u32 type     - VIRTIO_BLK_T_IN (read) or VIRTIO_BLK_T_OUT (write)
 ;   u32 reserved
@@ -399,6 +402,151 @@ asm_virtio_blk_submit_write:
     pop rbx
     ret
 
+; ═══════════════════════════════════════════════════════════════════════════
+; asm_virtio_blk_submit_flush
+; ═══════════════════════════════════════════════════════════════════════════
+; Submit a cache flush request to VirtIO-blk queue.
+;
+; Parameters:
+;   RCX = *VirtqueueState
+;   RDX = header_buf_phys
+;   R8  = status_buf_phys
+;   R9  = desc_idx
+;
+; Returns:
+;   EAX = 0 if success, 1 if queue full
+;
+; A flush has no data, so the chain is only 2 descriptors:
+;   Descriptor 0: Header (type = VIRTIO_BLK_T_FLUSH, sector = 0)
+;   Descriptor 1: Status byte (device-writable)
+;
+; Only touches volatile registers, so nothing is saved.
+; ═══════════════════════════════════════════════════════════════════════════
+global asm_virtio_blk_submit_flush
+asm_virtio_blk_submit_flush:
+    ; Check queue space
+    movzx eax, word [rcx + 0x2A]      ; next_avail_idx
+    movzx r10d, word [rcx + 0x28]     ; last_used_idx
+    sub eax, r10d
+    movzx r10d, word [rcx + 0x18]     ; queue_size
+    cmp eax, r10d
+    jge .queue_full_f
+    
+    ; Build header (VIRTIO_BLK_T_FLUSH = 4, sector must be 0)
+    mov dword [rdx], VIRTIO_BLK_T_FLUSH
+    mov dword [rdx + 4], 0
+    mov qword [rdx + 8], 0
+    
+    ; Setup descriptors
+    mov r10, [rcx]                    ; desc_base
+    movzx r11d, r9w                   ; desc_idx
+    shl r11d, 4
+    add r10, r11
+    
+    ; Desc 0: Header (readable)
+    mov [r10], rdx
+    mov dword [r10 + 8], 16
+    lea eax, [r9d + 1]
+    mov word [r10 + 0x0C], VIRTQ_DESC_F_NEXT
+    mov word [r10 + 0x0E], ax
+    
+    ; Desc 1: Status (writable)
+    mov [r10 + 16], r8
+    mov dword [r10 + 24], 1
+    mov word [r10 + 28], VIRTQ_DESC_F_WRITE
+    mov word [r10 + 30], 0
+    
+    ; Barriers and update avail ring
+    sfence
+    
+    mov r10, [rcx + 0x08]             ; avail_base
+    movzx eax, word [rcx + 0x2A]
+    movzx r11d, word [rcx + 0x18]
+    dec r11d
+    and eax, r11d
+    mov word [r10 + 4 + rax*2], r9w
+    
+    sfence
+    
+    movzx eax, word [rcx + 0x2A]
+    inc ax
+    mov [rcx + 0x2A], ax
+    mov [r10 + 2], ax
+    
+    mfence
+    
+    xor eax, eax
+    ret
+    
+.queue_full_f:
+    mov eax, 1
+    ret
+
+; ═══════════════════════════════════════════════════════════════════════════
+; asm_virtio_blk_submit_indirect
+; ═══════════════════════════════════════════════════════════════════════════
+; Publish a request whose chain lives in an indirect descriptor table
+; (VIRTIO_F_INDIRECT_DESC). The caller has already built the table and the
+; request header; the request takes a single ring descriptor.
+;
+; Parameters:
+;   RCX = *VirtqueueState
+;   RDX = table_phys (16-byte aligned)
+;   R8  = table_len (bytes, 16 per descriptor)
+;   R9  = desc_idx
+;
+; Returns:
+;   EAX = 0 if success, 1 if queue full
+;
+; Only touches volatile registers, so nothing is saved.
+; ═══════════════════════════════════════════════════════════════════════════
+global asm_virtio_blk_submit_indirect
+asm_virtio_blk_submit_indirect:
+    ; Check queue space
+    movzx eax, word [rcx + 0x2A]      ; next_avail_idx
+    movzx r10d, word [rcx + 0x28]     ; last_used_idx
+    sub eax, r10d
+    movzx r10d, word [rcx + 0x18]     ; queue_size
+    cmp eax, r10d
+    jge .queue_full_i
+    
+    ; Ring descriptor pointing at the table
+    mov r10, [rcx]                    ; desc_base
+    movzx r11d, r9w                   ; desc_idx
+    shl r11d, 4
+    add r10, r11
+    
+    mov [r10], rdx
+    mov [r10 + 8], r8d
+    mov word [r10 + 0x0C], VIRTQ_DESC_F_INDIRECT
+    mov word [r10 + 0x0E], 0
+    
+    ; Barriers and update avail ring
+    sfence
+    
+    mov r10, [rcx + 0x08]             ; avail_base
+    movzx eax, word [rcx + 0x2A]
+    movzx r11d, word [rcx + 0x18]
+    dec r11d
+    and eax, r11d
+    mov word [r10 + 4 + rax*2], r9w
+    
+    sfence
+    
+    movzx eax, word [rcx + 0x2A]
+    inc ax
+    mov [rcx + 0x2A], ax
+    mov [r10 + 2], ax
+    
+    mfence
+    
+    xor eax, eax
+    ret
+    
+.queue_full_i:
+    mov eax, 1
+    ret
+
 ; ═══════════════════════════════════════════════════════════════════════════
 ; asm_virtio_blk_poll_complete
 ; ═══════════════════════════════════════════════════════════════════════════
@@ -61,7 +61,8 @@ fn main() {
     // Always rerun if build.rs changes
     println!("cargo:rerun-if-changed=build.rs");
 
-    // Only build assembly for x86_64 targets
+    // Only build assembly for x86_64 targets; aarch64 uses the Rust
+    // implementations behind the same bindings (see src/arch)
     if !target.contains("x86_64") {
         println!(
             "cargo:warning=Skipping assembly for non-x86_64 target: {}",
@@ -0,0 +1,64 @@
+//! Minimal ACPI table lookup.
+//!
+//! Finds tables by signature from the RSDP handed over by the bootloader.
+//! Used for the FADT (reset register, PSCI flags), MCFG (ECAM base) and
+//! SPCR (serial console). ACPI tables stay identity mapped after
+//! ExitBootServices.
+
+/// Size of the common ACPI table header.
+pub const SDT_HEADER_LEN: usize = 36;
+
+/// Walk the XSDT (or RSDT) for a table with `signature`.
+///
+/// Returns the physical address of the table header.
+///
+/// # Safety
+/// `rsdp` must be 0 or point to the firmware's RSDP, with the ACPI tables
+/// identity mapped.
+pub unsafe fn find_table(rsdp: u64, signature: &[u8; 4]) -> Option<u64> {
+    if rsdp == 0 || core::slice::from_raw_parts(rsdp as *const u8, 8) != b"RSD PTR " {
+        return None;
+    }
+    let revision = *((rsdp + 15) as *const u8);
+    let xsdt = if revision >= 2 {
+        core::ptr::read_unaligned((rsdp + 24) as *const u64)
+    } else {
+        0
+    };
+    let (root, entry_size) = if xsdt != 0 {
+        (xsdt, 8)
+    } else {
+        (read_u32(rsdp + 16) as u64, 4)
+    };
+    if root == 0 {
+        return None;
+    }
+
+    let len = read_u32(root + 4) as u64;
+    let count = len.saturating_sub(SDT_HEADER_LEN as u64) / entry_size;
+    for i in 0..count {
+        let entry = root + SDT_HEADER_LEN as u64 + i * entry_size;
+        let table = if entry_size == 8 {
+            core::ptr::read_unaligned(entry as *const u64)
+        } else {
+            read_u32(entry) as u64
+        };
+        if table != 0 && core::slice::from_raw_parts(table as *const u8, 4) == signature {
+            return Some(table);
+        }
+    }
+    None
+}
+
+/// A table found by `find_table`, as bytes (header included).
+///
+/// # Safety
+/// `table` must be a valid ACPI table address.
+pub unsafe fn table_bytes<'a>(table: u64) -> &'a [u8] {
+    let len = read_u32(table + 4) as usize;
+    core::slice::from_raw_parts(table as *const u8, len)
+}
+
+unsafe fn read_u32(addr: u64) -> u32 {
+    core::ptr::read_unaligned(addr as *const u32)
+}
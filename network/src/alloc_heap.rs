@@ -44,6 +44,7 @@
 
 use core::alloc::{GlobalAlloc, Layout};
 use core::ptr::NonNull;
+use core::sync::atomic::{AtomicBool, Ordering};
 use linked_list_allocator::Heap;
 
 /// Heap size: 1MB - sufficient for FAT32 ops, manifest handling, etc.
@@ -54,7 +55,8 @@ const HEAP_SIZE: usize = 1024 * 1024;
 #[repr(C, align(4096))]
 struct AlignedHeapBuffer([u8; HEAP_SIZE]);
 
-/// Static heap buffer - lives in .bss, zero-initialized
+/// Static heap buffer - lives in .bss, zero-initialized.
+/// Only ever touched through a raw pointer, once, by `init_heap`.
 static mut HEAP_BUFFER: AlignedHeapBuffer = AlignedHeapBuffer([0u8; HEAP_SIZE]);
 
 /// Locked heap wrapper implementing GlobalAlloc
@@ -107,7 +109,7 @@ static GLOBAL: LockedHeap = LockedHeap::empty();
 static GLOBAL: LockedHeap = LockedHeap::empty();
 
 /// Track if heap is already initialized
-static mut HEAP_INITIALIZED: bool = false;
+static HEAP_INITIALIZED: AtomicBool = AtomicBool::new(false);
 
 /// Initialize the heap allocator
 ///
@@ -116,9 +118,9 @@ static mut HEAP_INITIALIZED: bool = false;
 ///
 /// # Safety
 /// - Must be called BEFORE any allocations (Vec, Box, String, etc.)
-/// - Thread-safety: Uses static bool guard, safe for single-core
+/// - Thread-safety: the first caller wins; later calls return at once
 pub unsafe fn init_heap() {
-    if HEAP_INITIALIZED {
+    if HEAP_INITIALIZED.swap(true, Ordering::AcqRel) {
         return; // Already initialized
     }
 
@@ -127,12 +129,11 @@ pub unsafe fn init_heap() {
     let heap_size = HEAP_SIZE;
 
     GLOBAL.init(heap_start, heap_size);
-    HEAP_INITIALIZED = true;
 }
 
 /// Check if heap is initialized
 pub fn is_initialized() -> bool {
-    unsafe { HEAP_INITIALIZED }
+    HEAP_INITIALIZED.load(Ordering::Acquire)
 }
 
 /// Get heap statistics for debugging
@@ -0,0 +1,160 @@
+//! aarch64 primitives.
+//!
+//! Inline assembly for the few things Rust cannot express: the generic
+//! timer, barriers, cache maintenance and SMC/HVC calls. MMIO is plain
+//! volatile access; device memory attributes come from the UEFI page
+//! tables (Device-nGnRE for MMIO regions).
+//!
+//! # Barriers
+//! DMA buffers are normal cacheable memory shared with the device, so
+//! ordering against the device needs the outer-shareable domain:
+//!
+//! | Need                              | Instruction  |
+//! |-----------------------------------|--------------|
+//! | Descriptor writes before doorbell | `dmb oshst`  |
+//! | Index read before ring read       | `dmb oshld`  |
+//! | Everything before MMIO            | `dsb sy`     |
+//!
+//! # Reference
+//! Arm ARM (DDI 0487) §D11 (generic timer), §B2.3 (barriers);
+//! SMC Calling Convention (DEN 0028)
+
+use core::arch::asm;
+
+// ═══════════════════════════════════════════════════════════════════════════
+// GENERIC TIMER
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Virtual counter (CNTVCT_EL0).
+///
+/// The `isb` keeps the read from being hoisted above earlier code, which
+/// matches how the TSC read is used for interval timing.
+#[inline]
+pub fn cntvct() -> u64 {
+    let value: u64;
+    unsafe {
+        asm!("isb", "mrs {}, cntvct_el0", out(reg) value, options(nomem, nostack, preserves_flags));
+    }
+    value
+}
+
+/// Counter frequency in Hz (CNTFRQ_EL0), as programmed by firmware.
+#[inline]
+pub fn cntfrq() -> u64 {
+    let value: u64;
+    unsafe {
+        asm!("mrs {}, cntfrq_el0", out(reg) value, options(nomem, nostack, preserves_flags));
+    }
+    value
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// BARRIERS
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Order prior stores before later stores, as seen by devices.
+#[inline]
+pub fn dmb_oshst() {
+    unsafe { asm!("dmb oshst", options(nostack, preserves_flags)) };
+}
+
+/// Order prior loads before later loads and stores, as seen by devices.
+#[inline]
+pub fn dmb_oshld() {
+    unsafe { asm!("dmb oshld", options(nostack, preserves_flags)) };
+}
+
+/// Complete all prior memory accesses before continuing.
+#[inline]
+pub fn dsb_sy() {
+    unsafe { asm!("dsb sy", options(nostack, preserves_flags)) };
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// CACHE MAINTENANCE
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Smallest data cache line size in bytes (CTR_EL0.DminLine).
+#[inline]
+pub fn dcache_line_size() -> usize {
+    let ctr: u64;
+    unsafe {
+        asm!("mrs {}, ctr_el0", out(reg) ctr, options(nomem, nostack, preserves_flags));
+    }
+    4 << ((ctr >> 16) & 0xF)
+}
+
+/// Clean and invalidate the data cache line holding `addr` to the point
+/// of coherency.
+///
+/// # Safety
+/// `addr` must be mapped.
+#[inline]
+pub unsafe fn dc_civac(addr: u64) {
+    asm!("dc civac, {}", in(reg) addr, options(nostack, preserves_flags));
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// CPU CONTROL
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Mask IRQ, FIQ, SError and debug exceptions.
+#[inline]
+pub fn mask_interrupts() {
+    unsafe { asm!("msr daifset, #0xf", options(nomem, nostack)) };
+}
+
+/// Wait for interrupt.
+#[inline]
+pub fn wfi() {
+    unsafe { asm!("wfi", options(nomem, nostack)) };
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// FIRMWARE CALLS (SMCCC)
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Secure monitor call. Returns x0.
+///
+/// # Safety
+/// Calls into EL3 firmware; the function ID decides what happens.
+#[inline]
+pub unsafe fn smc(function: u32, arg1: u64, arg2: u64, arg3: u64) -> u64 {
+    let ret: u64;
+    asm!(
+        "smc #0",
+        inlateout("x0") function as u64 => ret,
+        inlateout("x1") arg1 => _,
+        inlateout("x2") arg2 => _,
+        inlateout("x3") arg3 => _,
+        // SMCCC v1.0 leaves x4-x17 unpredictable
+        lateout("x4") _, lateout("x5") _, lateout("x6") _, lateout("x7") _,
+        lateout("x8") _, lateout("x9") _, lateout("x10") _, lateout("x11") _,
+        lateout("x12") _, lateout("x13") _, lateout("x14") _, lateout("x15") _,
+        lateout("x16") _, lateout("x17") _,
+        options(nostack)
+    );
+    ret
+}
+
+/// Hypervisor call. Returns x0.
+///
+/// # Safety
+/// Calls into EL2 firmware; the function ID decides what happens.
+#[inline]
+pub unsafe fn hvc(function: u32, arg1: u64, arg2: u64, arg3: u64) -> u64 {
+    let ret: u64;
+    asm!(
+        "hvc #0",
+        inlateout("x0") function as u64 => ret,
+        inlateout("x1") arg1 => _,
+        inlateout("x2") arg2 => _,
+        inlateout("x3") arg3 => _,
+        lateout("x4") _, lateout("x5") _, lateout("x6") _, lateout("x7") _,
+        lateout("x8") _, lateout("x9") _, lateout("x10") _, lateout("x11") _,
+        lateout("x12") _, lateout("x13") _, lateout("x14") _, lateout("x15") _,
+        lateout("x16") _, lateout("x17") _,
+        options(nostack)
+    );
+    ret
+}
@@ -0,0 +1,120 @@
+//! Architecture abstraction layer.
+//!
+//! Everything above the drivers is architecture neutral. What differs
+//! between x86_64 and aarch64 is a handful of primitives:
+//!
+//! | Primitive         | x86_64                    | aarch64                     |
+//! |-------------------|---------------------------|-----------------------------|
+//! | Tick counter      | TSC                       | CNTVCT_EL0                  |
+//! | Tick frequency    | Calibrated pre-EBS        | CNTFRQ_EL0                  |
+//! | DMA write fence   | `sfence`                  | `dmb oshst`                 |
+//! | DMA read fence    | `lfence`                  | `dmb oshld`                 |
+//! | Full fence        | `mfence`                  | `dsb sy`                    |
+//! | PCI config        | Ports 0xCF8/0xCFC         | ECAM (base from MCFG)       |
+//! | Reset             | ACPI, 0xCF9, 8042         | PSCI `SYSTEM_RESET`         |
+//! | Serial            | COM1 (0x3F8)              | PL011 (base from SPCR)      |
+//!
+//! The `asm` bindings keep their names and signatures on both. On x86_64
+//! they call the hand-written assembly; on aarch64 they are implemented in
+//! Rust on top of `aarch64`, so drivers compile unchanged.
+//!
+//! # Platform setup
+//!
+//! On aarch64 call `init_from_acpi()` with the RSDP from the boot handoff
+//! before scanning PCI; without an ECAM base every config read returns
+//! all-ones (no device).
+//!
+//! # Not ported
+//!
+//! AHCI and e1000e stay x86_64-only: their drivers refuse to initialise
+//! elsewhere. `hwinit` and the bootloader's x86 entry (GDT/IDT/PIC, stack
+//! switch) are out of scope for this layer.
+
+#[cfg(target_arch = "aarch64")]
+pub mod aarch64;
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+/// ECAM base for PCI segment 0 (0 = unknown).
+static ECAM_BASE: AtomicU64 = AtomicU64::new(0);
+
+/// PL011 UART base (0 = none).
+static UART_BASE: AtomicU64 = AtomicU64::new(0);
+
+/// Set the ECAM base used for PCI config access on architectures
+/// without port I/O.
+pub fn set_ecam_base(base: u64) {
+    ECAM_BASE.store(base, Ordering::Release);
+}
+
+/// ECAM base for PCI segment 0, if known.
+pub fn ecam_base() -> Option<u64> {
+    match ECAM_BASE.load(Ordering::Acquire) {
+        0 => None,
+        base => Some(base),
+    }
+}
+
+/// Set the MMIO base of the serial console UART.
+pub fn set_uart_base(base: u64) {
+    UART_BASE.store(base, Ordering::Release);
+}
+
+/// Serial console UART base, if known.
+pub fn uart_base() -> Option<u64> {
+    match UART_BASE.load(Ordering::Acquire) {
+        0 => None,
+        base => Some(base),
+    }
+}
+
+/// Tick counter frequency reported by the hardware, if it has one.
+///
+/// aarch64 publishes it in CNTFRQ_EL0; x86 has to calibrate the TSC.
+pub fn counter_frequency() -> Option<u64> {
+    #[cfg(target_arch = "aarch64")]
+    {
+        match aarch64::cntfrq() {
+            0 => None,
+            f => Some(f),
+        }
+    }
+    #[cfg(not(target_arch = "aarch64"))]
+    {
+        None
+    }
+}
+
+/// Pick up ECAM and UART bases from the ACPI tables.
+///
+/// Only needed where they cannot be reached through port I/O (aarch64);
+/// harmless elsewhere.
+///
+/// # Safety
+/// `rsdp` must be 0 or point to the firmware's RSDP, with the ACPI tables
+/// identity mapped.
+pub unsafe fn init_from_acpi(rsdp: u64) {
+    if let Some(base) = crate::pci::mcfg::ecam_base_from_rsdp(rsdp) {
+        set_ecam_base(base);
+    }
+    if let Some(base) = crate::mainloop::serial::uart_base_from_rsdp(rsdp) {
+        set_uart_base(base);
+    }
+}
+
+/// Stop this CPU for good.
+pub fn halt() -> ! {
+    loop {
+        #[cfg(target_arch = "x86_64")]
+        unsafe {
+            core::arch::asm!("cli", "hlt", options(nomem, nostack));
+        }
+        #[cfg(target_arch = "aarch64")]
+        unsafe {
+            aarch64::mask_interrupts();
+            aarch64::wfi();
+        }
+        #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+        core::hint::spin_loop();
+    }
+}
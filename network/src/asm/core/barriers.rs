@@ -1,5 +1,8 @@
 //! Memory barrier bindings.
 //!
+//! The x86 names are kept on aarch64, mapped to the outer-shareable `dmb`
+//! variants (device-visible ordering) and `dsb sy` for the full fence.
+//!
 //! # Reference
 //! NETWORK_IMPL_GUIDE.md §2.2.1, §2.4
 
@@ -43,15 +46,33 @@ pub fn mfence() {
     }
 }
 
-// Stubs for non-x86_64
-#[cfg(not(target_arch = "x86_64"))]
+#[cfg(target_arch = "aarch64")]
+#[inline]
+pub fn sfence() {
+    crate::arch::aarch64::dmb_oshst();
+}
+
+#[cfg(target_arch = "aarch64")]
+#[inline]
+pub fn lfence() {
+    crate::arch::aarch64::dmb_oshld();
+}
+
+#[cfg(target_arch = "aarch64")]
+#[inline]
+pub fn mfence() {
+    crate::arch::aarch64::dsb_sy();
+}
+
+// Stubs for other targets
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
 #[inline]
 pub fn sfence() {}
 
-#[cfg(not(target_arch = "x86_64"))]
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
 #[inline]
 pub fn lfence() {}
 
-#[cfg(not(target_arch = "x86_64"))]
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
 #[inline]
 pub fn mfence() {}
@@ -31,10 +31,34 @@ pub unsafe fn flush_range(addr: *const u8, len: usize) {
     asm_cache_flush_range(addr as u64, len as u64)
 }
 
-// Stubs for non-x86_64
-#[cfg(not(target_arch = "x86_64"))]
+/// Clean and invalidate the cache line containing address (`dc civac`).
+#[cfg(target_arch = "aarch64")]
+#[inline]
+pub unsafe fn clflush(addr: *const u8) {
+    crate::arch::aarch64::dc_civac(addr as u64);
+    crate::arch::aarch64::dsb_sy();
+}
+
+/// Clean and invalidate every cache line in the range.
+#[cfg(target_arch = "aarch64")]
+#[inline]
+pub unsafe fn flush_range(addr: *const u8, len: usize) {
+    use crate::arch::aarch64::{dc_civac, dcache_line_size, dsb_sy};
+
+    let line = dcache_line_size() as u64;
+    let mut p = addr as u64 & !(line - 1);
+    let end = addr as u64 + len as u64;
+    while p < end {
+        dc_civac(p);
+        p += line;
+    }
+    dsb_sy();
+}
+
+// Stubs for other targets
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
 #[inline]
 pub unsafe fn clflush(_addr: *const u8) {}
-#[cfg(not(target_arch = "x86_64"))]
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
 #[inline]
 pub unsafe fn flush_range(_addr: *const u8, _len: usize) {}
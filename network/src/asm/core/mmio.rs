@@ -1,5 +1,9 @@
 //! MMIO (Memory-Mapped I/O) bindings.
 //!
+//! On aarch64 these are volatile accesses with `writel`/`readl` style
+//! barriers: stores are ordered after prior stores to DMA memory, and
+//! later loads of DMA memory wait for the register read.
+//!
 //! # Safety
 //! - Address must be valid MMIO address
 //! - Address must be properly aligned
@@ -69,28 +73,74 @@ pub unsafe fn write32(addr: u64, value: u32) {
     asm_mmio_write32(addr, value)
 }
 
-// Stubs for non-x86_64
-#[cfg(not(target_arch = "x86_64"))]
+#[cfg(target_arch = "aarch64")]
+#[inline]
+unsafe fn mmio_read<T>(addr: u64) -> T {
+    let value = core::ptr::read_volatile(addr as *const T);
+    crate::arch::aarch64::dmb_oshld();
+    value
+}
+
+#[cfg(target_arch = "aarch64")]
+#[inline]
+unsafe fn mmio_write<T>(addr: u64, value: T) {
+    crate::arch::aarch64::dmb_oshst();
+    core::ptr::write_volatile(addr as *mut T, value);
+}
+
+#[cfg(target_arch = "aarch64")]
+#[inline]
+pub unsafe fn read8(addr: u64) -> u8 {
+    mmio_read(addr)
+}
+#[cfg(target_arch = "aarch64")]
+#[inline]
+pub unsafe fn write8(addr: u64, value: u8) {
+    mmio_write(addr, value)
+}
+#[cfg(target_arch = "aarch64")]
+#[inline]
+pub unsafe fn read16(addr: u64) -> u16 {
+    mmio_read(addr)
+}
+#[cfg(target_arch = "aarch64")]
+#[inline]
+pub unsafe fn write16(addr: u64, value: u16) {
+    mmio_write(addr, value)
+}
+#[cfg(target_arch = "aarch64")]
+#[inline]
+pub unsafe fn read32(addr: u64) -> u32 {
+    mmio_read(addr)
+}
+#[cfg(target_arch = "aarch64")]
+#[inline]
+pub unsafe fn write32(addr: u64, value: u32) {
+    mmio_write(addr, value)
+}
+
+// Stubs for other targets
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
 #[inline]
 pub unsafe fn read8(_addr: u64) -> u8 {
     0
 }
-#[cfg(not(target_arch = "x86_64"))]
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
 #[inline]
 pub unsafe fn write8(_addr: u64, _value: u8) {}
-#[cfg(not(target_arch = "x86_64"))]
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
 #[inline]
 pub unsafe fn read16(_addr: u64) -> u16 {
     0
 }
-#[cfg(not(target_arch = "x86_64"))]
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
 #[inline]
 pub unsafe fn write16(_addr: u64, _value: u16) {}
-#[cfg(not(target_arch = "x86_64"))]
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
 #[inline]
 pub unsafe fn read32(_addr: u64) -> u32 {
     0
 }
-#[cfg(not(target_arch = "x86_64"))]
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
 #[inline]
 pub unsafe fn write32(_addr: u64, _value: u32) {}
@@ -1,5 +1,8 @@
 //! TSC (Time Stamp Counter) bindings.
 //!
+//! On aarch64 the "TSC" is the generic timer's virtual counter
+//! (CNTVCT_EL0); its frequency comes from CNTFRQ_EL0 instead of calibration.
+//!
 //! # Safety
 //! TSC reads are always safe. Requires invariant TSC (verify via CPUID at boot).
 //!
@@ -35,15 +38,30 @@ pub fn read_tsc_serialized() -> u64 {
     unsafe { asm_tsc_read_serialized() }
 }
 
-/// Stub for non-x86_64 targets.
-#[cfg(not(target_arch = "x86_64"))]
+/// Read the generic timer counter.
+#[cfg(target_arch = "aarch64")]
+#[inline]
+pub fn read_tsc() -> u64 {
+    crate::arch::aarch64::cntvct()
+}
+
+/// Read the generic timer counter after all prior memory accesses complete.
+#[cfg(target_arch = "aarch64")]
+#[inline]
+pub fn read_tsc_serialized() -> u64 {
+    crate::arch::aarch64::dsb_sy();
+    crate::arch::aarch64::cntvct()
+}
+
+/// Stub for other targets.
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
 #[inline]
 pub fn read_tsc() -> u64 {
     0
 }
 
-/// Stub for non-x86_64 targets.
-#[cfg(not(target_arch = "x86_64"))]
+/// Stub for other targets.
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
 #[inline]
 pub fn read_tsc_serialized() -> u64 {
     0
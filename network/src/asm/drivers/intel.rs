@@ -36,10 +36,16 @@ impl RxPollResult {
     pub const ERR_SE: u8 = 1 << 1;
     /// Sequence error bit.
     pub const ERR_SEQ: u8 = 1 << 2;
+    /// TCP/UDP checksum error bit (only with RXCSUM offload).
+    pub const ERR_TCPE: u8 = 1 << 5;
+    /// IP checksum error bit (only with RXCSUM offload).
+    pub const ERR_IPE: u8 = 1 << 6;
     /// RX data error bit.
-    pub const ERR_RXE: u8 = 1 << 5;
+    pub const ERR_RXE: u8 = 1 << 7;
     /// All error bits mask.
     pub const ERR_MASK: u8 = Self::ERR_CE | Self::ERR_SE | Self::ERR_SEQ | Self::ERR_RXE;
+    /// Checksum error bits.
+    pub const ERR_CSUM: u8 = Self::ERR_TCPE | Self::ERR_IPE;
 
     /// Check if descriptor is done.
     #[inline]
@@ -503,6 +509,169 @@ extern "win64" {
     pub fn asm_intel_clear_smbus_mode(mmio_base: u64);
 }
 
+// ═══════════════════════════════════════════════════════════════════════════
+// Unported Targets
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// e1000e is only ported to x86_64. These keep the driver compiling
+/// elsewhere; `E1000eDriver::new` fails before any of them can run.
+#[cfg(not(target_arch = "x86_64"))]
+#[allow(dead_code, clippy::missing_safety_doc)]
+mod unported {
+    use super::{LinkStatusResult, RxPollResult};
+
+    pub unsafe fn asm_intel_reset(_: u64, _: u64) -> u32 {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_read_status(_: u64) -> u32 {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_read_mac(_: u64, _: *mut [u8; 6]) -> u32 {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_write_mac(_: u64, _: *const [u8; 6]) {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_clear_mta(_: u64) {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_disable_interrupts(_: u64) {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_setup_rx_ring(_: u64, _: u64, _: u32) {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_setup_tx_ring(_: u64, _: u64, _: u32) {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_enable_rx(_: u64) {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_enable_tx(_: u64) {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_set_link_up(_: u64) {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_read_reg(_: u64, _: u32) -> u32 {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_write_reg(_: u64, _: u32, _: u32) {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_tx_init_desc(_: *mut u8) {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_tx_submit(_: *mut u8, _: u64, _: u32) {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_tx_poll(_: *const u8) -> u32 {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_tx_update_tail(_: u64, _: u32) {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_tx_read_head(_: u64) -> u32 {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_tx_clear_desc(_: *mut u8) {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_rx_init_desc(_: *mut u8, _: u64) {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_rx_poll(_: *const u8, _: *mut RxPollResult) -> u32 {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_rx_update_tail(_: u64, _: u32) {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_rx_read_head(_: u64) -> u32 {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_rx_clear_desc(_: *mut u8) {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_rx_get_length(_: *const u8) -> u16 {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_rx_check_errors(_: *const u8) -> u32 {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_phy_read(_: u64, _: u32, _: u64) -> u32 {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_phy_write(_: u64, _: u32, _: u32, _: u64) -> u32 {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_link_status(_: u64, _: *mut LinkStatusResult) -> u32 {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_wait_link(_: u64, _: u64, _: u64) -> u32 {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_disable_ulp(_: u64, _: u64) -> u32 {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_toggle_lanphypc(_: u64, _: u64) -> u32 {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_phy_is_accessible(_: u64, _: u64) -> u32 {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_acquire_swflag(_: u64, _: u64) -> u32 {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_release_swflag(_: u64) {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_force_smbus_mode(_: u64) {
+        unreachable!("e1000e is x86_64-only")
+    }
+
+    pub unsafe fn asm_intel_clear_smbus_mode(_: u64) {
+        unreachable!("e1000e is x86_64-only")
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+pub use unported::*;
+
 // ═══════════════════════════════════════════════════════════════════════════
 // Safe Wrappers
 // ═══════════════════════════════════════════════════════════════════════════
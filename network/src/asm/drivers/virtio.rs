@@ -1,12 +1,17 @@
 //! VirtIO ASM bindings.
 //!
 //! Complete bindings for VirtIO device initialization and virtqueue operations.
+//! On targets without the assembly layer (aarch64) the same operations are
+//! implemented in Rust with identical ring semantics.
 //!
 //! # Reference
 //! NETWORK_IMPL_GUIDE.md §2.2.2, §4
 
 use crate::types::repr_c::{RxResult, VirtqueueState};
 
+#[cfg(not(target_arch = "x86_64"))]
+use crate::asm::core::{barriers::mfence, mmio};
+
 // ═══════════════════════════════════════════════════════════════════════════
 // Device Initialization Functions
 // ═══════════════════════════════════════════════════════════════════════════
@@ -149,6 +154,117 @@ extern "win64" {
     fn asm_vq_set_notify_addr(vq: *mut VirtqueueState, addr: u64);
 }
 
+// ═══════════════════════════════════════════════════════════════════════════
+// Rust Implementation (targets without the assembly layer)
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// "virt"
+#[cfg(not(target_arch = "x86_64"))]
+const VIRTIO_MAGIC: u32 = 0x7472_6976;
+
+/// VirtIO MMIO register offsets (VirtIO 1.2 §4.2.2).
+#[cfg(not(target_arch = "x86_64"))]
+mod regs {
+    pub const MAGIC: u64 = 0x000;
+    pub const VERSION: u64 = 0x004;
+    pub const DEVICE_ID: u64 = 0x008;
+    pub const DEVICE_FEATURES: u64 = 0x010;
+    pub const DEVICE_FEATURES_SEL: u64 = 0x014;
+    pub const DRIVER_FEATURES: u64 = 0x020;
+    pub const DRIVER_FEATURES_SEL: u64 = 0x024;
+    pub const QUEUE_SEL: u64 = 0x030;
+    pub const QUEUE_NUM_MAX: u64 = 0x034;
+    pub const QUEUE_NUM: u64 = 0x038;
+    pub const QUEUE_READY: u64 = 0x044;
+    pub const QUEUE_NOTIFY: u64 = 0x050;
+    pub const STATUS: u64 = 0x070;
+    pub const QUEUE_DESC_LOW: u64 = 0x080;
+    pub const QUEUE_DRIVER_LOW: u64 = 0x090;
+    pub const QUEUE_DEVICE_LOW: u64 = 0x0A0;
+    pub const CONFIG: u64 = 0x100;
+}
+
+/// Split virtqueue ring operations, mirroring `tx.s` / `rx.s`.
+///
+/// One descriptor per buffer, descriptor index == buffer index, rings
+/// identity mapped (CPU address == bus address).
+#[cfg(not(target_arch = "x86_64"))]
+mod ring {
+    use crate::asm::core::barriers::{lfence, mfence, sfence};
+    use crate::types::repr_c::VirtqueueState;
+    use core::ptr::{read_volatile, write_volatile};
+
+    pub const VIRTQ_DESC_F_WRITE: u16 = 2;
+
+    const AVAIL_IDX: u64 = 2;
+    const AVAIL_RING: u64 = 4;
+    const USED_IDX: u64 = 2;
+    const USED_RING: u64 = 4;
+
+    /// Buffers handed to the device and not yet returned.
+    pub fn in_flight(vq: &VirtqueueState) -> u16 {
+        vq.next_avail_idx.wrapping_sub(vq.last_used_idx)
+    }
+
+    /// Device's used index.
+    ///
+    /// # Safety
+    /// `vq.used_base` must point to the used ring.
+    pub unsafe fn used_idx(vq: &VirtqueueState) -> u16 {
+        read_volatile((vq.used_base + USED_IDX) as *const u16)
+    }
+
+    /// Fill descriptor `buffer_idx` and publish it. False if the queue is full.
+    ///
+    /// # Safety
+    /// `vq` must describe a live, initialized virtqueue.
+    pub unsafe fn submit(vq: &mut VirtqueueState, buffer_idx: u16, len: u16, flags: u16) -> bool {
+        if in_flight(vq) >= vq.queue_size {
+            return false;
+        }
+        let mask = vq.queue_size.wrapping_sub(1);
+
+        let desc = vq.desc_cpu_ptr + buffer_idx as u64 * 16;
+        let addr = vq.buffer_bus_base + buffer_idx as u64 * vq.buffer_size as u64;
+        write_volatile(desc as *mut u64, addr);
+        write_volatile((desc + 8) as *mut u32, len as u32);
+        write_volatile((desc + 12) as *mut u16, flags);
+        write_volatile((desc + 14) as *mut u16, 0);
+        sfence();
+
+        let avail_idx = (vq.avail_base + AVAIL_IDX) as *mut u16;
+        let idx = read_volatile(avail_idx);
+        let slot = vq.avail_base + AVAIL_RING + (idx & mask) as u64 * 2;
+        write_volatile(slot as *mut u16, buffer_idx);
+        sfence();
+
+        write_volatile(avail_idx, idx.wrapping_add(1));
+        vq.next_avail_idx = vq.next_avail_idx.wrapping_add(1);
+        mfence();
+        true
+    }
+
+    /// Take the next used element as `(id, len)`.
+    ///
+    /// # Safety
+    /// `vq` must describe a live, initialized virtqueue.
+    pub unsafe fn poll(vq: &mut VirtqueueState) -> Option<(u32, u32)> {
+        if used_idx(vq) == vq.last_used_idx {
+            return None;
+        }
+        lfence();
+
+        let slot = (vq.last_used_idx & vq.queue_size.wrapping_sub(1)) as u64;
+        let elem = vq.used_base + USED_RING + slot * 8;
+        let id = read_volatile(elem as *const u32);
+        let len = read_volatile((elem + 4) as *const u32);
+        lfence();
+
+        vq.last_used_idx = vq.last_used_idx.wrapping_add(1);
+        Some((id, len))
+    }
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // Safe Rust Wrappers
 // ═══════════════════════════════════════════════════════════════════════════
@@ -216,38 +332,71 @@ pub mod device {
         }
     }
 
-    // Stubs for non-x86_64
+    // Without the assembly layer: same register accesses via `core::mmio`.
     #[cfg(not(target_arch = "x86_64"))]
-    pub fn verify_magic(_mmio_base: u64) -> bool {
-        false
+    pub fn verify_magic(mmio_base: u64) -> bool {
+        unsafe { mmio::read32(mmio_base + regs::MAGIC) == VIRTIO_MAGIC }
     }
     #[cfg(not(target_arch = "x86_64"))]
-    pub fn get_version(_mmio_base: u64) -> u32 {
-        0
+    pub fn get_version(mmio_base: u64) -> u32 {
+        unsafe { mmio::read32(mmio_base + regs::VERSION) }
     }
     #[cfg(not(target_arch = "x86_64"))]
-    pub fn get_device_id(_mmio_base: u64) -> u32 {
-        0
+    pub fn get_device_id(mmio_base: u64) -> u32 {
+        unsafe { mmio::read32(mmio_base + regs::DEVICE_ID) }
     }
     #[cfg(not(target_arch = "x86_64"))]
-    pub fn reset(_mmio_base: u64) -> bool {
-        false
+    pub fn reset(mmio_base: u64) -> bool {
+        use crate::asm::core::tsc::read_tsc;
+
+        set_status(mmio_base, 0);
+        // 100ms, as in asm_virtio_reset
+        let timeout = crate::arch::counter_frequency().unwrap_or(0) / 10;
+        let start = read_tsc();
+        while get_status(mmio_base) != 0 {
+            if read_tsc().wrapping_sub(start) > timeout {
+                return false;
+            }
+            core::hint::spin_loop();
+        }
+        true
     }
     #[cfg(not(target_arch = "x86_64"))]
-    pub fn set_status(_mmio_base: u64, _status: u8) {}
+    pub fn set_status(mmio_base: u64, status: u8) {
+        unsafe { mmio::write32(mmio_base + regs::STATUS, status as u32) };
+        mfence();
+    }
     #[cfg(not(target_arch = "x86_64"))]
-    pub fn get_status(_mmio_base: u64) -> u8 {
-        0
+    pub fn get_status(mmio_base: u64) -> u8 {
+        unsafe { mmio::read32(mmio_base + regs::STATUS) as u8 }
     }
     #[cfg(not(target_arch = "x86_64"))]
-    pub fn read_features(_mmio_base: u64) -> u64 {
-        0
+    pub fn read_features(mmio_base: u64) -> u64 {
+        unsafe {
+            mmio::write32(mmio_base + regs::DEVICE_FEATURES_SEL, 0);
+            let low = mmio::read32(mmio_base + regs::DEVICE_FEATURES) as u64;
+            mmio::write32(mmio_base + regs::DEVICE_FEATURES_SEL, 1);
+            let high = mmio::read32(mmio_base + regs::DEVICE_FEATURES) as u64;
+            (high << 32) | low
+        }
     }
     #[cfg(not(target_arch = "x86_64"))]
-    pub fn write_features(_mmio_base: u64, _features: u64) {}
+    pub fn write_features(mmio_base: u64, features: u64) {
+        unsafe {
+            mmio::write32(mmio_base + regs::DRIVER_FEATURES_SEL, 0);
+            mmio::write32(mmio_base + regs::DRIVER_FEATURES, features as u32);
+            mmio::write32(mmio_base + regs::DRIVER_FEATURES_SEL, 1);
+            mmio::write32(mmio_base + regs::DRIVER_FEATURES, (features >> 32) as u32);
+        }
+        mfence();
+    }
     #[cfg(not(target_arch = "x86_64"))]
-    pub fn read_mac(_mmio_base: u64) -> Option<[u8; 6]> {
-        None
+    pub fn read_mac(mmio_base: u64) -> Option<[u8; 6]> {
+        let mut mac = [0u8; 6];
+        for (i, b) in mac.iter_mut().enumerate() {
+            *b = unsafe { mmio::read8(mmio_base + regs::CONFIG + i as u64) };
+        }
+        Some(mac)
     }
 }
 
@@ -332,40 +481,67 @@ pub mod queue {
         0x50 // VirtIO MMIO QueueNotify register offset
     }
 
-    // Stubs for non-x86_64
     #[cfg(not(target_arch = "x86_64"))]
-    pub fn select(_mmio_base: u64, _queue_idx: u16) {}
+    pub fn select(mmio_base: u64, queue_idx: u16) {
+        unsafe { mmio::write32(mmio_base + regs::QUEUE_SEL, queue_idx as u32) };
+    }
     #[cfg(not(target_arch = "x86_64"))]
-    pub fn get_max_size(_mmio_base: u64) -> u16 {
-        0
+    pub fn get_max_size(mmio_base: u64) -> u16 {
+        unsafe { mmio::read32(mmio_base + regs::QUEUE_NUM_MAX) as u16 }
     }
     #[cfg(not(target_arch = "x86_64"))]
-    pub fn get_size(_mmio_base: u64) -> u16 {
-        0
+    pub fn get_size(mmio_base: u64) -> u16 {
+        get_max_size(mmio_base)
     }
     #[cfg(not(target_arch = "x86_64"))]
-    pub fn set_size(_mmio_base: u64, _size: u16) {}
+    pub fn set_size(mmio_base: u64, size: u16) {
+        unsafe { mmio::write32(mmio_base + regs::QUEUE_NUM, size as u32) };
+    }
     #[cfg(not(target_arch = "x86_64"))]
-    pub fn set_desc_addr(_mmio_base: u64, _addr: u64) {}
+    pub fn set_desc_addr(mmio_base: u64, addr: u64) {
+        write_addr(mmio_base + regs::QUEUE_DESC_LOW, addr);
+    }
     #[cfg(not(target_arch = "x86_64"))]
-    pub fn set_driver_addr(_mmio_base: u64, _addr: u64) {}
+    pub fn set_driver_addr(mmio_base: u64, addr: u64) {
+        write_addr(mmio_base + regs::QUEUE_DRIVER_LOW, addr);
+    }
     #[cfg(not(target_arch = "x86_64"))]
-    pub fn set_device_addr(_mmio_base: u64, _addr: u64) {}
+    pub fn set_device_addr(mmio_base: u64, addr: u64) {
+        write_addr(mmio_base + regs::QUEUE_DEVICE_LOW, addr);
+    }
     #[cfg(not(target_arch = "x86_64"))]
-    pub fn enable(_mmio_base: u64) {}
+    pub fn enable(mmio_base: u64) {
+        unsafe { mmio::write32(mmio_base + regs::QUEUE_READY, 1) };
+        mfence();
+    }
     #[cfg(not(target_arch = "x86_64"))]
     pub fn setup(
-        _mmio_base: u64,
-        _queue_idx: u16,
-        _size: u16,
-        _desc_addr: u64,
-        _driver_addr: u64,
-        _device_addr: u64,
+        mmio_base: u64,
+        queue_idx: u16,
+        size: u16,
+        desc_addr: u64,
+        driver_addr: u64,
+        device_addr: u64,
     ) {
+        select(mmio_base, queue_idx);
+        set_size(mmio_base, size);
+        set_desc_addr(mmio_base, desc_addr);
+        set_driver_addr(mmio_base, driver_addr);
+        set_device_addr(mmio_base, device_addr);
+        enable(mmio_base);
     }
     #[cfg(not(target_arch = "x86_64"))]
     pub fn get_notify_offset(_mmio_base: u64) -> u64 {
-        0x50
+        regs::QUEUE_NOTIFY
+    }
+
+    /// Write a 64-bit address as LOW/HIGH register pair.
+    #[cfg(not(target_arch = "x86_64"))]
+    fn write_addr(low_reg: u64, addr: u64) {
+        unsafe {
+            mmio::write32(low_reg, addr as u32);
+            mmio::write32(low_reg + 4, (addr >> 32) as u32);
+        }
     }
 }
 
@@ -398,18 +574,17 @@ pub mod tx {
         unsafe { asm_vq_tx_avail_slots(vq) }
     }
 
-    // Stubs for non-x86_64
     #[cfg(not(target_arch = "x86_64"))]
-    pub fn submit(_vq: &mut VirtqueueState, _buffer_idx: u16, _len: u16) -> bool {
-        false
+    pub fn submit(vq: &mut VirtqueueState, buffer_idx: u16, len: u16) -> bool {
+        unsafe { ring::submit(vq, buffer_idx, len, 0) }
     }
     #[cfg(not(target_arch = "x86_64"))]
-    pub fn poll_complete(_vq: &mut VirtqueueState) -> Option<u16> {
-        None
+    pub fn poll_complete(vq: &mut VirtqueueState) -> Option<u16> {
+        unsafe { ring::poll(vq) }.map(|(id, _)| id as u16)
     }
     #[cfg(not(target_arch = "x86_64"))]
-    pub fn available_slots(_vq: &mut VirtqueueState) -> u16 {
-        0
+    pub fn available_slots(vq: &mut VirtqueueState) -> u16 {
+        vq.queue_size.wrapping_sub(ring::in_flight(vq))
     }
 }
 
@@ -442,28 +617,47 @@ pub mod rx {
         unsafe { asm_vq_rx_pending(vq) }
     }
 
-    // Stubs for non-x86_64
     #[cfg(not(target_arch = "x86_64"))]
-    pub fn submit(_vq: &mut VirtqueueState, _buffer_idx: u16, _capacity: u16) -> bool {
-        false
+    pub fn submit(vq: &mut VirtqueueState, buffer_idx: u16, capacity: u16) -> bool {
+        unsafe { ring::submit(vq, buffer_idx, capacity, ring::VIRTQ_DESC_F_WRITE) }
     }
     #[cfg(not(target_arch = "x86_64"))]
-    pub fn poll(_vq: &mut VirtqueueState) -> Option<RxResult> {
-        None
+    pub fn poll(vq: &mut VirtqueueState) -> Option<RxResult> {
+        unsafe { ring::poll(vq) }.map(|(id, len)| RxResult {
+            buffer_idx: id as u16,
+            length: len as u16,
+            _reserved: 0,
+        })
     }
     #[cfg(not(target_arch = "x86_64"))]
-    pub fn pending_count(_vq: &mut VirtqueueState) -> u16 {
-        0
+    pub fn pending_count(vq: &mut VirtqueueState) -> u16 {
+        unsafe { ring::used_idx(vq) }.wrapping_sub(vq.last_used_idx)
     }
 }
 
 /// Notification operations.
 pub mod notify {
     use super::*;
+    use crate::asm::core::barriers::mfence;
+    use crate::asm::core::pio::outw;
+
+    /// Tag on a notify address that is an I/O port (PCI legacy transport)
+    /// rather than an MMIO address; the port is in the low 16 bits.
+    pub const PIO_NOTIFY: u64 = 1 << 63;
+
+    /// Port I/O notify for the legacy transport.
+    fn notify_port(notify_addr: u64, queue_idx: u16) {
+        // Descriptors and avail idx must be visible before the kick
+        mfence();
+        unsafe { outw(notify_addr as u16, queue_idx) }
+    }
 
     /// Notify device about queue activity.
     #[cfg(target_arch = "x86_64")]
     pub fn notify(vq: &mut VirtqueueState) {
+        if vq.notify_addr & PIO_NOTIFY != 0 {
+            return notify_port(vq.notify_addr, vq.queue_index);
+        }
         // Sanity check the notify_addr (keep minimal safety checks)
         if vq.notify_addr == 0 || vq.notify_addr < 0x1000 {
             return; // Invalid notify address, skip
@@ -474,6 +668,9 @@ pub mod notify {
     /// Direct notify with explicit address.
     #[cfg(target_arch = "x86_64")]
     pub fn notify_direct(notify_addr: u64, queue_idx: u16) {
+        if notify_addr & PIO_NOTIFY != 0 {
+            return notify_port(notify_addr, queue_idx);
+        }
         unsafe { asm_vq_notify_direct(notify_addr, queue_idx) }
     }
 
@@ -483,13 +680,25 @@ pub mod notify {
         unsafe { asm_vq_should_notify(vq) == 1 }
     }
 
-    // Stubs for non-x86_64
     #[cfg(not(target_arch = "x86_64"))]
-    pub fn notify(_vq: &mut VirtqueueState) {}
+    pub fn notify(vq: &mut VirtqueueState) {
+        if vq.notify_addr & PIO_NOTIFY == 0 && (vq.notify_addr == 0 || vq.notify_addr < 0x1000) {
+            return;
+        }
+        notify_direct(vq.notify_addr, vq.queue_index);
+    }
     #[cfg(not(target_arch = "x86_64"))]
-    pub fn notify_direct(_notify_addr: u64, _queue_idx: u16) {}
+    pub fn notify_direct(notify_addr: u64, queue_idx: u16) {
+        if notify_addr & PIO_NOTIFY != 0 {
+            return notify_port(notify_addr, queue_idx);
+        }
+        mfence();
+        unsafe { mmio::write16(notify_addr, queue_idx) };
+    }
     #[cfg(not(target_arch = "x86_64"))]
-    pub fn should_notify(_vq: &mut VirtqueueState) -> bool {
-        false
+    pub fn should_notify(vq: &mut VirtqueueState) -> bool {
+        // VIRTQ_USED_F_NO_NOTIFY
+        let flags = unsafe { core::ptr::read_volatile(vq.used_base as *const u16) };
+        flags & 1 == 0
     }
 }
@@ -6,6 +6,9 @@
 //! # Reference
 //! ARCHITECTURE_V3.md - PCI layer
 
+#[cfg(not(target_arch = "x86_64"))]
+use crate::asm::core::mmio;
+
 #[cfg(target_arch = "x86_64")]
 extern "win64" {
     /// Calculate ECAM address for a device register.
@@ -71,26 +74,44 @@ pub unsafe fn write32(ecam_base: u64, bus: u8, dev: u8, func: u8, reg: u16, val:
     asm_pci_ecam_write32(ecam_base, bus, dev, func, reg, val)
 }
 
-// Stubs for non-x86_64
+// Without the assembly layer the same accesses go through `core::mmio`,
+// which carries the aarch64 barriers.
 #[cfg(not(target_arch = "x86_64"))]
-pub fn ecam_addr(_ecam_base: u64, _bus: u8, _dev: u8, _func: u8, _reg: u16) -> u64 {
-    0
+#[inline]
+pub fn ecam_addr(ecam_base: u64, bus: u8, dev: u8, func: u8, reg: u16) -> u64 {
+    ecam_base
+        + ((bus as u64) << 20)
+        + (((dev & 0x1F) as u64) << 15)
+        + (((func & 0x7) as u64) << 12)
+        + (reg & 0xFFF) as u64
 }
 #[cfg(not(target_arch = "x86_64"))]
-pub unsafe fn read8(_ecam_base: u64, _bus: u8, _dev: u8, _func: u8, _reg: u16) -> u8 {
-    0
+#[inline]
+pub unsafe fn read8(ecam_base: u64, bus: u8, dev: u8, func: u8, reg: u16) -> u8 {
+    mmio::read8(ecam_addr(ecam_base, bus, dev, func, reg))
 }
 #[cfg(not(target_arch = "x86_64"))]
-pub unsafe fn write8(_ecam_base: u64, _bus: u8, _dev: u8, _func: u8, _reg: u16, _val: u8) {}
+#[inline]
+pub unsafe fn write8(ecam_base: u64, bus: u8, dev: u8, func: u8, reg: u16, val: u8) {
+    mmio::write8(ecam_addr(ecam_base, bus, dev, func, reg), val)
+}
 #[cfg(not(target_arch = "x86_64"))]
-pub unsafe fn read16(_ecam_base: u64, _bus: u8, _dev: u8, _func: u8, _reg: u16) -> u16 {
-    0
+#[inline]
+pub unsafe fn read16(ecam_base: u64, bus: u8, dev: u8, func: u8, reg: u16) -> u16 {
+    mmio::read16(ecam_addr(ecam_base, bus, dev, func, reg))
 }
 #[cfg(not(target_arch = "x86_64"))]
-pub unsafe fn write16(_ecam_base: u64, _bus: u8, _dev: u8, _func: u8, _reg: u16, _val: u16) {}
+#[inline]
+pub unsafe fn write16(ecam_base: u64, bus: u8, dev: u8, func: u8, reg: u16, val: u16) {
+    mmio::write16(ecam_addr(ecam_base, bus, dev, func, reg), val)
+}
 #[cfg(not(target_arch = "x86_64"))]
-pub unsafe fn read32(_ecam_base: u64, _bus: u8, _dev: u8, _func: u8, _reg: u16) -> u32 {
-    0
+#[inline]
+pub unsafe fn read32(ecam_base: u64, bus: u8, dev: u8, func: u8, reg: u16) -> u32 {
+    mmio::read32(ecam_addr(ecam_base, bus, dev, func, reg))
 }
 #[cfg(not(target_arch = "x86_64"))]
-pub unsafe fn write32(_ecam_base: u64, _bus: u8, _dev: u8, _func: u8, _reg: u16, _val: u32) {}
+#[inline]
+pub unsafe fn write32(ecam_base: u64, bus: u8, dev: u8, func: u8, reg: u16, val: u32) {
+    mmio::write32(ecam_addr(ecam_base, bus, dev, func, reg), val)
+}
@@ -6,6 +6,12 @@
 //! # Supported Devices
 //! - VirtIO-blk (QEMU, cloud VMs)
 //! - AHCI SATA (Intel - ThinkPad T450s, etc.)
+//! - USB mass storage on an xHCI controller's root ports (USB sticks)
+//! - NVMe controllers are detected, but have no driver yet
+//!
+//! [`scan_block_devices`] lists every supported device and
+//! [`probe_block_devices`] brings all of them up at once, so the download
+//! target and the disk holding the ESP need not be the same device.
 //!
 //! # Usage
 //!
@@ -16,13 +22,18 @@
 //! match result {
 //!     BlockProbeResult::VirtIO(driver) => { /* use driver */ }
 //!     BlockProbeResult::Ahci(driver) => { /* use driver */ }
+//!     BlockProbeResult::Usb(driver) => { /* use driver */ }
 //! }
 //! ```
 
+use alloc::vec::Vec;
+
+use super::handoff::{BLK_TYPE_AHCI, BLK_TYPE_NVME, BLK_TYPE_USB, BLK_TYPE_VIRTIO};
 use crate::device::{UnifiedBlockDevice, UnifiedBlockError};
 use crate::driver::ahci::{
     AhciConfig, AhciDriver, AhciInitError, AHCI_DEVICE_IDS, INTEL_VENDOR_ID,
 };
+use crate::driver::usb_msc::{UsbMscConfig, UsbMscDriver, UsbMscInitError, PCI_CLASS_XHCI};
 use crate::driver::virtio_blk::{VirtioBlkConfig, VirtioBlkDriver, VirtioBlkInitError};
 use crate::pci::config::{offset, pci_cfg_read16, pci_cfg_read32, pci_cfg_write16, PciAddr};
 
@@ -40,6 +51,12 @@ const VIRTIO_BLK_DEVICE_MODERN: u16 = 0x1042;
 /// PCI Class code for SATA AHCI controller
 const PCI_CLASS_SATA_AHCI: u32 = 0x0106;
 
+/// PCI Class code for NVMe controller
+const PCI_CLASS_NVME: u32 = 0x0108;
+
+/// Most block devices [`scan_block_devices`] reports
+pub const MAX_BLOCK_DEVICES: usize = 8;
+
 // ═══════════════════════════════════════════════════════════════════════════
 // PROBE ERRORS
 // ═══════════════════════════════════════════════════════════════════════════
@@ -53,10 +70,14 @@ pub enum BlockProbeError {
     VirtioInitFailed,
     /// AHCI initialization failed
     AhciInitFailed,
+    /// xHCI initialization failed, or no mass storage device on it
+    UsbInitFailed,
     /// BAR mapping failed
     BarMappingFailed,
     /// Device not responding
     DeviceNotResponding,
+    /// Detected, but there is no driver for it
+    Unsupported,
 }
 
 impl From<VirtioBlkInitError> for BlockProbeError {
@@ -71,6 +92,12 @@ impl From<AhciInitError> for BlockProbeError {
     }
 }
 
+impl From<UsbMscInitError> for BlockProbeError {
+    fn from(_: UsbMscInitError) -> Self {
+        BlockProbeError::UsbInitFailed
+    }
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // DETECTED DEVICE INFO
 // ═══════════════════════════════════════════════════════════════════════════
@@ -82,6 +109,32 @@ pub enum DetectedBlockDevice {
     VirtIO { pci_addr: PciAddr, mmio_base: u64 },
     /// AHCI SATA controller
     Ahci(AhciInfo),
+    /// xHCI controller, possibly with mass storage on a root port
+    Usb { pci_addr: PciAddr, mmio_base: u64 },
+    /// NVMe controller (no driver yet)
+    Nvme { pci_addr: PciAddr, mmio_base: u64 },
+}
+
+impl DetectedBlockDevice {
+    /// PCI address of the controller.
+    pub fn pci_addr(&self) -> PciAddr {
+        match self {
+            Self::VirtIO { pci_addr, .. }
+            | Self::Usb { pci_addr, .. }
+            | Self::Nvme { pci_addr, .. } => *pci_addr,
+            Self::Ahci(info) => info.pci_addr,
+        }
+    }
+
+    /// Handoff block device type (`BLK_TYPE_*`).
+    pub fn handoff_type(&self) -> u8 {
+        match self {
+            Self::VirtIO { .. } => BLK_TYPE_VIRTIO,
+            Self::Ahci(_) => BLK_TYPE_AHCI,
+            Self::Usb { .. } => BLK_TYPE_USB,
+            Self::Nvme { .. } => BLK_TYPE_NVME,
+        }
+    }
 }
 
 /// Information about detected AHCI controller.
@@ -101,6 +154,8 @@ pub enum BlockProbeResult {
     VirtIO(VirtioBlkDriver),
     /// AHCI SATA driver
     Ahci(AhciDriver),
+    /// USB mass storage driver
+    Usb(UsbMscDriver),
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -110,7 +165,9 @@ pub enum BlockProbeResult {
 /// Scan PCI bus for supported block devices.
 ///
 /// Returns the first supported block device found, preferring AHCI over VirtIO
-/// (for real hardware priority - matches network probe behavior).
+/// (for real hardware priority - matches network probe behavior). An xHCI
+/// controller is only picked when neither is present; whether a USB stick
+/// is plugged in shows once its driver initializes.
 pub fn scan_for_block_device() -> Option<DetectedBlockDevice> {
     // First try to find AHCI controller (real hardware)
     if let Some(info) = find_ahci_controller() {
@@ -125,7 +182,146 @@ pub fn scan_for_block_device() -> Option<DetectedBlockDevice> {
         });
     }
 
-    None
+    // Then USB mass storage
+    let mut usb = None;
+    for_each_function(|addr, _| {
+        if usb.is_none() {
+            usb = xhci_base(addr).map(|mmio_base| DetectedBlockDevice::Usb {
+                pci_addr: addr,
+                mmio_base,
+            });
+        }
+    });
+    usb
+}
+
+/// Scan PCI bus for every supported block device.
+///
+/// AHCI controllers come first, then VirtIO-blk, then xHCI, then NVMe,
+/// each in bus order, so the first entry is the device
+/// [`scan_for_block_device`] picks.
+pub fn scan_block_devices() -> Vec<DetectedBlockDevice> {
+    let mut ahci = Vec::new();
+    let mut virtio = Vec::new();
+    let mut usb = Vec::new();
+    let mut nvme = Vec::new();
+    for_each_function(|addr, vendor_id| {
+        if let Some(info) = ahci_info(addr, vendor_id) {
+            ahci.push(DetectedBlockDevice::Ahci(info));
+        } else if let Some(mmio_base) = virtio_blk_base(addr, vendor_id) {
+            virtio.push(DetectedBlockDevice::VirtIO {
+                pci_addr: addr,
+                mmio_base,
+            });
+        } else if let Some(mmio_base) = xhci_base(addr) {
+            usb.push(DetectedBlockDevice::Usb {
+                pci_addr: addr,
+                mmio_base,
+            });
+        } else if let Some(mmio_base) = nvme_base(addr) {
+            nvme.push(DetectedBlockDevice::Nvme {
+                pci_addr: addr,
+                mmio_base,
+            });
+        }
+    });
+
+    let mut devices = ahci;
+    devices.append(&mut virtio);
+    devices.append(&mut usb);
+    devices.append(&mut nvme);
+    devices.truncate(MAX_BLOCK_DEVICES);
+    devices
+}
+
+/// Call `f` with the address and vendor ID of every present PCI function.
+fn for_each_function(mut f: impl FnMut(PciAddr, u16)) {
+    for bus in 0..=255u8 {
+        for device in 0..32u8 {
+            for function in 0..8u8 {
+                let addr = PciAddr::new(bus, device, function);
+
+                let vendor_id = pci_cfg_read16(addr, offset::VENDOR_ID);
+                if vendor_id == 0xFFFF {
+                    if function == 0 {
+                        break;
+                    }
+                    continue;
+                }
+
+                f(addr, vendor_id);
+
+                // Single-function device: skip functions 1-7
+                if function == 0 && pci_cfg_read16(addr, offset::HEADER_TYPE) & 0x80 == 0 {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// AHCI details of the function at `addr`, if it is a supported AHCI
+/// controller.
+fn ahci_info(addr: PciAddr, vendor_id: u16) -> Option<AhciInfo> {
+    if vendor_id != INTEL_VENDOR_ID {
+        return None;
+    }
+    let class = (pci_cfg_read32(addr, offset::CLASS_CODE) >> 8) & 0xFFFF;
+    let device_id = pci_cfg_read16(addr, offset::DEVICE_ID);
+    if class != PCI_CLASS_SATA_AHCI || !AHCI_DEVICE_IDS.contains(&device_id) {
+        return None;
+    }
+    let abar = memory_bar(addr, offset::BAR5)?;
+    Some(AhciInfo {
+        pci_addr: addr,
+        abar,
+        device_id,
+    })
+}
+
+/// MMIO base of the function at `addr`, if it is a VirtIO-blk device.
+fn virtio_blk_base(addr: PciAddr, vendor_id: u16) -> Option<u64> {
+    let device_id = pci_cfg_read16(addr, offset::DEVICE_ID);
+    if vendor_id != VIRTIO_VENDOR_ID
+        || (device_id != VIRTIO_BLK_DEVICE_LEGACY && device_id != VIRTIO_BLK_DEVICE_MODERN)
+    {
+        return None;
+    }
+    memory_bar(addr, offset::BAR0)
+}
+
+/// MMIO base of the function at `addr`, if it is an NVMe controller.
+fn nvme_base(addr: PciAddr) -> Option<u64> {
+    let class = (pci_cfg_read32(addr, offset::CLASS_CODE) >> 8) & 0xFFFF;
+    if class != PCI_CLASS_NVME {
+        return None;
+    }
+    memory_bar(addr, offset::BAR0)
+}
+
+/// MMIO base of the function at `addr`, if it is an xHCI controller.
+fn xhci_base(addr: PciAddr) -> Option<u64> {
+    // Class, subclass and programming interface
+    let class = pci_cfg_read32(addr, offset::CLASS_CODE) >> 8;
+    if class != PCI_CLASS_XHCI {
+        return None;
+    }
+    memory_bar(addr, offset::BAR0)
+}
+
+/// Address a memory BAR decodes (`None` for an absent or I/O BAR).
+fn memory_bar(addr: PciAddr, bar: u8) -> Option<u64> {
+    let low = pci_cfg_read32(addr, bar);
+    if low == 0 || low & 0x01 != 0 {
+        return None;
+    }
+    let base = (low & 0xFFFFFFF0) as u64;
+    if low & 0x06 == 0x04 {
+        let high = pci_cfg_read32(addr, bar + 4);
+        Some(((high as u64) << 32) | base)
+    } else {
+        Some(base)
+    }
 }
 
 /// Scan for AHCI SATA controller.
@@ -285,8 +481,14 @@ pub struct BlockDmaConfig {
     pub virtio_status_phys: u64,
     /// Notify address (for MMIO mode)
     pub virtio_notify_addr: u64,
+    /// Indirect descriptor tables (CPU pointer, null to disable)
+    pub virtio_indirect_cpu: *mut u8,
+    /// Indirect descriptor tables (physical, 48 bytes per request)
+    pub virtio_indirect_phys: u64,
     /// Queue size
     pub queue_size: u16,
+    /// Most requests kept in flight (0 = as many as the queue holds)
+    pub queue_depth: u16,
 
     // For AHCI
     /// Command List (CPU pointer, 1K aligned)
@@ -305,6 +507,13 @@ pub struct BlockDmaConfig {
     pub ahci_identify_cpu: *mut u8,
     /// IDENTIFY buffer (physical)
     pub ahci_identify_phys: u64,
+
+    // For USB mass storage
+    /// Rings, contexts and buffers (CPU pointer, 4K aligned,
+    /// `USB_MSC_DMA_SIZE` bytes)
+    pub usb_dma_cpu: *mut u8,
+    /// Rings, contexts and buffers (physical)
+    pub usb_dma_phys: u64,
 }
 
 /// Enable PCI device (bus mastering, memory space).
@@ -323,7 +532,18 @@ pub unsafe fn probe_and_create_block_driver(
     config: &BlockDmaConfig,
 ) -> Result<BlockProbeResult, BlockProbeError> {
     let detected = scan_for_block_device().ok_or(BlockProbeError::NoDevice)?;
+    create_block_driver(detected, config)
+}
 
+/// Create the driver for a detected block device.
+///
+/// # Safety
+/// Same as [`probe_and_create_block_driver`]. `config` must not be in use
+/// by another device.
+pub unsafe fn create_block_driver(
+    detected: DetectedBlockDevice,
+    config: &BlockDmaConfig,
+) -> Result<BlockProbeResult, BlockProbeError> {
     match detected {
         DetectedBlockDevice::Ahci(info) => {
             // Enable device
@@ -366,15 +586,63 @@ pub unsafe fn probe_and_create_block_driver(
                 status_cpu: config.virtio_status_cpu as u64,
                 notify_addr: config.virtio_notify_addr,
                 transport_type: 0, // MMIO
+                indirect_phys: if config.virtio_indirect_cpu.is_null() {
+                    0
+                } else {
+                    config.virtio_indirect_phys
+                },
+                indirect_cpu: config.virtio_indirect_cpu as u64,
+                queue_depth: config.queue_depth,
             };
 
             // Create driver
             let driver = VirtioBlkDriver::new(mmio_base, virtio_config)?;
             Ok(BlockProbeResult::VirtIO(driver))
         }
+
+        DetectedBlockDevice::Usb {
+            pci_addr,
+            mmio_base,
+        } => {
+            // Enable device
+            enable_pci_device(pci_addr);
+
+            let usb_config = UsbMscConfig {
+                tsc_freq: config.tsc_freq,
+                dma_cpu: config.usb_dma_cpu,
+                dma_phys: config.usb_dma_phys,
+            };
+
+            // Create driver
+            let driver = UsbMscDriver::new(mmio_base, usb_config)?;
+            Ok(BlockProbeResult::Usb(driver))
+        }
+
+        DetectedBlockDevice::Nvme { .. } => Err(BlockProbeError::Unsupported),
     }
 }
 
+/// Bring up every supported block device.
+///
+/// The n-th device [`scan_block_devices`] finds gets `configs[n]`; devices
+/// beyond the configs, without a driver or failing to initialize are left
+/// out.
+///
+/// # Safety
+/// Same as [`probe_and_create_block_driver`], for each config.
+pub unsafe fn probe_block_devices(configs: &[BlockDmaConfig]) -> Vec<UnifiedBlockDevice> {
+    scan_block_devices()
+        .into_iter()
+        .zip(configs)
+        .filter_map(|(detected, config)| match create_block_driver(detected, config) {
+            Ok(BlockProbeResult::VirtIO(driver)) => Some(UnifiedBlockDevice::VirtIO(driver)),
+            Ok(BlockProbeResult::Ahci(driver)) => Some(UnifiedBlockDevice::Ahci(driver)),
+            Ok(BlockProbeResult::Usb(driver)) => Some(UnifiedBlockDevice::Usb(driver)),
+            Err(_) => None,
+        })
+        .collect()
+}
+
 /// Probe and create unified block device.
 ///
 /// This is the main entry point for block device access.
@@ -388,9 +656,11 @@ pub unsafe fn probe_unified_block_device(
     match probe_and_create_block_driver(config) {
         Ok(BlockProbeResult::VirtIO(driver)) => Ok(UnifiedBlockDevice::VirtIO(driver)),
         Ok(BlockProbeResult::Ahci(driver)) => Ok(UnifiedBlockDevice::Ahci(driver)),
+        Ok(BlockProbeResult::Usb(driver)) => Ok(UnifiedBlockDevice::Usb(driver)),
         Err(BlockProbeError::NoDevice) => Err(UnifiedBlockError::NoDevice),
         Err(BlockProbeError::VirtioInitFailed) => Err(UnifiedBlockError::NoDevice),
         Err(BlockProbeError::AhciInitFailed) => Err(UnifiedBlockError::NoDevice),
+        Err(BlockProbeError::UsbInitFailed) => Err(UnifiedBlockError::NoDevice),
         Err(_) => Err(UnifiedBlockError::NoDevice),
     }
 }
@@ -406,6 +676,7 @@ pub enum BlockDeviceType {
     None = 0,
     VirtIO = 1,
     Ahci = 2,
+    Usb = 3,
 }
 
 /// Detect what type of block device is present without initializing.
@@ -422,5 +693,14 @@ pub fn detect_block_device_type() -> (BlockDeviceType, Option<u64>, Option<PciAd
         return (BlockDeviceType::VirtIO, Some(mmio_base), Some(pci_addr));
     }
 
+    // Then an xHCI controller that may have a USB stick attached
+    if let Some(DetectedBlockDevice::Usb {
+        pci_addr,
+        mmio_base,
+    }) = scan_for_block_device()
+    {
+        return (BlockDeviceType::Usb, Some(mmio_base), Some(pci_addr));
+    }
+
     (BlockDeviceType::None, None, None)
 }
@@ -13,6 +13,9 @@
 
 use core::fmt;
 
+use super::memory_map::MemoryMap;
+use crate::time::{select_clock, PmTimerClock, SystemClock, HPET_DEFAULT_BASE};
+
 // ═══════════════════════════════════════════════════════════════════════════
 // CONSTANTS
 // ═══════════════════════════════════════════════════════════════════════════
@@ -20,8 +23,20 @@ use core::fmt;
 /// Magic number: "MORPHEUS" in ASCII (little-endian)
 pub const HANDOFF_MAGIC: u64 = 0x5355_4548_5052_4F4D;
 
-/// Current structure version
-pub const HANDOFF_VERSION: u32 = 1;
+/// Current structure version (`BootHandoffV2`)
+pub const HANDOFF_VERSION: u32 = 2;
+
+/// Original single-device layout (`BootHandoff` alone)
+pub const HANDOFF_VERSION_V1: u32 = 1;
+
+/// Oldest version this build still accepts
+pub const HANDOFF_MIN_VERSION: u32 = HANDOFF_VERSION_V1;
+
+/// NIC slots in a v2 handoff
+pub const MAX_HANDOFF_NICS: usize = 8;
+
+/// Block device slots in a v2 handoff
+pub const MAX_HANDOFF_BLKS: usize = 8;
 
 /// Minimum DMA region size (2MB)
 pub const MIN_DMA_SIZE: u64 = 2 * 1024 * 1024;
@@ -30,8 +45,14 @@ pub const MIN_DMA_SIZE: u64 = 2 * 1024 * 1024;
 pub const MIN_STACK_SIZE: u64 = 64 * 1024;
 
 /// Minimum TSC frequency (1 GHz - sanity check)
+#[cfg(not(target_arch = "aarch64"))]
 pub const MIN_TSC_FREQ: u64 = 1_000_000_000;
 
+/// Minimum tick frequency on aarch64, whose generic timer typically runs
+/// at 24-100 MHz (1 MHz - sanity check)
+#[cfg(target_arch = "aarch64")]
+pub const MIN_TSC_FREQ: u64 = 1_000_000;
+
 /// Maximum TSC frequency (10 GHz - sanity check)
 pub const MAX_TSC_FREQ: u64 = 10_000_000_000;
 
@@ -62,6 +83,9 @@ pub const BLK_TYPE_VIRTIO: u8 = 1;
 pub const BLK_TYPE_NVME: u8 = 2;
 /// AHCI/SATA device (future)
 pub const BLK_TYPE_AHCI: u8 = 3;
+/// USB mass storage behind an xHCI controller (`blk_mmio_base` is the
+/// controller's BAR0)
+pub const BLK_TYPE_USB: u8 = 4;
 
 // ═══════════════════════════════════════════════════════════════════════════
 // TRANSPORT TYPE CONSTANTS
@@ -74,6 +98,13 @@ pub const TRANSPORT_PCI_MODERN: u8 = 1;
 /// VirtIO PCI Legacy transport (I/O ports)
 pub const TRANSPORT_PCI_LEGACY: u8 = 2;
 
+// ═══════════════════════════════════════════════════════════════════════════
+// CLOCK CONSTANTS
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// `pm_timer_flags` bit: counter is 32 bits wide
+pub const PM_TIMER_FLAG_32BIT: u8 = 1 << 0;
+
 // ═══════════════════════════════════════════════════════════════════════════
 // HANDOFF ERROR
 // ═══════════════════════════════════════════════════════════════════════════
@@ -145,7 +176,7 @@ pub struct BootHandoff {
     /// Magic number for validation: "MORPHEUS" = 0x5355_4548_5052_4F4D
     pub magic: u64,
 
-    /// Structure version (currently 1)
+    /// Structure version (1 = this struct alone, 2 = prefix of `BootHandoffV2`)
     pub version: u32,
 
     /// Structure size in bytes (for forward compatibility)
@@ -190,7 +221,7 @@ pub struct BootHandoff {
     /// Block device PCI function number
     pub blk_pci_function: u8,
 
-    /// Block device type: 0=None, 1=VirtIO-blk, 2=NVMe, 3=AHCI
+    /// Block device type: 0=None, 1=VirtIO-blk, 2=NVMe, 3=AHCI, 4=USB
     pub blk_type: u8,
 
     /// Block device sector size (typically 512)
@@ -306,9 +337,18 @@ pub struct BootHandoff {
     pub blk_device_cfg: u64,
 
     // ═══════════════════════════════════════════════════════════════════════
-    // RESERVED (8 bytes for future expansion)
+    // CLOCK FALLBACK (3 bytes, carved from reserved)
+    // ═══════════════════════════════════════════════════════════════════════
+    /// ACPI PM timer I/O port from FADT PM_TMR_BLK (0 = unknown)
+    pub pm_timer_port: u16,
+
+    /// PM timer flags: bit 0 = 32-bit counter (FADT TMR_VAL_EXT)
+    pub pm_timer_flags: u8,
+
+    // ═══════════════════════════════════════════════════════════════════════
+    // RESERVED (5 bytes for future expansion)
     // ═══════════════════════════════════════════════════════════════════════
-    pub _reserved: [u8; 8],
+    pub _reserved: [u8; 5],
 }
 
 // Compile-time size check (200 original + 40 blk PCI Modern = 240 fields, aligned to 64 = 256)
@@ -318,8 +358,8 @@ impl BootHandoff {
     /// Magic number constant
     pub const MAGIC: u64 = HANDOFF_MAGIC;
 
-    /// Current version constant
-    pub const VERSION: u32 = HANDOFF_VERSION;
+    /// Version of this layout on its own
+    pub const VERSION: u32 = HANDOFF_VERSION_V1;
 
     /// Expected structure size
     pub const SIZE: u32 = 256;
@@ -328,7 +368,7 @@ impl BootHandoff {
     pub const fn new() -> Self {
         Self {
             magic: HANDOFF_MAGIC,
-            version: HANDOFF_VERSION,
+            version: HANDOFF_VERSION_V1,
             size: Self::SIZE,
             nic_mmio_base: 0,
             nic_pci_bus: 0,
@@ -374,7 +414,9 @@ impl BootHandoff {
             blk_notify_cfg: 0,
             blk_isr_cfg: 0,
             blk_device_cfg: 0,
-            _reserved: [0; 8],
+            pm_timer_port: 0,
+            pm_timer_flags: 0,
+            _reserved: [0; 5],
         }
     }
 
@@ -385,15 +427,7 @@ impl BootHandoff {
     /// - `Err(HandoffError)` describing the first validation failure
     pub fn validate(&self) -> Result<(), HandoffError> {
         // Header validation
-        if self.magic != HANDOFF_MAGIC {
-            return Err(HandoffError::InvalidMagic);
-        }
-        if self.version != HANDOFF_VERSION {
-            return Err(HandoffError::UnsupportedVersion);
-        }
-        if self.size != Self::SIZE {
-            return Err(HandoffError::SizeMismatch);
-        }
+        self.check_header()?;
 
         // TSC validation (required)
         if self.tsc_freq < MIN_TSC_FREQ || self.tsc_freq > MAX_TSC_FREQ {
@@ -430,6 +464,21 @@ impl BootHandoff {
         Ok(())
     }
 
+    /// Validate magic, version and size only.
+    ///
+    /// Accepts any version in `HANDOFF_MIN_VERSION..=HANDOFF_VERSION` whose
+    /// `size` covers the layout for that version. Returns the version.
+    pub fn check_header(&self) -> Result<u32, HandoffError> {
+        if self.magic != HANDOFF_MAGIC {
+            return Err(HandoffError::InvalidMagic);
+        }
+        let required = size_for_version(self.version).ok_or(HandoffError::UnsupportedVersion)?;
+        if self.size < required {
+            return Err(HandoffError::SizeMismatch);
+        }
+        Ok(self.version)
+    }
+
     /// Validate for network-only operation (block device optional).
     pub fn validate_network_only(&self) -> Result<(), HandoffError> {
         // Same as validate() - NIC is required
@@ -448,6 +497,98 @@ impl BootHandoff {
         self.framebuffer_base != 0 && self.framebuffer_width > 0 && self.framebuffer_height > 0
     }
 
+    /// Framebuffer described by the handoff, if any.
+    pub fn framebuffer(&self) -> Option<HandoffFramebuffer> {
+        if !self.has_framebuffer() {
+            return None;
+        }
+        Some(HandoffFramebuffer {
+            base: self.framebuffer_base,
+            width: self.framebuffer_width,
+            height: self.framebuffer_height,
+            stride: self.framebuffer_stride,
+            format: self.framebuffer_format,
+        })
+    }
+
+    /// The single NIC carried in the v1 fields.
+    pub fn primary_nic(&self) -> Option<HandoffNic> {
+        if self.nic_type == NIC_TYPE_NONE {
+            return None;
+        }
+        Some(HandoffNic {
+            mmio_base: self.nic_mmio_base,
+            common_cfg: self.nic_common_cfg,
+            notify_cfg: self.nic_notify_cfg,
+            isr_cfg: self.nic_isr_cfg,
+            device_cfg: self.nic_device_cfg,
+            notify_off_multiplier: self.nic_notify_off_multiplier,
+            pci_bus: self.nic_pci_bus,
+            pci_device: self.nic_pci_device,
+            pci_function: self.nic_pci_function,
+            nic_type: self.nic_type,
+            transport_type: self.nic_transport_type,
+            mac_address: self.mac_address,
+            _pad: 0,
+        })
+    }
+
+    /// The single block device carried in the v1 fields.
+    pub fn primary_blk(&self) -> Option<HandoffBlk> {
+        if !self.has_block_device() {
+            return None;
+        }
+        Some(HandoffBlk {
+            mmio_base: self.blk_mmio_base,
+            common_cfg: self.blk_common_cfg,
+            notify_cfg: self.blk_notify_cfg,
+            isr_cfg: self.blk_isr_cfg,
+            device_cfg: self.blk_device_cfg,
+            total_sectors: self.blk_total_sectors,
+            sector_size: self.blk_sector_size,
+            notify_off_multiplier: self.blk_notify_off_multiplier,
+            pci_bus: self.blk_pci_bus,
+            pci_device: self.blk_pci_device,
+            pci_function: self.blk_pci_function,
+            blk_type: self.blk_type,
+            transport_type: self.blk_transport_type,
+            _pad: [0; 3],
+        })
+    }
+
+    /// Store `nic` in the v1 NIC fields.
+    pub fn set_primary_nic(&mut self, nic: &HandoffNic) {
+        self.nic_mmio_base = nic.mmio_base;
+        self.nic_pci_bus = nic.pci_bus;
+        self.nic_pci_device = nic.pci_device;
+        self.nic_pci_function = nic.pci_function;
+        self.nic_type = nic.nic_type;
+        self.mac_address = nic.mac_address;
+        self.nic_transport_type = nic.transport_type;
+        self.nic_notify_off_multiplier = nic.notify_off_multiplier;
+        self.nic_common_cfg = nic.common_cfg;
+        self.nic_notify_cfg = nic.notify_cfg;
+        self.nic_isr_cfg = nic.isr_cfg;
+        self.nic_device_cfg = nic.device_cfg;
+    }
+
+    /// Store `blk` in the v1 block device fields.
+    pub fn set_primary_blk(&mut self, blk: &HandoffBlk) {
+        self.blk_mmio_base = blk.mmio_base;
+        self.blk_pci_bus = blk.pci_bus;
+        self.blk_pci_device = blk.pci_device;
+        self.blk_pci_function = blk.pci_function;
+        self.blk_type = blk.blk_type;
+        self.blk_sector_size = blk.sector_size;
+        self.blk_total_sectors = blk.total_sectors;
+        self.blk_transport_type = blk.transport_type;
+        self.blk_notify_off_multiplier = blk.notify_off_multiplier;
+        self.blk_common_cfg = blk.common_cfg;
+        self.blk_notify_cfg = blk.notify_cfg;
+        self.blk_isr_cfg = blk.isr_cfg;
+        self.blk_device_cfg = blk.device_cfg;
+    }
+
     /// Get DMA region as raw pointer and size.
     ///
     /// # Safety
@@ -460,6 +601,33 @@ impl BootHandoff {
         )
     }
 
+    /// ACPI PM timer described by the handoff, if any.
+    pub fn pm_timer(&self) -> Option<PmTimerClock> {
+        if self.pm_timer_port == 0 {
+            return None;
+        }
+        Some(PmTimerClock::new(
+            self.pm_timer_port,
+            self.pm_timer_flags & PM_TIMER_FLAG_32BIT != 0,
+        ))
+    }
+
+    /// Select the timeout clock for this machine.
+    ///
+    /// Uses the TSC when `has_invariant_tsc()` holds; otherwise falls back
+    /// to the HPET at its architectural base, then the ACPI PM timer.
+    ///
+    /// # Safety
+    /// Must run post-EBS with the HPET range identity-mapped.
+    pub unsafe fn select_clock(&self) -> SystemClock {
+        select_clock(
+            self.tsc_freq,
+            has_invariant_tsc(),
+            Some(HPET_DEFAULT_BASE),
+            self.pm_timer(),
+        )
+    }
+
     /// Convert milliseconds to TSC ticks.
     #[inline]
     pub fn ms_to_ticks(&self, ms: u64) -> u64 {
@@ -510,9 +678,438 @@ impl fmt::Debug for BootHandoff {
     }
 }
 
+// ═══════════════════════════════════════════════════════════════════════════
+// BOOT HANDOFF V2
+// ═══════════════════════════════════════════════════════════════════════════
+//
+// v1 carries exactly one NIC and one block device. v2 appends device arrays,
+// the ACPI RSDP and the memory map descriptor version after an unchanged v1
+// prefix. The first NIC / block device are mirrored into the v1 fields, so a
+// v2 structure downgraded with `downgrade()` is a valid v1 handoff for old
+// network builds that only check `version == 1 && size == 256`.
+
+/// Network device entry in a v2 handoff.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HandoffNic {
+    /// MMIO base (from PCI BAR)
+    pub mmio_base: u64,
+    /// Common cfg address (PCI Modern)
+    pub common_cfg: u64,
+    /// Notify cfg address (PCI Modern)
+    pub notify_cfg: u64,
+    /// ISR cfg address (PCI Modern)
+    pub isr_cfg: u64,
+    /// Device cfg address (PCI Modern)
+    pub device_cfg: u64,
+    /// Notify offset multiplier (PCI Modern)
+    pub notify_off_multiplier: u32,
+    pub pci_bus: u8,
+    pub pci_device: u8,
+    pub pci_function: u8,
+    /// `NIC_TYPE_*`
+    pub nic_type: u8,
+    /// `TRANSPORT_*`
+    pub transport_type: u8,
+    /// MAC address (may be zeros if not yet read)
+    pub mac_address: [u8; 6],
+    pub _pad: u8,
+}
+
+const _: () = assert!(core::mem::size_of::<HandoffNic>() == 56);
+
+impl HandoffNic {
+    /// Unused slot.
+    pub const EMPTY: Self = Self {
+        mmio_base: 0,
+        common_cfg: 0,
+        notify_cfg: 0,
+        isr_cfg: 0,
+        device_cfg: 0,
+        notify_off_multiplier: 0,
+        pci_bus: 0,
+        pci_device: 0,
+        pci_function: 0,
+        nic_type: NIC_TYPE_NONE,
+        transport_type: TRANSPORT_MMIO,
+        mac_address: [0; 6],
+        _pad: 0,
+    };
+}
+
+impl Default for HandoffNic {
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
+
+/// Block device entry in a v2 handoff.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HandoffBlk {
+    /// MMIO base (legacy) or common cfg (PCI Modern)
+    pub mmio_base: u64,
+    /// Common cfg address (PCI Modern)
+    pub common_cfg: u64,
+    /// Notify cfg address (PCI Modern)
+    pub notify_cfg: u64,
+    /// ISR cfg address (PCI Modern)
+    pub isr_cfg: u64,
+    /// Device cfg address (PCI Modern)
+    pub device_cfg: u64,
+    /// Total sectors
+    pub total_sectors: u64,
+    /// Sector size in bytes (typically 512)
+    pub sector_size: u32,
+    /// Notify offset multiplier (PCI Modern)
+    pub notify_off_multiplier: u32,
+    pub pci_bus: u8,
+    pub pci_device: u8,
+    pub pci_function: u8,
+    /// `BLK_TYPE_*`
+    pub blk_type: u8,
+    /// `TRANSPORT_*`
+    pub transport_type: u8,
+    pub _pad: [u8; 3],
+}
+
+const _: () = assert!(core::mem::size_of::<HandoffBlk>() == 64);
+
+impl HandoffBlk {
+    /// Unused slot.
+    pub const EMPTY: Self = Self {
+        mmio_base: 0,
+        common_cfg: 0,
+        notify_cfg: 0,
+        isr_cfg: 0,
+        device_cfg: 0,
+        total_sectors: 0,
+        sector_size: 0,
+        notify_off_multiplier: 0,
+        pci_bus: 0,
+        pci_device: 0,
+        pci_function: 0,
+        blk_type: BLK_TYPE_NONE,
+        transport_type: TRANSPORT_MMIO,
+        _pad: [0; 3],
+    };
+}
+
+impl Default for HandoffBlk {
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
+
+/// GOP framebuffer as described by the handoff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HandoffFramebuffer {
+    pub base: u64,
+    pub width: u32,
+    pub height: u32,
+    /// Bytes per row
+    pub stride: u32,
+    /// 0=RGB (Rgbx), 1=BGR (Bgrx)
+    pub format: u32,
+}
+
+/// Version 2 handoff: v1 prefix plus device arrays, RSDP and memory map
+/// descriptor version.
+#[repr(C, align(64))]
+#[derive(Clone, Copy)]
+pub struct BootHandoffV2 {
+    /// v1-compatible prefix. `base.size` covers the whole v2 structure.
+    pub base: BootHandoff,
+
+    /// ACPI RSDP physical address (0 = unknown)
+    pub rsdp: u64,
+
+    /// UEFI memory map descriptor version
+    pub memory_map_desc_version: u32,
+
+    /// Valid entries in `nics`
+    pub nic_count: u8,
+
+    /// Valid entries in `blks`
+    pub blk_count: u8,
+
+    pub _pad: [u8; 2],
+
+    /// Detected NICs; `nics[0]` is mirrored into the v1 fields
+    pub nics: [HandoffNic; MAX_HANDOFF_NICS],
+
+    /// Detected block devices; `blks[0]` is mirrored into the v1 fields
+    pub blks: [HandoffBlk; MAX_HANDOFF_BLKS],
+}
+
+// 256 v1 + 16 + 8×56 NICs + 8×64 BLKs = 1232, aligned to 64 = 1280
+const _: () = assert!(core::mem::size_of::<BootHandoffV2>() == 1280);
+
+impl BootHandoffV2 {
+    /// Structure version
+    pub const VERSION: u32 = HANDOFF_VERSION;
+
+    /// Expected structure size
+    pub const SIZE: u32 = 1280;
+
+    /// Create an empty v2 handoff.
+    pub const fn new() -> Self {
+        let mut base = BootHandoff::new();
+        base.version = HANDOFF_VERSION;
+        base.size = Self::SIZE;
+        Self {
+            base,
+            rsdp: 0,
+            memory_map_desc_version: 0,
+            nic_count: 0,
+            blk_count: 0,
+            _pad: [0; 2],
+            nics: [HandoffNic::EMPTY; MAX_HANDOFF_NICS],
+            blks: [HandoffBlk::EMPTY; MAX_HANDOFF_BLKS],
+        }
+    }
+
+    /// Append a NIC. Returns `false` if all slots are used.
+    pub fn push_nic(&mut self, nic: HandoffNic) -> bool {
+        let idx = self.nic_count as usize;
+        if idx >= MAX_HANDOFF_NICS {
+            return false;
+        }
+        self.nics[idx] = nic;
+        self.nic_count += 1;
+        if idx == 0 {
+            self.base.set_primary_nic(&nic);
+        }
+        true
+    }
+
+    /// Append a block device. Returns `false` if all slots are used.
+    pub fn push_blk(&mut self, blk: HandoffBlk) -> bool {
+        let idx = self.blk_count as usize;
+        if idx >= MAX_HANDOFF_BLKS {
+            return false;
+        }
+        self.blks[idx] = blk;
+        self.blk_count += 1;
+        if idx == 0 {
+            self.base.set_primary_blk(&blk);
+        }
+        true
+    }
+
+    /// Record the UEFI memory map (copied into memory that survives EBS).
+    pub fn set_memory_map(&mut self, ptr: u64, size: u32, desc_size: u32, desc_version: u32) {
+        self.base.memory_map_ptr = ptr;
+        self.base.memory_map_size = size;
+        self.base.memory_map_desc_size = desc_size;
+        self.memory_map_desc_version = desc_version;
+    }
+
+    /// Record the GOP framebuffer.
+    pub fn set_framebuffer(&mut self, fb: &HandoffFramebuffer) {
+        self.base.framebuffer_base = fb.base;
+        self.base.framebuffer_width = fb.width;
+        self.base.framebuffer_height = fb.height;
+        self.base.framebuffer_stride = fb.stride;
+        self.base.framebuffer_format = fb.format;
+    }
+
+    /// Detected NICs.
+    pub fn nics(&self) -> &[HandoffNic] {
+        &self.nics[..(self.nic_count as usize).min(MAX_HANDOFF_NICS)]
+    }
+
+    /// Detected block devices.
+    pub fn blks(&self) -> &[HandoffBlk] {
+        &self.blks[..(self.blk_count as usize).min(MAX_HANDOFF_BLKS)]
+    }
+
+    /// Rewrite the header for a consumer that only understands `version`.
+    ///
+    /// Everything a v1 consumer reads already lives in the prefix, so this
+    /// only changes `version` and `size`.
+    pub fn downgrade(&mut self, version: u32) -> Result<(), HandoffError> {
+        let size = size_for_version(version).ok_or(HandoffError::UnsupportedVersion)?;
+        self.base.version = version;
+        self.base.size = size;
+        Ok(())
+    }
+
+    /// Pointer to hand to the consumer.
+    pub fn as_ptr(&self) -> *const BootHandoff {
+        &self.base
+    }
+}
+
+impl Default for BootHandoffV2 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Structure size required for `version`, if this build knows it.
+pub const fn size_for_version(version: u32) -> Option<u32> {
+    match version {
+        HANDOFF_VERSION_V1 => Some(BootHandoff::SIZE),
+        HANDOFF_VERSION => Some(BootHandoffV2::SIZE),
+        _ => None,
+    }
+}
+
+/// Highest version both sides understand, given the consumer's maximum.
+pub fn negotiate_version(consumer_max: u32) -> Option<u32> {
+    let version = consumer_max.min(HANDOFF_VERSION);
+    (version >= HANDOFF_MIN_VERSION).then_some(version)
+}
+
+/// A validated handoff of whichever version the producer wrote.
+#[derive(Clone, Copy)]
+pub enum HandoffRef<'a> {
+    V1(&'a BootHandoff),
+    V2(&'a BootHandoffV2),
+}
+
+impl<'a> HandoffRef<'a> {
+    /// Interpret a handoff pointer, checking its header.
+    ///
+    /// # Safety
+    /// `ptr` must point to a `BootHandoff` at least `size` bytes long, as
+    /// stated in its own header.
+    pub unsafe fn from_ptr(ptr: *const BootHandoff) -> Result<Self, HandoffError> {
+        let base = &*ptr;
+        match base.check_header()? {
+            HANDOFF_VERSION_V1 => Ok(Self::V1(base)),
+            _ => Ok(Self::V2(&*(ptr as *const BootHandoffV2))),
+        }
+    }
+
+    /// Version in use.
+    pub fn version(&self) -> u32 {
+        match self {
+            Self::V1(_) => HANDOFF_VERSION_V1,
+            Self::V2(_) => HANDOFF_VERSION,
+        }
+    }
+
+    /// The v1 fields (present in every version).
+    pub fn base(&self) -> &'a BootHandoff {
+        match self {
+            Self::V1(h) => h,
+            Self::V2(h) => &h.base,
+        }
+    }
+
+    /// ACPI RSDP address (v2 only).
+    pub fn rsdp(&self) -> Option<u64> {
+        match self {
+            Self::V2(h) if h.rsdp != 0 => Some(h.rsdp),
+            _ => None,
+        }
+    }
+
+    /// UEFI memory map, if the producer copied one.
+    ///
+    /// # Safety
+    /// The map must still be where the producer put it.
+    pub unsafe fn memory_map(&self) -> Option<MemoryMap<'a>> {
+        let base = self.base();
+        if base.memory_map_ptr == 0 || base.memory_map_size == 0 {
+            return None;
+        }
+        // v1 has no descriptor version field; UEFI has only ever defined 1.
+        let desc_version = match self {
+            Self::V1(_) => 1,
+            Self::V2(h) => h.memory_map_desc_version,
+        };
+        MemoryMap::from_raw(
+            base.memory_map_ptr as *const u8,
+            base.memory_map_size as usize,
+            base.memory_map_desc_size as usize,
+            desc_version,
+        )
+    }
+
+    /// GOP framebuffer, if any.
+    pub fn framebuffer(&self) -> Option<HandoffFramebuffer> {
+        self.base().framebuffer()
+    }
+
+    /// Number of NICs described.
+    pub fn nic_count(&self) -> usize {
+        match self {
+            Self::V1(h) => h.primary_nic().is_some() as usize,
+            Self::V2(h) => h.nics().len(),
+        }
+    }
+
+    /// NIC `index`.
+    pub fn nic(&self, index: usize) -> Option<HandoffNic> {
+        match self {
+            Self::V1(h) if index == 0 => h.primary_nic(),
+            Self::V1(_) => None,
+            Self::V2(h) => h.nics().get(index).copied(),
+        }
+    }
+
+    /// Number of block devices described.
+    pub fn blk_count(&self) -> usize {
+        match self {
+            Self::V1(h) => h.primary_blk().is_some() as usize,
+            Self::V2(h) => h.blks().len(),
+        }
+    }
+
+    /// Block device `index`.
+    pub fn blk(&self, index: usize) -> Option<HandoffBlk> {
+        match self {
+            Self::V1(h) if index == 0 => h.primary_blk(),
+            Self::V1(_) => None,
+            Self::V2(h) => h.blks().get(index).copied(),
+        }
+    }
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // TSC CALIBRATION HELPERS
 // ═══════════════════════════════════════════════════════════════════════════
+//
+// A wrong TSC frequency silently skews every timeout, so the frequency is
+// cross-checked: CPUID (where available) against a timed measurement.
+
+/// Relative disagreement (ppm) above which CPUID and a timed measurement
+/// are considered inconsistent (2%).
+pub const TSC_DISCREPANCY_LIMIT_PPM: u64 = 20_000;
+
+/// Frequency assumed when nothing else is usable.
+pub const FALLBACK_TSC_FREQ: u64 = 2_500_000_000;
+
+/// Where a TSC frequency came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TscSource {
+    /// CPUID leaf 0x15 (crystal clock × ratio) — exact
+    CpuidCrystal,
+    /// CPUID leaf 0x16 (processor base frequency, whole MHz) — nominal
+    CpuidBase,
+    /// aarch64 CNTFRQ_EL0 (generic timer frequency) — exact
+    ArchTimer,
+    /// Timed against a reference (UEFI Stall, PIT, ...)
+    Measured,
+    /// Hardcoded fallback — every timeout is a guess
+    Fallback,
+}
+
+impl TscSource {
+    /// Short name for logs.
+    pub fn name(self) -> &'static str {
+        match self {
+            TscSource::CpuidCrystal => "CPUID 0x15",
+            TscSource::CpuidBase => "CPUID 0x16",
+            TscSource::ArchTimer => "CNTFRQ_EL0",
+            TscSource::Measured => "measured",
+            TscSource::Fallback => "fallback",
+        }
+    }
+}
 
 /// TSC calibration result.
 #[derive(Debug, Clone, Copy)]
@@ -521,6 +1118,130 @@ pub struct TscCalibration {
     pub frequency: u64,
     /// Whether invariant TSC is available
     pub invariant: bool,
+    /// Source the chosen frequency came from
+    pub source: TscSource,
+    /// Frequency reported by CPUID, if any
+    pub cpuid_frequency: Option<u64>,
+    /// Frequency measured against a reference timer (0 = not measured)
+    pub measured_frequency: u64,
+    /// Disagreement between CPUID and measurement in ppm (0 if only one known)
+    pub discrepancy_ppm: u64,
+}
+
+fn in_sane_range(freq: u64) -> bool {
+    (MIN_TSC_FREQ..=MAX_TSC_FREQ).contains(&freq)
+}
+
+/// Disagreement between two frequencies, in ppm of `reference`.
+pub fn tsc_discrepancy_ppm(a: u64, reference: u64) -> u64 {
+    if reference == 0 {
+        return 0;
+    }
+    (a.abs_diff(reference) as u128 * 1_000_000 / reference as u128) as u64
+}
+
+/// Pick the most trustworthy TSC frequency.
+///
+/// - CPUID 0x15 is derived from the crystal and is exact: it wins whenever
+///   it is in range, and a disagreeing measurement is only reported. The
+///   aarch64 CNTFRQ_EL0 is treated the same way.
+/// - CPUID 0x16 is the nominal base clock in whole MHz. If the measurement
+///   agrees within `TSC_DISCREPANCY_LIMIT_PPM` it is the more precise of
+///   the two and is used; if it disagrees the measurement is assumed broken
+///   (e.g. a firmware Stall that returns early) and CPUID is used.
+/// - Without CPUID data, the measurement is used if sane, else the fallback.
+pub fn reconcile_tsc_frequency(
+    cpuid: Option<(u64, TscSource)>,
+    measured: u64,
+    invariant: bool,
+) -> TscCalibration {
+    let cpuid = cpuid.filter(|(f, _)| in_sane_range(*f));
+    let measured_ok = in_sane_range(measured);
+    let discrepancy_ppm = match cpuid {
+        Some((f, _)) if measured_ok => tsc_discrepancy_ppm(measured, f),
+        _ => 0,
+    };
+
+    let (frequency, source) = match cpuid {
+        Some((f, source @ (TscSource::CpuidCrystal | TscSource::ArchTimer))) => (f, source),
+        Some((_, _)) if measured_ok && discrepancy_ppm <= TSC_DISCREPANCY_LIMIT_PPM => {
+            (measured, TscSource::Measured)
+        }
+        Some((f, source)) => (f, source),
+        None if measured_ok => (measured, TscSource::Measured),
+        None => (FALLBACK_TSC_FREQ, TscSource::Fallback),
+    };
+
+    TscCalibration {
+        frequency,
+        invariant,
+        source,
+        cpuid_frequency: cpuid.map(|(f, _)| f),
+        measured_frequency: measured,
+        discrepancy_ppm,
+    }
+}
+
+/// Raw CPUID (subleaf 0).
+#[cfg(target_arch = "x86_64")]
+pub(crate) fn cpuid(leaf: u32) -> (u32, u32, u32, u32) {
+    let eax: u32;
+    let ebx: u32;
+    let ecx: u32;
+    let edx: u32;
+    unsafe {
+        core::arch::asm!(
+            // Save rbx since LLVM uses it internally
+            "push rbx",
+            "cpuid",
+            "mov {0:e}, ebx",
+            "pop rbx",
+            out(reg) ebx,
+            inout("eax") leaf => eax,
+            inout("ecx") 0u32 => ecx,
+            out("edx") edx,
+        );
+    }
+    (eax, ebx, ecx, edx)
+}
+
+/// TSC frequency as reported by CPUID, if the CPU exposes it.
+///
+/// Tries leaf 0x15 (TSC/crystal ratio × crystal Hz) first, then leaf
+/// 0x16 (base frequency in MHz). Hypervisors and pre-Skylake parts often
+/// report neither.
+#[cfg(target_arch = "x86_64")]
+pub fn cpuid_tsc_frequency() -> Option<(u64, TscSource)> {
+    let (max_leaf, _, _, _) = cpuid(0);
+
+    if max_leaf >= 0x15 {
+        let (denominator, numerator, crystal_hz, _) = cpuid(0x15);
+        if denominator != 0 && numerator != 0 && crystal_hz != 0 {
+            let freq = crystal_hz as u64 * numerator as u64 / denominator as u64;
+            return Some((freq, TscSource::CpuidCrystal));
+        }
+    }
+
+    if max_leaf >= 0x16 {
+        let (base_mhz, _, _, _) = cpuid(0x16);
+        let base_mhz = base_mhz & 0xFFFF;
+        if base_mhz != 0 {
+            return Some((base_mhz as u64 * 1_000_000, TscSource::CpuidBase));
+        }
+    }
+
+    None
+}
+
+/// Generic timer frequency; aarch64 has no CPUID but publishes it directly.
+#[cfg(target_arch = "aarch64")]
+pub fn cpuid_tsc_frequency() -> Option<(u64, TscSource)> {
+    crate::arch::counter_frequency().map(|f| (f, TscSource::ArchTimer))
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+pub fn cpuid_tsc_frequency() -> Option<(u64, TscSource)> {
+    None
 }
 
 /// Check if CPU has invariant TSC via CPUID.
@@ -552,7 +1273,13 @@ pub fn has_invariant_tsc() -> bool {
     (result & (1 << 8)) != 0
 }
 
-#[cfg(not(target_arch = "x86_64"))]
+/// The generic timer runs at a constant rate by definition.
+#[cfg(target_arch = "aarch64")]
+pub fn has_invariant_tsc() -> bool {
+    true
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
 pub fn has_invariant_tsc() -> bool {
     false
 }
@@ -574,8 +1301,141 @@ pub fn read_tsc_raw() -> u64 {
     ((hi as u64) << 32) | (lo as u64)
 }
 
-#[cfg(not(target_arch = "x86_64"))]
+#[cfg(target_arch = "aarch64")]
+#[inline]
+pub fn read_tsc_raw() -> u64 {
+    crate::arch::aarch64::cntvct()
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
 #[inline]
 pub fn read_tsc_raw() -> u64 {
     0
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crystal_always_wins() {
+        let cal = reconcile_tsc_frequency(
+            Some((2_112_000_000, TscSource::CpuidCrystal)),
+            1_800_000_000,
+            true,
+        );
+        assert_eq!(cal.frequency, 2_112_000_000);
+        assert_eq!(cal.source, TscSource::CpuidCrystal);
+        assert!(cal.discrepancy_ppm > TSC_DISCREPANCY_LIMIT_PPM);
+    }
+
+    #[test]
+    fn test_arch_timer_is_exact() {
+        // Armv8.6+ fixes CNTFRQ at 1 GHz
+        let cal = reconcile_tsc_frequency(
+            Some((1_000_000_000, TscSource::ArchTimer)),
+            1_100_000_000,
+            true,
+        );
+        assert_eq!(cal.frequency, 1_000_000_000);
+        assert_eq!(cal.source, TscSource::ArchTimer);
+    }
+
+    #[test]
+    fn test_base_frequency_refined_by_measurement() {
+        let cal = reconcile_tsc_frequency(
+            Some((3_000_000_000, TscSource::CpuidBase)),
+            2_995_000_000,
+            true,
+        );
+        assert_eq!(cal.frequency, 2_995_000_000);
+        assert_eq!(cal.source, TscSource::Measured);
+    }
+
+    #[test]
+    fn test_broken_stall_overridden_by_cpuid() {
+        // Stall returned after 5ms instead of 10ms: measurement reads half
+        let cal = reconcile_tsc_frequency(
+            Some((3_000_000_000, TscSource::CpuidBase)),
+            1_500_000_000,
+            false,
+        );
+        assert_eq!(cal.frequency, 3_000_000_000);
+        assert_eq!(cal.source, TscSource::CpuidBase);
+        assert_eq!(cal.discrepancy_ppm, 500_000);
+    }
+
+    #[test]
+    fn test_measurement_and_fallback() {
+        let cal = reconcile_tsc_frequency(None, 2_400_000_000, true);
+        assert_eq!(cal.source, TscSource::Measured);
+
+        let cal = reconcile_tsc_frequency(None, 12, true);
+        assert_eq!(cal.frequency, FALLBACK_TSC_FREQ);
+        assert_eq!(cal.source, TscSource::Fallback);
+
+        // Insane CPUID value is ignored
+        let cal = reconcile_tsc_frequency(Some((24_000_000, TscSource::CpuidBase)), 2_400_000_000, true);
+        assert_eq!(cal.source, TscSource::Measured);
+        assert_eq!(cal.cpuid_frequency, None);
+    }
+
+    fn nic(nic_type: u8, mmio_base: u64) -> HandoffNic {
+        HandoffNic {
+            nic_type,
+            mmio_base,
+            ..HandoffNic::EMPTY
+        }
+    }
+
+    #[test]
+    fn test_v2_mirrors_primary_devices() {
+        let mut h = BootHandoffV2::new();
+        assert!(h.push_nic(nic(NIC_TYPE_INTEL, 0xFEB0_0000)));
+        assert!(h.push_nic(nic(NIC_TYPE_VIRTIO, 0xFEC0_0000)));
+        assert_eq!(h.base.nic_type, NIC_TYPE_INTEL);
+        assert_eq!(h.base.nic_mmio_base, 0xFEB0_0000);
+        assert_eq!(h.nics().len(), 2);
+
+        for _ in 2..MAX_HANDOFF_NICS {
+            assert!(h.push_nic(nic(NIC_TYPE_VIRTIO, 1)));
+        }
+        assert!(!h.push_nic(nic(NIC_TYPE_VIRTIO, 1)));
+    }
+
+    #[test]
+    fn test_version_negotiation() {
+        assert_eq!(negotiate_version(1), Some(HANDOFF_VERSION_V1));
+        assert_eq!(negotiate_version(7), Some(HANDOFF_VERSION));
+        assert_eq!(negotiate_version(0), None);
+
+        let mut h = BootHandoffV2::new();
+        h.rsdp = 0xE0000;
+        h.push_nic(nic(NIC_TYPE_VIRTIO, 0x1000));
+        let view = unsafe { HandoffRef::from_ptr(h.as_ptr()) }.unwrap();
+        assert_eq!(view.version(), HANDOFF_VERSION);
+        assert_eq!(view.rsdp(), Some(0xE0000));
+
+        // Downgraded: exactly what a v1-only consumer checks for
+        h.downgrade(HANDOFF_VERSION_V1).unwrap();
+        assert_eq!(h.base.version, 1);
+        assert_eq!(h.base.size, 256);
+        let view = unsafe { HandoffRef::from_ptr(h.as_ptr()) }.unwrap();
+        assert_eq!(view.version(), HANDOFF_VERSION_V1);
+        assert_eq!(view.rsdp(), None);
+        assert_eq!(view.nic_count(), 1);
+        assert_eq!(view.nic(0).unwrap().mmio_base, 0x1000);
+    }
+
+    #[test]
+    fn test_header_checks() {
+        let mut h = BootHandoff::new();
+        assert_eq!(h.check_header(), Ok(HANDOFF_VERSION_V1));
+        h.version = HANDOFF_VERSION;
+        assert_eq!(h.check_header(), Err(HandoffError::SizeMismatch));
+        h.version = 99;
+        assert_eq!(h.check_header(), Err(HandoffError::UnsupportedVersion));
+        h.magic = 0;
+        assert_eq!(h.check_header(), Err(HandoffError::InvalidMagic));
+    }
+}
@@ -0,0 +1,215 @@
+//! UEFI memory map as forwarded in the boot handoff.
+//!
+//! The bootloader copies the map returned by `GetMemoryMap` into loader
+//! data before ExitBootServices. Descriptors are `desc_size` bytes apart,
+//! which firmware is free to make larger than `MemoryDescriptor` (48 is
+//! common), so entries are always read by stride, never by indexing a
+//! `[MemoryDescriptor]`.
+
+/// UEFI page size.
+pub const EFI_PAGE_SIZE: u64 = 4096;
+
+// ═══════════════════════════════════════════════════════════════════════════
+// MEMORY TYPES
+// ═══════════════════════════════════════════════════════════════════════════
+
+pub const EFI_RESERVED_MEMORY: u32 = 0;
+pub const EFI_LOADER_CODE: u32 = 1;
+pub const EFI_LOADER_DATA: u32 = 2;
+pub const EFI_BOOT_SERVICES_CODE: u32 = 3;
+pub const EFI_BOOT_SERVICES_DATA: u32 = 4;
+pub const EFI_RUNTIME_SERVICES_CODE: u32 = 5;
+pub const EFI_RUNTIME_SERVICES_DATA: u32 = 6;
+pub const EFI_CONVENTIONAL_MEMORY: u32 = 7;
+pub const EFI_UNUSABLE_MEMORY: u32 = 8;
+pub const EFI_ACPI_RECLAIM_MEMORY: u32 = 9;
+pub const EFI_ACPI_MEMORY_NVS: u32 = 10;
+pub const EFI_MEMORY_MAPPED_IO: u32 = 11;
+pub const EFI_MEMORY_MAPPED_IO_PORT_SPACE: u32 = 12;
+pub const EFI_PAL_CODE: u32 = 13;
+pub const EFI_PERSISTENT_MEMORY: u32 = 14;
+
+// ═══════════════════════════════════════════════════════════════════════════
+// MEMORY ATTRIBUTES
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Uncacheable
+pub const EFI_MEMORY_UC: u64 = 0x1;
+/// Write-combining
+pub const EFI_MEMORY_WC: u64 = 0x2;
+/// Write-through
+pub const EFI_MEMORY_WT: u64 = 0x4;
+/// Write-back
+pub const EFI_MEMORY_WB: u64 = 0x8;
+/// Needs a runtime mapping
+pub const EFI_MEMORY_RUNTIME: u64 = 1 << 63;
+
+// ═══════════════════════════════════════════════════════════════════════════
+// DESCRIPTOR
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// `EFI_MEMORY_DESCRIPTOR`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryDescriptor {
+    pub memory_type: u32,
+    pub _pad: u32,
+    pub physical_start: u64,
+    pub virtual_start: u64,
+    pub number_of_pages: u64,
+    pub attribute: u64,
+}
+
+impl MemoryDescriptor {
+    /// Size of the region in bytes.
+    pub fn size(&self) -> u64 {
+        self.number_of_pages * EFI_PAGE_SIZE
+    }
+
+    /// First address past the region.
+    pub fn end(&self) -> u64 {
+        self.physical_start + self.size()
+    }
+
+    /// Free for our use once boot services are gone.
+    pub fn is_usable_after_ebs(&self) -> bool {
+        matches!(
+            self.memory_type,
+            EFI_CONVENTIONAL_MEMORY | EFI_BOOT_SERVICES_CODE | EFI_BOOT_SERVICES_DATA
+        )
+    }
+
+    /// Device register space.
+    pub fn is_mmio(&self) -> bool {
+        matches!(
+            self.memory_type,
+            EFI_MEMORY_MAPPED_IO | EFI_MEMORY_MAPPED_IO_PORT_SPACE
+        )
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// MEMORY MAP
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Borrowed view of a raw UEFI memory map.
+#[derive(Debug, Clone, Copy)]
+pub struct MemoryMap<'a> {
+    bytes: &'a [u8],
+    desc_size: usize,
+    desc_version: u32,
+}
+
+impl<'a> MemoryMap<'a> {
+    /// Wrap a raw map. Returns `None` if `desc_size` is too small.
+    pub fn new(bytes: &'a [u8], desc_size: usize, desc_version: u32) -> Option<Self> {
+        if desc_size < core::mem::size_of::<MemoryDescriptor>() {
+            return None;
+        }
+        Some(Self {
+            bytes,
+            desc_size,
+            desc_version,
+        })
+    }
+
+    /// Wrap a map at a physical address.
+    ///
+    /// # Safety
+    /// `ptr..ptr + size` must be readable for `'a`.
+    pub unsafe fn from_raw(
+        ptr: *const u8,
+        size: usize,
+        desc_size: usize,
+        desc_version: u32,
+    ) -> Option<Self> {
+        if ptr.is_null() {
+            return None;
+        }
+        Self::new(core::slice::from_raw_parts(ptr, size), desc_size, desc_version)
+    }
+
+    /// Number of descriptors.
+    pub fn len(&self) -> usize {
+        self.bytes.len() / self.desc_size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Descriptor format version reported by firmware.
+    pub fn desc_version(&self) -> u32 {
+        self.desc_version
+    }
+
+    /// Descriptor `index`.
+    pub fn get(&self, index: usize) -> Option<MemoryDescriptor> {
+        if index >= self.len() {
+            return None;
+        }
+        let offset = index * self.desc_size;
+        // SAFETY: bounds checked above; desc_size >= size_of::<MemoryDescriptor>().
+        Some(unsafe {
+            core::ptr::read_unaligned(self.bytes[offset..].as_ptr() as *const MemoryDescriptor)
+        })
+    }
+
+    /// Iterate over all descriptors.
+    pub fn iter(&self) -> impl Iterator<Item = MemoryDescriptor> + 'a {
+        let map = *self;
+        (0..map.len()).filter_map(move |i| map.get(i))
+    }
+
+    /// Bytes usable after ExitBootServices.
+    pub fn usable_bytes(&self) -> u64 {
+        self.iter()
+            .filter(MemoryDescriptor::is_usable_after_ebs)
+            .map(|d| d.size())
+            .sum()
+    }
+
+    /// Highest physical address covered by any descriptor.
+    pub fn max_address(&self) -> u64 {
+        self.iter().map(|d| d.end()).max().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a map with firmware-style 48-byte descriptors.
+    fn build(entries: &[(u32, u64, u64)]) -> [u8; 48 * 4] {
+        let mut buf = [0u8; 48 * 4];
+        for (i, &(ty, start, pages)) in entries.iter().enumerate() {
+            let d = &mut buf[i * 48..];
+            d[0..4].copy_from_slice(&ty.to_le_bytes());
+            d[8..16].copy_from_slice(&start.to_le_bytes());
+            d[24..32].copy_from_slice(&pages.to_le_bytes());
+            d[32..40].copy_from_slice(&EFI_MEMORY_WB.to_le_bytes());
+        }
+        buf
+    }
+
+    #[test]
+    fn test_stride_and_usable() {
+        let buf = build(&[
+            (EFI_CONVENTIONAL_MEMORY, 0x10_0000, 256),
+            (EFI_LOADER_DATA, 0x20_0000, 16),
+            (EFI_BOOT_SERVICES_DATA, 0x30_0000, 16),
+            (EFI_MEMORY_MAPPED_IO, 0xFEC0_0000, 1),
+        ]);
+        let map = MemoryMap::new(&buf, 48, 1).unwrap();
+        assert_eq!(map.len(), 4);
+        assert_eq!(map.get(1).unwrap().physical_start, 0x20_0000);
+        assert!(map.get(3).unwrap().is_mmio());
+        assert_eq!(map.usable_bytes(), (256 + 16) * EFI_PAGE_SIZE);
+        assert_eq!(map.max_address(), 0xFEC0_1000);
+    }
+
+    #[test]
+    fn test_rejects_short_descriptor() {
+        assert!(MemoryMap::new(&[0u8; 64], 32, 1).is_none());
+    }
+}
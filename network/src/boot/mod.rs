@@ -5,8 +5,10 @@
 //! # Modules
 //!
 //! - `handoff` - BootHandoff structure (ABI with bootloader)
+//! - `memory_map` - UEFI memory map forwarded in the handoff
 //! - `probe` - PCI scanning and network driver creation
-//! - `block_probe` - PCI scanning and block driver creation
+//! - `block_probe` - PCI scanning and block driver creation (not in
+//!   `netboot-only` builds)
 //!
 //! # Usage
 //!
@@ -17,18 +19,26 @@
 //! let driver = probe_and_create_driver(&dma, tsc_freq)?;
 //! ```
 
+#[cfg(not(feature = "netboot-only"))]
 pub mod block_probe;
 pub mod handoff;
+pub mod memory_map;
 pub mod probe;
 
 // Re-exports - Boot handoff
 pub use handoff::{
-    has_invariant_tsc, read_tsc_raw, BootHandoff, HandoffError, TscCalibration, BLK_TYPE_AHCI,
-    BLK_TYPE_NONE, BLK_TYPE_NVME, BLK_TYPE_VIRTIO, HANDOFF_MAGIC, HANDOFF_VERSION,
+    cpuid_tsc_frequency, has_invariant_tsc, negotiate_version, read_tsc_raw,
+    reconcile_tsc_frequency, BootHandoff, BootHandoffV2, HandoffBlk, HandoffError,
+    HandoffFramebuffer, HandoffNic, HandoffRef, TscCalibration, TscSource, BLK_TYPE_AHCI,
+    BLK_TYPE_NONE, BLK_TYPE_NVME, BLK_TYPE_USB, BLK_TYPE_VIRTIO, HANDOFF_MAGIC, HANDOFF_MIN_VERSION,
+    HANDOFF_VERSION, HANDOFF_VERSION_V1, MAX_HANDOFF_BLKS, MAX_HANDOFF_NICS,
     NIC_TYPE_BROADCOM, NIC_TYPE_INTEL, NIC_TYPE_NONE, NIC_TYPE_REALTEK, NIC_TYPE_VIRTIO,
-    TRANSPORT_MMIO, TRANSPORT_PCI_MODERN,
+    PM_TIMER_FLAG_32BIT, TRANSPORT_MMIO, TRANSPORT_PCI_MODERN,
 };
 
+// Re-exports - Memory map
+pub use memory_map::{MemoryDescriptor, MemoryMap, EFI_PAGE_SIZE};
+
 // Re-exports - Network probe
 pub use probe::{
     create_intel_driver, create_virtio_driver, detect_nic_type, probe_and_create_driver,
@@ -36,8 +46,10 @@ pub use probe::{
 };
 
 // Re-exports - Block probe
+#[cfg(not(feature = "netboot-only"))]
 pub use block_probe::{
-    detect_block_device_type, find_ahci_controller, probe_and_create_block_driver,
-    probe_unified_block_device, scan_for_block_device, AhciInfo, BlockDeviceType, BlockDmaConfig,
-    BlockProbeError, BlockProbeResult, DetectedBlockDevice,
+    create_block_driver, detect_block_device_type, find_ahci_controller,
+    probe_and_create_block_driver, probe_block_devices, probe_unified_block_device,
+    scan_block_devices, scan_for_block_device, AhciInfo, BlockDeviceType, BlockDmaConfig,
+    BlockProbeError, BlockProbeResult, DetectedBlockDevice, MAX_BLOCK_DEVICES,
 };
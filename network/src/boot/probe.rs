@@ -4,7 +4,7 @@
 //! This is the main entry point for automatic driver selection.
 //!
 //! # Supported Devices
-//! - VirtIO-net (QEMU, cloud VMs)
+//! - VirtIO-net (QEMU, cloud VMs), modern or legacy (I/O port BAR)
 //! - Intel e1000e family (ThinkPad T450s, T520, etc.)
 //!
 //! # Usage
@@ -23,9 +23,9 @@
 use crate::dma::DmaRegion;
 use crate::driver::intel::{
     enable_device, find_intel_nic, validate_mmio_access, E1000eConfig, E1000eDriver, E1000eError,
-    IntelNicInfo,
+    IntelNicInfo, LinkMode, LowPowerPolicy,
 };
-use crate::driver::virtio::{VirtioConfig, VirtioInitError, VirtioNetDriver};
+use crate::driver::virtio::{VirtioConfig, VirtioInitError, VirtioNetDriver, VirtioTransport};
 use crate::pci::config::{offset, pci_cfg_read16, pci_cfg_read32, PciAddr};
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -82,6 +82,9 @@ impl From<E1000eError> for ProbeError {
 pub enum DetectedNic {
     /// VirtIO network device
     VirtIO { pci_addr: PciAddr, mmio_base: u64 },
+    /// Legacy/transitional VirtIO network device behind an I/O port BAR
+    /// (older QEMU machine types, Proxmox defaults)
+    VirtioLegacy { pci_addr: PciAddr, io_base: u16 },
     /// Intel e1000e network device
     Intel(IntelNicInfo),
 }
@@ -109,18 +112,14 @@ pub fn scan_for_nic() -> Option<DetectedNic> {
     }
 
     // Fall back to VirtIO (QEMU, VMs)
-    if let Some((pci_addr, mmio_base)) = find_virtio_nic() {
-        return Some(DetectedNic::VirtIO {
-            pci_addr,
-            mmio_base,
-        });
-    }
-
-    None
+    find_virtio_nic()
 }
 
 /// Scan for VirtIO network device.
-fn find_virtio_nic() -> Option<(PciAddr, u64)> {
+///
+/// A transitional device (0x1000) whose BAR0 is an I/O BAR is driven
+/// through the legacy interface; anything else needs a memory BAR.
+fn find_virtio_nic() -> Option<DetectedNic> {
     for bus in 0..=255u8 {
         for device in 0..32u8 {
             for function in 0..8u8 {
@@ -154,8 +153,15 @@ fn find_virtio_nic() -> Option<(PciAddr, u64)> {
                 // Read BAR0
                 let bar0 = pci_cfg_read32(addr, offset::BAR0);
                 if bar0 & 0x01 != 0 {
-                    // I/O BAR - skip (need MMIO)
-                    continue;
+                    // I/O BAR - legacy interface, transitional devices only
+                    let io_base = (bar0 & 0xFFFC) as u16;
+                    if device_id != VIRTIO_NET_DEVICE_START || io_base == 0 {
+                        continue;
+                    }
+                    return Some(DetectedNic::VirtioLegacy {
+                        pci_addr: addr,
+                        io_base,
+                    });
                 }
 
                 let is_64bit = (bar0 & 0x06) == 0x04;
@@ -166,7 +172,10 @@ fn find_virtio_nic() -> Option<(PciAddr, u64)> {
                     (bar0 & 0xFFFFFFF0) as u64
                 };
 
-                return Some((addr, mmio_base));
+                return Some(DetectedNic::VirtIO {
+                    pci_addr: addr,
+                    mmio_base,
+                });
             }
         }
     }
@@ -211,6 +220,9 @@ pub unsafe fn probe_and_create_driver(
                 tx_queue_size: 32,
                 buffer_size: 2048,
                 tsc_freq,
+                pci_addr: Some(info.pci_addr),
+                low_power: LowPowerPolicy::Auto,
+                link_mode: LinkMode::Auto,
             };
 
             // Create driver
@@ -239,6 +251,29 @@ pub unsafe fn probe_and_create_driver(
             let driver = VirtioNetDriver::new(mmio_base, config)?;
             Ok(ProbeResult::VirtIO(driver))
         }
+
+        DetectedNic::VirtioLegacy { pci_addr, io_base } => {
+            // Enable device (I/O space, bus mastering)
+            let cmd = pci_cfg_read16(pci_addr, offset::COMMAND);
+            crate::pci::config::pci_cfg_write16(pci_addr, offset::COMMAND, cmd | 0x05);
+
+            // Create VirtIO config
+            let config = VirtioConfig {
+                dma_cpu_base: dma.cpu_base(),
+                dma_bus_base: dma.bus_base(),
+                dma_size: dma.size(),
+                queue_size: 32,
+                buffer_size: 2048,
+            };
+
+            // Create driver
+            let driver = VirtioNetDriver::new_with_transport(
+                VirtioTransport::pci_legacy(io_base),
+                config,
+                tsc_freq,
+            )?;
+            Ok(ProbeResult::VirtIO(driver))
+        }
     }
 }
 
@@ -261,6 +296,9 @@ pub unsafe fn create_intel_driver(
         tx_queue_size: 32,
         buffer_size: 2048,
         tsc_freq,
+        pci_addr: None,
+        low_power: LowPowerPolicy::Auto,
+        link_mode: LinkMode::Auto,
     };
 
     E1000eDriver::new(mmio_base, config)
@@ -303,7 +341,8 @@ pub enum NicType {
 
 /// Detect what type of NIC is present without initializing.
 ///
-/// Useful for populating BootHandoff before ExitBootServices.
+/// Useful for populating BootHandoff before ExitBootServices. For a legacy
+/// VirtIO device the base is its I/O port.
 pub fn detect_nic_type() -> (NicType, Option<u64>, Option<PciAddr>) {
     // Check for Intel first (real hardware priority)
     if let Some(info) = find_intel_nic() {
@@ -311,8 +350,15 @@ pub fn detect_nic_type() -> (NicType, Option<u64>, Option<PciAddr>) {
     }
 
     // Check for VirtIO
-    if let Some((pci_addr, mmio_base)) = find_virtio_nic() {
-        return (NicType::VirtIO, Some(mmio_base), Some(pci_addr));
+    match find_virtio_nic() {
+        Some(DetectedNic::VirtIO {
+            pci_addr,
+            mmio_base,
+        }) => return (NicType::VirtIO, Some(mmio_base), Some(pci_addr)),
+        Some(DetectedNic::VirtioLegacy { pci_addr, io_base }) => {
+            return (NicType::VirtIO, Some(io_base as u64), Some(pci_addr))
+        }
+        _ => {}
     }
 
     (NicType::None, None, None)
@@ -432,13 +432,19 @@ impl<D: NetworkDevice> NativeHttpClient<D> {
     }
 
     /// Read full HTTP response (headers + body).
-    fn read_full_response(&mut self) -> Result<Vec<u8>> {
+    ///
+    /// A response to HEAD has no body whatever its Content-Length says.
+    fn read_full_response(&mut self, head: bool) -> Result<Vec<u8>> {
         let mut response_data = self.read_headers()?;
         let body_start = find_header_end(&response_data).ok_or(NetworkError::InvalidResponse)? + 4;
 
         let headers_str = core::str::from_utf8(&response_data[..body_start - 4])
             .map_err(|_| NetworkError::InvalidResponse)?;
-        let content_length = parse_content_length(headers_str);
+        let content_length = if head {
+            Some(0)
+        } else {
+            parse_content_length(headers_str)
+        };
 
         // Read remaining body
         self.read_remaining_body(&mut response_data, body_start, content_length)?;
@@ -534,7 +540,8 @@ impl<D: NetworkDevice> NativeHttpClient<D> {
         self.connect(ip, port)?;
         self.send_all(&request.to_wire_format())?;
 
-        let response_data = self.read_full_response()?;
+        let response_data =
+            self.read_full_response(matches!(request.method, HttpMethod::Head))?;
         let (response, _) = Response::parse(&response_data)?;
 
         Ok(response)
@@ -572,7 +579,8 @@ impl<D: NetworkDevice> NativeHttpClient<D> {
             new
         };
 
-        Ok(Request::get(new_url))
+        // Keep the method, so a HEAD stays a HEAD across redirects
+        Ok(Request::new(original.method, new_url))
     }
 
     // ========================================================================
@@ -233,15 +233,22 @@ impl NetworkDevice for UnifiedNetDevice {
 // UNIFIED BLOCK DEVICE
 // ═══════════════════════════════════════════════════════════════════════════
 
+#[cfg(not(feature = "netboot-only"))]
 use crate::driver::ahci::{AhciDriver, AhciInitError};
-use crate::driver::block_traits::{BlockCompletion, BlockDeviceInfo, BlockDriver, BlockError};
+use crate::driver::block_traits::{
+    BlockCompletion, BlockDeviceInfo, BlockDriver, BlockError, BlockEvent,
+};
+#[cfg(not(feature = "netboot-only"))]
+use crate::driver::usb_msc::{UsbMscDriver, UsbMscInitError};
+#[cfg(not(feature = "netboot-only"))]
 use crate::driver::virtio_blk::{VirtioBlkDriver, VirtioBlkInitError};
 
-/// Unified block device that works with both VirtIO-blk and AHCI.
+/// Unified block device that works with VirtIO-blk, AHCI and USB sticks.
 ///
 /// This provides automatic hardware detection and driver selection:
 /// - QEMU/VMs: Uses VirtIO-blk
 /// - Real hardware (ThinkPad T450s): Uses AHCI SATA driver
+/// - No disk controller, only xHCI: Uses the USB mass storage driver
 ///
 /// # Example
 ///
@@ -259,14 +266,18 @@ use crate::driver::virtio_blk::{VirtioBlkDriver, VirtioBlkInitError};
 ///     // handle completion
 /// }
 /// ```
+#[cfg(not(feature = "netboot-only"))]
 pub enum UnifiedBlockDevice {
     /// VirtIO-blk driver (QEMU, cloud VMs)
     VirtIO(VirtioBlkDriver),
     /// AHCI SATA driver (real hardware - ThinkPad T450s)
     Ahci(AhciDriver),
+    /// USB mass storage driver (USB stick on an xHCI root port)
+    Usb(UsbMscDriver),
 }
 
 /// Errors from unified block device operations.
+#[cfg(not(feature = "netboot-only"))]
 #[derive(Debug)]
 pub enum UnifiedBlockError {
     /// No supported block device found
@@ -275,26 +286,39 @@ pub enum UnifiedBlockError {
     VirtioError(VirtioBlkInitError),
     /// AHCI initialization failed
     AhciError(AhciInitError),
+    /// USB mass storage initialization failed
+    UsbError(UsbMscInitError),
 }
 
+#[cfg(not(feature = "netboot-only"))]
 impl From<VirtioBlkInitError> for UnifiedBlockError {
     fn from(e: VirtioBlkInitError) -> Self {
         UnifiedBlockError::VirtioError(e)
     }
 }
 
+#[cfg(not(feature = "netboot-only"))]
 impl From<AhciInitError> for UnifiedBlockError {
     fn from(e: AhciInitError) -> Self {
         UnifiedBlockError::AhciError(e)
     }
 }
 
+#[cfg(not(feature = "netboot-only"))]
+impl From<UsbMscInitError> for UnifiedBlockError {
+    fn from(e: UsbMscInitError) -> Self {
+        UnifiedBlockError::UsbError(e)
+    }
+}
+
+#[cfg(not(feature = "netboot-only"))]
 impl UnifiedBlockDevice {
     /// Get which driver type is being used.
     pub fn driver_type(&self) -> &'static str {
         match self {
             UnifiedBlockDevice::VirtIO(_) => "VirtIO-blk",
             UnifiedBlockDevice::Ahci(_) => "AHCI SATA",
+            UnifiedBlockDevice::Usb(_) => "USB mass storage",
         }
     }
 
@@ -303,15 +327,18 @@ impl UnifiedBlockDevice {
         match self {
             UnifiedBlockDevice::VirtIO(_) => true, // VirtIO always ready after init
             UnifiedBlockDevice::Ahci(d) => d.link_up(),
+            UnifiedBlockDevice::Usb(_) => true, // Medium checked during init
         }
     }
 }
 
+#[cfg(not(feature = "netboot-only"))]
 impl BlockDriver for UnifiedBlockDevice {
     fn info(&self) -> BlockDeviceInfo {
         match self {
             UnifiedBlockDevice::VirtIO(d) => d.info(),
             UnifiedBlockDevice::Ahci(d) => d.info(),
+            UnifiedBlockDevice::Usb(d) => d.info(),
         }
     }
 
@@ -319,6 +346,7 @@ impl BlockDriver for UnifiedBlockDevice {
         match self {
             UnifiedBlockDevice::VirtIO(d) => d.can_submit(),
             UnifiedBlockDevice::Ahci(d) => d.can_submit(),
+            UnifiedBlockDevice::Usb(d) => d.can_submit(),
         }
     }
 
@@ -336,6 +364,9 @@ impl BlockDriver for UnifiedBlockDevice {
             UnifiedBlockDevice::Ahci(d) => {
                 d.submit_read(sector, buffer_phys, num_sectors, request_id)
             }
+            UnifiedBlockDevice::Usb(d) => {
+                d.submit_read(sector, buffer_phys, num_sectors, request_id)
+            }
         }
     }
 
@@ -353,6 +384,9 @@ impl BlockDriver for UnifiedBlockDevice {
             UnifiedBlockDevice::Ahci(d) => {
                 d.submit_write(sector, buffer_phys, num_sectors, request_id)
             }
+            UnifiedBlockDevice::Usb(d) => {
+                d.submit_write(sector, buffer_phys, num_sectors, request_id)
+            }
         }
     }
 
@@ -360,6 +394,7 @@ impl BlockDriver for UnifiedBlockDevice {
         match self {
             UnifiedBlockDevice::VirtIO(d) => d.poll_completion(),
             UnifiedBlockDevice::Ahci(d) => d.poll_completion(),
+            UnifiedBlockDevice::Usb(d) => d.poll_completion(),
         }
     }
 
@@ -367,6 +402,7 @@ impl BlockDriver for UnifiedBlockDevice {
         match self {
             UnifiedBlockDevice::VirtIO(d) => d.notify(),
             UnifiedBlockDevice::Ahci(d) => d.notify(),
+            UnifiedBlockDevice::Usb(d) => d.notify(),
         }
     }
 
@@ -374,8 +410,109 @@ impl BlockDriver for UnifiedBlockDevice {
         match self {
             UnifiedBlockDevice::VirtIO(d) => d.flush(),
             UnifiedBlockDevice::Ahci(d) => d.flush(),
+            UnifiedBlockDevice::Usb(d) => d.flush(),
         }
     }
+
+    fn submit_flush(&mut self, request_id: u32) -> core::result::Result<(), BlockError> {
+        match self {
+            UnifiedBlockDevice::VirtIO(d) => d.submit_flush(request_id),
+            UnifiedBlockDevice::Ahci(d) => d.submit_flush(request_id),
+            UnifiedBlockDevice::Usb(d) => d.submit_flush(request_id),
+        }
+    }
+
+    fn spin_down(&mut self) -> core::result::Result<(), BlockError> {
+        match self {
+            UnifiedBlockDevice::VirtIO(d) => d.spin_down(),
+            UnifiedBlockDevice::Ahci(d) => d.spin_down(),
+            UnifiedBlockDevice::Usb(d) => d.spin_down(),
+        }
+    }
+
+    fn in_flight(&self) -> usize {
+        match self {
+            UnifiedBlockDevice::VirtIO(d) => d.in_flight(),
+            UnifiedBlockDevice::Ahci(d) => d.in_flight(),
+            UnifiedBlockDevice::Usb(d) => d.in_flight(),
+        }
+    }
+
+    fn poll_event(&mut self) -> Option<BlockEvent> {
+        match self {
+            UnifiedBlockDevice::VirtIO(d) => d.poll_event(),
+            UnifiedBlockDevice::Ahci(d) => d.poll_event(),
+            UnifiedBlockDevice::Usb(d) => d.poll_event(),
+        }
+    }
+}
+
+/// Block device stand-in for `netboot-only` builds.
+///
+/// There are no block drivers, so this has no values: every
+/// `Option<UnifiedBlockDevice>` is `None` and the disk write path
+/// compiles away without touching its callers.
+#[cfg(feature = "netboot-only")]
+pub enum UnifiedBlockDevice {}
+
+/// Errors from unified block device operations.
+#[cfg(feature = "netboot-only")]
+#[derive(Debug)]
+pub enum UnifiedBlockError {
+    /// Built without block drivers
+    NoDevice,
+}
+
+#[cfg(feature = "netboot-only")]
+impl UnifiedBlockDevice {
+    /// Get which driver type is being used.
+    pub fn driver_type(&self) -> &'static str {
+        match *self {}
+    }
+
+    /// Check if device is ready for I/O.
+    pub fn is_ready(&self) -> bool {
+        match *self {}
+    }
+}
+
+#[cfg(feature = "netboot-only")]
+impl BlockDriver for UnifiedBlockDevice {
+    fn info(&self) -> BlockDeviceInfo {
+        match *self {}
+    }
+
+    fn can_submit(&self) -> bool {
+        match *self {}
+    }
+
+    fn submit_read(
+        &mut self,
+        _sector: u64,
+        _buffer_phys: u64,
+        _num_sectors: u32,
+        _request_id: u32,
+    ) -> core::result::Result<(), BlockError> {
+        match *self {}
+    }
+
+    fn submit_write(
+        &mut self,
+        _sector: u64,
+        _buffer_phys: u64,
+        _num_sectors: u32,
+        _request_id: u32,
+    ) -> core::result::Result<(), BlockError> {
+        match *self {}
+    }
+
+    fn poll_completion(&mut self) -> Option<BlockCompletion> {
+        match *self {}
+    }
+
+    fn notify(&mut self) {
+        match *self {}
+    }
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -1,8 +1,10 @@
 //! Display output module for post-EBS framebuffer rendering.
 //!
 //! This module provides a static display instance that mirrors serial output
-//! to the framebuffer when the `display` feature is enabled.
+//! to the framebuffer when the `display` feature is enabled. The download's
+//! status screen can turn the mirror off and draw fixed lines instead.
 
+use core::sync::atomic::{AtomicBool, Ordering};
 use spin::Mutex;
 
 #[cfg(feature = "display")]
@@ -12,6 +14,14 @@ use morpheus_display::{fb_backend::FbTextOutput, FramebufferInfo, PixelFormat, T
 #[cfg(feature = "display")]
 static DISPLAY: Mutex<Option<FbTextOutput>> = Mutex::new(None);
 
+/// Whether serial output is mirrored to the display.
+static MIRROR: AtomicBool = AtomicBool::new(true);
+
+/// Turn mirroring of serial output on or off.
+pub fn set_mirror(enabled: bool) {
+    MIRROR.store(enabled, Ordering::Relaxed);
+}
+
 /// Initialize the framebuffer display from handoff info.
 ///
 /// # Safety
@@ -41,9 +51,12 @@ pub unsafe fn init_display(base: u64, width: u32, height: u32, stride: u32, form
     *DISPLAY.lock() = Some(display);
 }
 
-/// Write a string to the display (if initialized).
+/// Write a string to the display (if initialized and mirroring).
 #[cfg(feature = "display")]
 pub fn display_write(s: &str) {
+    if !MIRROR.load(Ordering::Relaxed) {
+        return;
+    }
     if let Some(ref mut display) = *DISPLAY.lock() {
         display.write_str(s);
     }
@@ -64,6 +77,38 @@ pub fn display_available() -> bool {
     DISPLAY.lock().is_some()
 }
 
+/// Clear the display.
+#[cfg(feature = "display")]
+pub fn display_clear() {
+    if let Some(ref mut display) = *DISPLAY.lock() {
+        display.set_attribute(morpheus_display::efi::DEFAULT_ATTR);
+        display.clear();
+    }
+}
+
+/// Replace text row `row` with `s` in EFI attribute `attr`, padded with
+/// blanks. The last column is left alone so the cursor never wraps and
+/// scrolls the screen.
+#[cfg(feature = "display")]
+pub fn display_line(row: usize, s: &str, attr: u8) {
+    if let Some(ref mut display) = *DISPLAY.lock() {
+        if row >= display.rows() {
+            return;
+        }
+        let width = display.cols().saturating_sub(1);
+        display.set_cursor(0, row);
+        display.set_attribute(attr);
+        let mut written = 0;
+        for c in s.chars().take(width) {
+            display.write_char(c);
+            written += 1;
+        }
+        for _ in written..width {
+            display.write_char(' ');
+        }
+    }
+}
+
 // Stubs when display feature is disabled
 #[cfg(not(feature = "display"))]
 pub unsafe fn init_display(_base: u64, _width: u32, _height: u32, _stride: u32, _format: u32) {}
@@ -78,3 +123,9 @@ pub fn display_writeln(_s: &str) {}
 pub fn display_available() -> bool {
     false
 }
+
+#[cfg(not(feature = "display"))]
+pub fn display_clear() {}
+
+#[cfg(not(feature = "display"))]
+pub fn display_line(_row: usize, _s: &str, _attr: u8) {}
@@ -0,0 +1,85 @@
+//! Heap-allocated DMA buffer.
+//!
+//! Post-EBS memory is identity mapped, so a page-aligned heap allocation
+//! is directly usable for device DMA: its address is its bus address.
+//! Replaces the per-state `static mut` buffers, which were shared between
+//! every caller of a state and sat in the loader image.
+
+extern crate alloc;
+
+use alloc::alloc::{alloc_zeroed, dealloc};
+use core::alloc::Layout;
+use core::ptr::NonNull;
+
+/// Alignment of heap DMA buffers (one page).
+pub const HEAP_DMA_ALIGN: usize = 4096;
+
+/// Owned, zeroed, page-aligned DMA buffer.
+pub struct HeapDmaBuffer {
+    ptr: NonNull<u8>,
+    layout: Layout,
+}
+
+impl HeapDmaBuffer {
+    /// Allocate `size` zeroed bytes. `None` if the heap is exhausted.
+    pub fn new(size: usize) -> Option<Self> {
+        let layout = Layout::from_size_align(size.max(1), HEAP_DMA_ALIGN).ok()?;
+        // SAFETY: layout has non-zero size.
+        let ptr = NonNull::new(unsafe { alloc_zeroed(layout) })?;
+        Some(Self { ptr, layout })
+    }
+
+    /// Buffer length in bytes.
+    pub fn len(&self) -> usize {
+        self.layout.size()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Device-visible address (identity mapped).
+    pub fn phys_addr(&self) -> u64 {
+        self.ptr.as_ptr() as u64
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        // SAFETY: we own `len` initialized bytes.
+        unsafe { core::slice::from_raw_parts(self.ptr.as_ptr(), self.len()) }
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        // SAFETY: we own `len` initialized bytes, borrowed mutably.
+        unsafe { core::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len()) }
+    }
+
+    /// Mutable slice together with its device address.
+    pub fn split_phys(&mut self) -> (&mut [u8], u64) {
+        let phys = self.phys_addr();
+        (self.as_mut_slice(), phys)
+    }
+}
+
+impl Drop for HeapDmaBuffer {
+    fn drop(&mut self) {
+        // SAFETY: allocated in `new` with this layout.
+        unsafe { dealloc(self.ptr.as_ptr(), self.layout) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heap_dma_buffer_aligned_and_zeroed() {
+        let mut buf = HeapDmaBuffer::new(64 * 1024).unwrap();
+        assert_eq!(buf.len(), 64 * 1024);
+        assert_eq!(buf.phys_addr() % HEAP_DMA_ALIGN as u64, 0);
+        assert!(buf.as_slice().iter().all(|&b| b == 0));
+
+        let (slice, phys) = buf.split_phys();
+        slice[0] = 0xAA;
+        assert_eq!(phys, slice.as_ptr() as u64);
+    }
+}
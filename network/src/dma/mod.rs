@@ -6,12 +6,14 @@
 //! NETWORK_IMPL_GUIDE.md §3
 
 pub mod buffer;
+pub mod heap_buffer;
 pub mod ownership;
 pub mod pool;
 pub mod region;
 
 // Re-exports
 pub use buffer::DmaBuffer;
+pub use heap_buffer::HeapDmaBuffer;
 pub use ownership::BufferOwnership;
 pub use pool::{BufferPool, MAX_POOL_SIZE};
 pub use region::DmaRegion;
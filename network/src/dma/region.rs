@@ -11,8 +11,16 @@
 //! 0x00C00     0x0108      TX Used Ring
 //! 0x01000     0x10000     RX Buffers (32 × 2KB)
 //! 0x11000     0x10000     TX Buffers (32 × 2KB)
+//! 0x21000     0x8000      RX Legacy VirtIO Ring (up to 1024 entries)
+//! 0x29000     0x8000      TX Legacy VirtIO Ring (up to 1024 entries)
+//! 0x31000     0x8000      VirtIO Control Ring (any transport)
+//! 0x39000     0x1000      VirtIO Control Command Buffer
 //! ```
 //!
+//! Legacy virtio devices fix the queue size themselves and want the whole
+//! ring page-aligned in one piece, so they get their own ring areas. The
+//! control queue uses that layout on every transport.
+//!
 //! # Reference
 //! NETWORK_IMPL_GUIDE.md §3.3
 
@@ -56,6 +64,18 @@ impl DmaRegion {
     pub const RX_BUFFERS_OFFSET: usize = 0x1000;
     /// TX buffers offset.
     pub const TX_BUFFERS_OFFSET: usize = 0x11000;
+    /// RX legacy VirtIO ring offset (page-aligned).
+    pub const LEGACY_RX_RING_OFFSET: usize = 0x21000;
+    /// TX legacy VirtIO ring offset (page-aligned).
+    pub const LEGACY_TX_RING_OFFSET: usize = 0x29000;
+    /// Room for each legacy VirtIO ring.
+    pub const LEGACY_RING_SIZE: usize = 0x8000;
+    /// VirtIO control ring offset (page-aligned, `LEGACY_RING_SIZE` bytes).
+    pub const CTRL_RING_OFFSET: usize = 0x31000;
+    /// VirtIO control command buffer offset.
+    pub const CTRL_BUFFER_OFFSET: usize = 0x39000;
+    /// VirtIO control command buffer size.
+    pub const CTRL_BUFFER_SIZE: usize = 0x1000;
 
     /// Create a new DMA region.
     ///
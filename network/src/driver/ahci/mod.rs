@@ -23,6 +23,16 @@
 //! - FIS Receive: 256-byte aligned, 256 bytes
 //! - Command Tables: 128-byte aligned, one per command slot
 //!
+//! # Command Queuing
+//!
+//! When both the HBA (CAP.SNCQ) and the device (IDENTIFY word 76) support
+//! NCQ, reads and writes go out as READ/WRITE FPDMA QUEUED with the slot
+//! number as the tag, so up to the device's queue depth can be outstanding
+//! and the drive reorders them. Everything else (FLUSH CACHE EXT) is
+//! non-queued and waits for the queue to drain. After a failed queued
+//! command the port is restarted and the driver falls back to issuing
+//! commands one at a time.
+//!
 //! # Reference
 //!
 //! - AHCI 1.3.1 Specification
@@ -37,6 +47,8 @@ use crate::driver::block_traits::{
     BlockCompletion, BlockDeviceInfo, BlockDriver, BlockDriverInit, BlockError,
 };
 use core::ptr;
+use morpheus_core::disk::identity::{ata_is_removable, ata_media_kind, DeviceIdentity};
+use regs::{ata, cap, pxis};
 
 // Re-exports
 pub use init::{AhciConfig, AhciInitError};
@@ -45,7 +57,29 @@ pub use init::{AhciConfig, AhciInitError};
 // ASM BINDINGS
 // ═══════════════════════════════════════════════════════════════════════════
 
-extern "win64" {
+/// Declare the AHCI assembly entry points.
+///
+/// AHCI is only ported to x86_64. Elsewhere each binding becomes an
+/// unreachable stub: `AhciDriver::new` refuses to construct a driver, so
+/// none of them can run.
+macro_rules! ahci_bindings {
+    ($(fn $name:ident($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)?;)*) => {
+        #[cfg(target_arch = "x86_64")]
+        extern "win64" {
+            $(fn $name($($arg: $ty),*) $(-> $ret)?;)*
+        }
+
+        $(
+            #[cfg(not(target_arch = "x86_64"))]
+            #[allow(dead_code)]
+            unsafe fn $name($(_: $ty),*) $(-> $ret)? {
+                unreachable!("AHCI is x86_64-only")
+            }
+        )*
+    };
+}
+
+ahci_bindings! {
     // HBA initialization
     fn asm_ahci_hba_reset(abar: u64, tsc_freq: u64) -> u32;
     fn asm_ahci_enable(abar: u64) -> u32;
@@ -69,6 +103,8 @@ extern "win64" {
     fn asm_ahci_port_read_is(abar: u64, port_num: u32) -> u32;
     fn asm_ahci_port_clear_is(abar: u64, port_num: u32, bits: u32);
     fn asm_ahci_port_disable_interrupts(abar: u64, port_num: u32);
+    fn asm_ahci_port_read_sact(abar: u64, port_num: u32) -> u32;
+    fn asm_ahci_port_read_ci(abar: u64, port_num: u32) -> u32;
 
     // Command operations
     fn asm_ahci_setup_cmd_header(cmd_header_ptr: u64, flags: u32, ctba_phys: u64);
@@ -97,6 +133,7 @@ extern "win64" {
     ) -> u32;
     fn asm_ahci_get_identify_capacity(identify_buf_ptr: u64) -> u64;
     fn asm_ahci_get_identify_sector_size(identify_buf_ptr: u64) -> u32;
+    fn asm_ahci_get_identify_ncq_depth(identify_buf_ptr: u64) -> u32;
 
     // I/O operations
     fn asm_ahci_submit_read(
@@ -121,6 +158,18 @@ extern "win64" {
         cmd_table_ptr: u64,
         cmd_table_phys: u64,
     ) -> u32;
+    fn asm_ahci_submit_fpdma(
+        abar: u64,
+        port_num: u32,
+        lba: u64,
+        data_buf_phys: u64,
+        num_sectors: u32,
+        cmd_slot: u32,
+        cmd_header_ptr: u64,
+        cmd_table_ptr: u64,
+        cmd_table_phys: u64,
+        write: u32,
+    ) -> u32;
     fn asm_ahci_flush_cache(
         abar: u64,
         port_num: u32,
@@ -188,6 +237,14 @@ struct InFlightRequest {
     slot: u8,
     /// Is this slot in use?
     active: bool,
+    /// Issued as an NCQ (FPDMA QUEUED) command
+    queued: bool,
+}
+
+/// Slots in `active` whose command has finished: a non-queued command once
+/// its PxCI bit clears, a queued one once its PxSACT bit clears as well.
+fn completed_slots(active: u32, queued: u32, ci: u32, sact: u32) -> u32 {
+    active & !ci & !(sact & queued)
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -212,6 +269,11 @@ pub struct AhciDriver {
     in_flight: [InFlightRequest; MAX_CMD_SLOTS],
     /// Next slot to use (round-robin)
     next_slot: u8,
+    /// NCQ queue depth (0 = NCQ off, commands are non-queued)
+    ncq_depth: u32,
+    /// A command failed: the rest are failed too and the port restarted
+    /// before anything new is issued
+    failing: bool,
     /// DMA: Command List base (CPU pointer)
     cmd_list_cpu: *mut u8,
     /// DMA: Command List base (physical)
@@ -242,6 +304,10 @@ impl AhciDriver {
             return Err(AhciInitError::InvalidConfig);
         }
 
+        if cfg!(not(target_arch = "x86_64")) {
+            return Err(AhciInitError::DeviceNotResponding);
+        }
+
         let tsc_freq = config.tsc_freq;
 
         // ═══════════════════════════════════════════════════════════════════
@@ -332,12 +398,26 @@ impl AhciDriver {
         // Parse IDENTIFY data
         let total_sectors = asm_ahci_get_identify_capacity(config.identify_cpu as u64);
         let sector_size = asm_ahci_get_identify_sector_size(config.identify_cpu as u64);
+        let identify = core::slice::from_raw_parts(config.identify_cpu as *const u8, 512);
+        let identity = DeviceIdentity::from_ata_identify(identify);
+        let removable = ata_is_removable(identify);
+        let media = ata_media_kind(identify);
+
+        // NCQ needs support on both sides of the link
+        let ncq_depth = if cap & cap::SNCQ != 0 {
+            asm_ahci_get_identify_ncq_depth(config.identify_cpu as u64).min(num_slots)
+        } else {
+            0
+        };
 
         let info = BlockDeviceInfo {
             total_sectors,
             sector_size,
             max_sectors_per_request: 256, // Conservative for DMA
             read_only: false,
+            removable,
+            identity,
+            media,
         };
 
         Ok(Self {
@@ -348,6 +428,8 @@ impl AhciDriver {
             num_slots,
             in_flight: [InFlightRequest::default(); MAX_CMD_SLOTS],
             next_slot: 0,
+            ncq_depth,
+            failing: false,
             cmd_list_cpu: config.cmd_list_cpu,
             cmd_list_phys: config.cmd_list_phys,
             fis_cpu: config.fis_cpu,
@@ -376,11 +458,45 @@ impl AhciDriver {
         self.cmd_tables_phys + (slot as u64) * 256
     }
 
-    /// Allocate a command slot
-    fn alloc_slot(&mut self) -> Option<u32> {
-        for _ in 0..self.num_slots {
-            let slot = self.next_slot as u32;
-            self.next_slot = ((self.next_slot as u32 + 1) % self.num_slots) as u8;
+    /// Slots usable for new commands. Under NCQ the slot doubles as the
+    /// tag, so it must stay below the device's queue depth.
+    fn usable_slots(&self) -> u32 {
+        if self.ncq_depth > 0 {
+            self.ncq_depth
+        } else {
+            self.num_slots
+        }
+    }
+
+    /// Bitmasks of the active slots and of those holding queued commands.
+    fn slot_masks(&self) -> (u32, u32) {
+        let mut active = 0;
+        let mut queued = 0;
+        for (slot, request) in self.in_flight.iter().enumerate() {
+            if request.active {
+                active |= 1 << slot;
+                if request.queued {
+                    queued |= 1 << slot;
+                }
+            }
+        }
+        (active, queued)
+    }
+
+    /// Allocate a command slot for a queued or non-queued command.
+    ///
+    /// The two kinds can't be outstanding together, and nothing new is
+    /// issued while a failed command is being cleaned up.
+    fn alloc_slot(&mut self, queued: bool) -> Option<u32> {
+        let (active, queued_mask) = self.slot_masks();
+        if self.failing || (active != 0 && (queued_mask == active) != queued) {
+            return None;
+        }
+
+        let slots = self.usable_slots();
+        for _ in 0..slots {
+            let slot = self.next_slot as u32 % slots;
+            self.next_slot = ((slot + 1) % slots) as u8;
 
             if !self.in_flight[slot as usize].active {
                 return Some(slot);
@@ -389,6 +505,79 @@ impl AhciDriver {
         None
     }
 
+    /// Submit a READ/WRITE FPDMA QUEUED command.
+    fn submit_queued(
+        &mut self,
+        sector: u64,
+        buffer_phys: u64,
+        num_sectors: u32,
+        request_id: u32,
+        write: bool,
+    ) -> Result<(), BlockError> {
+        let slot = self.alloc_slot(true).ok_or(BlockError::QueueFull)?;
+
+        let result = unsafe {
+            asm_ahci_submit_fpdma(
+                self.abar,
+                self.port_num,
+                sector,
+                buffer_phys,
+                num_sectors,
+                slot,
+                self.cmd_header_ptr(slot) as u64,
+                self.cmd_table_ptr(slot) as u64,
+                self.cmd_table_phys(slot),
+                write as u32,
+            )
+        };
+
+        if result != 0 {
+            return Err(BlockError::DeviceError);
+        }
+
+        self.in_flight[slot as usize] = InFlightRequest {
+            request_id,
+            slot: slot as u8,
+            active: true,
+            queued: true,
+        };
+
+        Ok(())
+    }
+
+    /// Free `slot` and build its completion.
+    fn complete(&mut self, slot: u32, status: u8) -> BlockCompletion {
+        // Read bytes transferred from command header
+        let bytes_transferred = unsafe { asm_ahci_read_prdbc(self.cmd_header_ptr(slot) as u64) };
+
+        let request = &mut self.in_flight[slot as usize];
+        request.active = false;
+
+        BlockCompletion {
+            request_id: request.request_id,
+            status,
+            bytes_transferred,
+        }
+    }
+
+    /// Restart the port once every command of a failed batch has been
+    /// reported. Clearing PxCMD.ST also clears PxCI and PxSACT.
+    fn recover_port(&mut self) {
+        unsafe {
+            asm_ahci_port_stop(self.abar, self.port_num, self.tsc_freq);
+            asm_ahci_port_clear_errors(self.abar, self.port_num);
+            asm_ahci_port_start(self.abar, self.port_num);
+        }
+        self.failing = false;
+        // Don't trust the queue again on a device that just failed in it
+        self.ncq_depth = 0;
+    }
+
+    /// NCQ queue depth in use (0 if commands are issued one at a time).
+    pub fn ncq_depth(&self) -> u32 {
+        self.ncq_depth
+    }
+
     /// Check link status
     pub fn link_up(&self) -> bool {
         let det = unsafe { asm_ahci_port_detect(self.abar, self.port_num) };
@@ -415,7 +604,10 @@ impl BlockDriver for AhciDriver {
     }
 
     fn can_submit(&self) -> bool {
-        self.in_flight.iter().any(|s| !s.active)
+        !self.failing
+            && self.in_flight[..self.usable_slots() as usize]
+                .iter()
+                .any(|s| !s.active)
     }
 
     fn submit_read(
@@ -433,8 +625,12 @@ impl BlockDriver for AhciDriver {
             return Err(BlockError::RequestTooLarge);
         }
 
+        if self.ncq_depth > 0 {
+            return self.submit_queued(sector, buffer_phys, num_sectors, request_id, false);
+        }
+
         // Allocate slot
-        let slot = self.alloc_slot().ok_or(BlockError::QueueFull)?;
+        let slot = self.alloc_slot(false).ok_or(BlockError::QueueFull)?;
 
         // Submit via ASM
         let result = unsafe {
@@ -460,6 +656,7 @@ impl BlockDriver for AhciDriver {
             request_id,
             slot: slot as u8,
             active: true,
+            queued: false,
         };
 
         Ok(())
@@ -485,8 +682,12 @@ impl BlockDriver for AhciDriver {
             return Err(BlockError::RequestTooLarge);
         }
 
+        if self.ncq_depth > 0 {
+            return self.submit_queued(sector, buffer_phys, num_sectors, request_id, true);
+        }
+
         // Allocate slot
-        let slot = self.alloc_slot().ok_or(BlockError::QueueFull)?;
+        let slot = self.alloc_slot(false).ok_or(BlockError::QueueFull)?;
 
         // Submit via ASM
         let result = unsafe {
@@ -512,48 +713,43 @@ impl BlockDriver for AhciDriver {
             request_id,
             slot: slot as u8,
             active: true,
+            queued: false,
         };
 
         Ok(())
     }
 
     fn poll_completion(&mut self) -> Option<BlockCompletion> {
-        // Check each active slot
-        for slot in 0..self.num_slots as usize {
-            if !self.in_flight[slot].active {
-                continue;
-            }
-
-            let slot_mask = 1u32 << slot;
-            let status =
-                unsafe { asm_ahci_check_cmd_complete(self.abar, self.port_num, slot_mask) };
-
-            if status == 0 {
-                // Still pending
-                continue;
+        let (active, queued) = self.slot_masks();
+        if active == 0 {
+            if self.failing {
+                self.recover_port();
             }
+            return None;
+        }
 
-            let request_id = self.in_flight[slot].request_id;
-
-            // Read bytes transferred from command header
-            let bytes_transferred =
-                unsafe { asm_ahci_read_prdbc(self.cmd_header_ptr(slot as u32) as u64) };
-
-            // Mark slot as free
-            self.in_flight[slot].active = false;
+        let (is, ci, sact) = unsafe {
+            (
+                asm_ahci_port_read_is(self.abar, self.port_num),
+                asm_ahci_port_read_ci(self.abar, self.port_num),
+                asm_ahci_port_read_sact(self.abar, self.port_num),
+            )
+        };
 
-            // Clear interrupt status for this completion
+        let done = completed_slots(active, queued, ci, sact);
+        if done != 0 {
+            // Clear the status we saw, but leave an error for the next poll
             unsafe {
-                asm_ahci_port_clear_is(self.abar, self.port_num, 0xFFFFFFFF);
+                asm_ahci_port_clear_is(self.abar, self.port_num, is & !pxis::TFES);
             }
+            return Some(self.complete(done.trailing_zeros(), 0));
+        }
 
-            let completion = BlockCompletion {
-                request_id,
-                status: if status == 1 { 0 } else { 1 }, // 1=complete, 2=error
-                bytes_transferred,
-            };
-
-            return Some(completion);
+        if self.failing || is & pxis::TFES != 0 {
+            // The HBA stops and the device aborts everything outstanding:
+            // fail one command per poll, then restart the port
+            self.failing = true;
+            return Some(self.complete(active.trailing_zeros(), 1));
         }
 
         None
@@ -565,8 +761,8 @@ impl BlockDriver for AhciDriver {
     }
 
     fn flush(&mut self) -> Result<(), BlockError> {
-        // Use slot 0 for flush (should be available after draining queue)
-        let slot = self.alloc_slot().ok_or(BlockError::QueueFull)?;
+        // Non-queued, so only available once queued commands have drained
+        let slot = self.alloc_slot(false).ok_or(BlockError::QueueFull)?;
 
         unsafe {
             let result = asm_ahci_flush_cache(
@@ -599,6 +795,67 @@ impl BlockDriver for AhciDriver {
 
         Ok(())
     }
+
+    fn submit_flush(&mut self, request_id: u32) -> Result<(), BlockError> {
+        let slot = self.alloc_slot(false).ok_or(BlockError::QueueFull)?;
+
+        // FLUSH CACHE EXT completes through the same CI bit as reads/writes
+        let result = unsafe {
+            asm_ahci_flush_cache(
+                self.abar,
+                self.port_num,
+                slot,
+                self.cmd_header_ptr(slot) as u64,
+                self.cmd_table_ptr(slot) as u64,
+                self.cmd_table_phys(slot),
+            )
+        };
+
+        if result != 0 {
+            return Err(BlockError::DeviceError);
+        }
+
+        self.in_flight[slot as usize] = InFlightRequest {
+            request_id,
+            slot: slot as u8,
+            active: true,
+            queued: false,
+        };
+
+        Ok(())
+    }
+
+    fn spin_down(&mut self) -> Result<(), BlockError> {
+        self.flush()?;
+        let slot = self.alloc_slot(false).ok_or(BlockError::QueueFull)?;
+
+        unsafe {
+            asm_ahci_build_h2d_fis(
+                self.cmd_table_ptr(slot) as u64,
+                ata::STANDBY_IMMEDIATE,
+                0,
+                0,
+            );
+            // CFL=5, no data, no PRDT
+            asm_ahci_setup_cmd_header(
+                self.cmd_header_ptr(slot) as u64,
+                5,
+                self.cmd_table_phys(slot),
+            );
+            asm_ahci_issue_cmd(self.abar, self.port_num, 1 << slot);
+
+            // Spinning down a disk takes a few seconds
+            if asm_ahci_poll_cmd(self.abar, self.port_num, 1 << slot, self.tsc_freq, 10000) != 0 {
+                return Err(BlockError::Timeout);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn in_flight(&self) -> usize {
+        self.in_flight.iter().filter(|s| s.active).count()
+    }
 }
 
 impl BlockDriverInit for AhciDriver {
@@ -620,3 +877,29 @@ impl BlockDriverInit for AhciDriver {
 
 // Safety: AhciDriver only contains raw pointers that are not shared
 unsafe impl Send for AhciDriver {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_completed_slots_non_queued() {
+        // Slots 0 and 2 issued, slot 2 still in PxCI
+        assert_eq!(completed_slots(0b101, 0, 0b100, 0), 0b001);
+        assert_eq!(completed_slots(0b101, 0, 0b101, 0), 0);
+    }
+
+    #[test]
+    fn test_completed_slots_queued() {
+        // PxCI clears when the FIS is sent; the tag is done once SACT clears
+        assert_eq!(completed_slots(0b111, 0b111, 0, 0b110), 0b001);
+        assert_eq!(completed_slots(0b111, 0b111, 0b100, 0b010), 0b001);
+        assert_eq!(completed_slots(0b111, 0b111, 0, 0), 0b111);
+    }
+
+    #[test]
+    fn test_completed_slots_ignores_idle_slots() {
+        assert_eq!(completed_slots(0b010, 0b010, 0, 0b100), 0b010);
+        assert_eq!(completed_slots(0, 0, 0, 0), 0);
+    }
+}
@@ -16,6 +16,12 @@ pub mod hba {
     pub const VS: u64 = 0x10;
 }
 
+/// Host Capabilities bits
+pub mod cap {
+    pub const SNCQ: u32 = 1 << 30; // Supports Native Command Queuing
+    pub const S64A: u32 = 1 << 31; // Supports 64-bit Addressing
+}
+
 /// Port registers (offset from ABAR + 0x100 + port * 0x80)
 pub mod port {
     /// Command List Base Address Low
@@ -86,8 +92,11 @@ pub mod pxis {
 pub mod ata {
     pub const READ_DMA_EXT: u8 = 0x25;
     pub const WRITE_DMA_EXT: u8 = 0x35;
+    pub const READ_FPDMA_QUEUED: u8 = 0x60;
+    pub const WRITE_FPDMA_QUEUED: u8 = 0x61;
     pub const IDENTIFY: u8 = 0xEC;
     pub const FLUSH_CACHE_EXT: u8 = 0xEA;
+    pub const STANDBY_IMMEDIATE: u8 = 0xE0;
 }
 
 /// FIS types
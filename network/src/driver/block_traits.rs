@@ -2,6 +2,8 @@
 //!
 //! Defines the interface for block storage devices (VirtIO-blk, etc.).
 
+use morpheus_core::disk::identity::{Capacity, DeviceIdentity, MediaKind};
+
 /// Block I/O error types.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BlockError {
@@ -47,6 +49,27 @@ pub struct BlockDeviceInfo {
     pub max_sectors_per_request: u32,
     /// Whether device is read-only
     pub read_only: bool,
+    /// Whether the media can be unplugged or swapped while running
+    pub removable: bool,
+    /// Model and serial number reported by the device
+    pub identity: DeviceIdentity,
+    /// Solid state or spinning, if the device says
+    pub media: MediaKind,
+}
+
+impl BlockDeviceInfo {
+    /// Total size in decimal units ("1.0 TB").
+    pub fn capacity(&self) -> Capacity {
+        Capacity(self.total_sectors * self.sector_size as u64)
+    }
+}
+
+/// Asynchronous device event, reported through [`BlockDriver::poll_event`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockEvent {
+    /// Device capacity changed (e.g. a virtual disk was resized).
+    /// `info().total_sectors` already reflects the new size.
+    CapacityChanged { old_sectors: u64, new_sectors: u64 },
 }
 
 /// Core block device interface.
@@ -119,6 +142,136 @@ pub trait BlockDriver {
         // Default: no-op for devices without flush support
         Ok(())
     }
+
+    /// Submit a cache flush request.
+    ///
+    /// Completes through `poll_completion` like reads and writes. Once it
+    /// completes successfully, every write that completed before it was
+    /// submitted is on stable storage.
+    ///
+    /// # Returns
+    /// - `Err(BlockError::Unsupported)`: Device has no volatile cache to flush
+    fn submit_flush(&mut self, _request_id: u32) -> Result<(), BlockError> {
+        Err(BlockError::Unsupported)
+    }
+
+    /// Flush the write cache and spin the device down, so removable
+    /// media can be unplugged safely. Blocks until the device is idle.
+    ///
+    /// # Returns
+    /// - `Err(BlockError::Unsupported)`: Device has no standby state
+    fn spin_down(&mut self) -> Result<(), BlockError> {
+        Err(BlockError::Unsupported)
+    }
+
+    /// Number of submitted requests that haven't completed yet.
+    fn in_flight(&self) -> usize {
+        0
+    }
+
+    /// Poll for a device event (capacity change, ...).
+    fn poll_event(&mut self) -> Option<BlockEvent> {
+        None
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// WRITE BARRIER
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Progress of a [`WriteBarrier`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BarrierStatus {
+    /// Still draining or flushing; call `step` again.
+    Pending,
+    /// Everything submitted before the barrier is on stable storage.
+    Done,
+    /// A request before the barrier, or the flush itself, failed.
+    Failed(BlockError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BarrierPhase {
+    Draining,
+    Flushing,
+    Finished(BarrierStatus),
+}
+
+/// Ordering point between two groups of writes.
+///
+/// Waits for every in-flight request to complete, then flushes the device
+/// cache. Nothing submitted after the barrier reports `Done` can reach the
+/// media before what was submitted ahead of it, which is what keeps a
+/// manifest from describing data that a power loss never let land.
+///
+/// Non-blocking: the caller drives it with [`step`](Self::step) and
+/// enforces its own timeout. Completions consumed while draining are
+/// dropped, so submit nothing else until the barrier finishes.
+#[derive(Debug)]
+pub struct WriteBarrier {
+    request_id: u32,
+    phase: BarrierPhase,
+    /// First failed completion seen while draining
+    drain_error: Option<BlockError>,
+}
+
+impl WriteBarrier {
+    /// Barrier whose flush request uses `request_id`.
+    pub fn new(request_id: u32) -> Self {
+        Self {
+            request_id,
+            phase: BarrierPhase::Draining,
+            drain_error: None,
+        }
+    }
+
+    /// Advance the barrier.
+    pub fn step<D: BlockDriver + ?Sized>(&mut self, driver: &mut D) -> BarrierStatus {
+        match self.phase {
+            BarrierPhase::Draining => {
+                while let Some(completion) = driver.poll_completion() {
+                    if completion.status != 0 && self.drain_error.is_none() {
+                        self.drain_error = Some(BlockError::IoError);
+                    }
+                }
+                if driver.in_flight() > 0 {
+                    return BarrierStatus::Pending;
+                }
+                if let Some(err) = self.drain_error {
+                    return self.finish(BarrierStatus::Failed(err));
+                }
+                match driver.submit_flush(self.request_id) {
+                    Ok(()) => {
+                        driver.notify();
+                        self.phase = BarrierPhase::Flushing;
+                        BarrierStatus::Pending
+                    }
+                    // Write-through device: drained is durable
+                    Err(BlockError::Unsupported) => self.finish(BarrierStatus::Done),
+                    Err(err) => self.finish(BarrierStatus::Failed(err)),
+                }
+            }
+            BarrierPhase::Flushing => {
+                while let Some(completion) = driver.poll_completion() {
+                    if completion.request_id != self.request_id {
+                        continue;
+                    }
+                    return self.finish(if completion.status == 0 {
+                        BarrierStatus::Done
+                    } else {
+                        BarrierStatus::Failed(BlockError::DeviceError)
+                    });
+                }
+                BarrierStatus::Pending
+            }
+            BarrierPhase::Finished(status) => status,
+        }
+    }
+
+    fn finish(&mut self, status: BarrierStatus) -> BarrierStatus {
+        self.phase = BarrierPhase::Finished(status);
+        status
+    }
 }
 
 /// Block driver initialization trait.
@@ -147,3 +300,127 @@ pub trait BlockDriverInit: Sized {
     /// - Configuration must be valid
     unsafe fn create(mmio_base: u64, config: Self::Config) -> Result<Self, Self::Error>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Driver whose requests complete one per poll, in order.
+    struct MockDriver {
+        pending: [Option<(u32, u8)>; 4],
+        supports_flush: bool,
+        flush_status: u8,
+        flushes: usize,
+    }
+
+    impl MockDriver {
+        fn new(supports_flush: bool) -> Self {
+            Self {
+                pending: [None; 4],
+                supports_flush,
+                flush_status: 0,
+                flushes: 0,
+            }
+        }
+
+        fn queue(&mut self, request_id: u32, status: u8) {
+            let slot = self.pending.iter_mut().find(|p| p.is_none()).unwrap();
+            *slot = Some((request_id, status));
+        }
+    }
+
+    impl BlockDriver for MockDriver {
+        fn info(&self) -> BlockDeviceInfo {
+            BlockDeviceInfo {
+                total_sectors: 1024,
+                sector_size: 512,
+                max_sectors_per_request: 128,
+                read_only: false,
+                removable: false,
+                identity: DeviceIdentity::unknown(),
+                media: MediaKind::Unknown,
+            }
+        }
+
+        fn can_submit(&self) -> bool {
+            self.pending.iter().any(|p| p.is_none())
+        }
+
+        fn submit_read(&mut self, _: u64, _: u64, _: u32, id: u32) -> Result<(), BlockError> {
+            self.queue(id, 0);
+            Ok(())
+        }
+
+        fn submit_write(&mut self, _: u64, _: u64, _: u32, id: u32) -> Result<(), BlockError> {
+            self.queue(id, 0);
+            Ok(())
+        }
+
+        fn poll_completion(&mut self) -> Option<BlockCompletion> {
+            let slot = self.pending.iter_mut().find(|p| p.is_some())?;
+            let (request_id, status) = slot.take()?;
+            Some(BlockCompletion {
+                request_id,
+                status,
+                bytes_transferred: 0,
+            })
+        }
+
+        fn notify(&mut self) {}
+
+        fn submit_flush(&mut self, request_id: u32) -> Result<(), BlockError> {
+            if !self.supports_flush {
+                return Err(BlockError::Unsupported);
+            }
+            self.flushes += 1;
+            self.queue(request_id, self.flush_status);
+            Ok(())
+        }
+
+        fn in_flight(&self) -> usize {
+            self.pending.iter().filter(|p| p.is_some()).count()
+        }
+    }
+
+    #[test]
+    fn test_barrier_drains_then_flushes() {
+        let mut driver = MockDriver::new(true);
+        driver.submit_write(0, 0, 1, 1).unwrap();
+        driver.submit_write(1, 0, 1, 2).unwrap();
+
+        let mut barrier = WriteBarrier::new(99);
+        assert_eq!(barrier.step(&mut driver), BarrierStatus::Pending);
+        assert_eq!(driver.flushes, 1);
+        assert_eq!(barrier.step(&mut driver), BarrierStatus::Done);
+        assert_eq!(barrier.step(&mut driver), BarrierStatus::Done);
+    }
+
+    #[test]
+    fn test_barrier_without_flush_support() {
+        let mut driver = MockDriver::new(false);
+        driver.submit_write(0, 0, 1, 1).unwrap();
+
+        let mut barrier = WriteBarrier::new(99);
+        assert_eq!(barrier.step(&mut driver), BarrierStatus::Done);
+    }
+
+    #[test]
+    fn test_barrier_reports_failures() {
+        let mut driver = MockDriver::new(true);
+        driver.queue(1, 1);
+        let mut barrier = WriteBarrier::new(99);
+        assert_eq!(
+            barrier.step(&mut driver),
+            BarrierStatus::Failed(BlockError::IoError)
+        );
+        assert_eq!(driver.flushes, 0);
+
+        driver.flush_status = 1;
+        let mut barrier = WriteBarrier::new(99);
+        assert_eq!(barrier.step(&mut driver), BarrierStatus::Pending);
+        assert_eq!(
+            barrier.step(&mut driver),
+            BarrierStatus::Failed(BlockError::DeviceError)
+        );
+    }
+}
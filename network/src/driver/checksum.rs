@@ -0,0 +1,230 @@
+//! Internet checksums of Ethernet frames.
+//!
+//! NICs that offload checksums still need a little help from the driver:
+//! the TCP/UDP checksum field must hold the pseudo-header sum before the
+//! hardware finishes it on transmit, and frames the hardware could not
+//! check on receive are checked here instead. IPv4 only, like the stack.
+//!
+//! # Reference
+//! RFC 1071, RFC 791 §3.1, RFC 793 §3.1, RFC 768
+
+/// Ethernet header length (no VLAN tag; the stack never adds one).
+pub const ETHERNET_HEADER_LEN: usize = 14;
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const IPV4_MIN_HEADER_LEN: usize = 20;
+const PROTO_TCP: u8 = 6;
+const PROTO_UDP: u8 = 17;
+
+/// Transport protocols with a checksum the hardware can insert.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum L4Protocol {
+    Tcp,
+    Udp,
+}
+
+impl L4Protocol {
+    /// Offset of the checksum field in the transport header.
+    pub fn checksum_offset(self) -> usize {
+        match self {
+            Self::Tcp => 16,
+            Self::Udp => 6,
+        }
+    }
+
+    /// Shortest valid transport header.
+    fn min_header_len(self) -> usize {
+        match self {
+            Self::Tcp => 20,
+            Self::Udp => 8,
+        }
+    }
+}
+
+/// Where the checksummed parts of an IPv4 frame are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameLayout {
+    /// IPv4 header length; the header checksum covers exactly this.
+    pub ip_header_len: usize,
+    /// End of the IP packet; frames may carry padding after it.
+    pub ip_end: usize,
+    /// TCP or UDP, unless the packet is something else or a fragment.
+    pub l4: Option<L4Protocol>,
+}
+
+impl FrameLayout {
+    /// Offset of the IPv4 header.
+    pub const IP_START: usize = ETHERNET_HEADER_LEN;
+
+    /// Offset of the header checksum in the IPv4 header.
+    pub const IP_CHECKSUM_OFFSET: usize = 10;
+
+    /// Parse an Ethernet frame; None for anything but well-formed IPv4.
+    pub fn parse(frame: &[u8]) -> Option<Self> {
+        let ip = frame.get(Self::IP_START..)?;
+        if frame.len() < Self::IP_START + IPV4_MIN_HEADER_LEN
+            || u16::from_be_bytes([frame[12], frame[13]]) != ETHERTYPE_IPV4
+            || ip[0] >> 4 != 4
+        {
+            return None;
+        }
+
+        let ip_header_len = (ip[0] & 0x0F) as usize * 4;
+        let total_len = u16::from_be_bytes([ip[2], ip[3]]) as usize;
+        if ip_header_len < IPV4_MIN_HEADER_LEN || total_len < ip_header_len || total_len > ip.len()
+        {
+            return None;
+        }
+
+        // More-fragments flag or a fragment offset: the transport checksum
+        // spans packets, nothing to do per frame
+        let fragment = u16::from_be_bytes([ip[6], ip[7]]) & 0x3FFF != 0;
+        let l4 = match ip[9] {
+            PROTO_TCP => Some(L4Protocol::Tcp),
+            PROTO_UDP => Some(L4Protocol::Udp),
+            _ => None,
+        }
+        .filter(|l4| !fragment && total_len - ip_header_len >= l4.min_header_len());
+
+        Some(Self {
+            ip_header_len,
+            ip_end: Self::IP_START + total_len,
+            l4,
+        })
+    }
+
+    /// Offset of the transport header.
+    pub fn l4_start(&self) -> usize {
+        Self::IP_START + self.ip_header_len
+    }
+
+    /// Offset of the transport checksum field, if there is one.
+    pub fn l4_checksum_field(&self) -> Option<usize> {
+        self.l4.map(|l4| self.l4_start() + l4.checksum_offset())
+    }
+
+    /// TCP/UDP pseudo-header sum: addresses, protocol and transport
+    /// length (RFC 793 §3.1). Not folded or complemented.
+    pub fn pseudo_header_sum(&self, frame: &[u8]) -> u32 {
+        let ip = &frame[Self::IP_START..];
+        sum(&ip[12..20]) + ip[9] as u32 + (self.ip_end - self.l4_start()) as u32
+    }
+}
+
+/// Ones' complement sum of `data` as big-endian 16-bit words, not folded.
+/// An odd last byte is padded with zero.
+pub fn sum(data: &[u8]) -> u32 {
+    let mut words = data.chunks_exact(2);
+    let mut total: u32 = words
+        .by_ref()
+        .map(|w| u16::from_be_bytes([w[0], w[1]]) as u32)
+        .sum();
+    if let [last] = words.remainder() {
+        total += (*last as u32) << 8;
+    }
+    total
+}
+
+/// Fold a sum to 16 bits.
+pub fn fold(mut sum: u32) -> u16 {
+    while sum > 0xFFFF {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+    sum as u16
+}
+
+/// Check the IPv4 header and TCP/UDP checksums of `frame` in software.
+///
+/// Frames that aren't IPv4 pass, as does UDP sent without a checksum.
+pub fn verify(frame: &[u8]) -> bool {
+    let Some(layout) = FrameLayout::parse(frame) else {
+        return true;
+    };
+
+    let header = &frame[FrameLayout::IP_START..layout.l4_start()];
+    if fold(sum(header)) != 0xFFFF {
+        return false;
+    }
+
+    let Some(field) = layout.l4_checksum_field() else {
+        return true;
+    };
+    if layout.l4 == Some(L4Protocol::Udp) && frame[field..field + 2] == [0, 0] {
+        return true;
+    }
+    let segment = &frame[layout.l4_start()..layout.ip_end];
+    fold(layout.pseudo_header_sum(frame) + sum(segment)) == 0xFFFF
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 10.0.0.1:1000 -> 10.0.0.2:2000 UDP "abc" with valid checksums,
+    /// plus two bytes of Ethernet padding.
+    fn udp_frame() -> [u8; 47] {
+        let mut frame = [0u8; 47];
+        frame[12..14].copy_from_slice(&ETHERTYPE_IPV4.to_be_bytes());
+        let ip = [
+            0x45, 0x00, 0x00, 0x1F, 0x12, 0x34, 0x40, 0x00, 0x40, PROTO_UDP, 0x00, 0x00, 10, 0, 0,
+            1, 10, 0, 0, 2,
+        ];
+        frame[14..34].copy_from_slice(&ip);
+        let udp = [
+            0x03, 0xE8, 0x07, 0xD0, 0x00, 0x0B, 0x00, 0x00, b'a', b'b', b'c',
+        ];
+        frame[34..45].copy_from_slice(&udp);
+
+        let ip_checksum = !fold(sum(&frame[14..34]));
+        frame[24..26].copy_from_slice(&ip_checksum.to_be_bytes());
+        let layout = FrameLayout::parse(&frame).unwrap();
+        let l4_checksum = !fold(layout.pseudo_header_sum(&frame) + sum(&frame[34..45]));
+        frame[40..42].copy_from_slice(&l4_checksum.to_be_bytes());
+        frame
+    }
+
+    #[test]
+    fn test_parse() {
+        let frame = udp_frame();
+        let layout = FrameLayout::parse(&frame).unwrap();
+        assert_eq!(layout.ip_header_len, 20);
+        assert_eq!(layout.ip_end, 45);
+        assert_eq!(layout.l4, Some(L4Protocol::Udp));
+        assert_eq!(layout.l4_checksum_field(), Some(40));
+
+        // Fragments keep the IP checksum but lose the transport one
+        let mut fragment = frame;
+        fragment[20] = 0x20;
+        assert_eq!(FrameLayout::parse(&fragment).unwrap().l4, None);
+
+        // ARP, truncated packets
+        let mut arp = frame;
+        arp[12..14].copy_from_slice(&0x0806u16.to_be_bytes());
+        assert_eq!(FrameLayout::parse(&arp), None);
+        assert_eq!(FrameLayout::parse(&frame[..40]), None);
+    }
+
+    #[test]
+    fn test_verify() {
+        let frame = udp_frame();
+        assert!(verify(&frame));
+
+        let mut bad_payload = frame;
+        bad_payload[43] ^= 0x01;
+        assert!(!verify(&bad_payload));
+
+        let mut bad_header = frame;
+        bad_header[22] ^= 0x01;
+        assert!(!verify(&bad_header));
+
+        // UDP without a checksum is allowed over IPv4
+        bad_payload[40..42].copy_from_slice(&[0, 0]);
+        assert!(verify(&bad_payload));
+    }
+
+    #[test]
+    fn test_sum_odd_length() {
+        assert_eq!(sum(&[0x12, 0x34, 0x56]), 0x1234 + 0x5600);
+        assert_eq!(fold(0x1_FFFE), 0xFFFF);
+    }
+}
@@ -5,15 +5,24 @@
 //! # Reference
 //! Intel 82579 Datasheet, NETWORK_IMPL_GUIDE.md §8
 
-use crate::driver::traits::{DriverInit, NetworkDriver, RxError, TxError};
+use crate::driver::mac_filter::MacFilter;
+use crate::driver::traits::{
+    ChecksumOffload, DriverInit, FilterError, LinkInfo, LoopbackMode, NetworkDriver, ResetState,
+    RingState, RxError, TxError,
+};
 use crate::mainloop::serial::serial_println;
 use crate::types::MacAddress;
+use crate::asm::core::mmio::{read32, write32};
 use crate::asm::drivers::intel::{asm_intel_link_status, LinkStatusResult};
 
+use crate::pci::config::{offset, pci_cfg_read16};
+
+use super::filter::{self, FilterLayout};
 use super::init::{init_e1000e, E1000eConfig, E1000eInitError};
 use super::phy::PhyManager;
 use super::rx::RxRing;
 use super::tx::TxRing;
+use super::regs;
 use super::{E1000E_DEVICE_IDS, INTEL_VENDOR_ID};
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -55,6 +64,10 @@ pub struct E1000eDriver {
     rx_ring: RxRing,
     /// TX descriptor ring.
     tx_ring: TxRing,
+    /// Filter registers (None = unknown part, no filtering).
+    filter_layout: Option<FilterLayout>,
+    /// Extra addresses programmed into the filters.
+    filters: MacFilter,
     /// Device is initialized.
     initialized: bool,
 }
@@ -71,6 +84,9 @@ impl E1000eDriver {
     /// - DMA region must be properly allocated and mapped
     pub unsafe fn new(mmio_base: u64, config: E1000eConfig) -> Result<Self, E1000eError> {
         serial_println("    [e1000e] E1000eDriver::new() entered");
+        if cfg!(not(target_arch = "x86_64")) {
+            return Err(E1000eError::NotReady);
+        }
         serial_println("    [e1000e] About to call init_e1000e()...");
         
         // Initialize device
@@ -81,12 +97,20 @@ impl E1000eDriver {
         // Create PHY manager
         let phy = PhyManager::new(mmio_base, config.tsc_freq);
 
+        // Init cleared the multicast table; the extra RARs start unused
+        let filter_layout = config
+            .pci_addr
+            .and_then(|addr| filter::filter_layout(pci_cfg_read16(addr, offset::DEVICE_ID)));
+        let unicast_slots = filter_layout.map_or(0, |layout| layout.extra_addresses);
+
         Ok(Self {
             mmio_base,
             mac: result.mac,
             phy,
             rx_ring: result.rx_ring,
             tx_ring: result.tx_ring,
+            filter_layout,
+            filters: MacFilter::new(result.mac, unicast_slots),
             initialized: true,
         })
     }
@@ -116,6 +140,50 @@ impl E1000eDriver {
     pub fn wait_for_link(&mut self, timeout_us: u64) -> bool {
         self.phy.wait_for_link(timeout_us).is_ok()
     }
+
+    /// Filter registers, if they can be programmed now.
+    fn filter_layout(&self) -> Result<FilterLayout, FilterError> {
+        match self.filter_layout {
+            _ if !self.initialized => Err(FilterError::DeviceError),
+            Some(layout) => Ok(layout),
+            None => Err(FilterError::Unsupported),
+        }
+    }
+
+    /// Apply `change` to the unicast list and reprogram the receive
+    /// address registers it touched.
+    fn update_unicast(
+        &mut self,
+        change: impl FnOnce(&mut MacFilter) -> Result<bool, FilterError>,
+    ) -> Result<(), FilterError> {
+        let layout = self.filter_layout()?;
+        let previous = self.filters;
+        if !change(&mut self.filters)? {
+            return Ok(());
+        }
+        let slots = previous.unicast().len().max(self.filters.unicast().len());
+        unsafe {
+            if filter::write_unicast(self.mmio_base, &layout, self.filters.unicast(), slots) {
+                return Ok(());
+            }
+            // Locked by firmware: put back what was there
+            self.filters = previous;
+            filter::write_unicast(self.mmio_base, &layout, self.filters.unicast(), slots);
+        }
+        Err(FilterError::DeviceError)
+    }
+
+    /// Apply `change` to the multicast list and rebuild the table.
+    fn update_multicast(
+        &mut self,
+        change: impl FnOnce(&mut MacFilter) -> Result<bool, FilterError>,
+    ) -> Result<(), FilterError> {
+        let layout = self.filter_layout()?;
+        if change(&mut self.filters)? {
+            unsafe { filter::write_multicast(self.mmio_base, &layout, self.filters.multicast()) };
+        }
+        Ok(())
+    }
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -138,6 +206,12 @@ impl NetworkDriver for E1000eDriver {
         self.initialized && self.rx_ring.can_receive()
     }
 
+    /// Descriptors between our clean pointer and the hardware head.
+    fn rx_backlog(&self) -> Option<(usize, usize)> {
+        self.initialized
+            .then(|| (self.rx_ring.backlog(), self.rx_ring.queue_size() as usize))
+    }
+
     /// Transmit an Ethernet frame (fire-and-forget).
     ///
     /// Returns immediately after queuing the frame.
@@ -192,6 +266,126 @@ impl NetworkDriver for E1000eDriver {
         }
         result.link_up != 0
     }
+
+    fn link_info(&self) -> Option<LinkInfo> {
+        let mut result = LinkStatusResult::default();
+        unsafe {
+            asm_intel_link_status(self.mmio_base, &mut result);
+        }
+        let speed_mbps = result.speed_mbps();
+        (result.is_link_up() && speed_mbps != 0).then_some(LinkInfo {
+            speed_mbps,
+            full_duplex: result.is_full_duplex(),
+        })
+    }
+
+    fn autoneg_complete(&self) -> Option<bool> {
+        self.phy
+            .read_bmsr()
+            .map(|bmsr| bmsr & regs::BMSR_ANEGCOMPLETE != 0)
+    }
+
+    fn quiesce(&mut self) {
+        if !self.initialized {
+            return;
+        }
+        // Clear the RX/TX enable bits; the flush read posts the writes.
+        unsafe {
+            let rctl = read32(self.mmio_base + regs::RCTL as u64);
+            write32(self.mmio_base + regs::RCTL as u64, rctl & !regs::RCTL_EN);
+            let tctl = read32(self.mmio_base + regs::TCTL as u64);
+            write32(self.mmio_base + regs::TCTL as u64, tctl & !regs::TCTL_EN);
+            let _ = read32(self.mmio_base + regs::STATUS as u64);
+        }
+        self.initialized = false;
+    }
+
+    fn set_loopback(&mut self, mode: Option<LoopbackMode>) -> bool {
+        if !self.initialized {
+            return false;
+        }
+        let ctrl_reg = self.mmio_base + regs::CTRL as u64;
+        let rctl_reg = self.mmio_base + regs::RCTL as u64;
+        // Loopback runs at forced 1000/full with the link forced up
+        let forced = regs::CTRL_SLU | regs::CTRL_FRCSPD | regs::CTRL_FRCDPLX | regs::CTRL_FD;
+        let phy_loopback = regs::BMCR_LOOPBACK | regs::BMCR_SPEED1000 | regs::BMCR_FULLDPLX;
+
+        unsafe {
+            let ctrl = read32(ctrl_reg) & !(regs::CTRL_SPEED_MASK | regs::CTRL_ASDE);
+            let rctl = read32(rctl_reg) & !regs::RCTL_LBM_MASK;
+            match mode {
+                Some(LoopbackMode::Mac) => {
+                    write32(ctrl_reg, ctrl | forced | regs::CTRL_SPEED_1000);
+                    write32(rctl_reg, rctl | regs::RCTL_LBM_MAC);
+                }
+                Some(LoopbackMode::Phy) => {
+                    if self.phy.write_reg(regs::PHY_BMCR, phy_loopback).is_err() {
+                        return false;
+                    }
+                    write32(ctrl_reg, ctrl | forced | regs::CTRL_SPEED_1000);
+                    write32(rctl_reg, rctl);
+                }
+                None => {
+                    write32(rctl_reg, rctl);
+                    write32(ctrl_reg, (ctrl & !forced) | regs::CTRL_SLU | regs::CTRL_ASDE);
+                    let _ = self.phy.restart_autoneg();
+                }
+            }
+            let _ = read32(self.mmio_base + regs::STATUS as u64);
+        }
+        true
+    }
+
+    /// IMS, RCTL.LBM and BMCR loopback, and the ring registers.
+    fn reset_state(&self) -> Option<ResetState> {
+        if !self.initialized {
+            return None;
+        }
+        let reg = |offset: u32| unsafe { read32(self.mmio_base + offset as u64) };
+        // RX and TX descriptors are both 16 bytes; the device owns
+        // head..tail
+        let ring = |size: u16, len: u32, head: u32, tail: u32| RingState {
+            size,
+            device_size: Some((reg(len) / 16) as u16),
+            posted: ((reg(tail) + size as u32 - reg(head)) % (size as u32).max(1)) as u16,
+        };
+        let phy_loopback = self
+            .phy
+            .read_reg(regs::PHY_BMCR)
+            .is_some_and(|bmcr| bmcr & regs::BMCR_LOOPBACK != 0);
+
+        Some(ResetState {
+            interrupts_masked: Some(reg(regs::IMS) == 0),
+            loopback: reg(regs::RCTL) & regs::RCTL_LBM_MASK != 0 || phy_loopback,
+            rx: ring(self.rx_ring.queue_size(), regs::RDLEN, regs::RDH, regs::RDT),
+            tx: ring(self.tx_ring.queue_size(), regs::TDLEN, regs::TDH, regs::TDT),
+        })
+    }
+
+    /// Set up in init Phase 9; both directions or neither.
+    fn checksum_offload(&self) -> ChecksumOffload {
+        if self.tx_ring.checksum_offload() {
+            ChecksumOffload::FULL
+        } else {
+            ChecksumOffload::NONE
+        }
+    }
+
+    fn add_unicast_filter(&mut self, mac: MacAddress) -> Result<(), FilterError> {
+        self.update_unicast(|filters| filters.add_unicast(mac))
+    }
+
+    fn remove_unicast_filter(&mut self, mac: MacAddress) -> Result<(), FilterError> {
+        self.update_unicast(|filters| Ok(filters.remove_unicast(&mac)))
+    }
+
+    fn join_multicast(&mut self, mac: MacAddress) -> Result<(), FilterError> {
+        self.update_multicast(|filters| filters.join(mac))
+    }
+
+    fn leave_multicast(&mut self, mac: MacAddress) -> Result<(), FilterError> {
+        self.update_multicast(|filters| Ok(filters.leave(&mac)))
+    }
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -0,0 +1,176 @@
+//! Receive address and multicast filters.
+//!
+//! Extra unicast addresses go into receive address registers after RAR[0]
+//! (our own). On 82579 and later PCH parts those are the shared SHRA
+//! registers, which the management engine may have locked; writes are
+//! read back to find out. Multicast groups set one bit each in the
+//! Multicast Table Array, a 4096-bit (1024 on ICH/PCH) hash of the top
+//! address bits, so unrelated groups can slip through and leaving a group
+//! means rebuilding the table from the ones still joined.
+//!
+//! # Reference
+//! Linux kernel drivers/net/ethernet/intel/e1000e/mac.c
+//! (e1000_hash_mc_addr_generic), ich8lan.c (e1000_rar_set_pch2lan)
+
+use crate::asm::core::mmio::{read32, write32};
+use crate::types::MacAddress;
+
+use super::regs;
+
+/// Filter registers of one part family.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FilterLayout {
+    /// Receive address registers beyond RAR[0].
+    pub extra_addresses: usize,
+    /// Register offset of the first of those.
+    pub extra_base: u32,
+    /// Multicast table size in registers.
+    pub mta_registers: usize,
+}
+
+/// 82577/82578 (PCH).
+const PCH: &[u16] = &[0x10EA, 0x10EB, 0x10EF, 0x10F0];
+
+/// 82579 (PCH2).
+const PCH2: &[u16] = &[0x1502, 0x1503];
+
+/// I210/I211.
+const I210_I211: &[u16] = &[0x1533, 0x1539];
+
+/// 82574L.
+const I82574: u16 = 0x10D3;
+
+/// Filter registers of `device_id`; None for parts we don't know, which
+/// keep receiving only their own address and broadcast.
+pub fn filter_layout(device_id: u16) -> Option<FilterLayout> {
+    let generic = |rar_entries: usize, mta_registers| FilterLayout {
+        extra_addresses: rar_entries - 1,
+        extra_base: regs::RAL0 + regs::RAR_STRIDE,
+        mta_registers,
+    };
+    if device_id == I82574 {
+        Some(generic(15, 128))
+    } else if I210_I211.contains(&device_id) {
+        Some(generic(16, 128))
+    } else if PCH.contains(&device_id) {
+        Some(generic(7, 32))
+    } else if PCH2.contains(&device_id) {
+        Some(FilterLayout {
+            extra_addresses: 4,
+            extra_base: regs::SHRAL0,
+            mta_registers: 32,
+        })
+    } else if super::E1000E_DEVICE_IDS.contains(&device_id) {
+        // I217/I218/I219 (LPT and later): 11 shared registers
+        Some(FilterLayout {
+            extra_addresses: 11,
+            extra_base: regs::SHRAL0,
+            mta_registers: 32,
+        })
+    } else {
+        None
+    }
+}
+
+/// RAL/RAH values for `mac`, marked valid.
+fn receive_address(mac: &MacAddress) -> (u32, u32) {
+    let low = u32::from_le_bytes([mac[0], mac[1], mac[2], mac[3]]);
+    let high = u16::from_le_bytes([mac[4], mac[5]]) as u32 | regs::RAH_AV;
+    (low, high)
+}
+
+/// MTA register index and bit for multicast `mac` (RCTL.MO = 0: the
+/// hash takes the top address bits that fit the table).
+pub fn mta_bit(mac: &MacAddress, mta_registers: usize) -> (usize, u32) {
+    let hash_mask = (mta_registers * 32 - 1) as u32;
+    let mut shift = 0;
+    while hash_mask >> shift != 0xFF {
+        shift += 1;
+    }
+    let hash = hash_mask & ((mac[4] >> (8 - shift)) as u32 | (mac[5] as u32) << shift);
+    ((hash >> 5) as usize, 1 << (hash & 0x1F))
+}
+
+/// Program `addresses` into the first `slots` extra receive address
+/// registers, clearing the rest of those. Registers past `slots` are left
+/// alone; firmware may hold them.
+///
+/// Returns false if the device didn't take a write (locked by firmware).
+///
+/// # Safety
+/// `mmio_base` must be the NIC's mapped register space.
+pub unsafe fn write_unicast(
+    mmio_base: u64,
+    layout: &FilterLayout,
+    addresses: &[MacAddress],
+    slots: usize,
+) -> bool {
+    let mut ok = true;
+    for i in 0..slots.min(layout.extra_addresses) {
+        let ral = mmio_base + (layout.extra_base + i as u32 * regs::RAR_STRIDE) as u64;
+        let rah = ral + 4;
+        let (low, high) = addresses.get(i).map_or((0, 0), receive_address);
+        // Invalidate before changing the low half
+        write32(rah, 0);
+        write32(ral, low);
+        write32(rah, high);
+        ok &= read32(ral) == low && read32(rah) == high;
+    }
+    let _ = read32(mmio_base + regs::STATUS as u64); // flush
+    ok
+}
+
+/// Rebuild the multicast table from `groups`.
+///
+/// # Safety
+/// `mmio_base` must be the NIC's mapped register space.
+pub unsafe fn write_multicast(mmio_base: u64, layout: &FilterLayout, groups: &[MacAddress]) {
+    let mut table = [0u32; 128];
+    for group in groups {
+        let (index, bit) = mta_bit(group, layout.mta_registers);
+        table[index] |= bit;
+    }
+    for (i, value) in table.iter().take(layout.mta_registers).enumerate() {
+        write32(mmio_base + (regs::MTA + i as u32 * 4) as u64, *value);
+    }
+    let _ = read32(mmio_base + regs::STATUS as u64); // flush
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mta_bit() {
+        // 01:00:5E:00:00:FB (mDNS): 128 registers hash 0xFB0, 32 hash 0x3EC
+        let mdns = [0x01, 0x00, 0x5E, 0x00, 0x00, 0xFB];
+        assert_eq!(mta_bit(&mdns, 128), (0x7D, 1 << 0x10));
+        assert_eq!(mta_bit(&mdns, 32), (0x1F, 1 << 0x0C));
+
+        // Only the top bits count
+        let other = [0x01, 0x00, 0x5E, 0x7F, 0x0F, 0xFB];
+        assert_eq!(mta_bit(&other, 128), mta_bit(&mdns, 128));
+        assert_ne!(
+            mta_bit(&[0x33, 0x33, 0, 0, 0x10, 0xFB], 128),
+            mta_bit(&mdns, 128)
+        );
+    }
+
+    #[test]
+    fn test_filter_layout() {
+        assert_eq!(filter_layout(0x10D3).unwrap().mta_registers, 128);
+        let pch2 = filter_layout(0x1502).unwrap();
+        assert_eq!(pch2.extra_base, regs::SHRAL0);
+        assert_eq!(pch2.extra_addresses, 4);
+        assert_eq!(filter_layout(0x15BB).unwrap().extra_addresses, 11);
+        assert_eq!(filter_layout(0x10EA).unwrap().extra_base, 0x5408);
+        assert_eq!(filter_layout(0x100E), None);
+    }
+
+    #[test]
+    fn test_receive_address() {
+        let (low, high) = receive_address(&[0x52, 0x54, 0x00, 0x12, 0x34, 0x56]);
+        assert_eq!(low, 0x1200_5452);
+        assert_eq!(high, 0x8000_5634);
+    }
+}
@@ -9,7 +9,7 @@
 //! - Phase 4: Device reset (MANDATORY, FAIL on timeout)
 //! - Phase 5: Wait for EEPROM auto-read done
 //! - Phase 6: Post-reset cleanup (interrupts, descriptors, RAR, loopback, MTA)
-//! - Phase 7: I218/PCH workarounds (ULP, PHY access, PHY wake)
+//! - Phase 7: I218/PCH workarounds (ULP, PHY access, PHY wake, EEE/ASPM)
 //! - Phase 8: Read/validate MAC from EEPROM
 //! - Phase 9: Program descriptor rings
 //! - Phase 10: Re-enable bus mastering, enable RX/TX, link up
@@ -36,9 +36,13 @@ use crate::asm::drivers::intel::{
     disable_ulp, toggle_lanphypc, phy_is_accessible, acquire_swflag, release_swflag,
 };
 use crate::dma::DmaRegion;
+use crate::pci::config::PciAddr;
 use crate::mainloop::serial::{serial_print, serial_println, serial_print_decimal};
 use crate::types::MacAddress;
+use crate::time::{self, Deadline};
 
+use super::phy::LinkMode;
+use super::quirks::{self, LowPowerPolicy};
 use super::regs;
 use super::rx::RxRing;
 use super::tx::TxRing;
@@ -62,6 +66,12 @@ pub struct E1000eConfig {
     pub dma_cpu_base: *mut u8,
     /// DMA region bus address.
     pub dma_bus_base: u64,
+    /// PCI address, for the device ID and ASPM (None = unknown).
+    pub pci_addr: Option<PciAddr>,
+    /// EEE/ASPM handling.
+    pub low_power: LowPowerPolicy,
+    /// Speeds advertised to the link partner.
+    pub link_mode: LinkMode,
 }
 
 impl E1000eConfig {
@@ -77,8 +87,23 @@ impl E1000eConfig {
             tsc_freq,
             dma_cpu_base,
             dma_bus_base,
+            pci_addr: None,
+            low_power: LowPowerPolicy::Auto,
+            link_mode: LinkMode::Auto,
         }
     }
+
+    /// Set the PCI address, enabling device-ID quirks and ASPM control.
+    pub fn with_pci_addr(mut self, pci_addr: PciAddr) -> Self {
+        self.pci_addr = Some(pci_addr);
+        self
+    }
+
+    /// Set the speeds to advertise.
+    pub fn with_link_mode(mut self, link_mode: LinkMode) -> Self {
+        self.link_mode = link_mode;
+        self
+    }
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -174,8 +199,8 @@ pub unsafe fn init_e1000e(
     
     // Wait for RX/TX to actually stop (poll RXDCTL/TXDCTL if queue was enabled)
     // Timeout after 10ms
-    let quiesce_timeout = config.tsc_freq / 100; // 10ms
-    let quiesce_start = crate::asm::core::tsc::read_tsc();
+    let clock = time::active_or_tsc(config.tsc_freq);
+    let quiesce = Deadline::after_ms(clock, 10);
     loop {
         let rxdctl = read32(mmio_base + regs::RXDCTL as u64);
         let txdctl = read32(mmio_base + regs::TXDCTL as u64);
@@ -183,7 +208,7 @@ pub unsafe fn init_e1000e(
         if (rxdctl & regs::XDCTL_QUEUE_ENABLE == 0) && (txdctl & regs::XDCTL_QUEUE_ENABLE == 0) {
             break;
         }
-        if crate::asm::core::tsc::read_tsc().wrapping_sub(quiesce_start) > quiesce_timeout {
+        if quiesce.expired() {
             serial_println("  [e1000e] WARN: RX/TX quiesce timeout (continuing)");
             break;
         }
@@ -201,14 +226,13 @@ pub unsafe fn init_e1000e(
     let _ = read32(mmio_base + regs::STATUS as u64); // flush
     
     // Wait for GIO Master to disable (poll STATUS.GIO_MASTER_EN)
-    let gio_timeout = config.tsc_freq / 100; // 10ms  
-    let gio_start = crate::asm::core::tsc::read_tsc();
+    let gio = Deadline::after_ms(clock, 10);
     loop {
         let status = read32(mmio_base + regs::STATUS as u64);
         if status & regs::STATUS_GIO_MASTER_EN == 0 {
             break;
         }
-        if crate::asm::core::tsc::read_tsc().wrapping_sub(gio_start) > gio_timeout {
+        if gio.expired() {
             serial_println("  [e1000e] WARN: GIO master disable timeout");
             break;
         }
@@ -233,14 +257,13 @@ pub unsafe fn init_e1000e(
     // PHASE 5: WAIT FOR EEPROM AUTO-READ COMPLETE
     // After reset, hardware loads config from EEPROM. Must wait.
     // ═══════════════════════════════════════════════════════════════════
-    let eecd_timeout = config.tsc_freq / 2; // 500ms (generous)
-    let eecd_start = crate::asm::core::tsc::read_tsc();
+    let eecd_deadline = Deadline::after_ms(clock, 500); // generous
     loop {
         let eecd = read32(mmio_base + regs::EECD as u64);
         if eecd & regs::EECD_AUTO_RD != 0 {
             break;
         }
-        if crate::asm::core::tsc::read_tsc().wrapping_sub(eecd_start) > eecd_timeout {
+        if eecd_deadline.expired() {
             serial_println("  [e1000e] WARN: EEPROM auto-read timeout");
             break;
         }
@@ -301,6 +324,9 @@ pub unsafe fn init_e1000e(
     
     wake_phy(mmio_base, config.tsc_freq);
 
+    // EEE/ASPM off before Phase 10 restarts auto-negotiation
+    quirks::apply(mmio_base, config.pci_addr, config.low_power, config.tsc_freq);
+
     // ═══════════════════════════════════════════════════════════════════
     // PHASE 8: READ/VALIDATE MAC
     // ═══════════════════════════════════════════════════════════════════
@@ -378,6 +404,16 @@ pub unsafe fn init_e1000e(
 
     // Initialize all TX descriptors
     tx_ring.init_descriptors();
+
+    // Checksum offload: the MAC checks IPv4/TCP/UDP checksums on receive
+    // and inserts them on transmit
+    write32(
+        mmio_base + regs::RXCSUM as u64,
+        regs::RXCSUM_IPOFL | regs::RXCSUM_TUOFL,
+    );
+    rx_ring.set_checksum_offload(true);
+    tx_ring.set_checksum_offload(true);
+    serial_println("  [e1000e] Checksum offload: IPv4/TCP/UDP RX+TX");
     
     let _ = read32(mmio_base + regs::STATUS as u64); // flush after ring setup
 
@@ -406,6 +442,7 @@ pub unsafe fn init_e1000e(
 
     // Set link up and restart auto-negotiation
     asm_intel_set_link_up(mmio_base);
+    advertise(mmio_base, config.link_mode, config.tsc_freq);
     
     if let Some(bmcr) = phy_read(mmio_base, regs::PHY_BMCR, config.tsc_freq) {
         let new_bmcr = bmcr | regs::BMCR_ANENABLE | regs::BMCR_ANRESTART;
@@ -413,11 +450,7 @@ pub unsafe fn init_e1000e(
     }
     
     // Brief delay for PHY to start negotiation (100ms)
-    let delay_start = crate::asm::core::tsc::read_tsc();
-    let delay_ticks = config.tsc_freq / 10;
-    while crate::asm::core::tsc::read_tsc().wrapping_sub(delay_start) < delay_ticks {
-        core::hint::spin_loop();
-    }
+    time::delay_ms(time::active_or_tsc(config.tsc_freq), 100);
 
     // NOTE: Interrupts remain MASKED (IMS = 0).
     // We do polled I/O - no interrupt handler needed.
@@ -432,6 +465,24 @@ pub unsafe fn init_e1000e(
     })
 }
 
+/// Advertise the speeds of `mode`; takes effect when auto-negotiation
+/// restarts. The PHY keeps its defaults if it can't be read.
+fn advertise(mmio_base: u64, mode: LinkMode, tsc_freq: u64) {
+    let (Some(anar), Some(gctrl)) = (
+        phy_read(mmio_base, regs::PHY_ANAR, tsc_freq),
+        phy_read(mmio_base, regs::PHY_1000T_CTRL, tsc_freq),
+    ) else {
+        return;
+    };
+    let (new_anar, new_gctrl) = mode.advertisement(anar, gctrl);
+    let _ = phy_write(mmio_base, regs::PHY_ANAR, new_anar, tsc_freq);
+    let _ = phy_write(mmio_base, regs::PHY_1000T_CTRL, new_gctrl, tsc_freq);
+    if mode != LinkMode::Auto {
+        serial_print("  [e1000e] Link mode: ");
+        serial_println(mode.name());
+    }
+}
+
 /// Generate a locally-administered MAC address from a seed.
 ///
 /// Used as fallback if EEPROM MAC is invalid.
@@ -496,11 +547,7 @@ unsafe fn ensure_phy_accessible(mmio_base: u64, tsc_freq: u64) -> bool {
                 // First attempt: just wait a bit longer after ULP disable
                 // Some I218 variants need extra time
                 serial_println("    Recovery: waiting 50ms...");
-                let start = crate::asm::core::tsc::read_tsc();
-                let delay = tsc_freq / 20; // 50ms
-                while crate::asm::core::tsc::read_tsc().wrapping_sub(start) < delay {
-                    core::hint::spin_loop();
-                }
+                time::delay_ms(time::active_or_tsc(tsc_freq), 50);
             }
             1 => {
                 // Second attempt: toggle LANPHYPC to power cycle PHY
@@ -573,11 +620,8 @@ unsafe fn wake_phy(mmio_base: u64, tsc_freq: u64) {
     // for PLL lock and analog circuitry stabilization. QEMU doesn't
     // need this, but real hardware absolutely does.
     // ═══════════════════════════════════════════════════════════════════
-    let start = crate::asm::core::tsc::read_tsc();
-    let delay_ticks = tsc_freq / 10; // 100ms (not 1ms!)
-    while crate::asm::core::tsc::read_tsc().wrapping_sub(start) < delay_ticks {
-        core::hint::spin_loop();
-    }
+    let clock = time::active_or_tsc(tsc_freq);
+    time::delay_ms(clock, 100); // 100ms (not 1ms!)
 
     // ═══════════════════════════════════════════════════════════════════
     // STEP 3: Issue PHY reset (BMCR.RESET)
@@ -593,8 +637,7 @@ unsafe fn wake_phy(mmio_base: u64, tsc_freq: u64) {
     // The PHY clears the RESET bit when reset is complete.
     // Timeout after 500ms (generous for real hardware).
     // ═══════════════════════════════════════════════════════════════════
-    let reset_start = crate::asm::core::tsc::read_tsc();
-    let reset_timeout = tsc_freq / 2; // 500ms timeout
+    let reset = Deadline::after_ms(clock, 500);
     loop {
         if let Some(bmcr) = phy_read(mmio_base, regs::PHY_BMCR, tsc_freq) {
             if bmcr & regs::BMCR_RESET == 0 {
@@ -602,7 +645,7 @@ unsafe fn wake_phy(mmio_base: u64, tsc_freq: u64) {
                 break;
             }
         }
-        if crate::asm::core::tsc::read_tsc().wrapping_sub(reset_start) >= reset_timeout {
+        if reset.expired() {
             // Timeout - continue anyway, some PHYs may not clear the bit
             break;
         }
@@ -610,11 +653,7 @@ unsafe fn wake_phy(mmio_base: u64, tsc_freq: u64) {
     }
 
     // Small delay after reset before continuing (10ms)
-    let post_reset_start = crate::asm::core::tsc::read_tsc();
-    let post_reset_delay = tsc_freq / 100; // 10ms
-    while crate::asm::core::tsc::read_tsc().wrapping_sub(post_reset_start) < post_reset_delay {
-        core::hint::spin_loop();
-    }
+    time::delay_ms(clock, 10);
 
     // ═══════════════════════════════════════════════════════════════════
     // STEP 5: Restart auto-negotiation
@@ -628,9 +667,5 @@ unsafe fn wake_phy(mmio_base: u64, tsc_freq: u64) {
     }
 
     // Small delay after starting autoneg (10ms)
-    let autoneg_start = crate::asm::core::tsc::read_tsc();
-    let autoneg_delay = tsc_freq / 100; // 10ms
-    while crate::asm::core::tsc::read_tsc().wrapping_sub(autoneg_start) < autoneg_delay {
-        core::hint::spin_loop();
-    }
+    time::delay_ms(clock, 10);
 }
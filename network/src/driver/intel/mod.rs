@@ -12,8 +12,10 @@
 //! Intel 82579 Datasheet, Section 10 (Programming Interface)
 
 pub mod e1000e;
+pub mod filter;
 pub mod init;
 pub mod phy;
+pub mod quirks;
 pub mod regs;
 pub mod rx;
 pub mod tx;
@@ -21,6 +23,8 @@ pub mod tx;
 // Re-exports
 pub use e1000e::{E1000eDriver, E1000eError};
 pub use init::{E1000eConfig, E1000eInitError};
+pub use phy::LinkMode;
+pub use quirks::LowPowerPolicy;
 
 /// Intel PCI Vendor ID.
 pub const INTEL_VENDOR_ID: u16 = 0x8086;
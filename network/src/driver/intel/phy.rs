@@ -80,6 +80,48 @@ impl From<LinkStatusResult> for LinkStatus {
     }
 }
 
+// ═══════════════════════════════════════════════════════════════════════════
+// LINK MODE
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Speeds the PHY negotiates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LinkMode {
+    /// Advertise 10/100 and 1000 full duplex.
+    #[default]
+    Auto,
+    /// Advertise only 100 Mbps full duplex, for switch ports where 1G
+    /// never settles. Auto-negotiation stays on: forcing the speed with
+    /// it off would leave an auto-negotiating partner at half duplex.
+    Force100Full,
+}
+
+impl LinkMode {
+    /// Display name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            LinkMode::Auto => "auto",
+            LinkMode::Force100Full => "100 Mbps full duplex",
+        }
+    }
+
+    /// ANAR and 1000BASE-T control values advertising this mode, from
+    /// the current ones. Pause and selector bits are kept.
+    pub fn advertisement(&self, anar: u16, gctrl: u16) -> (u16, u16) {
+        let gigabit = regs::GCTRL_1000HALF | regs::GCTRL_1000FULL;
+        match self {
+            LinkMode::Auto => (
+                anar | regs::ANAR_SPEED_MASK,
+                (gctrl & !gigabit) | regs::GCTRL_1000FULL,
+            ),
+            LinkMode::Force100Full => (
+                (anar & !regs::ANAR_SPEED_MASK) | regs::ANAR_100FULL,
+                gctrl & !gigabit,
+            ),
+        }
+    }
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // PHY MANAGER
 // ═══════════════════════════════════════════════════════════════════════════
@@ -232,3 +274,25 @@ impl PhyManager {
 
 // Safety: PhyManager only contains raw values, no references
 unsafe impl Send for PhyManager {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_link_mode_advertisement() {
+        // Selector 0x01 and symmetric pause survive
+        let anar = 0x0001 | (1 << 10) | regs::ANAR_SPEED_MASK;
+        let gctrl = regs::GCTRL_1000FULL;
+
+        let (anar_100, gctrl_100) = LinkMode::Force100Full.advertisement(anar, gctrl);
+        assert_eq!(anar_100, 0x0001 | (1 << 10) | regs::ANAR_100FULL);
+        assert_eq!(gctrl_100, 0);
+
+        // Auto undoes it
+        assert_eq!(
+            LinkMode::Auto.advertisement(anar_100, gctrl_100),
+            (anar, gctrl)
+        );
+    }
+}
@@ -0,0 +1,237 @@
+//! Power-saving quirks: Energy Efficient Ethernet and PCIe ASPM.
+//!
+//! EEE lets the PHY drop into Low Power Idle between frames; ASPM parks
+//! the NIC's PCIe link in L0s/L1 when it looks idle. Firmware usually
+//! leaves both enabled. Under an OS the driver's interrupt path hides the
+//! wake-up latency, but in our polled loop after ExitBootServices some
+//! PCH parts stall RX for milliseconds at a time or drop bursts entirely.
+//!
+//! Init switches them off for the parts known to misbehave
+//! ([`LowPowerPolicy::Auto`]), for every part ([`LowPowerPolicy::Disable`])
+//! or not at all ([`LowPowerPolicy::Keep`]).
+//!
+//! # Reference
+//! Linux kernel drivers/net/ethernet/intel/e1000e/ich8lan.c (e1000_set_eee_pchlan),
+//! drivers/net/ethernet/intel/e1000e/netdev.c (FLAG2_DISABLE_ASPM_*)
+
+use crate::asm::core::mmio::{read32, write32};
+use crate::asm::drivers::intel::{phy_read, phy_write};
+use crate::mainloop::serial::serial_println;
+use crate::pci::capability::{disable_aspm, PCI_EXP_LNKCTL_ASPM_L0S, PCI_EXP_LNKCTL_ASPM_L1};
+use crate::pci::config::{offset, pci_cfg_read16, PciAddr};
+
+use super::regs;
+
+/// What init does about EEE and ASPM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LowPowerPolicy {
+    /// Disable what the device ID is known to need (default)
+    #[default]
+    Auto,
+    /// Disable EEE and ASPM L0s/L1 on any device
+    Disable,
+    /// Leave the firmware's settings alone
+    Keep,
+}
+
+/// How a part's EEE advertisement is switched off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EeeControl {
+    /// No EEE, or no known way to turn it off
+    None,
+    /// PCH PHY: clear the advertisement at this EMI address
+    PhyEmi(u16),
+    /// I210/I211: clear EEER and IPCNFG in the MAC
+    MacEeer,
+}
+
+/// Power-saving features to turn off during init.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PowerQuirks {
+    pub eee: EeeControl,
+    /// ASPM states to disable (`PCI_EXP_LNKCTL_ASPM_*`)
+    pub aspm: u16,
+}
+
+impl PowerQuirks {
+    /// Nothing to do.
+    pub const NONE: Self = Self {
+        eee: EeeControl::None,
+        aspm: 0,
+    };
+}
+
+/// I217/I218 (Lynx Point): EEE wake-ups stall RX.
+const I217_I218: &[u16] = &[
+    0x153A, 0x153B, 0x1559, 0x155A, 0x15A0, 0x15A1, 0x15A2, 0x15A3,
+];
+
+/// I219 (Sunrise Point and later): EEE and ASPM L1 both stall RX.
+const I219: &[u16] = &[
+    0x156F, 0x1570, 0x15B7, 0x15B8, 0x15B9, 0x15BB, 0x15BC, 0x15BD, 0x15BE, 0x15D6, 0x15D7, 0x15D8,
+    0x15DF, 0x15E1, 0x15E2, 0x15E3, 0x0D4C, 0x0D4D, 0x0D4E, 0x0D4F,
+];
+
+/// 82579 PHY layout.
+const I82579: &[u16] = &[0x1502, 0x1503];
+
+/// 82574L: ASPM L0s and L1 both unreliable (errata).
+const I82574: u16 = 0x10D3;
+
+/// I210/I211: MAC-side EEE control.
+const I210_I211: &[u16] = &[0x1533, 0x1539];
+
+/// How `device_id` turns EEE off.
+pub fn eee_control(device_id: u16) -> EeeControl {
+    if I217_I218.contains(&device_id) || I219.contains(&device_id) {
+        EeeControl::PhyEmi(regs::I217_EEE_ADVERTISEMENT)
+    } else if I82579.contains(&device_id) {
+        EeeControl::PhyEmi(regs::I82579_EEE_ADVERTISEMENT)
+    } else if I210_I211.contains(&device_id) {
+        EeeControl::MacEeer
+    } else {
+        EeeControl::None
+    }
+}
+
+/// What the device is known to need.
+pub fn known_quirks(device_id: u16) -> PowerQuirks {
+    if I219.contains(&device_id) {
+        PowerQuirks {
+            eee: eee_control(device_id),
+            aspm: PCI_EXP_LNKCTL_ASPM_L1,
+        }
+    } else if I217_I218.contains(&device_id) {
+        PowerQuirks {
+            eee: eee_control(device_id),
+            aspm: 0,
+        }
+    } else if device_id == I82574 {
+        PowerQuirks {
+            eee: EeeControl::None,
+            aspm: PCI_EXP_LNKCTL_ASPM_L0S | PCI_EXP_LNKCTL_ASPM_L1,
+        }
+    } else {
+        PowerQuirks::NONE
+    }
+}
+
+impl LowPowerPolicy {
+    /// Quirks to apply to `device_id` (None = unknown device).
+    pub fn resolve(self, device_id: Option<u16>) -> PowerQuirks {
+        match (self, device_id) {
+            (LowPowerPolicy::Keep, _) | (LowPowerPolicy::Auto, None) => PowerQuirks::NONE,
+            (LowPowerPolicy::Auto, Some(id)) => known_quirks(id),
+            (LowPowerPolicy::Disable, id) => PowerQuirks {
+                eee: id.map_or(EeeControl::None, eee_control),
+                aspm: PCI_EXP_LNKCTL_ASPM_L0S | PCI_EXP_LNKCTL_ASPM_L1,
+            },
+        }
+    }
+}
+
+/// Apply `policy` to the NIC. Call before auto-negotiation is restarted
+/// so the link comes up without EEE.
+///
+/// # Safety
+/// Called during init, MMIO must be valid.
+pub unsafe fn apply(
+    mmio_base: u64,
+    pci_addr: Option<PciAddr>,
+    policy: LowPowerPolicy,
+    tsc_freq: u64,
+) {
+    let device_id = pci_addr.map(|addr| pci_cfg_read16(addr, offset::DEVICE_ID));
+    let quirks = policy.resolve(device_id);
+
+    match quirks.eee {
+        EeeControl::None => {}
+        EeeControl::PhyEmi(emi_addr) => {
+            if disable_eee_emi(mmio_base, emi_addr, tsc_freq) {
+                serial_println("    EEE advertisement disabled (PHY)");
+            } else {
+                serial_println("    WARN: EEE disable failed (PHY not responding)");
+            }
+        }
+        EeeControl::MacEeer => {
+            let eeer = read32(mmio_base + regs::EEER as u64);
+            let lpi = regs::EEER_TX_LPI_EN | regs::EEER_RX_LPI_EN | regs::EEER_LPI_FC;
+            write32(mmio_base + regs::EEER as u64, eeer & !lpi);
+            let ipcnfg = read32(mmio_base + regs::IPCNFG as u64);
+            let adv = regs::IPCNFG_EEE_100M_AN | regs::IPCNFG_EEE_1G_AN;
+            write32(mmio_base + regs::IPCNFG as u64, ipcnfg & !adv);
+            let _ = read32(mmio_base + regs::STATUS as u64); // flush
+            serial_println("    EEE disabled (EEER/IPCNFG)");
+        }
+    }
+
+    if quirks.aspm != 0 {
+        match pci_addr.and_then(|addr| disable_aspm(addr, quirks.aspm)) {
+            Some(was) if was & quirks.aspm != 0 => serial_println("    ASPM disabled"),
+            Some(_) => serial_println("    ASPM already off"),
+            None => serial_println("    WARN: no PCIe capability, ASPM left alone"),
+        }
+    }
+}
+
+/// Clear the 100M/1G EEE advertisement bits at `emi_addr`.
+unsafe fn disable_eee_emi(mmio_base: u64, emi_addr: u16, tsc_freq: u64) -> bool {
+    if phy_write(mmio_base, regs::I82579_EMI_ADDR, emi_addr, tsc_freq).is_err() {
+        return false;
+    }
+    let Some(adv) = phy_read(mmio_base, regs::I82579_EMI_DATA, tsc_freq) else {
+        return false;
+    };
+    let cleared = adv & !(regs::EEE_ADV_100 | regs::EEE_ADV_1000);
+    if cleared == adv {
+        return true;
+    }
+    // Each EMI access selects its address first
+    phy_write(mmio_base, regs::I82579_EMI_ADDR, emi_addr, tsc_freq).is_ok()
+        && phy_write(mmio_base, regs::I82579_EMI_DATA, cleared, tsc_freq).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auto_follows_device_table() {
+        let i219 = LowPowerPolicy::Auto.resolve(Some(0x15B8));
+        assert_eq!(i219.eee, EeeControl::PhyEmi(regs::I217_EEE_ADVERTISEMENT));
+        assert_eq!(i219.aspm, PCI_EXP_LNKCTL_ASPM_L1);
+
+        let i218 = LowPowerPolicy::Auto.resolve(Some(0x15A2));
+        assert_eq!(i218.aspm, 0);
+
+        let i82574 = LowPowerPolicy::Auto.resolve(Some(0x10D3));
+        assert_eq!(i82574.eee, EeeControl::None);
+        assert_eq!(
+            i82574.aspm,
+            PCI_EXP_LNKCTL_ASPM_L0S | PCI_EXP_LNKCTL_ASPM_L1
+        );
+
+        // Parts without known trouble, and unknown devices, are left alone
+        assert_eq!(
+            LowPowerPolicy::Auto.resolve(Some(0x1533)),
+            PowerQuirks::NONE
+        );
+        assert_eq!(LowPowerPolicy::Auto.resolve(None), PowerQuirks::NONE);
+    }
+
+    #[test]
+    fn test_disable_and_keep() {
+        let i210 = LowPowerPolicy::Disable.resolve(Some(0x1533));
+        assert_eq!(i210.eee, EeeControl::MacEeer);
+        assert_eq!(i210.aspm, PCI_EXP_LNKCTL_ASPM_L0S | PCI_EXP_LNKCTL_ASPM_L1);
+
+        let unknown = LowPowerPolicy::Disable.resolve(None);
+        assert_eq!(unknown.eee, EeeControl::None);
+        assert_ne!(unknown.aspm, 0);
+
+        assert_eq!(
+            LowPowerPolicy::Keep.resolve(Some(0x15B8)),
+            PowerQuirks::NONE
+        );
+    }
+}
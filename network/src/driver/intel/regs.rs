@@ -54,6 +54,8 @@ pub const RDH: u32 = 0x2810;
 pub const RDT: u32 = 0x2818;
 /// Receive Descriptor Control.
 pub const RXDCTL: u32 = 0x2828;
+/// Receive Checksum Control.
+pub const RXCSUM: u32 = 0x5000;
 
 // ═══════════════════════════════════════════════════════════════════════════
 // TRANSMIT REGISTERS
@@ -82,8 +84,12 @@ pub const TXDCTL: u32 = 0x3828;
 pub const RAL0: u32 = 0x5400;
 /// Receive Address High (MAC bytes 4-5 + flags).
 pub const RAH0: u32 = 0x5404;
-/// Multicast Table Array (128 entries × 4 bytes).
+/// Multicast Table Array (128 entries × 4 bytes; 32 on ICH/PCH parts).
 pub const MTA: u32 = 0x5200;
+/// Shared Receive Address Low 0 (PCH2 and later; RAR[1..] live here).
+pub const SHRAL0: u32 = 0x5438;
+/// Stride between receive address register pairs.
+pub const RAR_STRIDE: u32 = 8;
 
 // ═══════════════════════════════════════════════════════════════════════════
 // CTRL REGISTER BITS
@@ -157,6 +163,8 @@ pub const RCTL_MPE: u32 = 1 << 4;
 pub const RCTL_LPE: u32 = 1 << 5;
 /// Loopback Mode (bits 6-7).
 pub const RCTL_LBM_MASK: u32 = 3 << 6;
+/// MAC loopback.
+pub const RCTL_LBM_MAC: u32 = 1 << 6;
 /// Receive Descriptor Minimum Threshold (bits 8-9).
 pub const RCTL_RDMTS_MASK: u32 = 3 << 8;
 /// Multicast Offset (bits 12-13).
@@ -214,6 +222,15 @@ pub const TCTL_COLD_FD: u32 = 64 << TCTL_COLD_SHIFT;
 /// Default Collision Distance for Half Duplex (512).
 pub const TCTL_COLD_HD: u32 = 512 << TCTL_COLD_SHIFT;
 
+// ═══════════════════════════════════════════════════════════════════════════
+// RXCSUM REGISTER BITS
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// IP header checksum offload.
+pub const RXCSUM_IPOFL: u32 = 1 << 8;
+/// TCP/UDP checksum offload.
+pub const RXCSUM_TUOFL: u32 = 1 << 9;
+
 // ═══════════════════════════════════════════════════════════════════════════
 // RXDCTL / TXDCTL REGISTER BITS
 // ═══════════════════════════════════════════════════════════════════════════
@@ -297,6 +314,8 @@ pub const PHY_1000T_STATUS: u32 = 0x0A;
 
 /// Collision Test.
 pub const BMCR_CTST: u16 = 1 << 7;
+/// Speed Selection (LSB of 1000 Mb/s when BMCR_SPEED100 is clear).
+pub const BMCR_SPEED1000: u16 = 1 << 6;
 /// Full Duplex.
 pub const BMCR_FULLDPLX: u16 = 1 << 8;
 /// Restart Auto-Negotiation.
@@ -341,6 +360,25 @@ pub const BMSR_100FULL: u16 = 1 << 14;
 /// 100BASE-T4.
 pub const BMSR_100BASE4: u16 = 1 << 15;
 
+// ═══════════════════════════════════════════════════════════════════════════
+// PHY ANAR / 1000T_CTRL BITS
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Advertise 10BASE-T Half Duplex.
+pub const ANAR_10HALF: u16 = 1 << 5;
+/// Advertise 10BASE-T Full Duplex.
+pub const ANAR_10FULL: u16 = 1 << 6;
+/// Advertise 100BASE-TX Half Duplex.
+pub const ANAR_100HALF: u16 = 1 << 7;
+/// Advertise 100BASE-TX Full Duplex.
+pub const ANAR_100FULL: u16 = 1 << 8;
+/// All 10/100 speed bits.
+pub const ANAR_SPEED_MASK: u16 = ANAR_10HALF | ANAR_10FULL | ANAR_100HALF | ANAR_100FULL;
+/// Advertise 1000BASE-T Half Duplex.
+pub const GCTRL_1000HALF: u16 = 1 << 8;
+/// Advertise 1000BASE-T Full Duplex.
+pub const GCTRL_1000FULL: u16 = 1 << 9;
+
 // ═══════════════════════════════════════════════════════════════════════════
 // INTERRUPT BITS
 // ═══════════════════════════════════════════════════════════════════════════
@@ -397,6 +435,21 @@ pub const TXD_CMD_IDE: u8 = 1 << 7;
 /// Descriptor Done.
 pub const TXD_STA_DD: u8 = 1 << 0;
 
+/// Extended data descriptor type (DTYP, bits 23:20 of the length dword).
+pub const TXD_DTYP_DATA: u32 = 1 << 20;
+/// Context descriptor type.
+pub const TXD_DTYP_CONTEXT: u32 = 0;
+
+/// Insert IP checksum (data descriptor POPTS).
+pub const TXD_POPTS_IXSM: u8 = 1 << 0;
+/// Insert TCP/UDP checksum (data descriptor POPTS).
+pub const TXD_POPTS_TXSM: u8 = 1 << 1;
+
+/// Packet is TCP (context descriptor TUCMD).
+pub const TXD_TUCMD_TCP: u8 = 1 << 0;
+/// Packet is IPv4 (context descriptor TUCMD).
+pub const TXD_TUCMD_IP: u8 = 1 << 1;
+
 // ═══════════════════════════════════════════════════════════════════════════
 // RX DESCRIPTOR BITS
 // ═══════════════════════════════════════════════════════════════════════════
@@ -409,6 +462,12 @@ pub const RXD_STA_EOP: u8 = 1 << 1;
 pub const RXD_STA_IXSM: u8 = 1 << 2;
 /// VLAN Packet.
 pub const RXD_STA_VP: u8 = 1 << 3;
+/// UDP Checksum Calculated.
+pub const RXD_STA_UDPCS: u8 = 1 << 4;
+/// TCP (older parts: TCP or UDP) Checksum Calculated.
+pub const RXD_STA_TCPCS: u8 = 1 << 5;
+/// IP Checksum Calculated.
+pub const RXD_STA_IPCS: u8 = 1 << 6;
 
 /// CRC Error.
 pub const RXD_ERR_CE: u8 = 1 << 0;
@@ -418,12 +477,12 @@ pub const RXD_ERR_SE: u8 = 1 << 1;
 pub const RXD_ERR_SEQ: u8 = 1 << 2;
 /// Carrier Extension Error.
 pub const RXD_ERR_CXE: u8 = 1 << 4;
-/// RX Data Error.
-pub const RXD_ERR_RXE: u8 = 1 << 5;
+/// TCP/UDP Checksum Error.
+pub const RXD_ERR_TCPE: u8 = 1 << 5;
 /// IP Checksum Error.
 pub const RXD_ERR_IPE: u8 = 1 << 6;
-/// TCP/UDP Checksum Error.
-pub const RXD_ERR_TCPE: u8 = 1 << 7;
+/// RX Data Error.
+pub const RXD_ERR_RXE: u8 = 1 << 7;
 
 /// All fatal RX errors.
 pub const RXD_ERR_FATAL: u8 = RXD_ERR_CE | RXD_ERR_SE | RXD_ERR_SEQ | RXD_ERR_RXE;
@@ -555,6 +614,38 @@ pub const HV_OEM_BITS_LPLU: u16 = 1 << 2;
 /// KMRN Control register (for cable length).
 pub const HV_KMRN_MODE_CTRL: u32 = 0x1EA;
 
+// ═══════════════════════════════════════════════════════════════════════════
+// ENERGY EFFICIENT ETHERNET
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// EMI address register (PCH PHYs, page 0).
+pub const I82579_EMI_ADDR: u32 = 0x10;
+/// EMI data register (PCH PHYs, page 0).
+pub const I82579_EMI_DATA: u32 = 0x11;
+/// EEE advertisement, 82579 PHY (EMI address).
+pub const I82579_EEE_ADVERTISEMENT: u16 = 0x040E;
+/// EEE advertisement, I217/I218/I219 PHY (EMI address).
+pub const I217_EEE_ADVERTISEMENT: u16 = 0x8001;
+/// EEE advertisement: 100BASE-TX capable.
+pub const EEE_ADV_100: u16 = 1 << 1;
+/// EEE advertisement: 1000BASE-T capable.
+pub const EEE_ADV_1000: u16 = 1 << 2;
+
+/// EEE Register (I210/I211).
+pub const EEER: u32 = 0x0E30;
+/// EEER: Transmit LPI enable.
+pub const EEER_TX_LPI_EN: u32 = 1 << 16;
+/// EEER: Receive LPI enable.
+pub const EEER_RX_LPI_EN: u32 = 1 << 17;
+/// EEER: LPI on flow control.
+pub const EEER_LPI_FC: u32 = 1 << 18;
+/// Internal PHY Configuration (I210/I211).
+pub const IPCNFG: u32 = 0x0E38;
+/// IPCNFG: Advertise EEE at 100 Mb/s.
+pub const IPCNFG_EEE_100M_AN: u32 = 1 << 2;
+/// IPCNFG: Advertise EEE at 1 Gb/s.
+pub const IPCNFG_EEE_1G_AN: u32 = 1 << 3;
+
 // ═══════════════════════════════════════════════════════════════════════════
 // TIMEOUTS (in microseconds)
 // ═══════════════════════════════════════════════════════════════════════════
@@ -2,12 +2,20 @@
 //!
 //! Rust orchestration layer for receive operations.
 //! All hardware access is via ASM bindings.
+//!
+//! # Checksum Offload
+//! With RXCSUM enabled the MAC checks IPv4, TCP and UDP checksums and
+//! reports bad ones as descriptor errors. Frames it did not check (options
+//! it can't parse, UDP on parts without UDPCS) are checked in software so
+//! the stack can rely on every delivered frame.
 
+use super::regs;
 use crate::asm::core::barriers::{lfence, sfence};
 use crate::asm::drivers::intel::{
-    asm_intel_rx_clear_desc, asm_intel_rx_init_desc, asm_intel_rx_poll, asm_intel_rx_update_tail,
-    RxPollResult,
+    asm_intel_rx_clear_desc, asm_intel_rx_init_desc, asm_intel_rx_poll, asm_intel_rx_read_head,
+    asm_intel_rx_update_tail, RxPollResult,
 };
+use crate::driver::checksum::{self, FrameLayout, L4Protocol};
 use crate::mainloop::serial::{serial_print, serial_print_hex, serial_println};
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -65,6 +73,8 @@ pub struct RxRing {
     next_to_clean: u16,
     /// Last tail value written to hardware.
     tail: u16,
+    /// Checksums are validated (RXCSUM enabled).
+    checksum_offload: bool,
 }
 
 impl RxRing {
@@ -91,9 +101,15 @@ impl RxRing {
             queue_size,
             next_to_clean: 0,
             tail: 0,
+            checksum_offload: false,
         }
     }
 
+    /// Drop frames with bad checksums from now on; RXCSUM must be enabled.
+    pub fn set_checksum_offload(&mut self, enabled: bool) {
+        self.checksum_offload = enabled;
+    }
+
     /// Initialize all descriptors with buffer addresses.
     pub fn init_descriptors(&mut self) {
         // Print critical DMA info for hardware debugging
@@ -144,6 +160,23 @@ impl RxRing {
         (self.queue_size as u32) * (RX_DESC_SIZE as u32)
     }
 
+    /// Number of descriptors.
+    pub fn queue_size(&self) -> u16 {
+        self.queue_size
+    }
+
+    /// Descriptors the hardware has filled that haven't been cleaned yet.
+    ///
+    /// The head register (RDH) is one past the last descriptor written.
+    pub fn backlog(&self) -> usize {
+        let head = unsafe { asm_intel_rx_read_head(self.mmio_base) } as usize;
+        let size = self.queue_size as usize;
+        if size == 0 {
+            return 0;
+        }
+        (head + size - self.next_to_clean as usize) % size
+    }
+
     /// Check if a packet is available.
     #[inline]
     pub fn can_receive(&self) -> bool {
@@ -175,7 +208,12 @@ impl RxRing {
         }
 
         // Check for errors
-        if result.has_errors() {
+        let checksum_errors = if self.checksum_offload {
+            result.errors & RxPollResult::ERR_CSUM
+        } else {
+            0
+        };
+        if result.has_errors() || checksum_errors != 0 {
             // Still need to release the descriptor
             self.release_descriptor(desc_idx);
             return Err(RxError::PacketError(result.errors));
@@ -209,6 +247,14 @@ impl RxRing {
         // Release descriptor for reuse
         self.release_descriptor(desc_idx);
 
+        let frame = &out_buffer[..length];
+        if self.checksum_offload
+            && !hardware_checked(result.status, frame)
+            && !checksum::verify(frame)
+        {
+            return Err(RxError::PacketError(RxPollResult::ERR_CSUM));
+        }
+
         Ok(Some(length))
     }
 
@@ -259,3 +305,20 @@ impl RxRing {
 // Safety: RxRing is Send as it only holds raw pointers that are valid
 // for the lifetime of the driver.
 unsafe impl Send for RxRing {}
+
+/// Whether the MAC checked every checksum `frame` carries.
+fn hardware_checked(status: u8, frame: &[u8]) -> bool {
+    if status & regs::RXD_STA_IXSM != 0 {
+        return false;
+    }
+    let Some(layout) = FrameLayout::parse(frame) else {
+        return true;
+    };
+    let l4_checked = match layout.l4 {
+        Some(L4Protocol::Tcp) => status & regs::RXD_STA_TCPCS != 0,
+        // Parts without UDPCS report UDP under TCPCS
+        Some(L4Protocol::Udp) => status & (regs::RXD_STA_UDPCS | regs::RXD_STA_TCPCS) != 0,
+        None => true,
+    };
+    status & regs::RXD_STA_IPCS != 0 && l4_checked
+}
@@ -2,12 +2,21 @@
 //!
 //! Rust orchestration layer for transmit operations.
 //! All hardware access is via ASM bindings.
-
+//!
+//! # Checksum Offload
+//! With offload on, IPv4 frames go out as extended data descriptors asking
+//! the MAC to insert the IP and TCP/UDP checksums. Where those are comes
+//! from a context descriptor, which takes a ring slot of its own and is
+//! only written when the offsets change (in practice: once). Other frames
+//! keep using legacy descriptors.
+
+use super::regs;
 use crate::asm::core::barriers::sfence;
 use crate::asm::drivers::intel::{
     asm_intel_tx_clear_desc, asm_intel_tx_init_desc, asm_intel_tx_poll, asm_intel_tx_submit,
     asm_intel_tx_update_tail,
 };
+use crate::driver::checksum::{self, FrameLayout, L4Protocol};
 use crate::mainloop::serial::{serial_print, serial_print_hex, serial_println};
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -72,6 +81,10 @@ pub struct TxRing {
     next_to_use: u16,
     /// Next descriptor to check for completion.
     next_to_clean: u16,
+    /// Insert checksums in hardware.
+    checksum_offload: bool,
+    /// Last context descriptor written; the MAC keeps using it.
+    context: Option<[u64; 2]>,
 }
 
 impl TxRing {
@@ -98,9 +111,22 @@ impl TxRing {
             queue_size,
             next_to_use: 0,
             next_to_clean: 0,
+            checksum_offload: false,
+            context: None,
         }
     }
 
+    /// Insert IPv4, TCP and UDP checksums in hardware from now on.
+    pub fn set_checksum_offload(&mut self, enabled: bool) {
+        self.checksum_offload = enabled;
+        self.context = None;
+    }
+
+    /// Whether checksums are inserted in hardware.
+    pub fn checksum_offload(&self) -> bool {
+        self.checksum_offload
+    }
+
     /// Initialize all descriptors to zero.
     pub fn init_descriptors(&mut self) {
         // Print critical DMA info for hardware debugging
@@ -135,6 +161,11 @@ impl TxRing {
         (self.queue_size as u32) * (TX_DESC_SIZE as u32)
     }
 
+    /// Number of descriptors.
+    pub fn queue_size(&self) -> u16 {
+        self.queue_size
+    }
+
     /// Check if we can transmit a frame.
     #[inline]
     pub fn can_transmit(&self) -> bool {
@@ -178,11 +209,27 @@ impl TxRing {
             });
         }
 
-        // Check if we have a descriptor available
-        if !self.can_transmit() {
+        let layout = if self.checksum_offload {
+            FrameLayout::parse(frame)
+        } else {
+            None
+        };
+        let new_context = layout
+            .as_ref()
+            .map(context_descriptor)
+            .filter(|context| Some(*context) != self.context);
+
+        // Check if we have a descriptor available (two with a new context)
+        if self.available() < 1 + new_context.is_some() as u16 {
             return Err(TxError::QueueFull);
         }
 
+        if let Some(context) = new_context {
+            self.write_desc(self.next_to_use, context);
+            self.next_to_use = (self.next_to_use + 1) % self.queue_size;
+            self.context = Some(context);
+        }
+
         let desc_idx = self.next_to_use;
         let desc_ptr = self.desc_ptr(desc_idx);
         let buffer_cpu = self.buffer_cpu_ptr(desc_idx);
@@ -193,9 +240,17 @@ impl TxRing {
             core::ptr::copy_nonoverlapping(frame.as_ptr(), buffer_cpu, frame.len());
         }
 
-        // Submit descriptor (sets EOP, IFCS, RS, includes sfence)
-        unsafe {
-            asm_intel_tx_submit(desc_ptr, buffer_bus, frame.len() as u32);
+        match layout {
+            Some(layout) => {
+                // SAFETY: the buffer holds the frame just copied in
+                let buffer = unsafe { core::slice::from_raw_parts_mut(buffer_cpu, frame.len()) };
+                seed_checksums(buffer, &layout);
+                self.write_desc(desc_idx, data_descriptor(buffer_bus, frame.len(), &layout));
+            }
+            // Submit descriptor (sets EOP, IFCS, RS, includes sfence)
+            None => unsafe {
+                asm_intel_tx_submit(desc_ptr, buffer_bus, frame.len() as u32);
+            },
         }
 
         // Advance next_to_use
@@ -240,6 +295,16 @@ impl TxRing {
         unsafe { self.desc_cpu.add((idx as usize) * TX_DESC_SIZE) }
     }
 
+    /// Write a prepared descriptor; fenced before the tail update.
+    fn write_desc(&self, idx: u16, desc: [u64; 2]) {
+        let desc_ptr = self.desc_ptr(idx) as *mut u64;
+        unsafe {
+            core::ptr::write_volatile(desc_ptr, desc[0]);
+            core::ptr::write_volatile(desc_ptr.add(1), desc[1]);
+        }
+        sfence();
+    }
+
     /// Get bus address of buffer.
     #[inline]
     fn buffer_bus_addr(&self, idx: u16) -> u64 {
@@ -256,3 +321,123 @@ impl TxRing {
 // Safety: TxRing is Send as it only holds raw pointers that are valid
 // for the lifetime of the driver.
 unsafe impl Send for TxRing {}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// CHECKSUM OFFLOAD DESCRIPTORS
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// TCP/IP context descriptor for frames laid out like `layout`.
+///
+/// ```text
+/// IPCSS | IPCSO | IPCSE (16) | TUCSS | TUCSO | TUCSE (16)
+/// PAYLEN (20) | DTYP (4) | TUCMD | STA | rsvd | HDRLEN | MSS (16)
+/// ```
+/// Offsets are from the start of the frame; the END fields are inclusive.
+/// RS is set so the slot completes like any other.
+fn context_descriptor(layout: &FrameLayout) -> [u64; 2] {
+    let ip_start = FrameLayout::IP_START as u64;
+    let ip_checksum = ip_start + FrameLayout::IP_CHECKSUM_OFFSET as u64;
+    let ip_end = (layout.l4_start() - 1) as u64;
+    let mut low = ip_start | ip_checksum << 8 | ip_end << 16;
+
+    let mut tucmd = regs::TXD_CMD_DEXT | regs::TXD_CMD_RS | regs::TXD_TUCMD_IP;
+    if let (Some(l4), Some(field)) = (layout.l4, layout.l4_checksum_field()) {
+        let l4_start = layout.l4_start() as u64;
+        let l4_end = (layout.ip_end - 1) as u64;
+        low |= (l4_start | (field as u64) << 8 | l4_end << 16) << 32;
+        if l4 == L4Protocol::Tcp {
+            tucmd |= regs::TXD_TUCMD_TCP;
+        }
+    }
+
+    let high = (regs::TXD_DTYP_CONTEXT | (tucmd as u32) << 24) as u64;
+    [low, high]
+}
+
+/// Extended data descriptor asking for the checksums `layout` has.
+///
+/// ```text
+/// Buffer address (64)
+/// DTALEN (20) | DTYP (4) | DCMD | STA | rsvd | POPTS | VLAN (16)
+/// ```
+fn data_descriptor(buffer_bus: u64, len: usize, layout: &FrameLayout) -> [u64; 2] {
+    let dcmd = regs::TXD_CMD_EOP | regs::TXD_CMD_IFCS | regs::TXD_CMD_RS | regs::TXD_CMD_DEXT;
+    let mut popts = regs::TXD_POPTS_IXSM;
+    if layout.l4.is_some() {
+        popts |= regs::TXD_POPTS_TXSM;
+    }
+    let length = (len as u32 & 0xF_FFFF) | regs::TXD_DTYP_DATA | (dcmd as u32) << 24;
+    [buffer_bus, length as u64 | (popts as u64) << 40]
+}
+
+/// Prepare the checksum fields of an outgoing frame for the MAC: the IP
+/// checksum starts from zero, the TCP/UDP one from the pseudo-header sum.
+fn seed_checksums(frame: &mut [u8], layout: &FrameLayout) {
+    let ip_checksum = FrameLayout::IP_START + FrameLayout::IP_CHECKSUM_OFFSET;
+    frame[ip_checksum..ip_checksum + 2].fill(0);
+    if let Some(field) = layout.l4_checksum_field() {
+        let seed = checksum::fold(layout.pseudo_header_sum(frame));
+        frame[field..field + 2].copy_from_slice(&seed.to_be_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ethernet + 20-byte IPv4 header + 20-byte TCP header + 4 bytes data
+    fn tcp_frame() -> [u8; 58] {
+        let mut frame = [0u8; 58];
+        frame[12..14].copy_from_slice(&[0x08, 0x00]);
+        frame[14] = 0x45;
+        frame[16..18].copy_from_slice(&44u16.to_be_bytes());
+        frame[23] = 6;
+        frame[24..26].copy_from_slice(&[0xAB, 0xCD]);
+        frame[26..34].copy_from_slice(&[192, 168, 1, 2, 192, 168, 1, 1]);
+        frame[46] = 0x50;
+        frame
+    }
+
+    #[test]
+    fn test_context_descriptor() {
+        let layout = FrameLayout::parse(&tcp_frame()).unwrap();
+        let [low, high] = context_descriptor(&layout);
+
+        // IPCSS 14, IPCSO 24, IPCSE 33 / TUCSS 34, TUCSO 50, TUCSE 57
+        assert_eq!(low, 14 | 24 << 8 | 33 << 16 | (34 | 50 << 8 | 57 << 16) << 32);
+        let tucmd = (high >> 24) as u8;
+        assert_eq!(
+            tucmd,
+            regs::TXD_CMD_DEXT | regs::TXD_CMD_RS | regs::TXD_TUCMD_IP | regs::TXD_TUCMD_TCP
+        );
+        assert_eq!(high & 0xF0_0000, 0);
+    }
+
+    #[test]
+    fn test_data_descriptor() {
+        let layout = FrameLayout::parse(&tcp_frame()).unwrap();
+        let [addr, high] = data_descriptor(0x1234_5000, 58, &layout);
+        assert_eq!(addr, 0x1234_5000);
+        assert_eq!(high & 0xF_FFFF, 58);
+        assert_eq!(high & 0xF0_0000, regs::TXD_DTYP_DATA as u64);
+        assert_eq!(
+            (high >> 40) as u8,
+            regs::TXD_POPTS_IXSM | regs::TXD_POPTS_TXSM
+        );
+    }
+
+    #[test]
+    fn test_seed_checksums() {
+        let mut frame = tcp_frame();
+        let layout = FrameLayout::parse(&frame).unwrap();
+        seed_checksums(&mut frame, &layout);
+        assert_eq!(frame[24..26], [0, 0]);
+
+        // What the MAC then computes must verify
+        let ip = !checksum::fold(checksum::sum(&frame[14..34]));
+        frame[24..26].copy_from_slice(&ip.to_be_bytes());
+        let l4 = !checksum::fold(checksum::sum(&frame[34..58]));
+        frame[50..52].copy_from_slice(&l4.to_be_bytes());
+        assert!(checksum::verify(&frame));
+    }
+}
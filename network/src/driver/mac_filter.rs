@@ -0,0 +1,181 @@
+//! Receive address filters.
+//!
+//! NICs deliver frames sent to their own address and to broadcast. mDNS
+//! and IPv6 neighbour discovery also need multicast groups, and a second
+//! unicast address is occasionally useful; those go into the NIC's filter
+//! tables. [`MacFilter`] is the list a driver rebuilds its tables from,
+//! so removing one entry never needs the hardware to be read back (the
+//! Intel multicast table is a hash and can't be).
+
+use super::traits::FilterError;
+use crate::types::ethernet::{is_broadcast, is_multicast};
+use crate::types::MacAddress;
+
+/// Extra unicast addresses a filter list holds at most.
+pub const MAX_UNICAST_FILTERS: usize = 8;
+
+/// Multicast groups a filter list holds at most.
+pub const MAX_MULTICAST_FILTERS: usize = 32;
+
+/// Fixed-capacity set of addresses, in insertion order.
+#[derive(Debug, Clone, Copy)]
+struct AddressList<const N: usize> {
+    entries: [MacAddress; N],
+    len: usize,
+}
+
+impl<const N: usize> AddressList<N> {
+    const fn new() -> Self {
+        Self {
+            entries: [[0; 6]; N],
+            len: 0,
+        }
+    }
+
+    fn as_slice(&self) -> &[MacAddress] {
+        &self.entries[..self.len]
+    }
+
+    /// Ok(false) if already present.
+    fn insert(&mut self, mac: MacAddress, limit: usize) -> Result<bool, FilterError> {
+        if self.as_slice().contains(&mac) {
+            return Ok(false);
+        }
+        if self.len >= limit.min(N) {
+            return Err(FilterError::TableFull);
+        }
+        self.entries[self.len] = mac;
+        self.len += 1;
+        Ok(true)
+    }
+
+    /// False if not present.
+    fn remove(&mut self, mac: &MacAddress) -> bool {
+        let Some(pos) = self.as_slice().iter().position(|m| m == mac) else {
+            return false;
+        };
+        self.entries.copy_within(pos + 1..self.len, pos);
+        self.len -= 1;
+        true
+    }
+}
+
+/// Extra unicast addresses and multicast groups a NIC should receive.
+///
+/// The mutators return whether the list changed; only then does the
+/// driver need to touch the hardware.
+#[derive(Debug, Clone, Copy)]
+pub struct MacFilter {
+    own: MacAddress,
+    unicast_limit: usize,
+    unicast: AddressList<MAX_UNICAST_FILTERS>,
+    multicast: AddressList<MAX_MULTICAST_FILTERS>,
+}
+
+impl MacFilter {
+    /// Empty list for a NIC with address `own` and room for
+    /// `unicast_limit` more unicast addresses.
+    pub const fn new(own: MacAddress, unicast_limit: usize) -> Self {
+        Self {
+            own,
+            unicast_limit,
+            unicast: AddressList::new(),
+            multicast: AddressList::new(),
+        }
+    }
+
+    /// Extra unicast addresses.
+    pub fn unicast(&self) -> &[MacAddress] {
+        self.unicast.as_slice()
+    }
+
+    /// Multicast groups.
+    pub fn multicast(&self) -> &[MacAddress] {
+        self.multicast.as_slice()
+    }
+
+    /// Add unicast `mac`. Our own address is always received and can't
+    /// be added; neither can group addresses or all zeros.
+    pub fn add_unicast(&mut self, mac: MacAddress) -> Result<bool, FilterError> {
+        if is_multicast(&mac) || mac == [0; 6] || mac == self.own {
+            return Err(FilterError::InvalidAddress);
+        }
+        self.unicast.insert(mac, self.unicast_limit)
+    }
+
+    /// Remove unicast `mac`.
+    pub fn remove_unicast(&mut self, mac: &MacAddress) -> bool {
+        self.unicast.remove(mac)
+    }
+
+    /// Add multicast group `mac`. Broadcast is always received.
+    pub fn join(&mut self, mac: MacAddress) -> Result<bool, FilterError> {
+        if !is_multicast(&mac) || is_broadcast(&mac) {
+            return Err(FilterError::InvalidAddress);
+        }
+        self.multicast.insert(mac, MAX_MULTICAST_FILTERS)
+    }
+
+    /// Remove multicast group `mac`.
+    pub fn leave(&mut self, mac: &MacAddress) -> bool {
+        self.multicast.remove(mac)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ethernet::{ipv4_multicast_mac, ipv6_multicast_mac};
+
+    const OWN: MacAddress = [0x52, 0x54, 0x00, 0x12, 0x34, 0x56];
+
+    #[test]
+    fn test_unicast() {
+        let mut filter = MacFilter::new(OWN, 2);
+        let a = [0x02, 0, 0, 0, 0, 1];
+        let b = [0x02, 0, 0, 0, 0, 2];
+        assert_eq!(filter.add_unicast(a), Ok(true));
+        assert_eq!(filter.add_unicast(a), Ok(false));
+        assert_eq!(filter.add_unicast(b), Ok(true));
+        assert_eq!(
+            filter.add_unicast([0x02, 0, 0, 0, 0, 3]),
+            Err(FilterError::TableFull)
+        );
+        assert_eq!(filter.add_unicast(OWN), Err(FilterError::InvalidAddress));
+        assert_eq!(
+            filter.add_unicast([0x01, 0, 0x5E, 0, 0, 1]),
+            Err(FilterError::InvalidAddress)
+        );
+
+        assert!(filter.remove_unicast(&a));
+        assert!(!filter.remove_unicast(&a));
+        assert_eq!(filter.unicast(), &[b]);
+    }
+
+    #[test]
+    fn test_multicast() {
+        let mut filter = MacFilter::new(OWN, 0);
+        // mDNS over IPv4 and IPv6, all-nodes
+        let mdns = ipv4_multicast_mac([224, 0, 0, 251]);
+        assert_eq!(mdns, [0x01, 0x00, 0x5E, 0x00, 0x00, 0xFB]);
+        let mut ff02_fb = [0u8; 16];
+        ff02_fb[0..2].copy_from_slice(&[0xFF, 0x02]);
+        ff02_fb[15] = 0xFB;
+        let mdns6 = ipv6_multicast_mac(ff02_fb);
+        assert_eq!(mdns6, [0x33, 0x33, 0, 0, 0, 0xFB]);
+
+        assert_eq!(filter.join(mdns), Ok(true));
+        assert_eq!(filter.join(mdns6), Ok(true));
+        assert_eq!(filter.join(mdns), Ok(false));
+        assert_eq!(filter.join([0xFF; 6]), Err(FilterError::InvalidAddress));
+        assert_eq!(filter.join(OWN), Err(FilterError::InvalidAddress));
+        assert!(filter.leave(&mdns));
+        assert_eq!(filter.multicast(), &[mdns6]);
+
+        // No unicast room at all
+        assert_eq!(
+            filter.add_unicast([0x02, 0, 0, 0, 0, 1]),
+            Err(FilterError::TableFull)
+        );
+    }
+}
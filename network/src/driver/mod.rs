@@ -20,21 +20,36 @@
 //! - Rust code handles orchestration, state, and error handling
 //! - Unified abstractions for QEMU ↔ real hardware parity
 
+// Block drivers are left out of netboot-only builds
+#[cfg(not(feature = "netboot-only"))]
 pub mod ahci;
+#[cfg(not(feature = "netboot-only"))]
 pub mod block_io_adapter;
 pub mod block_traits;
+pub mod checksum;
+pub mod conformance;
 pub mod intel;
+pub mod mac_filter;
+pub mod selftest;
 pub mod traits;
 pub mod unified;
 pub mod unified_block_io;
+#[cfg(not(feature = "netboot-only"))]
+pub mod usb_msc;
 pub mod virtio;
+#[cfg(not(feature = "netboot-only"))]
 pub mod virtio_blk;
 // Future:
 // pub mod realtek;
 // pub mod broadcom;
 
 // Re-exports - Network
-pub use traits::{DriverInit, NetworkDriver, RxError, TxError};
+pub use selftest::{run_loopback, LoopbackReport, SelfTestError};
+pub use mac_filter::MacFilter;
+pub use traits::{
+    ChecksumOffload, DriverInit, FilterError, LinkInfo, LoopbackMode, NetworkDriver, ResetState,
+    RingState, RxError, TxError,
+};
 pub use virtio::{VirtioConfig, VirtioInitError, VirtioNetDriver};
 
 // Re-exports - Intel e1000e
@@ -45,13 +60,21 @@ pub use unified::{UnifiedDriverError, UnifiedNetworkDriver};
 
 // Re-exports - Block (VirtIO)
 pub use block_traits::{
-    BlockCompletion, BlockDeviceInfo, BlockDriver, BlockDriverInit, BlockError,
+    BarrierStatus, BlockCompletion, BlockDeviceInfo, BlockDriver, BlockDriverInit, BlockError,
+    BlockEvent, WriteBarrier,
 };
-pub use virtio_blk::{VirtioBlkConfig, VirtioBlkDriver, VirtioBlkInitError};
+#[cfg(not(feature = "netboot-only"))]
+pub use virtio_blk::{VirtioBlkConfig, VirtioBlkDriver, VirtioBlkInitError, VirtioBlkStats};
 
 // Re-exports - Block (AHCI/SATA for real hardware)
+#[cfg(not(feature = "netboot-only"))]
 pub use ahci::{AhciConfig, AhciDriver, AhciInitError};
 
+// Re-exports - Block (USB mass storage behind xHCI)
+#[cfg(not(feature = "netboot-only"))]
+pub use usb_msc::{UsbMscConfig, UsbMscDriver, UsbMscInitError, USB_MSC_DMA_SIZE};
+
 // Re-exports - BlockIo adapters (for filesystem compatibility)
-pub use block_io_adapter::{BlockIoError, VirtioBlkBlockIo};
+#[cfg(not(feature = "netboot-only"))]
+pub use block_io_adapter::{AhciBlockIo, BlockIoError, VirtioBlkBlockIo};
 pub use unified_block_io::{GenericBlockIo, UnifiedBlockIo, UnifiedBlockIoError};
@@ -0,0 +1,299 @@
+//! NIC loopback self-test.
+//!
+//! Puts the device in MAC or PHY loopback, sends a burst of numbered test
+//! frames to itself and checks that every one comes back intact. A NIC
+//! that passes MAC loopback but fails PHY loopback points at the PHY or
+//! the MAC/PHY interconnect; failing both points at DMA or descriptor
+//! handling.
+//!
+//! Loopback leaves the device in a forced-speed state. The caller must
+//! re-create the driver (brutal reset) before using it for traffic.
+
+use crate::asm::core::tsc::read_tsc;
+use crate::driver::traits::{LoopbackMode, NetworkDriver};
+
+/// Frames sent per run.
+pub const TEST_FRAMES: u16 = 32;
+
+/// Local experimental ethertype (IEEE 802 "Local Experimental 1").
+pub(crate) const ETHERTYPE_TEST: u16 = 0x88B5;
+
+/// Marks a frame as ours.
+const MAGIC: [u8; 4] = *b"MXLB";
+
+/// Ethernet header + magic + sequence number.
+const HEADER_LEN: usize = 14 + 4 + 2;
+
+/// Frame sizes cycled through: minimum, mid, full MTU.
+const FRAME_SIZES: [usize; 3] = [64, 512, 1514];
+
+/// Milliseconds to wait for each frame to come back.
+const FRAME_TIMEOUT_MS: u64 = 50;
+
+/// Why the test could not run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelfTestError {
+    /// The device has no such loopback mode
+    Unsupported,
+}
+
+/// Outcome of one loopback run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoopbackReport {
+    pub mode: LoopbackMode,
+    /// Frames handed to the device
+    pub sent: u16,
+    /// Test frames that came back intact
+    pub received: u16,
+    /// Test frames that came back with a wrong length or payload
+    pub corrupted: u16,
+    /// Frames the device refused to queue
+    pub tx_failed: u16,
+}
+
+impl LoopbackReport {
+    /// Every frame came back intact.
+    pub fn passed(&self) -> bool {
+        self.sent == TEST_FRAMES && self.received == TEST_FRAMES && self.corrupted == 0
+    }
+}
+
+/// Run a loopback test, timing out with the TSC.
+pub fn run_loopback<D: NetworkDriver>(
+    driver: &mut D,
+    mode: LoopbackMode,
+    tsc_freq: u64,
+) -> Result<LoopbackReport, SelfTestError> {
+    run_loopback_with_clock(driver, mode, tsc_freq / 1000 * FRAME_TIMEOUT_MS, read_tsc)
+}
+
+/// Run a loopback test; `timeout` is per frame, in ticks of `now`.
+pub fn run_loopback_with_clock<D: NetworkDriver>(
+    driver: &mut D,
+    mode: LoopbackMode,
+    timeout: u64,
+    now: fn() -> u64,
+) -> Result<LoopbackReport, SelfTestError> {
+    if !driver.set_loopback(Some(mode)) {
+        return Err(SelfTestError::Unsupported);
+    }
+
+    let mac = driver.mac_address();
+    let mut report = LoopbackReport {
+        mode,
+        sent: 0,
+        received: 0,
+        corrupted: 0,
+        tx_failed: 0,
+    };
+    let mut tx = [0u8; 1514];
+    let mut rx = [0u8; 1536];
+
+    // Drop anything queued before loopback was enabled
+    driver.refill_rx_queue();
+    while let Ok(Some(_)) = driver.receive(&mut rx) {}
+
+    for seq in 0..TEST_FRAMES {
+        let len = FRAME_SIZES[seq as usize % FRAME_SIZES.len()];
+        build_frame(&mut tx[..len], &mac, seq);
+
+        driver.collect_tx_completions();
+        if driver.transmit(&tx[..len]).is_err() {
+            report.tx_failed += 1;
+            continue;
+        }
+        report.sent += 1;
+
+        let start = now();
+        loop {
+            driver.refill_rx_queue();
+            if let Ok(Some(n)) = driver.receive(&mut rx) {
+                match check_frame(&rx[..n], &mac, seq, len) {
+                    Check::Foreign => continue,
+                    Check::Intact => {
+                        report.received += 1;
+                        break;
+                    }
+                    Check::Corrupt => {
+                        report.corrupted += 1;
+                        break;
+                    }
+                }
+            }
+            if now().wrapping_sub(start) >= timeout {
+                break;
+            }
+        }
+    }
+
+    driver.collect_tx_completions();
+    driver.set_loopback(None);
+    Ok(report)
+}
+
+/// Payload byte `i` of frame `seq`.
+fn pattern(seq: u16, i: usize) -> u8 {
+    (i as u8).wrapping_mul(31) ^ (seq as u8)
+}
+
+fn build_frame(frame: &mut [u8], mac: &[u8; 6], seq: u16) {
+    frame[0..6].copy_from_slice(mac);
+    frame[6..12].copy_from_slice(mac);
+    frame[12..14].copy_from_slice(&ETHERTYPE_TEST.to_be_bytes());
+    frame[14..18].copy_from_slice(&MAGIC);
+    frame[18..20].copy_from_slice(&seq.to_be_bytes());
+    for (i, byte) in frame[HEADER_LEN..].iter_mut().enumerate() {
+        *byte = pattern(seq, i);
+    }
+}
+
+enum Check {
+    /// Not the frame we're waiting for
+    Foreign,
+    Intact,
+    Corrupt,
+}
+
+fn check_frame(frame: &[u8], mac: &[u8; 6], seq: u16, len: usize) -> Check {
+    if frame.len() < HEADER_LEN
+        || frame[12..14] != ETHERTYPE_TEST.to_be_bytes()
+        || frame[14..18] != MAGIC
+        || frame[6..12] != mac[..]
+        || u16::from_be_bytes([frame[18], frame[19]]) != seq
+    {
+        return Check::Foreign;
+    }
+    // The NIC may append the FCS on loopback; ignore anything past `len`
+    if frame.len() < len || frame[0..6] != mac[..] {
+        return Check::Corrupt;
+    }
+    let intact = frame[HEADER_LEN..len]
+        .iter()
+        .enumerate()
+        .all(|(i, &b)| b == pattern(seq, i));
+    if intact {
+        Check::Intact
+    } else {
+        Check::Corrupt
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::traits::{RxError, TxError};
+    use alloc::collections::VecDeque;
+    use alloc::vec::Vec;
+    use core::sync::atomic::{AtomicU64, Ordering};
+
+    static CLOCK: AtomicU64 = AtomicU64::new(0);
+
+    fn tick() -> u64 {
+        CLOCK.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Echoes transmitted frames while in loopback, optionally damaging
+    /// or dropping some of them.
+    struct EchoDriver {
+        loopback: Option<LoopbackMode>,
+        supports_phy: bool,
+        queue: VecDeque<Vec<u8>>,
+        sent: u16,
+        corrupt_every: Option<u16>,
+        drop_every: Option<u16>,
+    }
+
+    impl EchoDriver {
+        fn new() -> Self {
+            Self {
+                loopback: None,
+                supports_phy: true,
+                queue: VecDeque::new(),
+                sent: 0,
+                corrupt_every: None,
+                drop_every: None,
+            }
+        }
+    }
+
+    impl NetworkDriver for EchoDriver {
+        fn mac_address(&self) -> [u8; 6] {
+            [0x52, 0x54, 0, 0x12, 0x34, 0x56]
+        }
+        fn can_transmit(&self) -> bool {
+            true
+        }
+        fn can_receive(&self) -> bool {
+            !self.queue.is_empty()
+        }
+        fn transmit(&mut self, frame: &[u8]) -> Result<(), TxError> {
+            self.sent += 1;
+            if self.loopback.is_none()
+                || self.drop_every.is_some_and(|n| self.sent.is_multiple_of(n))
+            {
+                return Ok(());
+            }
+            let mut echo = frame.to_vec();
+            if self
+                .corrupt_every
+                .is_some_and(|n| self.sent.is_multiple_of(n))
+            {
+                let last = echo.len() - 1;
+                echo[last] ^= 0xFF;
+            }
+            // Something else on the wire first
+            self.queue.push_back(alloc::vec![0u8; 60]);
+            self.queue.push_back(echo);
+            Ok(())
+        }
+        fn receive(&mut self, buffer: &mut [u8]) -> Result<Option<usize>, RxError> {
+            match self.queue.pop_front() {
+                Some(frame) => {
+                    buffer[..frame.len()].copy_from_slice(&frame);
+                    Ok(Some(frame.len()))
+                }
+                None => Ok(None),
+            }
+        }
+        fn refill_rx_queue(&mut self) {}
+        fn collect_tx_completions(&mut self) {}
+        fn set_loopback(&mut self, mode: Option<LoopbackMode>) -> bool {
+            if mode == Some(LoopbackMode::Phy) && !self.supports_phy {
+                return false;
+            }
+            self.loopback = mode;
+            true
+        }
+    }
+
+    #[test]
+    fn test_clean_loopback_passes() {
+        let mut driver = EchoDriver::new();
+        let report = run_loopback_with_clock(&mut driver, LoopbackMode::Mac, 100, tick).unwrap();
+        assert!(report.passed());
+        assert_eq!(report.sent, TEST_FRAMES);
+        assert_eq!(driver.loopback, None);
+    }
+
+    #[test]
+    fn test_corruption_and_loss_are_counted() {
+        let mut driver = EchoDriver::new();
+        driver.corrupt_every = Some(4);
+        driver.drop_every = Some(5);
+        let report = run_loopback_with_clock(&mut driver, LoopbackMode::Phy, 100, tick).unwrap();
+        assert!(!report.passed());
+        // Frames 5, 10, ..., 30 dropped; 4, 8, 12, 16, 24, 28, 32 corrupted
+        assert_eq!(report.corrupted, 7);
+        assert_eq!(report.received, TEST_FRAMES - 6 - 7);
+    }
+
+    #[test]
+    fn test_unsupported_mode() {
+        let mut driver = EchoDriver::new();
+        driver.supports_phy = false;
+        assert_eq!(
+            run_loopback_with_clock(&mut driver, LoopbackMode::Phy, 100, tick),
+            Err(SelfTestError::Unsupported)
+        );
+    }
+}
@@ -28,6 +28,115 @@ pub enum RxError {
     DeviceError,
 }
 
+/// Receive filter errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterError {
+    /// Device has no filters we can program.
+    Unsupported,
+    /// Wrong kind of address for the call, or one that is always received.
+    InvalidAddress,
+    /// No free filter entry.
+    TableFull,
+    /// Device rejected the update.
+    DeviceError,
+}
+
+/// NIC loopback point for self-tests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoopbackMode {
+    /// Frames turn around inside the MAC; the PHY is not involved.
+    Mac,
+    /// Frames go through the MAC and turn around inside the PHY.
+    Phy,
+}
+
+impl LoopbackMode {
+    /// Display name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            LoopbackMode::Mac => "MAC",
+            LoopbackMode::Phy => "PHY",
+        }
+    }
+}
+
+/// Checksums a NIC computes on transmit and checks on receive.
+///
+/// The smoltcp adapter leaves these to the hardware. A driver that claims
+/// a receive checksum must drop frames where it is wrong, including frames
+/// the hardware could not check itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ChecksumOffload {
+    /// IPv4 header checksum inserted on transmit.
+    pub tx_ipv4: bool,
+    /// TCP and UDP checksums inserted on transmit.
+    pub tx_l4: bool,
+    /// IPv4 header checksum checked on receive.
+    pub rx_ipv4: bool,
+    /// TCP and UDP checksums checked on receive.
+    pub rx_l4: bool,
+}
+
+impl ChecksumOffload {
+    /// Nothing offloaded; the stack computes and checks everything.
+    pub const NONE: Self = Self {
+        tx_ipv4: false,
+        tx_l4: false,
+        rx_ipv4: false,
+        rx_l4: false,
+    };
+
+    /// IPv4, TCP and UDP checksums offloaded both ways.
+    pub const FULL: Self = Self {
+        tx_ipv4: true,
+        tx_l4: true,
+        rx_ipv4: true,
+        rx_l4: true,
+    };
+}
+
+/// Speed and duplex of an established link.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LinkInfo {
+    /// Speed in Mbps.
+    pub speed_mbps: u32,
+    /// Full duplex.
+    pub full_duplex: bool,
+}
+
+impl core::fmt::Display for LinkInfo {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let duplex = if self.full_duplex { "full" } else { "half" };
+        write!(f, "{} Mbps {} duplex", self.speed_mbps, duplex)
+    }
+}
+
+/// One descriptor ring as the device sees it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RingState {
+    /// Descriptors in the driver's ring.
+    pub size: u16,
+    /// Ring size programmed into the device; None if it can't be read
+    /// back.
+    pub device_size: Option<u16>,
+    /// Descriptors currently handed to the device.
+    pub posted: u16,
+}
+
+/// Device state the reset contract (RESET_CONTRACT.md) pins down, read
+/// back from the hardware.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResetState {
+    /// All interrupt causes masked; None on devices without a mask.
+    pub interrupts_masked: Option<bool>,
+    /// MAC or PHY loopback enabled.
+    pub loopback: bool,
+    /// Receive ring.
+    pub rx: RingState,
+    /// Transmit ring.
+    pub tx: RingState,
+}
+
 /// Core network device interface.
 ///
 /// All NIC drivers must implement this trait. Higher layers
@@ -46,6 +155,14 @@ pub trait NetworkDriver {
     /// Returns true if `receive()` will return `Ok(Some(_))`.
     fn can_receive(&self) -> bool;
 
+    /// Frames the device has received that `receive()` hasn't taken yet,
+    /// and the RX ring size. None if the device can't tell.
+    ///
+    /// Lets the main loop spend more time draining when the ring fills up.
+    fn rx_backlog(&self) -> Option<(usize, usize)> {
+        None
+    }
+
     /// Transmit an Ethernet frame.
     ///
     /// # Arguments
@@ -87,6 +204,71 @@ pub trait NetworkDriver {
     fn link_up(&self) -> bool {
         true
     }
+
+    /// Negotiated speed and duplex; None while the link is down or on
+    /// devices that don't report it.
+    fn link_info(&self) -> Option<LinkInfo> {
+        None
+    }
+
+    /// Whether PHY auto-negotiation has completed; None on devices whose
+    /// PHY the driver can't read. A MAC may report link before it has.
+    fn autoneg_complete(&self) -> Option<bool> {
+        None
+    }
+
+    /// Stop all device DMA before the session ends.
+    ///
+    /// After this the driver must not be used again. Called on abort so
+    /// the NIC stops writing into memory the next boot stage may reuse.
+    fn quiesce(&mut self) {}
+
+    /// Put the device in loopback (`Some`) or back to normal (`None`).
+    ///
+    /// Returns false if the device can't do `mode`. Leaving loopback is
+    /// best effort; callers should reset the device afterwards.
+    fn set_loopback(&mut self, mode: Option<LoopbackMode>) -> bool {
+        mode.is_none()
+    }
+
+    /// Read back the state the reset contract pins down; None on
+    /// drivers that can't. See [`conformance`](super::conformance).
+    fn reset_state(&self) -> Option<ResetState> {
+        None
+    }
+
+    /// Checksums the hardware takes care of.
+    fn checksum_offload(&self) -> ChecksumOffload {
+        ChecksumOffload::NONE
+    }
+
+    /// Also receive frames sent to unicast `mac`.
+    ///
+    /// Our own address and broadcast are always received. Adding or
+    /// removing an address twice is not an error.
+    fn add_unicast_filter(&mut self, mac: MacAddress) -> Result<(), FilterError> {
+        let _ = mac;
+        Err(FilterError::Unsupported)
+    }
+
+    /// Stop receiving frames sent to unicast `mac`.
+    fn remove_unicast_filter(&mut self, mac: MacAddress) -> Result<(), FilterError> {
+        let _ = mac;
+        Err(FilterError::Unsupported)
+    }
+
+    /// Receive frames sent to multicast group `mac` (see
+    /// [`ipv4_multicast_mac`](crate::types::ethernet::ipv4_multicast_mac)).
+    fn join_multicast(&mut self, mac: MacAddress) -> Result<(), FilterError> {
+        let _ = mac;
+        Err(FilterError::Unsupported)
+    }
+
+    /// Stop receiving frames sent to multicast group `mac`.
+    fn leave_multicast(&mut self, mac: MacAddress) -> Result<(), FilterError> {
+        let _ = mac;
+        Err(FilterError::Unsupported)
+    }
 }
 
 /// Driver initialization trait.
@@ -19,7 +19,10 @@
 //! ```
 
 use crate::driver::intel::{E1000eDriver, E1000eError};
-use crate::driver::traits::{NetworkDriver, RxError, TxError};
+use crate::driver::traits::{
+    ChecksumOffload, FilterError, LinkInfo, LoopbackMode, NetworkDriver, ResetState, RxError,
+    TxError,
+};
 use crate::driver::virtio::{VirtioInitError, VirtioNetDriver};
 use crate::types::MacAddress;
 
@@ -105,6 +108,13 @@ impl NetworkDriver for UnifiedNetworkDriver {
         }
     }
 
+    fn rx_backlog(&self) -> Option<(usize, usize)> {
+        match self {
+            UnifiedNetworkDriver::VirtIO(d) => d.rx_backlog(),
+            UnifiedNetworkDriver::Intel(d) => d.rx_backlog(),
+        }
+    }
+
     fn transmit(&mut self, frame: &[u8]) -> Result<(), TxError> {
         match self {
             UnifiedNetworkDriver::VirtIO(d) => d.transmit(frame),
@@ -139,6 +149,76 @@ impl NetworkDriver for UnifiedNetworkDriver {
             UnifiedNetworkDriver::Intel(d) => d.link_up(),
         }
     }
+
+    fn link_info(&self) -> Option<LinkInfo> {
+        match self {
+            UnifiedNetworkDriver::VirtIO(d) => d.link_info(),
+            UnifiedNetworkDriver::Intel(d) => d.link_info(),
+        }
+    }
+
+    fn autoneg_complete(&self) -> Option<bool> {
+        match self {
+            UnifiedNetworkDriver::VirtIO(d) => d.autoneg_complete(),
+            UnifiedNetworkDriver::Intel(d) => d.autoneg_complete(),
+        }
+    }
+
+    fn quiesce(&mut self) {
+        match self {
+            UnifiedNetworkDriver::VirtIO(d) => d.quiesce(),
+            UnifiedNetworkDriver::Intel(d) => d.quiesce(),
+        }
+    }
+
+    fn set_loopback(&mut self, mode: Option<LoopbackMode>) -> bool {
+        match self {
+            UnifiedNetworkDriver::VirtIO(d) => d.set_loopback(mode),
+            UnifiedNetworkDriver::Intel(d) => d.set_loopback(mode),
+        }
+    }
+
+    fn reset_state(&self) -> Option<ResetState> {
+        match self {
+            UnifiedNetworkDriver::VirtIO(d) => d.reset_state(),
+            UnifiedNetworkDriver::Intel(d) => d.reset_state(),
+        }
+    }
+
+    fn checksum_offload(&self) -> ChecksumOffload {
+        match self {
+            UnifiedNetworkDriver::VirtIO(d) => d.checksum_offload(),
+            UnifiedNetworkDriver::Intel(d) => d.checksum_offload(),
+        }
+    }
+
+    fn add_unicast_filter(&mut self, mac: MacAddress) -> Result<(), FilterError> {
+        match self {
+            UnifiedNetworkDriver::VirtIO(d) => d.add_unicast_filter(mac),
+            UnifiedNetworkDriver::Intel(d) => d.add_unicast_filter(mac),
+        }
+    }
+
+    fn remove_unicast_filter(&mut self, mac: MacAddress) -> Result<(), FilterError> {
+        match self {
+            UnifiedNetworkDriver::VirtIO(d) => d.remove_unicast_filter(mac),
+            UnifiedNetworkDriver::Intel(d) => d.remove_unicast_filter(mac),
+        }
+    }
+
+    fn join_multicast(&mut self, mac: MacAddress) -> Result<(), FilterError> {
+        match self {
+            UnifiedNetworkDriver::VirtIO(d) => d.join_multicast(mac),
+            UnifiedNetworkDriver::Intel(d) => d.join_multicast(mac),
+        }
+    }
+
+    fn leave_multicast(&mut self, mac: MacAddress) -> Result<(), FilterError> {
+        match self {
+            UnifiedNetworkDriver::VirtIO(d) => d.leave_multicast(mac),
+            UnifiedNetworkDriver::Intel(d) => d.leave_multicast(mac),
+        }
+    }
 }
 
 // Safety: UnifiedNetworkDriver is Send because all variants are Send
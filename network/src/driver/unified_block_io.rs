@@ -46,14 +46,22 @@ use gpt_disk_types::{BlockSize, Lba};
 
 use super::block_traits::{BlockDriver, BlockError};
 use crate::device::UnifiedBlockDevice;
+use crate::time::TimeoutConfig;
+
+/// Completion status for a request the device doesn't support
+/// (`VIRTIO_BLK_S_UNSUPP`); any other non-zero status is a media error.
+const STATUS_UNSUPPORTED: u8 = 2;
 
 /// Error type for unified BlockIo operations.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum UnifiedBlockIoError {
-    /// Underlying block driver error
-    DriverError(BlockError),
-    /// Request timeout
+    /// The device didn't complete a request in time (spun down, hung)
     Timeout,
+    /// The device failed a request: unreadable sector, medium gone
+    Media,
+    /// The driver refused a request or the device didn't follow the
+    /// protocol
+    Protocol(BlockError),
     /// Buffer alignment error
     BufferAlignment,
     /// Invalid operation
@@ -62,11 +70,33 @@ pub enum UnifiedBlockIoError {
     DeviceNotReady,
 }
 
+impl UnifiedBlockIoError {
+    /// Whether trying the request again may succeed.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            Self::Timeout | Self::Media | Self::Protocol(BlockError::QueueFull)
+        )
+    }
+}
+
+impl From<BlockError> for UnifiedBlockIoError {
+    fn from(e: BlockError) -> Self {
+        match e {
+            BlockError::Timeout => Self::Timeout,
+            BlockError::IoError => Self::Media,
+            BlockError::DeviceNotReady => Self::DeviceNotReady,
+            other => Self::Protocol(other),
+        }
+    }
+}
+
 impl core::fmt::Display for UnifiedBlockIoError {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
-            Self::DriverError(e) => write!(f, "Block driver error: {:?}", e),
             Self::Timeout => write!(f, "I/O timeout"),
+            Self::Media => write!(f, "Media error"),
+            Self::Protocol(e) => write!(f, "Block driver error: {:?}", e),
             Self::BufferAlignment => write!(f, "Buffer alignment error"),
             Self::InvalidOperation => write!(f, "Invalid operation"),
             Self::DeviceNotReady => write!(f, "Block device not ready"),
@@ -81,6 +111,10 @@ impl core::fmt::Display for UnifiedBlockIoError {
 /// - **VirtIO-blk** (QEMU, cloud VMs)
 /// - **AHCI SATA** (real hardware like ThinkPad T450s)
 ///
+/// The I/O itself is [`GenericBlockIo`]'s, with the timeout from
+/// [`TimeoutConfig::block_io`]; this only adds the readiness check and the
+/// driver name.
+///
 /// # Example
 ///
 /// ```ignore
@@ -88,28 +122,19 @@ impl core::fmt::Display for UnifiedBlockIoError {
 /// let mut device = UnifiedBlockDevice::probe(&config)?;
 ///
 /// // Wrap with BlockIo adapter
-/// let mut bio = UnifiedBlockIo::new(&mut device, dma_buf, dma_phys, timeout)?;
+/// let mut bio = UnifiedBlockIo::new(&mut device, dma_buf, dma_phys, TimeoutConfig::new(tsc_freq))?;
 ///
 /// // Use with filesystem layer
 /// let info = bio.block_size();
 /// bio.read_blocks(Lba(0), &mut buffer)?;
 /// ```
 pub struct UnifiedBlockIo<'a> {
-    /// The underlying unified block device
-    device: &'a mut UnifiedBlockDevice,
-    /// DMA buffer for transfers (must be physically contiguous)
-    dma_buffer: &'a mut [u8],
-    /// Physical address of DMA buffer
-    dma_buffer_phys: u64,
-    /// Next request ID
-    next_request_id: u32,
-    /// Timeout in TSC ticks
-    timeout_ticks: u64,
+    inner: GenericBlockIo<'a, UnifiedBlockDevice>,
 }
 
 impl<'a> UnifiedBlockIo<'a> {
     /// Maximum transfer size per request (64KB default)
-    pub const MAX_TRANSFER_SIZE: usize = 64 * 1024;
+    pub const MAX_TRANSFER_SIZE: usize = GenericBlockIo::<UnifiedBlockDevice>::MAX_TRANSFER_SIZE;
 
     /// Create a new unified BlockIo adapter.
     ///
@@ -117,7 +142,8 @@ impl<'a> UnifiedBlockIo<'a> {
     /// * `device` - Unified block device (VirtIO-blk or AHCI)
     /// * `dma_buffer` - DMA-capable buffer (must be at least MAX_TRANSFER_SIZE bytes)
     /// * `dma_buffer_phys` - Physical address of DMA buffer
-    /// * `timeout_ticks` - Timeout for I/O operations in TSC ticks
+    /// * `timeouts` - TSC-based timeouts (`TimeoutConfig::new`); requests
+    ///   get [`TimeoutConfig::block_io`] each
     ///
     /// # Returns
     /// New adapter or error if buffer too small or device not ready
@@ -125,7 +151,7 @@ impl<'a> UnifiedBlockIo<'a> {
         device: &'a mut UnifiedBlockDevice,
         dma_buffer: &'a mut [u8],
         dma_buffer_phys: u64,
-        timeout_ticks: u64,
+        timeouts: TimeoutConfig,
     ) -> Result<Self, UnifiedBlockIoError> {
         if dma_buffer.len() < Self::MAX_TRANSFER_SIZE {
             return Err(UnifiedBlockIoError::BufferAlignment);
@@ -136,114 +162,19 @@ impl<'a> UnifiedBlockIo<'a> {
         }
 
         Ok(Self {
-            device,
-            dma_buffer,
-            dma_buffer_phys,
-            next_request_id: 1,
-            timeout_ticks,
+            inner: GenericBlockIo::new(device, dma_buffer, dma_buffer_phys, timeouts.block_io())?,
         })
     }
 
     /// Get the underlying device type as a string.
     pub fn device_type(&self) -> &'static str {
-        self.device.driver_type()
-    }
-
-    /// Wait for a specific request to complete.
-    fn wait_for_completion(&mut self, request_id: u32) -> Result<(), UnifiedBlockIoError> {
-        let start = crate::mainloop::runner::get_tsc();
-
-        loop {
-            // Poll for completions
-            if let Some(completion) = self.device.poll_completion() {
-                if completion.request_id == request_id {
-                    if completion.status == 0 {
-                        return Ok(());
-                    } else {
-                        return Err(UnifiedBlockIoError::DriverError(BlockError::IoError));
-                    }
-                }
-                // Not our completion, continue polling
-            }
-
-            // Check timeout
-            let now = crate::mainloop::runner::get_tsc();
-            if now.wrapping_sub(start) > self.timeout_ticks {
-                return Err(UnifiedBlockIoError::Timeout);
-            }
-
-            core::hint::spin_loop();
-        }
+        self.inner.driver().driver_type()
     }
 
-    /// Perform a synchronous read.
-    fn sync_read(
-        &mut self,
-        sector: u64,
-        num_sectors: u32,
-        dst: &mut [u8],
-    ) -> Result<(), UnifiedBlockIoError> {
-        let info = self.device.info();
-        let bytes_needed = num_sectors as usize * info.sector_size as usize;
-
-        if bytes_needed > self.dma_buffer.len() {
-            return Err(UnifiedBlockIoError::BufferAlignment);
-        }
-
-        // Drain any pending completions
-        while self.device.poll_completion().is_some() {}
-
-        // Submit read request
-        let request_id = self.next_request_id;
-        self.next_request_id = self.next_request_id.wrapping_add(1);
-
-        self.device
-            .submit_read(sector, self.dma_buffer_phys, num_sectors, request_id)
-            .map_err(UnifiedBlockIoError::DriverError)?;
-
-        self.device.notify();
-
-        // Wait for completion
-        self.wait_for_completion(request_id)?;
-
-        // Copy data to destination
-        dst.copy_from_slice(&self.dma_buffer[..bytes_needed]);
-
-        Ok(())
-    }
-
-    /// Perform a synchronous write.
-    fn sync_write(
-        &mut self,
-        sector: u64,
-        num_sectors: u32,
-        src: &[u8],
-    ) -> Result<(), UnifiedBlockIoError> {
-        let info = self.device.info();
-        let bytes_needed = num_sectors as usize * info.sector_size as usize;
-
-        if bytes_needed > self.dma_buffer.len() {
-            return Err(UnifiedBlockIoError::BufferAlignment);
-        }
-
-        // Copy data to DMA buffer
-        self.dma_buffer[..bytes_needed].copy_from_slice(src);
-
-        // Drain any pending completions
-        while self.device.poll_completion().is_some() {}
-
-        // Submit write request
-        let request_id = self.next_request_id;
-        self.next_request_id = self.next_request_id.wrapping_add(1);
-
-        self.device
-            .submit_write(sector, self.dma_buffer_phys, num_sectors, request_id)
-            .map_err(UnifiedBlockIoError::DriverError)?;
-
-        self.device.notify();
-
-        // Wait for completion
-        self.wait_for_completion(request_id)
+    /// The error the last failed operation returned, for reporting after
+    /// a filesystem call has folded it into its own error type.
+    pub fn last_error(&self) -> Option<UnifiedBlockIoError> {
+        self.inner.last_error()
     }
 }
 
@@ -251,85 +182,23 @@ impl<'a> BlockIo for UnifiedBlockIo<'a> {
     type Error = UnifiedBlockIoError;
 
     fn block_size(&self) -> BlockSize {
-        let info = self.device.info();
-        BlockSize::new(info.sector_size).expect("valid sector size")
+        self.inner.block_size()
     }
 
     fn num_blocks(&mut self) -> Result<u64, Self::Error> {
-        let info = self.device.info();
-        Ok(info.total_sectors)
+        self.inner.num_blocks()
     }
 
     fn read_blocks(&mut self, start_lba: Lba, dst: &mut [u8]) -> Result<(), Self::Error> {
-        let info = self.device.info();
-        let sector_size = info.sector_size as usize;
-
-        if dst.len() % sector_size != 0 {
-            return Err(UnifiedBlockIoError::BufferAlignment);
-        }
-
-        let total_sectors = (dst.len() / sector_size) as u32;
-        let max_sectors_per_request = (Self::MAX_TRANSFER_SIZE / sector_size) as u32;
-
-        let mut current_sector = start_lba.0;
-        let mut remaining = total_sectors;
-        let mut offset = 0;
-
-        while remaining > 0 {
-            let chunk_sectors = remaining.min(max_sectors_per_request);
-            let chunk_bytes = chunk_sectors as usize * sector_size;
-
-            self.sync_read(
-                current_sector,
-                chunk_sectors,
-                &mut dst[offset..offset + chunk_bytes],
-            )?;
-
-            current_sector += chunk_sectors as u64;
-            remaining -= chunk_sectors;
-            offset += chunk_bytes;
-        }
-
-        Ok(())
+        self.inner.read_blocks(start_lba, dst)
     }
 
     fn write_blocks(&mut self, start_lba: Lba, src: &[u8]) -> Result<(), Self::Error> {
-        let info = self.device.info();
-        let sector_size = info.sector_size as usize;
-
-        if src.len() % sector_size != 0 {
-            return Err(UnifiedBlockIoError::BufferAlignment);
-        }
-
-        let total_sectors = (src.len() / sector_size) as u32;
-        let max_sectors_per_request = (Self::MAX_TRANSFER_SIZE / sector_size) as u32;
-
-        let mut current_sector = start_lba.0;
-        let mut remaining = total_sectors;
-        let mut offset = 0;
-
-        while remaining > 0 {
-            let chunk_sectors = remaining.min(max_sectors_per_request);
-            let chunk_bytes = chunk_sectors as usize * sector_size;
-
-            self.sync_write(
-                current_sector,
-                chunk_sectors,
-                &src[offset..offset + chunk_bytes],
-            )?;
-
-            current_sector += chunk_sectors as u64;
-            remaining -= chunk_sectors;
-            offset += chunk_bytes;
-        }
-
-        Ok(())
+        self.inner.write_blocks(start_lba, src)
     }
 
     fn flush(&mut self) -> Result<(), Self::Error> {
-        self.device
-            .flush()
-            .map_err(UnifiedBlockIoError::DriverError)
+        self.inner.flush()
     }
 }
 
@@ -339,8 +208,14 @@ impl<'a> BlockIo for UnifiedBlockIo<'a> {
 
 /// Generic BlockIo adapter for any `BlockDriver` implementation.
 ///
-/// This allows creating a synchronous BlockIo wrapper around any driver
-/// that implements the `BlockDriver` trait, not just `UnifiedBlockDevice`.
+/// The one synchronous wrapper every block driver goes through, so they
+/// all share its semantics: each request is bounced through the DMA
+/// buffer, split to fit both the buffer and the driver's
+/// `max_sectors_per_request`, and waited on for at most `timeout_ticks`.
+/// Requests that fail transiently (timeout, media error, full queue) are
+/// submitted again up to `retries` times before the error is returned.
+/// [`UnifiedBlockIo`], `VirtioBlkBlockIo` and `AhciBlockIo` are all this
+/// underneath.
 ///
 /// # Example
 ///
@@ -362,20 +237,54 @@ pub struct GenericBlockIo<'a, D: BlockDriver> {
     dma_buffer_phys: u64,
     /// Next request ID
     next_request_id: u32,
-    /// Timeout in TSC ticks
+    /// Timeout in ticks of `now`
     timeout_ticks: u64,
+    /// Clock the timeout is measured against
+    now: fn() -> u64,
+    /// Extra attempts for a transiently failing request
+    retries: u8,
+    /// Error of the last failed operation
+    last_error: Option<UnifiedBlockIoError>,
 }
 
 impl<'a, D: BlockDriver> GenericBlockIo<'a, D> {
     /// Maximum transfer size per request (64KB default)
     pub const MAX_TRANSFER_SIZE: usize = 64 * 1024;
 
+    /// Retries per request unless [`with_retries`](Self::with_retries)
+    /// says otherwise
+    pub const DEFAULT_RETRIES: u8 = 2;
+
     /// Create a new generic BlockIo adapter.
+    ///
+    /// # Arguments
+    /// * `driver` - Block driver
+    /// * `dma_buffer` - DMA-capable buffer (must be at least MAX_TRANSFER_SIZE bytes)
+    /// * `dma_buffer_phys` - Physical address of DMA buffer
+    /// * `timeout_ticks` - Timeout for I/O operations in TSC ticks
     pub fn new(
         driver: &'a mut D,
         dma_buffer: &'a mut [u8],
         dma_buffer_phys: u64,
         timeout_ticks: u64,
+    ) -> Result<Self, UnifiedBlockIoError> {
+        Self::with_clock(
+            driver,
+            dma_buffer,
+            dma_buffer_phys,
+            timeout_ticks,
+            crate::mainloop::runner::get_tsc,
+        )
+    }
+
+    /// [`new`](Self::new) with the timeout measured against `now` instead
+    /// of the TSC.
+    pub fn with_clock(
+        driver: &'a mut D,
+        dma_buffer: &'a mut [u8],
+        dma_buffer_phys: u64,
+        timeout_ticks: u64,
+        now: fn() -> u64,
     ) -> Result<Self, UnifiedBlockIoError> {
         if dma_buffer.len() < Self::MAX_TRANSFER_SIZE {
             return Err(UnifiedBlockIoError::BufferAlignment);
@@ -387,25 +296,67 @@ impl<'a, D: BlockDriver> GenericBlockIo<'a, D> {
             dma_buffer_phys,
             next_request_id: 1,
             timeout_ticks,
+            now,
+            retries: Self::DEFAULT_RETRIES,
+            last_error: None,
         })
     }
 
+    /// Retry transiently failing requests `retries` times (0 = never).
+    pub fn with_retries(mut self, retries: u8) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// The wrapped driver.
+    pub fn driver(&self) -> &D {
+        self.driver
+    }
+
+    /// The error the last failed operation returned.
+    pub fn last_error(&self) -> Option<UnifiedBlockIoError> {
+        self.last_error
+    }
+
+    /// Remember the error of a failed operation.
+    fn record(
+        &mut self,
+        result: Result<(), UnifiedBlockIoError>,
+    ) -> Result<(), UnifiedBlockIoError> {
+        if let Err(e) = result {
+            self.last_error = Some(e);
+        }
+        result
+    }
+
+    /// Sectors per request: what fits the DMA buffer, capped by the
+    /// driver's own limit.
+    fn max_request_sectors(&self, sector_size: usize) -> u32 {
+        let fits = (Self::MAX_TRANSFER_SIZE / sector_size) as u32;
+        match self.driver.info().max_sectors_per_request {
+            0 => fits,
+            limit => fits.min(limit),
+        }
+    }
+
     /// Wait for a specific request to complete.
     fn wait_for_completion(&mut self, request_id: u32) -> Result<(), UnifiedBlockIoError> {
-        let start = crate::mainloop::runner::get_tsc();
+        let start = (self.now)();
 
         loop {
             if let Some(completion) = self.driver.poll_completion() {
                 if completion.request_id == request_id {
-                    if completion.status == 0 {
-                        return Ok(());
-                    } else {
-                        return Err(UnifiedBlockIoError::DriverError(BlockError::IoError));
-                    }
+                    return match completion.status {
+                        0 => Ok(()),
+                        STATUS_UNSUPPORTED => {
+                            Err(UnifiedBlockIoError::Protocol(BlockError::Unsupported))
+                        }
+                        _ => Err(UnifiedBlockIoError::Media),
+                    };
                 }
             }
 
-            let now = crate::mainloop::runner::get_tsc();
+            let now = (self.now)();
             if now.wrapping_sub(start) > self.timeout_ticks {
                 return Err(UnifiedBlockIoError::Timeout);
             }
@@ -414,6 +365,42 @@ impl<'a, D: BlockDriver> GenericBlockIo<'a, D> {
         }
     }
 
+    /// Submit one request on the DMA buffer and wait for it, retrying
+    /// transient failures.
+    fn submit_and_wait(
+        &mut self,
+        sector: u64,
+        num_sectors: u32,
+        write: bool,
+    ) -> Result<(), UnifiedBlockIoError> {
+        let mut retries_left = self.retries;
+        loop {
+            while self.driver.poll_completion().is_some() {}
+
+            let request_id = self.next_request_id;
+            self.next_request_id = self.next_request_id.wrapping_add(1);
+
+            let phys = self.dma_buffer_phys;
+            let result = if write {
+                self.driver
+                    .submit_write(sector, phys, num_sectors, request_id)
+            } else {
+                self.driver
+                    .submit_read(sector, phys, num_sectors, request_id)
+            }
+            .map_err(UnifiedBlockIoError::from)
+            .and_then(|()| {
+                self.driver.notify();
+                self.wait_for_completion(request_id)
+            });
+
+            match result {
+                Err(e) if e.is_transient() && retries_left > 0 => retries_left -= 1,
+                result => return result,
+            }
+        }
+    }
+
     /// Perform a synchronous read.
     fn sync_read(
         &mut self,
@@ -428,18 +415,7 @@ impl<'a, D: BlockDriver> GenericBlockIo<'a, D> {
             return Err(UnifiedBlockIoError::BufferAlignment);
         }
 
-        while self.driver.poll_completion().is_some() {}
-
-        let request_id = self.next_request_id;
-        self.next_request_id = self.next_request_id.wrapping_add(1);
-
-        self.driver
-            .submit_read(sector, self.dma_buffer_phys, num_sectors, request_id)
-            .map_err(UnifiedBlockIoError::DriverError)?;
-
-        self.driver.notify();
-
-        self.wait_for_completion(request_id)?;
+        self.submit_and_wait(sector, num_sectors, false)?;
 
         dst.copy_from_slice(&self.dma_buffer[..bytes_needed]);
 
@@ -462,44 +438,20 @@ impl<'a, D: BlockDriver> GenericBlockIo<'a, D> {
 
         self.dma_buffer[..bytes_needed].copy_from_slice(src);
 
-        while self.driver.poll_completion().is_some() {}
-
-        let request_id = self.next_request_id;
-        self.next_request_id = self.next_request_id.wrapping_add(1);
-
-        self.driver
-            .submit_write(sector, self.dma_buffer_phys, num_sectors, request_id)
-            .map_err(UnifiedBlockIoError::DriverError)?;
-
-        self.driver.notify();
-
-        self.wait_for_completion(request_id)
+        self.submit_and_wait(sector, num_sectors, true)
     }
-}
 
-impl<'a, D: BlockDriver> BlockIo for GenericBlockIo<'a, D> {
-    type Error = UnifiedBlockIoError;
-
-    fn block_size(&self) -> BlockSize {
-        let info = self.driver.info();
-        BlockSize::new(info.sector_size).expect("valid sector size")
-    }
-
-    fn num_blocks(&mut self) -> Result<u64, Self::Error> {
-        let info = self.driver.info();
-        Ok(info.total_sectors)
-    }
-
-    fn read_blocks(&mut self, start_lba: Lba, dst: &mut [u8]) -> Result<(), Self::Error> {
+    /// Read `dst.len()` bytes from `start_lba`, split into requests.
+    fn read(&mut self, start_lba: Lba, dst: &mut [u8]) -> Result<(), UnifiedBlockIoError> {
         let info = self.driver.info();
         let sector_size = info.sector_size as usize;
 
-        if dst.len() % sector_size != 0 {
+        if !dst.len().is_multiple_of(sector_size) {
             return Err(UnifiedBlockIoError::BufferAlignment);
         }
 
         let total_sectors = (dst.len() / sector_size) as u32;
-        let max_sectors_per_request = (Self::MAX_TRANSFER_SIZE / sector_size) as u32;
+        let max_sectors_per_request = self.max_request_sectors(sector_size);
 
         let mut current_sector = start_lba.0;
         let mut remaining = total_sectors;
@@ -523,16 +475,17 @@ impl<'a, D: BlockDriver> BlockIo for GenericBlockIo<'a, D> {
         Ok(())
     }
 
-    fn write_blocks(&mut self, start_lba: Lba, src: &[u8]) -> Result<(), Self::Error> {
+    /// Write `src` from `start_lba`, split into requests.
+    fn write(&mut self, start_lba: Lba, src: &[u8]) -> Result<(), UnifiedBlockIoError> {
         let info = self.driver.info();
         let sector_size = info.sector_size as usize;
 
-        if src.len() % sector_size != 0 {
+        if !src.len().is_multiple_of(sector_size) {
             return Err(UnifiedBlockIoError::BufferAlignment);
         }
 
         let total_sectors = (src.len() / sector_size) as u32;
-        let max_sectors_per_request = (Self::MAX_TRANSFER_SIZE / sector_size) as u32;
+        let max_sectors_per_request = self.max_request_sectors(sector_size);
 
         let mut current_sector = start_lba.0;
         let mut remaining = total_sectors;
@@ -555,17 +508,229 @@ impl<'a, D: BlockDriver> BlockIo for GenericBlockIo<'a, D> {
 
         Ok(())
     }
+}
+
+impl<'a, D: BlockDriver> BlockIo for GenericBlockIo<'a, D> {
+    type Error = UnifiedBlockIoError;
+
+    fn block_size(&self) -> BlockSize {
+        let info = self.driver.info();
+        BlockSize::new(info.sector_size).expect("valid sector size")
+    }
+
+    fn num_blocks(&mut self) -> Result<u64, Self::Error> {
+        let info = self.driver.info();
+        Ok(info.total_sectors)
+    }
+
+    fn read_blocks(&mut self, start_lba: Lba, dst: &mut [u8]) -> Result<(), Self::Error> {
+        let result = self.read(start_lba, dst);
+        self.record(result)
+    }
+
+    fn write_blocks(&mut self, start_lba: Lba, src: &[u8]) -> Result<(), Self::Error> {
+        let result = self.write(start_lba, src);
+        self.record(result)
+    }
 
     fn flush(&mut self) -> Result<(), Self::Error> {
-        self.driver
-            .flush()
-            .map_err(UnifiedBlockIoError::DriverError)
+        let result = self.driver.flush().map_err(UnifiedBlockIoError::from);
+        self.record(result)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::driver::block_traits::{BlockCompletion, BlockDeviceInfo};
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use core::sync::atomic::{AtomicU64, Ordering};
+    use morpheus_core::disk::identity::{DeviceIdentity, MediaKind};
+
+    const SECTORS: u64 = 512;
+
+    static CLOCK: AtomicU64 = AtomicU64::new(0);
+
+    fn tick() -> u64 {
+        CLOCK.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// In-memory disk whose DMA buffer bus address is its CPU address.
+    /// The next requests end as `failures` says (`None`: never
+    /// completes), the rest complete on the next poll.
+    struct MockDisk {
+        data: Vec<u8>,
+        max_sectors: u32,
+        requests: Vec<(u64, u32)>,
+        pending: Vec<BlockCompletion>,
+        failures: Vec<Option<u8>>,
+    }
+
+    impl MockDisk {
+        fn new(max_sectors: u32) -> Self {
+            Self {
+                data: (0..SECTORS as usize * 512)
+                    .map(|i| (i / 512) as u8)
+                    .collect(),
+                max_sectors,
+                requests: Vec::new(),
+                pending: Vec::new(),
+                failures: Vec::new(),
+            }
+        }
+
+        fn submit(&mut self, sector: u64, count: u32, id: u32) {
+            self.requests.push((sector, count));
+            let outcome = match self.failures.is_empty() {
+                true => Some(0),
+                false => self.failures.remove(0),
+            };
+            if let Some(status) = outcome {
+                self.pending.push(BlockCompletion {
+                    request_id: id,
+                    status,
+                    bytes_transferred: count * 512,
+                });
+            }
+        }
+    }
 
-    // Tests would go here with mock driver
+    impl BlockDriver for MockDisk {
+        fn info(&self) -> BlockDeviceInfo {
+            BlockDeviceInfo {
+                total_sectors: SECTORS,
+                sector_size: 512,
+                max_sectors_per_request: self.max_sectors,
+                read_only: false,
+                removable: false,
+                identity: DeviceIdentity::unknown(),
+                media: MediaKind::Unknown,
+            }
+        }
+        fn can_submit(&self) -> bool {
+            true
+        }
+        fn submit_read(
+            &mut self,
+            sector: u64,
+            phys: u64,
+            count: u32,
+            id: u32,
+        ) -> Result<(), BlockError> {
+            let at = sector as usize * 512;
+            let len = count as usize * 512;
+            unsafe {
+                core::ptr::copy_nonoverlapping(self.data[at..].as_ptr(), phys as *mut u8, len)
+            };
+            self.submit(sector, count, id);
+            Ok(())
+        }
+        fn submit_write(
+            &mut self,
+            sector: u64,
+            phys: u64,
+            count: u32,
+            id: u32,
+        ) -> Result<(), BlockError> {
+            let at = sector as usize * 512;
+            let len = count as usize * 512;
+            let dst = self.data[at..at + len].as_mut_ptr();
+            unsafe { core::ptr::copy_nonoverlapping(phys as *const u8, dst, len) };
+            self.submit(sector, count, id);
+            Ok(())
+        }
+        fn poll_completion(&mut self) -> Option<BlockCompletion> {
+            (!self.pending.is_empty()).then(|| self.pending.remove(0))
+        }
+        fn notify(&mut self) {}
+    }
+
+    fn adapter<'a>(disk: &'a mut MockDisk, dma: &'a mut [u8]) -> GenericBlockIo<'a, MockDisk> {
+        let phys = dma.as_mut_ptr() as u64;
+        GenericBlockIo::with_clock(disk, dma, phys, 100, tick).unwrap()
+    }
+
+    #[test]
+    fn test_requests_split_to_fit_buffer_and_driver() {
+        let mut dma = vec![0u8; GenericBlockIo::<MockDisk>::MAX_TRANSFER_SIZE];
+        let mut disk = MockDisk::new(0);
+        let mut dst = vec![0u8; 200 * 512];
+        adapter(&mut disk, &mut dma)
+            .read_blocks(Lba(10), &mut dst)
+            .unwrap();
+        assert_eq!(disk.requests, [(10, 128), (138, 72)]);
+        assert!(dst
+            .chunks(512)
+            .enumerate()
+            .all(|(i, s)| s[0] == (10 + i) as u8));
+
+        // A driver limit below the buffer's wins
+        let mut disk = MockDisk::new(64);
+        let src = vec![0xA5u8; 100 * 512];
+        adapter(&mut disk, &mut dma)
+            .write_blocks(Lba(1), &src)
+            .unwrap();
+        assert_eq!(disk.requests, [(1, 64), (65, 36)]);
+        assert!(disk.data[512..101 * 512].iter().all(|&b| b == 0xA5));
+        assert_eq!(disk.data[101 * 512], 101);
+    }
+
+    #[test]
+    fn test_transient_failures_retried() {
+        let mut dma = vec![0u8; GenericBlockIo::<MockDisk>::MAX_TRANSFER_SIZE];
+        let mut disk = MockDisk::new(0);
+        // A spun-down disk: first attempt times out, second fails
+        disk.failures = vec![None, Some(1)];
+        let mut dst = [0u8; 512];
+        let mut bio = adapter(&mut disk, &mut dma);
+        bio.read_blocks(Lba(7), &mut dst).unwrap();
+        assert_eq!(bio.last_error(), None);
+        assert_eq!(disk.requests, [(7, 1); 3]);
+        assert_eq!(dst[0], 7);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut dma = vec![0u8; GenericBlockIo::<MockDisk>::MAX_TRANSFER_SIZE];
+        let mut disk = MockDisk::new(0);
+        let mut bio = adapter(&mut disk, &mut dma);
+        assert!(matches!(
+            bio.read_blocks(Lba(0), &mut [0u8; 100]),
+            Err(UnifiedBlockIoError::BufferAlignment)
+        ));
+
+        let mut disk = MockDisk::new(0);
+        disk.failures = vec![Some(1); 3];
+        let mut bio = adapter(&mut disk, &mut dma);
+        assert_eq!(
+            bio.read_blocks(Lba(0), &mut [0u8; 512]),
+            Err(UnifiedBlockIoError::Media)
+        );
+        assert_eq!(bio.last_error(), Some(UnifiedBlockIoError::Media));
+        assert_eq!(disk.requests.len(), 3); // Tried, then retried twice
+
+        let mut disk = MockDisk::new(0);
+        disk.failures = vec![Some(STATUS_UNSUPPORTED)];
+        assert_eq!(
+            adapter(&mut disk, &mut dma).read_blocks(Lba(0), &mut [0u8; 512]),
+            Err(UnifiedBlockIoError::Protocol(BlockError::Unsupported))
+        );
+        assert_eq!(disk.requests.len(), 1); // Not worth retrying
+
+        let mut disk = MockDisk::new(0);
+        disk.failures = vec![None; 2];
+        let phys = dma.as_mut_ptr() as u64;
+        let bio = GenericBlockIo::with_clock(&mut disk, &mut dma, phys, 100, tick).unwrap();
+        assert_eq!(
+            bio.with_retries(1).write_blocks(Lba(0), &[0u8; 512]),
+            Err(UnifiedBlockIoError::Timeout)
+        );
+
+        let mut small = [0u8; 512];
+        assert!(matches!(
+            GenericBlockIo::with_clock(&mut MockDisk::new(0), &mut small, 0, 100, tick),
+            Err(UnifiedBlockIoError::BufferAlignment)
+        ));
+    }
 }
@@ -0,0 +1,414 @@
+//! USB Mass Storage Bulk-Only Transport and the SCSI commands it carries.
+//!
+//! Every command is three stages on the bulk pipes: a 31-byte Command
+//! Block Wrapper (CBW) out, an optional data stage, and a 13-byte Command
+//! Status Wrapper (CSW) in. The CBW wraps a SCSI CDB; the CSW echoes the
+//! CBW's tag and reports pass, fail or phase error.
+//!
+//! Everything here is pure byte layout, shared by the driver and its tests.
+//!
+//! # Reference
+//!
+//! - USB Mass Storage Class Bulk-Only Transport 1.0
+//! - SCSI Block Commands (SBC-3), SCSI Primary Commands (SPC-4)
+
+/// CBW signature ("USBC")
+pub const CBW_SIGNATURE: u32 = 0x4342_5355;
+/// CSW signature ("USBS")
+pub const CSW_SIGNATURE: u32 = 0x5342_5355;
+
+/// Command Block Wrapper length
+pub const CBW_LEN: usize = 31;
+/// Command Status Wrapper length
+pub const CSW_LEN: usize = 13;
+
+/// CBW flags: data stage direction device-to-host
+pub const CBW_FLAG_IN: u8 = 0x80;
+
+/// Interface class: Mass Storage
+pub const CLASS_MASS_STORAGE: u8 = 0x08;
+/// Interface subclass: SCSI transparent command set
+pub const SUBCLASS_SCSI: u8 = 0x06;
+/// Interface protocol: Bulk-Only Transport
+pub const PROTOCOL_BOT: u8 = 0x50;
+
+/// Class request: Bulk-Only Mass Storage Reset
+pub const REQUEST_RESET: u8 = 0xFF;
+
+/// SCSI operation codes
+pub mod scsi {
+    pub const TEST_UNIT_READY: u8 = 0x00;
+    pub const REQUEST_SENSE: u8 = 0x03;
+    pub const INQUIRY: u8 = 0x12;
+    pub const MODE_SENSE_6: u8 = 0x1A;
+    pub const START_STOP_UNIT: u8 = 0x1B;
+    pub const READ_CAPACITY_10: u8 = 0x25;
+    pub const READ_10: u8 = 0x28;
+    pub const WRITE_10: u8 = 0x2A;
+    pub const SYNCHRONIZE_CACHE_10: u8 = 0x35;
+    pub const READ_16: u8 = 0x88;
+    pub const WRITE_16: u8 = 0x8A;
+    pub const SERVICE_ACTION_IN_16: u8 = 0x9E;
+    /// SERVICE ACTION IN(16) action for READ CAPACITY(16)
+    pub const SA_READ_CAPACITY_16: u8 = 0x10;
+}
+
+/// Bytes of INQUIRY data requested
+pub const INQUIRY_LEN: u8 = 36;
+/// Bytes of fixed-format sense data requested
+pub const SENSE_LEN: u8 = 18;
+
+/// A SCSI command descriptor block, up to 16 bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cdb {
+    pub bytes: [u8; 16],
+    pub len: u8,
+}
+
+impl Cdb {
+    fn new(len: u8) -> Self {
+        Self {
+            bytes: [0; 16],
+            len,
+        }
+    }
+
+    fn with(opcode: u8, len: u8) -> Self {
+        let mut cdb = Self::new(len);
+        cdb.bytes[0] = opcode;
+        cdb
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.bytes[..self.len as usize]
+    }
+
+    pub fn test_unit_ready() -> Self {
+        Self::with(scsi::TEST_UNIT_READY, 6)
+    }
+
+    pub fn request_sense() -> Self {
+        let mut cdb = Self::with(scsi::REQUEST_SENSE, 6);
+        cdb.bytes[4] = SENSE_LEN;
+        cdb
+    }
+
+    pub fn inquiry() -> Self {
+        let mut cdb = Self::with(scsi::INQUIRY, 6);
+        cdb.bytes[4] = INQUIRY_LEN;
+        cdb
+    }
+
+    /// MODE SENSE(6) of all pages, for the header's write-protect bit
+    pub fn mode_sense(alloc: u8) -> Self {
+        let mut cdb = Self::with(scsi::MODE_SENSE_6, 6);
+        cdb.bytes[2] = 0x3F;
+        cdb.bytes[4] = alloc;
+        cdb
+    }
+
+    /// START STOP UNIT: spin up (`start`) or stop the medium
+    pub fn start_stop(start: bool) -> Self {
+        let mut cdb = Self::with(scsi::START_STOP_UNIT, 6);
+        cdb.bytes[4] = start as u8;
+        cdb
+    }
+
+    pub fn read_capacity_10() -> Self {
+        Self::with(scsi::READ_CAPACITY_10, 10)
+    }
+
+    pub fn read_capacity_16() -> Self {
+        let mut cdb = Self::with(scsi::SERVICE_ACTION_IN_16, 16);
+        cdb.bytes[1] = scsi::SA_READ_CAPACITY_16;
+        cdb.bytes[10..14].copy_from_slice(&32u32.to_be_bytes());
+        cdb
+    }
+
+    pub fn synchronize_cache() -> Self {
+        Self::with(scsi::SYNCHRONIZE_CACHE_10, 10)
+    }
+
+    /// READ or WRITE of `count` blocks at `lba`: the 10-byte form while
+    /// the range fits in 32 bits, the 16-byte form beyond
+    pub fn read_write(write: bool, lba: u64, count: u16) -> Self {
+        if lba + count as u64 <= u32::MAX as u64 + 1 {
+            let opcode = if write { scsi::WRITE_10 } else { scsi::READ_10 };
+            let mut cdb = Self::with(opcode, 10);
+            cdb.bytes[2..6].copy_from_slice(&(lba as u32).to_be_bytes());
+            cdb.bytes[7..9].copy_from_slice(&count.to_be_bytes());
+            cdb
+        } else {
+            let opcode = if write { scsi::WRITE_16 } else { scsi::READ_16 };
+            let mut cdb = Self::with(opcode, 16);
+            cdb.bytes[2..10].copy_from_slice(&lba.to_be_bytes());
+            cdb.bytes[10..14].copy_from_slice(&(count as u32).to_be_bytes());
+            cdb
+        }
+    }
+}
+
+/// Build a CBW for LUN 0 into `out`.
+pub fn build_cbw(out: &mut [u8; CBW_LEN], tag: u32, data_len: u32, data_in: bool, cdb: &Cdb) {
+    *out = [0; CBW_LEN];
+    out[0..4].copy_from_slice(&CBW_SIGNATURE.to_le_bytes());
+    out[4..8].copy_from_slice(&tag.to_le_bytes());
+    out[8..12].copy_from_slice(&data_len.to_le_bytes());
+    out[12] = if data_in { CBW_FLAG_IN } else { 0 };
+    out[13] = 0; // LUN
+    out[14] = cdb.len;
+    out[15..15 + cdb.len as usize].copy_from_slice(cdb.as_slice());
+}
+
+/// CSW status
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CswStatus {
+    /// Command passed; `residue` bytes of the data stage weren't moved
+    Passed { residue: u32 },
+    /// Command failed: REQUEST SENSE says why
+    Failed,
+    /// Phase error: the device needs a reset recovery
+    PhaseError,
+}
+
+/// Parse a CSW answering the CBW with `tag`. `None` if it isn't a valid
+/// CSW for that command, which is handled like a phase error.
+pub fn parse_csw(csw: &[u8], tag: u32) -> Option<CswStatus> {
+    if csw.len() < CSW_LEN {
+        return None;
+    }
+    let field = |at: usize| u32::from_le_bytes([csw[at], csw[at + 1], csw[at + 2], csw[at + 3]]);
+    if field(0) != CSW_SIGNATURE || field(4) != tag {
+        return None;
+    }
+    match csw[12] {
+        0 => Some(CswStatus::Passed { residue: field(8) }),
+        1 => Some(CswStatus::Failed),
+        2 => Some(CswStatus::PhaseError),
+        _ => None,
+    }
+}
+
+/// (last LBA, block size) from READ CAPACITY(10) data
+pub fn parse_capacity_10(data: &[u8]) -> (u64, u32) {
+    let last = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
+    let block = u32::from_be_bytes([data[4], data[5], data[6], data[7]]);
+    (last as u64, block)
+}
+
+/// (last LBA, block size) from READ CAPACITY(16) data
+pub fn parse_capacity_16(data: &[u8]) -> (u64, u32) {
+    let mut last = [0u8; 8];
+    last.copy_from_slice(&data[0..8]);
+    let block = u32::from_be_bytes([data[8], data[9], data[10], data[11]]);
+    (u64::from_be_bytes(last), block)
+}
+
+/// Sense key from fixed-format sense data
+pub fn sense_key(sense: &[u8]) -> u8 {
+    sense.get(2).map_or(0, |b| b & 0x0F)
+}
+
+/// Sense key: NOT READY
+pub const SENSE_NOT_READY: u8 = 0x02;
+/// Sense key: UNIT ATTENTION (medium changed, reset occurred)
+pub const SENSE_UNIT_ATTENTION: u8 = 0x06;
+
+/// A bulk endpoint, from its endpoint descriptor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BulkEndpoint {
+    /// Endpoint address, direction bit included
+    pub address: u8,
+    pub max_packet: u16,
+    /// SuperSpeed companion bMaxBurst (0 below SuperSpeed)
+    pub max_burst: u8,
+}
+
+impl BulkEndpoint {
+    /// xHCI Device Context Index: 2n for OUT endpoint n, 2n+1 for IN
+    pub fn dci(&self) -> u8 {
+        (self.address & 0x0F) * 2 + (self.address >> 7)
+    }
+}
+
+/// A Bulk-Only mass storage interface found in a configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MscInterface {
+    /// bConfigurationValue to select
+    pub configuration: u8,
+    pub interface: u8,
+    pub bulk_in: BulkEndpoint,
+    pub bulk_out: BulkEndpoint,
+}
+
+const DESC_CONFIGURATION: u8 = 2;
+const DESC_INTERFACE: u8 = 4;
+const DESC_ENDPOINT: u8 = 5;
+const DESC_SS_ENDPOINT_COMPANION: u8 = 48;
+
+/// Find the first Bulk-Only SCSI interface with a bulk pipe each way in a
+/// full configuration descriptor.
+pub fn find_msc_interface(config: &[u8]) -> Option<MscInterface> {
+    if config.len() < 9 || config[1] != DESC_CONFIGURATION {
+        return None;
+    }
+    let configuration = config[5];
+
+    let mut found: Option<MscInterface> = None;
+    // Which endpoint the next companion descriptor belongs to
+    let mut last_in = true;
+    let mut at = 0;
+    while at + 2 <= config.len() {
+        let len = config[at] as usize;
+        if len < 2 || at + len > config.len() {
+            break;
+        }
+        let desc = &config[at..at + len];
+        match desc[1] {
+            DESC_INTERFACE if len >= 9 => {
+                if let Some(msc) = found {
+                    if msc.bulk_in.address != 0 && msc.bulk_out.address != 0 {
+                        return found;
+                    }
+                }
+                // Alternate setting 0: UAS devices offer UAS as setting 1
+                found = (desc[3] == 0
+                    && desc[5] == CLASS_MASS_STORAGE
+                    && desc[6] == SUBCLASS_SCSI
+                    && desc[7] == PROTOCOL_BOT)
+                    .then_some(MscInterface {
+                        configuration,
+                        interface: desc[2],
+                        bulk_in: BulkEndpoint::default(),
+                        bulk_out: BulkEndpoint::default(),
+                    });
+            }
+            DESC_ENDPOINT if len >= 7 => {
+                // Bulk endpoints only (bmAttributes transfer type 2)
+                if let (Some(msc), 2) = (found.as_mut(), desc[3] & 0x03) {
+                    let endpoint = BulkEndpoint {
+                        address: desc[2],
+                        max_packet: u16::from_le_bytes([desc[4], desc[5]]) & 0x7FF,
+                        max_burst: 0,
+                    };
+                    last_in = desc[2] & 0x80 != 0;
+                    if last_in {
+                        msc.bulk_in = endpoint;
+                    } else {
+                        msc.bulk_out = endpoint;
+                    }
+                }
+            }
+            DESC_SS_ENDPOINT_COMPANION if len >= 6 => {
+                if let Some(msc) = found.as_mut() {
+                    let endpoint = if last_in {
+                        &mut msc.bulk_in
+                    } else {
+                        &mut msc.bulk_out
+                    };
+                    endpoint.max_burst = desc[2];
+                }
+            }
+            _ => {}
+        }
+        at += len;
+    }
+
+    found.filter(|msc| msc.bulk_in.address != 0 && msc.bulk_out.address != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cbw_layout() {
+        let mut cbw = [0u8; CBW_LEN];
+        build_cbw(&mut cbw, 7, 4096, true, &Cdb::read_write(false, 0x1234, 8));
+        assert_eq!(&cbw[0..4], b"USBC");
+        assert_eq!(&cbw[4..8], &7u32.to_le_bytes());
+        assert_eq!(&cbw[8..12], &4096u32.to_le_bytes());
+        assert_eq!((cbw[12], cbw[13], cbw[14]), (CBW_FLAG_IN, 0, 10));
+        assert_eq!(
+            &cbw[15..25],
+            &[scsi::READ_10, 0, 0, 0, 0x12, 0x34, 0, 0, 8, 0]
+        );
+        assert!(cbw[25..].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_read_write_switches_to_16_byte_form() {
+        // Last block at 2^32 - 1 still fits READ(10)
+        let cdb = Cdb::read_write(true, u32::MAX as u64, 1);
+        assert_eq!((cdb.bytes[0], cdb.len), (scsi::WRITE_10, 10));
+
+        let cdb = Cdb::read_write(true, u32::MAX as u64, 2);
+        assert_eq!((cdb.bytes[0], cdb.len), (scsi::WRITE_16, 16));
+        assert_eq!(&cdb.bytes[2..10], &(u32::MAX as u64).to_be_bytes());
+        assert_eq!(&cdb.bytes[10..14], &2u32.to_be_bytes());
+    }
+
+    #[test]
+    fn test_parse_csw() {
+        let mut csw = [0u8; CSW_LEN];
+        csw[0..4].copy_from_slice(b"USBS");
+        csw[4..8].copy_from_slice(&9u32.to_le_bytes());
+        csw[8..12].copy_from_slice(&512u32.to_le_bytes());
+        assert_eq!(parse_csw(&csw, 9), Some(CswStatus::Passed { residue: 512 }));
+        // Wrong tag: a stale CSW
+        assert_eq!(parse_csw(&csw, 8), None);
+        csw[12] = 1;
+        assert_eq!(parse_csw(&csw, 9), Some(CswStatus::Failed));
+        csw[12] = 2;
+        assert_eq!(parse_csw(&csw, 9), Some(CswStatus::PhaseError));
+        csw[0] = 0;
+        assert_eq!(parse_csw(&csw, 9), None);
+    }
+
+    #[test]
+    fn test_parse_capacity() {
+        let data = [0x00, 0x3B, 0x9F, 0xFF, 0x00, 0x00, 0x02, 0x00];
+        assert_eq!(parse_capacity_10(&data), (0x003B_9FFF, 512));
+
+        let mut data = [0u8; 32];
+        data[0..8].copy_from_slice(&0x1_0000_0000u64.to_be_bytes());
+        data[8..12].copy_from_slice(&4096u32.to_be_bytes());
+        assert_eq!(parse_capacity_16(&data), (0x1_0000_0000, 4096));
+    }
+
+    #[test]
+    fn test_find_msc_interface() {
+        #[rustfmt::skip]
+        let config = [
+            // Configuration 1
+            9, 2, 60, 0, 2, 1, 0, 0x80, 50,
+            // Interface 0: HID, with an interrupt endpoint
+            9, 4, 0, 0, 1, 0x03, 0x01, 0x01, 0,
+            7, 5, 0x83, 0x03, 8, 0, 10,
+            // Interface 1: mass storage, SCSI, Bulk-Only
+            9, 4, 1, 0, 2, 0x08, 0x06, 0x50, 0,
+            7, 5, 0x81, 0x02, 0x00, 0x04, 0,
+            6, 48, 15, 0, 0, 0,
+            7, 5, 0x02, 0x02, 0x00, 0x04, 0,
+            6, 48, 3, 0, 0, 0,
+        ];
+        let msc = find_msc_interface(&config).unwrap();
+        assert_eq!((msc.configuration, msc.interface), (1, 1));
+        assert_eq!(
+            msc.bulk_in,
+            BulkEndpoint {
+                address: 0x81,
+                max_packet: 1024,
+                max_burst: 15
+            }
+        );
+        assert_eq!(msc.bulk_out.max_burst, 3);
+        assert_eq!((msc.bulk_in.dci(), msc.bulk_out.dci()), (3, 4));
+
+        // UAS-only (protocol 0x62) and truncated descriptors don't match
+        let mut uas = config;
+        uas[32] = 0x62;
+        assert_eq!(find_msc_interface(&uas), None);
+        assert_eq!(find_msc_interface(&config[..40]), None);
+    }
+}
@@ -0,0 +1,106 @@
+//! USB mass storage driver initialization and configuration.
+
+/// xHCI page size the driver works in (PAGESIZE bit 0)
+pub const PAGE_SIZE: usize = 4096;
+
+/// Most scratchpad buffers the driver can hand the controller
+pub const MAX_SCRATCHPADS: usize = 32;
+
+/// Offsets of the driver's structures within its DMA region. Each ring
+/// and context gets a page, which satisfies every xHCI alignment and
+/// boundary rule.
+pub mod layout {
+    use super::{MAX_SCRATCHPADS, PAGE_SIZE};
+
+    /// Device Context Base Address Array (slot 0 and 1 used)
+    pub const DCBAA: usize = 0;
+    /// Event Ring Segment Table (one entry)
+    pub const ERST: usize = 0x800;
+    /// Scratchpad Buffer Array
+    pub const SCRATCHPAD_ARRAY: usize = 0xC00;
+    pub const COMMAND_RING: usize = PAGE_SIZE;
+    pub const EVENT_RING: usize = 2 * PAGE_SIZE;
+    pub const INPUT_CONTEXT: usize = 3 * PAGE_SIZE;
+    pub const DEVICE_CONTEXT: usize = 4 * PAGE_SIZE;
+    pub const EP0_RING: usize = 5 * PAGE_SIZE;
+    pub const BULK_IN_RING: usize = 6 * PAGE_SIZE;
+    pub const BULK_OUT_RING: usize = 7 * PAGE_SIZE;
+    /// Command Block Wrapper
+    pub const CBW: usize = 8 * PAGE_SIZE;
+    /// Command Status Wrapper
+    pub const CSW: usize = CBW + 0x40;
+    /// Descriptors and SCSI data the driver reads itself
+    pub const DATA: usize = CBW + 0x100;
+    pub const DATA_LEN: usize = PAGE_SIZE - 0x100;
+    /// Scratchpad buffers, one page each
+    pub const SCRATCHPADS: usize = 9 * PAGE_SIZE;
+    /// Size of the whole region
+    pub const SIZE: usize = SCRATCHPADS + MAX_SCRATCHPADS * PAGE_SIZE;
+}
+
+/// Bytes of DMA memory the driver needs
+pub const USB_MSC_DMA_SIZE: usize = layout::SIZE;
+
+/// USB mass storage driver configuration.
+///
+/// All of the driver's DMA structures are carved out of one region.
+#[derive(Debug, Clone)]
+pub struct UsbMscConfig {
+    /// TSC frequency for timeouts
+    pub tsc_freq: u64,
+
+    /// DMA region: CPU pointer (4K aligned, `USB_MSC_DMA_SIZE` bytes)
+    pub dma_cpu: *mut u8,
+    /// DMA region: Physical/bus address
+    pub dma_phys: u64,
+}
+
+impl UsbMscConfig {
+    /// CPU pointer and bus address of `offset` into the DMA region
+    pub(crate) fn at(&self, offset: usize) -> (*mut u8, u64) {
+        // Safety: offsets come from `layout`, all inside the region
+        (
+            unsafe { self.dma_cpu.add(offset) },
+            self.dma_phys + offset as u64,
+        )
+    }
+}
+
+/// USB mass storage driver initialization errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsbMscInitError {
+    /// Invalid configuration parameters
+    InvalidConfig,
+    /// Controller didn't halt or come out of reset
+    ResetFailed,
+    /// Controller doesn't support 4K pages
+    UnsupportedPageSize,
+    /// Controller wants more scratchpad buffers than the driver provides
+    TooManyScratchpads,
+    /// Controller can't address the DMA region
+    No64BitSupport,
+    /// No mass storage device on any root port
+    NoDeviceFound,
+    /// A controller command failed or timed out
+    CommandFailed,
+    /// A control or bulk transfer failed
+    TransferFailed,
+    /// Device never became ready (no medium)
+    NotReady,
+}
+
+impl core::fmt::Display for UsbMscInitError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::InvalidConfig => write!(f, "Invalid USB configuration"),
+            Self::ResetFailed => write!(f, "xHCI reset failed"),
+            Self::UnsupportedPageSize => write!(f, "xHCI doesn't support 4K pages"),
+            Self::TooManyScratchpads => write!(f, "Too many xHCI scratchpad buffers"),
+            Self::No64BitSupport => write!(f, "64-bit addressing not supported"),
+            Self::NoDeviceFound => write!(f, "No USB mass storage device found"),
+            Self::CommandFailed => write!(f, "xHCI command failed"),
+            Self::TransferFailed => write!(f, "USB transfer failed"),
+            Self::NotReady => write!(f, "USB device not ready"),
+        }
+    }
+}
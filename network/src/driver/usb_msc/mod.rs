@@ -0,0 +1,917 @@
+//! USB mass storage block device driver (xHCI + Bulk-Only Transport).
+//!
+//! Lets a download be written straight to a USB stick instead of the
+//! internal disk.
+//!
+//! # Target Hardware
+//!
+//! Any xHCI controller (PCI class 0x0C0330) with a Bulk-Only SCSI device
+//! (interface class 0x08, subclass 0x06, protocol 0x50) on a root port:
+//! USB sticks, card readers and most disk enclosures.
+//! - The first root port with such a device is used, LUN 0 only
+//! - Devices behind a hub and UAS-only devices aren't supported
+//!
+//! # Architecture
+//!
+//! Unlike AHCI, the controller is driven from Rust through the MMIO
+//! helpers: xHCI keeps its state in memory (rings and contexts), and the
+//! registers are only touched to start things.
+//! - `xhci`: controller reset, rings, commands, contexts, root ports
+//! - `bot`: CBW/CSW and SCSI command layout, descriptor parsing
+//! - Poll-based completion; the interrupter stays disabled
+//!
+//! # Command Flow
+//!
+//! Bulk-Only Transport runs one command at a time. A read or write queues
+//! the CBW on the bulk OUT ring, the data TRBs on the ring of its
+//! direction and the CSW on the bulk IN ring, and rings both doorbells;
+//! `poll_completion` waits for the CSW's transfer event and checks it.
+//! A stalled data stage is cleared and the CSW still read (BOT 6.7);
+//! anything worse (transaction error, phase error, bad CSW) gets a reset
+//! recovery (BOT 5.3.4) and fails the request.
+//!
+//! # DMA Memory Layout
+//!
+//! One 4K-aligned region of `USB_MSC_DMA_SIZE` bytes, a page per ring
+//! and context (see `init::layout`), plus up to 32 scratchpad pages for
+//! controllers that ask for them.
+//!
+//! # Reference
+//!
+//! - eXtensible Host Controller Interface 1.2
+//! - USB Mass Storage Class Bulk-Only Transport 1.0
+//! - USB 3.2 chapter 9 (device framework)
+
+pub mod bot;
+pub mod init;
+pub mod regs;
+pub mod xhci;
+
+use crate::driver::block_traits::{
+    BlockCompletion, BlockDeviceInfo, BlockDriver, BlockDriverInit, BlockError,
+};
+use crate::time::{self, Deadline};
+use bot::{Cdb, CswStatus, MscInterface};
+use core::ptr;
+use morpheus_core::disk::identity::{DeviceIdentity, MediaKind, SERIAL_LEN};
+use regs::{completion, ep, speed, trb, trb_type};
+use xhci::{fragments, Ring, Trb, Xhci, XhciError};
+
+// Re-exports
+pub use init::{UsbMscConfig, UsbMscInitError, USB_MSC_DMA_SIZE};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// CONSTANTS
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// PCI Class code for an xHCI USB controller
+pub const PCI_CLASS_XHCI: u32 = 0x0C0330;
+
+/// Vendors of common xHCI controllers. The block probe matches the class
+/// code, so this only serves `BlockDriverInit`.
+pub const XHCI_VENDOR_IDS: &[u16] = &[
+    0x8086, // Intel
+    0x1022, // AMD
+    0x1B21, // ASMedia
+    0x1912, // Renesas
+    0x1106, // VIA
+    0x1B73, // Fresco Logic
+    0x1B36, // Red Hat (QEMU qemu-xhci)
+];
+
+/// Largest data stage of one request
+pub const MAX_TRANSFER_BYTES: u32 = 64 * 1024;
+
+/// Wait for a control transfer
+const CONTROL_TIMEOUT_MS: u64 = 5000;
+/// Wait for a SCSI command the driver issues itself
+const SCSI_TIMEOUT_MS: u64 = 10_000;
+/// Wait for SYNCHRONIZE CACHE
+const FLUSH_TIMEOUT_MS: u64 = 30_000;
+/// How long a device may report "not ready" after it is configured
+const READY_TIMEOUT_MS: u64 = 5000;
+
+/// Standard requests
+const GET_DESCRIPTOR: u8 = 6;
+const SET_CONFIGURATION: u8 = 9;
+const CLEAR_FEATURE: u8 = 1;
+/// Descriptor types
+const DESC_DEVICE: u16 = 1;
+const DESC_CONFIGURATION: u16 = 2;
+const DESC_STRING: u16 = 3;
+/// String descriptor language: US English
+const LANG_EN_US: u16 = 0x0409;
+
+// ═══════════════════════════════════════════════════════════════════════════
+// REQUEST TRACKING
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// The command on the bulk pipes
+#[derive(Debug, Clone, Copy)]
+struct Pending {
+    /// Caller's request ID
+    request_id: u32,
+    /// CBW tag the CSW must echo
+    tag: u32,
+    /// The CSW's TRB as a dequeue pointer (bus address | cycle state)
+    csw: u64,
+    /// Bytes in the data stage
+    length: u32,
+    /// SYNCHRONIZE CACHE, which a device without a cache may reject
+    flush: bool,
+}
+
+impl Pending {
+    fn csw_trb(&self) -> u64 {
+        self.csw & !1
+    }
+}
+
+/// Whether a command the driver ran for itself passed (short data is
+/// fine there)
+fn passed(status: Result<CswStatus, XhciError>) -> bool {
+    matches!(status, Ok(CswStatus::Passed { .. }))
+}
+
+/// Completion status of a finished command: 0 only for a CSW that
+/// passed with the whole data stage moved
+fn completion_status(status: Result<CswStatus, XhciError>) -> u8 {
+    match status {
+        Ok(CswStatus::Passed { residue: 0 }) => 0,
+        _ => 1,
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// DRIVER
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// USB mass storage block device driver.
+///
+/// Drives one Bulk-Only SCSI device on an xHCI root port.
+pub struct UsbMscDriver {
+    /// Host controller
+    hc: Xhci,
+    /// Device slot (0 while no device is attached)
+    slot: u8,
+    /// Root port the device is on (1-based)
+    port: u8,
+    /// Interface and bulk endpoints in use
+    msc: MscInterface,
+    /// Transfer rings: default control pipe and the bulk pipes
+    ep0: Ring,
+    bulk_in: Ring,
+    bulk_out: Ring,
+    /// Device information
+    info: BlockDeviceInfo,
+    /// Next CBW tag
+    next_tag: u32,
+    /// Command in flight
+    pending: Option<Pending>,
+    /// The device rejected SYNCHRONIZE CACHE: it has no cache to flush
+    no_cache: bool,
+    /// DMA: Command Block Wrapper (CPU pointer)
+    cbw_cpu: *mut u8,
+    /// DMA: Command Block Wrapper (physical)
+    cbw_phys: u64,
+    /// DMA: Command Status Wrapper (CPU pointer)
+    csw_cpu: *mut u8,
+    /// DMA: Command Status Wrapper (physical)
+    csw_phys: u64,
+    /// DMA: descriptor and SCSI data buffer (CPU pointer)
+    data_cpu: *mut u8,
+    /// DMA: descriptor and SCSI data buffer (physical)
+    data_phys: u64,
+}
+
+impl UsbMscDriver {
+    /// Create and initialize the driver on the first mass storage device
+    /// found on the controller's root ports.
+    ///
+    /// # Safety
+    /// - `mmio_base` must be the xHCI controller's MMIO address (BAR0)
+    /// - `config` must describe a valid DMA region
+    pub unsafe fn new(mmio_base: u64, config: UsbMscConfig) -> Result<Self, UsbMscInitError> {
+        // Validate config
+        if config.dma_cpu.is_null() || !config.dma_phys.is_multiple_of(init::PAGE_SIZE as u64) {
+            return Err(UsbMscInitError::InvalidConfig);
+        }
+
+        // ═══════════════════════════════════════════════════════════════════
+        // STEP 1: Take the controller from the firmware, reset and start it
+        // ═══════════════════════════════════════════════════════════════════
+        let hc = Xhci::init(mmio_base, &config)?;
+
+        let ring = |offset| {
+            let (cpu, phys) = config.at(offset);
+            Ring::new(cpu as *mut Trb, phys)
+        };
+        let (cbw_cpu, cbw_phys) = config.at(init::layout::CBW);
+        let (csw_cpu, csw_phys) = config.at(init::layout::CSW);
+        let (data_cpu, data_phys) = config.at(init::layout::DATA);
+
+        let mut driver = Self {
+            hc,
+            slot: 0,
+            port: 0,
+            msc: MscInterface::default(),
+            ep0: ring(init::layout::EP0_RING),
+            bulk_in: ring(init::layout::BULK_IN_RING),
+            bulk_out: ring(init::layout::BULK_OUT_RING),
+            info: BlockDeviceInfo {
+                total_sectors: 0,
+                sector_size: 512,
+                max_sectors_per_request: 0,
+                read_only: false,
+                removable: true,
+                identity: DeviceIdentity::unknown(),
+                media: MediaKind::Unknown,
+            },
+            next_tag: 1,
+            pending: None,
+            no_cache: false,
+            cbw_cpu,
+            cbw_phys,
+            csw_cpu,
+            csw_phys,
+            data_cpu,
+            data_phys,
+        };
+
+        // ═══════════════════════════════════════════════════════════════════
+        // STEP 2: Let connections settle (USB 2.0 connect debounce)
+        // ═══════════════════════════════════════════════════════════════════
+        time::delay_ms(driver.hc.clock(), 100);
+
+        // ═══════════════════════════════════════════════════════════════════
+        // STEP 3: Attach to the first root port with a mass storage device
+        // ═══════════════════════════════════════════════════════════════════
+        for port in 1..=driver.hc.max_ports() {
+            let Some(speed) = driver.hc.reset_port(port) else {
+                continue; // Nothing connected, or the port didn't enable
+            };
+            if driver.attach(port, speed).is_ok() {
+                return Ok(driver);
+            }
+            driver.detach();
+        }
+
+        Err(UsbMscInitError::NoDeviceFound)
+    }
+
+    /// Address, configure and identify the device on `port`.
+    fn attach(&mut self, port: u8, speed: u8) -> Result<(), UsbMscInitError> {
+        self.slot = self.hc.enable_slot()?;
+        self.port = port;
+        self.address(speed)?;
+
+        // Device descriptor: serial number string index
+        self.control_in(GET_DESCRIPTOR, DESC_DEVICE << 8, 0, 18)?;
+        let serial_index = self.data()[16];
+
+        // Configuration descriptor, header first for its total length
+        self.control_in(GET_DESCRIPTOR, DESC_CONFIGURATION << 8, 0, 9)?;
+        let total =
+            u16::from_le_bytes([self.data()[2], self.data()[3]]).min(init::layout::DATA_LEN as u16);
+        self.control_in(GET_DESCRIPTOR, DESC_CONFIGURATION << 8, 0, total)?;
+        self.msc = bot::find_msc_interface(&self.data()[..total as usize])
+            .ok_or(UsbMscInitError::NoDeviceFound)?;
+
+        self.control(0x00, SET_CONFIGURATION, self.msc.configuration as u16, 0, 0)?;
+        self.configure_endpoints(speed)?;
+
+        let serial = self.serial_number(serial_index);
+        self.identify(&serial)
+    }
+
+    /// Give up on the device in the current slot.
+    fn detach(&mut self) {
+        if self.slot != 0 {
+            self.hc.disable_slot(self.slot);
+            self.slot = 0;
+        }
+        self.pending = None;
+        self.hc.clear_stash();
+    }
+
+    /// Address Device with EP0 set up for the port speed, then fix EP0's
+    /// packet size from the device descriptor where the speed doesn't
+    /// fix it.
+    fn address(&mut self, speed: u8) -> Result<(), UsbMscInitError> {
+        let max_packet = match speed {
+            speed::LOW | speed::FULL => 8,
+            speed::HIGH => 64,
+            _ => 512,
+        };
+        self.ep0.reset();
+        let ep0 = self.ep0.dequeue();
+        let contexts = &mut self.hc.contexts;
+        contexts.begin(0b11);
+        contexts.set_slot(speed, self.port, 1);
+        contexts.set_endpoint(1, ep::TYPE_CONTROL, max_packet, 0, ep0);
+        self.hc
+            .context_command(trb_type::ADDRESS_DEVICE, self.slot)?;
+
+        if max_packet == 8 {
+            // bMaxPacketSize0 is in the first 8 bytes, readable at any size
+            self.control_in(GET_DESCRIPTOR, DESC_DEVICE << 8, 0, 8)?;
+            let actual = self.data()[7] as u16;
+            if actual != max_packet && actual != 0 {
+                let ep0 = self.ep0.dequeue();
+                let contexts = &mut self.hc.contexts;
+                contexts.begin(0b10);
+                contexts.set_endpoint(1, ep::TYPE_CONTROL, actual, 0, ep0);
+                self.hc
+                    .context_command(trb_type::EVALUATE_CONTEXT, self.slot)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Configure Endpoint for the two bulk pipes.
+    fn configure_endpoints(&mut self, speed: u8) -> Result<(), UsbMscInitError> {
+        let (bulk_in, bulk_out) = (self.msc.bulk_in, self.msc.bulk_out);
+        self.bulk_in.reset();
+        self.bulk_out.reset();
+        let (in_ring, out_ring) = (self.bulk_in.dequeue(), self.bulk_out.dequeue());
+
+        let contexts = &mut self.hc.contexts;
+        contexts.begin(1 | 1 << bulk_in.dci() | 1 << bulk_out.dci());
+        contexts.set_slot(speed, self.port, bulk_in.dci().max(bulk_out.dci()));
+        contexts.set_endpoint(
+            bulk_in.dci(),
+            ep::TYPE_BULK_IN,
+            bulk_in.max_packet,
+            bulk_in.max_burst,
+            in_ring,
+        );
+        contexts.set_endpoint(
+            bulk_out.dci(),
+            ep::TYPE_BULK_OUT,
+            bulk_out.max_packet,
+            bulk_out.max_burst,
+            out_ring,
+        );
+        self.hc
+            .context_command(trb_type::CONFIGURE_ENDPOINT, self.slot)?;
+        Ok(())
+    }
+
+    /// The device's serial number string, ASCII (empty if it has none).
+    fn serial_number(&mut self, index: u8) -> ([u8; SERIAL_LEN], usize) {
+        let mut serial = [0u8; SERIAL_LEN];
+        if index == 0
+            || self
+                .control_in(
+                    GET_DESCRIPTOR,
+                    (DESC_STRING << 8) | index as u16,
+                    LANG_EN_US,
+                    255,
+                )
+                .is_err()
+        {
+            return (serial, 0);
+        }
+        // UTF-16LE after the two-byte header
+        let data = self.data();
+        let len = (data[0] as usize).min(255);
+        let mut count = 0;
+        for unit in data[2..len].chunks_exact(2).take(SERIAL_LEN) {
+            serial[count] = if unit[1] == 0 { unit[0] } else { b'?' };
+            count += 1;
+        }
+        (serial, count)
+    }
+
+    /// INQUIRY, wait for the medium, READ CAPACITY and write protection.
+    fn identify(&mut self, serial: &([u8; SERIAL_LEN], usize)) -> Result<(), UsbMscInitError> {
+        if !passed(self.scsi(&Cdb::inquiry(), bot::INQUIRY_LEN as u32, true)) {
+            return Err(UsbMscInitError::TransferFailed);
+        }
+        let inquiry = &self.data()[..bot::INQUIRY_LEN as usize];
+        // Direct-access block devices only (not CD-ROMs)
+        if inquiry[0] & 0x1F != 0 {
+            return Err(UsbMscInitError::NoDeviceFound);
+        }
+        let removable = inquiry[1] & 0x80 != 0;
+        let model = DeviceIdentity::from_scsi_inquiry(inquiry);
+        let identity = DeviceIdentity::new(model.model().as_bytes(), &serial.0[..serial.1]);
+
+        // Card readers report "not ready" without a card, and most devices
+        // a unit attention right after configuration
+        let ready = Deadline::after_ms(self.hc.clock(), READY_TIMEOUT_MS);
+        loop {
+            match self.scsi(&Cdb::test_unit_ready(), 0, false) {
+                Ok(CswStatus::Passed { .. }) => break,
+                Ok(CswStatus::Failed) => {
+                    // Fetching the sense data clears the condition
+                    let _ = self.scsi(&Cdb::request_sense(), bot::SENSE_LEN as u32, true);
+                }
+                _ => {}
+            }
+            if ready.expired() {
+                return Err(UsbMscInitError::NotReady);
+            }
+            time::delay_ms(self.hc.clock(), 50);
+        }
+
+        if !passed(self.scsi(&Cdb::read_capacity_10(), 8, true)) {
+            return Err(UsbMscInitError::TransferFailed);
+        }
+        let (mut last, mut block) = bot::parse_capacity_10(self.data());
+        if last == u32::MAX as u64 {
+            // Over 2 TiB: only the 16-byte form has room
+            if !passed(self.scsi(&Cdb::read_capacity_16(), 32, true)) {
+                return Err(UsbMscInitError::TransferFailed);
+            }
+            (last, block) = bot::parse_capacity_16(self.data());
+        }
+        if !block.is_power_of_two() || !(512..=4096).contains(&block) {
+            return Err(UsbMscInitError::TransferFailed);
+        }
+
+        // Write-protect switch: bit 7 of the mode parameter header's
+        // device-specific byte. Devices that don't answer are writable.
+        let read_only =
+            passed(self.scsi(&Cdb::mode_sense(4), 4, true)) && self.data()[2] & 0x80 != 0;
+
+        self.info = BlockDeviceInfo {
+            total_sectors: last + 1,
+            sector_size: block,
+            max_sectors_per_request: MAX_TRANSFER_BYTES / block,
+            read_only,
+            removable,
+            identity,
+            media: MediaKind::Unknown,
+        };
+        Ok(())
+    }
+
+    /// The driver's own data buffer
+    fn data(&self) -> &[u8] {
+        unsafe { core::slice::from_raw_parts(self.data_cpu, init::layout::DATA_LEN) }
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════
+    // CONTROL TRANSFERS
+    // ═══════════════════════════════════════════════════════════════════════
+
+    /// Standard device-to-host request with `length` bytes into the data
+    /// buffer
+    fn control_in(
+        &mut self,
+        request: u8,
+        value: u16,
+        index: u16,
+        length: u16,
+    ) -> Result<(), XhciError> {
+        self.control(0x80, request, value, index, length)
+    }
+
+    /// Run a control transfer on EP0, its data stage in the data buffer.
+    fn control(
+        &mut self,
+        request_type: u8,
+        request: u8,
+        value: u16,
+        index: u16,
+        length: u16,
+    ) -> Result<(), XhciError> {
+        let data_in = request_type & 0x80 != 0;
+        let setup = request_type as u64
+            | (request as u64) << 8
+            | (value as u64) << 16
+            | (index as u64) << 32
+            | (length as u64) << 48;
+        let transfer_type = match (length, data_in) {
+            (0, _) => trb::TRT_NONE,
+            (_, true) => trb::TRT_IN,
+            (_, false) => trb::TRT_OUT,
+        };
+        let direction = |input: bool| if input { trb::DIR_IN } else { 0 };
+
+        self.ep0.push(Trb::new(
+            setup,
+            8,
+            (trb_type::SETUP << trb::TYPE_SHIFT) | trb::IDT | transfer_type,
+        ));
+        if length > 0 {
+            self.ep0.push(Trb::new(
+                self.data_phys,
+                length as u32,
+                (trb_type::DATA << trb::TYPE_SHIFT) | direction(data_in),
+            ));
+        }
+        // The status stage runs opposite to the data stage
+        self.ep0.push(Trb::new(
+            0,
+            0,
+            (trb_type::STATUS << trb::TYPE_SHIFT) | trb::IOC | direction(length == 0 || !data_in),
+        ));
+        self.hc.ring(self.slot, 1);
+
+        let slot = self.slot;
+        let event = self.hc.wait_event(CONTROL_TIMEOUT_MS, |event| {
+            event.trb_type() == trb_type::TRANSFER_EVENT
+                && event.slot_id() == slot
+                && event.endpoint_id() == 1
+        });
+        match event {
+            Ok(event) if event.succeeded() => Ok(()),
+            Ok(event) => {
+                // A stalled request halts EP0 in the controller as well
+                self.restart_endpoint(1, None);
+                Err(XhciError::Failed(event.completion_code()))
+            }
+            Err(e) => {
+                self.restart_endpoint(1, None);
+                Err(e)
+            }
+        }
+    }
+
+    /// Get endpoint `dci` running again after an error: Reset Endpoint if
+    /// it halted, Stop Endpoint if not, then move its dequeue pointer to
+    /// `resume`, or past everything queued. Returns whether it had halted.
+    fn restart_endpoint(&mut self, dci: u8, resume: Option<u64>) -> bool {
+        let halted = self.hc.contexts.endpoint_state(dci) == ep::STATE_HALTED;
+        if halted {
+            let _ = self
+                .hc
+                .command(Xhci::slot_command(trb_type::RESET_ENDPOINT, self.slot, dci));
+        } else if self.hc.contexts.endpoint_state(dci) == ep::STATE_RUNNING {
+            let _ = self
+                .hc
+                .command(Xhci::slot_command(trb_type::STOP_ENDPOINT, self.slot, dci));
+        }
+
+        let ring = if dci == 1 {
+            &self.ep0
+        } else if dci == self.msc.bulk_in.dci() {
+            &self.bulk_in
+        } else {
+            &self.bulk_out
+        };
+        let mut command = Xhci::slot_command(trb_type::SET_TR_DEQUEUE, self.slot, dci);
+        command.parameter = resume.unwrap_or_else(|| ring.dequeue());
+        let _ = self.hc.command(command);
+        halted
+    }
+
+    /// Clear a halted bulk pipe on both sides and resume it at `resume`.
+    fn clear_halt(&mut self, dci: u8, resume: u64) -> Result<(), XhciError> {
+        self.restart_endpoint(dci, Some(resume));
+        let address = if dci == self.msc.bulk_in.dci() {
+            self.msc.bulk_in.address
+        } else {
+            self.msc.bulk_out.address
+        };
+        // CLEAR_FEATURE(ENDPOINT_HALT) to the endpoint
+        self.control(0x02, CLEAR_FEATURE, 0, address as u16, 0)
+    }
+
+    /// Bulk-Only reset recovery (BOT 5.3.4): reset the device's transport,
+    /// then get both bulk pipes going again with whatever was queued
+    /// dropped.
+    fn reset_recovery(&mut self) {
+        let _ = self.control(0x21, bot::REQUEST_RESET, 0, self.msc.interface as u16, 0);
+        for endpoint in [self.msc.bulk_in, self.msc.bulk_out] {
+            // Clearing a halt resets the device's data toggle; only do it
+            // where the controller's was reset too
+            if self.restart_endpoint(endpoint.dci(), None) {
+                let _ = self.control(0x02, CLEAR_FEATURE, 0, endpoint.address as u16, 0);
+            }
+        }
+        self.pending = None;
+        self.hc.clear_stash();
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════
+    // BULK-ONLY COMMANDS
+    // ═══════════════════════════════════════════════════════════════════════
+
+    /// Queue a command: CBW, data stage and CSW, then ring the doorbells.
+    fn start(
+        &mut self,
+        cdb: &Cdb,
+        data_phys: u64,
+        length: u32,
+        data_in: bool,
+        request_id: u32,
+        flush: bool,
+    ) {
+        // Nothing is outstanding, so any stashed event is stale
+        self.hc.clear_stash();
+
+        let tag = self.next_tag;
+        self.next_tag = self.next_tag.wrapping_add(1);
+        let mut cbw = [0u8; bot::CBW_LEN];
+        bot::build_cbw(&mut cbw, tag, length, data_in, cdb);
+        unsafe {
+            ptr::copy_nonoverlapping(cbw.as_ptr(), self.cbw_cpu, bot::CBW_LEN);
+            ptr::write_bytes(self.csw_cpu, 0, bot::CSW_LEN);
+        }
+
+        let normal = trb_type::NORMAL << trb::TYPE_SHIFT;
+        self.bulk_out
+            .push(Trb::new(self.cbw_phys, bot::CBW_LEN as u32, normal));
+
+        if length > 0 {
+            let (ring, max_packet) = if data_in {
+                (&mut self.bulk_in, self.msc.bulk_in.max_packet)
+            } else {
+                (&mut self.bulk_out, self.msc.bulk_out.max_packet)
+            };
+            let mut remaining = length;
+            for (phys, piece) in fragments(data_phys, length) {
+                remaining -= piece;
+                // TD Size: packets still to come after this TRB
+                let td_size = remaining.div_ceil(max_packet.max(1) as u32).min(31);
+                let chain = if remaining > 0 { trb::CHAIN } else { 0 };
+                ring.push(Trb::new(phys, piece | td_size << 17, normal | chain));
+            }
+        }
+
+        let csw = self.bulk_in.dequeue();
+        self.bulk_in.push(Trb::new(
+            self.csw_phys,
+            bot::CSW_LEN as u32,
+            normal | trb::IOC,
+        ));
+
+        self.pending = Some(Pending {
+            request_id,
+            tag,
+            csw,
+            length,
+            flush,
+        });
+        self.hc.ring(self.slot, self.msc.bulk_out.dci());
+        self.hc.ring(self.slot, self.msc.bulk_in.dci());
+    }
+
+    /// Check on the command in flight; its result once it has finished.
+    fn poll_command(&mut self) -> Option<(Pending, Result<CswStatus, XhciError>)> {
+        let pending = self.pending?;
+
+        while let Some(event) = self.hc.next_event() {
+            if event.trb_type() != trb_type::TRANSFER_EVENT || event.slot_id() != self.slot {
+                continue;
+            }
+            let code = event.completion_code();
+            if matches!(
+                code,
+                completion::STOPPED | completion::STOPPED_LENGTH_INVALID
+            ) {
+                continue;
+            }
+
+            if event.succeeded() && event.parameter == pending.csw_trb() {
+                self.pending = None;
+                let csw = unsafe { core::slice::from_raw_parts(self.csw_cpu, bot::CSW_LEN) };
+                return match bot::parse_csw(csw, pending.tag) {
+                    Some(CswStatus::PhaseError) | None => {
+                        self.reset_recovery();
+                        Some((pending, Ok(CswStatus::PhaseError)))
+                    }
+                    Some(status) => Some((pending, Ok(status))),
+                };
+            }
+
+            if event.succeeded() {
+                continue;
+            }
+
+            if code == completion::STALL && event.parameter != pending.csw_trb() {
+                // The device ended the data stage early (BOT 6.7.2, 6.7.3):
+                // clear the halt and go on to read the CSW
+                let dci = event.endpoint_id();
+                let resume = if dci == self.msc.bulk_in.dci() {
+                    pending.csw
+                } else {
+                    self.bulk_out.dequeue()
+                };
+                if self.clear_halt(dci, resume).is_ok() {
+                    self.hc.ring(self.slot, self.msc.bulk_in.dci());
+                    continue;
+                }
+            }
+
+            // The rest of the command is lost
+            self.reset_recovery();
+            return Some((pending, Err(XhciError::Failed(code))));
+        }
+
+        if self.hc.failed() {
+            self.pending = None;
+            return Some((pending, Err(XhciError::Failed(0))));
+        }
+        None
+    }
+
+    /// Run a SCSI command with its data stage in the data buffer and wait
+    /// for it.
+    fn scsi(&mut self, cdb: &Cdb, length: u32, data_in: bool) -> Result<CswStatus, XhciError> {
+        self.start(cdb, self.data_phys, length, data_in, 0, false);
+        self.wait(SCSI_TIMEOUT_MS)
+    }
+
+    /// Wait for the command in flight to finish.
+    fn wait(&mut self, ms: u64) -> Result<CswStatus, XhciError> {
+        let deadline = Deadline::after_ms(self.hc.clock(), ms);
+        loop {
+            if let Some((_, status)) = self.poll_command() {
+                return status;
+            }
+            if deadline.expired() {
+                self.reset_recovery();
+                return Err(XhciError::Timeout);
+            }
+            core::hint::spin_loop();
+        }
+    }
+
+    /// Queue a READ or WRITE for the caller.
+    fn submit(
+        &mut self,
+        write: bool,
+        sector: u64,
+        buffer_phys: u64,
+        num_sectors: u32,
+        request_id: u32,
+    ) -> Result<(), BlockError> {
+        // Validate
+        if sector + num_sectors as u64 > self.info.total_sectors {
+            return Err(BlockError::InvalidSector);
+        }
+        if num_sectors > self.info.max_sectors_per_request {
+            return Err(BlockError::RequestTooLarge);
+        }
+        if self.pending.is_some() {
+            return Err(BlockError::QueueFull);
+        }
+
+        let cdb = Cdb::read_write(write, sector, num_sectors as u16);
+        let length = num_sectors * self.info.sector_size;
+        self.start(&cdb, buffer_phys, length, !write, request_id, false);
+        Ok(())
+    }
+
+    /// Root port the device is on (1-based)
+    pub fn port(&self) -> u8 {
+        self.port
+    }
+}
+
+impl BlockDriver for UsbMscDriver {
+    fn info(&self) -> BlockDeviceInfo {
+        self.info
+    }
+
+    fn can_submit(&self) -> bool {
+        self.pending.is_none()
+    }
+
+    fn submit_read(
+        &mut self,
+        sector: u64,
+        buffer_phys: u64,
+        num_sectors: u32,
+        request_id: u32,
+    ) -> Result<(), BlockError> {
+        self.submit(false, sector, buffer_phys, num_sectors, request_id)
+    }
+
+    fn submit_write(
+        &mut self,
+        sector: u64,
+        buffer_phys: u64,
+        num_sectors: u32,
+        request_id: u32,
+    ) -> Result<(), BlockError> {
+        if self.info.read_only {
+            return Err(BlockError::ReadOnly);
+        }
+        self.submit(true, sector, buffer_phys, num_sectors, request_id)
+    }
+
+    fn poll_completion(&mut self) -> Option<BlockCompletion> {
+        let (pending, status) = self.poll_command()?;
+
+        let status = if pending.flush && status == Ok(CswStatus::Failed) {
+            // Rejected SYNCHRONIZE CACHE: nothing cached, so nothing lost
+            self.no_cache = true;
+            0
+        } else {
+            completion_status(status)
+        };
+
+        Some(BlockCompletion {
+            request_id: pending.request_id,
+            status,
+            bytes_transferred: if status == 0 { pending.length } else { 0 },
+        })
+    }
+
+    fn notify(&mut self) {
+        // Doorbells are rung as each command is queued
+    }
+
+    fn flush(&mut self) -> Result<(), BlockError> {
+        if self.pending.is_some() {
+            return Err(BlockError::QueueFull);
+        }
+        if self.no_cache {
+            return Ok(());
+        }
+
+        self.start(&Cdb::synchronize_cache(), 0, 0, false, 0, true);
+        match self.wait(FLUSH_TIMEOUT_MS)? {
+            CswStatus::Passed { .. } => Ok(()),
+            CswStatus::Failed => {
+                self.no_cache = true;
+                Ok(())
+            }
+            CswStatus::PhaseError => Err(BlockError::DeviceError),
+        }
+    }
+
+    fn submit_flush(&mut self, request_id: u32) -> Result<(), BlockError> {
+        if self.no_cache {
+            return Err(BlockError::Unsupported);
+        }
+        if self.pending.is_some() {
+            return Err(BlockError::QueueFull);
+        }
+
+        self.start(&Cdb::synchronize_cache(), 0, 0, false, request_id, true);
+        Ok(())
+    }
+
+    fn spin_down(&mut self) -> Result<(), BlockError> {
+        self.flush()?;
+
+        // START STOP UNIT with START clear: sticks ignore it, enclosures
+        // park their disk
+        self.start(&Cdb::start_stop(false), 0, 0, false, 0, false);
+        match self.wait(SCSI_TIMEOUT_MS)? {
+            CswStatus::Passed { .. } => Ok(()),
+            CswStatus::Failed => Err(BlockError::Unsupported),
+            CswStatus::PhaseError => Err(BlockError::DeviceError),
+        }
+    }
+
+    fn in_flight(&self) -> usize {
+        self.pending.is_some() as usize
+    }
+}
+
+impl BlockDriverInit for UsbMscDriver {
+    type Error = UsbMscInitError;
+    type Config = UsbMscConfig;
+
+    fn supported_vendors() -> &'static [u16] {
+        XHCI_VENDOR_IDS
+    }
+
+    fn supported_devices() -> &'static [u16] {
+        &[]
+    }
+
+    /// xHCI controllers are found by class code, not device ID
+    fn supports_device(vendor: u16, _device: u16) -> bool {
+        XHCI_VENDOR_IDS.contains(&vendor)
+    }
+
+    unsafe fn create(mmio_base: u64, config: Self::Config) -> Result<Self, Self::Error> {
+        Self::new(mmio_base, config)
+    }
+}
+
+// Safety: UsbMscDriver only contains raw pointers that are not shared
+unsafe impl Send for UsbMscDriver {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_completion_status() {
+        assert_eq!(completion_status(Ok(CswStatus::Passed { residue: 0 })), 0);
+        // A short data stage is a failed read or write
+        assert_eq!(completion_status(Ok(CswStatus::Passed { residue: 512 })), 1);
+        assert_eq!(completion_status(Ok(CswStatus::Failed)), 1);
+        assert_eq!(completion_status(Err(XhciError::Timeout)), 1);
+    }
+
+    #[test]
+    fn test_max_transfer_fits_cdb_and_trbs() {
+        // READ(10)/WRITE(10) carry a 16-bit block count
+        assert!(MAX_TRANSFER_BYTES / 512 <= u16::MAX as u32);
+        // An unaligned buffer of the largest request spans two TRBs
+        assert_eq!(fragments(0x1_0200, MAX_TRANSFER_BYTES).count(), 2);
+        // The driver's own structures fit their pages
+        const { assert!(init::layout::CSW + bot::CSW_LEN <= init::layout::DATA) };
+        const { assert!(init::layout::SCRATCHPAD_ARRAY + 8 * init::MAX_SCRATCHPADS <= init::PAGE_SIZE) };
+    }
+}
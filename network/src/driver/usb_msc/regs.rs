@@ -0,0 +1,211 @@
+//! xHCI register definitions and TRB constants.
+//!
+//! Offsets and bit positions from the xHCI 1.2 specification, chapter 5
+//! (registers) and 6.4 (TRBs).
+
+/// Capability registers (offset from BAR0)
+pub mod cap {
+    /// Capability register length (u8): operational registers start here
+    pub const CAPLENGTH: u64 = 0x00;
+    /// Structural parameters 1: MaxSlots, MaxIntrs, MaxPorts
+    pub const HCSPARAMS1: u64 = 0x04;
+    /// Structural parameters 2: scratchpad buffer count
+    pub const HCSPARAMS2: u64 = 0x08;
+    /// Capability parameters 1: AC64, CSZ, xECP
+    pub const HCCPARAMS1: u64 = 0x10;
+    /// Doorbell array offset
+    pub const DBOFF: u64 = 0x14;
+    /// Runtime register space offset
+    pub const RTSOFF: u64 = 0x18;
+}
+
+/// HCCPARAMS1 bits
+pub mod hccparams1 {
+    /// 64-bit addressing capability
+    pub const AC64: u32 = 1 << 0;
+    /// Context size: 64-byte contexts when set, 32-byte otherwise
+    pub const CSZ: u32 = 1 << 2;
+    /// Extended capabilities pointer, in dwords from BAR0
+    pub const XECP_SHIFT: u32 = 16;
+}
+
+/// Operational registers (offset from BAR0 + CAPLENGTH)
+pub mod op {
+    /// USB Command
+    pub const USBCMD: u64 = 0x00;
+    /// USB Status
+    pub const USBSTS: u64 = 0x04;
+    /// Supported page sizes (bit n = 2^(n+12) bytes)
+    pub const PAGESIZE: u64 = 0x08;
+    /// Command Ring Control (64-bit)
+    pub const CRCR: u64 = 0x18;
+    /// Device Context Base Address Array Pointer (64-bit)
+    pub const DCBAAP: u64 = 0x30;
+    /// Configure: MaxSlotsEn
+    pub const CONFIG: u64 = 0x38;
+    /// First port register set; port n (1-based) is at PORTSC + (n-1) * 0x10
+    pub const PORTSC: u64 = 0x400;
+}
+
+/// USBCMD bits
+pub mod usbcmd {
+    /// Run/Stop
+    pub const RS: u32 = 1 << 0;
+    /// Host Controller Reset
+    pub const HCRST: u32 = 1 << 1;
+}
+
+/// USBSTS bits
+pub mod usbsts {
+    /// HC Halted
+    pub const HCH: u32 = 1 << 0;
+    /// Host System Error
+    pub const HSE: u32 = 1 << 2;
+    /// Controller Not Ready
+    pub const CNR: u32 = 1 << 11;
+}
+
+/// PORTSC bits
+pub mod portsc {
+    /// Current Connect Status
+    pub const CCS: u32 = 1 << 0;
+    /// Port Enabled (RW1C: writing 1 disables the port)
+    pub const PED: u32 = 1 << 1;
+    /// Port Reset
+    pub const PR: u32 = 1 << 4;
+    /// Port Power
+    pub const PP: u32 = 1 << 9;
+    /// Port Speed field
+    pub const SPEED_SHIFT: u32 = 10;
+    pub const SPEED_MASK: u32 = 0xF;
+    /// Connect Status Change
+    pub const CSC: u32 = 1 << 17;
+    /// Port Reset Change
+    pub const PRC: u32 = 1 << 21;
+    /// Warm Port Reset Change
+    pub const WRC: u32 = 1 << 19;
+    /// Warm Port Reset (USB3 ports only)
+    pub const WPR: u32 = 1 << 31;
+    /// All RW1C change bits
+    pub const CHANGE_BITS: u32 = 0x00FE_0000;
+    /// Bits written back unchanged: read-only and read/write-preserved
+    /// fields. Everything else (PED and the change bits) is written as 0
+    /// so a read-modify-write doesn't disable the port or ack changes.
+    pub const PRESERVE: u32 = 0x4F00_FFE9;
+}
+
+/// Port speed IDs (default PSI values)
+pub mod speed {
+    pub const FULL: u8 = 1;
+    pub const LOW: u8 = 2;
+    pub const HIGH: u8 = 3;
+    pub const SUPER: u8 = 4;
+    pub const SUPER_PLUS: u8 = 5;
+}
+
+/// Interrupter 0 registers (offset from runtime base + 0x20)
+pub mod intr {
+    /// Offset of interrupter 0 within the runtime registers
+    pub const BASE: u64 = 0x20;
+    /// Interrupter Management
+    pub const IMAN: u64 = 0x00;
+    /// Event Ring Segment Table Size
+    pub const ERSTSZ: u64 = 0x08;
+    /// Event Ring Segment Table Base Address (64-bit)
+    pub const ERSTBA: u64 = 0x10;
+    /// Event Ring Dequeue Pointer (64-bit)
+    pub const ERDP: u64 = 0x18;
+    /// ERDP Event Handler Busy (RW1C)
+    pub const ERDP_EHB: u64 = 1 << 3;
+}
+
+/// USB Legacy Support extended capability
+pub mod legacy {
+    /// Extended capability ID
+    pub const CAP_ID: u8 = 1;
+    /// HC BIOS Owned Semaphore
+    pub const BIOS_OWNED: u32 = 1 << 16;
+    /// HC OS Owned Semaphore
+    pub const OS_OWNED: u32 = 1 << 24;
+    /// USBLEGCTLSTS offset from the capability
+    pub const CTLSTS: u64 = 0x04;
+    /// SMI enable bits in USBLEGCTLSTS
+    pub const SMI_ENABLES: u32 = 0x0000_E011;
+    /// RW1C SMI status bits in USBLEGCTLSTS
+    pub const SMI_STATUS: u32 = 0xE000_0000;
+}
+
+/// TRB fields
+pub mod trb {
+    /// Cycle bit
+    pub const CYCLE: u32 = 1 << 0;
+    /// Link TRB: Toggle Cycle
+    pub const TOGGLE_CYCLE: u32 = 1 << 1;
+    /// Interrupt on Short Packet
+    pub const ISP: u32 = 1 << 2;
+    /// Chain bit
+    pub const CHAIN: u32 = 1 << 4;
+    /// Interrupt On Completion
+    pub const IOC: u32 = 1 << 5;
+    /// Immediate Data (setup stage)
+    pub const IDT: u32 = 1 << 6;
+    /// TRB Type field
+    pub const TYPE_SHIFT: u32 = 10;
+    /// Data/Status stage direction: IN
+    pub const DIR_IN: u32 = 1 << 16;
+    /// Setup stage Transfer Type: no data, OUT data, IN data
+    pub const TRT_NONE: u32 = 0;
+    pub const TRT_OUT: u32 = 2 << 16;
+    pub const TRT_IN: u32 = 3 << 16;
+    /// Slot ID field of commands and events
+    pub const SLOT_SHIFT: u32 = 24;
+    /// Endpoint ID field of endpoint commands and transfer events
+    pub const ENDPOINT_SHIFT: u32 = 16;
+}
+
+/// TRB types
+pub mod trb_type {
+    pub const NORMAL: u32 = 1;
+    pub const SETUP: u32 = 2;
+    pub const DATA: u32 = 3;
+    pub const STATUS: u32 = 4;
+    pub const LINK: u32 = 6;
+    pub const ENABLE_SLOT: u32 = 9;
+    pub const DISABLE_SLOT: u32 = 10;
+    pub const ADDRESS_DEVICE: u32 = 11;
+    pub const CONFIGURE_ENDPOINT: u32 = 12;
+    pub const EVALUATE_CONTEXT: u32 = 13;
+    pub const RESET_ENDPOINT: u32 = 14;
+    pub const STOP_ENDPOINT: u32 = 15;
+    pub const SET_TR_DEQUEUE: u32 = 16;
+    pub const TRANSFER_EVENT: u32 = 32;
+    pub const COMMAND_COMPLETION: u32 = 33;
+    pub const PORT_STATUS_CHANGE: u32 = 34;
+}
+
+/// Completion codes
+pub mod completion {
+    pub const SUCCESS: u8 = 1;
+    pub const DATA_BUFFER_ERROR: u8 = 2;
+    pub const BABBLE: u8 = 3;
+    pub const USB_TRANSACTION_ERROR: u8 = 4;
+    pub const TRB_ERROR: u8 = 5;
+    pub const STALL: u8 = 6;
+    pub const SHORT_PACKET: u8 = 13;
+    /// Transfer stopped by a Stop Endpoint command
+    pub const STOPPED: u8 = 26;
+    pub const STOPPED_LENGTH_INVALID: u8 = 27;
+}
+
+/// Endpoint context fields
+pub mod ep {
+    /// Endpoint types
+    pub const TYPE_BULK_OUT: u32 = 2;
+    pub const TYPE_CONTROL: u32 = 4;
+    pub const TYPE_BULK_IN: u32 = 6;
+    /// Endpoint states
+    pub const STATE_RUNNING: u32 = 1;
+    pub const STATE_HALTED: u32 = 2;
+    /// Error count: retry transaction errors three times
+    pub const CERR: u32 = 3;
+}
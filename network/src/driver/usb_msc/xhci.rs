@@ -0,0 +1,719 @@
+//! xHCI host controller: rings, commands, contexts and root ports.
+//!
+//! Just enough xHCI for one device on a root port, driven by polling: a
+//! command ring, one event ring on interrupter 0 with interrupts off, and
+//! producer rings for the device's endpoints. Hubs aren't supported; the
+//! device has to be plugged into one of the controller's own ports.
+
+use super::init::{layout, UsbMscConfig, UsbMscInitError, MAX_SCRATCHPADS, PAGE_SIZE};
+use super::regs::{
+    cap, completion, hccparams1, intr, legacy, op, portsc, trb, trb_type, usbcmd, usbsts,
+};
+use crate::asm::core::mmio::{read32, read8, write32};
+use crate::driver::block_traits::BlockError;
+use crate::time::{self, Deadline, SystemClock};
+use core::ptr;
+use core::sync::atomic::{fence, Ordering};
+
+/// Bytes per TRB
+pub const TRB_SIZE: usize = 16;
+
+/// TRBs in a one-page ring, the last of a producer ring being its link
+pub const RING_TRBS: usize = PAGE_SIZE / TRB_SIZE;
+
+/// Command timeout; Address Device waits on the device
+const COMMAND_TIMEOUT_MS: u64 = 5000;
+
+/// Transfer events kept while waiting for something else
+const STASH_LEN: usize = 4;
+
+/// Transfer Request Block
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Trb {
+    pub parameter: u64,
+    pub status: u32,
+    pub control: u32,
+}
+
+impl Trb {
+    pub const fn new(parameter: u64, status: u32, control: u32) -> Self {
+        Self {
+            parameter,
+            status,
+            control,
+        }
+    }
+
+    /// A TRB of `kind` with nothing else set
+    pub const fn of_type(kind: u32) -> Self {
+        Self::new(0, 0, kind << trb::TYPE_SHIFT)
+    }
+
+    pub fn trb_type(&self) -> u32 {
+        (self.control >> trb::TYPE_SHIFT) & 0x3F
+    }
+
+    pub fn completion_code(&self) -> u8 {
+        (self.status >> 24) as u8
+    }
+
+    pub fn slot_id(&self) -> u8 {
+        (self.control >> trb::SLOT_SHIFT) as u8
+    }
+
+    pub fn endpoint_id(&self) -> u8 {
+        ((self.control >> trb::ENDPOINT_SHIFT) & 0x1F) as u8
+    }
+
+    /// Bytes of the TRB a transfer event names that weren't transferred
+    pub fn residue(&self) -> u32 {
+        self.status & 0x00FF_FFFF
+    }
+
+    /// Whether a transfer completed, possibly short
+    pub fn succeeded(&self) -> bool {
+        matches!(
+            self.completion_code(),
+            completion::SUCCESS | completion::SHORT_PACKET
+        )
+    }
+}
+
+/// Why a command or transfer didn't complete
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XhciError {
+    /// No completion event in time
+    Timeout,
+    /// Completed with this completion code
+    Failed(u8),
+}
+
+impl From<XhciError> for UsbMscInitError {
+    fn from(_: XhciError) -> Self {
+        Self::CommandFailed
+    }
+}
+
+impl From<XhciError> for BlockError {
+    fn from(e: XhciError) -> Self {
+        match e {
+            XhciError::Timeout => Self::Timeout,
+            XhciError::Failed(_) => Self::DeviceError,
+        }
+    }
+}
+
+/// Split a transfer buffer into TRB-sized pieces: a TRB's buffer may not
+/// cross a 64 KiB boundary.
+pub fn fragments(phys: u64, len: u32) -> impl Iterator<Item = (u64, u32)> {
+    let mut at = phys;
+    let end = phys + len as u64;
+    core::iter::from_fn(move || {
+        if at >= end {
+            return None;
+        }
+        let boundary = (at | 0xFFFF) + 1;
+        let piece = (boundary.min(end) - at) as u32;
+        let fragment = (at, piece);
+        at += piece as u64;
+        Some(fragment)
+    })
+}
+
+/// A producer ring: the command ring or an endpoint's transfer ring.
+///
+/// One page of TRBs, the last a Link TRB back to the first that toggles
+/// the cycle state.
+pub struct Ring {
+    cpu: *mut Trb,
+    phys: u64,
+    enqueue: usize,
+    cycle: bool,
+}
+
+impl Ring {
+    /// Empty ring in `RING_TRBS` TRBs at `cpu`, bus address `phys`.
+    ///
+    /// # Safety
+    /// `cpu` must point to `RING_TRBS` writable TRBs only this ring uses.
+    pub unsafe fn new(cpu: *mut Trb, phys: u64) -> Self {
+        let mut ring = Self {
+            cpu,
+            phys,
+            enqueue: 0,
+            cycle: true,
+        };
+        ring.reset();
+        ring
+    }
+
+    /// Empty the ring, for an endpoint that's being set up again
+    pub fn reset(&mut self) {
+        unsafe { ptr::write_bytes(self.cpu, 0, RING_TRBS) };
+        self.enqueue = 0;
+        self.cycle = true;
+    }
+
+    pub fn phys(&self) -> u64 {
+        self.phys
+    }
+
+    /// Where the controller should resume: the next TRB to be queued,
+    /// with the Dequeue Cycle State in bit 0
+    pub fn dequeue(&self) -> u64 {
+        (self.phys + (self.enqueue * TRB_SIZE) as u64) | self.cycle as u64
+    }
+
+    /// Queue `trb` and return its bus address. The cycle bit is written
+    /// last, so the controller never sees a half-written TRB.
+    pub fn push(&mut self, trb: Trb) -> u64 {
+        let at = self.phys + (self.enqueue * TRB_SIZE) as u64;
+        self.write(self.enqueue, trb);
+        self.enqueue += 1;
+
+        if self.enqueue == RING_TRBS - 1 {
+            // Hand over the link, chained if the TD continues past it
+            let link = Trb::new(
+                self.phys,
+                0,
+                (trb_type::LINK << trb::TYPE_SHIFT)
+                    | trb::TOGGLE_CYCLE
+                    | (trb.control & trb::CHAIN),
+            );
+            self.write(self.enqueue, link);
+            self.enqueue = 0;
+            self.cycle = !self.cycle;
+        }
+        at
+    }
+
+    fn write(&mut self, index: usize, trb: Trb) {
+        let control = (trb.control & !trb::CYCLE) | self.cycle as u32;
+        unsafe {
+            let slot = self.cpu.add(index);
+            ptr::addr_of_mut!((*slot).parameter).write_volatile(trb.parameter);
+            ptr::addr_of_mut!((*slot).status).write_volatile(trb.status);
+            fence(Ordering::Release);
+            ptr::addr_of_mut!((*slot).control).write_volatile(control);
+        }
+    }
+}
+
+/// The event ring's consumer side: one segment of `RING_TRBS` TRBs.
+pub struct EventRing {
+    cpu: *const Trb,
+    phys: u64,
+    dequeue: usize,
+    cycle: bool,
+}
+
+impl EventRing {
+    /// # Safety
+    /// `cpu` must point to `RING_TRBS` writable TRBs only this ring uses.
+    pub unsafe fn new(cpu: *mut Trb, phys: u64) -> Self {
+        ptr::write_bytes(cpu, 0, RING_TRBS);
+        Self {
+            cpu,
+            phys,
+            dequeue: 0,
+            cycle: true,
+        }
+    }
+
+    /// Next event the controller has written, if any
+    pub fn pop(&mut self) -> Option<Trb> {
+        let trb = unsafe {
+            let slot = self.cpu.add(self.dequeue);
+            let control = ptr::addr_of!((*slot).control).read_volatile();
+            if (control & trb::CYCLE != 0) != self.cycle {
+                return None;
+            }
+            fence(Ordering::Acquire);
+            Trb::new(
+                ptr::addr_of!((*slot).parameter).read_volatile(),
+                ptr::addr_of!((*slot).status).read_volatile(),
+                control,
+            )
+        };
+
+        self.dequeue += 1;
+        if self.dequeue == RING_TRBS {
+            self.dequeue = 0;
+            self.cycle = !self.cycle;
+        }
+        Some(trb)
+    }
+
+    /// Bus address of the next event, for ERDP
+    pub fn dequeue_pointer(&self) -> u64 {
+        self.phys + (self.dequeue * TRB_SIZE) as u64
+    }
+}
+
+/// Input and output device contexts of the one slot in use.
+pub struct Contexts {
+    input: *mut u8,
+    input_phys: u64,
+    device: *mut u8,
+    device_phys: u64,
+    /// 32 or 64 bytes per context (HCCPARAMS1.CSZ)
+    size: usize,
+}
+
+impl Contexts {
+    fn dword(base: *mut u8, size: usize, index: usize, dword: usize) -> *mut u32 {
+        unsafe { base.add(index * size + dword * 4) as *mut u32 }
+    }
+
+    fn set(&mut self, index: usize, dword: usize, value: u32) {
+        unsafe { Self::dword(self.input, self.size, index, dword).write_volatile(value) }
+    }
+
+    pub fn input_phys(&self) -> u64 {
+        self.input_phys
+    }
+
+    pub fn device_phys(&self) -> u64 {
+        self.device_phys
+    }
+
+    /// Clear the input context and set its Add Context flags
+    pub fn begin(&mut self, add: u32) {
+        unsafe { ptr::write_bytes(self.input, 0, 33 * self.size) };
+        self.set(0, 1, add);
+    }
+
+    /// Slot context: root port, speed and last valid endpoint
+    pub fn set_slot(&mut self, speed: u8, port: u8, entries: u8) {
+        self.set(1, 0, ((entries as u32) << 27) | ((speed as u32) << 20));
+        self.set(1, 1, (port as u32) << 16);
+    }
+
+    /// Endpoint context for Device Context Index `dci`
+    pub fn set_endpoint(
+        &mut self,
+        dci: u8,
+        ep_type: u32,
+        max_packet: u16,
+        max_burst: u8,
+        ring: u64,
+    ) {
+        use super::regs::ep;
+        let index = dci as usize + 1;
+        self.set(index, 0, 0);
+        self.set(
+            index,
+            1,
+            (ep::CERR << 1)
+                | (ep_type << 3)
+                | ((max_burst as u32) << 8)
+                | ((max_packet as u32) << 16),
+        );
+        self.set(index, 2, ring as u32);
+        self.set(index, 3, (ring >> 32) as u32);
+        // Average TRB length: setup packets on EP0, pages on bulk pipes
+        let average = if ep_type == ep::TYPE_CONTROL { 8 } else { 3072 };
+        self.set(index, 4, average);
+    }
+
+    /// Current state of endpoint `dci`, from the output context
+    pub fn endpoint_state(&self, dci: u8) -> u32 {
+        unsafe { Self::dword(self.device, self.size, dci as usize, 0).read_volatile() & 0x7 }
+    }
+}
+
+/// Host controller registers, command ring and event ring.
+pub struct Xhci {
+    op: u64,
+    runtime: u64,
+    doorbells: u64,
+    max_ports: u8,
+    clock: SystemClock,
+    commands: Ring,
+    events: EventRing,
+    dcbaa: *mut u64,
+    pub contexts: Contexts,
+    /// Transfer events that arrived during `wait_event`, oldest first
+    stash: [Option<Trb>; STASH_LEN],
+}
+
+/// Write a 64-bit register as two dwords, low first
+unsafe fn write64(addr: u64, value: u64) {
+    write32(addr, value as u32);
+    write32(addr + 4, (value >> 32) as u32);
+}
+
+/// Spin until `done` or `ms` pass; whether `done` was seen
+fn wait_for(clock: SystemClock, ms: u64, mut done: impl FnMut() -> bool) -> bool {
+    let deadline = Deadline::after_ms(clock, ms);
+    loop {
+        if done() {
+            return true;
+        }
+        if deadline.expired() {
+            return false;
+        }
+        core::hint::spin_loop();
+    }
+}
+
+impl Xhci {
+    /// Take the controller from the firmware, reset it and start it with
+    /// the rings in `config`'s DMA region.
+    ///
+    /// # Safety
+    /// `base` must be the controller's mapped BAR0 and `config` a valid
+    /// DMA region of `USB_MSC_DMA_SIZE` bytes.
+    pub unsafe fn init(base: u64, config: &UsbMscConfig) -> Result<Self, UsbMscInitError> {
+        let clock = time::active_or_tsc(config.tsc_freq);
+
+        let op = base + read8(base + cap::CAPLENGTH) as u64;
+        let hcs1 = read32(base + cap::HCSPARAMS1);
+        let hcs2 = read32(base + cap::HCSPARAMS2);
+        let hcc1 = read32(base + cap::HCCPARAMS1);
+        let runtime = base + (read32(base + cap::RTSOFF) & !0x1F) as u64;
+        let doorbells = base + (read32(base + cap::DBOFF) & !0x3) as u64;
+        let max_ports = (hcs1 >> 24) as u8;
+
+        if hcc1 & hccparams1::AC64 == 0 && config.dma_phys + layout::SIZE as u64 > 1 << 32 {
+            return Err(UsbMscInitError::No64BitSupport);
+        }
+
+        take_ownership(base, hcc1, clock);
+
+        // Halt, then reset
+        write32(op + op::USBCMD, read32(op + op::USBCMD) & !usbcmd::RS);
+        if !wait_for(clock, 100, || read32(op + op::USBSTS) & usbsts::HCH != 0) {
+            return Err(UsbMscInitError::ResetFailed);
+        }
+        write32(op + op::USBCMD, usbcmd::HCRST);
+        let reset = wait_for(clock, 1000, || {
+            read32(op + op::USBCMD) & usbcmd::HCRST == 0
+                && read32(op + op::USBSTS) & usbsts::CNR == 0
+        });
+        if !reset {
+            return Err(UsbMscInitError::ResetFailed);
+        }
+
+        if read32(op + op::PAGESIZE) & 1 == 0 {
+            return Err(UsbMscInitError::UnsupportedPageSize);
+        }
+        let scratchpads = (((hcs2 >> 21) & 0x1F) << 5 | ((hcs2 >> 27) & 0x1F)) as usize;
+        if scratchpads > MAX_SCRATCHPADS {
+            return Err(UsbMscInitError::TooManyScratchpads);
+        }
+
+        ptr::write_bytes(config.dma_cpu, 0, layout::SIZE);
+
+        // Device context array; entry 0 points at the scratchpad array
+        let (dcbaa, dcbaa_phys) = config.at(layout::DCBAA);
+        let dcbaa = dcbaa as *mut u64;
+        if scratchpads > 0 {
+            let (array, array_phys) = config.at(layout::SCRATCHPAD_ARRAY);
+            for i in 0..scratchpads {
+                let (_, page) = config.at(layout::SCRATCHPADS + i * PAGE_SIZE);
+                (array as *mut u64).add(i).write_volatile(page);
+            }
+            dcbaa.write_volatile(array_phys);
+        }
+
+        // One device slot is all we use
+        write32(op + op::CONFIG, (read32(op + op::CONFIG) & !0xFF) | 1);
+        write64(op + op::DCBAAP, dcbaa_phys);
+
+        let (ring, ring_phys) = config.at(layout::COMMAND_RING);
+        let commands = Ring::new(ring as *mut Trb, ring_phys);
+        write64(op + op::CRCR, ring_phys | 1);
+
+        // Event ring: a single segment, interrupts off
+        let (ring, ring_phys) = config.at(layout::EVENT_RING);
+        let events = EventRing::new(ring as *mut Trb, ring_phys);
+        let (erst, erst_phys) = config.at(layout::ERST);
+        (erst as *mut u64).write_volatile(ring_phys);
+        (erst.add(8) as *mut u32).write_volatile(RING_TRBS as u32);
+        let interrupter = runtime + intr::BASE;
+        write32(interrupter + intr::IMAN, 1);
+        write32(interrupter + intr::ERSTSZ, 1);
+        write64(interrupter + intr::ERDP, ring_phys);
+        write64(interrupter + intr::ERSTBA, erst_phys);
+
+        write32(op + op::USBCMD, usbcmd::RS);
+        if !wait_for(clock, 100, || read32(op + op::USBSTS) & usbsts::HCH == 0) {
+            return Err(UsbMscInitError::ResetFailed);
+        }
+
+        let (input, input_phys) = config.at(layout::INPUT_CONTEXT);
+        let (device, device_phys) = config.at(layout::DEVICE_CONTEXT);
+        let contexts = Contexts {
+            input,
+            input_phys,
+            device,
+            device_phys,
+            size: if hcc1 & hccparams1::CSZ != 0 { 64 } else { 32 },
+        };
+
+        Ok(Self {
+            op,
+            runtime,
+            doorbells,
+            max_ports,
+            clock,
+            commands,
+            events,
+            dcbaa,
+            contexts,
+            stash: [None; STASH_LEN],
+        })
+    }
+
+    pub fn clock(&self) -> SystemClock {
+        self.clock
+    }
+
+    pub fn max_ports(&self) -> u8 {
+        self.max_ports
+    }
+
+    /// Whether the controller stopped on a host system error
+    pub fn failed(&self) -> bool {
+        unsafe { read32(self.op + op::USBSTS) & usbsts::HSE != 0 }
+    }
+
+    /// Ring the doorbell of `slot` for endpoint `target` (0 for the
+    /// command ring on the host doorbell)
+    pub fn ring(&self, slot: u8, target: u8) {
+        unsafe { write32(self.doorbells + slot as u64 * 4, target as u32) }
+    }
+
+    /// Next event: a stashed transfer event, else one off the ring
+    pub fn next_event(&mut self) -> Option<Trb> {
+        match self.stash.iter().position(Option::is_some) {
+            Some(i) => self.stash[i].take(),
+            None => self.pop_event(),
+        }
+    }
+
+    /// Drop stashed transfer events, stale once their transfers are gone
+    pub fn clear_stash(&mut self) {
+        self.stash = [None; STASH_LEN];
+    }
+
+    /// Next event off the ring, acknowledged to the controller
+    fn pop_event(&mut self) -> Option<Trb> {
+        let event = self.events.pop()?;
+        unsafe {
+            write64(
+                self.runtime + intr::BASE + intr::ERDP,
+                self.events.dequeue_pointer() | intr::ERDP_EHB,
+            );
+        }
+        Some(event)
+    }
+
+    /// Wait for an event `wanted` accepts. Other transfer events are
+    /// stashed for `next_event`, anything else is dropped.
+    pub fn wait_event(&mut self, ms: u64, wanted: impl Fn(&Trb) -> bool) -> Result<Trb, XhciError> {
+        let deadline = Deadline::after_ms(self.clock, ms);
+        loop {
+            while let Some(event) = self.pop_event() {
+                if wanted(&event) {
+                    return Ok(event);
+                }
+                if event.trb_type() == trb_type::TRANSFER_EVENT {
+                    if let Some(free) = self.stash.iter_mut().find(|e| e.is_none()) {
+                        *free = Some(event);
+                    }
+                }
+            }
+            if deadline.expired() {
+                return Err(XhciError::Timeout);
+            }
+            core::hint::spin_loop();
+        }
+    }
+
+    /// Run a command and return its completion event
+    pub fn command(&mut self, command: Trb) -> Result<Trb, XhciError> {
+        let at = self.commands.push(command);
+        self.ring(0, 0);
+        let event = self.wait_event(COMMAND_TIMEOUT_MS, |event| {
+            event.trb_type() == trb_type::COMMAND_COMPLETION && event.parameter == at
+        })?;
+        match event.completion_code() {
+            completion::SUCCESS => Ok(event),
+            code => Err(XhciError::Failed(code)),
+        }
+    }
+
+    /// Command of `kind` on `slot`, endpoint `dci` (0 for slot commands)
+    pub fn slot_command(kind: u32, slot: u8, dci: u8) -> Trb {
+        let mut command = Trb::of_type(kind);
+        command.control |= (slot as u32) << trb::SLOT_SHIFT | (dci as u32) << trb::ENDPOINT_SHIFT;
+        command
+    }
+
+    /// Enable a device slot and point it at the device context
+    pub fn enable_slot(&mut self) -> Result<u8, XhciError> {
+        let slot = self.command(Trb::of_type(trb_type::ENABLE_SLOT))?.slot_id();
+        unsafe {
+            self.dcbaa
+                .add(slot as usize)
+                .write_volatile(self.contexts.device_phys());
+        }
+        Ok(slot)
+    }
+
+    pub fn disable_slot(&mut self, slot: u8) {
+        let _ = self.command(Self::slot_command(trb_type::DISABLE_SLOT, slot, 0));
+        unsafe { self.dcbaa.add(slot as usize).write_volatile(0) };
+    }
+
+    /// Run an input-context command (Address Device, Configure Endpoint,
+    /// Evaluate Context) on `slot`
+    pub fn context_command(&mut self, kind: u32, slot: u8) -> Result<(), XhciError> {
+        let mut command = Self::slot_command(kind, slot, 0);
+        command.parameter = self.contexts.input_phys();
+        self.command(command).map(|_| ())
+    }
+
+    fn portsc(&self, port: u8) -> u64 {
+        self.op + op::PORTSC + (port as u64 - 1) * 0x10
+    }
+
+    /// Reset root port `port` (1-based) if something is connected, and
+    /// return the speed of the enabled port.
+    ///
+    /// USB3 ports enable themselves once the link trains; USB2 ports are
+    /// enabled by a port reset.
+    pub fn reset_port(&mut self, port: u8) -> Option<u8> {
+        let reg = self.portsc(port);
+        unsafe {
+            let status = read32(reg);
+            if status & portsc::CCS == 0 || status & portsc::PP == 0 {
+                return None;
+            }
+            if status & portsc::PED == 0 {
+                write32(reg, (status & portsc::PRESERVE) | portsc::PR);
+                let done = wait_for(self.clock, 500, || {
+                    read32(reg) & (portsc::PRC | portsc::WRC) != 0
+                });
+                if !done {
+                    return None;
+                }
+                // Reset recovery time
+                time::delay_ms(self.clock, 10);
+            }
+
+            // Acknowledge whatever changed
+            let status = read32(reg);
+            write32(
+                reg,
+                (status & portsc::PRESERVE) | (status & portsc::CHANGE_BITS),
+            );
+            if status & portsc::PED == 0 {
+                return None;
+            }
+            Some(((status >> portsc::SPEED_SHIFT) & portsc::SPEED_MASK) as u8)
+        }
+    }
+}
+
+/// Ask the firmware to release the controller (USB Legacy Support
+/// capability) and turn off its SMIs. A firmware that doesn't answer
+/// within a second loses the controller anyway.
+unsafe fn take_ownership(base: u64, hcc1: u32, clock: SystemClock) {
+    let mut offset = ((hcc1 >> hccparams1::XECP_SHIFT) & 0xFFFF) as u64 * 4;
+    while offset != 0 {
+        let capability = base + offset;
+        let header = read32(capability);
+        if header as u8 == legacy::CAP_ID {
+            write32(capability, header | legacy::OS_OWNED);
+            wait_for(clock, 1000, || read32(capability) & legacy::BIOS_OWNED == 0);
+            write32(
+                capability,
+                (read32(capability) & !legacy::BIOS_OWNED) | legacy::OS_OWNED,
+            );
+            let control = read32(capability + legacy::CTLSTS);
+            write32(
+                capability + legacy::CTLSTS,
+                (control & !legacy::SMI_ENABLES) | legacy::SMI_STATUS,
+            );
+            return;
+        }
+        let next = ((header >> 8) & 0xFF) as u64;
+        offset = if next == 0 { 0 } else { offset + next * 4 };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fragments_split_at_64k() {
+        let mut pieces = fragments(0x1_F000, 0x1_0000);
+        assert_eq!(pieces.next(), Some((0x1_F000, 0x1000)));
+        assert_eq!(pieces.next(), Some((0x2_0000, 0xF000)));
+        assert_eq!(pieces.next(), None);
+
+        // Aligned buffers stay whole; nothing for an empty one
+        assert_eq!(fragments(0x3_0000, 0x1_0000).count(), 1);
+        assert_eq!(fragments(0x3_0000, 0).count(), 0);
+    }
+
+    #[test]
+    fn test_ring_wraps_through_link() {
+        let mut trbs = [Trb::default(); RING_TRBS];
+        let phys = 0x10_0000;
+        let mut ring = unsafe { Ring::new(trbs.as_mut_ptr(), phys) };
+        assert_eq!(ring.dequeue(), phys | 1);
+
+        let normal = Trb::of_type(trb_type::NORMAL);
+        for _ in 0..RING_TRBS - 2 {
+            ring.push(normal);
+        }
+        // The last data TRB before the link is chained into the next TD
+        let chained = Trb::new(0, 0, normal.control | trb::CHAIN);
+        assert_eq!(
+            ring.push(chained),
+            phys + ((RING_TRBS - 2) * TRB_SIZE) as u64
+        );
+        // Back at the start with the cycle state toggled
+        assert_eq!(ring.dequeue(), phys);
+        let again = ring.push(normal);
+        assert_eq!(again, phys);
+
+        let link = trbs[RING_TRBS - 1];
+        assert_eq!(link.trb_type(), trb_type::LINK);
+        assert_eq!(link.parameter, phys);
+        assert_eq!(
+            link.control & (trb::CYCLE | trb::TOGGLE_CYCLE | trb::CHAIN),
+            trb::CYCLE | trb::TOGGLE_CYCLE | trb::CHAIN
+        );
+        // Overwritten on the second lap with cycle 0
+        assert_eq!(trbs[0].control & trb::CYCLE, 0);
+        assert_eq!(trbs[1].control & trb::CYCLE, trb::CYCLE);
+    }
+
+    #[test]
+    fn test_event_ring_follows_cycle() {
+        let mut trbs = [Trb::default(); RING_TRBS];
+        let base = trbs.as_mut_ptr();
+        let mut events = unsafe { EventRing::new(base, 0x20_0000) };
+        assert_eq!(events.pop(), None);
+
+        // The controller fills the whole segment with cycle 1
+        for i in 0..RING_TRBS {
+            unsafe { base.add(i).write(Trb::new(i as u64, 0, trb::CYCLE)) };
+        }
+        for i in 0..RING_TRBS as u64 {
+            assert_eq!(events.pop().map(|e| e.parameter), Some(i));
+        }
+        // Wrapped: the old events don't count on the next lap
+        assert_eq!(events.dequeue_pointer(), 0x20_0000);
+        assert_eq!(events.pop(), None);
+        unsafe { base.write(Trb::new(99, 0, 0)) };
+        assert_eq!(events.pop().map(|e| e.parameter), Some(99));
+    }
+}
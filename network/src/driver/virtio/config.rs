@@ -3,6 +3,8 @@
 //! # Reference
 //! NETWORK_IMPL_GUIDE.md §4.4, VirtIO Spec §5.1.3
 
+use crate::types::VirtioNetHdr;
+
 /// VirtIO feature bits.
 pub mod features {
     /// VirtIO 1.0+ (modern device).
@@ -17,6 +19,10 @@ pub mod features {
     /// Checksum offload (host handles).
     pub const VIRTIO_NET_F_CSUM: u64 = 1 << 0;
 
+    /// Device may hand us packets with a partial checksum (NEEDS_CSUM)
+    /// and marks packets it already verified (DATA_VALID).
+    pub const VIRTIO_NET_F_GUEST_CSUM: u64 = 1 << 1;
+
     // ═══════════════════════════════════════════════════════════
     // FORBIDDEN FEATURES - DO NOT NEGOTIATE
     // ═══════════════════════════════════════════════════════════
@@ -33,8 +39,15 @@ pub mod features {
     /// Mergeable RX buffers - changes header semantics.
     pub const VIRTIO_NET_F_MRG_RXBUF: u64 = 1 << 15;
 
-    /// Control virtqueue - not needed for basic operation.
+    // ═══════════════════════════════════════════════════════════
+    // CONTROL QUEUE
+    // ═══════════════════════════════════════════════════════════
+
+    /// Control virtqueue (queue 2).
     pub const VIRTIO_NET_F_CTRL_VQ: u64 = 1 << 17;
+
+    /// Control queue takes RX mode and MAC filter commands.
+    pub const VIRTIO_NET_F_CTRL_RX: u64 = 1 << 18;
 }
 
 /// VirtIO device status bits.
@@ -57,14 +70,26 @@ pub mod status {
 pub const REQUIRED_FEATURES: u64 = features::VIRTIO_F_VERSION_1;
 
 /// Desired features (use if available).
-pub const DESIRED_FEATURES: u64 = features::VIRTIO_NET_F_MAC | features::VIRTIO_NET_F_STATUS;
+///
+/// GUEST_CSUM spares the host from checksumming packets that never leave
+/// it (other VMs, the host itself); the RX path completes those partial
+/// checksums. CSUM (TX offload) is left out: the stack always checksums
+/// its own frames, so there is nothing to gain.
+///
+/// The control queue carries the MAC filter table (CTRL_RX).
+pub const DESIRED_FEATURES: u64 = features::VIRTIO_NET_F_MAC
+    | features::VIRTIO_NET_F_STATUS
+    | features::VIRTIO_NET_F_GUEST_CSUM
+    | CONTROL_FEATURES;
+
+/// Features that need the control queue set up.
+pub const CONTROL_FEATURES: u64 = features::VIRTIO_NET_F_CTRL_VQ | features::VIRTIO_NET_F_CTRL_RX;
 
 /// Forbidden features (never negotiate).
 pub const FORBIDDEN_FEATURES: u64 = features::VIRTIO_NET_F_GUEST_TSO4
     | features::VIRTIO_NET_F_GUEST_TSO6
     | features::VIRTIO_NET_F_GUEST_UFO
-    | features::VIRTIO_NET_F_MRG_RXBUF
-    | features::VIRTIO_NET_F_CTRL_VQ;
+    | features::VIRTIO_NET_F_MRG_RXBUF;
 
 /// Feature negotiation error.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -94,6 +119,95 @@ pub fn negotiate_features(device_features: u64) -> Result<u64, FeatureError> {
     Ok(our_features)
 }
 
+/// Negotiate features with a legacy (pre-1.0) device.
+///
+/// Legacy devices only have 32 feature bits and never offer VERSION_1, so
+/// nothing is required: the device is driven as-is with whatever desired
+/// features it has.
+pub fn negotiate_legacy_features(device_features: u64) -> u64 {
+    DESIRED_FEATURES & device_features & !FORBIDDEN_FEATURES & 0xFFFF_FFFF
+}
+
+/// Size of the virtio-net header for a negotiated feature set.
+///
+/// `num_buffers` is only present with VERSION_1 or MRG_RXBUF.
+pub fn net_hdr_len(features: u64) -> usize {
+    if features & (features::VIRTIO_F_VERSION_1 | features::VIRTIO_NET_F_MRG_RXBUF) != 0 {
+        VirtioNetHdr::SIZE
+    } else {
+        VirtioNetHdr::LEGACY_SIZE
+    }
+}
+
+/// Feature bits named in the negotiation report.
+const FEATURE_NAMES: &[(u64, &str)] = &[
+    (features::VIRTIO_NET_F_CSUM, "CSUM"),
+    (features::VIRTIO_NET_F_GUEST_CSUM, "GUEST_CSUM"),
+    (features::VIRTIO_NET_F_MAC, "MAC"),
+    (features::VIRTIO_NET_F_GUEST_TSO4, "GUEST_TSO4"),
+    (features::VIRTIO_NET_F_GUEST_TSO6, "GUEST_TSO6"),
+    (features::VIRTIO_NET_F_GUEST_UFO, "GUEST_UFO"),
+    (features::VIRTIO_NET_F_MRG_RXBUF, "MRG_RXBUF"),
+    (features::VIRTIO_NET_F_STATUS, "STATUS"),
+    (features::VIRTIO_NET_F_CTRL_VQ, "CTRL_VQ"),
+    (features::VIRTIO_NET_F_CTRL_RX, "CTRL_RX"),
+    (features::VIRTIO_F_VERSION_1, "VERSION_1"),
+];
+
+/// What the device offered and what we took, for the boot log.
+///
+/// Throughput differs a lot between hosts depending on which offloads
+/// they offer; the report makes that visible.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeatureReport {
+    /// Features advertised by the device.
+    pub offered: u64,
+    /// Features we accepted.
+    pub negotiated: u64,
+}
+
+impl FeatureReport {
+    /// Offered but not accepted (forbidden, or not useful to us).
+    pub fn declined(&self) -> u64 {
+        self.offered & !self.negotiated
+    }
+
+    /// Whether the device may deliver partial checksums we must complete.
+    pub fn rx_checksum_offload(&self) -> bool {
+        self.negotiated & features::VIRTIO_NET_F_GUEST_CSUM != 0
+    }
+
+    /// Names of the known bits set in `features`.
+    pub fn names(features: u64) -> impl Iterator<Item = &'static str> {
+        FEATURE_NAMES
+            .iter()
+            .filter(move |(bit, _)| features & bit != 0)
+            .map(|(_, name)| *name)
+    }
+
+    /// Write the report to the serial log.
+    pub fn log(&self) {
+        use crate::mainloop::serial::{print, print_hex, println};
+
+        print("[VIRTIO] features offered=");
+        print_hex(self.offered);
+        print(" negotiated=");
+        print_hex(self.negotiated);
+        println("");
+        for (label, bits) in [
+            ("[VIRTIO]   using:   ", self.negotiated),
+            ("[VIRTIO]   declined:", self.declined()),
+        ] {
+            print(label);
+            for name in Self::names(bits) {
+                print(" ");
+                print(name);
+            }
+            println("");
+        }
+    }
+}
+
 /// VirtIO PCI vendor ID.
 pub const VIRTIO_VENDOR_ID: u16 = 0x1AF4;
 
@@ -129,3 +243,71 @@ impl VirtioConfig {
     /// Default buffer size (2KB).
     pub const DEFAULT_BUFFER_SIZE: usize = 2048;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate_legacy_features() {
+        let device = features::VIRTIO_NET_F_MAC
+            | features::VIRTIO_NET_F_MRG_RXBUF
+            | features::VIRTIO_NET_F_GUEST_TSO4;
+        let negotiated = negotiate_legacy_features(device);
+        assert_eq!(negotiated, features::VIRTIO_NET_F_MAC);
+        assert_eq!(
+            negotiate_legacy_features(features::VIRTIO_NET_F_GUEST_CSUM),
+            features::VIRTIO_NET_F_GUEST_CSUM
+        );
+        assert_eq!(net_hdr_len(negotiated), VirtioNetHdr::LEGACY_SIZE);
+
+        // Modern negotiation still insists on VERSION_1
+        assert!(negotiate_features(device).is_err());
+    }
+
+    #[test]
+    fn test_net_hdr_len() {
+        let modern = negotiate_features(features::VIRTIO_F_VERSION_1).unwrap();
+        assert_eq!(net_hdr_len(modern), VirtioNetHdr::SIZE);
+        assert_eq!(
+            net_hdr_len(features::VIRTIO_NET_F_MRG_RXBUF),
+            VirtioNetHdr::SIZE
+        );
+    }
+
+    #[test]
+    fn test_feature_report() {
+        let offered = features::VIRTIO_F_VERSION_1
+            | features::VIRTIO_NET_F_CSUM
+            | features::VIRTIO_NET_F_GUEST_CSUM
+            | features::VIRTIO_NET_F_MAC
+            | features::VIRTIO_NET_F_MRG_RXBUF;
+        let report = FeatureReport {
+            offered,
+            negotiated: negotiate_features(offered).unwrap(),
+        };
+        assert!(report.rx_checksum_offload());
+
+        let mut used = FeatureReport::names(report.negotiated);
+        assert_eq!(used.next(), Some("GUEST_CSUM"));
+        assert_eq!(used.next(), Some("MAC"));
+        assert_eq!(used.next(), Some("VERSION_1"));
+        assert_eq!(used.next(), None);
+
+        let mut declined = FeatureReport::names(report.declined());
+        assert_eq!(declined.next(), Some("CSUM"));
+        assert_eq!(declined.next(), Some("MRG_RXBUF"));
+        assert_eq!(declined.next(), None);
+    }
+
+    #[test]
+    fn test_control_features() {
+        let offered = features::VIRTIO_F_VERSION_1 | CONTROL_FEATURES;
+        let negotiated = negotiate_features(offered).unwrap();
+        assert_eq!(negotiated & CONTROL_FEATURES, CONTROL_FEATURES);
+        assert_eq!(
+            negotiate_legacy_features(CONTROL_FEATURES),
+            CONTROL_FEATURES
+        );
+    }
+}
@@ -0,0 +1,213 @@
+//! VirtIO-net control virtqueue.
+//!
+//! Queue 2, present with CTRL_VQ. Commands are rare (filter changes), so
+//! the queue runs synchronously: one command at a time in descriptors 0-2
+//! (header, data, ack), polled until the device hands it back. The ring
+//! uses the legacy layout on every transport; modern devices take any
+//! properly aligned rings.
+//!
+//! The only command sent is the MAC filter table. Devices start out
+//! promiscuous (QEMU does) and we leave the RX mode alone, so on those
+//! the table only matters once something switches promiscuous mode off.
+//!
+//! # Reference
+//! VirtIO 1.2 §5.1.6.5
+
+use crate::asm::core::barriers::{lfence, sfence};
+use crate::asm::core::tsc::read_tsc;
+use crate::asm::drivers::virtio::notify;
+use crate::driver::mac_filter::{MAX_MULTICAST_FILTERS, MAX_UNICAST_FILTERS};
+use crate::types::{MacAddress, VirtqueueState};
+
+/// Control queue index.
+pub const CTRL_QUEUE_INDEX: u16 = 2;
+
+/// Entries asked for on modern devices (a command takes three).
+pub const CTRL_QUEUE_SIZE: u16 = 8;
+
+/// Command classes.
+pub mod class {
+    /// RX mode (promiscuous, all-multicast, ...).
+    pub const RX: u8 = 0;
+    /// MAC filter table.
+    pub const MAC: u8 = 1;
+}
+
+/// MAC class commands.
+pub mod mac_cmd {
+    /// Replace the unicast and multicast tables.
+    pub const TABLE_SET: u8 = 0;
+}
+
+/// Ack value for success (VIRTIO_NET_OK).
+const VIRTIO_NET_OK: u8 = 0;
+
+const VIRTQ_DESC_F_NEXT: u16 = 1;
+const VIRTQ_DESC_F_WRITE: u16 = 2;
+
+/// Command data offset in the buffer, after the class/command header.
+const DATA_OFFSET: usize = 16;
+
+/// How long the device gets to answer.
+const COMMAND_TIMEOUT_MS: u64 = 100;
+
+/// Largest MAC_TABLE_SET payload a [`MacFilter`](crate::driver::MacFilter)
+/// produces.
+pub const MAC_TABLE_MAX: usize = 8 + 6 * (MAX_UNICAST_FILTERS + MAX_MULTICAST_FILTERS);
+
+/// Control command errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CtrlError {
+    /// Command doesn't fit the buffer.
+    TooLarge,
+    /// Device answered with an error.
+    Rejected,
+    /// Device didn't answer in time; the queue is unusable from then on.
+    Timeout,
+}
+
+/// Encode a MAC_TABLE_SET payload into `out`: the unicast table, then
+/// the multicast table, each a le32 count followed by the addresses.
+///
+/// Returns the payload length.
+pub fn mac_table(unicast: &[MacAddress], multicast: &[MacAddress], out: &mut [u8]) -> usize {
+    let mut len = 0;
+    for table in [unicast, multicast] {
+        out[len..len + 4].copy_from_slice(&(table.len() as u32).to_le_bytes());
+        len += 4;
+        for mac in table {
+            out[len..len + 6].copy_from_slice(mac);
+            len += 6;
+        }
+    }
+    len
+}
+
+/// Synchronous control queue.
+pub struct ControlQueue {
+    /// Ring and command buffer (`buffer_*`, one buffer).
+    state: VirtqueueState,
+    /// TSC frequency for the command timeout.
+    tsc_freq: u64,
+    /// A command timed out; its descriptors may still be in use.
+    stuck: bool,
+}
+
+impl ControlQueue {
+    /// Wrap a control queue set up by init.
+    ///
+    /// # Safety
+    /// `state` must describe the enabled control queue, with the rings
+    /// contiguous from `desc_cpu_ptr` and the command buffer in `buffer_*`.
+    pub unsafe fn new(state: VirtqueueState, tsc_freq: u64) -> Self {
+        Self {
+            state,
+            tsc_freq,
+            stuck: false,
+        }
+    }
+
+    /// CPU pointer to the ring part at bus address `bus`.
+    fn ring_ptr(&self, bus: u64) -> *mut u8 {
+        (self.state.desc_cpu_ptr + (bus - self.state.desc_base)) as *mut u8
+    }
+
+    /// Write descriptor `idx`.
+    unsafe fn write_desc(&self, idx: u16, addr: u64, len: u32, flags: u16, next: u16) {
+        let desc = (self.state.desc_cpu_ptr as *mut u8).add(idx as usize * 16);
+        core::ptr::write_volatile(desc as *mut u64, addr);
+        core::ptr::write_volatile(desc.add(8) as *mut u32, len);
+        core::ptr::write_volatile(desc.add(12) as *mut u16, flags);
+        core::ptr::write_volatile(desc.add(14) as *mut u16, next);
+    }
+
+    /// Send `class`/`command` with `data` and wait for the ack.
+    pub fn send(&mut self, class: u8, command: u8, data: &[u8]) -> Result<(), CtrlError> {
+        if self.stuck {
+            return Err(CtrlError::Timeout);
+        }
+        let ack_offset = DATA_OFFSET + data.len();
+        if ack_offset >= self.state.buffer_size as usize {
+            return Err(CtrlError::TooLarge);
+        }
+
+        let buffer = self.state.buffer_cpu_base as *mut u8;
+        let bus = self.state.buffer_bus_base;
+        let size = self.state.queue_size;
+        let avail = self.ring_ptr(self.state.avail_base);
+        let used = self.ring_ptr(self.state.used_base);
+
+        unsafe {
+            let command_buf = core::slice::from_raw_parts_mut(buffer, ack_offset + 1);
+            command_buf[0] = class;
+            command_buf[1] = command;
+            command_buf[DATA_OFFSET..ack_offset].copy_from_slice(data);
+            command_buf[ack_offset] = !VIRTIO_NET_OK;
+
+            // header -> data -> ack (device-writable)
+            let after_header = if data.is_empty() { 2 } else { 1 };
+            self.write_desc(0, bus, 2, VIRTQ_DESC_F_NEXT, after_header);
+            if !data.is_empty() {
+                let data_bus = bus + DATA_OFFSET as u64;
+                self.write_desc(1, data_bus, data.len() as u32, VIRTQ_DESC_F_NEXT, 2);
+            }
+            self.write_desc(2, bus + ack_offset as u64, 1, VIRTQ_DESC_F_WRITE, 0);
+
+            // avail: flags, idx, ring[size]
+            let idx = self.state.next_avail_idx;
+            let slot = avail.add(4 + 2 * (idx % size) as usize) as *mut u16;
+            core::ptr::write_volatile(slot, 0);
+            sfence();
+            core::ptr::write_volatile(avail.add(2) as *mut u16, idx.wrapping_add(1));
+            self.state.next_avail_idx = idx.wrapping_add(1);
+        }
+
+        // Includes the fence that publishes the avail index
+        notify::notify(&mut self.state);
+
+        // used: flags, idx, ring[size] of (id, len)
+        let used_idx = unsafe { used.add(2) } as *const u16;
+        let start = read_tsc();
+        let timeout = self.tsc_freq / 1000 * COMMAND_TIMEOUT_MS;
+        while unsafe { core::ptr::read_volatile(used_idx) } == self.state.last_used_idx {
+            if read_tsc().wrapping_sub(start) > timeout {
+                self.stuck = true;
+                return Err(CtrlError::Timeout);
+            }
+            core::hint::spin_loop();
+        }
+        self.state.last_used_idx = self.state.last_used_idx.wrapping_add(1);
+        lfence();
+
+        match unsafe { core::ptr::read_volatile(buffer.add(ack_offset)) } {
+            VIRTIO_NET_OK => Ok(()),
+            _ => Err(CtrlError::Rejected),
+        }
+    }
+}
+
+// Safety: raw pointers into the driver's DMA region, valid for its lifetime.
+unsafe impl Send for ControlQueue {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mac_table() {
+        let unicast = [[0x02, 0, 0, 0, 0, 1]];
+        let multicast = [[0x01, 0x00, 0x5E, 0, 0, 0xFB], [0x33, 0x33, 0, 0, 0, 0xFB]];
+        let mut out = [0u8; MAC_TABLE_MAX];
+        let len = mac_table(&unicast, &multicast, &mut out);
+
+        assert_eq!(len, 4 + 6 + 4 + 12);
+        assert_eq!(out[0..4], [1, 0, 0, 0]);
+        assert_eq!(out[4..10], unicast[0]);
+        assert_eq!(out[10..14], [2, 0, 0, 0]);
+        assert_eq!(out[14..20], multicast[0]);
+        assert_eq!(out[20..26], multicast[1]);
+
+        assert_eq!(mac_table(&[], &[], &mut out), 8);
+        assert_eq!(out[..8], [0; 8]);
+    }
+}
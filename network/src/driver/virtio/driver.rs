@@ -3,12 +3,18 @@
 //! # Reference
 //! NETWORK_IMPL_GUIDE.md §4, §8.4
 
-use super::config::{VirtioConfig, VIRTIO_NET_DEVICE_IDS, VIRTIO_VENDOR_ID};
+use super::config::{
+    features as feature_bits, net_hdr_len, VirtioConfig, VIRTIO_NET_DEVICE_IDS, VIRTIO_VENDOR_ID,
+};
+use super::ctrl::{self, ControlQueue, MAC_TABLE_MAX};
 use super::init::{virtio_net_init, virtio_net_init_transport, VirtioInitError};
-use super::transport::VirtioTransport;
+use super::transport::{TransportType, VirtioTransport};
 use super::{rx, tx};
 use crate::dma::{BufferPool, DmaRegion};
-use crate::driver::traits::{DriverInit, NetworkDriver, RxError, TxError};
+use crate::driver::mac_filter::{MacFilter, MAX_UNICAST_FILTERS};
+use crate::driver::traits::{
+    DriverInit, FilterError, NetworkDriver, ResetState, RingState, RxError, TxError,
+};
 use crate::types::{MacAddress, VirtqueueState};
 
 /// VirtIO network driver.
@@ -21,6 +27,8 @@ pub struct VirtioNetDriver {
     mac: MacAddress,
     /// Negotiated features.
     features: u64,
+    /// VirtIO header size in front of every frame (10 on legacy devices).
+    hdr_len: usize,
     /// RX virtqueue state.
     rx_state: VirtqueueState,
     /// TX virtqueue state.
@@ -29,6 +37,10 @@ pub struct VirtioNetDriver {
     rx_pool: BufferPool,
     /// TX buffer pool.
     tx_pool: BufferPool,
+    /// Control queue (CTRL_VQ only).
+    ctrl: Option<ControlQueue>,
+    /// MAC filter table last sent to the device.
+    filters: MacFilter,
 }
 
 impl VirtioNetDriver {
@@ -65,10 +77,13 @@ impl VirtioNetDriver {
             transport: VirtioTransport::mmio(mmio_base),
             mac,
             features,
+            hdr_len: net_hdr_len(features),
             rx_state,
             tx_state,
             rx_pool,
             tx_pool,
+            ctrl: None,
+            filters: MacFilter::new(mac, MAX_UNICAST_FILTERS),
         };
 
         // Pre-fill RX queue
@@ -79,7 +94,8 @@ impl VirtioNetDriver {
 
     /// Create a new VirtIO driver using transport abstraction.
     ///
-    /// This constructor auto-selects MMIO or PCI Modern based on the transport.
+    /// This constructor auto-selects MMIO, PCI Modern or PCI Legacy based on
+    /// the transport.
     ///
     /// # Arguments
     /// - `transport`: Transport handle (pre-configured)
@@ -95,7 +111,7 @@ impl VirtioNetDriver {
         tsc_freq: u64,
     ) -> Result<Self, VirtioInitError> {
         // Initialize device using transport abstraction
-        let (features, rx_state, tx_state, mac) =
+        let (features, rx_state, tx_state, ctrl_state, mac) =
             virtio_net_init_transport(&transport, &config, tsc_freq)?;
 
         // Create buffer pools
@@ -118,10 +134,13 @@ impl VirtioNetDriver {
             transport,
             mac,
             features,
+            hdr_len: net_hdr_len(features),
             rx_state,
             tx_state,
             rx_pool,
             tx_pool,
+            ctrl: ctrl_state.map(|state| ControlQueue::new(state, tsc_freq)),
+            filters: MacFilter::new(mac, MAX_UNICAST_FILTERS),
         };
 
         // Pre-fill RX queue
@@ -164,6 +183,35 @@ impl VirtioNetDriver {
     pub fn tx_buffers_available(&self) -> usize {
         self.tx_pool.available()
     }
+
+    /// Apply `change` to the filter list and send the device the new
+    /// MAC table; the list is left as it was if the device refuses.
+    fn update_filters(
+        &mut self,
+        change: impl FnOnce(&mut MacFilter) -> Result<bool, FilterError>,
+    ) -> Result<(), FilterError> {
+        if self.features & feature_bits::VIRTIO_NET_F_CTRL_RX == 0 {
+            return Err(FilterError::Unsupported);
+        }
+        let Some(ctrl) = self.ctrl.as_mut() else {
+            return Err(FilterError::Unsupported);
+        };
+
+        let previous = self.filters;
+        if !change(&mut self.filters)? {
+            return Ok(());
+        }
+        let mut table = [0u8; MAC_TABLE_MAX];
+        let len = ctrl::mac_table(self.filters.unicast(), self.filters.multicast(), &mut table);
+        if ctrl
+            .send(ctrl::class::MAC, ctrl::mac_cmd::TABLE_SET, &table[..len])
+            .is_err()
+        {
+            self.filters = previous;
+            return Err(FilterError::DeviceError);
+        }
+        Ok(())
+    }
 }
 
 impl NetworkDriver for VirtioNetDriver {
@@ -180,12 +228,19 @@ impl NetworkDriver for VirtioNetDriver {
         true
     }
 
+    fn rx_backlog(&self) -> Option<(usize, usize)> {
+        // The ASM helper wants a mutable state; it only reads the rings
+        let mut rx_state = self.rx_state.clone();
+        let pending = crate::asm::drivers::virtio::rx::pending_count(&mut rx_state);
+        Some((pending as usize, self.rx_state.queue_size as usize))
+    }
+
     fn transmit(&mut self, frame: &[u8]) -> Result<(), TxError> {
-        tx::transmit(&mut self.tx_state, &mut self.tx_pool, frame)
+        tx::transmit(&mut self.tx_state, &mut self.tx_pool, frame, self.hdr_len)
     }
 
     fn receive(&mut self, buffer: &mut [u8]) -> Result<Option<usize>, RxError> {
-        rx::receive(&mut self.rx_state, &mut self.rx_pool, buffer)
+        rx::receive(&mut self.rx_state, &mut self.rx_pool, buffer, self.hdr_len)
     }
 
     fn refill_rx_queue(&mut self) {
@@ -200,6 +255,50 @@ impl NetworkDriver for VirtioNetDriver {
         // TODO: Check link status register if VIRTIO_NET_F_STATUS negotiated
         true
     }
+
+    fn quiesce(&mut self) {
+        // Writing 0 to device status resets the device, which stops all
+        // queue processing (VirtIO 1.0 §2.1.2).
+        self.transport.set_status(0);
+    }
+
+    /// No interrupt mask (the queues are polled) and no loopback.
+    fn reset_state(&self) -> Option<ResetState> {
+        let ring = |state: &VirtqueueState| {
+            // MMIO only reports the maximum, not the size we set
+            let device_size = (self.transport.transport_type != TransportType::Mmio).then(|| {
+                self.transport.select_queue(state.queue_index);
+                self.transport.get_queue_size()
+            });
+            RingState {
+                size: state.queue_size,
+                device_size,
+                posted: state.next_avail_idx.wrapping_sub(state.last_used_idx),
+            }
+        };
+        Some(ResetState {
+            interrupts_masked: None,
+            loopback: false,
+            rx: ring(&self.rx_state),
+            tx: ring(&self.tx_state),
+        })
+    }
+
+    fn add_unicast_filter(&mut self, mac: MacAddress) -> Result<(), FilterError> {
+        self.update_filters(|filters| filters.add_unicast(mac))
+    }
+
+    fn remove_unicast_filter(&mut self, mac: MacAddress) -> Result<(), FilterError> {
+        self.update_filters(|filters| Ok(filters.remove_unicast(&mac)))
+    }
+
+    fn join_multicast(&mut self, mac: MacAddress) -> Result<(), FilterError> {
+        self.update_filters(|filters| filters.join(mac))
+    }
+
+    fn leave_multicast(&mut self, mac: MacAddress) -> Result<(), FilterError> {
+        self.update_filters(|filters| Ok(filters.leave(&mac)))
+    }
 }
 
 impl DriverInit for VirtioNetDriver {
@@ -23,10 +23,16 @@
 //! 8. Set DRIVER_OK
 //! 9. Read MAC address
 //!
+//! Legacy (pre-1.0) devices skip steps 5 and 6: they have no FEATURES_OK
+//! handshake and take whatever the driver writes.
+//!
 //! # Reference
 //! VirtIO 1.1 spec, Section 3.1
 
-use super::config::{features, negotiate_features, status, VirtioConfig};
+use super::config::{
+    features, negotiate_features, negotiate_legacy_features, status, FeatureReport, VirtioConfig,
+    CONTROL_FEATURES,
+};
 use super::transport::{TransportType, VirtioTransport};
 use crate::driver::traits::RxError;
 use crate::types::{MacAddress, VirtqueueState};
@@ -66,7 +72,7 @@ impl From<RxError> for VirtioInitError {
 /// # Safety
 /// - `mmio_base` must be valid VirtIO MMIO address
 /// - DMA region must be properly allocated
-#[cfg(target_arch = "x86_64")]
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
 pub unsafe fn virtio_net_init(
     mmio_base: u64,
     config: &VirtioConfig,
@@ -95,9 +101,16 @@ pub unsafe fn virtio_net_init(
     // STEP 4: FEATURE NEGOTIATION
     // ═══════════════════════════════════════════════════════════
     let device_features = device::read_features(mmio_base);
+    // This path sets up no control queue
     let our_features = negotiate_features(device_features)
-        .map_err(|_| VirtioInitError::FeatureNegotiationFailed)?;
+        .map_err(|_| VirtioInitError::FeatureNegotiationFailed)?
+        & !CONTROL_FEATURES;
     device::write_features(mmio_base, our_features);
+    FeatureReport {
+        offered: device_features,
+        negotiated: our_features,
+    }
+    .log();
 
     // ═══════════════════════════════════════════════════════════
     // STEP 5: SET FEATURES_OK
@@ -152,7 +165,7 @@ pub unsafe fn virtio_net_init(
 }
 
 /// Setup a single virtqueue.
-#[cfg(target_arch = "x86_64")]
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
 unsafe fn setup_queue(
     mmio_base: u64,
     queue_index: u16,
@@ -252,7 +265,7 @@ fn generate_local_mac() -> MacAddress {
 /// Initialize VirtIO network device using transport abstraction.
 ///
 /// This function auto-selects the correct initialization path based
-/// on the transport type (MMIO or PCI Modern).
+/// on the transport type (MMIO, PCI Modern or PCI Legacy).
 ///
 /// # Arguments
 /// - `transport`: Transport handle (already configured with addresses)
@@ -260,13 +273,24 @@ fn generate_local_mac() -> MacAddress {
 /// - `tsc_freq`: TSC frequency for timeout calculations
 ///
 /// # Returns
-/// Tuple of (negotiated_features, rx_queue_state, tx_queue_state, mac_address)
-#[cfg(target_arch = "x86_64")]
+/// Tuple of (negotiated_features, rx_queue_state, tx_queue_state,
+/// ctrl_queue_state, mac_address); the control queue only with CTRL_VQ.
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+#[allow(clippy::type_complexity)]
 pub unsafe fn virtio_net_init_transport(
     transport: &VirtioTransport,
     config: &VirtioConfig,
     tsc_freq: u64,
-) -> Result<(u64, VirtqueueState, VirtqueueState, MacAddress), VirtioInitError> {
+) -> Result<
+    (
+        u64,
+        VirtqueueState,
+        VirtqueueState,
+        Option<VirtqueueState>,
+        MacAddress,
+    ),
+    VirtioInitError,
+> {
     // ═══════════════════════════════════════════════════════════
     // STEP 1: RESET DEVICE
     // ═══════════════════════════════════════════════════════════
@@ -288,39 +312,68 @@ pub unsafe fn virtio_net_init_transport(
     // STEP 4: FEATURE NEGOTIATION
     // ═══════════════════════════════════════════════════════════
     let device_features = transport.read_features();
-    let our_features = negotiate_features(device_features)
-        .map_err(|_| VirtioInitError::FeatureNegotiationFailed)?;
+    let legacy = transport.is_legacy();
+    let our_features = if legacy {
+        negotiate_legacy_features(device_features)
+    } else {
+        negotiate_features(device_features)
+            .map_err(|_| VirtioInitError::FeatureNegotiationFailed)?
+    };
     transport.write_features(our_features);
+    FeatureReport {
+        offered: device_features,
+        negotiated: our_features,
+    }
+    .log();
+
+    // Legacy devices have no FEATURES_OK handshake
+    let features_ok = if legacy { 0 } else { status::FEATURES_OK };
 
     // ═══════════════════════════════════════════════════════════
     // STEP 5: SET FEATURES_OK
     // ═══════════════════════════════════════════════════════════
-    transport.set_status(status::ACKNOWLEDGE | status::DRIVER | status::FEATURES_OK);
+    if !legacy {
+        transport.set_status(status::ACKNOWLEDGE | status::DRIVER | status::FEATURES_OK);
 
-    // ═══════════════════════════════════════════════════════════
-    // STEP 6: VERIFY FEATURES_OK
-    // ═══════════════════════════════════════════════════════════
-    let current_status = transport.get_status();
-    if current_status & status::FEATURES_OK == 0 {
-        transport.set_status(status::FAILED);
-        return Err(VirtioInitError::FeaturesRejected);
+        // ═══════════════════════════════════════════════════════════
+        // STEP 6: VERIFY FEATURES_OK
+        // ═══════════════════════════════════════════════════════════
+        let current_status = transport.get_status();
+        if current_status & status::FEATURES_OK == 0 {
+            transport.set_status(status::FAILED);
+            return Err(VirtioInitError::FeaturesRejected);
+        }
     }
 
     // ═══════════════════════════════════════════════════════════
     // STEP 7: CONFIGURE VIRTQUEUES
     // ═══════════════════════════════════════════════════════════
 
-    // Setup RX queue (index 0)
-    let rx_queue = setup_queue_transport(transport, 0, config)?;
+    let (rx_queue, tx_queue) = if legacy {
+        (
+            setup_legacy_queue(transport, 0, config)?,
+            setup_legacy_queue(transport, 1, config)?,
+        )
+    } else {
+        (
+            // Setup RX queue (index 0)
+            setup_queue_transport(transport, 0, config)?,
+            // Setup TX queue (index 1)
+            setup_queue_transport(transport, 1, config)?,
+        )
+    };
 
-    // Setup TX queue (index 1)
-    let tx_queue = setup_queue_transport(transport, 1, config)?;
+    // Control queue (index 2) for the MAC filter table
+    let ctrl_queue = if our_features & features::VIRTIO_NET_F_CTRL_VQ != 0 {
+        Some(setup_ctrl_queue(transport, config, legacy)?)
+    } else {
+        None
+    };
 
     // ═══════════════════════════════════════════════════════════
     // STEP 9: SET DRIVER_OK
     // ═══════════════════════════════════════════════════════════
-    transport
-        .set_status(status::ACKNOWLEDGE | status::DRIVER | status::FEATURES_OK | status::DRIVER_OK);
+    transport.set_status(status::ACKNOWLEDGE | status::DRIVER | features_ok | status::DRIVER_OK);
 
     // ═══════════════════════════════════════════════════════════
     // STEP 10: READ MAC ADDRESS
@@ -336,11 +389,11 @@ pub unsafe fn virtio_net_init_transport(
         generate_local_mac()
     };
 
-    Ok((our_features, rx_queue, tx_queue, mac))
+    Ok((our_features, rx_queue, tx_queue, ctrl_queue, mac))
 }
 
 /// Setup a single virtqueue using transport abstraction.
-#[cfg(target_arch = "x86_64")]
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
 unsafe fn setup_queue_transport(
     transport: &VirtioTransport,
     queue_index: u16,
@@ -422,12 +475,156 @@ unsafe fn setup_queue_transport(
     })
 }
 
-// Stub for non-x86_64 platforms
-#[cfg(not(target_arch = "x86_64"))]
+/// Setup a single legacy virtqueue.
+///
+/// The device fixes the queue size and derives the avail and used rings
+/// from the page number of the descriptor table, so the ring lives in its
+/// own page-aligned area of the DMA region. Only `config.queue_size`
+/// buffers back it; the extra descriptors simply stay unused.
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+unsafe fn setup_legacy_queue(
+    transport: &VirtioTransport,
+    queue_index: u16,
+    config: &VirtioConfig,
+) -> Result<VirtqueueState, VirtioInitError> {
+    use super::transport::{legacy_vring_layout, LEGACY_MAX_QUEUE_SIZE, LEGACY_VRING_ALIGN};
+    use crate::dma::DmaRegion;
+
+    transport.select_queue(queue_index);
+
+    // Not negotiable on legacy devices; the ring code needs a power of two
+    let queue_size = transport.get_queue_size();
+    if queue_size == 0 || !queue_size.is_power_of_two() || queue_size > LEGACY_MAX_QUEUE_SIZE {
+        return Err(VirtioInitError::QueueSetupFailed);
+    }
+
+    let (ring_offset, buffer_offset) = if queue_index == 0 {
+        (
+            DmaRegion::LEGACY_RX_RING_OFFSET,
+            DmaRegion::RX_BUFFERS_OFFSET,
+        )
+    } else {
+        (
+            DmaRegion::LEGACY_TX_RING_OFFSET,
+            DmaRegion::TX_BUFFERS_OFFSET,
+        )
+    };
+    let ring_bus = config.dma_bus_base + ring_offset as u64;
+    if !ring_bus.is_multiple_of(LEGACY_VRING_ALIGN as u64) {
+        return Err(VirtioInitError::QueueSetupFailed);
+    }
+    let layout = legacy_vring_layout(queue_size);
+
+    // The device starts reading the rings as soon as it has the PFN
+    let ring_cpu = config.dma_cpu_base.add(ring_offset);
+    core::ptr::write_bytes(ring_cpu, 0, DmaRegion::LEGACY_RING_SIZE);
+    transport.set_queue_desc(ring_bus);
+
+    let buffer_bus = config.dma_bus_base + buffer_offset as u64;
+    let buffer_cpu = config.dma_cpu_base.add(buffer_offset);
+
+    Ok(VirtqueueState {
+        desc_base: ring_bus,
+        avail_base: ring_bus + layout.avail_offset as u64,
+        used_base: ring_bus + layout.used_offset as u64,
+        queue_size,
+        queue_index,
+        _pad: 0,
+        notify_addr: transport.get_notify_addr(queue_index),
+        last_used_idx: 0,
+        next_avail_idx: 0,
+        _pad2: 0,
+        desc_cpu_ptr: ring_cpu as u64,
+        buffer_cpu_base: buffer_cpu as u64,
+        buffer_bus_base: buffer_bus,
+        buffer_size: config.buffer_size as u32,
+        buffer_count: queue_size.min(config.queue_size) as u32,
+    })
+}
+
+/// Setup the control queue (index 2) and its command buffer.
+///
+/// The ring gets the legacy layout on every transport: legacy devices fix
+/// the size, modern ones are offered `CTRL_QUEUE_SIZE` entries.
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+unsafe fn setup_ctrl_queue(
+    transport: &VirtioTransport,
+    config: &VirtioConfig,
+    legacy: bool,
+) -> Result<VirtqueueState, VirtioInitError> {
+    use super::ctrl::{CTRL_QUEUE_INDEX, CTRL_QUEUE_SIZE};
+    use super::transport::{legacy_vring_layout, LEGACY_MAX_QUEUE_SIZE, LEGACY_VRING_ALIGN};
+    use crate::dma::DmaRegion;
+
+    transport.select_queue(CTRL_QUEUE_INDEX);
+
+    let device_queue_size = transport.get_queue_size();
+    let queue_size = if legacy {
+        device_queue_size
+    } else {
+        device_queue_size.min(CTRL_QUEUE_SIZE)
+    };
+    // A command takes three descriptors
+    if queue_size < 4 || !queue_size.is_power_of_two() || queue_size > LEGACY_MAX_QUEUE_SIZE {
+        return Err(VirtioInitError::QueueSetupFailed);
+    }
+
+    let ring_bus = config.dma_bus_base + DmaRegion::CTRL_RING_OFFSET as u64;
+    if !ring_bus.is_multiple_of(LEGACY_VRING_ALIGN as u64) {
+        return Err(VirtioInitError::QueueSetupFailed);
+    }
+    let layout = legacy_vring_layout(queue_size);
+    let avail_bus = ring_bus + layout.avail_offset as u64;
+    let used_bus = ring_bus + layout.used_offset as u64;
+
+    let ring_cpu = config.dma_cpu_base.add(DmaRegion::CTRL_RING_OFFSET);
+    core::ptr::write_bytes(ring_cpu, 0, DmaRegion::LEGACY_RING_SIZE);
+
+    if legacy {
+        transport.set_queue_desc(ring_bus);
+    } else {
+        transport.set_queue_size(queue_size);
+        transport.set_queue_desc(ring_bus);
+        transport.set_queue_avail(avail_bus);
+        transport.set_queue_used(used_bus);
+        transport.enable_queue();
+    }
+
+    Ok(VirtqueueState {
+        desc_base: ring_bus,
+        avail_base: avail_bus,
+        used_base: used_bus,
+        queue_size,
+        queue_index: CTRL_QUEUE_INDEX,
+        _pad: 0,
+        notify_addr: transport.get_notify_addr(CTRL_QUEUE_INDEX),
+        last_used_idx: 0,
+        next_avail_idx: 0,
+        _pad2: 0,
+        desc_cpu_ptr: ring_cpu as u64,
+        buffer_cpu_base: config.dma_cpu_base.add(DmaRegion::CTRL_BUFFER_OFFSET) as u64,
+        buffer_bus_base: config.dma_bus_base + DmaRegion::CTRL_BUFFER_OFFSET as u64,
+        buffer_size: DmaRegion::CTRL_BUFFER_SIZE as u32,
+        buffer_count: 1,
+    })
+}
+
+// Stub for targets without a port
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+#[allow(clippy::type_complexity)]
 pub unsafe fn virtio_net_init_transport(
     _transport: &VirtioTransport,
     _config: &VirtioConfig,
     _tsc_freq: u64,
-) -> Result<(u64, VirtqueueState, VirtqueueState, MacAddress), VirtioInitError> {
+) -> Result<
+    (
+        u64,
+        VirtqueueState,
+        VirtqueueState,
+        Option<VirtqueueState>,
+        MacAddress,
+    ),
+    VirtioInitError,
+> {
     Err(VirtioInitError::DeviceError)
 }
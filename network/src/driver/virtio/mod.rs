@@ -1,6 +1,7 @@
 //! VirtIO driver orchestration.
 
 pub mod config;
+pub mod ctrl;
 pub mod driver;
 pub mod init;
 pub mod rx;
@@ -8,7 +9,10 @@ pub mod transport;
 pub mod tx;
 
 // Re-exports
-pub use config::{features, is_virtio_net, negotiate_features, status, VirtioConfig};
+pub use config::{
+    features, is_virtio_net, negotiate_features, negotiate_legacy_features, net_hdr_len, status,
+    FeatureReport, VirtioConfig, CONTROL_FEATURES,
+};
 pub use config::{VIRTIO_NET_DEVICE_IDS, VIRTIO_VENDOR_ID};
 pub use driver::VirtioNetDriver;
 pub use init::{virtio_net_init, virtio_net_init_transport, VirtioInitError};
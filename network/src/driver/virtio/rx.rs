@@ -7,7 +7,7 @@
 
 use crate::dma::BufferPool;
 use crate::driver::traits::RxError;
-use crate::types::{RxResult, VirtioNetHdr, VirtqueueState};
+use crate::types::{RxResult, VirtioNetHdr, VirtqueueState, VIRTIO_NET_HDR_F_NEEDS_CSUM};
 
 /// Receive a packet via VirtIO.
 ///
@@ -15,6 +15,7 @@ use crate::types::{RxResult, VirtioNetHdr, VirtqueueState};
 /// - `rx_state`: RX virtqueue state
 /// - `rx_pool`: RX buffer pool
 /// - `out_buffer`: Buffer to copy received frame into
+/// - `hdr_len`: VirtIO header size for the negotiated features
 ///
 /// # Returns
 /// - `Ok(Some(len))`: Frame received, `len` bytes copied (without VirtIO header)
@@ -23,11 +24,12 @@ use crate::types::{RxResult, VirtioNetHdr, VirtqueueState};
 ///
 /// # Contract
 /// - MUST return immediately (no blocking)
-#[cfg(target_arch = "x86_64")]
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
 pub fn receive(
     rx_state: &mut VirtqueueState,
     rx_pool: &mut BufferPool,
     out_buffer: &mut [u8],
+    hdr_len: usize,
 ) -> Result<Option<usize>, RxError> {
     use crate::asm::drivers::virtio::rx as asm_rx;
 
@@ -47,15 +49,15 @@ pub fn receive(
         buf.mark_driver_owned();
     }
 
-    // Calculate frame length (skip 12-byte VirtIO header)
+    // Calculate frame length (skip VirtIO header)
     let frame_len = result.length as usize;
-    if frame_len < VirtioNetHdr::SIZE {
+    if frame_len < hdr_len {
         // Invalid - resubmit and report error
         resubmit_buffer(rx_state, rx_pool, result.buffer_idx);
         return Err(RxError::DeviceError);
     }
 
-    let payload_len = frame_len - VirtioNetHdr::SIZE;
+    let payload_len = frame_len - hdr_len;
 
     // Check if caller's buffer is large enough
     if payload_len > out_buffer.len() {
@@ -67,19 +69,26 @@ pub fn receive(
     }
 
     // Copy frame (skip VirtIO header)
-    out_buffer[..payload_len]
-        .copy_from_slice(&buf.as_slice()[VirtioNetHdr::SIZE..VirtioNetHdr::SIZE + payload_len]);
+    let hdr = VirtioNetHdr::from_bytes(&buf.as_slice()[..hdr_len]);
+    out_buffer[..payload_len].copy_from_slice(&buf.as_slice()[hdr_len..hdr_len + payload_len]);
 
     // Resubmit buffer to RX queue
     resubmit_buffer(rx_state, rx_pool, result.buffer_idx);
 
+    // With GUEST_CSUM, host-local packets arrive with a partial checksum
+    if let Some(hdr) = hdr.filter(|h| h.flags & VIRTIO_NET_HDR_F_NEEDS_CSUM != 0) {
+        if !hdr.complete_checksum(&mut out_buffer[..payload_len]) {
+            return Err(RxError::DeviceError);
+        }
+    }
+
     Ok(Some(payload_len))
 }
 
 /// Resubmit RX buffer after processing.
 ///
 /// Notifies device immediately to ensure packets keep flowing.
-#[cfg(target_arch = "x86_64")]
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
 fn resubmit_buffer(rx_state: &mut VirtqueueState, rx_pool: &mut BufferPool, buf_idx: u16) {
     use crate::asm::drivers::virtio::{notify, rx as asm_rx};
 
@@ -107,7 +116,7 @@ fn resubmit_buffer(rx_state: &mut VirtqueueState, rx_pool: &mut BufferPool, buf_
 /// Refill RX queue with available buffers.
 ///
 /// Call in main loop Phase 1.
-#[cfg(target_arch = "x86_64")]
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
 pub fn refill_queue(rx_state: &mut VirtqueueState, rx_pool: &mut BufferPool) {
     use crate::asm::drivers::virtio::{notify, rx as asm_rx};
 
@@ -144,7 +153,7 @@ pub fn refill_queue(rx_state: &mut VirtqueueState, rx_pool: &mut BufferPool) {
 /// Pre-fill RX queue during initialization.
 ///
 /// Should be called after queue setup, before DRIVER_OK.
-#[cfg(target_arch = "x86_64")]
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
 pub fn prefill_queue(
     rx_state: &mut VirtqueueState,
     rx_pool: &mut BufferPool,
@@ -183,20 +192,21 @@ pub fn prefill_queue(
     Ok(filled)
 }
 
-// Stubs for non-x86_64 platforms
-#[cfg(not(target_arch = "x86_64"))]
+// Stubs for targets without a port
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
 pub fn receive(
     _rx_state: &mut VirtqueueState,
     _rx_pool: &mut BufferPool,
     _out_buffer: &mut [u8],
+    _hdr_len: usize,
 ) -> Result<Option<usize>, RxError> {
     Ok(None)
 }
 
-#[cfg(not(target_arch = "x86_64"))]
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
 pub fn refill_queue(_rx_state: &mut VirtqueueState, _rx_pool: &mut BufferPool) {}
 
-#[cfg(not(target_arch = "x86_64"))]
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
 pub fn prefill_queue(
     _rx_state: &mut VirtqueueState,
     _rx_pool: &mut BufferPool,
@@ -6,8 +6,19 @@
 //! - PCI Legacy (older QEMU, uses BAR0 I/O ports)
 //!
 //! The transport is probed at runtime based on device discovery.
-
+//!
+//! # PCI Legacy
+//! Transitional devices (ID 0x1000) without usable modern capabilities
+//! only speak the pre-1.0 interface: a fixed register block in the I/O
+//! BAR, 32 feature bits (no VERSION_1, no FEATURES_OK handshake), a queue
+//! size fixed by the device, and one page number per queue from which the
+//! device derives where the rings are (see [`legacy_vring_layout`]).
+//! VirtIO 1.2 §4.1.4.8 and §2.7.
+
+use crate::asm::core::pio::{inb, inl, inw, outb, outl, outw};
+use crate::asm::core::tsc::read_tsc;
 use crate::asm::drivers::virtio::device as mmio_device;
+use crate::asm::drivers::virtio::notify::PIO_NOTIFY;
 
 /// VirtIO transport type, determined at probe time
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -17,10 +28,58 @@ pub enum TransportType {
     Mmio = 0,
     /// VirtIO PCI Modern transport (capability-based)
     PciModern = 1,
-    /// VirtIO PCI Legacy transport (BAR0 I/O ports) - not fully supported
+    /// VirtIO PCI Legacy transport (BAR0 I/O ports)
     PciLegacy = 2,
 }
 
+/// PCI Legacy register offsets in the I/O BAR
+mod legacy_reg {
+    pub const DEVICE_FEATURES: u16 = 0x00;
+    pub const DRIVER_FEATURES: u16 = 0x04;
+    pub const QUEUE_PFN: u16 = 0x08;
+    pub const QUEUE_SIZE: u16 = 0x0C;
+    pub const QUEUE_SELECT: u16 = 0x0E;
+    pub const QUEUE_NOTIFY: u16 = 0x10;
+    pub const STATUS: u16 = 0x12;
+    /// Device-specific config; here because MSI-X is never enabled
+    pub const DEVICE_CONFIG: u16 = 0x14;
+}
+
+/// Page size of QUEUE_PFN, and the alignment of a legacy used ring
+pub const LEGACY_VRING_ALIGN: usize = 4096;
+
+/// Largest legacy queue the DMA region has room for
+/// (see `DmaRegion::LEGACY_RING_SIZE`)
+pub const LEGACY_MAX_QUEUE_SIZE: u16 = 1024;
+
+/// Where the rings of a legacy virtqueue are, from its descriptor table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LegacyVringLayout {
+    /// Available ring offset (right after the descriptor table)
+    pub avail_offset: usize,
+    /// Used ring offset (next `LEGACY_VRING_ALIGN` boundary after the
+    /// available ring)
+    pub used_offset: usize,
+    /// Bytes the whole vring takes
+    pub size: usize,
+}
+
+/// Layout of a legacy vring of `queue_size` entries.
+pub const fn legacy_vring_layout(queue_size: u16) -> LegacyVringLayout {
+    let n = queue_size as usize;
+    let avail_offset = 16 * n;
+    // flags, idx, ring[n], used_event
+    let avail_end = avail_offset + 6 + 2 * n;
+    let used_offset = (avail_end + LEGACY_VRING_ALIGN - 1) & !(LEGACY_VRING_ALIGN - 1);
+    // flags, idx, ring[n] of (id, len), avail_event
+    let size = used_offset + 6 + 8 * n;
+    LegacyVringLayout {
+        avail_offset,
+        used_offset,
+        size,
+    }
+}
+
 /// Configuration for VirtIO PCI Modern transport
 #[derive(Debug, Clone, Copy)]
 #[repr(C)]
@@ -83,12 +142,31 @@ impl VirtioTransport {
         }
     }
 
+    /// Create PCI Legacy transport on the I/O BAR at `io_base`
+    pub fn pci_legacy(io_base: u16) -> Self {
+        Self {
+            transport_type: TransportType::PciLegacy,
+            base: io_base as u64,
+            pci_modern: PciModernConfig::default(),
+        }
+    }
+
+    /// Whether this is the pre-1.0 interface (see the module docs)
+    pub fn is_legacy(&self) -> bool {
+        self.transport_type == TransportType::PciLegacy
+    }
+
+    /// I/O port of legacy register `reg`
+    fn port(&self, reg: u16) -> u16 {
+        (self.base as u16).wrapping_add(reg)
+    }
+
     /// Get device status
     pub fn get_status(&self) -> u8 {
         match self.transport_type {
             TransportType::Mmio => mmio_device::get_status(self.base),
             TransportType::PciModern => unsafe { pci_modern::get_status(self.base) as u8 },
-            TransportType::PciLegacy => 0, // Not supported
+            TransportType::PciLegacy => unsafe { inb(self.port(legacy_reg::STATUS)) },
         }
     }
 
@@ -97,7 +175,7 @@ impl VirtioTransport {
         match self.transport_type {
             TransportType::Mmio => mmio_device::set_status(self.base, status),
             TransportType::PciModern => unsafe { pci_modern::set_status(self.base, status) },
-            TransportType::PciLegacy => {} // Not supported
+            TransportType::PciLegacy => unsafe { outb(self.port(legacy_reg::STATUS), status) },
         }
     }
 
@@ -107,7 +185,17 @@ impl VirtioTransport {
         match self.transport_type {
             TransportType::Mmio => mmio_device::reset(self.base),
             TransportType::PciModern => unsafe { pci_modern::reset(self.base, tsc_freq) == 0 },
-            TransportType::PciLegacy => false,
+            TransportType::PciLegacy => {
+                self.set_status(0);
+                let start = read_tsc();
+                while self.get_status() != 0 {
+                    if read_tsc().wrapping_sub(start) > tsc_freq / 10 {
+                        return false;
+                    }
+                    core::hint::spin_loop();
+                }
+                true
+            }
         }
     }
 
@@ -116,7 +204,10 @@ impl VirtioTransport {
         match self.transport_type {
             TransportType::Mmio => mmio_device::read_features(self.base),
             TransportType::PciModern => unsafe { pci_modern::read_features(self.base) },
-            TransportType::PciLegacy => 0,
+            // Legacy devices have 32 feature bits
+            TransportType::PciLegacy => unsafe {
+                inl(self.port(legacy_reg::DEVICE_FEATURES)) as u64
+            },
         }
     }
 
@@ -125,7 +216,9 @@ impl VirtioTransport {
         match self.transport_type {
             TransportType::Mmio => mmio_device::write_features(self.base, features),
             TransportType::PciModern => unsafe { pci_modern::write_features(self.base, features) },
-            TransportType::PciLegacy => {}
+            TransportType::PciLegacy => unsafe {
+                outl(self.port(legacy_reg::DRIVER_FEATURES), features as u32)
+            },
         }
     }
 
@@ -148,7 +241,7 @@ impl VirtioTransport {
                     core::arch::asm!("mfence", options(nostack, preserves_flags));
                 }
                 TransportType::PciModern => pci_modern::select_queue(self.base, queue_idx),
-                TransportType::PciLegacy => {}
+                TransportType::PciLegacy => outw(self.port(legacy_reg::QUEUE_SELECT), queue_idx),
             }
         }
     }
@@ -162,7 +255,7 @@ impl VirtioTransport {
                     core::ptr::read_volatile(queue_num_max_addr as *const u32) as u16
                 }
                 TransportType::PciModern => pci_modern::get_queue_size(self.base) as u16,
-                TransportType::PciLegacy => 0,
+                TransportType::PciLegacy => inw(self.port(legacy_reg::QUEUE_SIZE)),
             }
         }
     }
@@ -177,6 +270,7 @@ impl VirtioTransport {
                     core::arch::asm!("mfence", options(nostack, preserves_flags));
                 }
                 TransportType::PciModern => pci_modern::set_queue_size(self.base, size),
+                // Fixed by the device
                 TransportType::PciLegacy => {}
             }
         }
@@ -194,7 +288,11 @@ impl VirtioTransport {
                     core::arch::asm!("mfence", options(nostack, preserves_flags));
                 }
                 TransportType::PciModern => pci_modern::set_queue_desc(self.base, addr),
-                TransportType::PciLegacy => {}
+                // Page number of the whole vring; also activates the queue
+                TransportType::PciLegacy => outl(
+                    self.port(legacy_reg::QUEUE_PFN),
+                    (addr / LEGACY_VRING_ALIGN as u64) as u32,
+                ),
             }
         }
     }
@@ -211,6 +309,7 @@ impl VirtioTransport {
                     core::arch::asm!("mfence", options(nostack, preserves_flags));
                 }
                 TransportType::PciModern => pci_modern::set_queue_avail(self.base, addr),
+                // Follows from the descriptor table (legacy_vring_layout)
                 TransportType::PciLegacy => {}
             }
         }
@@ -228,6 +327,7 @@ impl VirtioTransport {
                     core::arch::asm!("mfence", options(nostack, preserves_flags));
                 }
                 TransportType::PciModern => pci_modern::set_queue_used(self.base, addr),
+                // Follows from the descriptor table (legacy_vring_layout)
                 TransportType::PciLegacy => {}
             }
         }
@@ -243,6 +343,7 @@ impl VirtioTransport {
                     core::arch::asm!("mfence", options(nostack, preserves_flags));
                 }
                 TransportType::PciModern => pci_modern::enable_queue(self.base, 1),
+                // Writing QUEUE_PFN already enabled it
                 TransportType::PciLegacy => {}
             }
         }
@@ -266,7 +367,8 @@ impl VirtioTransport {
                     notify_addr
                 }
             }
-            TransportType::PciLegacy => 0,
+            // A port, tagged so the ring code does port I/O
+            TransportType::PciLegacy => PIO_NOTIFY | self.port(legacy_reg::QUEUE_NOTIFY) as u64,
         }
     }
 
@@ -283,7 +385,7 @@ impl VirtioTransport {
                     let notify_addr = self.get_notify_addr(queue_idx);
                     pci_modern::notify_queue(notify_addr, queue_idx);
                 }
-                TransportType::PciLegacy => {}
+                TransportType::PciLegacy => outw(self.port(legacy_reg::QUEUE_NOTIFY), queue_idx),
             }
         }
     }
@@ -309,7 +411,12 @@ impl VirtioTransport {
                     false
                 }
             }
-            TransportType::PciLegacy => false,
+            TransportType::PciLegacy => {
+                for (i, byte) in mac_out.iter_mut().enumerate() {
+                    *byte = unsafe { inb(self.port(legacy_reg::DEVICE_CONFIG + i as u16)) };
+                }
+                true
+            }
         }
     }
 
@@ -377,7 +484,10 @@ impl VirtioTransport {
                     0
                 }
             }
-            TransportType::PciLegacy => 0,
+            TransportType::PciLegacy => unsafe {
+                let config = self.port(legacy_reg::DEVICE_CONFIG);
+                inl(config) as u64 | (inl(config + 4) as u64) << 32
+            },
         }
     }
 
@@ -399,12 +509,13 @@ impl VirtioTransport {
                     512
                 }
             }
-            TransportType::PciLegacy => 512,
+            TransportType::PciLegacy => unsafe { inl(self.port(legacy_reg::DEVICE_CONFIG) + 20) },
         }
     }
 }
 
 /// PCI Modern transport ASM bindings
+#[cfg(target_arch = "x86_64")]
 pub mod pci_modern {
     extern "win64" {
         #[link_name = "asm_virtio_pci_get_status"]
@@ -456,3 +567,149 @@ pub mod pci_modern {
         pub fn read_mac(device_cfg: u64, mac_out: *mut u8);
     }
 }
+
+/// PCI Modern transport for targets without the assembly layer.
+///
+/// Same names and contracts as the `pci_modern.s` bindings, on top of
+/// `core::mmio`. `common_cfg` layout: VirtIO 1.2 §4.1.4.3.
+///
+/// All functions require mapped `common_cfg` / `notify` / `device_cfg`
+/// regions of a VirtIO PCI Modern device.
+#[cfg(not(target_arch = "x86_64"))]
+#[allow(clippy::missing_safety_doc)]
+pub mod pci_modern {
+    use crate::asm::core::barriers::mfence;
+    use crate::asm::core::mmio::{read16, read32, read8, write16, write32, write8};
+    use crate::asm::core::tsc::read_tsc;
+
+    const DFSELECT: u64 = 0x00;
+    const DF: u64 = 0x04;
+    const GFSELECT: u64 = 0x08;
+    const GF: u64 = 0x0C;
+    const NUMQUEUES: u64 = 0x12;
+    const STATUS: u64 = 0x14;
+    const Q_SELECT: u64 = 0x16;
+    const Q_SIZE: u64 = 0x18;
+    const Q_ENABLE: u64 = 0x1C;
+    const Q_NOFF: u64 = 0x1E;
+    const Q_DESC: u64 = 0x20;
+    const Q_AVAIL: u64 = 0x28;
+    const Q_USED: u64 = 0x30;
+
+    unsafe fn write64(addr: u64, value: u64) {
+        write32(addr, value as u32);
+        write32(addr + 4, (value >> 32) as u32);
+        mfence();
+    }
+
+    pub unsafe fn get_status(common_cfg: u64) -> u32 {
+        read8(common_cfg + STATUS) as u32
+    }
+
+    pub unsafe fn set_status(common_cfg: u64, status: u8) {
+        write8(common_cfg + STATUS, status);
+        mfence();
+    }
+
+    /// Returns 0 on success, 1 if the device did not clear status in 100ms.
+    pub unsafe fn reset(common_cfg: u64, tsc_freq: u64) -> u32 {
+        set_status(common_cfg, 0);
+        let timeout = tsc_freq / 10;
+        let start = read_tsc();
+        while read8(common_cfg + STATUS) != 0 {
+            if read_tsc().wrapping_sub(start) > timeout {
+                return 1;
+            }
+            core::hint::spin_loop();
+        }
+        0
+    }
+
+    pub unsafe fn read_features(common_cfg: u64) -> u64 {
+        write32(common_cfg + DFSELECT, 0);
+        let low = read32(common_cfg + DF) as u64;
+        write32(common_cfg + DFSELECT, 1);
+        let high = read32(common_cfg + DF) as u64;
+        (high << 32) | low
+    }
+
+    pub unsafe fn write_features(common_cfg: u64, features: u64) {
+        write32(common_cfg + GFSELECT, 0);
+        write32(common_cfg + GF, features as u32);
+        write32(common_cfg + GFSELECT, 1);
+        write32(common_cfg + GF, (features >> 32) as u32);
+        mfence();
+    }
+
+    pub unsafe fn get_num_queues(common_cfg: u64) -> u32 {
+        read16(common_cfg + NUMQUEUES) as u32
+    }
+
+    pub unsafe fn select_queue(common_cfg: u64, queue_idx: u16) {
+        write16(common_cfg + Q_SELECT, queue_idx);
+        mfence();
+    }
+
+    pub unsafe fn get_queue_size(common_cfg: u64) -> u32 {
+        read16(common_cfg + Q_SIZE) as u32
+    }
+
+    pub unsafe fn set_queue_size(common_cfg: u64, size: u16) {
+        write16(common_cfg + Q_SIZE, size);
+        mfence();
+    }
+
+    pub unsafe fn enable_queue(common_cfg: u64, enable: u16) {
+        write16(common_cfg + Q_ENABLE, enable);
+        mfence();
+    }
+
+    pub unsafe fn set_queue_desc(common_cfg: u64, addr: u64) {
+        write64(common_cfg + Q_DESC, addr);
+    }
+
+    pub unsafe fn set_queue_avail(common_cfg: u64, addr: u64) {
+        write64(common_cfg + Q_AVAIL, addr);
+    }
+
+    pub unsafe fn set_queue_used(common_cfg: u64, addr: u64) {
+        write64(common_cfg + Q_USED, addr);
+    }
+
+    pub unsafe fn get_queue_notify_off(common_cfg: u64) -> u32 {
+        read16(common_cfg + Q_NOFF) as u32
+    }
+
+    pub unsafe fn notify_queue(notify_addr: u64, queue_idx: u16) {
+        mfence();
+        write16(notify_addr, queue_idx);
+        mfence();
+    }
+
+    pub unsafe fn read_mac(device_cfg: u64, mac_out: *mut u8) {
+        for i in 0..6 {
+            *mac_out.add(i) = read8(device_cfg + i as u64);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_vring_layout() {
+        // QEMU's default legacy queue
+        let layout = legacy_vring_layout(256);
+        assert_eq!(layout.avail_offset, 4096);
+        assert_eq!(layout.used_offset, 8192);
+        assert_eq!(layout.size, 8192 + 6 + 8 * 256);
+
+        // Small queues still start the used ring on the next page
+        let layout = legacy_vring_layout(16);
+        assert_eq!(layout.avail_offset, 256);
+        assert_eq!(layout.used_offset, LEGACY_VRING_ALIGN);
+
+        assert!(legacy_vring_layout(LEGACY_MAX_QUEUE_SIZE).size <= 0x8000);
+    }
+}
@@ -9,7 +9,7 @@ use crate::dma::BufferPool;
 use crate::driver::traits::TxError;
 use crate::types::{VirtioNetHdr, VirtqueueState};
 
-/// Maximum frame size including VirtIO header.
+/// Maximum frame size including the (largest) VirtIO header.
 pub const MAX_TX_FRAME_SIZE: usize = VirtioNetHdr::SIZE + 1514;
 
 /// Transmit a packet via VirtIO.
@@ -18,6 +18,7 @@ pub const MAX_TX_FRAME_SIZE: usize = VirtioNetHdr::SIZE + 1514;
 /// - `tx_state`: TX virtqueue state
 /// - `tx_pool`: TX buffer pool
 /// - `frame`: Ethernet frame (without VirtIO header)
+/// - `hdr_len`: VirtIO header size for the negotiated features
 ///
 /// # Returns
 /// - `Ok(())`: Frame queued (fire-and-forget)
@@ -26,16 +27,17 @@ pub const MAX_TX_FRAME_SIZE: usize = VirtioNetHdr::SIZE + 1514;
 /// # Contract
 /// - MUST return immediately (no completion wait)
 /// - Caller should call `collect_tx_completions` periodically
-#[cfg(target_arch = "x86_64")]
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
 pub fn transmit(
     tx_state: &mut VirtqueueState,
     tx_pool: &mut BufferPool,
     frame: &[u8],
+    hdr_len: usize,
 ) -> Result<(), TxError> {
     use crate::asm::drivers::virtio::{notify, tx as asm_tx};
 
     // Check frame size
-    let total_len = VirtioNetHdr::SIZE + frame.len();
+    let total_len = hdr_len + frame.len();
     if total_len > MAX_TX_FRAME_SIZE {
         return Err(TxError::FrameTooLarge);
     }
@@ -47,12 +49,12 @@ pub fn transmit(
     let buf = tx_pool.alloc().ok_or(TxError::QueueFull)?;
     let buf_idx = buf.index();
 
-    // Write VirtIO header (12 bytes, all zeros)
+    // Write VirtIO header (all zeros; legacy devices take the first 10 bytes)
     let hdr = VirtioNetHdr::zeroed();
-    buf.as_mut_slice()[..VirtioNetHdr::SIZE].copy_from_slice(hdr.as_bytes());
+    buf.as_mut_slice()[..hdr_len].copy_from_slice(&hdr.as_bytes()[..hdr_len]);
 
     // Copy frame after header
-    buf.as_mut_slice()[VirtioNetHdr::SIZE..total_len].copy_from_slice(frame);
+    buf.as_mut_slice()[hdr_len..total_len].copy_from_slice(frame);
 
     // Mark device-owned BEFORE submit
     unsafe {
@@ -88,7 +90,7 @@ pub fn transmit(
 ///
 /// Call periodically (main loop Phase 5) to reclaim TX buffers.
 /// Also sends batched notification for any TX submissions since last call.
-#[cfg(target_arch = "x86_64")]
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
 pub fn collect_completions(tx_state: &mut VirtqueueState, tx_pool: &mut BufferPool) {
     use crate::asm::drivers::virtio::{notify, tx as asm_tx};
 
@@ -114,15 +116,16 @@ pub fn collect_completions(tx_state: &mut VirtqueueState, tx_pool: &mut BufferPo
     }
 }
 
-// Stubs for non-x86_64 platforms
-#[cfg(not(target_arch = "x86_64"))]
+// Stubs for targets without a port
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
 pub fn transmit(
     _tx_state: &mut VirtqueueState,
     _tx_pool: &mut BufferPool,
     _frame: &[u8],
+    _hdr_len: usize,
 ) -> Result<(), TxError> {
     Err(TxError::DeviceNotReady)
 }
 
-#[cfg(not(target_arch = "x86_64"))]
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
 pub fn collect_completions(_tx_state: &mut VirtqueueState, _tx_pool: &mut BufferPool) {}
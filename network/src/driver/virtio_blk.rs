@@ -10,20 +10,38 @@
 //!   2. Data buffer: read/write data
 //!   3. Status byte: completion status
 //!
+//! Flushes have no data and use just the header and status descriptors.
+//!
+//! With VIRTIO_F_INDIRECT_DESC (and indirect table memory in the config)
+//! the chain goes in a per-request indirect table instead, so each request
+//! takes one ring descriptor and the queue holds three times as many.
+//!
+//! # Batching
+//!
+//! Submitting only publishes a request; `notify` tells the device about
+//! everything published since the last one. Callers that keep several
+//! requests in flight submit a batch and notify once, which costs one VM
+//! exit per batch instead of one per request. `stats` reports how deep the
+//! queue actually ran.
+//!
 //! # Reference
 //! VirtIO Spec 1.2 §5.2, NETWORK_IMPL_GUIDE.md §6
 
 use crate::driver::block_traits::{
-    BlockCompletion, BlockDeviceInfo, BlockDriver, BlockDriverInit, BlockError,
+    BarrierStatus, BlockCompletion, BlockDeviceInfo, BlockDriver, BlockDriverInit, BlockError,
+    BlockEvent, WriteBarrier,
 };
 use crate::driver::virtio::transport::{TransportType, VirtioTransport};
+use crate::time::{self, Deadline};
 use crate::types::VirtqueueState;
 use core::ptr;
+use morpheus_core::disk::identity::{DeviceIdentity, MediaKind};
 
 // ═══════════════════════════════════════════════════════════════════════════
 // ASM BINDINGS
 // ═══════════════════════════════════════════════════════════════════════════
 
+#[cfg(target_arch = "x86_64")]
 extern "win64" {
     fn asm_virtio_blk_read_capacity(mmio_base: u64) -> u64;
     fn asm_virtio_blk_read_blk_size(mmio_base: u64) -> u32;
@@ -45,11 +63,24 @@ extern "win64" {
         status_buf_phys: u64,
         desc_idx: u16,
     ) -> u32;
+    fn asm_virtio_blk_submit_flush(
+        vq: *mut VirtqueueState,
+        header_buf_phys: u64,
+        status_buf_phys: u64,
+        desc_idx: u16,
+    ) -> u32;
+    fn asm_virtio_blk_submit_indirect(
+        vq: *mut VirtqueueState,
+        table_phys: u64,
+        table_len: u32,
+        desc_idx: u16,
+    ) -> u32;
     fn asm_virtio_blk_poll_complete(vq: *mut VirtqueueState, result: *mut BlkPollResult) -> u32;
     fn asm_virtio_blk_notify(vq: *mut VirtqueueState);
 }
 
 // Use existing VirtIO init functions
+#[cfg(target_arch = "x86_64")]
 extern "win64" {
     fn asm_virtio_reset(mmio_base: u64) -> u32;
     fn asm_virtio_set_status(mmio_base: u64, status: u8);
@@ -66,6 +97,263 @@ extern "win64" {
     ) -> u32;
 }
 
+#[cfg(not(target_arch = "x86_64"))]
+use portable::*;
+
+/// Rust versions of the `blk.s` / `init.s` entry points for targets without
+/// the assembly layer. Same names and contracts, so the driver is shared.
+#[cfg(not(target_arch = "x86_64"))]
+mod portable {
+    use super::BlkPollResult;
+    use crate::asm::core::barriers::{lfence, mfence, sfence};
+    use crate::asm::core::mmio;
+    use crate::asm::drivers::virtio::{device, queue};
+    use crate::types::VirtqueueState;
+    use core::ptr::{read_volatile, write_volatile};
+
+    const VIRTIO_MMIO_CONFIG: u64 = 0x100;
+    const VIRTIO_BLK_CFG_BLK_SIZE: u64 = 0x14;
+    const VIRTIO_BLK_T_IN: u32 = 0;
+    const VIRTIO_BLK_T_OUT: u32 = 1;
+    const VIRTIO_BLK_T_FLUSH: u32 = 4;
+    const VIRTQ_DESC_F_NEXT: u16 = 1;
+    const VIRTQ_DESC_F_WRITE: u16 = 2;
+    const VIRTQ_DESC_F_INDIRECT: u16 = 4;
+
+    pub unsafe fn asm_virtio_blk_read_capacity(mmio_base: u64) -> u64 {
+        let low = mmio::read32(mmio_base + VIRTIO_MMIO_CONFIG) as u64;
+        let high = mmio::read32(mmio_base + VIRTIO_MMIO_CONFIG + 4) as u64;
+        (high << 32) | low
+    }
+
+    pub unsafe fn asm_virtio_blk_read_blk_size(mmio_base: u64) -> u32 {
+        mmio::read32(mmio_base + VIRTIO_MMIO_CONFIG + VIRTIO_BLK_CFG_BLK_SIZE)
+    }
+
+    unsafe fn write_desc(desc: u64, addr: u64, len: u32, flags: u16, next: u16) {
+        write_volatile(desc as *mut u64, addr);
+        write_volatile((desc + 8) as *mut u32, len);
+        write_volatile((desc + 12) as *mut u16, flags);
+        write_volatile((desc + 14) as *mut u16, next);
+    }
+
+    /// Build the header/data/status chain at `desc_idx` and publish it.
+    #[allow(clippy::too_many_arguments)]
+    unsafe fn submit(
+        vq: *mut VirtqueueState,
+        req_type: u32,
+        data_flags: u16,
+        sector: u64,
+        data_buf_phys: u64,
+        num_sectors: u64,
+        header_buf_phys: u64,
+        status_buf_phys: u64,
+        desc_idx: u16,
+    ) -> u32 {
+        let vq = &mut *vq;
+        if vq.next_avail_idx.wrapping_sub(vq.last_used_idx) >= vq.queue_size {
+            return 1;
+        }
+
+        write_volatile(header_buf_phys as *mut u32, req_type);
+        write_volatile((header_buf_phys + 4) as *mut u32, 0);
+        write_volatile((header_buf_phys + 8) as *mut u64, sector);
+
+        let desc = vq.desc_base + desc_idx as u64 * 16;
+        write_desc(desc, header_buf_phys, 16, VIRTQ_DESC_F_NEXT, desc_idx + 1);
+        write_desc(
+            desc + 16,
+            data_buf_phys,
+            (num_sectors << 9) as u32,
+            VIRTQ_DESC_F_NEXT | data_flags,
+            desc_idx + 2,
+        );
+        write_desc(desc + 32, status_buf_phys, 1, VIRTQ_DESC_F_WRITE, 0);
+        sfence();
+
+        let mask = vq.queue_size.wrapping_sub(1);
+        let slot = vq.avail_base + 4 + (vq.next_avail_idx & mask) as u64 * 2;
+        write_volatile(slot as *mut u16, desc_idx);
+        sfence();
+
+        vq.next_avail_idx = vq.next_avail_idx.wrapping_add(1);
+        write_volatile((vq.avail_base + 2) as *mut u16, vq.next_avail_idx);
+        mfence();
+        0
+    }
+
+    /// Header + status only: a flush carries no data.
+    pub unsafe fn asm_virtio_blk_submit_flush(
+        vq: *mut VirtqueueState,
+        header_buf_phys: u64,
+        status_buf_phys: u64,
+        desc_idx: u16,
+    ) -> u32 {
+        let vq = &mut *vq;
+        if vq.next_avail_idx.wrapping_sub(vq.last_used_idx) >= vq.queue_size {
+            return 1;
+        }
+
+        write_volatile(header_buf_phys as *mut u32, VIRTIO_BLK_T_FLUSH);
+        write_volatile((header_buf_phys + 4) as *mut u32, 0);
+        write_volatile((header_buf_phys + 8) as *mut u64, 0);
+
+        let desc = vq.desc_base + desc_idx as u64 * 16;
+        write_desc(desc, header_buf_phys, 16, VIRTQ_DESC_F_NEXT, desc_idx + 1);
+        write_desc(desc + 16, status_buf_phys, 1, VIRTQ_DESC_F_WRITE, 0);
+        sfence();
+
+        let mask = vq.queue_size.wrapping_sub(1);
+        let slot = vq.avail_base + 4 + (vq.next_avail_idx & mask) as u64 * 2;
+        write_volatile(slot as *mut u16, desc_idx);
+        sfence();
+
+        vq.next_avail_idx = vq.next_avail_idx.wrapping_add(1);
+        write_volatile((vq.avail_base + 2) as *mut u16, vq.next_avail_idx);
+        mfence();
+        0
+    }
+
+    /// One ring descriptor pointing at a caller-built indirect table.
+    pub unsafe fn asm_virtio_blk_submit_indirect(
+        vq: *mut VirtqueueState,
+        table_phys: u64,
+        table_len: u32,
+        desc_idx: u16,
+    ) -> u32 {
+        let vq = &mut *vq;
+        if vq.next_avail_idx.wrapping_sub(vq.last_used_idx) >= vq.queue_size {
+            return 1;
+        }
+
+        let desc = vq.desc_base + desc_idx as u64 * 16;
+        write_desc(desc, table_phys, table_len, VIRTQ_DESC_F_INDIRECT, 0);
+        sfence();
+
+        let mask = vq.queue_size.wrapping_sub(1);
+        let slot = vq.avail_base + 4 + (vq.next_avail_idx & mask) as u64 * 2;
+        write_volatile(slot as *mut u16, desc_idx);
+        sfence();
+
+        vq.next_avail_idx = vq.next_avail_idx.wrapping_add(1);
+        write_volatile((vq.avail_base + 2) as *mut u16, vq.next_avail_idx);
+        mfence();
+        0
+    }
+
+    pub unsafe fn asm_virtio_blk_submit_read(
+        vq: *mut VirtqueueState,
+        sector: u64,
+        data_buf_phys: u64,
+        num_sectors: u64,
+        header_buf_phys: u64,
+        status_buf_phys: u64,
+        desc_idx: u16,
+    ) -> u32 {
+        submit(
+            vq,
+            VIRTIO_BLK_T_IN,
+            VIRTQ_DESC_F_WRITE,
+            sector,
+            data_buf_phys,
+            num_sectors,
+            header_buf_phys,
+            status_buf_phys,
+            desc_idx,
+        )
+    }
+
+    pub unsafe fn asm_virtio_blk_submit_write(
+        vq: *mut VirtqueueState,
+        sector: u64,
+        data_buf_phys: u64,
+        num_sectors: u64,
+        header_buf_phys: u64,
+        status_buf_phys: u64,
+        desc_idx: u16,
+    ) -> u32 {
+        submit(
+            vq,
+            VIRTIO_BLK_T_OUT,
+            0,
+            sector,
+            data_buf_phys,
+            num_sectors,
+            header_buf_phys,
+            status_buf_phys,
+            desc_idx,
+        )
+    }
+
+    pub unsafe fn asm_virtio_blk_poll_complete(
+        vq: *mut VirtqueueState,
+        result: *mut BlkPollResult,
+    ) -> u32 {
+        let vq = &mut *vq;
+        if read_volatile((vq.used_base + 2) as *const u16) == vq.last_used_idx {
+            return 0;
+        }
+        lfence();
+
+        let slot = (vq.last_used_idx & vq.queue_size.wrapping_sub(1)) as u64;
+        let elem = vq.used_base + 4 + slot * 8;
+        let id = read_volatile(elem as *const u32);
+        let len = read_volatile((elem + 4) as *const u32);
+        lfence();
+
+        vq.last_used_idx = vq.last_used_idx.wrapping_add(1);
+        *result = BlkPollResult {
+            desc_idx: id as u16,
+            // Caller reads the real status from the status buffer
+            status: 0xFF,
+            _pad: 0,
+            bytes_written: len,
+        };
+        1
+    }
+
+    pub unsafe fn asm_virtio_set_status(mmio_base: u64, status: u8) {
+        device::set_status(mmio_base, status)
+    }
+
+    pub unsafe fn asm_virtio_get_status(mmio_base: u64) -> u8 {
+        device::get_status(mmio_base)
+    }
+
+    pub unsafe fn asm_virtio_read_features(mmio_base: u64) -> u64 {
+        device::read_features(mmio_base)
+    }
+
+    pub unsafe fn asm_virtio_write_features(mmio_base: u64, features: u64) {
+        device::write_features(mmio_base, features)
+    }
+
+    /// Returns 0 on success, 1 if the queue is unavailable or too small.
+    pub unsafe fn asm_virtio_setup_queue(
+        mmio_base: u64,
+        queue_idx: u32,
+        desc_phys: u64,
+        avail_phys: u64,
+        used_phys: u64,
+        queue_size: u32,
+    ) -> u32 {
+        queue::select(mmio_base, queue_idx as u16);
+        let max = queue::get_max_size(mmio_base) as u32;
+        if max == 0 || queue_size > max {
+            return 1;
+        }
+        queue::setup(
+            mmio_base,
+            queue_idx as u16,
+            queue_size as u16,
+            desc_phys,
+            avail_phys,
+            used_phys,
+        );
+        0
+    }
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // TYPES
 // ═══════════════════════════════════════════════════════════════════════════
@@ -112,6 +400,7 @@ const VIRTIO_STATUS_FAILED: u8 = 0x80;
 
 /// VirtIO-blk feature bits
 const VIRTIO_F_VERSION_1: u64 = 1 << 32;
+const VIRTIO_F_INDIRECT_DESC: u64 = 1 << 28;
 const VIRTIO_BLK_F_SIZE_MAX: u64 = 1 << 1;
 const VIRTIO_BLK_F_SEG_MAX: u64 = 1 << 2;
 const VIRTIO_BLK_F_BLK_SIZE: u64 = 1 << 6;
@@ -124,6 +413,13 @@ const REQUIRED_FEATURES: u64 = VIRTIO_F_VERSION_1;
 /// Desired features
 const DESIRED_FEATURES: u64 = VIRTIO_BLK_F_BLK_SIZE | VIRTIO_BLK_F_FLUSH;
 
+/// Descriptor flags
+const VIRTQ_DESC_F_NEXT: u16 = 1;
+const VIRTQ_DESC_F_WRITE: u16 = 2;
+
+/// Size of one request's indirect table (header, data, status)
+pub const INDIRECT_TABLE_SIZE: usize = 3 * 16;
+
 // ═══════════════════════════════════════════════════════════════════════════
 // CONFIGURATION
 // ═══════════════════════════════════════════════════════════════════════════
@@ -151,6 +447,42 @@ pub struct VirtioBlkConfig {
     pub notify_addr: u64,
     /// Transport type: 0=MMIO, 1=PCI Modern
     pub transport_type: u8,
+    /// Physical address of indirect descriptor tables (`INDIRECT_TABLE_SIZE`
+    /// bytes per request, 16-byte aligned), or 0 for direct chains only
+    pub indirect_phys: u64,
+    /// CPU pointer to indirect descriptor tables
+    pub indirect_cpu: u64,
+    /// Most requests kept in flight (0 = as many as the queue holds)
+    pub queue_depth: u16,
+}
+
+/// Queue statistics.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VirtioBlkStats {
+    /// Requests submitted
+    pub requests: u64,
+    /// Notifications sent to the device
+    pub notifies: u64,
+    /// Requests in flight right after each submit, summed
+    pub depth_sum: u64,
+}
+
+impl VirtioBlkStats {
+    /// Average requests in flight at submit time, in hundredths.
+    pub fn avg_in_flight_x100(&self) -> u64 {
+        if self.requests == 0 {
+            return 0;
+        }
+        self.depth_sum * 100 / self.requests
+    }
+
+    /// Average requests per notification, in hundredths.
+    pub fn avg_batch_x100(&self) -> u64 {
+        if self.notifies == 0 {
+            return 0;
+        }
+        self.requests * 100 / self.notifies
+    }
 }
 
 /// Initialization errors.
@@ -195,7 +527,7 @@ impl From<super::virtio::init::VirtioInitError> for VirtioBlkInitError {
 struct InFlightRequest {
     /// Caller's request ID
     request_id: u32,
-    /// First descriptor index (head of 3-chain)
+    /// Head descriptor index (of the 3-chain, or the indirect descriptor)
     desc_idx: u16,
     /// Is this slot in use?
     active: bool,
@@ -215,9 +547,16 @@ impl Default for InFlightRequest {
 // DRIVER
 // ═══════════════════════════════════════════════════════════════════════════
 
-/// Maximum in-flight requests (queue_size / 3 since each request uses 3 descriptors)
+/// Maximum in-flight requests (also bounded by the queue: queue_size / 3
+/// with direct chains, queue_size with indirect descriptors)
 const MAX_IN_FLIGHT: usize = 32;
 
+/// Request ID of the flush issued by the blocking `flush()`
+const FLUSH_REQUEST_ID: u32 = 0xFFFF_F1F1;
+
+/// Budget for the blocking `flush()`; a large write cache can take a while
+const FLUSH_TIMEOUT_MS: u64 = 30_000;
+
 /// VirtIO block device driver.
 pub struct VirtioBlkDriver {
     /// MMIO base address (legacy) or common_cfg (PCI Modern)
@@ -242,6 +581,36 @@ pub struct VirtioBlkDriver {
     headers_phys: u64,
     /// Status physical/bus address (for DMA descriptors)
     status_phys: u64,
+    /// Indirect tables CPU pointer (null without VIRTIO_F_INDIRECT_DESC)
+    indirect_cpu: *mut u8,
+    /// Indirect tables physical/bus address
+    indirect_phys: u64,
+    /// Most requests kept in flight
+    queue_depth: usize,
+    /// Requests published since the last notify
+    unnotified: u32,
+    /// Queue statistics
+    stats: VirtioBlkStats,
+}
+
+/// Requests that fit in a queue of `queue_size` descriptors, capped at
+/// `requested` (0 = no cap).
+fn effective_queue_depth(queue_size: u16, requested: u16, indirect: bool) -> usize {
+    let per_request = if indirect { 1 } else { 3 };
+    let depth = (queue_size as usize / per_request).min(MAX_IN_FLIGHT);
+    match requested {
+        0 => depth,
+        requested => depth.min(requested as usize),
+    }
+}
+
+/// Write one descriptor of an indirect table.
+unsafe fn write_table_desc(table: *mut u8, index: u16, addr: u64, len: u32, flags: u16) {
+    let desc = table.add(index as usize * 16);
+    ptr::write_volatile(desc as *mut u64, addr);
+    ptr::write_volatile(desc.add(8) as *mut u32, len);
+    ptr::write_volatile(desc.add(12) as *mut u16, flags);
+    ptr::write_volatile(desc.add(14) as *mut u16, index + 1);
 }
 
 impl VirtioBlkDriver {
@@ -293,7 +662,12 @@ impl VirtioBlkDriver {
             return Err(VirtioBlkInitError::FeatureNegotiationFailed);
         }
 
-        let our_features = REQUIRED_FEATURES | (DESIRED_FEATURES & device_features);
+        let desired = if config.indirect_phys != 0 {
+            DESIRED_FEATURES | VIRTIO_F_INDIRECT_DESC
+        } else {
+            DESIRED_FEATURES
+        };
+        let our_features = REQUIRED_FEATURES | (desired & device_features);
         asm_virtio_write_features(mmio_base, our_features);
 
         // ═══════════════════════════════════════════════════════════════════
@@ -349,12 +723,17 @@ impl VirtioBlkDriver {
             512
         };
         let read_only = device_features & VIRTIO_BLK_F_RO != 0;
+        let indirect = our_features & VIRTIO_F_INDIRECT_DESC != 0;
 
         let info = BlockDeviceInfo {
             total_sectors: capacity,
             sector_size,
             max_sectors_per_request: 128, // Conservative default
             read_only,
+            removable: false,
+            // GET_ID is not issued; the model alone tells disks apart from AHCI
+            identity: DeviceIdentity::from_virtio_id(&[]),
+            media: MediaKind::Unknown,
         };
 
         // Build queue state
@@ -388,6 +767,15 @@ impl VirtioBlkDriver {
             status_cpu: config.status_cpu as *mut u8,
             headers_phys: config.headers_phys,
             status_phys: config.status_phys,
+            indirect_cpu: if indirect {
+                config.indirect_cpu as *mut u8
+            } else {
+                ptr::null_mut()
+            },
+            indirect_phys: config.indirect_phys,
+            queue_depth: effective_queue_depth(config.queue_size, config.queue_depth, indirect),
+            unnotified: 0,
+            stats: VirtioBlkStats::default(),
         })
     }
 
@@ -447,7 +835,12 @@ impl VirtioBlkDriver {
             return Err(VirtioBlkInitError::FeatureNegotiationFailed);
         }
 
-        let our_features = REQUIRED_FEATURES | (DESIRED_FEATURES & device_features);
+        let desired = if config.indirect_phys != 0 {
+            DESIRED_FEATURES | VIRTIO_F_INDIRECT_DESC
+        } else {
+            DESIRED_FEATURES
+        };
+        let our_features = REQUIRED_FEATURES | (desired & device_features);
         transport.write_features(our_features);
 
         // ═══════════════════════════════════════════════════════════════════
@@ -495,12 +888,17 @@ impl VirtioBlkDriver {
             512
         };
         let read_only = device_features & VIRTIO_BLK_F_RO != 0;
+        let indirect = our_features & VIRTIO_F_INDIRECT_DESC != 0;
 
         let info = BlockDeviceInfo {
             total_sectors: capacity,
             sector_size,
             max_sectors_per_request: 128, // Conservative default
             read_only,
+            removable: false,
+            // GET_ID is not issued; the model alone tells disks apart from AHCI
+            identity: DeviceIdentity::from_virtio_id(&[]),
+            media: MediaKind::Unknown,
         };
 
         // Build queue state
@@ -534,21 +932,90 @@ impl VirtioBlkDriver {
             status_cpu: config.status_cpu as *mut u8,
             headers_phys: config.headers_phys,
             status_phys: config.status_phys,
+            indirect_cpu: if indirect {
+                config.indirect_cpu as *mut u8
+            } else {
+                ptr::null_mut()
+            },
+            indirect_phys: config.indirect_phys,
+            queue_depth: effective_queue_depth(config.queue_size, config.queue_depth, indirect),
+            unnotified: 0,
+            stats: VirtioBlkStats::default(),
         })
     }
 
-    /// Allocate a descriptor set (3 consecutive descriptors).
+    /// Whether requests go through indirect descriptor tables.
+    fn indirect(&self) -> bool {
+        !self.indirect_cpu.is_null()
+    }
+
+    /// Allocate a descriptor set (3 consecutive descriptors, or the one
+    /// ring descriptor of an indirect request).
     fn alloc_desc_set(&mut self) -> Option<(u16, u32)> {
+        let per_request = if self.indirect() { 1 } else { 3 };
         // Find free slot in in_flight
-        for (slot_idx, slot) in self.in_flight.iter_mut().enumerate() {
+        for (slot_idx, slot) in self.in_flight[..self.queue_depth].iter().enumerate() {
             if !slot.active {
-                let desc_idx = (slot_idx * 3) as u16;
+                let desc_idx = (slot_idx * per_request) as u16;
                 return Some((desc_idx, slot_idx as u32));
             }
         }
         None
     }
 
+    /// Publish the request in `slot_idx` through its indirect table.
+    ///
+    /// `data` is the data descriptor (address, length, flags); flushes
+    /// have none. The header must already be written.
+    unsafe fn submit_indirect(
+        &mut self,
+        slot_idx: usize,
+        desc_idx: u16,
+        data: Option<(u64, u32, u16)>,
+    ) -> u32 {
+        let table = self.indirect_cpu.add(slot_idx * INDIRECT_TABLE_SIZE);
+        let header_phys = self.header_phys(slot_idx);
+        write_table_desc(table, 0, header_phys, 16, VIRTQ_DESC_F_NEXT);
+        let mut count = 1;
+        if let Some((addr, len, flags)) = data {
+            write_table_desc(table, 1, addr, len, flags | VIRTQ_DESC_F_NEXT);
+            count = 2;
+        }
+        let status_phys = self.status_phys(slot_idx);
+        write_table_desc(table, count, status_phys, 1, VIRTQ_DESC_F_WRITE);
+        // The last descriptor ends the chain
+        ptr::write_volatile(table.add(count as usize * 16 + 14) as *mut u16, 0);
+
+        asm_virtio_blk_submit_indirect(
+            &mut self.queue,
+            self.indirect_phys + (slot_idx * INDIRECT_TABLE_SIZE) as u64,
+            (count as u32 + 1) * 16,
+            desc_idx,
+        )
+    }
+
+    /// Track a published request until it completes.
+    fn track(&mut self, slot_idx: u32, desc_idx: u16, request_id: u32) {
+        self.in_flight[slot_idx as usize] = InFlightRequest {
+            request_id,
+            desc_idx,
+            active: true,
+        };
+        self.unnotified += 1;
+        self.stats.requests += 1;
+        self.stats.depth_sum += self.in_flight() as u64;
+    }
+
+    /// Queue statistics since the driver was created.
+    pub fn stats(&self) -> VirtioBlkStats {
+        self.stats
+    }
+
+    /// Most requests this driver keeps in flight.
+    pub fn queue_depth(&self) -> usize {
+        self.queue_depth
+    }
+
     /// Get header physical address for a descriptor set.
     fn header_phys(&self, slot_idx: usize) -> u64 {
         // Use physical address for DMA - device needs bus address, not CPU pointer
@@ -574,7 +1041,7 @@ impl BlockDriver for VirtioBlkDriver {
 
     fn can_submit(&self) -> bool {
         // Check if we have a free descriptor set
-        self.in_flight.iter().any(|s| !s.active)
+        self.in_flight[..self.queue_depth].iter().any(|s| !s.active)
     }
 
     fn submit_read(
@@ -609,15 +1076,20 @@ impl BlockDriver for VirtioBlkDriver {
 
         // Submit via ASM
         let result = unsafe {
-            asm_virtio_blk_submit_read(
-                &mut self.queue,
-                sector,
-                buffer_phys,
-                num_sectors as u64,
-                self.header_phys(slot_idx as usize),
-                self.status_phys(slot_idx as usize),
-                desc_idx,
-            )
+            if self.indirect() {
+                let data = (buffer_phys, num_sectors << 9, VIRTQ_DESC_F_WRITE);
+                self.submit_indirect(slot_idx as usize, desc_idx, Some(data))
+            } else {
+                asm_virtio_blk_submit_read(
+                    &mut self.queue,
+                    sector,
+                    buffer_phys,
+                    num_sectors as u64,
+                    self.header_phys(slot_idx as usize),
+                    self.status_phys(slot_idx as usize),
+                    desc_idx,
+                )
+            }
         };
 
         if result != 0 {
@@ -625,11 +1097,7 @@ impl BlockDriver for VirtioBlkDriver {
         }
 
         // Track in-flight
-        self.in_flight[slot_idx as usize] = InFlightRequest {
-            request_id,
-            desc_idx,
-            active: true,
-        };
+        self.track(slot_idx, desc_idx, request_id);
 
         Ok(())
     }
@@ -671,15 +1139,20 @@ impl BlockDriver for VirtioBlkDriver {
 
         // Submit via ASM
         let result = unsafe {
-            asm_virtio_blk_submit_write(
-                &mut self.queue,
-                sector,
-                buffer_phys,
-                num_sectors as u64,
-                self.header_phys(slot_idx as usize),
-                self.status_phys(slot_idx as usize),
-                desc_idx,
-            )
+            if self.indirect() {
+                let data = (buffer_phys, num_sectors << 9, 0);
+                self.submit_indirect(slot_idx as usize, desc_idx, Some(data))
+            } else {
+                asm_virtio_blk_submit_write(
+                    &mut self.queue,
+                    sector,
+                    buffer_phys,
+                    num_sectors as u64,
+                    self.header_phys(slot_idx as usize),
+                    self.status_phys(slot_idx as usize),
+                    desc_idx,
+                )
+            }
         };
 
         if result != 0 {
@@ -687,11 +1160,7 @@ impl BlockDriver for VirtioBlkDriver {
         }
 
         // Track in-flight
-        self.in_flight[slot_idx as usize] = InFlightRequest {
-            request_id,
-            desc_idx,
-            active: true,
-        };
+        self.track(slot_idx, desc_idx, request_id);
 
         Ok(())
     }
@@ -706,8 +1175,12 @@ impl BlockDriver for VirtioBlkDriver {
         }
 
         // Find matching in-flight request
-        let slot_idx = (result.desc_idx / 3) as usize;
-        if slot_idx >= MAX_IN_FLIGHT {
+        let slot_idx = if self.indirect() {
+            result.desc_idx as usize
+        } else {
+            (result.desc_idx / 3) as usize
+        };
+        if slot_idx >= self.queue_depth {
             return None;
         }
 
@@ -736,21 +1209,88 @@ impl BlockDriver for VirtioBlkDriver {
     }
 
     fn notify(&mut self) {
+        // One notify covers the whole batch published since the last one
+        if self.unnotified == 0 {
+            return;
+        }
+        self.unnotified = 0;
+        self.stats.notifies += 1;
+
         // Use transport-aware notify (handles MMIO vs PCI Modern differences)
         self.transport.notify_queue(0); // queue 0 for VirtIO-blk
     }
 
     fn flush(&mut self) -> Result<(), BlockError> {
-        // VirtIO-blk flush requires a special 2-descriptor chain (header + status, no data).
-        // Our ASM submit_write expects a 3-descriptor chain and doesn't handle 0-sector writes.
-        // For now, return Ok() - writes are synchronous and QEMU will sync on exit.
-        // TODO: Implement proper flush with dedicated ASM function for 2-descriptor chains.
-        //
-        // Note: Even without explicit flush, VirtIO-blk writes are durable once
-        // the device returns completion status 0. The flush command is mainly
-        // needed for volatile write caches which QEMU doesn't use by default.
+        // Without an installed clock, assume a 4GHz TSC (the old fixed budget).
+        let deadline = Deadline::after_ms(time::active_or_tsc(4_000_000_000), FLUSH_TIMEOUT_MS);
+        let mut barrier = WriteBarrier::new(FLUSH_REQUEST_ID);
+        loop {
+            match barrier.step(self) {
+                BarrierStatus::Done => return Ok(()),
+                BarrierStatus::Failed(err) => return Err(err),
+                BarrierStatus::Pending if deadline.expired() => return Err(BlockError::Timeout),
+                BarrierStatus::Pending => core::hint::spin_loop(),
+            }
+        }
+    }
+
+    fn submit_flush(&mut self, request_id: u32) -> Result<(), BlockError> {
+        // Without VIRTIO_BLK_F_FLUSH the device is write-through
+        if self.features & VIRTIO_BLK_F_FLUSH == 0 {
+            return Err(BlockError::Unsupported);
+        }
+
+        let (desc_idx, slot_idx) = self.alloc_desc_set().ok_or(BlockError::QueueFull)?;
+
+        unsafe {
+            ptr::write_volatile(self.status_cpu.add(slot_idx as usize), 0xFF);
+        }
+
+        let result = unsafe {
+            if self.indirect() {
+                let header = VirtioBlkReqHeader {
+                    req_type: VirtioBlkReqHeader::TYPE_FLUSH,
+                    reserved: 0,
+                    sector: 0,
+                };
+                ptr::write_volatile(self.headers_cpu.add(slot_idx as usize), header);
+                self.submit_indirect(slot_idx as usize, desc_idx, None)
+            } else {
+                asm_virtio_blk_submit_flush(
+                    &mut self.queue,
+                    self.header_phys(slot_idx as usize),
+                    self.status_phys(slot_idx as usize),
+                    desc_idx,
+                )
+            }
+        };
+
+        if result != 0 {
+            return Err(BlockError::QueueFull);
+        }
+
+        self.track(slot_idx, desc_idx, request_id);
+
         Ok(())
     }
+
+    fn in_flight(&self) -> usize {
+        self.in_flight.iter().filter(|s| s.active).count()
+    }
+
+    fn poll_event(&mut self) -> Option<BlockEvent> {
+        // Capacity lives in device config space; a resize just changes it
+        let capacity = self.transport.read_blk_capacity();
+        if capacity == self.info.total_sectors {
+            return None;
+        }
+        let old_sectors = self.info.total_sectors;
+        self.info.total_sectors = capacity;
+        Some(BlockEvent::CapacityChanged {
+            old_sectors,
+            new_sectors: capacity,
+        })
+    }
 }
 
 impl BlockDriverInit for VirtioBlkDriver {
@@ -772,3 +1312,33 @@ impl BlockDriverInit for VirtioBlkDriver {
 
 // Safety: VirtioBlkDriver only contains raw pointers that are not shared
 unsafe impl Send for VirtioBlkDriver {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_effective_queue_depth() {
+        // Direct chains take 3 descriptors each
+        assert_eq!(effective_queue_depth(64, 0, false), 21);
+        assert_eq!(effective_queue_depth(64, 0, true), 32);
+        assert_eq!(effective_queue_depth(16, 0, true), 16);
+        assert_eq!(effective_queue_depth(256, 8, true), 8);
+        assert_eq!(effective_queue_depth(64, 100, false), 21);
+    }
+
+    #[test]
+    fn test_stats_averages() {
+        assert_eq!(VirtioBlkStats::default().avg_in_flight_x100(), 0);
+        assert_eq!(VirtioBlkStats::default().avg_batch_x100(), 0);
+
+        // Two batches of two: depths 1, 2, then 2, 3
+        let stats = VirtioBlkStats {
+            requests: 4,
+            notifies: 2,
+            depth_sum: 8,
+        };
+        assert_eq!(stats.avg_in_flight_x100(), 200);
+        assert_eq!(stats.avg_batch_x100(), 200);
+    }
+}
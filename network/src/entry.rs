@@ -34,15 +34,20 @@
 //!     url: "http://example.com/image.iso",
 //!     iso_name: "image.iso",
 //!     esp_start_lba: 2048,
+//!     rsdp: 0, // firmware RSDP; required on aarch64
 //! });
 //! ```
 
 use crate::dma::DmaRegion;
 use crate::boot::probe::{scan_for_nic, DetectedNic, ProbeError};
-use crate::driver::virtio::{VirtioConfig, VirtioNetDriver};
-use crate::driver::intel::{E1000eConfig, E1000eDriver};
-use crate::mainloop::{download_with_config, DownloadConfig, DownloadResult};
+use crate::driver::virtio::{VirtioConfig, VirtioNetDriver, VirtioTransport};
+use crate::driver::intel::{E1000eConfig, E1000eDriver, LinkMode, LowPowerPolicy};
+use crate::pci::config::{offset, pci_cfg_read16, pci_cfg_write16, PciAddr};
+use crate::mainloop::{download_with_config, DownloadConfig, DownloadResult, PostActions};
+use crate::transfer::disk::{DiskSelector, Placement};
 use crate::mainloop::serial::{print, println, print_hex};
+use crate::boot::handoff::has_invariant_tsc;
+use crate::time::{self, Clock, HPET_DEFAULT_BASE};
 
 // ═══════════════════════════════════════════════════════════════════════════
 // CONFIGURATION
@@ -64,6 +69,8 @@ pub struct RunConfig<'a> {
     pub iso_name: &'static str,
     /// ESP start LBA (0 = don't write to disk)
     pub esp_start_lba: u64,
+    /// ACPI RSDP (0 = none). aarch64 needs it to find ECAM and the UART.
+    pub rsdp: u64,
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -105,7 +112,25 @@ pub enum RunResult {
 /// - Must be called after hwinit
 /// - DMA region must be valid
 pub unsafe fn run_download(config: RunConfig<'_>) -> RunResult {
+    // No port I/O on aarch64: serial and PCI config need their MMIO bases
+    // from ACPI before anything else.
+    #[cfg(target_arch = "aarch64")]
+    crate::arch::init_from_acpi(config.rsdp);
+
     println("[NET] Network stack starting");
+
+    // Pick the timeout clock before any driver polls hardware.
+    let hpet = if cfg!(target_arch = "x86_64") {
+        Some(HPET_DEFAULT_BASE)
+    } else {
+        None
+    };
+    let clock = time::select_clock(config.tsc_freq, has_invariant_tsc(), hpet, None);
+    time::install(clock);
+    // File timestamps: the firmware RTC is gone, HTTP responses date them
+    morpheus_core::time::set_clock(Some(time::wall::now));
+    print("[NET] Clock: ");
+    println(clock.source().name());
     println("[NET] Scanning for NIC...");
 
     // Step 1: Find a NIC
@@ -125,11 +150,17 @@ pub unsafe fn run_download(config: RunConfig<'_>) -> RunResult {
             println("");
             run_with_virtio(config, mmio_base)
         }
+        DetectedNic::VirtioLegacy { pci_addr, io_base } => {
+            print("[NET] Found legacy VirtIO-net @ port ");
+            print_hex(io_base as u64);
+            println("");
+            run_with_virtio_legacy(config, pci_addr, io_base)
+        }
         DetectedNic::Intel(info) => {
             print("[NET] Found Intel e1000e @ ");
             print_hex(info.mmio_base);
             println("");
-            run_with_intel(config, info.mmio_base)
+            run_with_intel(config, info.mmio_base, info.pci_addr)
         }
     }
 }
@@ -156,8 +187,39 @@ unsafe fn run_with_virtio(config: RunConfig<'_>, mmio_base: u64) -> RunResult {
     run_download_with_driver(&mut driver, config)
 }
 
+/// Run download with a legacy VirtIO driver (I/O port transport).
+unsafe fn run_with_virtio_legacy(
+    config: RunConfig<'_>,
+    pci_addr: PciAddr,
+    io_base: u16,
+) -> RunResult {
+    // I/O space and bus mastering
+    let cmd = pci_cfg_read16(pci_addr, offset::COMMAND);
+    pci_cfg_write16(pci_addr, offset::COMMAND, cmd | 0x05);
+
+    let virtio_cfg = VirtioConfig {
+        dma_cpu_base: config.dma_region.cpu_base(),
+        dma_bus_base: config.dma_region.bus_base(),
+        dma_size: config.dma_region.size(),
+        queue_size: VirtioConfig::DEFAULT_QUEUE_SIZE,
+        buffer_size: VirtioConfig::DEFAULT_BUFFER_SIZE,
+    };
+
+    let transport = VirtioTransport::pci_legacy(io_base);
+    let mut driver = match VirtioNetDriver::new_with_transport(transport, virtio_cfg, config.tsc_freq) {
+        Ok(d) => d,
+        Err(_) => {
+            println("[NET] VirtIO driver init failed");
+            return RunResult::DriverInitFailed;
+        }
+    };
+
+    println("[NET] Legacy VirtIO driver initialized");
+    run_download_with_driver(&mut driver, config)
+}
+
 /// Run download with Intel e1000e driver.
-unsafe fn run_with_intel(config: RunConfig<'_>, mmio_base: u64) -> RunResult {
+unsafe fn run_with_intel(config: RunConfig<'_>, mmio_base: u64, pci_addr: PciAddr) -> RunResult {
     let intel_cfg = E1000eConfig {
         dma_cpu_base: config.dma_region.cpu_base(),
         dma_bus_base: config.dma_region.bus_base(),
@@ -165,6 +227,9 @@ unsafe fn run_with_intel(config: RunConfig<'_>, mmio_base: u64) -> RunResult {
         tx_queue_size: 32,
         buffer_size: 2048,
         tsc_freq: config.tsc_freq,
+        pci_addr: Some(pci_addr),
+        low_power: LowPowerPolicy::Auto,
+        link_mode: LinkMode::Auto,
     };
 
     let mut driver = match E1000eDriver::new(mmio_base, intel_cfg) {
@@ -193,6 +258,21 @@ fn run_download_with_driver<D: crate::driver::traits::NetworkDriver>(
         partition_uuid: [0u8; 16],
         iso_name: config.iso_name,
         expected_size: 0,
+        expected_sha256: None,
+        checksums: "",
+        placement: Placement::default(),
+        post_actions: PostActions::REBOOT,
+        target_disk: DiskSelector::First,
+        esp_disk: DiskSelector::First,
+        zeroed_target: false,
+        spot_check_every: 0,
+        require_tls: false,
+        beeps: false,
+        hosts: "",
+        rate_limit: 0,
+        proxy: "",
+        link_wait_secs: 0,
+        progress: None,
     };
 
     let result = download_with_config(driver, download_config, None, config.tsc_freq);
@@ -204,7 +284,7 @@ fn run_download_with_driver<D: crate::driver::traits::NetworkDriver>(
             println(" bytes");
             RunResult::Success { bytes: bytes_written as u64 }
         }
-        DownloadResult::Failed { reason } => {
+        DownloadResult::Failed { reason, .. } => {
             print("[NET] Download failed: ");
             println(reason);
             RunResult::DownloadFailed
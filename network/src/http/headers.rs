@@ -223,6 +223,110 @@ impl Headers {
     }
 }
 
+/// Parse an HTTP date (`Sun, 06 Nov 1994 08:49:37 GMT`, the IMF-fixdate
+/// servers are required to send) into Unix seconds.
+///
+/// The obsolete RFC 850 and asctime forms aren't accepted; neither are
+/// dates outside what a `u32` holds.
+pub fn parse_http_date(value: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let mut parts = value.trim().split(' ');
+    let _weekday = parts.next().filter(|d| d.ends_with(','))?;
+    let day: u32 = parts.next()?.parse().ok()?;
+    let month = parts.next()?;
+    let month = MONTHS.iter().position(|m| *m == month)? as u32 + 1;
+    let year: i64 = parts.next()?.parse().ok()?;
+    let mut time = parts.next()?.split(':').map(|t| t.parse::<u32>().ok());
+    let (hour, minute, second) = (time.next()??, time.next()??, time.next()??);
+    if parts.next()? != "GMT" || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60
+    {
+        return None;
+    }
+
+    // Days since 1970-01-01 (civil-from-days, inverted)
+    let (y, m) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * m as i64 + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+
+    let secs = days * 86_400 + (hour * 3600 + minute * 60 + second) as i64;
+    u32::try_from(secs).ok()
+}
+
+/// Length of an IMF-fixdate (`Sun, 06 Nov 1994 08:49:37 GMT`).
+pub const HTTP_DATE_LEN: usize = 29;
+
+/// Format Unix seconds as an HTTP date, the form [`parse_http_date`]
+/// reads back.
+pub fn format_http_date(secs: u32, buf: &mut [u8; HTTP_DATE_LEN]) -> &str {
+    const WEEKDAYS: [&[u8; 3]; 7] = [b"Sun", b"Mon", b"Tue", b"Wed", b"Thu", b"Fri", b"Sat"];
+    const MONTHS: [&[u8; 3]; 12] = [
+        b"Jan", b"Feb", b"Mar", b"Apr", b"May", b"Jun", b"Jul", b"Aug", b"Sep", b"Oct", b"Nov",
+        b"Dec",
+    ];
+
+    let days = (secs / 86_400) as i64;
+    let time = secs % 86_400;
+
+    // Civil date from days since 1970-01-01
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as usize;
+    let year = (yoe + era * 400 + (month <= 2) as i64) as u32;
+
+    let two = |n: u32| [b'0' + (n / 10) as u8, b'0' + (n % 10) as u8];
+    buf[0..3].copy_from_slice(WEEKDAYS[((days + 4) % 7) as usize]);
+    buf[3..5].copy_from_slice(b", ");
+    buf[5..7].copy_from_slice(&two(day));
+    buf[7] = b' ';
+    buf[8..11].copy_from_slice(MONTHS[month - 1]);
+    buf[11] = b' ';
+    buf[12..14].copy_from_slice(&two(year / 100));
+    buf[14..16].copy_from_slice(&two(year % 100));
+    buf[16] = b' ';
+    buf[17..19].copy_from_slice(&two(time / 3600));
+    buf[19] = b':';
+    buf[20..22].copy_from_slice(&two(time / 60 % 60));
+    buf[22] = b':';
+    buf[23..25].copy_from_slice(&two(time % 60));
+    buf[25..29].copy_from_slice(b" GMT");
+    // Only ASCII was written
+    core::str::from_utf8(buf).unwrap_or("")
+}
+
+/// Parse a `Content-Range` value (`bytes 100-199/1000`) into the first
+/// and last byte sent and the full length (`None` for `*`).
+///
+/// Unsatisfied ranges (`bytes */1000`) and other units are `None`.
+pub fn parse_content_range(value: &str) -> Option<(u64, u64, Option<u64>)> {
+    let range = value.trim().strip_prefix("bytes ")?;
+    let (span, complete) = range.split_once('/')?;
+    let (first, last) = span.split_once('-')?;
+    let (first, last): (u64, u64) = (first.parse().ok()?, last.parse().ok()?);
+    let complete = match complete {
+        "*" => None,
+        len => Some(len.parse().ok()?),
+    };
+    if last < first || complete.is_some_and(|len| last >= len) {
+        return None;
+    }
+    Some((first, last, complete))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -552,4 +656,57 @@ mod tests {
         assert!(header.name_matches("CONTENT-TYPE"));
         assert!(!header.name_matches("Content-Length"));
     }
+
+    // ==================== HTTP Dates ====================
+
+    #[test]
+    fn test_parse_http_date() {
+        assert_eq!(
+            parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"),
+            Some(784111777)
+        );
+        assert_eq!(parse_http_date("Thu, 01 Jan 1970 00:00:00 GMT"), Some(0));
+        // Leap day
+        assert_eq!(
+            parse_http_date("Thu, 29 Feb 2024 12:00:00 GMT"),
+            Some(1709208000)
+        );
+        // Obsolete forms, garbage, and dates before the epoch
+        assert_eq!(parse_http_date("Sunday, 06-Nov-94 08:49:37 GMT"), None);
+        assert_eq!(parse_http_date("Sun Nov  6 08:49:37 1994"), None);
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37 PST"), None);
+        assert_eq!(parse_http_date("Wed, 31 Dec 1969 23:59:59 GMT"), None);
+    }
+
+    #[test]
+    fn test_format_http_date() {
+        let mut buf = [0u8; HTTP_DATE_LEN];
+        assert_eq!(
+            format_http_date(784111777, &mut buf),
+            "Sun, 06 Nov 1994 08:49:37 GMT"
+        );
+        assert_eq!(
+            format_http_date(0, &mut buf),
+            "Thu, 01 Jan 1970 00:00:00 GMT"
+        );
+        for secs in [1709208000, 951782400, 4102444799, u32::MAX] {
+            assert_eq!(
+                parse_http_date(format_http_date(secs, &mut buf)),
+                Some(secs)
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_content_range() {
+        assert_eq!(
+            parse_content_range("bytes 100-199/1000"),
+            Some((100, 199, Some(1000)))
+        );
+        assert_eq!(parse_content_range(" bytes 0-0/*"), Some((0, 0, None)));
+        assert_eq!(parse_content_range("bytes */1000"), None);
+        assert_eq!(parse_content_range("bytes 200-100/1000"), None);
+        assert_eq!(parse_content_range("bytes 0-1000/1000"), None);
+        assert_eq!(parse_content_range("items 0-9/10"), None);
+    }
 }
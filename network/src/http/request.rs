@@ -95,6 +95,19 @@ impl Request {
         self
     }
 
+    /// Ask for the body from byte `offset` on (`Range: bytes=N-`), to
+    /// continue an interrupted download. The server answers 206 with a
+    /// `Content-Range`, or 200 with the whole body if it ignores ranges.
+    pub fn with_range(self, offset: u64) -> Self {
+        self.with_header("Range", alloc::format!("bytes={}-", offset))
+    }
+
+    /// Only honour the range if the resource still matches `validator`,
+    /// an ETag or an HTTP date; otherwise the server sends all of it.
+    pub fn with_if_range(self, validator: impl Into<String>) -> Self {
+        self.with_header("If-Range", validator)
+    }
+
     /// Get the request method as string.
     pub fn method_str(&self) -> &'static str {
         self.method.as_str()
@@ -247,6 +260,25 @@ mod tests {
         assert_eq!(request.headers.content_type(), Some("application/json"));
     }
 
+    #[test]
+    fn test_with_range() {
+        let request = Request::get(test_url())
+            .with_range(1_048_576)
+            .with_if_range("\"abc123\"");
+        let wire = String::from_utf8(request.to_wire_format()).unwrap();
+
+        assert!(wire.contains("Range: bytes=1048576-\r\n"));
+        assert!(wire.contains("If-Range: \"abc123\"\r\n"));
+    }
+
+    #[test]
+    fn test_with_range_replaces() {
+        let request = Request::get(test_url()).with_range(10).with_range(20);
+
+        assert_eq!(request.headers.get("Range"), Some("bytes=20-"));
+        assert_eq!(request.headers.get_all("Range").len(), 1);
+    }
+
     // ==================== Method String ====================
 
     #[test]
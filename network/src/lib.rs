@@ -34,8 +34,13 @@
 //! - `driver` - Network driver trait and implementations (VirtIO, Intel e1000e)
 //! - `boot` - Device probing and driver creation helpers
 //! - `asm` - Assembly bindings (MMIO, PIO, TSC, barriers)
+//! - `arch` - Per-architecture primitives (x86_64, aarch64) behind `asm`
+//! - `acpi` - ACPI table lookup (FADT, MCFG, SPCR)
 //! - `dma` - DMA buffer management
 //! - `time` - TSC-based timing utilities
+//! - `offload` - Running CPU-bound jobs (hashing) on other cores
+//! - `power` - CPU idling and thermal throttling for the poll loop
+//! - `sync` - Spin locks and once cells replacing `static mut` globals
 //!
 //! # Reset Contract
 //!
@@ -70,11 +75,13 @@ pub mod display;
 // ═══════════════════════════════════════════════════════════════
 // CORE MODULES
 // ═══════════════════════════════════════════════════════════════
+pub mod acpi;
 pub mod client;
 pub mod device;
 pub mod error;
 pub mod http;
 pub mod stack;
+pub mod sync;
 pub mod transfer;
 pub mod url;
 
@@ -83,15 +90,19 @@ pub mod url;
 // These provide post-ExitBootServices network support using
 // hand-written assembly for all hardware access.
 // ═══════════════════════════════════════════════════════════════
+pub mod arch; // Per-architecture primitives (timer, barriers, ECAM, PSCI)
 pub mod asm; // ASM bindings (TSC, MMIO, PIO, barriers)
 pub mod boot; // Boot handoff and initialization
 pub mod dma; // DMA buffer management with ownership tracking
 pub mod driver; // Driver abstraction and implementations
 pub mod entry; // Top-level entry point (run_download)
 pub mod mainloop; // 5-phase poll loop
+pub mod offload; // CPU-bound jobs on other cores
 pub mod pci;
+pub mod power; // Idle and thermal management
 pub mod state; // State machines (DHCP, TCP, HTTP, etc.)
 pub mod time; // Timing utilities
+pub mod tls; // TLS for https:// downloads
 pub mod types; // Shared types (#[repr(C)] structs) // PCI bus access
 
 // ═══════════════════════════════════════════════════════════════
@@ -112,17 +123,24 @@ pub use device::UnifiedBlockDevice;
 pub use driver::block_traits::{BlockCompletion, BlockDeviceInfo, BlockDriver, BlockError};
 
 // Block drivers
+#[cfg(not(feature = "netboot-only"))]
 pub use driver::ahci::{AhciConfig, AhciDriver, AhciInitError};
-pub use driver::virtio_blk::{VirtioBlkConfig, VirtioBlkDriver, VirtioBlkInitError};
+#[cfg(not(feature = "netboot-only"))]
+pub use driver::usb_msc::{UsbMscConfig, UsbMscDriver, UsbMscInitError};
+#[cfg(not(feature = "netboot-only"))]
+pub use driver::virtio_blk::{VirtioBlkConfig, VirtioBlkDriver, VirtioBlkInitError, VirtioBlkStats};
 
 // BlockIo adapters (for filesystem compatibility)
-pub use driver::block_io_adapter::{BlockIoError, VirtioBlkBlockIo};
+#[cfg(not(feature = "netboot-only"))]
+pub use driver::block_io_adapter::{AhciBlockIo, BlockIoError, VirtioBlkBlockIo};
 pub use driver::unified_block_io::{GenericBlockIo, UnifiedBlockIo, UnifiedBlockIoError};
 
 // Block probe
+#[cfg(not(feature = "netboot-only"))]
 pub use boot::block_probe::{
-    detect_block_device_type, probe_and_create_block_driver, probe_unified_block_device,
-    BlockDeviceType, BlockDmaConfig, BlockProbeError, BlockProbeResult,
+    detect_block_device_type, probe_and_create_block_driver, probe_block_devices,
+    probe_unified_block_device, BlockDeviceType, BlockDmaConfig, BlockProbeError,
+    BlockProbeResult,
 };
 
 // Client
@@ -0,0 +1,65 @@
+//! User-initiated abort of the post-EBS session.
+//!
+//! Once ExitBootServices is called the only way out used to be a power
+//! cut, which leaves a half-written ISO without a manifest. An abort
+//! request instead routes the state machine through `AbortState`, which
+//! flushes buffered data, records a partial manifest with the resume
+//! offset, stops NIC DMA and resets the platform properly.
+//!
+//! Requests come from `request_abort()`, or from ESC / Ctrl-C on the
+//! serial console or a PS/2 keyboard, checked by `poll()` every iteration.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use super::{keyboard, serial};
+
+/// Serial bytes that request an abort.
+const ABORT_KEYS: [u8; 2] = [0x1B, 0x03]; // ESC, Ctrl-C
+
+static ABORT_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Ask the running session to abort at the next iteration.
+pub fn request_abort() {
+    ABORT_REQUESTED.store(true, Ordering::Release);
+}
+
+/// Whether an abort has been requested.
+pub fn abort_requested() -> bool {
+    ABORT_REQUESTED.load(Ordering::Acquire)
+}
+
+/// Forget a pending request (start of a new session).
+pub fn clear() {
+    ABORT_REQUESTED.store(false, Ordering::Release);
+}
+
+/// Whether `byte` is one of the serial abort keys.
+pub fn is_abort_key(byte: u8) -> bool {
+    ABORT_KEYS.contains(&byte)
+}
+
+/// Check the serial console and the keyboard for an abort key, then the
+/// request flag.
+pub fn poll() -> bool {
+    if let Some(byte) = serial::read_byte() {
+        if is_abort_key(byte) {
+            request_abort();
+        }
+    }
+    if keyboard::poll() {
+        request_abort();
+    }
+    abort_requested()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_abort_keys() {
+        assert!(is_abort_key(0x1B));
+        assert!(is_abort_key(0x03));
+        assert!(!is_abort_key(b'q'));
+    }
+}
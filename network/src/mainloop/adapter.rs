@@ -3,11 +3,12 @@
 //! Bridges our NetworkDriver abstraction to smoltcp's Device trait.
 //! Uses fixed-size stack buffers — no heap allocation in packet path.
 
-use smoltcp::phy::{Device, DeviceCapabilities, Medium};
+use smoltcp::phy::{Checksum, Device, DeviceCapabilities, Medium};
 use smoltcp::time::Instant;
 
-use crate::driver::traits::NetworkDriver;
+use crate::driver::traits::{LinkInfo, NetworkDriver};
 use super::serial;
+use super::tcp_stats::{TcpTelemetry, TcpTracker};
 
 /// Adapter bridging NetworkDriver to smoltcp Device trait.
 pub struct SmoltcpAdapter<'a, D: NetworkDriver> {
@@ -16,6 +17,12 @@ pub struct SmoltcpAdapter<'a, D: NetworkDriver> {
     rx_len: usize,
     tx_count: u32,
     rx_count: u32,
+    /// Transmit tokens left until the next `set_tx_budget` (unlimited
+    /// unless set)
+    tx_budget: usize,
+    tx_drops: u32,
+    rx_errors: u32,
+    tcp: TcpTracker,
 }
 
 impl<'a, D: NetworkDriver> SmoltcpAdapter<'a, D> {
@@ -27,15 +34,28 @@ impl<'a, D: NetworkDriver> SmoltcpAdapter<'a, D> {
             rx_len: 0,
             tx_count: 0,
             rx_count: 0,
+            tx_budget: usize::MAX,
+            tx_drops: 0,
+            rx_errors: 0,
+            tcp: TcpTracker::default(),
         }
     }
 
+    /// Allow `packets` transmits until the next call; call before each poll.
+    pub fn set_tx_budget(&mut self, packets: usize) {
+        self.tx_budget = packets;
+    }
+
     /// Poll hardware for received packets.
     pub fn poll_receive(&mut self) {
         if self.rx_len == 0 {
-            if let Ok(Some(len)) = self.driver.receive(&mut self.rx_buffer) {
-                self.rx_len = len;
-                self.rx_count += 1;
+            match self.driver.receive(&mut self.rx_buffer) {
+                Ok(Some(len)) => {
+                    self.rx_len = len;
+                    self.rx_count += 1;
+                }
+                Ok(None) => {}
+                Err(_) => self.rx_errors += 1,
             }
         }
     }
@@ -50,6 +70,11 @@ impl<'a, D: NetworkDriver> SmoltcpAdapter<'a, D> {
         self.driver.collect_tx_completions();
     }
 
+    /// Stop NIC DMA (end of session).
+    pub fn quiesce(&mut self) {
+        self.driver.quiesce();
+    }
+
     /// Get MAC address.
     pub fn mac_address(&self) -> [u8; 6] {
         self.driver.mac_address()
@@ -65,11 +90,35 @@ impl<'a, D: NetworkDriver> SmoltcpAdapter<'a, D> {
         self.rx_count
     }
 
+    /// TCP telemetry for the current connection, with driver drop counts.
+    pub fn tcp_telemetry(&self) -> TcpTelemetry {
+        TcpTelemetry {
+            tx_drops: self.tx_drops,
+            rx_errors: self.rx_errors,
+            ..self.tcp.telemetry()
+        }
+    }
+
+    /// Frames waiting in the driver's RX ring, and its size.
+    pub fn rx_backlog(&self) -> Option<(usize, usize)> {
+        self.driver.rx_backlog()
+    }
+
     /// Check if PHY link is up.
     pub fn driver_link_up(&self) -> bool {
         self.driver.link_up()
     }
 
+    /// Negotiated link speed and duplex, if the driver reports them.
+    pub fn driver_link_info(&self) -> Option<LinkInfo> {
+        self.driver.link_info()
+    }
+
+    /// PHY auto-negotiation state, if the driver can read it.
+    pub fn driver_autoneg_complete(&self) -> Option<bool> {
+        self.driver.autoneg_complete()
+    }
+
     /// Increment TX counter (called from TxToken).
     fn inc_tx(&mut self) {
         self.tx_count += 1;
@@ -94,6 +143,9 @@ impl smoltcp::phy::RxToken for RxToken {
 /// TX token — writes directly via driver.
 pub struct TxToken<'a, D: NetworkDriver> {
     driver: &'a mut D,
+    tcp: &'a mut TcpTracker,
+    tx_drops: &'a mut u32,
+    now_ms: i64,
 }
 
 impl<'a, D: NetworkDriver> smoltcp::phy::TxToken for TxToken<'a, D> {
@@ -106,9 +158,12 @@ impl<'a, D: NetworkDriver> smoltcp::phy::TxToken for TxToken<'a, D> {
         let actual_len = len.min(MAX_FRAME);
         
         let result = f(&mut buffer[..actual_len]);
-        
+        self.tcp.observe_tx(&buffer[..actual_len], self.now_ms);
+
         // Fire-and-forget transmit
-        let _ = self.driver.transmit(&buffer[..actual_len]);
+        if self.driver.transmit(&buffer[..actual_len]).is_err() {
+            *self.tx_drops += 1;
+        }
         
         result
     }
@@ -118,7 +173,7 @@ impl<'a, D: NetworkDriver> Device for SmoltcpAdapter<'a, D> {
     type RxToken<'b> = RxToken where Self: 'b;
     type TxToken<'b> = TxToken<'b, D> where Self: 'b;
 
-    fn receive(&mut self, _timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+    fn receive(&mut self, timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
         self.poll_receive();
 
         if self.rx_len > 0 {
@@ -127,19 +182,31 @@ impl<'a, D: NetworkDriver> Device for SmoltcpAdapter<'a, D> {
             rx_buf[..copy_len].copy_from_slice(&self.rx_buffer[..copy_len]);
             let rx_len = copy_len;
             self.rx_len = 0;
+            self.tcp.observe_rx(&rx_buf[..rx_len], timestamp.total_millis());
 
             Some((
                 RxToken { buffer: rx_buf, len: rx_len },
-                TxToken { driver: self.driver },
+                TxToken {
+                    driver: self.driver,
+                    tcp: &mut self.tcp,
+                    tx_drops: &mut self.tx_drops,
+                    now_ms: timestamp.total_millis(),
+                },
             ))
         } else {
             None
         }
     }
 
-    fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
-        if self.driver.can_transmit() {
-            Some(TxToken { driver: self.driver })
+    fn transmit(&mut self, timestamp: Instant) -> Option<Self::TxToken<'_>> {
+        if self.tx_budget > 0 && self.driver.can_transmit() {
+            self.tx_budget -= 1;
+            Some(TxToken {
+                driver: self.driver,
+                tcp: &mut self.tcp,
+                tx_drops: &mut self.tx_drops,
+                now_ms: timestamp.total_millis(),
+            })
         } else {
             None
         }
@@ -150,6 +217,23 @@ impl<'a, D: NetworkDriver> Device for SmoltcpAdapter<'a, D> {
         caps.medium = Medium::Ethernet;
         caps.max_transmission_unit = 1514;
         caps.max_burst_size = Some(32);
+
+        // Let smoltcp skip what the NIC already does
+        let offload = self.driver.checksum_offload();
+        caps.checksum.ipv4 = stack_checksum(offload.tx_ipv4, offload.rx_ipv4);
+        caps.checksum.tcp = stack_checksum(offload.tx_l4, offload.rx_l4);
+        caps.checksum.udp = stack_checksum(offload.tx_l4, offload.rx_l4);
         caps
     }
 }
+
+/// Checksum work left to the stack when the hardware covers `hw_tx`
+/// and `hw_rx`.
+fn stack_checksum(hw_tx: bool, hw_rx: bool) -> Checksum {
+    match (hw_tx, hw_rx) {
+        (false, false) => Checksum::Both,
+        (true, false) => Checksum::Rx,
+        (false, true) => Checksum::Tx,
+        (true, true) => Checksum::None,
+    }
+}
@@ -0,0 +1,236 @@
+//! PC speaker beep codes for the post-EBS session.
+//!
+//! A machine with neither a serial console nor a display still has the
+//! speaker behind PIT channel 2. With `DownloadConfig::beeps` set, the
+//! session signals its milestones with short high beeps and ends a failed
+//! download with long low beeps counting out what went wrong:
+//!
+//! ```text
+//! short x1  network up (DHCP bound)      long x1  aborted
+//! short x2  transfer started             long x2  no link
+//! short x3  download complete            long x3  DHCP failed
+//!                                        long x4  DNS failed
+//!                                        long x5  could not connect
+//!                                        long x6  HTTP transfer failed
+//!                                        long x7  disk or manifest error
+//! ```
+//!
+//! Milestone beeps are played from the main loop without holding it up;
+//! the final code blocks, since the session is over by then.
+
+use crate::time::{self, Deadline, SystemClock};
+
+/// Milestone tone.
+const HIGH_HZ: u32 = 1760;
+/// Error tone.
+const LOW_HZ: u32 = 440;
+
+const SHORT_MS: u64 = 120;
+const LONG_MS: u64 = 500;
+const GAP_MS: u64 = 180;
+
+/// A beep sequence and what it means.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BeepCode {
+    NetworkUp,
+    Downloading,
+    Complete,
+    Aborted,
+    NoLink,
+    DhcpFailed,
+    DnsFailed,
+    ConnectFailed,
+    HttpFailed,
+    DiskFailed,
+}
+
+impl BeepCode {
+    /// Number of beeps and whether they are long (error) beeps.
+    pub fn pattern(self) -> (u8, bool) {
+        match self {
+            Self::NetworkUp => (1, false),
+            Self::Downloading => (2, false),
+            Self::Complete => (3, false),
+            Self::Aborted => (1, true),
+            Self::NoLink => (2, true),
+            Self::DhcpFailed => (3, true),
+            Self::DnsFailed => (4, true),
+            Self::ConnectFailed => (5, true),
+            Self::HttpFailed => (6, true),
+            Self::DiskFailed => (7, true),
+        }
+    }
+
+    /// Milestone reached on entering the state named `phase`.
+    pub fn for_phase(phase: &str) -> Option<Self> {
+        match phase {
+            "DNS" => Some(Self::NetworkUp),
+            "HTTP" => Some(Self::Downloading),
+            "Done" => Some(Self::Complete),
+            _ => None,
+        }
+    }
+
+    /// Error class of a failure in the state named `phase`.
+    pub fn for_failure(phase: &str) -> Self {
+        match phase {
+            "LinkWait" => Self::NoLink,
+            "DHCP" => Self::DhcpFailed,
+            "DNS" => Self::DnsFailed,
+            "Connect" => Self::ConnectFailed,
+            "HTTP" => Self::HttpFailed,
+            "Abort" => Self::Aborted,
+            _ => Self::DiskFailed,
+        }
+    }
+}
+
+/// Plays beep codes, one at a time. Does nothing when disabled.
+pub struct Beeper {
+    enabled: bool,
+    clock: SystemClock,
+    /// Beeps left in the current code
+    remaining: u8,
+    long: bool,
+    sounding: bool,
+    until: Option<Deadline>,
+}
+
+impl Beeper {
+    pub fn new(enabled: bool, tsc_freq: u64) -> Self {
+        Self {
+            enabled,
+            clock: time::active_or_tsc(tsc_freq),
+            remaining: 0,
+            long: false,
+            sounding: false,
+            until: None,
+        }
+    }
+
+    /// Start playing `code`, cutting off one still playing.
+    pub fn start(&mut self, code: BeepCode) {
+        if !self.enabled {
+            return;
+        }
+        let (count, long) = code.pattern();
+        speaker::off();
+        self.remaining = count;
+        self.long = long;
+        self.sounding = false;
+        self.until = None;
+    }
+
+    /// Advance the current code; call every main loop iteration.
+    pub fn tick(&mut self) {
+        if self.until.as_ref().is_some_and(|d| !d.expired()) {
+            return;
+        }
+        if self.sounding {
+            speaker::off();
+            self.sounding = false;
+            self.until = Some(Deadline::after_ms(self.clock, GAP_MS));
+        } else if self.remaining > 0 {
+            self.remaining -= 1;
+            speaker::on(if self.long { LOW_HZ } else { HIGH_HZ });
+            self.sounding = true;
+            let ms = if self.long { LONG_MS } else { SHORT_MS };
+            self.until = Some(Deadline::after_ms(self.clock, ms));
+        } else {
+            self.until = None;
+        }
+    }
+
+    /// Play `code` to the end before returning.
+    pub fn play_blocking(&mut self, code: BeepCode) {
+        if !self.enabled {
+            return;
+        }
+        self.start(code);
+        while self.remaining > 0 || self.sounding {
+            self.tick();
+            core::hint::spin_loop();
+        }
+        // Space it from whatever sound the reset makes
+        time::delay_ms(self.clock, GAP_MS);
+    }
+}
+
+impl Drop for Beeper {
+    fn drop(&mut self) {
+        if self.sounding {
+            speaker::off();
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod speaker {
+    use crate::asm::core::pio::{inb, outb};
+
+    /// PIT input clock
+    const PIT_HZ: u32 = 1_193_182;
+    const PIT_CHANNEL2: u16 = 0x42;
+    const PIT_COMMAND: u16 = 0x43;
+    /// Channel 2, lobyte/hibyte, mode 3 (square wave)
+    const PIT_CH2_SQUARE: u8 = 0xB6;
+    /// System control port B: bit 0 gates channel 2, bit 1 drives the speaker
+    const PORT_B: u16 = 0x61;
+    const SPEAKER_BITS: u8 = 0x03;
+
+    pub fn on(hz: u32) {
+        let divisor = (PIT_HZ / hz.max(19)) as u16;
+        // SAFETY: PIT channel 2 and port B only drive the speaker.
+        unsafe {
+            outb(PIT_COMMAND, PIT_CH2_SQUARE);
+            outb(PIT_CHANNEL2, divisor as u8);
+            outb(PIT_CHANNEL2, (divisor >> 8) as u8);
+            outb(PORT_B, inb(PORT_B) | SPEAKER_BITS);
+        }
+    }
+
+    pub fn off() {
+        // SAFETY: as above.
+        unsafe { outb(PORT_B, inb(PORT_B) & !SPEAKER_BITS) }
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+mod speaker {
+    pub fn on(_hz: u32) {}
+    pub fn off() {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes_are_distinct() {
+        let codes = [
+            BeepCode::NetworkUp,
+            BeepCode::Downloading,
+            BeepCode::Complete,
+            BeepCode::Aborted,
+            BeepCode::NoLink,
+            BeepCode::DhcpFailed,
+            BeepCode::DnsFailed,
+            BeepCode::ConnectFailed,
+            BeepCode::HttpFailed,
+            BeepCode::DiskFailed,
+        ];
+        for (i, a) in codes.iter().enumerate() {
+            for b in &codes[i + 1..] {
+                assert_ne!(a.pattern(), b.pattern());
+            }
+        }
+    }
+
+    #[test]
+    fn test_phase_mapping() {
+        assert_eq!(BeepCode::for_failure("DHCP").pattern(), (3, true));
+        assert_eq!(BeepCode::for_failure("GptPrep"), BeepCode::DiskFailed);
+        assert_eq!(BeepCode::for_phase("HTTP"), Some(BeepCode::Downloading));
+        assert_eq!(BeepCode::for_phase("Connect"), None);
+    }
+}
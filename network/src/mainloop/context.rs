@@ -3,20 +3,51 @@
 //! Self-contained — no handoff dependencies.
 //! Network receives already-initialized hardware from hwinit.
 
-use smoltcp::iface::SocketHandle;
-use smoltcp::wire::IpAddress;
+use core::net::Ipv4Addr;
+
+use morpheus_core::iso::SkipList;
 
 use crate::device::UnifiedBlockDevice;
+use crate::driver::block_traits::BlockDriver;
+use crate::time::TimeoutConfig;
+use crate::transfer::disk::{DiskSelector, Journal, Placement};
+
+use super::health::StallReason;
+use super::link_watch::LinkWatch;
+use super::post_actions::PostActions;
+use super::rate::TokenBucket;
+use super::retry::{self, RetryPhase, RetryPolicies, RetryStats, ScheduledRetry};
+use super::serial;
+
+/// Default wait for a PHY link, in seconds.
+pub const DEFAULT_LINK_WAIT_SECS: u64 = 15;
 
 /// Timeout configuration for network operations.
 #[derive(Clone, Copy)]
 pub struct Timeouts {
     tsc_freq: u64,
+    link_wait_secs: u64,
 }
 
 impl Timeouts {
     pub fn new(tsc_freq: u64) -> Self {
-        Self { tsc_freq }
+        Self {
+            tsc_freq,
+            link_wait_secs: DEFAULT_LINK_WAIT_SECS,
+        }
+    }
+
+    /// Wait `secs` for a link instead of the default (0 keeps it).
+    pub fn with_link_wait(mut self, secs: u64) -> Self {
+        if secs > 0 {
+            self.link_wait_secs = secs;
+        }
+        self
+    }
+
+    /// PHY link wait, counted from the start of the session.
+    pub fn link_wait(&self) -> u64 {
+        self.tsc_freq * self.link_wait_secs
     }
 
     /// DHCP timeout (10 seconds).
@@ -38,8 +69,76 @@ impl Timeouts {
     pub fn http_idle(&self) -> u64 {
         self.tsc_freq * 30
     }
+
+    /// TSC-based timeouts for `UnifiedBlockIo`.
+    pub fn block(&self) -> TimeoutConfig {
+        TimeoutConfig::new(self.tsc_freq)
+    }
+}
+
+/// What the HEAD request before the GET learned about the image.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Preflight {
+    /// Image size, for progress from the start and the capacity check
+    pub content_length: Option<u64>,
+    /// Server takes byte ranges (an interrupted download can resume)
+    pub accept_ranges: bool,
+    /// Last-Modified, Unix seconds (0 = not sent)
+    pub last_modified: u32,
+    /// `morpheus_core::iso::etag_hash` of the ETag (0 = not sent)
+    pub etag_hash: u32,
+}
+
+/// A point a download can continue from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// Image bytes durably on disk (whole sectors)
+    pub offset: u64,
+    /// SHA-256 state after `offset` bytes (`Sha256::midstate`)
+    pub sha256_state: [u8; 32],
+}
+
+/// A partial download from an earlier session, found by GPT prep.
+#[derive(Debug, Clone, Copy)]
+pub struct Resume {
+    /// Where it stopped
+    pub at: Checkpoint,
+    /// Last-Modified recorded with it, Unix seconds (0 = not sent)
+    pub last_modified: u32,
+    /// Hash of the ETag recorded with it (0 = not sent)
+    pub etag_hash: u32,
+    /// Zero sectors left unwritten before `at`
+    pub skipped: SkipList,
+}
+
+impl Resume {
+    /// Whether the server still has the image this came from, going by
+    /// the HEAD response. Takes a validator known on both sides: the ETag
+    /// if there is one, else Last-Modified.
+    pub fn matches(&self, preflight: &Preflight) -> bool {
+        if self.etag_hash != 0 && preflight.etag_hash != 0 {
+            self.etag_hash == preflight.etag_hash
+        } else {
+            self.last_modified != 0 && self.last_modified == preflight.last_modified
+        }
+    }
 }
 
+/// Download progress, as handed to [`DownloadConfig::progress`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// Body bytes received so far
+    pub bytes_downloaded: u64,
+    /// Content-Length, once the headers are in
+    pub content_length: Option<u64>,
+    /// Why no data is arriving, while stalled
+    pub stall: Option<StallReason>,
+}
+
+/// Progress callback, called about once a second during the transfer and
+/// whenever a stall starts, changes reason or ends.
+pub type ProgressFn = fn(&Progress);
+
 /// Full download configuration.
 #[derive(Clone)]
 pub struct DownloadConfig<'a> {
@@ -59,6 +158,41 @@ pub struct DownloadConfig<'a> {
     pub iso_name: &'a str,
     /// Expected ISO size (0 = unknown)
     pub expected_size: u64,
+    /// Expected SHA-256 of the image (None = record only, don't verify)
+    pub expected_sha256: Option<[u8; 32]>,
+    /// Checksum list next to the image (e.g. `SHA256SUMS`) to take the
+    /// expected SHA-256 from when none is given (empty = don't fetch)
+    pub checksums: &'a str,
+    /// Where the ISO partition goes when no start sector is requested
+    pub placement: Placement,
+    /// What to do once the ISO is stored
+    pub post_actions: PostActions,
+    /// Disk the ISO is written to, when several are present
+    pub target_disk: DiskSelector,
+    /// Disk holding the ESP (manifests, journal, boot files)
+    pub esp_disk: DiskSelector,
+    /// The target disk reads as zero where the ISO goes (freshly trimmed
+    /// or zeroed), so all-zero blocks are skipped rather than written
+    pub zeroed_target: bool,
+    /// Read back and compare every nth chunk written while the network is
+    /// idle, so a bad disk shows up before the whole image is down (0 = off)
+    pub spot_check_every: u32,
+    /// Refuse `http://` URLs: the image only comes over TLS
+    pub require_tls: bool,
+    /// Signal milestones and failures on the PC speaker
+    pub beeps: bool,
+    /// Static hostname mappings in `/etc/hosts` format, consulted before
+    /// DNS (empty = none)
+    pub hosts: &'a str,
+    /// Download rate cap in bytes per second (0 = no cap)
+    pub rate_limit: u64,
+    /// HTTP proxy as "host:port" or "http://host:port" (empty = direct)
+    pub proxy: &'a str,
+    /// Longest wait for a PHY link before going on without one, in
+    /// seconds (0 = [`DEFAULT_LINK_WAIT_SECS`])
+    pub link_wait_secs: u64,
+    /// Progress callback (None = serial log only)
+    pub progress: Option<ProgressFn>,
 }
 
 impl<'a> DownloadConfig<'a> {
@@ -73,6 +207,21 @@ impl<'a> DownloadConfig<'a> {
             partition_uuid: [0u8; 16],
             iso_name: "",
             expected_size: 0,
+            expected_sha256: None,
+            checksums: "",
+            placement: Placement::default(),
+            post_actions: PostActions::REBOOT,
+            target_disk: DiskSelector::First,
+            esp_disk: DiskSelector::First,
+            zeroed_target: false,
+            spot_check_every: 0,
+            require_tls: false,
+            beeps: false,
+            hosts: "",
+            rate_limit: 0,
+            proxy: "",
+            link_wait_secs: 0,
+            progress: None,
         }
     }
 
@@ -94,6 +243,21 @@ impl<'a> DownloadConfig<'a> {
             partition_uuid,
             iso_name,
             expected_size: 0,
+            expected_sha256: None,
+            checksums: "",
+            placement: Placement::default(),
+            post_actions: PostActions::REBOOT,
+            target_disk: DiskSelector::First,
+            esp_disk: DiskSelector::First,
+            zeroed_target: false,
+            spot_check_every: 0,
+            require_tls: false,
+            beeps: false,
+            hosts: "",
+            rate_limit: 0,
+            proxy: "",
+            link_wait_secs: 0,
+            progress: None,
         }
     }
 }
@@ -106,67 +270,168 @@ pub struct Context<'a> {
     pub tsc_freq: u64,
     /// Full configuration
     pub config: DownloadConfig<'a>,
-    /// DHCP socket handle
-    pub dhcp_handle: Option<SocketHandle>,
-    /// DNS socket handle
-    pub dns_handle: Option<SocketHandle>,
-    /// TCP socket handle
-    pub tcp_handle: Option<SocketHandle>,
     /// Block device for disk writes
     pub blk_device: Option<UnifiedBlockDevice>,
+    /// Disk holding the ESP when it isn't `blk_device` (`None`: same disk)
+    pub esp_device: Option<UnifiedBlockDevice>,
     /// Resolved IP address (from DNS)
-    pub resolved_ip: Option<IpAddress>,
+    pub resolved_ip: Option<Ipv4Addr>,
     /// Resolved port
     pub resolved_port: u16,
-    /// Path portion of URL
+    /// Path portion of URL (the whole URL when going through a proxy)
     pub url_path: &'a str,
     /// Host portion of URL
     pub url_host: &'a str,
+    /// The URL is `https://`: connections run TLS with `url_host`
+    pub url_tls: bool,
+    /// Host and explicit port of the URL, for the Host header
+    pub url_authority: &'a str,
+    /// Host to resolve and connect to: the URL's, or the proxy's
+    pub connect_host: &'a str,
     /// Content-Length from HTTP response
     pub content_length: Option<u64>,
     /// Total bytes downloaded
     pub bytes_downloaded: u64,
     /// Total bytes written to disk
     pub bytes_written: u64,
+    /// SHA-256 of the body received (set when the download completes)
+    pub sha256: Option<[u8; 32]>,
+    /// Zero sectors left unwritten on a `zeroed_target` (set with `sha256`)
+    pub skipped: SkipList,
+    /// Partial download GPT prep kept the partition of, to continue
+    pub resume: Option<Resume>,
+    /// Last point this download could resume from, once one is on disk
+    pub checkpoint: Option<Checkpoint>,
+    /// TSC when the first HTTP request started (0 = not yet), kept across
+    /// retries so the recorded duration covers the whole download
+    pub download_start_tsc: u64,
     /// Current write sector
     pub current_write_sector: u64,
     /// DNS servers from DHCP
-    pub dns_servers: [Option<IpAddress>; 3],
+    pub dns_servers: [Option<Ipv4Addr>; 3],
     /// Actual start sector (after GPT prep, may differ from config)
     pub actual_start_sector: u64,
+    /// Raw manifest copy sector at the end of the ISO partition (0 = none)
+    pub raw_manifest_sector: u64,
+    /// Retry policies for DHCP/DNS/Connect/HTTP
+    pub retry_policies: RetryPolicies,
+    /// Retries performed so far, per phase
+    pub retries: RetryStats,
+    /// Intent journal on the ESP (opened by GPT prep)
+    pub journal: Option<Journal>,
+    /// Steps the body was left in the socket because the disk was behind
+    pub disk_backpressure: u32,
+    /// HEAD preflight result (None = not done yet; a failed HEAD leaves
+    /// the default, so the GET goes ahead without it)
+    pub preflight: Option<Preflight>,
+    /// The checksum list was asked for (whether or not it had the image)
+    pub checksums_fetched: bool,
+    /// Body read budget under `config.rate_limit`
+    pub rate_limiter: Option<TokenBucket>,
+    /// PHY link since the session started (sampled until LinkWait ends)
+    pub link: LinkWatch,
 }
 
 impl<'a> Context<'a> {
     /// Create new context.
     pub fn new(config: DownloadConfig<'a>, tsc_freq: u64) -> Self {
         let start_sector = config.target_start_sector;
+        let rate_limiter =
+            (config.rate_limit > 0).then(|| TokenBucket::new(config.rate_limit, tsc_freq));
         Self {
-            timeouts: Timeouts::new(tsc_freq),
+            timeouts: Timeouts::new(tsc_freq).with_link_wait(config.link_wait_secs),
             tsc_freq,
             config,
-            dhcp_handle: None,
-            dns_handle: None,
-            tcp_handle: None,
             blk_device: None,
+            esp_device: None,
             resolved_ip: None,
             resolved_port: 80,
             url_path: "",
             url_host: "",
+            url_tls: false,
+            url_authority: "",
+            connect_host: "",
             content_length: None,
             bytes_downloaded: 0,
             bytes_written: 0,
+            sha256: None,
+            skipped: SkipList::new(),
+            resume: None,
+            checkpoint: None,
+            download_start_tsc: 0,
             current_write_sector: start_sector,
             dns_servers: [None; 3],
             actual_start_sector: start_sector,
+            raw_manifest_sector: 0,
+            retry_policies: RetryPolicies::default(),
+            retries: RetryStats::default(),
+            journal: None,
+            disk_backpressure: 0,
+            preflight: None,
+            checksums_fetched: false,
+            rate_limiter,
+            link: LinkWatch::new(tsc_freq),
         }
     }
 
+    /// Override retry policies.
+    pub fn with_retry_policies(mut self, policies: RetryPolicies) -> Self {
+        self.retry_policies = policies;
+        self
+    }
+
+    /// Schedule a retry for `phase`, logging the attempt.
+    ///
+    /// Returns `None` when the phase's retry budget is exhausted.
+    pub fn schedule_retry(&mut self, phase: RetryPhase, tsc: u64) -> Option<ScheduledRetry> {
+        let scheduled = retry::schedule(
+            &self.retry_policies,
+            &mut self.retries,
+            phase,
+            tsc,
+            self.tsc_freq,
+        )?;
+        serial::print("[");
+        serial::print(phase.name());
+        serial::print("] Retry ");
+        serial::print_u32(scheduled.retry);
+        serial::print("/");
+        serial::print_u32(scheduled.max_attempts.saturating_sub(1));
+        serial::print(" in ");
+        serial::print_u32(scheduled.delay_ms as u32);
+        serial::println(" ms");
+        Some(scheduled)
+    }
+
     /// Set block device for disk writes.
     pub fn with_block_device(mut self, device: UnifiedBlockDevice) -> Self {
         self.blk_device = Some(device);
         self
     }
 
+    /// Device the ESP is on: the separate ESP disk if there is one, else
+    /// the download target.
+    pub fn esp_blk(&mut self) -> Option<&mut UnifiedBlockDevice> {
+        match self.esp_device {
+            Some(ref mut esp) => Some(esp),
+            None => self.blk_device.as_mut(),
+        }
+    }
+
+    /// Whether the ESP is on another disk than the download target.
+    pub fn esp_is_separate(&self) -> bool {
+        self.esp_device.is_some()
+    }
+
+    /// `disk_id` recorded for the ISO's chunks: 0 when they share the
+    /// ESP's disk, else the target's identity fingerprint.
+    pub fn data_disk_id(&self) -> u32 {
+        match (&self.blk_device, self.esp_is_separate()) {
+            (Some(blk), true) => blk.info().identity.disk_id(),
+            _ => 0,
+        }
+    }
+
     /// Get URL from config.
     pub fn url(&self) -> &str {
         self.config.url
@@ -176,6 +441,13 @@ impl<'a> Context<'a> {
     pub fn should_write_to_disk(&self) -> bool {
         self.config.write_to_disk && self.blk_device.is_some()
     }
+
+    /// Whether the checksum list still has to be fetched before the GET.
+    pub fn wants_checksums(&self) -> bool {
+        !self.checksums_fetched
+            && !self.config.checksums.is_empty()
+            && self.config.expected_sha256.is_none()
+    }
 }
 
 /// Read TSC (Time Stamp Counter).
@@ -195,7 +467,14 @@ pub fn get_tsc() -> u64 {
     ((hi as u64) << 32) | (lo as u64)
 }
 
-#[cfg(not(target_arch = "x86_64"))]
+/// Read the generic timer counter (the aarch64 TSC equivalent).
+#[cfg(target_arch = "aarch64")]
+#[inline]
+pub fn get_tsc() -> u64 {
+    crate::arch::aarch64::cntvct()
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
 #[inline]
 pub fn get_tsc() -> u64 {
     0
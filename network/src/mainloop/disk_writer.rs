@@ -1,47 +1,264 @@
 //! Buffered disk writer for streaming ISO downloads.
 //!
-//! Accumulates data in a static buffer and flushes to disk in
+//! Accumulates data in locked static buffers and flushes to disk in
 //! sector-aligned chunks. Works with both VirtIO-blk and AHCI.
+//!
+//! Writes are write-behind: a full chunk is submitted and the writer moves
+//! on to the next buffer, only waiting when it comes back round to one
+//! whose write is still in flight. Up to `WRITE_BEHIND` chunks are
+//! outstanding, which keeps a queuing device (AHCI NCQ) busy. A failed
+//! write is only noticed once it completes, so after one the writer
+//! refuses further data and the final `flush` reports the failure.
+//!
+//! Chunks are announced to the device `NOTIFY_BATCH` at a time (and
+//! before waiting on one that hasn't been), so virtio-blk takes one VM exit
+//! per batch rather than per chunk.
+//!
+//! The final `flush` ends with a write barrier, so the image is on stable
+//! storage before the manifest describing it is written.
+//!
+//! A writer that would have to wait can push back instead: `room` says
+//! how much fits without waiting, and a caller that reads no more than
+//! that from its socket leaves the rest queued there. The socket's receive
+//! window then shrinks and the sender slows down, instead of the NIC ring
+//! overflowing while the loop is stuck waiting on the disk.
+//!
+//! `sync` makes the full chunks so far durable while the one being filled
+//! stays buffered, giving a point the download can resume from after a
+//! reset; `resuming` starts a writer that far into the image.
+//!
+//! An image spread over several disks is one stream: at a chunk boundary
+//! `switch_target` drains the writes to the current device and carries on
+//! at a sector on the next, keeping the byte count.
+//!
+//! On a target that already reads as zero (`skipping_zeros`), an all-zero
+//! chunk isn't submitted at all. It is still counted, and its
+//! sectors go into a `SkipList` for the manifest, so a verify can tell
+//! them apart. Once the list is full, zero chunks are written like any
+//! other.
+//!
+//! With `spot_checking(n)`, every nth chunk submitted is sampled: its
+//! digest is kept, and once its write has completed `spot_check` reads it
+//! back into a spare buffer and compares. The caller runs that while the
+//! network has nothing to deliver, so a disk that loses writes shows up
+//! minutes into a large image instead of at the end. A read may be served
+//! from the drive's cache, so this catches a bad path to the disk (cable,
+//! controller, firmware) more surely than a bad sector. Samples taken while
+//! the queue is full are dropped rather than holding up the download.
+
+use morpheus_core::iso::SkipList;
 
 use crate::device::UnifiedBlockDevice;
-use crate::driver::block_traits::BlockDriver;
+use crate::driver::block_traits::{
+    BarrierStatus, BlockCompletion, BlockDriver, BlockEvent, WriteBarrier,
+};
 use crate::mainloop::serial;
+use crate::mainloop::trace::{self, Phase};
+use crate::sync::SpinLock;
+use crate::transfer::sha256::Sha256;
+use crate::time::{self, Deadline};
+
+/// Write buffer size: 64KB = 128 sectors. Chunks are submitted this
+/// size, so `sync` leaves the stream durable up to a multiple of it.
+pub const BUFFER_SIZE: usize = 64 * 1024;
+
+/// Number of chunk buffers, i.e. the most writes kept in flight.
+const WRITE_BEHIND: usize = 4;
+
+/// Chunks submitted per device notify.
+const NOTIFY_BATCH: usize = WRITE_BEHIND / 2;
+
+/// Budget for waiting on a single chunk write.
+const WRITE_TIMEOUT_MS: u64 = 1000;
+
+/// Request ID of barrier flushes (outside the writer's own ID range).
+const BARRIER_REQUEST_ID: u32 = 0xFFFF_B000;
+
+/// Budget for a barrier; flushing a large write cache can take a while.
+const BARRIER_TIMEOUT_MS: u64 = 30_000;
+
+/// Request ID of spot check reads (outside the writer's own ID range).
+const SPOT_CHECK_REQUEST_ID: u32 = 0xFFFF_C000;
+
+/// Budget for reading one sampled chunk back.
+const SPOT_CHECK_TIMEOUT_MS: u64 = 1000;
+
+/// Sampled chunks waiting to be read back.
+const SPOT_CHECK_QUEUE: usize = 4;
+
+/// One chunk buffer and the write it was last submitted with.
+struct Chunk {
+    buffer: [u8; BUFFER_SIZE],
+    /// Write request in flight for this buffer.
+    in_flight: bool,
+    /// The device has been notified of the write.
+    notified: bool,
+    request_id: u32,
+    /// First sector and length of the write.
+    sector: u64,
+    len: usize,
+}
 
-/// Write buffer size: 64KB = 128 sectors.
-const BUFFER_SIZE: usize = 64 * 1024;
+const EMPTY_CHUNK: Chunk = Chunk {
+    buffer: [0u8; BUFFER_SIZE],
+    in_flight: false,
+    notified: false,
+    request_id: 0,
+    sector: 0,
+    len: 0,
+};
+
+/// A chunk sampled for read-back.
+#[derive(Clone, Copy)]
+struct SpotCheck {
+    /// Write request of the chunk; read back once it has completed
+    request_id: u32,
+    sector: u64,
+    num_sectors: u32,
+    /// SHA-256 of the sectors as submitted
+    digest: [u8; 32],
+}
+
+/// Sampled chunks in submit order.
+struct SpotQueue {
+    checks: [Option<SpotCheck>; SPOT_CHECK_QUEUE],
+    len: usize,
+}
+
+impl SpotQueue {
+    const fn new() -> Self {
+        Self {
+            checks: [None; SPOT_CHECK_QUEUE],
+            len: 0,
+        }
+    }
+
+    /// Queue `check`; `false` (and dropped) when the queue is full.
+    fn push(&mut self, check: SpotCheck) -> bool {
+        if self.len == SPOT_CHECK_QUEUE {
+            return false;
+        }
+        self.checks[self.len] = Some(check);
+        self.len += 1;
+        true
+    }
 
-/// Static buffer for accumulating data before disk write.
-static mut WRITE_BUFFER: [u8; BUFFER_SIZE] = [0u8; BUFFER_SIZE];
+    /// Oldest sampled chunk.
+    fn first(&self) -> Option<SpotCheck> {
+        self.checks[0]
+    }
 
-/// Current fill level of write buffer.
-static mut BUFFER_FILL: usize = 0;
+    /// Drop the oldest sampled chunk.
+    fn pop(&mut self) {
+        if self.len > 0 {
+            self.checks.rotate_left(1);
+            self.checks[SPOT_CHECK_QUEUE - 1] = None;
+            self.len -= 1;
+        }
+    }
 
-/// Next sector to write to.
-static mut NEXT_SECTOR: u64 = 0;
+    fn clear(&mut self) {
+        *self = Self::new();
+    }
+}
 
-/// Total bytes written to disk.
-static mut TOTAL_WRITTEN: u64 = 0;
+/// Writer state shared by every `DiskWriter`.
+///
+/// Static rather than per-writer so the DMA buffers have a fixed address
+/// and do not depend on the heap.
+struct WriterState {
+    /// Chunk buffers, filled and submitted in turn.
+    chunks: [Chunk; WRITE_BEHIND],
+    /// Chunk currently being filled.
+    current: usize,
+    /// Current fill level of the current chunk.
+    fill: usize,
+    /// Next sector to write to.
+    next_sector: u64,
+    /// Total bytes whose write has completed.
+    total_written: u64,
+    /// Lowest sector of a failed write, if any.
+    failed_sector: Option<u64>,
+    /// Chunks submitted since the last notify.
+    unnotified: usize,
+    /// Next request ID for block driver.
+    next_request_id: u32,
+    /// Leave all-zero chunks unwritten.
+    skip_zeros: bool,
+    /// Sectors of the image submitted or skipped so far.
+    image_sector: u64,
+    /// Zero chunks left unwritten, in image sectors.
+    skipped: SkipList,
+    /// Read back every nth chunk submitted (0 = never).
+    spot_every: u32,
+    /// Chunks submitted so far, for sampling.
+    submitted: u64,
+    /// Sampled chunks not read back yet.
+    spot_checks: SpotQueue,
+    /// Sampled chunks read back and found intact.
+    spot_passed: u32,
+    /// Spot check reads land here.
+    readback: [u8; BUFFER_SIZE],
+}
 
-/// Next request ID for block driver.
-static mut NEXT_REQUEST_ID: u32 = 1;
+static WRITER: SpinLock<WriterState> = SpinLock::new(
+    "disk writer",
+    WriterState {
+        chunks: [EMPTY_CHUNK; WRITE_BEHIND],
+        current: 0,
+        fill: 0,
+        next_sector: 0,
+        total_written: 0,
+        failed_sector: None,
+        unnotified: 0,
+        next_request_id: 1,
+        skip_zeros: false,
+        image_sector: 0,
+        skipped: SkipList::new(),
+        spot_every: 0,
+        submitted: 0,
+        spot_checks: SpotQueue::new(),
+        spot_passed: 0,
+        readback: [0u8; BUFFER_SIZE],
+    },
+);
 
 /// Disk writer state.
 pub struct DiskWriter {
+    /// First sector on the current device.
     start_sector: u64,
+    /// Bytes written to devices before the current one.
+    segment_base: u64,
     enabled: bool,
 }
 
 impl DiskWriter {
     /// Create a new disk writer starting at the given sector.
     pub fn new(start_sector: u64) -> Self {
-        unsafe {
-            BUFFER_FILL = 0;
-            NEXT_SECTOR = start_sector;
-            TOTAL_WRITTEN = 0;
-            NEXT_REQUEST_ID = 1;
+        {
+            let mut state = WRITER.lock();
+            // A previous writer's requests are gone with its device
+            for chunk in state.chunks.iter_mut() {
+                chunk.in_flight = false;
+            }
+            state.current = 0;
+            state.fill = 0;
+            state.next_sector = start_sector;
+            state.total_written = 0;
+            state.failed_sector = None;
+            state.unnotified = 0;
+            state.next_request_id = 1;
+            state.skip_zeros = false;
+            state.image_sector = 0;
+            state.skipped.clear();
+            state.spot_every = 0;
+            state.submitted = 0;
+            state.spot_checks.clear();
+            state.spot_passed = 0;
         }
         Self {
             start_sector,
+            segment_base: 0,
             enabled: true,
         }
     }
@@ -50,126 +267,520 @@ impl DiskWriter {
     pub fn disabled() -> Self {
         Self {
             start_sector: 0,
+            segment_base: 0,
             enabled: false,
         }
     }
 
+    /// Leave all-zero chunks unwritten, for a target known to read as
+    /// zero (freshly trimmed or zeroed). Their sectors are in `skipped`.
+    pub fn skipping_zeros(self) -> Self {
+        WRITER.lock().skip_zeros = self.enabled;
+        self
+    }
+
+    /// Sample every `every`th chunk for read-back with `spot_check`
+    /// (0 = never).
+    pub fn spot_checking(self, every: u32) -> Self {
+        if self.enabled {
+            WRITER.lock().spot_every = every;
+        }
+        self
+    }
+
+    /// Continue an image of which `offset` bytes (whole sectors) are on
+    /// disk already, `skipped` of them left unwritten: writes go on
+    /// `offset` past the start sector and are counted from there.
+    pub fn resuming(self, offset: u64, skipped: SkipList) -> Self {
+        if self.enabled {
+            let mut state = WRITER.lock();
+            state.next_sector = self.start_sector + offset / 512;
+            state.image_sector = offset / 512;
+            state.total_written = offset;
+            state.skipped = skipped;
+        }
+        self
+    }
+
     /// Check if disk writing is enabled.
     pub fn is_enabled(&self) -> bool {
         self.enabled
     }
 
     /// Get total bytes written to disk.
+    ///
+    /// After a failed write, only the bytes ahead of it count. Exact once
+    /// `flush` has returned; before that, writes still in flight are not
+    /// included.
     pub fn bytes_written(&self) -> u64 {
-        unsafe { TOTAL_WRITTEN }
+        let state = WRITER.lock();
+        match state.failed_sector {
+            Some(sector) => state
+                .total_written
+                .min(self.segment_base + (sector - self.start_sector) * 512),
+            None => state.total_written,
+        }
+    }
+
+    /// Sectors of the image left unwritten because they were zero
+    /// (relative to the start of the image).
+    pub fn skipped(&self) -> SkipList {
+        WRITER.lock().skipped
+    }
+
+    /// Bytes `write` can take right now without waiting on the disk.
+    ///
+    /// Reaps finished writes first. Unlimited when disabled or after a
+    /// failed write, since `write` then returns at once.
+    pub fn room(&mut self, blk: &mut UnifiedBlockDevice) -> usize {
+        if !self.enabled {
+            return usize::MAX;
+        }
+        let mut state = WRITER.lock();
+        reap_completions(&mut state, blk);
+        if state.failed_sector.is_some() {
+            return usize::MAX;
+        }
+        let in_flight: [bool; WRITE_BEHIND] = core::array::from_fn(|i| state.chunks[i].in_flight);
+        room_without_wait(state.current, state.fill, &in_flight)
     }
 
     /// Get current sector position.
     pub fn current_sector(&self) -> u64 {
-        unsafe { NEXT_SECTOR }
+        WRITER.lock().next_sector
     }
 
     /// Write data to disk (buffered).
     ///
-    /// Data is accumulated in an internal buffer and flushed to disk
-    /// when the buffer is full. Returns number of bytes consumed.
+    /// Data is accumulated in an internal buffer and submitted to disk
+    /// when the buffer is full. Returns number of bytes consumed (none
+    /// once a write has failed).
     pub fn write(&mut self, blk: &mut UnifiedBlockDevice, data: &[u8]) -> usize {
         if !self.enabled {
             return data.len(); // Pretend we wrote it
         }
-        unsafe { buffer_write(blk, data) }
+        let start = trace::start();
+        let written = buffer_write(&mut WRITER.lock(), blk, data);
+        trace::record(Phase::DiskWrite, start);
+        written
+    }
+
+    /// Read back the oldest sampled chunk, if its write has completed,
+    /// and compare it with what was submitted.
+    ///
+    /// Costs one chunk read, so call it when there is nothing else to do.
+    /// `false` if the chunk came back different or couldn't be read; the
+    /// writer then refuses further data.
+    pub fn spot_check(&mut self, blk: &mut UnifiedBlockDevice) -> bool {
+        if !self.enabled {
+            return true;
+        }
+        spot_check(&mut WRITER.lock(), blk)
+    }
+
+    /// Continue the stream on another device at `start_sector`.
+    ///
+    /// Everything buffered so far is written to `from` and made durable
+    /// first, so the switch has to fall on a sector boundary of the stream
+    /// (chunk boundaries do). Later `write`s go to the new device. `false`
+    /// if a write to `from` failed; the writer then refuses further data.
+    pub fn switch_target(&mut self, from: &mut UnifiedBlockDevice, start_sector: u64) -> bool {
+        if !self.enabled {
+            return true;
+        }
+        let mut state = WRITER.lock();
+        if !state.fill.is_multiple_of(512) {
+            serial::println("[DISK] ERROR: Target switch inside a sector");
+            return false;
+        }
+        if !(flush_remaining(&mut state, from) && barrier(from)) {
+            return false;
+        }
+        // Samples address `from`
+        if !drain_spot_checks(&mut state, from) {
+            return false;
+        }
+
+        // Nothing is in flight on `from` any more
+        self.segment_base = state.total_written;
+        self.start_sector = start_sector;
+        state.next_sector = start_sector;
+        state.unnotified = 0;
+        true
+    }
+
+    /// Wait for every chunk submitted so far and make it durable, leaving
+    /// the one being filled in its buffer.
+    ///
+    /// Afterwards `bytes_written` is the stream up to the last full chunk
+    /// (or the resume offset plus whole chunks), all on stable storage:
+    /// a point to continue from. `false` if a write failed.
+    pub fn sync(&mut self, blk: &mut UnifiedBlockDevice) -> bool {
+        if !self.enabled {
+            return true;
+        }
+        let start = trace::start();
+        let mut state = WRITER.lock();
+        let mut ok = true;
+        for index in 0..WRITE_BEHIND {
+            ok &= wait_chunk(&mut state, blk, index);
+        }
+        let ok = ok && barrier(blk);
+        trace::record(Phase::DiskWrite, start);
+        ok
     }
 
     /// Flush any remaining buffered data to disk.
     ///
-    /// Must be called at end of download to write partial buffer.
+    /// Must be called at end of download to write partial buffer. Waits
+    /// for every write in flight and returns once the data is on stable
+    /// storage (see [`barrier`]); `false` if any write failed.
     pub fn flush(&mut self, blk: &mut UnifiedBlockDevice) -> bool {
         if !self.enabled {
             return true;
         }
-        unsafe { flush_remaining(blk) }
+        let start = trace::start();
+        let mut state = WRITER.lock();
+        let ok =
+            flush_remaining(&mut state, blk) && barrier(blk) && drain_spot_checks(&mut state, blk);
+        trace::record(Phase::DiskWrite, start);
+        if state.spot_every > 0 {
+            serial::print("[DISK] Spot checks passed: ");
+            serial::print_u32(state.spot_passed);
+            serial::println("");
+        }
+        drop(state);
+        print_queue_stats(blk);
+        ok
     }
 }
 
-/// Flush the write buffer to disk.
-unsafe fn flush_buffer(blk: &mut UnifiedBlockDevice) -> usize {
-    if BUFFER_FILL == 0 {
+/// Wait until everything written so far is on stable storage.
+///
+/// Drains outstanding requests and flushes the device cache. Used to order
+/// "data → manifest": anything written after this returns `true` cannot
+/// reach the media before the writes ahead of it.
+pub fn barrier(blk: &mut UnifiedBlockDevice) -> bool {
+    // Without an installed clock, assume a 4GHz TSC (the old fixed budget).
+    let deadline = Deadline::after_ms(time::active_or_tsc(4_000_000_000), BARRIER_TIMEOUT_MS);
+    let mut barrier = WriteBarrier::new(BARRIER_REQUEST_ID);
+
+    loop {
+        match barrier.step(blk) {
+            BarrierStatus::Done => return true,
+            BarrierStatus::Failed(_) => {
+                serial::println("[DISK] ERROR: Write barrier failed");
+                return false;
+            }
+            BarrierStatus::Pending if deadline.expired() => {
+                serial::println("[DISK] ERROR: Write barrier timeout");
+                return false;
+            }
+            BarrierStatus::Pending => core::hint::spin_loop(),
+        }
+    }
+}
+
+/// Submit the current chunk and move on to the next buffer.
+///
+/// Returns the number of bytes submitted, 0 if nothing could be (the data
+/// stays buffered for the next attempt).
+fn flush_buffer(state: &mut WriterState, blk: &mut UnifiedBlockDevice) -> usize {
+    if state.fill == 0 || state.failed_sector.is_some() {
         return 0;
     }
 
-    let bytes_to_write = BUFFER_FILL;
+    let bytes_to_write = state.fill;
     let num_sectors = ((bytes_to_write + 511) / 512) as u32;
 
-    // Identity mapped post-EBS, so virtual == physical
-    let buffer_phys = (&raw const WRITE_BUFFER).cast::<u8>() as u64;
-
-    let request_id = NEXT_REQUEST_ID;
-    NEXT_REQUEST_ID = NEXT_REQUEST_ID.wrapping_add(1);
+    let current = state.current;
+    if state.skip_zeros && skip_zero_chunk(state, num_sectors) {
+        state.total_written += bytes_to_write as u64;
+        advance(state, num_sectors);
+        let next = state.current;
+        wait_chunk(state, blk, next);
+        return bytes_to_write;
+    }
 
-    // Drain pending completions
-    while let Some(_) = blk.poll_completion() {}
+    // Identity mapped post-EBS, so virtual == physical
+    let buffer_phys = state.chunks[current].buffer.as_ptr() as u64;
+
+    let request_id = state.next_request_id;
+    state.next_request_id = state.next_request_id.wrapping_add(1);
+
+    // Reap finished writes
+    reap_completions(state, blk);
+
+    if let Some(BlockEvent::CapacityChanged {
+        old_sectors,
+        new_sectors,
+    }) = blk.poll_event()
+    {
+        serial::print("[DISK] Capacity changed: ");
+        serial::print_hex(old_sectors);
+        serial::print(" -> ");
+        serial::print_hex(new_sectors);
+        serial::println(" sectors");
+    }
 
     if !blk.can_submit() {
         serial::println("[DISK] ERROR: Queue full");
         return 0;
     }
 
-    if blk.submit_write(NEXT_SECTOR, buffer_phys, num_sectors, request_id).is_err() {
+    if blk.submit_write(state.next_sector, buffer_phys, num_sectors, request_id).is_err() {
         serial::print("[DISK] ERROR: Submit failed at sector ");
-        serial::print_hex(NEXT_SECTOR);
+        serial::print_hex(state.next_sector);
         serial::println("");
         return 0;
     }
 
+    let chunk = &mut state.chunks[current];
+    chunk.in_flight = true;
+    chunk.notified = false;
+    chunk.request_id = request_id;
+    chunk.sector = state.next_sector;
+    chunk.len = bytes_to_write;
+
+    advance(state, num_sectors);
+
+    state.unnotified += 1;
+    if state.unnotified >= NOTIFY_BATCH {
+        notify(state, blk);
+    }
+
+    // Sample the chunk for a read-back once its write has completed
+    if state.spot_every > 0 && state.submitted.is_multiple_of(state.spot_every as u64) {
+        let len = num_sectors as usize * 512;
+        let check = SpotCheck {
+            request_id,
+            sector: state.chunks[current].sector,
+            num_sectors,
+            digest: Sha256::digest(&state.chunks[current].buffer[..len]),
+        };
+        state.spot_checks.push(check);
+    }
+    state.submitted += 1;
+
+    let next = state.current;
+    wait_chunk(state, blk, next);
+
+    bytes_to_write
+}
+
+/// Move on to the next buffer once the current chunk is submitted or
+/// skipped.
+fn advance(state: &mut WriterState, num_sectors: u32) {
+    state.next_sector += num_sectors as u64;
+    state.image_sector += num_sectors as u64;
+    state.current = (state.current + 1) % WRITE_BEHIND;
+    state.fill = 0;
+}
+
+/// Whether the current chunk is all zero and has been recorded as
+/// skipped. The tail of a short last chunk is zero padding already.
+fn skip_zero_chunk(state: &mut WriterState, num_sectors: u32) -> bool {
+    let len = num_sectors as usize * 512;
+    is_zero(&state.chunks[state.current].buffer[..len])
+        && state.skipped.push(state.image_sector, num_sectors)
+}
+
+/// Whether every byte of `data` is zero.
+fn is_zero(data: &[u8]) -> bool {
+    // Any bit pattern is a valid u64
+    let (head, words, tail) = unsafe { data.align_to::<u64>() };
+    head.iter().all(|&b| b == 0) && words.iter().all(|&w| w == 0) && tail.iter().all(|&b| b == 0)
+}
+
+/// Tell the device about every chunk submitted since the last notify.
+fn notify(state: &mut WriterState, blk: &mut UnifiedBlockDevice) {
     blk.notify();
+    state.unnotified = 0;
+    for chunk in state.chunks.iter_mut() {
+        chunk.notified = true;
+    }
+}
+
+/// Record the outcome of finished chunk writes.
+fn reap_completions(state: &mut WriterState, blk: &mut UnifiedBlockDevice) {
+    while let Some(completion) = blk.poll_completion() {
+        complete_chunk(state, &completion);
+    }
+}
 
-    // Poll for completion with timeout
-    let start_tsc = read_tsc();
-    let timeout: u64 = 4_000_000_000; // ~1s at 4GHz
+/// Record the outcome of one chunk write (other requests are ignored).
+fn complete_chunk(state: &mut WriterState, completion: &BlockCompletion) {
+    let Some(chunk) = state
+        .chunks
+        .iter_mut()
+        .find(|c| c.in_flight && c.request_id == completion.request_id)
+    else {
+        return;
+    };
+    chunk.in_flight = false;
+
+    if completion.status == 0 {
+        state.total_written += chunk.len as u64;
+    } else {
+        serial::print("[DISK] ERROR: Status ");
+        serial::print_u32(completion.status as u32);
+        serial::print(" at sector ");
+        serial::print_hex(chunk.sector);
+        serial::println("");
+        let sector = chunk.sector;
+        state.failed_sector = Some(state.failed_sector.map_or(sector, |s| s.min(sector)));
+    }
+}
 
+/// Read back the oldest sampled chunk if its write has completed.
+///
+/// `true` if it matched or nothing was due; the sample stays queued when
+/// the device has no room for the read.
+fn spot_check(state: &mut WriterState, blk: &mut UnifiedBlockDevice) -> bool {
+    reap_completions(state, blk);
+    if state.failed_sector.is_some() {
+        // Already failed; reported by whoever noticed
+        return true;
+    }
+    let Some(check) = state.spot_checks.first() else {
+        return true;
+    };
+    let pending = state
+        .chunks
+        .iter()
+        .any(|c| c.in_flight && c.request_id == check.request_id);
+    if pending || !blk.can_submit() {
+        return true;
+    }
+    state.spot_checks.pop();
+
+    // Identity mapped post-EBS, so virtual == physical
+    let buffer_phys = state.readback.as_ptr() as u64;
+    let read_ok = blk
+        .submit_read(
+            check.sector,
+            buffer_phys,
+            check.num_sectors,
+            SPOT_CHECK_REQUEST_ID,
+        )
+        .is_ok()
+        && wait_spot_read(state, blk);
+
+    let len = check.num_sectors as usize * 512;
+    if read_ok && Sha256::digest(&state.readback[..len]) == check.digest {
+        state.spot_passed += 1;
+        return true;
+    }
+
+    serial::print("[DISK] ERROR: Read-back ");
+    serial::print(if read_ok { "mismatch" } else { "failed" });
+    serial::print(" at sector ");
+    serial::print_hex(check.sector);
+    serial::println("");
+    let sector = check.sector;
+    state.failed_sector = Some(state.failed_sector.map_or(sector, |s| s.min(sector)));
+    false
+}
+
+/// Wait for the spot check read, recording chunk writes that finish
+/// meanwhile. `true` if the read succeeded.
+fn wait_spot_read(state: &mut WriterState, blk: &mut UnifiedBlockDevice) -> bool {
+    notify(state, blk);
+
+    // Without an installed clock, assume a 4GHz TSC (the old fixed budget).
+    let deadline = Deadline::after_ms(time::active_or_tsc(4_000_000_000), SPOT_CHECK_TIMEOUT_MS);
     loop {
-        if let Some(completion) = blk.poll_completion() {
-            if completion.request_id == request_id {
-                if completion.status == 0 {
-                    NEXT_SECTOR += num_sectors as u64;
-                    TOTAL_WRITTEN += bytes_to_write as u64;
-                    BUFFER_FILL = 0;
-                    return bytes_to_write;
-                } else {
-                    serial::print("[DISK] ERROR: Status ");
-                    serial::print_u32(completion.status as u32);
-                    serial::println("");
-                    return 0;
-                }
+        while let Some(completion) = blk.poll_completion() {
+            if completion.request_id == SPOT_CHECK_REQUEST_ID {
+                return completion.status == 0;
             }
+            complete_chunk(state, &completion);
+        }
+        if deadline.expired() {
+            return false;
+        }
+        core::hint::spin_loop();
+    }
+}
+
+/// Read back every sampled chunk; all writes must have completed.
+fn drain_spot_checks(state: &mut WriterState, blk: &mut UnifiedBlockDevice) -> bool {
+    while state.spot_checks.len > 0 {
+        let queued = state.spot_checks.len;
+        if !spot_check(state, blk) {
+            return false;
+        }
+        if state.spot_checks.len == queued {
+            // Nothing is in flight, so the device won't take it later either
+            break;
+        }
+    }
+    state.spot_checks.clear();
+    true
+}
+
+/// Wait until the write of chunk `index` (if any) has completed.
+fn wait_chunk(state: &mut WriterState, blk: &mut UnifiedBlockDevice, index: usize) -> bool {
+    // A write the device hasn't heard about would never complete
+    if state.chunks[index].in_flight && !state.chunks[index].notified {
+        notify(state, blk);
+    }
+
+    // Without an installed clock, assume a 4GHz TSC (the old fixed budget).
+    let deadline = Deadline::after_ms(time::active_or_tsc(4_000_000_000), WRITE_TIMEOUT_MS);
+
+    loop {
+        reap_completions(state, blk);
+        if !state.chunks[index].in_flight {
+            return state.failed_sector.is_none();
         }
 
-        if read_tsc().wrapping_sub(start_tsc) > timeout {
+        if deadline.expired() {
             serial::println("[DISK] ERROR: Timeout");
-            return 0;
+            // Give up on the buffer; the image is incomplete either way
+            let chunk = &mut state.chunks[index];
+            chunk.in_flight = false;
+            let sector = chunk.sector;
+            state.failed_sector = Some(state.failed_sector.map_or(sector, |s| s.min(sector)));
+            return false;
         }
 
         core::hint::spin_loop();
     }
 }
 
+/// Bytes that fit before a write has to wait: the rest of the current
+/// chunk and every chunk after it that is free. Filling a chunk waits for
+/// the one after it, so the last free chunk can only be filled to one byte
+/// short.
+fn room_without_wait(current: usize, fill: usize, in_flight: &[bool]) -> usize {
+    let n = in_flight.len();
+    let free = (1..n).take_while(|&i| !in_flight[(current + i) % n]).count();
+    BUFFER_SIZE - fill + free * BUFFER_SIZE - 1
+}
+
 /// Buffer data and flush when full.
-unsafe fn buffer_write(blk: &mut UnifiedBlockDevice, data: &[u8]) -> usize {
+fn buffer_write(state: &mut WriterState, blk: &mut UnifiedBlockDevice, data: &[u8]) -> usize {
+    if state.failed_sector.is_some() {
+        return 0;
+    }
+
     let mut consumed = 0;
     let mut remaining = data;
 
     while !remaining.is_empty() {
-        let space = BUFFER_SIZE - BUFFER_FILL;
+        let space = BUFFER_SIZE - state.fill;
         let to_copy = remaining.len().min(space);
 
-        let dst = BUFFER_FILL;
-        WRITE_BUFFER[dst..dst + to_copy].copy_from_slice(&remaining[..to_copy]);
-        BUFFER_FILL += to_copy;
+        let dst = state.fill;
+        let current = state.current;
+        state.chunks[current].buffer[dst..dst + to_copy].copy_from_slice(&remaining[..to_copy]);
+        state.fill += to_copy;
         consumed += to_copy;
         remaining = &remaining[to_copy..];
 
-        if BUFFER_FILL >= BUFFER_SIZE {
-            if flush_buffer(blk) == 0 {
+        if state.fill >= BUFFER_SIZE {
+            if flush_buffer(state, blk) == 0 {
                 break;
             }
         }
@@ -178,33 +789,107 @@ unsafe fn buffer_write(blk: &mut UnifiedBlockDevice, data: &[u8]) -> usize {
     consumed
 }
 
-/// Flush remaining data (pad with zeros for sector alignment).
-unsafe fn flush_remaining(blk: &mut UnifiedBlockDevice) -> bool {
-    if BUFFER_FILL == 0 {
-        return true;
+/// Flush remaining data (pad with zeros for sector alignment) and wait for
+/// every write in flight.
+fn flush_remaining(state: &mut WriterState, blk: &mut UnifiedBlockDevice) -> bool {
+    let mut ok = true;
+    if state.fill > 0 {
+        // Zero-pad to sector boundary
+        let fill = state.fill;
+        let current = state.current;
+        state.chunks[current].buffer[fill..].fill(0);
+
+        ok = flush_buffer(state, blk) > 0;
     }
 
-    // Zero-pad to sector boundary
-    for i in BUFFER_FILL..BUFFER_SIZE {
-        WRITE_BUFFER[i] = 0;
+    // Wait for all of them even after a failure, so bytes_written is exact
+    for index in 0..WRITE_BEHIND {
+        ok &= wait_chunk(state, blk, index);
     }
-
-    flush_buffer(blk) > 0
+    ok
 }
 
-#[cfg(target_arch = "x86_64")]
-#[inline]
-fn read_tsc() -> u64 {
-    let lo: u32;
-    let hi: u32;
-    unsafe {
-        core::arch::asm!("rdtsc", out("eax") lo, out("edx") hi, options(nostack, nomem));
+/// Log how deep the virtio-blk queue ran during the download.
+#[cfg(not(feature = "netboot-only"))]
+fn print_queue_stats(blk: &UnifiedBlockDevice) {
+    /// Print a value given in hundredths as "1.23".
+    fn print_x100(value: u64) {
+        serial::print_u32((value / 100) as u32);
+        serial::print(".");
+        if value % 100 < 10 {
+            serial::print("0");
+        }
+        serial::print_u32((value % 100) as u32);
     }
-    ((hi as u64) << 32) | (lo as u64)
+
+    let UnifiedBlockDevice::VirtIO(driver) = blk else {
+        return;
+    };
+    let stats = driver.stats();
+    serial::print("[DISK] virtio-blk: ");
+    print_x100(stats.avg_in_flight_x100());
+    serial::print(" requests in flight on average, ");
+    print_x100(stats.avg_batch_x100());
+    serial::println(" per notify");
 }
 
-#[cfg(not(target_arch = "x86_64"))]
-#[inline]
-fn read_tsc() -> u64 {
-    0
+#[cfg(feature = "netboot-only")]
+fn print_queue_stats(_blk: &UnifiedBlockDevice) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_zero() {
+        let mut data = [0u8; 1027];
+        assert!(is_zero(&data[1..]));
+        data[1026] = 1;
+        assert!(!is_zero(&data[1..]));
+        assert!(is_zero(&data[..1026]));
+    }
+
+    #[test]
+    fn test_spot_queue() {
+        let check = |sector| SpotCheck {
+            request_id: sector as u32,
+            sector,
+            num_sectors: 128,
+            digest: [0; 32],
+        };
+        let mut queue = SpotQueue::new();
+        assert!(queue.first().is_none());
+        for sector in 0..SPOT_CHECK_QUEUE as u64 {
+            assert!(queue.push(check(sector)));
+        }
+        // Full: later samples are dropped, the oldest stays first
+        assert!(!queue.push(check(99)));
+        for sector in 0..SPOT_CHECK_QUEUE as u64 {
+            assert_eq!(queue.first().map(|c| c.sector), Some(sector));
+            queue.pop();
+        }
+        assert!(queue.first().is_none());
+        queue.pop();
+        assert_eq!(queue.len, 0);
+    }
+
+    #[test]
+    fn test_room_without_wait() {
+        // Nothing in flight: everything but the byte that would wait on
+        // the chunk just submitted
+        assert_eq!(
+            room_without_wait(0, 0, &[false; WRITE_BEHIND]),
+            WRITE_BEHIND * BUFFER_SIZE - 1
+        );
+        // Next chunk busy: only up to one short of filling this one
+        assert_eq!(
+            room_without_wait(1, 100, &[false, false, true, false]),
+            BUFFER_SIZE - 101
+        );
+        // Free run stops at the first busy chunk, wrapping round
+        assert_eq!(
+            room_without_wait(2, 0, &[true, false, false, false]),
+            2 * BUFFER_SIZE - 1
+        );
+    }
 }
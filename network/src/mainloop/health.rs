@@ -0,0 +1,253 @@
+//! Stall diagnosis for the HTTP transfer.
+//!
+//! Once the body has been stalled for a few seconds, the counters that
+//! moved (or didn't) since the last byte arrived are turned into a reason
+//! a user can act on: plug the cable back in, pick another mirror, try a
+//! different NIC. The orchestrator logs it and hands it to the progress
+//! callback.
+
+use super::netstack::TcpStatus;
+use super::tcp_stats::TcpTelemetry;
+
+/// Seconds without new body bytes before a stall is reported.
+pub const STALL_SECS: u64 = 5;
+
+/// Why the download stopped moving, most fundamental cause first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StallReason {
+    /// The NIC reports no link
+    LinkDown,
+    /// Not a single frame arrived
+    NoRx,
+    /// The driver dropped frames or reported receive errors
+    DriverErrors,
+    /// The server closed or reset the connection
+    ConnectionClosed,
+    /// Our receive buffer is full; data isn't being consumed
+    ReceiveWindowFull,
+    /// Our segments go unacknowledged
+    Retransmitting,
+    /// The server is resending, or segments arrive after gaps
+    PacketLoss,
+    /// Frames arrive but the server sends nothing on the connection
+    ServerSilent,
+    /// Nothing in the counters explains it
+    Unknown,
+}
+
+impl StallReason {
+    /// One-line explanation for logs and the progress display.
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Self::LinkDown => "link down - check the cable or switch port",
+            Self::NoRx => "no packets received - NIC or driver not receiving",
+            Self::DriverErrors => "NIC dropping frames - driver or hardware trouble",
+            Self::ConnectionClosed => "server closed the connection",
+            Self::ReceiveWindowFull => "receive buffer full - disk writes not keeping up",
+            Self::Retransmitting => "our packets are not getting through (retransmitting)",
+            Self::PacketLoss => "packets lost between server and us - lossy network",
+            Self::ServerSilent => "server stopped sending - slow or overloaded mirror",
+            Self::Unknown => "no data arriving, cause unknown",
+        }
+    }
+}
+
+/// Counters read from the stack at one point in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HealthSample {
+    /// Body bytes received so far
+    pub bytes: u64,
+    pub link_up: bool,
+    /// Frames received by the NIC (None = the stack doesn't count them)
+    pub rx_frames: Option<u32>,
+    pub tcp_status: TcpStatus,
+    pub tcp: Option<TcpTelemetry>,
+}
+
+/// Stall state changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HealthEvent {
+    /// Stalled, or the reason for an ongoing stall changed
+    Stalled(StallReason),
+    /// Data is flowing again
+    Recovered,
+}
+
+/// Watches the transfer and diagnoses stalls.
+pub struct HealthMonitor {
+    stall_ticks: u64,
+    /// Sample taken when the last byte arrived
+    baseline: Option<HealthSample>,
+    last_progress_tsc: u64,
+    reported: Option<StallReason>,
+}
+
+impl HealthMonitor {
+    pub fn new(tsc_freq: u64) -> Self {
+        Self {
+            stall_ticks: tsc_freq * STALL_SECS,
+            baseline: None,
+            last_progress_tsc: 0,
+            reported: None,
+        }
+    }
+
+    /// Reason of the stall in progress, if any.
+    pub fn stall(&self) -> Option<StallReason> {
+        self.reported
+    }
+
+    /// Feed a new sample; returns an event when the stall state changes.
+    pub fn update(&mut self, sample: HealthSample, tsc: u64) -> Option<HealthEvent> {
+        let baseline = match self.baseline {
+            Some(baseline) if baseline.bytes == sample.bytes => baseline,
+            _ => {
+                self.baseline = Some(sample);
+                self.last_progress_tsc = tsc;
+                return self.reported.take().map(|_| HealthEvent::Recovered);
+            }
+        };
+
+        if tsc.wrapping_sub(self.last_progress_tsc) < self.stall_ticks {
+            return None;
+        }
+        let reason = diagnose(&baseline, &sample);
+        if self.reported == Some(reason) {
+            return None;
+        }
+        self.reported = Some(reason);
+        Some(HealthEvent::Stalled(reason))
+    }
+}
+
+/// Explain a stall from the counters at the last progress and now.
+pub fn diagnose(before: &HealthSample, now: &HealthSample) -> StallReason {
+    if !now.link_up {
+        return StallReason::LinkDown;
+    }
+    if let (Some(before), Some(now)) = (before.rx_frames, now.rx_frames) {
+        if before == now {
+            return StallReason::NoRx;
+        }
+    }
+
+    let tcp = match (before.tcp, now.tcp) {
+        (Some(before), Some(now)) => Some((before, now)),
+        _ => None,
+    };
+    if let Some((b, n)) = tcp {
+        if n.tx_drops > b.tx_drops || n.rx_errors > b.rx_errors {
+            return StallReason::DriverErrors;
+        }
+    }
+    if matches!(now.tcp_status, TcpStatus::Closed | TcpStatus::Closing) {
+        return StallReason::ConnectionClosed;
+    }
+    let Some((b, n)) = tcp else {
+        return StallReason::Unknown;
+    };
+
+    if n.rx_window == 0 && n.segments_out > 0 {
+        StallReason::ReceiveWindowFull
+    } else if n.retransmits > b.retransmits {
+        StallReason::Retransmitting
+    } else if n.peer_retransmits > b.peer_retransmits || n.out_of_order > b.out_of_order {
+        StallReason::PacketLoss
+    } else if n.segments_in == b.segments_in {
+        StallReason::ServerSilent
+    } else {
+        StallReason::Unknown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(bytes: u64) -> HealthSample {
+        HealthSample {
+            bytes,
+            link_up: true,
+            rx_frames: Some(100),
+            tcp_status: TcpStatus::Established,
+            tcp: Some(TcpTelemetry {
+                segments_in: 50,
+                segments_out: 20,
+                rx_window: 65536,
+                ..Default::default()
+            }),
+        }
+    }
+
+    fn with_tcp(mut s: HealthSample, f: impl FnOnce(&mut TcpTelemetry)) -> HealthSample {
+        f(s.tcp.as_mut().unwrap());
+        s
+    }
+
+    #[test]
+    fn test_diagnose_order() {
+        let before = sample(10);
+        let mut now = with_tcp(sample(10), |t| t.retransmits = 3);
+        now.rx_frames = Some(101);
+        assert_eq!(diagnose(&before, &now), StallReason::Retransmitting);
+
+        now.tcp_status = TcpStatus::Closed;
+        assert_eq!(diagnose(&before, &now), StallReason::ConnectionClosed);
+
+        now.rx_frames = Some(100);
+        assert_eq!(diagnose(&before, &now), StallReason::NoRx);
+
+        now.link_up = false;
+        assert_eq!(diagnose(&before, &now), StallReason::LinkDown);
+    }
+
+    #[test]
+    fn test_diagnose_tcp() {
+        let before = sample(10);
+        let mut now = sample(10);
+        now.rx_frames = Some(120);
+        assert_eq!(diagnose(&before, &now), StallReason::ServerSilent);
+
+        let loss = with_tcp(now, |t| {
+            t.segments_in = 60;
+            t.out_of_order = 1;
+        });
+        assert_eq!(diagnose(&before, &loss), StallReason::PacketLoss);
+
+        let full = with_tcp(loss, |t| t.rx_window = 0);
+        assert_eq!(diagnose(&before, &full), StallReason::ReceiveWindowFull);
+
+        let drops = with_tcp(full, |t| t.rx_errors = 1);
+        assert_eq!(diagnose(&before, &drops), StallReason::DriverErrors);
+    }
+
+    #[test]
+    fn test_monitor_reports_changes_only() {
+        let mut monitor = HealthMonitor::new(1000);
+        let stall = STALL_SECS * 1000;
+        assert_eq!(monitor.update(sample(10), 0), None);
+        assert_eq!(monitor.update(sample(10), stall - 1), None);
+
+        let mut silent = sample(10);
+        silent.rx_frames = Some(150);
+        assert_eq!(
+            monitor.update(silent, stall),
+            Some(HealthEvent::Stalled(StallReason::ServerSilent))
+        );
+        assert_eq!(monitor.update(silent, stall + 10), None);
+        assert_eq!(monitor.stall(), Some(StallReason::ServerSilent));
+
+        silent.link_up = false;
+        assert_eq!(
+            monitor.update(silent, stall + 20),
+            Some(HealthEvent::Stalled(StallReason::LinkDown))
+        );
+
+        assert_eq!(
+            monitor.update(sample(20), stall + 30),
+            Some(HealthEvent::Recovered)
+        );
+        assert_eq!(monitor.stall(), None);
+        assert_eq!(monitor.update(sample(30), stall + 40), None);
+    }
+}
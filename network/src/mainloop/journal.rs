@@ -0,0 +1,186 @@
+//! Intent journal for the download states.
+//!
+//! Opens the `transfer::disk::Journal` on the ESP before the first disk
+//! write, cleans up whatever an interrupted session left behind, and
+//! records each step of this one:
+//!
+//! ```text
+//! GptPrep   begin(CreatePartition) → create → advance(WriteData)
+//! Manifest  begin(WriteManifest)   → write  → commit
+//! Abort                              partial manifest → commit
+//! Http      checkpoint                 partial manifest → commit
+//! Recover   Relocate               → finish the move → commit
+//! ```
+//!
+//! The journal only adds crash safety, so when it can't be used the
+//! download carries on without it.
+
+use crate::device::UnifiedBlockDevice;
+use crate::dma::HeapDmaBuffer;
+use crate::driver::unified_block_io::UnifiedBlockIo;
+use crate::mainloop::context::Context;
+use crate::mainloop::serial;
+use crate::mainloop::states::{write_manifest_standalone, ManifestConfig};
+use crate::time::TimeoutConfig;
+#[cfg(feature = "fat32_manifest")]
+use crate::transfer::disk::CompactPlan;
+use crate::transfer::disk::{
+    DiskError, DiskResult, Journal, JournalEntry, JournalStep, Recovery, JOURNAL_PATH, SECTOR_SIZE,
+};
+
+/// DMA buffer size for journal I/O.
+const JOURNAL_DMA_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Open the journal (creating it on first use) and recover from an
+/// interrupted session. Leaves `ctx.journal` empty if that fails.
+pub fn open(ctx: &mut Context<'_>) {
+    let esp_start_lba = ctx.config.esp_start_lba;
+    let timeouts = ctx.timeouts.block();
+    let Some(blk) = ctx.blk_device.as_mut() else {
+        return;
+    };
+    if esp_start_lba == 0 {
+        serial::println("[JOURNAL] No ESP configured, running without journal");
+        return;
+    }
+    // Recovery deletes partitions on the disk holding the journal
+    if ctx.esp_device.is_some() {
+        serial::println("[JOURNAL] ESP on another disk, running without journal");
+        return;
+    }
+
+    let opened = with_adapter(blk, timeouts, |io| {
+        if let Some(journal) = Journal::open(io, esp_start_lba)? {
+            return Ok(journal);
+        }
+        serial::println("[JOURNAL] Creating journal");
+        let _ = morpheus_core::fs::create_directory(io, esp_start_lba, "/.iso");
+        morpheus_core::fs::write_file(io, esp_start_lba, JOURNAL_PATH, &[0u8; SECTOR_SIZE])
+            .map_err(|_| DiskError::IoError)?;
+        Journal::open(io, esp_start_lba)?.ok_or(DiskError::IoError)
+    });
+
+    match opened {
+        Ok(journal) => {
+            ctx.journal = Some(journal);
+            recover(ctx);
+        }
+        Err(e) => {
+            serial::print("[JOURNAL] WARN: Cannot open journal: ");
+            serial::println(match e {
+                DiskError::IoError => "IO error",
+                DiskError::NotSupported => "unsupported ESP",
+                _ => "unknown error",
+            });
+        }
+    }
+}
+
+/// Record `entry` as the operation in progress.
+pub fn begin(ctx: &mut Context<'_>, entry: JournalEntry) {
+    update(ctx, |journal, io| journal.begin(io, entry));
+}
+
+/// Move the operation in progress on to `step`.
+pub fn advance(ctx: &mut Context<'_>, step: JournalStep) {
+    update(ctx, |journal, io| journal.advance(io, step));
+}
+
+/// Mark the operation in progress as finished.
+pub fn commit(ctx: &mut Context<'_>) {
+    update(ctx, |journal, io| journal.commit(io));
+}
+
+/// Roll back or finish the operation the last session left uncommitted.
+fn recover(ctx: &mut Context<'_>) {
+    let esp_start_lba = ctx.config.esp_start_lba;
+    let partition_uuid = ctx.config.partition_uuid;
+    let timeouts = ctx.timeouts.block();
+    let (Some(blk), Some(journal)) = (ctx.blk_device.as_mut(), ctx.journal.as_mut()) else {
+        return;
+    };
+
+    let Some(pending) = journal.pending() else {
+        return;
+    };
+    serial::print("[JOURNAL] Interrupted operation: ");
+    serial::print(pending.name_str());
+    serial::print(" (");
+    serial::print(pending.step.as_str());
+    serial::println(")");
+
+    match with_adapter(blk, timeouts, |io| journal.recover(io, esp_start_lba)) {
+        Ok(Recovery::Clean) => {}
+        Ok(Recovery::RolledBack { start_lba, end_lba }) => {
+            serial::print("[JOURNAL] Rolled back partition ");
+            serial::print_hex(start_lba);
+            serial::print(" - ");
+            serial::print_hex(end_lba);
+            serial::println("");
+        }
+        Ok(Recovery::Completed) => {
+            serial::println("[JOURNAL] Manifest already written, committed");
+        }
+        Ok(Recovery::WriteManifest(entry)) => {
+            serial::println("[JOURNAL] Data complete, writing missing manifest");
+            let config = ManifestConfig::fat32(
+                entry.name_str(),
+                entry.iso_size,
+                entry.start_lba,
+                entry.end_lba + 1,
+                partition_uuid,
+                esp_start_lba,
+            );
+            if !write_manifest_standalone(blk, &config, timeouts) {
+                serial::println("[JOURNAL] WARN: Manifest write failed, will retry next run");
+                return;
+            }
+            if with_adapter(blk, timeouts, |io| journal.commit(io)).is_err() {
+                serial::println("[JOURNAL] WARN: Commit failed");
+            }
+        }
+        #[cfg(feature = "fat32_manifest")]
+        Ok(Recovery::Relocate(_)) => {
+            serial::println("[JOURNAL] Finishing interrupted partition move");
+            let mut buffer = alloc::vec![0u8; JOURNAL_DMA_BUFFER_SIZE];
+            let resumed = with_adapter(blk, timeouts, |io| {
+                CompactPlan::resume(io, esp_start_lba, journal, &mut buffer, None)
+            });
+            if resumed.is_err() {
+                serial::println("[JOURNAL] WARN: Partition move failed, will retry next run");
+            }
+        }
+        #[cfg(not(feature = "fat32_manifest"))]
+        Ok(Recovery::Relocate(_)) => {
+            serial::println("[JOURNAL] WARN: Partition move needs FAT32 manifests, left pending");
+        }
+        Err(_) => serial::println("[JOURNAL] WARN: Recovery failed, will retry next run"),
+    }
+}
+
+fn update(
+    ctx: &mut Context<'_>,
+    f: impl FnOnce(&mut Journal, &mut UnifiedBlockIo<'_>) -> DiskResult<()>,
+) {
+    let timeouts = ctx.timeouts.block();
+    let (Some(blk), Some(journal)) = (ctx.blk_device.as_mut(), ctx.journal.as_mut()) else {
+        return;
+    };
+    if with_adapter(blk, timeouts, |io| f(journal, io)).is_err() {
+        serial::println("[JOURNAL] WARN: Journal update failed");
+    }
+}
+
+/// Run `f` on a BlockIo adapter over `blk`.
+fn with_adapter<T>(
+    blk: &mut UnifiedBlockDevice,
+    timeouts: TimeoutConfig,
+    f: impl FnOnce(&mut UnifiedBlockIo<'_>) -> DiskResult<T>,
+) -> DiskResult<T> {
+    let mut dma = HeapDmaBuffer::new(JOURNAL_DMA_BUFFER_SIZE).ok_or(DiskError::IoError)?;
+    let (dma_buffer, dma_buffer_phys) = dma.split_phys();
+
+    let mut adapter = UnifiedBlockIo::new(blk, dma_buffer, dma_buffer_phys, timeouts)
+        .map_err(|_| DiskError::IoError)?;
+    f(&mut adapter)
+}
@@ -0,0 +1,144 @@
+//! PS/2 keyboard polling for the post-EBS session.
+//!
+//! The firmware's console input is gone with boot services, so without
+//! this a machine with no serial console has no way to abort. The 8042
+//! controller is polled (no interrupts are set up after EBS) and its
+//! scancodes decoded just far enough to spot ESC and Ctrl-C. Firmware
+//! leaves translation on, so the bytes are scancode set 1.
+//!
+//! USB keyboards work only where firmware keeps legacy emulation running
+//! behind the 8042 ports after the exit; there is no HID driver here.
+
+/// Bytes drained per poll, so a stuck controller can't hold up the loop.
+const MAX_BYTES_PER_POLL: usize = 16;
+
+/// Set 1 prefix of extended keys (right Ctrl, arrows, ...).
+const SC_EXTENDED: u8 = 0xE0;
+/// Set 1 prefix of the Pause sequence.
+const SC_PAUSE: u8 = 0xE1;
+/// Release flag in set 1.
+const SC_BREAK: u8 = 0x80;
+const SC_ESC: u8 = 0x01;
+const SC_CTRL: u8 = 0x1D;
+const SC_C: u8 = 0x2E;
+
+/// Scancode set 1 decoder that reports abort keys.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AbortKeyDecoder {
+    ctrl: bool,
+    extended: bool,
+}
+
+impl AbortKeyDecoder {
+    pub const fn new() -> Self {
+        Self {
+            ctrl: false,
+            extended: false,
+        }
+    }
+
+    /// Feed one scancode byte. Returns true when it completes a press of
+    /// ESC or Ctrl-C.
+    pub fn feed(&mut self, code: u8) -> bool {
+        if code == SC_EXTENDED {
+            self.extended = true;
+            return false;
+        }
+        if code == SC_PAUSE {
+            return false;
+        }
+        let extended = core::mem::take(&mut self.extended);
+        let pressed = code & SC_BREAK == 0;
+        match code & !SC_BREAK {
+            // Left Ctrl, or right Ctrl with the prefix
+            SC_CTRL => {
+                self.ctrl = pressed;
+                false
+            }
+            SC_ESC => pressed && !extended,
+            SC_C => pressed && !extended && self.ctrl,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod ps2 {
+    use super::{AbortKeyDecoder, MAX_BYTES_PER_POLL};
+    use crate::asm::core::pio::inb;
+
+    const DATA_PORT: u16 = 0x60;
+    const STATUS_PORT: u16 = 0x64;
+    /// Status: output buffer full
+    const STATUS_OBF: u8 = 0x01;
+    /// Status: the byte is from the mouse
+    const STATUS_AUX: u8 = 0x20;
+
+    static mut DECODER: AbortKeyDecoder = AbortKeyDecoder::new();
+
+    pub fn poll() -> bool {
+        let mut abort = false;
+        for _ in 0..MAX_BYTES_PER_POLL {
+            // SAFETY: reads of the 8042 ports have no side effects beyond
+            // taking the pending byte.
+            let status = unsafe { inb(STATUS_PORT) };
+            // 0xFF: no controller decodes the port
+            if status == 0xFF || status & STATUS_OBF == 0 {
+                break;
+            }
+            let byte = unsafe { inb(DATA_PORT) };
+            if status & STATUS_AUX != 0 {
+                continue;
+            }
+            // SAFETY: the session loop is single-threaded.
+            abort |= unsafe { (*core::ptr::addr_of_mut!(DECODER)).feed(byte) };
+        }
+        abort
+    }
+}
+
+/// Drain the PS/2 controller. Returns true if ESC or Ctrl-C was pressed.
+#[cfg(target_arch = "x86_64")]
+pub fn poll() -> bool {
+    ps2::poll()
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+pub fn poll() -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed_all(decoder: &mut AbortKeyDecoder, codes: &[u8]) -> bool {
+        codes
+            .iter()
+            .fold(false, |hit, &code| decoder.feed(code) | hit)
+    }
+
+    #[test]
+    fn test_esc_and_ctrl_c() {
+        let mut decoder = AbortKeyDecoder::new();
+        // ESC press, release
+        assert!(feed_all(&mut decoder, &[0x01, 0x81]));
+        // C alone, then Ctrl+C, then C after Ctrl is released
+        assert!(!feed_all(&mut decoder, &[0x2E, 0xAE]));
+        assert!(feed_all(&mut decoder, &[0x1D, 0x2E, 0xAE, 0x9D]));
+        assert!(!feed_all(&mut decoder, &[0x2E, 0xAE]));
+        // Right Ctrl
+        assert!(feed_all(&mut decoder, &[0xE0, 0x1D, 0x2E]));
+    }
+
+    #[test]
+    fn test_extended_and_releases_ignored() {
+        let mut decoder = AbortKeyDecoder::new();
+        // ESC release only, an extended 0x01, Pause
+        assert!(!feed_all(&mut decoder, &[0x81, 0xE0, 0x01]));
+        assert!(!feed_all(
+            &mut decoder,
+            &[0xE1, 0x1D, 0x45, 0xE1, 0x9D, 0xC5]
+        ));
+    }
+}
@@ -0,0 +1,204 @@
+//! PHY link watching while the first states run.
+//!
+//! The PHY starts negotiating when the driver comes up, so the link is
+//! often long settled by the time GPT prep is done. The orchestrator
+//! samples it every few milliseconds from the first iteration until
+//! LinkWait is done with it; LinkWait then only asks how long the link
+//! has been ready, and when it gives up, what went wrong.
+//!
+//! A link is ready when the MAC reports it up and, on NICs whose PHY the
+//! driver can read, auto-negotiation has completed. Links without a PHY
+//! (VirtIO) are ready as soon as they are up.
+
+/// Time between samples, in milliseconds. PHY reads go over MDIO, so
+/// they are kept off every iteration.
+pub const SAMPLE_INTERVAL_MS: u64 = 10;
+
+/// Link state read from the stack at one point in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LinkSample {
+    /// The MAC reports a link (e1000e STATUS.LU, VirtIO status)
+    pub link_up: bool,
+    /// PHY auto-negotiation complete (BMSR), None if not readable
+    pub autoneg_complete: Option<bool>,
+}
+
+impl LinkSample {
+    fn ready(&self) -> bool {
+        self.link_up && self.autoneg_complete != Some(false)
+    }
+}
+
+/// Why the link never became ready.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkProblem {
+    /// Never came up at all
+    NoLink,
+    /// Up at the MAC, but auto-negotiation never completed
+    NegotiationIncomplete,
+    /// Came up and dropped again
+    Flapping,
+}
+
+impl LinkProblem {
+    /// One-line explanation for the log.
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Self::NoLink => "no link - cable unplugged? Check the cable and switch port",
+            Self::NegotiationIncomplete => {
+                "link seen but auto-negotiation never completed - try the 100 Mbps link mode"
+            }
+            Self::Flapping => "link keeps dropping - bad cable or switch port?",
+        }
+    }
+}
+
+/// Link history since the session started.
+#[derive(Debug, Clone, Copy)]
+pub struct LinkWatch {
+    interval_ticks: u64,
+    /// First sample (None = not sampled yet)
+    first_sample_tsc: Option<u64>,
+    last_sample_tsc: u64,
+    /// Since when the link has been ready
+    ready_since: Option<u64>,
+    /// Some sample had the MAC link up
+    ever_up: bool,
+    /// Some sample had the MAC link down
+    seen_down: bool,
+    /// Ready periods that ended
+    drops: u32,
+    /// LinkWait is done; no more sampling
+    finished: bool,
+}
+
+impl LinkWatch {
+    pub fn new(tsc_freq: u64) -> Self {
+        Self {
+            interval_ticks: tsc_freq / 1000 * SAMPLE_INTERVAL_MS,
+            first_sample_tsc: None,
+            last_sample_tsc: 0,
+            ready_since: None,
+            ever_up: false,
+            seen_down: false,
+            drops: 0,
+            finished: false,
+        }
+    }
+
+    /// Whether the next sample should be taken at `tsc`.
+    pub fn due(&self, tsc: u64) -> bool {
+        !self.finished
+            && (self.first_sample_tsc.is_none()
+                || tsc.wrapping_sub(self.last_sample_tsc) >= self.interval_ticks)
+    }
+
+    /// Record a sample taken at `tsc`.
+    pub fn update(&mut self, sample: LinkSample, tsc: u64) {
+        self.first_sample_tsc.get_or_insert(tsc);
+        self.last_sample_tsc = tsc;
+        self.ever_up |= sample.link_up;
+        self.seen_down |= !sample.link_up;
+
+        match (sample.ready(), self.ready_since) {
+            (true, None) => self.ready_since = Some(tsc),
+            (false, Some(_)) => {
+                self.ready_since = None;
+                self.drops += 1;
+            }
+            _ => {}
+        }
+    }
+
+    /// Ticks the link has been ready for, None if it isn't.
+    pub fn ready_for(&self, tsc: u64) -> Option<u64> {
+        self.ready_since.map(|since| tsc.wrapping_sub(since))
+    }
+
+    /// Ticks since the first sample.
+    pub fn waited(&self, tsc: u64) -> u64 {
+        self.first_sample_tsc
+            .map_or(0, |first| tsc.wrapping_sub(first))
+    }
+
+    /// Whether the link was down at some point; anything the stack sent
+    /// then is lost.
+    pub fn seen_down(&self) -> bool {
+        self.seen_down
+    }
+
+    /// What went wrong, for a link that isn't ready.
+    pub fn problem(&self) -> LinkProblem {
+        if self.drops > 0 {
+            LinkProblem::Flapping
+        } else if self.ever_up {
+            LinkProblem::NegotiationIncomplete
+        } else {
+            LinkProblem::NoLink
+        }
+    }
+
+    /// Stop sampling; the download watches the link on its own.
+    pub fn finish(&mut self) {
+        self.finished = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UP: LinkSample = LinkSample {
+        link_up: true,
+        autoneg_complete: Some(true),
+    };
+    const DOWN: LinkSample = LinkSample {
+        link_up: false,
+        autoneg_complete: Some(false),
+    };
+    const NEGOTIATING: LinkSample = LinkSample {
+        link_up: true,
+        autoneg_complete: Some(false),
+    };
+
+    #[test]
+    fn test_ready_for() {
+        let mut watch = LinkWatch::new(1000);
+        assert!(watch.due(0));
+        watch.update(DOWN, 100);
+        assert!(!watch.due(105));
+        assert!(watch.due(110));
+
+        watch.update(NEGOTIATING, 110);
+        assert_eq!(watch.ready_for(120), None);
+        watch.update(UP, 120);
+        assert_eq!(watch.ready_for(620), Some(500));
+        assert_eq!(watch.waited(620), 520);
+        assert!(watch.seen_down());
+
+        // No PHY to ask: up is ready
+        let mut virtio = LinkWatch::new(1000);
+        let up = LinkSample {
+            link_up: true,
+            autoneg_complete: None,
+        };
+        virtio.update(up, 0);
+        assert_eq!(virtio.ready_for(10), Some(10));
+        assert!(!virtio.seen_down());
+
+        watch.finish();
+        assert!(!watch.due(10_000));
+    }
+
+    #[test]
+    fn test_problem() {
+        let mut watch = LinkWatch::new(1000);
+        watch.update(DOWN, 0);
+        assert_eq!(watch.problem(), LinkProblem::NoLink);
+        watch.update(NEGOTIATING, 10);
+        assert_eq!(watch.problem(), LinkProblem::NegotiationIncomplete);
+        watch.update(UP, 20);
+        watch.update(DOWN, 30);
+        assert_eq!(watch.problem(), LinkProblem::Flapping);
+    }
+}
@@ -20,17 +20,32 @@
 //!
 //! # State Flow
 //! ```text
-//! Init → GptPrep → LinkWait → DHCP → DNS → Connect → HTTP → Manifest → Done (reboot)
+//! Init → GptPrep → LinkWait → DHCP → DNS → Connect → HTTP → Manifest → Done (post actions)
+//!                                                 ↑ Preflight (HEAD) ↩
+//!                                                 ↑ Checksums (list next to the image) ↩
 //! ```
 //!
 //! # Modules
 //! - `state` - State trait and StepResult for state machine
 //! - `states` - Individual state implementations
 //! - `serial` - Serial output primitives (post-EBS)
+//! - `netstack` - TCP/IP stack interface the states talk to
+//! - `smoltcp_stack` - Default `NetStack` on smoltcp
 //! - `adapter` - smoltcp Device adapter
+//! - `tcp_stats` - RTT / retransmit / window telemetry from the wire
+//! - `health` - Stall diagnosis from link, RX and TCP counters
+//! - `link_watch` - PHY link sampling from session start until LinkWait
 //! - `context` - Shared context between states
 //! - `disk_writer` - Buffered disk writer for streaming writes
-//! - `orchestrator` - Entry point (`download_with_config`)
+//! - `post_actions` - What Done does after a successful download
+//! - `retry` - Exponential backoff policies shared by network states
+//! - `rate` - Token bucket behind the optional download rate cap
+//! - `trace` - Per-phase and per-state timing histograms, dumped at the end
+//! - `abort` - User abort requests (serial ESC / Ctrl-C or `request_abort()`)
+//! - `orchestrator` - Entry point (`download_with_config`,
+//!   `download_with_devices` to pick among several disks,
+//!   `download_with_disks` for the ESP on another disk, or
+//!   `download_with_stack` for another stack)
 //!
 //! # Usage
 //!
@@ -43,12 +58,26 @@
 //! ```
 
 // State machine modules
+pub mod abort;
 pub mod adapter;
+pub mod beep;
 pub mod context;
 pub mod disk_writer;
+pub mod health;
+pub mod journal;
+pub mod keyboard;
+pub mod link_watch;
+pub mod netstack;
+pub mod post_actions;
+pub mod rate;
+pub mod retry;
 pub mod serial;
+pub mod smoltcp_stack;
 pub mod state;
 pub mod states;
+pub mod status;
+pub mod tcp_stats;
+pub mod trace;
 pub mod orchestrator;
 
 // Support modules
@@ -56,13 +85,29 @@ pub mod phases;
 pub mod runner;
 
 // Re-exports
+pub use abort::{abort_requested, request_abort};
 pub use adapter::SmoltcpAdapter;
-pub use context::{Context, DownloadConfig, Timeouts};
+pub use beep::{BeepCode, Beeper};
+pub use context::{Context, DownloadConfig, Preflight, Progress, ProgressFn, Timeouts};
 pub use disk_writer::DiskWriter;
+pub use health::{diagnose, HealthEvent, HealthMonitor, HealthSample, StallReason};
+pub use link_watch::{LinkProblem, LinkSample, LinkWatch};
+pub use netstack::{DhcpEvent, DnsStatus, Ipv4Config, NetStack, StackError, TcpStatus};
+pub use post_actions::{Finish, PostAction, PostActions};
+pub use rate::TokenBucket;
+pub use retry::{RetryPhase, RetryPolicies, RetryPolicy, RetryStats};
 pub use serial::{print, println, print_hex, print_u32, print_mac, print_ipv4};
+pub use smoltcp_stack::SmoltcpStack;
 pub use state::{State, StepResult};
+pub use status::StatusScreen;
+pub use tcp_stats::TcpTelemetry;
 pub use states::{InitState, DhcpState, DnsState, ConnectState, HttpState, DoneState, FailedState};
+pub use states::AbortState;
+pub use states::{split_proxy, split_url, UrlParts};
 pub use states::{GptPrepState, LinkWaitState, ManifestState, ManifestConfig, ManifestMode};
-pub use orchestrator::{download, download_with_config, DownloadResult};
-pub use phases::{phase1_rx_refill, phase5_tx_completions, TX_BUDGET};
+pub use orchestrator::{
+    download, download_with_config, download_with_devices, download_with_disks,
+    download_with_stack, DownloadResult,
+};
+pub use phases::{phase1_rx_refill, phase5_tx_completions, AdaptiveBudget, Load, TX_BUDGET};
 pub use runner::{run_iteration, IterationResult, MainLoopConfig, get_tsc};
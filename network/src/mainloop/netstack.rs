@@ -0,0 +1,187 @@
+//! TCP/IP stack interface for the download state machine.
+//!
+//! States reach the network only through [`NetStack`]: one DHCP client,
+//! one DNS resolver, one TCP connection and one UDP socket, which is all
+//! a download needs. Addresses are `core::net` types so no stack's own
+//! types leak into the states or the context.
+//!
+//! [`SmoltcpStack`](super::smoltcp_stack::SmoltcpStack) is the default.
+//! Another stack (a minimal custom TCP, a port of a different library) is
+//! swapped in through
+//! [`download_with_stack`](super::orchestrator::download_with_stack), to
+//! benchmark it or to work around a smoltcp limitation.
+
+use core::net::Ipv4Addr;
+
+use super::tcp_stats::TcpTelemetry;
+use crate::driver::traits::LinkInfo;
+
+/// Socket operation failures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StackError {
+    /// The socket is not in a state that allows this
+    InvalidState,
+    /// No buffer or query slot free; retry after the next poll
+    Exhausted,
+    /// The stack does not implement this
+    Unsupported,
+}
+
+/// Address configuration handed out by DHCP.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ipv4Config {
+    pub address: Ipv4Addr,
+    pub prefix_len: u8,
+    pub router: Option<Ipv4Addr>,
+    pub dns_servers: [Option<Ipv4Addr>; 3],
+}
+
+/// DHCP lease changes. The stack applies the address and default route
+/// itself before reporting them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DhcpEvent {
+    Configured(Ipv4Config),
+    Deconfigured,
+}
+
+/// TCP connection state, reduced to what the states act on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TcpStatus {
+    /// No connection (never opened, reset, or fully closed)
+    Closed,
+    /// Handshake in progress
+    Connecting,
+    Established,
+    /// Either side has started closing
+    Closing,
+}
+
+/// Progress of the DNS query started by [`NetStack::dns_query`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DnsStatus {
+    Pending,
+    Resolved(Ipv4Addr),
+    /// The server answered without an IPv4 address
+    NoAddress,
+    Failed,
+}
+
+/// What the download state machine needs from a TCP/IP stack.
+pub trait NetStack {
+    /// Run timers and move packets between the NIC and the sockets.
+    /// `now_ms` is the session clock in milliseconds. Returns true if any
+    /// packet was sent or received.
+    fn poll(&mut self, now_ms: i64) -> bool;
+
+    /// Microseconds until the stack next needs a poll (None = only when a
+    /// packet arrives).
+    fn poll_delay(&mut self, now_ms: i64) -> Option<u64>;
+
+    /// Whether the NIC reports a PHY link.
+    fn link_up(&self) -> bool;
+
+    /// Speed and duplex of the link, if the NIC reports them.
+    fn link_info(&self) -> Option<LinkInfo> {
+        None
+    }
+
+    /// Whether the NIC's PHY has finished auto-negotiation, if readable.
+    fn autoneg_complete(&self) -> Option<bool> {
+        None
+    }
+
+    /// Frames received by the NIC so far, if the stack counts them.
+    fn rx_frames(&self) -> Option<u32> {
+        None
+    }
+
+    /// Cap the packets the next `poll` may send on its own (replies to
+    /// received frames aside).
+    fn set_tx_budget(&mut self, _packets: usize) {}
+
+    /// Frames waiting in the NIC's RX ring and the ring size, if known.
+    fn rx_backlog(&self) -> Option<(usize, usize)> {
+        None
+    }
+
+    /// Stop NIC DMA at the end of the session.
+    fn quiesce(&mut self);
+
+    // --- DHCP ---
+
+    /// Next lease change, if any.
+    fn dhcp_poll(&mut self) -> Option<DhcpEvent>;
+
+    /// Drop the lease and start discovery over.
+    fn dhcp_restart(&mut self);
+
+    // --- DNS ---
+
+    /// Start an A query for `host` against `server`, replacing any query
+    /// still pending.
+    fn dns_query(&mut self, host: &str, server: Ipv4Addr) -> Result<(), StackError>;
+
+    fn dns_status(&mut self) -> DnsStatus;
+
+    /// Give up on the pending query.
+    fn dns_cancel(&mut self);
+
+    // --- TCP ---
+
+    /// Start connecting to `remote`:`port` from `local_port`.
+    fn tcp_connect(
+        &mut self,
+        remote: Ipv4Addr,
+        port: u16,
+        local_port: u16,
+    ) -> Result<(), StackError>;
+
+    /// Like [`tcp_connect`](Self::tcp_connect), then run a TLS handshake
+    /// with `server_name` (SNI and certificate name). The status stays
+    /// `Connecting` until the handshake is done; send and receive carry
+    /// plaintext from then on.
+    fn tcp_connect_tls(
+        &mut self,
+        _remote: Ipv4Addr,
+        _port: u16,
+        _local_port: u16,
+        _server_name: &str,
+    ) -> Result<(), StackError> {
+        Err(StackError::Unsupported)
+    }
+
+    fn tcp_status(&self) -> TcpStatus;
+
+    /// Whether the connection can take data to send.
+    fn tcp_may_send(&self) -> bool;
+
+    /// Whether data may still arrive (open, or closed with data buffered).
+    fn tcp_may_recv(&self) -> bool;
+
+    /// Queue `data`; returns how much was taken.
+    fn tcp_send(&mut self, data: &[u8]) -> Result<usize, StackError>;
+
+    /// Read buffered data into `buf`; 0 if nothing is waiting.
+    fn tcp_recv(&mut self, buf: &mut [u8]) -> Result<usize, StackError>;
+
+    /// Drop the connection with a reset.
+    fn tcp_abort(&mut self);
+
+    /// RTT, retransmit and window counters for the connection, if the
+    /// stack keeps them.
+    fn tcp_telemetry(&self) -> Option<TcpTelemetry> {
+        None
+    }
+
+    // --- UDP ---
+
+    /// Open the UDP socket on `port`.
+    fn udp_bind(&mut self, port: u16) -> Result<(), StackError>;
+
+    fn udp_send(&mut self, data: &[u8], remote: Ipv4Addr, port: u16) -> Result<(), StackError>;
+
+    /// Next datagram: its length and sender.
+    fn udp_recv(&mut self, buf: &mut [u8]) -> Option<(usize, Ipv4Addr, u16)>;
+
+    fn udp_close(&mut self);
+}
@@ -2,7 +2,9 @@
 //!
 //! # Entry Point Contract
 //!
-//! **SOLE ENTRY**: `download_with_config()` or convenience wrapper `download()`
+//! **SOLE ENTRY**: `download_with_config()` or convenience wrapper `download()`.
+//! `download_with_stack()` runs the same session over a caller-supplied
+//! [`NetStack`] instead of the default smoltcp one.
 //!
 //! **PRECONDITIONS** (caller must ensure):
 //! 1. ExitBootServices has been called (no UEFI runtime)
@@ -12,7 +14,8 @@
 //! **WHAT THIS MODULE RECEIVES**:
 //! - `&mut D` - Already-reset driver implementing `NetworkDriver`
 //! - `DownloadConfig` - URL + optional disk write parameters
-//! - `Option<UnifiedBlockDevice>` - Block device if writing
+//! - `Option<UnifiedBlockDevice>` - Block device if writing, or every
+//!   probed device for `download_with_devices()` to pick from
 //! - `tsc_freq` - TSC frequency in Hz
 //!
 //! **WHAT THIS MODULE DOES NOT DO**:
@@ -27,30 +30,71 @@
 //! Init → GptPrep → LinkWait → DHCP → DNS → Connect → HTTP → Manifest → Done
 //! ```
 
-use smoltcp::iface::{Config as IfaceConfig, Interface, SocketSet, SocketStorage};
-use smoltcp::socket::dhcpv4::Socket as Dhcpv4Socket;
-use smoltcp::socket::tcp::{Socket as TcpSocket, SocketBuffer as TcpSocketBuffer};
-use smoltcp::time::Instant;
-use smoltcp::wire::{EthernetAddress, HardwareAddress};
+use morpheus_core::disk::identity::MediaKind;
+use morpheus_core::iso::{ExitReport, FailureReason, Verification};
 
 use crate::device::UnifiedBlockDevice;
+use crate::dma::HeapDmaBuffer;
+use crate::driver::block_traits::{BlockDeviceInfo, BlockDriver};
+use crate::driver::unified_block_io::UnifiedBlockIo;
 use crate::driver::traits::NetworkDriver;
-use crate::mainloop::adapter::SmoltcpAdapter;
-use crate::mainloop::context::{Context, DownloadConfig};
+use crate::mainloop::context::{Context, DownloadConfig, Progress};
+use crate::mainloop::health::{HealthEvent, HealthMonitor, HealthSample};
+use crate::mainloop::link_watch::LinkSample;
+use crate::mainloop::netstack::{NetStack, TcpStatus};
+use crate::mainloop::phases::AdaptiveBudget;
 use crate::mainloop::serial;
+use crate::time::TimeoutConfig;
+use crate::mainloop::smoltcp_stack::SmoltcpStack;
 use crate::mainloop::state::{State, StepResult};
-use crate::mainloop::states::InitState;
+use crate::mainloop::status::StatusScreen;
+use crate::mainloop::tcp_stats::TcpTelemetry;
+use crate::mainloop::abort;
+use crate::mainloop::beep::{BeepCode, Beeper};
+use crate::mainloop::trace::{self, Phase};
+use crate::mainloop::states::{write_exit_report, AbortState, InitState};
+use crate::power::{thermal, Idler, ThermalEvent, ThermalMonitor, ThermalPolicy};
+use crate::time::{self, Clock};
+use crate::tls::TlsStack;
+use crate::transfer::disk::{DiskSelector, GptOps};
 
 extern crate alloc;
 use alloc::boxed::Box;
+use alloc::format;
+use alloc::vec::Vec;
+
+/// Seconds between TCP telemetry lines while connected.
+const TELEMETRY_INTERVAL_SECS: u64 = 5;
+
+/// DMA buffer for reading a GPT header while picking disks.
+const DISK_GUID_DMA_SIZE: usize = 4096;
 
 /// Result of a download operation.
+///
+/// Both outcomes carry the session's [`ExitReport`], the same one left on
+/// the ESP at `EXIT_REPORT_PATH` for the next boot.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DownloadResult {
     /// Download completed successfully.
-    Success { bytes_downloaded: u64, bytes_written: u64 },
+    Success {
+        bytes_downloaded: u64,
+        bytes_written: u64,
+        report: ExitReport,
+    },
     /// Download failed.
-    Failed { reason: &'static str },
+    Failed {
+        reason: &'static str,
+        report: ExitReport,
+    },
+}
+
+impl DownloadResult {
+    /// Transfer figures, timings and retries of the session.
+    pub fn report(&self) -> &ExitReport {
+        match self {
+            Self::Success { report, .. } | Self::Failed { report, .. } => report,
+        }
+    }
 }
 
 /// Execute HTTP download using state machine.
@@ -81,89 +125,355 @@ pub fn download_with_config<D: NetworkDriver>(
     blk_device: Option<UnifiedBlockDevice>,
     tsc_freq: u64,
 ) -> DownloadResult {
-    serial::println("=================================");
-    serial::println("  MorpheusX Network Download     ");
-    serial::println("=================================");
-    serial::print("URL: ");
-    serial::println(config.url);
+    print_banner(config.url);
 
     let mac = driver.mac_address();
-    let eth_addr = EthernetAddress(mac);
+    serial::print("MAC: ");
+    serial::print_mac(&mac);
+    serial::println("");
 
+    let mut stack = SmoltcpStack::new(driver);
+    run(&mut stack, config, blk_device, None, tsc_freq)
+}
+
+/// Execute HTTP download, picking the disks from every probed device.
+///
+/// `config.target_disk` names the device the ISO is written to and
+/// `config.esp_disk` the one holding the ESP. When both name the same
+/// device it is used for everything, as with [`download_with_config`].
+/// Devices not picked are dropped.
+pub fn download_with_devices<D: NetworkDriver>(
+    driver: &mut D,
+    config: DownloadConfig<'static>,
+    devices: Vec<UnifiedBlockDevice>,
+    tsc_freq: u64,
+) -> DownloadResult {
+    print_banner(config.url);
+
+    let mac = driver.mac_address();
     serial::print("MAC: ");
     serial::print_mac(&mac);
     serial::println("");
 
-    if config.write_to_disk && blk_device.is_some() {
-        serial::print("Disk write: enabled (sector ");
-        serial::print_u32(config.target_start_sector as u32);
-        serial::println(")");
-    } else {
-        serial::println("Disk write: disabled");
-    }
+    let (blk_device, esp_device) = select_devices(&config, devices, TimeoutConfig::new(tsc_freq));
+    let mut stack = SmoltcpStack::new(driver);
+    run(&mut stack, config, blk_device, esp_device, tsc_freq)
+}
+
+/// Execute HTTP download to one disk with the ESP on another.
+///
+/// The ISO partition and its raw manifest copy go to `data_device`; the
+/// FAT32 manifest and the other ESP files go to `esp_device`, where
+/// `config.esp_start_lba` is. The manifest records the data disk by its
+/// identity so the bootloader can find the chunks there.
+pub fn download_with_disks<D: NetworkDriver>(
+    driver: &mut D,
+    config: DownloadConfig<'static>,
+    data_device: UnifiedBlockDevice,
+    esp_device: UnifiedBlockDevice,
+    tsc_freq: u64,
+) -> DownloadResult {
+    print_banner(config.url);
 
-    let mut adapter = SmoltcpAdapter::new(driver);
+    let mac = driver.mac_address();
+    serial::print("MAC: ");
+    serial::print_mac(&mac);
+    serial::println("");
 
-    let iface_config = IfaceConfig::new(HardwareAddress::Ethernet(eth_addr));
-    let mut iface = Interface::new(iface_config, &mut adapter, Instant::ZERO);
+    let mut stack = SmoltcpStack::new(driver);
+    run(&mut stack, config, Some(data_device), Some(esp_device), tsc_freq)
+}
+
+/// Execute HTTP download over a caller-supplied TCP/IP stack.
+///
+/// Same session as [`download_with_config`]; the stack has to be set up
+/// on an already-reset driver, as the driver would be for smoltcp.
+pub fn download_with_stack<S: NetStack>(
+    stack: &mut S,
+    config: DownloadConfig<'static>,
+    blk_device: Option<UnifiedBlockDevice>,
+    tsc_freq: u64,
+) -> DownloadResult {
+    print_banner(config.url);
+    run(stack, config, blk_device, None, tsc_freq)
+}
 
-    // Static socket storage
-    let mut socket_storage: [SocketStorage; 4] = Default::default();
-    let mut sockets = SocketSet::new(&mut socket_storage[..]);
+/// Take the target and ESP devices named by `config` out of `devices`.
+///
+/// The ESP device is `None` when it is the target itself.
+fn select_devices(
+    config: &DownloadConfig<'_>,
+    mut devices: Vec<UnifiedBlockDevice>,
+    timeouts: TimeoutConfig,
+) -> (Option<UnifiedBlockDevice>, Option<UnifiedBlockDevice>) {
+    let wants_guid = |selector: &DiskSelector| matches!(selector, DiskSelector::DiskGuid(_));
+    let read_guids = wants_guid(&config.target_disk) || wants_guid(&config.esp_disk);
+    let found: Vec<(BlockDeviceInfo, Option<[u8; 16]>)> = devices
+        .iter_mut()
+        .map(|blk| {
+            let guid = if read_guids { read_disk_guid(blk, timeouts) } else { None };
+            (blk.info(), guid)
+        })
+        .collect();
 
-    // DHCP socket
-    let dhcp_socket = Dhcpv4Socket::new();
-    let dhcp_handle = sockets.add(dhcp_socket);
+    let Some(target) = config.target_disk.select(&found) else {
+        serial::println("[DISK] WARNING: Target disk not found");
+        return (None, None);
+    };
+    let esp = match config.esp_disk.select(&found) {
+        Some(esp) => esp,
+        None => {
+            serial::println("[DISK] WARNING: ESP disk not found, using the target");
+            target
+        }
+    };
+    serial::print("[DISK] ");
+    serial::print_u32(found.len() as u32);
+    serial::println(" block device(s)");
 
-    // TCP socket
-    static mut TCP_RX_BUF: [u8; 65536] = [0u8; 65536];
-    static mut TCP_TX_BUF: [u8; 65536] = [0u8; 65536];
-    let tcp_socket = unsafe {
-        TcpSocket::new(
-            TcpSocketBuffer::new(&mut TCP_RX_BUF[..]),
-            TcpSocketBuffer::new(&mut TCP_TX_BUF[..]),
-        )
+    // Remove the higher index first so the lower one stays put
+    let (blk, esp) = if esp == target {
+        (devices.swap_remove(target), None)
+    } else if esp > target {
+        let esp = devices.swap_remove(esp);
+        (devices.swap_remove(target), Some(esp))
+    } else {
+        let blk = devices.swap_remove(target);
+        (blk, Some(devices.swap_remove(esp)))
     };
-    let tcp_handle = sockets.add(tcp_socket);
+    (Some(blk), esp)
+}
+
+/// GPT disk GUID of a device, `None` if it has no readable GPT.
+fn read_disk_guid(blk: &mut UnifiedBlockDevice, timeouts: TimeoutConfig) -> Option<[u8; 16]> {
+    let mut dma = HeapDmaBuffer::new(DISK_GUID_DMA_SIZE)?;
+    let (dma_buffer, dma_buffer_phys) = dma.split_phys();
+    let mut adapter = UnifiedBlockIo::new(blk, dma_buffer, dma_buffer_phys, timeouts).ok()?;
+    GptOps::disk_guid(&mut adapter).ok()
+}
+
+fn print_banner(url: &str) {
+    serial::println("=================================");
+    serial::println("  MorpheusX Network Download     ");
+    serial::println("=================================");
+    serial::print("URL: ");
+    serial::println(url);
+}
+
+/// Drive the state machine to completion.
+fn run(
+    stack: &mut dyn NetStack,
+    config: DownloadConfig<'static>,
+    blk_device: Option<UnifiedBlockDevice>,
+    esp_device: Option<UnifiedBlockDevice>,
+    tsc_freq: u64,
+) -> DownloadResult {
+    // https:// connections get TLS on top of whichever stack this is
+    let mut tls = TlsStack::new(stack);
+    let stack: &mut dyn NetStack = &mut tls;
+
+    // Idle waits are timed on the raw TSC, whatever clock drives timeouts.
+    let idler = Idler::detect(tsc_freq);
+
+    // All state timeouts are in ticks of the active clock. That is the TSC
+    // unless a fallback (HPET / ACPI PM) was installed for a broken TSC.
+    let clock = time::active_or_tsc(tsc_freq);
+    trace::reset(tsc_freq);
+    let tsc_freq = clock.frequency();
+    serial::print("Clock: ");
+    serial::println(clock.source().name());
+    serial::print("Idle: ");
+    serial::println(idler.method().name());
+
+    thermal::reset();
+    let mut thermal_monitor = ThermalMonitor::probe(ThermalPolicy::default(), tsc_freq);
+    if let Some(ref monitor) = thermal_monitor {
+        serial::print("Thermal: TjMax ");
+        serial::print_u32(monitor.tjmax() as u32);
+        serial::println(" C");
+    }
+
+    if let (true, Some(blk)) = (config.write_to_disk, blk_device.as_ref()) {
+        serial::print("Disk write: enabled (sector ");
+        serial::print_u64(config.target_start_sector);
+        serial::println(")");
+        print_disk_identity(&blk.info());
+        if let Some(esp) = esp_device.as_ref() {
+            serial::print("ESP ");
+            print_disk_identity(&esp.info());
+            // The manifest can only point at the data disk by identity
+            if !blk.info().identity.is_known() {
+                serial::println("WARNING: Data disk has no identity, bootloader may not find it");
+            }
+        }
+    } else {
+        serial::println("Disk write: disabled");
+    }
 
     // Context
     let mut ctx = Context::new(config, tsc_freq);
-    ctx.dhcp_handle = Some(dhcp_handle);
-    ctx.tcp_handle = Some(tcp_handle);
     ctx.blk_device = blk_device;
+    ctx.esp_device = esp_device;
+    let session_start_tsc = clock.now();
 
-    let mut current_state: Box<dyn State<D>> = Box::new(InitState::new());
+    let mut current_state: Box<dyn State> = Box::new(InitState::new());
+    let mut aborting = false;
+    let mut last_telemetry_tsc = 0u64;
+    let mut health = HealthMonitor::new(tsc_freq);
+    let mut last_progress_tsc = 0u64;
+    let mut status = StatusScreen::new(tsc_freq);
+    let mut beeper = Beeper::new(ctx.config.beeps, tsc_freq);
+    let mut budget = AdaptiveBudget::new();
+    // State left by the last transition, to tell what a failure came from
+    let mut last_phase = current_state.name();
+    abort::clear();
 
+    serial::println("ESC or Ctrl-C aborts the download");
     serial::println("---------------------------------");
     serial::print("State: ");
     serial::println(current_state.name());
+    status.phase(current_state.name());
 
     loop {
-        let tsc = read_tsc();
+        let tsc = clock.now();
         let millis = if tsc_freq > 0 {
             (tsc / (tsc_freq / 1000)) as i64
         } else {
             0
         };
-        let now = Instant::from_millis(millis);
-
-        let _ = iface.poll(now, &mut adapter, &mut sockets);
-
-        let (next_state, result) = current_state.step(
-            &mut ctx,
-            &mut iface,
-            &mut sockets,
-            &mut adapter,
-            now,
-            tsc,
-        );
+
+        stack.set_tx_budget(budget.tx_budget());
+        let poll_start = trace::start();
+        let activity = stack.poll(millis);
+        trace::record(Phase::Poll, poll_start);
+        budget.update(stack.rx_backlog(), activity);
+        beeper.tick();
+
+        if let Some(event) = thermal_monitor.as_mut().and_then(|m| m.poll(tsc)) {
+            print_thermal_event(event);
+        }
+
+        if stack.tcp_status() == TcpStatus::Established
+            && tsc.wrapping_sub(last_telemetry_tsc) >= tsc_freq * TELEMETRY_INTERVAL_SECS
+        {
+            last_telemetry_tsc = tsc;
+            if let Some(telemetry) = stack.tcp_telemetry() {
+                print_tcp_telemetry(&telemetry);
+            }
+        }
+
+        // ESC / Ctrl-C on serial or keyboard, or an external request_abort().
+        if !aborting && abort::poll() {
+            aborting = true;
+            serial::println("[ABORT] Abort requested");
+            current_state.abort(&mut ctx);
+            // AbortState resets the machine in its first step
+            let report = exit_report(
+                &ctx,
+                current_state.name(),
+                Some(FailureReason::Aborted),
+                session_start_tsc,
+                clock.now(),
+            );
+            write_exit_report(&mut ctx, &report);
+            current_state = Box::new(AbortState::new());
+            serial::print("State: ");
+            serial::println(current_state.name());
+            status.phase(current_state.name());
+        }
+
+        // Once the HTTP transfer has started, explain any stall
+        if ctx.download_start_tsc != 0 {
+            let sample = HealthSample {
+                bytes: ctx.bytes_downloaded,
+                link_up: stack.link_up(),
+                rx_frames: stack.rx_frames(),
+                tcp_status: stack.tcp_status(),
+                tcp: stack.tcp_telemetry(),
+            };
+            let event = health.update(sample, tsc);
+            match event {
+                Some(HealthEvent::Stalled(reason)) => {
+                    serial::print("[STALL] ");
+                    serial::println(reason.describe());
+                }
+                Some(HealthEvent::Recovered) => serial::println("[STALL] Data flowing again"),
+                None => {}
+            }
+            let progress = Progress {
+                bytes_downloaded: ctx.bytes_downloaded,
+                content_length: ctx.content_length,
+                stall: health.stall(),
+            };
+            status.progress(&progress, tsc, tsc.wrapping_sub(ctx.download_start_tsc));
+            if let Some(callback) = ctx.config.progress {
+                if event.is_some() || tsc.wrapping_sub(last_progress_tsc) >= tsc_freq {
+                    last_progress_tsc = tsc;
+                    callback(&progress);
+                }
+            }
+        }
+
+        // The PHY negotiates while Init and GPT prep run; LinkWait reads
+        // the history
+        if ctx.link.due(tsc) {
+            let sample = LinkSample {
+                link_up: stack.link_up(),
+                autoneg_complete: stack.autoneg_complete(),
+            };
+            ctx.link.update(sample, tsc);
+        }
+
+        let phase = current_state.name();
+        let step_start = trace::start();
+        let (mut next_state, mut result) = current_state.step(&mut ctx, stack, tsc);
+        trace::record_state(phase, step_start);
+
+        // RX ring filling up (a disk write held up the last step): keep
+        // draining it into the socket and the socket into the disk before
+        // the NIC runs out of buffers.
+        let mut rounds = 1;
+        while result == StepResult::Continue && rounds < budget.rounds() {
+            rounds += 1;
+            stack.set_tx_budget(budget.tx_budget());
+            let poll_start = trace::start();
+            stack.poll(millis);
+            trace::record(Phase::Poll, poll_start);
+            let step_start = trace::start();
+            (next_state, result) = next_state.step(&mut ctx, stack, tsc);
+            trace::record_state(phase, step_start);
+            budget.update(stack.rx_backlog(), true);
+        }
         current_state = next_state;
 
         match result {
-            StepResult::Continue => {}
+            StepResult::Continue => {
+                // No packets moved and none waiting: sleep until the
+                // stack's next timer (or one slice) instead of spinning.
+                if budget.may_idle() {
+                    idler.idle(stack.poll_delay(millis));
+                }
+            }
             StepResult::Transition => {
                 serial::print("State: ");
                 serial::println(current_state.name());
+                status.phase(current_state.name());
+                status.link(stack.link_info());
+                last_phase = phase;
+                match BeepCode::for_phase(current_state.name()) {
+                    // Done resets the machine in its first step
+                    Some(BeepCode::Complete) => {
+                        let report =
+                            exit_report(&ctx, "Done", None, session_start_tsc, clock.now());
+                        write_exit_report(&mut ctx, &report);
+                        print_backpressure(&ctx);
+                        trace::dump();
+                        print_budget_stats(&budget);
+                        beeper.play_blocking(BeepCode::Complete);
+                    }
+                    Some(code) => beeper.start(code),
+                    None => {}
+                }
             }
             StepResult::Done => {
                 serial::println("---------------------------------");
@@ -175,33 +485,226 @@ pub fn download_with_config<D: NetworkDriver>(
                     serial::print_u32((ctx.bytes_written / 1024 / 1024) as u32);
                     serial::println(" MB");
                 }
+                print_retry_stats(&ctx);
+                print_backpressure(&ctx);
+                if let Some(telemetry) = stack.tcp_telemetry() {
+                    print_tcp_telemetry(&telemetry);
+                }
                 return DownloadResult::Success {
                     bytes_downloaded: ctx.bytes_downloaded,
                     bytes_written: ctx.bytes_written,
+                    report: exit_report(&ctx, "Done", None, session_start_tsc, clock.now()),
                 };
             }
             StepResult::Failed(reason) => {
                 serial::println("---------------------------------");
                 serial::print("FAILED: ");
                 serial::println(reason);
-                return DownloadResult::Failed { reason };
+                status.error(reason);
+                let failed_in = if phase == "Failed" { last_phase } else { phase };
+                beeper.play_blocking(BeepCode::for_failure(failed_in));
+                let failure = classify_failure(failed_in, reason);
+                let report =
+                    exit_report(&ctx, failed_in, Some(failure), session_start_tsc, clock.now());
+                write_exit_report(&mut ctx, &report);
+                print_retry_stats(&ctx);
+                print_backpressure(&ctx);
+                if let Some(telemetry) = stack.tcp_telemetry() {
+                    print_tcp_telemetry(&telemetry);
+                }
+                trace::dump();
+                print_budget_stats(&budget);
+                return DownloadResult::Failed { reason, report };
             }
         }
     }
 }
 
-#[inline]
-fn read_tsc() -> u64 {
-    #[cfg(target_arch = "x86_64")]
-    unsafe {
-        let lo: u32;
-        let hi: u32;
-        core::arch::asm!("rdtsc", out("eax") lo, out("edx") hi, options(nostack, nomem));
-        ((hi as u64) << 32) | (lo as u64)
+/// What a session that failed in `phase` with `reason` ran into.
+///
+/// The reason names the disk and content errors, which can come from
+/// several states; the rest go by the state that gave up.
+fn classify_failure(phase: &str, reason: &str) -> FailureReason {
+    match reason {
+        "captive portal" | "captive portal detected" => return FailureReason::CaptivePortal,
+        "verify" => return FailureReason::Verification,
+        "gpt" | "gpt prep failed" | "disk excluded by placement policy" | "no blk" | "write"
+        | "flush" | "disk flush" | "image too large" | "image larger than partition" => {
+            return FailureReason::Disk
+        }
+        _ => {}
+    }
+    match phase {
+        "LinkWait" => FailureReason::NoLink,
+        "DHCP" => FailureReason::Dhcp,
+        "DNS" => FailureReason::Dns,
+        "Connect" => FailureReason::Connect,
+        "HTTP" | "Preflight" | "Checksums" => FailureReason::Http,
+        "GptPrep" | "Manifest" => FailureReason::Disk,
+        "Abort" => FailureReason::Aborted,
+        _ => FailureReason::Other,
+    }
+}
+
+/// Exit report of the session so far, ending in `state`.
+fn exit_report(
+    ctx: &Context<'_>,
+    state: &str,
+    failure: Option<FailureReason>,
+    session_start_tsc: u64,
+    now: u64,
+) -> ExitReport {
+    let ticks_per_ms = (ctx.tsc_freq / 1000).max(1);
+    let mut report = ExitReport::new(state, failure);
+    report.set_iso_name(ctx.config.iso_name);
+    // The hash is only final once the body is complete
+    if failure.is_none() {
+        report.verification = match ctx.config.expected_sha256 {
+            None => Verification::Unchecked,
+            Some(expected) if ctx.sha256 == Some(expected) => Verification::Verified,
+            Some(_) => Verification::Mismatch,
+        };
+    }
+    report.bytes_downloaded = ctx.bytes_downloaded;
+    report.bytes_written = ctx.bytes_written;
+    report.duration_ms = now.wrapping_sub(session_start_tsc) / ticks_per_ms;
+    if ctx.download_start_tsc != 0 {
+        report.transfer_ms = now.wrapping_sub(ctx.download_start_tsc) / ticks_per_ms;
+    }
+    report.retries = ctx.retries.into();
+    report
+}
+
+/// Log a thermal throttle state change.
+fn print_thermal_event(event: ThermalEvent) {
+    match event {
+        ThermalEvent::Throttle { temp_c } => {
+            serial::print("[THERMAL] ");
+            serial::print_u32(temp_c as u32);
+            serial::println(" C - throttling verification");
+        }
+        ThermalEvent::Resume { temp_c } => {
+            serial::print("[THERMAL] ");
+            serial::print_u32(temp_c as u32);
+            serial::println(" C - resuming");
+        }
+    }
+}
+
+/// Log how often the disk held up reading the body (only if it did).
+fn print_backpressure(ctx: &Context<'_>) {
+    if ctx.disk_backpressure == 0 {
+        return;
     }
+    serial::print("[DISK] Backpressure: body left in the socket for ");
+    serial::print_u32(ctx.disk_backpressure);
+    serial::println(" steps");
+}
 
-    #[cfg(not(target_arch = "x86_64"))]
-    {
-        0
+/// Log how often the RX ring backed up (only if it did).
+fn print_budget_stats(budget: &AdaptiveBudget) {
+    if budget.backlogged_iterations() == 0 {
+        return;
+    }
+    serial::print("[BUDGET] Drained an RX backlog for ");
+    serial::print_u64(budget.backlogged_iterations());
+    serial::println(" iterations");
+}
+
+/// Log per-phase retry counters (only if any retry happened).
+fn print_retry_stats(ctx: &Context<'_>) {
+    let r = &ctx.retries;
+    if r.total() == 0 {
+        return;
+    }
+    serial::print("Retries: DHCP ");
+    serial::print_u32(r.dhcp);
+    serial::print(", DNS ");
+    serial::print_u32(r.dns);
+    serial::print(", TCP ");
+    serial::print_u32(r.connect);
+    serial::print(", HTTP ");
+    serial::print_u32(r.http);
+    serial::println("");
+}
+
+/// Log TCP telemetry: where throughput is going.
+///
+/// A slow mirror shows high RTT with clean counters; a lossy path shows
+/// server resends and out-of-order arrivals; a struggling NIC shows
+/// driver drops.
+fn print_tcp_telemetry(t: &TcpTelemetry) {
+    serial::print("[TCP] RTT ");
+    match t.srtt_ms {
+        Some(ms) => {
+            serial::print_u32(ms);
+            serial::print(" ms");
+        }
+        None => serial::print("-"),
+    }
+    serial::print(", win ");
+    serial::print_u32(t.rx_window / 1024);
+    serial::print("K/");
+    serial::print_u32(t.peer_window / 1024);
+    serial::print("K, seg ");
+    serial::print_u32(t.segments_in);
+    serial::print(" in / ");
+    serial::print_u32(t.segments_out);
+    serial::print(" out, retx ");
+    serial::print_u32(t.retransmits);
+    serial::print(" ours / ");
+    serial::print_u32(t.peer_retransmits);
+    serial::print(" server, ooo ");
+    serial::print_u32(t.out_of_order);
+    serial::print(", drops tx ");
+    serial::print_u32(t.tx_drops);
+    serial::print(" rx ");
+    serial::print_u32(t.rx_errors);
+    serial::println("");
+}
+
+/// Log which disk is about to be written.
+fn print_disk_identity(info: &BlockDeviceInfo) {
+    let identity = &info.identity;
+    let model = match identity.model() {
+        "" => "unknown model",
+        model => model,
+    };
+    serial::print("Disk: ");
+    serial::print(model);
+    serial::print(" (");
+    serial::print(&format!("{}", info.capacity()));
+    if info.media != MediaKind::Unknown {
+        serial::print(", ");
+        serial::print(info.media.name());
+    }
+    serial::print(")");
+    if !identity.serial().is_empty() {
+        serial::print(", SN ");
+        serial::print(identity.serial());
+    }
+    serial::println("");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_failure() {
+        let cases = [
+            ("DHCP", "DHCP timeout", FailureReason::Dhcp),
+            ("DNS", "no DNS", FailureReason::Dns),
+            ("Connect", "TCP timeout", FailureReason::Connect),
+            ("HTTP", "status", FailureReason::Http),
+            ("HTTP", "captive portal", FailureReason::CaptivePortal),
+            ("HTTP", "verify", FailureReason::Verification),
+            ("HTTP", "flush", FailureReason::Disk),
+            ("GptPrep", "gpt prep failed", FailureReason::Disk),
+            ("Init", "invalid URL", FailureReason::Other),
+        ];
+        for (phase, reason, expected) in cases {
+            assert_eq!(classify_failure(phase, reason), expected, "{phase}: {reason}");
+        }
     }
 }
@@ -10,6 +10,15 @@
 //! Target: <1ms per iteration
 //! Maximum: 5ms per iteration
 //!
+//! # Adaptive budget
+//! One poll and one step per iteration is right while the RX ring keeps
+//! up. When a disk write stalls the step, frames pile up in the ring and
+//! the NIC starts dropping once it is full. [`AdaptiveBudget`] watches the
+//! ring's fill level and, past a high-water mark, runs several poll/step
+//! rounds per iteration (draining RX into the socket and the socket into
+//! the disk writer) until it is back under a low-water mark. The loop only
+//! idles when the ring is empty and nothing moved.
+//!
 //! # Reference
 //! NETWORK_IMPL_GUIDE.md §6.2, §6.3
 
@@ -18,6 +27,103 @@ use crate::driver::NetworkDriver;
 /// TX budget per iteration (max packets to send in Phase 3).
 pub const TX_BUDGET: usize = 16;
 
+/// RX ring fill level (percent) that starts draining.
+pub const RX_HIGH_WATER_PCT: usize = 50;
+
+/// RX ring fill level (percent) that ends draining.
+pub const RX_LOW_WATER_PCT: usize = 12;
+
+/// Poll/step rounds per iteration while draining.
+pub const MAX_DRAIN_ROUNDS: u32 = 8;
+
+/// How busy the receive path is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Load {
+    /// Ring empty and no packets moved: the loop may idle
+    Idle,
+    /// Keeping up: one round per iteration
+    Normal,
+    /// Ring filling up: drain with extra rounds
+    Backlogged,
+}
+
+/// Per-iteration budget driven by RX ring occupancy.
+#[derive(Debug, Clone, Copy)]
+pub struct AdaptiveBudget {
+    load: Load,
+    /// Iterations spent backlogged, for the end-of-session report
+    backlogged_iterations: u64,
+}
+
+impl AdaptiveBudget {
+    pub const fn new() -> Self {
+        Self {
+            load: Load::Normal,
+            backlogged_iterations: 0,
+        }
+    }
+
+    /// Classify this iteration from the ring backlog (frames waiting, ring
+    /// size) and whether the poll moved any packets. Drivers that can't
+    /// report a backlog never leave one round per iteration.
+    pub fn update(&mut self, backlog: Option<(usize, usize)>, activity: bool) -> Load {
+        let (pending, pct) = match backlog {
+            Some((pending, size)) if size > 0 => (pending, pending * 100 / size),
+            _ => (0, 0),
+        };
+        self.load = if pct >= RX_HIGH_WATER_PCT
+            || (self.load == Load::Backlogged && pct > RX_LOW_WATER_PCT)
+        {
+            Load::Backlogged
+        } else if pending == 0 && !activity {
+            Load::Idle
+        } else {
+            Load::Normal
+        };
+        if self.load == Load::Backlogged {
+            self.backlogged_iterations += 1;
+        }
+        self.load
+    }
+
+    pub fn load(&self) -> Load {
+        self.load
+    }
+
+    /// Poll/step rounds to run this iteration.
+    pub fn rounds(&self) -> u32 {
+        match self.load {
+            Load::Backlogged => MAX_DRAIN_ROUNDS,
+            _ => 1,
+        }
+    }
+
+    /// Packets smoltcp may send per poll. Draining mostly sends ACKs for
+    /// what it drains, so it gets more.
+    pub fn tx_budget(&self) -> usize {
+        match self.load {
+            Load::Backlogged => TX_BUDGET * 2,
+            Load::Normal => TX_BUDGET,
+            Load::Idle => TX_BUDGET / 2,
+        }
+    }
+
+    /// Whether the loop may idle until the next timer.
+    pub fn may_idle(&self) -> bool {
+        self.load == Load::Idle
+    }
+
+    pub fn backlogged_iterations(&self) -> u64 {
+        self.backlogged_iterations
+    }
+}
+
+impl Default for AdaptiveBudget {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Phase 1: Refill RX queue.
 ///
 /// Ensures device has buffers to receive into.
@@ -37,14 +143,51 @@ pub fn phase5_tx_completions<D: NetworkDriver>(device: &mut D) {
 /// Check if timing warning should be emitted.
 ///
 /// Returns true if iteration exceeded warning threshold.
-#[cfg(target_arch = "x86_64")]
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
 pub fn check_timing_warning(start_tsc: u64, warning_threshold_ticks: u64) -> bool {
     let now = crate::asm::core::tsc::read_tsc();
     let elapsed = now.wrapping_sub(start_tsc);
     elapsed > warning_threshold_ticks
 }
 
-#[cfg(not(target_arch = "x86_64"))]
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
 pub fn check_timing_warning(_start_tsc: u64, _warning_threshold_ticks: u64) -> bool {
     false
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_hysteresis() {
+        let mut budget = AdaptiveBudget::new();
+        assert_eq!(budget.update(Some((10, 256)), true), Load::Normal);
+        assert_eq!(budget.rounds(), 1);
+
+        // Past high water: drain until back under low water
+        assert_eq!(budget.update(Some((128, 256)), true), Load::Backlogged);
+        assert_eq!(budget.rounds(), MAX_DRAIN_ROUNDS);
+        assert_eq!(budget.tx_budget(), TX_BUDGET * 2);
+        assert_eq!(budget.update(Some((64, 256)), true), Load::Backlogged);
+        assert_eq!(budget.update(Some((16, 256)), true), Load::Normal);
+        assert_eq!(budget.backlogged_iterations(), 2);
+
+        // The same 25% doesn't start draining on its own
+        assert_eq!(budget.update(Some((64, 256)), true), Load::Normal);
+    }
+
+    #[test]
+    fn test_budget_idle() {
+        let mut budget = AdaptiveBudget::new();
+        assert_eq!(budget.update(Some((0, 256)), false), Load::Idle);
+        assert!(budget.may_idle());
+        // Packets moved, or some still waiting: keep going
+        assert_eq!(budget.update(Some((0, 256)), true), Load::Normal);
+        assert_eq!(budget.update(Some((1, 256)), false), Load::Normal);
+        // No backlog reporting: idle on activity alone, never drain
+        assert_eq!(budget.update(None, false), Load::Idle);
+        assert_eq!(budget.update(None, true), Load::Normal);
+        assert!(!budget.may_idle());
+    }
+}
@@ -0,0 +1,212 @@
+//! What happens after a successful download.
+//!
+//! [`DoneState`](super::states::DoneState) runs the actions in
+//! [`DownloadConfig::post_actions`](super::context::DownloadConfig) in
+//! order, then ends the session the way the list says: the last
+//! [`PostAction::PowerOff`] or [`PostAction::Reboot`] in it wins, and a
+//! list with neither reboots. Failed actions are logged and skipped; the
+//! ISO is already stored, so they never turn the download into a failure.
+//!
+//! Booting the ISO can't happen from here, with boot services gone. It is
+//! a one-shot [`BootRequest`](morpheus_core::iso::BootRequest) the
+//! bootloader acts on after the reboot.
+
+use core::net::Ipv4Addr;
+
+/// Most actions in one list.
+pub const MAX_POST_ACTIONS: usize = 6;
+
+/// Marker file written by [`PostAction::SuccessMarker`].
+pub const SUCCESS_MARKER_PATH: &str = "/.iso/LASTDL.OK";
+
+/// One step after the download.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PostAction {
+    /// Write the ISO name and size to [`SUCCESS_MARKER_PATH`]
+    SuccessMarker,
+    /// Make the ISO the bootloader's default entry
+    BootEntry,
+    /// Boot the ISO once at the next start
+    BootIso,
+    /// Send a status datagram over UDP
+    Report {
+        server: Ipv4Addr,
+        port: u16,
+    },
+    PowerOff,
+    Reboot,
+}
+
+/// How the session ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Finish {
+    Reboot,
+    PowerOff,
+}
+
+/// Ordered list of [`PostAction`]s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PostActions {
+    actions: [PostAction; MAX_POST_ACTIONS],
+    len: usize,
+}
+
+impl PostActions {
+    /// Reboot and nothing else (default).
+    pub const REBOOT: Self = Self::new().with(PostAction::Reboot);
+
+    /// Choices offered in the download confirm dialog.
+    pub const PRESETS: [(&'static str, Self); 4] = [
+        ("reboot", Self::REBOOT),
+        (
+            "boot the ISO",
+            Self::new()
+                .with(PostAction::SuccessMarker)
+                .with(PostAction::BootIso)
+                .with(PostAction::Reboot),
+        ),
+        (
+            "make default, reboot",
+            Self::new()
+                .with(PostAction::SuccessMarker)
+                .with(PostAction::BootEntry)
+                .with(PostAction::Reboot),
+        ),
+        (
+            "power off",
+            Self::new()
+                .with(PostAction::SuccessMarker)
+                .with(PostAction::PowerOff),
+        ),
+    ];
+
+    /// Empty list.
+    pub const fn new() -> Self {
+        Self {
+            actions: [PostAction::Reboot; MAX_POST_ACTIONS],
+            len: 0,
+        }
+    }
+
+    /// The list with `action` appended; unchanged if it is full.
+    pub const fn with(mut self, action: PostAction) -> Self {
+        if self.len < MAX_POST_ACTIONS {
+            self.actions[self.len] = action;
+            self.len += 1;
+        }
+        self
+    }
+
+    pub fn as_slice(&self) -> &[PostAction] {
+        &self.actions[..self.len]
+    }
+
+    /// How the session ends.
+    pub fn finish(&self) -> Finish {
+        self.as_slice()
+            .iter()
+            .rev()
+            .find_map(|action| match action {
+                PostAction::PowerOff => Some(Finish::PowerOff),
+                PostAction::Reboot => Some(Finish::Reboot),
+                _ => None,
+            })
+            .unwrap_or(Finish::Reboot)
+    }
+
+    /// Boot request to leave for the bootloader: `Some(true)` to boot the
+    /// ISO once, `Some(false)` to only make it the default.
+    pub fn boot_request(&self) -> Option<bool> {
+        if self.as_slice().contains(&PostAction::BootIso) {
+            Some(true)
+        } else if self.as_slice().contains(&PostAction::BootEntry) {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    /// Where to send the status report, if anywhere.
+    pub fn report_to(&self) -> Option<(Ipv4Addr, u16)> {
+        self.as_slice().iter().find_map(|action| match *action {
+            PostAction::Report { server, port } => Some((server, port)),
+            _ => None,
+        })
+    }
+
+    /// Preset name, or "custom" for a list built some other way.
+    pub fn name(&self) -> &'static str {
+        Self::PRESETS
+            .iter()
+            .find(|(_, preset)| preset == self)
+            .map_or("custom", |(name, _)| name)
+    }
+
+    /// Next preset, for cycling through them in the confirm dialog.
+    pub fn next_preset(&self) -> Self {
+        let next = Self::PRESETS
+            .iter()
+            .position(|(_, preset)| preset == self)
+            .map_or(0, |i| (i + 1) % Self::PRESETS.len());
+        Self::PRESETS[next].1
+    }
+}
+
+impl Default for PostActions {
+    fn default() -> Self {
+        Self::REBOOT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_last_terminal_action_wins() {
+        assert_eq!(PostActions::new().finish(), Finish::Reboot);
+        assert_eq!(PostActions::default().finish(), Finish::Reboot);
+        let list = PostActions::new()
+            .with(PostAction::Reboot)
+            .with(PostAction::SuccessMarker)
+            .with(PostAction::PowerOff);
+        assert_eq!(list.finish(), Finish::PowerOff);
+        assert_eq!(list.boot_request(), None);
+        assert_eq!(list.report_to(), None);
+    }
+
+    #[test]
+    fn test_boot_request_and_report() {
+        let server = Ipv4Addr::new(10, 0, 2, 2);
+        let list = PostActions::new()
+            .with(PostAction::BootEntry)
+            .with(PostAction::Report { server, port: 5140 });
+        assert_eq!(list.boot_request(), Some(false));
+        assert_eq!(list.report_to(), Some((server, 5140)));
+        assert_eq!(list.with(PostAction::BootIso).boot_request(), Some(true));
+    }
+
+    #[test]
+    fn test_full_list_ignores_more() {
+        let mut list = PostActions::new();
+        for _ in 0..MAX_POST_ACTIONS {
+            list = list.with(PostAction::SuccessMarker);
+        }
+        assert_eq!(list.with(PostAction::PowerOff).finish(), Finish::Reboot);
+    }
+
+    #[test]
+    fn test_presets_cycle() {
+        let mut list = PostActions::default();
+        assert_eq!(list.name(), "reboot");
+        for (name, _) in PostActions::PRESETS.iter().skip(1) {
+            list = list.next_preset();
+            assert_eq!(list.name(), *name);
+        }
+        assert_eq!(list.next_preset(), PostActions::REBOOT);
+
+        let custom = PostActions::new().with(PostAction::PowerOff);
+        assert_eq!(custom.name(), "custom");
+        assert_eq!(custom.next_preset(), PostActions::REBOOT);
+    }
+}
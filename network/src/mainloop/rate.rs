@@ -0,0 +1,106 @@
+//! Download rate cap.
+//!
+//! An unattended install on a shared office link shouldn't take all of
+//! it. With [`DownloadConfig::rate_limit`](super::DownloadConfig::rate_limit)
+//! set, the HTTP body is read through a token bucket: bytes accrue at the
+//! configured rate up to a small burst, and what the bucket can't cover
+//! stays in the socket. The shrinking receive window then slows the
+//! sender down, the same way a slow disk does.
+
+/// Longest the bucket fills for while nothing is read, in milliseconds.
+const BURST_MS: u64 = 250;
+
+/// Smallest burst, so one segment always fits.
+const MIN_BURST: u64 = 4096;
+
+/// Token bucket over TSC ticks.
+#[derive(Debug, Clone, Copy)]
+pub struct TokenBucket {
+    /// Bytes per second
+    rate: u64,
+    /// Most tokens held at once
+    burst: u64,
+    tokens: u64,
+    tsc_freq: u64,
+    /// TSC the tokens were last topped up at (0 = not yet)
+    last_tsc: u64,
+}
+
+impl TokenBucket {
+    /// Bucket for `rate` bytes per second on a `tsc_freq` Hz TSC.
+    /// Starts full.
+    pub fn new(rate: u64, tsc_freq: u64) -> Self {
+        let burst = (rate * BURST_MS / 1000).max(MIN_BURST);
+        Self {
+            rate,
+            burst,
+            tokens: burst,
+            tsc_freq: tsc_freq.max(1),
+            last_tsc: 0,
+        }
+    }
+
+    /// Bytes per second.
+    pub fn rate(&self) -> u64 {
+        self.rate
+    }
+
+    /// Bytes that may be read at `tsc`.
+    pub fn available(&mut self, tsc: u64) -> usize {
+        if self.last_tsc == 0 {
+            self.last_tsc = tsc;
+        }
+        let elapsed = tsc.saturating_sub(self.last_tsc);
+        let earned = (elapsed as u128 * self.rate as u128 / self.tsc_freq as u128) as u64;
+        if earned > 0 {
+            self.tokens = self.tokens.saturating_add(earned).min(self.burst);
+            // Keep the remainder of a partial byte for next time
+            self.last_tsc += (earned as u128 * self.tsc_freq as u128 / self.rate as u128) as u64;
+            if self.tokens == self.burst {
+                self.last_tsc = tsc;
+            }
+        }
+        self.tokens.min(usize::MAX as u64) as usize
+    }
+
+    /// Take `bytes` read from the bucket.
+    pub fn consume(&mut self, bytes: usize) {
+        self.tokens = self.tokens.saturating_sub(bytes as u64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FREQ: u64 = 1_000_000_000;
+
+    #[test]
+    fn test_rate_over_time() {
+        let rate = 1024 * 1024;
+        let mut bucket = TokenBucket::new(rate, FREQ);
+        let burst = bucket.available(1);
+        assert_eq!(burst as u64, rate / 4);
+
+        // Drain, then read for ten seconds in 1 ms steps
+        bucket.consume(burst);
+        let mut read = 0u64;
+        for ms in 1..=10_000u64 {
+            let n = bucket.available(1 + ms * FREQ / 1000);
+            bucket.consume(n);
+            read += n as u64;
+        }
+        assert!(read.abs_diff(10 * rate) < 16, "read {}", read);
+    }
+
+    #[test]
+    fn test_burst_cap() {
+        let mut bucket = TokenBucket::new(100, FREQ);
+        assert_eq!(bucket.available(1), MIN_BURST as usize);
+        bucket.consume(MIN_BURST as usize);
+        // Idle for an hour: still no more than the burst
+        assert_eq!(bucket.available(3600 * FREQ), MIN_BURST as usize);
+        bucket.consume(10_000);
+        assert_eq!(bucket.available(3600 * FREQ), 0);
+    }
+}
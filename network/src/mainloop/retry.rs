@@ -0,0 +1,276 @@
+//! Retry policy engine for network states.
+//!
+//! DHCP, DNS, TCP connect and the HTTP request phase can all fail for
+//! transient reasons (lossy link, slow server, RST during SYN). Instead of
+//! giving up on the first timeout — or hammering the network in a tight
+//! loop — each state consults a shared `RetryPolicy` and waits an
+//! exponentially growing, jittered delay before trying again.
+//!
+//! Attempt counters live in `Context::retries` so they survive state
+//! transitions (e.g. HTTP falling back to Connect) and can be reported
+//! once the download finishes.
+
+/// Network phase a retry applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryPhase {
+    Dhcp,
+    Dns,
+    Connect,
+    Http,
+}
+
+impl RetryPhase {
+    /// Short tag used in serial logs.
+    pub fn name(self) -> &'static str {
+        match self {
+            RetryPhase::Dhcp => "DHCP",
+            RetryPhase::Dns => "DNS",
+            RetryPhase::Connect => "TCP",
+            RetryPhase::Http => "HTTP",
+        }
+    }
+}
+
+/// Exponential backoff policy with jitter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total attempts allowed, including the first one (0 or 1 = no retry).
+    pub max_attempts: u32,
+    /// Delay before the first retry (ms).
+    pub base_delay_ms: u64,
+    /// Upper bound for any single delay (ms).
+    pub max_delay_ms: u64,
+    /// Jitter as a percentage of the computed delay (0..=100).
+    pub jitter_percent: u32,
+}
+
+impl RetryPolicy {
+    /// Policy that never retries.
+    pub const NONE: Self = Self::new(1, 0, 0);
+
+    /// Create a policy with the default 25% jitter.
+    pub const fn new(max_attempts: u32, base_delay_ms: u64, max_delay_ms: u64) -> Self {
+        Self {
+            max_attempts,
+            base_delay_ms,
+            max_delay_ms,
+            jitter_percent: 25,
+        }
+    }
+
+    /// Override the jitter percentage (clamped to 100).
+    pub const fn with_jitter(mut self, percent: u32) -> Self {
+        self.jitter_percent = if percent > 100 { 100 } else { percent };
+        self
+    }
+
+    /// Whether another attempt is allowed after `retries` retries.
+    pub fn allows(&self, retries: u32) -> bool {
+        retries.saturating_add(1) < self.max_attempts
+    }
+
+    /// Delay before retry number `retry` (1-based), in milliseconds.
+    ///
+    /// `entropy` seeds the jitter; callers pass the current TSC.
+    pub fn delay_ms(&self, retry: u32, entropy: u64) -> u64 {
+        let shift = retry.saturating_sub(1).min(32);
+        let base = self.base_delay_ms.saturating_mul(1u64 << shift);
+        let delay = base.min(self.max_delay_ms);
+
+        let span = delay * self.jitter_percent as u64 / 100;
+        if span == 0 {
+            return delay;
+        }
+
+        // Spread uniformly over [delay - span, delay + span], capped.
+        let offset = mix(entropy) % (2 * span + 1);
+        (delay - span + offset).min(self.max_delay_ms)
+    }
+}
+
+/// xorshift64* finaliser — cheap, good enough to decorrelate TSC bits.
+fn mix(mut x: u64) -> u64 {
+    x ^= x >> 12;
+    x ^= x << 25;
+    x ^= x >> 27;
+    x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+}
+
+/// Per-phase retry policies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicies {
+    pub dhcp: RetryPolicy,
+    pub dns: RetryPolicy,
+    pub connect: RetryPolicy,
+    pub http: RetryPolicy,
+}
+
+impl RetryPolicies {
+    /// Disable all retries (legacy fail-fast behaviour).
+    pub const fn none() -> Self {
+        Self {
+            dhcp: RetryPolicy::NONE,
+            dns: RetryPolicy::NONE,
+            connect: RetryPolicy::NONE,
+            http: RetryPolicy::NONE,
+        }
+    }
+
+    /// Policy for a given phase.
+    pub fn get(&self, phase: RetryPhase) -> &RetryPolicy {
+        match phase {
+            RetryPhase::Dhcp => &self.dhcp,
+            RetryPhase::Dns => &self.dns,
+            RetryPhase::Connect => &self.connect,
+            RetryPhase::Http => &self.http,
+        }
+    }
+}
+
+impl Default for RetryPolicies {
+    fn default() -> Self {
+        Self {
+            // DHCP servers can be slow to answer right after link-up.
+            dhcp: RetryPolicy::new(3, 1_000, 8_000),
+            dns: RetryPolicy::new(4, 500, 4_000),
+            connect: RetryPolicy::new(4, 1_000, 8_000),
+            // Only covers failures before the first body byte arrives.
+            http: RetryPolicy::new(3, 2_000, 10_000),
+        }
+    }
+}
+
+/// Retry counters, one per phase.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetryStats {
+    pub dhcp: u32,
+    pub dns: u32,
+    pub connect: u32,
+    pub http: u32,
+}
+
+impl RetryStats {
+    /// Retries performed so far for `phase`.
+    pub fn get(&self, phase: RetryPhase) -> u32 {
+        match phase {
+            RetryPhase::Dhcp => self.dhcp,
+            RetryPhase::Dns => self.dns,
+            RetryPhase::Connect => self.connect,
+            RetryPhase::Http => self.http,
+        }
+    }
+
+    fn bump(&mut self, phase: RetryPhase) -> u32 {
+        let slot = match phase {
+            RetryPhase::Dhcp => &mut self.dhcp,
+            RetryPhase::Dns => &mut self.dns,
+            RetryPhase::Connect => &mut self.connect,
+            RetryPhase::Http => &mut self.http,
+        };
+        *slot += 1;
+        *slot
+    }
+
+    /// Sum of all retries.
+    pub fn total(&self) -> u32 {
+        self.dhcp + self.dns + self.connect + self.http
+    }
+}
+
+impl From<RetryStats> for morpheus_core::iso::RetryCounts {
+    fn from(stats: RetryStats) -> Self {
+        let clamp = |n: u32| n.min(u16::MAX as u32) as u16;
+        Self {
+            dhcp: clamp(stats.dhcp),
+            dns: clamp(stats.dns),
+            connect: clamp(stats.connect),
+            http: clamp(stats.http),
+        }
+    }
+}
+
+/// Book-keeping for a pending retry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScheduledRetry {
+    /// Retry number (1-based).
+    pub retry: u32,
+    /// Attempts allowed by the policy.
+    pub max_attempts: u32,
+    /// Chosen delay (ms).
+    pub delay_ms: u64,
+    /// TSC value at which the retry may proceed.
+    pub resume_tsc: u64,
+}
+
+/// Consult `policies` for `phase`, bump `stats` and compute the resume time.
+///
+/// Returns `None` once the policy is exhausted.
+pub fn schedule(
+    policies: &RetryPolicies,
+    stats: &mut RetryStats,
+    phase: RetryPhase,
+    tsc: u64,
+    tsc_freq: u64,
+) -> Option<ScheduledRetry> {
+    let policy = policies.get(phase);
+    if !policy.allows(stats.get(phase)) {
+        return None;
+    }
+    let retry = stats.bump(phase);
+    let delay_ms = policy.delay_ms(retry, tsc);
+    let delay_ticks = delay_ms.saturating_mul(tsc_freq / 1_000);
+    Some(ScheduledRetry {
+        retry,
+        max_attempts: policy.max_attempts,
+        delay_ms,
+        resume_tsc: tsc.saturating_add(delay_ticks),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allows_counts_first_attempt() {
+        let policy = RetryPolicy::new(3, 100, 1_000);
+        assert!(policy.allows(0));
+        assert!(policy.allows(1));
+        assert!(!policy.allows(2));
+        assert!(!RetryPolicy::NONE.allows(0));
+    }
+
+    #[test]
+    fn test_delay_doubles_and_caps() {
+        let policy = RetryPolicy::new(10, 100, 1_000).with_jitter(0);
+        assert_eq!(policy.delay_ms(1, 0), 100);
+        assert_eq!(policy.delay_ms(2, 0), 200);
+        assert_eq!(policy.delay_ms(3, 0), 400);
+        assert_eq!(policy.delay_ms(5, 0), 1_000);
+        assert_eq!(policy.delay_ms(64, 0), 1_000);
+    }
+
+    #[test]
+    fn test_jitter_stays_in_bounds() {
+        let policy = RetryPolicy::new(10, 1_000, 60_000).with_jitter(25);
+        for seed in 0..256u64 {
+            let d = policy.delay_ms(2, seed.wrapping_mul(0x9E37_79B9));
+            assert!((1_500..=2_500).contains(&d), "delay {} out of range", d);
+        }
+    }
+
+    #[test]
+    fn test_schedule_exhausts() {
+        let policies = RetryPolicies::default();
+        let mut stats = RetryStats::default();
+        let max = policies.dns.max_attempts;
+        for n in 1..max {
+            let r = schedule(&policies, &mut stats, RetryPhase::Dns, 1_000, 1_000_000).unwrap();
+            assert_eq!(r.retry, n);
+            assert!(r.resume_tsc > 1_000);
+        }
+        assert!(schedule(&policies, &mut stats, RetryPhase::Dns, 0, 1_000_000).is_none());
+        assert_eq!(stats.dns, max - 1);
+        assert_eq!(stats.total(), max - 1);
+    }
+}
@@ -3,8 +3,11 @@
 //! # Reference
 //! NETWORK_IMPL_GUIDE.md §6.2
 
+use super::abort;
 use super::phases::{phase1_rx_refill, phase5_tx_completions, TX_BUDGET};
+use super::trace::{self, Phase};
 use crate::driver::NetworkDriver;
+use crate::power::Idler;
 
 /// Main loop configuration.
 pub struct MainLoopConfig {
@@ -12,6 +15,8 @@ pub struct MainLoopConfig {
     pub tsc_freq: u64,
     /// Warning threshold for iteration timing (ticks).
     pub timing_warning_ticks: u64,
+    /// CPU idling between iterations with no pending work.
+    pub idler: Idler,
 }
 
 impl MainLoopConfig {
@@ -21,9 +26,16 @@ impl MainLoopConfig {
             tsc_freq,
             // 5ms warning threshold
             timing_warning_ticks: tsc_freq / 200,
+            idler: Idler::detect(tsc_freq),
         }
     }
 
+    /// Disable idling (spin at 100% like the legacy loop).
+    pub fn without_idle(mut self) -> Self {
+        self.idler = Idler::disabled();
+        self
+    }
+
     /// Convert TSC ticks to milliseconds.
     pub fn ticks_to_ms(&self, ticks: u64) -> u64 {
         ticks * 1000 / self.tsc_freq
@@ -41,6 +53,8 @@ pub enum IterationResult {
     Failed,
     /// Application timed out.
     Timeout,
+    /// ESC / Ctrl-C or `request_abort()`; the caller takes its abort path.
+    Aborted,
 }
 
 /// Run a single main loop iteration.
@@ -51,16 +65,20 @@ pub enum IterationResult {
 /// # Arguments
 /// - `device`: Network device
 /// - `config`: Loop configuration
+/// - `next_timeout_us`: Time until the caller's next timeout, if any
 ///
 /// # Returns
 /// Whether to continue looping.
-#[cfg(target_arch = "x86_64")]
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
 pub fn run_iteration<D: NetworkDriver>(
     device: &mut D,
-    _config: &MainLoopConfig,
+    config: &MainLoopConfig,
+    next_timeout_us: Option<u64>,
 ) -> IterationResult {
     // Phase 1: Refill RX queue
+    let start = trace::start();
     phase1_rx_refill(device);
+    trace::record(Phase::RxRefill, start);
 
     // Phase 2: Would call smoltcp poll here
     // (requires smoltcp integration - handled by caller)
@@ -72,26 +90,39 @@ pub fn run_iteration<D: NetworkDriver>(
     // (handled by caller)
 
     // Phase 5: Collect TX completions
+    let start = trace::start();
     phase5_tx_completions(device);
+    trace::record(Phase::TxCompletion, start);
+
+    if abort::poll() {
+        return IterationResult::Aborted;
+    }
+
+    // Nothing to receive: idle instead of spinning, but never past the
+    // caller's next timeout.
+    if !device.can_receive() {
+        config.idler.idle(next_timeout_us);
+    }
 
     IterationResult::Continue
 }
 
-#[cfg(not(target_arch = "x86_64"))]
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
 pub fn run_iteration<D: NetworkDriver>(
     _device: &mut D,
     _config: &MainLoopConfig,
+    _next_timeout_us: Option<u64>,
 ) -> IterationResult {
     IterationResult::Continue
 }
 
 /// Get current TSC value.
-#[cfg(target_arch = "x86_64")]
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
 pub fn get_tsc() -> u64 {
     crate::asm::core::tsc::read_tsc()
 }
 
-#[cfg(not(target_arch = "x86_64"))]
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
 pub fn get_tsc() -> u64 {
     0
 }
@@ -1,11 +1,21 @@
 //! Serial output primitives for post-EBS bare-metal execution.
 //!
-//! Minimal, no-allocation serial output to COM1 (0x3F8).
+//! Minimal, no-allocation serial output to COM1 (0x3F8) on x86_64, or to
+//! the PL011 UART named by the ACPI SPCR table on aarch64.
 //! Also mirrors to framebuffer when display feature is enabled.
 
 /// Serial port base address (COM1).
 const SERIAL_PORT: u16 = 0x3F8;
 
+/// PL011 data register.
+const PL011_DR: u64 = 0x00;
+/// PL011 flag register.
+const PL011_FR: u64 = 0x18;
+/// PL011 FR: transmit FIFO full.
+const PL011_FR_TXFF: u32 = 1 << 5;
+/// PL011 FR: receive FIFO empty.
+const PL011_FR_RXFE: u32 = 1 << 4;
+
 /// Write a single byte to COM1 serial port.
 #[cfg(target_arch = "x86_64")]
 #[inline]
@@ -39,10 +49,112 @@ pub fn write_byte(byte: u8) {
     }
 }
 
-#[cfg(not(target_arch = "x86_64"))]
+#[cfg(target_arch = "aarch64")]
+#[inline]
+pub fn write_byte(byte: u8) {
+    use crate::asm::core::mmio::{read32, write32};
+
+    let Some(base) = crate::arch::uart_base() else {
+        return;
+    };
+    unsafe {
+        let mut retries = 0u32;
+        while read32(base + PL011_FR) & PL011_FR_TXFF != 0 {
+            retries += 1;
+            if retries > 10_000 {
+                return; // UART not draining
+            }
+            core::hint::spin_loop();
+        }
+        write32(base + PL011_DR, byte as u32);
+    }
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
 #[inline]
 pub fn write_byte(_byte: u8) {}
 
+/// Read a byte from COM1 if one is waiting (non-blocking).
+#[cfg(target_arch = "x86_64")]
+pub fn read_byte() -> Option<u8> {
+    unsafe {
+        let status: u8;
+        core::arch::asm!(
+            "in al, dx",
+            in("dx") SERIAL_PORT + 5,
+            out("al") status,
+            options(nomem, nostack, preserves_flags)
+        );
+        // LSR bit 0 = data ready; 0xFF means no UART at all.
+        if status == 0xFF || status & 0x01 == 0 {
+            return None;
+        }
+        let byte: u8;
+        core::arch::asm!(
+            "in al, dx",
+            in("dx") SERIAL_PORT,
+            out("al") byte,
+            options(nomem, nostack, preserves_flags)
+        );
+        Some(byte)
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+pub fn read_byte() -> Option<u8> {
+    use crate::asm::core::mmio::read32;
+
+    let base = crate::arch::uart_base()?;
+    unsafe {
+        if read32(base + PL011_FR) & PL011_FR_RXFE != 0 {
+            return None;
+        }
+        Some(read32(base + PL011_DR) as u8)
+    }
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+pub fn read_byte() -> Option<u8> {
+    None
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// SPCR (console UART location)
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// SPCR interface types with a PL011-compatible register layout.
+const SPCR_PL011: u8 = 0x03;
+const SPCR_SBSA_GENERIC_32: u8 = 0x0E;
+const SPCR_SBSA_GENERIC: u8 = 0x0D;
+
+/// MMIO base of the console UART from a raw SPCR, if it is a PL011.
+pub fn uart_base_from_spcr(spcr: &[u8]) -> Option<u64> {
+    // Interface type at 36, base address GAS at 40 (address at 44)
+    if spcr.len() < 52 || &spcr[0..4] != b"SPCR" {
+        return None;
+    }
+    if !matches!(spcr[36], SPCR_PL011 | SPCR_SBSA_GENERIC_32 | SPCR_SBSA_GENERIC) {
+        return None;
+    }
+    if spcr[40] != 0 {
+        return None; // not system memory
+    }
+    match u64::from_le_bytes(spcr[44..52].try_into().ok()?) {
+        0 => None,
+        base => Some(base),
+    }
+}
+
+/// Locate the SPCR from the RSDP and return a PL011 console base.
+///
+/// # Safety
+/// `rsdp` must be 0 or point to a valid RSDP, with the ACPI tables
+/// identity mapped.
+pub unsafe fn uart_base_from_rsdp(rsdp: u64) -> Option<u64> {
+    let spcr = crate::acpi::find_table(rsdp, b"SPCR")?;
+    uart_base_from_spcr(crate::acpi::table_bytes(spcr))
+}
+
 /// Write a string to serial port.
 #[inline]
 pub fn print(s: &str) {
@@ -122,6 +234,28 @@ pub fn print_u32(value: u32) {
     }
 }
 
+/// Print a u64 as decimal, for LBAs and byte counts past 32 bits.
+pub fn print_u64(value: u64) {
+    let mut buf = [0u8; 20];
+    let mut i = buf.len();
+    let mut val = value;
+    loop {
+        i -= 1;
+        buf[i] = b'0' + (val % 10) as u8;
+        val /= 10;
+        if val == 0 {
+            break;
+        }
+    }
+
+    for &b in &buf[i..] {
+        write_byte(b);
+    }
+    if let Ok(s) = core::str::from_utf8(&buf[i..]) {
+        crate::display::display_write(s);
+    }
+}
+
 /// Print MAC address in XX:XX:XX:XX:XX:XX format.
 pub fn print_mac(mac: &[u8; 6]) {
     for (i, byte) in mac.iter().enumerate() {
@@ -148,3 +282,27 @@ pub use println as serial_println;
 pub use print_hex as serial_print_hex;
 pub use print_hex_byte as serial_print_hex_byte;
 pub use print_u32 as serial_print_decimal;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spcr(interface: u8, space: u8, base: u64) -> [u8; 80] {
+        let mut t = [0u8; 80];
+        t[0..4].copy_from_slice(b"SPCR");
+        t[36] = interface;
+        t[40] = space;
+        t[44..52].copy_from_slice(&base.to_le_bytes());
+        t
+    }
+
+    #[test]
+    fn test_spcr_pl011_base() {
+        assert_eq!(uart_base_from_spcr(&spcr(SPCR_PL011, 0, 0x900_0000)), Some(0x900_0000));
+        assert_eq!(uart_base_from_spcr(&spcr(SPCR_SBSA_GENERIC_32, 0, 0x1000)), Some(0x1000));
+        // 16550 or port I/O consoles are not PL011s.
+        assert_eq!(uart_base_from_spcr(&spcr(0x00, 0, 0x900_0000)), None);
+        assert_eq!(uart_base_from_spcr(&spcr(SPCR_PL011, 1, 0x3F8)), None);
+        assert_eq!(uart_base_from_spcr(&spcr(SPCR_PL011, 0, 0x900_0000)[..48]), None);
+    }
+}
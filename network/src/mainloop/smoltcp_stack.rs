@@ -0,0 +1,345 @@
+//! smoltcp implementation of [`NetStack`].
+//!
+//! Owns the interface and socket set; the DNS and UDP sockets are only
+//! added once something uses them.
+
+extern crate alloc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use core::net::Ipv4Addr;
+
+use smoltcp::iface::{Config as IfaceConfig, Interface, SocketHandle, SocketSet};
+use smoltcp::socket::dhcpv4::{Event as SmoltcpDhcpEvent, Socket as DhcpSocket};
+use smoltcp::socket::dns::{GetQueryResultError, QueryHandle, Socket as DnsSocket};
+use smoltcp::socket::tcp::{Socket as TcpSocket, SocketBuffer as TcpSocketBuffer, State};
+use smoltcp::socket::udp::{PacketBuffer, PacketMetadata, Socket as UdpSocket};
+use smoltcp::time::Instant;
+use smoltcp::wire::{
+    DnsQueryType, EthernetAddress, HardwareAddress, IpAddress, IpCidr, IpEndpoint, Ipv4Address,
+};
+
+use super::adapter::SmoltcpAdapter;
+use super::netstack::{DhcpEvent, DnsStatus, Ipv4Config, NetStack, StackError, TcpStatus};
+use super::phases::TX_BUDGET;
+use super::tcp_stats::TcpTelemetry;
+use crate::driver::traits::{LinkInfo, NetworkDriver};
+
+/// TCP socket RX/TX buffer size.
+const TCP_BUFFER_SIZE: usize = 64 * 1024;
+
+/// UDP socket RX/TX payload buffer size.
+const UDP_BUFFER_SIZE: usize = 4 * 1024;
+
+/// Datagrams queued per direction on the UDP socket.
+const UDP_PACKETS: usize = 8;
+
+/// smoltcp interface plus the sockets a download uses.
+pub struct SmoltcpStack<'a, D: NetworkDriver> {
+    adapter: SmoltcpAdapter<'a, D>,
+    iface: Interface,
+    sockets: SocketSet<'static>,
+    dhcp: SocketHandle,
+    tcp: SocketHandle,
+    dns: Option<SocketHandle>,
+    dns_query: Option<QueryHandle>,
+    udp: Option<SocketHandle>,
+    /// Packets each poll may send on its own
+    tx_budget: usize,
+}
+
+impl<'a, D: NetworkDriver> SmoltcpStack<'a, D> {
+    /// Bring up an interface on `driver` with DHCP and one TCP socket.
+    pub fn new(driver: &'a mut D) -> Self {
+        let mut adapter = SmoltcpAdapter::new(driver);
+        let mac = EthernetAddress(adapter.mac_address());
+        let iface_config = IfaceConfig::new(HardwareAddress::Ethernet(mac));
+        let iface = Interface::new(iface_config, &mut adapter, Instant::ZERO);
+
+        let mut sockets = SocketSet::new(Vec::new());
+        let dhcp = sockets.add(DhcpSocket::new());
+        // Buffers on the heap, owned by the socket
+        let tcp = sockets.add(TcpSocket::new(
+            TcpSocketBuffer::new(vec![0u8; TCP_BUFFER_SIZE]),
+            TcpSocketBuffer::new(vec![0u8; TCP_BUFFER_SIZE]),
+        ));
+
+        Self {
+            adapter,
+            iface,
+            sockets,
+            dhcp,
+            tcp,
+            dns: None,
+            dns_query: None,
+            udp: None,
+            tx_budget: TX_BUDGET,
+        }
+    }
+
+    /// The device adapter, for packet counters.
+    pub fn adapter(&self) -> &SmoltcpAdapter<'a, D> {
+        &self.adapter
+    }
+
+    fn tcp(&self) -> &TcpSocket<'static> {
+        self.sockets.get::<TcpSocket>(self.tcp)
+    }
+
+    fn tcp_mut(&mut self) -> &mut TcpSocket<'static> {
+        self.sockets.get_mut::<TcpSocket>(self.tcp)
+    }
+
+    fn apply_lease(&mut self, address: IpCidr, router: Option<Ipv4Address>) {
+        self.iface.update_ip_addrs(|addrs| {
+            addrs.clear();
+            let _ = addrs.push(address);
+        });
+        let routes = self.iface.routes_mut();
+        routes.remove_default_ipv4_route();
+        if let Some(router) = router {
+            let _ = routes.add_default_ipv4_route(router);
+        }
+    }
+}
+
+impl<D: NetworkDriver> NetStack for SmoltcpStack<'_, D> {
+    fn poll(&mut self, now_ms: i64) -> bool {
+        let now = Instant::from_millis(now_ms);
+        self.adapter.set_tx_budget(self.tx_budget);
+        self.iface.poll(now, &mut self.adapter, &mut self.sockets)
+    }
+
+    fn poll_delay(&mut self, now_ms: i64) -> Option<u64> {
+        let now = Instant::from_millis(now_ms);
+        self.iface
+            .poll_delay(now, &self.sockets)
+            .map(|d| d.total_micros())
+    }
+
+    fn link_up(&self) -> bool {
+        self.adapter.driver_link_up()
+    }
+
+    fn link_info(&self) -> Option<LinkInfo> {
+        self.adapter.driver_link_info()
+    }
+
+    fn autoneg_complete(&self) -> Option<bool> {
+        self.adapter.driver_autoneg_complete()
+    }
+
+    fn rx_frames(&self) -> Option<u32> {
+        Some(self.adapter.rx_count())
+    }
+
+    fn set_tx_budget(&mut self, packets: usize) {
+        self.tx_budget = packets;
+    }
+
+    fn rx_backlog(&self) -> Option<(usize, usize)> {
+        self.adapter.rx_backlog()
+    }
+
+    fn quiesce(&mut self) {
+        self.adapter.quiesce();
+    }
+
+    fn dhcp_poll(&mut self) -> Option<DhcpEvent> {
+        // The event borrows the socket; copy the lease out before applying it
+        let lease = match self.sockets.get_mut::<DhcpSocket>(self.dhcp).poll()? {
+            SmoltcpDhcpEvent::Configured(config) => {
+                let mut dns_servers = [None; 3];
+                for (slot, server) in dns_servers.iter_mut().zip(config.dns_servers.iter()) {
+                    *slot = Some(Ipv4Addr::from(server.0));
+                }
+                Some((config.address, config.router, dns_servers))
+            }
+            SmoltcpDhcpEvent::Deconfigured => None,
+        };
+
+        match lease {
+            Some((address, router, dns_servers)) => {
+                self.apply_lease(IpCidr::Ipv4(address), router);
+                Some(DhcpEvent::Configured(Ipv4Config {
+                    address: Ipv4Addr::from(address.address().0),
+                    prefix_len: address.prefix_len(),
+                    router: router.map(|r| Ipv4Addr::from(r.0)),
+                    dns_servers,
+                }))
+            }
+            None => {
+                self.iface.update_ip_addrs(|addrs| addrs.clear());
+                self.iface.routes_mut().remove_default_ipv4_route();
+                Some(DhcpEvent::Deconfigured)
+            }
+        }
+    }
+
+    fn dhcp_restart(&mut self) {
+        self.sockets.get_mut::<DhcpSocket>(self.dhcp).reset();
+    }
+
+    fn dns_query(&mut self, host: &str, server: Ipv4Addr) -> Result<(), StackError> {
+        let server = IpAddress::Ipv4(Ipv4Address(server.octets()));
+        self.dns_cancel();
+        let handle = match self.dns {
+            Some(handle) => {
+                self.sockets
+                    .get_mut::<DnsSocket>(handle)
+                    .update_servers(&[server]);
+                handle
+            }
+            None => {
+                // The socket owns its query slot, so a later socket never
+                // aliases an old query.
+                let handle = self.sockets.add(DnsSocket::new(&[server], vec![None]));
+                self.dns = Some(handle);
+                handle
+            }
+        };
+        let query = self.sockets.get_mut::<DnsSocket>(handle).start_query(
+            self.iface.context(),
+            host,
+            DnsQueryType::A,
+        );
+        self.dns_query = Some(query.map_err(|_| StackError::Exhausted)?);
+        Ok(())
+    }
+
+    fn dns_status(&mut self) -> DnsStatus {
+        let (Some(handle), Some(query)) = (self.dns, self.dns_query) else {
+            return DnsStatus::Failed;
+        };
+        let result = self
+            .sockets
+            .get_mut::<DnsSocket>(handle)
+            .get_query_result(query);
+        match result {
+            Ok(addrs) => {
+                self.dns_query = None;
+                addrs
+                    .iter()
+                    .find_map(|addr| match addr {
+                        IpAddress::Ipv4(ip) => Some(DnsStatus::Resolved(Ipv4Addr::from(ip.0))),
+                        #[allow(unreachable_patterns)]
+                        _ => None,
+                    })
+                    .unwrap_or(DnsStatus::NoAddress)
+            }
+            Err(GetQueryResultError::Pending) => DnsStatus::Pending,
+            Err(GetQueryResultError::Failed) => {
+                self.dns_query = None;
+                DnsStatus::Failed
+            }
+        }
+    }
+
+    fn dns_cancel(&mut self) {
+        if let (Some(handle), Some(query)) = (self.dns, self.dns_query.take()) {
+            self.sockets
+                .get_mut::<DnsSocket>(handle)
+                .cancel_query(query);
+        }
+    }
+
+    fn tcp_connect(
+        &mut self,
+        remote: Ipv4Addr,
+        port: u16,
+        local_port: u16,
+    ) -> Result<(), StackError> {
+        let endpoint = IpEndpoint::new(IpAddress::Ipv4(Ipv4Address(remote.octets())), port);
+        let socket = self.sockets.get_mut::<TcpSocket>(self.tcp);
+        socket
+            .connect(self.iface.context(), endpoint, local_port)
+            .map_err(|_| StackError::InvalidState)
+    }
+
+    fn tcp_status(&self) -> TcpStatus {
+        match self.tcp().state() {
+            State::Closed | State::Listen | State::TimeWait => TcpStatus::Closed,
+            State::SynSent | State::SynReceived => TcpStatus::Connecting,
+            State::Established => TcpStatus::Established,
+            State::FinWait1
+            | State::FinWait2
+            | State::CloseWait
+            | State::Closing
+            | State::LastAck => TcpStatus::Closing,
+        }
+    }
+
+    fn tcp_may_send(&self) -> bool {
+        self.tcp().may_send()
+    }
+
+    fn tcp_may_recv(&self) -> bool {
+        self.tcp().may_recv()
+    }
+
+    fn tcp_send(&mut self, data: &[u8]) -> Result<usize, StackError> {
+        self.tcp_mut()
+            .send_slice(data)
+            .map_err(|_| StackError::InvalidState)
+    }
+
+    fn tcp_recv(&mut self, buf: &mut [u8]) -> Result<usize, StackError> {
+        self.tcp_mut()
+            .recv_slice(buf)
+            .map_err(|_| StackError::InvalidState)
+    }
+
+    fn tcp_abort(&mut self) {
+        self.tcp_mut().abort();
+    }
+
+    fn tcp_telemetry(&self) -> Option<TcpTelemetry> {
+        Some(self.adapter.tcp_telemetry())
+    }
+
+    fn udp_bind(&mut self, port: u16) -> Result<(), StackError> {
+        let handle = match self.udp {
+            Some(handle) => handle,
+            None => {
+                let buffer = || {
+                    PacketBuffer::new(
+                        vec![PacketMetadata::EMPTY; UDP_PACKETS],
+                        vec![0u8; UDP_BUFFER_SIZE],
+                    )
+                };
+                let handle = self.sockets.add(UdpSocket::new(buffer(), buffer()));
+                self.udp = Some(handle);
+                handle
+            }
+        };
+        self.sockets
+            .get_mut::<UdpSocket>(handle)
+            .bind(port)
+            .map_err(|_| StackError::InvalidState)
+    }
+
+    fn udp_send(&mut self, data: &[u8], remote: Ipv4Addr, port: u16) -> Result<(), StackError> {
+        let handle = self.udp.ok_or(StackError::InvalidState)?;
+        let endpoint = IpEndpoint::new(IpAddress::Ipv4(Ipv4Address(remote.octets())), port);
+        self.sockets
+            .get_mut::<UdpSocket>(handle)
+            .send_slice(data, endpoint)
+            .map_err(|_| StackError::Exhausted)
+    }
+
+    fn udp_recv(&mut self, buf: &mut [u8]) -> Option<(usize, Ipv4Addr, u16)> {
+        let socket = self.sockets.get_mut::<UdpSocket>(self.udp?);
+        let (len, meta) = socket.recv_slice(buf).ok()?;
+        match meta.endpoint.addr {
+            IpAddress::Ipv4(ip) => Some((len, Ipv4Addr::from(ip.0), meta.endpoint.port)),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        }
+    }
+
+    fn udp_close(&mut self) {
+        if let Some(handle) = self.udp {
+            self.sockets.get_mut::<UdpSocket>(handle).close();
+        }
+    }
+}
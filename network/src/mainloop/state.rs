@@ -7,12 +7,8 @@
 extern crate alloc;
 use alloc::boxed::Box;
 
-use smoltcp::iface::{Interface, SocketSet};
-use smoltcp::time::Instant;
-
-use crate::driver::traits::NetworkDriver;
-use super::adapter::SmoltcpAdapter;
 use super::context::Context;
+use super::netstack::NetStack;
 
 /// Result of a single state machine step.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -31,7 +27,9 @@ pub enum StepResult {
 ///
 /// The `self: Box<Self>` pattern allows states to consume themselves
 /// and return a different state type, enabling type-safe transitions.
-pub trait State<D: NetworkDriver> {
+/// States reach the network only through [`NetStack`], so they don't
+/// depend on which TCP/IP stack is underneath.
+pub trait State {
     /// Execute one step of this state.
     ///
     /// Returns the next state (which may be self for Continue,
@@ -39,12 +37,9 @@ pub trait State<D: NetworkDriver> {
     fn step(
         self: Box<Self>,
         ctx: &mut Context<'_>,
-        iface: &mut Interface,
-        sockets: &mut SocketSet<'_>,
-        adapter: &mut SmoltcpAdapter<'_, D>,
-        now: Instant,
+        stack: &mut dyn NetStack,
         tsc: u64,
-    ) -> (Box<dyn State<D>>, StepResult);
+    ) -> (Box<dyn State>, StepResult);
 
     /// Human-readable name for logging.
     fn name(&self) -> &'static str;
@@ -53,4 +48,8 @@ pub trait State<D: NetworkDriver> {
     fn is_terminal(&self) -> bool {
         false
     }
+
+    /// Called once when the session is aborted, before switching to
+    /// `AbortState`. States holding buffered data flush it into `ctx`.
+    fn abort(&mut self, _ctx: &mut Context<'_>) {}
 }
@@ -0,0 +1,97 @@
+//! Abort state — graceful end of an interrupted session.
+//!
+//! Entered from any state when `abort::poll()` fires. By the time we get
+//! here the previous state has flushed its buffers (`State::abort`), so
+//! this state only has to make the partial download durable and leave:
+//!
+//! 1. Sync the disk write cache
+//! 2. Write a partial manifest (COMPLETE clear, resume offset recorded)
+//! 3. Stop NIC DMA
+//! 4. Reset the platform via the mechanisms captured pre-EBS
+
+extern crate alloc;
+use alloc::boxed::Box;
+
+
+use crate::mainloop::netstack::NetStack;
+use crate::mainloop::context::Context;
+use crate::mainloop::serial;
+use crate::mainloop::state::{State, StepResult};
+use crate::power;
+
+use super::done::sync_disk;
+use super::manifest::{write_partial, ManifestConfig, ManifestMode};
+
+/// Abort terminal state.
+pub struct AbortState;
+
+impl AbortState {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Record how far we got so the download can be resumed.
+    fn write_partial_manifest(ctx: &mut Context<'_>) {
+        if ctx.bytes_written == 0 {
+            serial::println("[ABORT] Nothing written, no manifest");
+            return;
+        }
+
+        let config = ManifestConfig::partial_from_context(ctx);
+        if let ManifestMode::Skip = config.mode {
+            serial::println("[ABORT] Manifest not configured");
+            return;
+        }
+
+        serial::print("[ABORT] Resume offset: ");
+        serial::print_u32((config.resume_offset() / 1024) as u32);
+        serial::println(" KB");
+
+        if ctx.blk_device.is_none() {
+            serial::println("[ABORT] No block device");
+        } else if write_partial(ctx, &config) {
+            serial::println("[ABORT] Partial manifest written");
+        } else {
+            serial::println("[ABORT] WARN: Partial manifest write failed");
+        }
+    }
+}
+
+impl Default for AbortState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl State for AbortState {
+    fn step(
+        self: Box<Self>,
+        ctx: &mut Context<'_>,
+        stack: &mut dyn NetStack,
+        _tsc: u64,
+    ) -> (Box<dyn State>, StepResult) {
+        serial::println("=================================");
+        serial::println("        DOWNLOAD ABORTED         ");
+        serial::println("=================================");
+        serial::print("Downloaded: ");
+        serial::print_u32((ctx.bytes_downloaded / 1024) as u32);
+        serial::println(" KB");
+
+        sync_disk(ctx);
+        Self::write_partial_manifest(ctx);
+
+        serial::println("[ABORT] Stopping NIC DMA");
+        stack.quiesce();
+
+        serial::println("[ABORT] Resetting system...");
+        power::reset_system()
+    }
+
+    fn name(&self) -> &'static str {
+        "Abort"
+    }
+
+    fn is_terminal(&self) -> bool {
+        true
+    }
+}
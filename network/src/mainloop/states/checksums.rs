@@ -0,0 +1,292 @@
+//! Checksum list state — fetches the list next to the image for its hash.
+//!
+//! With `config.checksums` set and no expected SHA-256 given, the GET for
+//! the image waits for one more request on a connection of its own: the
+//! checksum list in the image's directory (e.g. `SHA256SUMS`). The entry
+//! for the image's file name becomes `config.expected_sha256`, which the
+//! manifest then checks the download against. A list that is missing or
+//! doesn't name the image leaves the download unverified, as it would
+//! have been without one; it never stops the download.
+
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::mainloop::context::Context;
+use crate::mainloop::netstack::{NetStack, TcpStatus};
+use crate::mainloop::serial;
+use crate::mainloop::state::{State, StepResult};
+use crate::transfer::checksums::{file_name, find_sha256, sibling_path, MAX_CHECKSUMS_LEN};
+use crate::transfer::chunked::ChunkedDecoder;
+
+use super::http::{
+    contains_ignore_case, find_header_end, format_http_request, header_value, parse_content_length,
+};
+use super::ConnectState;
+
+/// Largest response header taken, as for the image
+const MAX_HEADER_LEN: usize = 2048;
+
+/// Checksum list state.
+pub struct ChecksumState {
+    start_tsc: u64,
+    last_activity_tsc: u64,
+    /// Path of the list (set on the first step)
+    path: String,
+    request_sent: bool,
+    /// Response bytes up to the end of the headers
+    header_buf: Vec<u8>,
+    headers_complete: bool,
+    content_length: Option<u64>,
+    /// Decoder for a chunked body, `None` if the body comes as it is
+    chunked: Option<ChunkedDecoder>,
+    /// The list so far
+    body: Vec<u8>,
+}
+
+impl ChecksumState {
+    pub fn new() -> Self {
+        Self {
+            start_tsc: 0,
+            last_activity_tsc: 0,
+            path: String::new(),
+            request_sent: false,
+            header_buf: Vec::new(),
+            headers_complete: false,
+            content_length: None,
+            chunked: None,
+            body: Vec::new(),
+        }
+    }
+
+    /// Take response bytes; `Err` once the response can't be the list.
+    fn take(&mut self, data: &[u8]) -> Result<(), &'static str> {
+        if self.headers_complete {
+            return self.take_body(data);
+        }
+        self.header_buf.extend_from_slice(data);
+        let Some(end) = find_header_end(&self.header_buf) else {
+            return if self.header_buf.len() > MAX_HEADER_LEN {
+                Err("headers too large")
+            } else {
+                Ok(())
+            };
+        };
+
+        let headers = core::str::from_utf8(&self.header_buf[..end]).unwrap_or("");
+        if !headers.starts_with("HTTP/1.1 200") && !headers.starts_with("HTTP/1.0 200") {
+            return Err("not on the server");
+        }
+        let chunked = header_value(headers, "transfer-encoding")
+            .is_some_and(|te| contains_ignore_case(te, "chunked"));
+        self.content_length = parse_content_length(headers).filter(|_| !chunked);
+        if self
+            .content_length
+            .is_some_and(|len| len > MAX_CHECKSUMS_LEN as u64)
+        {
+            return Err("list too large");
+        }
+        self.chunked = chunked.then(ChunkedDecoder::new);
+        self.headers_complete = true;
+
+        let body = self.header_buf.split_off(end + 4);
+        self.take_body(&body)
+    }
+
+    fn take_body(&mut self, data: &[u8]) -> Result<(), &'static str> {
+        match self.chunked.as_mut() {
+            Some(decoder) => {
+                let body = &mut self.body;
+                decoder
+                    .decode_with(data, |run| body.extend_from_slice(run))
+                    .map_err(|_| "bad chunk")?;
+            }
+            None => self.body.extend_from_slice(data),
+        }
+        if self.body.len() > MAX_CHECKSUMS_LEN {
+            return Err("list too large");
+        }
+        Ok(())
+    }
+
+    /// Whether the whole list is in (a body without a length ends with
+    /// the connection).
+    fn body_complete(&self) -> bool {
+        match &self.chunked {
+            Some(decoder) => decoder.is_done(),
+            None => self
+                .content_length
+                .is_some_and(|len| self.body.len() as u64 >= len),
+        }
+    }
+
+    /// Look the image up in the list (`Err`: why there is none) and
+    /// reconnect for the GET.
+    fn finish(
+        &self,
+        ctx: &mut Context<'_>,
+        stack: &mut dyn NetStack,
+        list: Result<(), &'static str>,
+    ) -> (Box<dyn State>, StepResult) {
+        stack.tcp_abort();
+        ctx.checksums_fetched = true;
+
+        let image = file_name(ctx.url_path);
+        let found = list.and_then(|()| {
+            let text = core::str::from_utf8(&self.body).map_err(|_| "not text")?;
+            find_sha256(text, image).ok_or("image not listed")
+        });
+        match found {
+            Ok(sha256) => {
+                serial::print("[SUMS] SHA-256 of ");
+                serial::print(image);
+                serial::print(" from ");
+                serial::print(ctx.config.checksums);
+                serial::print(": ");
+                for byte in &sha256[..8] {
+                    serial::print_hex_byte(*byte);
+                }
+                serial::println("...");
+                ctx.config.expected_sha256 = Some(sha256);
+            }
+            Err(why) => {
+                serial::print("[SUMS] ");
+                serial::print(ctx.config.checksums);
+                serial::print(": ");
+                serial::print(why);
+                serial::println(", downloading without verification");
+            }
+        }
+        serial::println("[SUMS] -> Connect");
+        (Box::new(ConnectState::new()), StepResult::Transition)
+    }
+}
+
+impl Default for ChecksumState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl State for ChecksumState {
+    fn step(
+        mut self: Box<Self>,
+        ctx: &mut Context<'_>,
+        stack: &mut dyn NetStack,
+        tsc: u64,
+    ) -> (Box<dyn State>, StepResult) {
+        if self.start_tsc == 0 {
+            self.start_tsc = tsc;
+            self.last_activity_tsc = tsc;
+            self.path = sibling_path(ctx.url_path, ctx.config.checksums);
+        }
+
+        if tsc.saturating_sub(self.last_activity_tsc) > ctx.timeouts.http_idle() {
+            return self.finish(ctx, stack, Err("timed out"));
+        }
+
+        if !self.request_sent {
+            if !stack.tcp_may_send() {
+                return (self, StepResult::Continue);
+            }
+            let mut req_buf = [0u8; 512];
+            let req_len =
+                format_http_request(&mut req_buf, "GET", &self.path, ctx.url_authority, None);
+            if req_len == 0 {
+                return self.finish(ctx, stack, Err("request too large"));
+            }
+            serial::print("[SUMS] Fetching ");
+            serial::println(&self.path);
+            if stack.tcp_send(&req_buf[..req_len]).is_err() {
+                return self.finish(ctx, stack, Err("send failed"));
+            }
+            self.request_sent = true;
+            self.last_activity_tsc = tsc;
+            return (self, StepResult::Continue);
+        }
+
+        if !stack.tcp_may_recv() {
+            if self.headers_complete && self.body_complete() {
+                return self.finish(ctx, stack, Ok(()));
+            }
+            if stack.tcp_status() != TcpStatus::Established {
+                // No length and no chunks: the close ends the list
+                let closed_end = self.headers_complete
+                    && self.content_length.is_none()
+                    && self.chunked.is_none();
+                let list = if closed_end {
+                    Ok(())
+                } else {
+                    Err("connection closed")
+                };
+                return self.finish(ctx, stack, list);
+            }
+            return (self, StepResult::Continue);
+        }
+
+        let mut buf = [0u8; 4096];
+        match stack.tcp_recv(&mut buf) {
+            Ok(0) | Err(_) => {}
+            Ok(n) => {
+                self.last_activity_tsc = tsc;
+                if let Err(why) = self.take(&buf[..n]) {
+                    return self.finish(ctx, stack, Err(why));
+                }
+                if self.headers_complete && self.body_complete() {
+                    return self.finish(ctx, stack, Ok(()));
+                }
+            }
+        }
+
+        (self, StepResult::Continue)
+    }
+
+    fn name(&self) -> &'static str {
+        "Checksums"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHA: &str = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+
+    #[test]
+    fn test_takes_chunked_list() {
+        let mut state = ChecksumState::new();
+        let list = alloc::format!("{SHA}  a.iso\n");
+        let response = alloc::format!(
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n{list}\r\n0\r\n\r\n",
+            list.len()
+        );
+        // Split inside the headers and inside the chunk
+        let (a, b) = response.as_bytes().split_at(20);
+        let (b, c) = b.split_at(50);
+        for part in [a, b, c] {
+            state.take(part).unwrap();
+        }
+        assert!(state.headers_complete && state.body_complete());
+        assert_eq!(state.body, list.as_bytes());
+    }
+
+    #[test]
+    fn test_rejects_other_responses() {
+        let mut state = ChecksumState::new();
+        assert!(state.take(b"HTTP/1.1 404 Not Found\r\n\r\n").is_err());
+
+        let mut state = ChecksumState::new();
+        assert!(state
+            .take(b"HTTP/1.1 200 OK\r\nContent-Length: 1000000\r\n\r\n")
+            .is_err());
+
+        let mut state = ChecksumState::new();
+        state
+            .take(b"HTTP/1.0 200 OK\r\nContent-Length: 5\r\n\r\nab")
+            .unwrap();
+        assert!(!state.body_complete());
+        state.take(b"cde").unwrap();
+        assert!(state.body_complete());
+    }
+}
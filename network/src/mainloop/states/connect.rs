@@ -3,24 +3,23 @@
 extern crate alloc;
 use alloc::boxed::Box;
 
-use smoltcp::iface::{Interface, SocketHandle, SocketSet};
-use smoltcp::socket::tcp::{Socket as TcpSocket, State as TcpState};
-use smoltcp::time::Instant;
-use smoltcp::wire::{IpAddress, IpEndpoint};
+use core::net::Ipv4Addr;
 
-use crate::driver::traits::NetworkDriver;
-use crate::mainloop::adapter::SmoltcpAdapter;
 use crate::mainloop::context::Context;
+use crate::mainloop::netstack::{NetStack, TcpStatus};
+use crate::mainloop::retry::RetryPhase;
 use crate::mainloop::serial;
 use crate::mainloop::state::{State, StepResult};
 
-use super::{FailedState, HttpState};
+use super::{ChecksumState, FailedState, HttpState};
 
 /// TCP connection state.
 pub struct ConnectState {
     start_tsc: u64,
     connect_started: bool,
-    target: Option<IpEndpoint>,
+    target: Option<(Ipv4Addr, u16)>,
+    /// Backoff deadline before (re)connecting (None = connect immediately).
+    retry_at: Option<u64>,
 }
 
 impl ConnectState {
@@ -29,14 +28,43 @@ impl ConnectState {
             start_tsc: 0,
             connect_started: false,
             target: None,
+            retry_at: None,
         }
     }
 
-    pub fn with_endpoint(addr: IpAddress, port: u16) -> Self {
+    pub fn with_endpoint(addr: Ipv4Addr, port: u16) -> Self {
         Self {
             start_tsc: 0,
             connect_started: false,
-            target: Some(IpEndpoint::new(addr, port)),
+            target: Some((addr, port)),
+            retry_at: None,
+        }
+    }
+
+    /// Reconnect once the TSC reaches `resume_tsc` (used by HTTP retries).
+    pub fn retry_after(resume_tsc: u64) -> Self {
+        Self {
+            retry_at: Some(resume_tsc),
+            ..Self::new()
+        }
+    }
+
+    /// Abort the connection and schedule a retry, or fail if the budget is spent.
+    fn retry_or_fail(
+        mut self: Box<Self>,
+        ctx: &mut Context<'_>,
+        stack: &mut dyn NetStack,
+        tsc: u64,
+        reason: &'static str,
+    ) -> (Box<dyn State>, StepResult) {
+        stack.tcp_abort();
+        match ctx.schedule_retry(RetryPhase::Connect, tsc) {
+            Some(retry) => {
+                self.retry_at = Some(retry.resume_tsc);
+                self.connect_started = false;
+                (self, StepResult::Continue)
+            }
+            None => (Box::new(FailedState::new(reason)), StepResult::Failed(reason)),
         }
     }
 }
@@ -47,29 +75,28 @@ impl Default for ConnectState {
     }
 }
 
-impl<D: NetworkDriver> State<D> for ConnectState {
+impl State for ConnectState {
     fn step(
         mut self: Box<Self>,
         ctx: &mut Context<'_>,
-        iface: &mut Interface,
-        sockets: &mut SocketSet<'_>,
-        _adapter: &mut SmoltcpAdapter<'_, D>,
-        _now: Instant,
+        stack: &mut dyn NetStack,
         tsc: u64,
-    ) -> (Box<dyn State<D>>, StepResult) {
+    ) -> (Box<dyn State>, StepResult) {
+        // Waiting out a backoff delay
+        if let Some(resume) = self.retry_at {
+            if tsc < resume {
+                return (self, StepResult::Continue);
+            }
+            self.retry_at = None;
+            self.start_tsc = 0;
+        }
+
         if self.start_tsc == 0 {
             self.start_tsc = tsc;
             serial::println("[TCP] Starting connection...");
         }
 
-        let elapsed_ticks = tsc.saturating_sub(self.start_tsc);
-        let timeout_ticks = ctx.timeouts.tcp_connect();
-        if elapsed_ticks > timeout_ticks {
-            serial::println("[TCP] ERROR: Connection timeout");
-            return (Box::new(FailedState::new("TCP timeout")), StepResult::Failed("TCP timeout"));
-        }
-
-        let endpoint = match self.target {
+        let (remote, port) = match self.target {
             Some(ep) => ep,
             None => {
                 let ip = match ctx.resolved_ip {
@@ -79,58 +106,66 @@ impl<D: NetworkDriver> State<D> for ConnectState {
                         return (Box::new(FailedState::new("no IP")), StepResult::Failed("no IP"));
                     }
                 };
-                IpEndpoint::new(ip, ctx.resolved_port)
+                (ip, ctx.resolved_port)
             }
         };
 
-        // Get TCP handle from context
-        let tcp_handle = match ctx.tcp_handle {
-            Some(h) => h,
-            None => {
-                serial::println("[TCP] ERROR: No TCP socket");
-                return (Box::new(FailedState::new("no TCP socket")), StepResult::Failed("no socket"));
-            }
-        };
-
-        let socket = sockets.get_mut::<TcpSocket>(tcp_handle);
+        let elapsed_ticks = tsc.saturating_sub(self.start_tsc);
+        let timeout_ticks = ctx.timeouts.tcp_connect();
+        if elapsed_ticks > timeout_ticks {
+            serial::println("[TCP] ERROR: Connection timeout");
+            return self.retry_or_fail(ctx, stack, tsc, "TCP timeout");
+        }
 
         if !self.connect_started {
             serial::print("[TCP] Connecting to ");
-            if let IpAddress::Ipv4(ip) = endpoint.addr {
-                serial::print_ipv4(&ip.0);
-            }
+            serial::print_ipv4(&remote.octets());
             serial::print(":");
-            serial::print_u32(endpoint.port as u32);
+            serial::print_u32(port as u32);
             serial::println("");
 
             let local_port = 49152 + ((tsc & 0xFFFF) as u16 % 16384);
 
-            if socket.connect(iface.context(), endpoint, local_port).is_err() {
+            let connected = if ctx.url_tls {
+                stack.tcp_connect_tls(remote, port, local_port, ctx.url_host)
+            } else {
+                stack.tcp_connect(remote, port, local_port)
+            };
+            if connected.is_err() {
                 serial::println("[TCP] ERROR: Connect failed");
-                return (Box::new(FailedState::new("connect failed")), StepResult::Failed("connect"));
+                return self.retry_or_fail(ctx, stack, tsc, "connect failed");
             }
             self.connect_started = true;
             return (self, StepResult::Continue);
         }
 
-        match socket.state() {
-            TcpState::Established => {
+        match stack.tcp_status() {
+            TcpStatus::Established => {
                 serial::println("[TCP] Connected!");
                 serial::println("[TCP] -> HTTP");
-                // Create HTTP state with disk writing if enabled
-                let http_state = if ctx.should_write_to_disk() {
-                    HttpState::with_disk_write(tcp_handle, ctx.config.target_start_sector)
+                // HEAD first, then the checksum list if wanted, then the
+                // GET (with disk writing if enabled)
+                if ctx.preflight.is_some() && ctx.wants_checksums() {
+                    return (Box::new(ChecksumState::new()), StepResult::Transition);
+                }
+                let http_state = if ctx.preflight.is_none() {
+                    HttpState::preflight()
+                } else if ctx.should_write_to_disk() {
+                    HttpState::with_disk_write(
+                        ctx.actual_start_sector,
+                        ctx.config.zeroed_target,
+                        ctx.config.spot_check_every,
+                    )
                 } else {
-                    HttpState::new(tcp_handle)
+                    HttpState::new()
                 };
                 return (Box::new(http_state), StepResult::Transition);
             }
-            TcpState::SynSent | TcpState::SynReceived => {}
-            TcpState::Closed | TcpState::TimeWait => {
+            TcpStatus::Connecting | TcpStatus::Closing => {}
+            TcpStatus::Closed => {
                 serial::println("[TCP] ERROR: Connection closed/reset");
-                return (Box::new(FailedState::new("connection closed")), StepResult::Failed("closed"));
+                return self.retry_or_fail(ctx, stack, tsc, "connection closed");
             }
-            _ => {}
         }
 
         (self, StepResult::Continue)
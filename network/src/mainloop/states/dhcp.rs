@@ -3,14 +3,9 @@
 extern crate alloc;
 use alloc::boxed::Box;
 
-use smoltcp::iface::{Interface, SocketSet};
-use smoltcp::socket::dhcpv4::{Event as DhcpEvent, Socket as DhcpSocket};
-use smoltcp::time::Instant;
-use smoltcp::wire::IpCidr;
-
-use crate::driver::traits::NetworkDriver;
-use crate::mainloop::adapter::SmoltcpAdapter;
 use crate::mainloop::context::Context;
+use crate::mainloop::netstack::{DhcpEvent, NetStack};
+use crate::mainloop::retry::RetryPhase;
 use crate::mainloop::serial;
 use crate::mainloop::state::{State, StepResult};
 
@@ -20,6 +15,8 @@ use super::{DnsState, FailedState};
 pub struct DhcpState {
     start_tsc: u64,
     got_ip: bool,
+    /// Backoff deadline before restarting discovery (None = not waiting).
+    retry_at: Option<u64>,
 }
 
 impl DhcpState {
@@ -27,6 +24,7 @@ impl DhcpState {
         Self {
             start_tsc: 0,
             got_ip: false,
+            retry_at: None,
         }
     }
 }
@@ -37,80 +35,70 @@ impl Default for DhcpState {
     }
 }
 
-impl<D: NetworkDriver> State<D> for DhcpState {
+impl State for DhcpState {
     fn step(
         mut self: Box<Self>,
         ctx: &mut Context<'_>,
-        iface: &mut Interface,
-        sockets: &mut SocketSet<'_>,
-        _adapter: &mut SmoltcpAdapter<'_, D>,
-        _now: Instant,
+        stack: &mut dyn NetStack,
         tsc: u64,
-    ) -> (Box<dyn State<D>>, StepResult) {
+    ) -> (Box<dyn State>, StepResult) {
         if self.start_tsc == 0 {
             self.start_tsc = tsc;
             serial::println("[DHCP] Starting DHCP discovery...");
         }
 
-        let elapsed_ticks = tsc.saturating_sub(self.start_tsc);
-        let timeout_ticks = ctx.timeouts.dhcp();
-        if elapsed_ticks > timeout_ticks {
-            serial::println("[DHCP] ERROR: Timeout");
-            return (Box::new(FailedState::new("DHCP timeout")), StepResult::Failed("DHCP timeout"));
-        }
-
         if self.got_ip {
             serial::println("[DHCP] -> DNS");
             return (Box::new(DnsState::new()), StepResult::Transition);
         }
 
-        // Get DHCP handle from context
-        let dhcp_handle = match ctx.dhcp_handle {
-            Some(h) => h,
-            None => {
-                serial::println("[DHCP] ERROR: No DHCP socket");
-                return (Box::new(FailedState::new("no DHCP socket")), StepResult::Failed("no socket"));
+        // Waiting out a backoff delay
+        if let Some(resume) = self.retry_at {
+            if tsc < resume {
+                return (self, StepResult::Continue);
             }
-        };
+            serial::println("[DHCP] Restarting discovery...");
+            stack.dhcp_restart();
+            self.retry_at = None;
+            self.start_tsc = tsc;
+        }
 
-        let socket = sockets.get_mut::<DhcpSocket>(dhcp_handle);
+        let elapsed_ticks = tsc.saturating_sub(self.start_tsc);
+        let timeout_ticks = ctx.timeouts.dhcp();
+        if elapsed_ticks > timeout_ticks {
+            serial::println("[DHCP] ERROR: Timeout");
+            if let Some(retry) = ctx.schedule_retry(RetryPhase::Dhcp, tsc) {
+                self.retry_at = Some(retry.resume_tsc);
+                return (self, StepResult::Continue);
+            }
+            return (Box::new(FailedState::new("DHCP timeout")), StepResult::Failed("DHCP timeout"));
+        }
 
-        if let Some(event) = socket.poll() {
+        if let Some(event) = stack.dhcp_poll() {
             match event {
                 DhcpEvent::Configured(config) => {
-                    let addr = config.address;
-                    // Ipv4Address.0 is [u8; 4]
-                    let ip_bytes = addr.address().0;
+                    // The stack has already applied the address and route
                     serial::print("[DHCP] Got IP: ");
-                    serial::print_ipv4(&ip_bytes);
+                    serial::print_ipv4(&config.address.octets());
                     serial::print("/");
-                    serial::print_u32(addr.prefix_len() as u32);
+                    serial::print_u32(config.prefix_len as u32);
                     serial::println("");
 
-                    iface.update_ip_addrs(|addrs| {
-                        if let Some(addr_slot) = addrs.iter_mut().next() {
-                            *addr_slot = IpCidr::Ipv4(addr);
-                        }
-                    });
-
                     if let Some(router) = config.router {
                         serial::print("[DHCP] Gateway: ");
-                        serial::print_ipv4(&router.0);
+                        serial::print_ipv4(&router.octets());
                         serial::println("");
-                        iface.routes_mut().add_default_ipv4_route(router).ok();
                     }
 
                     // Store DNS servers in context
-                    for (i, dns) in config.dns_servers.iter().enumerate() {
+                    for (i, dns) in config.dns_servers.iter().flatten().enumerate() {
                         serial::print("[DHCP] DNS ");
                         serial::print_u32(i as u32);
                         serial::print(": ");
-                        serial::print_ipv4(&dns.0);
+                        serial::print_ipv4(&dns.octets());
                         serial::println("");
-                        if i < 3 {
-                            ctx.dns_servers[i] = Some(smoltcp::wire::IpAddress::Ipv4(*dns));
-                        }
                     }
+                    ctx.dns_servers = config.dns_servers;
 
                     self.got_ip = true;
                 }
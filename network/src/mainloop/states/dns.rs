@@ -1,40 +1,57 @@
 //! DNS resolution state — resolves hostname to IP address.
 //!
-//! Uses smoltcp's DNS socket for resolution. Falls back to direct
-//! IP address parsing when hostname is already an IP.
+//! Resolves through the stack's DNS client. Hosts that need no server
+//! skip the query: IP literals (IPv6 ones only when IPv4-mapped, the stack
+//! being IPv4-only) and names in the static hosts table from
+//! [`DownloadConfig::hosts`](crate::mainloop::DownloadConfig::hosts).
 
 extern crate alloc;
 use alloc::boxed::Box;
 
-use smoltcp::iface::{Interface, SocketSet};
-use smoltcp::socket::dns::{GetQueryResultError, QueryHandle, Socket as DnsSocket};
-use smoltcp::time::Instant;
-use smoltcp::wire::{DnsQueryType, IpAddress, Ipv4Address};
+use core::net::{Ipv4Addr, Ipv6Addr};
 
-use crate::driver::traits::NetworkDriver;
-use crate::mainloop::adapter::SmoltcpAdapter;
 use crate::mainloop::context::Context;
+use crate::mainloop::netstack::{DnsStatus, NetStack};
+use crate::mainloop::retry::RetryPhase;
 use crate::mainloop::serial;
 use crate::mainloop::state::{State, StepResult};
 
 use super::{ConnectState, FailedState};
 
-/// Static storage for DNS queries (smoltcp requirement).
-static mut DNS_QUERIES: [Option<smoltcp::socket::dns::DnsQuery>; 1] = [None];
-
 /// DNS resolution state.
 pub struct DnsState {
     start_tsc: u64,
-    query_handle: Option<QueryHandle>,
-    dns_handle_added: bool,
+    query_started: bool,
+    /// Backoff deadline before re-sending the query (None = not waiting).
+    retry_at: Option<u64>,
+    /// Index into `ctx.dns_servers` of the server in use.
+    server_idx: usize,
 }
 
 impl DnsState {
     pub fn new() -> Self {
         Self {
             start_tsc: 0,
-            query_handle: None,
-            dns_handle_added: false,
+            query_started: false,
+            retry_at: None,
+            server_idx: 0,
+        }
+    }
+
+    /// Schedule a retry after `reason`, or fail if the budget is spent.
+    fn retry_or_fail(
+        mut self: Box<Self>,
+        ctx: &mut Context<'_>,
+        tsc: u64,
+        reason: &'static str,
+    ) -> (Box<dyn State>, StepResult) {
+        match ctx.schedule_retry(RetryPhase::Dns, tsc) {
+            Some(retry) => {
+                self.retry_at = Some(retry.resume_tsc);
+                self.query_started = false;
+                (self, StepResult::Continue)
+            }
+            None => (Box::new(FailedState::new(reason)), StepResult::Failed(reason)),
         }
     }
 }
@@ -45,117 +62,104 @@ impl Default for DnsState {
     }
 }
 
-impl<D: NetworkDriver> State<D> for DnsState {
+impl State for DnsState {
     fn step(
         mut self: Box<Self>,
         ctx: &mut Context<'_>,
-        iface: &mut Interface,
-        sockets: &mut SocketSet<'_>,
-        _adapter: &mut SmoltcpAdapter<'_, D>,
-        _now: Instant,
+        stack: &mut dyn NetStack,
         tsc: u64,
-    ) -> (Box<dyn State<D>>, StepResult) {
+    ) -> (Box<dyn State>, StepResult) {
         if self.start_tsc == 0 {
             self.start_tsc = tsc;
             serial::println("[DNS] Starting resolution...");
         }
 
+        // Waiting out a backoff delay; rotate to the next DHCP-provided server
+        if let Some(resume) = self.retry_at {
+            if tsc < resume {
+                return (self, StepResult::Continue);
+            }
+            self.retry_at = None;
+            self.start_tsc = tsc;
+            let count = ctx.dns_servers.iter().filter(|s| s.is_some()).count();
+            if count > 1 {
+                self.server_idx = (self.server_idx + 1) % count;
+                serial::println("[DNS] Switching server");
+            }
+        }
+
         // Check timeout
         let elapsed = tsc.saturating_sub(self.start_tsc);
         let timeout = ctx.timeouts.dns();
         if elapsed > timeout {
             serial::println("[DNS] ERROR: Timeout");
-            return (Box::new(FailedState::new("DNS timeout")), StepResult::Failed("DNS timeout"));
+            stack.dns_cancel();
+            return self.retry_or_fail(ctx, tsc, "DNS timeout");
         }
 
-        let hostname = ctx.url_host;
+        let hostname = ctx.connect_host;
 
-        // Try parsing as IP address first
+        // Literal addresses and static mappings need no server
+        if let Some(literal) = hostname.strip_prefix('[').and_then(|h| h.strip_suffix(']')) {
+            return match parse_ipv6_literal(literal) {
+                Some(ip) => resolved(ctx, ip, "[DNS] Host is IPv4-mapped IPv6: "),
+                None => {
+                    serial::println("[DNS] ERROR: IPv6 hosts are not supported");
+                    (Box::new(FailedState::new("IPv6 not supported")), StepResult::Failed("IPv6 host"))
+                }
+            };
+        }
         if let Some(ip) = parse_ipv4(hostname) {
-            serial::print("[DNS] Host is IP: ");
-            serial::print_ipv4(&ip.0);
-            serial::println("");
-            ctx.resolved_ip = Some(IpAddress::Ipv4(ip));
-            serial::println("[DNS] -> Connect");
-            return (Box::new(ConnectState::new()), StepResult::Transition);
+            return resolved(ctx, ip, "[DNS] Host is IP: ");
+        }
+        if let Some(ip) = lookup_host(ctx.config.hosts, hostname) {
+            return resolved(ctx, ip, "[DNS] Host from hosts table: ");
         }
 
-        // Need DNS resolution
-        serial::print("[DNS] Resolving: ");
-        serial::println(hostname);
-
-        // Get DNS server from DHCP
-        let dns_server = match ctx.dns_servers.iter().find_map(|s| *s) {
-            Some(IpAddress::Ipv4(ip)) => ip,
-            _ => {
-                serial::println("[DNS] ERROR: No DNS server from DHCP");
-                return (Box::new(FailedState::new("no DNS server")), StepResult::Failed("no DNS"));
-            }
-        };
+        // Start query if not started
+        if !self.query_started {
+            // Get DNS server from DHCP
+            let dns_server = match ctx.dns_servers.iter().flatten().nth(self.server_idx) {
+                Some(ip) => *ip,
+                None => {
+                    serial::println("[DNS] ERROR: No DNS server from DHCP");
+                    return (Box::new(FailedState::new("no DNS server")), StepResult::Failed("no DNS"));
+                }
+            };
 
-        // Create DNS socket if not done yet
-        if !self.dns_handle_added {
+            serial::print("[DNS] Resolving: ");
+            serial::println(hostname);
             serial::print("[DNS] Using server: ");
-            serial::print_ipv4(&dns_server.0);
+            serial::print_ipv4(&dns_server.octets());
             serial::println("");
 
-            let dns_servers: &[IpAddress] = &[IpAddress::Ipv4(dns_server)];
-            let dns_socket = unsafe { DnsSocket::new(dns_servers, &mut DNS_QUERIES[..]) };
-            let handle = sockets.add(dns_socket);
-            ctx.dns_handle = Some(handle);
-            self.dns_handle_added = true;
-        }
-
-        let dns_handle = match ctx.dns_handle {
-            Some(h) => h,
-            None => {
-                serial::println("[DNS] ERROR: No DNS socket");
-                return (Box::new(FailedState::new("no DNS socket")), StepResult::Failed("no socket"));
-            }
-        };
-
-        // Start query if not started
-        if self.query_handle.is_none() {
-            let dns = sockets.get_mut::<DnsSocket>(dns_handle);
-            match dns.start_query(iface.context(), hostname, DnsQueryType::A) {
-                Ok(handle) => {
+            match stack.dns_query(hostname, dns_server) {
+                Ok(()) => {
                     serial::println("[DNS] Query sent");
-                    self.query_handle = Some(handle);
+                    self.query_started = true;
                 }
                 Err(_) => {
                     serial::println("[DNS] ERROR: Query start failed");
-                    return (Box::new(FailedState::new("DNS query failed")), StepResult::Failed("query"));
+                    return self.retry_or_fail(ctx, tsc, "DNS query failed");
                 }
             }
             return (self, StepResult::Continue);
         }
 
         // Poll for result
-        let query_handle = self.query_handle.unwrap();
-        let dns = sockets.get_mut::<DnsSocket>(dns_handle);
-        match dns.get_query_result(query_handle) {
-            Ok(addrs) => {
-                // Find first IPv4 address
-                for addr in addrs {
-                    if let IpAddress::Ipv4(ip) = addr {
-                        serial::print("[DNS] Resolved: ");
-                        serial::print_ipv4(&ip.0);
-                        serial::println("");
-                        ctx.resolved_ip = Some(IpAddress::Ipv4(ip));
-                        serial::println("[DNS] -> Connect");
-                        return (Box::new(ConnectState::new()), StepResult::Transition);
-                    }
-                }
+        match stack.dns_status() {
+            DnsStatus::Resolved(ip) => resolved(ctx, ip, "[DNS] Resolved: "),
+            DnsStatus::NoAddress => {
                 serial::println("[DNS] ERROR: No IPv4 in response");
                 (Box::new(FailedState::new("no IPv4")), StepResult::Failed("no IPv4"))
             }
-            Err(GetQueryResultError::Pending) => {
+            DnsStatus::Pending => {
                 // Still waiting
                 (self, StepResult::Continue)
             }
-            Err(GetQueryResultError::Failed) => {
+            DnsStatus::Failed => {
                 serial::println("[DNS] ERROR: Query failed");
-                (Box::new(FailedState::new("DNS failed")), StepResult::Failed("DNS failed"))
+                self.retry_or_fail(ctx, tsc, "DNS failed")
             }
         }
     }
@@ -165,8 +169,37 @@ impl<D: NetworkDriver> State<D> for DnsState {
     }
 }
 
+/// Record `ip` as the server's address and move on to Connect.
+fn resolved(ctx: &mut Context<'_>, ip: Ipv4Addr, source: &str) -> (Box<dyn State>, StepResult) {
+    serial::print(source);
+    serial::print_ipv4(&ip.octets());
+    serial::println("");
+    ctx.resolved_ip = Some(ip);
+    serial::println("[DNS] -> Connect");
+    (Box::new(ConnectState::new()), StepResult::Transition)
+}
+
+/// IPv4 address of an IPv6 literal (without brackets), if it is an
+/// IPv4-mapped one (`::ffff:a.b.c.d`).
+pub fn parse_ipv6_literal(s: &str) -> Option<Ipv4Addr> {
+    s.parse::<Ipv6Addr>().ok()?.to_ipv4_mapped()
+}
+
+/// Look `host` up in `hosts`, text in `/etc/hosts` format: one
+/// "address name [alias...]" entry per line, `#` starting a comment.
+/// Names match case-insensitively and the first entry wins. IPv6
+/// entries are skipped.
+pub fn lookup_host(hosts: &str, host: &str) -> Option<Ipv4Addr> {
+    hosts.lines().find_map(|line| {
+        let line = line.split('#').next().unwrap_or("");
+        let mut fields = line.split_ascii_whitespace();
+        let ip = parse_ipv4(fields.next()?)?;
+        fields.any(|name| name.eq_ignore_ascii_case(host)).then_some(ip)
+    })
+}
+
 /// Parse IPv4 address from dotted decimal string.
-pub fn parse_ipv4(s: &str) -> Option<Ipv4Address> {
+pub fn parse_ipv4(s: &str) -> Option<Ipv4Addr> {
     let bytes = s.as_bytes();
     let mut octets = [0u8; 4];
     let mut octet_idx = 0;
@@ -198,5 +231,34 @@ pub fn parse_ipv4(s: &str) -> Option<Ipv4Address> {
     }
     octets[3] = current as u8;
 
-    Some(Ipv4Address::new(octets[0], octets[1], octets[2], octets[3]))
+    Some(Ipv4Addr::from(octets))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_host() {
+        let hosts = "# lab mirrors\n\
+                     10.0.0.5  mirror.internal  mirror # primary\n\
+                     fd00::5   v6.internal\n\
+                     10.0.0.6\tMIRROR.internal backup.internal\n";
+        assert_eq!(lookup_host(hosts, "mirror.internal"), Some(Ipv4Addr::new(10, 0, 0, 5)));
+        assert_eq!(lookup_host(hosts, "Mirror"), Some(Ipv4Addr::new(10, 0, 0, 5)));
+        assert_eq!(lookup_host(hosts, "backup.internal"), Some(Ipv4Addr::new(10, 0, 0, 6)));
+        assert_eq!(lookup_host(hosts, "v6.internal"), None);
+        assert_eq!(lookup_host(hosts, "primary"), None);
+        assert_eq!(lookup_host("", "mirror.internal"), None);
+    }
+
+    #[test]
+    fn test_ip_literals() {
+        assert_eq!(parse_ipv4("192.168.1.10"), Some(Ipv4Addr::new(192, 168, 1, 10)));
+        assert_eq!(parse_ipv4("192.168.1"), None);
+        assert_eq!(parse_ipv4("256.1.1.1"), None);
+        assert_eq!(parse_ipv6_literal("::ffff:10.0.0.5"), Some(Ipv4Addr::new(10, 0, 0, 5)));
+        assert_eq!(parse_ipv6_literal("fe80::1"), None);
+        assert_eq!(parse_ipv6_literal("mirror.internal"), None);
+    }
 }
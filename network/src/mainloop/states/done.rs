@@ -2,22 +2,44 @@
 
 extern crate alloc;
 use alloc::boxed::Box;
+use alloc::format;
 
-use smoltcp::iface::{Interface, SocketSet};
-use smoltcp::time::Instant;
+use morpheus_core::iso::{
+    BootRequest, ExitReport, BOOT_REQUEST_PATH, BOOT_REQUEST_SIZE, EXIT_REPORT_PATH,
+    EXIT_REPORT_SIZE,
+};
 
+use crate::dma::HeapDmaBuffer;
 use crate::driver::block_traits::BlockDriver;
-use crate::driver::traits::NetworkDriver;
-use crate::mainloop::adapter::SmoltcpAdapter;
+use crate::driver::unified_block_io::UnifiedBlockIo;
+use crate::mainloop::netstack::NetStack;
 use crate::mainloop::context::Context;
+use crate::mainloop::post_actions::{Finish, PostAction, SUCCESS_MARKER_PATH};
 use crate::mainloop::serial;
 use crate::mainloop::state::{State, StepResult};
+use crate::power;
+
+/// Local UDP port the status report is sent from.
+const REPORT_LOCAL_PORT: u16 = 49_170;
+
+/// How long the stack keeps polling after queueing the report, so ARP
+/// can resolve and the datagram leaves before the machine goes down.
+const REPORT_LINGER_MS: u64 = 1000;
+
+/// DMA buffer size for the ESP writes.
+const FAT32_DMA_BUFFER_SIZE: usize = 64 * 1024;
 
 /// Success terminal state.
+///
+/// Runs the configured [`PostActions`](crate::mainloop::PostActions),
+/// then powers off or reboots.
 pub struct DoneState {
     logged: bool,
     flushed: bool,
-    rebooting: bool,
+    /// TSC until which the report may still be in flight (0 = not sent)
+    report_until: u64,
+    report_done: bool,
+    finishing: bool,
 }
 
 impl DoneState {
@@ -25,65 +47,30 @@ impl DoneState {
         Self {
             logged: false,
             flushed: false,
-            rebooting: false,
+            report_until: 0,
+            report_done: false,
+            finishing: false,
         }
     }
 
-    /// Initiate system reboot.
-    #[cfg(target_arch = "x86_64")]
-    fn reboot() {
+    /// End the session. Never returns.
+    fn finish(finish: Finish) {
         serial::println("");
         serial::println("=====================================");
         serial::println("  ISO Download Complete!");
         serial::println("=====================================");
         serial::println("");
-        serial::println("Initiating safe system reboot...");
-
-        unsafe {
-            // 1) Keyboard controller reset (most compatible)
-            serial::println("[REBOOT] Using keyboard controller reset (0x64 -> 0xFE)");
-            core::arch::asm!(
-                "out dx, al",
-                in("dx") 0x64u16,
-                in("al") 0xFEu8,
-                options(nomem, nostack)
-            );
-
-            // Wait for reboot
-            for _ in 0..50_000_000 {
-                core::hint::spin_loop();
+        match finish {
+            Finish::Reboot => {
+                serial::println("Initiating safe system reboot...");
+                power::reset_system();
             }
-
-            // 2) Fallback: Port 0xCF9 reset (modern systems)
-            serial::println("[REBOOT] Fallback: Port 0xCF9 reset");
-            core::arch::asm!(
-                "out dx, al",
-                in("dx") 0xCF9u16,
-                in("al") 0x06u8,
-                options(nomem, nostack)
-            );
-
-            // Wait again
-            for _ in 0..50_000_000 {
-                core::hint::spin_loop();
-            }
-
-            // 3) If reboot failed, halt gracefully
-            serial::println("[REBOOT] Reboot methods failed - halting system");
-            serial::println("[REBOOT] Please manually power cycle the system");
-            loop {
-                core::arch::asm!("hlt", options(nomem, nostack));
+            Finish::PowerOff => {
+                serial::println("Powering off...");
+                power::power_off();
             }
         }
     }
-
-    #[cfg(not(target_arch = "x86_64"))]
-    fn reboot() {
-        serial::println("[REBOOT] Reboot not supported on this architecture");
-        loop {
-            core::hint::spin_loop();
-        }
-    }
 }
 
 impl Default for DoneState {
@@ -92,36 +79,19 @@ impl Default for DoneState {
     }
 }
 
-impl<D: NetworkDriver> State<D> for DoneState {
+impl State for DoneState {
     fn step(
         mut self: Box<Self>,
         ctx: &mut Context<'_>,
-        _iface: &mut Interface,
-        _sockets: &mut SocketSet<'_>,
-        _adapter: &mut SmoltcpAdapter<'_, D>,
-        _now: Instant,
-        _tsc: u64,
-    ) -> (Box<dyn State<D>>, StepResult) {
-        // Flush disk before reporting done
+        stack: &mut dyn NetStack,
+        tsc: u64,
+    ) -> (Box<dyn State>, StepResult) {
+        // ESP files first, so the flush below covers them too
         if !self.flushed {
             self.flushed = true;
-            if let Some(ref mut blk) = ctx.blk_device {
-                serial::println("[DISK] Syncing disk cache...");
-                match blk.flush() {
-                    Ok(()) => serial::println("[OK] Disk cache synced"),
-                    Err(e) => {
-                        serial::print("[WARN] Disk sync: ");
-                        serial::println(match e {
-                            crate::driver::block_traits::BlockError::Unsupported => {
-                                "not supported (assuming durable)"
-                            }
-                            crate::driver::block_traits::BlockError::Timeout => "timeout",
-                            crate::driver::block_traits::BlockError::DeviceError => "device error",
-                            _ => "unknown",
-                        });
-                    }
-                }
-            }
+            write_esp_files(ctx);
+            sync_disk(ctx);
+            release_removable(ctx);
         }
 
         if !self.logged {
@@ -139,10 +109,30 @@ impl<D: NetworkDriver> State<D> for DoneState {
             self.logged = true;
         }
 
-        // Trigger reboot (never returns)
-        if !self.rebooting {
-            self.rebooting = true;
-            Self::reboot();
+        if !self.report_done {
+            match ctx.config.post_actions.report_to() {
+                None => self.report_done = true,
+                Some((server, port)) if self.report_until == 0 => {
+                    self.report_until = tsc + ctx.tsc_freq / 1000 * REPORT_LINGER_MS;
+                    if !send_report(ctx, stack, server, port) {
+                        self.report_done = true;
+                    }
+                }
+                Some(_) if tsc < self.report_until => return (self, StepResult::Continue),
+                Some(_) => {
+                    stack.udp_close();
+                    self.report_done = true;
+                }
+            }
+            if !self.report_done {
+                return (self, StepResult::Continue);
+            }
+        }
+
+        // Power off or reboot (never returns)
+        if !self.finishing {
+            self.finishing = true;
+            Self::finish(ctx.config.post_actions.finish());
         }
 
         // Unreachable, but required for type system
@@ -154,6 +144,174 @@ impl<D: NetworkDriver> State<D> for DoneState {
     }
 }
 
+/// Queue the status report. Returns false if it could not be sent.
+fn send_report(
+    ctx: &Context<'_>,
+    stack: &mut dyn NetStack,
+    server: core::net::Ipv4Addr,
+    port: u16,
+) -> bool {
+    let message = format!(
+        "MORPHEUSX DONE name={} bytes={} written={}\n",
+        ctx.config.iso_name, ctx.bytes_downloaded, ctx.bytes_written
+    );
+    serial::print("[DONE] Reporting to ");
+    serial::print_ipv4(&server.octets());
+    serial::println("");
+    let sent = stack
+        .udp_bind(REPORT_LOCAL_PORT)
+        .and_then(|()| stack.udp_send(message.as_bytes(), server, port));
+    if sent.is_err() {
+        serial::println("[WARN] Status report not sent");
+        stack.udp_close();
+    }
+    sent.is_ok()
+}
+
+/// Write the success marker and boot request the post actions ask for.
+///
+/// Both are best effort: the ISO is stored either way.
+fn write_esp_files(ctx: &mut Context<'_>) {
+    let actions = ctx.config.post_actions;
+    let marker = actions.as_slice().contains(&PostAction::SuccessMarker);
+    let boot_once = actions.boot_request();
+    if !marker && boot_once.is_none() {
+        return;
+    }
+
+    let esp_start_lba = ctx.config.esp_start_lba;
+    let iso_name = ctx.config.iso_name;
+    let size = ctx.bytes_written.max(ctx.bytes_downloaded);
+    let timeouts = ctx.timeouts.block();
+    let (Some(blk), true) = (ctx.esp_blk(), esp_start_lba > 0) else {
+        serial::println("[WARN] No ESP, skipping marker and boot request");
+        return;
+    };
+    let Some(mut dma) = HeapDmaBuffer::new(FAT32_DMA_BUFFER_SIZE) else {
+        serial::println("[WARN] DMA buffer allocation failed, skipping ESP files");
+        return;
+    };
+    let (dma_buffer, dma_buffer_phys) = dma.split_phys();
+    let Ok(mut adapter) = UnifiedBlockIo::new(blk, dma_buffer, dma_buffer_phys, timeouts) else {
+        serial::println("[WARN] BlockIo adapter failed, skipping ESP files");
+        return;
+    };
+    let _ = morpheus_core::fs::create_directory(&mut adapter, esp_start_lba, "/.iso");
+
+    if marker {
+        let text = format!("{}\n{}\n", iso_name, size);
+        match morpheus_core::fs::replace_file(
+            &mut adapter,
+            esp_start_lba,
+            SUCCESS_MARKER_PATH,
+            text.as_bytes(),
+        ) {
+            Ok(()) => serial::println("[OK] Success marker written"),
+            Err(_) => serial::println("[WARN] Success marker write failed"),
+        }
+    }
+
+    if let Some(once) = boot_once {
+        let mut buffer = [0u8; BOOT_REQUEST_SIZE];
+        let written = BootRequest::new(iso_name, once)
+            .serialize(&mut buffer)
+            .is_ok()
+            && morpheus_core::fs::replace_file(
+                &mut adapter,
+                esp_start_lba,
+                BOOT_REQUEST_PATH,
+                &buffer,
+            )
+            .is_ok();
+        match (written, once) {
+            (true, true) => serial::println("[OK] ISO will boot on the next start"),
+            (true, false) => serial::println("[OK] ISO set as default boot entry"),
+            (false, _) => serial::println("[WARN] Boot request write failed"),
+        }
+    }
+}
+
+/// Leave the session's exit report on the ESP for the bootloader's
+/// dashboard, then flush the ESP disk.
+///
+/// Best effort like the other ESP files; the failure and abort paths end
+/// the session right after this, so it does its own flush.
+pub(crate) fn write_exit_report(ctx: &mut Context<'_>, report: &ExitReport) {
+    let esp_start_lba = ctx.config.esp_start_lba;
+    let timeouts = ctx.timeouts.block();
+    let (Some(blk), true) = (ctx.esp_blk(), esp_start_lba > 0) else {
+        return;
+    };
+    let mut buffer = [0u8; EXIT_REPORT_SIZE];
+    if report.serialize(&mut buffer).is_err() {
+        return;
+    }
+    let Some(mut dma) = HeapDmaBuffer::new(FAT32_DMA_BUFFER_SIZE) else {
+        serial::println("[WARN] DMA buffer allocation failed, skipping exit report");
+        return;
+    };
+    let (dma_buffer, dma_buffer_phys) = dma.split_phys();
+    let written = match UnifiedBlockIo::new(blk, dma_buffer, dma_buffer_phys, timeouts) {
+        Ok(mut adapter) => {
+            let _ = morpheus_core::fs::create_directory(&mut adapter, esp_start_lba, "/.iso");
+            morpheus_core::fs::replace_file(&mut adapter, esp_start_lba, EXIT_REPORT_PATH, &buffer)
+                .is_ok()
+        }
+        Err(_) => false,
+    };
+    if !written {
+        serial::println("[WARN] Exit report write failed");
+        return;
+    }
+    serial::println("[OK] Exit report written");
+    if let Some(blk) = ctx.esp_blk() {
+        let _ = blk.flush();
+    }
+}
+
+/// Flush the write cache of the target disk and a separate ESP disk,
+/// logging the outcome.
+pub(super) fn sync_disk(ctx: &mut Context<'_>) {
+    for blk in [ctx.blk_device.as_mut(), ctx.esp_device.as_mut()]
+        .into_iter()
+        .flatten()
+    {
+        serial::println("[DISK] Syncing disk cache...");
+        match blk.flush() {
+            Ok(()) => serial::println("[OK] Disk cache synced"),
+            Err(e) => {
+                serial::print("[WARN] Disk sync: ");
+                serial::println(match e {
+                    crate::driver::block_traits::BlockError::Unsupported => {
+                        "not supported (assuming durable)"
+                    }
+                    crate::driver::block_traits::BlockError::Timeout => "timeout",
+                    crate::driver::block_traits::BlockError::DeviceError => "device error",
+                    _ => "unknown",
+                });
+            }
+        }
+    }
+}
+
+/// Spin down removable media after the write, so the user can unplug it
+/// once the ISO is on it.
+fn release_removable(ctx: &mut Context<'_>) {
+    let Some(ref mut blk) = ctx.blk_device else {
+        return;
+    };
+    if !blk.info().removable {
+        return;
+    }
+    match blk.spin_down() {
+        Ok(()) => {
+            serial::print("[OK] Safe to remove: ");
+            serial::println(blk.info().identity.model());
+        }
+        Err(_) => serial::println("[WARN] Spin-down failed, wait for the reboot before unplugging"),
+    }
+}
+
 /// Failure terminal state.
 pub struct FailedState {
     reason: &'static str,
@@ -174,16 +332,13 @@ impl FailedState {
     }
 }
 
-impl<D: NetworkDriver> State<D> for FailedState {
+impl State for FailedState {
     fn step(
         mut self: Box<Self>,
         _ctx: &mut Context<'_>,
-        _iface: &mut Interface,
-        _sockets: &mut SocketSet<'_>,
-        _adapter: &mut SmoltcpAdapter<'_, D>,
-        _now: Instant,
+        _stack: &mut dyn NetStack,
         _tsc: u64,
-    ) -> (Box<dyn State<D>>, StepResult) {
+    ) -> (Box<dyn State>, StepResult) {
         if !self.logged {
             serial::println("=================================");
             serial::println("        DOWNLOAD FAILED          ");
@@ -2,29 +2,43 @@
 //!
 //! Creates GPT partition for ISO storage, verifying no overlap with
 //! existing partitions. Handles automatic relocation if needed.
+//!
+//! First disk touch of a session, so it also opens the intent journal and
+//! cleans up after an interrupted earlier session.
+//!
+//! Re-downloading a stored ISO writes over its old chunk partition when
+//! the new image fits, and deletes the old partitions otherwise, so a
+//! re-download never leaks space.
+//!
+//! A partial download of the same ISO is kept instead: its partition is
+//! used as it is and the HTTP state asks the server for the rest.
 
 extern crate alloc;
 use alloc::boxed::Box;
 
-use smoltcp::iface::{Interface, SocketSet};
-use smoltcp::time::Instant;
 
 use crate::device::UnifiedBlockDevice;
-use crate::driver::traits::NetworkDriver;
+use crate::dma::HeapDmaBuffer;
+use crate::driver::block_traits::BlockDriver;
 use crate::driver::unified_block_io::UnifiedBlockIo;
-use crate::mainloop::adapter::SmoltcpAdapter;
+use crate::mainloop::netstack::NetStack;
 use crate::mainloop::context::Context;
+#[cfg(feature = "fat32_manifest")]
+use crate::mainloop::context::{Checkpoint, Resume};
+use crate::mainloop::journal;
 use crate::mainloop::serial;
 use crate::mainloop::state::{State, StepResult};
-use crate::transfer::disk::{DiskError, GptOps};
+use crate::time::TimeoutConfig;
+#[cfg(feature = "fat32_manifest")]
+use crate::transfer::disk::{ResumePoint, ReusePlan};
+use crate::transfer::disk::{DiskError, GptOps, JournalEntry, JournalStep, PlacementPolicy};
+use morpheus_core::iso::{raw_manifest_lba, RAW_MANIFEST_SECTORS};
 
 use super::{FailedState, LinkWaitState};
 
 /// DMA buffer size for GPT operations.
 const GPT_DMA_BUFFER_SIZE: usize = 64 * 1024;
 
-/// Static DMA buffer for GPT operations.
-static mut GPT_DMA_BUFFER: [u8; GPT_DMA_BUFFER_SIZE] = [0u8; GPT_DMA_BUFFER_SIZE];
 
 /// GPT preparation state.
 pub struct GptPrepState {
@@ -41,98 +55,181 @@ impl GptPrepState {
     }
 
     /// Verify requested range is free, find alternative if not.
+    ///
+    /// A requested start of 0 means "anywhere": the placement policy
+    /// picks the range straight away.
     fn verify_or_find_space(
         &self,
         blk: &mut UnifiedBlockDevice,
         requested_start: u64,
         requested_end: u64,
+        policy: PlacementPolicy,
+        timeouts: TimeoutConfig,
     ) -> Result<(u64, u64), &'static str> {
-        let (dma_buffer, dma_buffer_phys) = unsafe {
-            let buf = core::slice::from_raw_parts_mut(
-                (&raw mut GPT_DMA_BUFFER).cast::<u8>(),
-                GPT_DMA_BUFFER_SIZE,
-            );
-            let phys = (&raw const GPT_DMA_BUFFER).cast::<u8>() as u64;
-            (buf, phys)
-        };
-        let timeout_ticks = 100_000_000u64;
+        let mut dma = HeapDmaBuffer::new(GPT_DMA_BUFFER_SIZE).ok_or("DMA buffer allocation failed")?;
+        let (dma_buffer, dma_buffer_phys) = dma.split_phys();
 
-        let mut adapter = match UnifiedBlockIo::new(blk, dma_buffer, dma_buffer_phys, timeout_ticks) {
+        let mut adapter = match UnifiedBlockIo::new(blk, dma_buffer, dma_buffer_phys, timeouts) {
             Ok(a) => a,
             Err(_) => return Err("failed to create BlockIo adapter"),
         };
 
         // Check if requested range is free
-        match GptOps::verify_range_free(&mut adapter, requested_start, requested_end) {
-            Ok(true) => {
-                serial::println("[GPT] ✓ Range verified free");
-                return Ok((requested_start, requested_end));
+        if requested_start > 0 {
+            match GptOps::verify_range_free(&mut adapter, requested_start, requested_end) {
+                Ok(true) => {
+                    serial::println("[GPT] ✓ Range verified free");
+                    return Ok((requested_start, requested_end));
+                }
+                Ok(false) => {
+                    serial::println("[GPT] WARNING: Requested range overlaps existing partition");
+                    serial::print("[GPT] Requested: ");
+                    serial::print_hex(requested_start);
+                    serial::print(" - ");
+                    serial::print_hex(requested_end);
+                    serial::println("");
+                }
+                Err(e) => {
+                    serial::print("[GPT] ERROR: Could not verify range: ");
+                    serial::println(match e {
+                        DiskError::IoError => "IO error",
+                        DiskError::InvalidGpt => "Invalid GPT",
+                        _ => "Unknown error",
+                    });
+                    return Err("range verification failed");
+                }
             }
-            Ok(false) => {
-                serial::println("[GPT] WARNING: Requested range overlaps existing partition");
-                serial::print("[GPT] Requested: ");
-                serial::print_hex(requested_start);
+        }
+
+        // Range not free (or none requested), place by policy
+        let needed_size = requested_end - requested_start + 1;
+        serial::print("[GPT] Searching for free space (");
+        serial::print(policy.name());
+        serial::println(")...");
+        match GptOps::find_free_space(&mut adapter, policy, needed_size) {
+            Ok((start, end)) => {
+                serial::print("[GPT] ✓ Found suitable free space: ");
+                serial::print_hex(start);
                 serial::print(" - ");
-                serial::print_hex(requested_end);
-                serial::println("");
+                serial::print_hex(end);
+                serial::print(" (");
+                serial::print_u64(needed_size * 512 / (1024 * 1024 * 1024));
+                serial::println(" GB)");
+                Ok((start, end))
             }
             Err(e) => {
-                serial::print("[GPT] ERROR: Could not verify range: ");
+                serial::print("[GPT] ERROR: Could not find free space: ");
                 serial::println(match e {
                     DiskError::IoError => "IO error",
                     DiskError::InvalidGpt => "Invalid GPT",
+                    DiskError::NoFreeSpace => "No gap large enough",
                     _ => "Unknown error",
                 });
-                return Err("range verification failed");
+                Err("no free space found")
             }
         }
+    }
 
-        // Range not free, find alternative
-        serial::println("[GPT] Searching for alternative free space...");
-        match GptOps::find_free_space(&mut adapter) {
-            Ok((free_start, free_end)) => {
-                let free_size = free_end - free_start + 1;
-                let needed_size = requested_end - requested_start + 1;
-
-                if free_size >= needed_size {
-                    serial::print("[GPT] ✓ Found suitable free space: ");
-                    serial::print_hex(free_start);
-                    serial::print(" - ");
-                    serial::print_hex(free_end);
-                    serial::print(" (");
-                    serial::print_u32((free_size * 512 / (1024 * 1024 * 1024)) as u32);
-                    serial::println(" GB)");
-
-                    // Align to 1MB boundary (2048 sectors)
-                    let aligned_start = ((free_start + 2047) / 2048) * 2048;
-                    let aligned_end = aligned_start + needed_size - 1;
+    /// Look for a partial download of the ISO to continue.
+    #[cfg(feature = "fat32_manifest")]
+    fn find_resume(
+        &self,
+        blk: &mut UnifiedBlockDevice,
+        esp_start_lba: u64,
+        iso_name: &str,
+        sectors_needed: u64,
+        requested_start: u64,
+        timeouts: TimeoutConfig,
+    ) -> Option<ResumePoint> {
+        if esp_start_lba == 0 {
+            return None;
+        }
+        let mut dma = HeapDmaBuffer::new(GPT_DMA_BUFFER_SIZE)?;
+        let (dma_buffer, dma_buffer_phys) = dma.split_phys();
+        let mut adapter = UnifiedBlockIo::new(blk, dma_buffer, dma_buffer_phys, timeouts).ok()?;
+
+        match ResumePoint::find(
+            &mut adapter,
+            esp_start_lba,
+            iso_name,
+            sectors_needed,
+            requested_start,
+        ) {
+            Ok(point) => point,
+            Err(_) => {
+                serial::println("[GPT] WARNING: Could not check for a partial download");
+                None
+            }
+        }
+    }
 
-                    serial::print("[GPT] Using aligned range: ");
-                    serial::print_hex(aligned_start);
-                    serial::print(" - ");
-                    serial::print_hex(aligned_end);
-                    serial::println("");
+    /// Release the stored copy of the ISO being downloaded again.
+    ///
+    /// Returns the partition to write over (first, last sector) if the old
+    /// one fits, and otherwise where the old copy started, as a placement
+    /// hint. Failures are logged and leave the old copy alone.
+    #[cfg(feature = "fat32_manifest")]
+    fn reuse_existing(
+        &self,
+        blk: &mut UnifiedBlockDevice,
+        esp_start_lba: u64,
+        iso_name: &str,
+        sectors_needed: u64,
+        requested_start: u64,
+        timeouts: TimeoutConfig,
+    ) -> (Option<(u64, u64)>, Option<u64>) {
+        if esp_start_lba == 0 {
+            return (None, None);
+        }
+        let Some(mut dma) = HeapDmaBuffer::new(GPT_DMA_BUFFER_SIZE) else {
+            return (None, None);
+        };
+        let (dma_buffer, dma_buffer_phys) = dma.split_phys();
+        let Ok(mut adapter) = UnifiedBlockIo::new(blk, dma_buffer, dma_buffer_phys, timeouts) else {
+            return (None, None);
+        };
 
-                    Ok((aligned_start, aligned_end))
-                } else {
-                    serial::print("[GPT] ERROR: Free space too small (");
-                    serial::print_u32((free_size * 512 / (1024 * 1024 * 1024)) as u32);
-                    serial::print(" GB < ");
-                    serial::print_u32((needed_size * 512 / (1024 * 1024 * 1024)) as u32);
-                    serial::println(" GB needed)");
-                    Err("insufficient free space")
-                }
+        let plan = match ReusePlan::find(
+            &mut adapter,
+            esp_start_lba,
+            iso_name,
+            sectors_needed,
+            requested_start,
+        ) {
+            Ok(Some(plan)) => plan,
+            Ok(None) => return (None, None),
+            Err(_) => {
+                serial::println("[GPT] WARNING: Could not check for a stored copy");
+                return (None, None);
             }
-            Err(e) => {
-                serial::print("[GPT] ERROR: Could not find free space: ");
-                serial::println(match e {
-                    DiskError::IoError => "IO error",
-                    DiskError::InvalidGpt => "Invalid GPT",
-                    DiskError::NoFreeSpace => "No free space",
-                    _ => "Unknown error",
-                });
-                Err("no free space found")
+        };
+
+        serial::println("[GPT] ISO already stored, releasing the old copy");
+        if plan.release(&mut adapter, esp_start_lba).is_err() {
+            serial::println("[GPT] WARNING: Could not release the old copy, leaving it");
+            return (None, None);
+        }
+        for part in plan.deletes() {
+            serial::print("[GPT] Deleted old partition: ");
+            serial::print_hex(part.start_lba);
+            serial::print(" - ");
+            serial::print_hex(part.end_lba);
+            serial::print(" (");
+            serial::print(part.type_name());
+            serial::println(")");
+        }
+        match plan.reused() {
+            Some(part) => {
+                serial::print("[GPT] Reusing old partition: ");
+                serial::print_hex(part.start_lba);
+                serial::print(" - ");
+                serial::print_hex(part.end_lba);
+                serial::print(" (");
+                serial::print(part.type_name());
+                serial::println(")");
+                (Some((part.start_lba, part.end_lba)), None)
             }
+            None => (None, plan.hint_lba()),
         }
     }
 
@@ -142,28 +239,22 @@ impl GptPrepState {
         blk: &mut UnifiedBlockDevice,
         start_sector: u64,
         end_sector: u64,
+        timeouts: TimeoutConfig,
     ) -> Result<[u8; 16], &'static str> {
         use morpheus_core::disk::gpt_ops::create_partition;
         use morpheus_core::disk::partition::PartitionType;
 
-        let (dma_buffer, dma_buffer_phys) = unsafe {
-            let buf = core::slice::from_raw_parts_mut(
-                (&raw mut GPT_DMA_BUFFER).cast::<u8>(),
-                GPT_DMA_BUFFER_SIZE,
-            );
-            let phys = (&raw const GPT_DMA_BUFFER).cast::<u8>() as u64;
-            (buf, phys)
-        };
-        let timeout_ticks = 100_000_000u64;
+        let mut dma = HeapDmaBuffer::new(GPT_DMA_BUFFER_SIZE).ok_or("DMA buffer allocation failed")?;
+        let (dma_buffer, dma_buffer_phys) = dma.split_phys();
 
-        let adapter = match UnifiedBlockIo::new(blk, dma_buffer, dma_buffer_phys, timeout_ticks) {
+        let adapter = match UnifiedBlockIo::new(blk, dma_buffer, dma_buffer_phys, timeouts) {
             Ok(a) => a,
             Err(_) => return Err("failed to create BlockIo adapter"),
         };
 
         serial::println("[GPT] Writing partition entry to GPT...");
 
-        match create_partition(adapter, PartitionType::BasicData, start_sector, end_sector) {
+        match create_partition(adapter, PartitionType::IsoChunk, start_sector, end_sector) {
             Ok(()) => {
                 serial::println("[GPT] ───────────────────────────────────────");
                 serial::println("[GPT] PARTITION CREATED SUCCESSFULLY");
@@ -173,7 +264,8 @@ impl GptPrepState {
                 serial::print(" - ");
                 serial::print_hex(end_sector);
                 serial::println("");
-                serial::println("[GPT] Type: Microsoft Basic Data");
+                serial::print("[GPT] Type: ");
+                serial::println(PartitionType::IsoChunk.name());
                 serial::println("[GPT] Status: Active in GPT partition table");
 
                 // Return placeholder GUID
@@ -205,16 +297,13 @@ impl Default for GptPrepState {
     }
 }
 
-impl<D: NetworkDriver> State<D> for GptPrepState {
+impl State for GptPrepState {
     fn step(
         mut self: Box<Self>,
         ctx: &mut Context<'_>,
-        _iface: &mut Interface,
-        _sockets: &mut SocketSet<'_>,
-        _adapter: &mut SmoltcpAdapter<'_, D>,
-        _now: Instant,
+        _stack: &mut dyn NetStack,
         _tsc: u64,
-    ) -> (Box<dyn State<D>>, StepResult) {
+    ) -> (Box<dyn State>, StepResult) {
         if self.completed {
             serial::println("[GPT] -> LinkWait");
             return (Box::new(LinkWaitState::new()), StepResult::Transition);
@@ -231,14 +320,32 @@ impl<D: NetworkDriver> State<D> for GptPrepState {
             }
 
             // Need block device
-            let blk = match &mut ctx.blk_device {
-                Some(b) => b,
-                None => {
-                    serial::println("[GPT] No block device available, skipping partition setup");
-                    self.completed = true;
-                    return (self, StepResult::Continue);
-                }
-            };
+            if ctx.blk_device.is_none() {
+                serial::println("[GPT] No block device available, skipping partition setup");
+                self.completed = true;
+                return (self, StepResult::Continue);
+            }
+
+            // The placement policy may rule this disk out
+            let info = ctx.blk_device.as_ref().unwrap().info();
+            let disk_preference = ctx.config.placement.disk;
+            if !disk_preference.allows(&info) {
+                serial::print("[GPT] Placement policy excludes this disk (");
+                serial::print(info.media.name());
+                serial::println(")");
+                return (
+                    Box::new(FailedState::new("disk excluded by placement policy")),
+                    StepResult::Failed("gpt"),
+                );
+            }
+            if !disk_preference.prefers(&info) {
+                serial::println("[GPT] WARNING: Preferred disk not present, using this one");
+            }
+
+            // Recover before looking for free space: a rolled back
+            // partition frees its range again
+            journal::open(ctx);
+            let blk = ctx.blk_device.as_mut().unwrap();
 
             serial::println("=================================");
             serial::println("   GPT PARTITION PREPARATION     ");
@@ -255,22 +362,90 @@ impl<D: NetworkDriver> State<D> for GptPrepState {
             } else {
                 8 * 1024 * 1024 * 1024 // Default 8GB max
             };
-            let sectors_needed = (size_bytes + 511) / 512;
-            let requested_end = ctx.config.target_start_sector + sectors_needed - 1;
+            // Plus room for the raw manifest copy at the partition's end
+            let sectors_needed = size_bytes.div_ceil(512) + RAW_MANIFEST_SECTORS;
+
+            // Manifests live on the ESP, so finding an earlier download
+            // only works when the ESP is on this disk
+            #[cfg(feature = "fat32_manifest")]
+            let esp_start_lba = if ctx.esp_device.is_some() {
+                0
+            } else {
+                ctx.config.esp_start_lba
+            };
+
+            // A partial download is carried on in its partition, which its
+            // partial manifest already claims: nothing to journal
+            #[cfg(feature = "fat32_manifest")]
+            if let Some(point) = self.find_resume(
+                blk,
+                esp_start_lba,
+                ctx.config.iso_name,
+                sectors_needed,
+                ctx.config.target_start_sector,
+                ctx.timeouts.block(),
+            ) {
+                let part = point.partition;
+                serial::print("[GPT] Partial download found: ");
+                serial::print_u64(point.offset / (1024 * 1024));
+                serial::print(" MB in ");
+                serial::print_hex(part.start_lba);
+                serial::print(" - ");
+                serial::print_hex(part.end_lba);
+                serial::println("");
+                ctx.actual_start_sector = part.start_lba;
+                ctx.raw_manifest_sector = raw_manifest_lba(part.end_lba);
+                ctx.resume = Some(Resume {
+                    at: Checkpoint {
+                        offset: point.offset,
+                        sha256_state: point.sha256_state,
+                    },
+                    last_modified: point.last_modified,
+                    etag_hash: point.etag_hash,
+                    skipped: point.skipped,
+                });
+                self.completed = true;
+                return (self, StepResult::Continue);
+            }
+
+            // A re-download takes over the old copy's space
+            #[cfg(feature = "fat32_manifest")]
+            let (reused, hint) = self.reuse_existing(
+                blk,
+                esp_start_lba,
+                ctx.config.iso_name,
+                sectors_needed,
+                ctx.config.target_start_sector,
+                ctx.timeouts.block(),
+            );
+            #[cfg(not(feature = "fat32_manifest"))]
+            let (reused, hint): (Option<(u64, u64)>, Option<u64>) = (None, None);
+
+            let requested_start = match ctx.config.target_start_sector {
+                0 => hint.unwrap_or(0),
+                start => start,
+            };
+            let requested_end = requested_start + sectors_needed - 1;
 
             serial::print("[GPT] Requested end sector: ");
             serial::print_hex(requested_end);
             serial::println("");
             serial::print("[GPT] Size: ");
-            serial::print_u32((size_bytes / (1024 * 1024 * 1024)) as u32);
+            serial::print_u64(size_bytes / (1024 * 1024 * 1024));
             serial::println(" GB");
 
             // Verify or find space
-            let (actual_start, actual_end) = match self.verify_or_find_space(
-                blk,
-                ctx.config.target_start_sector,
-                requested_end,
-            ) {
+            let placed = match reused {
+                Some(range) => Ok(range),
+                None => self.verify_or_find_space(
+                    blk,
+                    requested_start,
+                    requested_end,
+                    ctx.config.placement.policy,
+                    ctx.timeouts.block(),
+                ),
+            };
+            let (actual_start, actual_end) = match placed {
                 Ok((s, e)) => (s, e),
                 Err(msg) => {
                     serial::print("[GPT] ");
@@ -286,13 +461,35 @@ impl<D: NetworkDriver> State<D> for GptPrepState {
             // Update context with actual start sector
             ctx.actual_start_sector = actual_start;
 
+            journal::begin(
+                ctx,
+                JournalEntry::new(
+                    JournalStep::CreatePartition,
+                    ctx.config.iso_name,
+                    actual_start,
+                    actual_end,
+                    size_bytes,
+                ),
+            );
+
+            // The reused partition is already in the GPT; a rollback
+            // deletes it like a new one, its old manifest is gone already
+            if reused.is_some() {
+                ctx.raw_manifest_sector = raw_manifest_lba(actual_end);
+                journal::advance(ctx, JournalStep::WriteData);
+                self.completed = true;
+                return (self, StepResult::Continue);
+            }
+
             // Create partition - get block device again (borrow was released)
             let blk = ctx.blk_device.as_mut().unwrap();
-            match self.create_partition(blk, actual_start, actual_end) {
+            match self.create_partition(blk, actual_start, actual_end, ctx.timeouts.block()) {
                 Ok(uuid) => {
                     serial::println("[GPT] ISO partition created and claimed");
                     // Could store UUID in context if needed
                     let _ = uuid;
+                    ctx.raw_manifest_sector = raw_manifest_lba(actual_end);
+                    journal::advance(ctx, JournalStep::WriteData);
                 }
                 Err(msg) => {
                     serial::print("[GPT] WARNING: ");
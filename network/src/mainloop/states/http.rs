@@ -1,20 +1,65 @@
 //! HTTP download state — sends request, receives response, streams to disk.
+//!
+//! The download starts with a HEAD request on a connection of its own
+//! (see [`HttpState::preflight`]): the size it reports shows progress
+//! from the first byte and is checked against the partition before
+//! anything is written, Accept-Ranges says whether an interrupted
+//! download could resume, and Last-Modified / ETag go into the manifest
+//! for update checks. A server that won't answer HEAD only costs the
+//! attempt; the GET then runs without it.
+//!
+//! Guest networks answer every request with their login page until the
+//! user has signed in. Written to disk, that page would pass for a short
+//! download, so both responses are checked before any body byte is kept:
+//! a 511, a redirect that looks like a login page, or HTML where the image
+//! should be ends the download with "captive portal detected".
+//!
+//! The body is hashed as it arrives, on another core while it is copied
+//! into the disk writer's buffers when there is one, so the image's SHA-256
+//! is ready with the last byte: nothing is read back to compute it, in
+//! download-only mode as much as when writing to disk.
+//!
+//! A server that sends the image without a Content-Length uses the
+//! chunked encoding (or closes the connection after the last byte). The
+//! chunk framing is taken off as the bytes arrive, so only the image
+//! reaches the hash and the disk writer, and the last chunk marks the
+//! end of the download.
+//!
+//! When GPT prep found a partial download of the image, the GET asks for
+//! the rest (`Range: bytes=N-`, with an `If-Range` on the Last-Modified
+//! date) if the HEAD response says the server takes ranges and still has
+//! the same image. A 206 starting at N carries on the hash from the state
+//! the manifest recorded and writes on N bytes into the partition; a 200
+//! means the server sent all of it, and the download starts over.
+//!
+//! Every `CHECKPOINT_INTERVAL` the writes so far are made durable up to
+//! the last full chunk and a partial manifest records that point with
+//! the hash state there, so a reset costs at most that much again.
 
 extern crate alloc;
 use alloc::boxed::Box;
 
-use smoltcp::iface::{Interface, SocketHandle, SocketSet};
-use smoltcp::socket::tcp::Socket as TcpSocket;
-use smoltcp::time::Instant;
-
-use crate::driver::traits::NetworkDriver;
-use crate::mainloop::adapter::SmoltcpAdapter;
-use crate::mainloop::context::Context;
+use crate::device::UnifiedBlockDevice;
+use crate::http::headers::{
+    format_http_date, parse_content_range, parse_http_date, HTTP_DATE_LEN,
+};
+use crate::mainloop::context::{Checkpoint, Context, Preflight, Resume};
+use crate::mainloop::netstack::{NetStack, TcpStatus};
+use crate::mainloop::retry::RetryPhase;
 use crate::mainloop::serial;
 use crate::mainloop::state::{State, StepResult};
-use crate::mainloop::disk_writer::DiskWriter;
+use crate::mainloop::disk_writer::{DiskWriter, BUFFER_SIZE};
+use crate::offload::Task;
+use crate::time;
+use crate::transfer::chunked::ChunkedDecoder;
+use crate::transfer::sha256::Sha256;
+
+use super::manifest::{write_partial, ManifestConfig};
+use super::{ConnectState, DoneState, FailedState, ManifestState};
 
-use super::{DoneState, FailedState, ManifestState};
+/// Body bytes between partial manifests: at most this much is fetched
+/// again after a reset.
+const CHECKPOINT_INTERVAL: u64 = 256 * 1024 * 1024;
 
 /// HTTP download phase.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -29,10 +74,9 @@ pub enum HttpPhase {
 ///
 /// # Standalone Usage
 /// ```ignore
-/// let http_state = HttpState::with_request(tcp_handle, "GET", "/path", "host");
+/// let http_state = HttpState::with_request("GET", "/path", "host");
 /// ```
 pub struct HttpState {
-    tcp_handle: SocketHandle,
     phase: HttpPhase,
     start_tsc: u64,
     last_activity_tsc: u64,
@@ -45,22 +89,36 @@ pub struct HttpState {
     /// Response parsing state
     headers_complete: bool,
     content_length: Option<u64>,
-    chunked: bool,
+    /// Decoder for a chunked body, `None` if the body comes as it is
+    chunked: Option<ChunkedDecoder>,
+    /// Body bytes so far (the image, without chunk framing)
     bytes_received: u64,
     
     /// Header parsing buffer
     header_buf: [u8; 2048],
     header_len: usize,
     
+    /// Whether the start of the body has been checked for a portal page
+    body_checked: bool,
+
+    /// SHA-256 of the body received so far
+    body_hash: BodyHash,
+
+    /// Image offset the body starts at (non-zero when resuming)
+    offset: u64,
+    /// The request asked for a range from `ctx.resume`
+    range_requested: bool,
+    /// `ctx.bytes_written` at which to write the next partial manifest
+    next_checkpoint: u64,
+
     /// Disk writer for streaming to disk
     disk_writer: Option<DiskWriter>,
 }
 
 impl HttpState {
     /// Create HTTP state for download (uses path/host from context).
-    pub fn new(tcp_handle: SocketHandle) -> Self {
+    pub fn new() -> Self {
         Self {
-            tcp_handle,
             phase: HttpPhase::SendRequest,
             start_tsc: 0,
             last_activity_tsc: 0,
@@ -69,18 +127,23 @@ impl HttpState {
             host: None,
             headers_complete: false,
             content_length: None,
-            chunked: false,
+            chunked: None,
             bytes_received: 0,
             header_buf: [0u8; 2048],
             header_len: 0,
+            body_checked: false,
+            body_hash: BodyHash::new(0),
+            offset: 0,
+            range_requested: false,
+            next_checkpoint: CHECKPOINT_INTERVAL,
             disk_writer: None,
         }
     }
 
     /// Create HTTP state for download with disk writing enabled.
-    pub fn with_disk_write(tcp_handle: SocketHandle, start_sector: u64) -> Self {
+    pub fn with_disk_write(start_sector: u64, zeroed_target: bool, spot_check_every: u32) -> Self {
+        let writer = DiskWriter::new(start_sector).spot_checking(spot_check_every);
         Self {
-            tcp_handle,
             phase: HttpPhase::SendRequest,
             start_tsc: 0,
             last_activity_tsc: 0,
@@ -89,23 +152,34 @@ impl HttpState {
             host: None,
             headers_complete: false,
             content_length: None,
-            chunked: false,
+            chunked: None,
             bytes_received: 0,
             header_buf: [0u8; 2048],
             header_len: 0,
-            disk_writer: Some(DiskWriter::new(start_sector)),
+            body_checked: false,
+            body_hash: BodyHash::new(0),
+            offset: 0,
+            range_requested: false,
+            next_checkpoint: CHECKPOINT_INTERVAL,
+            disk_writer: Some(if zeroed_target {
+                writer.skipping_zeros()
+            } else {
+                writer
+            }),
+        }
+    }
+
+    /// Create HTTP state for the HEAD request ahead of the download.
+    pub fn preflight() -> Self {
+        Self {
+            method: "HEAD",
+            ..Self::new()
         }
     }
 
     /// Create HTTP state with explicit request (for standalone use).
-    pub fn with_request(
-        tcp_handle: SocketHandle,
-        method: &'static str,
-        path: &'static str,
-        host: &'static str,
-    ) -> Self {
+    pub fn with_request(method: &'static str, path: &'static str, host: &'static str) -> Self {
         Self {
-            tcp_handle,
             phase: HttpPhase::SendRequest,
             start_tsc: 0,
             last_activity_tsc: 0,
@@ -114,10 +188,15 @@ impl HttpState {
             host: Some(host),
             headers_complete: false,
             content_length: None,
-            chunked: false,
+            chunked: None,
             bytes_received: 0,
             header_buf: [0u8; 2048],
             header_len: 0,
+            body_checked: false,
+            body_hash: BodyHash::new(0),
+            offset: 0,
+            range_requested: false,
+            next_checkpoint: CHECKPOINT_INTERVAL,
             disk_writer: None,
         }
     }
@@ -136,23 +215,257 @@ impl HttpState {
     pub fn content_length(&self) -> Option<u64> {
         self.content_length
     }
+
+    /// Whether this is the HEAD request ahead of the download.
+    pub fn is_preflight(&self) -> bool {
+        self.method == "HEAD"
+    }
+
+    /// Record what the HEAD request learned (`None`: nothing) and
+    /// reconnect for the GET.
+    fn finish_preflight(
+        self: Box<Self>,
+        ctx: &mut Context<'_>,
+        stack: &mut dyn NetStack,
+        preflight: Option<Preflight>,
+    ) -> (Box<dyn State>, StepResult) {
+        // The server closes after the response; no need to wait for it
+        stack.tcp_abort();
+        let preflight = match preflight {
+            Some(preflight) => preflight,
+            None => {
+                serial::println("[HTTP] HEAD failed, downloading without preflight");
+                ctx.preflight = Some(Preflight::default());
+                serial::println("[HTTP] -> Connect");
+                return (Box::new(ConnectState::new()), StepResult::Transition);
+            }
+        };
+
+        serial::print("[HTTP] HEAD: ");
+        match preflight.content_length {
+            Some(len) => {
+                serial::print_u64(len / (1024 * 1024));
+                serial::print(" MB");
+            }
+            None => serial::print("size unknown"),
+        }
+        serial::print(if preflight.accept_ranges { ", resumable" } else { ", no ranges" });
+        if preflight.etag_hash != 0 {
+            serial::print(", ETag");
+        }
+        if preflight.last_modified != 0 {
+            serial::print(", Last-Modified");
+        }
+        serial::println("");
+
+        if let Some(len) = preflight.content_length {
+            ctx.content_length = Some(len);
+            // The partition from GPT prep ends at the raw manifest copy
+            if ctx.should_write_to_disk() && ctx.raw_manifest_sector > ctx.actual_start_sector {
+                let capacity = (ctx.raw_manifest_sector - ctx.actual_start_sector) * 512;
+                if len > capacity {
+                    serial::print("[HTTP] ERROR: Image does not fit the partition (");
+                    serial::print_u64(capacity / (1024 * 1024));
+                    serial::println(" MB)");
+                    return (
+                        Box::new(FailedState::new("image larger than partition")),
+                        StepResult::Failed("image too large"),
+                    );
+                }
+            }
+        }
+        ctx.preflight = Some(preflight);
+        serial::println("[HTTP] -> Connect");
+        (Box::new(ConnectState::new()), StepResult::Transition)
+    }
+
+    /// End the download on a response that came from a captive portal.
+    fn portal_detected(
+        self: Box<Self>,
+        stack: &mut dyn NetStack,
+        why: &str,
+    ) -> (Box<dyn State>, StepResult) {
+        stack.tcp_abort();
+        serial::print("[HTTP] ERROR: Captive portal detected: ");
+        serial::println(why);
+        serial::println("[HTTP] Sign in to the network from a browser, then retry the download");
+        (
+            Box::new(FailedState::new("captive portal detected")),
+            StepResult::Failed("captive portal"),
+        )
+    }
+
+    /// Read back a sampled chunk while no body data is waiting. `None` to
+    /// carry on, or the failure if the disk didn't return what was written.
+    fn spot_check(&mut self, ctx: &mut Context<'_>) -> Option<(Box<dyn State>, StepResult)> {
+        if let (Some(ref mut writer), Some(ref mut blk)) =
+            (&mut self.disk_writer, &mut ctx.blk_device) {
+            if !writer.spot_check(blk) {
+                serial::println("[HTTP] ERROR: Disk read-back check failed");
+                return Some((Box::new(FailedState::new("disk read-back")), StepResult::Failed("verify")));
+            }
+        }
+        None
+    }
+
+    /// The body framing is broken: what follows can't be told apart from
+    /// the image.
+    fn bad_chunk(self: Box<Self>) -> (Box<dyn State>, StepResult) {
+        serial::println("[HTTP] ERROR: Bad chunked encoding");
+        (
+            Box::new(FailedState::new("bad chunked encoding")),
+            StepResult::Failed("chunked"),
+        )
+    }
+
+    /// Whether the whole body is in: the last chunk of a chunked body, or
+    /// Content-Length bytes.
+    fn body_complete(&self) -> bool {
+        match &self.chunked {
+            Some(decoder) => decoder.is_done(),
+            None => self
+                .content_length
+                .is_some_and(|expected| self.bytes_received >= expected),
+        }
+    }
+
+    /// The partial download to continue with this GET, if any: the server
+    /// has to take ranges and still have the image it came from.
+    fn resume_from(&self, ctx: &mut Context<'_>) -> Option<Resume> {
+        if self.is_preflight() || self.disk_writer.is_none() {
+            return None;
+        }
+        let resume = ctx.resume?;
+        let preflight = ctx.preflight.unwrap_or_default();
+        if preflight.accept_ranges && resume.matches(&preflight) {
+            return Some(resume);
+        }
+        serial::println("[HTTP] Server can't continue the partial download, starting over");
+        ctx.resume = None;
+        None
+    }
+
+    /// Take up a 206 answer to the range request: carry on the hash and
+    /// the disk writes where the partial download stopped. `false` if it
+    /// isn't the range asked for, or not of the same image.
+    fn start_resumed(
+        &mut self,
+        ctx: &mut Context<'_>,
+        range_start: Option<u64>,
+        etag: u32,
+    ) -> bool {
+        let Some(resume) = ctx.resume.filter(|_| self.range_requested) else {
+            return false;
+        };
+        let offset = resume.at.offset;
+        if range_start != Some(offset) {
+            return false;
+        }
+        // If-Range only covers Last-Modified; an ETag sent has to agree
+        if etag != 0 && resume.etag_hash != 0 && etag != resume.etag_hash {
+            return false;
+        }
+
+        self.offset = offset;
+        self.body_hash = BodyHash::resumed(&resume.at);
+        self.next_checkpoint = offset + CHECKPOINT_INTERVAL;
+        self.disk_writer = self
+            .disk_writer
+            .take()
+            .map(|writer| writer.resuming(offset, resume.skipped));
+        ctx.bytes_written = offset;
+        ctx.skipped = resume.skipped;
+        ctx.checkpoint = Some(resume.at);
+        serial::print("[HTTP] Got 206, resuming at ");
+        serial::print_u64(offset / (1024 * 1024));
+        serial::println(" MB");
+        true
+    }
+
+    /// Make the writes so far durable up to the last full chunk and
+    /// record that point in `ctx.checkpoint`. `false` if a write failed.
+    fn sync_checkpoint(&mut self, ctx: &mut Context<'_>) -> bool {
+        let (Some(writer), Some(blk)) = (self.disk_writer.as_mut(), ctx.blk_device.as_mut()) else {
+            return true;
+        };
+        if !writer.sync(blk) {
+            return false;
+        }
+        ctx.bytes_written = writer.bytes_written();
+        ctx.skipped = writer.skipped();
+        // A chunk that couldn't be submitted leaves the writes behind the hash
+        if let Some(at) = self.body_hash.checkpoint().filter(|at| at.offset == ctx.bytes_written) {
+            ctx.checkpoint = Some(at);
+        }
+        true
+    }
+
+    /// Write a partial manifest at the last full chunk, so a reset from
+    /// here on resumes there.
+    fn checkpoint(&mut self, ctx: &mut Context<'_>) {
+        self.next_checkpoint = ctx.bytes_written + CHECKPOINT_INTERVAL;
+        if !self.sync_checkpoint(ctx) {
+            // The writer refuses further data; the final flush reports it
+            return;
+        }
+        let config = ManifestConfig::partial_from_context(ctx);
+        if ctx.checkpoint.is_some() && write_partial(ctx, &config) {
+            serial::print("[HTTP] Checkpoint at ");
+            serial::print_u64(config.resume_offset() / (1024 * 1024));
+            serial::println(" MB");
+        }
+    }
+
+    /// Reconnect and resend the request if nothing of the body has been
+    /// received yet; otherwise (or once the budget is spent) fail.
+    fn retry_or_fail(
+        self: Box<Self>,
+        ctx: &mut Context<'_>,
+        stack: &mut dyn NetStack,
+        tsc: u64,
+        reason: &'static str,
+    ) -> (Box<dyn State>, StepResult) {
+        // A failed HEAD doesn't hold up the download
+        if self.is_preflight() {
+            return self.finish_preflight(ctx, stack, None);
+        }
+        if self.phase != HttpPhase::ReceiveBody && self.bytes_received == 0 {
+            if let Some(retry) = ctx.schedule_retry(RetryPhase::Http, tsc) {
+                stack.tcp_abort();
+                serial::println("[HTTP] -> Connect");
+                return (Box::new(ConnectState::retry_after(retry.resume_tsc)), StepResult::Transition);
+            }
+        }
+        (Box::new(FailedState::new(reason)), StepResult::Failed(reason))
+    }
+}
+
+impl Default for HttpState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-impl<D: NetworkDriver> State<D> for HttpState {
+impl State for HttpState {
     fn step(
         mut self: Box<Self>,
         ctx: &mut Context<'_>,
-        _iface: &mut Interface,
-        sockets: &mut SocketSet<'_>,
-        _adapter: &mut SmoltcpAdapter<'_, D>,
-        _now: Instant,
+        stack: &mut dyn NetStack,
         tsc: u64,
-    ) -> (Box<dyn State<D>>, StepResult) {
+    ) -> (Box<dyn State>, StepResult) {
         // Initialize on first call
         if self.start_tsc == 0 {
             self.start_tsc = tsc;
             self.last_activity_tsc = tsc;
+            if ctx.download_start_tsc == 0 && !self.is_preflight() {
+                ctx.download_start_tsc = tsc;
+            }
             serial::println("[HTTP] Starting HTTP request...");
+            if let Some(bucket) = ctx.rate_limiter.as_ref().filter(|_| !self.is_preflight()) {
+                serial::print("[HTTP] Rate limit: ");
+                serial::print_u64(bucket.rate() / 1024);
+                serial::println(" KB/s");
+            }
         }
 
         // Check idle timeout
@@ -160,23 +473,34 @@ impl<D: NetworkDriver> State<D> for HttpState {
         let idle_timeout = ctx.timeouts.http_idle();
         if idle_ticks > idle_timeout {
             serial::println("[HTTP] ERROR: Idle timeout");
-            return (Box::new(FailedState::new("HTTP idle timeout")), StepResult::Failed("idle timeout"));
+            return self.retry_or_fail(ctx, stack, tsc, "HTTP idle timeout");
         }
 
-        let socket = sockets.get_mut::<TcpSocket>(self.tcp_handle);
-
         match self.phase {
             HttpPhase::SendRequest => {
-                if !socket.may_send() {
+                if !stack.tcp_may_send() {
                     return (self, StepResult::Continue);
                 }
 
                 // Build request
                 let path = self.path.unwrap_or(ctx.url_path);
-                let host = self.host.unwrap_or(ctx.url_host);
+                let host = self.host.unwrap_or(ctx.url_authority);
+
+                // Ask for the rest of a partial download
+                let resume = self.resume_from(ctx);
+                let mut date = [0u8; HTTP_DATE_LEN];
+                let range = resume.map(|resume| {
+                    let last_modified = ctx.preflight.map_or(0, |p| p.last_modified);
+                    let if_range = match last_modified {
+                        0 => "",
+                        secs => format_http_date(secs, &mut date),
+                    };
+                    (resume.at.offset, if_range)
+                });
+                self.range_requested = range.is_some();
 
                 let mut req_buf = [0u8; 512];
-                let req_len = format_http_request(&mut req_buf, self.method, path, host);
+                let req_len = format_http_request(&mut req_buf, self.method, path, host, range);
 
                 if req_len == 0 {
                     serial::println("[HTTP] ERROR: Request too large");
@@ -186,11 +510,16 @@ impl<D: NetworkDriver> State<D> for HttpState {
                 serial::print("[HTTP] Sending ");
                 serial::print(self.method);
                 serial::print(" ");
-                serial::println(path);
+                serial::print(path);
+                if let Some((offset, _)) = range {
+                    serial::print(" from byte ");
+                    serial::print_u64(offset);
+                }
+                serial::println("");
 
-                if socket.send_slice(&req_buf[..req_len]).is_err() {
+                if stack.tcp_send(&req_buf[..req_len]).is_err() {
                     serial::println("[HTTP] ERROR: Send failed");
-                    return (Box::new(FailedState::new("send failed")), StepResult::Failed("send"));
+                    return self.retry_or_fail(ctx, stack, tsc, "send failed");
                 }
 
                 self.phase = HttpPhase::ReceiveHeaders;
@@ -198,10 +527,10 @@ impl<D: NetworkDriver> State<D> for HttpState {
             }
 
             HttpPhase::ReceiveHeaders => {
-                if !socket.may_recv() {
-                    if socket.state() != smoltcp::socket::tcp::State::Established {
+                if !stack.tcp_may_recv() {
+                    if stack.tcp_status() != TcpStatus::Established {
                         serial::println("[HTTP] ERROR: Connection closed during headers");
-                        return (Box::new(FailedState::new("connection closed")), StepResult::Failed("closed"));
+                        return self.retry_or_fail(ctx, stack, tsc, "connection closed");
                     }
                     return (self, StepResult::Continue);
                 }
@@ -213,7 +542,7 @@ impl<D: NetworkDriver> State<D> for HttpState {
                     return (Box::new(FailedState::new("headers too large")), StepResult::Failed("headers"));
                 }
 
-                match socket.recv_slice(&mut self.header_buf[self.header_len..]) {
+                match stack.tcp_recv(&mut self.header_buf[self.header_len..]) {
                     Ok(0) => {}
                     Ok(n) => {
                         self.header_len += n;
@@ -225,8 +554,30 @@ impl<D: NetworkDriver> State<D> for HttpState {
                             let header_str = core::str::from_utf8(&self.header_buf[..end])
                                 .unwrap_or("");
 
+                            // Servers' Date is our only wall clock post-EBS
+                            if let Some(date) = header_value(header_str, "date").and_then(parse_http_date) {
+                                time::wall::sync(date as u64, tsc, ctx.tsc_freq);
+                            }
+
+                            // Before anything of the response is kept
+                            let body = &self.header_buf[end + 4..self.header_len];
+                            let portal = portal_response(header_str, ctx.url_host, ctx.config.expected_size)
+                                .or_else(|| portal_body(body));
+                            if let Some(why) = portal {
+                                return self.portal_detected(stack, why);
+                            }
+                            self.body_checked = !body.is_empty();
+
+                            if self.is_preflight() {
+                                let preflight = parse_preflight(header_str);
+                                return self.finish_preflight(ctx, stack, preflight);
+                            }
+
                             // Check status
-                            if !header_str.starts_with("HTTP/1.1 200") 
+                            let partial = header_str.starts_with("HTTP/1.1 206")
+                                || header_str.starts_with("HTTP/1.0 206");
+                            if !partial
+                                && !header_str.starts_with("HTTP/1.1 200")
                                 && !header_str.starts_with("HTTP/1.0 200") {
                                 serial::print("[HTTP] ERROR: Bad status: ");
                                 if let Some(line_end) = header_str.find('\r') {
@@ -235,34 +586,67 @@ impl<D: NetworkDriver> State<D> for HttpState {
                                 return (Box::new(FailedState::new("bad HTTP status")), StepResult::Failed("status"));
                             }
 
-                            serial::println("[HTTP] Got 200 OK");
+                            // Read what's needed of the headers before the state changes
+                            // Transfer-Encoding overrides Content-Length (RFC 7230 3.3.3)
+                            let chunked = header_value(header_str, "transfer-encoding")
+                                .is_some_and(|te| contains_ignore_case(te, "chunked"));
+                            let content_length = parse_content_length(header_str).filter(|_| !chunked);
+                            self.chunked = chunked.then(ChunkedDecoder::new);
+                            let range_start = header_value(header_str, "content-range")
+                                .and_then(parse_content_range)
+                                .map(|(first, _, _)| first);
+                            let etag = header_value(header_str, "etag").map_or(0, morpheus_core::iso::etag_hash);
+
+                            if partial {
+                                if !self.start_resumed(ctx, range_start, etag) {
+                                    serial::println("[HTTP] ERROR: Range response doesn't fit, starting over");
+                                    ctx.resume = None;
+                                    return self.retry_or_fail(ctx, stack, tsc, "bad range response");
+                                }
+                            } else {
+                                if self.range_requested {
+                                    serial::println("[HTTP] Server sent the whole image, starting over");
+                                    ctx.resume = None;
+                                }
+                                serial::println("[HTTP] Got 200 OK");
+                            }
 
-                            // Parse Content-Length
-                            self.content_length = parse_content_length(header_str);
-                            if let Some(len) = self.content_length {
+                            // Content-Length is of the rest, when resuming
+                            self.content_length = content_length;
+                            let image_len = self.content_length.map(|len| self.offset + len);
+                            let head_len = ctx.preflight.and_then(|p| p.content_length);
+                            if head_len.is_some() && self.chunked.is_none() && image_len != head_len {
+                                serial::println("[HTTP] WARN: Size differs from the HEAD response");
+                            }
+                            if let Some(len) = image_len {
                                 serial::print("[HTTP] Content-Length: ");
                                 serial::print_u32((len / 1024 / 1024) as u32);
                                 serial::println(" MB");
                                 ctx.content_length = Some(len);
                             }
 
-                            // Check for chunked encoding
-                            self.chunked = contains_ignore_case(header_str, "transfer-encoding: chunked");
-
                             // Move body data to start of buffer
                             let body_start = end + 4; // Skip \r\n\r\n
                             let body_len = self.header_len - body_start;
                             if body_len > 0 {
                                 // Process initial body data
-                                self.bytes_received += body_len as u64;
-                                ctx.bytes_downloaded = self.bytes_received;
-                                
-                                // Write initial body data to disk if enabled
-                                if let (Some(ref mut writer), Some(ref mut blk)) = 
-                                    (&mut self.disk_writer, &mut ctx.blk_device) {
-                                    let written = writer.write(blk, &self.header_buf[body_start..self.header_len]);
-                                    ctx.bytes_written += written as u64;
+                                if let Some(bucket) = ctx.rate_limiter.as_mut() {
+                                    bucket.consume(body_len);
                                 }
+
+                                // Hash initial body data, writing it to disk if enabled
+                                let target = self.disk_writer.as_mut().zip(ctx.blk_device.as_mut());
+                                let Some((body, written)) = take_wire(
+                                    self.chunked.as_mut(),
+                                    &mut self.body_hash,
+                                    target,
+                                    &self.header_buf[body_start..self.header_len],
+                                ) else {
+                                    return self.bad_chunk();
+                                };
+                                self.bytes_received += body;
+                                ctx.bytes_downloaded = self.offset + self.bytes_received;
+                                ctx.bytes_written += written;
                             }
 
                             self.phase = HttpPhase::ReceiveBody;
@@ -274,94 +658,141 @@ impl<D: NetworkDriver> State<D> for HttpState {
             }
 
             HttpPhase::ReceiveBody => {
-                if !socket.may_recv() {
+                if !stack.tcp_may_recv() {
                     // Check if we're done
-                    if let Some(expected) = self.content_length {
-                        if self.bytes_received >= expected {
-                            // Flush disk buffer
-                            if let (Some(ref mut writer), Some(ref mut blk)) = 
-                                (&mut self.disk_writer, &mut ctx.blk_device) {
-                                if !writer.flush(blk) {
-                                    serial::println("[HTTP] ERROR: Disk flush failed");
-                                    return (Box::new(FailedState::new("disk flush")), StepResult::Failed("flush"));
-                                }
-                                ctx.bytes_written = writer.bytes_written();
+                    if self.body_complete() {
+                        // Flush disk buffer
+                        if let (Some(ref mut writer), Some(ref mut blk)) = 
+                            (&mut self.disk_writer, &mut ctx.blk_device) {
+                            if !writer.flush(blk) {
+                                serial::println("[HTTP] ERROR: Disk flush failed");
+                                return (Box::new(FailedState::new("disk flush")), StepResult::Failed("flush"));
                             }
-                            serial::println("[HTTP] Download complete");
-                            self.phase = HttpPhase::Complete;
-                            ctx.bytes_downloaded = self.bytes_received;
-                            return (Box::new(ManifestState::from_context(ctx)), StepResult::Transition);
+                            ctx.bytes_written = writer.bytes_written();
+                            ctx.skipped = writer.skipped();
                         }
+                        ctx.sha256 = Some(self.body_hash.finalize());
+                        serial::println("[HTTP] Download complete");
+                        self.phase = HttpPhase::Complete;
+                        ctx.bytes_downloaded = self.offset + self.bytes_received;
+                        return (Box::new(ManifestState::from_context(ctx)), StepResult::Transition);
                     }
 
                     // Connection closed?
-                    if socket.state() != smoltcp::socket::tcp::State::Established {
-                        if self.content_length.is_none() {
+                    if stack.tcp_status() != TcpStatus::Established {
+                        if self.content_length.is_none() && self.chunked.is_none() {
                             // No Content-Length, connection close = end
                             // Flush disk buffer
                             if let (Some(ref mut writer), Some(ref mut blk)) = 
                                 (&mut self.disk_writer, &mut ctx.blk_device) {
                                 writer.flush(blk);
                                 ctx.bytes_written = writer.bytes_written();
+                                ctx.skipped = writer.skipped();
                             }
+                            ctx.sha256 = Some(self.body_hash.finalize());
                             serial::println("[HTTP] Download complete (connection closed)");
-                            ctx.bytes_downloaded = self.bytes_received;
+                            ctx.bytes_downloaded = self.offset + self.bytes_received;
                             return (Box::new(ManifestState::from_context(ctx)), StepResult::Transition);
                         }
                         serial::println("[HTTP] ERROR: Premature connection close");
                         return (Box::new(FailedState::new("premature close")), StepResult::Failed("close"));
                     }
+                    if let Some(failed) = self.spot_check(ctx) {
+                        return failed;
+                    }
                     return (self, StepResult::Continue);
                 }
 
-                // Read body data
+                // Read no more than the disk takes without waiting. The
+                // rest stays in the socket, whose shrinking receive window
+                // slows the sender down.
                 let mut buf = [0u8; 4096];
-                match socket.recv_slice(&mut buf) {
-                    Ok(0) => {}
+                let mut limit = buf.len();
+                if let (Some(ref mut writer), Some(ref mut blk)) =
+                    (&mut self.disk_writer, &mut ctx.blk_device) {
+                    limit = limit.min(writer.room(blk));
+                }
+                if limit == 0 {
+                    ctx.disk_backpressure += 1;
+                    return (self, StepResult::Continue);
+                }
+                // The rate cap holds back the same way
+                if let Some(bucket) = ctx.rate_limiter.as_mut() {
+                    limit = limit.min(bucket.available(tsc));
+                    if limit == 0 {
+                        // Waiting on the cap is not the server going quiet
+                        self.last_activity_tsc = tsc;
+                        return (self, StepResult::Continue);
+                    }
+                }
+
+                // Read body data
+                match stack.tcp_recv(&mut buf[..limit]) {
+                    Ok(0) => {
+                        // Nothing waiting: a moment to check the disk
+                        if let Some(failed) = self.spot_check(ctx) {
+                            return failed;
+                        }
+                    }
                     Ok(n) => {
-                        self.bytes_received += n as u64;
+                        if let Some(bucket) = ctx.rate_limiter.as_mut() {
+                            bucket.consume(n);
+                        }
+                        if !self.body_checked {
+                            self.body_checked = true;
+                            if let Some(why) = portal_body(&buf[..n]) {
+                                return self.portal_detected(stack, why);
+                            }
+                        }
                         self.last_activity_tsc = tsc;
-                        ctx.bytes_downloaded = self.bytes_received;
+
+                        // Hash, and write to disk if enabled
+                        let target = self.disk_writer.as_mut().zip(ctx.blk_device.as_mut());
+                        let Some((body, written)) =
+                            take_wire(self.chunked.as_mut(), &mut self.body_hash, target, &buf[..n])
+                        else {
+                            return self.bad_chunk();
+                        };
+                        self.bytes_received += body;
+                        ctx.bytes_downloaded = self.offset + self.bytes_received;
+                        ctx.bytes_written += written;
 
                         // Progress every 1MB
-                        let mb = self.bytes_received / (1024 * 1024);
-                        let prev_mb = (self.bytes_received - n as u64) / (1024 * 1024);
+                        let mb = ctx.bytes_downloaded / (1024 * 1024);
+                        let prev_mb = (ctx.bytes_downloaded - body) / (1024 * 1024);
                         if mb > prev_mb {
                             serial::print("[HTTP] Downloaded: ");
                             serial::print_u32(mb as u32);
                             if let Some(total) = self.content_length {
                                 serial::print("/");
-                                serial::print_u32((total / 1024 / 1024) as u32);
+                                serial::print_u32(((self.offset + total) / 1024 / 1024) as u32);
                             }
                             serial::println(" MB");
                         }
 
-                        // Write to disk if enabled
-                        if let (Some(ref mut writer), Some(ref mut blk)) = 
-                            (&mut self.disk_writer, &mut ctx.blk_device) {
-                            let written = writer.write(blk, &buf[..n]);
-                            ctx.bytes_written += written as u64;
+                        if self.disk_writer.is_some() && ctx.bytes_written >= self.next_checkpoint {
+                            self.checkpoint(ctx);
                         }
                     }
                     Err(_) => {}
                 }
 
                 // Check if download complete
-                if let Some(expected) = self.content_length {
-                    if self.bytes_received >= expected {
-                        // Flush remaining disk buffer
-                        if let (Some(ref mut writer), Some(ref mut blk)) = 
-                            (&mut self.disk_writer, &mut ctx.blk_device) {
-                            if !writer.flush(blk) {
-                                serial::println("[HTTP] ERROR: Final disk flush failed");
-                                return (Box::new(FailedState::new("disk flush")), StepResult::Failed("flush"));
-                            }
-                            ctx.bytes_written = writer.bytes_written();
+                if self.body_complete() {
+                    // Flush remaining disk buffer
+                    if let (Some(ref mut writer), Some(ref mut blk)) = 
+                        (&mut self.disk_writer, &mut ctx.blk_device) {
+                        if !writer.flush(blk) {
+                            serial::println("[HTTP] ERROR: Final disk flush failed");
+                            return (Box::new(FailedState::new("disk flush")), StepResult::Failed("flush"));
                         }
-                        serial::println("[HTTP] Download complete");
-                        ctx.bytes_downloaded = self.bytes_received;
-                        return (Box::new(ManifestState::from_context(ctx)), StepResult::Transition);
+                        ctx.bytes_written = writer.bytes_written();
+                        ctx.skipped = writer.skipped();
                     }
+                    ctx.sha256 = Some(self.body_hash.finalize());
+                    serial::println("[HTTP] Download complete");
+                    ctx.bytes_downloaded = self.offset + self.bytes_received;
+                    return (Box::new(ManifestState::from_context(ctx)), StepResult::Transition);
                 }
             }
 
@@ -376,23 +807,187 @@ impl<D: NetworkDriver> State<D> for HttpState {
     }
 
     fn name(&self) -> &'static str {
-        "HTTP"
+        if self.is_preflight() {
+            "Preflight"
+        } else {
+            "HTTP"
+        }
+    }
+
+    fn abort(&mut self, ctx: &mut Context<'_>) {
+        ctx.bytes_downloaded = self.offset + self.bytes_received;
+        // The chunk being filled stays out: a resume starts at the last
+        // full one
+        if !self.sync_checkpoint(ctx) {
+            serial::println("[HTTP] WARN: Disk sync failed during abort");
+        }
     }
 }
 
+/// SHA-256 of the image, and a copy of it at the last boundary of the
+/// disk writer's chunks: the state a checkpoint records.
+#[derive(Clone)]
+struct BodyHash {
+    hasher: Sha256,
+    /// `hasher` at the last multiple of `BUFFER_SIZE` past `base`
+    at_chunk: Sha256,
+    /// Image offset the writer's chunks count from
+    base: u64,
+}
+
+impl BodyHash {
+    fn new(base: u64) -> Self {
+        Self {
+            hasher: Sha256::new(),
+            at_chunk: Sha256::new(),
+            base,
+        }
+    }
+
+    /// Carry on from a checkpoint.
+    fn resumed(at: &Checkpoint) -> Self {
+        let hasher = Sha256::from_midstate(&at.sha256_state, at.offset);
+        Self {
+            at_chunk: hasher.clone(),
+            hasher,
+            base: at.offset,
+        }
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        let chunk = BUFFER_SIZE as u64;
+        loop {
+            let to_boundary = chunk - (self.hasher.len() - self.base) % chunk;
+            if (data.len() as u64) < to_boundary {
+                self.hasher.update(data);
+                return;
+            }
+            let (head, rest) = data.split_at(to_boundary as usize);
+            self.hasher.update(head);
+            self.at_chunk = self.hasher.clone();
+            data = rest;
+        }
+    }
+
+    /// The last chunk boundary passed, with the hash state there.
+    fn checkpoint(&self) -> Option<Checkpoint> {
+        let offset = self.at_chunk.len();
+        Some(Checkpoint {
+            offset,
+            sha256_state: self.at_chunk.midstate().filter(|_| offset > 0)?,
+        })
+    }
+
+    fn finalize(&self) -> [u8; 32] {
+        self.hasher.clone().finalize()
+    }
+}
+
+/// Arguments of a `hash_body` job.
+struct HashJob {
+    hash: *mut BodyHash,
+    data: *const u8,
+    len: usize,
+}
+
+/// `offload` job: hash a piece of the body.
+unsafe fn hash_body(arg: *mut ()) {
+    let job = &*(arg as *const HashJob);
+    (*job.hash).update(core::slice::from_raw_parts(job.data, job.len));
+}
+
+/// Add body bytes to the image hash and write them to `target` if given,
+/// returning the bytes written. The hash runs while the writer copies
+/// them (and waits on the disk).
+fn take_body(
+    hash: &mut BodyHash,
+    target: Option<(&mut DiskWriter, &mut UnifiedBlockDevice)>,
+    data: &[u8],
+) -> u64 {
+    let mut job = HashJob {
+        hash,
+        data: data.as_ptr(),
+        len: data.len(),
+    };
+    let mut hash = Task::new(hash_body, &mut job as *mut HashJob as *mut ());
+    // Safety: `job` and `data` outlive the task, which is waited on below
+    unsafe { hash.start() };
+    let written = target.map_or(0, |(writer, blk)| writer.write(blk, data) as u64);
+    hash.wait();
+    written
+}
+
+/// Take body bytes off the wire: strip the chunk framing if there is a
+/// `decoder`, and pass the image bytes to `take_body`. Returns the image
+/// bytes they held and the bytes written, `None` if the framing is broken.
+fn take_wire(
+    decoder: Option<&mut ChunkedDecoder>,
+    hash: &mut BodyHash,
+    mut target: Option<(&mut DiskWriter, &mut UnifiedBlockDevice)>,
+    data: &[u8],
+) -> Option<(u64, u64)> {
+    let Some(decoder) = decoder else {
+        return Some((data.len() as u64, take_body(hash, target, data)));
+    };
+    let (mut body, mut written) = (0, 0);
+    decoder
+        .decode_with(data, |run| {
+            body += run.len() as u64;
+            let target = target.as_mut().map(|(writer, blk)| (&mut **writer, &mut **blk));
+            written += take_body(hash, target, run);
+        })
+        .ok()?;
+    Some((body, written))
+}
+
 /// Format HTTP GET request into buffer. Returns length or 0 if buffer too small.
-fn format_http_request(buf: &mut [u8], method: &str, path: &str, host: &str) -> usize {
+///
+/// `range` asks for the body from an offset on, with an If-Range
+/// validator unless it is empty.
+pub(super) fn format_http_request(
+    buf: &mut [u8],
+    method: &str,
+    path: &str,
+    host: &str,
+    range: Option<(u64, &str)>,
+) -> usize {
     let mut pos = 0;
 
+    // Decimal offset, right-aligned
+    let mut digits = [0u8; 20];
+    let mut start = digits.len();
+    if let Some((mut offset, _)) = range {
+        loop {
+            start -= 1;
+            digits[start] = b'0' + (offset % 10) as u8;
+            offset /= 10;
+            if offset == 0 {
+                break;
+            }
+        }
+    }
+    let (offset, validator) = match range {
+        Some((_, validator)) => (&digits[start..], validator.as_bytes()),
+        None => (&digits[..0], &b""[..]),
+    };
+    let range_lines: [&[u8]; 3] = [b"Range: bytes=", offset, b"-\r\n"];
+    let if_range_lines: [&[u8]; 3] = [b"If-Range: ", validator, b"\r\n"];
+    let end: [&[u8]; 1] = [b"\r\n"];
+
     // "{METHOD} {path} HTTP/1.1\r\nHost: {host}\r\n..."
-    let parts: &[&[u8]] = &[
+    let head: [&[u8]; 6] = [
         method.as_bytes(),
         b" ",
         path.as_bytes(),
         b" HTTP/1.1\r\nHost: ",
         host.as_bytes(),
-        b"\r\nUser-Agent: MorpheusX/1.0\r\nAccept: */*\r\nConnection: close\r\n\r\n",
+        b"\r\nUser-Agent: MorpheusX/1.0\r\nAccept: */*\r\nConnection: close\r\n",
     ];
+    let parts = head
+        .iter()
+        .chain(range_lines.iter().filter(|_| range.is_some()))
+        .chain(if_range_lines.iter().filter(|_| !validator.is_empty()))
+        .chain(end.iter());
 
     for part in parts {
         if pos + part.len() > buf.len() {
@@ -406,7 +1001,7 @@ fn format_http_request(buf: &mut [u8], method: &str, path: &str, host: &str) ->
 }
 
 /// Find end of HTTP headers (double CRLF).
-fn find_header_end(data: &[u8]) -> Option<usize> {
+pub(super) fn find_header_end(data: &[u8]) -> Option<usize> {
     for i in 0..data.len().saturating_sub(3) {
         if &data[i..i + 4] == b"\r\n\r\n" {
             return Some(i);
@@ -415,8 +1010,85 @@ fn find_header_end(data: &[u8]) -> Option<usize> {
     None
 }
 
+/// Parse a HEAD response. `None` unless the status is 200.
+fn parse_preflight(headers: &str) -> Option<Preflight> {
+    if !headers.starts_with("HTTP/1.1 200") && !headers.starts_with("HTTP/1.0 200") {
+        return None;
+    }
+    Some(Preflight {
+        content_length: parse_content_length(headers),
+        accept_ranges: header_value(headers, "accept-ranges")
+            .is_some_and(|v| v.eq_ignore_ascii_case("bytes")),
+        last_modified: header_value(headers, "last-modified")
+            .and_then(parse_http_date)
+            .unwrap_or(0),
+        etag_hash: header_value(headers, "etag").map_or(0, morpheus_core::iso::etag_hash),
+    })
+}
+
+/// Smallest response taken for an image the catalog says is larger.
+const MIN_IMAGE_RESPONSE: u64 = 1024 * 1024;
+
+/// Why the status and headers of a response look like a captive portal
+/// rather than the image on `url_host`, if they do. `expected_size` is
+/// the size the catalog announced (0 = unknown).
+fn portal_response(headers: &str, url_host: &str, expected_size: u64) -> Option<&'static str> {
+    let status = headers
+        .split_ascii_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse::<u16>().ok())?;
+    match status {
+        // RFC 6585: Network Authentication Required
+        511 => Some("network login required (511)"),
+        301 | 302 | 303 | 307 | 308 => {
+            let location = header_value(headers, "location")?;
+            let target = location
+                .strip_prefix("http://")
+                .or_else(|| location.strip_prefix("https://"))?;
+            let (host, path) = target.split_at(target.find('/').unwrap_or(target.len()));
+            let host = host.split(':').next().unwrap_or(host);
+            let login_path = ["login", "portal", "captive", "hotspot", "splash", "auth"]
+                .iter()
+                .any(|word| contains_ignore_case(path, word));
+            let foreign = !host.eq_ignore_ascii_case(url_host);
+            (foreign && (login_path || super::dns::parse_ipv4(host).is_some()))
+                .then_some("redirected to a login page")
+        }
+        200 => {
+            if header_value(headers, "content-type")
+                .is_some_and(|ct| contains_ignore_case(ct, "text/html"))
+            {
+                return Some("got an HTML page instead of the image");
+            }
+            let len = parse_content_length(headers)?;
+            (expected_size >= MIN_IMAGE_RESPONSE && len < MIN_IMAGE_RESPONSE)
+                .then_some("response far smaller than the image")
+        }
+        _ => None,
+    }
+}
+
+/// Whether the first bytes of a body are an HTML page, which no disk
+/// image starts with.
+fn portal_body(body: &[u8]) -> Option<&'static str> {
+    let start = body.iter().position(|b| !b.is_ascii_whitespace())?;
+    let head = &body[start..body.len().min(start + 256)];
+    let head = core::str::from_utf8(head).ok()?;
+    (head.starts_with('<')
+        && (contains_ignore_case(head, "<!doctype html") || contains_ignore_case(head, "<html")))
+    .then_some("got an HTML page instead of the image")
+}
+
+/// Value of the header `name` (lowercase), trimmed.
+pub(super) fn header_value<'h>(headers: &'h str, name: &str) -> Option<&'h str> {
+    headers.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        key.eq_ignore_ascii_case(name).then(|| value.trim())
+    })
+}
+
 /// Parse Content-Length from headers (case-insensitive).
-fn parse_content_length(headers: &str) -> Option<u64> {
+pub(super) fn parse_content_length(headers: &str) -> Option<u64> {
     for line in headers.lines() {
         // Case-insensitive check without allocation
         if line.len() >= 15 && line[..15].eq_ignore_ascii_case("content-length:") {
@@ -428,7 +1100,7 @@ fn parse_content_length(headers: &str) -> Option<u64> {
 }
 
 /// Case-insensitive substring search without allocation.
-fn contains_ignore_case(haystack: &str, needle: &str) -> bool {
+pub(super) fn contains_ignore_case(haystack: &str, needle: &str) -> bool {
     if needle.len() > haystack.len() {
         return false;
     }
@@ -439,3 +1111,124 @@ fn contains_ignore_case(haystack: &str, needle: &str) -> bool {
     }
     false
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_parse_preflight() {
+        let headers = "HTTP/1.1 200 OK\r\n\
+            Content-Length: 2147483648\r\n\
+            accept-ranges: bytes\r\n\
+            Last-Modified: Thu, 29 Feb 2024 12:00:00 GMT\r\n\
+            ETag: \"80000000-6128\"";
+        let preflight = parse_preflight(headers).unwrap();
+        assert_eq!(preflight.content_length, Some(2 * 1024 * 1024 * 1024));
+        assert!(preflight.accept_ranges);
+        assert_eq!(preflight.last_modified, 1709208000);
+        assert_eq!(
+            preflight.etag_hash,
+            morpheus_core::iso::etag_hash("\"80000000-6128\"")
+        );
+
+        let bare = parse_preflight("HTTP/1.0 200 OK\r\nAccept-Ranges: none").unwrap();
+        assert_eq!(bare, Preflight::default());
+        assert_eq!(parse_preflight("HTTP/1.1 405 Method Not Allowed"), None);
+    }
+
+    #[test]
+    fn test_portal_response() {
+        let host = "mirror.example.org";
+        let gb = 1024 * 1024 * 1024;
+        assert!(portal_response("HTTP/1.1 511 Network Authentication Required", host, 0).is_some());
+        assert!(portal_response(
+            "HTTP/1.1 302 Found\r\nLocation: http://10.1.0.1/login.html?orig=x",
+            host,
+            0
+        )
+        .is_some());
+        assert!(portal_response(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8",
+            host,
+            gb
+        )
+        .is_some());
+        assert!(portal_response("HTTP/1.1 200 OK\r\nContent-Length: 5120", host, gb).is_some());
+
+        // Mirror redirects and plain downloads are not portals
+        assert_eq!(
+            portal_response(
+                "HTTP/1.1 302 Found\r\nLocation: http://cdn.example.org/iso/x.iso",
+                host,
+                0
+            ),
+            None
+        );
+        assert_eq!(portal_response("HTTP/1.1 301 Moved\r\nLocation: /new/x.iso", host, 0), None);
+        assert_eq!(
+            portal_response(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/octet-stream\r\nContent-Length: 2147483648",
+                host,
+                gb
+            ),
+            None
+        );
+        assert_eq!(portal_response("HTTP/1.1 200 OK\r\nContent-Length: 5120", host, 0), None);
+    }
+
+    #[test]
+    fn test_portal_body() {
+        assert!(portal_body(b"\r\n  <!DOCTYPE HTML>\n<html><head>").is_some());
+        assert!(portal_body(b"<html>\n<body>Please log in").is_some());
+        assert_eq!(portal_body(&[0u8; 512]), None);
+        assert_eq!(portal_body(b"<?xml version=\"1.0\"?>"), None);
+        assert_eq!(portal_body(b""), None);
+    }
+
+    #[test]
+    fn test_format_http_request_range() {
+        let mut buf = [0u8; 512];
+        let len = format_http_request(&mut buf, "GET", "/a.iso", "host", None);
+        let request = core::str::from_utf8(&buf[..len]).unwrap();
+        assert!(request.starts_with("GET /a.iso HTTP/1.1\r\nHost: host\r\n"));
+        assert!(request.ends_with("Connection: close\r\n\r\n"));
+        assert!(!request.contains("Range"));
+
+        let date = "Thu, 29 Feb 2024 12:00:00 GMT";
+        let len = format_http_request(&mut buf, "GET", "/a.iso", "host", Some((1_048_576, date)));
+        let request = core::str::from_utf8(&buf[..len]).unwrap();
+        assert!(request.ends_with(
+            "Connection: close\r\nRange: bytes=1048576-\r\n\
+             If-Range: Thu, 29 Feb 2024 12:00:00 GMT\r\n\r\n"
+        ));
+
+        let len = format_http_request(&mut buf, "GET", "/a.iso", "host", Some((0, "")));
+        let request = core::str::from_utf8(&buf[..len]).unwrap();
+        assert!(request.ends_with("Range: bytes=0-\r\n\r\n"));
+        assert_eq!(format_http_request(&mut buf[..64], "GET", "/a.iso", "host", Some((1, date))), 0);
+    }
+
+    #[test]
+    fn test_body_hash_checkpoint() {
+        let data: Vec<u8> = (0..3 * BUFFER_SIZE + 100).map(|i| (i * 7) as u8).collect();
+        let mut hash = BodyHash::new(0);
+        assert_eq!(hash.checkpoint(), None);
+        for piece in data.chunks(1500) {
+            hash.update(piece);
+        }
+        assert_eq!(hash.finalize(), Sha256::digest(&data));
+
+        // At the last chunk boundary, and the rest hashes on from there
+        let at = hash.checkpoint().unwrap();
+        assert_eq!(at.offset, 3 * BUFFER_SIZE as u64);
+        let mut resumed = BodyHash::resumed(&at);
+        resumed.update(&data[at.offset as usize..]);
+        assert_eq!(resumed.finalize(), Sha256::digest(&data));
+
+        // Boundaries count from where a resumed hash started
+        resumed.update(&[0u8; BUFFER_SIZE]);
+        assert_eq!(resumed.checkpoint().unwrap().offset, at.offset + BUFFER_SIZE as u64);
+    }
+}
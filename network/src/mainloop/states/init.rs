@@ -3,11 +3,8 @@
 extern crate alloc;
 use alloc::boxed::Box;
 
-use smoltcp::iface::{Interface, SocketSet};
-use smoltcp::time::Instant;
 
-use crate::driver::traits::NetworkDriver;
-use crate::mainloop::adapter::SmoltcpAdapter;
+use crate::mainloop::netstack::NetStack;
 use crate::mainloop::context::Context;
 use crate::mainloop::serial;
 use crate::mainloop::state::{State, StepResult};
@@ -31,16 +28,13 @@ impl Default for InitState {
     }
 }
 
-impl<D: NetworkDriver> State<D> for InitState {
+impl State for InitState {
     fn step(
         mut self: Box<Self>,
         ctx: &mut Context<'_>,
-        _iface: &mut Interface,
-        _sockets: &mut SocketSet<'_>,
-        _adapter: &mut SmoltcpAdapter<'_, D>,
-        _now: Instant,
+        _stack: &mut dyn NetStack,
         _tsc: u64,
-    ) -> (Box<dyn State<D>>, StepResult) {
+    ) -> (Box<dyn State>, StepResult) {
         if self.validated {
             serial::println("[INIT] -> GPT Prep");
             return (Box::new(GptPrepState::new()), StepResult::Transition);
@@ -51,52 +45,66 @@ impl<D: NetworkDriver> State<D> for InitState {
         serial::println("=====================================");
         serial::println("");
 
-        // Parse URL by computing indices first, avoiding borrow conflicts.
         // URL format: scheme://host[:port]/path
-        let (scheme_end, default_port) = {
-            let url = ctx.config.url;
-            if url.starts_with("https://") {
-                (8usize, 443u16)
-            } else if url.starts_with("http://") {
-                (7usize, 80u16)
-            } else {
-                serial::println("[INIT] ERROR: URL must start with http:// or https://");
-                return (Box::new(super::FailedState::new("invalid URL scheme")), StepResult::Failed("invalid URL"));
-            }
-        };
-
-        // Compute indices for host and path
         let url = ctx.config.url;
-        let rest = &url[scheme_end..];
-        
-        let (host_end, path_start) = match rest.find('/') {
-            Some(idx) => (scheme_end + idx, scheme_end + idx),
-            None => (url.len(), url.len()),
-        };
-
-        let host_port_slice = &url[scheme_end..host_end];
-        let (host_slice_end, port) = match host_port_slice.find(':') {
-            Some(colon_idx) => {
-                let port_str = &host_port_slice[colon_idx + 1..];
-                let port = parse_port(port_str).unwrap_or(default_port);
-                (scheme_end + colon_idx, port)
+        let parts = match split_url(url) {
+            Ok(parts) => parts,
+            Err(why) => {
+                serial::print("[INIT] ERROR: ");
+                serial::println(why);
+                return (Box::new(super::FailedState::new(why)), StepResult::Failed("invalid URL"));
             }
-            None => (host_end, default_port),
-        };
-
-        // Assign using direct indexing
-        ctx.resolved_port = port;
-        ctx.url_host = &ctx.config.url[scheme_end..host_slice_end];
-        ctx.url_path = if path_start < ctx.config.url.len() {
-            &ctx.config.url[path_start..]
-        } else {
-            "/"
         };
+        if ctx.config.require_tls && !parts.tls {
+            serial::println("[INIT] ERROR: TLS required, URL is not https://");
+            return (
+                Box::new(super::FailedState::new("TLS required")),
+                StepResult::Failed("invalid URL"),
+            );
+        }
+        ctx.url_tls = parts.tls;
+        ctx.url_host = parts.host;
+        ctx.url_authority = parts.authority;
+        ctx.url_path = parts.path;
+        ctx.connect_host = parts.host;
+        ctx.resolved_port = parts.port;
+
+        // Through a proxy: connect there and send the whole URL
+        if !ctx.config.proxy.is_empty() {
+            if parts.tls {
+                // That takes a CONNECT tunnel, which isn't implemented
+                serial::println("[INIT] ERROR: https:// through a proxy is not supported");
+                return (
+                    Box::new(super::FailedState::new("HTTPS proxy")),
+                    StepResult::Failed("invalid proxy"),
+                );
+            }
+            match split_proxy(ctx.config.proxy) {
+                Ok((host, port)) => {
+                    ctx.connect_host = host;
+                    ctx.resolved_port = port;
+                    ctx.url_path = url.split('#').next().unwrap_or(url);
+                    serial::print("[INIT] Proxy: ");
+                    serial::print(host);
+                    serial::print(":");
+                    serial::print_u32(port as u32);
+                    serial::println("");
+                }
+                Err(why) => {
+                    serial::print("[INIT] ERROR: Proxy: ");
+                    serial::println(why);
+                    return (Box::new(super::FailedState::new(why)), StepResult::Failed("invalid proxy"));
+                }
+            }
+        }
 
         serial::print("[INIT] URL: ");
         serial::println(ctx.config.url);
         serial::print("[INIT] Host: ");
         serial::println(ctx.url_host);
+        if ctx.url_tls {
+            serial::println("[INIT] TLS: yes");
+        }
         serial::print("[INIT] Port: ");
         serial::print_u32(ctx.resolved_port as u32);
         serial::println("");
@@ -115,20 +123,123 @@ impl<D: NetworkDriver> State<D> for InitState {
     }
 }
 
+/// Parts of a download URL, borrowed from it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UrlParts<'u> {
+    /// `https://`: the connection needs TLS
+    pub tls: bool,
+    /// Host, with the brackets of an IPv6 literal
+    pub host: &'u str,
+    /// Explicit port, or the scheme's default
+    pub port: u16,
+    /// Host and explicit port as written, for the Host header
+    pub authority: &'u str,
+    /// Path and query ("/" when the URL has none)
+    pub path: &'u str,
+}
+
+/// Split an `http://` or `https://` URL, rejecting a missing host or a
+/// port that isn't a number from 1 to 65535.
+pub fn split_url(url: &str) -> Result<UrlParts<'_>, &'static str> {
+    let (rest, default_port, tls) = if let Some(rest) = url.strip_prefix("https://") {
+        (rest, 443, true)
+    } else if let Some(rest) = url.strip_prefix("http://") {
+        (rest, 80, false)
+    } else {
+        return Err("URL must start with http:// or https://");
+    };
+
+    let rest = rest.split('#').next().unwrap_or(rest);
+    let authority_end = rest.find(['/', '?']).unwrap_or(rest.len());
+    let (authority, path) = rest.split_at(authority_end);
+    let (host, port) = split_authority(authority, default_port)?;
+    Ok(UrlParts {
+        tls,
+        host,
+        port,
+        authority,
+        path: if path.is_empty() { "/" } else { path },
+    })
+}
+
+/// Proxy host and port from "host:port", optionally written as an
+/// `http://` URL. The port defaults to 80.
+pub fn split_proxy(proxy: &str) -> Result<(&str, u16), &'static str> {
+    let authority = proxy.strip_prefix("http://").unwrap_or(proxy);
+    split_authority(authority.trim_end_matches('/'), 80)
+}
+
+/// Host and port of "host[:port]"; an IPv6 literal keeps its brackets
+/// and the port comes after the closing one.
+fn split_authority(authority: &str, default_port: u16) -> Result<(&str, u16), &'static str> {
+    let host_end = if authority.starts_with('[') {
+        authority.find(']').ok_or("unterminated IPv6 address")? + 1
+    } else {
+        authority.find(':').unwrap_or(authority.len())
+    };
+    let (host, port) = authority.split_at(host_end);
+    if host.is_empty() || host == "[]" {
+        return Err("URL has no host");
+    }
+    let port = match port.strip_prefix(':') {
+        Some(port) => parse_port(port).ok_or("invalid port")?,
+        None if port.is_empty() => default_port,
+        None => return Err("invalid port"),
+    };
+    Ok((host, port))
+}
+
+/// Port number from 1 to 65535, digits only.
 fn parse_port(s: &str) -> Option<u16> {
-    let mut result: u32 = 0;
-    for c in s.bytes() {
-        if !c.is_ascii_digit() {
-            break;
-        }
-        result = result * 10 + (c - b'0') as u32;
-        if result > 65535 {
-            return None;
-        }
+    if s.is_empty() || s.len() > 5 || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
     }
-    if result > 0 {
-        Some(result as u16)
-    } else {
-        None
+    s.parse::<u16>().ok().filter(|&port| port != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_url() {
+        let parts = split_url("http://10.0.0.5:8080/alpine.iso").unwrap();
+        assert_eq!(parts.host, "10.0.0.5");
+        assert_eq!(parts.port, 8080);
+        assert_eq!(parts.authority, "10.0.0.5:8080");
+        assert_eq!(parts.path, "/alpine.iso");
+
+        assert!(!parts.tls);
+
+        let parts = split_url("https://mirror.example.org").unwrap();
+        assert_eq!((parts.host, parts.port, parts.path), ("mirror.example.org", 443, "/"));
+        assert!(parts.tls);
+
+        let parts = split_url("http://[::ffff:10.0.0.5]:81/x.iso?r=1#top").unwrap();
+        assert_eq!((parts.host, parts.port), ("[::ffff:10.0.0.5]", 81));
+        assert_eq!(parts.path, "/x.iso?r=1");
+
+        let parts = split_url("http://host?x=1").unwrap();
+        assert_eq!((parts.authority, parts.path), ("host", "?x=1"));
+    }
+
+    #[test]
+    fn test_bad_urls() {
+        assert!(split_url("ftp://host/x.iso").is_err());
+        assert!(split_url("http:///x.iso").is_err());
+        assert!(split_url("http://host:/x.iso").is_err());
+        assert!(split_url("http://host:0/x.iso").is_err());
+        assert!(split_url("http://host:65536/x.iso").is_err());
+        assert!(split_url("http://host:80abc/x.iso").is_err());
+        assert!(split_url("http://[::1/x.iso").is_err());
+        assert!(split_url("http://[::1]x/x.iso").is_err());
+    }
+
+    #[test]
+    fn test_split_proxy() {
+        assert_eq!(split_proxy("proxy.lan:3128"), Ok(("proxy.lan", 3128)));
+        assert_eq!(split_proxy("http://10.0.0.1:8080/"), Ok(("10.0.0.1", 8080)));
+        assert_eq!(split_proxy("proxy.lan"), Ok(("proxy.lan", 80)));
+        assert!(split_proxy("proxy.lan:99999").is_err());
     }
 }
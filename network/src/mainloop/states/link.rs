@@ -1,29 +1,29 @@
 //! PHY link wait state — waits for Ethernet link to establish.
 //!
 //! Real hardware (unlike QEMU) needs time for PHY auto-negotiation.
-//! This state polls the driver until link_up() returns true, with
-//! timeout handling and a brief stabilization delay.
+//! The orchestrator samples the link from the first iteration on (see
+//! [`LinkWatch`](crate::mainloop::link_watch::LinkWatch)), so negotiation
+//! overlaps Init and GPT prep and the wait is counted from the start of
+//! the session. This state waits until the link has been ready for a
+//! brief stabilization delay, or gives up with a diagnosis once the
+//! configured patience runs out.
 
 extern crate alloc;
 use alloc::boxed::Box;
+use alloc::format;
 
-use smoltcp::iface::{Interface, SocketSet};
-use smoltcp::time::Instant;
 
-use crate::driver::traits::NetworkDriver;
-use crate::mainloop::adapter::SmoltcpAdapter;
+use crate::mainloop::netstack::NetStack;
 use crate::mainloop::context::Context;
 use crate::mainloop::serial;
 use crate::mainloop::state::{State, StepResult};
 
-use super::{DhcpState, FailedState};
+use super::DhcpState;
 
 /// PHY link wait state.
 pub struct LinkWaitState {
     started: bool,
-    start_tsc: u64,
     link_established: bool,
-    stable_start_tsc: u64,
     last_dot_tsc: u64,
 }
 
@@ -31,21 +31,29 @@ impl LinkWaitState {
     pub fn new() -> Self {
         Self {
             started: false,
-            start_tsc: 0,
             link_established: false,
-            stable_start_tsc: 0,
             last_dot_tsc: 0,
         }
     }
 
-    /// 15 second timeout for PHY auto-negotiation.
-    const LINK_TIMEOUT_SECS: u64 = 15;
-
     /// 500ms stabilization delay after link comes up.
     const STABILIZE_MS: u64 = 500;
 
     /// Print progress dot every second.
     const DOT_INTERVAL_SECS: u64 = 1;
+
+    /// Hand over to DHCP, restarting discovery if the link was down at
+    /// some point: the DISCOVERs sent then are lost, and the stack would
+    /// otherwise only retry on its own timer.
+    fn to_dhcp(ctx: &mut Context<'_>, stack: &mut dyn NetStack) -> (Box<dyn State>, StepResult) {
+        ctx.link.finish();
+        if ctx.link.seen_down() {
+            serial::println("[LINK] Restarting DHCP discovery on the live link");
+            stack.dhcp_restart();
+        }
+        serial::println("[LINK] -> DHCP");
+        (Box::new(DhcpState::new()), StepResult::Transition)
+    }
 }
 
 impl Default for LinkWaitState {
@@ -54,43 +62,44 @@ impl Default for LinkWaitState {
     }
 }
 
-impl<D: NetworkDriver> State<D> for LinkWaitState {
+impl State for LinkWaitState {
     fn step(
         mut self: Box<Self>,
         ctx: &mut Context<'_>,
-        _iface: &mut Interface,
-        _sockets: &mut SocketSet<'_>,
-        adapter: &mut SmoltcpAdapter<'_, D>,
-        _now: Instant,
+        stack: &mut dyn NetStack,
         tsc: u64,
-    ) -> (Box<dyn State<D>>, StepResult) {
+    ) -> (Box<dyn State>, StepResult) {
         if !self.started {
             self.started = true;
-            self.start_tsc = tsc;
             self.last_dot_tsc = tsc;
             serial::println("[NET] Waiting for PHY link...");
         }
 
-        // If link already established, wait for stabilization
-        if self.link_established {
+        if let Some(ready_for) = ctx.link.ready_for(tsc) {
+            if !self.link_established {
+                serial::println("");
+                serial::println("[OK] PHY link established");
+                self.link_established = true;
+            }
             let stabilize_ticks = (ctx.tsc_freq * Self::STABILIZE_MS) / 1000;
-            if tsc.wrapping_sub(self.stable_start_tsc) >= stabilize_ticks {
-                serial::println("[OK] Link stable");
-                serial::println("[LINK] -> DHCP");
-                return (Box::new(DhcpState::new()), StepResult::Transition);
+            if ready_for >= stabilize_ticks {
+                match stack.link_info() {
+                    Some(info) => {
+                        serial::println(&format!("[OK] Link stable: {}", info));
+                        if !info.full_duplex {
+                            serial::println("[WARN] Half duplex - expect collisions and slow TCP");
+                        }
+                    }
+                    None => serial::println("[OK] Link stable"),
+                }
+                return Self::to_dhcp(ctx, stack);
             }
             // Still stabilizing
             return (self, StepResult::Continue);
         }
-
-        // Check if link is up
-        if adapter.driver_link_up() {
-            serial::println("");
-            serial::println("[OK] PHY link established");
-            serial::println("[NET] Link stabilization delay...");
-            self.link_established = true;
-            self.stable_start_tsc = tsc;
-            return (self, StepResult::Continue);
+        if self.link_established {
+            serial::println("[NET] Link dropped, waiting again...");
+            self.link_established = false;
         }
 
         // Print progress dot every second
@@ -100,15 +109,14 @@ impl<D: NetworkDriver> State<D> for LinkWaitState {
             self.last_dot_tsc = tsc;
         }
 
-        // Check timeout
-        let timeout_ticks = ctx.tsc_freq * Self::LINK_TIMEOUT_SECS;
-        if tsc.wrapping_sub(self.start_tsc) >= timeout_ticks {
+        // Out of patience: DHCP will fail with a proper error if the
+        // link really isn't there
+        if ctx.link.waited(tsc) >= ctx.timeouts.link_wait() {
             serial::println("");
-            serial::println("[WARN] PHY link timeout - continuing anyway...");
-            // Continue to DHCP even without link - it will fail with proper error
-            // if link really isn't available
-            serial::println("[LINK] -> DHCP");
-            return (Box::new(DhcpState::new()), StepResult::Transition);
+            serial::print("[WARN] PHY link timeout: ");
+            serial::println(ctx.link.problem().describe());
+            serial::println("[WARN] Continuing anyway...");
+            return Self::to_dhcp(ctx, stack);
         }
 
         (self, StepResult::Continue)
@@ -1,27 +1,41 @@
 //! Manifest writing state — writes ISO manifest after download.
 //!
-//! Supports two modes:
+//! Supports three modes:
 //! - FAT32: Write to `/.iso/<name>.manifest` on ESP
-//! - Raw sector: Write to a specific disk sector (legacy)
+//! - Raw sector: Write a CRC-checked copy to specific disk sectors
+//! - Both: FAT32 plus a raw copy in the sectors GPT prep reserved at the
+//!   end of the ISO partition, which the bootloader falls back to when
+//!   the file is missing or corrupt
 //!
 //! Can be used standalone to regenerate a manifest for an existing ISO.
+//!
+//! Writes are ordered data flush → manifest write → manifest flush, so a
+//! power loss can't leave a manifest that describes data never written.
+//! The intent journal is moved to `WriteManifest` before and committed
+//! after, so a manifest lost that way is written on the next run.
 
 extern crate alloc;
 use alloc::boxed::Box;
 use alloc::format;
 
-use smoltcp::iface::{Interface, SocketSet};
-use smoltcp::time::Instant;
+use gpt_disk_io::BlockIo;
 
-use morpheus_core::iso::{IsoManifest, MAX_MANIFEST_SIZE};
+use morpheus_core::iso::{
+    history_filename, DownloadRecord, IsoManifest, SkipList, Verification, HISTORY_RECORD_SIZE,
+    MAX_HISTORY_RECORDS, MAX_MANIFEST_SIZE, RAW_MANIFEST_SIZE,
+};
 
 use crate::device::UnifiedBlockDevice;
-use crate::driver::traits::NetworkDriver;
+use crate::dma::HeapDmaBuffer;
 use crate::driver::unified_block_io::UnifiedBlockIo;
-use crate::mainloop::adapter::SmoltcpAdapter;
-use crate::mainloop::context::Context;
+use crate::mainloop::netstack::NetStack;
+use crate::mainloop::context::{Context, Preflight};
+use crate::mainloop::disk_writer;
+use crate::mainloop::journal;
 use crate::mainloop::serial;
 use crate::mainloop::state::{State, StepResult};
+use crate::time::{self, Deadline, TimeoutConfig};
+use crate::transfer::disk::{JournalEntry, JournalStep};
 
 use super::{DoneState, FailedState};
 
@@ -31,17 +45,16 @@ const MAX_ISO_NAME_LEN: usize = 128;
 /// DMA buffer size for FAT32 operations.
 const FAT32_DMA_BUFFER_SIZE: usize = 64 * 1024;
 
-/// Static DMA buffer for FAT32 manifest operations.
-/// Separate from disk_writer's buffer to avoid conflicts.
-static mut FAT32_DMA_BUFFER: [u8; FAT32_DMA_BUFFER_SIZE] = [0u8; FAT32_DMA_BUFFER_SIZE];
-
 /// Manifest write mode.
 #[derive(Debug, Clone, Copy)]
 pub enum ManifestMode {
     /// Write to FAT32 ESP filesystem
     Fat32 { esp_start_lba: u64 },
-    /// Write to raw disk sector
+    /// Write a raw copy starting at this sector
+    /// (`RAW_MANIFEST_SECTORS` long)
     RawSector { sector: u64 },
+    /// Write to the ESP and a raw copy; either one is enough
+    Both { esp_start_lba: u64, sector: u64 },
     /// Skip manifest writing
     Skip,
 }
@@ -63,6 +76,21 @@ pub struct ManifestConfig {
     pub partition_uuid: [u8; 16],
     /// Write mode
     pub mode: ManifestMode,
+    /// Bytes durably on disk for a partial download (`None` = complete)
+    pub written_size: Option<u64>,
+    /// SHA-256 of the image as written; for a partial download, the hash
+    /// state after `written_size` bytes
+    pub sha256: Option<[u8; 32]>,
+    /// `sha256` matched the expected hash
+    pub verified: bool,
+    /// Disk holding the ISO (`DeviceIdentity::disk_id`), 0 for the ESP's
+    pub disk_id: u32,
+    /// Server Last-Modified, Unix seconds (0 = unknown)
+    pub last_modified: u32,
+    /// Hash of the server ETag (0 = none)
+    pub etag_hash: u32,
+    /// Zero sectors the download left unwritten
+    pub skipped: SkipList,
 }
 
 impl ManifestConfig {
@@ -92,6 +120,88 @@ impl ManifestConfig {
             end_sector,
             partition_uuid,
             mode,
+            written_size: None,
+            sha256: None,
+            verified: false,
+            disk_id: 0,
+            last_modified: 0,
+            etag_hash: 0,
+            skipped: SkipList::new(),
+        }
+    }
+
+    /// Mark this as a partial download with `written` bytes on disk.
+    ///
+    /// The size is rounded down to a whole sector so a resumed download
+    /// rewrites the (zero-padded) last sector instead of trusting it.
+    pub fn partial(mut self, written: u64) -> Self {
+        self.written_size = Some(written / 512 * 512);
+        self
+    }
+
+    /// Record the image hash, verifying it against `expected` if given.
+    pub fn with_hash(mut self, sha256: Option<[u8; 32]>, expected: Option<[u8; 32]>) -> Self {
+        self.sha256 = sha256;
+        self.verified = sha256.is_some() && sha256 == expected;
+        self
+    }
+
+    /// Record the ISO as stored on another disk than the ESP.
+    pub fn on_disk(mut self, disk_id: u32) -> Self {
+        self.disk_id = disk_id;
+        self
+    }
+
+    /// Record the server's validators, for later update checks.
+    pub fn with_validators(mut self, preflight: Option<Preflight>) -> Self {
+        if let Some(preflight) = preflight {
+            self.last_modified = preflight.last_modified;
+            self.etag_hash = preflight.etag_hash;
+        }
+        self
+    }
+
+    /// Record the zero sectors the download skipped, for verification.
+    pub fn with_skipped(mut self, skipped: SkipList) -> Self {
+        self.skipped = skipped;
+        self
+    }
+
+    /// Offset a resumed download should continue from (0 if complete).
+    pub fn resume_offset(&self) -> u64 {
+        self.written_size.unwrap_or(0)
+    }
+
+    /// Partial-download config from the session so far: up to the last
+    /// checkpoint, with the hash state a resume carries on from, or all
+    /// that was written if there is none (which can't be resumed).
+    ///
+    /// The chunk still spans the full expected size so the reserved
+    /// sectors stay claimed for the resume.
+    pub fn partial_from_context(ctx: &Context<'_>) -> Self {
+        let total = ctx
+            .content_length
+            .unwrap_or(ctx.config.expected_size)
+            .max(ctx.bytes_written);
+        let start_sector = ctx.actual_start_sector;
+        let end_sector = start_sector + total.div_ceil(512);
+
+        let config = Self::new(
+            ctx.config.iso_name,
+            total,
+            start_sector,
+            end_sector,
+            ctx.config.partition_uuid,
+            manifest_mode(ctx),
+        )
+        .on_disk(ctx.data_disk_id())
+        .with_validators(ctx.preflight)
+        .with_skipped(ctx.skipped);
+        match ctx.checkpoint {
+            Some(at) => config
+                .partial(at.offset)
+                .with_hash(Some(at.sha256_state), None),
+            None => config.partial(ctx.bytes_written),
         }
     }
 
@@ -143,6 +253,13 @@ impl ManifestConfig {
             end_sector: 0,
             partition_uuid: [0u8; 16],
             mode: ManifestMode::Skip,
+            written_size: None,
+            sha256: None,
+            verified: false,
+            disk_id: 0,
+            last_modified: 0,
+            etag_hash: 0,
+            skipped: SkipList::new(),
         }
     }
 }
@@ -152,15 +269,24 @@ pub struct ManifestState {
     config: ManifestConfig,
     started: bool,
     completed: bool,
+    /// Download to add to the history next to the manifest (FAT32 only)
+    history: Option<DownloadRecord>,
+    /// TSC the download started at, for the history duration
+    download_start_tsc: u64,
+    /// Timeouts for the ESP writes
+    timeouts: TimeoutConfig,
 }
 
 impl ManifestState {
     /// Create manifest state with configuration.
-    pub fn new(config: ManifestConfig) -> Self {
+    pub fn new(config: ManifestConfig, timeouts: TimeoutConfig) -> Self {
         Self {
             config,
             started: false,
             completed: false,
+            history: None,
+            download_start_tsc: 0,
+            timeouts,
         }
     }
 
@@ -172,29 +298,54 @@ impl ManifestState {
         let num_sectors = (iso_size + 511) / 512;
         let end_sector = start_sector + num_sectors;
 
-        let mode = if ctx.config.esp_start_lba > 0 {
-            ManifestMode::Fat32 { esp_start_lba: ctx.config.esp_start_lba }
-        } else if ctx.config.manifest_sector > 0 {
-            ManifestMode::RawSector { sector: ctx.config.manifest_sector }
-        } else {
-            ManifestMode::Skip
-        };
-
-        Self::new(ManifestConfig::new(
+        let config = ManifestConfig::new(
             ctx.config.iso_name,
             iso_size,
             start_sector,
             end_sector,
             ctx.config.partition_uuid,
-            mode,
-        ))
+            manifest_mode(ctx),
+        )
+        .on_disk(ctx.data_disk_id())
+        .with_validators(ctx.preflight)
+        .with_skipped(ctx.skipped)
+        .with_hash(ctx.sha256, ctx.config.expected_sha256);
+
+        if !ctx.skipped.is_empty() {
+            serial::print("[MANIFEST] Zero sectors not written: ");
+            serial::print_u64(ctx.skipped.sectors());
+            serial::println("");
+        }
+
+        if ctx.config.expected_sha256.is_some() {
+            if config.verified {
+                serial::println("[MANIFEST] SHA-256 verified");
+            } else {
+                serial::println("[MANIFEST] WARNING: SHA-256 mismatch");
+            }
+        }
+
+        let verification = match ctx.config.expected_sha256 {
+            None => Verification::Unchecked,
+            Some(_) if config.verified => Verification::Verified,
+            Some(_) => Verification::Mismatch,
+        };
+        // Duration is filled in when the state runs and the end time is known
+        let record =
+            DownloadRecord::new(ctx.config.iso_name, ctx.config.url, iso_size, 0, verification);
+
+        let mut state = Self::new(config, ctx.timeouts.block());
+        state.history = Some(record);
+        state.download_start_tsc = ctx.download_start_tsc;
+        state
     }
 
     /// Build manifest structure.
     fn build_manifest(&self) -> Option<IsoManifest> {
         let mut manifest = IsoManifest::new(self.config.iso_name(), self.config.iso_size);
 
-        if manifest.add_chunk(
+        if manifest.add_chunk_on_disk(
+            self.config.disk_id,
             self.config.partition_uuid,
             self.config.start_sector,
             self.config.end_sector,
@@ -203,12 +354,26 @@ impl ManifestState {
             return None;
         }
 
+        // A partial manifest leaves COMPLETE clear; the chunk's data_size
+        // is the resume offset.
+        let complete = self.config.written_size.is_none();
         if let Some(chunk) = manifest.chunks.chunks.get_mut(0) {
-            chunk.data_size = self.config.iso_size;
-            chunk.written = true;
+            chunk.data_size = self.config.written_size.unwrap_or(self.config.iso_size);
+            chunk.written = complete;
         }
 
-        manifest.mark_complete();
+        if complete {
+            manifest.mark_complete();
+        }
+        if let Some(hash) = &self.config.sha256 {
+            manifest.set_sha256(hash);
+        }
+        if self.config.verified {
+            manifest.mark_verified();
+        }
+        manifest.last_modified = self.config.last_modified;
+        manifest.etag_hash = self.config.etag_hash;
+        manifest.skipped = self.config.skipped;
         Some(manifest)
     }
 
@@ -242,25 +407,28 @@ impl ManifestState {
             }
         };
 
+        // Data flush: the ISO must be durable before a manifest points at it
+        if !disk_writer::barrier(blk) {
+            serial::println("[MANIFEST] ERROR: Data flush failed");
+            return false;
+        }
+
         // Create BlockIo adapter for FAT32 operations
         serial::println("[MANIFEST] Creating BlockIo adapter for FAT32...");
-        let (dma_buffer, dma_buffer_phys) = unsafe {
-            let buf = core::slice::from_raw_parts_mut(
-                (&raw mut FAT32_DMA_BUFFER).cast::<u8>(),
-                FAT32_DMA_BUFFER_SIZE,
-            );
-            let phys = (&raw const FAT32_DMA_BUFFER).cast::<u8>() as u64;
-            (buf, phys)
+        let Some(mut dma) = HeapDmaBuffer::new(FAT32_DMA_BUFFER_SIZE) else {
+            serial::println("[MANIFEST] ERROR: DMA buffer allocation failed");
+            return false;
         };
-        let timeout_ticks = 500_000_000u64; // ~500ms
+        let (dma_buffer, dma_buffer_phys) = dma.split_phys();
 
-        let mut adapter = match UnifiedBlockIo::new(blk, dma_buffer, dma_buffer_phys, timeout_ticks) {
+        let mut adapter = match UnifiedBlockIo::new(blk, dma_buffer, dma_buffer_phys, self.timeouts) {
             Ok(a) => {
                 serial::println("[MANIFEST] BlockIo adapter created");
                 a
             }
-            Err(_) => {
-                serial::println("[MANIFEST] ERROR: Failed to create BlockIo adapter");
+            Err(e) => {
+                serial::print("[MANIFEST] ERROR: Failed to create BlockIo adapter: ");
+                serial::println(&format!("{}", e));
                 return false;
             }
         };
@@ -276,7 +444,7 @@ impl ManifestState {
         let _ = morpheus_core::fs::create_directory(&mut adapter, esp_start_lba, "/.iso");
 
         // Write manifest file
-        match morpheus_core::fs::write_file(
+        let written = match morpheus_core::fs::write_file(
             &mut adapter,
             esp_start_lba,
             &manifest_path,
@@ -284,6 +452,9 @@ impl ManifestState {
         ) {
             Ok(()) => {
                 serial::println("[MANIFEST] OK: Written to ESP");
+                if let Some(record) = &self.history {
+                    write_history(&mut adapter, esp_start_lba, record);
+                }
                 true
             }
             Err(e) => {
@@ -294,10 +465,20 @@ impl ManifestState {
                     morpheus_core::fs::Fat32Error::PartitionTooLarge => "Partition too large",
                     morpheus_core::fs::Fat32Error::InvalidBlockSize => "Invalid block size",
                     morpheus_core::fs::Fat32Error::NotImplemented => "Not implemented",
+                    morpheus_core::fs::Fat32Error::PathTooDeep => "Path too deep",
+                    morpheus_core::fs::Fat32Error::InvalidPath => "Invalid path",
                 });
+                if let Some(cause) = adapter.last_error() {
+                    serial::print("[MANIFEST] Disk: ");
+                    serial::println(&format!("{}", cause));
+                }
                 false
             }
-        }
+        };
+        drop(adapter);
+
+        // Manifest flush: only report success once the manifest is durable
+        written && flush_manifest(blk)
     }
 
     /// Write manifest using raw sector method.
@@ -312,107 +493,153 @@ impl ManifestState {
             None => return false,
         };
 
-        // Serialize
-        let mut buffer = [0u8; MAX_MANIFEST_SIZE];
-        let len = match manifest.serialize(&mut buffer) {
-            Ok(l) => l,
-            Err(_) => {
-                serial::println("[MANIFEST] ERROR: Serialize failed");
-                return false;
-            }
-        };
+        // Serialize with the raw copy's own header and CRC
+        let mut buffer = [0u8; RAW_MANIFEST_SIZE];
+        if manifest.serialize_raw(&mut buffer).is_err() {
+            serial::println("[MANIFEST] ERROR: Serialize failed");
+            return false;
+        }
 
         serial::print("[MANIFEST] Serialized ");
-        serial::print_u32(len as u32);
+        serial::print_u32(manifest.serialized_size() as u32);
         serial::println(" bytes");
 
-        // Write to disk
-        unsafe { write_sector(blk, sector, &buffer) }
+        // Data flush, then the manifest, then the manifest flush
+        if !disk_writer::barrier(blk) {
+            serial::println("[MANIFEST] ERROR: Data flush failed");
+            return false;
+        }
+        unsafe { write_sectors(blk, sector, &buffer) && flush_manifest(blk) }
+    }
+
+    /// Write the manifest as `mode` asks: the ESP copy on `esp` (or on
+    /// `blk` when the ESP is on the same disk), the raw copy on `blk`.
+    ///
+    /// Returns whether anything was written and whether the ESP copy was.
+    fn write_mode(
+        &self,
+        blk: &mut UnifiedBlockDevice,
+        esp: Option<&mut UnifiedBlockDevice>,
+        mode: ManifestMode,
+    ) -> (bool, bool) {
+        match mode {
+            ManifestMode::Skip => {
+                serial::println("[MANIFEST] Skipping (not configured)");
+                (true, false)
+            }
+            ManifestMode::Fat32 { esp_start_lba } => {
+                let ok = self.write_fat32(esp.unwrap_or(blk), esp_start_lba);
+                (ok, ok)
+            }
+            ManifestMode::RawSector { sector } => (self.write_raw_sector(blk, sector), false),
+            ManifestMode::Both { esp_start_lba, sector } => {
+                let fat32 = match esp {
+                    Some(esp) => self.write_fat32(esp, esp_start_lba),
+                    None => self.write_fat32(blk, esp_start_lba),
+                };
+                let raw = self.write_raw_sector(blk, sector);
+                match (fat32, raw) {
+                    (true, false) => {
+                        serial::println("[MANIFEST] WARN: Raw copy failed, ESP copy only")
+                    }
+                    (false, true) => {
+                        serial::println("[MANIFEST] WARN: ESP write failed, raw copy only")
+                    }
+                    _ => {}
+                }
+                (fat32 || raw, fat32)
+            }
+        }
     }
 }
 
-impl<D: NetworkDriver> State<D> for ManifestState {
+impl State for ManifestState {
     fn step(
         mut self: Box<Self>,
         ctx: &mut Context<'_>,
-        _iface: &mut Interface,
-        _sockets: &mut SocketSet<'_>,
-        _adapter: &mut SmoltcpAdapter<'_, D>,
-        _now: Instant,
-        _tsc: u64,
-    ) -> (Box<dyn State<D>>, StepResult) {
+        _stack: &mut dyn NetStack,
+        tsc: u64,
+    ) -> (Box<dyn State>, StepResult) {
         if self.completed {
             return (Box::new(DoneState::new()), StepResult::Transition);
         }
 
         if !self.started {
             self.started = true;
-            
-            match self.config.mode {
-                ManifestMode::Skip => {
-                    serial::println("[MANIFEST] Skipping (not configured)");
-                    self.completed = true;
-                    return (self, StepResult::Continue);
+
+            if let Some(record) = &mut self.history {
+                if self.download_start_tsc > 0 && ctx.tsc_freq > 0 {
+                    let ticks = tsc.saturating_sub(self.download_start_tsc);
+                    record.duration_ms = (ticks as u128 * 1000 / ctx.tsc_freq as u128) as u64;
                 }
-                ManifestMode::Fat32 { esp_start_lba } => {
-                    serial::println("=================================");
-                    serial::println("     WRITING ISO MANIFEST        ");
-                    serial::println("=================================");
-                    serial::print("[MANIFEST] ISO: ");
-                    serial::println(self.config.iso_name());
-                    serial::print("[MANIFEST] Size: ");
-                    serial::print_u32((self.config.iso_size / 1024 / 1024) as u32);
-                    serial::println(" MB");
-                    serial::print("[MANIFEST] Sectors: ");
-                    serial::print_hex(self.config.start_sector);
-                    serial::print(" - ");
-                    serial::print_hex(self.config.end_sector);
-                    serial::println("");
-                    serial::print("[MANIFEST] Mode: FAT32 (ESP LBA ");
-                    serial::print_u32(esp_start_lba as u32);
-                    serial::println(")");
-
-                    let blk = match &mut ctx.blk_device {
-                        Some(b) => b,
-                        None => {
-                            serial::println("[MANIFEST] ERROR: No block device");
-                            return (Box::new(FailedState::new("no block device")), StepResult::Failed("no blk"));
-                        }
-                    };
-
-                    if self.write_fat32(blk, esp_start_lba) {
-                        serial::println("[MANIFEST] Write successful");
-                        self.completed = true;
-                    } else {
-                        return (Box::new(FailedState::new("manifest write failed")), StepResult::Failed("write"));
-                    }
+            }
+            
+            let mode = self.config.mode;
+            if let ManifestMode::Skip = mode {
+                serial::println("[MANIFEST] Skipping (not configured)");
+                self.completed = true;
+                return (self, StepResult::Continue);
+            }
+
+            serial::println("=================================");
+            serial::println("     WRITING ISO MANIFEST        ");
+            serial::println("=================================");
+            serial::print("[MANIFEST] ISO: ");
+            serial::println(self.config.iso_name());
+            serial::print("[MANIFEST] Size: ");
+            serial::print_u64(self.config.iso_size / 1024 / 1024);
+            serial::println(" MB");
+
+            if let ManifestMode::Fat32 { esp_start_lba } | ManifestMode::Both { esp_start_lba, .. } =
+                mode
+            {
+                serial::print("[MANIFEST] Sectors: ");
+                serial::print_hex(self.config.start_sector);
+                serial::print(" - ");
+                serial::print_hex(self.config.end_sector);
+                serial::println("");
+                serial::print("[MANIFEST] Mode: FAT32 (ESP LBA ");
+                serial::print_u64(esp_start_lba);
+                if let ManifestMode::Both { sector, .. } = mode {
+                    serial::print(") + raw copy (sector ");
+                    serial::print_hex(sector);
                 }
-                ManifestMode::RawSector { sector } => {
-                    serial::println("=================================");
-                    serial::println("     WRITING ISO MANIFEST        ");
-                    serial::println("=================================");
-                    serial::print("[MANIFEST] ISO: ");
-                    serial::println(self.config.iso_name());
-                    serial::print("[MANIFEST] Size: ");
-                    serial::print_u32((self.config.iso_size / 1024 / 1024) as u32);
-                    serial::println(" MB");
-
-                    let blk = match &mut ctx.blk_device {
-                        Some(b) => b,
-                        None => {
-                            serial::println("[MANIFEST] ERROR: No block device");
-                            return (Box::new(FailedState::new("no block device")), StepResult::Failed("no blk"));
-                        }
-                    };
-
-                    if self.write_raw_sector(blk, sector) {
-                        serial::println("[MANIFEST] Write successful");
-                        self.completed = true;
-                    } else {
-                        return (Box::new(FailedState::new("manifest write failed")), StepResult::Failed("write"));
-                    }
+                serial::println(")");
+
+                // Records the final size; the journal flushes the data first
+                journal::begin(
+                    ctx,
+                    JournalEntry::new(
+                        JournalStep::WriteManifest,
+                        self.config.iso_name(),
+                        self.config.start_sector,
+                        self.config.end_sector.saturating_sub(1),
+                        self.config.iso_size,
+                    ),
+                );
+            }
+
+            let blk = match &mut ctx.blk_device {
+                Some(b) => b,
+                None => {
+                    serial::println("[MANIFEST] ERROR: No block device");
+                    return (Box::new(FailedState::new("no block device")), StepResult::Failed("no blk"));
                 }
+            };
+
+            // (written, ESP copy written)
+            let (written, on_esp) = self.write_mode(blk, ctx.esp_device.as_mut(), mode);
+
+            if !written {
+                return (Box::new(FailedState::new("manifest write failed")), StepResult::Failed("write"));
             }
+            serial::println("[MANIFEST] Write successful");
+            // Without the ESP copy the entry stays open, so the next run's
+            // recovery writes it
+            if on_esp {
+                journal::commit(ctx);
+            }
+            self.completed = true;
         }
 
         (self, StepResult::Continue)
@@ -423,19 +650,79 @@ impl<D: NetworkDriver> State<D> for ManifestState {
     }
 }
 
-/// Write a buffer to a disk sector.
-unsafe fn write_sector(blk: &mut UnifiedBlockDevice, sector: u64, data: &[u8]) -> bool {
-    use crate::driver::block_traits::BlockDriver;
+/// Store `record` in the first free history slot on the ESP.
+///
+/// History is informational, so failures are logged and otherwise ignored.
+fn write_history<B: BlockIo>(adapter: &mut B, esp_start_lba: u64, record: &DownloadRecord) {
+    let mut buffer = [0u8; HISTORY_RECORD_SIZE];
+    if record.serialize(&mut buffer).is_err() {
+        serial::println("[MANIFEST] WARN: Failed to serialize history record");
+        return;
+    }
+
+    for index in 0..MAX_HISTORY_RECORDS {
+        let path = format!("/.iso/{}", history_filename(index));
+        match morpheus_core::fs::file_exists(adapter, esp_start_lba, &path) {
+            Ok(true) => continue,
+            Ok(false) => {}
+            Err(_) => {
+                serial::println("[MANIFEST] WARN: Cannot read history directory");
+                return;
+            }
+        }
+
+        match morpheus_core::fs::write_file(adapter, esp_start_lba, &path, &buffer) {
+            Ok(()) => {
+                serial::print("[MANIFEST] History: ");
+                serial::println(&path);
+            }
+            Err(_) => serial::println("[MANIFEST] WARN: History write failed"),
+        }
+        return;
+    }
+    serial::println("[MANIFEST] WARN: History full, download not recorded");
+}
+
+/// Manifest destination configured for this session.
+///
+/// With an ESP and a raw copy area reserved by GPT prep, both are written.
+fn manifest_mode(ctx: &Context<'_>) -> ManifestMode {
+    let esp_start_lba = ctx.config.esp_start_lba;
+    if esp_start_lba > 0 && ctx.raw_manifest_sector > 0 {
+        ManifestMode::Both { esp_start_lba, sector: ctx.raw_manifest_sector }
+    } else if esp_start_lba > 0 {
+        ManifestMode::Fat32 { esp_start_lba }
+    } else if ctx.config.manifest_sector > 0 {
+        ManifestMode::RawSector { sector: ctx.config.manifest_sector }
+    } else {
+        ManifestMode::Skip
+    }
+}
 
-    // Pad to sector size
-    static mut SECTOR_BUF: [u8; 512] = [0u8; 512];
-    let copy_len = data.len().min(512);
-    SECTOR_BUF[..copy_len].copy_from_slice(&data[..copy_len]);
-    for i in copy_len..512 {
-        SECTOR_BUF[i] = 0;
+/// Make the just-written manifest durable.
+fn flush_manifest(blk: &mut UnifiedBlockDevice) -> bool {
+    if disk_writer::barrier(blk) {
+        serial::println("[MANIFEST] OK: Manifest flushed");
+        true
+    } else {
+        serial::println("[MANIFEST] ERROR: Manifest flush failed");
+        false
     }
+}
+
+/// Write a buffer to consecutive disk sectors starting at `sector`.
+unsafe fn write_sectors(blk: &mut UnifiedBlockDevice, sector: u64, data: &[u8]) -> bool {
+    use crate::driver::block_traits::BlockDriver;
+
+    // Pad to sector size (buffer is zeroed)
+    let count = data.len().div_ceil(512);
+    let Some(mut sector_buf) = HeapDmaBuffer::new(count * 512) else {
+        serial::println("[MANIFEST] ERROR: Sector buffer allocation failed");
+        return false;
+    };
+    sector_buf.as_mut_slice()[..data.len()].copy_from_slice(data);
 
-    let buffer_phys = (&raw const SECTOR_BUF).cast::<u8>() as u64;
+    let buffer_phys = sector_buf.phys_addr();
 
     // Drain pending
     while blk.poll_completion().is_some() {}
@@ -446,7 +733,7 @@ unsafe fn write_sector(blk: &mut UnifiedBlockDevice, sector: u64, data: &[u8]) -
     }
 
     let request_id = 0xFFFF_0001u32;
-    if blk.submit_write(sector, buffer_phys, 1, request_id).is_err() {
+    if blk.submit_write(sector, buffer_phys, count as u32, request_id).is_err() {
         serial::println("[MANIFEST] ERROR: Submit failed");
         return false;
     }
@@ -454,8 +741,8 @@ unsafe fn write_sector(blk: &mut UnifiedBlockDevice, sector: u64, data: &[u8]) -
     blk.notify();
 
     // Poll for completion
-    let start = read_tsc();
-    let timeout: u64 = 2_000_000_000; // ~500ms
+    // Without an installed clock, assume a 4GHz TSC (the old fixed budget).
+    let deadline = Deadline::after_ms(time::active_or_tsc(4_000_000_000), 500);
 
     loop {
         if let Some(completion) = blk.poll_completion() {
@@ -463,7 +750,7 @@ unsafe fn write_sector(blk: &mut UnifiedBlockDevice, sector: u64, data: &[u8]) -
                 return completion.status == 0;
             }
         }
-        if read_tsc().wrapping_sub(start) > timeout {
+        if deadline.expired() {
             serial::println("[MANIFEST] ERROR: Timeout");
             return false;
         }
@@ -471,23 +758,6 @@ unsafe fn write_sector(blk: &mut UnifiedBlockDevice, sector: u64, data: &[u8]) -
     }
 }
 
-#[cfg(target_arch = "x86_64")]
-#[inline]
-fn read_tsc() -> u64 {
-    let lo: u32;
-    let hi: u32;
-    unsafe {
-        core::arch::asm!("rdtsc", out("eax") lo, out("edx") hi, options(nostack, nomem));
-    }
-    ((hi as u64) << 32) | (lo as u64)
-}
-
-#[cfg(not(target_arch = "x86_64"))]
-#[inline]
-fn read_tsc() -> u64 {
-    0
-}
-
 // ============================================================================
 // Standalone API for manifest regeneration
 // ============================================================================
@@ -500,27 +770,48 @@ fn read_tsc() -> u64 {
 /// # Arguments
 /// * `blk` - Block device to write to
 /// * `config` - Manifest configuration describing the ISO location
+/// * `timeouts` - TSC-based timeouts (`TimeoutConfig::new(tsc_freq)`)
 ///
 /// # Returns
 /// `true` if manifest was written successfully
 pub fn write_manifest_standalone(
     blk: &mut UnifiedBlockDevice,
     config: &ManifestConfig,
+    timeouts: TimeoutConfig,
 ) -> bool {
-    let state = ManifestState::new(config.clone());
+    write_manifest_to(blk, None, config, timeouts)
+}
 
-    match config.mode {
-        ManifestMode::Skip => {
-            serial::println("[MANIFEST] Skipping (not configured)");
-            true
-        }
-        ManifestMode::Fat32 { esp_start_lba } => {
-            state.write_fat32(blk, esp_start_lba)
-        }
-        ManifestMode::RawSector { sector } => {
-            state.write_raw_sector(blk, sector)
-        }
+/// [`write_manifest_standalone`] for an ESP on another disk than the ISO:
+/// the ESP copy goes to `esp`, the raw copy to `blk`.
+pub fn write_manifest_to(
+    blk: &mut UnifiedBlockDevice,
+    esp: Option<&mut UnifiedBlockDevice>,
+    config: &ManifestConfig,
+    timeouts: TimeoutConfig,
+) -> bool {
+    ManifestState::new(config.clone(), timeouts)
+        .write_mode(blk, esp, config.mode)
+        .0
+}
+
+/// Write the partial manifest `config` (from
+/// [`ManifestConfig::partial_from_context`]) for the session in `ctx`.
+///
+/// Once written the manifest claims the partition for a resume, so the
+/// journal entry that would roll it back is committed.
+pub fn write_partial(ctx: &mut Context<'_>, config: &ManifestConfig) -> bool {
+    let timeouts = ctx.timeouts.block();
+    let Some(blk) = ctx.blk_device.as_mut() else {
+        return false;
+    };
+    if !write_manifest_to(blk, ctx.esp_device.as_mut(), config, timeouts) {
+        return false;
+    }
+    if ctx.journal.as_ref().is_some_and(|j| j.pending().is_some()) {
+        journal::commit(ctx);
     }
+    true
 }
 
 /// Regenerate manifest for an existing ISO on disk.
@@ -536,6 +827,7 @@ pub fn write_manifest_standalone(
 /// * `partition_uuid` - UUID of the partition containing the ISO
 /// * `esp_start_lba` - ESP start LBA (for FAT32 mode, 0 to skip)
 /// * `manifest_sector` - Raw sector for manifest (for raw mode, 0 to skip)
+/// * `timeouts` - TSC-based timeouts (`TimeoutConfig::new(tsc_freq)`)
 pub fn regenerate_manifest(
     blk: &mut UnifiedBlockDevice,
     iso_name: &str,
@@ -545,6 +837,7 @@ pub fn regenerate_manifest(
     partition_uuid: [u8; 16],
     esp_start_lba: u64,
     manifest_sector: u64,
+    timeouts: TimeoutConfig,
 ) -> bool {
     serial::println("=================================");
     serial::println("  REGENERATING ISO MANIFEST      ");
@@ -552,7 +845,7 @@ pub fn regenerate_manifest(
     serial::print("[MANIFEST] ISO: ");
     serial::println(iso_name);
     serial::print("[MANIFEST] Size: ");
-    serial::print_u32((iso_size / 1024 / 1024) as u32);
+    serial::print_u64(iso_size / 1024 / 1024);
     serial::println(" MB");
     serial::print("[MANIFEST] Sectors: ");
     serial::print_hex(start_sector);
@@ -562,7 +855,7 @@ pub fn regenerate_manifest(
 
     let mode = if esp_start_lba > 0 {
         serial::print("[MANIFEST] Mode: FAT32 (ESP LBA ");
-        serial::print_u32(esp_start_lba as u32);
+        serial::print_u64(esp_start_lba);
         serial::println(")");
         ManifestMode::Fat32 { esp_start_lba }
     } else if manifest_sector > 0 {
@@ -584,5 +877,5 @@ pub fn regenerate_manifest(
         mode,
     );
 
-    write_manifest_standalone(blk, &config)
+    write_manifest_standalone(blk, &config, timeouts)
 }
@@ -6,17 +6,22 @@ pub mod link;
 pub mod dhcp;
 pub mod dns;
 pub mod connect;
+pub mod checksums;
 pub mod http;
 pub mod done;
+pub mod abort;
 pub mod manifest;
 
-pub use init::InitState;
+pub use init::{split_proxy, split_url, InitState, UrlParts};
 pub use gpt::GptPrepState;
 pub use link::LinkWaitState;
 pub use dhcp::DhcpState;
 pub use dns::DnsState;
 pub use connect::ConnectState;
+pub use checksums::ChecksumState;
 pub use http::HttpState;
 pub use done::{DoneState, FailedState};
+pub(crate) use done::write_exit_report;
+pub use abort::AbortState;
 pub use manifest::{ManifestState, ManifestConfig, ManifestMode};
-pub use manifest::{write_manifest_standalone, regenerate_manifest};
+pub use manifest::{write_manifest_standalone, write_manifest_to, regenerate_manifest};
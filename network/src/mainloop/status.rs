@@ -0,0 +1,210 @@
+//! On-screen status for the post-EBS session.
+//!
+//! With boot services gone only serial shows what the download is doing.
+//! When the bootloader has set up the GOP framebuffer from the handoff
+//! (see [`crate::display::init_display`]), this screen replaces the serial
+//! mirror with a few fixed lines: the phase, how much has arrived, the
+//! throughput and the last error or stall. Redraws are throttled so the
+//! framebuffer writes stay off the hot path.
+
+use alloc::format;
+use alloc::string::String;
+
+use super::context::Progress;
+use crate::display;
+use crate::driver::traits::LinkInfo;
+
+/// Time between progress redraws, in milliseconds.
+const REDRAW_INTERVAL_MS: u64 = 500;
+
+/// Widest progress bar, in characters.
+const MAX_BAR_WIDTH: usize = 60;
+
+// EFI text attributes (foreground | background << 4)
+const ATTR_TITLE: u8 = 0x0F;
+const ATTR_TEXT: u8 = 0x07;
+const ATTR_BAR: u8 = 0x0B;
+const ATTR_WARN: u8 = 0x0E;
+const ATTR_ERROR: u8 = 0x0C;
+
+const ROW_TITLE: usize = 1;
+const ROW_PHASE: usize = 3;
+const ROW_PROGRESS: usize = 4;
+const ROW_BAR: usize = 5;
+const ROW_SPEED: usize = 6;
+const ROW_LINK: usize = 7;
+const ROW_STATUS: usize = 8;
+
+/// Status screen state. Does nothing without a display.
+pub struct StatusScreen {
+    enabled: bool,
+    tsc_freq: u64,
+    last_draw_tsc: u64,
+}
+
+impl StatusScreen {
+    /// Take over the display, if there is one.
+    pub fn new(tsc_freq: u64) -> Self {
+        let enabled = display::display_available();
+        if enabled {
+            display::set_mirror(false);
+            display::display_clear();
+            display::display_line(
+                ROW_TITLE,
+                "  MorpheusX network download - ESC aborts",
+                ATTR_TITLE,
+            );
+        }
+        Self {
+            enabled,
+            tsc_freq,
+            last_draw_tsc: 0,
+        }
+    }
+
+    /// Show the state machine's current phase. Progress is redrawn at
+    /// the next update, so the final numbers show before a terminal state
+    /// resets the machine.
+    pub fn phase(&mut self, name: &str) {
+        if self.enabled {
+            display::display_line(ROW_PHASE, &format!("  Phase:    {}", name), ATTR_TEXT);
+            self.last_draw_tsc = 0;
+        }
+    }
+
+    /// Show the negotiated link, or that the NIC doesn't report one.
+    pub fn link(&mut self, info: Option<LinkInfo>) {
+        if self.enabled {
+            let text = match info {
+                Some(info) => format!("  Link:     {}", info),
+                None => String::from("  Link:     -"),
+            };
+            display::display_line(ROW_LINK, &text, ATTR_TEXT);
+        }
+    }
+
+    /// Show transfer progress, at most every [`REDRAW_INTERVAL_MS`].
+    ///
+    /// `elapsed` is the time since the body started, in ticks.
+    pub fn progress(&mut self, progress: &Progress, tsc: u64, elapsed: u64) {
+        let interval = self.tsc_freq / 1000 * REDRAW_INTERVAL_MS;
+        if !self.enabled || tsc.wrapping_sub(self.last_draw_tsc) < interval {
+            return;
+        }
+        self.last_draw_tsc = tsc;
+
+        let bytes = progress.bytes_downloaded;
+        display::display_line(
+            ROW_PROGRESS,
+            &format!(
+                "  Progress: {}",
+                progress_text(bytes, progress.content_length)
+            ),
+            ATTR_TEXT,
+        );
+        if let Some(total) = progress.content_length {
+            display::display_line(
+                ROW_BAR,
+                &format!("  {}", progress_bar(bytes, total, MAX_BAR_WIDTH)),
+                ATTR_BAR,
+            );
+        }
+        display::display_line(
+            ROW_SPEED,
+            &format!("  Speed:    {}", rate_text(bytes, elapsed, self.tsc_freq)),
+            ATTR_TEXT,
+        );
+        match progress.stall {
+            Some(reason) => display::display_line(
+                ROW_STATUS,
+                &format!("  Stalled: {}", reason.describe()),
+                ATTR_WARN,
+            ),
+            None => display::display_line(ROW_STATUS, "", ATTR_TEXT),
+        }
+    }
+
+    /// Show why the download failed.
+    pub fn error(&mut self, reason: &str) {
+        if self.enabled {
+            display::display_line(ROW_STATUS, &format!("  FAILED: {}", reason), ATTR_ERROR);
+        }
+    }
+}
+
+/// "512 MB of 2048 MB (25%)", or just the amount with no total known.
+pub fn progress_text(bytes: u64, total: Option<u64>) -> String {
+    let mb = bytes / (1024 * 1024);
+    match total {
+        Some(total) if total > 0 => format!(
+            "{} MB of {} MB ({}%)",
+            mb,
+            total / (1024 * 1024),
+            bytes.min(total) * 100 / total
+        ),
+        _ => format!("{} MB", mb),
+    }
+}
+
+/// Throughput over `elapsed` ticks, "12.3 MB/s".
+pub fn rate_text(bytes: u64, elapsed: u64, tsc_freq: u64) -> String {
+    if elapsed == 0 || tsc_freq == 0 {
+        return String::from("-");
+    }
+    let per_sec = bytes as u128 * tsc_freq as u128 / elapsed as u128;
+    let tenths = per_sec * 10 / (1024 * 1024);
+    format!("{}.{} MB/s", tenths / 10, tenths % 10)
+}
+
+/// "[#####.....]" with `width` cells between the brackets.
+pub fn progress_bar(bytes: u64, total: u64, width: usize) -> String {
+    let filled = if total == 0 {
+        0
+    } else {
+        (bytes.min(total) as u128 * width as u128 / total as u128) as usize
+    };
+    let mut bar = String::with_capacity(width + 2);
+    bar.push('[');
+    (0..width).for_each(|i| bar.push(if i < filled { '#' } else { '.' }));
+    bar.push(']');
+    bar
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: u64 = 1024 * 1024;
+
+    #[test]
+    fn test_progress_text() {
+        assert_eq!(
+            progress_text(512 * MB, Some(2048 * MB)),
+            "512 MB of 2048 MB (25%)"
+        );
+        assert_eq!(progress_text(3 * MB, None), "3 MB");
+        assert_eq!(progress_text(3 * MB, Some(0)), "3 MB");
+        // More than announced never shows past 100%
+        assert_eq!(progress_text(5 * MB, Some(4 * MB)), "5 MB of 4 MB (100%)");
+    }
+
+    #[test]
+    fn test_rate_text() {
+        let freq = 1_000_000_000;
+        assert_eq!(rate_text(25 * MB, 2 * freq, freq), "12.5 MB/s");
+        assert_eq!(rate_text(MB, 0, freq), "-");
+        // Large counts don't overflow
+        assert_eq!(
+            rate_text(u64::MAX / 2, u64::MAX / 2, 3_000_000_000),
+            "2861.0 MB/s"
+        );
+    }
+
+    #[test]
+    fn test_progress_bar() {
+        assert_eq!(progress_bar(0, 100, 4), "[....]");
+        assert_eq!(progress_bar(50, 100, 4), "[##..]");
+        assert_eq!(progress_bar(200, 100, 4), "[####]");
+        assert_eq!(progress_bar(1, 0, 2), "[..]");
+    }
+}
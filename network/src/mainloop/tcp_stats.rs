@@ -0,0 +1,382 @@
+//! TCP telemetry taken from the frames crossing the adapter.
+//!
+//! smoltcp keeps its RTT estimator and retransmit state private, so the
+//! adapter watches the download connection on the wire instead. The
+//! numbers are meant to tell the usual throughput complaints apart:
+//!
+//! - slow mirror: high RTT or small server window, no retransmits
+//! - lossy LAN: the server resends data we already have, or segments
+//!   arrive after a gap
+//! - driver drops: the NIC refused frames or reported receive errors
+//!
+//! RTT is sampled from segments we send (handshake, request), one at a
+//! time, skipping retransmitted ones (Karn's rule).
+
+/// Counters for the TCP connection, as seen on the wire.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TcpTelemetry {
+    /// Smoothed round-trip time in ms (None = no sample yet)
+    pub srtt_ms: Option<u32>,
+    /// RTT samples taken
+    pub rtt_samples: u32,
+    /// TCP segments sent
+    pub segments_out: u32,
+    /// TCP segments received
+    pub segments_in: u32,
+    /// Segments we sent again
+    pub retransmits: u32,
+    /// Segments the server sent again (data we already had)
+    pub peer_retransmits: u32,
+    /// Segments that arrived after a gap (loss between server and us)
+    pub out_of_order: u32,
+    /// Receive window we last advertised, in bytes
+    pub rx_window: u32,
+    /// Window the server last advertised, in bytes
+    pub peer_window: u32,
+    /// Frames the driver refused to send
+    pub tx_drops: u32,
+    /// Receive errors reported by the driver
+    pub rx_errors: u32,
+}
+
+const ETH_HEADER_LEN: usize = 14;
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const IP_PROTO_TCP: u8 = 6;
+
+const TCP_FIN: u8 = 0x01;
+const TCP_SYN: u8 = 0x02;
+const TCP_ACK: u8 = 0x10;
+
+const TCP_OPT_END: u8 = 0;
+const TCP_OPT_NOP: u8 = 1;
+const TCP_OPT_WSCALE: u8 = 3;
+
+/// Fields of one TCP segment.
+struct Segment {
+    seq: u32,
+    ack: u32,
+    flags: u8,
+    window: u16,
+    payload_len: u32,
+    wscale: Option<u8>,
+}
+
+impl Segment {
+    /// Parse an Ethernet frame; None if it isn't IPv4 TCP.
+    fn parse(frame: &[u8]) -> Option<Self> {
+        if frame.len() < ETH_HEADER_LEN + 20 {
+            return None;
+        }
+        if u16::from_be_bytes([frame[12], frame[13]]) != ETHERTYPE_IPV4 {
+            return None;
+        }
+        let ip = &frame[ETH_HEADER_LEN..];
+        let ihl = ((ip[0] & 0x0F) as usize) * 4;
+        let total_len = u16::from_be_bytes([ip[2], ip[3]]) as usize;
+        if ip[9] != IP_PROTO_TCP || ihl < 20 || total_len > ip.len() || total_len < ihl + 20 {
+            return None;
+        }
+
+        let tcp = &ip[ihl..total_len];
+        let doff = ((tcp[12] >> 4) as usize) * 4;
+        if doff < 20 || doff > tcp.len() {
+            return None;
+        }
+
+        Some(Self {
+            seq: u32::from_be_bytes([tcp[4], tcp[5], tcp[6], tcp[7]]),
+            ack: u32::from_be_bytes([tcp[8], tcp[9], tcp[10], tcp[11]]),
+            flags: tcp[13],
+            window: u16::from_be_bytes([tcp[14], tcp[15]]),
+            payload_len: (tcp.len() - doff) as u32,
+            wscale: parse_wscale(&tcp[20..doff]),
+        })
+    }
+
+    fn has(&self, flag: u8) -> bool {
+        self.flags & flag != 0
+    }
+
+    /// Sequence space taken (payload plus SYN/FIN).
+    fn len(&self) -> u32 {
+        self.payload_len + self.has(TCP_SYN) as u32 + self.has(TCP_FIN) as u32
+    }
+}
+
+/// Window scale shift from a SYN's options.
+fn parse_wscale(mut options: &[u8]) -> Option<u8> {
+    while let Some(&kind) = options.first() {
+        match kind {
+            TCP_OPT_END => return None,
+            TCP_OPT_NOP => options = &options[1..],
+            _ => {
+                let len = *options.get(1)? as usize;
+                if len < 2 || len > options.len() {
+                    return None;
+                }
+                if kind == TCP_OPT_WSCALE && len == 3 {
+                    return Some(options[2].min(14));
+                }
+                options = &options[len..];
+            }
+        }
+    }
+    None
+}
+
+/// `a` before `b` in sequence space.
+fn seq_lt(a: u32, b: u32) -> bool {
+    (a.wrapping_sub(b) as i32) < 0
+}
+
+/// Tracks the most recent TCP connection opened through the adapter.
+#[derive(Debug, Default)]
+pub struct TcpTracker {
+    stats: TcpTelemetry,
+    /// Window scale shifts offered in our SYN and the server's
+    our_wscale: Option<u8>,
+    peer_wscale: Option<u8>,
+    /// End of the highest sequence we have sent
+    snd_max: Option<u32>,
+    /// Next sequence expected from the server
+    rcv_nxt: Option<u32>,
+    /// Sequence an ACK has to reach, and when the segment went out
+    rtt_probe: Option<(u32, i64)>,
+    /// Smoothed RTT in ms, times 8 (RFC 6298 fixed point)
+    srtt_x8: u32,
+    /// Initial sequence of our SYN, to tell a resent SYN from a new one
+    syn_seq: Option<u32>,
+}
+
+impl TcpTracker {
+    /// Counters so far. Driver drop counts are left at zero.
+    pub fn telemetry(&self) -> TcpTelemetry {
+        self.stats
+    }
+
+    /// Window shift once both sides have agreed on scaling.
+    fn shift(&self, ours: bool) -> u32 {
+        match (self.our_wscale, self.peer_wscale) {
+            (Some(our), Some(peer)) => (if ours { our } else { peer }) as u32,
+            _ => 0,
+        }
+    }
+
+    /// Look at a frame we are about to send.
+    pub fn observe_tx(&mut self, frame: &[u8], now_ms: i64) {
+        let Some(seg) = Segment::parse(frame) else {
+            return;
+        };
+
+        if seg.has(TCP_SYN) && !seg.has(TCP_ACK) && self.syn_seq != Some(seg.seq) {
+            // A new connection (or a reconnect after a retry)
+            *self = Self::default();
+            self.syn_seq = Some(seg.seq);
+            self.our_wscale = seg.wscale;
+        }
+        self.stats.segments_out += 1;
+
+        if seg.has(TCP_ACK) {
+            match self.rcv_nxt {
+                Some(nxt) if !seq_lt(nxt, seg.ack) => {}
+                _ => self.rcv_nxt = Some(seg.ack),
+            }
+        }
+        if !seg.has(TCP_SYN) {
+            self.stats.rx_window = (seg.window as u32) << self.shift(true);
+        }
+
+        if seg.len() == 0 {
+            return;
+        }
+        let end = seg.seq.wrapping_add(seg.len());
+        match self.snd_max {
+            Some(max) if seq_lt(seg.seq, max) => {
+                self.stats.retransmits += 1;
+                // Karn: an ACK can't be matched to either copy
+                self.rtt_probe = None;
+            }
+            _ => {
+                self.snd_max = Some(end);
+                if self.rtt_probe.is_none() {
+                    self.rtt_probe = Some((end, now_ms));
+                }
+            }
+        }
+    }
+
+    /// Look at a frame the NIC received.
+    pub fn observe_rx(&mut self, frame: &[u8], now_ms: i64) {
+        let Some(seg) = Segment::parse(frame) else {
+            return;
+        };
+        self.stats.segments_in += 1;
+
+        if seg.has(TCP_SYN) {
+            self.peer_wscale = seg.wscale;
+            self.rcv_nxt = Some(seg.seq.wrapping_add(1));
+            self.stats.peer_window = seg.window as u32;
+        } else {
+            self.stats.peer_window = (seg.window as u32) << self.shift(false);
+        }
+
+        if seg.has(TCP_ACK) {
+            if let Some((end, sent_ms)) = self.rtt_probe {
+                if !seq_lt(seg.ack, end) {
+                    self.rtt_probe = None;
+                    self.add_rtt_sample(now_ms.saturating_sub(sent_ms).max(0) as u32);
+                }
+            }
+        }
+
+        if seg.payload_len == 0 || seg.has(TCP_SYN) {
+            return;
+        }
+        let Some(nxt) = self.rcv_nxt else {
+            return;
+        };
+        let end = seg.seq.wrapping_add(seg.len());
+        if !seq_lt(nxt, end) {
+            self.stats.peer_retransmits += 1;
+        } else if seq_lt(nxt, seg.seq) {
+            self.stats.out_of_order += 1;
+        } else {
+            self.rcv_nxt = Some(end);
+        }
+    }
+
+    fn add_rtt_sample(&mut self, rtt_ms: u32) {
+        let rtt_ms = rtt_ms.min(u32::MAX / 16);
+        self.srtt_x8 = if self.stats.rtt_samples == 0 {
+            rtt_ms * 8
+        } else {
+            // srtt = 7/8 srtt + 1/8 sample
+            self.srtt_x8 - self.srtt_x8 / 8 + rtt_ms
+        };
+        self.stats.rtt_samples += 1;
+        self.stats.srtt_ms = Some(self.srtt_x8.div_ceil(8));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    /// Build an Ethernet/IPv4/TCP frame.
+    fn frame(
+        seq: u32,
+        ack: u32,
+        flags: u8,
+        window: u16,
+        options: &[u8],
+        payload: usize,
+    ) -> Vec<u8> {
+        let doff = 20 + options.len();
+        let total = 20 + doff + payload;
+        let mut f = vec![0u8; ETH_HEADER_LEN + total];
+        f[12..14].copy_from_slice(&ETHERTYPE_IPV4.to_be_bytes());
+        let ip = &mut f[ETH_HEADER_LEN..];
+        ip[0] = 0x45;
+        ip[2..4].copy_from_slice(&(total as u16).to_be_bytes());
+        ip[9] = IP_PROTO_TCP;
+        let tcp = &mut ip[20..];
+        tcp[4..8].copy_from_slice(&seq.to_be_bytes());
+        tcp[8..12].copy_from_slice(&ack.to_be_bytes());
+        tcp[12] = ((doff / 4) as u8) << 4;
+        tcp[13] = flags;
+        tcp[14..16].copy_from_slice(&window.to_be_bytes());
+        tcp[20..doff].copy_from_slice(options);
+        f
+    }
+
+    const WSCALE_7: [u8; 4] = [TCP_OPT_NOP, TCP_OPT_WSCALE, 3, 7];
+    const WSCALE_2: [u8; 4] = [TCP_OPT_NOP, TCP_OPT_WSCALE, 3, 2];
+
+    /// Handshake at t=0/40 plus a 100 byte request at t=40, acked at t=70.
+    fn connected() -> TcpTracker {
+        let mut t = TcpTracker::default();
+        t.observe_tx(&frame(1000, 0, TCP_SYN, 64240, &WSCALE_7, 0), 0);
+        t.observe_rx(
+            &frame(5000, 1001, TCP_SYN | TCP_ACK, 65535, &WSCALE_2, 0),
+            40,
+        );
+        t.observe_tx(&frame(1001, 5001, TCP_ACK, 512, &[], 100), 40);
+        t.observe_rx(&frame(5001, 1101, TCP_ACK, 1000, &[], 0), 70);
+        t
+    }
+
+    #[test]
+    fn test_rtt_and_windows() {
+        let s = connected().telemetry();
+        assert_eq!(s.rtt_samples, 2);
+        // 40 first, then 7/8 * 40 + 1/8 * 30
+        assert_eq!(s.srtt_ms, Some(39));
+        assert_eq!(s.rx_window, 512 << 7);
+        assert_eq!(s.peer_window, 1000 << 2);
+        assert_eq!(s.retransmits, 0);
+    }
+
+    #[test]
+    fn test_no_scaling_unless_both_offer_it() {
+        let mut t = TcpTracker::default();
+        t.observe_tx(&frame(1, 0, TCP_SYN, 64240, &WSCALE_7, 0), 0);
+        t.observe_rx(&frame(9, 2, TCP_SYN | TCP_ACK, 65535, &[], 0), 1);
+        t.observe_tx(&frame(2, 10, TCP_ACK, 512, &[], 0), 1);
+        assert_eq!(t.telemetry().rx_window, 512);
+    }
+
+    #[test]
+    fn test_our_retransmit_skips_rtt() {
+        let mut t = TcpTracker::default();
+        t.observe_tx(&frame(1000, 0, TCP_SYN, 64240, &[], 0), 0);
+        t.observe_tx(&frame(1000, 0, TCP_SYN, 64240, &[], 0), 1000);
+        t.observe_rx(&frame(5000, 1001, TCP_SYN | TCP_ACK, 65535, &[], 0), 1010);
+        t.observe_tx(&frame(1001, 5001, TCP_ACK, 512, &[], 100), 1010);
+        t.observe_tx(&frame(1001, 5001, TCP_ACK, 512, &[], 100), 2000);
+        t.observe_rx(&frame(5001, 1101, TCP_ACK, 1000, &[], 0), 2010);
+
+        let s = t.telemetry();
+        assert_eq!(s.retransmits, 2);
+        assert_eq!(s.rtt_samples, 0);
+
+        // The next clean segment is sampled again
+        t.observe_tx(&frame(1101, 5001, TCP_ACK, 512, &[], 10), 3000);
+        t.observe_rx(&frame(5001, 1111, TCP_ACK, 1000, &[], 0), 3025);
+        assert_eq!(t.telemetry().srtt_ms, Some(25));
+
+        // A SYN with a new sequence starts over
+        t.observe_tx(&frame(9000, 0, TCP_SYN, 64240, &[], 0), 4000);
+        assert_eq!(t.telemetry().retransmits, 0);
+    }
+
+    #[test]
+    fn test_peer_loss() {
+        let mut t = connected();
+        t.observe_rx(&frame(5001, 1101, TCP_ACK, 1000, &[], 1000), 80);
+        // 6001..7001 lost, 7001 arrives after the gap
+        t.observe_rx(&frame(7001, 1101, TCP_ACK, 1000, &[], 1000), 81);
+        t.observe_tx(&frame(1101, 6001, TCP_ACK, 512, &[], 0), 81);
+        // Server resends 5001 (already acked), then fills the gap
+        t.observe_rx(&frame(5001, 1101, TCP_ACK, 1000, &[], 1000), 300);
+        t.observe_rx(&frame(6001, 1101, TCP_ACK, 1000, &[], 1000), 301);
+        t.observe_tx(&frame(1101, 8001, TCP_ACK, 512, &[], 0), 301);
+        t.observe_rx(&frame(8001, 1101, TCP_ACK, 1000, &[], 1000), 302);
+
+        let s = t.telemetry();
+        assert_eq!(s.out_of_order, 1);
+        assert_eq!(s.peer_retransmits, 1);
+        assert_eq!(s.segments_in, 7);
+    }
+
+    #[test]
+    fn test_ignores_non_tcp() {
+        let mut t = connected();
+        let mut udp = frame(1, 1, TCP_ACK, 1, &[], 10);
+        udp[ETH_HEADER_LEN + 9] = 17;
+        t.observe_rx(&udp, 100);
+        t.observe_rx(&[0u8; 10], 100);
+        assert_eq!(t.telemetry().segments_in, 2);
+    }
+}
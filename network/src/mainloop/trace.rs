@@ -0,0 +1,295 @@
+//! Where the main loop spends its time.
+//!
+//! A slow download can be waiting on the NIC, on smoltcp, on the disk or
+//! on one state doing too much per step, and the throughput numbers alone
+//! don't say which. This records how long each loop phase and each state
+//! step takes into log2 histograms, and dumps them to serial when the
+//! session ends.
+//!
+//! Timing uses the raw TSC: one read costs a few cycles, where the HPET
+//! or PM timer fallbacks would cost a bus access each. On a machine whose
+//! TSC drifts the durations are approximate, which is enough to find the
+//! phase that dominates.
+
+use alloc::format;
+use alloc::string::String;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use super::runner::get_tsc;
+use super::serial;
+use crate::sync::SpinLock;
+
+/// Buckets per histogram. Bucket `i` counts durations below `2^(i+1)` us;
+/// the last one also takes everything longer (~8 s and up).
+pub const BUCKETS: usize = 24;
+
+/// Distinct state names tracked.
+const MAX_STATES: usize = 16;
+
+/// Main loop phases that get their own histogram.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// smoltcp `Interface::poll`, including driver RX/TX it does itself
+    Poll,
+    /// RX descriptor refill
+    RxRefill,
+    /// TX completion collection
+    TxCompletion,
+    /// `DiskWriter` writes and flushes
+    DiskWrite,
+}
+
+impl Phase {
+    const ALL: [Phase; 4] = [
+        Phase::Poll,
+        Phase::RxRefill,
+        Phase::TxCompletion,
+        Phase::DiskWrite,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Phase::Poll => "poll",
+            Phase::RxRefill => "rx refill",
+            Phase::TxCompletion => "tx complete",
+            Phase::DiskWrite => "disk write",
+        }
+    }
+}
+
+/// Log2 histogram of durations in microseconds.
+#[derive(Debug, Clone, Copy)]
+pub struct Histogram {
+    buckets: [u32; BUCKETS],
+    count: u64,
+    total_us: u64,
+    max_us: u64,
+}
+
+impl Histogram {
+    pub const fn new() -> Self {
+        Self {
+            buckets: [0; BUCKETS],
+            count: 0,
+            total_us: 0,
+            max_us: 0,
+        }
+    }
+
+    /// Bucket holding `us`.
+    pub fn bucket(us: u64) -> usize {
+        // 0 and 1 us share bucket 0
+        (63 - (us | 1).leading_zeros() as usize).min(BUCKETS - 1)
+    }
+
+    pub fn record(&mut self, us: u64) {
+        let bucket = &mut self.buckets[Self::bucket(us)];
+        *bucket = bucket.saturating_add(1);
+        self.count += 1;
+        self.total_us = self.total_us.saturating_add(us);
+        self.max_us = self.max_us.max(us);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn total_us(&self) -> u64 {
+        self.total_us
+    }
+
+    pub fn max_us(&self) -> u64 {
+        self.max_us
+    }
+
+    pub fn mean_us(&self) -> u64 {
+        self.total_us.checked_div(self.count).unwrap_or(0)
+    }
+
+    /// Upper bound of the bucket holding the `pct`th percentile, capped
+    /// at the longest duration seen.
+    pub fn percentile_us(&self, pct: u32) -> u64 {
+        if self.count == 0 {
+            return 0;
+        }
+        let rank = (self.count * pct.min(100) as u64).div_ceil(100).max(1);
+        let mut seen = 0u64;
+        for (i, &n) in self.buckets.iter().enumerate() {
+            seen += n as u64;
+            if seen >= rank {
+                return ((1u64 << (i + 1)) - 1).min(self.max_us);
+            }
+        }
+        self.max_us
+    }
+
+    /// Non-empty buckets as "<4us:10 <8us:3".
+    pub fn buckets_text(&self) -> String {
+        let mut text = String::new();
+        for (i, &n) in self.buckets.iter().enumerate().filter(|(_, &n)| n > 0) {
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            if i == BUCKETS - 1 {
+                text.push_str(&format!(">={}us:{}", 1u64 << i, n));
+            } else {
+                text.push_str(&format!("<{}us:{}", 1u64 << (i + 1), n));
+            }
+        }
+        text
+    }
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// One summary line of the dump.
+pub fn summary_text(name: &str, h: &Histogram) -> String {
+    format!(
+        "{:<12} n={} total={}ms mean={}us p50={}us p99={}us max={}us",
+        name,
+        h.count(),
+        h.total_us() / 1000,
+        h.mean_us(),
+        h.percentile_us(50),
+        h.percentile_us(99),
+        h.max_us()
+    )
+}
+
+struct Trace {
+    phases: [Histogram; Phase::ALL.len()],
+    states: [(Option<&'static str>, Histogram); MAX_STATES],
+}
+
+static TRACE: SpinLock<Trace> = SpinLock::new(
+    "trace",
+    Trace {
+        phases: [Histogram::new(); Phase::ALL.len()],
+        states: [(None, Histogram::new()); MAX_STATES],
+    },
+);
+
+/// TSC ticks per microsecond; 0 until `reset`, which leaves tracing off.
+static TICKS_PER_US: AtomicU64 = AtomicU64::new(0);
+
+/// Clear all histograms and start tracing with a `tsc_freq` Hz TSC.
+pub fn reset(tsc_freq: u64) {
+    let mut trace = TRACE.lock();
+    trace.phases = [Histogram::new(); Phase::ALL.len()];
+    trace.states = [(None, Histogram::new()); MAX_STATES];
+    TICKS_PER_US.store(tsc_freq / 1_000_000, Ordering::Relaxed);
+}
+
+/// Timestamp to pass to `record` / `record_state` when the timed work ends.
+#[inline]
+pub fn start() -> u64 {
+    get_tsc()
+}
+
+fn elapsed_us(start: u64) -> Option<u64> {
+    match TICKS_PER_US.load(Ordering::Relaxed) {
+        0 => None,
+        per_us => Some(get_tsc().wrapping_sub(start) / per_us),
+    }
+}
+
+/// Record `phase` as having run since `start`.
+pub fn record(phase: Phase, start: u64) {
+    if let Some(us) = elapsed_us(start) {
+        TRACE.lock().phases[phase as usize].record(us);
+    }
+}
+
+/// Record one step of the state named `name` as having run since `start`.
+pub fn record_state(name: &'static str, start: u64) {
+    let Some(us) = elapsed_us(start) else {
+        return;
+    };
+    let mut trace = TRACE.lock();
+    // States beyond the table go unrecorded
+    let slot = trace
+        .states
+        .iter()
+        .position(|(n, _)| n.is_none_or(|n| n == name));
+    if let Some(slot) = slot {
+        trace.states[slot].0 = Some(name);
+        trace.states[slot].1.record(us);
+    }
+}
+
+/// Print every histogram with samples to serial.
+pub fn dump() {
+    if TICKS_PER_US.load(Ordering::Relaxed) == 0 {
+        return;
+    }
+    let trace = TRACE.lock();
+    serial::println("[TRACE] Time per loop phase:");
+    for (phase, h) in Phase::ALL.iter().zip(trace.phases.iter()) {
+        print_histogram(phase.name(), h);
+    }
+    serial::println("[TRACE] Time per state step:");
+    for (name, h) in trace.states.iter() {
+        if let Some(name) = name {
+            print_histogram(name, h);
+        }
+    }
+}
+
+fn print_histogram(name: &str, h: &Histogram) {
+    if h.count() == 0 {
+        return;
+    }
+    serial::print("[TRACE]   ");
+    serial::println(&summary_text(name, h));
+    serial::print("[TRACE]     ");
+    serial::println(&h.buckets_text());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buckets() {
+        assert_eq!(Histogram::bucket(0), 0);
+        assert_eq!(Histogram::bucket(1), 0);
+        assert_eq!(Histogram::bucket(2), 1);
+        assert_eq!(Histogram::bucket(3), 1);
+        assert_eq!(Histogram::bucket(1024), 10);
+        assert_eq!(Histogram::bucket(u64::MAX), BUCKETS - 1);
+
+        let mut h = Histogram::new();
+        h.record(3);
+        h.record(5);
+        h.record(6);
+        assert_eq!(h.buckets_text(), "<4us:1 <8us:2");
+        h.record(u64::MAX);
+        assert!(h.buckets_text().ends_with(">=8388608us:1"));
+    }
+
+    #[test]
+    fn test_percentiles() {
+        let mut h = Histogram::new();
+        assert_eq!(h.percentile_us(50), 0);
+        assert_eq!(h.mean_us(), 0);
+        for _ in 0..99 {
+            h.record(10);
+        }
+        h.record(5000);
+        assert_eq!(h.count(), 100);
+        assert_eq!(h.mean_us(), (99 * 10 + 5000) / 100);
+        // 10 us lands in [8, 16)
+        assert_eq!(h.percentile_us(50), 15);
+        assert_eq!(h.percentile_us(99), 15);
+        // Capped at the longest duration seen
+        assert_eq!(h.percentile_us(100), 5000);
+        assert_eq!(
+            summary_text("poll", &h),
+            "poll         n=100 total=5ms mean=59us p50=15us p99=15us max=5000us"
+        );
+    }
+}
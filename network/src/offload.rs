@@ -0,0 +1,116 @@
+//! Offloading CPU-bound work to other cores.
+//!
+//! The poll loop owns core 0. Work that does not touch devices (hashing,
+//! compression) can run elsewhere while core 0 keeps the NIC and disk
+//! busy. This crate does not start cores itself; the platform installs a
+//! spawn function (hwinit's SMP work queue) with `install()`. Without
+//! one, or when it declines a job, the job runs inline.
+//!
+//! ```ignore
+//! let mut task = Task::new(hash_chunk, &mut job as *mut Job as *mut ());
+//! unsafe { task.start() };
+//! // ... drive I/O ...
+//! task.wait();
+//! ```
+
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// A job function; the argument is the pointer given to `Task::new`.
+pub type JobFn = unsafe fn(*mut ());
+
+/// Platform spawn function: queue `func(arg)` on another core and set
+/// `done` when it returns. Returns false if the job was not queued.
+pub type SpawnFn = unsafe fn(func: JobFn, arg: *mut (), done: *const AtomicBool) -> bool;
+
+/// Installed spawn function (0 = none).
+static SPAWN: AtomicUsize = AtomicUsize::new(0);
+
+/// Install the platform's spawn function.
+pub fn install(spawn: SpawnFn) {
+    SPAWN.store(spawn as usize, Ordering::Release);
+}
+
+/// Whether jobs can leave this core.
+pub fn available() -> bool {
+    SPAWN.load(Ordering::Acquire) != 0
+}
+
+fn installed() -> Option<SpawnFn> {
+    match SPAWN.load(Ordering::Acquire) {
+        0 => None,
+        // SAFETY: only ever stored from a valid `SpawnFn`.
+        ptr => Some(unsafe { core::mem::transmute::<usize, SpawnFn>(ptr) }),
+    }
+}
+
+/// One job and its completion flag.
+pub struct Task {
+    func: JobFn,
+    arg: *mut (),
+    done: AtomicBool,
+}
+
+impl Task {
+    pub fn new(func: JobFn, arg: *mut ()) -> Self {
+        Self {
+            func,
+            arg,
+            done: AtomicBool::new(true),
+        }
+    }
+
+    /// Run the job, on another core if possible.
+    ///
+    /// # Safety
+    /// `func(arg)` must be safe on any core, `arg` must stay valid until
+    /// `wait()` returns, and the task must not move before then.
+    pub unsafe fn start(&mut self) {
+        self.done.store(false, Ordering::Relaxed);
+        if let Some(spawn) = installed() {
+            if spawn(self.func, self.arg, &self.done) {
+                return;
+            }
+        }
+        (self.func)(self.arg);
+        self.done.store(true, Ordering::Release);
+    }
+
+    /// Whether the job has finished (true if never started).
+    pub fn is_done(&self) -> bool {
+        self.done.load(Ordering::Acquire)
+    }
+
+    /// Spin until the job has finished.
+    pub fn wait(&self) {
+        while !self.is_done() {
+            core::hint::spin_loop();
+        }
+    }
+}
+
+impl Drop for Task {
+    fn drop(&mut self) {
+        // Another core may still be using `arg` or `done`.
+        self.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn add_one(arg: *mut ()) {
+        *(arg as *mut u32) += 1;
+    }
+
+    #[test]
+    fn test_task_runs_inline_without_spawn() {
+        let mut value = 41u32;
+        let mut task = Task::new(add_one, &mut value as *mut u32 as *mut ());
+        assert!(task.is_done());
+        unsafe { task.start() };
+        task.wait();
+        drop(task);
+        assert_eq!(value, 42);
+    }
+}
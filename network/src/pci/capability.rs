@@ -1,17 +1,20 @@
 //! PCI Capability chain walking and VirtIO capability parsing.
 //!
 //! Provides functions to discover and parse VirtIO PCI Modern capabilities.
+//! On x86_64 the public API calls the assembly in `virtio_cap.s`; elsewhere
+//! it is implemented on top of `WalkCaps` with the same semantics.
 //!
 //! # Reference
 //! - PCI Spec 3.0 §6.7 (Capability List)
 //! - VirtIO Spec 1.2 §4.1.4 (PCI Device Discovery)
 
-use super::config::{pci_cfg_read16, pci_cfg_read32, pci_cfg_read8, PciAddr};
+use super::config::{pci_cfg_read16, pci_cfg_read32, pci_cfg_read8, pci_cfg_write16, PciAddr};
 
 // ═══════════════════════════════════════════════════════════════════════════
 // ASM BINDINGS
 // ═══════════════════════════════════════════════════════════════════════════
 
+#[cfg(target_arch = "x86_64")]
 extern "win64" {
     /// Check if device has capability list.
     fn asm_pci_has_capabilities(bus: u8, device: u8, function: u8) -> u32;
@@ -50,6 +53,18 @@ extern "win64" {
 /// PCI capability ID: Vendor-specific (used by VirtIO).
 pub const PCI_CAP_ID_VNDR: u8 = 0x09;
 
+/// PCI capability ID: PCI Express.
+pub const PCI_CAP_ID_EXP: u8 = 0x10;
+
+/// PCIe capability: Link Control register offset.
+pub const PCI_EXP_LNKCTL: u8 = 0x10;
+
+/// Link Control: ASPM L0s entry enabled.
+pub const PCI_EXP_LNKCTL_ASPM_L0S: u16 = 1 << 0;
+
+/// Link Control: ASPM L1 entry enabled.
+pub const PCI_EXP_LNKCTL_ASPM_L1: u16 = 1 << 1;
+
 /// VirtIO PCI capability type: Common configuration.
 pub const VIRTIO_PCI_CAP_COMMON: u8 = 1;
 
@@ -157,12 +172,26 @@ impl VirtioPciCaps {
 
 /// Check if a PCI device supports capability list.
 pub fn has_capabilities(addr: PciAddr) -> bool {
-    unsafe { asm_pci_has_capabilities(addr.bus, addr.device, addr.function) != 0 }
+    #[cfg(target_arch = "x86_64")]
+    {
+        unsafe { asm_pci_has_capabilities(addr.bus, addr.device, addr.function) != 0 }
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        pci_cfg_read16(addr, super::config::offset::STATUS) & super::config::status::CAP_LIST != 0
+    }
 }
 
 /// Get the first capability pointer for a device.
 pub fn get_cap_ptr(addr: PciAddr) -> Option<u8> {
+    #[cfg(target_arch = "x86_64")]
     let ptr = unsafe { asm_pci_get_cap_ptr(addr.bus, addr.device, addr.function) };
+    #[cfg(not(target_arch = "x86_64"))]
+    let ptr = if has_capabilities(addr) {
+        (pci_cfg_read8(addr, super::config::offset::CAP_PTR) & 0xFC) as u32
+    } else {
+        0
+    };
     if ptr != 0 && ptr < 256 {
         Some(ptr as u8)
     } else {
@@ -172,7 +201,12 @@ pub fn get_cap_ptr(addr: PciAddr) -> Option<u8> {
 
 /// Find a capability by ID.
 pub fn find_cap(addr: PciAddr, cap_id: u8) -> Option<u8> {
+    #[cfg(target_arch = "x86_64")]
     let offset = unsafe { asm_pci_find_cap(addr.bus, addr.device, addr.function, cap_id) };
+    #[cfg(not(target_arch = "x86_64"))]
+    let offset = WalkCaps::new(addr)
+        .find(|&(_, id)| id == cap_id)
+        .map_or(0, |(off, _)| off as u32);
     if offset != 0 && offset < 256 {
         Some(offset as u8)
     } else {
@@ -180,9 +214,29 @@ pub fn find_cap(addr: PciAddr, cap_id: u8) -> Option<u8> {
     }
 }
 
+/// Clear ASPM states in `states` (`PCI_EXP_LNKCTL_ASPM_*`) in the
+/// device's PCIe Link Control register.
+///
+/// Returns the ASPM bits that were set before, or None if the device has
+/// no PCIe capability.
+pub fn disable_aspm(addr: PciAddr, states: u16) -> Option<u16> {
+    let cap = find_cap(addr, PCI_CAP_ID_EXP)?;
+    let lnkctl_off = cap + PCI_EXP_LNKCTL;
+    let lnkctl = pci_cfg_read16(addr, lnkctl_off);
+    if lnkctl & states != 0 {
+        pci_cfg_write16(addr, lnkctl_off, lnkctl & !states);
+    }
+    Some(lnkctl & (PCI_EXP_LNKCTL_ASPM_L0S | PCI_EXP_LNKCTL_ASPM_L1))
+}
+
 /// Find a VirtIO capability by cfg_type.
 pub fn find_virtio_cap(addr: PciAddr, cfg_type: u8) -> Option<u8> {
+    #[cfg(target_arch = "x86_64")]
     let offset = unsafe { asm_pci_find_virtio_cap(addr.bus, addr.device, addr.function, cfg_type) };
+    #[cfg(not(target_arch = "x86_64"))]
+    let offset = WalkCaps::new(addr)
+        .find(|&(off, id)| id == PCI_CAP_ID_VNDR && pci_cfg_read8(addr, off + 3) == cfg_type)
+        .map_or(0, |(off, _)| off as u32);
     if offset != 0 && offset < 256 {
         Some(offset as u8)
     } else {
@@ -192,20 +246,58 @@ pub fn find_virtio_cap(addr: PciAddr, cfg_type: u8) -> Option<u8> {
 
 /// Parse a VirtIO capability at the given config space offset.
 pub fn parse_virtio_cap(addr: PciAddr, cap_offset: u8) -> Option<VirtioCapInfo> {
-    let mut info = VirtioCapInfo::default();
-    let result = unsafe {
-        asm_virtio_pci_parse_cap(addr.bus, addr.device, addr.function, cap_offset, &mut info)
-    };
-    if result != 0 {
-        Some(info)
-    } else {
-        None
+    #[cfg(target_arch = "x86_64")]
+    {
+        let mut info = VirtioCapInfo::default();
+        let result = unsafe {
+            asm_virtio_pci_parse_cap(addr.bus, addr.device, addr.function, cap_offset, &mut info)
+        };
+        if result != 0 {
+            Some(info)
+        } else {
+            None
+        }
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        let cfg_type = pci_cfg_read8(addr, cap_offset + 3);
+        Some(VirtioCapInfo {
+            cfg_type,
+            bar: pci_cfg_read8(addr, cap_offset + 4),
+            offset: pci_cfg_read32(addr, cap_offset + 8),
+            length: pci_cfg_read32(addr, cap_offset + 12),
+            notify_off_multiplier: if cfg_type == VIRTIO_PCI_CAP_NOTIFY {
+                pci_cfg_read32(addr, cap_offset.wrapping_add(16))
+            } else {
+                0
+            },
+            cap_offset,
+            ..VirtioCapInfo::default()
+        })
     }
 }
 
 /// Read a BAR base address.
 pub fn read_bar(addr: PciAddr, bar_idx: u8) -> u64 {
-    unsafe { asm_virtio_pci_read_bar(addr.bus, addr.device, addr.function, bar_idx) }
+    #[cfg(target_arch = "x86_64")]
+    {
+        unsafe { asm_virtio_pci_read_bar(addr.bus, addr.device, addr.function, bar_idx) }
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        let low = pci_cfg_read32(addr, 0x10 + bar_idx * 4);
+        if low & 1 != 0 {
+            // I/O BAR
+            return (low & !0x3) as u64;
+        }
+        let base = (low & !0xF) as u64;
+        if low & 0x6 == 0x4 && bar_idx < 5 {
+            let high = pci_cfg_read32(addr, 0x10 + (bar_idx + 1) * 4);
+            ((high as u64) << 32) | base
+        } else {
+            base
+        }
+    }
 }
 
 /// Probe all VirtIO capabilities for a device.
@@ -218,9 +310,22 @@ pub fn probe_virtio_caps(addr: PciAddr) -> VirtioPciCaps {
     let mut cap_array = [VirtioCapInfo::default(); 5];
 
     // Probe via ASM
+    #[cfg(target_arch = "x86_64")]
     let found = unsafe {
         asm_virtio_pci_probe_caps(addr.bus, addr.device, addr.function, cap_array.as_mut_ptr())
     };
+    #[cfg(not(target_arch = "x86_64"))]
+    let found = {
+        let mut found = 0u32;
+        for cfg_type in VIRTIO_PCI_CAP_COMMON..=VIRTIO_PCI_CAP_PCI_CFG {
+            let info = find_virtio_cap(addr, cfg_type).and_then(|off| parse_virtio_cap(addr, off));
+            if let Some(info) = info {
+                cap_array[(cfg_type - 1) as usize] = info;
+                found |= 1 << (cfg_type - 1);
+            }
+        }
+        found
+    };
 
     caps.found_mask = found as u8;
 
@@ -1,11 +1,16 @@
 //! PCI configuration space access bindings.
 //!
 //! Thin wrappers around ASM functions for PCI config space read/write.
+//!
+//! x86_64 uses the legacy 0xCF8/0xCFC mechanism. Other architectures have
+//! no port I/O and go through ECAM at `arch::ecam_base()`; with no base
+//! set, reads return all-ones like an empty slot and writes are dropped.
 
 // ═══════════════════════════════════════════════════════════════════════════
 // ASM BINDINGS
 // ═══════════════════════════════════════════════════════════════════════════
 
+#[cfg(target_arch = "x86_64")]
 extern "win64" {
     /// Read 8-bit value from PCI config space.
     fn asm_pci_cfg_read8(bus: u8, device: u8, function: u8, offset: u8) -> u8;
@@ -52,37 +57,107 @@ impl PciAddr {
 /// Read 8-bit value from PCI configuration space.
 #[inline]
 pub fn pci_cfg_read8(addr: PciAddr, offset: u8) -> u8 {
-    unsafe { asm_pci_cfg_read8(addr.bus, addr.device, addr.function, offset) }
+    #[cfg(target_arch = "x86_64")]
+    unsafe {
+        asm_pci_cfg_read8(addr.bus, addr.device, addr.function, offset)
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        ecam_read(addr, offset, ecam::read8, u8::MAX)
+    }
 }
 
 /// Read 16-bit value from PCI configuration space.
 #[inline]
 pub fn pci_cfg_read16(addr: PciAddr, offset: u8) -> u16 {
-    unsafe { asm_pci_cfg_read16(addr.bus, addr.device, addr.function, offset) }
+    #[cfg(target_arch = "x86_64")]
+    unsafe {
+        asm_pci_cfg_read16(addr.bus, addr.device, addr.function, offset)
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        ecam_read(addr, offset, ecam::read16, u16::MAX)
+    }
 }
 
 /// Read 32-bit value from PCI configuration space.
 #[inline]
 pub fn pci_cfg_read32(addr: PciAddr, offset: u8) -> u32 {
-    unsafe { asm_pci_cfg_read32(addr.bus, addr.device, addr.function, offset) }
+    #[cfg(target_arch = "x86_64")]
+    unsafe {
+        asm_pci_cfg_read32(addr.bus, addr.device, addr.function, offset)
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        ecam_read(addr, offset, ecam::read32, u32::MAX)
+    }
 }
 
 /// Write 8-bit value to PCI configuration space.
 #[inline]
 pub fn pci_cfg_write8(addr: PciAddr, offset: u8, value: u8) {
-    unsafe { asm_pci_cfg_write8(addr.bus, addr.device, addr.function, offset, value) }
+    #[cfg(target_arch = "x86_64")]
+    unsafe {
+        asm_pci_cfg_write8(addr.bus, addr.device, addr.function, offset, value)
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        ecam_write(addr, offset, ecam::write8, value)
+    }
 }
 
 /// Write 16-bit value to PCI configuration space.
 #[inline]
 pub fn pci_cfg_write16(addr: PciAddr, offset: u8, value: u16) {
-    unsafe { asm_pci_cfg_write16(addr.bus, addr.device, addr.function, offset, value) }
+    #[cfg(target_arch = "x86_64")]
+    unsafe {
+        asm_pci_cfg_write16(addr.bus, addr.device, addr.function, offset, value)
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        ecam_write(addr, offset, ecam::write16, value)
+    }
 }
 
 /// Write 32-bit value to PCI configuration space.
 #[inline]
 pub fn pci_cfg_write32(addr: PciAddr, offset: u8, value: u32) {
-    unsafe { asm_pci_cfg_write32(addr.bus, addr.device, addr.function, offset, value) }
+    #[cfg(target_arch = "x86_64")]
+    unsafe {
+        asm_pci_cfg_write32(addr.bus, addr.device, addr.function, offset, value)
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        ecam_write(addr, offset, ecam::write32, value)
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// ECAM BACKEND (no port I/O)
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[cfg(not(target_arch = "x86_64"))]
+use crate::asm::pci::ecam;
+
+#[cfg(not(target_arch = "x86_64"))]
+type EcamRead<T> = unsafe fn(u64, u8, u8, u8, u16) -> T;
+
+#[cfg(not(target_arch = "x86_64"))]
+type EcamWrite<T> = unsafe fn(u64, u8, u8, u8, u16, T);
+
+#[cfg(not(target_arch = "x86_64"))]
+fn ecam_read<T>(addr: PciAddr, offset: u8, read: EcamRead<T>, absent: T) -> T {
+    match crate::arch::ecam_base() {
+        Some(base) => unsafe { read(base, addr.bus, addr.device, addr.function, offset as u16) },
+        None => absent,
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn ecam_write<T>(addr: PciAddr, offset: u8, write: EcamWrite<T>, value: T) {
+    if let Some(base) = crate::arch::ecam_base() {
+        unsafe { write(base, addr.bus, addr.device, addr.function, offset as u16, value) }
+    }
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
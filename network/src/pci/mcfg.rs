@@ -0,0 +1,100 @@
+//! MCFG table parsing (PCIe ECAM location).
+//!
+//! Without legacy port I/O (aarch64) config space is only reachable
+//! through ECAM, whose base the firmware publishes in the ACPI MCFG table.
+//!
+//! # Layout
+//! ```text
+//! +0   SDT header (36 bytes)
+//! +36  reserved (8 bytes)
+//! +44  allocation[n] (16 bytes each):
+//!        +0 base (u64)  +8 segment (u16)  +10 start bus (u8)  +11 end bus (u8)
+//! ```
+//!
+//! # Reference
+//! PCI Firmware Spec 3.2 §4.1.2
+
+use crate::acpi::{find_table, table_bytes, SDT_HEADER_LEN};
+
+/// Offset of the first allocation entry.
+const MCFG_ENTRIES: usize = SDT_HEADER_LEN + 8;
+
+/// Size of one allocation entry.
+const MCFG_ENTRY_LEN: usize = 16;
+
+/// One ECAM window.
+///
+/// `base` is where bus 0 would be, even if the window starts at a later
+/// bus, so config addresses are always `base + (bus << 20) + ...`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EcamWindow {
+    pub base: u64,
+    pub segment: u16,
+    pub start_bus: u8,
+    pub end_bus: u8,
+}
+
+/// Iterate the ECAM windows of a raw MCFG.
+pub fn windows(mcfg: &[u8]) -> impl Iterator<Item = EcamWindow> + '_ {
+    let entries = if mcfg.len() >= MCFG_ENTRIES && &mcfg[0..4] == b"MCFG" {
+        &mcfg[MCFG_ENTRIES..]
+    } else {
+        &[]
+    };
+    entries.chunks_exact(MCFG_ENTRY_LEN).map(|e| EcamWindow {
+        base: u64::from_le_bytes(e[0..8].try_into().unwrap()),
+        segment: u16::from_le_bytes([e[8], e[9]]),
+        start_bus: e[10],
+        end_bus: e[11],
+    })
+}
+
+/// ECAM base of PCI segment 0 from a raw MCFG.
+pub fn segment0_base(mcfg: &[u8]) -> Option<u64> {
+    windows(mcfg)
+        .find(|w| w.segment == 0 && w.base != 0)
+        .map(|w| w.base)
+}
+
+/// Locate the MCFG from the RSDP and return the segment 0 ECAM base.
+///
+/// # Safety
+/// `rsdp` must be 0 or point to a valid RSDP, with the ACPI tables
+/// identity mapped.
+pub unsafe fn ecam_base_from_rsdp(rsdp: u64) -> Option<u64> {
+    let mcfg = find_table(rsdp, b"MCFG")?;
+    segment0_base(table_bytes(mcfg))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mcfg(entries: &[(u64, u16, u8, u8)]) -> [u8; 76] {
+        let mut t = [0u8; 76];
+        t[0..4].copy_from_slice(b"MCFG");
+        for (i, &(base, segment, start, end)) in entries.iter().enumerate() {
+            let e = MCFG_ENTRIES + i * MCFG_ENTRY_LEN;
+            t[e..e + 8].copy_from_slice(&base.to_le_bytes());
+            t[e + 8..e + 10].copy_from_slice(&segment.to_le_bytes());
+            t[e + 10] = start;
+            t[e + 11] = end;
+        }
+        t
+    }
+
+    #[test]
+    fn test_mcfg_segment0_base() {
+        let table = mcfg(&[(0x30_0000_0000, 1, 0, 0xFF), (0x40_1000_0000, 0, 0, 0xFF)]);
+        assert_eq!(windows(&table).count(), 2);
+        assert_eq!(segment0_base(&table), Some(0x40_1000_0000));
+    }
+
+    #[test]
+    fn test_mcfg_rejects_bad_tables() {
+        let mut table = mcfg(&[(0xB000_0000, 0, 0, 0xFF)]);
+        assert_eq!(segment0_base(&table[..40]), None);
+        table[0] = b'X';
+        assert_eq!(segment0_base(&table), None);
+    }
+}
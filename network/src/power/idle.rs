@@ -0,0 +1,339 @@
+//! CPU idling for poll loops.
+//!
+//! The main loop polls hardware with interrupts masked, so the naive way to
+//! "wait" is to spin. Spinning keeps the core in C0 at full clock for the
+//! whole download, which on thin laptops means fans at 100% and, eventually,
+//! thermal throttling. This module picks the cheapest wait primitive that is
+//! still guaranteed to come back on its own:
+//!
+//! | Method   | Wakes on                      | Needs                     |
+//! |----------|-------------------------------|---------------------------|
+//! | `Tpause` | TSC deadline                  | CPUID.7:ECX.WAITPKG       |
+//! | `Mwaitx` | TSC-based timer               | CPUID.80000001:ECX.MWAITX |
+//! | `Mwait`  | interrupt / monitored store   | MONITOR + periodic IRQ    |
+//! | `Hlt`    | interrupt                     | periodic IRQ              |
+//! | `Pause`  | nothing (bounded spin)        | —                         |
+//!
+//! `Mwait` and `Hlt` are only chosen when the caller guarantees a periodic
+//! wake source (IF=1 with a timer armed). Post-EBS hwinit masks every IRQ,
+//! so by default only the self-timed methods or `Pause` are used.
+
+use crate::asm::core::tsc::read_tsc;
+
+/// Default upper bound for one idle slice (µs).
+///
+/// Keeps RX latency low: the NIC is polled, not interrupt driven, so every
+/// microsecond idled is a microsecond a frame may sit in the ring.
+pub const DEFAULT_MAX_SLICE_US: u64 = 500;
+
+/// Wait primitive used by `Idler`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdleMethod {
+    /// `PAUSE` spin until the deadline.
+    Pause,
+    /// `TPAUSE` (Intel WAITPKG), C0.2 until a TSC deadline.
+    Tpause,
+    /// `MONITORX`/`MWAITX` (AMD) with the timer extension.
+    Mwaitx,
+    /// `MONITOR`/`MWAIT`, woken by the periodic interrupt.
+    Mwait,
+    /// `HLT`, woken by the periodic interrupt.
+    Hlt,
+}
+
+impl IdleMethod {
+    /// Short name for logs.
+    pub fn name(self) -> &'static str {
+        match self {
+            IdleMethod::Pause => "PAUSE",
+            IdleMethod::Tpause => "TPAUSE",
+            IdleMethod::Mwaitx => "MWAITX",
+            IdleMethod::Mwait => "MWAIT",
+            IdleMethod::Hlt => "HLT",
+        }
+    }
+}
+
+/// CPU features relevant to idling.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IdleCaps {
+    /// `TPAUSE` available (CPUID.7.0:ECX bit 5).
+    pub waitpkg: bool,
+    /// `MWAITX` available (CPUID.80000001:ECX bit 29).
+    pub mwaitx: bool,
+    /// `MONITOR`/`MWAIT` available (CPUID.1:ECX bit 3).
+    pub monitor: bool,
+    /// Interrupts are enabled and a periodic one is armed.
+    pub wake_source: bool,
+}
+
+impl IdleCaps {
+    /// Probe CPUID for idle instructions.
+    ///
+    /// `periodic_irq` tells whether the caller armed a timer interrupt;
+    /// it only counts as a wake source if RFLAGS.IF is also set.
+    #[cfg(target_arch = "x86_64")]
+    pub fn detect(periodic_irq: bool) -> Self {
+        use crate::boot::handoff::cpuid;
+
+        let (max_leaf, _, _, _) = cpuid(0);
+        let (_, _, ecx1, _) = cpuid(1);
+        let waitpkg = max_leaf >= 7 && (cpuid(7).2 & (1 << 5)) != 0;
+
+        let (max_ext, _, _, _) = cpuid(0x8000_0000);
+        let mwaitx = max_ext >= 0x8000_0001 && (cpuid(0x8000_0001).2 & (1 << 29)) != 0;
+
+        Self {
+            waitpkg,
+            mwaitx,
+            monitor: (ecx1 & (1 << 3)) != 0,
+            wake_source: periodic_irq && interrupts_enabled(),
+        }
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    pub fn detect(_periodic_irq: bool) -> Self {
+        Self::default()
+    }
+}
+
+/// Pick the best idle method for `caps`.
+///
+/// Self-timed methods win because they cannot oversleep; interrupt-woken
+/// ones are only safe with a periodic wake source.
+pub fn choose_method(caps: IdleCaps) -> IdleMethod {
+    if caps.waitpkg {
+        IdleMethod::Tpause
+    } else if caps.mwaitx {
+        IdleMethod::Mwaitx
+    } else if caps.wake_source && caps.monitor {
+        IdleMethod::Mwait
+    } else if caps.wake_source {
+        IdleMethod::Hlt
+    } else {
+        IdleMethod::Pause
+    }
+}
+
+/// Length of the next idle slice (µs).
+///
+/// `budget_us` is the time until the next pending timeout (e.g. smoltcp's
+/// `poll_delay`); `None` means no timer is pending.
+pub fn slice_us(budget_us: Option<u64>, max_slice_us: u64) -> u64 {
+    match budget_us {
+        Some(budget) => budget.min(max_slice_us),
+        None => max_slice_us,
+    }
+}
+
+/// Idles the CPU between poll iterations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Idler {
+    method: IdleMethod,
+    tsc_freq: u64,
+    max_slice_us: u64,
+    enabled: bool,
+}
+
+impl Idler {
+    /// Create an idler using `method`.
+    pub const fn new(method: IdleMethod, tsc_freq: u64) -> Self {
+        Self {
+            method,
+            tsc_freq,
+            max_slice_us: DEFAULT_MAX_SLICE_US,
+            enabled: true,
+        }
+    }
+
+    /// Idler that never idles (legacy 100% spin behaviour).
+    pub const fn disabled() -> Self {
+        Self {
+            method: IdleMethod::Pause,
+            tsc_freq: 0,
+            max_slice_us: 0,
+            enabled: false,
+        }
+    }
+
+    /// Probe the CPU and pick the best self-timed method.
+    pub fn detect(tsc_freq: u64) -> Self {
+        Self::new(choose_method(IdleCaps::detect(false)), tsc_freq)
+    }
+
+    /// Override the maximum slice length.
+    pub const fn with_max_slice_us(mut self, us: u64) -> Self {
+        self.max_slice_us = us;
+        self
+    }
+
+    /// Method in use.
+    pub fn method(&self) -> IdleMethod {
+        self.method
+    }
+
+    /// Whether idling is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled && self.tsc_freq > 0 && self.max_slice_us > 0
+    }
+
+    /// Idle for at most `budget_us` (bounded by the max slice).
+    ///
+    /// Returns the number of TSC ticks spent idle.
+    pub fn idle(&self, budget_us: Option<u64>) -> u64 {
+        if !self.is_enabled() {
+            return 0;
+        }
+        let us = slice_us(budget_us, self.max_slice_us);
+        if us == 0 {
+            return 0;
+        }
+
+        let start = read_tsc();
+        let deadline = start.saturating_add(us.saturating_mul(self.tsc_freq / 1_000_000));
+
+        loop {
+            let now = read_tsc();
+            if now >= deadline {
+                return now.wrapping_sub(start);
+            }
+            unsafe { wait(self.method, deadline, deadline - now) };
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// PRIMITIVES
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// One cache line that nothing writes to; `MONITOR` target for timed waits.
+#[repr(align(64))]
+struct MonitorLine([u8; 64]);
+
+static MONITOR_LINE: MonitorLine = MonitorLine([0; 64]);
+
+/// Check RFLAGS.IF.
+#[cfg(target_arch = "x86_64")]
+fn interrupts_enabled() -> bool {
+    let rflags: u64;
+    unsafe {
+        core::arch::asm!("pushfq", "pop {}", out(reg) rflags, options(preserves_flags));
+    }
+    (rflags & (1 << 9)) != 0
+}
+
+/// Wait once using `method`. May return early; callers loop on the TSC.
+///
+/// # Safety
+/// `method` must be supported by the CPU (see `IdleCaps::detect`).
+#[cfg(target_arch = "x86_64")]
+unsafe fn wait(method: IdleMethod, deadline: u64, remaining: u64) {
+    use core::arch::asm;
+
+    match method {
+        IdleMethod::Pause => core::hint::spin_loop(),
+        IdleMethod::Tpause => {
+            // tpause ecx — ECX=0 requests C0.2. The OS-less default
+            // IA32_UMWAIT_CONTROL caps each wait, hence the caller's loop.
+            asm!(
+                ".byte 0x66, 0x0f, 0xae, 0xf1",
+                in("ecx") 0u32,
+                in("eax") deadline as u32,
+                in("edx") (deadline >> 32) as u32,
+                options(nomem, nostack),
+            );
+        }
+        IdleMethod::Mwaitx => {
+            let timeout = remaining.min(u32::MAX as u64) as u32;
+            // monitorx rax, ecx, edx
+            asm!(
+                ".byte 0x0f, 0x01, 0xfa",
+                in("rax") MONITOR_LINE.0.as_ptr(),
+                in("ecx") 0u32,
+                in("edx") 0u32,
+                options(nostack),
+            );
+            // mwaitx eax, ecx, ebx — ECX bit 1 enables the EBX timer.
+            asm!(
+                "xchg {t:r}, rbx",
+                ".byte 0x0f, 0x01, 0xfb",
+                "xchg {t:r}, rbx",
+                t = inout(reg) timeout as u64 => _,
+                in("eax") 0u32,
+                in("ecx") 2u32,
+                options(nostack),
+            );
+        }
+        IdleMethod::Mwait => {
+            // monitor rax, ecx, edx ; mwait eax, ecx
+            asm!(
+                ".byte 0x0f, 0x01, 0xc8",
+                in("rax") MONITOR_LINE.0.as_ptr(),
+                in("ecx") 0u32,
+                in("edx") 0u32,
+                options(nostack),
+            );
+            asm!(".byte 0x0f, 0x01, 0xc9", in("eax") 0u32, in("ecx") 0u32, options(nostack));
+        }
+        IdleMethod::Hlt => asm!("hlt", options(nomem, nostack)),
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+unsafe fn wait(_method: IdleMethod, _deadline: u64, _remaining: u64) {
+    core::hint::spin_loop();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_self_timed_methods_preferred() {
+        let caps = IdleCaps {
+            waitpkg: true,
+            mwaitx: true,
+            monitor: true,
+            wake_source: true,
+        };
+        assert_eq!(choose_method(caps), IdleMethod::Tpause);
+        let caps = IdleCaps { waitpkg: false, ..caps };
+        assert_eq!(choose_method(caps), IdleMethod::Mwaitx);
+    }
+
+    #[test]
+    fn test_interrupt_methods_need_wake_source() {
+        let caps = IdleCaps {
+            monitor: true,
+            ..IdleCaps::default()
+        };
+        assert_eq!(choose_method(caps), IdleMethod::Pause);
+        assert_eq!(
+            choose_method(IdleCaps { wake_source: true, ..caps }),
+            IdleMethod::Mwait
+        );
+        assert_eq!(
+            choose_method(IdleCaps {
+                wake_source: true,
+                ..IdleCaps::default()
+            }),
+            IdleMethod::Hlt
+        );
+    }
+
+    #[test]
+    fn test_slice_bounded_by_timeout() {
+        assert_eq!(slice_us(None, 500), 500);
+        assert_eq!(slice_us(Some(120), 500), 120);
+        assert_eq!(slice_us(Some(10_000), 500), 500);
+        assert_eq!(slice_us(Some(0), 500), 0);
+    }
+
+    #[test]
+    fn test_disabled_idler() {
+        assert!(!Idler::disabled().is_enabled());
+        assert!(!Idler::new(IdleMethod::Pause, 0).is_enabled());
+        assert!(!Idler::new(IdleMethod::Pause, 1_000_000).with_max_slice_us(0).is_enabled());
+        assert!(Idler::new(IdleMethod::Tpause, 1_000_000).is_enabled());
+    }
+}
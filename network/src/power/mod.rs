@@ -0,0 +1,19 @@
+//! Power management for the post-EBS session.
+//!
+//! A full ISO download keeps the CPU in the poll loop for minutes, and the
+//! only way out afterwards is a platform reset. This module covers both:
+//!
+//! - `idle` - C-state friendly waits between iterations when nothing is pending
+//! - `thermal` - Optional MSR temperature readout that throttles optional work
+//! - `reset` - Platform reset and power off via ACPI / UEFI mechanisms captured pre-EBS
+//! - `psci` - PSCI reset and power off (aarch64)
+
+pub mod idle;
+pub mod psci;
+pub mod reset;
+pub mod thermal;
+
+pub use idle::{choose_method, IdleCaps, IdleMethod, Idler, DEFAULT_MAX_SLICE_US};
+pub use psci::PsciConduit;
+pub use reset::{power_off, reset_system, AcpiResetRegister, SystemReset};
+pub use thermal::{throttled, ThermalEvent, ThermalMonitor, ThermalPolicy};
@@ -0,0 +1,129 @@
+//! PSCI (Power State Coordination Interface).
+//!
+//! On aarch64 the firmware-independent way to reset or power off is a
+//! PSCI call into EL3 (SMC) or the hypervisor (HVC). Which conduit to use
+//! is published in the FADT `ARM_BOOT_ARCH` flags; ACPI-booting Arm
+//! servers and VMs set `PSCI_COMPLIANT`.
+//!
+//! # Reference
+//! Arm DEN 0022 (PSCI) §5.10-5.11; ACPI 6.5 §5.2.9 (FADT `ARM_BOOT_ARCH`)
+
+use crate::acpi::{find_table, table_bytes};
+
+/// `SYSTEM_OFF` function ID.
+pub const PSCI_SYSTEM_OFF: u32 = 0x8400_0008;
+
+/// `SYSTEM_RESET` function ID.
+pub const PSCI_SYSTEM_RESET: u32 = 0x8400_0009;
+
+/// FADT offset of `ARM_BOOT_ARCH` (ACPI 5.1+).
+const FADT_ARM_BOOT_ARCH: usize = 129;
+
+/// `ARM_BOOT_ARCH.PSCI_COMPLIANT`.
+const ARM_PSCI_COMPLIANT: u16 = 1 << 0;
+
+/// `ARM_BOOT_ARCH.PSCI_USE_HVC`.
+const ARM_PSCI_USE_HVC: u16 = 1 << 1;
+
+/// How PSCI calls reach the firmware.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PsciConduit {
+    /// Secure monitor call (bare metal, EL3 firmware)
+    Smc,
+    /// Hypervisor call (VMs)
+    Hvc,
+}
+
+impl PsciConduit {
+    /// Read the conduit from a raw FADT.
+    ///
+    /// Returns `None` if the table predates `ARM_BOOT_ARCH` or the
+    /// platform is not PSCI compliant.
+    pub fn from_fadt(fadt: &[u8]) -> Option<Self> {
+        if fadt.len() < FADT_ARM_BOOT_ARCH + 2 || &fadt[0..4] != b"FACP" {
+            return None;
+        }
+        let flags = u16::from_le_bytes([fadt[FADT_ARM_BOOT_ARCH], fadt[FADT_ARM_BOOT_ARCH + 1]]);
+        if flags & ARM_PSCI_COMPLIANT == 0 {
+            return None;
+        }
+        Some(if flags & ARM_PSCI_USE_HVC != 0 {
+            Self::Hvc
+        } else {
+            Self::Smc
+        })
+    }
+
+    /// Locate the FADT from the RSDP and read its PSCI flags.
+    ///
+    /// # Safety
+    /// `rsdp` must be 0 or point to a valid RSDP, with the ACPI tables
+    /// identity mapped.
+    pub unsafe fn from_rsdp(rsdp: u64) -> Option<Self> {
+        let fadt = find_table(rsdp, b"FACP")?;
+        Self::from_fadt(table_bytes(fadt))
+    }
+
+    /// Issue a PSCI call. Returns the PSCI status (x0).
+    ///
+    /// # Safety
+    /// The function ID decides what happens; reset and off do not return.
+    pub unsafe fn call(self, function: u32) -> i64 {
+        #[cfg(target_arch = "aarch64")]
+        {
+            use crate::arch::aarch64::{hvc, smc};
+            let ret = match self {
+                Self::Smc => smc(function, 0, 0, 0),
+                Self::Hvc => hvc(function, 0, 0, 0),
+            };
+            ret as i64
+        }
+        #[cfg(not(target_arch = "aarch64"))]
+        {
+            let _ = function;
+            // PSCI NOT_SUPPORTED
+            -1
+        }
+    }
+
+    /// `SYSTEM_RESET`. Only returns if the firmware refused.
+    ///
+    /// # Safety
+    /// Resets the machine.
+    pub unsafe fn system_reset(self) {
+        self.call(PSCI_SYSTEM_RESET);
+    }
+
+    /// `SYSTEM_OFF`. Only returns if the firmware refused.
+    ///
+    /// # Safety
+    /// Powers the machine off.
+    pub unsafe fn system_off(self) {
+        self.call(PSCI_SYSTEM_OFF);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fadt(arm_boot_arch: u16) -> [u8; 140] {
+        let mut t = [0u8; 140];
+        t[0..4].copy_from_slice(b"FACP");
+        t[FADT_ARM_BOOT_ARCH..FADT_ARM_BOOT_ARCH + 2].copy_from_slice(&arm_boot_arch.to_le_bytes());
+        t
+    }
+
+    #[test]
+    fn test_psci_conduit_from_fadt() {
+        assert_eq!(PsciConduit::from_fadt(&fadt(ARM_PSCI_COMPLIANT)), Some(PsciConduit::Smc));
+        assert_eq!(
+            PsciConduit::from_fadt(&fadt(ARM_PSCI_COMPLIANT | ARM_PSCI_USE_HVC)),
+            Some(PsciConduit::Hvc)
+        );
+        assert_eq!(PsciConduit::from_fadt(&fadt(ARM_PSCI_USE_HVC)), None);
+        assert_eq!(PsciConduit::from_fadt(&fadt(0)), None);
+        // Pre-5.1 FADT has no ARM_BOOT_ARCH.
+        assert_eq!(PsciConduit::from_fadt(&fadt(ARM_PSCI_COMPLIANT)[..129]), None);
+    }
+}
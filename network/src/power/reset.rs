@@ -0,0 +1,309 @@
+//! Platform reset.
+//!
+//! After ExitBootServices there is no firmware to return to, so ending a
+//! session means resetting the machine. The legacy tricks (keyboard
+//! controller pulse, port 0xCF9) work on most desktops but are missing or
+//! ignored on many laptops and servers. The proper mechanisms have to be
+//! captured while UEFI is still up:
+//!
+//! - the ACPI reset register from the FADT (`RESET_REG` / `RESET_VALUE`)
+//! - the PSCI conduit from the FADT `ARM_BOOT_ARCH` flags (aarch64)
+//! - the `ResetSystem` runtime service pointer
+//!
+//! The bootloader records them with `install()` before ExitBootServices;
+//! `reset_system()` then tries them in order of how little firmware code
+//! they depend on, falling back to the legacy ports and finally halting.
+
+use core::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+use super::psci::PsciConduit;
+use crate::acpi::{find_table, table_bytes};
+use crate::asm::core::mmio::write8;
+use crate::asm::core::pio::outb;
+use crate::mainloop::serial;
+use crate::pci::config::{pci_cfg_write8, PciAddr};
+
+/// UEFI `ResetSystem` runtime service.
+pub type EfiResetSystem =
+    extern "efiapi" fn(reset_type: u32, status: usize, data_size: usize, data: *const ()) -> !;
+
+/// `EfiResetCold`.
+pub const EFI_RESET_COLD: u32 = 0;
+
+/// `EfiResetShutdown`.
+pub const EFI_RESET_SHUTDOWN: u32 = 2;
+
+/// FADT `Flags.RESET_REG_SUP`.
+const FADT_RESET_REG_SUP: u32 = 1 << 10;
+
+/// Minimum FADT length containing `RESET_VALUE` (ACPI 2.0+).
+const FADT_MIN_RESET_LEN: usize = 129;
+
+// ═══════════════════════════════════════════════════════════════════════════
+// ACPI RESET REGISTER
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Address space of a Generic Address Structure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum AcpiAddressSpace {
+    SystemMemory = 0,
+    SystemIo = 1,
+    PciConfig = 2,
+}
+
+impl AcpiAddressSpace {
+    fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Self::SystemMemory),
+            1 => Some(Self::SystemIo),
+            2 => Some(Self::PciConfig),
+            _ => None,
+        }
+    }
+}
+
+/// FADT reset register and value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AcpiResetRegister {
+    pub space: AcpiAddressSpace,
+    pub address: u64,
+    pub value: u8,
+}
+
+impl AcpiResetRegister {
+    /// Parse the reset register out of a raw FADT.
+    ///
+    /// Returns `None` if the table is too short, the platform does not set
+    /// `RESET_REG_SUP`, or the register is in an unsupported space.
+    pub fn from_fadt(fadt: &[u8]) -> Option<Self> {
+        if fadt.len() < FADT_MIN_RESET_LEN || &fadt[0..4] != b"FACP" {
+            return None;
+        }
+        let flags = u32::from_le_bytes(fadt[112..116].try_into().ok()?);
+        if flags & FADT_RESET_REG_SUP == 0 {
+            return None;
+        }
+
+        // Generic Address Structure at offset 116.
+        let space = AcpiAddressSpace::from_id(fadt[116])?;
+        let address = u64::from_le_bytes(fadt[120..128].try_into().ok()?);
+        if address == 0 {
+            return None;
+        }
+
+        Some(Self {
+            space,
+            address,
+            value: fadt[128],
+        })
+    }
+
+    /// Locate the FADT from the RSDP and parse its reset register.
+    ///
+    /// # Safety
+    /// `rsdp` must point to a valid RSDP and the ACPI tables must be
+    /// identity mapped (true both before and after ExitBootServices).
+    pub unsafe fn from_rsdp(rsdp: u64) -> Option<Self> {
+        let fadt = find_table(rsdp, b"FACP")?;
+        Self::from_fadt(table_bytes(fadt))
+    }
+
+    /// Write the reset value.
+    ///
+    /// # Safety
+    /// Resets the machine.
+    pub unsafe fn write(&self) {
+        match self.space {
+            AcpiAddressSpace::SystemIo => outb(self.address as u16, self.value),
+            AcpiAddressSpace::SystemMemory => write8(self.address, self.value),
+            AcpiAddressSpace::PciConfig => {
+                // ACPI encodes device in bits 47:32, function 31:16,
+                // register 15:0; the reset register is always on bus 0.
+                let addr = PciAddr {
+                    bus: 0,
+                    device: (self.address >> 32) as u8,
+                    function: (self.address >> 16) as u8,
+                };
+                pci_cfg_write8(addr, self.address as u8, self.value);
+            }
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// INSTALLED RESET MECHANISMS
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Reset mechanisms captured before ExitBootServices.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemReset {
+    pub acpi: Option<AcpiResetRegister>,
+    pub psci: Option<PsciConduit>,
+    pub efi_reset_system: Option<EfiResetSystem>,
+}
+
+/// 0 = none, otherwise `AcpiAddressSpace as u8 + 1`.
+static ACPI_SPACE: AtomicU8 = AtomicU8::new(0);
+static ACPI_ADDRESS: AtomicU64 = AtomicU64::new(0);
+static ACPI_VALUE: AtomicU8 = AtomicU8::new(0);
+/// 0 = none, 1 = SMC, 2 = HVC.
+static PSCI: AtomicU8 = AtomicU8::new(0);
+static EFI_RESET: AtomicU64 = AtomicU64::new(0);
+
+/// Record the reset mechanisms for use after ExitBootServices.
+pub fn install(reset: SystemReset) {
+    match reset.acpi {
+        Some(reg) => {
+            ACPI_ADDRESS.store(reg.address, Ordering::Relaxed);
+            ACPI_VALUE.store(reg.value, Ordering::Relaxed);
+            ACPI_SPACE.store(reg.space as u8 + 1, Ordering::Release);
+        }
+        None => ACPI_SPACE.store(0, Ordering::Release),
+    }
+    let psci = match reset.psci {
+        None => 0,
+        Some(PsciConduit::Smc) => 1,
+        Some(PsciConduit::Hvc) => 2,
+    };
+    PSCI.store(psci, Ordering::Release);
+    let efi = reset.efi_reset_system.map_or(0, |f| f as usize as u64);
+    EFI_RESET.store(efi, Ordering::Release);
+}
+
+/// The currently installed reset mechanisms.
+pub fn installed() -> SystemReset {
+    let acpi = match ACPI_SPACE.load(Ordering::Acquire) {
+        0 => None,
+        n => AcpiAddressSpace::from_id(n - 1).map(|space| AcpiResetRegister {
+            space,
+            address: ACPI_ADDRESS.load(Ordering::Relaxed),
+            value: ACPI_VALUE.load(Ordering::Relaxed),
+        }),
+    };
+    let psci = match PSCI.load(Ordering::Acquire) {
+        1 => Some(PsciConduit::Smc),
+        2 => Some(PsciConduit::Hvc),
+        _ => None,
+    };
+    let efi_reset_system = match EFI_RESET.load(Ordering::Acquire) {
+        0 => None,
+        // SAFETY: only ever stored from a valid `EfiResetSystem`.
+        ptr => Some(unsafe { core::mem::transmute::<usize, EfiResetSystem>(ptr as usize) }),
+    };
+    SystemReset {
+        acpi,
+        psci,
+        efi_reset_system,
+    }
+}
+
+/// Reset the machine. Never returns.
+///
+/// Order: ACPI reset register, PSCI `SYSTEM_RESET`, UEFI `ResetSystem`,
+/// then (x86 only) port 0xCF9 and the keyboard controller, then halt.
+pub fn reset_system() -> ! {
+    let reset = installed();
+
+    unsafe {
+        if let Some(reg) = reset.acpi {
+            serial::println("[RESET] ACPI reset register");
+            reg.write();
+            settle();
+        }
+
+        if let Some(psci) = reset.psci {
+            serial::println("[RESET] PSCI SYSTEM_RESET");
+            psci.system_reset();
+            settle();
+        }
+
+        if let Some(efi_reset) = reset.efi_reset_system {
+            serial::println("[RESET] UEFI ResetSystem");
+            efi_reset(EFI_RESET_COLD, 0, 0, core::ptr::null());
+        }
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            serial::println("[RESET] Port 0xCF9");
+            outb(0xCF9, 0x06);
+            settle();
+
+            serial::println("[RESET] Keyboard controller (0x64 -> 0xFE)");
+            outb(0x64, 0xFE);
+            settle();
+        }
+    }
+
+    serial::println("[RESET] All reset methods failed - halting");
+    serial::println("[RESET] Please manually power cycle the system");
+    crate::arch::halt()
+}
+
+/// Power the machine off. Never returns.
+///
+/// Order: PSCI `SYSTEM_OFF`, then UEFI `ResetSystem(EfiResetShutdown)`,
+/// then halt. ACPI S5 needs the `\_S5` sleep type from the DSDT, which
+/// takes an AML interpreter, so x86 relies on the runtime service.
+pub fn power_off() -> ! {
+    let reset = installed();
+
+    unsafe {
+        if let Some(psci) = reset.psci {
+            serial::println("[POWER] PSCI SYSTEM_OFF");
+            psci.system_off();
+            settle();
+        }
+
+        if let Some(efi_reset) = reset.efi_reset_system {
+            serial::println("[POWER] UEFI ResetSystem (shutdown)");
+            efi_reset(EFI_RESET_SHUTDOWN, 0, 0, core::ptr::null());
+        }
+    }
+
+    serial::println("[POWER] No power-off method available - halting");
+    serial::println("[POWER] Safe to switch off the system");
+    crate::arch::halt()
+}
+
+/// Give a reset write time to take effect.
+fn settle() {
+    for _ in 0..50_000_000 {
+        core::hint::spin_loop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fadt(flags: u32, space: u8, address: u64, value: u8) -> [u8; 140] {
+        let mut t = [0u8; 140];
+        t[0..4].copy_from_slice(b"FACP");
+        t[4..8].copy_from_slice(&140u32.to_le_bytes());
+        t[112..116].copy_from_slice(&flags.to_le_bytes());
+        t[116] = space;
+        t[117] = 8;
+        t[120..128].copy_from_slice(&address.to_le_bytes());
+        t[128] = value;
+        t
+    }
+
+    #[test]
+    fn test_fadt_reset_register_io() {
+        let table = fadt(FADT_RESET_REG_SUP, 1, 0xCF9, 0x06);
+        let reg = AcpiResetRegister::from_fadt(&table).unwrap();
+        assert_eq!(reg.space, AcpiAddressSpace::SystemIo);
+        assert_eq!(reg.address, 0xCF9);
+        assert_eq!(reg.value, 0x06);
+    }
+
+    #[test]
+    fn test_fadt_without_reset_support() {
+        assert!(AcpiResetRegister::from_fadt(&fadt(0, 1, 0xCF9, 6)).is_none());
+        assert!(AcpiResetRegister::from_fadt(&fadt(FADT_RESET_REG_SUP, 1, 0, 6)).is_none());
+        assert!(AcpiResetRegister::from_fadt(&fadt(FADT_RESET_REG_SUP, 7, 0xCF9, 6)).is_none());
+        // ACPI 1.0 FADT is too short to carry RESET_REG.
+        assert!(AcpiResetRegister::from_fadt(&fadt(FADT_RESET_REG_SUP, 1, 0xCF9, 6)[..116]).is_none());
+    }
+}
@@ -0,0 +1,238 @@
+//! CPU temperature readout and throttling.
+//!
+//! Intel CPUs with a digital thermal sensor (CPUID.6:EAX bit 0) report the
+//! core temperature as "degrees below TjMax" in `IA32_THERM_STATUS`; TjMax
+//! itself lives in `MSR_TEMPERATURE_TARGET`. The monitor samples that at a
+//! low rate and flips a global throttle flag with hysteresis. Optional work
+//! (e.g. read-back verification of written sectors) checks `throttled()`
+//! and defers itself while the package is hot.
+//!
+//! Everything is opt-in: on CPUs without the sensor `ThermalMonitor::probe`
+//! returns `None` and `throttled()` stays false.
+
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+/// `IA32_THERM_STATUS`.
+pub const MSR_THERM_STATUS: u32 = 0x19C;
+
+/// `MSR_TEMPERATURE_TARGET` (TjMax in bits 23:16).
+pub const MSR_TEMPERATURE_TARGET: u32 = 0x1A2;
+
+/// TjMax assumed when the target MSR reports zero.
+pub const DEFAULT_TJMAX_C: u8 = 100;
+
+/// Global throttle flag (set by the monitor, read by optional work).
+static THROTTLED: AtomicBool = AtomicBool::new(false);
+
+/// Last sampled temperature in °C (0 = never sampled).
+static LAST_TEMP_C: AtomicU8 = AtomicU8::new(0);
+
+/// Whether optional work should back off right now.
+#[inline]
+pub fn throttled() -> bool {
+    THROTTLED.load(Ordering::Relaxed)
+}
+
+/// Last sampled temperature, if the monitor has run.
+pub fn last_temperature() -> Option<u8> {
+    match LAST_TEMP_C.load(Ordering::Relaxed) {
+        0 => None,
+        t => Some(t),
+    }
+}
+
+/// Decode `IA32_THERM_STATUS` into °C.
+///
+/// Returns `None` if the reading-valid bit (31) is clear.
+pub fn decode_therm_status(raw: u64, tjmax: u8) -> Option<u8> {
+    if raw & (1 << 31) == 0 {
+        return None;
+    }
+    let below = ((raw >> 16) & 0x7F) as u8;
+    Some(tjmax.saturating_sub(below))
+}
+
+/// Throttle thresholds (°C).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThermalPolicy {
+    /// Start throttling at or above this temperature.
+    pub throttle_at_c: u8,
+    /// Stop throttling at or below this temperature.
+    pub resume_at_c: u8,
+    /// Sampling interval (ms).
+    pub interval_ms: u64,
+}
+
+impl Default for ThermalPolicy {
+    fn default() -> Self {
+        Self {
+            throttle_at_c: 90,
+            resume_at_c: 80,
+            interval_ms: 1_000,
+        }
+    }
+}
+
+impl ThermalPolicy {
+    /// Next throttle state given the current one and a new sample.
+    pub fn next_state(&self, throttled: bool, temp_c: u8) -> bool {
+        if throttled {
+            temp_c > self.resume_at_c
+        } else {
+            temp_c >= self.throttle_at_c
+        }
+    }
+}
+
+/// Throttle state change reported by `ThermalMonitor::poll`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThermalEvent {
+    /// Temperature crossed `throttle_at_c`.
+    Throttle { temp_c: u8 },
+    /// Temperature dropped to `resume_at_c`.
+    Resume { temp_c: u8 },
+}
+
+/// Periodic temperature sampler.
+#[derive(Debug, Clone, Copy)]
+pub struct ThermalMonitor {
+    tjmax: u8,
+    policy: ThermalPolicy,
+    interval_ticks: u64,
+    last_sample: Option<u64>,
+}
+
+impl ThermalMonitor {
+    /// Probe for the digital thermal sensor.
+    ///
+    /// `tick_freq` is the frequency of the timestamps passed to `poll`.
+    /// Returns `None` on non-Intel CPUs or without CPUID.6:EAX.DTS.
+    #[cfg(target_arch = "x86_64")]
+    pub fn probe(policy: ThermalPolicy, tick_freq: u64) -> Option<Self> {
+        use crate::boot::handoff::cpuid;
+
+        let (max_leaf, ebx, ecx, edx) = cpuid(0);
+        // "GenuineIntel" — the MSR layout below is Intel-specific.
+        let intel = ebx == 0x756E_6547 && edx == 0x4965_6E69 && ecx == 0x6C65_746E;
+        if !intel || max_leaf < 6 || cpuid(6).0 & 1 == 0 {
+            return None;
+        }
+
+        let target = unsafe { rdmsr(MSR_TEMPERATURE_TARGET) };
+        let tjmax = match ((target >> 16) & 0xFF) as u8 {
+            0 => DEFAULT_TJMAX_C,
+            t => t,
+        };
+
+        Some(Self::with_tjmax(tjmax, policy, tick_freq))
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    pub fn probe(_policy: ThermalPolicy, _tick_freq: u64) -> Option<Self> {
+        None
+    }
+
+    /// Build a monitor for a known TjMax (no hardware access).
+    pub fn with_tjmax(tjmax: u8, policy: ThermalPolicy, tick_freq: u64) -> Self {
+        Self {
+            tjmax,
+            policy,
+            interval_ticks: policy.interval_ms.saturating_mul(tick_freq / 1_000),
+            last_sample: None,
+        }
+    }
+
+    /// TjMax in °C.
+    pub fn tjmax(&self) -> u8 {
+        self.tjmax
+    }
+
+    /// Sample the sensor if the interval has elapsed.
+    ///
+    /// Returns an event when the throttle state flips.
+    pub fn poll(&mut self, now: u64) -> Option<ThermalEvent> {
+        if let Some(last) = self.last_sample {
+            if now.wrapping_sub(last) < self.interval_ticks {
+                return None;
+            }
+        }
+        self.last_sample = Some(now);
+
+        let temp_c = decode_therm_status(read_therm_status(), self.tjmax)?;
+        self.record(temp_c)
+    }
+
+    /// Feed a sample into the governor and update the global flag.
+    pub fn record(&mut self, temp_c: u8) -> Option<ThermalEvent> {
+        LAST_TEMP_C.store(temp_c.max(1), Ordering::Relaxed);
+
+        let was = throttled();
+        let now = self.policy.next_state(was, temp_c);
+        if now == was {
+            return None;
+        }
+        THROTTLED.store(now, Ordering::Relaxed);
+        Some(if now {
+            ThermalEvent::Throttle { temp_c }
+        } else {
+            ThermalEvent::Resume { temp_c }
+        })
+    }
+}
+
+/// Clear the throttle flag (end of a long operation).
+pub fn reset() {
+    THROTTLED.store(false, Ordering::Relaxed);
+    LAST_TEMP_C.store(0, Ordering::Relaxed);
+}
+
+#[cfg(target_arch = "x86_64")]
+fn read_therm_status() -> u64 {
+    unsafe { rdmsr(MSR_THERM_STATUS) }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn read_therm_status() -> u64 {
+    0
+}
+
+/// Read a model-specific register.
+///
+/// # Safety
+/// `#GP` if the MSR does not exist; callers gate on CPUID.
+#[cfg(target_arch = "x86_64")]
+unsafe fn rdmsr(msr: u32) -> u64 {
+    let lo: u32;
+    let hi: u32;
+    core::arch::asm!(
+        "rdmsr",
+        in("ecx") msr,
+        out("eax") lo,
+        out("edx") hi,
+        options(nomem, nostack, preserves_flags),
+    );
+    ((hi as u64) << 32) | lo as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_therm_status() {
+        // Valid, 35 degrees below TjMax.
+        let raw = (1u64 << 31) | (35 << 16);
+        assert_eq!(decode_therm_status(raw, 100), Some(65));
+        assert_eq!(decode_therm_status(35 << 16, 100), None);
+        assert_eq!(decode_therm_status((1 << 31) | (0x7F << 16), 100), Some(0));
+    }
+
+    #[test]
+    fn test_policy_hysteresis() {
+        let policy = ThermalPolicy::default();
+        assert!(!policy.next_state(false, 89));
+        assert!(policy.next_state(false, 90));
+        assert!(policy.next_state(true, 85));
+        assert!(!policy.next_state(true, 80));
+    }
+}
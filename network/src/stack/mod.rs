@@ -37,6 +37,7 @@
 mod interface;
 
 use crate::device::NetworkDevice;
+use crate::sync::SpinLock;
 use core::marker::PhantomData;
 use smoltcp::phy::{Device, DeviceCapabilities, Medium, RxToken, TxToken};
 use smoltcp::time::Instant;
@@ -293,8 +294,7 @@ impl DebugRing {
     }
 }
 
-static mut DEBUG_RING: DebugRing = DebugRing::new();
-static DEBUG_RING_LOCK: core::sync::atomic::AtomicBool = core::sync::atomic::AtomicBool::new(false);
+static DEBUG_RING: SpinLock<DebugRing> = SpinLock::new("debug ring", DebugRing::new());
 
 /// Push a debug message to the ring buffer (AND write to serial)
 pub fn debug_log(stage: u32, msg: &str) {
@@ -302,77 +302,54 @@ pub fn debug_log(stage: u32, msg: &str) {
     #[cfg(target_arch = "x86_64")]
     crate::serial_stage(stage, msg);
 
-    // Bounded try-lock: in single-threaded context, lock should never be held
+    // Try-lock: in single-threaded context, lock should never be held
     // If it is, we have a bug - don't spin forever, just skip the log
-    if DEBUG_RING_LOCK.swap(true, core::sync::atomic::Ordering::Acquire) {
-        // Lock held - impossible in single-threaded, skip to avoid hang
+    let Some(mut ring) = DEBUG_RING.try_lock() else {
         return;
-    }
+    };
 
-    unsafe {
-        let entry = &mut DEBUG_RING.entries[DEBUG_RING.write_idx];
-        let bytes = msg.as_bytes();
-        let copy_len = bytes.len().min(DEBUG_MSG_LEN);
-        entry.msg[..copy_len].copy_from_slice(&bytes[..copy_len]);
-        entry.len = copy_len;
-        entry.stage = stage;
-
-        DEBUG_RING.write_idx = (DEBUG_RING.write_idx + 1) % DEBUG_RING_SIZE;
-        if DEBUG_RING.count < DEBUG_RING_SIZE {
-            DEBUG_RING.count += 1;
-        } else {
-            // Overwrite oldest - advance read pointer
-            DEBUG_RING.read_idx = (DEBUG_RING.read_idx + 1) % DEBUG_RING_SIZE;
-        }
+    let write_idx = ring.write_idx;
+    let entry = &mut ring.entries[write_idx];
+    let bytes = msg.as_bytes();
+    let copy_len = bytes.len().min(DEBUG_MSG_LEN);
+    entry.msg[..copy_len].copy_from_slice(&bytes[..copy_len]);
+    entry.len = copy_len;
+    entry.stage = stage;
+
+    ring.write_idx = (write_idx + 1) % DEBUG_RING_SIZE;
+    if ring.count < DEBUG_RING_SIZE {
+        ring.count += 1;
+    } else {
+        // Overwrite oldest - advance read pointer
+        ring.read_idx = (ring.read_idx + 1) % DEBUG_RING_SIZE;
     }
-
-    DEBUG_RING_LOCK.store(false, core::sync::atomic::Ordering::Release);
 }
 
 /// Pop the next debug message from the ring buffer (FIFO order)
 /// Returns None if buffer is empty
 pub fn debug_log_pop() -> Option<DebugLogEntry> {
-    // Bounded try-lock: in single-threaded context, lock should never be held
-    if DEBUG_RING_LOCK.swap(true, core::sync::atomic::Ordering::Acquire) {
-        // Lock held - impossible in single-threaded, return None
+    let mut ring = DEBUG_RING.try_lock()?;
+    if ring.count == 0 {
         return None;
     }
-
-    let result = unsafe {
-        if DEBUG_RING.count == 0 {
-            None
-        } else {
-            let entry = DEBUG_RING.entries[DEBUG_RING.read_idx];
-            DEBUG_RING.read_idx = (DEBUG_RING.read_idx + 1) % DEBUG_RING_SIZE;
-            DEBUG_RING.count -= 1;
-            Some(entry)
-        }
-    };
-
-    DEBUG_RING_LOCK.store(false, core::sync::atomic::Ordering::Release);
-    result
+    let entry = ring.entries[ring.read_idx];
+    ring.read_idx = (ring.read_idx + 1) % DEBUG_RING_SIZE;
+    ring.count -= 1;
+    Some(entry)
 }
 
 /// Check if there are pending debug messages
 pub fn debug_log_available() -> bool {
-    unsafe { DEBUG_RING.count > 0 }
+    DEBUG_RING.try_lock().is_some_and(|ring| ring.count > 0)
 }
 
 /// Clear all debug messages
 pub fn debug_log_clear() {
-    // Bounded try-lock: in single-threaded context, lock should never be held
-    if DEBUG_RING_LOCK.swap(true, core::sync::atomic::Ordering::Acquire) {
-        // Lock held - impossible in single-threaded, skip
-        return;
+    if let Some(mut ring) = DEBUG_RING.try_lock() {
+        ring.write_idx = 0;
+        ring.read_idx = 0;
+        ring.count = 0;
     }
-
-    unsafe {
-        DEBUG_RING.write_idx = 0;
-        DEBUG_RING.read_idx = 0;
-        DEBUG_RING.count = 0;
-    }
-
-    DEBUG_RING_LOCK.store(false, core::sync::atomic::Ordering::Release);
 }
 
 /// Set debug init stage and log it
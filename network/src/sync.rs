@@ -0,0 +1,211 @@
+//! Synchronisation primitives for global state.
+//!
+//! Replaces `static mut` buffers and counters. Everything here is safe to
+//! share between cores, but the poll loop is still single-threaded, so a
+//! lock that is already held when we try to take it can only mean the
+//! holder is further up our own stack. Debug builds turn that re-entrancy
+//! into a panic naming the lock instead of a silent spin forever.
+//!
+//! - `SpinLock<T>`: mutual exclusion for mutable globals (buffers, rings)
+//! - `OnceCell<T>`: write-once globals set during init
+
+use core::cell::UnsafeCell;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicU8, Ordering};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// SPIN LOCK
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Named spin lock.
+///
+/// The name only appears in the re-entrancy panic.
+pub struct SpinLock<T> {
+    inner: spin::Mutex<T>,
+    name: &'static str,
+}
+
+impl<T> SpinLock<T> {
+    pub const fn new(name: &'static str, value: T) -> Self {
+        Self {
+            inner: spin::Mutex::new(value),
+            name,
+        }
+    }
+
+    /// Acquire the lock.
+    ///
+    /// # Panics
+    /// In debug builds, if the lock is already held (re-entrancy).
+    pub fn lock(&self) -> SpinLockGuard<'_, T> {
+        if let Some(guard) = self.inner.try_lock() {
+            return SpinLockGuard { guard };
+        }
+        if cfg!(debug_assertions) {
+            panic!("re-entrant lock of {}", self.name);
+        }
+        SpinLockGuard {
+            guard: self.inner.lock(),
+        }
+    }
+
+    /// Acquire the lock if it is free.
+    ///
+    /// For paths that must never block (logging, diagnostics) and would
+    /// rather drop their work than deadlock.
+    pub fn try_lock(&self) -> Option<SpinLockGuard<'_, T>> {
+        self.inner.try_lock().map(|guard| SpinLockGuard { guard })
+    }
+
+    /// Whether the lock is currently held.
+    pub fn is_locked(&self) -> bool {
+        self.inner.is_locked()
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+/// Exclusive access to the data of a `SpinLock`; unlocks on drop.
+pub struct SpinLockGuard<'a, T> {
+    guard: spin::MutexGuard<'a, T>,
+}
+
+impl<T> Deref for SpinLockGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T> DerefMut for SpinLockGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// ONCE CELL
+// ═══════════════════════════════════════════════════════════════════════════
+
+const UNINIT: u8 = 0;
+const INITIALIZING: u8 = 1;
+const READY: u8 = 2;
+
+/// Write-once cell.
+pub struct OnceCell<T> {
+    state: AtomicU8,
+    value: UnsafeCell<Option<T>>,
+}
+
+// SAFETY: the value is written once, before `state` becomes READY with
+// Release ordering, and only read after observing READY with Acquire.
+unsafe impl<T: Send + Sync> Sync for OnceCell<T> {}
+unsafe impl<T: Send> Send for OnceCell<T> {}
+
+impl<T> OnceCell<T> {
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicU8::new(UNINIT),
+            value: UnsafeCell::new(None),
+        }
+    }
+
+    /// The value, if set.
+    pub fn get(&self) -> Option<&T> {
+        if self.state.load(Ordering::Acquire) == READY {
+            // SAFETY: READY means the value is written and never changes.
+            unsafe { (*self.value.get()).as_ref() }
+        } else {
+            None
+        }
+    }
+
+    /// Set the value. Returns it back if the cell was already set.
+    pub fn set(&self, value: T) -> Result<(), T> {
+        if self
+            .state
+            .compare_exchange(UNINIT, INITIALIZING, Ordering::Acquire, Ordering::Acquire)
+            .is_err()
+        {
+            return Err(value);
+        }
+        // SAFETY: INITIALIZING gives us exclusive access.
+        unsafe { *self.value.get() = Some(value) };
+        self.state.store(READY, Ordering::Release);
+        Ok(())
+    }
+
+    /// The value, initializing it with `f` on first use.
+    ///
+    /// # Panics
+    /// If `f` (directly or indirectly) calls back into this cell.
+    pub fn get_or_init(&self, f: impl FnOnce() -> T) -> &T {
+        match self
+            .state
+            .compare_exchange(UNINIT, INITIALIZING, Ordering::Acquire, Ordering::Acquire)
+        {
+            Ok(_) => {
+                let value = f();
+                // SAFETY: INITIALIZING gives us exclusive access.
+                unsafe { *self.value.get() = Some(value) };
+                self.state.store(READY, Ordering::Release);
+            }
+            Err(INITIALIZING) => {
+                // Another core would finish; on ours it is our own caller.
+                if cfg!(debug_assertions) {
+                    panic!("re-entrant OnceCell initialization");
+                }
+                while self.state.load(Ordering::Acquire) != READY {
+                    core::hint::spin_loop();
+                }
+            }
+            Err(_) => {}
+        }
+        self.get().expect("OnceCell ready")
+    }
+}
+
+impl<T> Default for OnceCell<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spin_lock_exclusive() {
+        let lock = SpinLock::new("test", 0u32);
+        {
+            let mut guard = lock.lock();
+            *guard += 1;
+            assert!(lock.is_locked());
+            assert!(lock.try_lock().is_none());
+        }
+        assert!(!lock.is_locked());
+        assert_eq!(*lock.lock(), 1);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "re-entrant lock of ring")]
+    fn test_spin_lock_reentry_panics() {
+        let lock = SpinLock::new("ring", ());
+        let _outer = lock.lock();
+        let _inner = lock.lock();
+    }
+
+    #[test]
+    fn test_once_cell() {
+        let cell = OnceCell::new();
+        assert!(cell.get().is_none());
+        assert_eq!(*cell.get_or_init(|| 7u32), 7);
+        assert_eq!(cell.set(9), Err(9));
+        assert_eq!(*cell.get_or_init(|| 11), 7);
+    }
+}
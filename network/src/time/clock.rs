@@ -0,0 +1,340 @@
+//! Clock abstraction.
+//!
+//! Every timeout in the stack is "ticks since X compared against N ticks".
+//! Historically the ticks were always TSC ticks, which breaks on machines
+//! whose TSC is not invariant (it slows down in deep C-states or changes
+//! with P-states) or whose firmware mis-reports it. `Clock` decouples the
+//! tick source from the timeout logic:
+//!
+//! - `TscClock` — preferred when CPUID reports an invariant TSC
+//! - `HpetClock` — memory-mapped, fixed rate, moderately cheap to read
+//! - `PmTimerClock` — ACPI PM timer, slow to read but always there
+//!
+//! `select_clock()` picks one; `install()` makes it the process-wide clock
+//! used by `now()`, `Deadline` and `TimeoutConfig::active()`.
+
+use core::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+use crate::asm::core::tsc::read_tsc;
+
+use super::hpet::HpetClock;
+use super::pm_timer::PmTimerClock;
+
+/// Which hardware counter backs a clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockSource {
+    Tsc,
+    Hpet,
+    AcpiPm,
+}
+
+impl ClockSource {
+    /// Short name for logs.
+    pub fn name(self) -> &'static str {
+        match self {
+            ClockSource::Tsc if cfg!(target_arch = "aarch64") => "CNTVCT",
+            ClockSource::Tsc => "TSC",
+            ClockSource::Hpet => "HPET",
+            ClockSource::AcpiPm => "ACPI PM",
+        }
+    }
+}
+
+/// Monotonic tick source.
+pub trait Clock {
+    /// Current tick count (monotonic, arbitrary epoch).
+    fn now(&self) -> u64;
+
+    /// Ticks per second.
+    fn frequency(&self) -> u64;
+
+    /// Backing hardware.
+    fn source(&self) -> ClockSource;
+
+    /// Convert milliseconds to ticks.
+    #[inline]
+    fn ms_to_ticks(&self, ms: u64) -> u64 {
+        ms.saturating_mul(self.frequency()) / 1_000
+    }
+
+    /// Convert ticks to milliseconds.
+    #[inline]
+    fn ticks_to_ms(&self, ticks: u64) -> u64 {
+        match self.frequency() {
+            0 => 0,
+            f => ticks.saturating_mul(1_000) / f,
+        }
+    }
+
+    /// Ticks elapsed since `start`.
+    #[inline]
+    fn elapsed(&self, start: u64) -> u64 {
+        self.now().wrapping_sub(start)
+    }
+}
+
+/// TSC-backed clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TscClock {
+    frequency: u64,
+}
+
+impl TscClock {
+    /// Create from a calibrated TSC frequency (Hz).
+    pub const fn new(frequency: u64) -> Self {
+        Self { frequency }
+    }
+}
+
+impl Clock for TscClock {
+    #[inline]
+    fn now(&self) -> u64 {
+        read_tsc()
+    }
+
+    #[inline]
+    fn frequency(&self) -> u64 {
+        self.frequency
+    }
+
+    fn source(&self) -> ClockSource {
+        ClockSource::Tsc
+    }
+}
+
+/// Any of the supported clocks, chosen at runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SystemClock {
+    Tsc(TscClock),
+    Hpet(HpetClock),
+    AcpiPm(PmTimerClock),
+}
+
+impl Clock for SystemClock {
+    #[inline]
+    fn now(&self) -> u64 {
+        match self {
+            SystemClock::Tsc(c) => c.now(),
+            SystemClock::Hpet(c) => c.now(),
+            SystemClock::AcpiPm(c) => c.now(),
+        }
+    }
+
+    #[inline]
+    fn frequency(&self) -> u64 {
+        match self {
+            SystemClock::Tsc(c) => c.frequency(),
+            SystemClock::Hpet(c) => c.frequency(),
+            SystemClock::AcpiPm(c) => c.frequency(),
+        }
+    }
+
+    fn source(&self) -> ClockSource {
+        match self {
+            SystemClock::Tsc(c) => c.source(),
+            SystemClock::Hpet(c) => c.source(),
+            SystemClock::AcpiPm(c) => c.source(),
+        }
+    }
+}
+
+/// Pick the best clock for this machine.
+///
+/// Invariant TSC wins outright. Otherwise HPET (if `hpet_base` probes
+/// sane), then the ACPI PM timer (if `pm_timer` is known), and finally the
+/// TSC anyway — a drifting clock beats no clock.
+///
+/// # Safety
+/// `hpet_base`, if given, must be identity-mapped MMIO.
+pub unsafe fn select_clock(
+    tsc_freq: u64,
+    invariant_tsc: bool,
+    hpet_base: Option<u64>,
+    pm_timer: Option<PmTimerClock>,
+) -> SystemClock {
+    // Only touch the HPET if the TSC is not good enough on its own.
+    let hpet = match hpet_base {
+        Some(base) if !(invariant_tsc && tsc_freq != 0) => HpetClock::probe(base),
+        _ => None,
+    };
+    choose_clock(tsc_freq, invariant_tsc, hpet, pm_timer)
+}
+
+/// Selection policy behind `select_clock()`, with the HPET already probed.
+pub fn choose_clock(
+    tsc_freq: u64,
+    invariant_tsc: bool,
+    hpet: Option<HpetClock>,
+    pm_timer: Option<PmTimerClock>,
+) -> SystemClock {
+    if invariant_tsc && tsc_freq != 0 {
+        return SystemClock::Tsc(TscClock::new(tsc_freq));
+    }
+    if let Some(hpet) = hpet {
+        return SystemClock::Hpet(hpet);
+    }
+    if let Some(pm) = pm_timer {
+        return SystemClock::AcpiPm(pm);
+    }
+    SystemClock::Tsc(TscClock::new(tsc_freq))
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// PROCESS-WIDE CLOCK
+// ═══════════════════════════════════════════════════════════════════════════
+
+const KIND_NONE: u8 = 0;
+const KIND_TSC: u8 = 1;
+const KIND_HPET: u8 = 2;
+const KIND_HPET_WIDE: u8 = 3;
+const KIND_PM: u8 = 4;
+const KIND_PM_WIDE: u8 = 5;
+
+static ACTIVE_KIND: AtomicU8 = AtomicU8::new(KIND_NONE);
+/// HPET base or PM timer port.
+static ACTIVE_PARAM: AtomicU64 = AtomicU64::new(0);
+static ACTIVE_FREQ: AtomicU64 = AtomicU64::new(0);
+
+/// Make `clock` the process-wide clock.
+pub fn install(clock: SystemClock) {
+    let (kind, param) = match clock {
+        SystemClock::Tsc(_) => (KIND_TSC, 0),
+        SystemClock::Hpet(h) => (
+            if h.is_wide() { KIND_HPET_WIDE } else { KIND_HPET },
+            h.base(),
+        ),
+        SystemClock::AcpiPm(p) => (
+            if p.is_wide() { KIND_PM_WIDE } else { KIND_PM },
+            p.port() as u64,
+        ),
+    };
+    ACTIVE_PARAM.store(param, Ordering::Relaxed);
+    ACTIVE_FREQ.store(clock.frequency(), Ordering::Relaxed);
+    ACTIVE_KIND.store(kind, Ordering::Release);
+}
+
+/// The installed clock, if any.
+pub fn installed() -> Option<SystemClock> {
+    let kind = ACTIVE_KIND.load(Ordering::Acquire);
+    let param = ACTIVE_PARAM.load(Ordering::Relaxed);
+    let freq = ACTIVE_FREQ.load(Ordering::Relaxed);
+    match kind {
+        KIND_TSC => Some(SystemClock::Tsc(TscClock::new(freq))),
+        KIND_HPET | KIND_HPET_WIDE => Some(SystemClock::Hpet(HpetClock::from_parts(
+            param,
+            freq,
+            kind == KIND_HPET_WIDE,
+        ))),
+        KIND_PM | KIND_PM_WIDE => Some(SystemClock::AcpiPm(PmTimerClock::new(
+            param as u16,
+            kind == KIND_PM_WIDE,
+        ))),
+        _ => None,
+    }
+}
+
+/// The installed clock, or a TSC clock at `tsc_freq` if none was installed.
+///
+/// Drivers call this with their configured TSC frequency so they keep
+/// working when used standalone (e.g. before `install()`).
+pub fn active_or_tsc(tsc_freq: u64) -> SystemClock {
+    installed().unwrap_or(SystemClock::Tsc(TscClock::new(tsc_freq)))
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// DEADLINE
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// A point in the future on a given clock.
+#[derive(Debug, Clone, Copy)]
+pub struct Deadline {
+    clock: SystemClock,
+    start: u64,
+    ticks: u64,
+}
+
+impl Deadline {
+    /// Deadline `ms` milliseconds from now on `clock`.
+    pub fn after_ms(clock: SystemClock, ms: u64) -> Self {
+        Self {
+            clock,
+            start: clock.now(),
+            ticks: clock.ms_to_ticks(ms),
+        }
+    }
+
+    /// Whether the deadline has passed.
+    #[inline]
+    pub fn expired(&self) -> bool {
+        self.clock.elapsed(self.start) > self.ticks
+    }
+
+    /// Milliseconds elapsed since the deadline was created.
+    pub fn elapsed_ms(&self) -> u64 {
+        self.clock.ticks_to_ms(self.clock.elapsed(self.start))
+    }
+}
+
+/// Busy-wait `ms` milliseconds on `clock`.
+pub fn delay_ms(clock: SystemClock, ms: u64) {
+    let deadline = Deadline::after_ms(clock, ms);
+    while !deadline.expired() {
+        core::hint::spin_loop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::pm_timer::extend;
+
+    #[test]
+    fn test_invariant_tsc_preferred() {
+        let hpet = HpetClock::from_parts(0xFED0_0000, 14_318_180, true);
+        let pm = PmTimerClock::new(0x608, false);
+        let clock = choose_clock(2_000_000_000, true, Some(hpet), Some(pm));
+        assert_eq!(clock.source(), ClockSource::Tsc);
+        assert_eq!(clock.frequency(), 2_000_000_000);
+    }
+
+    #[test]
+    fn test_pm_timer_fallback() {
+        let pm = PmTimerClock::new(0x608, false);
+        let clock = choose_clock(2_000_000_000, false, None, Some(pm));
+        assert_eq!(clock, SystemClock::AcpiPm(pm));
+        assert_eq!(clock.ms_to_ticks(1_000), 3_579_545);
+    }
+
+    #[test]
+    fn test_hpet_before_pm_timer() {
+        let hpet = HpetClock::from_parts(0xFED0_0000, 14_318_180, true);
+        let pm = PmTimerClock::new(0x608, false);
+        let clock = choose_clock(2_000_000_000, false, Some(hpet), Some(pm));
+        assert_eq!(clock.source(), ClockSource::Hpet);
+        assert_eq!(clock.frequency(), 14_318_180);
+    }
+
+    #[test]
+    fn test_unknown_falls_back_to_tsc() {
+        let clock = choose_clock(1_500_000_000, false, None, None);
+        assert_eq!(clock.source(), ClockSource::Tsc);
+    }
+
+    #[test]
+    fn test_tick_conversions() {
+        let clock = TscClock::new(3_000_000_000);
+        assert_eq!(clock.ms_to_ticks(10), 30_000_000);
+        assert_eq!(clock.ticks_to_ms(30_000_000), 10);
+        assert_eq!(TscClock::new(0).ticks_to_ms(123), 0);
+    }
+
+    #[test]
+    fn test_pm_timer_extend_wraps() {
+        assert_eq!(extend(0, 0x00_1000, 24), 0x1000);
+        assert_eq!(extend(0xFF_FFF0, 0x00_0010, 24), 0x100_0010);
+        assert_eq!(extend(0x100_0010, 0x00_0020, 24), 0x100_0020);
+        // Upper garbage bits of a 24-bit read are ignored
+        assert_eq!(extend(0, 0xFF00_0005, 24), 5);
+        assert_eq!(extend(0xFFFF_FFF0, 0x10, 32), 0x1_0000_0010);
+    }
+}
@@ -0,0 +1,110 @@
+//! HPET (High Precision Event Timer) clock backend.
+//!
+//! Only the main counter is used — no comparators, no interrupts.
+//! The counter runs at a fixed rate advertised in the capabilities
+//! register, independent of CPU P-/C-states.
+//!
+//! # Reference
+//! IA-PC HPET Specification 1.0a §2.3
+
+use crate::asm::core::mmio::{read32, write32};
+
+use super::clock::{Clock, ClockSource};
+
+/// Architectural default HPET base on PC-compatible chipsets.
+pub const HPET_DEFAULT_BASE: u64 = 0xFED0_0000;
+
+/// General Capabilities and ID register (64-bit).
+const REG_CAPABILITIES: u64 = 0x000;
+/// General Configuration register.
+const REG_CONFIG: u64 = 0x010;
+/// Main Counter Value register (64-bit).
+const REG_MAIN_COUNTER: u64 = 0x0F0;
+
+/// Configuration: overall enable.
+const CONFIG_ENABLE: u32 = 1 << 0;
+/// Capabilities: main counter is 64 bits wide.
+const CAP_COUNT_SIZE_64: u32 = 1 << 13;
+
+/// Spec upper bound for COUNTER_CLK_PERIOD (100ns, in femtoseconds).
+const MAX_PERIOD_FS: u32 = 100_000_000;
+
+const FEMTOS_PER_SEC: u64 = 1_000_000_000_000_000;
+
+/// HPET main-counter clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HpetClock {
+    base: u64,
+    frequency: u64,
+    wide: bool,
+}
+
+impl HpetClock {
+    /// Probe and enable the HPET at `base`.
+    ///
+    /// Returns `None` if nothing sane answers at that address
+    /// (all-ones read, zero or out-of-spec period).
+    ///
+    /// # Safety
+    /// `base` must be identity-mapped MMIO (or unbacked, reading all-ones).
+    pub unsafe fn probe(base: u64) -> Option<Self> {
+        let cap_lo = read32(base + REG_CAPABILITIES);
+        let period_fs = read32(base + REG_CAPABILITIES + 4);
+        if cap_lo == 0xFFFF_FFFF || period_fs == 0 || period_fs > MAX_PERIOD_FS {
+            return None;
+        }
+
+        let config = read32(base + REG_CONFIG);
+        if config & CONFIG_ENABLE == 0 {
+            write32(base + REG_CONFIG, config | CONFIG_ENABLE);
+        }
+
+        Some(Self {
+            base,
+            frequency: FEMTOS_PER_SEC / period_fs as u64,
+            wide: cap_lo & CAP_COUNT_SIZE_64 != 0,
+        })
+    }
+
+    /// Rebuild from known-good parameters (no probing, no MMIO).
+    pub(crate) const fn from_parts(base: u64, frequency: u64, wide: bool) -> Self {
+        Self { base, frequency, wide }
+    }
+
+    /// MMIO base address.
+    pub fn base(&self) -> u64 {
+        self.base
+    }
+
+    /// Whether the main counter is 64 bits wide.
+    pub fn is_wide(&self) -> bool {
+        self.wide
+    }
+}
+
+impl Clock for HpetClock {
+    fn now(&self) -> u64 {
+        let counter = self.base + REG_MAIN_COUNTER;
+        unsafe {
+            if !self.wide {
+                return read32(counter) as u64;
+            }
+            // hi-lo-hi so a carry between the two halves is not torn
+            loop {
+                let hi = read32(counter + 4);
+                let lo = read32(counter);
+                if read32(counter + 4) == hi {
+                    return ((hi as u64) << 32) | lo as u64;
+                }
+            }
+        }
+    }
+
+    fn frequency(&self) -> u64 {
+        self.frequency
+    }
+
+    fn source(&self) -> ClockSource {
+        ClockSource::Hpet
+    }
+}
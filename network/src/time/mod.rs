@@ -1,8 +1,25 @@
 //! Time and timing module.
 //!
-//! TSC-based timing with calibrated timeouts.
+//! Clock-agnostic timing with calibrated timeouts. The tick source is
+//! TSC, HPET or ACPI PM timer (see `clock`); timeouts are expressed in
+//! ticks of whichever clock is active. `wall` keeps the date, learned
+//! from HTTP servers, for file timestamps; `rtc` reads the CMOS clock
+//! for a date before any server has given one.
 
-/// Timeout configuration derived from TSC frequency.
+pub mod clock;
+pub mod hpet;
+pub mod pm_timer;
+pub mod rtc;
+pub mod wall;
+
+pub use clock::{
+    active_or_tsc, choose_clock, delay_ms, install, installed, select_clock, Clock, ClockSource, Deadline,
+    SystemClock, TscClock,
+};
+pub use hpet::{HpetClock, HPET_DEFAULT_BASE};
+pub use pm_timer::{PmTimerClock, PM_TIMER_FREQUENCY};
+
+/// Timeout configuration derived from a clock frequency.
 #[derive(Debug, Clone, Copy)]
 pub struct TimeoutConfig {
     ticks_per_ms: u64,
@@ -16,6 +33,18 @@ impl TimeoutConfig {
         }
     }
 
+    /// Create for an arbitrary clock (ticks are that clock's ticks).
+    pub fn from_clock<C: Clock>(clock: &C) -> Self {
+        Self {
+            ticks_per_ms: clock.frequency() / 1_000,
+        }
+    }
+
+    /// Create for the installed clock, falling back to TSC at `tsc_freq`.
+    pub fn active(tsc_freq: u64) -> Self {
+        Self::from_clock(&active_or_tsc(tsc_freq))
+    }
+
     /// DHCP timeout (30 seconds)
     #[inline]
     pub fn dhcp(&self) -> u64 {
@@ -64,6 +93,13 @@ impl TimeoutConfig {
         5 * self.ticks_per_ms
     }
 
+    /// Block I/O request timeout (10 seconds, long enough for a spun-down
+    /// hard disk to spin up)
+    #[inline]
+    pub fn block_io(&self) -> u64 {
+        10_000 * self.ticks_per_ms
+    }
+
     /// Device reset timeout (100ms)
     #[inline]
     pub fn device_reset(&self) -> u64 {
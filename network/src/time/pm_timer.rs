@@ -0,0 +1,80 @@
+//! ACPI PM timer clock backend.
+//!
+//! The PM timer is a free-running 24- or 32-bit counter at 3.579545 MHz,
+//! read through the I/O port given by the FADT's `PM_TMR_BLK`. It is slow
+//! to read (~1µs per `in`) but immune to TSC drift, which makes it the
+//! fallback of last resort.
+//!
+//! The counter is extended to 64 bits in software, so it must be read at
+//! least once per wrap (~4.7s for 24-bit, ~20min for 32-bit). The poll
+//! loops do that many times per millisecond.
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use crate::asm::core::pio::inl;
+
+use super::clock::{Clock, ClockSource};
+
+/// PM timer frequency (Hz), fixed by the ACPI spec.
+pub const PM_TIMER_FREQUENCY: u64 = 3_579_545;
+
+/// Last extended value; the low `bits` bits mirror the hardware counter.
+static PM_EXTENDED: AtomicU64 = AtomicU64::new(0);
+
+/// ACPI PM timer clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PmTimerClock {
+    port: u16,
+    bits: u32,
+}
+
+impl PmTimerClock {
+    /// Create a PM timer clock on `port` (FADT `PM_TMR_BLK`).
+    ///
+    /// `wide` reflects FADT flag `TMR_VAL_EXT` (32-bit counter).
+    pub const fn new(port: u16, wide: bool) -> Self {
+        Self {
+            port,
+            bits: if wide { 32 } else { 24 },
+        }
+    }
+
+    /// I/O port of the counter.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Whether the counter is 32 bits wide.
+    pub fn is_wide(&self) -> bool {
+        self.bits == 32
+    }
+}
+
+/// Extend a `bits`-wide raw counter reading against the previous extended value.
+pub(crate) fn extend(previous: u64, raw: u32, bits: u32) -> u64 {
+    let mask = (1u64 << bits) - 1;
+    let raw = raw as u64 & mask;
+    let mut high = previous & !mask;
+    if raw < previous & mask {
+        high = high.wrapping_add(mask + 1);
+    }
+    high | raw
+}
+
+impl Clock for PmTimerClock {
+    fn now(&self) -> u64 {
+        let raw = unsafe { inl(self.port) };
+        let previous = PM_EXTENDED.load(Ordering::Relaxed);
+        let value = extend(previous, raw, self.bits);
+        PM_EXTENDED.store(value, Ordering::Relaxed);
+        value
+    }
+
+    fn frequency(&self) -> u64 {
+        PM_TIMER_FREQUENCY
+    }
+
+    fn source(&self) -> ClockSource {
+        ClockSource::AcpiPm
+    }
+}
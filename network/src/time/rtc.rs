@@ -0,0 +1,155 @@
+//! CMOS real-time clock.
+//!
+//! The date before any server has told us one. The RTC keeps whatever
+//! the firmware setup says, usually local time, so it can be hours off
+//! UTC; good enough to check a certificate's validity period, not to
+//! stamp files with.
+
+use morpheus_core::time::DateTime;
+
+use crate::asm::core::pio::{inb, outb};
+
+/// CMOS index port (bit 7 masks NMIs, left clear)
+const CMOS_INDEX: u16 = 0x70;
+/// CMOS data port
+const CMOS_DATA: u16 = 0x71;
+
+/// Time registers
+const REG_SECONDS: u8 = 0x00;
+const REG_MINUTES: u8 = 0x02;
+const REG_HOURS: u8 = 0x04;
+const REG_DAY: u8 = 0x07;
+const REG_MONTH: u8 = 0x08;
+const REG_YEAR: u8 = 0x09;
+/// Status A: bit 7 set while the time is being updated
+const REG_STATUS_A: u8 = 0x0A;
+/// Status B: bit 1 = 24-hour mode, bit 2 = binary (not BCD)
+const REG_STATUS_B: u8 = 0x0B;
+
+const STATUS_A_UPDATING: u8 = 1 << 7;
+const STATUS_B_24H: u8 = 1 << 1;
+const STATUS_B_BINARY: u8 = 1 << 2;
+/// Hour register: PM in 12-hour mode
+const HOUR_PM: u8 = 1 << 7;
+
+/// Reads to wait out an update in progress (one takes under 2 ms)
+const UPDATE_SPINS: u32 = 100_000;
+
+/// The RTC's date as Unix seconds, treating it as UTC. None without a
+/// CMOS RTC or when it reads as garbage (flat battery).
+pub fn unix_now() -> Option<u64> {
+    if !cfg!(target_arch = "x86_64") {
+        return None;
+    }
+    // Read until two reads agree, so no update tore the first
+    let mut last = read_raw()?;
+    for _ in 0..4 {
+        let again = read_raw()?;
+        if again == last {
+            let status_b = read_register(REG_STATUS_B);
+            return decode(last, status_b)
+                .filter(DateTime::is_valid)
+                .map(to_unix);
+        }
+        last = again;
+    }
+    None
+}
+
+/// Seconds, minutes, hours, day, month, year as stored.
+type RawTime = [u8; 6];
+
+fn read_raw() -> Option<RawTime> {
+    let mut spins = 0;
+    while read_register(REG_STATUS_A) & STATUS_A_UPDATING != 0 {
+        spins += 1;
+        if spins > UPDATE_SPINS {
+            return None;
+        }
+    }
+    Some([
+        read_register(REG_SECONDS),
+        read_register(REG_MINUTES),
+        read_register(REG_HOURS),
+        read_register(REG_DAY),
+        read_register(REG_MONTH),
+        read_register(REG_YEAR),
+    ])
+}
+
+fn read_register(reg: u8) -> u8 {
+    // Safety: the CMOS ports exist on every PC-compatible x86 machine
+    unsafe {
+        outb(CMOS_INDEX, reg);
+        inb(CMOS_DATA)
+    }
+}
+
+/// Decode raw registers in the format status B describes. The year is
+/// taken to be 20xx; the century register isn't reliably there.
+fn decode(raw: RawTime, status_b: u8) -> Option<DateTime> {
+    let value = |b: u8| {
+        if status_b & STATUS_B_BINARY != 0 {
+            Some(b)
+        } else if b & 0x0F < 10 && b >> 4 < 10 {
+            Some((b >> 4) * 10 + (b & 0x0F))
+        } else {
+            None
+        }
+    };
+    let [second, minute, hour, day, month, year] = raw;
+    let mut hours = value(hour & !HOUR_PM)?;
+    if status_b & STATUS_B_24H == 0 {
+        // 12-hour mode: 12 AM is midnight, 12 PM noon
+        hours %= 12;
+        if hour & HOUR_PM != 0 {
+            hours += 12;
+        }
+    }
+    Some(DateTime {
+        year: 2000 + value(year)? as u16,
+        month: value(month)?,
+        day: value(day)?,
+        hour: hours,
+        minute: value(minute)?,
+        second: value(second)?,
+    })
+}
+
+/// Unix seconds of a date (days-from-civil, Howard Hinnant).
+fn to_unix(t: DateTime) -> u64 {
+    let (y, m) = if t.month <= 2 {
+        (t.year as i64 - 1, t.month as i64 + 9)
+    } else {
+        (t.year as i64, t.month as i64 - 3)
+    };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * m + 2) / 5 + t.day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+    days as u64 * 86_400 + t.hour as u64 * 3600 + t.minute as u64 * 60 + t.second as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        // 2024-02-29 15:25:07, BCD, 24-hour
+        let t = decode([0x07, 0x25, 0x15, 0x29, 0x02, 0x24], STATUS_B_24H).unwrap();
+        assert_eq!(to_unix(t), 1_709_208_000 + 3 * 3600 + 25 * 60 + 7);
+        assert_eq!(DateTime::from_unix(to_unix(t)), t);
+
+        // Same in binary, 12-hour: 3 PM
+        let binary = STATUS_B_BINARY;
+        let pm = decode([7, 25, HOUR_PM | 3, 29, 2, 24], binary).unwrap();
+        assert_eq!(pm, t);
+        // 12 AM is midnight
+        assert_eq!(decode([0, 0, 0x12, 1, 1, 0x25], 0).unwrap().hour, 0);
+
+        // Not BCD
+        assert_eq!(decode([0x0A, 0, 0, 1, 1, 0x25], STATUS_B_24H), None);
+    }
+}
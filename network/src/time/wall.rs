@@ -0,0 +1,68 @@
+//! Wall-clock time after ExitBootServices.
+//!
+//! The firmware's RTC is out of reach once boot services are gone, and
+//! there is no NTP client in the stack, so the date comes from the servers
+//! we talk to: every HTTP response carries a `Date` header. Each one
+//! resyncs the clock, which advances with the installed tick clock in
+//! between; good to a second or so, which is all a FAT timestamp holds.
+//!
+//! `run_download` installs [`now`] as `morpheus_core::time`'s clock, so
+//! manifests and other files written after the download get timestamps.
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use morpheus_core::time::DateTime;
+
+use super::clock::{installed, Clock};
+
+/// Unix seconds at the last sync (0 = never synced).
+static SYNC_UNIX: AtomicU64 = AtomicU64::new(0);
+/// Clock ticks at the last sync.
+static SYNC_TICKS: AtomicU64 = AtomicU64::new(0);
+/// Clock frequency at the last sync.
+static SYNC_FREQ: AtomicU64 = AtomicU64::new(0);
+
+/// Set the wall clock to `unix_secs` as of `ticks` of the installed clock,
+/// which runs at `frequency`.
+pub fn sync(unix_secs: u64, ticks: u64, frequency: u64) {
+    if unix_secs == 0 || frequency == 0 {
+        return;
+    }
+    SYNC_TICKS.store(ticks, Ordering::Relaxed);
+    SYNC_FREQ.store(frequency, Ordering::Relaxed);
+    SYNC_UNIX.store(unix_secs, Ordering::Release);
+}
+
+/// Unix seconds at `ticks`, None before the first sync.
+pub fn unix_at(ticks: u64) -> Option<u64> {
+    let unix = SYNC_UNIX.load(Ordering::Acquire);
+    if unix == 0 {
+        return None;
+    }
+    let elapsed = ticks.wrapping_sub(SYNC_TICKS.load(Ordering::Relaxed));
+    Some(unix + elapsed / SYNC_FREQ.load(Ordering::Relaxed))
+}
+
+/// Current date and time, None before the first sync.
+pub fn now() -> Option<DateTime> {
+    unix_at(installed()?.now()).map(DateTime::from_unix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unix_at() {
+        // Only test touching the statics
+        assert_eq!(unix_at(0), None);
+        sync(0, 5, 1000); // Ignored: no date
+        assert_eq!(unix_at(0), None);
+
+        sync(1_709_208_000, 10_000, 1000);
+        assert_eq!(unix_at(10_000), Some(1_709_208_000));
+        assert_eq!(unix_at(12_999), Some(1_709_208_002));
+        let today = DateTime::from_unix(unix_at(12_999).unwrap());
+        assert_eq!((today.year, today.month, today.day), (2024, 2, 29));
+    }
+}
@@ -0,0 +1,626 @@
+//! TLS 1.2/1.3 for `https://` downloads.
+//!
+//! [`TlsStack`] wraps another [`NetStack`] and puts a rustls client
+//! session on the TCP connection opened with
+//! [`NetStack::tcp_connect_tls`]: the states keep talking plaintext HTTP
+//! through `tcp_send`/`tcp_recv`, and connections opened with plain
+//! `tcp_connect` pass straight through. rustls runs in its unbuffered
+//! mode (the only one without `std`), so the session keeps its own
+//! record buffers and pumps them on every `poll`.
+//!
+//! The server name goes out as SNI and the chain is checked against the
+//! Mozilla roots bundled by `webpki-roots`. Crypto is RustCrypto
+//! (see `provider`), randomness comes from RDRAND. Certificate validity is
+//! checked against the date the last HTTP response carried, the CMOS RTC
+//! before that, and [`TIME_FLOOR`] if neither gives a sane date.
+//!
+//! # Modules
+//! - `provider` - rustls `CryptoProvider`: suites, key exchange, RNG
+//! - `verify` - Signature algorithms for chains and handshakes
+
+pub mod provider;
+pub mod verify;
+
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::sync::Arc;
+use alloc::vec;
+use core::net::Ipv4Addr;
+use core::time::Duration;
+
+use rustls::client::UnbufferedClientConnection;
+use rustls::pki_types::{ServerName, UnixTime};
+use rustls::time_provider::TimeProvider;
+use rustls::unbuffered::{ConnectionState, EncodeError, UnbufferedStatus};
+use rustls::{ClientConfig, RootCertStore};
+
+use crate::driver::traits::LinkInfo;
+use crate::mainloop::netstack::{DhcpEvent, DnsStatus, NetStack, StackError, TcpStatus};
+use crate::mainloop::serial;
+use crate::mainloop::tcp_stats::TcpTelemetry;
+use crate::time::{self, rtc, wall, Clock};
+
+/// Earliest date the TLS clock will report (2026-10-01 00:00 UTC): a
+/// dead RTC battery must not make every certificate "not yet valid".
+pub const TIME_FLOOR: u64 = 1_790_812_800;
+
+/// Largest TLS record on the wire: header, 16 KiB of plaintext and the
+/// most expansion TLS 1.2 allows
+const MAX_RECORD_LEN: usize = 5 + 16384 + 2048;
+
+/// Decrypted bytes waiting for `tcp_recv`: one full record
+const PLAIN_IN_SIZE: usize = 16384;
+
+/// Bytes from `tcp_send` waiting to be encrypted (requests are small)
+const PLAIN_OUT_SIZE: usize = 4096;
+
+/// Room left in the outgoing buffer for a record's header, explicit
+/// nonce, content type and tag
+const RECORD_OVERHEAD: usize = 64;
+
+/// Most `process_tls_records` rounds in one pump; each one either
+/// consumes input, produces output or stops, so this is only a backstop
+const MAX_ROUNDS: usize = 64;
+
+/// Where the certificate clock's date came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeSource {
+    /// The `Date` of an HTTP response
+    Server,
+    /// The CMOS RTC
+    Rtc,
+    /// Nothing better than [`TIME_FLOOR`]
+    Floor,
+}
+
+impl TimeSource {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Server => "server date",
+            Self::Rtc => "RTC",
+            Self::Floor => "build floor",
+        }
+    }
+}
+
+/// Current Unix time for certificate checks, and where it came from.
+pub fn unix_time() -> (u64, TimeSource) {
+    let wall = time::installed().and_then(|clock| wall::unix_at(clock.now()));
+    if let Some(secs) = wall.filter(|&secs| secs >= TIME_FLOOR) {
+        return (secs, TimeSource::Server);
+    }
+    match rtc::unix_now().filter(|&secs| secs >= TIME_FLOOR) {
+        Some(secs) => (secs, TimeSource::Rtc),
+        None => (TIME_FLOOR, TimeSource::Floor),
+    }
+}
+
+/// rustls' clock: [`unix_time`].
+#[derive(Debug)]
+struct TlsTime;
+
+impl TimeProvider for TlsTime {
+    fn current_time(&self) -> Option<UnixTime> {
+        Some(UnixTime::since_unix_epoch(Duration::from_secs(
+            unix_time().0,
+        )))
+    }
+}
+
+/// Client configuration: our provider, TLS 1.2 and 1.3, the bundled roots.
+pub fn client_config() -> Result<ClientConfig, rustls::Error> {
+    let roots = RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    Ok(
+        ClientConfig::builder_with_details(Arc::new(provider::provider()), Arc::new(TlsTime))
+            .with_safe_default_protocol_versions()?
+            .with_root_certificates(roots)
+            .with_no_client_auth(),
+    )
+}
+
+/// Fixed-size byte queue.
+struct Buffer {
+    data: Box<[u8]>,
+    start: usize,
+    end: usize,
+}
+
+impl Buffer {
+    fn new(size: usize) -> Self {
+        Self {
+            data: vec![0u8; size].into_boxed_slice(),
+            start: 0,
+            end: 0,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Bytes that can still be added.
+    fn free(&self) -> usize {
+        self.data.len() - self.len()
+    }
+
+    fn bytes(&self) -> &[u8] {
+        &self.data[self.start..self.end]
+    }
+
+    fn bytes_mut(&mut self) -> &mut [u8] {
+        &mut self.data[self.start..self.end]
+    }
+
+    /// Space after the queued bytes, moved to the front first; mark what
+    /// gets written with `fill`.
+    fn spare(&mut self) -> &mut [u8] {
+        if self.start > 0 {
+            self.data.copy_within(self.start..self.end, 0);
+            self.end -= self.start;
+            self.start = 0;
+        }
+        &mut self.data[self.end..]
+    }
+
+    fn fill(&mut self, n: usize) {
+        self.end += n;
+    }
+
+    fn consume(&mut self, n: usize) {
+        self.start += n;
+        if self.start == self.end {
+            self.start = 0;
+            self.end = 0;
+        }
+    }
+
+    /// Queue as much of `bytes` as fits; returns how much.
+    fn push(&mut self, bytes: &[u8]) -> usize {
+        let n = bytes.len().min(self.free());
+        self.spare()[..n].copy_from_slice(&bytes[..n]);
+        self.fill(n);
+        n
+    }
+
+    /// Move queued bytes into `out`; returns how many.
+    fn pop(&mut self, out: &mut [u8]) -> usize {
+        let n = out.len().min(self.len());
+        out[..n].copy_from_slice(&self.bytes()[..n]);
+        self.consume(n);
+        n
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Handshake,
+    Open,
+    /// The server sent close_notify
+    PeerClosed,
+    /// Handshake or record error; the TCP connection is gone
+    Failed,
+}
+
+/// One TLS session over the inner stack's TCP connection.
+struct Session {
+    conn: UnbufferedClientConnection,
+    phase: Phase,
+    /// Records from the server not processed yet
+    incoming: Buffer,
+    /// Records for the server not sent yet
+    outgoing: Buffer,
+    plain_in: Buffer,
+    plain_out: Buffer,
+}
+
+impl Session {
+    fn new(conn: UnbufferedClientConnection) -> Self {
+        Self {
+            conn,
+            phase: Phase::Handshake,
+            incoming: Buffer::new(MAX_RECORD_LEN),
+            outgoing: Buffer::new(MAX_RECORD_LEN),
+            plain_in: Buffer::new(PLAIN_IN_SIZE),
+            plain_out: Buffer::new(PLAIN_OUT_SIZE),
+        }
+    }
+
+    /// Move records between the TCP connection and rustls. Returns true
+    /// if anything moved.
+    fn pump(&mut self, tcp: &mut dyn NetStack) -> bool {
+        if self.phase == Phase::Failed {
+            return false;
+        }
+        let mut moved = self.flush(tcp);
+        if tcp.tcp_may_recv() && self.incoming.free() > 0 {
+            if let Ok(n) = tcp.tcp_recv(self.incoming.spare()) {
+                self.incoming.fill(n);
+                moved |= n > 0;
+            }
+        }
+        if let Err(e) = self.process() {
+            serial::print("[TLS] ERROR: ");
+            serial::println(&format!("{}", e));
+            self.phase = Phase::Failed;
+            tcp.tcp_abort();
+            return true;
+        }
+        moved | self.flush(tcp)
+    }
+
+    /// Send what the TCP connection takes of the outgoing records.
+    fn flush(&mut self, tcp: &mut dyn NetStack) -> bool {
+        let mut sent = false;
+        while !self.outgoing.is_empty() && tcp.tcp_may_send() {
+            match tcp.tcp_send(self.outgoing.bytes()) {
+                Ok(n) if n > 0 => {
+                    self.outgoing.consume(n);
+                    sent = true;
+                }
+                _ => break,
+            }
+        }
+        sent
+    }
+
+    /// Run rustls over the buffered input until it needs more data, more
+    /// output room or a reader.
+    fn process(&mut self) -> Result<(), rustls::Error> {
+        let Self {
+            conn,
+            phase,
+            incoming,
+            outgoing,
+            plain_in,
+            plain_out,
+        } = self;
+
+        for _ in 0..MAX_ROUNDS {
+            let UnbufferedStatus { mut discard, state } =
+                conn.process_tls_records(incoming.bytes_mut());
+            let mut stop = false;
+            let mut opened = false;
+            match state? {
+                ConnectionState::ReadTraffic(mut traffic) => {
+                    while let Some(len) = traffic.peek_len() {
+                        if plain_in.free() < len.get() {
+                            // Wait for tcp_recv to make room
+                            stop = true;
+                            break;
+                        }
+                        match traffic.next_record() {
+                            Some(Ok(record)) => {
+                                discard += record.discard;
+                                plain_in.push(record.payload);
+                            }
+                            Some(Err(e)) => return Err(e),
+                            None => break,
+                        }
+                    }
+                }
+                ConnectionState::EncodeTlsData(mut data) => match data.encode(outgoing.spare()) {
+                    Ok(n) => outgoing.fill(n),
+                    // Flush first; the record is still there next round
+                    Err(EncodeError::InsufficientSize(_)) => stop = true,
+                    Err(EncodeError::AlreadyEncoded) => {}
+                },
+                // Records go out from `outgoing` as the connection takes them
+                ConnectionState::TransmitTlsData(data) => data.done(),
+                ConnectionState::BlockedHandshake => stop = true,
+                ConnectionState::WriteTraffic(mut traffic) => {
+                    opened = *phase == Phase::Handshake;
+                    let room = outgoing.free().saturating_sub(RECORD_OVERHEAD);
+                    let n = plain_out.len().min(room).min(PLAIN_IN_SIZE);
+                    if n > 0 {
+                        if let Ok(written) =
+                            traffic.encrypt(&plain_out.bytes()[..n], outgoing.spare())
+                        {
+                            outgoing.fill(written);
+                            plain_out.consume(n);
+                        }
+                    }
+                    stop = true;
+                }
+                ConnectionState::PeerClosed => *phase = Phase::PeerClosed,
+                ConnectionState::Closed => {
+                    *phase = Phase::PeerClosed;
+                    stop = true;
+                }
+                _ => stop = true,
+            }
+            incoming.consume(discard);
+            if opened {
+                *phase = Phase::Open;
+                serial::print("[TLS] Handshake complete: ");
+                serial::println(&format!(
+                    "{:?} {:?}",
+                    conn.protocol_version(),
+                    conn.negotiated_cipher_suite().map(|suite| suite.suite()),
+                ));
+            }
+            if stop {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A [`NetStack`] that adds TLS to connections opened with
+/// [`tcp_connect_tls`](NetStack::tcp_connect_tls).
+pub struct TlsStack<'s> {
+    inner: &'s mut dyn NetStack,
+    /// Built on the first TLS connection
+    config: Option<Arc<ClientConfig>>,
+    session: Option<Box<Session>>,
+}
+
+impl<'s> TlsStack<'s> {
+    pub fn new(inner: &'s mut dyn NetStack) -> Self {
+        Self {
+            inner,
+            config: None,
+            session: None,
+        }
+    }
+
+    /// Use `config` for TLS connections instead of [`client_config`], to
+    /// trust a private CA, say.
+    pub fn with_config(inner: &'s mut dyn NetStack, config: Arc<ClientConfig>) -> Self {
+        Self {
+            inner,
+            config: Some(config),
+            session: None,
+        }
+    }
+
+    fn config(&mut self) -> Result<Arc<ClientConfig>, rustls::Error> {
+        if let Some(config) = &self.config {
+            return Ok(config.clone());
+        }
+        let config = Arc::new(client_config()?);
+        self.config = Some(config.clone());
+        Ok(config)
+    }
+
+    fn pump(&mut self) -> bool {
+        match self.session.as_mut() {
+            Some(session) => session.pump(&mut *self.inner),
+            None => false,
+        }
+    }
+}
+
+impl NetStack for TlsStack<'_> {
+    fn poll(&mut self, now_ms: i64) -> bool {
+        let activity = self.inner.poll(now_ms);
+        self.pump() | activity
+    }
+
+    fn poll_delay(&mut self, now_ms: i64) -> Option<u64> {
+        self.inner.poll_delay(now_ms)
+    }
+
+    fn link_up(&self) -> bool {
+        self.inner.link_up()
+    }
+
+    fn link_info(&self) -> Option<LinkInfo> {
+        self.inner.link_info()
+    }
+
+    fn autoneg_complete(&self) -> Option<bool> {
+        self.inner.autoneg_complete()
+    }
+
+    fn rx_frames(&self) -> Option<u32> {
+        self.inner.rx_frames()
+    }
+
+    fn set_tx_budget(&mut self, packets: usize) {
+        self.inner.set_tx_budget(packets)
+    }
+
+    fn rx_backlog(&self) -> Option<(usize, usize)> {
+        self.inner.rx_backlog()
+    }
+
+    fn quiesce(&mut self) {
+        self.session = None;
+        self.inner.quiesce()
+    }
+
+    fn dhcp_poll(&mut self) -> Option<DhcpEvent> {
+        self.inner.dhcp_poll()
+    }
+
+    fn dhcp_restart(&mut self) {
+        self.inner.dhcp_restart()
+    }
+
+    fn dns_query(&mut self, host: &str, server: Ipv4Addr) -> Result<(), StackError> {
+        self.inner.dns_query(host, server)
+    }
+
+    fn dns_status(&mut self) -> DnsStatus {
+        self.inner.dns_status()
+    }
+
+    fn dns_cancel(&mut self) {
+        self.inner.dns_cancel()
+    }
+
+    fn tcp_connect(
+        &mut self,
+        remote: Ipv4Addr,
+        port: u16,
+        local_port: u16,
+    ) -> Result<(), StackError> {
+        self.session = None;
+        self.inner.tcp_connect(remote, port, local_port)
+    }
+
+    fn tcp_connect_tls(
+        &mut self,
+        remote: Ipv4Addr,
+        port: u16,
+        local_port: u16,
+        server_name: &str,
+    ) -> Result<(), StackError> {
+        self.session = None;
+        // An IPv6 literal keeps its brackets in the URL
+        let name = server_name.trim_start_matches('[').trim_end_matches(']');
+        let Ok(name) = ServerName::try_from(name) else {
+            serial::print("[TLS] ERROR: Invalid server name ");
+            serial::println(server_name);
+            return Err(StackError::InvalidState);
+        };
+        let conn = self
+            .config()
+            .and_then(|config| UnbufferedClientConnection::new(config, name.to_owned()));
+        let conn = match conn {
+            Ok(conn) => conn,
+            Err(e) => {
+                serial::print("[TLS] ERROR: ");
+                serial::println(&format!("{}", e));
+                return Err(StackError::Unsupported);
+            }
+        };
+
+        let (now, source) = unix_time();
+        serial::print("[TLS] Certificate clock: ");
+        serial::print(source.name());
+        serial::print(", ");
+        serial::print(&format!("{}", now));
+        serial::println("");
+        if source == TimeSource::Floor {
+            serial::println(
+                "[TLS] WARNING: No date known, recently issued certificates may be rejected",
+            );
+        }
+
+        self.inner.tcp_connect(remote, port, local_port)?;
+        self.session = Some(Box::new(Session::new(conn)));
+        Ok(())
+    }
+
+    fn tcp_status(&self) -> TcpStatus {
+        let tcp = self.inner.tcp_status();
+        let Some(session) = &self.session else {
+            return tcp;
+        };
+        match (session.phase, tcp) {
+            (Phase::Failed, _) => TcpStatus::Closed,
+            // Connected once the handshake is done
+            (Phase::Handshake, TcpStatus::Connecting | TcpStatus::Established) => {
+                TcpStatus::Connecting
+            }
+            (Phase::Handshake, _) => TcpStatus::Closed,
+            (Phase::PeerClosed, TcpStatus::Established) => TcpStatus::Closing,
+            (_, tcp) => tcp,
+        }
+    }
+
+    fn tcp_may_send(&self) -> bool {
+        match &self.session {
+            Some(session) => {
+                session.phase == Phase::Open
+                    && session.plain_out.free() > 0
+                    && self.inner.tcp_may_send()
+            }
+            None => self.inner.tcp_may_send(),
+        }
+    }
+
+    fn tcp_may_recv(&self) -> bool {
+        match &self.session {
+            Some(session) => {
+                !session.plain_in.is_empty()
+                    || (matches!(session.phase, Phase::Handshake | Phase::Open)
+                        && (self.inner.tcp_may_recv() || !session.incoming.is_empty()))
+            }
+            None => self.inner.tcp_may_recv(),
+        }
+    }
+
+    fn tcp_send(&mut self, data: &[u8]) -> Result<usize, StackError> {
+        let Some(session) = self.session.as_mut() else {
+            return self.inner.tcp_send(data);
+        };
+        if session.phase != Phase::Open {
+            return Err(StackError::InvalidState);
+        }
+        let n = session.plain_out.push(data);
+        session.pump(&mut *self.inner);
+        Ok(n)
+    }
+
+    fn tcp_recv(&mut self, buf: &mut [u8]) -> Result<usize, StackError> {
+        let Some(session) = self.session.as_mut() else {
+            return self.inner.tcp_recv(buf);
+        };
+        session.pump(&mut *self.inner);
+        if session.plain_in.is_empty() && session.phase == Phase::Failed {
+            return Err(StackError::InvalidState);
+        }
+        let n = session.plain_in.pop(buf);
+        // Room freed: decrypt what was waiting on it
+        session.pump(&mut *self.inner);
+        Ok(n)
+    }
+
+    fn tcp_abort(&mut self) {
+        self.session = None;
+        self.inner.tcp_abort()
+    }
+
+    fn tcp_telemetry(&self) -> Option<TcpTelemetry> {
+        self.inner.tcp_telemetry()
+    }
+
+    fn udp_bind(&mut self, port: u16) -> Result<(), StackError> {
+        self.inner.udp_bind(port)
+    }
+
+    fn udp_send(&mut self, data: &[u8], remote: Ipv4Addr, port: u16) -> Result<(), StackError> {
+        self.inner.udp_send(data, remote, port)
+    }
+
+    fn udp_recv(&mut self, buf: &mut [u8]) -> Option<(usize, Ipv4Addr, u16)> {
+        self.inner.udp_recv(buf)
+    }
+
+    fn udp_close(&mut self) {
+        self.inner.udp_close()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buffer() {
+        let mut buf = Buffer::new(8);
+        assert_eq!(buf.push(b"hello"), 5);
+        assert_eq!(buf.push(b"world"), 3);
+        assert_eq!(buf.bytes(), b"hellowor");
+
+        let mut out = [0u8; 4];
+        assert_eq!(buf.pop(&mut out), 4);
+        assert_eq!(&out, b"hell");
+        assert_eq!(buf.free(), 4);
+        // Compacts to make the freed room usable
+        assert_eq!(buf.spare().len(), 4);
+        assert_eq!(buf.bytes(), b"owor");
+        buf.consume(4);
+        assert!(buf.is_empty());
+    }
+}
@@ -0,0 +1,902 @@
+//! rustls crypto provider on RustCrypto primitives.
+//!
+//! Follows the layout of rustls' own ring provider: hashes and HMACs,
+//! AEADs for each TLS version, key exchange groups and the cipher suites
+//! built from them. HKDF and the TLS 1.2 PRF come from rustls on top of
+//! the HMACs. Clients never sign, so there is no key provider to speak of.
+
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use aes_gcm::aead::generic_array::GenericArray;
+use aes_gcm::aead::{AeadInPlace, KeyInit};
+use aes_gcm::{Aes128Gcm, Aes256Gcm};
+use chacha20poly1305::ChaCha20Poly1305;
+use hmac::{Mac, SimpleHmac};
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use rustls::crypto::cipher::{
+    make_tls12_aad, make_tls13_aad, AeadKey, InboundOpaqueMessage, InboundPlainMessage, Iv,
+    KeyBlockShape, MessageDecrypter, MessageEncrypter, Nonce, OutboundOpaqueMessage,
+    OutboundPlainMessage, PrefixedPayload, Tls12AeadAlgorithm, Tls13AeadAlgorithm,
+    UnsupportedOperationError, NONCE_LEN,
+};
+use rustls::crypto::tls12::PrfUsingHmac;
+use rustls::crypto::tls13::HkdfUsingHmac;
+use rustls::crypto::{
+    hash, hmac as rhmac, ActiveKeyExchange, CipherSuiteCommon, CryptoProvider, GetRandomFailed,
+    KeyExchangeAlgorithm, KeyProvider, SecureRandom, SharedSecret, SupportedKxGroup,
+};
+use rustls::pki_types::PrivateKeyDer;
+use rustls::sign::SigningKey;
+use rustls::{
+    CipherSuite, ConnectionTrafficSecrets, ContentType, Error, NamedGroup, PeerMisbehaved,
+    ProtocolVersion, SignatureScheme, SupportedCipherSuite, Tls12CipherSuite, Tls13CipherSuite,
+};
+use sha2::{Digest, Sha256, Sha384};
+
+use super::verify;
+
+/// The provider: AES-GCM and ChaCha20-Poly1305 suites for TLS 1.3 and
+/// ECDHE TLS 1.2, X25519 and P-256 key exchange.
+pub fn provider() -> CryptoProvider {
+    CryptoProvider {
+        cipher_suites: vec![
+            TLS13_AES_128_GCM_SHA256,
+            TLS13_AES_256_GCM_SHA384,
+            TLS13_CHACHA20_POLY1305_SHA256,
+            TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256,
+            TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384,
+            TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256,
+            TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256,
+            TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384,
+            TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256,
+        ],
+        kx_groups: vec![&X25519, &SECP256R1],
+        signature_verification_algorithms: verify::SUPPORTED_SIG_ALGS,
+        secure_random: &Rdrand,
+        key_provider: &NoKeys,
+    }
+}
+
+// --- Randomness ---
+
+/// Random bytes from the CPU's RDRAND instruction.
+#[derive(Debug)]
+pub struct Rdrand;
+
+impl SecureRandom for Rdrand {
+    fn fill(&self, buf: &mut [u8]) -> Result<(), GetRandomFailed> {
+        fill_random(buf).ok_or(GetRandomFailed)
+    }
+}
+
+/// Fill `buf` from RDRAND; None if the CPU has none or it keeps failing.
+#[cfg(target_arch = "x86_64")]
+fn fill_random(buf: &mut [u8]) -> Option<()> {
+    use core::arch::x86_64::{__cpuid, _rdrand64_step};
+
+    /// Retries per word before giving up, as Intel recommends
+    const RDRAND_RETRIES: usize = 10;
+
+    #[target_feature(enable = "rdrand")]
+    unsafe fn rdrand64() -> Option<u64> {
+        let mut value = 0u64;
+        for _ in 0..RDRAND_RETRIES {
+            if _rdrand64_step(&mut value) == 1 {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    // CPUID.01H:ECX.RDRAND[bit 30]
+    if __cpuid(1).ecx & (1 << 30) == 0 {
+        return None;
+    }
+    for chunk in buf.chunks_mut(8) {
+        // Safety: CPUID says RDRAND is there
+        let word = unsafe { rdrand64() }?;
+        chunk.copy_from_slice(&word.to_le_bytes()[..chunk.len()]);
+    }
+    Some(())
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn fill_random(_buf: &mut [u8]) -> Option<()> {
+    None
+}
+
+/// Key provider for a client that never authenticates itself.
+#[derive(Debug)]
+struct NoKeys;
+
+impl KeyProvider for NoKeys {
+    fn load_private_key(
+        &self,
+        _key_der: PrivateKeyDer<'static>,
+    ) -> Result<Arc<dyn SigningKey>, Error> {
+        Err(Error::General(
+            "client certificates are not supported".into(),
+        ))
+    }
+}
+
+// --- Hashes and HMACs ---
+
+struct HashAlg<D>(PhantomData<fn() -> D>, hash::HashAlgorithm);
+
+static SHA256: HashAlg<Sha256> = HashAlg(PhantomData, hash::HashAlgorithm::SHA256);
+static SHA384: HashAlg<Sha384> = HashAlg(PhantomData, hash::HashAlgorithm::SHA384);
+
+impl<D: Digest + Clone + Send + Sync + 'static> hash::Hash for HashAlg<D> {
+    fn start(&self) -> Box<dyn hash::Context> {
+        Box::new(HashContext(D::new()))
+    }
+
+    fn hash(&self, data: &[u8]) -> hash::Output {
+        hash::Output::new(&D::digest(data))
+    }
+
+    fn output_len(&self) -> usize {
+        <D as Digest>::output_size()
+    }
+
+    fn algorithm(&self) -> hash::HashAlgorithm {
+        self.1
+    }
+}
+
+struct HashContext<D>(D);
+
+impl<D: Digest + Clone + Send + Sync + 'static> hash::Context for HashContext<D> {
+    fn fork_finish(&self) -> hash::Output {
+        hash::Output::new(&self.0.clone().finalize())
+    }
+
+    fn fork(&self) -> Box<dyn hash::Context> {
+        Box::new(HashContext(self.0.clone()))
+    }
+
+    fn finish(self: Box<Self>) -> hash::Output {
+        hash::Output::new(&self.0.finalize())
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+}
+
+struct HmacAlg<D>(PhantomData<fn() -> D>);
+
+static HMAC_SHA256: HmacAlg<Sha256> = HmacAlg(PhantomData);
+static HMAC_SHA384: HmacAlg<Sha384> = HmacAlg(PhantomData);
+
+impl<D> rhmac::Hmac for HmacAlg<D>
+where
+    D: Digest + sha2::digest::core_api::BlockSizeUser + Clone + Send + Sync + 'static,
+{
+    fn with_key(&self, key: &[u8]) -> Box<dyn rhmac::Key> {
+        // SimpleHmac takes keys of any length
+        Box::new(HmacKey(
+            <SimpleHmac<D> as KeyInit>::new_from_slice(key).unwrap(),
+        ))
+    }
+
+    fn hash_output_len(&self) -> usize {
+        <D as Digest>::output_size()
+    }
+}
+
+struct HmacKey<D: Digest + sha2::digest::core_api::BlockSizeUser>(SimpleHmac<D>);
+
+impl<D> rhmac::Key for HmacKey<D>
+where
+    D: Digest + sha2::digest::core_api::BlockSizeUser + Clone + Send + Sync + 'static,
+{
+    fn sign_concat(&self, first: &[u8], middle: &[&[u8]], last: &[u8]) -> rhmac::Tag {
+        let mut mac = self.0.clone();
+        mac.update(first);
+        for part in middle {
+            mac.update(part);
+        }
+        mac.update(last);
+        rhmac::Tag::new(&mac.finalize().into_bytes())
+    }
+
+    fn tag_len(&self) -> usize {
+        <D as Digest>::output_size()
+    }
+}
+
+// --- AEADs ---
+
+/// Bytes of an AEAD tag (all three ciphers)
+const TAG_LEN: usize = 16;
+
+/// Explicit nonce carried in each TLS 1.2 AES-GCM record
+const GCM_EXPLICIT_NONCE_LEN: usize = 8;
+
+/// Largest plaintext in a record
+const MAX_FRAGMENT_LEN: usize = 16384;
+
+/// An AEAD cipher with 12-byte nonces and 16-byte tags.
+trait Cipher: AeadInPlace + KeyInit + Send + Sync + 'static {
+    fn traffic_secrets(key: AeadKey, iv: Iv) -> ConnectionTrafficSecrets;
+}
+
+impl Cipher for Aes128Gcm {
+    fn traffic_secrets(key: AeadKey, iv: Iv) -> ConnectionTrafficSecrets {
+        ConnectionTrafficSecrets::Aes128Gcm { key, iv }
+    }
+}
+
+impl Cipher for Aes256Gcm {
+    fn traffic_secrets(key: AeadKey, iv: Iv) -> ConnectionTrafficSecrets {
+        ConnectionTrafficSecrets::Aes256Gcm { key, iv }
+    }
+}
+
+impl Cipher for ChaCha20Poly1305 {
+    fn traffic_secrets(key: AeadKey, iv: Iv) -> ConnectionTrafficSecrets {
+        ConnectionTrafficSecrets::Chacha20Poly1305 { key, iv }
+    }
+}
+
+fn cipher<C: Cipher>(key: &AeadKey) -> C {
+    // rustls derives keys of exactly `key_len` bytes
+    C::new_from_slice(key.as_ref()).unwrap()
+}
+
+fn key_len<C: Cipher>() -> usize {
+    <C as aes_gcm::KeySizeUser>::key_size()
+}
+
+struct Aead<C>(PhantomData<fn() -> C>);
+
+static AES128_GCM: Aead<Aes128Gcm> = Aead(PhantomData);
+static AES256_GCM: Aead<Aes256Gcm> = Aead(PhantomData);
+static CHACHA20_POLY1305: Aead<ChaCha20Poly1305> = Aead(PhantomData);
+
+impl<C: Cipher> Tls13AeadAlgorithm for Aead<C> {
+    fn encrypter(&self, key: AeadKey, iv: Iv) -> Box<dyn MessageEncrypter> {
+        Box::new(Tls13Encrypter {
+            cipher: cipher::<C>(&key),
+            iv,
+        })
+    }
+
+    fn decrypter(&self, key: AeadKey, iv: Iv) -> Box<dyn MessageDecrypter> {
+        Box::new(Tls13Decrypter {
+            cipher: cipher::<C>(&key),
+            iv,
+        })
+    }
+
+    fn key_len(&self) -> usize {
+        key_len::<C>()
+    }
+
+    fn extract_keys(
+        &self,
+        key: AeadKey,
+        iv: Iv,
+    ) -> Result<ConnectionTrafficSecrets, UnsupportedOperationError> {
+        Ok(C::traffic_secrets(key, iv))
+    }
+}
+
+struct Tls13Encrypter<C> {
+    cipher: C,
+    iv: Iv,
+}
+
+impl<C: Cipher> MessageEncrypter for Tls13Encrypter<C> {
+    fn encrypt(
+        &mut self,
+        msg: OutboundPlainMessage<'_>,
+        seq: u64,
+    ) -> Result<OutboundOpaqueMessage, Error> {
+        let total_len = self.encrypted_payload_len(msg.payload.len());
+        let mut payload = PrefixedPayload::with_capacity(total_len);
+        payload.extend_from_chunks(&msg.payload);
+        payload.extend_from_slice(&[u8::from(msg.typ)]);
+
+        let nonce = Nonce::new(&self.iv, seq).0;
+        let tag = self
+            .cipher
+            .encrypt_in_place_detached(
+                GenericArray::from_slice(&nonce),
+                &make_tls13_aad(total_len),
+                payload.as_mut(),
+            )
+            .map_err(|_| Error::EncryptError)?;
+        payload.extend_from_slice(&tag);
+
+        Ok(OutboundOpaqueMessage::new(
+            ContentType::ApplicationData,
+            ProtocolVersion::TLSv1_2,
+            payload,
+        ))
+    }
+
+    fn encrypted_payload_len(&self, payload_len: usize) -> usize {
+        payload_len + 1 + TAG_LEN
+    }
+}
+
+struct Tls13Decrypter<C> {
+    cipher: C,
+    iv: Iv,
+}
+
+impl<C: Cipher> MessageDecrypter for Tls13Decrypter<C> {
+    fn decrypt<'a>(
+        &mut self,
+        mut msg: InboundOpaqueMessage<'a>,
+        seq: u64,
+    ) -> Result<InboundPlainMessage<'a>, Error> {
+        let payload = &mut msg.payload;
+        let Some(plain_len) = payload.len().checked_sub(TAG_LEN) else {
+            return Err(Error::DecryptError);
+        };
+        let nonce = Nonce::new(&self.iv, seq).0;
+        let aad = make_tls13_aad(payload.len());
+        let (data, tag) = payload.split_at_mut(plain_len);
+        self.cipher
+            .decrypt_in_place_detached(
+                GenericArray::from_slice(&nonce),
+                &aad,
+                data,
+                GenericArray::from_slice(tag),
+            )
+            .map_err(|_| Error::DecryptError)?;
+
+        payload.truncate(plain_len);
+        msg.into_tls13_unpadded_message()
+    }
+}
+
+/// TLS 1.2 AES-GCM: 4 bytes of implicit salt, 8 of explicit nonce sent
+/// with each record.
+struct Tls12Gcm<C>(PhantomData<fn() -> C>);
+
+static TLS12_AES128_GCM: Tls12Gcm<Aes128Gcm> = Tls12Gcm(PhantomData);
+static TLS12_AES256_GCM: Tls12Gcm<Aes256Gcm> = Tls12Gcm(PhantomData);
+
+fn gcm_iv(write_iv: &[u8], explicit: &[u8]) -> Iv {
+    let mut iv = [0u8; NONCE_LEN];
+    iv[..4].copy_from_slice(write_iv);
+    iv[4..].copy_from_slice(explicit);
+    Iv::new(iv)
+}
+
+impl<C: Cipher> Tls12AeadAlgorithm for Tls12Gcm<C> {
+    fn encrypter(&self, key: AeadKey, iv: &[u8], extra: &[u8]) -> Box<dyn MessageEncrypter> {
+        Box::new(Tls12GcmEncrypter {
+            cipher: cipher::<C>(&key),
+            iv: gcm_iv(iv, extra),
+        })
+    }
+
+    fn decrypter(&self, key: AeadKey, iv: &[u8]) -> Box<dyn MessageDecrypter> {
+        let mut salt = [0u8; 4];
+        salt.copy_from_slice(iv);
+        Box::new(Tls12GcmDecrypter {
+            cipher: cipher::<C>(&key),
+            salt,
+        })
+    }
+
+    fn key_block_shape(&self) -> KeyBlockShape {
+        KeyBlockShape {
+            enc_key_len: key_len::<C>(),
+            fixed_iv_len: 4,
+            explicit_nonce_len: GCM_EXPLICIT_NONCE_LEN,
+        }
+    }
+
+    fn extract_keys(
+        &self,
+        key: AeadKey,
+        iv: &[u8],
+        explicit: &[u8],
+    ) -> Result<ConnectionTrafficSecrets, UnsupportedOperationError> {
+        Ok(C::traffic_secrets(key, gcm_iv(iv, explicit)))
+    }
+}
+
+struct Tls12GcmEncrypter<C> {
+    cipher: C,
+    iv: Iv,
+}
+
+impl<C: Cipher> MessageEncrypter for Tls12GcmEncrypter<C> {
+    fn encrypt(
+        &mut self,
+        msg: OutboundPlainMessage<'_>,
+        seq: u64,
+    ) -> Result<OutboundOpaqueMessage, Error> {
+        let total_len = self.encrypted_payload_len(msg.payload.len());
+        let mut payload = PrefixedPayload::with_capacity(total_len);
+        let nonce = Nonce::new(&self.iv, seq).0;
+        let aad = make_tls12_aad(seq, msg.typ, msg.version, msg.payload.len());
+        payload.extend_from_slice(&nonce[4..]);
+        payload.extend_from_chunks(&msg.payload);
+
+        let tag = self
+            .cipher
+            .encrypt_in_place_detached(
+                GenericArray::from_slice(&nonce),
+                &aad,
+                &mut payload.as_mut()[GCM_EXPLICIT_NONCE_LEN..],
+            )
+            .map_err(|_| Error::EncryptError)?;
+        payload.extend_from_slice(&tag);
+
+        Ok(OutboundOpaqueMessage::new(msg.typ, msg.version, payload))
+    }
+
+    fn encrypted_payload_len(&self, payload_len: usize) -> usize {
+        payload_len + GCM_EXPLICIT_NONCE_LEN + TAG_LEN
+    }
+}
+
+struct Tls12GcmDecrypter<C> {
+    cipher: C,
+    salt: [u8; 4],
+}
+
+impl<C: Cipher> MessageDecrypter for Tls12GcmDecrypter<C> {
+    fn decrypt<'a>(
+        &mut self,
+        mut msg: InboundOpaqueMessage<'a>,
+        seq: u64,
+    ) -> Result<InboundPlainMessage<'a>, Error> {
+        let payload = &mut msg.payload;
+        let Some(plain_len) = payload.len().checked_sub(GCM_EXPLICIT_NONCE_LEN + TAG_LEN) else {
+            return Err(Error::DecryptError);
+        };
+        let mut nonce = [0u8; NONCE_LEN];
+        nonce[..4].copy_from_slice(&self.salt);
+        nonce[4..].copy_from_slice(&payload[..GCM_EXPLICIT_NONCE_LEN]);
+        let aad = make_tls12_aad(seq, msg.typ, msg.version, plain_len);
+
+        let (data, tag) = payload[GCM_EXPLICIT_NONCE_LEN..].split_at_mut(plain_len);
+        self.cipher
+            .decrypt_in_place_detached(
+                GenericArray::from_slice(&nonce),
+                &aad,
+                data,
+                GenericArray::from_slice(tag),
+            )
+            .map_err(|_| Error::DecryptError)?;
+        if plain_len > MAX_FRAGMENT_LEN {
+            return Err(Error::PeerSentOversizedRecord);
+        }
+
+        Ok(
+            msg.into_plain_message_range(
+                GCM_EXPLICIT_NONCE_LEN..GCM_EXPLICIT_NONCE_LEN + plain_len,
+            ),
+        )
+    }
+}
+
+/// TLS 1.2 ChaCha20-Poly1305 (RFC 7905): the whole nonce is implicit.
+struct Tls12Chacha;
+
+impl Tls12AeadAlgorithm for Tls12Chacha {
+    fn encrypter(&self, key: AeadKey, iv: &[u8], _extra: &[u8]) -> Box<dyn MessageEncrypter> {
+        Box::new(Tls12ChachaCrypter {
+            cipher: cipher::<ChaCha20Poly1305>(&key),
+            iv: Iv::copy(iv),
+        })
+    }
+
+    fn decrypter(&self, key: AeadKey, iv: &[u8]) -> Box<dyn MessageDecrypter> {
+        Box::new(Tls12ChachaCrypter {
+            cipher: cipher::<ChaCha20Poly1305>(&key),
+            iv: Iv::copy(iv),
+        })
+    }
+
+    fn key_block_shape(&self) -> KeyBlockShape {
+        KeyBlockShape {
+            enc_key_len: 32,
+            fixed_iv_len: NONCE_LEN,
+            explicit_nonce_len: 0,
+        }
+    }
+
+    fn extract_keys(
+        &self,
+        key: AeadKey,
+        iv: &[u8],
+        _explicit: &[u8],
+    ) -> Result<ConnectionTrafficSecrets, UnsupportedOperationError> {
+        Ok(ChaCha20Poly1305::traffic_secrets(key, Iv::copy(iv)))
+    }
+}
+
+struct Tls12ChachaCrypter {
+    cipher: ChaCha20Poly1305,
+    iv: Iv,
+}
+
+impl MessageEncrypter for Tls12ChachaCrypter {
+    fn encrypt(
+        &mut self,
+        msg: OutboundPlainMessage<'_>,
+        seq: u64,
+    ) -> Result<OutboundOpaqueMessage, Error> {
+        let total_len = self.encrypted_payload_len(msg.payload.len());
+        let mut payload = PrefixedPayload::with_capacity(total_len);
+        let nonce = Nonce::new(&self.iv, seq).0;
+        let aad = make_tls12_aad(seq, msg.typ, msg.version, msg.payload.len());
+        payload.extend_from_chunks(&msg.payload);
+
+        let tag = self
+            .cipher
+            .encrypt_in_place_detached(GenericArray::from_slice(&nonce), &aad, payload.as_mut())
+            .map_err(|_| Error::EncryptError)?;
+        payload.extend_from_slice(&tag);
+
+        Ok(OutboundOpaqueMessage::new(msg.typ, msg.version, payload))
+    }
+
+    fn encrypted_payload_len(&self, payload_len: usize) -> usize {
+        payload_len + TAG_LEN
+    }
+}
+
+impl MessageDecrypter for Tls12ChachaCrypter {
+    fn decrypt<'a>(
+        &mut self,
+        mut msg: InboundOpaqueMessage<'a>,
+        seq: u64,
+    ) -> Result<InboundPlainMessage<'a>, Error> {
+        let payload = &mut msg.payload;
+        let Some(plain_len) = payload.len().checked_sub(TAG_LEN) else {
+            return Err(Error::DecryptError);
+        };
+        let nonce = Nonce::new(&self.iv, seq).0;
+        let aad = make_tls12_aad(seq, msg.typ, msg.version, plain_len);
+
+        let (data, tag) = payload.split_at_mut(plain_len);
+        self.cipher
+            .decrypt_in_place_detached(
+                GenericArray::from_slice(&nonce),
+                &aad,
+                data,
+                GenericArray::from_slice(tag),
+            )
+            .map_err(|_| Error::DecryptError)?;
+        if plain_len > MAX_FRAGMENT_LEN {
+            return Err(Error::PeerSentOversizedRecord);
+        }
+
+        payload.truncate(plain_len);
+        Ok(msg.into_plain_message())
+    }
+}
+
+// --- Key exchange ---
+
+#[derive(Debug)]
+struct X25519Group;
+
+static X25519: X25519Group = X25519Group;
+
+impl SupportedKxGroup for X25519Group {
+    fn start(&self) -> Result<Box<dyn ActiveKeyExchange>, Error> {
+        let mut secret = [0u8; 32];
+        Rdrand.fill(&mut secret)?;
+        let pub_key = x25519_dalek::x25519(secret, x25519_dalek::X25519_BASEPOINT_BYTES);
+        Ok(Box::new(X25519Exchange { secret, pub_key }))
+    }
+
+    fn name(&self) -> NamedGroup {
+        NamedGroup::X25519
+    }
+}
+
+struct X25519Exchange {
+    secret: [u8; 32],
+    pub_key: [u8; 32],
+}
+
+impl ActiveKeyExchange for X25519Exchange {
+    fn complete(self: Box<Self>, peer_pub_key: &[u8]) -> Result<SharedSecret, Error> {
+        let peer: [u8; 32] = peer_pub_key
+            .try_into()
+            .map_err(|_| PeerMisbehaved::InvalidKeyShare)?;
+        let shared = x25519_dalek::x25519(self.secret, peer);
+        // A low-order point gives all zeros (RFC 7748 section 6.1)
+        if shared.iter().all(|&b| b == 0) {
+            return Err(PeerMisbehaved::InvalidKeyShare.into());
+        }
+        Ok(SharedSecret::from(&shared[..]))
+    }
+
+    fn pub_key(&self) -> &[u8] {
+        &self.pub_key
+    }
+
+    fn group(&self) -> NamedGroup {
+        NamedGroup::X25519
+    }
+}
+
+#[derive(Debug)]
+struct Secp256r1Group;
+
+static SECP256R1: Secp256r1Group = Secp256r1Group;
+
+impl SupportedKxGroup for Secp256r1Group {
+    fn start(&self) -> Result<Box<dyn ActiveKeyExchange>, Error> {
+        // Almost every 32-byte string is a valid scalar
+        let secret = loop {
+            let mut bytes = [0u8; 32];
+            Rdrand.fill(&mut bytes)?;
+            if let Ok(secret) = p256::SecretKey::from_slice(&bytes) {
+                break secret;
+            }
+        };
+        let pub_key = secret
+            .public_key()
+            .to_encoded_point(false)
+            .as_bytes()
+            .to_vec();
+        Ok(Box::new(Secp256r1Exchange { secret, pub_key }))
+    }
+
+    fn name(&self) -> NamedGroup {
+        NamedGroup::secp256r1
+    }
+}
+
+struct Secp256r1Exchange {
+    secret: p256::SecretKey,
+    pub_key: Vec<u8>,
+}
+
+impl ActiveKeyExchange for Secp256r1Exchange {
+    fn complete(self: Box<Self>, peer_pub_key: &[u8]) -> Result<SharedSecret, Error> {
+        let peer = p256::PublicKey::from_sec1_bytes(peer_pub_key)
+            .map_err(|_| PeerMisbehaved::InvalidKeyShare)?;
+        let shared = p256::ecdh::diffie_hellman(self.secret.to_nonzero_scalar(), peer.as_affine());
+        Ok(SharedSecret::from(&shared.raw_secret_bytes()[..]))
+    }
+
+    fn pub_key(&self) -> &[u8] {
+        &self.pub_key
+    }
+
+    fn group(&self) -> NamedGroup {
+        NamedGroup::secp256r1
+    }
+}
+
+// --- Cipher suites ---
+
+static TLS13_AES_128_GCM_SHA256: SupportedCipherSuite =
+    SupportedCipherSuite::Tls13(&Tls13CipherSuite {
+        common: CipherSuiteCommon {
+            suite: CipherSuite::TLS13_AES_128_GCM_SHA256,
+            hash_provider: &SHA256,
+            confidentiality_limit: 1 << 24,
+        },
+        hkdf_provider: &HkdfUsingHmac(&HMAC_SHA256),
+        aead_alg: &AES128_GCM,
+        quic: None,
+    });
+
+static TLS13_AES_256_GCM_SHA384: SupportedCipherSuite =
+    SupportedCipherSuite::Tls13(&Tls13CipherSuite {
+        common: CipherSuiteCommon {
+            suite: CipherSuite::TLS13_AES_256_GCM_SHA384,
+            hash_provider: &SHA384,
+            confidentiality_limit: 1 << 24,
+        },
+        hkdf_provider: &HkdfUsingHmac(&HMAC_SHA384),
+        aead_alg: &AES256_GCM,
+        quic: None,
+    });
+
+static TLS13_CHACHA20_POLY1305_SHA256: SupportedCipherSuite =
+    SupportedCipherSuite::Tls13(&Tls13CipherSuite {
+        common: CipherSuiteCommon {
+            suite: CipherSuite::TLS13_CHACHA20_POLY1305_SHA256,
+            hash_provider: &SHA256,
+            confidentiality_limit: u64::MAX,
+        },
+        hkdf_provider: &HkdfUsingHmac(&HMAC_SHA256),
+        aead_alg: &CHACHA20_POLY1305,
+        quic: None,
+    });
+
+static TLS12_ECDSA_SCHEMES: &[SignatureScheme] = &[
+    SignatureScheme::ECDSA_NISTP384_SHA384,
+    SignatureScheme::ECDSA_NISTP256_SHA256,
+];
+
+static TLS12_RSA_SCHEMES: &[SignatureScheme] = &[
+    SignatureScheme::RSA_PSS_SHA512,
+    SignatureScheme::RSA_PSS_SHA384,
+    SignatureScheme::RSA_PSS_SHA256,
+    SignatureScheme::RSA_PKCS1_SHA512,
+    SignatureScheme::RSA_PKCS1_SHA384,
+    SignatureScheme::RSA_PKCS1_SHA256,
+];
+
+/// An ECDHE TLS 1.2 suite.
+macro_rules! tls12_suite {
+    ($suite:ident, $sign:expr, $aead:expr, $hash:expr, $hmac:expr, $limit:expr) => {
+        SupportedCipherSuite::Tls12(&Tls12CipherSuite {
+            common: CipherSuiteCommon {
+                suite: CipherSuite::$suite,
+                hash_provider: $hash,
+                confidentiality_limit: $limit,
+            },
+            kx: KeyExchangeAlgorithm::ECDHE,
+            sign: $sign,
+            aead_alg: $aead,
+            prf_provider: &PrfUsingHmac($hmac),
+        })
+    };
+}
+
+static TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256: SupportedCipherSuite = tls12_suite!(
+    TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256,
+    TLS12_ECDSA_SCHEMES,
+    &TLS12_AES128_GCM,
+    &SHA256,
+    &HMAC_SHA256,
+    1 << 24
+);
+
+static TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384: SupportedCipherSuite = tls12_suite!(
+    TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384,
+    TLS12_ECDSA_SCHEMES,
+    &TLS12_AES256_GCM,
+    &SHA384,
+    &HMAC_SHA384,
+    1 << 24
+);
+
+static TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256: SupportedCipherSuite = tls12_suite!(
+    TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256,
+    TLS12_ECDSA_SCHEMES,
+    &Tls12Chacha,
+    &SHA256,
+    &HMAC_SHA256,
+    u64::MAX
+);
+
+static TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256: SupportedCipherSuite = tls12_suite!(
+    TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256,
+    TLS12_RSA_SCHEMES,
+    &TLS12_AES128_GCM,
+    &SHA256,
+    &HMAC_SHA256,
+    1 << 24
+);
+
+static TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384: SupportedCipherSuite = tls12_suite!(
+    TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384,
+    TLS12_RSA_SCHEMES,
+    &TLS12_AES256_GCM,
+    &SHA384,
+    &HMAC_SHA384,
+    1 << 24
+);
+
+static TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256: SupportedCipherSuite = tls12_suite!(
+    TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256,
+    TLS12_RSA_SCHEMES,
+    &Tls12Chacha,
+    &SHA256,
+    &HMAC_SHA256,
+    u64::MAX
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rhmac::Hmac as _;
+    use rustls::crypto::cipher::OutboundChunks;
+
+    const REQUEST: &[u8] = b"GET /alpine.iso HTTP/1.1\r\n";
+
+    fn seal(enc: &mut dyn MessageEncrypter, seq: u64) -> OutboundOpaqueMessage {
+        let msg = OutboundPlainMessage {
+            typ: ContentType::ApplicationData,
+            version: ProtocolVersion::TLSv1_2,
+            payload: OutboundChunks::from(REQUEST),
+        };
+        let sealed = enc.encrypt(msg, seq).unwrap();
+        assert_eq!(
+            sealed.payload.as_ref().len(),
+            enc.encrypted_payload_len(REQUEST.len())
+        );
+        sealed
+    }
+
+    /// Encrypt a record, decrypt it, then check a wrong sequence number
+    /// or a flipped bit is rejected.
+    fn round_trip(mut enc: Box<dyn MessageEncrypter>, mut dec: Box<dyn MessageDecrypter>) {
+        let sealed = seal(&mut *enc, 7);
+        let mut payload = sealed.payload.as_ref().to_vec();
+        let msg = InboundOpaqueMessage::new(sealed.typ, sealed.version, &mut payload);
+        let plain = dec.decrypt(msg, 7).unwrap();
+        assert_eq!(plain.typ, ContentType::ApplicationData);
+        assert_eq!(plain.payload, REQUEST);
+
+        let sealed = seal(&mut *enc, 8);
+        let mut payload = sealed.payload.as_ref().to_vec();
+        let msg = InboundOpaqueMessage::new(sealed.typ, sealed.version, &mut payload);
+        assert!(dec.decrypt(msg, 9).is_err());
+
+        let mut payload = sealed.payload.as_ref().to_vec();
+        let last = payload.len() - 1;
+        payload[last] ^= 1;
+        let msg = InboundOpaqueMessage::new(sealed.typ, sealed.version, &mut payload);
+        assert!(dec.decrypt(msg, 8).is_err());
+    }
+
+    #[test]
+    fn test_tls13_aeads() {
+        let key = || AeadKey::from([0x42; 32]);
+        let iv = || Iv::new([7; NONCE_LEN]);
+        round_trip(
+            AES256_GCM.encrypter(key(), iv()),
+            AES256_GCM.decrypter(key(), iv()),
+        );
+        round_trip(
+            CHACHA20_POLY1305.encrypter(key(), iv()),
+            CHACHA20_POLY1305.decrypter(key(), iv()),
+        );
+    }
+
+    #[test]
+    fn test_tls12_aeads() {
+        let key = || AeadKey::from([0x42; 32]);
+        round_trip(
+            TLS12_AES256_GCM.encrypter(key(), &[1, 2, 3, 4], &[5; 8]),
+            TLS12_AES256_GCM.decrypter(key(), &[1, 2, 3, 4]),
+        );
+        round_trip(
+            Tls12Chacha.encrypter(key(), &[9; NONCE_LEN], &[]),
+            Tls12Chacha.decrypter(key(), &[9; NONCE_LEN]),
+        );
+    }
+
+    #[test]
+    fn test_key_exchange() {
+        let groups: [&dyn SupportedKxGroup; 2] = [&X25519, &SECP256R1];
+        for group in groups {
+            let ours = group.start().unwrap();
+            let theirs = group.start().unwrap();
+            let (our_pub, their_pub) = (ours.pub_key().to_vec(), theirs.pub_key().to_vec());
+            let a = ours.complete(&their_pub).unwrap();
+            let b = theirs.complete(&our_pub).unwrap();
+            assert_eq!(a.secret_bytes(), b.secret_bytes());
+            assert!(group.start().unwrap().complete(&[0; 3]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_hmac() {
+        // RFC 4231 test case 2
+        let key = HMAC_SHA256.with_key(b"Jefe");
+        let tag = key.sign(&[b"what do ya want ", b"for nothing?"]);
+        assert_eq!(
+            tag.as_ref()[..8],
+            [0x5b, 0xdc, 0xc1, 0x46, 0xbf, 0x60, 0x75, 0x4e]
+        );
+    }
+}
@@ -0,0 +1,205 @@
+//! Signature verification for certificate chains and handshakes.
+//!
+//! ECDSA on P-256 and P-384, RSA PKCS#1 v1.5 and RSA-PSS with SHA-2:
+//! what the roots in `webpki-roots` and the certificates they issue use.
+
+use p256::ecdsa::signature::hazmat::PrehashVerifier;
+use rsa::pkcs1::DecodeRsaPublicKey;
+use rsa::signature::Verifier;
+use rsa::RsaPublicKey;
+use rustls::crypto::WebPkiSupportedAlgorithms;
+use rustls::pki_types::{
+    alg_id, AlgorithmIdentifier, InvalidSignature, SignatureVerificationAlgorithm,
+};
+use rustls::SignatureScheme;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+/// Everything the provider verifies, and which algorithms each TLS
+/// signature scheme maps to.
+pub static SUPPORTED_SIG_ALGS: WebPkiSupportedAlgorithms = WebPkiSupportedAlgorithms {
+    all: &[
+        ECDSA_P256_SHA256,
+        ECDSA_P256_SHA384,
+        ECDSA_P384_SHA256,
+        ECDSA_P384_SHA384,
+        RSA_PKCS1_SHA256,
+        RSA_PKCS1_SHA384,
+        RSA_PKCS1_SHA512,
+        RSA_PSS_SHA256,
+        RSA_PSS_SHA384,
+        RSA_PSS_SHA512,
+    ],
+    mapping: &[
+        (
+            SignatureScheme::ECDSA_NISTP384_SHA384,
+            &[ECDSA_P384_SHA384, ECDSA_P256_SHA384],
+        ),
+        (
+            SignatureScheme::ECDSA_NISTP256_SHA256,
+            &[ECDSA_P256_SHA256, ECDSA_P384_SHA256],
+        ),
+        (SignatureScheme::RSA_PSS_SHA512, &[RSA_PSS_SHA512]),
+        (SignatureScheme::RSA_PSS_SHA384, &[RSA_PSS_SHA384]),
+        (SignatureScheme::RSA_PSS_SHA256, &[RSA_PSS_SHA256]),
+        (SignatureScheme::RSA_PKCS1_SHA512, &[RSA_PKCS1_SHA512]),
+        (SignatureScheme::RSA_PKCS1_SHA384, &[RSA_PKCS1_SHA384]),
+        (SignatureScheme::RSA_PKCS1_SHA256, &[RSA_PKCS1_SHA256]),
+    ],
+};
+
+/// Smallest RSA modulus accepted, in bits (as webpki's ring backend)
+const RSA_MIN_BITS: usize = 2048;
+/// Largest RSA modulus accepted, in bits
+const RSA_MAX_BITS: usize = 8192;
+
+#[derive(Debug, Clone, Copy)]
+enum Curve {
+    P256,
+    P384,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum HashAlg {
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum RsaPadding {
+    Pkcs1,
+    Pss,
+}
+
+/// ECDSA with a curve and a hash, which need not match.
+#[derive(Debug)]
+struct Ecdsa(Curve, HashAlg);
+
+/// RSA with a padding and a hash.
+#[derive(Debug)]
+struct Rsa(RsaPadding, HashAlg);
+
+static ECDSA_P256_SHA256: &dyn SignatureVerificationAlgorithm =
+    &Ecdsa(Curve::P256, HashAlg::Sha256);
+static ECDSA_P256_SHA384: &dyn SignatureVerificationAlgorithm =
+    &Ecdsa(Curve::P256, HashAlg::Sha384);
+static ECDSA_P384_SHA256: &dyn SignatureVerificationAlgorithm =
+    &Ecdsa(Curve::P384, HashAlg::Sha256);
+static ECDSA_P384_SHA384: &dyn SignatureVerificationAlgorithm =
+    &Ecdsa(Curve::P384, HashAlg::Sha384);
+static RSA_PKCS1_SHA256: &dyn SignatureVerificationAlgorithm =
+    &Rsa(RsaPadding::Pkcs1, HashAlg::Sha256);
+static RSA_PKCS1_SHA384: &dyn SignatureVerificationAlgorithm =
+    &Rsa(RsaPadding::Pkcs1, HashAlg::Sha384);
+static RSA_PKCS1_SHA512: &dyn SignatureVerificationAlgorithm =
+    &Rsa(RsaPadding::Pkcs1, HashAlg::Sha512);
+static RSA_PSS_SHA256: &dyn SignatureVerificationAlgorithm = &Rsa(RsaPadding::Pss, HashAlg::Sha256);
+static RSA_PSS_SHA384: &dyn SignatureVerificationAlgorithm = &Rsa(RsaPadding::Pss, HashAlg::Sha384);
+static RSA_PSS_SHA512: &dyn SignatureVerificationAlgorithm = &Rsa(RsaPadding::Pss, HashAlg::Sha512);
+
+impl SignatureVerificationAlgorithm for Ecdsa {
+    fn verify_signature(
+        &self,
+        public_key: &[u8],
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), InvalidSignature> {
+        // Hashes up to 64 bytes; the verifier truncates to the curve size
+        let mut digest = [0u8; 64];
+        let digest = match self.1 {
+            HashAlg::Sha256 => copy_digest(&mut digest, &Sha256::digest(message)),
+            HashAlg::Sha384 => copy_digest(&mut digest, &Sha384::digest(message)),
+            HashAlg::Sha512 => copy_digest(&mut digest, &Sha512::digest(message)),
+        };
+        let ok = match self.0 {
+            Curve::P256 => {
+                let key = p256::ecdsa::VerifyingKey::from_sec1_bytes(public_key);
+                let sig = p256::ecdsa::Signature::from_der(signature);
+                matches!((key, sig), (Ok(key), Ok(sig)) if key.verify_prehash(digest, &sig).is_ok())
+            }
+            Curve::P384 => {
+                let key = p384::ecdsa::VerifyingKey::from_sec1_bytes(public_key);
+                let sig = p384::ecdsa::Signature::from_der(signature);
+                matches!((key, sig), (Ok(key), Ok(sig)) if key.verify_prehash(digest, &sig).is_ok())
+            }
+        };
+        ok.then_some(()).ok_or(InvalidSignature)
+    }
+
+    fn public_key_alg_id(&self) -> AlgorithmIdentifier {
+        match self.0 {
+            Curve::P256 => alg_id::ECDSA_P256,
+            Curve::P384 => alg_id::ECDSA_P384,
+        }
+    }
+
+    fn signature_alg_id(&self) -> AlgorithmIdentifier {
+        match self.1 {
+            HashAlg::Sha256 => alg_id::ECDSA_SHA256,
+            HashAlg::Sha384 => alg_id::ECDSA_SHA384,
+            HashAlg::Sha512 => alg_id::ECDSA_SHA512,
+        }
+    }
+}
+
+fn copy_digest<'d>(buf: &'d mut [u8; 64], digest: &[u8]) -> &'d [u8] {
+    buf[..digest.len()].copy_from_slice(digest);
+    &buf[..digest.len()]
+}
+
+impl SignatureVerificationAlgorithm for Rsa {
+    fn verify_signature(
+        &self,
+        public_key: &[u8],
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), InvalidSignature> {
+        use rsa::{pkcs1v15, pss};
+
+        let key = RsaPublicKey::from_pkcs1_der(public_key).map_err(|_| InvalidSignature)?;
+        let bits = rsa::traits::PublicKeyParts::size(&key) * 8;
+        if !(RSA_MIN_BITS..=RSA_MAX_BITS).contains(&bits) {
+            return Err(InvalidSignature);
+        }
+        let result = match self.0 {
+            RsaPadding::Pkcs1 => {
+                let sig = pkcs1v15::Signature::try_from(signature).map_err(|_| InvalidSignature)?;
+                match self.1 {
+                    HashAlg::Sha256 => {
+                        pkcs1v15::VerifyingKey::<Sha256>::new(key).verify(message, &sig)
+                    }
+                    HashAlg::Sha384 => {
+                        pkcs1v15::VerifyingKey::<Sha384>::new(key).verify(message, &sig)
+                    }
+                    HashAlg::Sha512 => {
+                        pkcs1v15::VerifyingKey::<Sha512>::new(key).verify(message, &sig)
+                    }
+                }
+            }
+            RsaPadding::Pss => {
+                let sig = pss::Signature::try_from(signature).map_err(|_| InvalidSignature)?;
+                match self.1 {
+                    HashAlg::Sha256 => pss::VerifyingKey::<Sha256>::new(key).verify(message, &sig),
+                    HashAlg::Sha384 => pss::VerifyingKey::<Sha384>::new(key).verify(message, &sig),
+                    HashAlg::Sha512 => pss::VerifyingKey::<Sha512>::new(key).verify(message, &sig),
+                }
+            }
+        };
+        result.map_err(|_| InvalidSignature)
+    }
+
+    fn public_key_alg_id(&self) -> AlgorithmIdentifier {
+        alg_id::RSA_ENCRYPTION
+    }
+
+    fn signature_alg_id(&self) -> AlgorithmIdentifier {
+        match (self.0, self.1) {
+            (RsaPadding::Pkcs1, HashAlg::Sha256) => alg_id::RSA_PKCS1_SHA256,
+            (RsaPadding::Pkcs1, HashAlg::Sha384) => alg_id::RSA_PKCS1_SHA384,
+            (RsaPadding::Pkcs1, HashAlg::Sha512) => alg_id::RSA_PKCS1_SHA512,
+            (RsaPadding::Pss, HashAlg::Sha256) => alg_id::RSA_PSS_SHA256,
+            (RsaPadding::Pss, HashAlg::Sha384) => alg_id::RSA_PSS_SHA384,
+            (RsaPadding::Pss, HashAlg::Sha512) => alg_id::RSA_PSS_SHA512,
+        }
+    }
+}
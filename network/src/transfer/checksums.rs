@@ -0,0 +1,149 @@
+//! Checksum lists published next to an image.
+//!
+//! Distributions put a `SHA256SUMS` (or similarly named) file in the
+//! directory the ISOs are in, so the download can look its image up there
+//! instead of asking for the hash. Both layouts in use are read:
+//!
+//! - GNU `sha256sum`: `<hex>  <name>`, or `<hex> *<name>` in binary mode
+//! - BSD `sha256 -r` / tagged: `SHA256 (<name>) = <hex>`
+//!
+//! Lines for other algorithms, comments and PGP armor around a signed
+//! list are skipped.
+
+extern crate alloc;
+use alloc::string::String;
+
+/// Largest checksum list fetched; real ones are a few KiB.
+pub const MAX_CHECKSUMS_LEN: usize = 64 * 1024;
+
+/// SHA-256 listed for `file_name` in `list`, `None` if it isn't there.
+pub fn find_sha256(list: &str, file_name: &str) -> Option<[u8; 32]> {
+    list.lines().find_map(|line| {
+        let (hex, name) = parse_line(line.trim())?;
+        if name == file_name {
+            parse_hex(hex)
+        } else {
+            None
+        }
+    })
+}
+
+/// Name of the file a URL path points at: its last segment, without a
+/// query or fragment.
+pub fn file_name(url_path: &str) -> &str {
+    let path = url_path.split(['?', '#']).next().unwrap_or(url_path);
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// Path of `list_name` in the same directory as `url_path`.
+pub fn sibling_path(url_path: &str, list_name: &str) -> String {
+    let path = url_path.split(['?', '#']).next().unwrap_or(url_path);
+    let dir = &path[..path.rfind('/').map_or(0, |slash| slash + 1)];
+    let mut sibling = String::with_capacity(dir.len() + list_name.len());
+    sibling.push_str(dir);
+    sibling.push_str(list_name);
+    sibling
+}
+
+/// Hex digest and file name of a checksum line, either layout.
+fn parse_line(line: &str) -> Option<(&str, &str)> {
+    if let Some(rest) = line.strip_prefix("SHA256 (") {
+        let (name, hex) = rest.rsplit_once(") = ")?;
+        return Some((hex.trim(), name));
+    }
+    let (hex, name) = line.split_once(char::is_whitespace)?;
+    let name = name.trim_start();
+    // Binary mode marks the name with '*'
+    Some((hex, name.strip_prefix('*').unwrap_or(name)))
+}
+
+/// 64 hex digits to a digest; `None` for anything else (other algorithms).
+fn parse_hex(hex: &str) -> Option<[u8; 32]> {
+    let hex = hex.as_bytes();
+    if hex.len() != 64 {
+        return None;
+    }
+    let mut digest = [0u8; 32];
+    for (byte, pair) in digest.iter_mut().zip(hex.chunks_exact(2)) {
+        let high = (pair[0] as char).to_digit(16)?;
+        let low = (pair[1] as char).to_digit(16)?;
+        *byte = ((high << 4) | low) as u8;
+    }
+    Some(digest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALPINE: &str = "4a63d8e2c5b7a1f0e9d8c7b6a5f4e3d2c1b0a9f8e7d6c5b4a3f2e1d0c9b8a7f6";
+    const DEBIAN: &str = "0123456789ABCDEF0123456789abcdef0123456789abcdef0123456789abcdef";
+
+    fn digest(hex: &str) -> [u8; 32] {
+        parse_hex(hex).unwrap()
+    }
+
+    #[test]
+    fn test_gnu_layout() {
+        let list = alloc::format!(
+            "{ALPINE}  alpine-standard-3.20.3-x86_64.iso\n\
+             {DEBIAN} *debian-12.7.0-amd64-netinst.iso\n"
+        );
+        assert_eq!(
+            find_sha256(&list, "debian-12.7.0-amd64-netinst.iso"),
+            Some(digest(DEBIAN))
+        );
+        assert_eq!(
+            find_sha256(&list, "alpine-standard-3.20.3-x86_64.iso"),
+            Some(digest(ALPINE))
+        );
+        assert_eq!(find_sha256(&list, "alpine-standard-3.20.3-x86_64.is"), None);
+        assert_eq!(digest(DEBIAN)[..2], [0x01, 0x23]);
+    }
+
+    #[test]
+    fn test_bsd_layout_in_signed_list() {
+        let list = alloc::format!(
+            "-----BEGIN PGP SIGNED MESSAGE-----\n\
+             Hash: SHA256\n\n\
+             # Fedora-Workstation-Live-x86_64-41-1.4.iso: 2398523392 bytes\n\
+             SHA1 (Fedora-Workstation-Live-x86_64-41-1.4.iso) = 0123456789abcdef\n\
+             SHA256 (Fedora-Workstation-Live-x86_64-41-1.4.iso) = {ALPINE}\n\
+             -----BEGIN PGP SIGNATURE-----\n"
+        );
+        assert_eq!(
+            find_sha256(&list, "Fedora-Workstation-Live-x86_64-41-1.4.iso"),
+            Some(digest(ALPINE))
+        );
+    }
+
+    #[test]
+    fn test_not_a_sha256() {
+        // A SHA-512 list, and a digest with a stray character
+        let sha512 = alloc::format!("{ALPINE}{ALPINE}  a.iso");
+        assert_eq!(find_sha256(&sha512, "a.iso"), None);
+        let bad = alloc::format!("{}g  a.iso", &ALPINE[..63]);
+        assert_eq!(find_sha256(&bad, "a.iso"), None);
+        assert_eq!(
+            find_sha256("<html><body>Not Found</body></html>", "a.iso"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_paths() {
+        let path = "/debian-cd/12.7.0/amd64/iso-cd/debian-12.7.0-amd64-netinst.iso";
+        assert_eq!(file_name(path), "debian-12.7.0-amd64-netinst.iso");
+        assert_eq!(file_name("/a/b.iso?mirror=1"), "b.iso");
+        assert_eq!(
+            sibling_path(path, "SHA256SUMS"),
+            "/debian-cd/12.7.0/amd64/iso-cd/SHA256SUMS"
+        );
+        assert_eq!(sibling_path("/b.iso?x=/y", "SUMS"), "/SUMS");
+        // Through a proxy the path is the whole URL
+        assert_eq!(
+            sibling_path("http://example.org/iso/b.iso", "SHA256SUMS"),
+            "http://example.org/iso/SHA256SUMS"
+        );
+    }
+}
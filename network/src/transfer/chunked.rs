@@ -21,6 +21,14 @@
 //! let result = ChunkedDecoder::decode(data).unwrap();
 //! assert_eq!(result, b"Hello");
 //! ```
+//!
+//! # Streaming
+//!
+//! `feed` collects the body, which only suits small responses. An image
+//! goes through `decode_with`, which hands each run of chunk data to a
+//! callback as a slice of the input: nothing is copied or buffered but
+//! the size line, so the download states can pass it on to the hash and
+//! the disk writer as it arrives.
 
 use crate::error::{NetworkError, Result};
 use alloc::vec::Vec;
@@ -36,6 +44,8 @@ pub enum DecoderState {
     ExpectingCR,
     /// Expecting \n after chunk data.
     ExpectingLF,
+    /// Reading trailer fields after the last chunk, up to an empty line.
+    ReadingTrailer,
     /// Finished reading all chunks.
     Done,
 }
@@ -47,12 +57,12 @@ pub enum DecoderState {
 pub struct ChunkedDecoder {
     /// Current decoder state.
     state: DecoderState,
-    /// Buffer for incomplete chunk size line.
+    /// Buffer for incomplete chunk size (or trailer) line.
     size_buffer: Vec<u8>,
     /// Expected size of current chunk.
-    current_chunk_size: usize,
+    current_chunk_size: u64,
     /// Bytes read in current chunk.
-    chunk_bytes_read: usize,
+    chunk_bytes_read: u64,
     /// Decoded output data.
     output: Vec<u8>,
 }
@@ -107,21 +117,58 @@ impl ChunkedDecoder {
     ///
     /// Returns the number of bytes consumed.
     pub fn feed(&mut self, data: &[u8]) -> Result<usize> {
+        let mut output = core::mem::take(&mut self.output);
+        let consumed = self.decode_with(data, |piece| output.extend_from_slice(piece));
+        self.output = output;
+        consumed
+    }
+
+    /// Decode `data`, passing each run of chunk data to `sink` instead of
+    /// collecting it. Runs are slices of `data`, in order; one chunk can
+    /// arrive as several.
+    ///
+    /// Returns the number of bytes consumed, which falls short of
+    /// `data.len()` only once the body is complete (what follows is not
+    /// part of it).
+    pub fn decode_with(&mut self, data: &[u8], mut sink: impl FnMut(&[u8])) -> Result<usize> {
         let mut consumed = 0;
 
         while consumed < data.len() && self.state != DecoderState::Done {
+            if self.state == DecoderState::ReadingData {
+                // As much of the chunk as this input holds, in one piece
+                let left = self.current_chunk_size - self.chunk_bytes_read;
+                let take = (data.len() - consumed).min(left.min(usize::MAX as u64) as usize);
+                sink(&data[consumed..consumed + take]);
+                consumed += take;
+                self.chunk_bytes_read += take as u64;
+
+                if self.chunk_bytes_read == self.current_chunk_size {
+                    // Finished this chunk, expect trailing CRLF
+                    self.state = DecoderState::ExpectingCR;
+                }
+                continue;
+            }
+
             let byte = data[consumed];
             consumed += 1;
 
             match self.state {
-                DecoderState::ReadingSize => {
+                DecoderState::ReadingSize | DecoderState::ReadingTrailer => {
                     if byte == b'\n'
                         && !self.size_buffer.is_empty()
                         && self.size_buffer.last() == Some(&b'\r')
                     {
-                        // Found end of size line
+                        // Found end of line
                         self.size_buffer.pop(); // Remove \r
-                        self.parse_chunk_size()?;
+                        if self.state == DecoderState::ReadingSize {
+                            self.parse_chunk_size()?;
+                        } else if self.size_buffer.is_empty() {
+                            // Empty line ends the trailer, and the body
+                            self.state = DecoderState::Done;
+                        } else {
+                            // Trailer fields are of no use here
+                            self.size_buffer.clear();
+                        }
                     } else {
                         // Limit size buffer to prevent DoS (max chunk size is ~16 hex chars + extension)
                         if self.size_buffer.len() >= 256 {
@@ -130,15 +177,6 @@ impl ChunkedDecoder {
                         self.size_buffer.push(byte);
                     }
                 }
-                DecoderState::ReadingData => {
-                    self.output.push(byte);
-                    self.chunk_bytes_read += 1;
-
-                    if self.chunk_bytes_read == self.current_chunk_size {
-                        // Finished this chunk, expect trailing CRLF
-                        self.state = DecoderState::ExpectingCR;
-                    }
-                }
                 DecoderState::ExpectingCR => {
                     if byte == b'\r' {
                         self.state = DecoderState::ExpectingLF;
@@ -154,7 +192,7 @@ impl ChunkedDecoder {
                         return Err(NetworkError::InvalidResponse);
                     }
                 }
-                DecoderState::Done => break,
+                DecoderState::ReadingData | DecoderState::Done => break,
             }
         }
 
@@ -170,14 +208,14 @@ impl ChunkedDecoder {
         let size_part = size_str.split(';').next().unwrap_or("").trim();
 
         self.current_chunk_size =
-            usize::from_str_radix(size_part, 16).map_err(|_| NetworkError::InvalidResponse)?;
+            u64::from_str_radix(size_part, 16).map_err(|_| NetworkError::InvalidResponse)?;
 
         self.size_buffer.clear();
         self.chunk_bytes_read = 0;
 
         if self.current_chunk_size == 0 {
-            // Final chunk
-            self.state = DecoderState::Done;
+            // Last chunk; the trailer (usually empty) follows
+            self.state = DecoderState::ReadingTrailer;
         } else {
             self.state = DecoderState::ReadingData;
         }
@@ -354,6 +392,47 @@ mod tests {
         assert!(result.is_err());
     }
 
+    // ==================== Streaming ====================
+
+    #[test]
+    fn test_decode_with_passes_runs() {
+        let data = b"5\r\nHello\r\n6\r\n World\r\n0\r\n\r\n";
+        let mut decoder = ChunkedDecoder::new();
+        let mut runs: Vec<Vec<u8>> = Vec::new();
+        for part in data.chunks(8) {
+            decoder
+                .decode_with(part, |run| runs.push(run.to_vec()))
+                .unwrap();
+        }
+        assert!(decoder.is_done());
+        assert_eq!(runs.concat(), b"Hello World");
+        // Split only where the input was
+        assert_eq!(runs, [&b"Hello"[..], b" Wo", b"rld"]);
+        assert!(decoder.output().is_empty());
+    }
+
+    #[test]
+    fn test_decode_with_stops_at_end() {
+        let mut decoder = ChunkedDecoder::new();
+        let data = b"3\r\nABC\r\n0\r\nExpires: never\r\n\r\nHTTP/1.1";
+        let mut body = Vec::new();
+        let consumed = decoder
+            .decode_with(data, |run| body.extend_from_slice(run))
+            .unwrap();
+        assert!(decoder.is_done());
+        assert_eq!(body, b"ABC");
+        assert_eq!(&data[consumed..], b"HTTP/1.1");
+    }
+
+    #[test]
+    fn test_last_chunk_needs_empty_line() {
+        let mut decoder = ChunkedDecoder::new();
+        decoder.feed(b"3\r\nABC\r\n0\r\n").unwrap();
+        assert_eq!(decoder.state(), DecoderState::ReadingTrailer);
+        decoder.feed(b"\r\n").unwrap();
+        assert!(decoder.is_done());
+    }
+
     // ==================== Real-World Data ====================
 
     #[test]
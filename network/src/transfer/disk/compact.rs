@@ -0,0 +1,695 @@
+//! ISO store compaction.
+//!
+//! Deleting ISOs leaves holes between chunk partitions, and a large ISO
+//! can fail to find a gap even though the disk has room for it in total.
+//! Compaction moves the chunk partitions on the ESP's disk as close to the
+//! end of the disk as they fit, highest first, so the space they leave
+//! behind joins into one gap below them.
+//!
+//! A move copies the partition back to front, then moves its GPT entry and
+//! rewrites the manifests pointing at it. It is journalled as
+//! [`JournalStep::Relocate`] together with how far the copy got. The copy
+//! never overwrites a sector it still has to read after the last recorded
+//! position, so an interrupted move is finished by [`CompactPlan::resume`]
+//! rather than undone.
+//!
+//! Partitions no readable manifest names, and chunks on other disks, stay
+//! where they are.
+
+use gpt_disk_io::BlockIo;
+use gpt_disk_types::Lba;
+
+use super::gpt::GptOps;
+use super::journal::{Journal, JournalEntry, JournalStep};
+use super::manifest::{ManifestReader, ManifestWriter, MAX_MANIFEST_SIZE};
+use super::placement::PlacementPolicy;
+use super::scan::MAX_SCANNED_MANIFESTS;
+use super::types::{DiskError, DiskResult, PartitionInfo, MAX_ISO_NAME_LEN, SECTOR_SIZE};
+
+/// Most moves in a plan (partitions `GptOps::scan_partitions` reports)
+pub const MAX_RELOCATIONS: usize = 16;
+
+/// Record copy progress at least this often (64MB in sectors)
+const RECORD_INTERVAL_SECTORS: u64 = 128 * 1024;
+
+/// FAT32 boot sector and its backup, relative to the partition start
+const BOOT_SECTORS: [u64; 2] = [0, 6];
+
+/// Move of one chunk partition
+#[derive(Debug, Clone, Copy)]
+pub struct Relocation {
+    /// GPT slot of the partition
+    pub slot: u8,
+    /// Current first sector
+    pub start_lba: u64,
+    /// Current last sector (inclusive)
+    pub end_lba: u64,
+    /// First sector once moved
+    pub to_lba: u64,
+    name: [u8; MAX_ISO_NAME_LEN],
+}
+
+impl Relocation {
+    const NONE: Self = Self {
+        slot: 0,
+        start_lba: 0,
+        end_lba: 0,
+        to_lba: 0,
+        name: [0u8; MAX_ISO_NAME_LEN],
+    };
+
+    fn new(slot: u8, start_lba: u64, end_lba: u64, to_lba: u64, iso_name: &str) -> Self {
+        let mut name = [0u8; MAX_ISO_NAME_LEN];
+        let len = iso_name.len().min(MAX_ISO_NAME_LEN - 1);
+        name[..len].copy_from_slice(&iso_name.as_bytes()[..len]);
+        Self {
+            slot,
+            start_lba,
+            end_lba,
+            to_lba,
+            name,
+        }
+    }
+
+    /// Partition size in sectors
+    pub fn sectors(&self) -> u64 {
+        self.end_lba - self.start_lba + 1
+    }
+
+    /// Last sector once moved (inclusive)
+    pub fn to_end_lba(&self) -> u64 {
+        self.to_lba + self.sectors() - 1
+    }
+
+    /// ISO the partition holds a chunk of
+    pub fn name_str(&self) -> &str {
+        let len = self
+            .name
+            .iter()
+            .position(|&c| c == 0)
+            .unwrap_or(self.name.len());
+        core::str::from_utf8(&self.name[..len]).unwrap_or("")
+    }
+}
+
+/// Where a compaction run is, passed to the progress callback after every
+/// batch of sectors
+#[derive(Debug, Clone, Copy)]
+pub struct CompactProgress {
+    /// Move being made (0-based)
+    pub relocation: usize,
+    /// Number of moves in the run
+    pub relocations: usize,
+    /// Sectors copied across all moves
+    pub sectors_done: u64,
+    /// Sectors all moves copy
+    pub sectors_total: u64,
+}
+
+impl CompactProgress {
+    /// Whole run completion, 0-100
+    pub fn percent(&self) -> u8 {
+        if self.sectors_total == 0 {
+            return 100;
+        }
+        (self.sectors_done.min(self.sectors_total) * 100 / self.sectors_total) as u8
+    }
+}
+
+/// Progress callback for [`CompactPlan::run`] and [`CompactPlan::resume`]
+pub type CompactProgressFn<'a> = Option<&'a mut dyn FnMut(&CompactProgress)>;
+
+/// The moves that compact the ISO store, in the order they are made
+pub struct CompactPlan {
+    moves: [Relocation; MAX_RELOCATIONS],
+    count: usize,
+}
+
+impl CompactPlan {
+    /// Work out the moves for the disk whose ESP starts at `esp_start_lba`.
+    pub fn scan<B: BlockIo>(block_io: &mut B, esp_start_lba: u64) -> DiskResult<Self> {
+        let usable = GptOps::usable_range(block_io)?;
+        let (partitions, count) = GptOps::scan_partitions(block_io)?;
+        let partitions = &partitions[..count];
+
+        // A partition is ours to move if a manifest names it as a chunk on
+        // this disk
+        let mut owners = [[0u8; MAX_ISO_NAME_LEN]; MAX_RELOCATIONS];
+        let mut movable = [false; MAX_RELOCATIONS];
+        let mut scan = ManifestReader::scan_all(block_io, esp_start_lba)?;
+        for scanned in &mut scan {
+            let Ok((info, chunks)) = scanned.parsed else {
+                continue;
+            };
+            for chunk in chunks.chunks[..chunks.count]
+                .iter()
+                .filter(|chunk| chunk.disk_id == 0)
+            {
+                if let Some(i) = partitions.iter().position(|p| {
+                    p.start_lba == chunk.info.start_lba && p.end_lba == chunk.info.end_lba
+                }) {
+                    movable[i] = true;
+                    owners[i] = info.name;
+                }
+            }
+        }
+        if let Some(e) = scan.error() {
+            return Err(e);
+        }
+
+        let mut plan = Self {
+            moves: [Relocation::NONE; MAX_RELOCATIONS],
+            count: 0,
+        };
+        let (moves, move_count) = plan_moves(partitions, &movable[..count], usable);
+        for &(i, to_lba) in &moves[..move_count] {
+            let part = &partitions[i];
+            plan.moves[plan.count] = Relocation {
+                slot: part.index,
+                start_lba: part.start_lba,
+                end_lba: part.end_lba,
+                to_lba,
+                name: owners[i],
+            };
+            plan.count += 1;
+        }
+        Ok(plan)
+    }
+
+    /// Moves in the order they are made
+    pub fn moves(&self) -> &[Relocation] {
+        &self.moves[..self.count]
+    }
+
+    /// Whether the store is already compact
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Sectors the whole plan copies
+    pub fn total_sectors(&self) -> u64 {
+        self.moves().iter().map(Relocation::sectors).sum()
+    }
+
+    /// Make every move, each under its own journal record.
+    ///
+    /// `buffer` holds one batch of sectors; larger is faster. Fails if the
+    /// journal has an operation pending - recover it first.
+    pub fn run<B: BlockIo>(
+        &self,
+        block_io: &mut B,
+        esp_start_lba: u64,
+        journal: &mut Journal,
+        buffer: &mut [u8],
+        progress: CompactProgressFn<'_>,
+    ) -> DiskResult<()> {
+        if journal.pending().is_some() {
+            return Err(DiskError::InvalidParameter);
+        }
+        let mut ignore = |_: &CompactProgress| {};
+        let progress: &mut dyn FnMut(&CompactProgress) = progress.unwrap_or(&mut ignore);
+
+        let mut report = CompactProgress {
+            relocation: 0,
+            relocations: self.count,
+            sectors_done: 0,
+            sectors_total: self.total_sectors(),
+        };
+        for (i, reloc) in self.moves().iter().enumerate() {
+            report.relocation = i;
+            let entry = JournalEntry::relocation(
+                reloc.name_str(),
+                reloc.start_lba,
+                reloc.end_lba,
+                reloc.to_lba,
+            );
+            journal.begin(block_io, entry)?;
+            relocate(
+                block_io,
+                esp_start_lba,
+                journal,
+                reloc,
+                buffer,
+                &mut report,
+                progress,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Finish the move the journal says was interrupted, and commit it.
+    pub fn resume<B: BlockIo>(
+        block_io: &mut B,
+        esp_start_lba: u64,
+        journal: &mut Journal,
+        buffer: &mut [u8],
+        progress: CompactProgressFn<'_>,
+    ) -> DiskResult<()> {
+        let entry = match journal.pending() {
+            Some(entry) if entry.step == JournalStep::Relocate => *entry,
+            _ => return Err(DiskError::InvalidParameter),
+        };
+        if entry.end_lba < entry.start_lba || entry.dest_lba <= entry.start_lba {
+            return Err(DiskError::InvalidParameter);
+        }
+
+        // The GPT entry moves last; if it already has, only the manifests
+        // may be left
+        let moved_end = entry.dest_lba + (entry.end_lba - entry.start_lba);
+        let slot = match GptOps::find_partition(block_io, entry.start_lba, entry.end_lba)? {
+            Some(slot) => slot,
+            None => GptOps::find_partition(block_io, entry.dest_lba, moved_end)?
+                .ok_or(DiskError::PartitionNotFound)?,
+        };
+
+        let reloc = Relocation::new(
+            slot,
+            entry.start_lba,
+            entry.end_lba,
+            entry.dest_lba,
+            entry.name_str(),
+        );
+        let mut report = CompactProgress {
+            relocation: 0,
+            relocations: 1,
+            sectors_done: 0,
+            sectors_total: reloc.sectors(),
+        };
+        let mut ignore = |_: &CompactProgress| {};
+        relocate(
+            block_io,
+            esp_start_lba,
+            journal,
+            &reloc,
+            buffer,
+            &mut report,
+            progress.unwrap_or(&mut ignore),
+        )
+    }
+}
+
+/// Pick the partitions to move and where to, as (index into `partitions`,
+/// new start LBA), highest partition first.
+///
+/// Each movable partition goes into the highest gap above its current
+/// start that holds it, between the partitions that stay and those already
+/// placed. Partitions never move down, so every copy runs back to front.
+fn plan_moves(
+    partitions: &[PartitionInfo],
+    movable: &[bool],
+    usable: (u64, u64),
+) -> ([(usize, u64); MAX_RELOCATIONS], usize) {
+    let mut occupied = [(0u64, 0u64); MAX_RELOCATIONS];
+    let mut occupied_count = 0;
+    let mut order = [0usize; MAX_RELOCATIONS];
+    let mut order_count = 0;
+    for (i, part) in partitions.iter().enumerate().take(MAX_RELOCATIONS) {
+        if movable[i] {
+            order[order_count] = i;
+            order_count += 1;
+        } else {
+            occupied[occupied_count] = (part.start_lba, part.end_lba);
+            occupied_count += 1;
+        }
+    }
+    order[..order_count].sort_unstable_by_key(|&i| core::cmp::Reverse(partitions[i].start_lba));
+
+    let policy = PlacementPolicy::EndOfDisk;
+    let mut moves = [(0usize, 0u64); MAX_RELOCATIONS];
+    let mut move_count = 0;
+    for &i in &order[..order_count] {
+        let part = &partitions[i];
+        let needed = part.size_sectors();
+
+        let mut gaps = [(0u64, 0u64); MAX_RELOCATIONS + 1];
+        let gap_count = free_gaps(
+            &mut occupied[..occupied_count],
+            (part.start_lba, usable.1),
+            &mut gaps,
+        );
+        let target = policy
+            .choose(&gaps[..gap_count], needed)
+            .and_then(|gap| policy.place(gap, needed));
+
+        let range = match target {
+            Some((start, end)) if start > part.start_lba => {
+                moves[move_count] = (i, start);
+                move_count += 1;
+                (start, end)
+            }
+            _ => (part.start_lba, part.end_lba),
+        };
+        occupied[occupied_count] = range;
+        occupied_count += 1;
+    }
+
+    (moves, move_count)
+}
+
+/// Free inclusive ranges inside `bounds` not covered by `occupied`, in LBA
+/// order. Sorts `occupied`.
+fn free_gaps(occupied: &mut [(u64, u64)], bounds: (u64, u64), gaps: &mut [(u64, u64)]) -> usize {
+    let (first, last) = bounds;
+    occupied.sort_unstable();
+
+    let mut count = 0;
+    let mut current = first;
+    for &(start, end) in occupied.iter() {
+        if current > last {
+            break;
+        }
+        if start > current {
+            gaps[count] = (current, (start - 1).min(last));
+            count += 1;
+        }
+        current = current.max(end.saturating_add(1));
+    }
+    if current <= last {
+        gaps[count] = (current, last);
+        count += 1;
+    }
+    count
+}
+
+/// Make one journalled move and commit it. Safe to run again on a move
+/// that was interrupted at any point.
+fn relocate<B: BlockIo>(
+    block_io: &mut B,
+    esp_start_lba: u64,
+    journal: &mut Journal,
+    reloc: &Relocation,
+    buffer: &mut [u8],
+    report: &mut CompactProgress,
+    progress: &mut dyn FnMut(&CompactProgress),
+) -> DiskResult<()> {
+    if GptOps::find_partition(block_io, reloc.start_lba, reloc.end_lba)?.is_some() {
+        copy_sectors(block_io, journal, reloc, buffer, report, progress)?;
+        patch_hidden_sectors(block_io, reloc.to_lba)?;
+        GptOps::move_partition(block_io, reloc.slot, reloc.to_lba)?;
+    }
+    rewrite_manifests(block_io, esp_start_lba, reloc)?;
+    journal.commit(block_io)
+}
+
+/// Copy the partition to its new place, last batch first.
+///
+/// Batches are at most the shift long, so a batch never overlaps its own
+/// destination, and the journal is told how far the copy got before the
+/// sectors written since the last record add up to more than the shift.
+/// Everything still to be copied after that record is therefore intact,
+/// and a resumed copy just repeats the batches since.
+fn copy_sectors<B: BlockIo>(
+    block_io: &mut B,
+    journal: &mut Journal,
+    reloc: &Relocation,
+    buffer: &mut [u8],
+    report: &mut CompactProgress,
+    progress: &mut dyn FnMut(&CompactProgress),
+) -> DiskResult<()> {
+    let total = reloc.sectors();
+    let shift = reloc.to_lba - reloc.start_lba;
+    let batch_max = ((buffer.len() / SECTOR_SIZE) as u64).min(shift);
+    if batch_max == 0 {
+        return Err(DiskError::BufferTooSmall);
+    }
+    let record_every = shift.min(RECORD_INTERVAL_SECTORS);
+
+    let base = report.sectors_done;
+    let mut copied = journal.pending().map_or(0, |entry| entry.copied).min(total);
+    let mut recorded = copied;
+    while copied < total {
+        let batch = batch_max.min(total - copied);
+        if copied + batch - recorded > record_every && copied > recorded {
+            journal.record_copied(block_io, copied)?;
+            recorded = copied;
+        }
+
+        let first = reloc.end_lba + 1 - copied - batch;
+        let data = &mut buffer[..batch as usize * SECTOR_SIZE];
+        block_io
+            .read_blocks(Lba(first), data)
+            .map_err(|_| DiskError::IoError)?;
+        block_io
+            .write_blocks(Lba(first + shift), data)
+            .map_err(|_| DiskError::IoError)?;
+        copied += batch;
+
+        report.sectors_done = base + copied;
+        progress(report);
+    }
+
+    block_io.flush().map_err(|_| DiskError::IoError)
+}
+
+/// Point a moved FAT32 volume's hidden sectors field (its partition start)
+/// at the new place, in the boot sector and its backup.
+fn patch_hidden_sectors<B: BlockIo>(block_io: &mut B, start_lba: u64) -> DiskResult<()> {
+    // 32-bit BPB field; 0 for partitions starting past 2TiB, as formatted
+    let hidden = u32::try_from(start_lba).unwrap_or(0);
+
+    let mut sector = [0u8; SECTOR_SIZE];
+    for offset in BOOT_SECTORS {
+        block_io
+            .read_blocks(Lba(start_lba + offset), &mut sector)
+            .map_err(|_| DiskError::IoError)?;
+        if &sector[82..90] != b"FAT32   " || sector[510..512] != [0x55, 0xAA] {
+            continue;
+        }
+        sector[28..32].copy_from_slice(&hidden.to_le_bytes());
+        block_io
+            .write_blocks(Lba(start_lba + offset), &sector)
+            .map_err(|_| DiskError::IoError)?;
+    }
+    Ok(())
+}
+
+/// Point every manifest chunk at the old range to the new one.
+fn rewrite_manifests<B: BlockIo>(
+    block_io: &mut B,
+    esp_start_lba: u64,
+    reloc: &Relocation,
+) -> DiskResult<()> {
+    // The scan borrows the disk, so rewrite one manifest per scan
+    for _ in 0..MAX_SCANNED_MANIFESTS {
+        let found = ManifestReader::scan_all(block_io, esp_start_lba)?.find_map(|scanned| {
+            let mut name = [0u8; 12];
+            let path = alloc::format!("/.iso/{}", scanned.filename(&mut name));
+            let (info, mut chunks) = scanned.parsed.ok()?;
+
+            let mut hit = false;
+            for chunk in chunks.chunks[..chunks.count].iter_mut() {
+                if chunk.disk_id == 0
+                    && chunk.info.start_lba == reloc.start_lba
+                    && chunk.info.end_lba == reloc.end_lba
+                {
+                    chunk.info.start_lba = reloc.to_lba;
+                    chunk.info.end_lba = reloc.to_end_lba();
+                    hit = true;
+                }
+            }
+            hit.then_some((path, info, chunks))
+        });
+        let Some((path, info, chunks)) = found else {
+            return Ok(());
+        };
+
+        let mut buffer = [0u8; MAX_MANIFEST_SIZE];
+        let len = ManifestWriter::from_info(&info).serialize(&chunks, &mut buffer)?;
+        morpheus_core::fs::replace_file(block_io, esp_start_lba, &path, &buffer[..len])
+            .map_err(|_| DiskError::ManifestError)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fat32::Fat32Formatter;
+    use super::super::journal::JOURNAL_PATH;
+    use super::super::types::{guid, ChunkPartition, ChunkSet};
+    use super::*;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use gpt_disk_io::BlockIoAdapter;
+    use gpt_disk_types::BlockSize;
+
+    /// Smallest volume the formatter accepts
+    const ESP_SECTORS: u64 = 133_120;
+    const ESP_START: u64 = 2048;
+    const DISK_SECTORS: u64 = 163_840;
+    const CHUNK_SECTORS: u64 = 4096;
+
+    fn part(index: u8, start_lba: u64, sectors: u64) -> PartitionInfo {
+        PartitionInfo::new(index, start_lba, start_lba + sectors - 1, guid::BASIC_DATA)
+    }
+
+    #[test]
+    fn test_plan_packs_towards_end() {
+        let usable = (34, DISK_SECTORS - 34);
+        let esp = part(0, ESP_START, ESP_SECTORS);
+        let a = part(1, 136_192, CHUNK_SECTORS);
+        let fixed = part(2, 145_408, CHUNK_SECTORS);
+        let c = part(3, 151_552, CHUNK_SECTORS);
+        let partitions = [esp, a, fixed, c];
+
+        let (moves, count) = plan_moves(&partitions, &[false, true, false, true], usable);
+        // C to the last aligned spot, A right below it, past the fixed one
+        assert_eq!(&moves[..count], &[(3, 157_696), (1, 153_600)]);
+
+        // Already compact
+        let packed = [
+            esp,
+            part(1, 153_600, CHUNK_SECTORS),
+            part(3, 157_696, CHUNK_SECTORS),
+        ];
+        let (_, count) = plan_moves(&packed, &[false, true, true], usable);
+        assert_eq!(count, 0);
+
+        let mut occupied = [(10, 19), (40, 49)];
+        let mut gaps = [(0, 0); 3];
+        let count = free_gaps(&mut occupied, (15, 45), &mut gaps);
+        assert_eq!(&gaps[..count], &[(20, 39)]);
+    }
+
+    fn fill(disk: &mut BlockIoAdapter<&mut [u8]>, start_lba: u64, tag: u8) {
+        let mut sector = [tag; SECTOR_SIZE];
+        for i in 0..CHUNK_SECTORS {
+            sector[..8].copy_from_slice(&i.to_le_bytes());
+            disk.write_blocks(Lba(start_lba + i), &sector).unwrap();
+        }
+        // Looks like a chunk's FAT32 boot sector
+        sector[82..90].copy_from_slice(b"FAT32   ");
+        sector[510..512].copy_from_slice(&[0x55, 0xAA]);
+        for offset in BOOT_SECTORS {
+            sector[..8].copy_from_slice(&offset.to_le_bytes());
+            disk.write_blocks(Lba(start_lba + offset), &sector).unwrap();
+        }
+    }
+
+    fn check(disk: &mut BlockIoAdapter<&mut [u8]>, start_lba: u64, tag: u8) {
+        let mut sector = [0u8; SECTOR_SIZE];
+        for i in 0..CHUNK_SECTORS {
+            disk.read_blocks(Lba(start_lba + i), &mut sector).unwrap();
+            assert_eq!(sector[..8], i.to_le_bytes());
+            assert_eq!(sector[100], tag);
+        }
+        disk.read_blocks(Lba(start_lba + 6), &mut sector).unwrap();
+        assert_eq!(sector[28..32], (start_lba as u32).to_le_bytes());
+    }
+
+    fn write_manifest(disk: &mut BlockIoAdapter<&mut [u8]>, name: &str, path: &str, lba: u64) {
+        let mut chunks = ChunkSet::new();
+        chunks
+            .add(ChunkPartition::new(part(0, lba, CHUNK_SECTORS), 0))
+            .unwrap();
+        let mut buffer = [0u8; MAX_MANIFEST_SIZE];
+        let mut writer = ManifestWriter::new(name, 1234);
+        writer.set_complete(true);
+        let len = writer.serialize(&chunks, &mut buffer).unwrap();
+        morpheus_core::fs::write_file(disk, ESP_START, path, &buffer[..len]).unwrap();
+    }
+
+    fn chunk_starts(disk: &mut BlockIoAdapter<&mut [u8]>) -> Vec<u64> {
+        let mut starts: Vec<u64> = ManifestReader::scan_all(disk, ESP_START)
+            .unwrap()
+            .map(|scanned| {
+                let (info, chunks) = scanned.parsed.unwrap();
+                assert!(info.is_complete());
+                chunks.chunks[0].info.start_lba
+            })
+            .collect();
+        starts.sort_unstable();
+        starts
+    }
+
+    #[test]
+    fn test_compact_and_resume() {
+        let mut storage = vec![0u8; DISK_SECTORS as usize * SECTOR_SIZE];
+        morpheus_core::disk::gpt_ops::create_gpt(
+            BlockIoAdapter::new(storage.as_mut_slice(), BlockSize::BS_512),
+            DISK_SECTORS,
+        )
+        .unwrap();
+        let mut disk = BlockIoAdapter::new(storage.as_mut_slice(), BlockSize::BS_512);
+
+        GptOps::create_partition(
+            &mut disk,
+            ESP_START,
+            ESP_START + ESP_SECTORS - 1,
+            guid::EFI_SYSTEM,
+            "ESP",
+        )
+        .unwrap();
+        Fat32Formatter::format(&mut disk, ESP_START, ESP_SECTORS, "ESP").unwrap();
+        morpheus_core::fs::create_directory(&mut disk, ESP_START, "/.iso").unwrap();
+        morpheus_core::fs::write_file(&mut disk, ESP_START, JOURNAL_PATH, &[0u8; SECTOR_SIZE])
+            .unwrap();
+
+        for (lba, name) in [(136_192, "a"), (145_408, "b"), (151_552, "c")] {
+            GptOps::create_partition(
+                &mut disk,
+                lba,
+                lba + CHUNK_SECTORS - 1,
+                guid::BASIC_DATA,
+                name,
+            )
+            .unwrap();
+        }
+        fill(&mut disk, 136_192, 0xA1);
+        fill(&mut disk, 151_552, 0xC3);
+        write_manifest(&mut disk, "a.iso", "/.iso/A.MFS", 136_192);
+        write_manifest(&mut disk, "c.iso", "/.iso/C.MFS", 151_552);
+
+        let plan = CompactPlan::scan(&mut disk, ESP_START).unwrap();
+        assert_eq!(plan.moves().len(), 2);
+        assert_eq!(plan.moves()[0].name_str(), "c.iso");
+        assert_eq!(plan.total_sectors(), 2 * CHUNK_SECTORS);
+
+        let mut journal = Journal::open(&mut disk, ESP_START).unwrap().unwrap();
+        let mut buffer = [0u8; 64 * SECTOR_SIZE];
+        let mut last = 0;
+        let mut progress = |p: &CompactProgress| last = p.percent();
+        plan.run(
+            &mut disk,
+            ESP_START,
+            &mut journal,
+            &mut buffer,
+            Some(&mut progress),
+        )
+        .unwrap();
+        assert_eq!(last, 100);
+        assert!(journal.pending().is_none());
+
+        check(&mut disk, 153_600, 0xA1);
+        check(&mut disk, 157_696, 0xC3);
+        assert_eq!(chunk_starts(&mut disk), [153_600, 157_696]);
+        assert!(GptOps::find_partition(&mut disk, 153_600, 157_695)
+            .unwrap()
+            .is_some());
+        assert!(CompactPlan::scan(&mut disk, ESP_START).unwrap().is_empty());
+
+        // A move interrupted before anything was copied is finished on resume
+        GptOps::create_partition(
+            &mut disk,
+            ESP_START + ESP_SECTORS,
+            ESP_START + ESP_SECTORS + CHUNK_SECTORS - 1,
+            guid::BASIC_DATA,
+            "d",
+        )
+        .unwrap();
+        fill(&mut disk, ESP_START + ESP_SECTORS, 0xD4);
+        write_manifest(&mut disk, "d.iso", "/.iso/D.MFS", ESP_START + ESP_SECTORS);
+        let entry = JournalEntry::relocation(
+            "d.iso",
+            ESP_START + ESP_SECTORS,
+            ESP_START + ESP_SECTORS + CHUNK_SECTORS - 1,
+            149_504,
+        );
+        journal.begin(&mut disk, entry).unwrap();
+
+        let mut journal = Journal::open(&mut disk, ESP_START).unwrap().unwrap();
+        assert!(CompactPlan::resume(&mut disk, ESP_START, &mut journal, &mut buffer, None).is_ok());
+        assert!(journal.pending().is_none());
+        check(&mut disk, 149_504, 0xD4);
+        assert_eq!(chunk_starts(&mut disk), [149_504, 153_600, 157_696]);
+    }
+}
@@ -27,7 +27,7 @@ impl Fat32Formatter {
         volume_label: &str,
     ) -> DiskResult<Fat32Info> {
         // Validate partition size (min ~65MB, max ~2TB for FAT32)
-        if partition_sectors < 133120 {
+        if partition_sectors < 133120 || partition_sectors > u32::MAX as u64 {
             return Err(DiskError::InvalidSize);
         }
 
@@ -49,7 +49,8 @@ impl Fat32Formatter {
             total_sectors,
             fat_size,
             sectors_per_cluster,
-            partition_start_lba as u32,
+            // 32-bit BPB field; 0 for partitions starting past 2TiB
+            u32::try_from(partition_start_lba).unwrap_or(0),
             volume_label,
         );
 
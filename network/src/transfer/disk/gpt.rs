@@ -1,22 +1,29 @@
 //! GPT partition operations for post-EBS.
 //!
-//! Allocation-free GPT manipulation using only stack buffers.
-//! All operations work with the VirtioBlkBlockIo adapter.
+//! Thin layer over the `morpheus-gpt` engine, which the pre-EBS storage
+//! manager uses too. Allocation-free: partition arrays are read into a
+//! stack buffer of [`DEFAULT_ARRAY_BYTES`]. All operations take any
+//! `BlockIo`: `UnifiedBlockIo`, or the per-driver adapters over it.
 
 use gpt_disk_io::BlockIo;
-use gpt_disk_types::{Lba, LbaLe};
+use morpheus_gpt::{Gpt, GptError, DEFAULT_ARRAY_BYTES, DEFAULT_ENTRIES};
 
-use super::types::{guid, DiskError, DiskResult, PartitionInfo, SECTOR_SIZE};
-
-/// GPT header constants
-const GPT_SIGNATURE: &[u8; 8] = b"EFI PART";
-const GPT_REVISION: u32 = 0x00010000;
-const PARTITION_ENTRY_SIZE: usize = 128;
-const MAX_PARTITION_ENTRIES: usize = 128;
+use super::placement::PlacementPolicy;
+use super::types::{DiskError, DiskResult, PartitionInfo};
 
 /// GPT operations helper
 pub struct GptOps;
 
+/// The disk's GPT (primary, or backup if the primary is damaged)
+fn read_gpt<B: BlockIo>(block_io: &mut B) -> DiskResult<Gpt<[u8; DEFAULT_ARRAY_BYTES]>> {
+    Ok(Gpt::read(block_io, |_| [0u8; DEFAULT_ARRAY_BYTES])?)
+}
+
+/// GPT slot as the `u8` callers track partitions by
+fn slot_u8(slot: u32) -> DiskResult<u8> {
+    u8::try_from(slot).map_err(|_| DiskError::NoFreeSpace)
+}
+
 impl GptOps {
     /// Scan disk for existing partitions
     ///
@@ -27,62 +34,21 @@ impl GptOps {
         let mut partitions = [PartitionInfo::default(); 16];
         let mut count = 0;
 
-        // Read GPT header (LBA 1)
-        let mut header_buf = [0u8; SECTOR_SIZE];
-        block_io
-            .read_blocks(Lba(1), &mut header_buf)
-            .map_err(|_| DiskError::IoError)?;
-
-        // Validate signature
-        if &header_buf[0..8] != GPT_SIGNATURE {
-            return Err(DiskError::InvalidGpt);
-        }
-
-        // Get partition entry LBA and count
-        let entry_lba = u64::from_le_bytes(header_buf[72..80].try_into().unwrap());
-        let num_entries = u32::from_le_bytes(header_buf[80..84].try_into().unwrap()) as usize;
-        let entry_size = u32::from_le_bytes(header_buf[84..88].try_into().unwrap()) as usize;
-
-        if entry_size != PARTITION_ENTRY_SIZE {
-            return Err(DiskError::InvalidGpt);
-        }
-
-        // Read partition entries (32 sectors for 128 entries)
-        let mut entry_buf = [0u8; SECTOR_SIZE * 32];
-        for i in 0..32 {
-            let sector_buf = &mut entry_buf[i * SECTOR_SIZE..(i + 1) * SECTOR_SIZE];
-            block_io
-                .read_blocks(Lba(entry_lba + i as u64), sector_buf)
-                .map_err(|_| DiskError::IoError)?;
-        }
-
-        // Parse entries
-        let entries_to_check = num_entries.min(MAX_PARTITION_ENTRIES);
-        for i in 0..entries_to_check {
-            let offset = i * PARTITION_ENTRY_SIZE;
-            let entry = &entry_buf[offset..offset + PARTITION_ENTRY_SIZE];
-
-            // Check if entry is used (type GUID not zero)
-            let type_guid: [u8; 16] = entry[0..16].try_into().unwrap();
-            if type_guid == [0u8; 16] {
-                continue;
-            }
-
+        let gpt = read_gpt(block_io)?;
+        for entry in gpt.entries() {
             if count >= 16 {
                 break; // Max partitions we track
             }
+            let Ok(slot) = u8::try_from(entry.slot) else {
+                break;
+            };
 
-            let start_lba = u64::from_le_bytes(entry[32..40].try_into().unwrap());
-            let end_lba = u64::from_le_bytes(entry[40..48].try_into().unwrap());
-
-            partitions[count] = PartitionInfo::new(i as u8, start_lba, end_lba, type_guid);
+            let info = &mut partitions[count];
+            *info = PartitionInfo::new(slot, entry.start_lba, entry.end_lba, entry.type_guid);
 
-            // Copy name (UTF-16LE to ASCII)
-            for j in 0..36 {
-                let utf16_offset = 56 + j * 2;
-                if utf16_offset < PARTITION_ENTRY_SIZE {
-                    partitions[count].name[j] = entry[utf16_offset];
-                }
+            // Name (UTF-16 to ASCII, low byte)
+            for (dst, &unit) in info.name.iter_mut().zip(entry.name.iter()) {
+                *dst = unit as u8;
             }
 
             count += 1;
@@ -91,133 +57,54 @@ impl GptOps {
         Ok((partitions, count))
     }
 
-    /// Find contiguous free space on disk
+    /// Find room for a partition of `needed` sectors
     ///
-    /// Returns (start_lba, end_lba) of largest free region.
-    pub fn find_free_space<B: BlockIo>(block_io: &mut B) -> DiskResult<(u64, u64)> {
-        // Read GPT header
-        let mut header_buf = [0u8; SECTOR_SIZE];
-        block_io
-            .read_blocks(Lba(1), &mut header_buf)
-            .map_err(|_| DiskError::IoError)?;
-
-        if &header_buf[0..8] != GPT_SIGNATURE {
-            return Err(DiskError::InvalidGpt);
-        }
-
-        let first_usable = u64::from_le_bytes(header_buf[40..48].try_into().unwrap());
-        let last_usable = u64::from_le_bytes(header_buf[48..56].try_into().unwrap());
-
-        // Scan partitions
-        let (partitions, count) = Self::scan_partitions(block_io)?;
-
-        if count == 0 {
-            return Ok((first_usable, last_usable));
-        }
-
-        // Sort partitions by start LBA (simple bubble sort, count is small)
-        let mut sorted: [(u64, u64); 16] = [(0, 0); 16];
-        for i in 0..count {
-            sorted[i] = (partitions[i].start_lba, partitions[i].end_lba);
-        }
-        for _ in 0..count {
-            for j in 0..count.saturating_sub(1) {
-                if sorted[j].0 > sorted[j + 1].0 {
-                    sorted.swap(j, j + 1);
-                }
-            }
-        }
-
-        // Find largest gap
-        let mut best_start = 0u64;
-        let mut best_size = 0u64;
-
-        // Gap before first partition
-        if sorted[0].0 > first_usable {
-            let gap_size = sorted[0].0 - first_usable;
-            if gap_size > best_size {
-                best_start = first_usable;
-                best_size = gap_size;
-            }
-        }
-
-        // Gaps between partitions
-        for i in 0..count.saturating_sub(1) {
-            let gap_start = sorted[i].1 + 1;
-            let gap_end = sorted[i + 1].0.saturating_sub(1);
-            if gap_end > gap_start {
-                let gap_size = gap_end - gap_start + 1;
-                if gap_size > best_size {
-                    best_start = gap_start;
-                    best_size = gap_size;
-                }
-            }
-        }
-
-        // Gap after last partition
-        if sorted[count - 1].1 < last_usable {
-            let gap_start = sorted[count - 1].1 + 1;
-            let gap_size = last_usable - gap_start + 1;
-            if gap_size > best_size {
-                best_start = gap_start;
-                best_size = gap_size;
-            }
-        }
-
-        if best_size == 0 {
-            return Err(DiskError::NoFreeSpace);
+    /// Returns the aligned (start_lba, end_lba) range in the free gap
+    /// `policy` picks.
+    pub fn find_free_space<B: BlockIo>(
+        block_io: &mut B,
+        policy: PlacementPolicy,
+        needed: u64,
+    ) -> DiskResult<(u64, u64)> {
+        let gpt = read_gpt(block_io)?;
+
+        // Gaps before, between and after partitions
+        let mut gaps = [(0u64, 0u64); DEFAULT_ENTRIES as usize + 1];
+        let mut gap_count = 0;
+        for (slot, gap) in gaps.iter_mut().zip(gpt.gaps()) {
+            *slot = gap;
+            gap_count += 1;
         }
 
-        Ok((best_start, best_start + best_size - 1))
+        let gap = policy
+            .choose(&gaps[..gap_count], needed)
+            .ok_or(DiskError::NoFreeSpace)?;
+        policy.place(gap, needed).ok_or(DiskError::NoFreeSpace)
     }
 
     /// Verify that a given LBA range doesn't overlap any existing partition
     ///
-    /// Returns Ok(true) if range is free, Ok(false) if it overlaps.
+    /// Returns Ok(true) if range is free, Ok(false) if it overlaps or
+    /// leaves the usable area.
     pub fn verify_range_free<B: BlockIo>(
         block_io: &mut B,
         start_lba: u64,
         end_lba: u64,
     ) -> DiskResult<bool> {
-        // Scan existing partitions
-        let (partitions, count) = Self::scan_partitions(block_io)?;
-
-        // Check for overlaps with any existing partition
-        for i in 0..count {
-            let part = &partitions[i];
-
-            // Check if ranges overlap
-            // Two ranges [a,b] and [c,d] overlap if: a <= d && c <= b
-            if start_lba <= part.end_lba && part.start_lba <= end_lba {
-                return Ok(false); // Overlap detected
-            }
-        }
-
-        // Also check against GPT usable area
-        let mut header_buf = [0u8; SECTOR_SIZE];
-        block_io
-            .read_blocks(Lba(1), &mut header_buf)
-            .map_err(|_| DiskError::IoError)?;
-
-        if &header_buf[0..8] != GPT_SIGNATURE {
-            return Err(DiskError::InvalidGpt);
+        match read_gpt(block_io)?.check_range(start_lba, end_lba, None) {
+            Ok(()) => Ok(true),
+            Err(GptError::InvalidSize | GptError::OverlappingPartitions) => Ok(false),
+            Err(e) => Err(e.into()),
         }
-
-        let first_usable = u64::from_le_bytes(header_buf[40..48].try_into().unwrap());
-        let last_usable = u64::from_le_bytes(header_buf[48..56].try_into().unwrap());
-
-        // Check if range is within usable area
-        if start_lba < first_usable || end_lba > last_usable {
-            return Ok(false); // Outside usable area
-        }
-
-        Ok(true) // Range is free
     }
 
     /// Create a new partition
     ///
-    /// Finds free slot in GPT and writes partition entry.
+    /// Finds free slot in GPT and writes partition entry. Chunk partitions
+    /// ([`guid::ISO_CHUNK`]) are marked hidden and no-automount.
     /// Updates BOTH primary and backup GPT headers and partition arrays.
+    ///
+    /// [`guid::ISO_CHUNK`]: super::types::guid::ISO_CHUNK
     pub fn create_partition<B: BlockIo>(
         block_io: &mut B,
         start_lba: u64,
@@ -225,153 +112,249 @@ impl GptOps {
         type_guid: [u8; 16],
         name: &str,
     ) -> DiskResult<u8> {
-        // Read primary GPT header (LBA 1)
-        let mut primary_header = [0u8; SECTOR_SIZE];
-        block_io
-            .read_blocks(Lba(1), &mut primary_header)
-            .map_err(|_| DiskError::IoError)?;
-
-        if &primary_header[0..8] != GPT_SIGNATURE {
-            return Err(DiskError::InvalidGpt);
-        }
-
-        // Extract critical fields from primary header
-        let my_lba = u64::from_le_bytes(primary_header[24..32].try_into().unwrap()); // Should be 1
-        let alternate_lba = u64::from_le_bytes(primary_header[32..40].try_into().unwrap()); // Backup header LBA
-        let entry_lba = u64::from_le_bytes(primary_header[72..80].try_into().unwrap());
-        let num_entries = u32::from_le_bytes(primary_header[80..84].try_into().unwrap());
-        let entry_size = u32::from_le_bytes(primary_header[84..88].try_into().unwrap());
-
-        // Read partition entries (primary)
-        let mut entry_buf = [0u8; SECTOR_SIZE * 32];
-        for i in 0..32 {
-            let sector_buf = &mut entry_buf[i * SECTOR_SIZE..(i + 1) * SECTOR_SIZE];
-            block_io
-                .read_blocks(Lba(entry_lba + i as u64), sector_buf)
-                .map_err(|_| DiskError::IoError)?;
-        }
-
-        // Find empty slot
-        let mut slot_index = None;
-        for i in 0..MAX_PARTITION_ENTRIES {
-            let offset = i * PARTITION_ENTRY_SIZE;
-            let type_bytes = &entry_buf[offset..offset + 16];
-            if type_bytes == &[0u8; 16] {
-                slot_index = Some(i);
-                break;
-            }
-        }
-
-        let slot = slot_index.ok_or(DiskError::NoFreeSpace)?;
-        let offset = slot * PARTITION_ENTRY_SIZE;
-
-        // Build partition entry
-        entry_buf[offset..offset + 16].copy_from_slice(&type_guid);
+        let mut gpt = read_gpt(block_io)?;
+        let slot = slot_u8(gpt.add(type_guid, start_lba, end_lba, name)?)?;
+        gpt.write(block_io)?;
+        Ok(slot)
+    }
 
-        // Unique GUID (simple non-zero value based on slot and timestamp-like value)
-        entry_buf[offset + 16] = (slot + 1) as u8;
-        entry_buf[offset + 17] = 0x12;
-        entry_buf[offset + 18] = 0x34;
-        entry_buf[offset + 19] = 0x56;
+    /// Delete the partition in GPT slot `slot`
+    ///
+    /// Clears the entry in both primary and backup partition arrays.
+    pub fn delete_partition<B: BlockIo>(block_io: &mut B, slot: u8) -> DiskResult<()> {
+        let mut gpt = read_gpt(block_io)?;
+        gpt.remove(slot as u32)?;
+        Ok(gpt.write(block_io)?)
+    }
 
-        // LBAs
-        entry_buf[offset + 32..offset + 40].copy_from_slice(&start_lba.to_le_bytes());
-        entry_buf[offset + 40..offset + 48].copy_from_slice(&end_lba.to_le_bytes());
+    /// Move the partition in GPT slot `slot` so it starts at `start_lba`,
+    /// keeping its size
+    ///
+    /// Only the entry changes; copying the data is up to the caller.
+    pub fn move_partition<B: BlockIo>(
+        block_io: &mut B,
+        slot: u8,
+        start_lba: u64,
+    ) -> DiskResult<()> {
+        let mut gpt = read_gpt(block_io)?;
+        let entry = gpt.entry(slot as u32).ok_or(DiskError::PartitionNotFound)?;
+        let end_lba = start_lba
+            .checked_add(entry.end_lba - entry.start_lba)
+            .ok_or(DiskError::InvalidSize)?;
+
+        // The new range may only overlap the partition's own old range
+        gpt.set_range(slot as u32, start_lba, end_lba)?;
+        Ok(gpt.write(block_io)?)
+    }
 
-        // Attributes (zero)
-        entry_buf[offset + 48..offset + 56].fill(0);
+    /// First and last usable LBA of the disk
+    pub fn usable_range<B: BlockIo>(block_io: &mut B) -> DiskResult<(u64, u64)> {
+        Ok(read_gpt(block_io)?.usable_range())
+    }
 
-        // Name (UTF-16LE)
-        let name_bytes = name.as_bytes();
-        for (i, &b) in name_bytes.iter().take(36).enumerate() {
-            entry_buf[offset + 56 + i * 2] = b;
-            entry_buf[offset + 56 + i * 2 + 1] = 0;
-        }
+    /// Disk GUID from the GPT header, as stored on disk
+    pub fn disk_guid<B: BlockIo>(block_io: &mut B) -> DiskResult<[u8; 16]> {
+        Ok(read_gpt(block_io)?.header().disk_guid)
+    }
 
-        // Calculate CRC32 for partition entry array
-        let array_crc = crc32(&entry_buf[..MAX_PARTITION_ENTRIES * PARTITION_ENTRY_SIZE]);
+    /// GPT slot of the partition spanning exactly `start_lba..=end_lba`
+    pub fn find_partition<B: BlockIo>(
+        block_io: &mut B,
+        start_lba: u64,
+        end_lba: u64,
+    ) -> DiskResult<Option<u8>> {
+        let (partitions, count) = Self::scan_partitions(block_io)?;
+        Ok(partitions[..count]
+            .iter()
+            .find(|p| p.start_lba == start_lba && p.end_lba == end_lba)
+            .map(|p| p.index))
+    }
+}
 
-        // Update primary header with new array CRC
-        primary_header[88..92].copy_from_slice(&array_crc.to_le_bytes());
+#[cfg(test)]
+mod tests {
+    use super::super::types::{guid, SECTOR_SIZE};
+    use super::*;
+    use alloc::collections::BTreeMap;
+    use core::fmt;
+    use gpt_disk_types::Lba;
 
-        // Recalculate primary header CRC32
-        primary_header[16..20].fill(0); // Zero CRC field first
-        let primary_header_crc = crc32(&primary_header[0..92]);
-        primary_header[16..20].copy_from_slice(&primary_header_crc.to_le_bytes());
+    /// 4 TiB in 512-byte sectors - twice what 32-bit LBAs can address
+    const BIG_DISK: u64 = 1 << 33;
 
-        // === Write primary GPT ===
+    #[derive(Debug)]
+    struct SparseError;
 
-        // Write primary partition entries
-        for i in 0..32 {
-            let sector_buf = &entry_buf[i * SECTOR_SIZE..(i + 1) * SECTOR_SIZE];
-            block_io
-                .write_blocks(Lba(entry_lba + i as u64), sector_buf)
-                .map_err(|_| DiskError::IoError)?;
+    impl fmt::Display for SparseError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "SparseError")
         }
+    }
 
-        // Write primary header
-        block_io
-            .write_blocks(Lba(1), &primary_header)
-            .map_err(|_| DiskError::IoError)?;
-
-        // === Create and write backup GPT ===
+    /// Disk that only stores sectors that were written; the rest read as zero
+    struct SparseDisk<'a> {
+        sectors: &'a mut BTreeMap<u64, [u8; SECTOR_SIZE]>,
+    }
 
-        // Backup partition entries come BEFORE the backup header
-        let backup_entries_lba = alternate_lba - 32;
+    impl BlockIo for SparseDisk<'_> {
+        type Error = SparseError;
 
-        // Write backup partition entries (same as primary)
-        for i in 0..32 {
-            let sector_buf = &entry_buf[i * SECTOR_SIZE..(i + 1) * SECTOR_SIZE];
-            block_io
-                .write_blocks(Lba(backup_entries_lba + i as u64), sector_buf)
-                .map_err(|_| DiskError::IoError)?;
+        fn block_size(&self) -> gpt_disk_types::BlockSize {
+            gpt_disk_types::BlockSize::BS_512
         }
 
-        // Create backup header (copy of primary with swapped LBAs)
-        let mut backup_header = primary_header;
-
-        // Swap my_lba and alternate_lba
-        backup_header[24..32].copy_from_slice(&alternate_lba.to_le_bytes()); // My LBA = backup position
-        backup_header[32..40].copy_from_slice(&my_lba.to_le_bytes()); // Alternate = primary position
+        fn num_blocks(&mut self) -> Result<u64, Self::Error> {
+            Ok(BIG_DISK)
+        }
 
-        // Update partition entry array LBA to backup position
-        backup_header[72..80].copy_from_slice(&backup_entries_lba.to_le_bytes());
+        fn read_blocks(&mut self, lba: Lba, buffer: &mut [u8]) -> Result<(), Self::Error> {
+            for (i, chunk) in buffer.chunks_mut(SECTOR_SIZE).enumerate() {
+                let sector = lba.0 + i as u64;
+                if sector >= BIG_DISK {
+                    return Err(SparseError);
+                }
+                match self.sectors.get(&sector) {
+                    Some(data) => chunk.copy_from_slice(data),
+                    None => chunk.fill(0),
+                }
+            }
+            Ok(())
+        }
 
-        // Partition array CRC is the same (same entries)
-        backup_header[88..92].copy_from_slice(&array_crc.to_le_bytes());
+        fn write_blocks(&mut self, lba: Lba, buffer: &[u8]) -> Result<(), Self::Error> {
+            for (i, chunk) in buffer.chunks(SECTOR_SIZE).enumerate() {
+                let sector = lba.0 + i as u64;
+                if sector >= BIG_DISK {
+                    return Err(SparseError);
+                }
+                self.sectors.insert(sector, chunk.try_into().unwrap());
+            }
+            Ok(())
+        }
 
-        // Recalculate backup header CRC32
-        backup_header[16..20].fill(0);
-        let backup_header_crc = crc32(&backup_header[0..92]);
-        backup_header[16..20].copy_from_slice(&backup_header_crc.to_le_bytes());
+        fn flush(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
 
-        // Write backup header
-        block_io
-            .write_blocks(Lba(alternate_lba), &backup_header)
-            .map_err(|_| DiskError::IoError)?;
+    fn disk(sectors: &mut BTreeMap<u64, [u8; SECTOR_SIZE]>) -> SparseDisk<'_> {
+        SparseDisk { sectors }
+    }
 
-        // Flush all writes
-        block_io.flush().map_err(|_| DiskError::IoError)?;
+    fn big_disk() -> BTreeMap<u64, [u8; SECTOR_SIZE]> {
+        let mut sectors = BTreeMap::new();
+        morpheus_core::disk::gpt_ops::create_gpt(disk(&mut sectors), BIG_DISK).unwrap();
+        sectors
+    }
 
-        Ok(slot as u8)
+    #[test]
+    fn test_chunk_partitions_beyond_2tib() {
+        let mut sectors = big_disk();
+        let start = 4_294_967_296u64; // First LBA a u32 can't hold
+        let end = start + 8_388_607; // 4 GiB
+
+        let slot = GptOps::create_partition(
+            &mut disk(&mut sectors),
+            start,
+            end,
+            guid::BASIC_DATA,
+            "chunk",
+        )
+        .unwrap();
+
+        let (partitions, count) = GptOps::scan_partitions(&mut disk(&mut sectors)).unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(
+            (partitions[0].start_lba, partitions[0].end_lba),
+            (start, end)
+        );
+        assert_eq!(
+            GptOps::find_partition(&mut disk(&mut sectors), start, end).unwrap(),
+            Some(slot)
+        );
+
+        // Largest gap is everything below the partition; the one above it
+        // runs to the last usable LBA
+        let needed = 8 * 1024 * 1024;
+        let (free_start, _) =
+            GptOps::find_free_space(&mut disk(&mut sectors), PlacementPolicy::LargestGap, needed)
+                .unwrap();
+        assert_eq!(free_start, 2048);
+        let (_, free_end) =
+            GptOps::find_free_space(&mut disk(&mut sectors), PlacementPolicy::EndOfDisk, needed)
+                .unwrap();
+        assert!(free_end <= BIG_DISK - 34 && free_end > BIG_DISK - 34 - 2048);
+        assert!(!GptOps::verify_range_free(&mut disk(&mut sectors), end, end + 10).unwrap());
+        assert!(
+            GptOps::verify_range_free(&mut disk(&mut sectors), end + 1, BIG_DISK - 34).unwrap()
+        );
+
+        // Backup header sits on the last sector and matches the primary's array CRC
+        let mut primary = [0u8; SECTOR_SIZE];
+        let mut backup = [0u8; SECTOR_SIZE];
+        disk(&mut sectors)
+            .read_blocks(Lba(1), &mut primary)
+            .unwrap();
+        disk(&mut sectors)
+            .read_blocks(Lba(BIG_DISK - 1), &mut backup)
+            .unwrap();
+        assert_eq!(&backup[0..8], b"EFI PART");
+        assert_eq!(backup[88..92], primary[88..92]);
+        assert_eq!(
+            GptOps::disk_guid(&mut disk(&mut sectors)).unwrap()[..],
+            primary[56..72]
+        );
     }
-}
 
-/// CRC32 (IEEE 802.3 polynomial) - allocation-free implementation
-fn crc32(data: &[u8]) -> u32 {
-    const POLYNOMIAL: u32 = 0xEDB88320;
-    let mut crc = 0xFFFF_FFFFu32;
-
-    for &byte in data {
-        crc ^= byte as u32;
-        for _ in 0..8 {
-            if crc & 1 != 0 {
-                crc = (crc >> 1) ^ POLYNOMIAL;
-            } else {
-                crc >>= 1;
-            }
-        }
+    #[test]
+    fn test_usable_range_validation() {
+        let mut sectors = big_disk();
+
+        // Into the backup partition array
+        assert_eq!(
+            GptOps::create_partition(
+                &mut disk(&mut sectors),
+                BIG_DISK - 100,
+                BIG_DISK - 2,
+                guid::BASIC_DATA,
+                "bad"
+            ),
+            Err(DiskError::InvalidSize)
+        );
+        assert!(!GptOps::verify_range_free(&mut disk(&mut sectors), 2048, BIG_DISK - 1).unwrap());
     }
 
-    !crc
+    #[test]
+    fn test_move_partition() {
+        let mut sectors = big_disk();
+        let a =
+            GptOps::create_partition(&mut disk(&mut sectors), 2048, 4095, guid::BASIC_DATA, "a")
+                .unwrap();
+        GptOps::create_partition(&mut disk(&mut sectors), 8192, 10239, guid::BASIC_DATA, "b")
+            .unwrap();
+
+        // Onto its neighbour
+        assert_eq!(
+            GptOps::move_partition(&mut disk(&mut sectors), a, 7168),
+            Err(DiskError::NoFreeSpace)
+        );
+        // Overlapping its own old range is fine
+        GptOps::move_partition(&mut disk(&mut sectors), a, 3072).unwrap();
+        assert_eq!(
+            GptOps::find_partition(&mut disk(&mut sectors), 3072, 5119).unwrap(),
+            Some(a)
+        );
+        assert_eq!(
+            GptOps::move_partition(&mut disk(&mut sectors), a, BIG_DISK - 40),
+            Err(DiskError::InvalidSize)
+        );
+        assert_eq!(
+            GptOps::move_partition(&mut disk(&mut sectors), 7, 2048),
+            Err(DiskError::PartitionNotFound)
+        );
+        assert_eq!(
+            GptOps::usable_range(&mut disk(&mut sectors)).unwrap(),
+            (34, BIG_DISK - 34)
+        );
+    }
 }
@@ -0,0 +1,489 @@
+//! Intent journal for multi-step disk operations.
+//!
+//! Putting an ISO on disk takes several dependent writes: the GPT entry, the
+//! data, then the manifest on the ESP. Power loss between them leaves a
+//! partition nothing points at, or data without its manifest. Before each
+//! step the journal records what is about to happen; the recovery pass on
+//! the next run reads the record and either rolls the operation back or
+//! finishes it.
+//!
+//! Compaction uses the same record to move a chunk partition: the copy is
+//! resumable, so an interrupted move is finished rather than undone.
+//!
+//! The journal is a single sector: the first data sector of
+//! `/.iso/INTENT.JNL` on the ESP. The file is created once through the FAT32
+//! code and afterwards rewritten in place, so every update is one sector
+//! write and never touches the FAT or directory.
+//!
+//! # Record Format (512 bytes)
+//!
+//! ```text
+//! Offset  Size  Field
+//! 0x00    8     Magic "MXJNL\x01\0\0"
+//! 0x08    1     Step
+//! 0x09    3     Reserved
+//! 0x0C    4     Sequence number
+//! 0x10    8     Partition start LBA
+//! 0x18    8     Partition end LBA (inclusive)
+//! 0x20    8     ISO size in bytes
+//! 0x28    4     CRC32 of the sector with this field zeroed
+//! 0x2C    4     Reserved
+//! 0x30    64    ISO name (null-terminated)
+//! 0x70    8     Relocation target LBA
+//! 0x78    8     Sectors already relocated
+//! ```
+//!
+//! A sector without the magic or with a bad CRC (a torn write) reads as
+//! idle: the previous record is gone, but so is any claim it made.
+
+use gpt_disk_io::BlockIo;
+use gpt_disk_types::Lba;
+
+use super::gpt::GptOps;
+use super::manifest::{crc32, ManifestReader};
+use super::scan::locate_iso_file;
+use super::types::{DiskError, DiskResult, MAX_ISO_NAME_LEN, SECTOR_SIZE};
+
+/// Magic bytes identifying a journal record
+pub const JOURNAL_MAGIC: [u8; 8] = *b"MXJNL\x01\x00\x00";
+
+/// Journal file on the ESP, created by the caller if missing
+pub const JOURNAL_PATH: &str = "/.iso/INTENT.JNL";
+
+/// 8.3 directory name of [`JOURNAL_PATH`]
+const JOURNAL_SHORT_NAME: &[u8; 11] = b"INTENT  JNL";
+
+const CRC_OFFSET: usize = 0x28;
+const NAME_OFFSET: usize = 0x30;
+
+/// Step of an ISO write that was started and not yet committed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JournalStep {
+    /// No operation in progress
+    Idle,
+    /// GPT entry about to be written
+    CreatePartition,
+    /// Chunk partition about to be formatted
+    Format,
+    /// Partition exists, ISO data being written
+    WriteData,
+    /// Data is durable, manifest about to be written
+    WriteManifest,
+    /// Chunk partition being moved to `dest_lba`
+    Relocate,
+}
+
+impl JournalStep {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Idle => "idle",
+            Self::CreatePartition => "create partition",
+            Self::Format => "format",
+            Self::WriteData => "write data",
+            Self::WriteManifest => "write manifest",
+            Self::Relocate => "relocate",
+        }
+    }
+
+    /// Whether recovery undoes this step rather than finishing it.
+    ///
+    /// Everything before the manifest is rolled back: without a manifest
+    /// the data can't be found, and its size can't be trusted. Once the
+    /// data is durable, finishing is cheaper than throwing it away.
+    pub const fn rolls_back(&self) -> bool {
+        matches!(self, Self::CreatePartition | Self::Format | Self::WriteData)
+    }
+
+    const fn to_byte(self) -> u8 {
+        match self {
+            Self::Idle => 0,
+            Self::CreatePartition => 1,
+            Self::Format => 2,
+            Self::WriteData => 3,
+            Self::WriteManifest => 4,
+            Self::Relocate => 5,
+        }
+    }
+
+    const fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Self::Idle),
+            1 => Some(Self::CreatePartition),
+            2 => Some(Self::Format),
+            3 => Some(Self::WriteData),
+            4 => Some(Self::WriteManifest),
+            5 => Some(Self::Relocate),
+            _ => None,
+        }
+    }
+}
+
+/// What an operation in progress is doing, and where
+#[derive(Debug, Clone, Copy)]
+pub struct JournalEntry {
+    pub step: JournalStep,
+    /// First sector of the ISO partition
+    pub start_lba: u64,
+    /// Last sector of the ISO partition (inclusive)
+    pub end_lba: u64,
+    /// Expected ISO size in bytes
+    pub iso_size: u64,
+    /// Where a relocated partition starts once moved
+    pub dest_lba: u64,
+    /// Sectors of a relocation copied so far, counted from the end
+    pub copied: u64,
+    name: [u8; MAX_ISO_NAME_LEN],
+    name_len: usize,
+}
+
+impl JournalEntry {
+    pub fn new(
+        step: JournalStep,
+        iso_name: &str,
+        start_lba: u64,
+        end_lba: u64,
+        iso_size: u64,
+    ) -> Self {
+        let mut name = [0u8; MAX_ISO_NAME_LEN];
+        let len = iso_name.len().min(MAX_ISO_NAME_LEN - 1);
+        name[..len].copy_from_slice(&iso_name.as_bytes()[..len]);
+
+        Self {
+            step,
+            start_lba,
+            end_lba,
+            iso_size,
+            dest_lba: 0,
+            copied: 0,
+            name,
+            name_len: len,
+        }
+    }
+
+    /// Move of the partition at `start_lba..=end_lba` to `dest_lba`.
+    pub fn relocation(iso_name: &str, start_lba: u64, end_lba: u64, dest_lba: u64) -> Self {
+        let mut entry = Self::new(JournalStep::Relocate, iso_name, start_lba, end_lba, 0);
+        entry.dest_lba = dest_lba;
+        entry
+    }
+
+    const fn idle() -> Self {
+        Self {
+            step: JournalStep::Idle,
+            start_lba: 0,
+            end_lba: 0,
+            iso_size: 0,
+            dest_lba: 0,
+            copied: 0,
+            name: [0u8; MAX_ISO_NAME_LEN],
+            name_len: 0,
+        }
+    }
+
+    pub fn name_str(&self) -> &str {
+        core::str::from_utf8(&self.name[..self.name_len]).unwrap_or("")
+    }
+
+    fn serialize(&self, sequence: u32, sector: &mut [u8; SECTOR_SIZE]) {
+        sector.fill(0);
+        sector[0..8].copy_from_slice(&JOURNAL_MAGIC);
+        sector[0x08] = self.step.to_byte();
+        sector[0x0C..0x10].copy_from_slice(&sequence.to_le_bytes());
+        sector[0x10..0x18].copy_from_slice(&self.start_lba.to_le_bytes());
+        sector[0x18..0x20].copy_from_slice(&self.end_lba.to_le_bytes());
+        sector[0x20..0x28].copy_from_slice(&self.iso_size.to_le_bytes());
+        sector[0x70..0x78].copy_from_slice(&self.dest_lba.to_le_bytes());
+        sector[0x78..0x80].copy_from_slice(&self.copied.to_le_bytes());
+        sector[NAME_OFFSET..NAME_OFFSET + self.name_len]
+            .copy_from_slice(&self.name[..self.name_len]);
+
+        let crc = crc32(sector);
+        sector[CRC_OFFSET..CRC_OFFSET + 4].copy_from_slice(&crc.to_le_bytes());
+    }
+
+    /// Entry and sequence number, or `None` for a blank or torn record.
+    fn deserialize(sector: &[u8; SECTOR_SIZE]) -> Option<(Self, u32)> {
+        if sector[0..8] != JOURNAL_MAGIC {
+            return None;
+        }
+
+        let mut check = *sector;
+        check[CRC_OFFSET..CRC_OFFSET + 4].fill(0);
+        if read_u32(sector, CRC_OFFSET) != crc32(&check) {
+            return None;
+        }
+
+        let step = JournalStep::from_byte(sector[0x08])?;
+        let name = &sector[NAME_OFFSET..NAME_OFFSET + MAX_ISO_NAME_LEN - 1];
+        let name_len = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+
+        let mut entry = Self {
+            step,
+            start_lba: read_u64(sector, 0x10),
+            end_lba: read_u64(sector, 0x18),
+            iso_size: read_u64(sector, 0x20),
+            dest_lba: read_u64(sector, 0x70),
+            copied: read_u64(sector, 0x78),
+            name: [0u8; MAX_ISO_NAME_LEN],
+            name_len,
+        };
+        entry.name[..name_len].copy_from_slice(&name[..name_len]);
+        Some((entry, read_u32(sector, 0x0C)))
+    }
+}
+
+/// Outcome of [`Journal::recover`]
+#[derive(Debug, Clone, Copy)]
+pub enum Recovery {
+    /// Nothing was interrupted
+    Clean,
+    /// The interrupted operation's partition was removed (or never made it
+    /// into the GPT)
+    RolledBack { start_lba: u64, end_lba: u64 },
+    /// The manifest of an interrupted operation turned out to be on disk
+    Completed,
+    /// Data is on disk but its manifest is missing. The caller writes the
+    /// manifest (it needs the FAT32 writer) and then calls
+    /// [`Journal::commit`].
+    WriteManifest(JournalEntry),
+    /// A chunk partition move was interrupted. The caller finishes it
+    /// with `CompactPlan::resume`, which commits.
+    Relocate(JournalEntry),
+}
+
+/// Handle on the journal sector
+pub struct Journal {
+    lba: u64,
+    sequence: u32,
+    entry: JournalEntry,
+}
+
+impl Journal {
+    /// Open the journal on the ESP at `esp_start_lba`.
+    ///
+    /// `None` if [`JOURNAL_PATH`] doesn't exist yet; create it (one zeroed
+    /// sector is enough) and open again.
+    pub fn open<B: BlockIo>(block_io: &mut B, esp_start_lba: u64) -> DiskResult<Option<Self>> {
+        let Some(lba) = locate_iso_file(block_io, esp_start_lba, JOURNAL_SHORT_NAME)? else {
+            return Ok(None);
+        };
+
+        let mut sector = [0u8; SECTOR_SIZE];
+        block_io
+            .read_blocks(Lba(lba), &mut sector)
+            .map_err(|_| DiskError::IoError)?;
+
+        let (entry, sequence) =
+            JournalEntry::deserialize(&sector).unwrap_or((JournalEntry::idle(), 0));
+        Ok(Some(Self {
+            lba,
+            sequence,
+            entry,
+        }))
+    }
+
+    /// The uncommitted operation, if any
+    pub fn pending(&self) -> Option<&JournalEntry> {
+        (self.entry.step != JournalStep::Idle).then_some(&self.entry)
+    }
+
+    /// Record the first step of a new operation.
+    pub fn begin<B: BlockIo>(&mut self, block_io: &mut B, entry: JournalEntry) -> DiskResult<()> {
+        self.store(block_io, entry)
+    }
+
+    /// Move the current operation on to `step`.
+    pub fn advance<B: BlockIo>(&mut self, block_io: &mut B, step: JournalStep) -> DiskResult<()> {
+        if self.entry.step == JournalStep::Idle {
+            return Err(DiskError::InvalidParameter);
+        }
+        let mut entry = self.entry;
+        entry.step = step;
+        self.store(block_io, entry)
+    }
+
+    /// Record how far the current relocation has copied.
+    pub fn record_copied<B: BlockIo>(&mut self, block_io: &mut B, copied: u64) -> DiskResult<()> {
+        if self.entry.step != JournalStep::Relocate {
+            return Err(DiskError::InvalidParameter);
+        }
+        let mut entry = self.entry;
+        entry.copied = copied;
+        self.store(block_io, entry)
+    }
+
+    /// Mark the current operation as finished.
+    pub fn commit<B: BlockIo>(&mut self, block_io: &mut B) -> DiskResult<()> {
+        self.store(block_io, JournalEntry::idle())
+    }
+
+    /// Roll back or finish whatever the last run left uncommitted.
+    pub fn recover<B: BlockIo>(
+        &mut self,
+        block_io: &mut B,
+        esp_start_lba: u64,
+    ) -> DiskResult<Recovery> {
+        let entry = self.entry;
+        if entry.step == JournalStep::Idle {
+            return Ok(Recovery::Clean);
+        }
+        if entry.step == JournalStep::Relocate {
+            return Ok(Recovery::Relocate(entry));
+        }
+
+        if entry.step.rolls_back() {
+            if let Some(slot) = GptOps::find_partition(block_io, entry.start_lba, entry.end_lba)? {
+                GptOps::delete_partition(block_io, slot)?;
+            }
+            self.commit(block_io)?;
+            return Ok(Recovery::RolledBack {
+                start_lba: entry.start_lba,
+                end_lba: entry.end_lba,
+            });
+        }
+
+        let present = ManifestReader::scan_all(block_io, esp_start_lba)?.any(|scanned| {
+            scanned.parsed.is_ok_and(|(info, chunks)| {
+                info.name_str() == entry.name_str()
+                    && chunks.chunks[..chunks.count]
+                        .iter()
+                        .any(|chunk| chunk.info.start_lba == entry.start_lba)
+            })
+        });
+        if present {
+            self.commit(block_io)?;
+            Ok(Recovery::Completed)
+        } else {
+            Ok(Recovery::WriteManifest(entry))
+        }
+    }
+
+    /// Write `entry` as the new record.
+    ///
+    /// Flushes before the write, so everything the previous step did is
+    /// durable before the journal claims it done, and after it, so the
+    /// record is durable before the next step starts.
+    fn store<B: BlockIo>(&mut self, block_io: &mut B, entry: JournalEntry) -> DiskResult<()> {
+        let sequence = self.sequence.wrapping_add(1);
+        let mut sector = [0u8; SECTOR_SIZE];
+        entry.serialize(sequence, &mut sector);
+
+        block_io.flush().map_err(|_| DiskError::IoError)?;
+        block_io
+            .write_blocks(Lba(self.lba), &sector)
+            .map_err(|_| DiskError::IoError)?;
+        block_io.flush().map_err(|_| DiskError::IoError)?;
+
+        self.sequence = sequence;
+        self.entry = entry;
+        Ok(())
+    }
+}
+
+fn read_u32(buffer: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(buffer[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(buffer: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(buffer[offset..offset + 8].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fat32::Fat32Formatter;
+    use super::super::manifest::{ManifestWriter, MAX_MANIFEST_SIZE};
+    use super::super::types::{guid, ChunkPartition, ChunkSet, PartitionInfo};
+    use super::*;
+    use alloc::vec;
+    use gpt_disk_io::BlockIoAdapter;
+    use gpt_disk_types::BlockSize;
+
+    /// Smallest volume the formatter accepts
+    const ESP_SECTORS: u64 = 133_120;
+
+    #[test]
+    fn test_record_roundtrip() {
+        let entry = JournalEntry::new(JournalStep::WriteData, "tails.iso", 2048, 4095, 1 << 20);
+        let mut sector = [0u8; SECTOR_SIZE];
+        entry.serialize(7, &mut sector);
+
+        let (restored, sequence) = JournalEntry::deserialize(&sector).unwrap();
+        assert_eq!(sequence, 7);
+        assert_eq!(restored.step, JournalStep::WriteData);
+        assert_eq!(restored.name_str(), "tails.iso");
+        assert_eq!((restored.start_lba, restored.end_lba), (2048, 4095));
+        assert_eq!(restored.iso_size, 1 << 20);
+
+        let mut entry = JournalEntry::relocation("tails.iso", 2048, 4095, 8192);
+        entry.copied = 512;
+        entry.serialize(8, &mut sector);
+        let (restored, _) = JournalEntry::deserialize(&sector).unwrap();
+        assert_eq!(restored.step, JournalStep::Relocate);
+        assert_eq!((restored.dest_lba, restored.copied), (8192, 512));
+        assert!(!JournalStep::Relocate.rolls_back());
+    }
+
+    #[test]
+    fn test_torn_record_reads_as_idle() {
+        let entry = JournalEntry::new(JournalStep::CreatePartition, "a.iso", 1, 2, 3);
+        let mut sector = [0u8; SECTOR_SIZE];
+        entry.serialize(1, &mut sector);
+
+        sector[0x12] ^= 0xFF;
+        assert!(JournalEntry::deserialize(&sector).is_none());
+        assert!(JournalEntry::deserialize(&[0u8; SECTOR_SIZE]).is_none());
+        assert!(JournalStep::WriteData.rolls_back());
+        assert!(!JournalStep::WriteManifest.rolls_back());
+    }
+
+    #[test]
+    fn test_journal_survives_reopen_and_completes() {
+        let mut storage = vec![0u8; ESP_SECTORS as usize * SECTOR_SIZE];
+        let mut disk = BlockIoAdapter::new(storage.as_mut_slice(), BlockSize::BS_512);
+        Fat32Formatter::format(&mut disk, 0, ESP_SECTORS, "ESP").unwrap();
+
+        assert!(Journal::open(&mut disk, 0).unwrap().is_none());
+        morpheus_core::fs::create_directory(&mut disk, 0, "/.iso").unwrap();
+        morpheus_core::fs::write_file(&mut disk, 0, JOURNAL_PATH, &[0u8; SECTOR_SIZE]).unwrap();
+
+        let mut journal = Journal::open(&mut disk, 0).unwrap().unwrap();
+        assert!(journal.pending().is_none());
+        let entry = JournalEntry::new(
+            JournalStep::CreatePartition,
+            "tails.iso",
+            200_000,
+            300_000,
+            1234,
+        );
+        journal.begin(&mut disk, entry).unwrap();
+        journal
+            .advance(&mut disk, JournalStep::WriteManifest)
+            .unwrap();
+
+        // Interrupted before the manifest: recovery asks for it
+        let mut journal = Journal::open(&mut disk, 0).unwrap().unwrap();
+        assert_eq!(journal.pending().unwrap().step, JournalStep::WriteManifest);
+        let Recovery::WriteManifest(pending) = journal.recover(&mut disk, 0).unwrap() else {
+            panic!("expected manifest write");
+        };
+        assert_eq!(pending.name_str(), "tails.iso");
+
+        // Interrupted after the manifest: recovery only commits
+        let mut chunks = ChunkSet::new();
+        let part = PartitionInfo::new(1, 200_000, 300_000, guid::BASIC_DATA);
+        chunks.add(ChunkPartition::new(part, 0)).unwrap();
+        let mut buffer = [0u8; MAX_MANIFEST_SIZE];
+        let len = ManifestWriter::new("tails.iso", 1234)
+            .serialize(&chunks, &mut buffer)
+            .unwrap();
+        morpheus_core::fs::write_file(&mut disk, 0, "/.iso/TAILS.MFS", &buffer[..len]).unwrap();
+
+        assert!(matches!(
+            journal.recover(&mut disk, 0).unwrap(),
+            Recovery::Completed
+        ));
+        let journal = Journal::open(&mut disk, 0).unwrap().unwrap();
+        assert!(journal.pending().is_none());
+        assert_eq!(journal.sequence, 3);
+    }
+}
@@ -8,9 +8,12 @@ extern crate alloc;
 
 use gpt_disk_io::BlockIo;
 use gpt_disk_types::Lba;
+use morpheus_gpt::part_type;
 
+use super::scan::ManifestScan;
 use super::types::{
-    ChunkSet, DiskError, DiskResult, MAX_CHUNK_PARTITIONS, MAX_ISO_NAME_LEN, SECTOR_SIZE,
+    ChunkSet, DiskError, DiskResult, PartitionInfo, MAX_CHUNK_PARTITIONS, MAX_ISO_NAME_LEN,
+    SECTOR_SIZE,
 };
 
 /// Manifest magic: "MXISO\x01\x00\x00" (v1 - compatible with morpheus_core)
@@ -77,6 +80,15 @@ impl ManifestWriter {
         }
     }
 
+    /// Writer reproducing a parsed manifest, for rewriting it with new
+    /// chunk locations
+    pub fn from_info(info: &IsoManifestInfo) -> Self {
+        let mut writer = Self::new(info.name_str(), info.total_size);
+        writer.sha256 = info.sha256;
+        writer.flags = info.flags;
+        writer
+    }
+
     /// Set SHA256 hash
     pub fn set_hash(&mut self, hash: &[u8; 32]) {
         self.sha256.copy_from_slice(hash);
@@ -151,6 +163,9 @@ impl ManifestWriter {
 
             // Flags (written bit)
             buffer[offset + 41] = if chunk.complete { 0x01 } else { 0x00 };
+
+            // Disk ID
+            buffer[offset + 44..offset + 48].copy_from_slice(&chunk.disk_id.to_le_bytes());
         }
 
         // Calculate and write header CRC32
@@ -232,11 +247,15 @@ impl ManifestWriter {
         let short_name = make_8_3_filename(name_str);
         let path = format!("/.iso/{}", short_name);
 
+        let mut volume = morpheus_core::fs::Fat32Volume::open(block_io, esp_start_lba)
+            .map_err(|_| DiskError::ManifestError)?;
+
         // Ensure .iso directory exists
-        let _ = morpheus_core::fs::create_directory(block_io, esp_start_lba, "/.iso");
+        let _ = volume.create_directory("/.iso");
 
         // Write manifest file
-        morpheus_core::fs::write_file(block_io, esp_start_lba, &path, &buffer[..len])
+        volume
+            .write_file(&path, &buffer[..len])
             .map_err(|_| DiskError::ManifestError)?;
 
         Ok(())
@@ -325,12 +344,14 @@ impl ManifestReader {
                 u64::from_le_bytes(buffer[offset + 32..offset + 40].try_into().unwrap());
             let chunk_index = buffer[offset + 40];
             let chunk_flags = buffer[offset + 41];
+            let disk_id = u32::from_le_bytes(buffer[offset + 44..offset + 48].try_into().unwrap());
 
             let part_info =
                 super::types::PartitionInfo::new(i as u8, start_lba, end_lba, type_guid);
             let mut chunk = super::types::ChunkPartition::new(part_info, chunk_index);
             chunk.bytes_written = data_size;
             chunk.complete = chunk_flags & 0x01 != 0;
+            chunk.disk_id = disk_id;
 
             chunks.add(chunk)?;
             chunks.bytes_written += data_size;
@@ -346,6 +367,33 @@ impl ManifestReader {
         Ok((info, chunks))
     }
 
+    /// Enumerate every manifest under `/.iso/` on a FAT32 ESP
+    ///
+    /// Allocation-free and bounded to
+    /// [`MAX_SCANNED_MANIFESTS`](super::scan::MAX_SCANNED_MANIFESTS)
+    /// entries. A missing `/.iso` directory scans as empty.
+    pub fn scan_all<B: BlockIo>(
+        block_io: &mut B,
+        esp_start_lba: u64,
+    ) -> DiskResult<ManifestScan<'_, B>> {
+        ManifestScan::new(block_io, esp_start_lba)
+    }
+
+    /// Check whether a manifest points at partitions that no longer exist
+    ///
+    /// A chunk is present if its LBA range lies inside one of `partitions`
+    /// (as returned by `GptOps::scan_partitions`) that has a chunk type.
+    /// The manifest is stale if any chunk is missing.
+    pub fn is_stale(chunks: &ChunkSet, partitions: &[PartitionInfo]) -> bool {
+        chunks.chunks[..chunks.count].iter().any(|chunk| {
+            !partitions.iter().any(|part| {
+                part_type::is_chunk_type(&part.type_guid)
+                    && part.start_lba <= chunk.info.start_lba
+                    && chunk.info.end_lba <= part.end_lba
+            })
+        })
+    }
+
     /// Read manifest from ESP
     pub fn read_from_esp<B: BlockIo>(
         block_io: &mut B,
@@ -407,20 +455,4 @@ impl IsoManifestInfo {
 }
 
 /// CRC32 (IEEE 802.3)
-fn crc32(data: &[u8]) -> u32 {
-    const POLYNOMIAL: u32 = 0xEDB88320;
-    let mut crc = 0xFFFF_FFFFu32;
-
-    for &byte in data {
-        crc ^= byte as u32;
-        for _ in 0..8 {
-            if crc & 1 != 0 {
-                crc = (crc >> 1) ^ POLYNOMIAL;
-            } else {
-                crc >>= 1;
-            }
-        }
-    }
-
-    !crc
-}
+pub(super) use morpheus_core::hash::crc32;
@@ -21,7 +21,7 @@
 //!          └─────────────────┼─────────────────┘
 //!                            ▼
 //! ┌────────────────────────────────────────────────────────────┐
-//! │               VirtioBlkBlockIo (driver adapter)            │
+//! │          UnifiedBlockIo (VirtIO-blk or AHCI adapter)       │
 //! └────────────────────────────────────────────────────────────┘
 //! ```
 //!
@@ -31,18 +31,45 @@
 //! 2. **Streaming writes** - Data written as it arrives from HTTP download
 //! 3. **Chunk partitions** - ISO split across FAT32 partitions (4GB limit each)
 //! 4. **Manifest tracking** - Binary manifest for bootloader to find chunks
+//! 5. **Manifest scan** - `ManifestReader::scan_all` lists `/.iso` without a heap
+//! 6. **Intent journal** - `Journal` records each step so an interrupted write
+//!    is rolled back or finished on the next run
+//! 7. **Compaction** - `CompactPlan` packs chunk partitions towards the end of
+//!    the disk so free space collects in one gap
+//! 8. **Reuse** - `ReusePlan` writes a re-download over the ISO's old chunk
+//!    partitions instead of leaking them
+//! 9. **Resume** - `ResumePoint` finds a partial download to continue where
+//!    it stopped
 
+#[cfg(feature = "fat32_manifest")]
+mod compact;
 mod fat32;
 mod gpt;
+mod journal;
 mod manifest;
+mod placement;
+#[cfg(feature = "fat32_manifest")]
+mod resume;
+#[cfg(feature = "fat32_manifest")]
+mod reuse;
+mod scan;
 mod types;
 mod writer;
 
+#[cfg(feature = "fat32_manifest")]
+pub use compact::{CompactPlan, CompactProgress, CompactProgressFn, Relocation, MAX_RELOCATIONS};
 pub use fat32::{Fat32Formatter, Fat32Info};
 pub use gpt::GptOps;
+pub use journal::{Journal, JournalEntry, JournalStep, Recovery, JOURNAL_PATH};
 pub use manifest::{IsoManifestInfo, ManifestReader, ManifestWriter};
+pub use placement::{DiskPreference, DiskSelector, Placement, PlacementPolicy};
+#[cfg(feature = "fat32_manifest")]
+pub use resume::ResumePoint;
+#[cfg(feature = "fat32_manifest")]
+pub use reuse::ReusePlan;
+pub use scan::{ManifestScan, ScannedManifest, MAX_SCANNED_MANIFESTS};
 pub use types::{
     ChunkPartition, ChunkSet, DiskError, DiskResult, PartitionInfo, MAX_CHUNK_PARTITIONS,
     SECTOR_SIZE,
 };
-pub use writer::IsoWriter;
+pub use writer::{print_chunk_progress, ChunkProgress, IsoWriter, ProgressFn};
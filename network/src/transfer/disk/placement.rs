@@ -0,0 +1,259 @@
+//! Chunk partition placement policy.
+//!
+//! Decides which free gap a new ISO partition goes into and where inside
+//! that gap, plus which disks it may land on at all. The user picks a
+//! policy before the download; it travels in `DownloadConfig`, along with
+//! [`DiskSelector`]s naming the target disk and the disk holding the ESP
+//! when more than one block device is present.
+
+use morpheus_core::disk::identity::{DeviceIdentity, MediaKind};
+
+use crate::driver::block_traits::BlockDeviceInfo;
+
+use super::types::SECTOR_SIZE;
+
+/// Which free gap a partition goes into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PlacementPolicy {
+    /// Largest gap, partition at its start
+    #[default]
+    LargestGap,
+    /// Last gap that fits, partition at its end - keeps the space right
+    /// after existing partitions free for them to grow into
+    EndOfDisk,
+    /// Smallest gap that fits, so larger gaps stay in one piece
+    Contiguous,
+}
+
+impl PlacementPolicy {
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::LargestGap => "largest gap",
+            Self::EndOfDisk => "end of disk",
+            Self::Contiguous => "keep contiguous",
+        }
+    }
+
+    /// Next policy, for cycling through them in a settings screen.
+    pub const fn next(self) -> Self {
+        match self {
+            Self::LargestGap => Self::EndOfDisk,
+            Self::EndOfDisk => Self::Contiguous,
+            Self::Contiguous => Self::LargestGap,
+        }
+    }
+
+    /// Pick a gap (inclusive LBA range) from `gaps`, sorted by start LBA,
+    /// that holds `needed` aligned sectors.
+    pub fn choose(self, gaps: &[(u64, u64)], needed: u64) -> Option<(u64, u64)> {
+        let mut fitting = gaps
+            .iter()
+            .copied()
+            .filter(move |&gap| self.place(gap, needed).is_some());
+        match self {
+            Self::LargestGap => fitting.max_by_key(|&(start, end)| end - start),
+            Self::EndOfDisk => fitting.next_back(),
+            Self::Contiguous => fitting.min_by_key(|&(start, end)| end - start),
+        }
+    }
+
+    /// Aligned range of `needed` sectors inside `gap`, at the end the
+    /// policy prefers.
+    pub fn place(self, gap: (u64, u64), needed: u64) -> Option<(u64, u64)> {
+        let (gap_start, gap_end) = gap;
+        if needed == 0 || gap_end < gap_start {
+            return None;
+        }
+
+        let start = match self {
+            Self::EndOfDisk => {
+                let latest = (gap_end + 1).checked_sub(needed)?;
+                morpheus_gpt::align_down(latest, SECTOR_SIZE as u32)
+            }
+            Self::LargestGap | Self::Contiguous => {
+                morpheus_gpt::align_up(gap_start, SECTOR_SIZE as u32)
+            }
+        };
+        let end = start.checked_add(needed - 1)?;
+
+        (start >= gap_start && end <= gap_end).then_some((start, end))
+    }
+}
+
+/// Which disks a partition may go on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DiskPreference {
+    #[default]
+    Any,
+    /// The disk with this model and serial number, when it is present
+    Disk(DeviceIdentity),
+    /// Keep ISOs off solid state drives
+    AvoidSolidState,
+    /// Keep ISOs off spinning disks
+    AvoidRotational,
+}
+
+impl DiskPreference {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Any => "any disk",
+            Self::Disk(identity) => identity.model(),
+            Self::AvoidSolidState => "avoid SSDs",
+            Self::AvoidRotational => "avoid HDDs",
+        }
+    }
+
+    /// Whether the disk may be written at all. Media that can't be told
+    /// apart is allowed; a preferred disk is only a preference.
+    pub fn allows(&self, info: &BlockDeviceInfo) -> bool {
+        match self {
+            Self::AvoidSolidState => info.media != MediaKind::SolidState,
+            Self::AvoidRotational => info.media != MediaKind::Rotational,
+            Self::Any | Self::Disk(_) => true,
+        }
+    }
+
+    /// Whether the disk is the one asked for.
+    pub fn prefers(&self, info: &BlockDeviceInfo) -> bool {
+        match self {
+            Self::Disk(identity) => info.identity == *identity,
+            _ => self.allows(info),
+        }
+    }
+}
+
+/// A block device named by something that stays the same across boots
+/// and PCI renumbering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DiskSelector {
+    /// The first device found
+    #[default]
+    First,
+    /// The device with this model and serial number
+    Identity(DeviceIdentity),
+    /// The device whose GPT header carries this disk GUID
+    DiskGuid([u8; 16]),
+}
+
+impl DiskSelector {
+    /// Whether a device is the one named, given its info and GPT disk GUID
+    /// (`None` without a readable GPT).
+    pub fn matches(&self, info: &BlockDeviceInfo, disk_guid: Option<[u8; 16]>) -> bool {
+        match self {
+            Self::First => true,
+            Self::Identity(identity) => identity.is_known() && info.identity == *identity,
+            Self::DiskGuid(guid) => disk_guid == Some(*guid),
+        }
+    }
+
+    /// Index of the device named among `devices`, as (info, disk GUID).
+    pub fn select(&self, devices: &[(BlockDeviceInfo, Option<[u8; 16]>)]) -> Option<usize> {
+        devices
+            .iter()
+            .position(|(info, guid)| self.matches(info, *guid))
+    }
+}
+
+/// Placement settings for a download.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Placement {
+    pub policy: PlacementPolicy,
+    pub disk: DiskPreference,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 1GB in sectors
+    const GB: u64 = 2 * 1024 * 1024;
+
+    fn info(media: MediaKind, identity: DeviceIdentity) -> BlockDeviceInfo {
+        BlockDeviceInfo {
+            total_sectors: 1024 * GB,
+            sector_size: 512,
+            max_sectors_per_request: 128,
+            read_only: false,
+            removable: false,
+            identity,
+            media,
+        }
+    }
+
+    #[test]
+    fn test_choose_gap() {
+        // 10GB, 30GB and 20GB gaps
+        let gaps = [(34, 10 * GB), (20 * GB, 50 * GB), (100 * GB, 120 * GB)];
+        let needed = 15 * GB;
+
+        assert_eq!(
+            PlacementPolicy::LargestGap.choose(&gaps, needed),
+            Some(gaps[1])
+        );
+        assert_eq!(
+            PlacementPolicy::EndOfDisk.choose(&gaps, needed),
+            Some(gaps[2])
+        );
+        assert_eq!(
+            PlacementPolicy::Contiguous.choose(&gaps, needed),
+            Some(gaps[2])
+        );
+        assert_eq!(
+            PlacementPolicy::Contiguous.choose(&gaps, 25 * GB),
+            Some(gaps[1])
+        );
+        assert_eq!(PlacementPolicy::LargestGap.choose(&gaps, 40 * GB), None);
+    }
+
+    #[test]
+    fn test_place_aligned() {
+        let gap = (34, 10 * GB);
+        let (start, end) = PlacementPolicy::LargestGap.place(gap, GB).unwrap();
+        assert_eq!((start, end), (2048, 2048 + GB - 1));
+
+        let (start, end) = PlacementPolicy::EndOfDisk.place(gap, GB).unwrap();
+        assert_eq!(start % 2048, 0);
+        assert!(end <= gap.1 && gap.1 - end < 2048);
+
+        // Fits unaligned but not once aligned
+        assert_eq!(PlacementPolicy::LargestGap.place((34, 2100), 100), None);
+        assert_eq!(PlacementPolicy::EndOfDisk.place((100, 2000), 100), None);
+    }
+
+    #[test]
+    fn test_disk_preference() {
+        let ssd = info(MediaKind::SolidState, DeviceIdentity::new(b"Fast", b"SN1"));
+        let hdd = info(MediaKind::Rotational, DeviceIdentity::new(b"Slow", b"SN2"));
+        let virtio = info(MediaKind::Unknown, DeviceIdentity::unknown());
+
+        assert!(!DiskPreference::AvoidSolidState.allows(&ssd));
+        assert!(DiskPreference::AvoidSolidState.allows(&hdd));
+        assert!(DiskPreference::AvoidRotational.allows(&virtio));
+
+        let pick_hdd = DiskPreference::Disk(hdd.identity);
+        assert!(pick_hdd.allows(&ssd));
+        assert!(!pick_hdd.prefers(&ssd));
+        assert!(pick_hdd.prefers(&hdd));
+    }
+
+    #[test]
+    fn test_disk_selector() {
+        let ssd = info(MediaKind::SolidState, DeviceIdentity::new(b"Fast", b"SN1"));
+        let virtio = info(MediaKind::Unknown, DeviceIdentity::unknown());
+        let guid = [7u8; 16];
+        let devices = [(ssd, None), (virtio, Some(guid))];
+
+        assert_eq!(DiskSelector::First.select(&devices), Some(0));
+        assert_eq!(
+            DiskSelector::Identity(ssd.identity).select(&devices),
+            Some(0)
+        );
+        assert_eq!(DiskSelector::DiskGuid(guid).select(&devices), Some(1));
+        assert_eq!(DiskSelector::DiskGuid([8u8; 16]).select(&devices), None);
+        // An unknown identity names nothing, not every unidentified disk
+        assert_eq!(
+            DiskSelector::Identity(DeviceIdentity::unknown()).select(&devices),
+            None
+        );
+    }
+}
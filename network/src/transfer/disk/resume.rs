@@ -0,0 +1,206 @@
+//! Partial downloads to continue.
+//!
+//! A download that stopped part way leaves a manifest with COMPLETE clear.
+//! Its chunk's `data_size` is how much of the image is on disk, and its
+//! SHA-256 field holds the hash state after those bytes
+//! (`Sha256::midstate`). [`ResumePoint::find`] looks that manifest up
+//! before GPT prep places the download, so the partition is kept and only
+//! the rest of the image is fetched.
+//!
+//! Partial manifests from before the hash state was recorded (all zero)
+//! can't be continued; [`ReusePlan`](super::ReusePlan) writes over them.
+
+use gpt_disk_io::BlockIo;
+use morpheus_core::iso::{IsoManifest, SkipList, RAW_MANIFEST_SECTORS};
+
+use super::gpt::GptOps;
+use super::types::{DiskError, DiskResult, PartitionInfo, SECTOR_SIZE};
+
+/// A partial download found on disk
+#[derive(Debug, Clone, Copy)]
+pub struct ResumePoint {
+    /// Partition holding it; the image starts at its first sector
+    pub partition: PartitionInfo,
+    /// Bytes of the image on disk (whole sectors)
+    pub offset: u64,
+    /// SHA-256 state after `offset` bytes
+    pub sha256_state: [u8; 32],
+    /// Server Last-Modified recorded with it (0 = unknown)
+    pub last_modified: u32,
+    /// Hash of the server ETag recorded with it (0 = none)
+    pub etag_hash: u32,
+    /// Zero sectors left unwritten so far
+    pub skipped: SkipList,
+}
+
+impl ResumePoint {
+    /// The partial download of `iso_name`, `None` if there is none that
+    /// can be continued.
+    ///
+    /// `sectors_needed` and `requested_start` as for
+    /// [`ReusePlan::find`](super::ReusePlan::find): the partition has to
+    /// hold the whole image.
+    pub fn find<B: BlockIo>(
+        block_io: &mut B,
+        esp_start_lba: u64,
+        iso_name: &str,
+        sectors_needed: u64,
+        requested_start: u64,
+    ) -> DiskResult<Option<Self>> {
+        let path = alloc::format!(
+            "/.iso/{}",
+            morpheus_core::fs::generate_8_3_manifest_name(iso_name)
+        );
+        match morpheus_core::fs::file_exists(block_io, esp_start_lba, &path) {
+            Ok(true) => {}
+            Ok(false) => return Ok(None),
+            Err(_) => return Err(DiskError::IoError),
+        }
+        let data = morpheus_core::fs::read_file(block_io, esp_start_lba, &path)
+            .map_err(|_| DiskError::IoError)?;
+        let Ok(manifest) = IsoManifest::deserialize(&data) else {
+            return Ok(None);
+        };
+
+        // One chunk on this disk, part written, with the hash state
+        let chunk = &manifest.chunks.chunks[0];
+        if manifest.name_str() != iso_name
+            || manifest.is_complete()
+            || manifest.chunks.count != 1
+            || chunk.disk_id != 0
+            || chunk.data_size == 0
+            || chunk.data_size % SECTOR_SIZE as u64 != 0
+            || manifest.sha256 == [0u8; 32]
+        {
+            return Ok(None);
+        }
+
+        let (partitions, count) = GptOps::scan_partitions(block_io)?;
+        let Some(part) = partitions[..count]
+            .iter()
+            .find(|p| p.start_lba == chunk.start_lba && chunk.end_lba <= p.end_lba)
+        else {
+            return Ok(None);
+        };
+        let sectors = part.end_lba - part.start_lba + 1;
+        let data_sectors = sectors.saturating_sub(RAW_MANIFEST_SECTORS);
+        if sectors < sectors_needed
+            || (requested_start != 0 && requested_start != part.start_lba)
+            || chunk.data_size > data_sectors * SECTOR_SIZE as u64
+        {
+            return Ok(None);
+        }
+
+        Ok(Some(Self {
+            partition: *part,
+            offset: chunk.data_size,
+            sha256_state: manifest.sha256,
+            last_modified: manifest.last_modified,
+            etag_hash: manifest.etag_hash,
+            skipped: manifest.skipped,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fat32::Fat32Formatter;
+    use super::super::types::guid;
+    use super::*;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use gpt_disk_io::BlockIoAdapter;
+    use gpt_disk_types::BlockSize;
+    use morpheus_core::iso::MAX_MANIFEST_SIZE;
+
+    /// Smallest volume the formatter accepts
+    const ESP_SECTORS: u64 = 133_120;
+    const ESP_START: u64 = 2048;
+    const DISK_SECTORS: u64 = 163_840;
+    const CHUNK_SECTORS: u64 = 4096;
+    const CHUNK_START: u64 = 136_192;
+
+    fn disk_with_manifest(
+        storage: &mut Vec<u8>,
+        edit: impl FnOnce(&mut IsoManifest),
+    ) -> BlockIoAdapter<&mut [u8]> {
+        morpheus_core::disk::gpt_ops::create_gpt(
+            BlockIoAdapter::new(storage.as_mut_slice(), BlockSize::BS_512),
+            DISK_SECTORS,
+        )
+        .unwrap();
+        let mut disk = BlockIoAdapter::new(storage.as_mut_slice(), BlockSize::BS_512);
+        GptOps::create_partition(
+            &mut disk,
+            ESP_START,
+            ESP_START + ESP_SECTORS - 1,
+            guid::EFI_SYSTEM,
+            "ESP",
+        )
+        .unwrap();
+        Fat32Formatter::format(&mut disk, ESP_START, ESP_SECTORS, "ESP").unwrap();
+        morpheus_core::fs::create_directory(&mut disk, ESP_START, "/.iso").unwrap();
+
+        let end = CHUNK_START + CHUNK_SECTORS - 1;
+        GptOps::create_partition(&mut disk, CHUNK_START, end, guid::BASIC_DATA, "a").unwrap();
+        let mut manifest = IsoManifest::new("a.iso", 1_000_000);
+        manifest
+            .add_chunk([0u8; 16], CHUNK_START, CHUNK_START + 1953)
+            .unwrap();
+        manifest.chunks.chunks[0].data_size = 512 * 1024;
+        manifest.set_sha256(&[0x5A; 32]);
+        manifest.etag_hash = 0x1234_5678;
+        edit(&mut manifest);
+
+        let mut buffer = [0u8; MAX_MANIFEST_SIZE];
+        let len = manifest.serialize(&mut buffer).unwrap();
+        let path = alloc::format!(
+            "/.iso/{}",
+            morpheus_core::fs::generate_8_3_manifest_name("a.iso")
+        );
+        morpheus_core::fs::write_file(&mut disk, ESP_START, &path, &buffer[..len]).unwrap();
+        disk
+    }
+
+    #[test]
+    fn test_finds_partial_download() {
+        let mut storage = vec![0u8; DISK_SECTORS as usize * SECTOR_SIZE];
+        let mut disk = disk_with_manifest(&mut storage, |_| {});
+
+        assert!(ResumePoint::find(&mut disk, ESP_START, "b.iso", 1, 0)
+            .unwrap()
+            .is_none());
+        let point = ResumePoint::find(&mut disk, ESP_START, "a.iso", CHUNK_SECTORS, 0)
+            .unwrap()
+            .unwrap();
+        assert_eq!(point.partition.start_lba, CHUNK_START);
+        assert_eq!(point.offset, 512 * 1024);
+        assert_eq!(point.sha256_state, [0x5A; 32]);
+        assert_eq!(point.etag_hash, 0x1234_5678);
+
+        // Too small for the image, or not where it was asked for
+        for (needed, start) in [(CHUNK_SECTORS + 1, 0), (CHUNK_SECTORS, 150_000)] {
+            assert!(
+                ResumePoint::find(&mut disk, ESP_START, "a.iso", needed, start)
+                    .unwrap()
+                    .is_none()
+            );
+        }
+    }
+
+    #[test]
+    fn test_nothing_to_continue() {
+        let cases: [fn(&mut IsoManifest); 3] = [
+            |m| m.mark_complete(),
+            |m| m.set_sha256(&[0u8; 32]),
+            |m| m.chunks.chunks[0].data_size = 1000,
+        ];
+        for edit in cases {
+            let mut storage = vec![0u8; DISK_SECTORS as usize * SECTOR_SIZE];
+            let mut disk = disk_with_manifest(&mut storage, edit);
+            assert!(ResumePoint::find(&mut disk, ESP_START, "a.iso", 1, 0)
+                .unwrap()
+                .is_none());
+        }
+    }
+}
@@ -0,0 +1,244 @@
+//! Chunk partition reuse on re-download.
+//!
+//! Downloading an ISO that is already stored would otherwise claim a new
+//! partition and leak the old one along with its manifest. Before GPT prep
+//! places the download, [`ReusePlan::find`] looks up the stored copy by ISO
+//! name: a chunk partition big enough for the new image is written over in
+//! place, the others are deleted. If none fits, all of them go and the
+//! lowest freed range is offered as a placement hint, so a copy that grew
+//! takes its old spot if the space after it is free.
+//!
+//! [`ReusePlan::release`] removes the old manifest and the raw copy in the
+//! reused partition before anything is deleted, so nothing describes the
+//! old image while the new one is being written over it.
+
+use gpt_disk_io::BlockIo;
+use gpt_disk_types::Lba;
+use morpheus_core::iso::{raw_manifest_lba, RAW_MANIFEST_SECTORS};
+
+use super::gpt::GptOps;
+use super::manifest::ManifestReader;
+use super::types::{DiskError, DiskResult, PartitionInfo, MAX_CHUNK_PARTITIONS, SECTOR_SIZE};
+
+/// What to do with the stored copy of an ISO being downloaded again
+#[derive(Debug, Clone, Copy)]
+pub struct ReusePlan {
+    /// Partition the download is written into, if one fits
+    reuse: Option<PartitionInfo>,
+    /// Partitions to delete
+    delete: [PartitionInfo; MAX_CHUNK_PARTITIONS],
+    delete_count: usize,
+    /// Old manifest's filename in `/.iso` ("A1B2C3D4.MFS")
+    filename: [u8; 12],
+    filename_len: usize,
+}
+
+impl ReusePlan {
+    /// Plan for the stored copy of `iso_name`, `None` if there is none.
+    ///
+    /// `sectors_needed` is the full partition size the download needs.
+    /// With a `requested_start` (0 = anywhere) only a partition starting
+    /// there is reused.
+    pub fn find<B: BlockIo>(
+        block_io: &mut B,
+        esp_start_lba: u64,
+        iso_name: &str,
+        sectors_needed: u64,
+        requested_start: u64,
+    ) -> DiskResult<Option<Self>> {
+        let found = ManifestReader::scan_all(block_io, esp_start_lba)?.find_map(|scanned| {
+            let mut filename = [0u8; 12];
+            let filename_len = scanned.filename(&mut filename).len();
+            let (info, chunks) = scanned.parsed.ok()?;
+            (info.name_str() == iso_name).then_some((filename, filename_len, chunks))
+        });
+        let Some((filename, filename_len, chunks)) = found else {
+            return Ok(None);
+        };
+        let (partitions, count) = GptOps::scan_partitions(block_io)?;
+
+        let mut plan = Self {
+            reuse: None,
+            delete: [PartitionInfo::default(); MAX_CHUNK_PARTITIONS],
+            delete_count: 0,
+            filename,
+            filename_len,
+        };
+        // Chunks on other disks are out of reach of this block device
+        for chunk in chunks.chunks[..chunks.count]
+            .iter()
+            .filter(|c| c.disk_id == 0)
+        {
+            let Some(part) = partitions[..count]
+                .iter()
+                .find(|p| p.start_lba <= chunk.info.start_lba && chunk.info.end_lba <= p.end_lba)
+            else {
+                continue;
+            };
+            if plan.reuse.is_some_and(|r| r.index == part.index)
+                || plan.deletes().iter().any(|d| d.index == part.index)
+            {
+                continue;
+            }
+
+            let fits = part.end_lba - part.start_lba + 1 >= sectors_needed
+                && (requested_start == 0 || requested_start == part.start_lba);
+            if fits && plan.reuse.is_none() {
+                plan.reuse = Some(*part);
+            } else {
+                plan.delete[plan.delete_count] = *part;
+                plan.delete_count += 1;
+            }
+        }
+        Ok(Some(plan))
+    }
+
+    /// Partition to write the download into.
+    pub fn reused(&self) -> Option<&PartitionInfo> {
+        self.reuse.as_ref()
+    }
+
+    /// Partitions [`release`](Self::release) deletes.
+    pub fn deletes(&self) -> &[PartitionInfo] {
+        &self.delete[..self.delete_count]
+    }
+
+    /// Where to try placing the download when no partition is reused:
+    /// the lowest range the old copy occupied.
+    pub fn hint_lba(&self) -> Option<u64> {
+        self.deletes().iter().map(|p| p.start_lba).min()
+    }
+
+    /// Remove the old manifest, clear the reused partition's raw manifest
+    /// copy and delete the partitions that are not reused.
+    pub fn release<B: BlockIo>(&self, block_io: &mut B, esp_start_lba: u64) -> DiskResult<()> {
+        let filename = core::str::from_utf8(&self.filename[..self.filename_len])
+            .map_err(|_| DiskError::ManifestError)?;
+        let path = alloc::format!("/.iso/{}", filename);
+        morpheus_core::fs::delete_file(block_io, esp_start_lba, &path)
+            .map_err(|_| DiskError::ManifestError)?;
+
+        if let Some(part) = &self.reuse {
+            if part.end_lba - part.start_lba + 1 >= RAW_MANIFEST_SECTORS {
+                let zero = [0u8; SECTOR_SIZE];
+                let lba = raw_manifest_lba(part.end_lba);
+                for i in 0..RAW_MANIFEST_SECTORS {
+                    block_io
+                        .write_blocks(Lba(lba + i), &zero)
+                        .map_err(|_| DiskError::IoError)?;
+                }
+            }
+        }
+
+        for part in self.deletes() {
+            GptOps::delete_partition(block_io, part.index)?;
+        }
+        block_io.flush().map_err(|_| DiskError::IoError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fat32::Fat32Formatter;
+    use super::super::manifest::{ManifestWriter, MAX_MANIFEST_SIZE};
+    use super::super::types::{guid, ChunkPartition, ChunkSet};
+    use super::*;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use gpt_disk_io::BlockIoAdapter;
+    use gpt_disk_types::BlockSize;
+
+    /// Smallest volume the formatter accepts
+    const ESP_SECTORS: u64 = 133_120;
+    const ESP_START: u64 = 2048;
+    const DISK_SECTORS: u64 = 163_840;
+    const CHUNK_SECTORS: u64 = 4096;
+    const CHUNK_START: u64 = 136_192;
+
+    fn disk_with_copy(storage: &mut Vec<u8>) -> BlockIoAdapter<&mut [u8]> {
+        morpheus_core::disk::gpt_ops::create_gpt(
+            BlockIoAdapter::new(storage.as_mut_slice(), BlockSize::BS_512),
+            DISK_SECTORS,
+        )
+        .unwrap();
+        let mut disk = BlockIoAdapter::new(storage.as_mut_slice(), BlockSize::BS_512);
+        GptOps::create_partition(
+            &mut disk,
+            ESP_START,
+            ESP_START + ESP_SECTORS - 1,
+            guid::EFI_SYSTEM,
+            "ESP",
+        )
+        .unwrap();
+        Fat32Formatter::format(&mut disk, ESP_START, ESP_SECTORS, "ESP").unwrap();
+        morpheus_core::fs::create_directory(&mut disk, ESP_START, "/.iso").unwrap();
+
+        let end = CHUNK_START + CHUNK_SECTORS - 1;
+        GptOps::create_partition(&mut disk, CHUNK_START, end, guid::BASIC_DATA, "a").unwrap();
+        let mut chunks = ChunkSet::new();
+        let data = PartitionInfo::new(0, CHUNK_START, end - RAW_MANIFEST_SECTORS, guid::BASIC_DATA);
+        chunks.add(ChunkPartition::new(data, 0)).unwrap();
+        let mut buffer = [0u8; MAX_MANIFEST_SIZE];
+        let len = ManifestWriter::new("a.iso", 1234)
+            .serialize(&chunks, &mut buffer)
+            .unwrap();
+        morpheus_core::fs::write_file(&mut disk, ESP_START, "/.iso/A.MFS", &buffer[..len]).unwrap();
+        disk.write_blocks(Lba(raw_manifest_lba(end)), &[0xAA; SECTOR_SIZE])
+            .unwrap();
+        disk
+    }
+
+    #[test]
+    fn test_reuse_in_place() {
+        let mut storage = vec![0u8; DISK_SECTORS as usize * SECTOR_SIZE];
+        let mut disk = disk_with_copy(&mut storage);
+
+        assert!(ReusePlan::find(&mut disk, ESP_START, "b.iso", 1, 0)
+            .unwrap()
+            .is_none());
+        let plan = ReusePlan::find(&mut disk, ESP_START, "a.iso", CHUNK_SECTORS, 0)
+            .unwrap()
+            .unwrap();
+        assert_eq!(plan.reused().unwrap().start_lba, CHUNK_START);
+        assert!(plan.deletes().is_empty());
+
+        plan.release(&mut disk, ESP_START).unwrap();
+        assert!(ReusePlan::find(&mut disk, ESP_START, "a.iso", 1, 0)
+            .unwrap()
+            .is_none());
+        let end = CHUNK_START + CHUNK_SECTORS - 1;
+        assert!(GptOps::find_partition(&mut disk, CHUNK_START, end)
+            .unwrap()
+            .is_some());
+        let mut sector = [0xFFu8; SECTOR_SIZE];
+        disk.read_blocks(Lba(raw_manifest_lba(end)), &mut sector)
+            .unwrap();
+        assert_eq!(sector, [0u8; SECTOR_SIZE]);
+    }
+
+    #[test]
+    fn test_too_small_is_deleted() {
+        let mut storage = vec![0u8; DISK_SECTORS as usize * SECTOR_SIZE];
+        let mut disk = disk_with_copy(&mut storage);
+
+        // Bigger image, and a fitting partition at the wrong start
+        for (needed, start) in [(CHUNK_SECTORS + 1, 0), (CHUNK_SECTORS, 150_000)] {
+            let plan = ReusePlan::find(&mut disk, ESP_START, "a.iso", needed, start)
+                .unwrap()
+                .unwrap();
+            assert!(plan.reused().is_none());
+            assert_eq!(plan.deletes().len(), 1);
+            assert_eq!(plan.hint_lba(), Some(CHUNK_START));
+        }
+
+        let plan = ReusePlan::find(&mut disk, ESP_START, "a.iso", CHUNK_SECTORS + 1, 0)
+            .unwrap()
+            .unwrap();
+        plan.release(&mut disk, ESP_START).unwrap();
+        assert!(
+            GptOps::find_partition(&mut disk, CHUNK_START, CHUNK_START + CHUNK_SECTORS - 1)
+                .unwrap()
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,446 @@
+//! Allocation-free manifest directory scan.
+//!
+//! Walks `/.iso` on a FAT32 ESP straight through `BlockIo` and parses every
+//! `*.MFS` manifest it finds, one sector buffer at a time. Nothing here
+//! touches the heap, so the bootloader can list manifests before its
+//! allocator is up and the post-EBS path can use it as well.
+//!
+//! Only the parts of FAT32 a read-only scan needs are handled: 512-byte
+//! sectors, cluster chains and 8.3 directory entries (long name entries are
+//! skipped).
+
+use gpt_disk_io::BlockIo;
+use gpt_disk_types::Lba;
+
+use super::manifest::{IsoManifestInfo, ManifestReader, MAX_MANIFEST_SIZE};
+use super::types::{ChunkSet, DiskError, DiskResult, PartitionInfo, SECTOR_SIZE};
+
+/// Manifests a single scan yields at most
+pub const MAX_SCANNED_MANIFESTS: usize = 32;
+
+/// Clusters followed in one chain before giving up (guards against loops)
+const MAX_CHAIN_CLUSTERS: usize = 256;
+
+const DIR_ENTRY_SIZE: usize = 32;
+const ATTR_DIRECTORY: u8 = 0x10;
+const ATTR_VOLUME_ID: u8 = 0x08;
+const ATTR_LONG_NAME: u8 = 0x0F;
+const ENTRY_END: u8 = 0x00;
+const ENTRY_DELETED: u8 = 0xE5;
+
+/// `.iso` as written by our own FAT32 code
+const ISO_DIR_NAME: &[u8; 11] = b"        ISO";
+/// `.iso` as the short alias other tools give the long name
+const ISO_DIR_ALIAS: &[u8; 4] = b"ISO~";
+
+/// FAT32 geometry needed to follow cluster chains
+#[derive(Debug, Clone, Copy)]
+struct Volume {
+    sectors_per_cluster: u32,
+    fat_lba: u64,
+    data_lba: u64,
+    root_cluster: u32,
+}
+
+impl Volume {
+    fn read<B: BlockIo>(block_io: &mut B, start_lba: u64) -> DiskResult<Self> {
+        let mut sector = [0u8; SECTOR_SIZE];
+        block_io
+            .read_blocks(Lba(start_lba), &mut sector)
+            .map_err(|_| DiskError::IoError)?;
+
+        let bytes_per_sector = u16::from_le_bytes([sector[0x0B], sector[0x0C]]) as usize;
+        if sector[0x1FE..0x200] != [0x55, 0xAA] || bytes_per_sector != SECTOR_SIZE {
+            return Err(DiskError::NotSupported);
+        }
+
+        let sectors_per_cluster = sector[0x0D] as u32;
+        let reserved = u16::from_le_bytes([sector[0x0E], sector[0x0F]]) as u64;
+        let num_fats = sector[0x10] as u64;
+        let fat_size = u32::from_le_bytes(sector[0x24..0x28].try_into().unwrap()) as u64;
+        let root_cluster = u32::from_le_bytes(sector[0x2C..0x30].try_into().unwrap());
+
+        if sectors_per_cluster == 0 || fat_size == 0 || root_cluster < 2 {
+            return Err(DiskError::NotSupported);
+        }
+
+        Ok(Self {
+            sectors_per_cluster,
+            fat_lba: start_lba + reserved,
+            data_lba: start_lba + reserved + num_fats * fat_size,
+            root_cluster,
+        })
+    }
+
+    fn cluster_lba(&self, cluster: u32) -> u64 {
+        self.data_lba + (cluster as u64 - 2) * self.sectors_per_cluster as u64
+    }
+
+    /// Next cluster in the chain, `None` at the end (or on a bad entry).
+    fn next_cluster<B: BlockIo>(&self, block_io: &mut B, cluster: u32) -> DiskResult<Option<u32>> {
+        let offset = cluster as u64 * 4;
+        let mut sector = [0u8; SECTOR_SIZE];
+        block_io
+            .read_blocks(Lba(self.fat_lba + offset / SECTOR_SIZE as u64), &mut sector)
+            .map_err(|_| DiskError::IoError)?;
+
+        let at = (offset % SECTOR_SIZE as u64) as usize;
+        let next = u32::from_le_bytes(sector[at..at + 4].try_into().unwrap()) & 0x0FFF_FFFF;
+        Ok(if (2..0x0FFF_FFF8).contains(&next) {
+            Some(next)
+        } else {
+            None
+        })
+    }
+}
+
+/// Cursor over the 32-byte entries of one directory.
+struct DirCursor {
+    cluster: u32,
+    sector: u32,
+    entry: usize,
+    clusters_seen: usize,
+    buffer: [u8; SECTOR_SIZE],
+    loaded: bool,
+    done: bool,
+}
+
+impl DirCursor {
+    fn new(cluster: u32) -> Self {
+        Self {
+            cluster,
+            sector: 0,
+            entry: 0,
+            clusters_seen: 1,
+            buffer: [0u8; SECTOR_SIZE],
+            loaded: false,
+            done: false,
+        }
+    }
+
+    /// Next in-use short entry, as a copy of its 32 bytes.
+    fn next<B: BlockIo>(
+        &mut self,
+        block_io: &mut B,
+        volume: &Volume,
+    ) -> DiskResult<Option<[u8; DIR_ENTRY_SIZE]>> {
+        while !self.done {
+            if self.entry == SECTOR_SIZE / DIR_ENTRY_SIZE {
+                self.entry = 0;
+                self.sector += 1;
+                self.loaded = false;
+            }
+            if self.sector == volume.sectors_per_cluster {
+                self.sector = 0;
+                match volume.next_cluster(block_io, self.cluster)? {
+                    Some(next) if self.clusters_seen < MAX_CHAIN_CLUSTERS => {
+                        self.cluster = next;
+                        self.clusters_seen += 1;
+                    }
+                    _ => {
+                        self.done = true;
+                        break;
+                    }
+                }
+            }
+            if !self.loaded {
+                let lba = volume.cluster_lba(self.cluster) + self.sector as u64;
+                block_io
+                    .read_blocks(Lba(lba), &mut self.buffer)
+                    .map_err(|_| DiskError::IoError)?;
+                self.loaded = true;
+            }
+
+            let at = self.entry * DIR_ENTRY_SIZE;
+            self.entry += 1;
+            let mut entry = [0u8; DIR_ENTRY_SIZE];
+            entry.copy_from_slice(&self.buffer[at..at + DIR_ENTRY_SIZE]);
+
+            match entry[0] {
+                ENTRY_END => self.done = true,
+                ENTRY_DELETED => {}
+                _ if entry[11] == ATTR_LONG_NAME || entry[11] & ATTR_VOLUME_ID != 0 => {}
+                _ => return Ok(Some(entry)),
+            }
+        }
+        Ok(None)
+    }
+}
+
+fn entry_cluster(entry: &[u8; DIR_ENTRY_SIZE]) -> u32 {
+    let high = u16::from_le_bytes([entry[20], entry[21]]) as u32;
+    let low = u16::from_le_bytes([entry[26], entry[27]]) as u32;
+    (high << 16) | low
+}
+
+fn is_iso_dir(entry: &[u8; DIR_ENTRY_SIZE]) -> bool {
+    if entry[11] & ATTR_DIRECTORY == 0 {
+        return false;
+    }
+    let name = &entry[..11];
+    name.eq_ignore_ascii_case(ISO_DIR_NAME) || name[..4].eq_ignore_ascii_case(ISO_DIR_ALIAS)
+}
+
+fn is_manifest_file(entry: &[u8; DIR_ENTRY_SIZE]) -> bool {
+    entry[11] & ATTR_DIRECTORY == 0 && entry[8..11].eq_ignore_ascii_case(b"MFS")
+}
+
+/// First cluster of `/.iso`, `None` if the directory doesn't exist.
+fn find_iso_dir<B: BlockIo>(block_io: &mut B, volume: &Volume) -> DiskResult<Option<u32>> {
+    let mut root = DirCursor::new(volume.root_cluster);
+    while let Some(entry) = root.next(block_io, volume)? {
+        if is_iso_dir(&entry) && entry_cluster(&entry) >= 2 {
+            return Ok(Some(entry_cluster(&entry)));
+        }
+    }
+    Ok(None)
+}
+
+/// First data sector of the file `short_name` (8.3, space padded) in
+/// `/.iso`, `None` if it doesn't exist or has no data.
+pub(super) fn locate_iso_file<B: BlockIo>(
+    block_io: &mut B,
+    esp_start_lba: u64,
+    short_name: &[u8; 11],
+) -> DiskResult<Option<u64>> {
+    let volume = Volume::read(block_io, esp_start_lba)?;
+    let Some(dir) = find_iso_dir(block_io, &volume)? else {
+        return Ok(None);
+    };
+
+    let mut cursor = DirCursor::new(dir);
+    while let Some(entry) = cursor.next(block_io, &volume)? {
+        if entry[11] & ATTR_DIRECTORY == 0 && entry[..11].eq_ignore_ascii_case(short_name) {
+            let cluster = entry_cluster(&entry);
+            return Ok((cluster >= 2).then(|| volume.cluster_lba(cluster)));
+        }
+    }
+    Ok(None)
+}
+
+/// One manifest file found by [`ManifestScan`].
+pub struct ScannedManifest {
+    /// 8.3 directory name, space padded ("A1B2C3D4MFS")
+    pub short_name: [u8; 11],
+    /// Parsed manifest, or why it couldn't be read
+    pub parsed: DiskResult<(IsoManifestInfo, ChunkSet)>,
+}
+
+impl ScannedManifest {
+    /// Filename as "A1B2C3D4.MFS"
+    pub fn filename<'a>(&self, buf: &'a mut [u8; 12]) -> &'a str {
+        let mut len = 0;
+        for &c in self.short_name[..8].iter().filter(|&&c| c != b' ') {
+            buf[len] = c;
+            len += 1;
+        }
+        buf[len] = b'.';
+        len += 1;
+        for &c in self.short_name[8..].iter().filter(|&&c| c != b' ') {
+            buf[len] = c;
+            len += 1;
+        }
+        core::str::from_utf8(&buf[..len]).unwrap_or("")
+    }
+
+    /// See [`ManifestReader::is_stale`]. Unreadable manifests aren't stale,
+    /// just broken.
+    pub fn is_stale(&self, partitions: &[PartitionInfo]) -> bool {
+        match &self.parsed {
+            Ok((_, chunks)) => ManifestReader::is_stale(chunks, partitions),
+            Err(_) => false,
+        }
+    }
+}
+
+/// Iterator over the manifests in `/.iso`, from [`ManifestReader::scan_all`].
+///
+/// Yields at most [`MAX_SCANNED_MANIFESTS`] entries. An I/O error while
+/// walking the directory ends the scan; check [`error`](Self::error)
+/// afterwards to tell that apart from a clean end.
+pub struct ManifestScan<'a, B: BlockIo> {
+    block_io: &'a mut B,
+    volume: Volume,
+    /// `None` if there is no `/.iso` directory
+    dir: Option<DirCursor>,
+    yielded: usize,
+    error: Option<DiskError>,
+}
+
+impl<'a, B: BlockIo> ManifestScan<'a, B> {
+    pub(super) fn new(block_io: &'a mut B, esp_start_lba: u64) -> DiskResult<Self> {
+        let volume = Volume::read(block_io, esp_start_lba)?;
+        let dir = find_iso_dir(block_io, &volume)?.map(DirCursor::new);
+
+        Ok(Self {
+            block_io,
+            volume,
+            dir,
+            yielded: 0,
+            error: None,
+        })
+    }
+
+    /// The I/O error that ended the scan early, if any
+    pub fn error(&self) -> Option<DiskError> {
+        self.error
+    }
+
+    /// Read a manifest file (at most [`MAX_MANIFEST_SIZE`] bytes) and parse it.
+    fn read_manifest(
+        &mut self,
+        entry: &[u8; DIR_ENTRY_SIZE],
+    ) -> DiskResult<(IsoManifestInfo, ChunkSet)> {
+        let file_size = u32::from_le_bytes(entry[28..32].try_into().unwrap()) as usize;
+        let len = file_size.min(MAX_MANIFEST_SIZE);
+        let mut buffer = [0u8; MAX_MANIFEST_SIZE.next_multiple_of(SECTOR_SIZE)];
+
+        let mut cluster = entry_cluster(entry);
+        let mut read = 0;
+        'chain: while read < len {
+            if cluster < 2 {
+                return Err(DiskError::ManifestError);
+            }
+            let lba = self.volume.cluster_lba(cluster);
+            for sector in 0..self.volume.sectors_per_cluster as u64 {
+                if read >= len {
+                    break 'chain;
+                }
+                self.block_io
+                    .read_blocks(Lba(lba + sector), &mut buffer[read..read + SECTOR_SIZE])
+                    .map_err(|_| DiskError::IoError)?;
+                read += SECTOR_SIZE;
+            }
+            match self.volume.next_cluster(self.block_io, cluster)? {
+                Some(next) => cluster = next,
+                None => break,
+            }
+        }
+
+        ManifestReader::parse(&buffer[..len.min(read)])
+    }
+}
+
+impl<B: BlockIo> Iterator for ManifestScan<'_, B> {
+    type Item = ScannedManifest;
+
+    fn next(&mut self) -> Option<ScannedManifest> {
+        if self.yielded >= MAX_SCANNED_MANIFESTS {
+            return None;
+        }
+
+        loop {
+            let dir = self.dir.as_mut()?;
+            let entry = match dir.next(self.block_io, &self.volume) {
+                Ok(Some(entry)) => entry,
+                Ok(None) => return None,
+                Err(e) => {
+                    self.error = Some(e);
+                    self.dir = None;
+                    return None;
+                }
+            };
+            if !is_manifest_file(&entry) {
+                continue;
+            }
+
+            let mut short_name = [0u8; 11];
+            short_name.copy_from_slice(&entry[..11]);
+            self.yielded += 1;
+            return Some(ScannedManifest {
+                short_name,
+                parsed: self.read_manifest(&entry),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fat32::Fat32Formatter;
+    use super::super::manifest::ManifestWriter;
+    use super::super::types::{guid, ChunkPartition};
+    use super::*;
+    use alloc::vec;
+    use gpt_disk_io::BlockIoAdapter;
+    use gpt_disk_types::BlockSize;
+
+    /// Smallest volume the formatter accepts
+    const ESP_SECTORS: u64 = 133_120;
+
+    fn entry(name: &[u8; 11], attr: u8) -> [u8; DIR_ENTRY_SIZE] {
+        let mut entry = [0u8; DIR_ENTRY_SIZE];
+        entry[..11].copy_from_slice(name);
+        entry[11] = attr;
+        entry
+    }
+
+    #[test]
+    fn test_iso_dir_names() {
+        assert!(is_iso_dir(&entry(b"        ISO", ATTR_DIRECTORY)));
+        assert!(is_iso_dir(&entry(b"ISO~1      ", ATTR_DIRECTORY)));
+        assert!(!is_iso_dir(&entry(b"        ISO", 0x20)));
+        assert!(!is_iso_dir(&entry(b"EFI        ", ATTR_DIRECTORY)));
+    }
+
+    #[test]
+    fn test_filename_from_short_name() {
+        let scanned = ScannedManifest {
+            short_name: *b"A1B2C3D4MFS",
+            parsed: Err(DiskError::ManifestError),
+        };
+        let mut buf = [0u8; 12];
+        assert_eq!(scanned.filename(&mut buf), "A1B2C3D4.MFS");
+        assert!(is_manifest_file(&entry(b"TAILS   MFS", 0x20)));
+        assert!(!is_manifest_file(&entry(b"DL0001  HST", 0x20)));
+    }
+
+    #[test]
+    fn test_scan_finds_manifests_and_stale() {
+        let mut storage = vec![0u8; ESP_SECTORS as usize * SECTOR_SIZE];
+        let mut disk = BlockIoAdapter::new(storage.as_mut_slice(), BlockSize::BS_512);
+        Fat32Formatter::format(&mut disk, 0, ESP_SECTORS, "ESP").unwrap();
+
+        let mut chunks = ChunkSet::new();
+        let part = PartitionInfo::new(1, 200_000, 300_000, guid::BASIC_DATA);
+        chunks.add(ChunkPartition::new(part, 0)).unwrap();
+        let mut buffer = [0u8; MAX_MANIFEST_SIZE];
+        let len = ManifestWriter::new("tails.iso", 1234)
+            .serialize(&chunks, &mut buffer)
+            .unwrap();
+
+        morpheus_core::fs::create_directory(&mut disk, 0, "/.iso").unwrap();
+        morpheus_core::fs::write_file(&mut disk, 0, "/.iso/TAILS.MFS", &buffer[..len]).unwrap();
+        morpheus_core::fs::write_file(&mut disk, 0, "/.iso/DL0000.HST", b"other").unwrap();
+
+        let mut scan = ManifestReader::scan_all(&mut disk, 0).unwrap();
+        let found = scan.next().unwrap();
+        assert!(scan.next().is_none());
+        assert!(scan.error().is_none());
+
+        let mut name = [0u8; 12];
+        assert_eq!(found.filename(&mut name), "TAILS.MFS");
+        let (info, _) = found.parsed.as_ref().unwrap();
+        assert_eq!(info.name_str(), "tails.iso");
+        assert_eq!(info.total_size, 1234);
+
+        let present = [PartitionInfo::new(1, 100_000, 400_000, guid::ISO_CHUNK)];
+        let legacy = [PartitionInfo::new(1, 100_000, 400_000, guid::BASIC_DATA)];
+        let elsewhere = [PartitionInfo::new(1, 250_000, 400_000, guid::ISO_CHUNK)];
+        let foreign = [PartitionInfo::new(1, 100_000, 400_000, guid::LINUX_FS)];
+        assert!(!found.is_stale(&present));
+        assert!(!found.is_stale(&legacy));
+        assert!(found.is_stale(&elsewhere));
+        assert!(found.is_stale(&foreign));
+        assert!(found.is_stale(&[]));
+    }
+
+    #[test]
+    fn test_scan_without_iso_dir_is_empty() {
+        let mut storage = vec![0u8; ESP_SECTORS as usize * SECTOR_SIZE];
+        let mut disk = BlockIoAdapter::new(storage.as_mut_slice(), BlockSize::BS_512);
+        Fat32Formatter::format(&mut disk, 0, ESP_SECTORS, "ESP").unwrap();
+
+        assert_eq!(ManifestReader::scan_all(&mut disk, 0).unwrap().count(), 0);
+    }
+}
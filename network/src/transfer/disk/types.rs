@@ -62,28 +62,26 @@ impl core::fmt::Display for DiskError {
     }
 }
 
+impl From<morpheus_gpt::GptError> for DiskError {
+    fn from(e: morpheus_gpt::GptError) -> Self {
+        use morpheus_gpt::GptError as E;
+        match e {
+            E::IoError => Self::IoError,
+            E::InvalidHeader | E::BufferTooSmall => Self::InvalidGpt,
+            E::NoSpace | E::OverlappingPartitions => Self::NoFreeSpace,
+            E::PartitionNotFound => Self::PartitionNotFound,
+            E::InvalidSize => Self::InvalidSize,
+            E::UnsupportedBlockSize => Self::NotSupported,
+        }
+    }
+}
+
 /// Result type for disk operations
 pub type DiskResult<T> = Result<T, DiskError>;
 
-/// GPT partition type GUIDs
+/// GPT partition type GUIDs, from the shared type registry
 pub mod guid {
-    /// EFI System Partition
-    pub const EFI_SYSTEM: [u8; 16] = [
-        0x28, 0x73, 0x2A, 0xC1, 0x1F, 0xF8, 0xD2, 0x11, 0xBA, 0x4B, 0x00, 0xA0, 0xC9, 0x3E, 0xC9,
-        0x3B,
-    ];
-
-    /// Microsoft Basic Data (used for FAT32 data partitions)
-    pub const BASIC_DATA: [u8; 16] = [
-        0xA2, 0xA0, 0xD0, 0xEB, 0xE5, 0xB9, 0x33, 0x44, 0x87, 0xC0, 0x68, 0xB6, 0xB7, 0x26, 0x99,
-        0xC7,
-    ];
-
-    /// Linux filesystem
-    pub const LINUX_FS: [u8; 16] = [
-        0xAF, 0x3D, 0xC6, 0x0F, 0x83, 0x84, 0x72, 0x47, 0x8E, 0x79, 0x3D, 0x69, 0xD8, 0x47, 0x7D,
-        0xE4,
-    ];
+    pub use morpheus_gpt::part_type::{BASIC_DATA, EFI_SYSTEM, ISO_CHUNK, LINUX_FS};
 }
 
 /// Information about a partition
@@ -133,6 +131,11 @@ impl PartitionInfo {
         self.name[len] = 0;
     }
 
+    /// Display name of the partition type ("Basic Data", "Unknown", ...)
+    pub fn type_name(&self) -> &'static str {
+        morpheus_gpt::part_type::name(&self.type_guid)
+    }
+
     /// Get partition size in sectors
     pub fn size_sectors(&self) -> u64 {
         self.end_lba.saturating_sub(self.start_lba) + 1
@@ -155,6 +158,9 @@ pub struct ChunkPartition {
     pub bytes_written: u64,
     /// Whether chunk is complete
     pub complete: bool,
+    /// Disk holding the partition (`DeviceIdentity::disk_id`), 0 for the
+    /// ESP's disk
+    pub disk_id: u32,
 }
 
 impl ChunkPartition {
@@ -165,6 +171,7 @@ impl ChunkPartition {
             chunk_index,
             bytes_written: 0,
             complete: false,
+            disk_id: 0,
         }
     }
 
@@ -217,6 +224,7 @@ impl ChunkSet {
                 chunk_index: 0,
                 bytes_written: 0,
                 complete: false,
+                disk_id: 0,
             }; MAX_CHUNK_PARTITIONS],
             count: 0,
             total_size: 0,
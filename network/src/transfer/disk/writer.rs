@@ -6,8 +6,11 @@
 use gpt_disk_io::BlockIo;
 use gpt_disk_types::Lba;
 
+use crate::mainloop::serial;
+
 use super::fat32::{Fat32Formatter, Fat32Info};
 use super::gpt::GptOps;
+use super::placement::PlacementPolicy;
 use super::types::{
     guid, ChunkPartition, ChunkSet, DiskError, DiskResult, PartitionInfo, DEFAULT_CHUNK_SIZE,
     MAX_CHUNK_PARTITIONS, SECTOR_SIZE,
@@ -28,8 +31,47 @@ pub enum WriterState {
     Failed,
 }
 
+/// Where a multi-partition write is, per chunk and overall
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkProgress {
+    /// Chunk being written (0-based)
+    pub chunk_index: usize,
+    /// Number of chunk partitions
+    pub chunk_count: usize,
+    /// Bytes written to the current chunk
+    pub chunk_bytes: u64,
+    /// Bytes the current chunk will hold when complete
+    pub chunk_size: u64,
+    /// Bytes written across all chunks
+    pub total_bytes: u64,
+    /// Size of the whole ISO
+    pub total_size: u64,
+}
+
+impl ChunkProgress {
+    /// Current chunk completion, 0-100
+    pub fn chunk_percent(&self) -> u8 {
+        percent(self.chunk_bytes, self.chunk_size)
+    }
+
+    /// Whole ISO completion, 0-100
+    pub fn total_percent(&self) -> u8 {
+        percent(self.total_bytes, self.total_size)
+    }
+}
+
+fn percent(done: u64, total: u64) -> u8 {
+    if total == 0 {
+        return 100;
+    }
+    ((done as u128 * 100) / total as u128).min(100) as u8
+}
+
 /// Progress callback type
-pub type ProgressFn = fn(bytes_written: u64, total_bytes: u64, chunk: usize, total_chunks: usize);
+///
+/// Called whenever the current chunk or its whole percentage changes, so
+/// at most ~100 times per chunk.
+pub type ProgressFn = fn(progress: &ChunkProgress);
 
 /// Streaming ISO writer
 ///
@@ -57,6 +99,10 @@ pub struct IsoWriter {
     name_len: usize,
     /// Progress callback
     progress_fn: Option<ProgressFn>,
+    /// (chunk, percent) last passed to the callback
+    last_reported: Option<(usize, u8)>,
+    /// Where chunk partitions go
+    placement: PlacementPolicy,
 }
 
 impl IsoWriter {
@@ -78,6 +124,8 @@ impl IsoWriter {
             iso_name: name,
             name_len: len,
             progress_fn: None,
+            last_reported: None,
+            placement: PlacementPolicy::LargestGap,
         }
     }
 
@@ -86,6 +134,11 @@ impl IsoWriter {
         self.progress_fn = Some(f);
     }
 
+    /// Set the placement policy for chunk partitions
+    pub fn set_placement(&mut self, policy: PlacementPolicy) {
+        self.placement = policy;
+    }
+
     /// Get current state
     pub fn state(&self) -> WriterState {
         self.state
@@ -101,6 +154,22 @@ impl IsoWriter {
         &self.chunks
     }
 
+    /// Current per-chunk and overall progress
+    pub fn progress(&self) -> ChunkProgress {
+        let chunk_start = self.current_chunk as u64 * self.chunk_size;
+        ChunkProgress {
+            chunk_index: self.current_chunk,
+            chunk_count: self.chunks.count,
+            chunk_bytes: self.chunk_bytes,
+            // The last chunk only holds what's left of the ISO
+            chunk_size: self
+                .chunk_size
+                .min(self.total_size.saturating_sub(chunk_start)),
+            total_bytes: self.total_bytes,
+            total_size: self.total_size,
+        }
+    }
+
     /// Initialize writer: create partitions and format FAT32
     ///
     /// Call this before writing any data.
@@ -122,31 +191,26 @@ impl IsoWriter {
             let mut name_buf = [0u8; 16];
             let chunk_name = self.chunk_name_str(i, &mut name_buf);
 
-            // Find free space
-            let (start, end) = GptOps::find_free_space(block_io)?;
-
-            // Calculate partition size (chunk_size in sectors, or remaining space)
-            let sectors_needed = (self.chunk_size / SECTOR_SIZE as u64) + 8192; // Data + overhead
-            let available = end - start + 1;
-
-            if available < sectors_needed {
-                if i == 0 {
-                    return Err(DiskError::NoFreeSpace);
-                }
-                break; // Use what we have
-            }
+            // Partition size: chunk_size in sectors plus FAT32 overhead
+            let sectors_needed = (self.chunk_size / SECTOR_SIZE as u64) + 8192;
 
-            let part_end = start + sectors_needed - 1;
+            // Find free space
+            let (start, part_end) =
+                match GptOps::find_free_space(block_io, self.placement, sectors_needed) {
+                    Ok(range) => range,
+                    Err(DiskError::NoFreeSpace) if i > 0 => break, // Use what we have
+                    Err(e) => return Err(e),
+                };
 
             // Create GPT partition
             let slot =
-                GptOps::create_partition(block_io, start, part_end, guid::BASIC_DATA, chunk_name)?;
+                GptOps::create_partition(block_io, start, part_end, guid::ISO_CHUNK, chunk_name)?;
 
             // Format as FAT32
             let fat32_info = Fat32Formatter::format(block_io, start, sectors_needed, chunk_name)?;
 
             // Record chunk info
-            let mut part_info = PartitionInfo::new(slot, start, part_end, guid::BASIC_DATA);
+            let mut part_info = PartitionInfo::new(slot, start, part_end, guid::ISO_CHUNK);
             part_info.set_name(chunk_name);
 
             let chunk = ChunkPartition::new(part_info, i as u8);
@@ -177,7 +241,7 @@ impl IsoWriter {
         self.chunks.total_size = self.total_size;
 
         for (i, (&(start, end), &info)) in partitions.iter().zip(fat32_infos.iter()).enumerate() {
-            let mut part_info = PartitionInfo::new(i as u8, start, end, guid::BASIC_DATA);
+            let mut part_info = PartitionInfo::new(i as u8, start, end, guid::ISO_CHUNK);
             let mut name_buf = [0u8; 16];
             let name = self.chunk_name_str(i, &mut name_buf);
             part_info.set_name(name);
@@ -250,15 +314,7 @@ impl IsoWriter {
                 c.bytes_written = self.chunk_bytes;
             }
 
-            // Progress callback
-            if let Some(f) = self.progress_fn {
-                f(
-                    self.total_bytes,
-                    self.total_size,
-                    self.current_chunk,
-                    self.chunks.count,
-                );
-            }
+            self.report_progress();
         }
 
         // Check if complete
@@ -273,6 +329,19 @@ impl IsoWriter {
         Ok(written)
     }
 
+    /// Call the progress callback if the chunk or its percentage moved.
+    fn report_progress(&mut self) {
+        let Some(f) = self.progress_fn else {
+            return;
+        };
+        let progress = self.progress();
+        let key = (progress.chunk_index, progress.chunk_percent());
+        if self.last_reported != Some(key) {
+            self.last_reported = Some(key);
+            f(&progress);
+        }
+    }
+
     /// Write sectors to disk
     fn write_sectors<B: BlockIo>(
         &self,
@@ -354,3 +423,55 @@ impl IsoWriter {
         core::str::from_utf8(&buf[..10]).unwrap_or("ISO_CHK_00")
     }
 }
+
+/// Ready-made [`ProgressFn`] that draws one status line on the serial
+/// console and framebuffer, e.g.
+/// `[ISO] Chunk 2/3 [##########----------] 50% | Total 66% (2730/4096 MB)`
+pub fn print_chunk_progress(progress: &ChunkProgress) {
+    const BAR_WIDTH: usize = 20;
+
+    let mut bar = [b'-'; BAR_WIDTH];
+    let filled = progress.chunk_percent() as usize * BAR_WIDTH / 100;
+    bar[..filled].fill(b'#');
+
+    serial::print("[ISO] Chunk ");
+    serial::print_u32(progress.chunk_index as u32 + 1);
+    serial::print("/");
+    serial::print_u32(progress.chunk_count as u32);
+    serial::print(" [");
+    serial::print(core::str::from_utf8(&bar).unwrap_or(""));
+    serial::print("] ");
+    serial::print_u32(progress.chunk_percent() as u32);
+    serial::print("% | Total ");
+    serial::print_u32(progress.total_percent() as u32);
+    serial::print("% (");
+    serial::print_u32((progress.total_bytes / (1024 * 1024)) as u32);
+    serial::print("/");
+    serial::print_u32((progress.total_size / (1024 * 1024)) as u32);
+    serial::println(" MB)");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_last_chunk_holds_remainder() {
+        let mut writer = IsoWriter::new("big.iso", DEFAULT_CHUNK_SIZE * 2 + 1000);
+        writer.current_chunk = 2;
+        writer.chunk_bytes = 500;
+        writer.total_bytes = DEFAULT_CHUNK_SIZE * 2 + 500;
+
+        let progress = writer.progress();
+        assert_eq!(progress.chunk_size, 1000);
+        assert_eq!(progress.chunk_percent(), 50);
+        assert_eq!(progress.total_percent(), 99);
+    }
+
+    #[test]
+    fn test_percent_bounds() {
+        assert_eq!(percent(0, 0), 100);
+        assert_eq!(percent(5, 4), 100);
+        assert_eq!(percent(u64::MAX / 2, u64::MAX), 49);
+    }
+}
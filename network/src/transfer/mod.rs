@@ -5,6 +5,8 @@
 //! - Streaming downloads with progress
 //! - Progress tracking utilities
 //! - End-to-end orchestration
+//! - SHA-256 for image verification
+//! - Checksum lists published next to an image
 //!
 //! # Post-EBS Disk Operations (new modular approach)
 //!
@@ -14,8 +16,10 @@
 //! - Streaming ISO writer with chunking
 //! - Binary manifest for bootloader integration
 
+pub mod checksums;
 pub mod chunked;
 pub mod orchestrator;
+pub mod sha256;
 pub mod streaming;
 
 // Post-EBS disk operations (allocation-free, modular)
@@ -26,10 +30,11 @@ pub use orchestrator::{
     OrchestratorError, OrchestratorResult, PersistenceConfig, PersistenceOrchestrator,
     PersistencePhase, PersistenceProgress, PersistenceResult,
 };
+pub use sha256::Sha256;
 pub use streaming::{ProgressTracker, StreamConfig, StreamReader, StreamState, StreamWriter};
 
 // Re-export disk module types for convenience
 pub use disk::{
     ChunkPartition, ChunkSet, DiskError, DiskResult, Fat32Formatter, Fat32Info, GptOps,
-    IsoManifestInfo, IsoWriter, ManifestReader, ManifestWriter, PartitionInfo,
+    ChunkProgress, IsoManifestInfo, IsoWriter, ManifestReader, ManifestWriter, PartitionInfo,
 };
@@ -418,6 +418,11 @@ impl PersistenceOrchestrator {
             }
 
             OrchestratorState::Verifying => {
+                // Verification is optional work: defer it while the CPU is hot.
+                if crate::power::throttled() {
+                    return OrchestratorResult::Pending;
+                }
+
                 // TODO: Read back data and verify checksum
                 // For now, skip verification
                 self.finalize(now_tsc);
@@ -0,0 +1,6 @@
+//! SHA-256, from `morpheus_core::hash`.
+//!
+//! Used to verify downloaded images; the HTTP state feeds it the body as
+//! it arrives, on another core when one is available (see `offload`).
+
+pub use morpheus_core::hash::Sha256;
@@ -82,3 +82,13 @@ pub fn generate_local_mac(seed: u64) -> MacAddress {
     mac[5] = bytes[5];
     mac
 }
+
+/// Multicast MAC address of IPv4 group `ip` (01:00:5E + low 23 bits).
+pub fn ipv4_multicast_mac(ip: [u8; 4]) -> MacAddress {
+    [0x01, 0x00, 0x5E, ip[1] & 0x7F, ip[2], ip[3]]
+}
+
+/// Multicast MAC address of IPv6 group `ip` (33:33 + low 32 bits).
+pub fn ipv6_multicast_mac(ip: [u8; 16]) -> MacAddress {
+    [0x33, 0x33, ip[12], ip[13], ip[14], ip[15]]
+}
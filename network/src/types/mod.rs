@@ -15,7 +15,7 @@ pub mod virtio_hdr;
 pub use ethernet::{EthernetHeader, MacAddress, ETH_ALEN, ETH_FRAME_MAX, ETH_HLEN, ETH_MTU};
 pub use repr_c::{DriverState, RxPollResult, RxResult, TxPollResult, VirtqDesc, VirtqueueState};
 pub use result::AsmResult;
-pub use virtio_hdr::{VirtioNetHdr, VIRTIO_NET_HDR_GSO_NONE};
+pub use virtio_hdr::{VirtioNetHdr, VIRTIO_NET_HDR_F_NEEDS_CSUM, VIRTIO_NET_HDR_GSO_NONE};
 
 // ═══════════════════════════════════════════════════════════════════════════
 // HTTP Types
@@ -29,6 +29,10 @@ impl VirtioNetHdr {
     /// Header size in bytes.
     pub const SIZE: usize = 12;
 
+    /// Header size on legacy devices without MRG_RXBUF, which drop
+    /// `num_buffers`.
+    pub const LEGACY_SIZE: usize = 10;
+
     /// Create a zeroed header (correct for all our transmits).
     pub const fn zeroed() -> Self {
         Self {
@@ -46,6 +50,56 @@ impl VirtioNetHdr {
     pub fn as_bytes(&self) -> &[u8] {
         unsafe { core::slice::from_raw_parts(self as *const _ as *const u8, Self::SIZE) }
     }
+
+    /// Parse the header at the start of an RX buffer (10 or 12 bytes).
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let field = |at: usize| u16::from_le_bytes([bytes[at], bytes[at + 1]]);
+        if bytes.len() < Self::LEGACY_SIZE {
+            return None;
+        }
+        Some(Self {
+            flags: bytes[0],
+            gso_type: bytes[1],
+            hdr_len: field(2),
+            gso_size: field(4),
+            csum_start: field(6),
+            csum_offset: field(8),
+            num_buffers: if bytes.len() >= Self::SIZE {
+                field(10)
+            } else {
+                0
+            },
+        })
+    }
+
+    /// Finish the partial checksum of a NEEDS_CSUM packet.
+    ///
+    /// The checksum field already holds the pseudo-header sum; summing from
+    /// `csum_start` to the end of `frame` and storing the complement gives
+    /// the real checksum (VirtIO 1.2 §5.1.6.4). Returns false when the
+    /// offsets don't fit the frame.
+    pub fn complete_checksum(&self, frame: &mut [u8]) -> bool {
+        let start = self.csum_start as usize;
+        let field = start + self.csum_offset as usize;
+        if start > frame.len() || field + 2 > frame.len() {
+            return false;
+        }
+
+        let mut sum = 0u32;
+        let mut words = frame[start..].chunks_exact(2);
+        for word in &mut words {
+            sum += u16::from_be_bytes([word[0], word[1]]) as u32;
+        }
+        if let [last] = words.remainder() {
+            sum += (*last as u32) << 8;
+        }
+        while sum > 0xFFFF {
+            sum = (sum & 0xFFFF) + (sum >> 16);
+        }
+
+        frame[field..field + 2].copy_from_slice(&(!(sum as u16)).to_be_bytes());
+        true
+    }
 }
 
 impl Default for VirtioNetHdr {
@@ -67,7 +121,70 @@ pub const VIRTIO_NET_HDR_GSO_TCPV6: u8 = 4;
 pub const VIRTIO_NET_HDR_GSO_ECN: u8 = 0x80;
 
 // Header flags
-/// Checksum is valid/needed.
+/// Checksum is partial; the receiver must complete it.
 pub const VIRTIO_NET_HDR_F_NEEDS_CSUM: u8 = 1;
 /// Data is valid (for hash reports).
 pub const VIRTIO_NET_HDR_F_DATA_VALID: u8 = 2;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_bytes() {
+        let mut bytes = [0u8; VirtioNetHdr::SIZE];
+        bytes[0] = VIRTIO_NET_HDR_F_NEEDS_CSUM;
+        bytes[6..8].copy_from_slice(&34u16.to_le_bytes());
+        bytes[8..10].copy_from_slice(&6u16.to_le_bytes());
+        bytes[10..12].copy_from_slice(&1u16.to_le_bytes());
+
+        let hdr = VirtioNetHdr::from_bytes(&bytes).unwrap();
+        assert_eq!(hdr.flags, VIRTIO_NET_HDR_F_NEEDS_CSUM);
+        assert_eq!((hdr.csum_start, hdr.csum_offset), (34, 6));
+        assert_eq!(hdr.num_buffers, 1);
+
+        let legacy = VirtioNetHdr::from_bytes(&bytes[..VirtioNetHdr::LEGACY_SIZE]).unwrap();
+        assert_eq!(legacy.num_buffers, 0);
+        assert!(VirtioNetHdr::from_bytes(&bytes[..9]).is_none());
+    }
+
+    #[test]
+    fn test_complete_checksum() {
+        // UDP 10.0.0.1:1000 -> 10.0.0.2:2000, payload "abc", after a
+        // 20-byte IPv4 header (not checked, just skipped by csum_start)
+        let mut frame = [0u8; 20 + 11];
+        let udp = [
+            0x03, 0xE8, 0x07, 0xD0, 0x00, 0x0B, 0x00, 0x00, b'a', b'b', b'c',
+        ];
+        frame[20..].copy_from_slice(&udp);
+
+        // Pseudo-header sum: addresses, protocol 17, UDP length 11
+        let pseudo: u32 = 0x0A00 + 0x0001 + 0x0A00 + 0x0002 + 17 + 11;
+        frame[26..28].copy_from_slice(&(pseudo as u16).to_be_bytes());
+
+        let hdr = VirtioNetHdr {
+            flags: VIRTIO_NET_HDR_F_NEEDS_CSUM,
+            csum_start: 20,
+            csum_offset: 6,
+            ..VirtioNetHdr::zeroed()
+        };
+        assert!(hdr.complete_checksum(&mut frame));
+
+        // A correct checksum makes the whole sum (with pseudo-header) 0xFFFF
+        let mut sum = pseudo;
+        for word in frame[20..].chunks(2) {
+            let hi = (word[0] as u32) << 8;
+            sum += hi | word.get(1).copied().unwrap_or(0) as u32;
+        }
+        while sum > 0xFFFF {
+            sum = (sum & 0xFFFF) + (sum >> 16);
+        }
+        assert_eq!(sum, 0xFFFF);
+
+        let bad = VirtioNetHdr {
+            csum_start: 30,
+            ..hdr
+        };
+        assert!(!bad.complete_checksum(&mut frame));
+    }
+}
@@ -27,6 +27,14 @@ if [ -f testing/test-disk.img ] || [ -f testing/test-disk-10g.img ]; then
     fi
 fi
 
+# Slim variants: MORPHEUS_FEATURES=netboot-only ./build.sh (see BUILD.md).
+# Both passes must use the same features or the reloc data won't match.
+FEATURE_ARGS=()
+if [ -n "$MORPHEUS_FEATURES" ]; then
+    FEATURE_ARGS=(--features "$MORPHEUS_FEATURES")
+    echo "Features: $MORPHEUS_FEATURES"
+fi
+
 # Install rust target if not present
 rustup target add x86_64-unknown-uefi 2>/dev/null || true
 
@@ -46,7 +54,7 @@ echo ""
 # PASS 1: Build bootloader to get binary for reloc extraction
 # =============================================================================
 echo "Step 2: Building bootloader (pass 1)..."
-cargo build --target x86_64-unknown-uefi -p morpheus-bootloader --release
+cargo build --target x86_64-unknown-uefi -p morpheus-bootloader --release "${FEATURE_ARGS[@]}"
 
 # Extract relocation data from the built binary
 echo ""
@@ -60,7 +68,7 @@ echo ""
 echo "Step 4: Building bootloader (pass 2 with reloc data)..."
 # Clean just the bootloader to force rebuild with new reloc data
 cargo clean -p morpheus-bootloader
-cargo build --target x86_64-unknown-uefi -p morpheus-bootloader --release
+cargo build --target x86_64-unknown-uefi -p morpheus-bootloader --release "${FEATURE_ARGS[@]}"
 
 # Rebuild initrd if rootfs exists
 if [ -d "testing/esp/rootfs" ]; then